@@ -0,0 +1,140 @@
+#ifndef RUSTYRTC_H
+#define RUSTYRTC_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Success.
+ */
+#define RTC_OK 0
+
+/**
+ * A required pointer argument was null.
+ */
+#define RTC_ERR_NULL -1
+
+/**
+ * A string argument was not valid UTF-8.
+ */
+#define RTC_ERR_UTF8 -2
+
+/**
+ * The engine rejected the call; see [`rtc_engine_last_error`].
+ */
+#define RTC_ERR_ENGINE -3
+
+/**
+ * Kind of an event returned by [`rtc_engine_poll_event`].
+ */
+typedef enum RtcEventKind {
+  /**
+   * No event was pending.
+   */
+  RTC_EVENT_KIND_NONE = 0,
+  RTC_EVENT_KIND_STATUS = 1,
+  RTC_EVENT_KIND_ICE_NOMINATED = 2,
+  RTC_EVENT_KIND_ESTABLISHED = 3,
+  RTC_EVENT_KIND_STATE_CHANGED = 4,
+  RTC_EVENT_KIND_CLOSING = 5,
+  RTC_EVENT_KIND_CLOSED = 6,
+  RTC_EVENT_KIND_ERROR = 7,
+  RTC_EVENT_KIND_REMOTE_TRACK_ADDED = 8,
+  RTC_EVENT_KIND_REMOTE_TRACK_REMOVED = 9,
+  /**
+   * Any other engine event; `text` holds its debug representation.
+   */
+  RTC_EVENT_KIND_OTHER = 10,
+} RtcEventKind;
+
+/**
+ * Opaque engine handle.
+ */
+typedef struct RtcEngine RtcEngine;
+
+/**
+ * An engine event. `text` is owned by the caller (free it with
+ * [`rtc_string_free`]) and may be null.
+ */
+typedef struct RtcEvent {
+  enum RtcEventKind kind;
+  char *text;
+} RtcEvent;
+
+/**
+ * Creates an engine. `config_path` may be null to use built-in defaults.
+ *
+ * Returns null if the configuration cannot be loaded.
+ */
+struct RtcEngine *rtc_engine_create(const char *config_path);
+
+/**
+ * Stops and frees an engine. Null is ignored.
+ */
+void rtc_engine_destroy(struct RtcEngine *engine);
+
+/**
+ * Creates an offer. `*out_sdp` receives it, or null if an offer is
+ * already pending.
+ */
+int32_t rtc_engine_negotiate(struct RtcEngine *engine, char **out_sdp);
+
+/**
+ * Applies a remote offer or answer. For an offer, `*out_answer` receives
+ * the answer to send back; otherwise it is set to null.
+ */
+int32_t rtc_engine_apply_sdp(struct RtcEngine *engine, const char *sdp, char **out_answer);
+
+/**
+ * Applies a remote ICE candidate line (`candidate:...`).
+ */
+int32_t rtc_engine_apply_candidate(struct RtcEngine *engine, const char *candidate);
+
+/**
+ * Drives the engine and pops the next event into `*out`.
+ *
+ * Returns 1 if an event was written, 0 if none was pending (`out->kind`
+ * is then `None`), or a negative `RTC_*` code. Call it regularly (e.g.
+ * every 10-50 ms): it also starts the session and the media pipeline.
+ */
+int32_t rtc_engine_poll_event(struct RtcEngine *engine, struct RtcEvent *out);
+
+/**
+ * Adds a video track fed by [`rtc_engine_send_frame`]. `*out_track`
+ * receives its id and `*out_sdp` the re-offer to send (null if an offer is
+ * already pending). The call must be established.
+ */
+int32_t rtc_engine_add_frame_track(struct RtcEngine *engine, uint32_t *out_track, char **out_sdp);
+
+/**
+ * Pushes one packed RGB24 frame (`width * height * 3` bytes) to a track
+ * created with [`rtc_engine_add_frame_track`]. The pixels are copied.
+ */
+int32_t rtc_engine_send_frame(struct RtcEngine *engine,
+                              uint32_t track,
+                              const uint8_t *rgb,
+                              uint32_t width,
+                              uint32_t height);
+
+/**
+ * Removes a track created with [`rtc_engine_add_frame_track`]. `*out_sdp`
+ * receives the re-offer to send.
+ */
+int32_t rtc_engine_remove_track(struct RtcEngine *engine, uint32_t track, char **out_sdp);
+
+/**
+ * Message of the last failed call on `engine`, or null. The string is owned
+ * by the engine and valid until the next call on it.
+ */
+const char *rtc_engine_last_error(const struct RtcEngine *engine);
+
+/**
+ * Frees a string returned by this library. Null is ignored.
+ */
+void rtc_string_free(char *s);
+
+#endif  /* RUSTYRTC_H */
@@ -0,0 +1,398 @@
+//! Client-side call history for the History tab.
+//!
+//! Every call attempt is recorded with its peer, direction, outcome and
+//! duration, and kept in a small tab-separated file next to the settings
+//! (`client_roomrtc.history`), newest last. Only the last
+//! [`MAX_RECORDS`] calls are kept.
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::app::i18n::Locale;
+
+/// Calls kept in the history.
+pub const MAX_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallDirection {
+    Outgoing,
+    Incoming,
+}
+
+/// How a call ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallOutcome {
+    /// Answered; the record has a duration.
+    Completed,
+    /// We hung up an outgoing call before it was answered.
+    Cancelled,
+    /// The peer did not take our call.
+    Rejected,
+    /// We declined an incoming call.
+    Declined,
+    /// An incoming call ended before we answered (or arrived while busy).
+    Missed,
+    /// The peer never answered the signaling or the connection failed.
+    Failed,
+}
+
+impl CallOutcome {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::Cancelled => "cancelled",
+            Self::Rejected => "rejected",
+            Self::Declined => "declined",
+            Self::Missed => "missed",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "completed" => Self::Completed,
+            "cancelled" => Self::Cancelled,
+            "rejected" => Self::Rejected,
+            "declined" => Self::Declined,
+            "missed" => Self::Missed,
+            "failed" => Self::Failed,
+            _ => return None,
+        })
+    }
+}
+
+/// Who or what ended a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallEnd {
+    Local,
+    Remote,
+    Failed,
+}
+
+/// One call in the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallRecord {
+    pub peer: String,
+    pub direction: CallDirection,
+    /// Unix seconds when the call was placed or received.
+    pub started: u64,
+    pub outcome: CallOutcome,
+    /// Seconds from answer to hang up, for answered calls.
+    pub duration_secs: Option<u64>,
+}
+
+impl CallRecord {
+    fn to_line(&self) -> String {
+        let direction = match self.direction {
+            CallDirection::Outgoing => "out",
+            CallDirection::Incoming => "in",
+        };
+        let duration = self
+            .duration_secs
+            .map_or_else(String::new, |d| d.to_string());
+        // Usernames never contain tabs; keep the file parseable anyway
+        let peer = self.peer.replace(['\t', '\n', '\r'], " ");
+        format!(
+            "{}\t{direction}\t{peer}\t{}\t{duration}",
+            self.started,
+            self.outcome.as_str()
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let started = fields.next()?.parse().ok()?;
+        let direction = match fields.next()? {
+            "out" => CallDirection::Outgoing,
+            "in" => CallDirection::Incoming,
+            _ => return None,
+        };
+        let peer = fields.next()?.to_owned();
+        let outcome = CallOutcome::parse(fields.next()?)?;
+        let duration_secs = fields.next().and_then(|d| d.parse().ok());
+        Some(Self {
+            peer,
+            direction,
+            started,
+            outcome,
+            duration_secs,
+        })
+    }
+}
+
+/// The call in progress, until it ends.
+#[derive(Debug, Clone)]
+struct PendingCall {
+    peer: String,
+    direction: CallDirection,
+    started: u64,
+    answered: Option<u64>,
+}
+
+/// Recorded calls, oldest first, and the call in progress.
+#[derive(Debug, Default)]
+pub struct CallHistory {
+    path: Option<PathBuf>,
+    records: VecDeque<CallRecord>,
+    pending: Option<PendingCall>,
+}
+
+impl CallHistory {
+    /// Loads the history saved at `path`; a missing file is an empty history.
+    /// Malformed lines are skipped.
+    #[must_use]
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let records = fs::read_to_string(&path)
+            .map(|text| text.lines().filter_map(CallRecord::from_line).collect())
+            .unwrap_or_default();
+        let mut history = Self {
+            path: Some(path),
+            records,
+            pending: None,
+        };
+        history.truncate();
+        history
+    }
+
+    /// A history that is never written anywhere.
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Recorded calls, oldest first.
+    #[must_use]
+    pub fn records(&self) -> &VecDeque<CallRecord> {
+        &self.records
+    }
+
+    /// A call was placed or is ringing.
+    pub fn begin(&mut self, peer: &str, direction: CallDirection, now: u64) {
+        self.pending = Some(PendingCall {
+            peer: peer.to_owned(),
+            direction,
+            started: now,
+            answered: None,
+        });
+    }
+
+    /// The call in progress was answered (by either side).
+    pub fn answered(&mut self, now: u64) {
+        if let Some(call) = &mut self.pending
+            && call.answered.is_none()
+        {
+            call.answered = Some(now);
+        }
+    }
+
+    /// Records how the call in progress ended. Does nothing without one.
+    pub fn finish(&mut self, end: CallEnd, now: u64) -> Option<&CallRecord> {
+        let call = self.pending.take()?;
+        let outcome = match (call.answered, end, call.direction) {
+            (_, CallEnd::Failed, _) => CallOutcome::Failed,
+            (Some(_), _, _) => CallOutcome::Completed,
+            (None, CallEnd::Local, CallDirection::Outgoing) => CallOutcome::Cancelled,
+            (None, CallEnd::Remote, CallDirection::Outgoing) => CallOutcome::Rejected,
+            (None, CallEnd::Local, CallDirection::Incoming) => CallOutcome::Declined,
+            (None, CallEnd::Remote, CallDirection::Incoming) => CallOutcome::Missed,
+        };
+        self.push(CallRecord {
+            peer: call.peer,
+            direction: call.direction,
+            started: call.started,
+            outcome,
+            duration_secs: call.answered.map(|at| now.saturating_sub(at)),
+        });
+        self.records.back()
+    }
+
+    /// Records an incoming call that could not ring (we were busy).
+    pub fn missed(&mut self, peer: &str, now: u64) {
+        self.push(CallRecord {
+            peer: peer.to_owned(),
+            direction: CallDirection::Incoming,
+            started: now,
+            outcome: CallOutcome::Missed,
+            duration_secs: None,
+        });
+    }
+
+    /// Forgets every recorded call.
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Writes the history to its file (a no-op for an in-memory history),
+    /// creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be written.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut out = String::new();
+        for record in &self.records {
+            let _ = writeln!(out, "{}", record.to_line());
+        }
+        fs::write(path, out)
+    }
+
+    fn push(&mut self, record: CallRecord) {
+        self.records.push_back(record);
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        while self.records.len() > MAX_RECORDS {
+            self.records.pop_front();
+        }
+    }
+}
+
+/// Current time in Unix seconds.
+#[must_use]
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `started` relative to `now`, e.g. "5 min ago".
+#[must_use]
+pub fn format_ago(started: u64, now: u64, locale: Locale) -> String {
+    let secs = now.saturating_sub(started);
+    match secs {
+        0..60 => locale.tr("just now").to_owned(),
+        60..3_600 => locale.trf("{n} min ago", &[("n", &(secs / 60))]),
+        3_600..86_400 => locale.trf("{n} h ago", &[("n", &(secs / 3_600))]),
+        _ => locale.trf("{n} d ago", &[("n", &(secs / 86_400))]),
+    }
+}
+
+/// A duration as `m:ss` (or `h:mm:ss`).
+#[must_use]
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 3_600 {
+        format!("{}:{:02}:{:02}", secs / 3_600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Bytes sent and received on a call, in megabytes.
+#[must_use]
+pub fn format_traffic(sent: u64, received: u64, locale: Locale) -> String {
+    let megabytes = |bytes: u64| format!("{:.1}", bytes as f64 / 1e6);
+    locale.trf(
+        "Sent {sent} MB, received {received} MB",
+        &[
+            ("sent", &megabytes(sent)),
+            ("received", &megabytes(received)),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn outcomes_follow_who_ended_the_call() {
+        let mut history = CallHistory::in_memory();
+        assert!(history.finish(CallEnd::Local, 0).is_none());
+
+        history.begin("bob", CallDirection::Outgoing, 100);
+        history.answered(105);
+        let done = history.finish(CallEnd::Remote, 170).unwrap();
+        assert_eq!(done.outcome, CallOutcome::Completed);
+        assert_eq!(done.duration_secs, Some(65));
+
+        let cases = [
+            (
+                CallDirection::Outgoing,
+                CallEnd::Local,
+                CallOutcome::Cancelled,
+            ),
+            (
+                CallDirection::Outgoing,
+                CallEnd::Remote,
+                CallOutcome::Rejected,
+            ),
+            (
+                CallDirection::Incoming,
+                CallEnd::Local,
+                CallOutcome::Declined,
+            ),
+            (
+                CallDirection::Incoming,
+                CallEnd::Remote,
+                CallOutcome::Missed,
+            ),
+            (
+                CallDirection::Incoming,
+                CallEnd::Failed,
+                CallOutcome::Failed,
+            ),
+        ];
+        for (direction, end, outcome) in cases {
+            history.begin("carol", direction, 200);
+            let done = history.finish(end, 210).unwrap();
+            assert_eq!((done.outcome, done.duration_secs), (outcome, None));
+        }
+        history.missed("dave", 300);
+        assert_eq!(history.records().len(), 7);
+    }
+
+    #[test]
+    fn history_roundtrips_through_its_file() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-{}.history", std::process::id()));
+        let mut history = CallHistory::load(&path);
+        assert!(history.records().is_empty());
+        history.begin("bob", CallDirection::Incoming, 1_700_000_000);
+        history.answered(1_700_000_002);
+        history.finish(CallEnd::Local, 1_700_000_062);
+        history.missed("al\tice", 1_700_000_100);
+        history.save().unwrap();
+
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "garbage line\n").unwrap();
+        let loaded = CallHistory::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.records().len(), 2);
+        assert_eq!(loaded.records()[0], history.records()[0]);
+        assert_eq!(loaded.records()[1].peer, "al ice");
+    }
+
+    #[test]
+    fn times_are_formatted_for_display() {
+        assert_eq!(format_ago(100, 130, Locale::En), "just now");
+        assert_eq!(format_ago(0, 7_200, Locale::En), "2 h ago");
+        assert_eq!(format_duration(65), "1:05");
+        assert_eq!(format_duration(3_725), "1:02:05");
+        assert_eq!(
+            format_traffic(1_260_000, 40_000, Locale::En),
+            "Sent 1.3 MB, received 0.0 MB"
+        );
+        assert_eq!(
+            format_traffic(12_345_678, 0, Locale::Es),
+            "Enviados 12.3 MB, recibidos 0.0 MB"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Live preview of a camera outside of a call, for the Settings window.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+    },
+    thread::JoinHandle,
+};
+
+use crate::{
+    camera_manager::capture_settings::CaptureSettings,
+    log::log_sink::LogSink,
+    media_agent::{camera_worker::spawn_camera_worker, video_frame::VideoFrame},
+};
+
+/// A capture worker running for the preview. Dropping it releases the
+/// device, so a call can open it.
+pub struct CameraPreview {
+    camera_id: i32,
+    settings: CaptureSettings,
+    running: Arc<AtomicBool>,
+    frames: Receiver<VideoFrame>,
+    handle: Option<JoinHandle<()>>,
+    /// What the camera worker reported on start (resolution or error).
+    pub status: Option<String>,
+}
+
+impl CameraPreview {
+    /// Opens `camera_id` with `settings` and starts capturing.
+    #[must_use]
+    pub fn start(logger: Arc<dyn LogSink>, camera_id: i32, settings: CaptureSettings) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let (frames, status, handle) =
+            spawn_camera_worker(settings, logger, camera_id, running.clone());
+        Self {
+            camera_id,
+            settings,
+            running,
+            frames,
+            handle,
+            status,
+        }
+    }
+
+    /// Whether this preview shows `camera_id` captured with `settings`.
+    #[must_use]
+    pub fn is_for(&self, camera_id: i32, settings: &CaptureSettings) -> bool {
+        self.camera_id == camera_id && self.settings == *settings
+    }
+
+    /// The newest frame captured since the last call, if any.
+    #[must_use]
+    pub fn latest(&self) -> Option<VideoFrame> {
+        self.frames.try_iter().last()
+    }
+}
+
+impl Drop for CameraPreview {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
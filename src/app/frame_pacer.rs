@@ -0,0 +1,186 @@
+//! Presentation of the remote video at its own frame rate.
+//!
+//! Repainting at a fixed `[UI] fps` and showing whatever frame is newest
+//! makes 24 or 25 fps video judder: some frames stay up for two repaints,
+//! others for three. With `[UI] frame_pacing = "vsync"` (the default) the
+//! GUI repaints on every vsync while video is shown, and decoded frames are
+//! queued with a display time, their arrival plus `[UI] frame_delay_ms`.
+//! Each repaint shows the newest frame that is due, so a frame is repeated
+//! until the next one is due and dropped if a later one already is.
+//! `"fixed"` keeps the old behavior.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{config::Config, media_agent::video_frame::VideoFrame};
+
+const DEFAULT_DELAY_MS: u64 = 20;
+const MAX_DELAY_MS: u64 = 500;
+/// Frames held at most; older ones are dropped past it.
+const MAX_QUEUED: usize = 8;
+/// How far a display time may drift from the clock before the schedule is
+/// started over (a stall, or a new stream).
+const RESYNC: Duration = Duration::from_millis(250);
+
+/// How the GUI paces its repaints while video is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FramePacing {
+    /// Every vsync, showing frames at their display time.
+    #[default]
+    Vsync,
+    /// Every `1 / [UI] fps` seconds, showing the newest frame.
+    Fixed,
+}
+
+impl FramePacing {
+    /// `[UI] frame_pacing`, vsync if missing or unknown.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        match config.get_non_empty("UI", "frame_pacing") {
+            Some(v) if v.trim().eq_ignore_ascii_case("fixed") => Self::Fixed,
+            _ => Self::Vsync,
+        }
+    }
+}
+
+/// Queue of decoded frames waiting for their display time.
+#[derive(Debug)]
+pub struct FramePacer {
+    delay: Duration,
+    queue: VecDeque<(Instant, VideoFrame)>,
+    current: Option<VideoFrame>,
+    /// A frame timestamp and when it was due, which later frames are
+    /// scheduled from.
+    anchor: Option<(u128, Instant)>,
+    dropped: u64,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(DEFAULT_DELAY_MS))
+    }
+}
+
+impl FramePacer {
+    #[must_use]
+    pub const fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            queue: VecDeque::new(),
+            current: None,
+            anchor: None,
+            dropped: 0,
+        }
+    }
+
+    /// With `[UI] frame_delay_ms` (20 if unset, at most 500).
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let ms = config
+            .get_non_empty("UI", "frame_delay_ms")
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map_or(DEFAULT_DELAY_MS, |ms| ms.min(MAX_DELAY_MS));
+        Self::new(Duration::from_millis(ms))
+    }
+
+    /// Queues `frame` (the newest decoded one) unless it is already queued
+    /// or shown. Its display time keeps the spacing of the frame timestamps,
+    /// however late the GUI looked at it.
+    pub fn push(&mut self, frame: VideoFrame, now: Instant) {
+        let last = self.queue.back().map(|(_, f)| f).or(self.current.as_ref());
+        if last.is_some_and(|f| f.is_same_frame(&frame)) {
+            return;
+        }
+        let scheduled = self.anchor.and_then(|(ts, at)| {
+            let since = u64::try_from(frame.timestamp_ms.checked_sub(ts)?).ok()?;
+            let due = at + Duration::from_millis(since);
+            (due + RESYNC >= now && due <= now + self.delay + RESYNC).then_some(due)
+        });
+        let due = scheduled.unwrap_or_else(|| {
+            let due = now + self.delay;
+            self.anchor = Some((frame.timestamp_ms, due));
+            due
+        });
+        // Never ahead of a frame already queued
+        let due = self.queue.back().map_or(due, |(last, _)| due.max(*last));
+        self.queue.push_back((due, frame));
+        while self.queue.len() > MAX_QUEUED {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+    }
+
+    /// The frame to show at `now`: the newest one due, or the one shown
+    /// before if none is.
+    pub fn frame_at(&mut self, now: Instant) -> Option<&VideoFrame> {
+        let mut taken = 0;
+        while let Some((due, _)) = self.queue.front()
+            && *due <= now
+        {
+            self.current = self.queue.pop_front().map(|(_, f)| f);
+            taken += 1;
+        }
+        self.dropped += taken.max(1) - 1;
+        self.current.as_ref()
+    }
+
+    /// When the next queued frame is due.
+    #[must_use]
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queue.front().map(|(due, _)| *due)
+    }
+
+    /// Frames never shown because a later one was due first.
+    #[must_use]
+    pub const fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Forgets every frame (the call ended).
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.current = None;
+        self.anchor = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn frame(ts: u128) -> VideoFrame {
+        let mut f = VideoFrame::synthetic_yuv420(4, 4, 0);
+        f.timestamp_ms = ts;
+        f
+    }
+
+    #[test]
+    fn frames_are_shown_on_their_schedule() {
+        let mut pacer = FramePacer::new(Duration::from_millis(20));
+        let t0 = Instant::now();
+        let ms = Duration::from_millis;
+
+        // 25 fps: 40 ms apart, but the GUI sees the second one late
+        pacer.push(frame(1000), t0);
+        assert!(pacer.frame_at(t0).is_none());
+        assert_eq!(pacer.frame_at(t0 + ms(20)).unwrap().timestamp_ms, 1000);
+        pacer.push(frame(1040), t0 + ms(55));
+        assert_eq!(pacer.next_due(), Some(t0 + ms(60)));
+        // Repeated until the next one is due
+        assert_eq!(pacer.frame_at(t0 + ms(57)).unwrap().timestamp_ms, 1000);
+        assert_eq!(pacer.frame_at(t0 + ms(60)).unwrap().timestamp_ms, 1040);
+
+        // Both due at once: the older one is dropped
+        pacer.push(frame(1080), t0 + ms(70));
+        pacer.push(frame(1120), t0 + ms(71));
+        assert_eq!(pacer.frame_at(t0 + ms(140)).unwrap().timestamp_ms, 1120);
+        assert_eq!(pacer.dropped(), 1);
+
+        // A stall starts the schedule over
+        pacer.push(frame(1200), t0 + ms(2000));
+        assert_eq!(pacer.next_due(), Some(t0 + ms(2020)));
+    }
+}
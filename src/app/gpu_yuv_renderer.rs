@@ -9,15 +9,12 @@
 //! 1. Create a `GpuYuvRenderer` instance during initialization, providing the `wgpu::Device`
 //!    and the target texture format.
 //! 2. For each new `VideoFrame`, call `update_frame()` to upload the Y, U, and V planes
-//!    to the GPU and run the conversion shader.
+//!    to the GPU and run the conversion shader. Only the picture is uploaded, from
+//!    planes of any stride or cropped from a larger coded size.
 //! 3. The resulting RGB texture can be obtained via `output_texture()` and then
 //!    registered with `egui` to be displayed in the UI.
 //!
-use crate::{
-    log::log_sink::LogSink,
-    media_agent::video_frame::{VideoFrame, VideoFrameData},
-    sink_debug,
-};
+use crate::{log::log_sink::LogSink, media_agent::video_frame::VideoFrame, sink_debug};
 use eframe::wgpu::{self, PipelineCompilationOptions, util::DeviceExt};
 use std::sync::Arc;
 
@@ -45,6 +42,11 @@ pub struct GpuYuvRenderer {
     // Tracked texture sizes to detect dimension changes.
     y_size: (u32, u32),
     uv_size: (u32, u32),
+    out_size: (u32, u32),
+
+    /// Staging buffers the Y, U and V planes are copied to the textures
+    /// from, kept between frames of the same size.
+    staging: [Option<StagingBuffer>; 3],
 
     output_format: wgpu::TextureFormat,
 
@@ -236,6 +238,8 @@ impl GpuYuvRenderer {
             vertex_count: 3,
             y_size: (0, 0),
             uv_size: (0, 0),
+            out_size: (0, 0),
+            staging: [None, None, None],
             output_format,
             logger,
             u_info_buffer,
@@ -259,27 +263,15 @@ impl GpuYuvRenderer {
         frame: &VideoFrame,
         logger: Arc<dyn LogSink>,
     ) {
-        let (width, height, y_plane, u_plane, v_plane, y_stride, u_stride, v_stride) =
-            match &frame.data {
-                VideoFrameData::Yuv420 {
-                    y,
-                    u,
-                    v,
-                    y_stride,
-                    u_stride,
-                    v_stride,
-                } => (
-                    frame.width,
-                    frame.height,
-                    y.clone(),
-                    u.clone(),
-                    v.clone(),
-                    *y_stride,
-                    *u_stride,
-                    *v_stride,
-                ),
-                _ => return,
-            };
+        // Planes start at the picture, however larger the decoder made them
+        let Some((y_plane, u_plane, v_plane, y_stride, u_stride, v_stride)) = frame.as_yuv_planes()
+        else {
+            return;
+        };
+        let (width, height) = (frame.width, frame.height);
+        if width == 0 || height == 0 {
+            return;
+        }
 
         let y_w = width;
         let y_h = height;
@@ -287,6 +279,7 @@ impl GpuYuvRenderer {
         let uv_h = (height).div_ceil(2);
 
         // Recreate textures if frame dimensions have changed
+        let mut resized = false;
         if self.y_size != (y_w, y_h) || self.tex_y.is_none() {
             self.tex_y = Some(device.create_texture(&wgpu::TextureDescriptor {
                 label: Some("y-plane"),
@@ -305,6 +298,7 @@ impl GpuYuvRenderer {
                 view_formats: &[],
             }));
             self.y_size = (y_w, y_h);
+            resized = true;
         }
 
         if self.uv_size != (uv_w, uv_h) || self.tex_u.is_none() || self.tex_v.is_none() {
@@ -327,10 +321,24 @@ impl GpuYuvRenderer {
             self.tex_u = Some(create_uv("u-plane"));
             self.tex_v = Some(create_uv("v-plane"));
             self.uv_size = (uv_w, uv_h);
+            resized = true;
+
+            // With an odd width or height the chroma planes hold half a
+            // sample more than the picture covers
+            let chroma_scale = [
+                y_w as f32 / (2 * uv_w) as f32,
+                y_h as f32 / (2 * uv_h) as f32,
+                0.0,
+                0.0,
+            ];
+            queue.write_buffer(&self.u_info_buffer, 0, bytemuck::cast_slice(&chroma_scale));
         }
 
         // Recreate output texture if size has changed
-        if self.output_texture.is_none() || self.output_view.is_none() || self.y_size.0 != y_w {
+        if self.output_texture.is_none()
+            || self.output_view.is_none()
+            || self.out_size != (y_w, y_h)
+        {
             let out = device.create_texture(&wgpu::TextureDescriptor {
                 label: Some("yuv-rgb-output"),
                 size: wgpu::Extent3d {
@@ -348,88 +356,86 @@ impl GpuYuvRenderer {
             });
             self.output_view = Some(out.create_view(&wgpu::TextureViewDescriptor::default()));
             self.output_texture = Some(out);
+            self.out_size = (y_w, y_h);
         }
 
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("yuv-render-encoder"),
+        });
+
         // Upload plane data to GPU textures
-        upload_plane(
-            logger.clone(),
-            self.tex_y.as_ref().expect("Y-plane texture missing"),
-            &y_plane,
-            y_w,
-            y_h,
-            y_stride,
-            queue,
-        );
-        upload_plane(
-            logger.clone(),
-            self.tex_u.as_ref().expect("U-plane texture missing"),
-            &u_plane,
-            uv_w,
-            uv_h,
-            u_stride,
-            queue,
-        );
-        upload_plane(
-            logger,
-            self.tex_v.as_ref().expect("V-plane texture missing"),
-            &v_plane,
-            uv_w,
-            uv_h,
-            v_stride,
-            queue,
-        );
+        let planes = [
+            ("y-staging", &self.tex_y, y_plane, y_w, y_h, y_stride),
+            ("u-staging", &self.tex_u, u_plane, uv_w, uv_h, u_stride),
+            ("v-staging", &self.tex_v, v_plane, uv_w, uv_h, v_stride),
+        ];
+        for (staging, (label, tex, data, w, h, stride)) in self.staging.iter_mut().zip(planes) {
+            if !staging.as_ref().is_some_and(|buf| buf.fits(w, h)) {
+                *staging = Some(StagingBuffer::new(device, label, w, h));
+            }
+            let Some(staging) = staging.as_ref() else {
+                continue;
+            };
+            staging.upload(
+                &logger,
+                queue,
+                &mut encoder,
+                tex.as_ref().expect("plane texture missing"),
+                data,
+                stride,
+            );
+        }
 
-        // Create the bind group for the shader
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("yuv-bind-group"),
-            layout: &self.bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(
-                        &self
-                            .tex_y
-                            .as_ref()
-                            .expect("Y texture missing for bind group")
-                            .create_view(&Default::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(
-                        &self
-                            .tex_u
-                            .as_ref()
-                            .expect("U texture missing for bind group")
-                            .create_view(&Default::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: wgpu::BindingResource::TextureView(
-                        &self
-                            .tex_v
-                            .as_ref()
-                            .expect("V texture missing for bind group")
-                            .create_view(&Default::default()),
-                    ),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: wgpu::BindingResource::Sampler(&self.sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: self.u_info_buffer.as_entire_binding(),
-                },
-            ],
-        });
-        self.bind_group = Some(bind_group);
+        // The bind group only changes with the textures
+        if resized || self.bind_group.is_none() {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("yuv-bind-group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(
+                            &self
+                                .tex_y
+                                .as_ref()
+                                .expect("Y texture missing for bind group")
+                                .create_view(&Default::default()),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(
+                            &self
+                                .tex_u
+                                .as_ref()
+                                .expect("U texture missing for bind group")
+                                .create_view(&Default::default()),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::TextureView(
+                            &self
+                                .tex_v
+                                .as_ref()
+                                .expect("V texture missing for bind group")
+                                .create_view(&Default::default()),
+                        ),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: self.u_info_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            self.bind_group = Some(bind_group);
+        }
 
         // Execute the render pass to perform the YUV-to-RGB conversion
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("yuv-render-encoder"),
-        });
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("yuv-render-pass"),
@@ -473,96 +479,132 @@ impl GpuYuvRenderer {
     }
 }
 
-/// Uploads a single Y, U, or V plane to a `wgpu` texture, handling stride alignment.
-///
-/// `wgpu` requires that the `bytes_per_row` in `write_texture` be a multiple of 256.
-/// Video decoders often produce frames with a different stride (bytes per row).
+/// A GPU buffer a plane is staged in before being copied to its texture.
 ///
-/// This function checks if the source `stride` matches the required alignment. If not,
-/// it creates a temporary, correctly aligned buffer and copies the image data into it
-/// row-by-row before uploading to the GPU. This avoids an expensive copy when the
-/// strides already match.
-fn upload_plane(
-    logger: Arc<dyn LogSink>,
-    tex: &wgpu::Texture,
-    data: &[u8],
+/// `wgpu` requires that the `bytes_per_row` of a buffer-to-texture copy be a
+/// multiple of 256, while video decoders often produce frames with a
+/// different stride (bytes per row). The rows of each frame are written
+/// straight into the queue's staging memory at the aligned stride, so a
+/// plane costs a single copy whatever its source stride, and the buffer and
+/// its size are only recreated when the frame size changes.
+struct StagingBuffer {
+    buffer: wgpu::Buffer,
     width: u32,
     height: u32,
-    stride: usize,
-    queue: &wgpu::Queue,
-) {
-    let w = width as usize;
-    let h = height as usize;
-
-    // wgpu requires texture row alignment to be a multiple of 256 bytes.
-    let aligned_bpr = ((w as u32).div_ceil(256)) * 256;
-
-    sink_debug!(
-        logger,
-        "[UPLOAD] w={} h={} stride={} aligned_bpr={}",
-        w,
-        h,
-        stride,
-        aligned_bpr
-    );
-
-    // If the source stride happens to match the required alignment, we can copy directly.
-    // This avoids an expensive allocation and copy for every frame plane.
-    if stride as u32 == aligned_bpr {
-        queue.write_texture(
-            tex.as_image_copy(),
+    bytes_per_row: u32,
+}
+
+impl StagingBuffer {
+    fn new(device: &wgpu::Device, label: &str, width: u32, height: u32) -> Self {
+        let bytes_per_row =
+            width.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: u64::from(bytes_per_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            width,
+            height,
+            bytes_per_row,
+        }
+    }
+
+    const fn fits(&self, width: u32, height: u32) -> bool {
+        self.width == width && self.height == height
+    }
+
+    /// Stages a plane and records its copy to `tex` in `encoder`.
+    ///
+    /// `data` starts at the first pixel of the picture and may hold more
+    /// rows and wider ones than the picture (a coded size cropped for
+    /// display, or decoder padding); only the picture is uploaded.
+    fn upload(
+        &self,
+        logger: &Arc<dyn LogSink>,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        tex: &wgpu::Texture,
+        data: &[u8],
+        stride: usize,
+    ) {
+        sink_debug!(
+            logger,
+            "[UPLOAD] w={} h={} stride={} aligned_bpr={}",
+            self.width,
+            self.height,
+            stride,
+            self.bytes_per_row
+        );
+
+        let Some(size) = wgpu::BufferSize::new(self.buffer.size()) else {
+            return;
+        };
+        let Some(mut view) = queue.write_buffer_with(&self.buffer, 0, size) else {
+            return;
+        };
+        pack_rows(
+            &mut view,
+            self.bytes_per_row as usize,
             data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(stride as u32),
-                rows_per_image: Some(height),
+            stride,
+            self.width as usize,
+            self.height as usize,
+        );
+        drop(view);
+
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.bytes_per_row),
+                    rows_per_image: Some(self.height),
+                },
             },
+            tex.as_image_copy(),
             wgpu::Extent3d {
-                width,
-                height,
+                width: self.width,
+                height: self.height,
                 depth_or_array_layers: 1,
             },
         );
-        return;
     }
+}
 
-    // The stride from the decoder is not 256-byte aligned. We must copy the data
-    // row-by-row into a temporary, correctly aligned buffer before uploading to the GPU.
-    let mut aligned_data = Vec::with_capacity((aligned_bpr * height) as usize);
-    for row_idx in 0..h {
-        let src_start = row_idx * stride;
-        let src_end = src_start + w;
-
-        if src_end > data.len() {
-            // Source data is smaller than expected for this row.
-            // Avoid panicking by padding the rest of the row with black.
-            let valid_len = data.len().saturating_sub(src_start).max(0);
-            if valid_len > 0 {
-                aligned_data.extend_from_slice(&data[src_start..src_start + valid_len]);
-            }
-            aligned_data.extend(std::iter::repeat_n(0, w - valid_len));
-        } else {
-            // Copy the actual pixel data for the row.
-            aligned_data.extend_from_slice(&data[src_start..src_end]);
-        }
-
-        // Add padding to the end of the row to meet wgpu's alignment requirement.
-        let padding = aligned_bpr as usize - w;
-        aligned_data.extend(std::iter::repeat_n(0, padding));
+/// Copies the `width` x `height` picture of a plane with rows `stride` bytes
+/// apart into `dst`, with rows `bytes_per_row` apart. Rows the source is too
+/// short for are padded with black rather than read out of bounds.
+fn pack_rows(
+    dst: &mut [u8],
+    bytes_per_row: usize,
+    data: &[u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+) {
+    for (row, out) in dst.chunks_mut(bytes_per_row).take(height).enumerate() {
+        let out = &mut out[..width.min(out.len())];
+        let src = data.get(row * stride..).unwrap_or_default();
+        let valid = src.len().min(out.len());
+        out[..valid].copy_from_slice(&src[..valid]);
+        out[valid..].fill(0);
     }
+}
 
-    queue.write_texture(
-        tex.as_image_copy(),
-        &aligned_data,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(aligned_bpr),
-            rows_per_image: Some(height),
-        },
-        wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        },
-    );
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn rows_are_realigned_and_short_planes_padded() {
+        // 3x3 picture in rows of 5 bytes, the last row cut short
+        let data = [1, 2, 3, 9, 9, 4, 5, 6, 9, 9, 7];
+        let mut dst = [0xAA; 12];
+        pack_rows(&mut dst, 4, &data, 5, 3, 3);
+        assert_eq!(dst, [1, 2, 3, 0xAA, 4, 5, 6, 0xAA, 7, 0, 0, 0xAA]);
+    }
 }
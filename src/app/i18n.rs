@@ -0,0 +1,735 @@
+//! Translations of the GUI text.
+//!
+//! Messages are looked up by their English text, so the English catalog is
+//! the source itself and any message missing from a catalog is shown in
+//! English. Parameters are written `{name}` and filled in by
+//! [`Locale::trf`]. The language comes from `[UI] language` (`en`, `es`) or,
+//! when that is empty, from the system locale (`LC_ALL`, `LC_MESSAGES`,
+//! `LANG`).
+
+use std::{collections::HashMap, env, fmt::Display, sync::OnceLock};
+
+use crate::config::Config;
+
+/// A language the GUI is translated to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    pub const ALL: [Self; 2] = [Self::En, Self::Es];
+
+    /// Code used in `[UI] language`.
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Es => "es",
+        }
+    }
+
+    /// Name of the language, in that language.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Es => "Español",
+        }
+    }
+
+    /// Locale of a language tag such as `es`, `es-AR` or `es_AR.UTF-8`.
+    /// `None` for languages without a catalog.
+    #[must_use]
+    pub fn parse(tag: &str) -> Option<Self> {
+        let lang = tag
+            .trim()
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self::ALL.into_iter().find(|l| l.code() == lang)
+    }
+
+    /// Locale of the environment, English if it names none we have.
+    #[must_use]
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|v| !v.is_empty())
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or_default()
+    }
+
+    /// `[UI] language`, or the system locale when it is empty or unknown.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        config
+            .get_non_empty("UI", "language")
+            .and_then(Self::parse)
+            .unwrap_or_else(Self::system)
+    }
+
+    /// Translation of `msg` (its English text).
+    #[must_use]
+    pub fn tr(self, msg: &'static str) -> &'static str {
+        match self {
+            Self::En => msg,
+            Self::Es => {
+                static CATALOG: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+                CATALOG
+                    .get_or_init(|| ES.iter().copied().collect())
+                    .get(msg)
+                    .copied()
+                    .unwrap_or(msg)
+            }
+        }
+    }
+
+    /// Translation of `msg` with its `{name}` parameters filled in.
+    #[must_use]
+    pub fn trf(self, msg: &'static str, args: &[(&str, &dyn Display)]) -> String {
+        let mut out = self.tr(msg).to_owned();
+        for (name, value) in args {
+            out = out.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        out
+    }
+}
+
+/// Spanish catalog, by English text.
+const ES: &[(&str, &str)] = &[
+    ("Ready.", "Listo."),
+    (
+        "Please enter a signaling server address (host:port)",
+        "Ingresá la dirección del servidor de señalización (host:puerto)",
+    ),
+    ("Connecting to {addr}…", "Conectando a {addr}…"),
+    (
+        "Failed to connect to signaling server: {error}",
+        "No se pudo conectar al servidor de señalización: {error}",
+    ),
+    (
+        "Disconnected from signaling server.",
+        "Desconectado del servidor de señalización.",
+    ),
+    (
+        "Connected to signaling server.",
+        "Conectado al servidor de señalización.",
+    ),
+    ("Logged in as {username}", "Sesión iniciada como {username}"),
+    (
+        "Login failed with code {code}",
+        "El inicio de sesión falló con código {code}",
+    ),
+    (
+        "Registered {username}. You can now log in.",
+        "{username} registrado. Ya podés iniciar sesión.",
+    ),
+    (
+        "Registration failed with code {code}",
+        "El registro falló con código {code}",
+    ),
+    (
+        "Room created, share code {code}",
+        "Sala creada, compartí el código {code}",
+    ),
+    ("Joined room {code}", "Entraste a la sala {code}"),
+    (
+        "No room with code {room}",
+        "No hay ninguna sala con código {room}",
+    ),
+    ("Room {room} is full", "La sala {room} está llena"),
+    (
+        "Log in before joining a room",
+        "Iniciá sesión antes de entrar a una sala",
+    ),
+    (
+        "Could not join room {room} (code {code})",
+        "No se pudo entrar a la sala {room} (código {code})",
+    ),
+    (
+        "Could not join room {room}: {reason}",
+        "No se pudo entrar a la sala {room}: {reason}",
+    ),
+    (
+        "Login failed: {reason}",
+        "El inicio de sesión falló: {reason}",
+    ),
+    (
+        "Registration failed: {reason}",
+        "El registro falló: {reason}",
+    ),
+    ("user is already logged in", "el usuario ya inició sesión"),
+    (
+        "not authorized to log in",
+        "no tiene permiso para iniciar sesión",
+    ),
+    (
+        "invalid username or password",
+        "usuario o contraseña incorrectos",
+    ),
+    ("internal server error", "error interno del servidor"),
+    (
+        "username already taken",
+        "el nombre de usuario ya está en uso",
+    ),
+    ("invalid username", "nombre de usuario inválido"),
+    ("password is too weak", "la contraseña es demasiado débil"),
+    (
+        "registration is not supported",
+        "el servidor no admite registros",
+    ),
+    ("Incoming call from {from}", "Llamada entrante de {from}"),
+    (
+        "Received answer from {from}",
+        "Respuesta recibida de {from}",
+    ),
+    (
+        "Failed to apply ICE candidate from {from}: {error}",
+        "No se pudo aplicar el candidato ICE de {from}: {error}",
+    ),
+    (
+        "Failed to send signaling message: {error}",
+        "No se pudo enviar el mensaje de señalización: {error}",
+    ),
+    (
+        "Not connected to signaling server.",
+        "Sin conexión con el servidor de señalización.",
+    ),
+    (
+        "Please login before sending candidates.",
+        "Iniciá sesión antes de enviar candidatos.",
+    ),
+    (
+        "Finish or cancel the current call first.",
+        "Primero terminá o cancelá la llamada en curso.",
+    ),
+    (
+        "Please login before calling.",
+        "Iniciá sesión antes de llamar.",
+    ),
+    (
+        "Failed to create local SDP: {error}",
+        "No se pudo crear el SDP local: {error}",
+    ),
+    ("Local SDP is empty.", "El SDP local está vacío."),
+    ("Sent offer to {peer}", "Oferta enviada a {peer}"),
+    ("Answer not generated.", "No se generó la respuesta."),
+    ("Sent answer to {from}", "Respuesta enviada a {from}"),
+    (
+        "Failed to accept call: {error}",
+        "No se pudo aceptar la llamada: {error}",
+    ),
+    (
+        "Failed to apply re-offer: {error}",
+        "No se pudo aplicar la nueva oferta: {error}",
+    ),
+    (
+        "Renegotiation already in progress.",
+        "Ya hay una renegociación en curso.",
+    ),
+    (
+        "Failed to update hold state: {error}",
+        "No se pudo cambiar la espera: {error}",
+    ),
+    (
+        "Resuming call with {peer}",
+        "Retomando la llamada con {peer}",
+    ),
+    ("Call with {peer} on hold", "Llamada con {peer} en espera"),
+    (
+        "Sharing a second video with {peer}",
+        "Compartiendo un segundo video con {peer}",
+    ),
+    (
+        "Stopped sharing with {peer}",
+        "Se dejó de compartir con {peer}",
+    ),
+    (
+        "Failed to update tracks: {error}",
+        "No se pudieron actualizar las pistas: {error}",
+    ),
+    (
+        "Local OFFER created. Share it with the peer.",
+        "OFERTA local creada. Compartila con el par.",
+    ),
+    (
+        "Negotiation already in progress (have-local-offer).",
+        "Ya hay una negociación en curso (have-local-offer).",
+    ),
+    (
+        "Remote OFFER set → Local ANSWER created. Share it back.",
+        "OFERTA remota aplicada → RESPUESTA local creada. Devolvela.",
+    ),
+    ("Remote ANSWER set.", "RESPUESTA remota aplicada."),
+    ("Established.", "Establecida."),
+    ("Closed.", "Cerrada."),
+    ("Connection {state}.", "Conexión {state}."),
+    (
+        "ICE nominated. Press Start.",
+        "ICE nominado. Presioná Iniciar.",
+    ),
+    (
+        "File offer: {name} ({size})",
+        "Oferta de archivo: {name} ({size})",
+    ),
+    (
+        "Peer accepted file (id: {id}). Sending...",
+        "El par aceptó el archivo (id: {id}). Enviando...",
+    ),
+    (
+        "Peer rejected file (id: {id}).",
+        "El par rechazó el archivo (id: {id}).",
+    ),
+    (
+        "File transfer cancelled (id: {id}).",
+        "Transferencia cancelada (id: {id}).",
+    ),
+    (
+        "File transfer finished (sent).",
+        "Transferencia terminada (enviado).",
+    ),
+    (
+        "File transfer finished (received).",
+        "Transferencia terminada (recibido).",
+    ),
+    (
+        "Peer started sharing {label}",
+        "El par empezó a compartir {label}",
+    ),
+    ("Peer stopped sharing", "El par dejó de compartir"),
+    (
+        "Camera {camera_id} disconnected, sending placeholder",
+        "Cámara {camera_id} desconectada, enviando imagen de reemplazo",
+    ),
+    (
+        "Camera {camera_id} reconnected",
+        "Cámara {camera_id} reconectada",
+    ),
+    ("Configuration reloaded.", "Configuración recargada."),
+    (
+        "Configuration file has errors; not reloaded.",
+        "El archivo de configuración tiene errores; no se recargó.",
+    ),
+    ("File Transfer", "Transferencia de archivos"),
+    ("Debug State", "Estado de depuración"),
+    ("Path:", "Ruta:"),
+    ("Send File", "Enviar archivo"),
+    ("Preparing file...", "Preparando archivo..."),
+    ("Transfer in progress...", "Transferencia en curso..."),
+    ("Cancel", "Cancelar"),
+    (
+        "Connect to a peer to transfer files.",
+        "Conectate con un par para transferir archivos.",
+    ),
+    (
+        "Incoming file: {name} ({size} bytes)",
+        "Archivo entrante: {name} ({size} bytes)",
+    ),
+    ("Accept", "Aceptar"),
+    ("Reject", "Rechazar"),
+    (
+        "Sending {name}... {progress}%",
+        "Enviando {name}... {progress}%",
+    ),
+    (
+        "Receiving {name}... {progress}%",
+        "Recibiendo {name}... {progress}%",
+    ),
+    ("Camera View", "Vista de cámaras"),
+    ("Grid", "Cuadrícula"),
+    ("Speaker", "Orador"),
+    ("Keypad", "Teclado"),
+    ("Call controls:", "Controles de llamada:"),
+    ("Hang up", "Colgar"),
+    ("Waiting for video…", "Esperando video…"),
+    (
+        "Double-click or Esc to leave fullscreen",
+        "Doble clic o Esc para salir de pantalla completa",
+    ),
+    ("DTMF failed: {error}", "Falló el DTMF: {error}"),
+    ("Sent: {digits}", "Enviados: {digits}"),
+    ("● {who} speaking", "● {who} hablando"),
+    ("camera off", "cámara apagada"),
+    ("Keyboard shortcuts", "Atajos de teclado"),
+    (
+        "Hold to talk (push-to-talk mode)",
+        "Mantener para hablar (modo pulsar para hablar)",
+    ),
+    (
+        "Change them in the [Shortcuts] section of the configuration.",
+        "Se cambian en la sección [Shortcuts] de la configuración.",
+    ),
+    (
+        "Mute / unmute the microphone",
+        "Silenciar / activar el micrófono",
+    ),
+    ("Turn the camera off / on", "Apagar / encender la cámara"),
+    (
+        "Hang up, cancel or decline a call",
+        "Colgar, cancelar o rechazar una llamada",
+    ),
+    ("Accept an incoming call", "Aceptar una llamada entrante"),
+    ("Show this list", "Mostrar esta lista"),
+    ("Local video: {frame}", "Video local: {frame}"),
+    ("Remote video: {frame}", "Video remoto: {frame}"),
+    ("Signaling", "Señalización"),
+    ("Server address:", "Dirección del servidor:"),
+    ("Connect", "Conectar"),
+    ("Login", "Iniciar sesión"),
+    ("Username", "Usuario"),
+    ("Password", "Contraseña"),
+    ("Register", "Registrarse"),
+    ("Disconnect", "Desconectar"),
+    ("Refresh peers", "Actualizar pares"),
+    ("Available peers:", "Pares disponibles:"),
+    ("No peers online.", "No hay pares conectados."),
+    ("Available", "Disponible"),
+    ("Busy", "Ocupado"),
+    ("Call {peer}", "Llamar a {peer}"),
+    ("Peers", "Pares"),
+    ("Room", "Sala"),
+    ("History", "Historial"),
+    ("Contacts", "Contactos"),
+    ("Add contact", "Agregar contacto"),
+    ("No contacts yet.", "Todavía no tenés contactos."),
+    ("Offline", "Desconectado"),
+    ("Remove", "Quitar"),
+    ("Block", "Bloquear"),
+    ("Unblock", "Desbloquear"),
+    ("Blocked:", "Bloqueados:"),
+    (
+        "Refuse their calls and hide each from the other",
+        "Rechazar sus llamadas y ocultarse mutuamente",
+    ),
+    (
+        "Call to {peer} refused: {reason}",
+        "Llamada a {peer} rechazada: {reason}",
+    ),
+    (
+        "the user does not accept your calls",
+        "el usuario no acepta tus llamadas",
+    ),
+    (
+        "the server does not let you call this user",
+        "el servidor no te permite llamar a este usuario",
+    ),
+    ("Capacity:", "Capacidad:"),
+    ("Create room", "Crear sala"),
+    ("Code:", "Código:"),
+    ("Join by code", "Entrar con código"),
+    ("Room code:", "Código de la sala:"),
+    ("Copy", "Copiar"),
+    (
+        "Nobody else is here yet. Share the code to invite someone.",
+        "Todavía no hay nadie más. Compartí el código para invitar a alguien.",
+    ),
+    (
+        "You or they are in a call",
+        "Vos o el otro usuario están en una llamada",
+    ),
+    (
+        "Everyone (through the server)",
+        "Todos (a través del servidor)",
+    ),
+    ("Join the room call", "Unirse a la llamada de la sala"),
+    ("Leave room", "Salir de la sala"),
+    ("Left room {code}", "Saliste de la sala {code}"),
+    ("No calls yet.", "Todavía no hay llamadas."),
+    ("Call", "Llamar"),
+    (
+        "Peer is offline or busy",
+        "El par está desconectado u ocupado",
+    ),
+    ("Clear history", "Borrar historial"),
+    (
+        "Could not save call history: {error}",
+        "No se pudo guardar el historial de llamadas: {error}",
+    ),
+    ("completed", "completada"),
+    ("cancelled", "cancelada"),
+    ("rejected", "rechazada"),
+    ("declined", "rechazada por vos"),
+    ("missed", "perdida"),
+    ("failed", "fallida"),
+    ("just now", "recién"),
+    ("{n} min ago", "hace {n} min"),
+    ("{n} h ago", "hace {n} h"),
+    ("{n} d ago", "hace {n} d"),
+    ("No active calls.", "No hay llamadas activas."),
+    ("Calling {peer}…", "Llamando a {peer}…"),
+    ("Cancel outgoing call", "Cancelar llamada saliente"),
+    ("Decline", "Rechazar"),
+    ("In call with {peer}", "En llamada con {peer}"),
+    (
+        "In call with {peer} (on hold)",
+        "En llamada con {peer} (en espera)",
+    ),
+    (
+        "In call with {peer} (held by peer)",
+        "En llamada con {peer} (en espera por el par)",
+    ),
+    ("Resume", "Retomar"),
+    ("Hold", "En espera"),
+    ("Stop sharing", "Dejar de compartir"),
+    ("Share pattern", "Compartir patrón"),
+    ("Transfer", "Transferir"),
+    ("(audio only)", "(solo audio)"),
+    ("Peer does not support video", "El par no admite video"),
+    (
+        "{peer} supports none of our codecs",
+        "{peer} no admite ninguno de nuestros códecs",
+    ),
+    (
+        "Sent offer to {peer}; peer does not support video",
+        "Oferta enviada a {peer}; el par no admite video",
+    ),
+    ("transferred to {target}", "transferida a {target}"),
+    (
+        "{from} is transferring you to {target}",
+        "{from} te está transfiriendo a {target}",
+    ),
+    ("Start Connection", "Iniciar conexión"),
+    ("Failed to start: {error}", "No se pudo iniciar: {error}"),
+    ("End call", "Terminar llamada"),
+    ("Mute", "Silenciar"),
+    ("Unmute", "Activar micrófono"),
+    ("Start video", "Encender video"),
+    ("Stop video", "Apagar video"),
+    ("Push-to-talk ({key})", "Pulsar para hablar ({key})"),
+    ("Talking", "Hablando"),
+    ("You", "Vos"),
+    ("Peer", "Par"),
+    ("State: {state}", "Estado: {state}"),
+    ("Default", "Predeterminado"),
+    ("Mic", "Micrófono"),
+    ("Stop recording", "Detener grabación"),
+    (
+        "Failed to save recording: {error}",
+        "No se pudo guardar la grabación: {error}",
+    ),
+    ("Record", "Grabar"),
+    (
+        "Failed to start recording: {error}",
+        "No se pudo iniciar la grabación: {error}",
+    ),
+    ("Camera", "Cámara"),
+    ("Rescan", "Volver a buscar"),
+    ("Test video", "Video de prueba"),
+    ("Test tone", "Tono de prueba"),
+    ("Settings", "Ajustes"),
+    ("Network", "Red"),
+    ("Signaling server:", "Servidor de señalización:"),
+    (
+        "The server address is used on the next connect.",
+        "La dirección del servidor se usa en la próxima conexión.",
+    ),
+    ("Language", "Idioma"),
+    ("Appearance", "Apariencia"),
+    ("Theme", "Tema"),
+    ("System", "Sistema"),
+    ("Dark", "Oscuro"),
+    ("Light", "Claro"),
+    ("UI scale", "Escala de la interfaz"),
+    ("Quality", "Calidad"),
+    ("Low data mode", "Modo de bajo consumo de datos"),
+    (
+        "Caps video at a low bitrate and frame rate; audio changes with the next call.",
+        "Limita el video a una tasa de bits y de cuadros baja; el audio cambia en la próxima llamada.",
+    ),
+    ("low", "baja"),
+    ("medium", "media"),
+    ("high", "alta"),
+    ("Resolution", "Resolución"),
+    (
+        "Could not save settings: {error}",
+        "No se pudieron guardar los ajustes: {error}",
+    ),
+    ("Logs:", "Registro:"),
+    ("Search:", "Buscar:"),
+    ("Pause", "Pausar"),
+    ("Auto-scroll", "Desplazamiento automático"),
+    ("Clear", "Borrar"),
+    (
+        "{count} new lines while paused",
+        "{count} líneas nuevas en pausa",
+    ),
+    ("Network Health", "Estado de la red"),
+    ("Encoder Bitrate:", "Tasa de bits del codificador:"),
+    ("Unknown", "Desconocido"),
+    ("Round Trip Time (RTT):", "Tiempo de ida y vuelta (RTT):"),
+    ("Packet Loss:", "Pérdida de paquetes:"),
+    ("{loss}% ({packets} pkts)", "{loss}% ({packets} paq.)"),
+    ("Highest Seq Recv:", "Secuencia más alta recibida:"),
+    ("Status:", "Estado:"),
+    ("Waiting for RTCP reports...", "Esperando reportes RTCP..."),
+    (
+        "RTP Total: {packets} pkts / {mb} MB",
+        "RTP total: {packets} paq. / {mb} MB",
+    ),
+    ("Show graphs", "Mostrar gráficos"),
+    ("Call statistics", "Estadísticas de la llamada"),
+    ("Waiting for stats…", "Esperando estadísticas…"),
+    ("Export CSV", "Exportar CSV"),
+    ("Bitrate", "Tasa de bits"),
+    ("Loss", "Pérdida"),
+    (
+        "{metric}: {value} {unit} (max {max})",
+        "{metric}: {value} {unit} (máx. {max})",
+    ),
+    ("Pipeline latency (ms)", "Latencia del pipeline (ms)"),
+    ("Stage", "Etapa"),
+    ("Encode", "Codificación"),
+    ("Packetize", "Empaquetado"),
+    ("Send", "Envío"),
+    ("Depacketize", "Desempaquetado"),
+    ("Decode", "Decodificación"),
+    ("Render", "Renderizado"),
+    (
+        "Untrusted server certificate",
+        "Certificado del servidor no confiable",
+    ),
+    (
+        "The server at {addr} presented a certificate no trusted CA vouches for. Key fingerprint:",
+        "El servidor en {addr} presentó un certificado que ninguna CA de confianza respalda. Huella de la clave:",
+    ),
+    (
+        "Only trust it if the server administrator gave you the same fingerprint.",
+        "Confiá en él solo si el administrador del servidor te dio la misma huella.",
+    ),
+    ("Trust this server", "Confiar en este servidor"),
+    (
+        "Stats exported to {path}",
+        "Estadísticas exportadas a {path}",
+    ),
+    (
+        "Could not export stats: {error}",
+        "No se pudieron exportar las estadísticas: {error}",
+    ),
+    ("Call ended: {reason}", "Llamada terminada: {reason}"),
+    ("Call ended.", "Llamada terminada."),
+    ("Remote SDP processed.", "SDP remoto procesado."),
+    (
+        "Failed to set remote SDP: {error}",
+        "No se pudo aplicar el SDP remoto: {error}",
+    ),
+    ("Shortcuts", "Atajos"),
+    (
+        "Connect and log in to place a call.",
+        "Conectate e iniciá sesión para llamar.",
+    ),
+    ("Test call", "Llamada de prueba"),
+    ("End test call", "Terminar llamada de prueba"),
+    (
+        "Call yourself to check the camera, microphone and codecs",
+        "Llamate a vos mismo para probar la cámara, el micrófono y los códecs",
+    ),
+    (
+        "Test call: you should see and hear yourself.",
+        "Llamada de prueba: deberías verte y escucharte.",
+    ),
+    (
+        "Test call failed: {error}",
+        "Falló la llamada de prueba: {error}",
+    ),
+    ("High contrast", "Alto contraste"),
+    (
+        "Solid backgrounds, full-strength text and thick outlines",
+        "Fondos sólidos, texto a plena intensidad y bordes gruesos",
+    ),
+    ("Shortcut: {keys}", "Atajo: {keys}"),
+    (
+        "Call video. Double-click for fullscreen",
+        "Video de la llamada. Doble clic para pantalla completa",
+    ),
+    (
+        "Your camera. Drag to move",
+        "Tu cámara. Arrastrá para moverla",
+    ),
+    ("Dial tone {key}", "Tono {key}"),
+    (
+        "Sent {sent} MB, received {received} MB",
+        "Enviados {sent} MB, recibidos {received} MB",
+    ),
+    ("switch camera", "cambiar de cámara"),
+    ("change resolution", "cambiar la resolución"),
+    ("turn the light on", "encender la luz"),
+    ("turn the light off", "apagar la luz"),
+    ("{peer} accepted: {what}", "{peer} aceptó: {what}"),
+    (
+        "{peer} refused: {what} ({reason})",
+        "{peer} rechazó: {what} ({reason})",
+    ),
+    ("Camera control", "Control de cámara"),
+    (
+        "{peer} asks to control your camera: {what}.",
+        "{peer} pide controlar tu cámara: {what}.",
+    ),
+    ("Allow", "Permitir"),
+    ("Allow for this call", "Permitir durante esta llamada"),
+    ("Deny", "Denegar"),
+    ("Their camera", "Su cámara"),
+    ("Volume", "Volumen"),
+    ("Volume of {peer}", "Volumen de {peer}"),
+    ("Switch camera", "Cambiar cámara"),
+    ("Zoom in", "Acercar"),
+    ("Zoom out", "Alejar"),
+    ("Light on", "Encender luz"),
+    ("Light off", "Apagar luz"),
+    (
+        "Waiting for {peer} to allow it…",
+        "Esperando a que {peer} lo permita…",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn language_tags_pick_a_catalog() {
+        assert_eq!(Locale::parse("es"), Some(Locale::Es));
+        assert_eq!(Locale::parse("es_AR.UTF-8"), Some(Locale::Es));
+        assert_eq!(Locale::parse("ES-es"), Some(Locale::Es));
+        assert_eq!(Locale::parse("en_US"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr_FR"), None);
+        assert_eq!(Locale::parse(""), None);
+    }
+
+    #[test]
+    fn messages_fall_back_to_english() {
+        assert_eq!(Locale::Es.tr("Hang up"), "Colgar");
+        assert_eq!(Locale::Es.tr("not in any catalog"), "not in any catalog");
+        assert_eq!(
+            Locale::Es.trf("Call {peer}", &[("peer", &"bob")]),
+            "Llamar a bob"
+        );
+        assert_eq!(
+            Locale::En.trf("Call {peer}", &[("peer", &"bob")]),
+            "Call bob"
+        );
+    }
+
+    #[test]
+    fn translations_keep_their_parameters() {
+        let params = |s: &str| {
+            let mut names: Vec<String> = s
+                .split('{')
+                .skip(1)
+                .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_owned()))
+                .collect();
+            names.sort();
+            names
+        };
+        let mut seen = std::collections::HashSet::new();
+        for (en, es) in ES {
+            assert!(seen.insert(en), "duplicate entry {en:?}");
+            assert_eq!(params(en), params(es), "parameters of {en:?}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Kiosk mode, for unattended door intercoms and monitoring stations.
+//!
+//! With `[Kiosk] enabled = true` the GUI connects and logs in on its own,
+//! answers calls from the users in `allow` (declining everybody else),
+//! shows little more than the video, and reconnects after
+//! `restart_after_ms` whenever the signaling connection drops or the login
+//! fails.
+
+use std::time::Duration;
+
+use crate::config::Config;
+
+/// Environment variable that overrides `[Kiosk] password`.
+pub const PASSWORD_ENV: &str = "ROOMRTC_KIOSK_PASSWORD";
+
+const DEFAULT_RESTART_AFTER: Duration = Duration::from_secs(5);
+
+/// The kiosk settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kiosk {
+    pub username: String,
+    pub password: String,
+    /// Callers answered automatically; `*` answers anybody.
+    allow: Vec<String>,
+    /// Wait before reconnecting after a failure.
+    pub restart_after: Duration,
+}
+
+impl Kiosk {
+    /// The `[Kiosk]` section, or `None` if kiosk mode is off or has no
+    /// username to log in with.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.get_non_empty("Kiosk", "enabled") != Some("true") {
+            return None;
+        }
+        let username = config.get_non_empty("Kiosk", "username")?.trim().to_owned();
+        let password = std::env::var(PASSWORD_ENV)
+            .ok()
+            .or_else(|| config.get("Kiosk", "password").map(str::to_owned))
+            .unwrap_or_default();
+        let allow = config
+            .get("Kiosk", "allow")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|user| !user.is_empty())
+            .map(str::to_owned)
+            .collect();
+        let restart_after = config
+            .get_non_empty("Kiosk", "restart_after_ms")
+            .and_then(|v| v.parse().ok())
+            .map_or(DEFAULT_RESTART_AFTER, Duration::from_millis);
+        Some(Self {
+            username,
+            password,
+            allow,
+            restart_after,
+        })
+    }
+
+    /// Whether a call from `caller` is answered without asking.
+    #[must_use]
+    pub fn answers(&self, caller: &str) -> bool {
+        self.allow.iter().any(|user| user == "*" || user == caller)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn only_allowed_callers_are_answered() {
+        let mut config = Config::empty();
+        config.set("Kiosk", "username", "door");
+        assert_eq!(Kiosk::from_config(&config), None);
+
+        config.set("Kiosk", "enabled", "true");
+        config.set("Kiosk", "allow", "alice, bob,");
+        config.set("Kiosk", "restart_after_ms", "250");
+        let kiosk = Kiosk::from_config(&config).unwrap();
+        assert_eq!(kiosk.username, "door");
+        assert_eq!(kiosk.restart_after, Duration::from_millis(250));
+        assert!(kiosk.answers("bob"));
+        assert!(!kiosk.answers("mallory"));
+
+        config.set("Kiosk", "allow", "*");
+        assert!(Kiosk::from_config(&config).unwrap().answers("mallory"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Log lines shown in the GUI: a bounded ring buffer with level and text
+//! filters and a pause switch.
+//!
+//! Lines arrive from the logger's UI tap as `[Level] message`; the level is
+//! parsed back so it can be filtered and colored. Lines without one (the
+//! app's own notices) are always shown.
+
+use std::collections::VecDeque;
+
+use crate::log::log_level::LogLevel;
+
+/// Lines kept by default.
+pub const DEFAULT_CAPACITY: usize = 2_000;
+
+/// One line of the viewer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub level: Option<LogLevel>,
+    pub text: String,
+}
+
+impl LogLine {
+    /// Splits the `[Level] ` prefix off `line`, if it has one.
+    #[must_use]
+    pub fn parse(line: String) -> Self {
+        let level = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(level, _)| level.parse().ok());
+        Self { level, text: line }
+    }
+}
+
+/// The lines and how they are filtered.
+#[derive(Debug)]
+pub struct LogViewer {
+    lines: VecDeque<LogLine>,
+    /// Lines received while paused, shown on resume.
+    held: VecDeque<LogLine>,
+    capacity: usize,
+    /// Shown levels, indexed like [`LogLevel::ALL`].
+    shown: [bool; LogLevel::ALL.len()],
+    /// Case-insensitive text to look for; empty shows everything.
+    pub search: String,
+    paused: bool,
+    /// Keep the newest line in view.
+    pub auto_scroll: bool,
+}
+
+impl Default for LogViewer {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl LogViewer {
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            held: VecDeque::new(),
+            capacity: capacity.max(1),
+            shown: [true; LogLevel::ALL.len()],
+            search: String::new(),
+            paused: false,
+            auto_scroll: true,
+        }
+    }
+
+    /// Adds a line, dropping the oldest one when full.
+    pub fn push(&mut self, line: impl Into<String>) {
+        let line = LogLine::parse(line.into());
+        let buf = if self.paused {
+            &mut self.held
+        } else {
+            &mut self.lines
+        };
+        if buf.len() == self.capacity {
+            buf.pop_front();
+        }
+        buf.push_back(line);
+    }
+
+    #[must_use]
+    pub const fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Freezes the shown lines, or resumes and appends what arrived in the
+    /// meantime.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.lines.append(&mut self.held);
+            let excess = self.lines.len().saturating_sub(self.capacity);
+            self.lines.drain(..excess);
+        }
+    }
+
+    /// Lines received while paused.
+    #[must_use]
+    pub fn held(&self) -> usize {
+        self.held.len()
+    }
+
+    #[must_use]
+    pub fn shows(&self, level: LogLevel) -> bool {
+        self.shown[level as usize]
+    }
+
+    pub fn set_shown(&mut self, level: LogLevel, shown: bool) {
+        self.shown[level as usize] = shown;
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.held.clear();
+    }
+
+    /// Lines passing the level and text filters, oldest first.
+    pub fn visible(&self) -> impl Iterator<Item = &LogLine> {
+        let needle = self.search.trim().to_lowercase();
+        self.lines.iter().filter(move |line| {
+            line.level.is_none_or(|level| self.shows(level))
+                && (needle.is_empty() || line.text.to_lowercase().contains(&needle))
+        })
+    }
+
+    /// The visible lines, one per line, for the clipboard.
+    #[must_use]
+    pub fn visible_text(&self) -> String {
+        self.visible()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn filters_by_level_and_text() {
+        let mut viewer = LogViewer::with_capacity(3);
+        viewer.push("[Info] dropped");
+        viewer.push("[Info] call started");
+        viewer.push("[Warn] Call quality low");
+        viewer.push("(logger) dropped 100 background log lines");
+        assert_eq!(viewer.visible().count(), 3);
+
+        viewer.set_shown(LogLevel::Info, false);
+        viewer.search = "CALL".into();
+        assert_eq!(viewer.visible_text(), "[Warn] Call quality low");
+
+        viewer.search.clear();
+        assert_eq!(
+            viewer.visible_text(),
+            "[Warn] Call quality low\n(logger) dropped 100 background log lines"
+        );
+    }
+
+    #[test]
+    fn pause_holds_new_lines_until_resumed() {
+        let mut viewer = LogViewer::with_capacity(2);
+        viewer.push("[Info] one");
+        viewer.set_paused(true);
+        viewer.push("[Info] two");
+        viewer.push("[Error] three");
+        assert_eq!(viewer.visible_text(), "[Info] one");
+        assert_eq!(viewer.held(), 2);
+
+        viewer.set_paused(false);
+        assert_eq!(viewer.visible_text(), "[Info] two\n[Error] three");
+        assert_eq!(
+            viewer.visible().last().unwrap().level,
+            Some(LogLevel::Error)
+        );
+    }
+}
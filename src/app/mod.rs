@@ -1,10 +1,19 @@
 //! The `app` module contains the main GUI application logic, including the `RtcApp` struct,
 //! which is the main entry point for the `eframe` application. It also contains helper
-//! modules for managing connection state, GPU rendering, and GUI errors.
+//! modules for GPU rendering and GUI errors.
 
-pub mod conn_state;
+pub mod call_history;
+mod camera_preview;
 pub mod debug_yuv_to_rgb;
+pub mod frame_pacer;
 pub mod gpu_yuv_renderer;
 pub mod gui_error;
+pub mod i18n;
+pub mod kiosk;
+pub mod log_viewer;
 pub mod rtc_app;
+pub mod shortcuts;
+pub mod stats_history;
+pub mod theme;
 mod utils;
+pub mod video_grid;
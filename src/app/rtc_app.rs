@@ -1,35 +1,82 @@
 use super::{
-    conn_state::ConnState, gpu_yuv_renderer::GpuYuvRenderer, gui_error::GuiError,
-    utils::show_camera_in_ui,
+    call_history::{self, CallDirection, CallEnd, CallHistory, CallOutcome},
+    camera_preview::CameraPreview,
+    frame_pacer::{FramePacer, FramePacing},
+    gpu_yuv_renderer::GpuYuvRenderer,
+    gui_error::GuiError,
+    i18n::Locale,
+    kiosk::Kiosk,
+    log_viewer::LogViewer,
+    shortcuts::{Action, Shortcuts},
+    stats_history::{Metric, StatsHistory},
+    theme::{self, Status, Theme, status_color},
+    utils::{
+        fit_size, focus_if_unfocused, paint_video, pip_rect, show_camera_in_ui, submitted,
+        with_hint,
+    },
+    video_grid::{ActiveSpeaker, VideoLayout, grid_dims},
 };
 use crate::{
-    app::utils::{update_rgb_texture, update_yuv_texture},
-    config::Config,
+    app::utils::{mark_shown, update_rgb_texture, update_yuv_texture},
+    camera_manager::{
+        capture_settings::CaptureSettings,
+        devices::{CameraDevice, list_cameras},
+    },
+    config::{Config, watcher::ConfigWatcher},
     congestion_controller::NetworkMetrics,
     core::{
-        engine::Engine,
+        connection_state::PeerConnectionState,
+        engine::{Engine, PeerEvent, PeerId},
         events::EngineEvent::{
             self, Closed, Closing, Error, Established, IceNominated, Log, RtpIn, Status,
         },
+        loopback::{LOOPBACK_PEER, LoopbackPeer},
+        quality::{LowDataMode, QualityPreset},
     },
     log::{log_level::LogLevel, log_sink::LogSink, logger::Logger},
-    media_agent::video_frame::{VideoFrame, VideoFrameData},
-    signaling::protocol::{SignalingMsg, peer_status::PeerStatus},
-    signaling_client::{SignalingClient, SignalingEvent},
+    media_agent::{
+        audio_devices::{AudioDevice, configured_device, list_input_devices, list_output_devices},
+        audio_mixer::MAX_GAIN,
+        constants::DEFAULT_CAMERA_ID,
+        recorder::RecordStreams,
+        vad::PushToTalk,
+        video_frame::{VideoFrame, VideoFrameData},
+        video_track::{TrackId, VideoSource},
+    },
+    rtp::audio_level::AudioLevel,
+    settings::Settings,
+    sfu::SFU_USERNAME,
+    signaling::{
+        capture::SignalingRecorder,
+        errors::{CallErrorCode, JoinErrorCode, LoginErrorCode, RegisterErrorCode},
+        protocol::{
+            SignalingMsg, camera_request::CameraRequest, candidate_item::CandidateItem,
+            capabilities::Capabilities, peer_status::PeerStatus,
+        },
+    },
+    signaling_client::{
+        SignalingClient, SignalingEvent, device_label,
+        transport::TransportKind,
+        trust::{TrustOptions, UntrustedCertificate},
+    },
     sink_debug,
 };
 use eframe::{App, Frame, egui, egui_wgpu::RenderState};
 use std::{
-    collections::VecDeque,
-    io,
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
         mpsc::TrySendError,
     },
-    time::Instant,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+/// How long closing the window waits for the `Bye` to reach the server.
+const SIGNALING_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SignalingScreen {
     Connect,
@@ -52,9 +99,47 @@ enum CallFlow {
     },
     Active {
         peer: String,
+        hold: HoldState,
+        /// When the call was answered, for the elapsed timer.
+        since: Instant,
     },
 }
 
+/// Video of a remote peer other than the primary one (multi-party calls).
+#[derive(Default)]
+struct PeerTile {
+    texture: Option<(egui::TextureId, (u32, u32))>,
+    yuv_renderer: Option<GpuYuvRenderer>,
+    /// The frame in `texture`.
+    shown: Option<VideoFrame>,
+}
+
+/// Tabs of the Home screen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum HomeTab {
+    #[default]
+    Peers,
+    Room,
+    History,
+    Contacts,
+}
+
+/// Signaling session ("room") we created or joined by its code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Room {
+    session_id: String,
+    code: String,
+    /// Other members, in the order they joined.
+    members: Vec<String>,
+}
+
+/// Which side(s) put an active call on hold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct HoldState {
+    local: bool,
+    remote: bool,
+}
+
 #[derive(Debug, Clone)]
 enum FileTransferState {
     Idle,
@@ -94,11 +179,11 @@ pub struct RtcApp {
     has_remote_description: bool,
     has_local_description: bool,
     is_local_offerer: bool,
-    conn_state: ConnState,
+    conn_state: PeerConnectionState,
 
     // UI log
     logger: Logger,
-    ui_logs: VecDeque<String>,
+    log_viewer: LogViewer,
     bg_dropped: usize,
 
     // RTP summaries
@@ -116,22 +201,53 @@ pub struct RtcApp {
     register_username: String,
     register_password: String,
     peers_online: Vec<(String, PeerStatus)>,
+    /// What each online peer answered to our `CapabilityQuery`.
+    peer_caps: HashMap<String, Capabilities>,
+    /// Our contact and block lists, as the server last sent them.
+    contacts: Vec<String>,
+    blocked: Vec<String>,
+    contact_input: String,
+    /// User our call was transferred to: its offer is accepted on arrival.
+    pending_transfer: Option<String>,
     current_username: Option<String>,
     signaling_error: Option<String>,
+    /// How the signaling server certificate is trusted.
+    signaling_trust: TrustOptions,
+    /// Server certificate the user is asked to trust, with its address.
+    untrusted_server: Option<(String, UntrustedCertificate)>,
     call_flow: CallFlow,
     next_txn_id: u64,
+    /// Extra test-pattern track shared in the active call.
+    shared_track: Option<TrackId>,
+    /// The far end of a test call to ourselves.
+    loopback: Option<LoopbackPeer>,
 
     // Renderers and textures
     local_camera_texture: Option<(egui::TextureId, (u32, u32))>,
     remote_camera_texture: Option<(egui::TextureId, (u32, u32))>,
+    /// The frames in the camera textures, so unchanged ones are not uploaded
+    /// again.
+    local_shown: Option<VideoFrame>,
+    remote_shown: Option<VideoFrame>,
+    /// Remote frames waiting for their display time (`[UI] frame_pacing`).
+    remote_pacer: FramePacer,
+    frame_pacing: FramePacing,
 
     local_yuv_renderer: Option<GpuYuvRenderer>,
     remote_yuv_renderer: Option<GpuYuvRenderer>,
 
     config: Arc<Config>,
+    /// Reloads the configuration file when it changes, if watched.
+    config_watcher: Option<ConfigWatcher>,
     //Network Metrics
     last_metrics: Option<NetworkMetrics>,
     current_bitrate: Option<u32>,
+    /// Graphed history of the call's stats, sampled every second.
+    stats_history: StatsHistory,
+    stats_last_sample: Instant,
+    show_stats_overlay: bool,
+    /// Bytes sent and received on the call's sockets, as of the last sample.
+    call_traffic: (u64, u64),
 
     // File Transfer
     sending_files: Arc<AtomicBool>,
@@ -140,15 +256,103 @@ pub struct RtcApp {
     file_path_input: String,
 
     is_muted: bool,
+    /// Local camera turned off with "Stop video".
+    is_video_muted: bool,
+    /// The peer's camera is off, as reported by the engine.
+    remote_video_muted: bool,
+    /// Selected bandwidth preset (`None`: the configured `max_bitrate`).
+    quality: Option<QualityPreset>,
+    /// Lower caps, frame rate and audio codec for constrained links.
+    low_data_mode: bool,
+    /// Devices found by the last scan (probing opens each camera, so this is
+    /// only refreshed on demand).
+    cameras: Vec<CameraDevice>,
+    /// Camera picked in the dropdown (`None`: the configured one).
+    selected_camera: Option<i32>,
+    audio_inputs: Vec<AudioDevice>,
+    audio_outputs: Vec<AudioDevice>,
+    /// Microphone and speaker picked in the dropdowns (`None`: default).
+    selected_mic: Option<String>,
+    selected_speaker: Option<String>,
+    /// Push-to-talk mode, its hotkey and whether the key is currently held.
+    push_to_talk: bool,
+    push_to_talk_key: egui::Key,
+    push_to_talk_held: bool,
+    /// Voice activity reported by the engine, for the speaking indicators.
+    local_speaking: bool,
+    remote_speaking: bool,
+    /// Latest audio level the peer sent along with its audio.
+    remote_audio_level: Option<AudioLevel>,
+    /// Digits sent with the keypad during the current call.
+    dtmf_sent: String,
+    /// The peer's requests to control our camera, waiting for the user.
+    camera_requests: VecDeque<CameraRequest>,
+    /// Whether the peer may control our camera for the rest of the call.
+    camera_control_allowed: bool,
+    /// Zoom level last asked of the peer's camera.
+    remote_zoom: u16,
+    /// Requested capture format (resolution picked in Settings).
+    capture_settings: CaptureSettings,
+    /// Where the choices made in Settings are saved.
+    settings: Settings,
+    show_settings: bool,
+    /// Past calls, saved next to the settings.
+    call_history: CallHistory,
+    home_tab: HomeTab,
+    /// Room we are in, the code typed to join one (kept until the server
+    /// answers) and the capacity of rooms we create.
+    room: Option<Room>,
+    room_code_input: String,
+    pending_room_code: Option<String>,
+    room_capacity: u8,
+    /// Camera preview while Settings is open outside of a call.
+    camera_preview: Option<CameraPreview>,
+    preview_texture: Option<(egui::TextureId, (u32, u32))>,
+    /// Position of the local picture-in-picture tile, (1, 1) bottom-right.
+    pip_anchor: egui::Vec2,
+    video_fullscreen: bool,
+    /// Video of the other peers of a multi-party call, and how it is laid out.
+    peer_tiles: HashMap<PeerId, PeerTile>,
+    video_layout: VideoLayout,
+    active_speaker: ActiveSpeaker,
+    /// Call control hotkeys and their reference dialog.
+    shortcuts: Shortcuts,
+    show_shortcuts: bool,
+    /// Language of the GUI text.
+    locale: Locale,
+    /// Color theme, contrast and zoom factor picked in Settings.
+    theme: Theme,
+    high_contrast: bool,
+    ui_scale: f32,
+    /// The (dark, high contrast) pair the visuals were last set for.
+    applied_visuals: Option<(bool, bool)>,
+    /// Unattended mode, and when it next (re)connects.
+    kiosk: Option<Kiosk>,
+    kiosk_restart_at: Option<Instant>,
 }
 
 impl RtcApp {
     const HEADER_TITLE: &str = "RoomRTC • SDP Messenger";
     const CAMERAS_WINDOW_WIDTH: f32 = 800.0;
     const CAMERAS_WINDOW_HEIGHT: f32 = 400.0;
-    const LOCAL_CAMERA_SIZE: f32 = 400.0;
-    const REMOTE_CAMERA_SIZE: f32 = 400.0;
+    /// Room left under the video for indicators, keypad and controls.
+    const CALL_CONTROLS_HEIGHT: f32 = 90.0;
+    const MIN_STAGE_HEIGHT: f32 = 120.0;
+    /// Largest share of the stage the local tile may take, and its margin.
+    const PIP_FRACTION: f32 = 0.25;
+    const PIP_MARGIN: f32 = 8.0;
+    /// Height share of the strip under the active speaker.
+    const SPEAKER_STRIP_FRACTION: f32 = 0.25;
     const SERVER_ADDR: &str = "127.0.0.1:5005";
+    const DEFAULT_ROOM_CAPACITY: u8 = 4;
+    const MAX_ROOM_CAPACITY: u8 = 8;
+    const PREVIEW_SIZE: f32 = 240.0;
+    const STATS_GRAPH_HEIGHT: f32 = 48.0;
+    const STATS_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+    /// Capture resolutions offered in Settings.
+    const RESOLUTIONS: [(u32, u32); 3] = [(640, 480), (1280, 720), (1920, 1080)];
+    /// Zoom change per click on the peer's camera, in its driver's units.
+    const ZOOM_STEP: u16 = 10;
 
     /// Creates a new `RtcApp`.
     ///
@@ -156,14 +360,20 @@ impl RtcApp {
     ///
     /// * `cc` - The eframe creation context.
     /// * `config` - The application configuration.
+    /// * `settings` - Saved user choices (already applied to `config`); the
+    ///   Settings window writes to it.
     #[must_use]
-    pub fn new(cc: &eframe::CreationContext<'_>, config: Arc<Config>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, config: Arc<Config>, settings: Settings) -> Self {
         let logger = Logger::start_client(4096, 256, 50, config.clone());
         let logger_handle = Arc::new(logger.handle());
 
         let server_addr_input = config
             .get_non_empty_or_default("Signaling", "server_address", Self::SERVER_ADDR)
             .to_string();
+        let login_username = config.get("Signaling", "username").unwrap_or("").to_owned();
+        let call_history = settings.path().map_or_else(CallHistory::in_memory, |path| {
+            CallHistory::load(path.with_extension("history"))
+        });
 
         let (local_yuv_renderer, remote_yuv_renderer) = cc.wgpu_render_state.as_ref().map_or_else(
             || (None, None),
@@ -184,12 +394,34 @@ impl RtcApp {
 
         let sending_files = Arc::new(AtomicBool::new(false));
         let receiving_files = Arc::new(AtomicBool::new(false));
+        let quality = QualityPreset::from_config(&config);
+        let low_data_mode = LowDataMode::is_enabled(&config);
+        let capture_settings = CaptureSettings::from_config(&config);
+        let config_camera = config.get("Media", "camera").and_then(|s| s.parse().ok());
+        let selected_mic = configured_device(&config, "audio_input");
+        let selected_speaker = configured_device(&config, "audio_output");
+        let push_to_talk = PushToTalk::from_config(&config).is_enabled();
+        let push_to_talk_key = config
+            .get("Media", "push_to_talk_key")
+            .and_then(|name| egui::Key::from_name(name.trim()))
+            .unwrap_or(egui::Key::Space);
+        let show_settings = config.get("UI", "settings_open") == Some("true");
+        let shortcuts = Shortcuts::from_config(&config);
 
-        Self {
+        let locale = Locale::from_config(&config);
+        let theme = Theme::from_config(&config);
+        let high_contrast = theme::high_contrast_from_config(&config);
+        let kiosk = Kiosk::from_config(&config);
+        let ui_scale = theme::scale_from_config(&config);
+        cc.egui_ctx.set_zoom_factor(ui_scale);
+        let mut style = (*cc.egui_ctx.style()).clone();
+        theme::enlarge_hit_targets(&mut style);
+        cc.egui_ctx.set_style(style);
+        let mut app = Self {
             remote_sdp_text: String::new(),
             local_sdp_text: String::new(),
             pending_remote_sdp: None,
-            status_line: "Ready.".into(),
+            status_line: locale.tr("Ready.").into(),
             engine: Engine::new(
                 logger_handle,
                 config.clone(),
@@ -199,46 +431,136 @@ impl RtcApp {
             has_remote_description: false,
             has_local_description: false,
             is_local_offerer: false,
-            conn_state: ConnState::Idle,
+            conn_state: PeerConnectionState::New,
             logger,
-            ui_logs: VecDeque::with_capacity(256),
+            log_viewer: LogViewer::default(),
             bg_dropped: 0,
             rtp_pkts: 0,
             rtp_bytes: 0,
             rtp_last_report: Instant::now(),
             local_camera_texture: None,
             remote_camera_texture: None,
+            local_shown: None,
+            remote_shown: None,
+            remote_pacer: FramePacer::from_config(&config),
+            frame_pacing: FramePacing::from_config(&config),
             signaling_client: None,
             signaling_screen: SignalingScreen::Connect,
             server_addr_input,
-            login_username: String::new(),
+            login_username,
             login_password: String::new(),
             register_username: String::new(),
             register_password: String::new(),
             peers_online: Vec::new(),
+            peer_caps: HashMap::new(),
+            contacts: Vec::new(),
+            blocked: Vec::new(),
+            contact_input: String::new(),
+            pending_transfer: None,
             current_username: None,
             signaling_error: None,
+            signaling_trust: TrustOptions::from_config(&config),
+            untrusted_server: None,
             call_flow: CallFlow::Idle,
             next_txn_id: 1,
+            shared_track: None,
+            loopback: None,
             local_yuv_renderer,
             remote_yuv_renderer,
             config,
+            config_watcher: None,
             last_metrics: None,
             current_bitrate: None,
+            stats_history: StatsHistory::default(),
+            stats_last_sample: Instant::now(),
+            call_traffic: (0, 0),
+            show_stats_overlay: false,
             sending_files,
             receiving_files,
             file_transfer_state: FileTransferState::Idle,
             file_path_input: String::new(),
             is_muted: false,
+            is_video_muted: false,
+            remote_video_muted: false,
+            quality,
+            low_data_mode,
+            cameras: list_cameras(),
+            selected_camera: config_camera,
+            audio_inputs: list_input_devices(),
+            audio_outputs: list_output_devices(),
+            selected_mic,
+            selected_speaker,
+            push_to_talk,
+            push_to_talk_key,
+            push_to_talk_held: false,
+            local_speaking: false,
+            remote_speaking: false,
+            remote_audio_level: None,
+            dtmf_sent: String::new(),
+            camera_requests: VecDeque::new(),
+            camera_control_allowed: false,
+            remote_zoom: 0,
+            capture_settings,
+            settings,
+            show_settings,
+            call_history,
+            home_tab: HomeTab::default(),
+            room: None,
+            room_code_input: String::new(),
+            pending_room_code: None,
+            room_capacity: Self::DEFAULT_ROOM_CAPACITY,
+            camera_preview: None,
+            preview_texture: None,
+            pip_anchor: egui::Vec2::splat(1.0),
+            video_fullscreen: false,
+            peer_tiles: HashMap::new(),
+            video_layout: VideoLayout::default(),
+            active_speaker: ActiveSpeaker::new(),
+            shortcuts,
+            show_shortcuts: false,
+            locale,
+            theme,
+            high_contrast,
+            ui_scale,
+            applied_visuals: None,
+            kiosk_restart_at: kiosk.as_ref().map(|_| Instant::now()),
+            kiosk,
+        };
+        for binding in app.shortcuts.invalid.clone() {
+            app.push_ui_log(format!("Invalid shortcut {binding}, using the default"));
         }
+        app
     }
 
-    fn push_ui_log<T: Into<String>>(&mut self, s: T) {
-        // Only keep a small tail in the UI
-        if self.ui_logs.len() == 256 {
-            self.ui_logs.pop_front();
+    /// Applies the configuration reloads of `watcher` while the app runs.
+    pub fn watch_config(&mut self, watcher: ConfigWatcher) {
+        self.config_watcher = Some(watcher);
+    }
+
+    /// Hands a reloaded configuration to the engine; it comes back as
+    /// `ConfigUpdated`. A file that failed to load only reaches the log.
+    fn poll_config_watcher(&mut self) {
+        let Some(result) = self
+            .config_watcher
+            .as_ref()
+            .and_then(ConfigWatcher::try_recv)
+        else {
+            return;
+        };
+        match result {
+            Ok(config) => self.engine.update_config(Arc::new(config)),
+            Err(e) => {
+                self.push_ui_log(format!("Configuration not reloaded: {e}"));
+                self.status_line = self
+                    .locale
+                    .tr("Configuration file has errors; not reloaded.")
+                    .into();
+            }
         }
-        self.ui_logs.push_back(s.into());
+    }
+
+    fn push_ui_log<T: Into<String>>(&mut self, s: T) {
+        self.log_viewer.push(s);
     }
 
     fn background_log<L: Into<String>>(&mut self, level: LogLevel, text: L) {
@@ -285,10 +607,27 @@ impl RtcApp {
     fn connect_to_signaling(&mut self) {
         let log_sink = Arc::new(self.logger.handle());
 
+        // Transport chosen at runtime; TLS unless the config asks otherwise.
+        let kind = match self
+            .config
+            .get_non_empty("Signaling", "transport")
+            .map(str::parse::<TransportKind>)
+        {
+            Some(Ok(kind)) => kind,
+            Some(Err(e)) => {
+                self.push_ui_log(format!("{e}, falling back to TLS"));
+                TransportKind::Tls
+            }
+            None => TransportKind::default(),
+        };
+
         // Trim and basic sanity check
-        let addr = self.server_addr_input.trim();
+        let addr = self.server_addr_input.trim().to_owned();
         if addr.is_empty() {
-            let msg = "Please enter a signaling server address (host:port)".to_string();
+            let msg = self
+                .locale
+                .tr("Please enter a signaling server address (host:port)")
+                .to_owned();
             self.signaling_error = Some(msg.clone());
             self.push_ui_log(msg);
             return;
@@ -301,34 +640,144 @@ impl RtcApp {
             self.config
                 .get_non_empty_or_default("Signaling", "tls_domain", "signal.internal");
 
-        // Build TLS config + connect over TLS, handling errors explicitly (no `?`).
-        let res: io::Result<SignalingClient> =
-            SignalingClient::default_tls_config().and_then(|tls_cfg| {
-                // `addr` is "host:port", `domain` is the bare host for SNI
-                SignalingClient::connect_tls(addr, domain, tls_cfg, log_sink.clone())
-            });
+        // `addr` is "host:port", `domain` is the bare host for SNI
+        let res: io::Result<SignalingClient> = SignalingClient::connect_with(
+            kind,
+            &addr,
+            domain,
+            &self.signaling_trust,
+            SignalingRecorder::from_config(&self.config, log_sink.as_ref()),
+            log_sink.clone(),
+        );
 
         match res {
             Ok(client) => {
                 self.signaling_client = Some(client);
                 self.signaling_screen = SignalingScreen::Login;
                 self.signaling_error = None;
-                self.status_line = format!("Connecting to {addr}…");
+                self.status_line = self.locale.trf("Connecting to {addr}…", &[("addr", &addr)]);
+                // Offered again on the next launch
+                self.save_setting("Signaling", "server_address", addr);
             }
             Err(e) => {
-                let msg = format!("Failed to connect to signaling server: {e}");
+                let msg = self.locale.trf(
+                    "Failed to connect to signaling server: {error}",
+                    &[("error", &e)],
+                );
                 self.signaling_error = Some(msg.clone());
                 self.push_ui_log(msg);
+                // Pinned keys are never overridden from the GUI
+                if self.signaling_trust.trust_on_first_use
+                    && self.signaling_trust.pins.is_empty()
+                    && let Some(untrusted) = UntrustedCertificate::from_io(&e)
+                {
+                    self.untrusted_server = Some((addr, untrusted.clone()));
+                }
             }
         }
     }
 
+    /// Asks whether to trust a server certificate no CA vouches for, and
+    /// reconnects if so.
+    fn render_untrusted_server_window(&mut self, ctx: &egui::Context) {
+        let Some((addr, untrusted)) = &self.untrusted_server else {
+            return;
+        };
+        let mut trust = false;
+        let mut cancel = false;
+        egui::Window::new(self.locale.tr("Untrusted server certificate"))
+            .id(egui::Id::new("untrusted_server_window"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(self.locale.trf(
+                    "The server at {addr} presented a certificate no trusted CA vouches for. Key fingerprint:",
+                    &[("addr", addr)],
+                ));
+                ui.monospace(&untrusted.fingerprint);
+                ui.weak(&untrusted.reason);
+                ui.label(self.locale.tr(
+                    "Only trust it if the server administrator gave you the same fingerprint.",
+                ));
+                ui.horizontal(|ui| {
+                    trust = ui.button(self.locale.tr("Trust this server")).clicked();
+                    cancel = ui.button(self.locale.tr("Cancel")).clicked();
+                });
+            });
+        if trust && let Some((_, untrusted)) = self.untrusted_server.take() {
+            self.signaling_trust.accept(&untrusted.fingerprint);
+            let accepted = self.signaling_trust.accepted_setting();
+            self.save_setting("TLS", "signaling_accepted_keys", accepted);
+            self.connect_to_signaling();
+        } else if cancel {
+            self.untrusted_server = None;
+        }
+    }
+
     fn disconnect_from_signaling(&mut self) {
         if let Some(client) = &self.signaling_client {
             client.disconnect();
         }
         self.clear_signaling_state();
-        self.status_line = "Disconnected from signaling server.".into();
+        self.status_line = self.locale.tr("Disconnected from signaling server.").into();
+    }
+
+    /// Reconnects a kiosk after its restart delay.
+    fn schedule_kiosk_restart(&mut self) {
+        if let Some(kiosk) = &self.kiosk {
+            self.kiosk_restart_at = Some(Instant::now() + kiosk.restart_after);
+        }
+    }
+
+    /// Connects a kiosk once its restart time comes; another attempt is
+    /// scheduled if this one fails.
+    fn poll_kiosk_restart(&mut self, ctx: &egui::Context) {
+        let Some(at) = self.kiosk_restart_at else {
+            return;
+        };
+        let now = Instant::now();
+        if now < at {
+            ctx.request_repaint_after(at - now);
+            return;
+        }
+        self.kiosk_restart_at = None;
+        if self.signaling_client.is_none() {
+            self.push_ui_log("Kiosk: connecting to the signaling server");
+            self.connect_to_signaling();
+            if self.signaling_client.is_none() {
+                self.schedule_kiosk_restart();
+            }
+        }
+    }
+
+    /// What a kiosk shows: who it is, the call and its video.
+    fn render_kiosk_screen(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if let Some(kiosk) = &self.kiosk {
+            ui.heading(&kiosk.username);
+        }
+        match self.call_flow.clone() {
+            CallFlow::Active { peer, .. } => {
+                ui.horizontal(|ui| {
+                    ui.label(self.locale.trf("In call with {peer}", &[("peer", &peer)]));
+                    let hang_up = egui::Button::new(self.locale.tr("Hang up"))
+                        .min_size(egui::vec2(120.0, 2.0 * theme::MIN_HIT_TARGET));
+                    if ui.add(hang_up).clicked() {
+                        self.teardown_call(Some("hangup".into()), true);
+                    }
+                });
+            }
+            CallFlow::Idle => {
+                ui.label(self.locale.tr("No active calls."));
+            }
+            CallFlow::Dialing { .. } | CallFlow::Incoming { .. } => {}
+        }
+        self.render_status_line(ui);
+        let have_video =
+            self.local_camera_texture.is_some() || self.remote_camera_texture.is_some();
+        if self.conn_state.is_connected() || have_video {
+            let stage = ui.available_size().max(egui::vec2(1.0, 1.0));
+            self.render_video_stage(ctx, ui, stage);
+        }
     }
 
     fn clear_signaling_state(&mut self) {
@@ -336,6 +785,13 @@ impl RtcApp {
         self.signaling_screen = SignalingScreen::Connect;
         self.current_username = None;
         self.peers_online.clear();
+        self.peer_caps.clear();
+        self.contacts.clear();
+        self.blocked.clear();
+        self.pending_transfer = None;
+        self.room = None;
+        self.pending_room_code = None;
+        self.finish_call_record(CallEnd::Local);
         self.call_flow = CallFlow::Idle;
     }
 
@@ -357,17 +813,39 @@ impl RtcApp {
     fn handle_signaling_event(&mut self, event: SignalingEvent) {
         match event {
             SignalingEvent::Connected => {
-                self.status_line = "Connected to signaling server.".into();
+                self.status_line = self.locale.tr("Connected to signaling server.").into();
+                if let Some(kiosk) = &self.kiosk {
+                    let login = SignalingMsg::Login {
+                        username: kiosk.username.clone(),
+                        password: kiosk.password.clone(),
+                    };
+                    let _ = self.send_signaling(login);
+                }
             }
             SignalingEvent::Disconnected => {
                 self.push_ui_log("Signaling server disconnected.");
                 self.clear_signaling_state();
+                self.schedule_kiosk_restart();
             }
             SignalingEvent::Error(err) => {
                 self.signaling_error = Some(err.clone());
                 self.push_ui_log(format!("Signaling error: {err}"));
             }
             SignalingEvent::ServerMsg(msg) => self.handle_signaling_server_msg(msg),
+            SignalingEvent::CallFailed { peer, txn_id } => {
+                self.push_ui_log(format!(
+                    "No ACK from {peer} for txn_id={txn_id}, giving up on the call."
+                ));
+                let ours = match &self.call_flow {
+                    CallFlow::Dialing { peer: p, txn_id: t } => *p == peer && *t == txn_id,
+                    CallFlow::Active { peer: p, .. } => *p == peer,
+                    _ => false,
+                };
+                if ours {
+                    self.finish_call_record(CallEnd::Failed);
+                    self.teardown_call(Some(format!("{peer} did not respond")), true);
+                }
+            }
         }
     }
 
@@ -377,30 +855,221 @@ impl RtcApp {
             SignalingMsg::LoginOk { username } => {
                 self.current_username = Some(username.clone());
                 self.signaling_screen = SignalingScreen::Home;
-                self.status_line = format!("Logged in as {username}");
+                self.status_line = self
+                    .locale
+                    .trf("Logged in as {username}", &[("username", &username)]);
                 self.login_password.clear();
+                self.engine.set_local_user(&username);
                 self.request_peer_list();
+                let _ = self.send_signaling(SignalingMsg::ListContacts);
+                let label = device_label(&self.config);
+                let _ = self.send_signaling(SignalingMsg::SetDevice { label });
+                self.save_setting("Signaling", "username", username);
             }
-            SignalingMsg::LoginErr { code } => {
-                let msg = format!("Login failed with code {code}");
+            SignalingMsg::LoginErr { code, reason } => {
+                let reason = LoginErrorCode::from_u16(code)
+                    .map(|c| self.locale.tr(c.reason()).to_owned())
+                    .or(reason);
+                let msg = match reason {
+                    Some(reason) => self
+                        .locale
+                        .trf("Login failed: {reason}", &[("reason", &reason)]),
+                    None => self
+                        .locale
+                        .trf("Login failed with code {code}", &[("code", &code)]),
+                };
                 self.signaling_error = Some(msg.clone());
                 self.push_ui_log(msg);
+                if self.kiosk.is_some() {
+                    self.disconnect_from_signaling();
+                    self.schedule_kiosk_restart();
+                }
             }
             SignalingMsg::RegisterOk { username } => {
-                self.status_line = format!("Registered {username}. You can now log in.");
+                self.status_line = self.locale.trf(
+                    "Registered {username}. You can now log in.",
+                    &[("username", &username)],
+                );
                 self.login_username = username;
             }
-            SignalingMsg::RegisterErr { code } => {
-                let msg = format!("Registration failed with code {code}");
+            SignalingMsg::RegisterErr { code, reason } => {
+                let reason = RegisterErrorCode::from_u16(code)
+                    .map(|c| self.locale.tr(c.reason()).to_owned())
+                    .or(reason);
+                let msg = match reason {
+                    Some(reason) => self
+                        .locale
+                        .trf("Registration failed: {reason}", &[("reason", &reason)]),
+                    None => self
+                        .locale
+                        .trf("Registration failed with code {code}", &[("code", &code)]),
+                };
                 self.signaling_error = Some(msg.clone());
                 self.push_ui_log(msg);
             }
             SignalingMsg::PeersOnline { peers } => {
+                self.peer_caps
+                    .retain(|user, _| peers.iter().any(|(p, _)| p == user));
+                let me = self.current_username.clone().unwrap_or_default();
+                for (peer, _) in &peers {
+                    if !self.peer_caps.contains_key(peer) {
+                        let _ = self.send_signaling(SignalingMsg::CapabilityQuery {
+                            from: me.clone(),
+                            to: peer.clone(),
+                        });
+                    }
+                }
                 self.peers_online = peers;
             }
+            SignalingMsg::CapabilityQuery { from, .. } => {
+                let reply = SignalingMsg::CapabilityReply {
+                    from: self.current_username.clone().unwrap_or_default(),
+                    to: from,
+                    caps: self.engine.local_capabilities(),
+                };
+                let _ = self.send_signaling(reply);
+            }
+            SignalingMsg::CapabilityReply { from, caps, .. } => {
+                self.peer_caps.insert(from, caps);
+            }
+            SignalingMsg::CameraControl { from, request, .. } => {
+                // Only the peer we are in a call with, and only if we say so
+                if !matches!(&self.call_flow, CallFlow::Active { peer, .. } if *peer == from) {
+                    self.reply_camera_request(&from, request, Err("not in a call with you".into()));
+                } else if self.camera_control_allowed {
+                    let outcome = self.apply_camera_request(request);
+                    self.reply_camera_request(&from, request, outcome);
+                } else {
+                    self.camera_requests.push_back(request);
+                }
+            }
+            SignalingMsg::CameraControlReply {
+                from,
+                request,
+                accepted,
+                reason,
+                ..
+            } => {
+                let what = self.locale.tr(request.describe());
+                self.status_line = if accepted {
+                    self.locale.trf(
+                        "{peer} accepted: {what}",
+                        &[("peer", &from), ("what", &what)],
+                    )
+                } else {
+                    let reason = reason.unwrap_or_else(|| self.locale.tr("declined").into());
+                    self.locale.trf(
+                        "{peer} refused: {what} ({reason})",
+                        &[("peer", &from), ("what", &what), ("reason", &reason)],
+                    )
+                };
+            }
+            SignalingMsg::Contacts { contacts, blocked } => {
+                self.contacts = contacts;
+                self.blocked = blocked;
+            }
+            SignalingMsg::OfferErr { to, code, reason } => {
+                let reason = CallErrorCode::from_u16(code)
+                    .map(|c| self.locale.tr(c.reason()).to_owned())
+                    .or(reason)
+                    .unwrap_or_else(|| code.to_string());
+                let msg = self.locale.trf(
+                    "Call to {peer} refused: {reason}",
+                    &[("peer", &to), ("reason", &reason)],
+                );
+                if matches!(&self.call_flow, CallFlow::Dialing { peer, .. } if *peer == to) {
+                    self.finish_call_record(CallEnd::Failed);
+                    self.teardown_call(Some(msg.clone()), false);
+                }
+                self.signaling_error = Some(msg.clone());
+                self.push_ui_log(msg);
+            }
+            SignalingMsg::Created {
+                session_id,
+                session_code,
+            } => {
+                self.status_line = self.locale.trf(
+                    "Room created, share code {code}",
+                    &[("code", &session_code)],
+                );
+                self.room = Some(Room {
+                    session_id,
+                    code: session_code,
+                    members: Vec::new(),
+                });
+            }
+            SignalingMsg::JoinOk { session_id } => {
+                let code = self.pending_room_code.take().unwrap_or_default();
+                self.status_line = self.locale.trf("Joined room {code}", &[("code", &code)]);
+                self.room_code_input.clear();
+                self.room = Some(Room {
+                    session_id,
+                    code,
+                    members: Vec::new(),
+                });
+            }
+            SignalingMsg::JoinErr { code, reason } => {
+                let room = self.pending_room_code.take().unwrap_or_default();
+                let msg = match code {
+                    c if c == JoinErrorCode::NotFound.as_u16() => self
+                        .locale
+                        .trf("No room with code {room}", &[("room", &room)]),
+                    c if c == JoinErrorCode::Full.as_u16() => {
+                        self.locale.trf("Room {room} is full", &[("room", &room)])
+                    }
+                    c if c == JoinErrorCode::NotLoggedIn.as_u16() => {
+                        self.locale.tr("Log in before joining a room").to_owned()
+                    }
+                    c => match reason {
+                        Some(reason) => self.locale.trf(
+                            "Could not join room {room}: {reason}",
+                            &[("room", &room), ("reason", &reason)],
+                        ),
+                        None => self.locale.trf(
+                            "Could not join room {room} (code {code})",
+                            &[("room", &room), ("code", &c)],
+                        ),
+                    },
+                };
+                self.signaling_error = Some(msg.clone());
+                self.push_ui_log(msg);
+            }
+            SignalingMsg::PeerJoined {
+                session_id,
+                username,
+            } => {
+                if let Some(room) = &mut self.room
+                    && room.session_id == session_id
+                    && !room.members.contains(&username)
+                {
+                    let msg = format!("{username} joined room {}", room.code);
+                    room.members.push(username);
+                    self.push_ui_log(msg);
+                }
+            }
+            SignalingMsg::PeerLeft {
+                session_id,
+                username,
+            } => {
+                if let Some(room) = &mut self.room
+                    && room.session_id == session_id
+                {
+                    room.members.retain(|m| *m != username);
+                    let msg = format!("{username} left room {}", room.code);
+                    self.push_ui_log(msg);
+                }
+            }
             SignalingMsg::Offer {
                 from, txn_id, sdp, ..
             } => {
+                // A new offer from the peer we are talking to is a
+                // renegotiation (hold/resume), not a new call.
+                if let CallFlow::Active { peer, .. } = &self.call_flow
+                    && *peer == from
+                {
+                    self.handle_reoffer(from, txn_id, sdp);
+                    return;
+                }
                 // PROTECTION: If we are not Idle, we are busy. Reject the call.
                 if !matches!(self.call_flow, CallFlow::Idle) {
                     self.background_log(
@@ -411,25 +1080,47 @@ impl RtcApp {
                     // Send a Bye immediately to stop the caller's ringing state
                     let _ = self.send_signaling(SignalingMsg::Bye {
                         from: self.current_username.clone().unwrap_or_default(),
-                        to: from,
+                        to: from.clone(),
                         reason: Some("User is busy".into()),
                     });
+                    self.call_history.missed(&from, call_history::now_secs());
+                    self.save_call_history();
                     return;
                 }
                 match String::from_utf8(sdp) {
                     Ok(body) => {
+                        let transferred = self.pending_transfer.as_ref() == Some(&from);
+                        // Nobody is there to answer a kiosk: it's the allowlist's call
+                        let kiosk_answers = self.kiosk.as_ref().map(|k| k.answers(&from));
                         self.remote_sdp_text = body.clone();
                         self.call_flow = CallFlow::Incoming {
                             from: from.clone(),
                             txn_id,
                             sdp: body,
                         };
-                        self.status_line = format!("Incoming call from {from}");
+                        self.call_history.begin(
+                            &from,
+                            CallDirection::Incoming,
+                            call_history::now_secs(),
+                        );
+                        self.status_line = self
+                            .locale
+                            .trf("Incoming call from {from}", &[("from", &from)]);
                         let _ = self.send_signaling(SignalingMsg::Ack {
                             from: self.current_username.clone().unwrap_or_default(),
                             to: from,
                             txn_id,
                         });
+                        if transferred {
+                            self.pending_transfer = None;
+                            self.accept_incoming_call();
+                        } else if let Some(answers) = kiosk_answers {
+                            if answers {
+                                self.accept_incoming_call();
+                            } else {
+                                self.decline_incoming_call();
+                            }
+                        }
                     }
                     Err(e) => {
                         self.push_ui_log(format!("Invalid SDP from {from}: {e}"));
@@ -442,9 +1133,18 @@ impl RtcApp {
                 Ok(body) => {
                     self.remote_sdp_text = body.clone();
                     self.pending_remote_sdp = Some(body);
-                    self.call_flow = CallFlow::Active { peer: from.clone() };
-                    self.status_line = format!("Received answer from {from}");
-                    // Acknowledge receipt so the sender can stop retries if they add reliability.
+                    if !matches!(self.call_flow, CallFlow::Active { .. }) {
+                        self.call_flow = CallFlow::Active {
+                            peer: from.clone(),
+                            hold: HoldState::default(),
+                            since: Instant::now(),
+                        };
+                        self.call_history.answered(call_history::now_secs());
+                    }
+                    self.status_line = self
+                        .locale
+                        .trf("Received answer from {from}", &[("from", &from)]);
+                    // Acknowledge receipt so the sender stops retransmitting.
                     let _ = self.send_signaling(SignalingMsg::Ack {
                         from: self.current_username.clone().unwrap_or_default(),
                         to: from.clone(),
@@ -453,21 +1153,22 @@ impl RtcApp {
                 }
                 Err(e) => self.push_ui_log(format!("Invalid answer from {from}: {e}")),
             },
-            SignalingMsg::Candidate { from, cand, .. } => match String::from_utf8(cand) {
-                Ok(line) => match self.engine.apply_remote_candidate(&line) {
-                    Ok(()) => {
-                        self.push_ui_log(format!("Applied ICE candidate from {from}"));
-                    }
-                    Err(e) => {
-                        let msg = format!("Failed to apply ICE candidate from {from}: {e}");
-                        self.signaling_error = Some(msg.clone());
-                        self.push_ui_log(msg);
-                    }
-                },
-                Err(e) => {
-                    self.push_ui_log(format!("Invalid ICE candidate from {from}: {e}"));
+            SignalingMsg::Candidate { from, cand, .. } => {
+                self.apply_remote_candidate_bytes(&from, cand);
+            }
+            SignalingMsg::Candidates {
+                from,
+                items,
+                end_of_candidates,
+                ..
+            } => {
+                for item in items {
+                    self.apply_remote_candidate_bytes(&from, item.cand);
                 }
-            },
+                if end_of_candidates {
+                    self.push_ui_log(format!("{from} finished sending ICE candidates"));
+                }
+            }
             SignalingMsg::Ping { nonce } => {
                 let _ = self.send_signaling(SignalingMsg::Pong { nonce });
             }
@@ -479,6 +1180,9 @@ impl RtcApp {
             SignalingMsg::Ack { txn_id, from, .. } => {
                 self.push_ui_log(format!("Received ACK from {from} for txn_id={txn_id}"));
             }
+            SignalingMsg::Transfer { from, to, target } => {
+                self.handle_transfer(&from, &to, target);
+            }
             other => {
                 self.background_log(
                     LogLevel::Debug,
@@ -488,6 +1192,27 @@ impl RtcApp {
         }
     }
 
+    fn apply_remote_candidate_bytes(&mut self, from: &str, cand: Vec<u8>) {
+        match String::from_utf8(cand) {
+            Ok(line) => match self.engine.apply_remote_candidate(&line) {
+                Ok(()) => {
+                    self.push_ui_log(format!("Applied ICE candidate from {from}"));
+                }
+                Err(e) => {
+                    let msg = self.locale.trf(
+                        "Failed to apply ICE candidate from {from}: {error}",
+                        &[("from", &from), ("error", &e)],
+                    );
+                    self.signaling_error = Some(msg.clone());
+                    self.push_ui_log(msg);
+                }
+            },
+            Err(e) => {
+                self.push_ui_log(format!("Invalid ICE candidate from {from}: {e}"));
+            }
+        }
+    }
+
     fn request_peer_list(&mut self) {
         let _ = self.send_signaling(SignalingMsg::ListPeers);
     }
@@ -495,14 +1220,20 @@ impl RtcApp {
     fn send_signaling(&mut self, msg: SignalingMsg) -> Result<(), ()> {
         if let Some(client) = self.signaling_client.as_ref() {
             if let Err(e) = client.send(msg) {
-                let err = format!("Failed to send signaling message: {e}");
+                let err = self.locale.trf(
+                    "Failed to send signaling message: {error}",
+                    &[("error", &e)],
+                );
                 self.signaling_error = Some(err.clone());
                 self.push_ui_log(err);
                 return Err(());
             }
             Ok(())
         } else {
-            let err = "Not connected to signaling server.".to_string();
+            let err = self
+                .locale
+                .tr("Not connected to signaling server.")
+                .to_owned();
             self.signaling_error = Some(err.clone());
             self.push_ui_log(err);
             Err(())
@@ -511,23 +1242,34 @@ impl RtcApp {
 
     fn send_local_candidates(&mut self, peer: &str) {
         let Some(user) = self.current_username.clone() else {
-            self.signaling_error = Some("Please login before sending candidates.".into());
+            self.signaling_error = Some(
+                self.locale
+                    .tr("Please login before sending candidates.")
+                    .into(),
+            );
             return;
         };
         let candidates = self.engine.local_candidates_as_sdp_lines();
         if candidates.is_empty() {
             return;
         }
-        for cand_line in candidates {
-            let msg = SignalingMsg::Candidate {
-                from: user.clone(),
-                to: peer.to_string(),
+        // Gathering is complete by the time we get here, so a single batch
+        // carries every candidate plus the end-of-candidates marker.
+        let items = candidates
+            .into_iter()
+            .map(|cand_line| CandidateItem {
                 mid: "0".into(),
                 mline_index: 0,
                 cand: cand_line.into_bytes(),
-            };
-            let _ = self.send_signaling(msg);
-        }
+            })
+            .collect();
+        let msg = SignalingMsg::Candidates {
+            from: user,
+            to: peer.to_string(),
+            items,
+            end_of_candidates: true,
+        };
+        let _ = self.send_signaling(msg);
     }
 
     fn send_bye(&mut self, peer: &str, reason: Option<String>) {
@@ -544,19 +1286,41 @@ impl RtcApp {
 
     fn start_outgoing_call(&mut self, peer: &str) {
         if !matches!(self.call_flow, CallFlow::Idle) {
-            self.status_line = "Finish or cancel the current call first.".into();
+            self.status_line = self
+                .locale
+                .tr("Finish or cancel the current call first.")
+                .into();
             return;
         }
         if self.current_username.is_none() {
-            self.signaling_error = Some("Please login before calling.".into());
+            self.signaling_error = Some(self.locale.tr("Please login before calling.").into());
             return;
         }
+        let audio_only = match self.peer_caps.get(peer) {
+            Some(caps)
+                if self
+                    .engine
+                    .local_capabilities()
+                    .common_codecs(caps)
+                    .is_empty() =>
+            {
+                self.status_line = self
+                    .locale
+                    .trf("{peer} supports none of our codecs", &[("peer", &peer)]);
+                return;
+            }
+            Some(caps) => !caps.supports_video(),
+            None => false,
+        };
         if let Err(e) = self.create_or_renegotiate_local_sdp() {
-            self.status_line = format!("Failed to create local SDP: {e:?}");
+            self.status_line = self.locale.trf(
+                "Failed to create local SDP: {error}",
+                &[("error", &format!("{e:?}"))],
+            );
             return;
         }
         if self.local_sdp_text.trim().is_empty() {
-            self.status_line = "Local SDP is empty.".into();
+            self.status_line = self.locale.tr("Local SDP is empty.").into();
             return;
         }
         let txn_id = self.next_txn_id;
@@ -573,11 +1337,68 @@ impl RtcApp {
                 peer: peer.to_string(),
                 txn_id,
             };
-            self.status_line = format!("Sent offer to {peer}");
+            self.call_history
+                .begin(peer, CallDirection::Outgoing, call_history::now_secs());
+            self.status_line = if audio_only {
+                self.locale.trf(
+                    "Sent offer to {peer}; peer does not support video",
+                    &[("peer", &peer)],
+                )
+            } else {
+                self.locale.trf("Sent offer to {peer}", &[("peer", &peer)])
+            };
             self.send_local_candidates(peer);
         }
     }
 
+    /// Hands the active call over to `target`, who will call our peer.
+    fn transfer_call(&mut self, target: &str) {
+        let CallFlow::Active { peer, .. } = self.call_flow.clone() else {
+            return;
+        };
+        let msg = SignalingMsg::Transfer {
+            from: self.current_username.clone().unwrap_or_default(),
+            to: peer.clone(),
+            target: target.to_string(),
+        };
+        if self.send_signaling(msg).is_ok() {
+            self.push_ui_log(format!("Transferred {peer} to {target}"));
+            self.finish_call_record(CallEnd::Local);
+            // The server tells the peer; no Bye
+            self.teardown_call(
+                Some(
+                    self.locale
+                        .trf("transferred to {target}", &[("target", &target)]),
+                ),
+                false,
+            );
+        }
+    }
+
+    /// `from` handed its call with `to` over to `target`. As `to`, the call
+    /// with `from` ends and `target`'s offer will be accepted; as `target`,
+    /// we call `to`.
+    fn handle_transfer(&mut self, from: &str, to: &str, target: String) {
+        let me = self.current_username.clone().unwrap_or_default();
+        if to == me {
+            if matches!(&self.call_flow, CallFlow::Active { peer, .. } if peer == from) {
+                self.teardown_call(None, false);
+            }
+            self.status_line = self.locale.trf(
+                "{from} is transferring you to {target}",
+                &[("from", &from), ("target", &target)],
+            );
+            self.pending_transfer = Some(target);
+        } else if target == me {
+            self.push_ui_log(format!("{from} transferred {to} to us"));
+            if matches!(self.call_flow, CallFlow::Idle) {
+                self.start_outgoing_call(to);
+            } else {
+                self.send_bye(to, Some("User is busy".into()));
+            }
+        }
+    }
+
     fn accept_incoming_call(&mut self) {
         let CallFlow::Incoming { from, txn_id, sdp } = self.call_flow.clone() else {
             return;
@@ -585,7 +1406,7 @@ impl RtcApp {
         match self.set_remote_sdp(&sdp) {
             Ok(()) => {
                 if self.local_sdp_text.trim().is_empty() {
-                    self.status_line = "Answer not generated.".into();
+                    self.status_line = self.locale.tr("Answer not generated.").into();
                     return;
                 }
                 let msg = SignalingMsg::Answer {
@@ -595,17 +1416,158 @@ impl RtcApp {
                     sdp: self.local_sdp_text.as_bytes().to_vec(),
                 };
                 if self.send_signaling(msg).is_ok() {
-                    self.call_flow = CallFlow::Active { peer: from.clone() };
-                    self.status_line = format!("Sent answer to {from}");
+                    self.call_flow = CallFlow::Active {
+                        peer: from.clone(),
+                        hold: HoldState::default(),
+                        since: Instant::now(),
+                    };
+                    self.call_history.answered(call_history::now_secs());
+                    self.status_line = self.locale.trf("Sent answer to {from}", &[("from", &from)]);
                     self.send_local_candidates(&from);
                 }
             }
             Err(e) => {
-                self.status_line = format!("Failed to accept call: {e:?}");
+                self.status_line = self.locale.trf(
+                    "Failed to accept call: {error}",
+                    &[("error", &format!("{e:?}"))],
+                );
+            }
+        }
+    }
+
+    /// Applies a re-offer from the active peer and answers it right away.
+    fn handle_reoffer(&mut self, from: String, txn_id: u64, sdp: Vec<u8>) {
+        let body = match String::from_utf8(sdp) {
+            Ok(body) => body,
+            Err(e) => {
+                self.push_ui_log(format!("Invalid re-offer from {from}: {e}"));
+                return;
+            }
+        };
+        let _ = self.send_signaling(SignalingMsg::Ack {
+            from: self.current_username.clone().unwrap_or_default(),
+            to: from.clone(),
+            txn_id,
+        });
+        self.remote_sdp_text = body.clone();
+        if let Err(e) = self.set_remote_sdp(&body) {
+            self.status_line = self.locale.trf(
+                "Failed to apply re-offer: {error}",
+                &[("error", &format!("{e:?}"))],
+            );
+            return;
+        }
+        let msg = SignalingMsg::Answer {
+            txn_id,
+            from: self.current_username.clone().unwrap_or_default(),
+            to: from.clone(),
+            sdp: self.local_sdp_text.as_bytes().to_vec(),
+        };
+        if self.send_signaling(msg).is_ok()
+            && let CallFlow::Active { hold, .. } = &mut self.call_flow
+        {
+            hold.remote = self.engine.is_remote_on_hold();
+            self.status_line = if hold.remote {
+                format!("{from} put the call on hold")
+            } else {
+                format!("{from} resumed the call")
+            };
+        }
+    }
+
+    /// Puts the active call on hold, or resumes it, by re-offering.
+    fn toggle_hold(&mut self) {
+        let CallFlow::Active { peer, hold, since } = self.call_flow.clone() else {
+            return;
+        };
+        let result = if hold.local {
+            self.engine.resume()
+        } else {
+            self.engine.hold()
+        };
+        let sdp = match result {
+            Ok(Some(sdp)) => sdp,
+            Ok(None) => {
+                self.status_line = self.locale.tr("Renegotiation already in progress.").into();
+                return;
+            }
+            Err(e) => {
+                self.status_line = self
+                    .locale
+                    .trf("Failed to update hold state: {error}", &[("error", &e)]);
+                return;
+            }
+        };
+        if self.send_reoffer(&peer, sdp) {
+            self.call_flow = CallFlow::Active {
+                peer: peer.clone(),
+                hold: HoldState {
+                    local: !hold.local,
+                    ..hold
+                },
+                since,
+            };
+            self.status_line = if hold.local {
+                self.locale
+                    .trf("Resuming call with {peer}", &[("peer", &peer)])
+            } else {
+                self.locale
+                    .trf("Call with {peer} on hold", &[("peer", &peer)])
+            };
+        }
+    }
+
+    /// Starts or stops sharing an extra test-pattern video track.
+    fn toggle_share(&mut self) {
+        let CallFlow::Active { peer, .. } = self.call_flow.clone() else {
+            return;
+        };
+        let result = match self.shared_track {
+            Some(id) => self.engine.remove_track(id).map(|sdp| (None, sdp)),
+            None => self
+                .engine
+                .add_video_track(VideoSource::TestPattern)
+                .map(|(id, sdp)| (Some(id), sdp)),
+        };
+        match result {
+            Ok((track, Some(sdp))) => {
+                self.shared_track = track;
+                if self.send_reoffer(&peer, sdp) {
+                    self.status_line = if track.is_some() {
+                        self.locale
+                            .trf("Sharing a second video with {peer}", &[("peer", &peer)])
+                    } else {
+                        self.locale
+                            .trf("Stopped sharing with {peer}", &[("peer", &peer)])
+                    };
+                }
+            }
+            Ok((track, None)) => {
+                self.shared_track = track;
+                self.status_line = self.locale.tr("Renegotiation already in progress.").into();
+            }
+            Err(e) => {
+                self.status_line = self
+                    .locale
+                    .trf("Failed to update tracks: {error}", &[("error", &e)])
             }
         }
     }
 
+    /// Sends a re-offer for the active call. Returns `true` if it was sent.
+    fn send_reoffer(&mut self, peer: &str, sdp: String) -> bool {
+        self.local_sdp_text = sdp;
+        let txn_id = self.next_txn_id;
+        self.next_txn_id += 1;
+        let msg = SignalingMsg::Offer {
+            txn_id,
+            from: self.current_username.clone().unwrap_or_default(),
+            to: peer.to_string(),
+            sdp: self.local_sdp_text.as_bytes().to_vec(),
+        };
+        self.send_signaling(msg).is_ok()
+    }
+
     fn decline_incoming_call(&mut self) {
         self.teardown_call(Some("declined".into()), true);
     }
@@ -620,10 +1582,16 @@ impl RtcApp {
                 self.local_sdp_text = s;
                 self.has_local_description = true;
                 self.is_local_offerer = true;
-                self.status_line = "Local OFFER created. Share it with the peer.".into();
+                self.status_line = self
+                    .locale
+                    .tr("Local OFFER created. Share it with the peer.")
+                    .into();
             }
             None => {
-                self.status_line = "Negotiation already in progress (have-local-offer).".into();
+                self.status_line = self
+                    .locale
+                    .tr("Negotiation already in progress (have-local-offer).")
+                    .into();
             }
         }
         Ok(())
@@ -639,10 +1607,13 @@ impl RtcApp {
                 self.local_sdp_text = answer;
                 self.has_local_description = true;
                 self.is_local_offerer = false;
-                self.status_line = "Remote OFFER set → Local ANSWER created. Share it back.".into();
+                self.status_line = self
+                    .locale
+                    .tr("Remote OFFER set → Local ANSWER created. Share it back.")
+                    .into();
             }
             None => {
-                self.status_line = "Remote ANSWER set.".into();
+                self.status_line = self.locale.tr("Remote ANSWER set.").into();
             }
         }
         self.has_remote_description = true;
@@ -672,17 +1643,15 @@ impl RtcApp {
                     }
                 }
                 Established => {
-                    self.conn_state = ConnState::Running;
-                    self.status_line = "Established.".into();
+                    self.status_line = self.locale.tr("Established.").into();
                     self.engine.start_media_transport();
+                    self.stats_history.clear();
                 }
                 Closing { graceful: _ } => {
-                    self.conn_state = ConnState::Stopped;
                     self.call_flow = CallFlow::Idle;
                 }
                 Closed => {
-                    self.conn_state = ConnState::Stopped;
-                    self.status_line = "Closed.".into();
+                    self.status_line = self.locale.tr("Closed.").into();
                     self.engine.close_session();
                     self.call_flow = CallFlow::Idle;
                 }
@@ -695,16 +1664,48 @@ impl RtcApp {
                     );
                 }
                 Error(e) => {
-                    self.status_line = format!("Error: {e}");
+                    self.status_line = self.locale.trf("Error: {error}", &[("error", &e)]);
                     self.background_log(LogLevel::Error, &e);
                     self.push_ui_log(e);
                 }
+                EngineEvent::PeerConnectionStateChanged(state) => {
+                    self.conn_state = state;
+                    self.background_log(LogLevel::Info, format!("[PC] state {state:?}"));
+                    if matches!(
+                        state,
+                        PeerConnectionState::Failed | PeerConnectionState::Disconnected
+                    ) {
+                        self.status_line = self
+                            .locale
+                            .trf("Connection {state}.", &[("state", &format!("{state:?}"))]);
+                    }
+                    if state == PeerConnectionState::Failed
+                        && !matches!(self.call_flow, CallFlow::Idle)
+                    {
+                        self.finish_call_record(CallEnd::Failed);
+                        self.teardown_call(Some("connection failed".into()), true);
+                    }
+                }
+                EngineEvent::IceConnectionStateChanged(state) => {
+                    self.background_log(LogLevel::Info, format!("[ICE] state {state:?}"));
+                }
+                EngineEvent::DtlsStateChanged(state) => {
+                    self.background_log(LogLevel::Info, format!("[DTLS] state {state:?}"));
+                }
                 IceNominated { local, remote } => {
-                    self.status_line = "ICE nominated. Press Start.".into();
+                    self.status_line = self.locale.tr("ICE nominated. Press Start.").into();
                     self.background_log(
                         LogLevel::Info,
                         format!("[ICE] nominated local={local} remote={remote}"),
                     );
+                    // Nobody to wait for in a test call or at a kiosk
+                    if (self.loopback.is_some() || self.kiosk.is_some())
+                        && let Err(e) = self.engine.start()
+                    {
+                        self.status_line = self
+                            .locale
+                            .trf("Failed to start: {error}", &[("error", &e)]);
+                    }
                 }
                 EngineEvent::NetworkMetrics(metrics) => {
                     // Update state with new metrics from the Congestion Controller
@@ -715,23 +1716,31 @@ impl RtcApp {
                     self.current_bitrate = Some(bps);
                 }
                 EngineEvent::ReceivedFileOffer(props) => {
-                    self.status_line =
-                        format!("File offer: {} ({})", props.file_name, props.file_size);
+                    self.status_line = self.locale.trf(
+                        "File offer: {name} ({size})",
+                        &[("name", &props.file_name), ("size", &props.file_size)],
+                    );
                     self.file_transfer_state = FileTransferState::RemoteOffered { props };
                     // If we were busy, we might want to auto-reject?
                     // But for now assume one file at a time.
                 }
                 EngineEvent::ReceivedFileAccept(id) => {
-                    self.status_line = format!("Peer accepted file (id: {id}). Sending...");
+                    self.status_line = self
+                        .locale
+                        .trf("Peer accepted file (id: {id}). Sending...", &[("id", &id)]);
                     // state is already Sending likely
                 }
                 EngineEvent::ReceivedFileReject(id) => {
-                    self.status_line = format!("Peer rejected file (id: {id}).");
+                    self.status_line = self
+                        .locale
+                        .trf("Peer rejected file (id: {id}).", &[("id", &id)]);
                     self.file_transfer_state = FileTransferState::Idle;
                     self.sending_files.store(false, Ordering::SeqCst);
                 }
                 EngineEvent::ReceivedFileCancel(id) => {
-                    self.status_line = format!("File transfer cancelled (id: {id}).");
+                    self.status_line = self
+                        .locale
+                        .trf("File transfer cancelled (id: {id}).", &[("id", &id)]);
                     self.file_transfer_state = FileTransferState::Idle;
                     self.sending_files.store(false, Ordering::SeqCst);
                     self.receiving_files.store(false, Ordering::SeqCst);
@@ -754,12 +1763,12 @@ impl RtcApp {
                     // Internal
                 }
                 EngineEvent::SendFileEnd(_) => {
-                    self.status_line = "File transfer finished (sent).".into();
+                    self.status_line = self.locale.tr("File transfer finished (sent).").into();
                     self.file_transfer_state = FileTransferState::Idle;
                     self.sending_files.store(false, Ordering::SeqCst);
                 }
                 EngineEvent::ReceivedFileEnd(_) => {
-                    self.status_line = "File transfer finished (received).".into();
+                    self.status_line = self.locale.tr("File transfer finished (received).").into();
                     self.file_transfer_state = FileTransferState::Idle;
                     self.receiving_files.store(false, Ordering::SeqCst);
                 }
@@ -791,21 +1800,138 @@ impl RtcApp {
                 EngineEvent::ToggleAudio(muted) => {
                     self.is_muted = muted;
                 }
-            }
-        }
-    }
-
-    fn render_file_transfer(&mut self, ui: &mut egui::Ui) {
-        ui.separator();
-        ui.heading("File Transfer");
-
-        // Check atomic flags for active state
+                EngineEvent::RemoteTrackAdded { ssrc, label } => {
+                    self.push_ui_log(format!("Remote track {label} added (ssrc={ssrc})"));
+                    self.status_line = self
+                        .locale
+                        .trf("Peer started sharing {label}", &[("label", &label)]);
+                }
+                EngineEvent::RemoteTrackRemoved { ssrc } => {
+                    self.push_ui_log(format!("Remote track removed (ssrc={ssrc})"));
+                    self.status_line = self.locale.tr("Peer stopped sharing").into();
+                }
+                EngineEvent::CameraLost { camera_id, reason } => {
+                    self.push_ui_log(format!("Camera {camera_id} lost: {reason}"));
+                    self.status_line = self.locale.trf(
+                        "Camera {camera_id} disconnected, sending placeholder",
+                        &[("camera_id", &camera_id)],
+                    );
+                }
+                EngineEvent::CameraRecovered { camera_id } => {
+                    self.push_ui_log(format!("Camera {camera_id} is back"));
+                    self.status_line = self.locale.trf(
+                        "Camera {camera_id} reconnected",
+                        &[("camera_id", &camera_id)],
+                    );
+                }
+                EngineEvent::Speaking { remote, speaking } => {
+                    if remote {
+                        self.remote_speaking = speaking;
+                    } else {
+                        self.local_speaking = speaking;
+                    }
+                }
+                EngineEvent::RemoteVideoMuted(muted) => {
+                    self.remote_video_muted = muted;
+                    let state = if muted { "off" } else { "on" };
+                    self.push_ui_log(format!("Peer turned camera {state}"));
+                }
+                EngineEvent::ConfigUpdated { config, changed } => {
+                    self.logger.set_min_level(Logger::configured_level(&config));
+                    self.config = config;
+                    if changed.is_empty() {
+                        self.push_ui_log("Configuration reloaded");
+                    } else {
+                        self.push_ui_log(format!("Configuration reloaded: {}", changed.join(", ")));
+                    }
+                    self.status_line = self.locale.tr("Configuration reloaded.").into();
+                }
+                EngineEvent::RemoteAudioLevel(level) => {
+                    self.remote_audio_level = Some(level);
+                    let primary = self.engine.primary_peer().to_owned();
+                    self.active_speaker
+                        .on_level(&primary, level, Instant::now());
+                }
+            }
+        }
+
+        // The other peers of a multi-party call only feed the video grid
+        for PeerEvent { peer, event } in self.engine.poll_others() {
+            match event {
+                Log(m) => {
+                    self.background_log(m.level, format!("[{peer}] {} | {}", m.target, m.text));
+                }
+                EngineEvent::RemoteAudioLevel(level) => {
+                    self.active_speaker.on_level(&peer, level, Instant::now());
+                }
+                Closed => {
+                    self.peer_tiles.remove(&peer);
+                    self.active_speaker.remove(&peer);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Updates one texture per remote peer besides the primary; tiles of
+    /// peers that left are dropped.
+    fn update_peer_tiles(
+        &mut self,
+        ctx: &egui::Context,
+        render_state: Option<&RenderState>,
+        logger: &Arc<dyn LogSink>,
+    ) {
+        let peers = self.engine.peer_ids();
+        let gone: Vec<PeerId> = self
+            .peer_tiles
+            .keys()
+            .filter(|p| !peers.contains(p))
+            .cloned()
+            .collect();
+        for peer in gone {
+            self.peer_tiles.remove(&peer);
+            self.active_speaker.remove(&peer);
+        }
+        if matches!(self.call_flow, CallFlow::Idle) {
+            return;
+        }
+        for (peer, frame) in self.engine.other_remote_frames() {
+            let tile = self
+                .peer_tiles
+                .entry(peer.clone())
+                .or_insert_with(|| PeerTile {
+                    texture: None,
+                    yuv_renderer: render_state.map(|rs| {
+                        GpuYuvRenderer::new(&rs.device, rs.target_format, logger.clone())
+                    }),
+                    shown: None,
+                });
+            if !mark_shown(&mut tile.shown, &frame) {
+                continue;
+            }
+            update_texture_from_frame(
+                ctx,
+                &frame,
+                &mut tile.texture,
+                &mut tile.yuv_renderer,
+                render_state,
+                &format!("camera/{peer}"),
+                logger.clone(),
+            );
+        }
+    }
+
+    fn render_file_transfer(&mut self, ui: &mut egui::Ui) {
+        ui.separator();
+        ui.heading(self.locale.tr("File Transfer"));
+
+        // Check atomic flags for active state
         let sending = self.sending_files.load(Ordering::SeqCst);
         let receiving = self.receiving_files.load(Ordering::SeqCst);
 
         // Debug info to diagnose button visibility issues
-        ui.collapsing("Debug State", |ui| {
-            ui.label(format!("ConnState: {:?}", self.conn_state));
+        ui.collapsing(self.locale.tr("Debug State"), |ui| {
+            ui.label(format!("PeerConnectionState: {:?}", self.conn_state));
             ui.label(format!("Sending: {}", sending));
             ui.label(format!("Receiving: {}", receiving));
             ui.label(format!("TransferState: {:?}", self.file_transfer_state));
@@ -813,11 +1939,11 @@ impl RtcApp {
 
         match &self.file_transfer_state {
             FileTransferState::Idle => {
-                if matches!(self.conn_state, ConnState::Running) && !sending && !receiving {
+                if self.conn_state.is_connected() && !sending && !receiving {
                     ui.horizontal(|ui| {
-                        ui.label("Path:");
+                        ui.label(self.locale.tr("Path:"));
                         ui.text_edit_singleline(&mut self.file_path_input);
-                        if ui.button("Send File").clicked() {
+                        if ui.button(self.locale.tr("Send File")).clicked() {
                             println!("[CLI DEBUG] Send File button clicked!"); // Force output to console
                             let path = self.file_path_input.trim().to_string();
                             if !path.is_empty() {
@@ -828,7 +1954,7 @@ impl RtcApp {
                                 // Use a random ID or sequential
                                 let id = rand::random::<u32>();
                                 self.engine.send_file(path, id);
-                                self.status_line = "Preparing file...".into();
+                                self.status_line = self.locale.tr("Preparing file...").into();
                                 // We wait for SendFileOffer event to switch state
                             } else {
                                 self.background_log(
@@ -839,29 +1965,32 @@ impl RtcApp {
                         }
                     });
                 } else if sending || receiving {
-                    ui.label("Transfer in progress...");
-                    if ui.button("Cancel").clicked() {
+                    ui.label(self.locale.tr("Transfer in progress..."));
+                    if ui.button(self.locale.tr("Cancel")).clicked() {
                         self.engine.cancel_file(0);
                         self.sending_files.store(false, Ordering::SeqCst);
                         self.receiving_files.store(false, Ordering::SeqCst);
                     }
                 } else {
-                    ui.label("Connect to a peer to transfer files.");
+                    ui.label(self.locale.tr("Connect to a peer to transfer files."));
                 }
             }
             FileTransferState::RemoteOffered {
                 props: remote_props,
             } => {
-                ui.label(format!(
-                    "Incoming file: {} ({} bytes)",
-                    remote_props.file_name, remote_props.file_size
+                ui.label(self.locale.trf(
+                    "Incoming file: {name} ({size} bytes)",
+                    &[
+                        ("name", &remote_props.file_name),
+                        ("size", &remote_props.file_size),
+                    ],
                 ));
                 let id_to_accept = remote_props.transaction_id;
                 let filename_to_receive = remote_props.file_name.clone();
                 let filesize_to_receive = remote_props.file_size as usize;
 
                 ui.horizontal(|ui| {
-                    if ui.button("Accept").clicked() {
+                    if ui.button(self.locale.tr("Accept")).clicked() {
                         self.engine
                             .accept_file(id_to_accept, filename_to_receive.clone());
                         self.file_transfer_state = FileTransferState::Receiving {
@@ -871,7 +2000,7 @@ impl RtcApp {
                             progress: 0.0,
                         };
                     }
-                    if ui.button("Reject").clicked() {
+                    if ui.button(self.locale.tr("Reject")).clicked() {
                         self.engine.reject_file(id_to_accept);
                         self.file_transfer_state = FileTransferState::Idle;
                     }
@@ -882,9 +2011,12 @@ impl RtcApp {
                 filename,
                 progress,
             } => {
-                ui.label(format!("Sending {}... {:.1}%", filename, progress));
+                ui.label(self.locale.trf(
+                    "Sending {name}... {progress}%",
+                    &[("name", filename), ("progress", &format!("{progress:.1}"))],
+                ));
                 ui.add(egui::ProgressBar::new(progress / 100.0));
-                if ui.button("Cancel").clicked() {
+                if ui.button(self.locale.tr("Cancel")).clicked() {
                     self.engine.cancel_file(*id);
                     self.sending_files.store(false, Ordering::SeqCst);
                     self.file_transfer_state = FileTransferState::Idle;
@@ -896,9 +2028,12 @@ impl RtcApp {
                 progress,
                 ..
             } => {
-                ui.label(format!("Receiving {}... {:.1}%", filename, progress));
+                ui.label(self.locale.trf(
+                    "Receiving {name}... {progress}%",
+                    &[("name", filename), ("progress", &format!("{progress:.1}"))],
+                ));
                 ui.add(egui::ProgressBar::new(progress / 100.0));
-                if ui.button("Cancel").clicked() {
+                if ui.button(self.locale.tr("Cancel")).clicked() {
                     self.engine.cancel_file(*id);
                     self.receiving_files.store(false, Ordering::SeqCst);
                     self.file_transfer_state = FileTransferState::Idle;
@@ -906,7 +2041,7 @@ impl RtcApp {
             }
             FileTransferState::Finished { msg } => {
                 ui.label(msg);
-                if ui.button("OK").clicked() {
+                if ui.button(self.locale.tr("OK")).clicked() {
                     self.file_transfer_state = FileTransferState::Idle;
                 }
             }
@@ -920,56 +2055,600 @@ impl RtcApp {
         _remote_frame: Option<&VideoFrame>,
     ) {
         // show the window if we are running OR we already have any texture
-        let have_any_texture =
-            self.local_camera_texture.is_some() || self.remote_camera_texture.is_some();
+        let have_any_texture = self.local_camera_texture.is_some()
+            || self.remote_camera_texture.is_some()
+            || !self.peer_tiles.is_empty();
+
+        if !self.conn_state.is_connected() && !have_any_texture {
+            if self.video_fullscreen {
+                self.set_video_fullscreen(ctx, false);
+            }
+            return;
+        }
 
-        if matches!(self.conn_state, ConnState::Running) || have_any_texture {
-            egui::Window::new("Camera View")
-                .default_size([Self::CAMERAS_WINDOW_WIDTH, Self::CAMERAS_WINDOW_HEIGHT])
-                .resizable(true)
+        if self.video_fullscreen && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.set_video_fullscreen(ctx, false);
+        }
+        if self.video_fullscreen {
+            // Video only, over the whole window
+            let screen = ctx.screen_rect();
+            egui::Area::new(egui::Id::new("video_fullscreen"))
+                .fixed_pos(screen.min)
+                .order(egui::Order::Foreground)
                 .show(ctx, |ui| {
-                    // If only remote is present → give it full real estate
-                    let only_remote =
-                        self.local_camera_texture.is_none() && self.remote_camera_texture.is_some();
-
-                    if only_remote {
-                        show_camera_in_ui(
-                            ui,
-                            self.remote_camera_texture,
-                            Self::CAMERAS_WINDOW_WIDTH - 16.0,
-                            Self::CAMERAS_WINDOW_HEIGHT - 16.0,
+                    self.render_video_stage(ctx, ui, screen.size());
+                });
+            return;
+        }
+
+        egui::Window::new(self.locale.tr("Camera View"))
+            .id(egui::Id::new("camera_view"))
+            .default_size([Self::CAMERAS_WINDOW_WIDTH, Self::CAMERAS_WINDOW_HEIGHT])
+            .resizable(true)
+            .show(ctx, |ui| {
+                // The remote video takes whatever the controls leave
+                let stage = egui::vec2(
+                    ui.available_width(),
+                    (ui.available_height() - Self::CALL_CONTROLS_HEIGHT)
+                        .max(Self::MIN_STAGE_HEIGHT),
+                );
+                self.render_video_stage(ctx, ui, stage);
+                ui.horizontal(|ui| {
+                    Self::render_speaking(ui, self.locale, "You", self.local_speaking);
+                    Self::render_camera_off(ui, self.locale, self.is_video_muted);
+                    ui.separator();
+                    // The peer's own voice flag reacts before our detector
+                    let peer_speaking =
+                        self.remote_speaking || self.remote_audio_level.is_some_and(|l| l.voice);
+                    Self::render_speaking(ui, self.locale, "Peer", peer_speaking);
+                    Self::render_audio_level(ui, self.remote_audio_level);
+                    Self::render_camera_off(ui, self.locale, self.remote_video_muted);
+                    if !self.peer_tiles.is_empty() {
+                        ui.separator();
+                        ui.selectable_value(
+                            &mut self.video_layout,
+                            VideoLayout::Grid,
+                            self.locale.tr("Grid"),
                         );
-                    } else {
-                        ui.horizontal(|ui| {
-                            show_camera_in_ui(
-                                ui,
-                                self.local_camera_texture,
-                                Self::LOCAL_CAMERA_SIZE,
-                                Self::LOCAL_CAMERA_SIZE,
-                            );
-                            ui.separator();
-                            show_camera_in_ui(
-                                ui,
-                                self.remote_camera_texture,
-                                Self::REMOTE_CAMERA_SIZE,
-                                Self::REMOTE_CAMERA_SIZE,
-                            );
+                        ui.selectable_value(
+                            &mut self.video_layout,
+                            VideoLayout::ActiveSpeaker,
+                            self.locale.tr("Speaker"),
+                        );
+                    }
+                });
+                ui.collapsing(self.locale.tr("Keypad"), |ui| self.render_keypad(ui));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(self.locale.tr("Call controls:"));
+                    if ui
+                        .button(egui::RichText::new(self.locale.tr("Hang up")).strong())
+                        .clicked()
+                    {
+                        self.teardown_call(Some("hangup".into()), true);
+                    }
+                    self.render_remote_camera_menu(ui);
+                    self.render_volume_menu(ui);
+                    if let CallFlow::Active { since, .. } = &self.call_flow {
+                        ui.separator();
+                        let (sent, received) = self.call_traffic;
+                        ui.monospace(call_history::format_duration(since.elapsed().as_secs()));
+                        ui.label(call_history::format_traffic(sent, received, self.locale));
+                    }
+                });
+            });
+    }
+
+    /// Remote video filling `size`, with the local camera as a draggable
+    /// picture-in-picture tile. Before the remote video arrives the local
+    /// camera fills the stage instead. Double-click toggles fullscreen.
+    fn render_video_stage(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, size: egui::Vec2) {
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
+        let stage_label = self.locale.tr("Call video. Double-click for fullscreen");
+        response
+            .widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Other, true, stage_label));
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+
+        // Every remote video, the primary peer first
+        let primary = self.engine.primary_peer().to_owned();
+        let mut remotes: Vec<(PeerId, (egui::TextureId, (u32, u32)))> = self
+            .remote_camera_texture
+            .map(|t| (primary, t))
+            .into_iter()
+            .collect();
+        let mut others: Vec<_> = self
+            .peer_tiles
+            .iter()
+            .filter_map(|(peer, tile)| tile.texture.map(|t| (peer.clone(), t)))
+            .collect();
+        others.sort_by(|a, b| a.0.cmp(&b.0));
+        remotes.extend(others);
+
+        let (main, pip) = match remotes.first() {
+            Some(&(_, remote)) => (Some(remote), self.local_camera_texture),
+            None => (self.local_camera_texture, None),
+        };
+        match main {
+            Some(_) if remotes.len() > 1 => self.paint_remote_tiles(&painter, rect, &remotes),
+            Some(texture) => paint_video(&painter, rect, texture),
+            None => {
+                painter.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    self.locale.tr("Waiting for video…"),
+                    egui::FontId::proportional(16.0),
+                    egui::Color32::GRAY,
+                );
+            }
+        }
+
+        if let Some(texture @ (_, (w, h))) = pip {
+            let tile_size = fit_size(
+                egui::vec2(w as f32, h as f32),
+                rect.size() * Self::PIP_FRACTION,
+            );
+            let tile = pip_rect(rect, tile_size, self.pip_anchor, Self::PIP_MARGIN);
+            let drag = ui.interact(tile, ui.id().with("pip"), egui::Sense::drag());
+            let pip_label = self.locale.tr("Your camera. Drag to move");
+            drag.widget_info(|| {
+                egui::WidgetInfo::labeled(egui::WidgetType::Other, true, pip_label)
+            });
+            if drag.dragged() {
+                // Anchor is relative to the free space, so it survives resizes
+                let free =
+                    (rect.shrink(Self::PIP_MARGIN).size() - tile_size).max(egui::Vec2::splat(1.0));
+                self.pip_anchor = (self.pip_anchor + drag.drag_delta() / free)
+                    .clamp(egui::Vec2::ZERO, egui::Vec2::splat(1.0));
+            }
+            let tile = pip_rect(rect, tile_size, self.pip_anchor, Self::PIP_MARGIN);
+            paint_video(&painter, tile, texture);
+            painter.rect_stroke(tile, 2.0, egui::Stroke::new(1.0, egui::Color32::WHITE));
+        }
+
+        if self.video_fullscreen {
+            painter.text(
+                rect.center_top() + egui::vec2(0.0, 8.0),
+                egui::Align2::CENTER_TOP,
+                self.locale.tr("Double-click or Esc to leave fullscreen"),
+                egui::FontId::proportional(12.0),
+                egui::Color32::GRAY,
+            );
+        }
+        if response.double_clicked() {
+            self.set_video_fullscreen(ctx, !self.video_fullscreen);
+        }
+    }
+
+    /// Several remote videos, as a grid or with the active speaker large.
+    fn paint_remote_tiles(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        remotes: &[(PeerId, (egui::TextureId, (u32, u32)))],
+    ) {
+        let now = Instant::now();
+        let mut tiles: Vec<(egui::Rect, usize)> = Vec::with_capacity(remotes.len());
+        match self.video_layout {
+            VideoLayout::Grid => {
+                let (cols, rows) = grid_dims(remotes.len());
+                let cell = egui::vec2(rect.width() / cols as f32, rect.height() / rows as f32);
+                for i in 0..remotes.len() {
+                    let at = egui::vec2((i % cols) as f32, (i / cols) as f32) * cell;
+                    tiles.push((egui::Rect::from_min_size(rect.min + at, cell), i));
+                }
+            }
+            VideoLayout::ActiveSpeaker => {
+                let main = self
+                    .active_speaker
+                    .current()
+                    .and_then(|p| remotes.iter().position(|(peer, _)| peer == p))
+                    .unwrap_or(0);
+                let strip_h = rect.height() * Self::SPEAKER_STRIP_FRACTION;
+                let (top, strip) = rect.split_top_bottom_at_y(rect.bottom() - strip_h);
+                tiles.push((top, main));
+                let rest: Vec<usize> = (0..remotes.len()).filter(|&i| i != main).collect();
+                let w = strip.width() / rest.len().max(1) as f32;
+                for (slot, i) in rest.into_iter().enumerate() {
+                    let min = strip.min + egui::vec2(slot as f32 * w, 0.0);
+                    tiles.push((egui::Rect::from_min_size(min, egui::vec2(w, strip_h)), i));
+                }
+            }
+        }
+        for (tile, i) in tiles {
+            let (peer, texture) = &remotes[i];
+            let tile = tile.shrink(1.0);
+            paint_video(painter, tile, *texture);
+            if self.active_speaker.current() == Some(peer.as_str())
+                && self.active_speaker.is_speaking(peer, now)
+            {
+                painter.rect_stroke(tile, 2.0, egui::Stroke::new(3.0, egui::Color32::GREEN));
+            }
+            // The primary peer's id is internal; show who we called
+            let name = match self.current_peer() {
+                Some(name) if peer == self.engine.primary_peer() => name,
+                _ => peer.clone(),
+            };
+            painter.text(
+                tile.left_bottom() + egui::vec2(4.0, -4.0),
+                egui::Align2::LEFT_BOTTOM,
+                name,
+                egui::FontId::proportional(12.0),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+
+    fn set_video_fullscreen(&mut self, ctx: &egui::Context, on: bool) {
+        self.video_fullscreen = on;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(on));
+    }
+
+    /// Carries out the peer's `request` on our camera.
+    fn apply_camera_request(&mut self, request: CameraRequest) -> Result<(), String> {
+        let status = match request {
+            CameraRequest::NextCamera => {
+                let next = match self.selected_camera {
+                    Some(id) => self
+                        .cameras
+                        .iter()
+                        .skip_while(|c| c.index != id)
+                        .nth(1)
+                        .or_else(|| self.cameras.first()),
+                    None => self.cameras.get(1),
+                };
+                let Some(id) = next
+                    .map(|c| c.index)
+                    .filter(|id| Some(*id) != self.selected_camera)
+                else {
+                    return Err("there is no other camera".into());
+                };
+                self.selected_camera = Some(id);
+                self.engine.switch_camera(id)
+            }
+            CameraRequest::Resolution { width, height } => {
+                self.capture_settings.width = Some(u32::from(width));
+                self.capture_settings.height = Some(u32::from(height));
+                self.engine.set_capture_settings(self.capture_settings)
+            }
+            CameraRequest::Zoom { level } => {
+                self.capture_settings.zoom = Some(level);
+                self.engine.set_capture_settings(self.capture_settings)
+            }
+            CameraRequest::Torch { .. } => return Err("this camera has no light".into()),
+        };
+        if let Some(status) = status {
+            self.push_ui_log(status);
+        }
+        Ok(())
+    }
+
+    /// Tells `to` what became of its camera `request`.
+    fn reply_camera_request(
+        &mut self,
+        to: &str,
+        request: CameraRequest,
+        outcome: Result<(), String>,
+    ) {
+        let reply = SignalingMsg::CameraControlReply {
+            from: self.current_username.clone().unwrap_or_default(),
+            to: to.to_owned(),
+            request,
+            accepted: outcome.is_ok(),
+            reason: outcome.err(),
+        };
+        let _ = self.send_signaling(reply);
+    }
+
+    /// Asks the user about the peer's oldest camera request.
+    fn render_camera_request_window(&mut self, ctx: &egui::Context) {
+        let Some(&request) = self.camera_requests.front() else {
+            return;
+        };
+        let peer = self.current_peer().unwrap_or_default();
+        let mut answer = None;
+        egui::Window::new(self.locale.tr("Camera control"))
+            .id(egui::Id::new("camera_request_window"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let what = self.locale.tr(request.describe());
+                let heading = ui.label(self.locale.trf(
+                    "{peer} asks to control your camera: {what}.",
+                    &[("peer", &peer), ("what", &what)],
+                ));
+                ui.horizontal(|ui| {
+                    let allow = ui.button(self.locale.tr("Allow")).labelled_by(heading.id);
+                    focus_if_unfocused(&allow);
+                    if allow.clicked() {
+                        answer = Some((true, false));
+                    }
+                    if ui
+                        .button(self.locale.tr("Allow for this call"))
+                        .labelled_by(heading.id)
+                        .clicked()
+                    {
+                        answer = Some((true, true));
+                    }
+                    if ui
+                        .button(self.locale.tr("Deny"))
+                        .labelled_by(heading.id)
+                        .clicked()
+                    {
+                        answer = Some((false, false));
+                    }
+                });
+            });
+        let Some((allowed, for_the_call)) = answer else {
+            return;
+        };
+        self.camera_requests.pop_front();
+        if !allowed {
+            self.reply_camera_request(&peer, request, Err("declined".into()));
+            return;
+        }
+        self.camera_control_allowed = for_the_call;
+        let outcome = self.apply_camera_request(request);
+        self.reply_camera_request(&peer, request, outcome);
+        if for_the_call {
+            // The rest were waiting on the same question
+            for request in std::mem::take(&mut self.camera_requests) {
+                let outcome = self.apply_camera_request(request);
+                self.reply_camera_request(&peer, request, outcome);
+            }
+        }
+    }
+
+    /// Menu of requests for the peer's camera, for remote assistance.
+    fn render_remote_camera_menu(&mut self, ui: &mut egui::Ui) {
+        let Some(peer) = self.current_peer().filter(|_| self.loopback.is_none()) else {
+            return;
+        };
+        let mut request = None;
+        ui.menu_button(self.locale.tr("Their camera"), |ui| {
+            if ui.button(self.locale.tr("Switch camera")).clicked() {
+                request = Some(CameraRequest::NextCamera);
+            }
+            ui.menu_button(self.locale.tr("Resolution"), |ui| {
+                for (w, h) in Self::RESOLUTIONS {
+                    if ui.button(format!("{w}x{h}")).clicked() {
+                        request = Some(CameraRequest::Resolution {
+                            width: u16::try_from(w).unwrap_or(u16::MAX),
+                            height: u16::try_from(h).unwrap_or(u16::MAX),
                         });
                     }
-                    ui.separator();
-                    ui.horizontal(|ui| {
-                        ui.label("Call controls:");
-                        if ui.button(egui::RichText::new("Hang up").strong()).clicked() {
-                            self.teardown_call(Some("hangup".into()), true);
-                        }
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button(self.locale.tr("Zoom in")).clicked() {
+                    self.remote_zoom = self.remote_zoom.saturating_add(Self::ZOOM_STEP);
+                    request = Some(CameraRequest::Zoom {
+                        level: self.remote_zoom,
                     });
+                }
+                if ui.button(self.locale.tr("Zoom out")).clicked() {
+                    self.remote_zoom = self.remote_zoom.saturating_sub(Self::ZOOM_STEP);
+                    request = Some(CameraRequest::Zoom {
+                        level: self.remote_zoom,
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.button(self.locale.tr("Light on")).clicked() {
+                    request = Some(CameraRequest::Torch { on: true });
+                }
+                if ui.button(self.locale.tr("Light off")).clicked() {
+                    request = Some(CameraRequest::Torch { on: false });
+                }
+            });
+            if request.is_some() {
+                ui.close_menu();
+            }
+        });
+        if let Some(request) = request {
+            let msg = SignalingMsg::CameraControl {
+                from: self.current_username.clone().unwrap_or_default(),
+                to: peer.clone(),
+                request,
+            };
+            if self.send_signaling(msg).is_ok() {
+                self.status_line = self
+                    .locale
+                    .trf("Waiting for {peer} to allow it…", &[("peer", &peer)]);
+            }
+        }
+    }
+
+    /// Playout volume and local mute of each remote peer. Only what we hear
+    /// changes; nothing is renegotiated.
+    fn render_volume_menu(&mut self, ui: &mut egui::Ui) {
+        let primary = self.engine.primary_peer().to_owned();
+        let primary_label = self
+            .current_peer()
+            .unwrap_or_else(|| self.locale.tr("Peer").to_owned());
+        let mut peers: Vec<(PeerId, String)> = vec![(primary, primary_label)];
+        peers.extend(self.peer_tiles.keys().map(|p| (p.clone(), p.clone())));
+        ui.menu_button(self.locale.tr("Volume"), |ui| {
+            for (peer, label) in peers {
+                let mut volume = self.engine.peer_volume(&peer);
+                let mut muted = self.engine.is_peer_muted(&peer);
+                ui.horizontal(|ui| {
+                    ui.label(&label);
+                    let slider = egui::Slider::new(&mut volume, 0.0..=MAX_GAIN)
+                        .custom_formatter(|v, _| format!("{:.0}%", v * 100.0));
+                    if ui
+                        .add_enabled(!muted, slider)
+                        .on_hover_text(self.locale.trf("Volume of {peer}", &[("peer", &label)]))
+                        .changed()
+                    {
+                        self.engine.set_peer_volume(&peer, volume);
+                    }
+                    if ui.checkbox(&mut muted, self.locale.tr("Mute")).changed() {
+                        self.engine.set_peer_muted(&peer, muted);
+                    }
                 });
+            }
+        });
+    }
+
+    /// DTMF keypad, for calls bridged into phone menus (IVRs).
+    fn render_keypad(&mut self, ui: &mut egui::Ui) {
+        const KEYS: [[char; 4]; 4] = [
+            ['1', '2', '3', 'A'],
+            ['4', '5', '6', 'B'],
+            ['7', '8', '9', 'C'],
+            ['*', '0', '#', 'D'],
+        ];
+        egui::Grid::new("dtmf_keypad").show(ui, |ui| {
+            for row in KEYS {
+                for key in row {
+                    let button = egui::Button::new(key.to_string())
+                        .min_size(egui::vec2(theme::MIN_HIT_TARGET, theme::MIN_HIT_TARGET));
+                    let tone = self.locale.trf("Dial tone {key}", &[("key", &key)]);
+                    let response = ui.add(button);
+                    response.widget_info(|| {
+                        egui::WidgetInfo::labeled(egui::WidgetType::Button, true, &tone)
+                    });
+                    if response.clicked() {
+                        match self.engine.send_dtmf(&key.to_string()) {
+                            Ok(()) => self.dtmf_sent.push(key),
+                            Err(e) => {
+                                self.status_line =
+                                    self.locale.trf("DTMF failed: {error}", &[("error", &e)])
+                            }
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+        if !self.dtmf_sent.is_empty() {
+            ui.label(
+                self.locale
+                    .trf("Sent: {digits}", &[("digits", &self.dtmf_sent)]),
+            );
+        }
+    }
+
+    fn render_speaking(ui: &mut egui::Ui, locale: Locale, who: &'static str, speaking: bool) {
+        let who = locale.tr(who);
+        if speaking {
+            ui.colored_label(
+                status_color(ui.visuals(), Status::Good),
+                locale.trf("● {who} speaking", &[("who", &who)]),
+            );
+        } else {
+            ui.weak(format!("○ {who}"));
+        }
+    }
+
+    /// Level meter over the top 60 dB, from the peer's audio level extension.
+    fn render_audio_level(ui: &mut egui::Ui, level: Option<AudioLevel>) {
+        if let Some(level) = level {
+            let fill = (1.0 - f32::from(level.level) / 60.0).clamp(0.0, 1.0);
+            ui.add(egui::ProgressBar::new(fill).desired_width(60.0))
+                .on_hover_text(format!("-{} dBov", level.level));
+        }
+    }
+
+    fn render_camera_off(ui: &mut egui::Ui, locale: Locale, off: bool) {
+        if off {
+            ui.colored_label(
+                status_color(ui.visuals(), Status::Bad),
+                locale.tr("camera off"),
+            );
         }
     }
+
+    /// Follows the push-to-talk key while the mode is on. The key is ignored
+    /// while a text field has focus.
+    fn poll_push_to_talk(&mut self, ctx: &egui::Context) {
+        let held = self.push_to_talk
+            && !ctx.wants_keyboard_input()
+            && ctx.input(|i| i.key_down(self.push_to_talk_key));
+        if held != self.push_to_talk_held {
+            self.push_to_talk_held = held;
+            self.engine.set_push_to_talk_pressed(held);
+        }
+    }
+
+    fn toggle_mute(&mut self) {
+        self.is_muted = !self.is_muted;
+        self.engine.set_audio_mute(self.is_muted);
+    }
+
+    fn toggle_video(&mut self) {
+        self.is_video_muted = !self.is_video_muted;
+        self.engine.set_video_muted(self.is_video_muted);
+    }
+
+    /// Tooltip naming `action`'s shortcut, if it has one.
+    fn shortcut_hint(&self, ctx: &egui::Context, action: Action) -> Option<String> {
+        self.shortcuts.get(action).map(|shortcut| {
+            self.locale.trf(
+                "Shortcut: {keys}",
+                &[("keys", &ctx.format_shortcut(shortcut))],
+            )
+        })
+    }
+
+    /// Runs the actions whose shortcut was pressed.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        for action in self.shortcuts.pressed(ctx) {
+            match action {
+                Action::ToggleMute => self.toggle_mute(),
+                Action::ToggleVideo => self.toggle_video(),
+                Action::HangUp => match self.call_flow {
+                    CallFlow::Idle => {}
+                    CallFlow::Dialing { .. } => {
+                        self.teardown_call(Some("cancelled".into()), true);
+                    }
+                    CallFlow::Incoming { .. } => self.decline_incoming_call(),
+                    CallFlow::Active { .. } => {
+                        self.teardown_call(Some("hangup".into()), true);
+                    }
+                },
+                Action::AcceptCall => {
+                    if matches!(self.call_flow, CallFlow::Incoming { .. }) {
+                        self.accept_incoming_call();
+                    }
+                }
+                Action::ShowShortcuts => self.show_shortcuts = !self.show_shortcuts,
+            }
+        }
+    }
+
+    /// Reference of the configured shortcuts.
+    fn render_shortcuts_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_shortcuts;
+        egui::Window::new(self.locale.tr("Keyboard shortcuts"))
+            .id(egui::Id::new("shortcuts_window"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortcuts_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (action, shortcut) in self.shortcuts.iter() {
+                            ui.monospace(ctx.format_shortcut(shortcut));
+                            ui.label(self.locale.tr(action.description()));
+                            ui.end_row();
+                        }
+                        ui.monospace(self.push_to_talk_key.name());
+                        ui.label(self.locale.tr("Hold to talk (push-to-talk mode)"));
+                        ui.end_row();
+                    });
+                ui.weak(
+                    self.locale
+                        .tr("Change them in the [Shortcuts] section of the configuration."),
+                );
+            });
+        self.show_shortcuts = open;
+    }
+
     const fn can_start(&self) -> bool {
-        self.has_remote_description
-            && self.has_local_description
-            && matches!(self.conn_state, ConnState::Idle | ConnState::Stopped)
+        self.has_remote_description && self.has_local_description && !self.conn_state.is_connected()
     }
 
     fn render_header(ui: &mut egui::Ui) {
@@ -981,106 +2660,161 @@ impl RtcApp {
 
     fn render_video_summary(
         ui: &mut egui::Ui,
+        locale: Locale,
         local_frame: Option<&VideoFrame>,
         remote_frame: Option<&VideoFrame>,
     ) {
         ui.separator();
-        ui.label(format!(
-            "Local video: {}",
-            Self::summarize_frame(local_frame)
+        ui.label(locale.trf(
+            "Local video: {frame}",
+            &[("frame", &Self::summarize_frame(local_frame))],
         ));
-        ui.label(format!(
-            "Remote video: {}",
-            Self::summarize_frame(remote_frame)
+        ui.label(locale.trf(
+            "Remote video: {frame}",
+            &[("frame", &Self::summarize_frame(remote_frame))],
         ));
     }
 
     fn render_signaling_panel(&mut self, ui: &mut egui::Ui) {
         ui.separator();
-        ui.heading("Signaling");
+        ui.heading(self.locale.tr("Signaling"));
         match self.signaling_screen {
             SignalingScreen::Connect => self.render_connect_screen(ui),
             SignalingScreen::Login => self.render_login_screen(ui),
             SignalingScreen::Home => self.render_home_screen(ui),
         }
         if let Some(err) = &self.signaling_error {
-            ui.colored_label(egui::Color32::LIGHT_RED, err);
+            ui.colored_label(status_color(ui.visuals(), Status::Bad), err);
         }
     }
 
     fn render_connect_screen(&mut self, ui: &mut egui::Ui) {
-        ui.label("Server address:");
-        ui.text_edit_singleline(&mut self.server_addr_input);
-        if ui.button("Connect").clicked() {
+        let label = ui.label(self.locale.tr("Server address:"));
+        let addr = ui
+            .text_edit_singleline(&mut self.server_addr_input)
+            .labelled_by(label.id);
+        focus_if_unfocused(&addr);
+        if ui.button(self.locale.tr("Connect")).clicked() || submitted(ui, &addr) {
             self.connect_to_signaling();
         }
     }
 
     fn render_login_screen(&mut self, ui: &mut egui::Ui) {
-        ui.label("Login");
+        ui.label(self.locale.tr("Login"));
         ui.horizontal(|ui| {
-            ui.label("Username");
-            ui.text_edit_singleline(&mut self.login_username);
+            let label = ui.label(self.locale.tr("Username"));
+            let user = ui
+                .text_edit_singleline(&mut self.login_username)
+                .labelled_by(label.id);
+            focus_if_unfocused(&user);
         });
-        ui.horizontal(|ui| {
-            ui.label("Password");
-            ui.add(egui::TextEdit::singleline(&mut self.login_password).password(true));
-        });
-        if ui.button("Login").clicked() {
+        let password = ui
+            .horizontal(|ui| {
+                let label = ui.label(self.locale.tr("Password"));
+                ui.add(egui::TextEdit::singleline(&mut self.login_password).password(true))
+                    .labelled_by(label.id)
+            })
+            .inner;
+        if ui.button(self.locale.tr("Login")).clicked() || submitted(ui, &password) {
             let _ = self.send_signaling(SignalingMsg::Login {
                 username: self.login_username.clone(),
                 password: self.login_password.clone(),
             });
         }
         ui.separator();
-        ui.label("Register");
+        ui.label(self.locale.tr("Register"));
         ui.horizontal(|ui| {
-            ui.label("Username");
-            ui.text_edit_singleline(&mut self.register_username);
+            let label = ui.label(self.locale.tr("Username"));
+            ui.text_edit_singleline(&mut self.register_username)
+                .labelled_by(label.id);
         });
-        ui.horizontal(|ui| {
-            ui.label("Password");
-            ui.add(egui::TextEdit::singleline(&mut self.register_password).password(true));
-        });
-        if ui.button("Register").clicked() {
+        let password = ui
+            .horizontal(|ui| {
+                let label = ui.label(self.locale.tr("Password"));
+                ui.add(egui::TextEdit::singleline(&mut self.register_password).password(true))
+                    .labelled_by(label.id)
+            })
+            .inner;
+        if ui.button(self.locale.tr("Register")).clicked() || submitted(ui, &password) {
             let _ = self.send_signaling(SignalingMsg::Register {
                 username: self.register_username.clone(),
                 password: self.register_password.clone(),
             });
         }
-        if ui.button("Disconnect").clicked() {
+        if ui.button(self.locale.tr("Disconnect")).clicked() {
             self.disconnect_from_signaling();
         }
     }
 
     fn render_home_screen(&mut self, ui: &mut egui::Ui) {
         if let Some(user) = &self.current_username {
-            ui.label(format!("Logged in as {user}"));
+            ui.label(
+                self.locale
+                    .trf("Logged in as {username}", &[("username", &user)]),
+            );
         }
         ui.horizontal(|ui| {
-            if ui.button("Refresh peers").clicked() {
+            if ui.button(self.locale.tr("Refresh peers")).clicked() {
                 self.request_peer_list();
             }
-            if ui.button("Disconnect").clicked() {
+            if ui.button(self.locale.tr("Disconnect")).clicked() {
                 self.disconnect_from_signaling();
             }
         });
         ui.separator();
-        ui.label("Available peers:");
+        ui.horizontal(|ui| {
+            for (tab, label) in [
+                (HomeTab::Peers, "Peers"),
+                (HomeTab::Room, "Room"),
+                (HomeTab::History, "History"),
+                (HomeTab::Contacts, "Contacts"),
+            ] {
+                ui.selectable_value(&mut self.home_tab, tab, self.locale.tr(label));
+            }
+        });
+        match self.home_tab {
+            HomeTab::Peers => self.render_peer_list(ui),
+            HomeTab::Room => self.render_room(ui),
+            HomeTab::History => self.render_call_history(ui),
+            HomeTab::Contacts => self.render_contacts(ui),
+        }
+        self.render_call_flow_ui(ui);
+    }
+
+    fn render_peer_list(&mut self, ui: &mut egui::Ui) {
+        ui.label(self.locale.tr("Available peers:"));
         if self.peers_online.is_empty() {
-            ui.label("No peers online.");
+            ui.label(self.locale.tr("No peers online."));
         } else {
             let peers = self.peers_online.clone();
             for (peer, status) in peers {
                 ui.horizontal(|ui| {
                     // 1. Visual Status Indicator
-                    let (icon, color, text) = match status {
-                        PeerStatus::Available => ("●", egui::Color32::GREEN, "Available"),
-                        PeerStatus::Busy => ("busy", egui::Color32::RED, "Busy"),
+                    let (icon, indicator, text) = match status {
+                        PeerStatus::Available => ("●", Status::Good, "Available"),
+                        PeerStatus::Busy => ("busy", Status::Bad, "Busy"),
                     };
+                    let text = self.locale.tr(text);
+                    let color = status_color(ui.visuals(), indicator);
 
+                    // Read as "alice, Available" rather than the glyph
                     ui.colored_label(color, format!("{} {}", icon, peer))
-                        .on_hover_text(text);
+                        .on_hover_text(text)
+                        .widget_info(|| {
+                            egui::WidgetInfo::labeled(
+                                egui::WidgetType::Label,
+                                true,
+                                format!("{peer}, {text}"),
+                            )
+                        });
+                    if self
+                        .peer_caps
+                        .get(&peer)
+                        .is_some_and(|caps| !caps.supports_video())
+                    {
+                        ui.label(self.locale.tr("(audio only)"))
+                            .on_hover_text(self.locale.tr("Peer does not support video"));
+                    }
 
                     // 2. Logic to disable call button
                     // We can't call if:
@@ -1096,45 +2830,362 @@ impl RtcApp {
 
                     let can_call = !i_am_busy && !peer_is_busy;
 
-                    if ui
-                        .add_enabled(can_call, egui::Button::new(format!("Call {peer}")))
-                        .clicked()
-                    {
-                        self.start_outgoing_call(&peer);
-                    }
-                });
-            }
+                    if ui
+                        .add_enabled(
+                            can_call,
+                            egui::Button::new(self.locale.trf("Call {peer}", &[("peer", &peer)])),
+                        )
+                        .clicked()
+                    {
+                        self.start_outgoing_call(&peer);
+                    }
+                    if ui
+                        .small_button(self.locale.tr("Block"))
+                        .on_hover_text(
+                            self.locale
+                                .tr("Refuse their calls and hide each from the other"),
+                        )
+                        .clicked()
+                    {
+                        let _ = self.send_signaling(SignalingMsg::Block {
+                            username: peer.clone(),
+                        });
+                    }
+                });
+            }
+        }
+    }
+
+    /// Our contacts, each with its online status, and the users we blocked.
+    fn render_contacts(&mut self, ui: &mut egui::Ui) {
+        let mut change = None;
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.contact_input)
+                    .hint_text(self.locale.tr("Username"))
+                    .desired_width(120.0),
+            );
+            let user = self.contact_input.trim().to_owned();
+            if ui
+                .add_enabled(
+                    !user.is_empty(),
+                    egui::Button::new(self.locale.tr("Add contact")),
+                )
+                .clicked()
+            {
+                change = Some(SignalingMsg::AddContact {
+                    username: user.clone(),
+                });
+            }
+            if ui
+                .add_enabled(!user.is_empty(), egui::Button::new(self.locale.tr("Block")))
+                .clicked()
+            {
+                change = Some(SignalingMsg::Block { username: user });
+            }
+        });
+        if self.contacts.is_empty() {
+            ui.label(self.locale.tr("No contacts yet."));
+        }
+        for contact in &self.contacts {
+            ui.horizontal(|ui| {
+                let online = self.peers_online.iter().any(|(p, _)| p == contact);
+                let (indicator, text) = if online {
+                    (Status::Good, "Available")
+                } else {
+                    (Status::Bad, "Offline")
+                };
+                ui.colored_label(status_color(ui.visuals(), indicator), contact)
+                    .on_hover_text(self.locale.tr(text));
+                if ui.small_button(self.locale.tr("Remove")).clicked() {
+                    change = Some(SignalingMsg::RemoveContact {
+                        username: contact.clone(),
+                    });
+                }
+            });
+        }
+        if !self.blocked.is_empty() {
+            ui.separator();
+            ui.label(self.locale.tr("Blocked:"));
+            for user in &self.blocked {
+                ui.horizontal(|ui| {
+                    ui.label(user);
+                    if ui.small_button(self.locale.tr("Unblock")).clicked() {
+                        change = Some(SignalingMsg::Unblock {
+                            username: user.clone(),
+                        });
+                    }
+                });
+            }
+        }
+        if let Some(msg) = change {
+            if matches!(
+                msg,
+                SignalingMsg::AddContact { .. } | SignalingMsg::Block { .. }
+            ) {
+                self.contact_input.clear();
+            }
+            let _ = self.send_signaling(msg);
+        }
+    }
+
+    /// Create a room or join one by code; inside a room, its code to share
+    /// and the other members, each with a Call button.
+    fn render_room(&mut self, ui: &mut egui::Ui) {
+        let Some(room) = self.room.clone() else {
+            let waiting = self.pending_room_code.is_some();
+            ui.horizontal(|ui| {
+                ui.label(self.locale.tr("Capacity:"));
+                ui.add(egui::Slider::new(
+                    &mut self.room_capacity,
+                    2..=Self::MAX_ROOM_CAPACITY,
+                ));
+                if ui
+                    .add_enabled(!waiting, egui::Button::new(self.locale.tr("Create room")))
+                    .clicked()
+                {
+                    let capacity = self.room_capacity;
+                    let _ = self.send_signaling(SignalingMsg::CreateSession { capacity });
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(self.locale.tr("Code:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.room_code_input)
+                        .char_limit(6)
+                        .desired_width(80.0),
+                );
+                let code = self.room_code_input.trim().to_owned();
+                if ui
+                    .add_enabled(
+                        !waiting && !code.is_empty(),
+                        egui::Button::new(self.locale.tr("Join by code")),
+                    )
+                    .clicked()
+                    && self
+                        .send_signaling(SignalingMsg::Join {
+                            session_code: code.clone(),
+                        })
+                        .is_ok()
+                {
+                    self.pending_room_code = Some(code);
+                }
+            });
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(self.locale.tr("Room code:"));
+            ui.strong(&room.code);
+            if ui.small_button(self.locale.tr("Copy")).clicked() {
+                ui.output_mut(|o| o.copied_text = room.code.clone());
+            }
+        });
+        let idle = matches!(self.call_flow, CallFlow::Idle);
+        let mut call = None;
+        if room.members.iter().all(|m| m == SFU_USERNAME) {
+            ui.label(
+                self.locale
+                    .tr("Nobody else is here yet. Share the code to invite someone."),
+            );
+        }
+        for member in &room.members {
+            ui.horizontal(|ui| {
+                // The server's SFU relays the whole room in one call.
+                let sfu = member == SFU_USERNAME;
+                let label = if sfu {
+                    ui.label(self.locale.tr("Everyone (through the server)"));
+                    self.locale.tr("Join the room call").to_owned()
+                } else {
+                    ui.label(member);
+                    self.locale.trf("Call {peer}", &[("peer", &member)])
+                };
+                let available = sfu
+                    || self
+                        .peers_online
+                        .iter()
+                        .any(|(p, status)| p == member && matches!(status, PeerStatus::Available));
+                if ui
+                    .add_enabled(idle && available, egui::Button::new(label))
+                    .on_disabled_hover_text(self.locale.tr("You or they are in a call"))
+                    .clicked()
+                {
+                    call = Some(member.clone());
+                }
+            });
+        }
+        if ui.button(self.locale.tr("Leave room")).clicked() {
+            let _ = self.send_signaling(SignalingMsg::Leave {
+                session_id: room.session_id,
+            });
+            self.room = None;
+            self.status_line = self.locale.trf("Left room {code}", &[("code", &room.code)]);
+        }
+        if let Some(peer) = call {
+            self.start_outgoing_call(&peer);
+        }
+    }
+
+    /// Past calls, newest first, with a button to call the peer again.
+    fn render_call_history(&mut self, ui: &mut egui::Ui) {
+        if self.call_history.records().is_empty() {
+            ui.label(self.locale.tr("No calls yet."));
+            return;
+        }
+        let now = call_history::now_secs();
+        let idle = matches!(self.call_flow, CallFlow::Idle);
+        let mut redial = None;
+        egui::ScrollArea::vertical()
+            .id_source("call_history")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("call_history_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for record in self.call_history.records().iter().rev() {
+                            ui.label(match record.direction {
+                                CallDirection::Outgoing => "↗",
+                                CallDirection::Incoming => "↙",
+                            });
+                            ui.label(&record.peer);
+                            let outcome = self.locale.tr(record.outcome.as_str());
+                            let outcome = match record.duration_secs {
+                                Some(d) => {
+                                    format!("{outcome} ({})", call_history::format_duration(d))
+                                }
+                                None => outcome.to_owned(),
+                            };
+                            if record.outcome == CallOutcome::Missed {
+                                ui.colored_label(status_color(ui.visuals(), Status::Bad), outcome);
+                            } else {
+                                ui.label(outcome);
+                            }
+                            ui.label(call_history::format_ago(record.started, now, self.locale));
+                            let available = self.peers_online.iter().any(|(p, status)| {
+                                *p == record.peer && matches!(status, PeerStatus::Available)
+                            });
+                            if ui
+                                .add_enabled(
+                                    idle && available,
+                                    egui::Button::new(self.locale.tr("Call")),
+                                )
+                                .on_disabled_hover_text(self.locale.tr("Peer is offline or busy"))
+                                .clicked()
+                            {
+                                redial = Some(record.peer.clone());
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if ui.button(self.locale.tr("Clear history")).clicked() {
+            self.call_history.clear();
+            self.save_call_history();
+        }
+        if let Some(peer) = redial {
+            self.start_outgoing_call(&peer);
+        }
+    }
+
+    /// Records how the call in progress ended and saves the history.
+    fn finish_call_record(&mut self, end: CallEnd) {
+        if self
+            .call_history
+            .finish(end, call_history::now_secs())
+            .is_some()
+        {
+            self.save_call_history();
+        }
+    }
+
+    fn save_call_history(&mut self) {
+        if let Err(e) = self.call_history.save() {
+            self.status_line = self
+                .locale
+                .trf("Could not save call history: {error}", &[("error", &e)]);
         }
-        self.render_call_flow_ui(ui);
     }
     fn render_call_flow_ui(&mut self, ui: &mut egui::Ui) {
         ui.separator();
         match self.call_flow.clone() {
             CallFlow::Idle => {
-                ui.label("No active calls.");
+                ui.label(self.locale.tr("No active calls."));
             }
             CallFlow::Dialing { peer, .. } => {
-                ui.label(format!("Calling {peer}…"));
-                if ui.button("Cancel outgoing call").clicked() {
+                ui.label(self.locale.trf("Calling {peer}…", &[("peer", &peer)]));
+                let cancel = ui.button(self.locale.tr("Cancel outgoing call"));
+                focus_if_unfocused(&cancel);
+                if with_hint(cancel, self.shortcut_hint(ui.ctx(), Action::HangUp)).clicked() {
                     self.teardown_call(Some("cancelled".into()), true);
                 }
             }
             CallFlow::Incoming { from, .. } => {
-                ui.label(format!("Incoming call from {from}"));
+                let heading = ui.label(
+                    self.locale
+                        .trf("Incoming call from {from}", &[("from", &from)]),
+                );
                 ui.horizontal(|ui| {
-                    if ui.button("Accept").clicked() {
+                    // Enter or Space answers; Tab moves to Decline
+                    let accept = ui.button(self.locale.tr("Accept")).labelled_by(heading.id);
+                    focus_if_unfocused(&accept);
+                    if with_hint(accept, self.shortcut_hint(ui.ctx(), Action::AcceptCall)).clicked()
+                    {
                         self.accept_incoming_call();
                     }
-                    if ui.button("Decline").clicked() {
+                    let decline = ui.button(self.locale.tr("Decline")).labelled_by(heading.id);
+                    if with_hint(decline, self.shortcut_hint(ui.ctx(), Action::HangUp)).clicked() {
                         self.decline_incoming_call();
                     }
                 });
             }
-            CallFlow::Active { peer } => {
-                ui.label(format!("In call with {peer}"));
-                if ui.button("Hang up").clicked() {
-                    self.teardown_call(Some("hangup".into()), true);
-                }
+            CallFlow::Active { peer, hold, .. } => {
+                let status = match (hold.local, hold.remote) {
+                    (false, false) => "In call with {peer}",
+                    (true, _) => "In call with {peer} (on hold)",
+                    (false, true) => "In call with {peer} (held by peer)",
+                };
+                ui.label(self.locale.trf(status, &[("peer", &peer)]));
+                ui.horizontal(|ui| {
+                    let hold_label = if hold.local { "Resume" } else { "Hold" };
+                    if ui.button(self.locale.tr(hold_label)).clicked() {
+                        self.toggle_hold();
+                    }
+                    let share_label = if self.shared_track.is_some() {
+                        "Stop sharing"
+                    } else {
+                        "Share pattern"
+                    };
+                    if ui.button(self.locale.tr(share_label)).clicked() {
+                        self.toggle_share();
+                    }
+                    let targets: Vec<String> = self
+                        .peers_online
+                        .iter()
+                        .filter(|(user, status)| {
+                            *user != peer && matches!(status, PeerStatus::Available)
+                        })
+                        .map(|(user, _)| user.clone())
+                        .collect();
+                    let mut transfer_to = None;
+                    ui.add_enabled_ui(!targets.is_empty(), |ui| {
+                        ui.menu_button(self.locale.tr("Transfer"), |ui| {
+                            for target in &targets {
+                                if ui.button(target).clicked() {
+                                    transfer_to = Some(target.clone());
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                    if let Some(target) = transfer_to {
+                        self.transfer_call(&target);
+                    }
+                    let hang_up = ui.button(self.locale.tr("Hang up"));
+                    if with_hint(hang_up, self.shortcut_hint(ui.ctx(), Action::HangUp)).clicked() {
+                        self.teardown_call(Some("hangup".into()), true);
+                    }
+                });
             }
         }
     }
@@ -1143,16 +3194,21 @@ impl RtcApp {
         ui.separator();
         ui.horizontal(|ui| {
             if ui
-                .add_enabled(self.can_start(), egui::Button::new("Start Connection"))
+                .add_enabled(
+                    self.can_start(),
+                    egui::Button::new(self.locale.tr("Start Connection")),
+                )
                 .clicked()
                 && let Err(e) = self.engine.start()
             {
-                self.status_line = format!("Failed to start: {e}");
+                self.status_line = self
+                    .locale
+                    .trf("Failed to start: {error}", &[("error", &e)]);
             }
             if ui
                 .add_enabled(
-                    matches!(self.conn_state, ConnState::Running),
-                    egui::Button::new("End call"),
+                    self.conn_state.is_connected(),
+                    egui::Button::new(self.locale.tr("End call")),
                 )
                 .clicked()
             {
@@ -1160,24 +3216,559 @@ impl RtcApp {
             }
 
             let mute_label = if self.is_muted { "Unmute" } else { "Mute" };
-            if ui.button(mute_label).clicked() {
-                self.is_muted = !self.is_muted;
-                self.engine.set_audio_mute(self.is_muted);
+            let mute = ui.button(self.locale.tr(mute_label));
+            if with_hint(mute, self.shortcut_hint(ui.ctx(), Action::ToggleMute)).clicked() {
+                self.toggle_mute();
+            }
+            let video_label = if self.is_video_muted {
+                "Start video"
+            } else {
+                "Stop video"
+            };
+            let video = ui.button(self.locale.tr(video_label));
+            if with_hint(video, self.shortcut_hint(ui.ctx(), Action::ToggleVideo)).clicked() {
+                self.toggle_video();
+            }
+            let ptt_label = self.locale.trf(
+                "Push-to-talk ({key})",
+                &[("key", &self.push_to_talk_key.name())],
+            );
+            if ui.checkbox(&mut self.push_to_talk, ptt_label).changed() {
+                self.engine.set_push_to_talk(self.push_to_talk);
+            }
+            if self.push_to_talk && self.push_to_talk_held {
+                ui.colored_label(
+                    status_color(ui.visuals(), Status::Good),
+                    self.locale.tr("Talking"),
+                );
             }
 
-            ui.label(format!("State: {:?}", self.conn_state));
+            self.render_record_button(ui);
+
+            ui.label(self.locale.trf(
+                "State: {state}",
+                &[("state", &format!("{:?}", self.conn_state))],
+            ));
         });
     }
 
-    fn render_log_section(&self, ui: &mut egui::Ui) {
+    fn render_audio_pickers(&mut self, ui: &mut egui::Ui) {
+        if let Some(mic) = Self::audio_combo(
+            ui,
+            self.locale,
+            "Mic",
+            &self.audio_inputs,
+            &self.selected_mic,
+        ) {
+            self.push_ui_log(format!(
+                "Microphone: {}",
+                mic.as_deref().unwrap_or("default")
+            ));
+            self.engine.set_audio_input(mic.clone());
+            // Empty means the default device, whatever the config says
+            self.save_setting("Media", "audio_input", mic.clone().unwrap_or_default());
+            self.selected_mic = mic;
+        }
+        if let Some(speaker) = Self::audio_combo(
+            ui,
+            self.locale,
+            "Speaker",
+            &self.audio_outputs,
+            &self.selected_speaker,
+        ) {
+            self.push_ui_log(format!(
+                "Speaker: {}",
+                speaker.as_deref().unwrap_or("default")
+            ));
+            self.engine.set_audio_output(speaker.clone());
+            self.save_setting("Media", "audio_output", speaker.clone().unwrap_or_default());
+            self.selected_speaker = speaker;
+        }
+    }
+
+    /// Device dropdown; returns the new selection (`Some(None)` = default)
+    /// when it changed.
+    fn audio_combo(
+        ui: &mut egui::Ui,
+        locale: Locale,
+        label: &'static str,
+        devices: &[AudioDevice],
+        selected: &Option<String>,
+    ) -> Option<Option<String>> {
+        let mut picked = None;
+        let default = locale.tr("Default");
+        egui::ComboBox::from_label(locale.tr(label))
+            .selected_text(selected.as_deref().unwrap_or(default))
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(selected.is_none(), default).clicked() {
+                    picked = Some(None);
+                }
+                for device in devices {
+                    let is_selected = selected.as_deref() == Some(device.name.as_str());
+                    if ui.selectable_label(is_selected, device.label()).clicked() {
+                        picked = Some(Some(device.name.clone()));
+                    }
+                }
+            });
+        picked.filter(|p| p != selected)
+    }
+
+    fn render_record_button(&mut self, ui: &mut egui::Ui) {
+        if let Some(status) = self.engine.recording() {
+            if ui.button(self.locale.tr("Stop recording")).clicked() {
+                match self.engine.stop_recording() {
+                    Ok(Some(path)) => {
+                        self.push_ui_log(format!("Recording saved to {}", path.display()));
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.status_line = self
+                            .locale
+                            .trf("Failed to save recording: {error}", &[("error", &e)])
+                    }
+                }
+            }
+            let secs = status.elapsed.as_secs();
+            ui.colored_label(
+                status_color(ui.visuals(), Status::Bad),
+                format!("● REC {:02}:{:02}", secs / 60, secs % 60),
+            );
+        } else if ui
+            .add_enabled(
+                self.conn_state.is_connected(),
+                egui::Button::new(self.locale.tr("Record")),
+            )
+            .clicked()
+        {
+            match self.engine.start_recording(None, RecordStreams::default()) {
+                Ok(path) => self.push_ui_log(format!("Recording to {}", path.display())),
+                Err(e) => {
+                    self.status_line = self
+                        .locale
+                        .trf("Failed to start recording: {error}", &[("error", &e)])
+                }
+            }
+        }
+    }
+
+    fn render_camera_picker(&mut self, ui: &mut egui::Ui) {
+        let selected = self.selected_camera.map_or_else(
+            || self.locale.tr("Default").to_owned(),
+            |id| {
+                self.cameras
+                    .iter()
+                    .find(|c| c.index == id)
+                    .map_or_else(|| format!("{id}"), |c| c.name.clone())
+            },
+        );
+        let mut picked = None;
+        egui::ComboBox::from_label(self.locale.tr("Camera"))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for camera in &self.cameras {
+                    if ui
+                        .selectable_label(
+                            self.selected_camera == Some(camera.index),
+                            camera.label(),
+                        )
+                        .clicked()
+                    {
+                        picked = Some(camera.index);
+                    }
+                }
+            });
+        if ui.button(self.locale.tr("Rescan")).clicked() {
+            self.cameras = list_cameras();
+            self.audio_inputs = list_input_devices();
+            self.audio_outputs = list_output_devices();
+            self.push_ui_log(format!(
+                "Found {} camera(s), {} microphone(s), {} speaker(s)",
+                self.cameras.len(),
+                self.audio_inputs.len(),
+                self.audio_outputs.len()
+            ));
+        }
+
+        let (mut test_video, mut test_audio) = self.engine.test_sources();
+        let video_changed = ui
+            .checkbox(&mut test_video, self.locale.tr("Test video"))
+            .changed();
+        let audio_changed = ui
+            .checkbox(&mut test_audio, self.locale.tr("Test tone"))
+            .changed();
+        if video_changed || audio_changed {
+            self.engine.set_test_sources(test_video, test_audio);
+        }
+
+        if let Some(id) = picked
+            && self.selected_camera != Some(id)
+        {
+            self.selected_camera = Some(id);
+            self.save_setting("Media", "camera", id.to_string());
+            match self.engine.switch_camera(id) {
+                Some(status) => self.push_ui_log(status),
+                None => self.push_ui_log(format!("Camera {id} will be used when the call starts")),
+            }
+        }
+    }
+
+    /// Device, quality, server and language choices, saved as they are made.
+    fn render_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        egui::Window::new(self.locale.tr("Settings"))
+            .id(egui::Id::new("settings_window"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading(self.locale.tr("Video"));
+                self.render_camera_picker(ui);
+                self.render_resolution_picker(ui);
+                self.render_settings_preview(ui);
+                ui.separator();
+                ui.heading(self.locale.tr("Audio"));
+                self.render_audio_pickers(ui);
+                ui.separator();
+                ui.heading(self.locale.tr("Network"));
+                self.render_quality_picker(ui);
+                let mut low_data_mode = self.low_data_mode;
+                if ui
+                    .checkbox(&mut low_data_mode, self.locale.tr("Low data mode"))
+                    .on_hover_text(self.locale.tr(
+                        "Caps video at a low bitrate and frame rate; audio changes with the next call.",
+                    ))
+                    .changed()
+                {
+                    self.set_low_data_mode(low_data_mode);
+                }
+                ui.horizontal(|ui| {
+                    ui.label(self.locale.tr("Signaling server:"));
+                    let edit = ui.text_edit_singleline(&mut self.server_addr_input);
+                    if edit.lost_focus() {
+                        let addr = self.server_addr_input.trim().to_owned();
+                        self.save_setting("Signaling", "server_address", addr);
+                    }
+                });
+                ui.weak(
+                    self.locale
+                        .tr("The server address is used on the next connect."),
+                );
+                ui.separator();
+                ui.heading(self.locale.tr("Appearance"));
+                self.render_language_picker(ui);
+                self.render_appearance_pickers(ui);
+            });
+        self.show_settings = open;
+    }
+
+    fn render_appearance_pickers(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        egui::ComboBox::from_label(self.locale.tr("Theme"))
+            .selected_text(self.locale.tr(self.theme.label()))
+            .show_ui(ui, |ui| {
+                for theme in Theme::ALL {
+                    if ui
+                        .selectable_label(self.theme == theme, self.locale.tr(theme.label()))
+                        .clicked()
+                    {
+                        picked = Some(theme);
+                    }
+                }
+            });
+        if let Some(theme) = picked.filter(|t| *t != self.theme) {
+            self.theme = theme;
+            self.save_setting("UI", "theme", theme.as_str().to_owned());
+        }
+        if ui
+            .checkbox(&mut self.high_contrast, self.locale.tr("High contrast"))
+            .on_hover_text(
+                self.locale
+                    .tr("Solid backgrounds, full-strength text and thick outlines"),
+            )
+            .changed()
+        {
+            self.save_setting("UI", "high_contrast", self.high_contrast.to_string());
+        }
+
+        let slider = ui.add(
+            egui::Slider::new(&mut self.ui_scale, theme::MIN_SCALE..=theme::MAX_SCALE)
+                .step_by(0.05)
+                .text(self.locale.tr("UI scale")),
+        );
+        // Rescaling while dragging would move the slider under the pointer
+        if slider.drag_stopped() || (slider.changed() && !slider.dragged()) {
+            ui.ctx().set_zoom_factor(self.ui_scale);
+            self.save_setting("UI", "scale", format!("{:.2}", self.ui_scale));
+        }
+    }
+
+    /// Switches egui's visuals when the theme (or, for the system theme,
+    /// the operating system's) or the contrast asks for other ones.
+    fn apply_theme(&mut self, ctx: &egui::Context, frame: &Frame) {
+        let system_dark = frame.info().system_theme.map(|t| t == eframe::Theme::Dark);
+        let wanted = (self.theme.is_dark(system_dark), self.high_contrast);
+        if self.applied_visuals != Some(wanted) {
+            ctx.set_visuals(theme::visuals(wanted.0, wanted.1));
+            self.applied_visuals = Some(wanted);
+        }
+    }
+
+    fn render_language_picker(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        egui::ComboBox::from_label(self.locale.tr("Language"))
+            .selected_text(self.locale.name())
+            .show_ui(ui, |ui| {
+                for locale in Locale::ALL {
+                    if ui
+                        .selectable_label(self.locale == locale, locale.name())
+                        .clicked()
+                    {
+                        picked = Some(locale);
+                    }
+                }
+            });
+        if let Some(locale) = picked.filter(|l| *l != self.locale) {
+            self.locale = locale;
+            self.save_setting("UI", "language", locale.code().to_owned());
+        }
+    }
+
+    fn render_quality_picker(&mut self, ui: &mut egui::Ui) {
+        let selected = self
+            .locale
+            .tr(self.quality.map_or("Default", |q| q.as_str()));
+        egui::ComboBox::from_label(self.locale.tr("Quality"))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for preset in [
+                    QualityPreset::Low,
+                    QualityPreset::Medium,
+                    QualityPreset::High,
+                ] {
+                    if ui
+                        .selectable_label(
+                            self.quality == Some(preset),
+                            self.locale.tr(preset.as_str()),
+                        )
+                        .clicked()
+                    {
+                        self.quality = Some(preset);
+                        self.engine.set_quality_preset(preset);
+                        self.save_setting("Media", "quality", preset.as_str().to_owned());
+                    }
+                }
+            });
+    }
+
+    /// Applies low data mode to the engine and the camera, and saves it.
+    fn set_low_data_mode(&mut self, on: bool) {
+        self.low_data_mode = on;
+        self.engine.set_low_data_mode(on);
+        if let Some(preset) = self.quality {
+            self.engine.set_quality_preset(preset);
+        }
+        let mut config = (*self.config).clone();
+        config.set("Media", "low_data_mode", on.to_string());
+        self.capture_settings.fps = CaptureSettings::from_config(&config).fps;
+        self.config = Arc::new(config);
+        if let Some(status) = self.engine.set_capture_settings(self.capture_settings) {
+            self.push_ui_log(status);
+        }
+        self.save_setting("Media", "low_data_mode", on.to_string());
+    }
+
+    fn render_resolution_picker(&mut self, ui: &mut egui::Ui) {
+        let current = (self.capture_settings.width, self.capture_settings.height);
+        let selected = match current {
+            (Some(w), Some(h)) => format!("{w}x{h}"),
+            _ => self.locale.tr("Default").to_owned(),
+        };
+        let mut picked = None;
+        egui::ComboBox::from_label(self.locale.tr("Resolution"))
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                if ui
+                    .selectable_label(current == (None, None), self.locale.tr("Default"))
+                    .clicked()
+                {
+                    picked = Some((None, None));
+                }
+                for (w, h) in Self::RESOLUTIONS {
+                    if ui
+                        .selectable_label(current == (Some(w), Some(h)), format!("{w}x{h}"))
+                        .clicked()
+                    {
+                        picked = Some((Some(w), Some(h)));
+                    }
+                }
+            });
+        let Some((width, height)) = picked.filter(|p| *p != current) else {
+            return;
+        };
+        self.capture_settings.width = width;
+        self.capture_settings.height = height;
+        if let Some(status) = self.engine.set_capture_settings(self.capture_settings) {
+            self.push_ui_log(status);
+        }
+        // Empty values leave the resolution to the driver
+        let to_value = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_default();
+        self.save_setting("Media", "capture_width", to_value(width));
+        self.save_setting("Media", "capture_height", to_value(height));
+    }
+
+    /// The selected camera: the preview outside of a call, the local video
+    /// during one.
+    fn render_settings_preview(&self, ui: &mut egui::Ui) {
+        let texture = if matches!(self.call_flow, CallFlow::Idle) {
+            self.preview_texture
+        } else {
+            self.local_camera_texture
+        };
+        show_camera_in_ui(ui, texture, Self::PREVIEW_SIZE, Self::PREVIEW_SIZE);
+        if let Some(status) = self.camera_preview.as_ref().and_then(|p| p.status.as_ref()) {
+            ui.weak(status);
+        }
+    }
+
+    /// Runs the camera preview while Settings is open and there is no call,
+    /// restarting it when the camera or resolution changes.
+    fn update_camera_preview(&mut self, ctx: &egui::Context) {
+        if !self.show_settings || !matches!(self.call_flow, CallFlow::Idle) {
+            // Releases the device before a call needs it
+            self.camera_preview = None;
+            if let Some((id, _)) = self.preview_texture.take() {
+                ctx.tex_manager().write().free(id);
+            }
+            return;
+        }
+
+        let camera_id = self
+            .selected_camera
+            .or_else(|| self.cameras.first().map(|c| c.index))
+            .unwrap_or(DEFAULT_CAMERA_ID);
+        let current = self
+            .camera_preview
+            .as_ref()
+            .is_some_and(|p| p.is_for(camera_id, &self.capture_settings));
+        if !current {
+            // Stop the old capture first: it may hold the same device
+            self.camera_preview = None;
+            self.camera_preview = Some(CameraPreview::start(
+                Arc::new(self.logger.handle()),
+                camera_id,
+                self.capture_settings,
+            ));
+        }
+
+        if let Some(frame) = self.camera_preview.as_ref().and_then(CameraPreview::latest)
+            && let VideoFrameData::Rgb(rgb) = &frame.data
+        {
+            update_rgb_texture(
+                ctx,
+                &mut self.preview_texture,
+                frame.width,
+                frame.height,
+                rgb,
+                "camera/preview",
+            );
+        }
+        ctx.request_repaint_after(std::time::Duration::from_millis(33));
+    }
+
+    /// Saves one choice made in Settings; failures only reach the status line.
+    fn save_setting(&mut self, section: &str, key: &str, value: String) {
+        self.settings.set(section, key, Some(value));
+        if let Err(e) = self.settings.save() {
+            self.status_line = self
+                .locale
+                .trf("Could not save settings: {error}", &[("error", &e)]);
+        }
+    }
+
+    /// Remembers the window size and position (and whether Settings was
+    /// open) for the next launch.
+    fn save_window_layout(&mut self, ctx: &egui::Context) {
+        let (inner, outer) = ctx.input(|i| (i.viewport().inner_rect, i.viewport().outer_rect));
+        if let Some(inner) = inner {
+            self.settings.set(
+                "UI",
+                "window_width",
+                Some(inner.width().round().to_string()),
+            );
+            self.settings.set(
+                "UI",
+                "window_height",
+                Some(inner.height().round().to_string()),
+            );
+        }
+        if let Some(outer) = outer {
+            self.settings
+                .set("UI", "window_x", Some(outer.min.x.round().to_string()));
+            self.settings
+                .set("UI", "window_y", Some(outer.min.y.round().to_string()));
+        }
+        self.settings
+            .set("UI", "settings_open", Some(self.show_settings.to_string()));
+        if let Err(e) = self.settings.save() {
+            sink_warn!(
+                self.logger.handle(),
+                "[Settings] could not save window layout: {e}"
+            );
+        }
+    }
+
+    fn render_log_section(&mut self, ui: &mut egui::Ui) {
         ui.separator();
-        ui.label("Logs:");
+        let viewer = &mut self.log_viewer;
+        ui.horizontal_wrapped(|ui| {
+            ui.label(self.locale.tr("Logs:"));
+            for level in LogLevel::ALL {
+                let mut shown = viewer.shows(level);
+                if ui.checkbox(&mut shown, format!("{level:?}")).changed() {
+                    viewer.set_shown(level, shown);
+                }
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label(self.locale.tr("Search:"));
+            ui.add(egui::TextEdit::singleline(&mut viewer.search).desired_width(160.0));
+            let mut paused = viewer.is_paused();
+            if ui.checkbox(&mut paused, self.locale.tr("Pause")).changed() {
+                viewer.set_paused(paused);
+            }
+            ui.checkbox(&mut viewer.auto_scroll, self.locale.tr("Auto-scroll"));
+            if ui.button(self.locale.tr("Copy")).clicked() {
+                let text = viewer.visible_text();
+                ui.output_mut(|o| o.copied_text = text);
+            }
+            if ui.button(self.locale.tr("Clear")).clicked() {
+                viewer.clear();
+            }
+            if viewer.held() > 0 {
+                ui.weak(self.locale.trf(
+                    "{count} new lines while paused",
+                    &[("count", &viewer.held())],
+                ));
+            }
+        });
         egui::ScrollArea::vertical()
-            .stick_to_bottom(true)
+            .stick_to_bottom(viewer.auto_scroll && !viewer.is_paused())
+            .auto_shrink([false, true])
             .max_height(180.0)
             .show(ui, |ui| {
-                for line in &self.ui_logs {
-                    ui.monospace(line);
+                for line in viewer.visible() {
+                    let color = match line.level {
+                        Some(LogLevel::Error) => Some(status_color(ui.visuals(), Status::Bad)),
+                        Some(LogLevel::Warn) => Some(status_color(ui.visuals(), Status::Warn)),
+                        Some(LogLevel::Debug | LogLevel::Trace) => {
+                            Some(ui.visuals().weak_text_color())
+                        }
+                        Some(LogLevel::Info) | None => None,
+                    };
+                    let mut text = egui::RichText::new(&line.text).monospace();
+                    if let Some(color) = color {
+                        text = text.color(color);
+                    }
+                    ui.label(text);
                 }
             });
     }
@@ -1224,9 +3815,9 @@ impl RtcApp {
         }
     }
     // Render function for Network Metrics
-    fn render_network_stats(&self, ui: &mut egui::Ui) {
+    fn render_network_stats(&mut self, ui: &mut egui::Ui) {
         ui.separator();
-        ui.heading("Network Health");
+        ui.heading(self.locale.tr("Network Health"));
 
         egui::Grid::new("metrics_grid")
             .num_columns(2)
@@ -1234,81 +3825,292 @@ impl RtcApp {
             .striped(true)
             .show(ui, |ui| {
                 // Bitrate
-                ui.label("Encoder Bitrate:");
+                ui.label(self.locale.tr("Encoder Bitrate:"));
                 if let Some(bps) = self.current_bitrate {
                     ui.label(format!("{:.2} Mbps", bps as f32 / 1_000_000.0));
                 } else {
-                    ui.label("Unknown");
+                    ui.label(self.locale.tr("Unknown"));
                 }
                 ui.end_row();
 
                 if let Some(m) = &self.last_metrics {
                     // RTT
-                    ui.label("Round Trip Time (RTT):");
+                    ui.label(self.locale.tr("Round Trip Time (RTT):"));
                     let rtt_ms = m.round_trip_time.as_millis();
                     // Color code RTT: Green < 100ms, Yellow < 200ms, Red > 200ms
-                    let color = if rtt_ms < 100 {
-                        egui::Color32::GREEN
+                    let status = if rtt_ms < 100 {
+                        Status::Good
                     } else if rtt_ms < 200 {
-                        egui::Color32::YELLOW
+                        Status::Warn
                     } else {
-                        egui::Color32::RED
+                        Status::Bad
                     };
+                    let color = status_color(ui.visuals(), status);
                     ui.colored_label(color, format!("{} ms", rtt_ms));
                     ui.end_row();
 
                     // Packet Loss
-                    ui.label("Packet Loss:");
+                    ui.label(self.locale.tr("Packet Loss:"));
                     // fraction_lost is 0..255 (0 = 0%, 255 = 100%)
                     let loss_pct = (m.fraction_lost as f32 / 255.0) * 100.0;
 
-                    let color = if loss_pct < 2.0 {
-                        egui::Color32::GREEN
+                    let status = if loss_pct < 2.0 {
+                        Status::Good
                     } else if loss_pct < 5.0 {
-                        egui::Color32::YELLOW
+                        Status::Warn
                     } else {
-                        egui::Color32::RED
+                        Status::Bad
                     };
+                    let color = status_color(ui.visuals(), status);
 
-                    ui.colored_label(color, format!("{:.2}% ({} pkts)", loss_pct, m.packets_lost));
+                    ui.colored_label(
+                        color,
+                        self.locale.trf(
+                            "{loss}% ({packets} pkts)",
+                            &[
+                                ("loss", &format!("{loss_pct:.2}")),
+                                ("packets", &m.packets_lost),
+                            ],
+                        ),
+                    );
                     ui.end_row();
 
                     // Sequence Number (Debugging)
-                    ui.label("Highest Seq Recv:");
+                    ui.label(self.locale.tr("Highest Seq Recv:"));
                     ui.label(format!("{}", m.highest_sequence_number));
                     ui.end_row();
                 } else {
-                    ui.label("Status:");
-                    ui.label("Waiting for RTCP reports...");
+                    ui.label(self.locale.tr("Status:"));
+                    ui.label(self.locale.tr("Waiting for RTCP reports..."));
                     ui.end_row();
                 }
             });
 
         // Optional: Add transport stats summary
         ui.add_space(5.0);
-        ui.label(format!(
-            "RTP Total: {} pkts / {} MB",
-            self.rtp_pkts,
-            self.rtp_bytes / 1_000_000
+        ui.label(self.locale.trf(
+            "RTP Total: {packets} pkts / {mb} MB",
+            &[
+                ("packets", &self.rtp_pkts),
+                ("mb", &(self.rtp_bytes / 1_000_000)),
+            ],
+        ));
+        ui.toggle_value(&mut self.show_stats_overlay, self.locale.tr("Show graphs"));
+    }
+
+    /// Takes a stats sample every second while connected, and counts the
+    /// remote frames shown for the frame rate graph.
+    /// The remote frame to show now: with vsync pacing, the newest one due
+    /// from those decoded so far, otherwise the newest one.
+    fn pace_remote_frame(&mut self, latest: Option<VideoFrame>) -> Option<VideoFrame> {
+        if self.frame_pacing == FramePacing::Fixed {
+            return latest;
+        }
+        let Some(frame) = latest else {
+            self.remote_pacer.clear();
+            return None;
+        };
+        let now = Instant::now();
+        self.remote_pacer.push(frame, now);
+        self.remote_pacer.frame_at(now).cloned()
+    }
+
+    fn update_stats_history(&mut self, remote_frame: Option<&VideoFrame>) {
+        if let Some(f) = remote_frame {
+            self.stats_history.on_remote_frame(f.timestamp_ms);
+        }
+        if self.conn_state.is_connected()
+            && self.stats_last_sample.elapsed() >= Self::STATS_SAMPLE_INTERVAL
+        {
+            self.stats_last_sample = Instant::now();
+            let report = self.engine.get_stats();
+            self.call_traffic = report.sockets.iter().fold((0, 0), |(sent, received), s| {
+                (sent + s.bytes_sent, received + s.bytes_received)
+            });
+            self.stats_history.record(&report);
+        }
+    }
+
+    /// Overlay with rolling graphs of the call's stats and a CSV export.
+    fn render_stats_overlay(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_stats_overlay;
+        egui::Window::new(self.locale.tr("Call statistics"))
+            .id(egui::Id::new("stats_overlay"))
+            .open(&mut open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                if self.stats_history.is_empty() {
+                    ui.label(self.locale.tr("Waiting for stats…"));
+                }
+                for metric in Metric::ALL {
+                    Self::render_stats_graph(
+                        ui,
+                        self.locale,
+                        metric,
+                        &self.stats_history.series(metric),
+                    );
+                }
+                self.render_latency_table(ui);
+                ui.separator();
+                ui.add_enabled_ui(!self.stats_history.is_empty(), |ui| {
+                    if ui.button(self.locale.tr("Export CSV")).clicked() {
+                        self.export_stats_csv();
+                    }
+                });
+            });
+        self.show_stats_overlay = open;
+    }
+
+    /// Pipeline latency percentiles per stage, in ms since capture (send
+    /// stages) or since the first packet arrived (receive stages).
+    fn render_latency_table(&self, ui: &mut egui::Ui) {
+        let latency = self.stats_history.latency();
+        if latency.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label(self.locale.tr("Pipeline latency (ms)"));
+        egui::Grid::new("latency_grid")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(self.locale.tr("Stage"));
+                ui.label("p50");
+                ui.label("p95");
+                ui.label("p99");
+                ui.end_row();
+                for l in latency {
+                    ui.label(self.locale.tr(l.stage.label()));
+                    ui.label(l.p50_ms.to_string());
+                    ui.label(l.p95_ms.to_string());
+                    ui.label(l.p99_ms.to_string());
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// One rolling line graph, scaled to its largest value.
+    fn render_stats_graph(
+        ui: &mut egui::Ui,
+        locale: Locale,
+        metric: Metric,
+        points: &[(f64, f64)],
+    ) {
+        let max = points.iter().map(|&(_, v)| v).fold(0.0, f64::max);
+        let current = points.last().map(|&(_, v)| v);
+        let label = locale.tr(metric.label());
+        ui.label(match current {
+            Some(v) => locale.trf(
+                "{metric}: {value} {unit} (max {max})",
+                &[
+                    ("metric", &label),
+                    ("value", &format!("{v:.1}")),
+                    ("unit", &metric.unit()),
+                    ("max", &format!("{max:.1}")),
+                ],
+            ),
+            None => format!("{label}: –"),
+        });
+
+        let size = egui::vec2(ui.available_width(), Self::STATS_GRAPH_HEIGHT);
+        let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let (Some(&(first, _)), Some(&(last, _))) = (points.first(), points.last()) else {
+            return;
+        };
+        let span = (last - first).max(1.0);
+        let top = if max > 0.0 { max } else { 1.0 };
+        let line: Vec<egui::Pos2> = points
+            .iter()
+            .map(|&(t, v)| {
+                egui::pos2(
+                    rect.left() + ((t - first) / span) as f32 * rect.width(),
+                    rect.bottom() - (v / top) as f32 * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            line,
+            egui::Stroke::new(1.5, ui.visuals().selection.bg_fill),
         ));
     }
 
+    /// Writes the stats history to `[UI] stats_dir` (default `stats`).
+    fn export_stats_csv(&mut self) {
+        let dir = self
+            .config
+            .get_non_empty_or_default("UI", "stats_dir", "stats")
+            .to_owned();
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = Path::new(&dir).join(format!("call-stats-{secs}.csv"));
+        let res =
+            fs::create_dir_all(&dir).and_then(|()| fs::write(&path, self.stats_history.to_csv()));
+        self.status_line = match res {
+            Ok(()) => self
+                .locale
+                .trf("Stats exported to {path}", &[("path", &path.display())]),
+            Err(e) => self
+                .locale
+                .trf("Could not export stats: {error}", &[("error", &e)]),
+        };
+    }
+
+    /// Calls ourselves through a second engine in this process, to check
+    /// the camera, microphone and codecs without a server or anyone to call.
+    fn start_loopback_call(&mut self) {
+        let sink: Arc<dyn LogSink> = Arc::new(self.logger.handle());
+        match LoopbackPeer::call(&mut self.engine, &sink, self.config.clone()) {
+            Ok(peer) => {
+                self.loopback = Some(peer);
+                self.has_local_description = true;
+                self.has_remote_description = true;
+                self.call_flow = CallFlow::Active {
+                    peer: LOOPBACK_PEER.into(),
+                    hold: HoldState::default(),
+                    since: Instant::now(),
+                };
+                self.status_line = self
+                    .locale
+                    .tr("Test call: you should see and hear yourself.")
+                    .into();
+            }
+            Err(e) => {
+                self.status_line = self
+                    .locale
+                    .trf("Test call failed: {error}", &[("error", &e)]);
+            }
+        }
+    }
+
     fn current_peer(&self) -> Option<String> {
         match &self.call_flow {
-            CallFlow::Dialing { peer, .. } | CallFlow::Active { peer } => Some(peer.clone()),
+            CallFlow::Dialing { peer, .. } | CallFlow::Active { peer, .. } => Some(peer.clone()),
             CallFlow::Incoming { from, .. } => Some(from.clone()),
             CallFlow::Idle => None,
         }
     }
 
     fn teardown_call(&mut self, reason: Option<String>, send_bye: bool) {
+        let loopback = self.loopback.take();
         // 1) Conditionally send Bye Singaling Message
-        if send_bye && let Some(peer) = self.current_peer() {
+        if send_bye
+            && loopback.is_none()
+            && let Some(peer) = self.current_peer()
+        {
             self.send_bye(&peer, reason.clone());
         }
+        self.finish_call_record(if send_bye {
+            CallEnd::Local
+        } else {
+            CallEnd::Remote
+        });
 
         // 2) Tear down media (safe to call even if session never started)
         self.engine.stop();
+        drop(loopback);
 
         // Reset file transfer state
         self.file_transfer_state = FileTransferState::Idle;
@@ -1316,19 +4118,37 @@ impl RtcApp {
         self.sending_files.store(false, Ordering::SeqCst);
         self.receiving_files.store(false, Ordering::SeqCst);
 
-        // 3) Re-initialize the Engine for the next call.
-        let logger_handle = Arc::new(self.logger.handle());
-        self.engine = Engine::new(
-            logger_handle,
-            self.config.clone(),
-            self.sending_files.clone(),
-            self.receiving_files.clone(),
-        );
+        // 3) Replace the peer connection with a fresh one for the next call.
+        let primary = self.engine.primary_peer().to_string();
+        self.engine.reset_peer(&primary);
+        // The new peer connection starts from the config; keep the picks
+        if let Some(id) = self.selected_camera {
+            let _ = self.engine.switch_camera(id);
+        }
+        self.engine.set_audio_input(self.selected_mic.clone());
+        self.engine.set_audio_output(self.selected_speaker.clone());
+        self.engine.set_push_to_talk(self.push_to_talk);
+        self.engine.set_video_muted(self.is_video_muted);
+        let _ = self.engine.set_capture_settings(self.capture_settings);
+        if let Some(preset) = self.quality {
+            self.engine.set_quality_preset(preset);
+        }
+        self.push_to_talk_held = false;
+        self.local_speaking = false;
+        self.remote_speaking = false;
+        self.remote_video_muted = false;
+        self.remote_audio_level = None;
+        self.dtmf_sent.clear();
+        self.call_traffic = (0, 0);
+        self.camera_requests.clear();
+        self.camera_control_allowed = false;
+        self.remote_zoom = 0;
 
         // 4) Reset call-related state
         self.call_flow = CallFlow::Idle;
+        self.shared_track = None;
 
-        self.conn_state = ConnState::Idle;
+        self.conn_state = PeerConnectionState::New;
 
         self.pending_remote_sdp = None;
         self.has_local_description = false;
@@ -1340,39 +4160,64 @@ impl RtcApp {
         // This ensures 'have_any_texture' becomes false, closing the window.
         self.local_camera_texture = None;
         self.remote_camera_texture = None;
+        self.local_shown = None;
+        self.remote_shown = None;
+        self.remote_pacer.clear();
+        self.peer_tiles.clear();
+        self.active_speaker = ActiveSpeaker::new();
 
         if let Some(r) = reason {
-            self.status_line = format!("Call ended: {r}");
+            self.status_line = self.locale.trf("Call ended: {reason}", &[("reason", &r)]);
         } else {
-            self.status_line = "Call ended.".into();
+            self.status_line = self.locale.tr("Call ended.").into();
         }
     }
 }
 
 impl App for RtcApp {
     fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
-        // repaint policy: if connection is running OR any texture is alive, tick ~60 fps
+        self.apply_theme(ctx, frame);
+        self.poll_config_watcher();
+
+        // repaint policy: if connection is running OR any texture is alive, tick
+        // on every vsync (paced video) or every 1 / [UI] fps
         let ui_fps = self
             .config
             .get("UI", "fps")
             .and_then(|s| s.parse().ok())
             .unwrap_or(60);
 
-        let time = 1 / ui_fps;
+        let time = 1000 / ui_fps.max(1);
         let any_video = self.local_camera_texture.is_some() || self.remote_camera_texture.is_some();
-        if matches!(self.conn_state, ConnState::Running) || any_video {
+        if any_video && self.frame_pacing == FramePacing::Vsync {
+            ctx.request_repaint();
+        } else if self.conn_state.is_connected() || any_video {
             ctx.request_repaint_after(std::time::Duration::from_millis(time));
         }
 
         if let Some(sdp) = self.pending_remote_sdp.take() {
             match self.set_remote_sdp(&sdp) {
-                Ok(()) => self.status_line = String::from("Remote SDP processed."),
-                Err(e) => self.status_line = format!("Failed to set remote SDP: {e:?}"),
+                Ok(()) => self.status_line = self.locale.tr("Remote SDP processed.").into(),
+                Err(e) => {
+                    self.status_line = self.locale.trf(
+                        "Failed to set remote SDP: {error}",
+                        &[("error", &format!("{e:?}"))],
+                    )
+                }
             }
         }
 
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.save_window_layout(ctx);
+        }
+
         self.poll_engine_events();
+        if let Some(peer) = &mut self.loopback {
+            peer.poll();
+        }
         self.poll_signaling_events();
+        self.poll_push_to_talk(ctx);
+        self.handle_shortcuts(ctx);
         self.drain_ui_log_tap();
 
         // If we hung up (CallFlow::Idle), force frames to None.
@@ -1380,17 +4225,24 @@ impl App for RtcApp {
         // while the Engine is busy closing gracefully in the background.
         let (local_frame, remote_frame) = if matches!(self.call_flow, CallFlow::Idle) {
             (None, None)
+        } else if let Some(peer) = &self.loopback {
+            // In a test call, show the local video as the far end decoded it
+            (self.engine.snapshot_frames().0, peer.received_frame())
         } else {
             self.engine.snapshot_frames()
         };
 
         self.debug_frame_alias_and_size(local_frame.as_ref(), remote_frame.as_ref());
+        self.update_stats_history(remote_frame.as_ref());
+        let remote_frame = self.pace_remote_frame(remote_frame);
 
         let logger_handle = Arc::new(self.logger.handle());
 
         // Inlined texture update logic
         if let Some(render_state) = frame.wgpu_render_state() {
-            if let Some(f) = local_frame.as_ref() {
+            if let Some(f) = local_frame.as_ref()
+                && mark_shown(&mut self.local_shown, f)
+            {
                 update_texture_from_frame(
                     ctx,
                     f,
@@ -1401,7 +4253,9 @@ impl App for RtcApp {
                     logger_handle.clone(),
                 );
             }
-            if let Some(f) = remote_frame.as_ref() {
+            if let Some(f) = remote_frame.as_ref()
+                && mark_shown(&mut self.remote_shown, f)
+            {
                 update_texture_from_frame(
                     ctx,
                     f,
@@ -1409,24 +4263,68 @@ impl App for RtcApp {
                     &mut self.remote_yuv_renderer,
                     Some(render_state),
                     "camera/remote",
-                    logger_handle,
+                    logger_handle.clone(),
                 );
             }
         }
+        let logger: Arc<dyn LogSink> = logger_handle;
+        self.update_peer_tiles(ctx, frame.wgpu_render_state(), &logger);
+
+        if self.kiosk.is_some() {
+            self.poll_kiosk_restart(ctx);
+            egui::CentralPanel::default().show(ctx, |ui| self.render_kiosk_screen(ctx, ui));
+            return;
+        }
 
         self.render_camera_view(ctx, local_frame.as_ref(), remote_frame.as_ref());
+        self.update_camera_preview(ctx);
+        self.render_settings_window(ctx);
+        self.render_stats_overlay(ctx);
+        self.render_shortcuts_window(ctx);
+        self.render_camera_request_window(ctx);
+        self.render_untrusted_server_window(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             Self::render_header(ui);
+            ui.horizontal(|ui| {
+                if ui.button(self.locale.tr("Settings")).clicked() {
+                    self.show_settings = true;
+                }
+                if ui.button(self.locale.tr("Shortcuts")).clicked() {
+                    self.show_shortcuts = true;
+                }
+                if self.loopback.is_some() {
+                    if ui.button(self.locale.tr("End test call")).clicked() {
+                        self.teardown_call(None, false);
+                    }
+                } else if ui
+                    .add_enabled(
+                        matches!(self.call_flow, CallFlow::Idle),
+                        egui::Button::new(self.locale.tr("Test call")),
+                    )
+                    .on_hover_text(
+                        self.locale
+                            .tr("Call yourself to check the camera, microphone and codecs"),
+                    )
+                    .clicked()
+                {
+                    self.start_loopback_call();
+                }
+            });
             self.render_signaling_panel(ui);
             if !matches!(self.signaling_screen, SignalingScreen::Home) {
                 ui.separator();
-                ui.label("Connect and log in to place a call.");
+                ui.label(self.locale.tr("Connect and log in to place a call."));
                 self.render_status_line(ui);
                 self.render_log_section(ui);
                 return;
             }
-            Self::render_video_summary(ui, local_frame.as_ref(), remote_frame.as_ref());
+            Self::render_video_summary(
+                ui,
+                self.locale,
+                local_frame.as_ref(),
+                remote_frame.as_ref(),
+            );
             self.render_file_transfer(ui);
             self.render_network_stats(ui);
             self.render_connection_controls(ui);
@@ -1434,6 +4332,35 @@ impl App for RtcApp {
             self.render_log_section(ui);
         });
     }
+
+    /// The window is closing: hang up (the peer gets a `Bye` and a DTLS
+    /// `close_notify`), release the camera and microphone, and join the
+    /// workers, so nothing keeps running until the process is killed.
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let loopback = self.loopback.take();
+        if loopback.is_none()
+            && let Some(peer) = self.current_peer()
+        {
+            let reason = match &self.call_flow {
+                CallFlow::Active { since, .. } => format!(
+                    "Window closed after {}",
+                    call_history::format_duration(since.elapsed().as_secs())
+                ),
+                _ => "Window closed".to_owned(),
+            };
+            self.send_bye(&peer, Some(reason));
+        }
+        if !matches!(self.call_flow, CallFlow::Idle) {
+            self.finish_call_record(CallEnd::Local);
+        }
+        drop(loopback);
+        self.camera_preview = None;
+        self.engine.shutdown();
+        self.config_watcher = None;
+        if let Some(client) = self.signaling_client.take() {
+            client.close(SIGNALING_CLOSE_TIMEOUT);
+        }
+    }
 }
 
 fn update_texture_from_frame(
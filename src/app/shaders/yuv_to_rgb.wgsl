@@ -3,6 +3,8 @@
 @group(0) @binding(2) var v_tex: texture_2d<f32>;
 @group(0) @binding(3) var samp: sampler;
 
+// params.xy: scale from picture to chroma texture coordinates (below 1
+// when an odd width or height leaves the chroma half a sample wider).
 struct Info {
     params: vec4<f32>,
 };
@@ -24,8 +26,9 @@ fn vs_main(@location(0) pos: vec2<f32>, @location(1) uv: vec2<f32>) -> VertexOut
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let y_raw = textureSample(y_tex, samp, in.uv).r * 255.0;
-    let u_raw = textureSample(u_tex, samp, in.uv).r * 255.0;
-    let v_raw = textureSample(v_tex, samp, in.uv).r * 255.0;
+    let chroma_uv = in.uv * u_info.params.xy;
+    let u_raw = textureSample(u_tex, samp, chroma_uv).r * 255.0;
+    let v_raw = textureSample(v_tex, samp, chroma_uv).r * 255.0;
 
     let y_scaled = max(0.0, (y_raw - 16.0) * (255.0 / 219.0));
     let u_scaled = (u_raw - 128.0) * (255.0 / 224.0);
@@ -0,0 +1,188 @@
+//! Keyboard shortcuts for call control.
+//!
+//! Each action has a default binding that the `[Shortcuts]` section of the
+//! configuration can change (`mute = "Ctrl+M"`) or turn off (`mute = ""`).
+//! A binding is any number of `Ctrl`, `Shift` and `Alt` modifiers followed
+//! by an egui key name, joined with `+`. Push-to-talk keeps its own
+//! `[Media] push_to_talk_key`, since it is held rather than pressed.
+
+use eframe::egui;
+
+use crate::config::Config;
+
+/// Something a shortcut can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ToggleMute,
+    ToggleVideo,
+    HangUp,
+    AcceptCall,
+    ShowShortcuts,
+}
+
+impl Action {
+    pub const ALL: [Self; 5] = [
+        Self::ToggleMute,
+        Self::ToggleVideo,
+        Self::HangUp,
+        Self::AcceptCall,
+        Self::ShowShortcuts,
+    ];
+
+    /// Key of the action in the `[Shortcuts]` section.
+    #[must_use]
+    pub const fn config_key(self) -> &'static str {
+        match self {
+            Self::ToggleMute => "mute",
+            Self::ToggleVideo => "video",
+            Self::HangUp => "hang_up",
+            Self::AcceptCall => "accept",
+            Self::ShowShortcuts => "help",
+        }
+    }
+
+    #[must_use]
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::ToggleMute => "Mute / unmute the microphone",
+            Self::ToggleVideo => "Turn the camera off / on",
+            Self::HangUp => "Hang up, cancel or decline a call",
+            Self::AcceptCall => "Accept an incoming call",
+            Self::ShowShortcuts => "Show this list",
+        }
+    }
+
+    const fn default_binding(self) -> &'static str {
+        match self {
+            Self::ToggleMute => "Ctrl+M",
+            Self::ToggleVideo => "Ctrl+E",
+            Self::HangUp => "Ctrl+Shift+H",
+            Self::AcceptCall => "Ctrl+Shift+A",
+            Self::ShowShortcuts => "F1",
+        }
+    }
+}
+
+/// Modifiers of a binding, as written in the configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Mods {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+/// Splits `"Ctrl+Shift+M"` into its modifiers and key name. `None` for an
+/// empty binding or an unknown modifier.
+#[must_use]
+pub fn parse_binding(binding: &str) -> Option<(Mods, &str)> {
+    let mut parts: Vec<&str> = binding.split('+').map(str::trim).collect();
+    let key = parts.pop().filter(|k| !k.is_empty())?;
+    let mut mods = Mods::default();
+    for part in parts {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" | "cmd" | "command" => mods.ctrl = true,
+            "shift" => mods.shift = true,
+            "alt" | "option" => mods.alt = true,
+            _ => return None,
+        }
+    }
+    Some((mods, key))
+}
+
+fn to_shortcut(binding: &str) -> Option<egui::KeyboardShortcut> {
+    let (mods, key) = parse_binding(binding)?;
+    let key = egui::Key::from_name(key)?;
+    let mut modifiers = egui::Modifiers::NONE;
+    if mods.ctrl {
+        // Cmd on macOS
+        modifiers = modifiers | egui::Modifiers::COMMAND;
+    }
+    if mods.shift {
+        modifiers = modifiers | egui::Modifiers::SHIFT;
+    }
+    if mods.alt {
+        modifiers = modifiers | egui::Modifiers::ALT;
+    }
+    Some(egui::KeyboardShortcut::new(modifiers, key))
+}
+
+/// The configured bindings.
+#[derive(Debug, Clone)]
+pub struct Shortcuts {
+    bindings: Vec<(Action, egui::KeyboardShortcut)>,
+    /// Configured values that could not be parsed (the default is used).
+    pub invalid: Vec<String>,
+}
+
+impl Shortcuts {
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let mut bindings = Vec::new();
+        let mut invalid = Vec::new();
+        for action in Action::ALL {
+            let configured = config.get("Shortcuts", action.config_key());
+            let shortcut = match configured.map(str::trim) {
+                Some("") => continue,
+                Some(value) => to_shortcut(value).or_else(|| {
+                    invalid.push(format!("{} = \"{value}\"", action.config_key()));
+                    to_shortcut(action.default_binding())
+                }),
+                None => to_shortcut(action.default_binding()),
+            };
+            if let Some(shortcut) = shortcut {
+                bindings.push((action, shortcut));
+            }
+        }
+        Self { bindings, invalid }
+    }
+
+    /// Actions whose shortcut was pressed this frame. The key presses are
+    /// consumed so they don't reach other widgets. Shortcuts without
+    /// modifiers are ignored while a text field has focus.
+    pub fn pressed(&self, ctx: &egui::Context) -> Vec<Action> {
+        let typing = ctx.wants_keyboard_input();
+        ctx.input_mut(|i| {
+            self.bindings
+                .iter()
+                .filter(|(_, sc)| !(typing && sc.modifiers.is_none()))
+                .filter(|(_, sc)| i.consume_shortcut(sc))
+                .map(|(action, _)| *action)
+                .collect()
+        })
+    }
+
+    /// The binding of `action`, unless it was turned off.
+    #[must_use]
+    pub fn get(&self, action: Action) -> Option<&egui::KeyboardShortcut> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, shortcut)| shortcut)
+    }
+
+    /// Bindings in display order.
+    pub fn iter(&self) -> impl Iterator<Item = &(Action, egui::KeyboardShortcut)> {
+        self.bindings.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn bindings_are_split_into_modifiers_and_key() {
+        let ctrl_shift = Mods {
+            ctrl: true,
+            shift: true,
+            alt: false,
+        };
+        assert_eq!(parse_binding("Ctrl+Shift+H"), Some((ctrl_shift, "H")));
+        assert_eq!(parse_binding(" ctrl + shift + H "), Some((ctrl_shift, "H")));
+        assert_eq!(parse_binding("F1"), Some((Mods::default(), "F1")));
+        assert_eq!(parse_binding(""), None);
+        assert_eq!(parse_binding("Ctrl+"), None);
+        assert_eq!(parse_binding("Hyper+M"), None);
+    }
+}
@@ -0,0 +1,341 @@
+//! Rolling history of call statistics, for the graphs of the stats overlay
+//! and their CSV export.
+//!
+//! The GUI records a [`StatsReport`] about once a second. Send bitrate is
+//! derived from the byte counters of successive reports; RTT and loss come
+//! from the remote's receiver reports, jitter from our own inbound streams
+//! and the frame rate from the remote frames the GUI displays. The pipeline
+//! latency of the latest report is kept too, with the render stage measured
+//! here when a remote frame is first shown.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+};
+
+use crate::core::{
+    latency::{LatencySamples, LatencyStage, LatencyStats},
+    stats::StatsReport,
+};
+
+/// Samples kept by default: five minutes at one per second.
+pub const DEFAULT_CAPACITY: usize = 300;
+
+/// One of the graphed metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Bitrate,
+    Rtt,
+    Loss,
+    Fps,
+    Jitter,
+}
+
+impl Metric {
+    pub const ALL: [Self; 5] = [
+        Self::Bitrate,
+        Self::Rtt,
+        Self::Loss,
+        Self::Fps,
+        Self::Jitter,
+    ];
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Bitrate => "Bitrate",
+            Self::Rtt => "RTT",
+            Self::Loss => "Loss",
+            Self::Fps => "FPS",
+            Self::Jitter => "Jitter",
+        }
+    }
+
+    #[must_use]
+    pub const fn unit(self) -> &'static str {
+        match self {
+            Self::Bitrate => "kbps",
+            Self::Rtt | Self::Jitter => "ms",
+            Self::Loss => "%",
+            Self::Fps => "fps",
+        }
+    }
+}
+
+/// Metrics at one point of the call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsSample {
+    /// Seconds since the first sample.
+    pub at_secs: f64,
+    /// Send bitrate over all outbound streams.
+    pub bitrate_kbps: f64,
+    /// `None` until the remote answered a sender report.
+    pub rtt_ms: Option<f64>,
+    /// Worst fraction lost reported by the remote, in percent.
+    pub loss_pct: f64,
+    /// Remote video frames displayed per second.
+    pub fps: f64,
+    /// Worst interarrival jitter of our inbound streams.
+    pub jitter_ms: f64,
+}
+
+impl StatsSample {
+    #[must_use]
+    pub fn get(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Bitrate => Some(self.bitrate_kbps),
+            Metric::Rtt => self.rtt_ms,
+            Metric::Loss => Some(self.loss_pct),
+            Metric::Fps => Some(self.fps),
+            Metric::Jitter => Some(self.jitter_ms),
+        }
+    }
+}
+
+/// Ring buffer of the last samples of a call.
+#[derive(Debug)]
+pub struct StatsHistory {
+    capacity: usize,
+    samples: VecDeque<StatsSample>,
+    /// Time of the first and of the previous report, in Unix ms.
+    first_ms: Option<u64>,
+    last_ms: Option<u64>,
+    /// Bytes sent per outbound SSRC at the previous report.
+    bytes_sent: HashMap<u32, u32>,
+    /// Remote frames seen since the previous report.
+    frames: u32,
+    last_frame_ts: Option<u128>,
+    /// Pipeline latency of the latest report.
+    latency: Vec<LatencyStats>,
+    /// Time from the arrival of a remote frame to its display.
+    render: LatencySamples,
+}
+
+impl StatsHistory {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::with_capacity(capacity),
+            first_ms: None,
+            last_ms: None,
+            bytes_sent: HashMap::new(),
+            frames: 0,
+            last_frame_ts: None,
+            latency: Vec::new(),
+            render: LatencySamples::default(),
+        }
+    }
+
+    /// Forgets everything, for a new call.
+    pub fn clear(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+
+    /// Counts a remote frame shown by the GUI and times its render stage.
+    /// The same frame polled twice (same timestamp) counts once.
+    pub fn on_remote_frame(&mut self, timestamp_ms: u128) {
+        if self.last_frame_ts != Some(timestamp_ms) {
+            self.last_frame_ts = Some(timestamp_ms);
+            self.frames += 1;
+            self.render.record_since(LatencyStage::Render, timestamp_ms);
+        }
+    }
+
+    /// Adds a sample from `report`. The first report only sets the baseline
+    /// for rates.
+    pub fn record(&mut self, report: &StatsReport) {
+        let now = report.timestamp_ms;
+        let mut sent_bits = 0u64;
+        for s in &report.outbound_rtp {
+            let prev = self.bytes_sent.insert(s.ssrc, s.bytes_sent).unwrap_or(0);
+            sent_bits += u64::from(s.bytes_sent.wrapping_sub(prev)) * 8;
+        }
+        let frames = std::mem::take(&mut self.frames);
+        self.latency.clone_from(&report.latency);
+
+        let Some(last) = self.last_ms.replace(now) else {
+            self.first_ms = Some(now);
+            return;
+        };
+        let dt = now.saturating_sub(last) as f64 / 1000.0;
+        if dt <= 0.0 {
+            return;
+        }
+
+        let rtt_ms = report
+            .outbound_rtp
+            .iter()
+            .filter_map(|s| s.rtt_ms)
+            .max()
+            .map(f64::from)
+            .or_else(|| {
+                report
+                    .congestion
+                    .as_ref()
+                    .and_then(|c| c.last_rtt_ms)
+                    .map(|ms| ms as f64)
+            });
+        let loss = report
+            .outbound_rtp
+            .iter()
+            .map(|s| s.remote_fraction_lost)
+            .max()
+            .unwrap_or(0);
+        let jitter_ms = report
+            .inbound_rtp
+            .iter()
+            .map(|s| s.jitter_ms())
+            .fold(0.0, f64::max);
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(StatsSample {
+            at_secs: now.saturating_sub(self.first_ms.unwrap_or(now)) as f64 / 1000.0,
+            bitrate_kbps: sent_bits as f64 / dt / 1000.0,
+            rtt_ms,
+            loss_pct: f64::from(loss) * 100.0 / 255.0,
+            fps: f64::from(frames) / dt,
+            jitter_ms,
+        });
+    }
+
+    #[must_use]
+    pub fn samples(&self) -> &VecDeque<StatsSample> {
+        &self.samples
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Latency percentiles of every stage with samples, render included.
+    #[must_use]
+    pub fn latency(&self) -> Vec<LatencyStats> {
+        let mut stats = self.latency.clone();
+        stats.extend(self.render.stats());
+        stats
+    }
+
+    /// `(at_secs, value)` points of one metric, skipping missing values.
+    #[must_use]
+    pub fn series(&self, metric: Metric) -> Vec<(f64, f64)> {
+        self.samples
+            .iter()
+            .filter_map(|s| s.get(metric).map(|v| (s.at_secs, v)))
+            .collect()
+    }
+
+    /// The history as CSV, one row per sample; a missing RTT is empty.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("time_s,bitrate_kbps,rtt_ms,loss_pct,fps,jitter_ms\n");
+        for s in &self.samples {
+            let rtt = s.rtt_ms.map_or_else(String::new, |r| format!("{r:.0}"));
+            let _ = writeln!(
+                out,
+                "{:.1},{:.1},{rtt},{:.2},{:.1},{:.2}",
+                s.at_secs, s.bitrate_kbps, s.loss_pct, s.fps, s.jitter_ms
+            );
+        }
+        out
+    }
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::core::stats::{InboundRtpStats, OutboundRtpStats};
+
+    fn report(at_ms: u64, bytes_sent: u32, rtt_ms: Option<u32>) -> StatsReport {
+        let mut r = StatsReport::new();
+        r.timestamp_ms = at_ms;
+        r.outbound_rtp.push(OutboundRtpStats {
+            id: "OutboundRTP_1".into(),
+            timestamp_ms: at_ms,
+            ssrc: 1,
+            codec: "H264".into(),
+            payload_type: 96,
+            packets_sent: 0,
+            bytes_sent,
+            remote_packets_lost: 0,
+            remote_fraction_lost: 51,
+            remote_jitter: 0,
+            nacked_packets: 0,
+            retransmitted_packets: 0,
+            unrecoverable_packets: 0,
+            nack_delay_ms: None,
+            rtt_ms,
+        });
+        r.inbound_rtp.push(InboundRtpStats {
+            id: "InboundRTP_2".into(),
+            timestamp_ms: at_ms,
+            ssrc: 2,
+            codec: "H264".into(),
+            payload_type: 96,
+            packets_received: 0,
+            packets_lost: 0,
+            highest_seq: 0,
+            jitter: 900,
+            clock_rate: 90_000,
+            packets_duplicated: 0,
+            packets_reordered: 0,
+            max_reorder_depth: 0,
+            packets_late: 0,
+        });
+        r
+    }
+
+    #[test]
+    fn rates_come_from_successive_reports() {
+        let mut history = StatsHistory::default();
+        history.record(&report(10_000, 1_000, None));
+        assert!(history.is_empty());
+
+        for ts in [1, 2, 2, 3] {
+            history.on_remote_frame(ts);
+        }
+        history.record(&report(12_000, 251_000, Some(40)));
+        let s = history.samples()[0];
+        assert_eq!(s.at_secs, 2.0);
+        assert_eq!(s.bitrate_kbps, 1000.0);
+        assert_eq!(s.rtt_ms, Some(40.0));
+        assert_eq!(s.loss_pct, 20.0);
+        assert_eq!(s.fps, 1.5);
+        assert_eq!(s.jitter_ms, 10.0);
+
+        let latency = history.latency();
+        assert_eq!(latency.len(), 1);
+        assert_eq!(latency[0].stage, LatencyStage::Render);
+        assert_eq!(latency[0].samples, 3);
+
+        assert_eq!(
+            history.to_csv(),
+            "time_s,bitrate_kbps,rtt_ms,loss_pct,fps,jitter_ms\n2.0,1000.0,40,20.00,1.5,10.00\n"
+        );
+    }
+
+    #[test]
+    fn oldest_samples_are_dropped() {
+        let mut history = StatsHistory::new(2);
+        for i in 0..5 {
+            history.record(&report(i * 1000, 0, None));
+        }
+        let times: Vec<f64> = history.samples().iter().map(|s| s.at_secs).collect();
+        assert_eq!(times, [3.0, 4.0]);
+        assert!(history.series(Metric::Rtt).is_empty());
+        assert_eq!(history.series(Metric::Bitrate), [(3.0, 0.0), (4.0, 0.0)]);
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
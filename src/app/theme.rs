@@ -0,0 +1,225 @@
+//! Theme, UI scale, contrast and the colors of status indicators.
+//!
+//! The theme (`[UI] theme`: `system`, `dark` or `light`), the scale factor
+//! (`[UI] scale`) and the high-contrast mode (`[UI] high_contrast`) are set
+//! in Settings and saved with the other settings. Widgets are given a
+//! minimum size of [`MIN_HIT_TARGET`] so they are easy to hit with a
+//! pointer and to see when focused. Status indicators don't use egui's saturated named colors,
+//! which wash out on a light background: each status has a bright shade
+//! for dark themes and a darker one for light themes.
+
+use eframe::egui;
+
+use crate::config::Config;
+
+/// Smallest and largest UI scale offered in Settings.
+pub const MIN_SCALE: f32 = 0.75;
+pub const MAX_SCALE: f32 = 2.0;
+
+/// Smallest height of a button, checkbox or text field, in points.
+pub const MIN_HIT_TARGET: f32 = 28.0;
+
+/// Color theme of the GUI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Follows the operating system.
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Self; 3] = [Self::System, Self::Dark, Self::Light];
+
+    /// Value used in `[UI] theme`.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::System => "system",
+            Self::Dark => "dark",
+            Self::Light => "light",
+        }
+    }
+
+    /// Name shown in Settings (English, for the translation catalog).
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(value))
+    }
+
+    /// `[UI] theme`, the system theme if missing or unknown.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        config
+            .get_non_empty("UI", "theme")
+            .and_then(Self::parse)
+            .unwrap_or_default()
+    }
+
+    /// Whether to use dark visuals, given the system theme if known.
+    #[must_use]
+    pub fn is_dark(self, system_dark: Option<bool>) -> bool {
+        match self {
+            Self::System => system_dark.unwrap_or(true),
+            Self::Dark => true,
+            Self::Light => false,
+        }
+    }
+}
+
+/// `[UI] scale`, clamped to the offered range; 1.0 if missing or invalid.
+#[must_use]
+pub fn scale_from_config(config: &Config) -> f32 {
+    config
+        .get_non_empty("UI", "scale")
+        .and_then(|v| v.parse::<f32>().ok())
+        .filter(|v| v.is_finite())
+        .map_or(1.0, |v| v.clamp(MIN_SCALE, MAX_SCALE))
+}
+
+/// `[UI] high_contrast`; off if missing or not a boolean.
+#[must_use]
+pub fn high_contrast_from_config(config: &Config) -> bool {
+    config
+        .get_non_empty("UI", "high_contrast")
+        .is_some_and(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "true" | "yes" | "1"))
+}
+
+/// egui's visuals for the theme, with black-on-white (or white-on-black)
+/// text, solid backgrounds and thick outlines in high-contrast mode.
+#[must_use]
+pub fn visuals(dark: bool, high_contrast: bool) -> egui::Visuals {
+    let mut visuals = if dark {
+        egui::Visuals::dark()
+    } else {
+        egui::Visuals::light()
+    };
+    if !high_contrast {
+        return visuals;
+    }
+    let (fg, bg, accent) = if dark {
+        (
+            egui::Color32::WHITE,
+            egui::Color32::BLACK,
+            egui::Color32::from_rgb(255, 230, 0),
+        )
+    } else {
+        (
+            egui::Color32::BLACK,
+            egui::Color32::WHITE,
+            egui::Color32::from_rgb(0, 60, 200),
+        )
+    };
+    visuals.override_text_color = Some(fg);
+    visuals.panel_fill = bg;
+    visuals.window_fill = bg;
+    visuals.extreme_bg_color = bg;
+    visuals.faint_bg_color = bg;
+    visuals.window_stroke = egui::Stroke::new(2.0, fg);
+    visuals.hyperlink_color = accent;
+    visuals.selection.bg_fill = accent;
+    visuals.selection.stroke = egui::Stroke::new(2.0, bg);
+    let widgets = &mut visuals.widgets;
+    for (state, width) in [
+        (&mut widgets.noninteractive, 1.0),
+        (&mut widgets.inactive, 2.0),
+        (&mut widgets.hovered, 3.0),
+        (&mut widgets.active, 3.0),
+        (&mut widgets.open, 3.0),
+    ] {
+        state.bg_fill = bg;
+        state.weak_bg_fill = bg;
+        state.bg_stroke = egui::Stroke::new(width, fg);
+        state.fg_stroke = egui::Stroke::new(width.max(1.5), fg);
+    }
+    // Hovered and pressed widgets stand out by the accent, not a lighter gray
+    widgets.hovered.bg_stroke.color = accent;
+    widgets.active.bg_stroke.color = accent;
+    visuals
+}
+
+/// Grows the spacing of `style` so every widget is at least
+/// [`MIN_HIT_TARGET`] high.
+pub fn enlarge_hit_targets(style: &mut egui::Style) {
+    let spacing = &mut style.spacing;
+    spacing.interact_size.y = spacing.interact_size.y.max(MIN_HIT_TARGET);
+    spacing.button_padding = spacing.button_padding.max(egui::vec2(8.0, 6.0));
+    spacing.item_spacing = spacing.item_spacing.max(egui::vec2(8.0, 6.0));
+    spacing.icon_width = spacing.icon_width.max(18.0);
+}
+
+/// Meaning of a status indicator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Good,
+    Warn,
+    Bad,
+}
+
+/// Color of `status`, readable on the current background.
+#[must_use]
+pub fn status_color(visuals: &egui::Visuals, status: Status) -> egui::Color32 {
+    let (r, g, b) = match (status, visuals.dark_mode) {
+        (Status::Good, true) => (90, 230, 110),
+        (Status::Warn, true) => (255, 205, 60),
+        (Status::Bad, true) => (255, 105, 105),
+        (Status::Good, false) => (0, 120, 40),
+        (Status::Warn, false) => (150, 95, 0),
+        (Status::Bad, false) => (190, 20, 20),
+    };
+    egui::Color32::from_rgb(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn theme_and_scale_come_from_the_config() {
+        let mut config = Config::empty();
+        assert_eq!(Theme::from_config(&config), Theme::System);
+        assert_eq!(scale_from_config(&config), 1.0);
+
+        config.set("UI", "theme", "Light");
+        config.set("UI", "scale", "5");
+        assert_eq!(Theme::from_config(&config), Theme::Light);
+        assert_eq!(scale_from_config(&config), MAX_SCALE);
+
+        config.set("UI", "theme", "sepia");
+        config.set("UI", "scale", "big");
+        assert_eq!(Theme::from_config(&config), Theme::System);
+        assert_eq!(scale_from_config(&config), 1.0);
+
+        assert!(Theme::System.is_dark(None));
+        assert!(!Theme::System.is_dark(Some(false)));
+        assert!(Theme::Dark.is_dark(Some(false)));
+    }
+
+    #[test]
+    fn high_contrast_turns_text_solid() {
+        let mut config = Config::empty();
+        assert!(!high_contrast_from_config(&config));
+        config.set("UI", "high_contrast", "Yes");
+        assert!(high_contrast_from_config(&config));
+
+        assert_eq!(visuals(true, false), egui::Visuals::dark());
+        let contrast = visuals(false, true);
+        assert!(!contrast.dark_mode);
+        assert_eq!(contrast.override_text_color, Some(egui::Color32::BLACK));
+        assert_eq!(contrast.panel_fill, egui::Color32::WHITE);
+    }
+}
@@ -6,6 +6,17 @@ use std::sync::Arc;
 
 use eframe::{egui, egui_wgpu::RenderState};
 
+/// Records `frame` as the one on screen and returns whether it differs from
+/// the previous one. The UI polls frames every tick, far more often than
+/// they change; only new ones need converting and uploading.
+pub fn mark_shown(shown: &mut Option<VideoFrame>, frame: &VideoFrame) -> bool {
+    if shown.as_ref().is_some_and(|s| s.is_same_frame(frame)) {
+        return false;
+    }
+    *shown = Some(frame.clone());
+    true
+}
+
 pub fn show_camera_in_ui(
     ui: &mut egui::Ui,
     texture: Option<(egui::TextureId, (u32, u32))>,
@@ -37,6 +48,69 @@ pub fn show_camera_in_ui(
     }
 }
 
+/// Largest size with the aspect ratio of `content` that fits in `bounds`.
+pub fn fit_size(content: egui::Vec2, bounds: egui::Vec2) -> egui::Vec2 {
+    if content.x <= 0.0 || content.y <= 0.0 {
+        return bounds;
+    }
+    let scale = (bounds.x / content.x).min(bounds.y / content.y);
+    content * scale
+}
+
+/// Paints `texture` as large as fits in `rect`, centered, keeping the
+/// frame's aspect ratio.
+pub fn paint_video(
+    painter: &egui::Painter,
+    rect: egui::Rect,
+    texture: (egui::TextureId, (u32, u32)),
+) {
+    let (id, (w, h)) = texture;
+    let size = fit_size(egui::vec2(w as f32, h as f32), rect.size());
+    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+    painter.image(
+        id,
+        egui::Rect::from_center_size(rect.center(), size),
+        uv,
+        egui::Color32::WHITE,
+    );
+}
+
+/// Rect of a picture-in-picture tile of `size` inside `stage`. `anchor` is
+/// the tile's position within the free space, from (0, 0) top-left to
+/// (1, 1) bottom-right; `margin` keeps it off the edges.
+pub fn pip_rect(
+    stage: egui::Rect,
+    size: egui::Vec2,
+    anchor: egui::Vec2,
+    margin: f32,
+) -> egui::Rect {
+    let inner = stage.shrink(margin);
+    let free = (inner.size() - size).max(egui::Vec2::ZERO);
+    let anchor = anchor.clamp(egui::Vec2::ZERO, egui::Vec2::splat(1.0));
+    egui::Rect::from_min_size(inner.min + free * anchor, size)
+}
+
+/// Gives `response`'s widget keyboard focus if no widget has it, so the
+/// main action of a screen or dialog is reachable without the pointer.
+pub fn focus_if_unfocused(response: &egui::Response) {
+    if response.ctx.memory(|m| m.focused().is_none()) {
+        response.request_focus();
+    }
+}
+
+/// Whether Enter was pressed in the text field of `response`.
+pub fn submitted(ui: &egui::Ui, response: &egui::Response) -> bool {
+    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))
+}
+
+/// `response` with `hint` as its tooltip, if any.
+pub fn with_hint(response: egui::Response, hint: Option<String>) -> egui::Response {
+    match hint {
+        Some(hint) => response.on_hover_text(hint),
+        None => response,
+    }
+}
+
 pub fn update_rgb_texture(
     ctx: &egui::Context,
     texture: &mut Option<(egui::TextureId, (u32, u32))>,
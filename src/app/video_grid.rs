@@ -0,0 +1,185 @@
+//! Layout of the camera view when more than one peer sends video.
+//!
+//! With several remote peers the view is either a grid of equal tiles or an
+//! active speaker layout (the speaker large, the others in a strip). The
+//! active speaker follows the audio levels the peers send with their audio
+//! (RFC 6464): the loudest peer flagged as speaking takes over, and keeps the
+//! spot until they stop or someone louder speaks for a while, so short
+//! interjections don't make the layout jump around.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{core::engine::PeerId, rtp::audio_level::AudioLevel};
+
+/// How long another peer must keep talking to take the spot.
+const SWITCH_AFTER: Duration = Duration::from_millis(1_500);
+/// A voice flag older than this no longer counts as speaking.
+const VOICE_TIMEOUT: Duration = Duration::from_millis(1_000);
+
+/// How remote tiles are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VideoLayout {
+    #[default]
+    Grid,
+    ActiveSpeaker,
+}
+
+/// Columns and rows of a grid holding `tiles` tiles, as square as possible
+/// (wider than tall when it can't be square).
+#[must_use]
+pub fn grid_dims(tiles: usize) -> (usize, usize) {
+    if tiles == 0 {
+        return (0, 0);
+    }
+    let mut cols = 1;
+    while cols * cols < tiles {
+        cols += 1;
+    }
+    let rows = tiles.div_ceil(cols);
+    (cols, rows)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Voice {
+    level: u8,
+    /// When the peer started its current stretch of speech.
+    since: Instant,
+    last: Instant,
+}
+
+/// Tracks which peer is the active speaker.
+#[derive(Debug, Default)]
+pub struct ActiveSpeaker {
+    voices: HashMap<PeerId, Voice>,
+    current: Option<PeerId>,
+}
+
+impl ActiveSpeaker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The active speaker, if anyone spoke yet.
+    #[must_use]
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Whether `peer` is speaking right now.
+    #[must_use]
+    pub fn is_speaking(&self, peer: &str, now: Instant) -> bool {
+        self.voices
+            .get(peer)
+            .is_some_and(|v| now.saturating_duration_since(v.last) < VOICE_TIMEOUT)
+    }
+
+    /// Records an audio level received from `peer`.
+    pub fn on_level(&mut self, peer: &str, level: AudioLevel, now: Instant) {
+        if !level.voice {
+            self.voices.remove(peer);
+        } else {
+            let voice = self.voices.entry(peer.to_owned()).or_insert(Voice {
+                level: level.level,
+                since: now,
+                last: now,
+            });
+            if now.saturating_duration_since(voice.last) >= VOICE_TIMEOUT {
+                voice.since = now;
+            }
+            voice.level = level.level;
+            voice.last = now;
+        }
+        self.update(now);
+    }
+
+    /// Forgets a peer that left.
+    pub fn remove(&mut self, peer: &str) {
+        self.voices.remove(peer);
+        if self.current.as_deref() == Some(peer) {
+            self.current = None;
+        }
+    }
+
+    fn update(&mut self, now: Instant) {
+        let speaking = |v: &Voice| now.saturating_duration_since(v.last) < VOICE_TIMEOUT;
+        // Level of the current speaker while they are still talking
+        let current_level = self
+            .current
+            .as_ref()
+            .and_then(|p| self.voices.get(p))
+            .filter(|v| speaking(v))
+            .map(|v| v.level);
+        // Anyone talking takes a free spot; while the current speaker talks,
+        // only someone louder (lower -dBov) who kept talking long enough
+        let candidate = self
+            .voices
+            .iter()
+            .filter(|(peer, v)| {
+                speaking(v)
+                    && Some(*peer) != self.current.as_ref()
+                    && current_level.is_none_or(|level| {
+                        v.level < level && now.saturating_duration_since(v.since) >= SWITCH_AFTER
+                    })
+            })
+            .min_by_key(|(peer, v)| (v.level, (*peer).clone()))
+            .map(|(peer, _)| peer.clone());
+        if let Some(peer) = candidate {
+            self.current = Some(peer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn grids_are_as_square_as_possible() {
+        assert_eq!(grid_dims(0), (0, 0));
+        assert_eq!(grid_dims(1), (1, 1));
+        assert_eq!(grid_dims(2), (2, 1));
+        assert_eq!(grid_dims(3), (2, 2));
+        assert_eq!(grid_dims(5), (3, 2));
+        assert_eq!(grid_dims(9), (3, 3));
+    }
+
+    #[test]
+    fn speaker_keeps_the_spot_through_interjections() {
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+        let talk = AudioLevel::new(30, true);
+        let mut speaker = ActiveSpeaker::new();
+        assert_eq!(speaker.current(), None);
+
+        speaker.on_level("alice", talk, ms(0));
+        assert_eq!(speaker.current(), Some("alice"));
+
+        // Bob interjects briefly while Alice keeps talking
+        for t in (100..1_000).step_by(100) {
+            speaker.on_level("alice", talk, ms(t));
+            speaker.on_level("bob", AudioLevel::new(10, true), ms(t));
+        }
+        assert_eq!(speaker.current(), Some("alice"));
+        assert!(speaker.is_speaking("bob", ms(1_000)));
+
+        // ...and takes over after talking long enough
+        for t in (1_000..1_700).step_by(100) {
+            speaker.on_level("alice", talk, ms(t));
+            speaker.on_level("bob", AudioLevel::new(10, true), ms(t));
+        }
+        assert_eq!(speaker.current(), Some("bob"));
+
+        // Bob goes quiet: the next speaker takes the spot at once
+        speaker.on_level("bob", AudioLevel::new(90, false), ms(1_800));
+        speaker.on_level("alice", talk, ms(1_800));
+        assert_eq!(speaker.current(), Some("alice"));
+
+        speaker.remove("alice");
+        assert_eq!(speaker.current(), None);
+    }
+}
@@ -0,0 +1,1222 @@
+//! Headless command-line client for the RoomRTC application.
+//!
+//! Drives the same `Engine` and `SignalingClient` as the GUI, without a window.
+//! Commands are read line by line from stdin, so sessions can be scripted:
+//!
+//! ```text
+//! echo "login alice secret
+//! call bob
+//! wait 30
+//! hangup
+//! quit" | rustyrtc-cli client_default.conf
+//! ```
+//!
+//! Flags (all optional, applied before reading stdin):
+//! `--server ADDR`, `--user NAME`, `--password PW`, `--call PEER`,
+//! `--auto-accept` (answer incoming calls and file offers),
+//! `--send-file PATH` (sent once the call is established),
+//! `--duration SECS` (hang up and exit after this long).
+//!
+//! `--loopback` instead calls this client itself, without a server, to check
+//! the camera, microphone and codecs end to end: the exit status says whether
+//! the local video made it back.
+
+use rustyrtc::{
+    camera_manager::{
+        capture_settings::{CaptureSettings, PixelFormat},
+        devices::list_cameras,
+    },
+    config::Config,
+    core::{
+        connection_state::PeerConnectionState,
+        engine::Engine,
+        events::EngineEvent,
+        loopback::{LOOPBACK_PEER, LoopbackPeer},
+        quality::QualityPreset,
+    },
+    log::{log_sink::LogSink, logger::Logger},
+    media_agent::{
+        audio_devices::{AudioDevice, list_input_devices, list_output_devices},
+        recorder::RecordStreams,
+        video_frame::VideoFrame,
+        video_track::VideoSource,
+    },
+    settings::Settings,
+    signaling::{
+        capture::SignalingRecorder,
+        protocol::{SignalingMsg, candidate_item::CandidateItem},
+    },
+    signaling_client::{
+        SignalingClient, SignalingEvent, device_label,
+        transport::TransportKind,
+        trust::{TrustOptions, UntrustedCertificate},
+    },
+};
+use std::{
+    env,
+    io::{self, BufRead},
+    path::PathBuf,
+    process,
+    sync::{
+        Arc,
+        atomic::AtomicBool,
+        mpsc::{self, Receiver},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "usage: rustyrtc-cli [CONFIG] [--server ADDR] [--user NAME] [--password PW] \
+[--call PEER] [--auto-accept] [--send-file PATH] [--duration SECS] [--write-config PATH.toml] \
+[--loopback]";
+
+/// How long `--loopback` runs without `--duration`.
+const LOOPBACK_DURATION: Duration = Duration::from_secs(10);
+/// How many times, and how far apart, the client tries to reconnect after
+/// the signaling connection drops mid-session.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+const HELP: &str = "commands:
+  login <user> <password>     log in to the signaling server
+  register <user> <password>  create an account
+  peers                       list online peers
+  contacts [add|remove <user>]
+                              list, add or remove contacts
+  block | unblock <user>      refuse (or accept again) calls from a user
+  call <peer>                 place a call
+  accept | decline            answer or refuse the pending incoming call
+  hangup                      end the current call
+  send <path>                 send a file to the peer
+  accept-file | reject-file   answer the pending file offer
+  mute | unmute               toggle the microphone
+  video <on|off>              turn the camera on or off (sends a camera-off picture)
+  ptt <on|off>                push-to-talk: only send audio while talking
+  talk <on|off>               press or release the push-to-talk key
+  dtmf <digits>               send DTMF tones (0-9 * # A-D, ',' pauses 2s)
+  hold | resume               put the call on hold or take it off hold
+  track add [camera <id>]     add a test-pattern (or camera) video track to the call
+  track remove <id>           remove a track added with 'track add'
+  cameras                     list camera devices and their modes
+  camera <id>                 switch the camera (mid-call included)
+  capture <W>x<H> [fps] [mjpg|yuyv]
+                              change the capture format (mid-call included)
+  test-source <video|audio|both|off>
+                              send color bars and/or a sine tone instead
+  audio-devices               list microphones and speakers
+  mic <name|default>          switch the microphone (mid-call included)
+  speaker <name|default>      switch the speaker (mid-call included)
+  record [local|remote] [path]
+                              record the call (both sides by default) to MKV
+  record stop                 finish the recording
+  quality <low|medium|high>   apply a bandwidth preset
+  max-bitrate <bps>           cap the video bitrate
+  stats [json]                print media counters (or a full stats report as JSON)
+  wait <secs>                 pause command processing
+  quit                        hang up and exit";
+
+fn print_audio_devices(kind: &str, devices: &[AudioDevice]) {
+    println!("{kind}:");
+    if devices.is_empty() {
+        println!("  (none)");
+    }
+    for device in devices {
+        println!("  {}", device.label());
+    }
+}
+
+#[derive(Debug, Default)]
+struct CliArgs {
+    config_path: Option<String>,
+    server: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+    call: Option<String>,
+    auto_accept: bool,
+    send_file: Option<String>,
+    duration: Option<Duration>,
+    write_config: Option<String>,
+    loopback: bool,
+}
+
+impl CliArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut out = Self::default();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{name} requires a value"))
+            };
+            match arg.as_str() {
+                "--server" => out.server = Some(value("--server")?),
+                "--user" => out.user = Some(value("--user")?),
+                "--password" => out.password = Some(value("--password")?),
+                "--call" => out.call = Some(value("--call")?),
+                "--send-file" => out.send_file = Some(value("--send-file")?),
+                "--duration" => {
+                    let secs: u64 = value("--duration")?
+                        .parse()
+                        .map_err(|_| "--duration expects seconds".to_string())?;
+                    out.duration = Some(Duration::from_secs(secs));
+                }
+                "--write-config" => out.write_config = Some(value("--write-config")?),
+                "--auto-accept" => out.auto_accept = true,
+                "--loopback" => out.loopback = true,
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other if other.starts_with("--") => return Err(format!("unknown flag {other}")),
+                path => out.config_path = Some(path.to_string()),
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CliCall {
+    Idle,
+    Dialing {
+        peer: String,
+    },
+    Incoming {
+        from: String,
+        txn_id: u64,
+        sdp: String,
+    },
+    Active {
+        peer: String,
+    },
+}
+
+struct Cli {
+    config: Arc<Config>,
+    logger: Logger,
+    engine: Engine,
+    signaling: SignalingClient,
+    /// Connects to the signaling server again after the connection drops.
+    dial: Box<dyn Fn() -> io::Result<SignalingClient>>,
+    /// The last `login`, sent again after reconnecting.
+    credentials: Option<(String, String)>,
+    /// Where to resume the dropped connection from, once logged in again.
+    resume_seq: Option<u64>,
+    username: Option<String>,
+    call: CliCall,
+    next_txn_id: u64,
+    auto_accept: bool,
+    pending_send_file: Option<String>,
+    pending_file_offer: Option<(u32, String)>,
+    sending_files: Arc<AtomicBool>,
+    receiving_files: Arc<AtomicBool>,
+    established: bool,
+    rtp_pkts: u64,
+    rtp_bytes: u64,
+    /// Camera picked with `camera <id>`, reapplied after each hangup.
+    camera: Option<i32>,
+    /// Format picked with `capture`, reapplied after each hangup.
+    capture: Option<CaptureSettings>,
+    /// `(video, audio)` picked with `test-source`, reapplied after each hangup.
+    test_sources: Option<(bool, bool)>,
+    /// Microphone picked with `mic`, reapplied after each hangup.
+    mic: Option<Option<String>>,
+    /// Speaker picked with `speaker`, reapplied after each hangup.
+    speaker: Option<Option<String>>,
+    /// Push-to-talk mode set with `ptt`, reapplied after each hangup.
+    push_to_talk: Option<bool>,
+    /// Camera state set with `video`, reapplied after each hangup.
+    video_muted: Option<bool>,
+}
+
+impl Cli {
+    fn new_engine(
+        logger: &Logger,
+        config: &Arc<Config>,
+        sending: &Arc<AtomicBool>,
+        receiving: &Arc<AtomicBool>,
+    ) -> Engine {
+        let sink: Arc<dyn LogSink> = Arc::new(logger.handle());
+        Engine::new(sink, config.clone(), sending.clone(), receiving.clone())
+    }
+
+    fn peer(&self) -> Option<String> {
+        match &self.call {
+            CliCall::Dialing { peer } | CliCall::Active { peer } => Some(peer.clone()),
+            CliCall::Incoming { from, .. } => Some(from.clone()),
+            CliCall::Idle => None,
+        }
+    }
+
+    fn send(&self, msg: SignalingMsg) {
+        if let Err(e) = self.signaling.send(msg) {
+            eprintln!("signaling send failed: {e}");
+        }
+    }
+
+    fn me(&self) -> String {
+        self.username.clone().unwrap_or_default()
+    }
+
+    fn send_candidates(&self, peer: &str) {
+        let items: Vec<CandidateItem> = self
+            .engine
+            .local_candidates_as_sdp_lines()
+            .into_iter()
+            .map(|line| CandidateItem {
+                mid: "0".into(),
+                mline_index: 0,
+                cand: line.into_bytes(),
+            })
+            .collect();
+        if items.is_empty() {
+            return;
+        }
+        self.send(SignalingMsg::Candidates {
+            from: self.me(),
+            to: peer.to_string(),
+            items,
+            end_of_candidates: true,
+        });
+    }
+
+    fn place_call(&mut self, peer: &str) {
+        if self.call != CliCall::Idle {
+            println!("busy: hang up first");
+            return;
+        }
+        let sdp = match self.engine.negotiate() {
+            Ok(Some(sdp)) => sdp,
+            Ok(None) => {
+                println!("negotiation already in progress");
+                return;
+            }
+            Err(e) => {
+                println!("failed to create offer: {e}");
+                return;
+            }
+        };
+        let txn_id = self.next_txn_id;
+        self.next_txn_id += 1;
+        self.send(SignalingMsg::Offer {
+            txn_id,
+            from: self.me(),
+            to: peer.to_string(),
+            sdp: sdp.into_bytes(),
+        });
+        self.call = CliCall::Dialing {
+            peer: peer.to_string(),
+        };
+        self.send_candidates(peer);
+        println!("calling {peer}...");
+    }
+
+    fn accept_call(&mut self) {
+        let CliCall::Incoming { from, txn_id, sdp } = self.call.clone() else {
+            println!("no incoming call");
+            return;
+        };
+        match self.engine.apply_remote_sdp(&sdp) {
+            Ok(Some(answer)) => {
+                self.send(SignalingMsg::Answer {
+                    txn_id,
+                    from: self.me(),
+                    to: from.clone(),
+                    sdp: answer.into_bytes(),
+                });
+                self.call = CliCall::Active { peer: from.clone() };
+                self.send_candidates(&from);
+                println!("accepted call from {from}");
+            }
+            Ok(None) => println!("no answer generated"),
+            Err(e) => println!("failed to accept call: {e}"),
+        }
+    }
+
+    fn set_hold(&mut self, hold: bool) {
+        let CliCall::Active { peer } = self.call.clone() else {
+            println!("no active call");
+            return;
+        };
+        let result = if hold {
+            self.engine.hold()
+        } else {
+            self.engine.resume()
+        };
+        match result {
+            Ok(Some(sdp)) => {
+                self.send_reoffer(&peer, sdp);
+                println!(
+                    "{} call with {peer}",
+                    if hold { "holding" } else { "resuming" }
+                );
+            }
+            Ok(None) => println!("negotiation already in progress"),
+            Err(e) => println!("failed to renegotiate: {e}"),
+        }
+    }
+
+    fn add_track(&mut self, source: VideoSource) {
+        let CliCall::Active { peer } = self.call.clone() else {
+            println!("no active call");
+            return;
+        };
+        match self.engine.add_video_track(source) {
+            Ok((id, sdp)) => {
+                if let Some(sdp) = sdp {
+                    self.send_reoffer(&peer, sdp);
+                }
+                println!("added track {id}");
+            }
+            Err(e) => println!("failed to add track: {e}"),
+        }
+    }
+
+    fn remove_track(&mut self, id: u32) {
+        let CliCall::Active { peer } = self.call.clone() else {
+            println!("no active call");
+            return;
+        };
+        match self.engine.remove_track(id) {
+            Ok(sdp) => {
+                if let Some(sdp) = sdp {
+                    self.send_reoffer(&peer, sdp);
+                }
+                println!("removed track {id}");
+            }
+            Err(e) => println!("failed to remove track: {e}"),
+        }
+    }
+
+    fn send_reoffer(&mut self, peer: &str, sdp: String) {
+        let txn_id = self.next_txn_id;
+        self.next_txn_id += 1;
+        self.send(SignalingMsg::Offer {
+            txn_id,
+            from: self.me(),
+            to: peer.to_string(),
+            sdp: sdp.into_bytes(),
+        });
+    }
+
+    fn hangup(&mut self, reason: &str, send_bye: bool) {
+        if send_bye && let Some(peer) = self.peer() {
+            self.send(SignalingMsg::Bye {
+                from: self.me(),
+                to: peer,
+                reason: Some(reason.to_string()),
+            });
+        }
+        self.engine.stop();
+        let max_bitrate = self.engine.max_bitrate();
+        self.engine = Self::new_engine(
+            &self.logger,
+            &self.config,
+            &self.sending_files,
+            &self.receiving_files,
+        );
+        self.engine.set_max_bitrate(max_bitrate);
+        if let Some(id) = self.camera {
+            self.engine.switch_camera(id);
+        }
+        if let Some(settings) = self.capture {
+            self.engine.set_capture_settings(settings);
+        }
+        if let Some((video, audio)) = self.test_sources {
+            self.engine.set_test_sources(video, audio);
+        }
+        if let Some(mic) = &self.mic {
+            self.engine.set_audio_input(mic.clone());
+        }
+        if let Some(speaker) = &self.speaker {
+            self.engine.set_audio_output(speaker.clone());
+        }
+        if let Some(enabled) = self.push_to_talk {
+            self.engine.set_push_to_talk(enabled);
+        }
+        if let Some(muted) = self.video_muted {
+            self.engine.set_video_muted(muted);
+        }
+        self.call = CliCall::Idle;
+        self.established = false;
+        self.pending_file_offer = None;
+        println!("call ended: {reason}");
+    }
+
+    /// Parses `capture` arguments on top of the current settings.
+    fn parse_capture(
+        &self,
+        size: &str,
+        fps: Option<&str>,
+        format: Option<&str>,
+    ) -> Option<CaptureSettings> {
+        let (w, h) = size.split_once('x')?;
+        let mut settings = self.engine.capture_settings();
+        settings.width = Some(w.parse().ok()?);
+        settings.height = Some(h.parse().ok()?);
+        if let Some(fps) = fps {
+            settings.fps = fps.parse().ok()?;
+        }
+        if let Some(format) = format {
+            settings.pixel_format = Some(format.parse::<PixelFormat>().ok()?);
+        }
+        Some(settings)
+    }
+
+    /// Returns `false` when the CLI should exit.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let mut parts = line.split_whitespace();
+        let Some(cmd) = parts.next() else {
+            return true;
+        };
+        let arg1 = parts.next();
+        let arg2 = parts.next();
+        match (cmd, arg1, arg2) {
+            ("login", Some(u), Some(p)) => {
+                self.credentials = Some((u.to_string(), p.to_string()));
+                self.send(SignalingMsg::Login {
+                    username: u.to_string(),
+                    password: p.to_string(),
+                });
+            }
+            ("register", Some(u), Some(p)) => self.send(SignalingMsg::Register {
+                username: u.to_string(),
+                password: p.to_string(),
+            }),
+            ("peers", ..) => self.send(SignalingMsg::ListPeers),
+            ("contacts", None, _) => self.send(SignalingMsg::ListContacts),
+            ("contacts", Some("add"), Some(user)) => self.send(SignalingMsg::AddContact {
+                username: user.to_string(),
+            }),
+            ("contacts", Some("remove"), Some(user)) => self.send(SignalingMsg::RemoveContact {
+                username: user.to_string(),
+            }),
+            ("block", Some(user), _) => self.send(SignalingMsg::Block {
+                username: user.to_string(),
+            }),
+            ("unblock", Some(user), _) => self.send(SignalingMsg::Unblock {
+                username: user.to_string(),
+            }),
+            ("call", Some(peer), _) => self.place_call(peer),
+            ("accept", ..) => self.accept_call(),
+            ("decline", ..) | ("hangup", ..) => {
+                if self.call == CliCall::Idle {
+                    println!("no call");
+                } else {
+                    self.hangup(
+                        if cmd == "decline" {
+                            "declined"
+                        } else {
+                            "hangup"
+                        },
+                        true,
+                    );
+                }
+            }
+            ("send", Some(path), _) => {
+                if self.established {
+                    self.engine
+                        .send_file(path.to_string(), rand::random::<u32>());
+                } else {
+                    self.pending_send_file = Some(path.to_string());
+                    println!("file queued until the call is established");
+                }
+            }
+            ("accept-file", ..) => match self.pending_file_offer.take() {
+                Some((id, name)) => self.engine.accept_file(id, name),
+                None => println!("no pending file offer"),
+            },
+            ("reject-file", ..) => match self.pending_file_offer.take() {
+                Some((id, _)) => self.engine.reject_file(id),
+                None => println!("no pending file offer"),
+            },
+            ("hold", ..) => self.set_hold(true),
+            ("resume", ..) => self.set_hold(false),
+            ("track", Some("add"), None) => self.add_track(VideoSource::TestPattern),
+            ("track", Some("add"), Some("camera")) => {
+                match parts.next().and_then(|id| id.parse().ok()) {
+                    Some(id) => self.add_track(VideoSource::Camera(id)),
+                    None => println!("usage: track add camera <id>"),
+                }
+            }
+            ("track", Some("remove"), Some(id)) => match id.parse() {
+                Ok(id) => self.remove_track(id),
+                Err(_) => println!("usage: track remove <id>"),
+            },
+            ("cameras", ..) => {
+                let cameras = list_cameras();
+                if cameras.is_empty() {
+                    println!("no cameras found");
+                }
+                for camera in cameras {
+                    let modes: Vec<String> = camera
+                        .modes
+                        .iter()
+                        .map(|m| format!("{}x{}@{}", m.width, m.height, m.fps))
+                        .collect();
+                    println!("{}: {} [{}]", camera.index, camera.name, modes.join(", "));
+                }
+            }
+            ("camera", Some(id), _) => match id.parse() {
+                Ok(id) => {
+                    self.camera = Some(id);
+                    match self.engine.switch_camera(id) {
+                        Some(status) => println!("{status}"),
+                        None => println!("camera {id} will be used when the call starts"),
+                    }
+                }
+                Err(_) => println!("usage: camera <id>"),
+            },
+            ("capture", Some(size), _) => match self.parse_capture(size, arg2, parts.next()) {
+                Some(settings) => {
+                    self.capture = Some(settings);
+                    match self.engine.set_capture_settings(settings) {
+                        Some(status) => println!("{status}"),
+                        None => println!("capture format will be used when the call starts"),
+                    }
+                }
+                None => println!("usage: capture <W>x<H> [fps] [mjpg|yuyv]"),
+            },
+            ("test-source", Some(which), _) => {
+                let selection = match which {
+                    "video" => Some((true, false)),
+                    "audio" => Some((false, true)),
+                    "both" => Some((true, true)),
+                    "off" => Some((false, false)),
+                    _ => None,
+                };
+                match selection {
+                    Some((video, audio)) => {
+                        self.test_sources = Some((video, audio));
+                        self.engine.set_test_sources(video, audio);
+                    }
+                    None => println!("usage: test-source <video|audio|both|off>"),
+                }
+            }
+            ("audio-devices", ..) => {
+                print_audio_devices("inputs", &list_input_devices());
+                print_audio_devices("outputs", &list_output_devices());
+            }
+            ("mic" | "speaker", Some(_), _) => {
+                // Device names may contain spaces
+                let name = line.trim_start()[cmd.len()..].trim();
+                let device = (name != "default").then(|| name.to_string());
+                if cmd == "mic" {
+                    self.mic = Some(device.clone());
+                    self.engine.set_audio_input(device);
+                } else {
+                    self.speaker = Some(device.clone());
+                    self.engine.set_audio_output(device);
+                }
+                println!("{cmd}: {name}");
+            }
+            ("record", Some("stop"), _) => match self.engine.stop_recording() {
+                Ok(Some(path)) => println!("saved {}", path.display()),
+                Ok(None) => println!("not recording"),
+                Err(e) => println!("{e}"),
+            },
+            ("record", ..) => {
+                let (streams, path) = match arg1 {
+                    Some("local") => (
+                        RecordStreams {
+                            local: true,
+                            remote: false,
+                        },
+                        arg2,
+                    ),
+                    Some("remote") => (
+                        RecordStreams {
+                            local: false,
+                            remote: true,
+                        },
+                        arg2,
+                    ),
+                    path => (RecordStreams::default(), path),
+                };
+                match self
+                    .engine
+                    .start_recording(path.map(PathBuf::from), streams)
+                {
+                    Ok(path) => println!("recording to {}", path.display()),
+                    Err(e) => println!("{e}"),
+                }
+            }
+            ("quality", Some(preset), _) => match preset.parse::<QualityPreset>() {
+                Ok(preset) => {
+                    self.engine.set_quality_preset(preset);
+                    println!("quality {preset}: max {} bps", self.engine.max_bitrate());
+                }
+                Err(()) => println!("usage: quality <low|medium|high>"),
+            },
+            ("max-bitrate", Some(bps), _) => match bps.parse() {
+                Ok(bps) => self.engine.set_max_bitrate(bps),
+                Err(_) => println!("usage: max-bitrate <bps>"),
+            },
+            ("mute", ..) => self.engine.set_audio_mute(true),
+            ("unmute", ..) => self.engine.set_audio_mute(false),
+            ("video", Some(state @ ("on" | "off")), _) => {
+                let muted = state == "off";
+                self.video_muted = Some(muted);
+                self.engine.set_video_muted(muted);
+            }
+            ("dtmf", Some(_), _) => {
+                let digits = line.trim_start()[cmd.len()..].trim();
+                if let Err(e) = self.engine.send_dtmf(digits) {
+                    println!("{e}");
+                }
+            }
+            ("ptt", Some(mode @ ("on" | "off")), _) => {
+                let enabled = mode == "on";
+                self.push_to_talk = Some(enabled);
+                self.engine.set_push_to_talk(enabled);
+            }
+            ("talk", Some(state @ ("on" | "off")), _) => {
+                self.engine.set_push_to_talk_pressed(state == "on");
+            }
+            ("stats", Some("json"), _) => println!("{}", self.engine.get_stats().to_json()),
+            ("stats", ..) => {
+                let (local, remote) = self.engine.snapshot_frames();
+                println!(
+                    "call={:?} established={} rtp_pkts={} rtp_bytes={} local_frame={} remote_frame={}",
+                    self.call,
+                    self.established,
+                    self.rtp_pkts,
+                    self.rtp_bytes,
+                    local.map_or_else(|| "-".to_string(), |f| format!("{}x{}", f.width, f.height)),
+                    remote.map_or_else(|| "-".to_string(), |f| format!("{}x{}", f.width, f.height)),
+                );
+            }
+            ("wait", Some(secs), _) => {
+                if let Ok(secs) = secs.parse::<u64>() {
+                    let until = Instant::now() + Duration::from_secs(secs);
+                    while Instant::now() < until {
+                        if !self.tick() {
+                            return false;
+                        }
+                    }
+                }
+            }
+            ("quit", ..) | ("exit", ..) => return false,
+            ("help", ..) => println!("{HELP}"),
+            _ => println!("unknown command '{line}' (try 'help')"),
+        }
+        true
+    }
+
+    fn handle_signaling(&mut self, ev: SignalingEvent) -> bool {
+        match ev {
+            SignalingEvent::Connected => println!("connected to signaling server"),
+            SignalingEvent::Disconnected => {
+                println!("signaling server disconnected");
+                return self.username.is_some() && self.reconnect();
+            }
+            SignalingEvent::Error(e) => eprintln!("signaling error: {e}"),
+            SignalingEvent::CallFailed { peer, txn_id } => {
+                println!("{peer} never acknowledged txn {txn_id}");
+                if self.peer().as_deref() == Some(peer.as_str()) {
+                    self.hangup("peer did not respond", true);
+                }
+            }
+            SignalingEvent::ServerMsg(msg) => self.handle_server_msg(msg),
+        }
+        true
+    }
+
+    fn handle_server_msg(&mut self, msg: SignalingMsg) {
+        match msg {
+            SignalingMsg::LoginOk { username } => {
+                println!("logged in as {username}");
+                self.username = Some(username);
+                self.send(SignalingMsg::SetDevice {
+                    label: device_label(&self.config),
+                });
+                // Number the server's messages, or pick up the dropped
+                // connection's where they stopped
+                let seq = self.resume_seq.take().unwrap_or(0);
+                self.send(SignalingMsg::ResumeFrom { seq });
+            }
+            SignalingMsg::LoginErr { code, reason } => match reason {
+                Some(reason) => println!("login failed: {reason} (code {code})"),
+                None => println!("login failed (code {code})"),
+            },
+            SignalingMsg::RegisterOk { username } => println!("registered {username}"),
+            SignalingMsg::RegisterErr { code, reason } => match reason {
+                Some(reason) => println!("register failed: {reason} (code {code})"),
+                None => println!("register failed (code {code})"),
+            },
+            SignalingMsg::PeersOnline { peers } => {
+                for (name, status) in peers {
+                    println!("  {name} ({status:?})");
+                }
+            }
+            SignalingMsg::OfferErr { to, code, reason } => {
+                println!(
+                    "call to {to} refused: {} (code {code})",
+                    reason.as_deref().unwrap_or("no reason given")
+                );
+                if self.peer().as_deref() == Some(to.as_str()) {
+                    self.hangup("call refused", false);
+                }
+            }
+            SignalingMsg::Contacts { contacts, blocked } => {
+                println!("contacts: {}", contacts.join(", "));
+                println!("blocked: {}", blocked.join(", "));
+            }
+            SignalingMsg::Offer {
+                from, txn_id, sdp, ..
+            } => {
+                if self.call == (CliCall::Active { peer: from.clone() }) {
+                    self.handle_reoffer(from, txn_id, sdp);
+                    return;
+                }
+                if self.call != CliCall::Idle {
+                    self.send(SignalingMsg::Bye {
+                        from: self.me(),
+                        to: from,
+                        reason: Some("User is busy".into()),
+                    });
+                    return;
+                }
+                let Ok(sdp) = String::from_utf8(sdp) else {
+                    println!("invalid SDP from {from}");
+                    return;
+                };
+                self.send(SignalingMsg::Ack {
+                    from: self.me(),
+                    to: from.clone(),
+                    txn_id,
+                });
+                println!("incoming call from {from}");
+                self.call = CliCall::Incoming { from, txn_id, sdp };
+                if self.auto_accept {
+                    self.accept_call();
+                }
+            }
+            SignalingMsg::Answer {
+                from, txn_id, sdp, ..
+            } => {
+                self.send(SignalingMsg::Ack {
+                    from: self.me(),
+                    to: from.clone(),
+                    txn_id,
+                });
+                let Ok(sdp) = String::from_utf8(sdp) else {
+                    println!("invalid answer from {from}");
+                    return;
+                };
+                if let Err(e) = self.engine.apply_remote_sdp(&sdp) {
+                    println!("failed to apply answer: {e}");
+                    return;
+                }
+                self.call = CliCall::Active { peer: from.clone() };
+                println!("{from} answered");
+            }
+            SignalingMsg::Candidate { cand, .. } => self.apply_candidate(cand),
+            SignalingMsg::Candidates { items, .. } => {
+                for item in items {
+                    self.apply_candidate(item.cand);
+                }
+            }
+            SignalingMsg::Bye { from, reason, .. } => {
+                if self.peer().as_deref() == Some(from.as_str()) {
+                    self.hangup(reason.as_deref().unwrap_or("remote hangup"), false);
+                }
+            }
+            SignalingMsg::Ping { nonce } => self.send(SignalingMsg::Pong { nonce }),
+            SignalingMsg::Resumed { replayed, complete } => {
+                if complete {
+                    println!("resumed, {replayed} missed messages replayed");
+                } else {
+                    println!("could not resume: messages sent while disconnected are lost");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Answers a re-offer (hold/resume, tracks) from the peer we are talking to.
+    fn handle_reoffer(&mut self, from: String, txn_id: u64, sdp: Vec<u8>) {
+        self.send(SignalingMsg::Ack {
+            from: self.me(),
+            to: from.clone(),
+            txn_id,
+        });
+        let Ok(sdp) = String::from_utf8(sdp) else {
+            println!("invalid re-offer from {from}");
+            return;
+        };
+        match self.engine.apply_remote_sdp(&sdp) {
+            Ok(Some(answer)) => {
+                self.send(SignalingMsg::Answer {
+                    txn_id,
+                    from: self.me(),
+                    to: from.clone(),
+                    sdp: answer.into_bytes(),
+                });
+                if self.engine.is_remote_on_hold() {
+                    println!("{from} put the call on hold");
+                } else {
+                    println!("{from} resumed the call");
+                }
+            }
+            Ok(None) => println!("no answer generated"),
+            Err(e) => println!("failed to apply re-offer: {e}"),
+        }
+    }
+
+    fn apply_candidate(&mut self, cand: Vec<u8>) {
+        if let Ok(line) = String::from_utf8(cand)
+            && let Err(e) = self.engine.apply_remote_candidate(&line)
+        {
+            eprintln!("failed to apply candidate: {e}");
+        }
+    }
+
+    fn handle_engine(&mut self, ev: EngineEvent) {
+        match ev {
+            EngineEvent::IceNominated { local, remote } => {
+                println!("ICE nominated {local} -> {remote}");
+                if let Err(e) = self.engine.start() {
+                    println!("failed to start session: {e}");
+                }
+            }
+            EngineEvent::Established => {
+                println!("media established");
+                self.established = true;
+                self.engine.start_media_transport();
+                if let Some(path) = self.pending_send_file.take() {
+                    self.engine.send_file(path, rand::random::<u32>());
+                }
+            }
+            EngineEvent::Closed => {
+                self.engine.close_session();
+                if self.call != CliCall::Idle {
+                    self.hangup("session closed", true);
+                }
+            }
+            EngineEvent::PeerConnectionStateChanged(PeerConnectionState::Failed) => {
+                if self.call != CliCall::Idle {
+                    self.hangup("connection failed", true);
+                }
+            }
+            EngineEvent::Error(e) => eprintln!("engine error: {e}"),
+            EngineEvent::Status(s) => println!("{s}"),
+            EngineEvent::RtpIn(pkt) => {
+                self.rtp_pkts += 1;
+                self.rtp_bytes += pkt.payload.len() as u64;
+            }
+            EngineEvent::ReceivedFileOffer(props) => {
+                println!(
+                    "file offer: {} ({} bytes)",
+                    props.file_name, props.file_size
+                );
+                if self.auto_accept {
+                    self.engine
+                        .accept_file(props.transaction_id, props.file_name);
+                } else {
+                    self.pending_file_offer = Some((props.transaction_id, props.file_name));
+                }
+            }
+            EngineEvent::SendFileEnd(id) => println!("file {id} sent"),
+            EngineEvent::ReceivedFileEnd(id) => println!("file {id} received"),
+            EngineEvent::ReceivedFileReject(id) => println!("file {id} rejected"),
+            EngineEvent::ReceivedFileCancel(id) => println!("file {id} cancelled"),
+            EngineEvent::RemoteTrackAdded { ssrc, label } => {
+                println!("remote track {label} added (ssrc={ssrc})");
+            }
+            EngineEvent::RemoteTrackRemoved { ssrc } => {
+                println!("remote track removed (ssrc={ssrc})")
+            }
+            EngineEvent::CameraLost { camera_id, reason } => {
+                println!("camera {camera_id} lost: {reason}");
+            }
+            EngineEvent::CameraRecovered { camera_id } => println!("camera {camera_id} is back"),
+            EngineEvent::Speaking {
+                remote: true,
+                speaking,
+            } => println!("peer {}", if speaking { "speaking" } else { "silent" }),
+            EngineEvent::RemoteVideoMuted(muted) => {
+                println!("peer camera {}", if muted { "off" } else { "on" });
+            }
+            _ => {}
+        }
+    }
+
+    /// One iteration of the event pump. Returns `false` when the CLI should exit.
+    /// Connects again after the signaling connection dropped, logs in again
+    /// and resumes where the old connection stopped. Returns whether the
+    /// client is connected again.
+    fn reconnect(&mut self) -> bool {
+        let seq = self.signaling.last_seq();
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            thread::sleep(RECONNECT_DELAY);
+            match (self.dial)() {
+                Ok(signaling) => {
+                    println!("reconnected to signaling server");
+                    self.signaling = signaling;
+                    self.resume_seq = (seq > 0).then_some(seq);
+                    // Certificate logins need nothing sent
+                    if let Some((username, password)) = self.credentials.clone() {
+                        self.send(SignalingMsg::Login { username, password });
+                    }
+                    return true;
+                }
+                Err(e) => eprintln!("reconnect attempt {attempt} failed: {e}"),
+            }
+        }
+        false
+    }
+
+    fn tick(&mut self) -> bool {
+        while let Some(ev) = self.signaling.try_recv() {
+            if !self.handle_signaling(ev) {
+                return false;
+            }
+        }
+        for ev in self.engine.poll() {
+            self.handle_engine(ev);
+        }
+        thread::sleep(Duration::from_millis(10));
+        true
+    }
+}
+
+/// Calls this client itself for `duration` and reports what went through.
+/// Returns whether the local video came back decoded.
+fn run_loopback(config: Arc<Config>, duration: Duration) -> bool {
+    let logger = Logger::start_client(1024, 128, 10, config.clone());
+    let sink: Arc<dyn LogSink> = Arc::new(logger.handle());
+    let files = Arc::new(AtomicBool::new(false));
+    let mut engine = Engine::new(sink.clone(), config.clone(), files.clone(), files);
+    let mut peer = match LoopbackPeer::call(&mut engine, &sink, config) {
+        Ok(peer) => peer,
+        Err(e) => {
+            eprintln!("loopback call failed: {e}");
+            return false;
+        }
+    };
+    println!("calling {LOOPBACK_PEER}...");
+
+    let mut established = false;
+    let mut rtp_pkts = 0u64;
+    let mut frames = 0u64;
+    let mut shown: Option<VideoFrame> = None;
+    let until = Instant::now() + duration;
+    while Instant::now() < until {
+        for ev in engine.poll() {
+            match ev {
+                EngineEvent::IceNominated { local, remote } => {
+                    println!("ICE nominated {local} -> {remote}");
+                    if let Err(e) = engine.start() {
+                        println!("failed to start session: {e}");
+                    }
+                }
+                EngineEvent::Established => {
+                    println!("media established");
+                    established = true;
+                    engine.start_media_transport();
+                }
+                EngineEvent::RtpIn(_) => rtp_pkts += 1,
+                EngineEvent::Error(e) => eprintln!("engine error: {e}"),
+                _ => {}
+            }
+        }
+        peer.poll();
+        if let Some(frame) = peer.received_frame()
+            && !shown.as_ref().is_some_and(|s| s.is_same_frame(&frame))
+        {
+            if frames == 0 {
+                println!("video is back: {}x{}", frame.width, frame.height);
+            }
+            frames += 1;
+            shown = Some(frame);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    drop(peer);
+    engine.stop();
+    println!(
+        "established: {}, local video frames decoded: {frames}, RTP packets received: {rtp_pkts}",
+        if established { "yes" } else { "no" }
+    );
+    established && frames > 0
+}
+
+fn spawn_stdin_reader() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+fn main() {
+    let args = match CliArgs::parse(env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(2);
+        }
+    };
+
+    if let Some(path) = &args.write_config {
+        match Config::write_default(path) {
+            Ok(()) => println!("Wrote default configuration to {path}"),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let config_result = match args.config_path.as_deref() {
+        Some(path) => Config::load(path),
+        None => {
+            Config::load("client_roomrtc.conf").or_else(|_| Config::load("client_default.conf"))
+        }
+    };
+    let mut config = match config_result {
+        Ok(config) => config,
+        // A config named on the command line must be valid
+        Err(e) if args.config_path.is_some() => {
+            eprintln!("Error loading config: {e}");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error loading config: {e}. Using empty config.");
+            Config::empty()
+        }
+    };
+    // Devices and the last server picked in the GUI apply here too, and
+    // ROOMRTC_* variables over them
+    let settings = Settings::load_for(args.config_path.as_deref().unwrap_or("client_roomrtc.conf"));
+    for name in config.apply_layers(&settings, &[]) {
+        eprintln!("Ignoring {name}: no such configuration section");
+    }
+    let config = Arc::new(config);
+
+    if args.loopback {
+        let ok = run_loopback(config, args.duration.unwrap_or(LOOPBACK_DURATION));
+        process::exit(if ok { 0 } else { 1 });
+    }
+
+    let Some(server) = args.server.clone().or_else(|| {
+        config
+            .get_non_empty("Signaling", "server_address")
+            .map(str::to_string)
+    }) else {
+        eprintln!("no signaling server: pass --server or set [Signaling] server_address");
+        process::exit(2);
+    };
+    let domain = config
+        .get_non_empty_or_default("Signaling", "tls_domain", "signal.internal")
+        .to_string();
+    let kind = config
+        .get_non_empty("Signaling", "transport")
+        .and_then(|s| s.parse::<TransportKind>().ok())
+        .unwrap_or_default();
+
+    let logger = Logger::start_client(1024, 128, 10, config.clone());
+    let sink: Arc<dyn LogSink> = Arc::new(logger.handle());
+
+    let trust = TrustOptions::from_config(&config);
+    let recorder = SignalingRecorder::from_config(&config, sink.as_ref());
+    let dial = {
+        let server = server.clone();
+        move || {
+            SignalingClient::connect_with(
+                kind,
+                &server,
+                &domain,
+                &trust,
+                recorder.clone(),
+                sink.clone(),
+            )
+        }
+    };
+    let signaling = match dial() {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("failed to connect to {server}: {e}");
+            if let Some(untrusted) = UntrustedCertificate::from_io(&e) {
+                eprintln!(
+                    "to trust this server, add {} to [TLS] signaling_accepted_keys",
+                    untrusted.fingerprint
+                );
+            }
+            process::exit(1);
+        }
+    };
+
+    let sending_files = Arc::new(AtomicBool::new(false));
+    let receiving_files = Arc::new(AtomicBool::new(false));
+    let engine = Cli::new_engine(&logger, &config, &sending_files, &receiving_files);
+
+    let mut cli = Cli {
+        config,
+        logger,
+        engine,
+        signaling,
+        dial: Box::new(dial),
+        credentials: None,
+        resume_seq: None,
+        username: None,
+        call: CliCall::Idle,
+        next_txn_id: 1,
+        auto_accept: args.auto_accept,
+        pending_send_file: args.send_file,
+        pending_file_offer: None,
+        sending_files,
+        receiving_files,
+        established: false,
+        rtp_pkts: 0,
+        rtp_bytes: 0,
+        camera: None,
+        capture: None,
+        test_sources: None,
+        mic: None,
+        speaker: None,
+        push_to_talk: None,
+        video_muted: None,
+    };
+
+    if let (Some(user), Some(pw)) = (&args.user, &args.password) {
+        cli.handle_command(&format!("login {user} {pw}"));
+        // Give the server a moment so a following `--call` is sent logged in.
+        let until = Instant::now() + Duration::from_secs(2);
+        while cli.username.is_none() && Instant::now() < until {
+            if !cli.tick() {
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(peer) = &args.call {
+        cli.place_call(peer);
+    }
+
+    let deadline = args.duration.map(|d| Instant::now() + d);
+    let stdin = spawn_stdin_reader();
+
+    loop {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            break;
+        }
+        if let Ok(line) = stdin.try_recv()
+            && !cli.handle_command(line.trim())
+        {
+            break;
+        }
+        if !cli.tick() {
+            break;
+        }
+    }
+
+    if cli.call != CliCall::Idle {
+        cli.hangup("quit", true);
+    }
+    cli.engine.stop();
+    cli.signaling.disconnect();
+}
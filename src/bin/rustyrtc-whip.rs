@@ -0,0 +1,214 @@
+//! Gateway between the RoomRTC engine and standard media servers.
+//!
+//! `publish` sends our camera and microphone to a WHIP ingest endpoint;
+//! `play` pulls a stream from a WHEP endpoint. Offer and answer go over
+//! plain HTTP, no signaling server is involved:
+//!
+//! ```text
+//! rustyrtc-whip publish https://media.example.com/whip/live --token s3cret
+//! rustyrtc-whip play http://192.168.0.20:8889/live/whep --duration 60
+//! ```
+//!
+//! The URL, token and CA bundle may also come from the `[WHIP]` section.
+//! Runs until `--duration` elapses or the session ends, then DELETEs it.
+
+use rustyrtc::{
+    config::Config,
+    connection_manager::media_direction::MediaDirection,
+    core::{connection_state::PeerConnectionState, engine::Engine, events::EngineEvent},
+    log::{log_sink::LogSink, logger::Logger},
+    settings::Settings,
+    whip::{WhipClient, WhipMode},
+};
+use std::{
+    env, process,
+    sync::{Arc, atomic::AtomicBool},
+    thread,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str =
+    "usage: rustyrtc-whip <publish|play> [URL] [CONFIG] [--token TOKEN] [--duration SECS]";
+
+#[derive(Debug)]
+struct WhipArgs {
+    mode: WhipMode,
+    url: Option<String>,
+    config_path: Option<String>,
+    token: Option<String>,
+    duration: Option<Duration>,
+}
+
+impl WhipArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mode = match args.next().as_deref() {
+            Some("publish") => WhipMode::Publish,
+            Some("play") => WhipMode::Play,
+            _ => return Err(USAGE.to_string()),
+        };
+        let mut out = Self {
+            mode,
+            url: None,
+            config_path: None,
+            token: None,
+            duration: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{name} requires a value"))
+            };
+            match arg.as_str() {
+                "--token" => out.token = Some(value("--token")?),
+                "--duration" => {
+                    let secs: u64 = value("--duration")?
+                        .parse()
+                        .map_err(|_| "--duration expects seconds".to_string())?;
+                    out.duration = Some(Duration::from_secs(secs));
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other if other.starts_with("--") => return Err(format!("unknown flag {other}")),
+                url if url.starts_with("http://") || url.starts_with("https://") => {
+                    out.url = Some(url.to_string());
+                }
+                path => out.config_path = Some(path.to_string()),
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn main() {
+    let args = match WhipArgs::parse(env::args().skip(1)) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(2);
+        }
+    };
+
+    let config_result = match args.config_path.as_deref() {
+        Some(path) => Config::load(path),
+        None => {
+            Config::load("client_roomrtc.conf").or_else(|_| Config::load("client_default.conf"))
+        }
+    };
+    let mut config = match config_result {
+        Ok(config) => config,
+        Err(e) if args.config_path.is_some() => {
+            eprintln!("Error loading config: {e}");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error loading config: {e}. Using empty config.");
+            Config::empty()
+        }
+    };
+    let settings = Settings::load_for(args.config_path.as_deref().unwrap_or("client_roomrtc.conf"));
+    for name in config.apply_layers(&settings, &[]) {
+        eprintln!("Ignoring {name}: no such configuration section");
+    }
+    let (url_key, direction) = match args.mode {
+        WhipMode::Publish => ("whip_url", MediaDirection::SendOnly),
+        WhipMode::Play => ("whep_url", MediaDirection::RecvOnly),
+    };
+    if let Some(url) = &args.url {
+        config.set("WHIP", url_key, url.as_str());
+    }
+    if let Some(token) = &args.token {
+        config.set("WHIP", "token", token.as_str());
+    }
+    let config = Arc::new(config);
+
+    let mut client = match WhipClient::from_config(args.mode, &config) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(2);
+        }
+    };
+
+    let logger = Logger::start_client(1024, 128, 10, config.clone());
+    let sink: Arc<dyn LogSink> = Arc::new(logger.handle());
+    let mut engine = Engine::new(
+        sink,
+        config.clone(),
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(AtomicBool::new(false)),
+    );
+    engine.set_local_direction(direction);
+
+    let offer = match engine.negotiate() {
+        Ok(Some(offer)) => offer,
+        Ok(None) => {
+            eprintln!("no offer generated");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("failed to create offer: {e}");
+            process::exit(1);
+        }
+    };
+    let answer = match client.offer(&offer) {
+        Ok(answer) => answer,
+        Err(e) => {
+            eprintln!("offer rejected: {e}");
+            process::exit(1);
+        }
+    };
+    if let Some(resource) = client.resource() {
+        println!("session created at {resource}");
+    }
+    if let Err(e) = engine.apply_remote_sdp(&answer) {
+        eprintln!("failed to apply answer: {e}");
+        let _ = client.close();
+        process::exit(1);
+    }
+
+    let deadline = args.duration.map(|d| Instant::now() + d);
+    let (mut rtp_pkts, mut rtp_bytes) = (0u64, 0u64);
+    'session: loop {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            break;
+        }
+        for ev in engine.poll() {
+            match ev {
+                EngineEvent::IceNominated { local, remote } => {
+                    println!("ICE nominated {local} -> {remote}");
+                    if let Err(e) = engine.start() {
+                        println!("failed to start session: {e}");
+                        break 'session;
+                    }
+                }
+                EngineEvent::Established => {
+                    println!("media established");
+                    engine.start_media_transport();
+                }
+                EngineEvent::Closed
+                | EngineEvent::PeerConnectionStateChanged(PeerConnectionState::Failed) => {
+                    println!("session ended");
+                    break 'session;
+                }
+                EngineEvent::Error(e) => eprintln!("engine error: {e}"),
+                EngineEvent::Status(s) => println!("{s}"),
+                EngineEvent::RtpIn(pkt) => {
+                    rtp_pkts += 1;
+                    rtp_bytes += pkt.payload.len() as u64;
+                }
+                EngineEvent::RemoteTrackAdded { ssrc, label } => {
+                    println!("remote track {label} added (ssrc={ssrc})");
+                }
+                _ => {}
+            }
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    if args.mode == WhipMode::Play {
+        println!("received {rtp_pkts} RTP packets ({rtp_bytes} bytes)");
+    }
+    engine.stop();
+    if let Err(e) = client.close() {
+        eprintln!("failed to delete the session: {e}");
+    }
+}
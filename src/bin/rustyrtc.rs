@@ -1,34 +1,138 @@
 //! The client binary for the RoomRTC application.
 //! It starts the `eframe` application and the `RtcApp`.
 
-use rustyrtc::{app::rtc_app::RtcApp, config::Config};
-use std::env;
-use std::sync::Arc; // Importamos env para leer argumentos
+use clap::Parser;
+use eframe::egui;
+use rustyrtc::{
+    app::rtc_app::RtcApp,
+    config::{Config, ENV_CONFIG, Override, watcher::ConfigWatcher},
+    settings::Settings,
+};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+
+/// RoomRTC video call client.
+///
+/// Flags override `ROOMRTC_*` environment variables, which override the
+/// saved settings and the configuration file.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Configuration file (.conf or .toml); defaults to client_roomrtc.conf,
+    /// then client_default.conf
+    #[arg(env = ENV_CONFIG)]
+    config: Option<String>,
+    /// Signaling server address (host:port)
+    #[arg(long, value_name = "ADDR")]
+    server: Option<String>,
+    /// Username prefilled on the login screen
+    #[arg(long)]
+    username: Option<String>,
+    /// Lowest level written to the log file
+    #[arg(long, value_name = "LEVEL", value_parser = ["trace", "debug", "info", "warn", "error"], ignore_case = true)]
+    log_level: Option<String>,
+    /// Send test video and audio instead of using the camera and microphone
+    #[arg(long)]
+    headless: bool,
+}
+
+impl Args {
+    fn overrides(&self) -> Vec<Override> {
+        let mut out = Vec::new();
+        if let Some(server) = &self.server {
+            out.push(Override::new("Signaling", "server_address", server));
+        }
+        if let Some(username) = &self.username {
+            out.push(Override::new("Signaling", "username", username));
+        }
+        if let Some(level) = &self.log_level {
+            out.push(Override::global("log_level", level));
+        }
+        if self.headless {
+            out.push(Override::new("Media", "video_source", "test"));
+            out.push(Override::new("Media", "audio_source", "test"));
+        }
+        out
+    }
+}
 
 fn main() -> eframe::Result<()> {
-    let args: Vec<String> = env::args().collect();
-
-    let config_result = if args.len() > 1 {
-        let path = &args[1];
-        println!("Intentando cargar configuración personalizada: {}", path);
-        Config::load(path)
-    } else {
-        Config::load("client_roomrtc.conf").or_else(|_| Config::load("client_default.conf"))
+    let args = Args::parse();
+
+    let config_result = match &args.config {
+        Some(path) => Config::load(path),
+        None => {
+            Config::load("client_roomrtc.conf").or_else(|_| Config::load("client_default.conf"))
+        }
+    };
+
+    let mut config = match config_result {
+        Ok(config) => config,
+        // A config named on the command line must be valid
+        Err(e) if args.config.is_some() => {
+            eprintln!("Error loading config: {e}");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error loading config: {e}. Using empty config.");
+            Config::empty()
+        }
     };
 
-    let config = config_result.unwrap_or_else(|e| {
-        eprintln!("Error loading config: {e}. Using empty config.");
-        Config::empty()
+    // Choices saved from the Settings window (and the last server, username
+    // and window layout) override the file; the environment and the flags
+    // override both
+    let settings = Settings::load_for(args.config.as_deref().unwrap_or("client_roomrtc.conf"));
+    let overrides = args.overrides();
+    for name in config.apply_layers(&settings, &overrides) {
+        eprintln!("Ignoring {name}: no such configuration section");
+    }
+
+    // Edits to the file apply while running, under the same overrides
+    let watched = args.config.clone().or_else(|| {
+        ["client_roomrtc.conf", "client_default.conf"]
+            .into_iter()
+            .find(|path| Path::new(path).exists())
+            .map(str::to_owned)
+    });
+    let settings_path = settings.path().map(Path::to_path_buf);
+    let watcher = watched.map(|path| {
+        ConfigWatcher::spawn(path, move |config| {
+            let settings = settings_path
+                .clone()
+                .map_or_else(Settings::in_memory, Settings::load);
+            let _ = config.apply_layers(&settings, &overrides);
+        })
     });
 
+    let native_options = eframe::NativeOptions {
+        viewport: saved_viewport(&config),
+        ..Default::default()
+    };
     let config = Arc::new(config);
-    let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "RoomRTC • SDP Messenger",
         native_options,
         Box::new(|cc| {
-            let app = RtcApp::new(cc, config);
+            let mut app = RtcApp::new(cc, config, settings);
+            if let Some(watcher) = watcher {
+                app.watch_config(watcher);
+            }
             Ok(Box::new(app))
         }),
     )
 }
+
+/// Window size and position from the `[UI]` section, as saved on exit.
+fn saved_viewport(config: &Config) -> egui::ViewportBuilder {
+    let num = |key| config.get("UI", key).and_then(|v| v.parse::<f32>().ok());
+    let mut viewport = egui::ViewportBuilder::default();
+    if let (Some(w), Some(h)) = (num("window_width"), num("window_height")) {
+        viewport = viewport.with_inner_size([w, h]);
+    }
+    if let (Some(x), Some(y)) = (num("window_x"), num("window_y")) {
+        viewport = viewport.with_position([x, y]);
+    }
+    viewport
+}
@@ -1,32 +1,84 @@
 //! The signaling server binary for the RoomRTC application.
 //! It starts the signaling server and listens for incoming connections.
 
-use rustyrtc::config::Config;
+use clap::Parser;
+use rustyrtc::config::{Config, ENV_CONFIG, Override};
 use rustyrtc::log::log_sink::LogSink;
 use rustyrtc::log::logger::Logger;
+use rustyrtc::settings::Settings;
 use rustyrtc::signaling::run::run_signaling_server_with_log;
+use std::process;
 use std::sync::Arc;
-use std::{env, process};
+
+/// RoomRTC signaling server.
+///
+/// Flags override `ROOMRTC_*` environment variables, which override the
+/// configuration file.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    /// Configuration file (.conf or .toml); defaults to server_roomrtc.conf,
+    /// then server_default.conf
+    #[arg(env = ENV_CONFIG)]
+    config: Option<String>,
+    /// Address to listen on (host:port)
+    #[arg(long, value_name = "ADDR")]
+    listen: Option<String>,
+    /// User database file
+    #[arg(long, value_name = "PATH")]
+    database: Option<String>,
+    /// Lowest level written to the log file
+    #[arg(long, value_name = "LEVEL", value_parser = ["trace", "debug", "info", "warn", "error"], ignore_case = true)]
+    log_level: Option<String>,
+}
+
+impl Args {
+    fn overrides(&self) -> Vec<Override> {
+        let mut out = Vec::new();
+        if let Some(listen) = &self.listen {
+            out.push(Override::new("Signaling", "listen_address", listen));
+        }
+        if let Some(database) = &self.database {
+            out.push(Override::new("Signaling", "database_path", database));
+        }
+        if let Some(level) = &self.log_level {
+            out.push(Override::global("log_level", level));
+        }
+        out
+    }
+}
 
 fn main() -> std::io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let config_result = if args.len() > 1 {
-        let path = &args[1];
-        println!("Trying to load personal config: {}", path);
-        Config::load(path)
-    } else {
-        Config::load("server_roomrtc.conf").or_else(|_| Config::load("server_default.conf"))
+    let args = Args::parse();
+    let config_result = match &args.config {
+        Some(path) => Config::load(path),
+        None => {
+            Config::load("server_roomrtc.conf").or_else(|_| Config::load("server_default.conf"))
+        }
     };
 
-    let config = config_result.unwrap_or_else(|e| {
-        eprintln!("Error loading config: {e}. Using empty config.");
-        Config::empty()
-    });
+    let mut config = match config_result {
+        Ok(config) => config,
+        // A config named on the command line must be valid
+        Err(e) if args.config.is_some() => {
+            eprintln!("Error loading config: {e}");
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error loading config: {e}. Using empty config.");
+            Config::empty()
+        }
+    };
+    for name in config.apply_layers(&Settings::in_memory(), &args.overrides()) {
+        eprintln!("Ignoring {name}: no such configuration section");
+    }
 
     let config = Arc::new(config);
 
     let Some(addr) = config.get_non_empty("Signaling", "listen_address") else {
-        eprintln!("You need to set the listen_addres parameter in the config file");
+        eprintln!(
+            "You need to set listen_address in the config file, ROOMRTC_SIGNALING_LISTEN_ADDRESS or --listen"
+        );
         process::exit(1);
     };
 
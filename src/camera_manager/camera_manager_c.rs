@@ -14,7 +14,7 @@ use std::sync::Arc;
 
 use crate::log::log_sink::LogSink;
 
-use super::camera_error::CameraError;
+use super::{camera_error::CameraError, capture_settings::CaptureSettings};
 
 /// Struct responsible for managing a single camera device.
 ///
@@ -111,6 +111,74 @@ impl CameraManager {
         }
     }
 
+    /// Opens the device and requests the given capture format.
+    ///
+    /// The pixel format is applied first, since some drivers only offer
+    /// larger resolutions with MJPG. Values the driver does not accept are
+    /// silently replaced by the closest supported ones; [`width`](Self::width),
+    /// [`height`](Self::height) and [`fps`](Self::fps) report what was applied.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn with_settings(
+        device_id: i32,
+        settings: &CaptureSettings,
+        logger: Arc<dyn LogSink>,
+    ) -> Result<Self, CameraError> {
+        let mut me = Self::new(device_id, logger)?;
+        if let Some(cam) = me.cam.as_mut() {
+            if let Some(format) = settings.pixel_format {
+                let _ = cam.set(videoio::CAP_PROP_FOURCC, f64::from(format.fourcc()));
+            }
+            if let Some(width) = settings.width {
+                let _ = cam.set(videoio::CAP_PROP_FRAME_WIDTH, f64::from(width));
+            }
+            if let Some(height) = settings.height {
+                let _ = cam.set(videoio::CAP_PROP_FRAME_HEIGHT, f64::from(height));
+            }
+            let _ = cam.set(videoio::CAP_PROP_FPS, f64::from(settings.fps));
+            if let Some(zoom) = settings.zoom {
+                // Ignored by cameras without optical or digital zoom
+                let _ = cam.set(videoio::CAP_PROP_ZOOM, f64::from(zoom));
+            }
+        }
+        me.refresh_size()?;
+        Ok(me)
+    }
+
+    /// Re-reads the frame size from the device.
+    fn refresh_size(&mut self) -> Result<(), CameraError> {
+        let Some(cam) = self.cam.as_ref() else {
+            return Err(CameraError::CameraOff);
+        };
+        let width_f64 = cam
+            .get(videoio::CAP_PROP_FRAME_WIDTH)
+            .map_err(|e| CameraError::InitializationFailed(e.to_string()))?
+            .clamp(1.0, 8192.0);
+        let height_f64 = cam
+            .get(videoio::CAP_PROP_FRAME_HEIGHT)
+            .map_err(|e| CameraError::InitializationFailed(e.to_string()))?
+            .clamp(1.0, 8192.0);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        {
+            self.width = width_f64.round() as u32;
+            self.height = height_f64.round() as u32;
+        }
+        Ok(())
+    }
+
+    /// Frame rate reported by the device (0 if unknown).
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn fps(&self) -> u32 {
+        self.cam
+            .as_ref()
+            .and_then(|cam| cam.get(videoio::CAP_PROP_FPS).ok())
+            .map_or(0, |fps| fps.round().max(0.0) as u32)
+    }
+
     #[must_use]
     /// Returns the width of the camera frames.
     ///
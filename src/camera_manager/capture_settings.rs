@@ -0,0 +1,122 @@
+//! Requested capture format of a camera device.
+//!
+//! Drivers are free to pick the closest mode they support, so the values are
+//! requests; the camera manager reports what was actually applied.
+
+use std::{fmt, str::FromStr};
+
+use crate::{config::Config, core::quality::LowDataMode, media_agent::constants::TARGET_FPS};
+
+/// Pixel format requested from the device.
+///
+/// MJPG usually allows higher resolutions at full frame rate over USB 2.0;
+/// YUYV avoids the decode cost but is bandwidth-limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Mjpg,
+    Yuyv,
+}
+
+impl PixelFormat {
+    /// FOURCC code as `OpenCV` expects it for `CAP_PROP_FOURCC`.
+    #[must_use]
+    pub const fn fourcc(self) -> i32 {
+        let code = match self {
+            Self::Mjpg => *b"MJPG",
+            Self::Yuyv => *b"YUYV",
+        };
+        i32::from_le_bytes(code)
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mjpg => "mjpg",
+            Self::Yuyv => "yuyv",
+        }
+    }
+}
+
+impl fmt::Display for PixelFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mjpg" | "mjpeg" => Ok(Self::Mjpg),
+            "yuyv" | "yuy2" => Ok(Self::Yuyv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Capture resolution, frame rate and pixel format to request.
+///
+/// `None` leaves the driver default in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureSettings {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: u32,
+    pub pixel_format: Option<PixelFormat>,
+    /// Zoom in the driver's units, for the cameras that support it.
+    pub zoom: Option<u16>,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            width: None,
+            height: None,
+            fps: TARGET_FPS,
+            pixel_format: None,
+            zoom: None,
+        }
+    }
+}
+
+impl CaptureSettings {
+    /// Reads the `[Media]` keys `capture_width`, `capture_height`, `fps` and
+    /// `pixel_format`; low data mode lowers the frame rate.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let parse = |key| config.get("Media", key).and_then(|s| s.parse().ok());
+        let fps = parse("fps").unwrap_or(TARGET_FPS);
+        Self {
+            width: parse("capture_width"),
+            height: parse("capture_height"),
+            fps: LowDataMode::from_config(config).map_or(fps, |mode| fps.min(mode.max_fps)),
+            pixel_format: config
+                .get("Media", "pixel_format")
+                .and_then(|s| s.parse().ok()),
+            zoom: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn pixel_format_parses_aliases_and_fourcc() {
+        assert_eq!("MJPEG".parse(), Ok(PixelFormat::Mjpg));
+        assert_eq!(" yuy2".parse(), Ok(PixelFormat::Yuyv));
+        assert!("h264".parse::<PixelFormat>().is_err());
+        // 'M' | 'J' << 8 | 'P' << 16 | 'G' << 24, as cv::VideoWriter::fourcc
+        assert_eq!(PixelFormat::Mjpg.fourcc(), 0x4750_4A4D);
+    }
+
+    #[test]
+    fn empty_config_keeps_driver_defaults() {
+        let settings = CaptureSettings::from_config(&Config::empty());
+        assert_eq!(settings, CaptureSettings::default());
+        assert_eq!(settings.fps, TARGET_FPS);
+    }
+}
@@ -0,0 +1,138 @@
+//! Camera device enumeration.
+//!
+//! `OpenCV` has no portable device listing, so indices are probed one by one.
+//! Supported modes are found by requesting common resolutions and reading
+//! back what the driver actually accepted.
+
+use opencv::{
+    prelude::*,
+    videoio::{self, VideoCapture, VideoCaptureTrait, VideoCaptureTraitConst},
+};
+
+/// Highest device index probed by [`list_cameras`].
+pub const MAX_PROBED_CAMERAS: i32 = 8;
+
+/// Resolutions requested while probing a device.
+const PROBE_RESOLUTIONS: [(u32, u32); 4] = [(320, 240), (640, 480), (1280, 720), (1920, 1080)];
+
+/// A capture mode accepted by a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CameraMode {
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+}
+
+/// A camera found on this machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraDevice {
+    /// `OpenCV` device index (what `CameraManager::new` expects).
+    pub index: i32,
+    /// Human-readable name (from the OS when available).
+    pub name: String,
+    /// Modes the device accepted while probing, smallest first.
+    pub modes: Vec<CameraMode>,
+}
+
+impl CameraDevice {
+    /// Label for device pickers, e.g. `"0: HD Webcam (1280x720@30)"`.
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self.modes.last() {
+            Some(m) => format!(
+                "{}: {} ({}x{}@{})",
+                self.index, self.name, m.width, m.height, m.fps
+            ),
+            None => format!("{}: {}", self.index, self.name),
+        }
+    }
+}
+
+/// Lists the cameras that can be opened, probing their supported modes.
+///
+/// Opening a device can take a moment, so avoid calling this every frame.
+/// Devices already in use by this process may not show up.
+#[must_use]
+pub fn list_cameras() -> Vec<CameraDevice> {
+    (0..MAX_PROBED_CAMERAS).filter_map(probe_device).collect()
+}
+
+fn probe_device(index: i32) -> Option<CameraDevice> {
+    let mut cam = VideoCapture::new(index, videoio::CAP_ANY).ok()?;
+    if !cam.is_opened().unwrap_or(false) {
+        return None;
+    }
+    let backend = cam.get_backend_name().unwrap_or_default();
+
+    let mut modes = Vec::new();
+    for (w, h) in PROBE_RESOLUTIONS {
+        let _ = cam.set(videoio::CAP_PROP_FRAME_WIDTH, f64::from(w));
+        let _ = cam.set(videoio::CAP_PROP_FRAME_HEIGHT, f64::from(h));
+        if let Some(mode) = current_mode(&cam)
+            && !modes.contains(&mode)
+        {
+            modes.push(mode);
+        }
+    }
+    modes.sort_by_key(|m| (m.width * m.height, m.fps));
+    let _ = cam.release();
+
+    Some(CameraDevice {
+        index,
+        name: os_device_name(index).unwrap_or_else(|| format!("Camera {index} ({backend})")),
+        modes,
+    })
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn current_mode(cam: &VideoCapture) -> Option<CameraMode> {
+    let width = cam.get(videoio::CAP_PROP_FRAME_WIDTH).ok()?;
+    let height = cam.get(videoio::CAP_PROP_FRAME_HEIGHT).ok()?;
+    let fps = cam.get(videoio::CAP_PROP_FPS).unwrap_or(0.0);
+    (width >= 1.0 && height >= 1.0).then(|| CameraMode {
+        width: width.round() as u32,
+        height: height.round() as u32,
+        fps: fps.round().max(0.0) as u32,
+    })
+}
+
+/// The V4L2 device name on Linux; `None` elsewhere.
+fn os_device_name(index: i32) -> Option<String> {
+    let path = format!("/sys/class/video4linux/video{index}/name");
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn label_shows_largest_mode() {
+        let dev = CameraDevice {
+            index: 2,
+            name: "Webcam".into(),
+            modes: vec![
+                CameraMode {
+                    width: 640,
+                    height: 480,
+                    fps: 30,
+                },
+                CameraMode {
+                    width: 1280,
+                    height: 720,
+                    fps: 30,
+                },
+            ],
+        };
+        assert_eq!(dev.label(), "2: Webcam (1280x720@30)");
+        let bare = CameraDevice {
+            modes: vec![],
+            ..dev
+        };
+        assert_eq!(bare.label(), "2: Webcam");
+    }
+}
@@ -1,4 +1,6 @@
 //! Manages camera devices using OpenCV for frame capturing.
 pub mod camera_error;
 pub mod camera_manager_c;
+pub mod capture_settings;
+pub mod devices;
 pub mod utils;
@@ -0,0 +1,55 @@
+use std::{fmt, io, path::PathBuf};
+
+/// Why a configuration file could not be loaded or written.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// The file is not valid TOML.
+    Parse(toml::de::Error),
+    /// Values that fail the schema, one line each.
+    Invalid(Vec<String>),
+    /// The defaults could not be written as TOML.
+    Serialize(toml::ser::Error),
+    /// A parse or schema error in the file at `path`.
+    File {
+        path: PathBuf,
+        source: Box<ConfigError>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "cannot access {}: {source}", path.display()),
+            Self::Parse(e) => write!(f, "{e}"),
+            Self::Invalid(errors) => write!(f, "\n  {}", errors.join("\n  ")),
+            Self::Serialize(e) => write!(f, "cannot write the defaults: {e}"),
+            Self::File { path, source } => write!(f, "Invalid config {}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse(e) => Some(e),
+            Self::Serialize(e) => Some(e),
+            Self::File { source, .. } => Some(source.as_ref()),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(e: toml::de::Error) -> Self {
+        Self::Parse(e)
+    }
+}
+
+impl From<toml::ser::Error> for ConfigError {
+    fn from(e: toml::ser::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
@@ -0,0 +1,147 @@
+//! Schema check of the INI-style `.conf` format.
+//!
+//! A `.conf` file has no types: every value is text. To check it against
+//! the same schema as TOML, each section the schema knows is deserialized
+//! with its text parsed into whatever type the field asks for. An empty
+//! value is unset, as [`Config::get_non_empty`](super::Config::get_non_empty)
+//! reads it.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+use serde::de::{
+    self, IntoDeserializer, Visitor,
+    value::{Error, MapDeserializer},
+};
+
+use super::schema::ConfigFile;
+
+/// A `.conf` value, parsed when the field's type is known.
+struct Text<'a>(&'a str);
+
+macro_rules! parse_into {
+    ($($method:ident => $visit:ident),* $(,)?) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0.parse() {
+                Ok(value) => visitor.$visit(value),
+                Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Text<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    parse_into! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    de::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct newtype_struct
+        seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Text<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The schema section `name`, or its defaults if it is missing or invalid
+/// (the problem is added to `errors`).
+fn section<'a, T: Deserialize<'a> + Default>(
+    sections: &'a HashMap<String, HashMap<String, String>>,
+    name: &str,
+    errors: &mut Vec<String>,
+) -> T {
+    let Some(values) = sections.get(name) else {
+        return T::default();
+    };
+    let values = values
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (key.as_str(), Text(value)));
+    T::deserialize(MapDeserializer::new(values)).unwrap_or_else(|e| {
+        errors.push(format!("[{name}] {e}"));
+        T::default()
+    })
+}
+
+/// Checks a parsed `.conf` file against [`ConfigFile`]. Returns one message
+/// per problem, empty if the file is valid.
+pub(super) fn validate(
+    globals: &HashMap<String, String>,
+    sections: &HashMap<String, HashMap<String, String>>,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let file = ConfigFile {
+        log_level: globals.get("log_level").filter(|v| !v.is_empty()).cloned(),
+        signaling: section(sections, "Signaling", &mut errors),
+        media: section(sections, "Media", &mut errors),
+        ice: section(sections, "ICE", &mut errors),
+        logging: section(sections, "Logging", &mut errors),
+        other: BTreeMap::new(),
+    };
+    errors.extend(file.validate());
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn sections(pairs: &[(&str, &str, &str)]) -> HashMap<String, HashMap<String, String>> {
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (section, key, value) in pairs {
+            sections
+                .entry((*section).to_owned())
+                .or_default()
+                .insert((*key).to_owned(), (*value).to_owned());
+        }
+        sections
+    }
+
+    #[test]
+    fn text_values_are_checked_as_their_field_type() {
+        let valid = sections(&[
+            ("Media", "fps", "30"),
+            ("Media", "vad_threshold_db", "-45"),
+            ("Media", "push_to_talk", "true"),
+            ("Media", "h264_level", "3.1"),
+            ("Signaling", "username", "1234"),
+            ("ICE", "mtu", ""),
+            ("UI", "anything", "goes"),
+        ]);
+        assert!(validate(&HashMap::new(), &valid).is_empty());
+
+        let errors = validate(&HashMap::new(), &sections(&[("Media", "fps", "thirty")]));
+        assert!(errors[0].starts_with("[Media] invalid value"), "{errors:?}");
+        let errors = validate(&HashMap::new(), &sections(&[("Media", "fps", "0")]));
+        assert!(errors[0].contains("[Media] fps = 0"), "{errors:?}");
+        let errors = validate(&HashMap::new(), &sections(&[("ICE", "mut", "1200")]));
+        assert!(errors[0].contains("unknown field `mut`"), "{errors:?}");
+    }
+}
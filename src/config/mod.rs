@@ -0,0 +1,444 @@
+//! Configuration management module.
+//!
+//! Handles loading and parsing of configuration files: the INI-style
+//! `.conf` format and TOML (`.toml`), both checked against a typed schema
+//! (see [`schema`]).
+//!
+//! # Precedence
+//!
+//! A value is looked up in these layers, each overriding the ones before
+//! it (see [`Config::apply_layers`]):
+//!
+//! 1. the built-in default of the module that reads it;
+//! 2. the configuration file;
+//! 3. the settings saved from the GUI ([`Settings`]);
+//! 4. `ROOMRTC_*` environment variables (see [`Config::apply_env_vars`]);
+//! 5. command-line flags ([`Override`]).
+
+pub mod config_error;
+mod ini;
+pub mod schema;
+pub mod watcher;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+pub use config_error::ConfigError;
+use schema::ConfigFile;
+
+use crate::settings::Settings;
+
+/// Prefix of the environment variables that override the configuration.
+pub const ENV_PREFIX: &str = "ROOMRTC_";
+
+/// Environment variable naming the configuration file. It is read by the
+/// binaries, not applied as a value.
+pub const ENV_CONFIG: &str = "ROOMRTC_CONFIG";
+
+/// Sections that environment variables can reach, besides the ones already
+/// in the configuration.
+const ENV_SECTIONS: [&str; 10] = [
+    "Signaling",
+    "Media",
+    "ICE",
+    "Logging",
+    "TLS",
+    "file_handler",
+    "Shortcuts",
+    "UI",
+    "WHIP",
+    "SFU",
+];
+
+/// First lines of a file written by [`Config::write_default`].
+const DEFAULT_HEADER: &str = "# RoomRTC default configuration for the client.
+# Every key is optional; a missing one keeps the built-in default. See
+# client_default.conf for what each key does.
+
+";
+
+/// A value set on the command line; the highest precedence layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Override {
+    /// Section of the value, `None` for a global one.
+    pub section: Option<&'static str>,
+    pub key: &'static str,
+    pub value: String,
+}
+
+impl Override {
+    #[must_use]
+    pub fn new(section: &'static str, key: &'static str, value: impl Into<String>) -> Self {
+        Self {
+            section: Some(section),
+            key,
+            value: value.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn global(key: &'static str, value: impl Into<String>) -> Self {
+        Self {
+            section: None,
+            key,
+            value: value.into(),
+        }
+    }
+}
+
+/// Represents a configuration file with global settings and named sections.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Global key-value pairs.
+    pub globals: HashMap<String, String>,
+    /// Section-specific key-value pairs.
+    pub sections: HashMap<String, HashMap<String, String>>,
+}
+
+impl Config {
+    /// Loads a configuration from a file.
+    ///
+    /// The file format is a simple INI-style format.
+    /// Lines starting with `#` are comments.
+    /// Sections are denoted by `[section_name]`.
+    /// Key-value pairs are `key = value`.
+    ///
+    /// A path ending in `.toml` is read as TOML instead; its tables become
+    /// the sections. Either way the values are validated against
+    /// [`ConfigFile`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Io`] if the file cannot be read, or
+    /// [`ConfigError::File`] if it is malformed TOML or has invalid values
+    /// (all of them are listed).
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.into(),
+            source,
+        })?;
+        if Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+        {
+            return Self::from_toml(&content).map_err(|e| ConfigError::File {
+                path: path.into(),
+                source: Box::new(e),
+            });
+        }
+
+        let mut globals = HashMap::new();
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut current_section: Option<String> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = &line[1..line.len() - 1];
+                current_section = Some(name.to_string());
+                continue;
+            }
+
+            if let Some(pos) = line.find('=') {
+                let key = line[..pos].trim().to_string();
+                let value = line[pos + 1..].trim().trim_matches('"').to_string();
+
+                match &current_section {
+                    None => {
+                        globals.insert(key, value);
+                    }
+                    Some(sec) => {
+                        sections.entry(sec.clone()).or_default().insert(key, value);
+                    }
+                }
+            }
+        }
+        let errors = ini::validate(&globals, &sections);
+        if !errors.is_empty() {
+            return Err(ConfigError::File {
+                path: path.into(),
+                source: Box::new(ConfigError::Invalid(errors)),
+            });
+        }
+        Ok(Config { globals, sections })
+    }
+
+    /// Parses and validates a TOML configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Parse`], or [`ConfigError::Invalid`] with
+    /// one line per invalid value.
+    pub fn from_toml(content: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile = toml::from_str(content)?;
+        let errors = file.validate();
+        if !errors.is_empty() {
+            return Err(ConfigError::Invalid(errors));
+        }
+
+        let table: toml::Table = content.parse()?;
+        let mut globals = HashMap::new();
+        let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (key, value) in table {
+            match value {
+                toml::Value::Table(values) => {
+                    let section = sections.entry(key).or_default();
+                    for (k, v) in values {
+                        section.insert(k, toml_to_string(v));
+                    }
+                }
+                value => {
+                    globals.insert(key, toml_to_string(value));
+                }
+            }
+        }
+        Ok(Config { globals, sections })
+    }
+
+    /// Writes the client defaults to `path` as TOML, ready to be edited
+    /// and loaded with [`load`](Self::load).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::Io`] if the file cannot be written.
+    pub fn write_default(path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        let body = toml::to_string(&ConfigFile::client_default())?;
+        fs::write(path, format!("{DEFAULT_HEADER}{body}")).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Applies the layers above the configuration file, in precedence
+    /// order: `settings`, then the `ROOMRTC_*` environment variables, then
+    /// the command-line `overrides`.
+    ///
+    /// Returns the environment variables that name no known section (see
+    /// [`apply_env_vars`](Self::apply_env_vars)), for the caller to report.
+    #[must_use]
+    pub fn apply_layers(&mut self, settings: &Settings, overrides: &[Override]) -> Vec<String> {
+        settings.apply_to(self);
+        let ignored = self.apply_env_vars(env::vars());
+        for o in overrides {
+            match o.section {
+                Some(section) => self.set(section, o.key, o.value.clone()),
+                None => {
+                    self.globals.insert(o.key.to_owned(), o.value.clone());
+                }
+            }
+        }
+        ignored
+    }
+
+    /// Applies `ROOMRTC_<SECTION>_<KEY>` variables from `vars`, e.g.
+    /// `ROOMRTC_SIGNALING_SERVER_ADDRESS` or `ROOMRTC_ICE_STUN_SERVER`.
+    /// The part after the prefix is case-insensitive; `ROOMRTC_LOG_LEVEL`
+    /// sets the global `log_level`. Other variables are left alone.
+    ///
+    /// Returns the `ROOMRTC_*` names that match no section.
+    #[must_use]
+    pub fn apply_env_vars(
+        &mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Vec<String> {
+        let mut ignored = Vec::new();
+        for (name, value) in vars {
+            let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if name == ENV_CONFIG {
+                continue;
+            }
+            let rest = rest.to_ascii_lowercase();
+            if rest == "log_level" {
+                self.globals.insert(rest, value);
+                continue;
+            }
+            // The longest matching section, so `FILE_HANDLER_*` isn't read
+            // as a `file` section
+            let section = ENV_SECTIONS
+                .into_iter()
+                .map(str::to_owned)
+                .chain(self.sections.keys().cloned())
+                .filter(|section| {
+                    rest.strip_prefix(&section.to_ascii_lowercase())
+                        .is_some_and(|key| key.len() > 1 && key.starts_with('_'))
+                })
+                .max_by_key(String::len);
+            match section {
+                Some(section) => {
+                    let key = rest[section.len() + 1..].to_owned();
+                    self.set(&section, &key, value);
+                }
+                None => ignored.push(name),
+            }
+        }
+        ignored
+    }
+
+    /// Creates an empty configuration.
+    pub fn empty() -> Self {
+        Self {
+            globals: HashMap::new(),
+            sections: HashMap::new(),
+        }
+    }
+
+    /// Gets a value from a section.
+    #[must_use]
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .get(section)
+            .and_then(|sec| sec.get(key))
+            .map(|s| s.as_str())
+    }
+
+    /// Sets a value in a section, replacing any previous one.
+    pub fn set(&mut self, section: &str, key: &str, value: impl Into<String>) {
+        self.sections
+            .entry(section.to_owned())
+            .or_default()
+            .insert(key.to_owned(), value.into());
+    }
+
+    /// Gets a non-empty value from a section.
+    #[must_use]
+    pub fn get_non_empty(&self, section: &str, key: &str) -> Option<&str> {
+        self.get(section, key).filter(|s| !s.is_empty())
+    }
+
+    /// Gets a global value.
+    #[must_use]
+    pub fn get_global(&self, key: &str) -> Option<&str> {
+        self.globals.get(key).map(|s| s.as_str())
+    }
+
+    /// Gets a value from a section or a global value, or a default value.
+    #[must_use]
+    pub fn get_or_default<'a>(&'a self, section: &str, key: &str, default: &'a str) -> &'a str {
+        self.get(section, key)
+            .or_else(|| self.get_global(key))
+            .unwrap_or(default)
+    }
+
+    /// Gets a non-empty value from a section or a global value, or a default value.
+    #[must_use]
+    pub fn get_non_empty_or_default<'a>(
+        &'a self,
+        section: &str,
+        key: &str,
+        default: &'a str,
+    ) -> &'a str {
+        self.get_non_empty(section, key)
+            .or_else(|| self.get_global(key).filter(|s| !s.is_empty()))
+            .unwrap_or(default)
+    }
+}
+
+/// A TOML scalar as the text `get` returns: strings without quotes.
+fn toml_to_string(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn written_defaults_load_back() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-{}.toml", std::process::id()));
+        Config::write_default(&path).unwrap();
+        let config = Config::load(path.to_str().unwrap());
+        let _ = fs::remove_file(&path);
+        let config = config.unwrap();
+
+        assert_eq!(config.get_global("log_level"), Some("Info"));
+        assert_eq!(config.get("Media", "fps"), Some("30"));
+        assert_eq!(config.get("Media", "push_to_talk"), Some("false"));
+        assert_eq!(config.get("ICE", "consent_timeout_ms"), Some("5000"));
+        assert_eq!(config.get("Shortcuts", "mute"), Some("Ctrl+M"));
+    }
+
+    #[test]
+    fn invalid_toml_lists_every_problem() {
+        let err = Config::from_toml(
+            "log_level = \"Loud\"\n\
+             [Media]\nfps = 0\nmin_bitrate = 2000000\nmax_bitrate = 1000000\n\
+             h264_level = \"6\"\nb_frames = 2\n\
+             [ICE]\nconsent_keepalive_ms = 1000\nconsent_timeout_ms = 500\n",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("log_level = Loud"), "{err}");
+        assert!(err.contains("[Media] fps = 0"), "{err}");
+        assert!(err.contains("[Media] min_bitrate"), "{err}");
+        assert!(err.contains("[Media] h264_level = 6"), "{err}");
+        assert!(err.contains("[Media] b_frames = 2"), "{err}");
+        assert!(err.contains("[ICE] consent_timeout_ms"), "{err}");
+
+        let err = Config::from_toml("[Media]\nfsp = 30\n").unwrap_err();
+        assert!(matches!(err, ConfigError::Parse(_)));
+        assert!(err.to_string().contains("fsp"), "{err}");
+        assert!(Config::from_toml("[Media]\nfps = \"thirty\"\n").is_err());
+
+        let config = Config::from_toml("[UI]\ntheme = \"dark\"\n").unwrap();
+        assert_eq!(config.get("UI", "theme"), Some("dark"));
+    }
+
+    #[test]
+    fn layers_apply_in_precedence_order() {
+        let mut config = Config::empty();
+        config.set("Signaling", "server_address", "10.0.0.1:7000");
+        config.set("Media", "fps", "30");
+
+        let ignored = config.apply_env_vars([
+            (
+                "ROOMRTC_SIGNALING_SERVER_ADDRESS".into(),
+                "10.0.0.2:7000".into(),
+            ),
+            (
+                "ROOMRTC_FILE_HANDLER_STORAGE_PATH".into(),
+                "/tmp/files".into(),
+            ),
+            ("ROOMRTC_LOG_LEVEL".into(), "debug".into()),
+            ("ROOMRTC_CONFIG".into(), "other.conf".into()),
+            ("ROOMRTC_NOPE_KEY".into(), "x".into()),
+            ("HOME".into(), "/home/alice".into()),
+        ]);
+        assert_eq!(ignored, ["ROOMRTC_NOPE_KEY"]);
+        assert_eq!(
+            config.get("Signaling", "server_address"),
+            Some("10.0.0.2:7000")
+        );
+        assert_eq!(
+            config.get("file_handler", "storage_path"),
+            Some("/tmp/files")
+        );
+        assert_eq!(config.get_global("log_level"), Some("debug"));
+        assert_eq!(config.get("Media", "fps"), Some("30"));
+
+        let _ = config.apply_layers(
+            &Settings::in_memory(),
+            &[
+                Override::new("Signaling", "server_address", "10.0.0.3:7000"),
+                Override::global("log_level", "warn"),
+            ],
+        );
+        assert_eq!(
+            config.get("Signaling", "server_address"),
+            Some("10.0.0.3:7000")
+        );
+        assert_eq!(config.get_global("log_level"), Some("warn"));
+    }
+}
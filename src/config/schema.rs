@@ -0,0 +1,607 @@
+//! Typed schema of the TOML configuration.
+//!
+//! The `Signaling`, `Media`, `ICE` and `Logging` tables are checked key by
+//! key: an unknown key, a value of the wrong type or one out of range is an
+//! error at startup instead of being silently ignored later. Other tables
+//! (`TLS`, `Shortcuts`, `UI`, ...) are read as plain key-value sections.
+//! Every key is optional; a missing one keeps the built-in default.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera_manager::capture_settings::PixelFormat,
+    core::{
+        path_mtu::{DEFAULT_MTU, MAX_MTU, MIN_MTU},
+        quality::QualityPreset,
+    },
+    ice::interface_preference::InterfaceKind,
+    log::log_level::LogLevel,
+    media_agent::{
+        encoder_tunables::{H264Level, H264Profile},
+        hw_codec::HwBackend,
+        watermark::Corner,
+    },
+    signaling_client::transport::TransportKind,
+};
+
+/// The whole configuration file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(rename = "Signaling", default)]
+    pub signaling: SignalingSection,
+    #[serde(rename = "Media", default)]
+    pub media: MediaSection,
+    #[serde(rename = "ICE", default)]
+    pub ice: IceSection,
+    #[serde(rename = "Logging", default)]
+    pub logging: LoggingSection,
+    /// Tables without a schema, and any unknown top-level key.
+    #[serde(flatten)]
+    pub other: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignalingSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_log_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_command_timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward_policy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contacts_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_devices_per_user: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_label: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MediaSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture_height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_low_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_medium_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_high_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_data_mode: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_data_bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_data_fps: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bitrate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe_interval: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub h264_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub h264_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub b_frames: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tune: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slice_max_bytes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyframe_on_join: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intra_refresh_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hw_codec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recording_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark_position: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_camera: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_input: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_sample_rate: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_channels: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vad_threshold_db: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_to_talk: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_to_talk_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub video_source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_tone_hz: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e2ee_key: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IceSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stun_server: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stun_request_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_candidate_pairs: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_keepalive_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consent_timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ice_restart_after_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ice_restart_grace_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface_preference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_interfaces: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu_discovery: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_log_filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_log_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_log_filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_log_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotate_daily: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compress: Option<bool>,
+}
+
+impl ConfigFile {
+    /// The client defaults, as shipped in `client_default.conf`.
+    #[must_use]
+    pub fn client_default() -> Self {
+        let table = |pairs: &[(&str, &str)]| {
+            toml::Value::Table(
+                pairs
+                    .iter()
+                    .map(|(k, v)| ((*k).to_owned(), toml::Value::String((*v).to_owned())))
+                    .collect(),
+            )
+        };
+        let other = BTreeMap::from([
+            (
+                "TLS".to_owned(),
+                table(&[
+                    ("signaling_cert", "certs/signaling/cert.pem"),
+                    ("signaling_key", "certs/signaling/key.pem"),
+                    ("dtls_cert", "certs/dtls/cert.pem"),
+                    ("dtls_key", "certs/dtls/key.pem"),
+                ]),
+            ),
+            (
+                "Shortcuts".to_owned(),
+                table(&[
+                    ("mute", "Ctrl+M"),
+                    ("video", "Ctrl+E"),
+                    ("hang_up", "Ctrl+Shift+H"),
+                    ("accept", "Ctrl+Shift+A"),
+                    ("help", "F1"),
+                ]),
+            ),
+        ]);
+        Self {
+            log_level: Some("Info".into()),
+            signaling: SignalingSection {
+                server_address: Some("192.168.0.12:7000".into()),
+                tls_domain: Some("signal.internal".into()),
+                transport: Some("tls".into()),
+                ..SignalingSection::default()
+            },
+            media: MediaSection {
+                fps: Some(30),
+                min_bitrate: Some(500_000),
+                max_bitrate: Some(1_500_000),
+                quality_low_bitrate: Some(300_000),
+                quality_medium_bitrate: Some(800_000),
+                quality_high_bitrate: Some(1_500_000),
+                low_data_mode: Some(false),
+                low_data_bitrate: Some(300_000),
+                low_data_fps: Some(15),
+                bitrate: Some(1_500_000),
+                keyframe_interval: Some(90),
+                h264_profile: Some("baseline".into()),
+                h264_level: Some("3.1".into()),
+                b_frames: Some(0),
+                tune: Some("zerolatency".into()),
+                slice_max_bytes: Some(1178),
+                keyframe_on_join: Some(true),
+                intra_refresh_ms: Some(0),
+                hw_codec: Some("auto".into()),
+                watermark_position: Some("bottom_right".into()),
+                default_camera: Some(0),
+                audio_sample_rate: Some(8000),
+                audio_channels: Some(1),
+                vad_threshold_db: Some(-45.0),
+                push_to_talk: Some(false),
+                push_to_talk_key: Some("Space".into()),
+                video_source: Some("camera".into()),
+                audio_source: Some("mic".into()),
+                test_tone_hz: Some(440),
+                ..MediaSection::default()
+            },
+            ice: IceSection {
+                stun_server: Some("stun.l.google.com:19302".into()),
+                stun_request_timeout_secs: Some(2),
+                max_candidate_pairs: Some(100),
+                consent_keepalive_ms: Some(1000),
+                consent_timeout_ms: Some(5000),
+                ice_restart_after_ms: Some(2000),
+                ice_restart_grace_ms: Some(30_000),
+                interface_preference: Some("wired, wifi, unknown, vpn, virtual".into()),
+                exclude_interfaces: Some("virtual".into()),
+                mtu: Some(DEFAULT_MTU),
+                mtu_discovery: Some(false),
+            },
+            logging: LoggingSection {
+                client_log_filename: Some("roomrtc".into()),
+                ..LoggingSection::default()
+            },
+            other,
+        }
+    }
+
+    /// Checks the values the types alone don't constrain. Returns one
+    /// message per problem, empty if the file is valid.
+    #[must_use]
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut fail = |section: &str, key: &str, value: &dyn std::fmt::Display, why: &str| {
+            let at = if section.is_empty() {
+                key.to_owned()
+            } else {
+                format!("[{section}] {key}")
+            };
+            errors.push(format!("{at} = {value}: {why}"));
+        };
+
+        if let Some(level) = &self.log_level
+            && level.parse::<LogLevel>().is_err()
+        {
+            fail(
+                "",
+                "log_level",
+                level,
+                "expected one of Trace, Debug, Info, Warn, Error",
+            );
+        }
+
+        let s = &self.signaling;
+        for (key, value) in [
+            ("server_address", &s.server_address),
+            ("listen_address", &s.listen_address),
+        ] {
+            if let Some(addr) = non_empty(value)
+                && !is_host_port(addr)
+            {
+                fail("Signaling", key, addr, "expected host:port");
+            }
+        }
+        if let Some(transport) = non_empty(&s.transport)
+            && transport.parse::<TransportKind>().is_err()
+        {
+            fail(
+                "Signaling",
+                "transport",
+                transport,
+                "expected \"tls\" or \"tcp\"",
+            );
+        }
+
+        let m = &self.media;
+        for (key, value) in [("fps", m.fps), ("low_data_fps", m.low_data_fps)] {
+            if let Some(fps) = value
+                && !(1..=120).contains(&fps)
+            {
+                fail("Media", key, &fps, "must be between 1 and 120");
+            }
+        }
+        match (m.capture_width, m.capture_height) {
+            (Some(0), _) => fail("Media", "capture_width", &0, "must be positive"),
+            (_, Some(0)) => fail("Media", "capture_height", &0, "must be positive"),
+            (Some(w), None) => fail("Media", "capture_width", &w, "capture_height is missing"),
+            (None, Some(h)) => fail("Media", "capture_height", &h, "capture_width is missing"),
+            _ => {}
+        }
+        if let Some(format) = non_empty(&m.pixel_format)
+            && format.parse::<PixelFormat>().is_err()
+        {
+            fail(
+                "Media",
+                "pixel_format",
+                format,
+                "expected \"mjpg\" or \"yuyv\"",
+            );
+        }
+        if let (Some(min), Some(max)) = (m.min_bitrate, m.max_bitrate)
+            && min > max
+        {
+            fail("Media", "min_bitrate", &min, "must not exceed max_bitrate");
+        }
+        for (key, value) in [
+            ("bitrate", m.bitrate),
+            ("max_bitrate", m.max_bitrate),
+            ("quality_low_bitrate", m.quality_low_bitrate),
+            ("quality_medium_bitrate", m.quality_medium_bitrate),
+            ("quality_high_bitrate", m.quality_high_bitrate),
+            ("low_data_bitrate", m.low_data_bitrate),
+            ("keyframe_interval", m.keyframe_interval),
+        ] {
+            if value == Some(0) {
+                fail("Media", key, &0, "must be positive");
+            }
+        }
+        let profile = non_empty(&m.h264_profile);
+        if let Some(value) = profile
+            && value.parse::<H264Profile>().is_err()
+        {
+            fail(
+                "Media",
+                "h264_profile",
+                value,
+                "expected \"baseline\", \"main\" or \"high\"",
+            );
+        }
+        if let Some(level) = non_empty(&m.h264_level)
+            && level.parse::<H264Level>().is_err()
+        {
+            fail(
+                "Media",
+                "h264_level",
+                level,
+                "expected a level from 1 to 5.2",
+            );
+        }
+        if let Some(b_frames) = m.b_frames.filter(|b| *b > 0)
+            && profile.and_then(|p| p.parse().ok()).unwrap_or_default() == H264Profile::Baseline
+        {
+            fail(
+                "Media",
+                "b_frames",
+                &b_frames,
+                "needs h264_profile \"main\" or \"high\"",
+            );
+        }
+        if let Some(tune) = non_empty(&m.tune)
+            && !["zerolatency", "none"].contains(&tune.trim().to_ascii_lowercase().as_str())
+        {
+            fail(
+                "Media",
+                "tune",
+                tune,
+                "expected \"zerolatency\" or \"none\"",
+            );
+        }
+        if let Some(position) = non_empty(&m.watermark_position)
+            && position.parse::<Corner>().is_err()
+        {
+            fail(
+                "Media",
+                "watermark_position",
+                position,
+                "expected \"top_left\", \"top_right\", \"bottom_left\" or \"bottom_right\"",
+            );
+        }
+        if let Some(quality) = non_empty(&m.quality)
+            && quality.parse::<QualityPreset>().is_err()
+        {
+            fail(
+                "Media",
+                "quality",
+                quality,
+                "expected \"low\", \"medium\" or \"high\"",
+            );
+        }
+        if let Some(hw) = non_empty(&m.hw_codec) {
+            let hw_lower = hw.trim().to_ascii_lowercase();
+            if !matches!(hw_lower.as_str(), "auto" | "off" | "none" | "software")
+                && hw.parse::<HwBackend>().is_err()
+            {
+                fail(
+                    "Media",
+                    "hw_codec",
+                    hw,
+                    "expected \"auto\", \"off\", \"vaapi\", \"nvenc\" or \"videotoolbox\"",
+                );
+            }
+        }
+        if let Some(rate) = m.audio_sample_rate
+            && !(8000..=48_000).contains(&rate)
+        {
+            fail(
+                "Media",
+                "audio_sample_rate",
+                &rate,
+                "must be between 8000 and 48000",
+            );
+        }
+        if let Some(channels) = m.audio_channels
+            && !(1..=2).contains(&channels)
+        {
+            fail("Media", "audio_channels", &channels, "must be 1 or 2");
+        }
+        for (key, value, default, expected) in [
+            (
+                "video_source",
+                &m.video_source,
+                "camera",
+                "expected \"camera\", \"test\" or an rtsp:// URL",
+            ),
+            (
+                "audio_source",
+                &m.audio_source,
+                "mic",
+                "expected \"mic\" or \"test\"",
+            ),
+        ] {
+            if let Some(source) = non_empty(value)
+                && !source.eq_ignore_ascii_case(default)
+                && !source.eq_ignore_ascii_case("test")
+                && !(key == "video_source" && source.starts_with("rtsp://"))
+            {
+                fail("Media", key, source, expected);
+            }
+        }
+
+        let i = &self.ice;
+        if let Some(stun) = non_empty(&i.stun_server)
+            && !is_host_port(stun)
+        {
+            fail("ICE", "stun_server", stun, "expected host:port");
+        }
+        for (key, value) in [
+            ("stun_request_timeout_secs", i.stun_request_timeout_secs),
+            ("consent_keepalive_ms", i.consent_keepalive_ms),
+            ("ice_restart_after_ms", i.ice_restart_after_ms),
+        ] {
+            if value == Some(0) {
+                fail("ICE", key, &0, "must be positive");
+            }
+        }
+        if i.max_candidate_pairs == Some(0) {
+            fail("ICE", "max_candidate_pairs", &0, "must be positive");
+        }
+        if let Some(mtu) = i.mtu
+            && !(MIN_MTU..=MAX_MTU).contains(&mtu)
+        {
+            fail(
+                "ICE",
+                "mtu",
+                &mtu,
+                &format!("must be between {MIN_MTU} and {MAX_MTU}"),
+            );
+        }
+        if let (Some(keepalive), Some(timeout)) = (i.consent_keepalive_ms, i.consent_timeout_ms)
+            && timeout <= keepalive
+        {
+            fail(
+                "ICE",
+                "consent_timeout_ms",
+                &timeout,
+                "must be longer than consent_keepalive_ms",
+            );
+        }
+        if let (Some(after), Some(grace)) = (i.ice_restart_after_ms, i.ice_restart_grace_ms)
+            && grace < after
+        {
+            fail(
+                "ICE",
+                "ice_restart_grace_ms",
+                &grace,
+                "must not be shorter than ice_restart_after_ms",
+            );
+        }
+        if let Some(order) = non_empty(&i.interface_preference) {
+            for kind in order.split(',').filter(|k| !k.trim().is_empty()) {
+                if kind.parse::<InterfaceKind>().is_err() {
+                    fail(
+                        "ICE",
+                        "interface_preference",
+                        kind.trim(),
+                        "expected wired, wifi, vpn, virtual or unknown",
+                    );
+                }
+            }
+        }
+
+        if let Some(format) = non_empty(&self.logging.format)
+            && !["text", "json"].contains(&format.trim().to_ascii_lowercase().as_str())
+        {
+            fail("Logging", "format", format, "expected \"text\" or \"json\"");
+        }
+
+        for (name, value) in &self.other {
+            match value {
+                toml::Value::Table(values) => {
+                    for (key, value) in values {
+                        if matches!(value, toml::Value::Table(_) | toml::Value::Array(_)) {
+                            fail(
+                                name,
+                                key,
+                                value,
+                                "nested tables and arrays are not supported",
+                            );
+                        }
+                    }
+                }
+                _ => fail("", name, value, "unknown key"),
+            }
+        }
+        errors
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|s| !s.trim().is_empty())
+}
+
+/// `host:port`, with a non-empty host and a valid port.
+fn is_host_port(addr: &str) -> bool {
+    addr.rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
@@ -0,0 +1,132 @@
+//! Reloads the configuration file when it changes on disk.
+//!
+//! A background thread checks the file's modification time and size every
+//! [`POLL_INTERVAL`] and reloads it when either changes. Each reload is
+//! passed through a `layer` callback (saved settings, environment,
+//! command-line flags; see [`Config::apply_layers`]) before it is handed
+//! over, so the overrides keep winning. A file that fails to load is
+//! reported and the previous configuration stays in use.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use super::{Config, ConfigError};
+
+/// How often the file is checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Modification time and size of the file, `None` if it cannot be read.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Watches one configuration file; stops when dropped.
+pub struct ConfigWatcher {
+    rx: Receiver<Result<Config, ConfigError>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+    /// Starts watching `path`, checking every [`POLL_INTERVAL`].
+    #[must_use]
+    pub fn spawn(path: impl Into<PathBuf>, layer: impl Fn(&mut Config) + Send + 'static) -> Self {
+        Self::spawn_with_interval(path, POLL_INTERVAL, layer)
+    }
+
+    /// Starts watching `path`, checking every `interval`.
+    #[must_use]
+    pub fn spawn_with_interval(
+        path: impl Into<PathBuf>,
+        interval: Duration,
+        layer: impl Fn(&mut Config) + Send + 'static,
+    ) -> Self {
+        let path = path.into();
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let _ = thread::Builder::new()
+            .name("config-watcher".into())
+            .spawn(move || {
+                let mut last = stamp(&path);
+                while !stop_flag.load(Ordering::Relaxed) {
+                    thread::sleep(interval);
+                    let current = stamp(&path);
+                    // A missing file (mid-save by some editors) is not a change
+                    if current.is_none() || current == last {
+                        continue;
+                    }
+                    last = current;
+                    let result = path
+                        .to_str()
+                        .ok_or_else(|| ConfigError::Io {
+                            path: path.clone(),
+                            source: io::Error::new(io::ErrorKind::InvalidInput, "not UTF-8"),
+                        })
+                        .and_then(Config::load)
+                        .map(|mut config| {
+                            layer(&mut config);
+                            config
+                        });
+                    if tx.send(result).is_err() {
+                        break;
+                    }
+                }
+            });
+        Self { rx, stop }
+    }
+
+    /// The latest reload since the last call, if any: the new
+    /// configuration, or why the file could not be loaded.
+    #[must_use]
+    pub fn try_recv(&self) -> Option<Result<Config, ConfigError>> {
+        self.rx.try_iter().last()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn reloads_after_the_file_changes() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-watch-{}.conf", std::process::id()));
+        fs::write(&path, "[Media]\nmax_bitrate = 1000\n").unwrap();
+        let watcher =
+            ConfigWatcher::spawn_with_interval(&path, Duration::from_millis(20), |config| {
+                config.set("UI", "fps", "30");
+            });
+        thread::sleep(Duration::from_millis(60));
+        assert!(watcher.try_recv().is_none());
+
+        fs::write(&path, "[Media]\nmax_bitrate = 250000\n").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let config = loop {
+            if let Some(result) = watcher.try_recv() {
+                break result.unwrap();
+            }
+            assert!(Instant::now() < deadline, "no reload");
+            thread::sleep(Duration::from_millis(20));
+        };
+        let _ = fs::remove_file(&path);
+        assert_eq!(config.get("Media", "max_bitrate"), Some("250000"));
+        assert_eq!(config.get("UI", "fps"), Some("30"));
+    }
+}
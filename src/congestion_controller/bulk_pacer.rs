@@ -0,0 +1,125 @@
+//! Pacing of bulk data (file transfer) against the media on the same link.
+//!
+//! Media and bulk are two priority classes sharing the congestion
+//! controller's estimate by weight: bulk is guaranteed `bulk_weight /
+//! MEDIA_WEIGHT` of the media bitrate and may grow beyond it while the link
+//! is clean, but it is the first to give way when loss or delay shows up.
+//! The drain thread asks the [`BulkPacer`] before each chunk.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Weight of the media class; the bulk weight is relative to it.
+pub const MEDIA_WEIGHT: u32 = 4;
+/// Default weight of the bulk class.
+pub const DEFAULT_BULK_WEIGHT: u32 = 1;
+/// Bulk never drops below this, so a transfer always makes progress.
+pub const MIN_BULK_BITRATE: u32 = 64_000;
+/// Bulk never grows past this.
+pub const MAX_BULK_BITRATE: u32 = 50_000_000;
+/// Tokens a pacer can save up while idle.
+const MAX_BURST: Duration = Duration::from_millis(200);
+/// Burst allowed however low the rate, so a chunk always fits.
+const MIN_BURST_BYTES: f64 = 32.0 * 1024.0;
+
+#[derive(Debug)]
+struct Bucket {
+    rate_bps: u32,
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl Bucket {
+    fn capacity(&self) -> f64 {
+        (f64::from(self.rate_bps) / 8.0 * MAX_BURST.as_secs_f64()).max(MIN_BURST_BYTES)
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(last) = self.last_refill {
+            let elapsed = now.saturating_duration_since(last).as_secs_f64();
+            self.tokens =
+                (self.tokens + f64::from(self.rate_bps) / 8.0 * elapsed).min(self.capacity());
+        }
+        self.last_refill = Some(now);
+    }
+}
+
+/// Token bucket the bulk sender draws from. Clones share the bucket.
+#[derive(Debug, Clone)]
+pub struct BulkPacer {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl BulkPacer {
+    #[must_use]
+    pub fn new(rate_bps: u32) -> Self {
+        let bucket = Bucket {
+            rate_bps,
+            tokens: 0.0,
+            last_refill: None,
+        };
+        let tokens = bucket.capacity();
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket { tokens, ..bucket })),
+        }
+    }
+
+    /// Changes the rate tokens arrive at; saved tokens above the new burst
+    /// are dropped.
+    pub fn set_rate(&self, rate_bps: u32) {
+        if let Ok(mut bucket) = self.bucket.lock() {
+            bucket.rate_bps = rate_bps;
+            bucket.tokens = bucket.tokens.min(bucket.capacity());
+        }
+    }
+
+    #[must_use]
+    pub fn rate(&self) -> u32 {
+        self.bucket.lock().map(|b| b.rate_bps).unwrap_or(0)
+    }
+
+    /// Takes `bytes` worth of tokens if there are enough.
+    pub fn try_take(&self, bytes: usize, now: Instant) -> bool {
+        let Ok(mut bucket) = self.bucket.lock() else {
+            return false;
+        };
+        bucket.refill(now);
+        let bytes = bytes as f64;
+        if bucket.tokens >= bytes {
+            bucket.tokens -= bytes;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The bulk class's share of a media bitrate.
+#[must_use]
+pub fn weighted_share(media_bps: u32, bulk_weight: u32) -> u32 {
+    let share = u64::from(media_bps) * u64::from(bulk_weight) / u64::from(MEDIA_WEIGHT);
+    u32::try_from(share)
+        .unwrap_or(u32::MAX)
+        .clamp(MIN_BULK_BITRATE, MAX_BULK_BITRATE)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn chunks_go_out_at_the_set_rate() {
+        let start = Instant::now();
+        // 32 KiB of burst, then 16 KiB every 100 ms
+        let pacer = BulkPacer::new(16 * 1024 * 8 * 10);
+        assert!(pacer.try_take(16 * 1024, start));
+        assert!(pacer.clone().try_take(16 * 1024, start));
+        assert!(!pacer.try_take(16 * 1024, start));
+        assert!(!pacer.try_take(16 * 1024, start + Duration::from_millis(50)));
+        assert!(pacer.try_take(16 * 1024, start + Duration::from_millis(110)));
+
+        assert_eq!(weighted_share(1_000_000, 1), 250_000);
+        assert_eq!(weighted_share(100_000, 1), MIN_BULK_BITRATE);
+    }
+}
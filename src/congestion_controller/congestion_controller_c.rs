@@ -1,7 +1,15 @@
+use super::bulk_pacer::{self, BulkPacer, DEFAULT_BULK_WEIGHT, MAX_BULK_BITRATE, MEDIA_WEIGHT};
 use super::constants::*;
 use crate::{
-    core::events::EngineEvent, log::log_sink::LogSink, rtcp::report_block::ReportBlock,
-    rtp_session::tx_tracker::TxTracker, sink_debug, sink_error, sink_warn,
+    core::{
+        clock::{self, SharedClock},
+        events::EngineEvent,
+        stats::{CongestionStats, now_unix_ms},
+    },
+    log::log_sink::LogSink,
+    rtcp::report_block::ReportBlock,
+    rtp_session::tx_tracker::TxTracker,
+    sink_debug, sink_error, sink_warn,
 };
 use std::{
     sync::{Arc, mpsc::Sender},
@@ -40,6 +48,7 @@ pub struct CongestionController {
     max_bitrate_bps: u32,
 
     last_update: Instant,
+    clock: SharedClock,
 
     loss_threshold: f32,
     rtt_threshold: Duration,
@@ -48,6 +57,14 @@ pub struct CongestionController {
     increase_factor: f64,
     decrease_factor: f64,
 
+    last_metrics: Option<NetworkMetrics>,
+
+    /// Whether a file transfer shares the link with the media.
+    bulk_active: bool,
+    bulk_weight: u32,
+    bulk_bitrate_bps: u32,
+    bulk_pacer: BulkPacer,
+
     logger: Arc<dyn LogSink>,
     tx_evt: Sender<EngineEvent>,
 }
@@ -61,6 +78,8 @@ impl CongestionController {
         logger: Arc<dyn LogSink>,
         tx_evt: Sender<EngineEvent>,
     ) -> Self {
+        let min_bitrate = min_bitrate.min(max_bitrate);
+        let initial_bitrate = initial_bitrate.clamp(min_bitrate, max_bitrate);
         if let Err(e) = tx_evt.send(EngineEvent::UpdateBitrate(initial_bitrate)) {
             sink_error!(
                 logger.as_ref(),
@@ -68,25 +87,40 @@ impl CongestionController {
                 e
             );
         }
+        let clock = clock::system();
+        let bulk_bitrate = bulk_pacer::weighted_share(initial_bitrate, DEFAULT_BULK_WEIGHT);
         Self {
             current_bitrate_bps: initial_bitrate,
             min_bitrate_bps: min_bitrate,
             max_bitrate_bps: max_bitrate,
-            last_update: Instant::now(),
+            last_update: clock.now(),
+            clock,
             loss_threshold: LOSS_THRESHOLD,
             rtt_threshold: Duration::from_millis(RTT_THRESHOLD_MILLIS),
             increase_interval: Duration::from_secs(INCREASE_INTERVAL),
             increase_factor: INCREASE_FACTOR,
             decrease_factor: DECREASE_FACTOR,
+            last_metrics: None,
+            bulk_active: false,
+            bulk_weight: DEFAULT_BULK_WEIGHT,
+            bulk_bitrate_bps: bulk_bitrate,
+            bulk_pacer: BulkPacer::new(bulk_bitrate),
             logger,
             tx_evt,
         }
     }
 
+    /// Times the bitrate increases with `clock` instead of the system clock.
+    pub fn set_clock(&mut self, clock: SharedClock) {
+        self.last_update = clock.now();
+        self.clock = clock;
+    }
+
     /// Updates the congestion controller with new network metrics.
     pub fn on_network_metrics(&mut self, metrics: NetworkMetrics) {
-        let now = Instant::now();
+        let now = self.clock.now();
         let mut new_bitrate = self.current_bitrate_bps;
+        self.last_metrics = Some(metrics.clone());
 
         let fraction_lost_float = metrics.fraction_lost as f32 / 255.0;
         sink_debug!(
@@ -101,8 +135,14 @@ impl CongestionController {
             metrics.round_trip_time.as_millis(),
         );
 
-        // If loss exceeds a threshold, drastically reduce bitrate.
-        if fraction_lost_float > self.loss_threshold {
+        let congested = fraction_lost_float > self.loss_threshold
+            || metrics.round_trip_time > self.rtt_threshold;
+
+        if congested && self.yield_bulk(now) {
+            // The file transfer gave way; the media keeps its bitrate.
+
+            // If loss exceeds a threshold, drastically reduce bitrate.
+        } else if fraction_lost_float > self.loss_threshold {
             new_bitrate = (new_bitrate as f64 * self.decrease_factor) as u32;
             sink_warn!(
                 self.logger.as_ref(),
@@ -128,12 +168,24 @@ impl CongestionController {
                 "[Congestion] Network stable, increasing bitrate to {} bps",
                 new_bitrate
             );
+            self.grow_bulk(now);
         }
 
         // Ensure the new bitrate is within limits
         new_bitrate = new_bitrate.clamp(self.min_bitrate_bps, self.max_bitrate_bps);
 
         if new_bitrate != self.current_bitrate_bps {
+            if self.bulk_active {
+                // Bulk keeps at least its share, and falls with the media
+                // once it is down to it.
+                let floor = bulk_pacer::weighted_share(new_bitrate, self.bulk_weight);
+                self.bulk_bitrate_bps = if new_bitrate < self.current_bitrate_bps {
+                    floor
+                } else {
+                    self.bulk_bitrate_bps.max(floor)
+                };
+                self.bulk_pacer.set_rate(self.bulk_bitrate_bps);
+            }
             self.current_bitrate_bps = new_bitrate;
             self.last_update = now;
 
@@ -147,4 +199,191 @@ impl CongestionController {
             }
         }
     }
+
+    /// Cuts the file transfer rate instead of the media's, if it is above
+    /// its weighted share. Returns whether it was.
+    fn yield_bulk(&mut self, now: Instant) -> bool {
+        let floor = bulk_pacer::weighted_share(self.current_bitrate_bps, self.bulk_weight);
+        if !self.bulk_active || self.bulk_bitrate_bps <= floor {
+            return false;
+        }
+        self.bulk_bitrate_bps =
+            ((self.bulk_bitrate_bps as f64 * BULK_DECREASE_FACTOR) as u32).max(floor);
+        self.bulk_pacer.set_rate(self.bulk_bitrate_bps);
+        self.last_update = now;
+        sink_warn!(
+            self.logger.as_ref(),
+            "[Congestion] Congestion during file transfer, file rate down to {} bps",
+            self.bulk_bitrate_bps
+        );
+        true
+    }
+
+    fn grow_bulk(&mut self, now: Instant) {
+        if !self.bulk_active {
+            return;
+        }
+        self.bulk_bitrate_bps =
+            ((self.bulk_bitrate_bps as f64 * BULK_INCREASE_FACTOR) as u32).min(MAX_BULK_BITRATE);
+        self.bulk_pacer.set_rate(self.bulk_bitrate_bps);
+        self.last_update = now;
+    }
+
+    /// Marks a file transfer as sharing the link, or done. A new transfer
+    /// starts at its weighted share of the media bitrate.
+    pub fn set_bulk_active(&mut self, active: bool) {
+        if active && !self.bulk_active {
+            self.bulk_bitrate_bps =
+                bulk_pacer::weighted_share(self.current_bitrate_bps, self.bulk_weight);
+            self.bulk_pacer.set_rate(self.bulk_bitrate_bps);
+            sink_debug!(
+                self.logger.as_ref(),
+                "[Congestion] File transfer started at {} bps",
+                self.bulk_bitrate_bps
+            );
+        }
+        self.bulk_active = active;
+    }
+
+    /// Sets the weight of file transfer against the media's
+    /// [`MEDIA_WEIGHT`], from 1 up to it.
+    pub fn set_bulk_weight(&mut self, weight: u32) {
+        self.bulk_weight = weight.clamp(1, MEDIA_WEIGHT);
+    }
+
+    /// The pacer file chunks are drawn through.
+    #[must_use]
+    pub fn bulk_pacer(&self) -> BulkPacer {
+        self.bulk_pacer.clone()
+    }
+
+    /// Caps the bitrate (e.g. for metered connections).
+    ///
+    /// The current bitrate is clamped right away and the encoder is told
+    /// if it changes. The minimum is lowered if it exceeds the new cap.
+    pub fn set_max_bitrate(&mut self, max_bitrate: u32) {
+        self.max_bitrate_bps = max_bitrate;
+        self.min_bitrate_bps = self.min_bitrate_bps.min(max_bitrate);
+        sink_debug!(
+            self.logger.as_ref(),
+            "[Congestion] Max bitrate set to {} bps",
+            max_bitrate
+        );
+
+        let clamped = self
+            .current_bitrate_bps
+            .clamp(self.min_bitrate_bps, self.max_bitrate_bps);
+        if clamped != self.current_bitrate_bps {
+            self.current_bitrate_bps = clamped;
+            self.last_update = self.clock.now();
+            if let Err(e) = self.tx_evt.send(EngineEvent::UpdateBitrate(clamped)) {
+                sink_error!(
+                    self.logger.as_ref(),
+                    "[Congestion] Failed to send UpdateBitrate event: {}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// The highest bitrate the controller may request.
+    #[must_use]
+    pub const fn max_bitrate(&self) -> u32 {
+        self.max_bitrate_bps
+    }
+
+    /// The bitrate currently requested from the encoder.
+    #[must_use]
+    pub const fn current_bitrate(&self) -> u32 {
+        self.current_bitrate_bps
+    }
+
+    /// Snapshot of the controller for `get_stats()`.
+    #[must_use]
+    pub fn stats(&self) -> CongestionStats {
+        CongestionStats {
+            id: "CongestionController".to_string(),
+            timestamp_ms: now_unix_ms(),
+            current_bitrate_bps: self.current_bitrate_bps,
+            min_bitrate_bps: self.min_bitrate_bps,
+            max_bitrate_bps: self.max_bitrate_bps,
+            bulk_bitrate_bps: self.bulk_active.then_some(self.bulk_bitrate_bps),
+            last_rtt_ms: self
+                .last_metrics
+                .as_ref()
+                .map(|m| m.round_trip_time.as_millis() as u64),
+            last_fraction_lost: self.last_metrics.as_ref().map(|m| m.fraction_lost),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::{core::clock::ManualClock, log::noop_log_sink::NoopLogSink};
+    use std::sync::mpsc;
+
+    fn metrics(rtt_ms: u64, fraction_lost: u8) -> NetworkMetrics {
+        NetworkMetrics {
+            round_trip_time: Duration::from_millis(rtt_ms),
+            fraction_lost,
+            packets_lost: 0,
+            highest_sequence_number: 0,
+        }
+    }
+
+    #[test]
+    fn the_bitrate_grows_once_per_interval_and_drops_on_loss() {
+        let clock = ManualClock::new();
+        let (tx, rx) = mpsc::channel();
+        let mut cc =
+            CongestionController::new(1_000_000, 100_000, 2_000_000, Arc::new(NoopLogSink), tx);
+        cc.set_clock(Arc::new(clock.clone()));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(EngineEvent::UpdateBitrate(1_000_000))
+        ));
+
+        cc.on_network_metrics(metrics(50, 0));
+        assert_eq!(cc.current_bitrate(), 1_000_000);
+
+        clock.advance(Duration::from_millis(1001));
+        cc.on_network_metrics(metrics(50, 0));
+        assert_eq!(cc.current_bitrate(), 1_100_000);
+        cc.on_network_metrics(metrics(50, 0));
+        assert_eq!(cc.current_bitrate(), 1_100_000);
+
+        cc.on_network_metrics(metrics(50, 64));
+        let decreased = (1_100_000.0 * DECREASE_FACTOR) as u32;
+        assert_eq!(cc.current_bitrate(), decreased);
+    }
+
+    #[test]
+    fn file_transfer_yields_before_the_media_does() {
+        let clock = ManualClock::new();
+        let (tx, _rx) = mpsc::channel();
+        let mut cc =
+            CongestionController::new(1_000_000, 100_000, 2_000_000, Arc::new(NoopLogSink), tx);
+        cc.set_clock(Arc::new(clock.clone()));
+        cc.set_bulk_active(true);
+        assert_eq!(cc.bulk_pacer().rate(), 250_000);
+
+        clock.advance(Duration::from_millis(1001));
+        cc.on_network_metrics(metrics(50, 0));
+        assert_eq!(cc.current_bitrate(), 1_100_000);
+        assert_eq!(cc.bulk_pacer().rate(), 312_500);
+
+        // The transfer backs off to its share, the media is untouched
+        cc.on_network_metrics(metrics(50, 64));
+        assert_eq!(cc.current_bitrate(), 1_100_000);
+        assert_eq!(cc.bulk_pacer().rate(), 275_000);
+
+        // Only then does the media back off, taking the transfer with it
+        cc.on_network_metrics(metrics(300, 0));
+        let decreased = (1_100_000.0 * DECREASE_FACTOR) as u32;
+        assert_eq!(cc.current_bitrate(), decreased);
+        assert_eq!(cc.bulk_pacer().rate(), decreased / 4);
+        assert_eq!(cc.stats().bulk_bitrate_bps, Some(decreased / 4));
+    }
 }
@@ -8,3 +8,7 @@ pub const INCREASE_INTERVAL: u64 = 1;
 pub const INCREASE_FACTOR: f64 = 1.1;
 /// The factor by which to decrease bitrate.
 pub const DECREASE_FACTOR: f64 = 0.85;
+/// The factor by which the bulk (file transfer) rate grows on a stable network.
+pub const BULK_INCREASE_FACTOR: f64 = 1.25;
+/// The factor by which the bulk rate drops when it yields to media.
+pub const BULK_DECREASE_FACTOR: f64 = 0.5;
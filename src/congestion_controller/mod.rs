@@ -1,4 +1,6 @@
 //! A simple congestion controller that adjusts bitrate based on packet loss and RTT.
+pub mod bulk_pacer;
 pub mod congestion_controller_c;
+pub use bulk_pacer::BulkPacer;
 pub use congestion_controller_c::{CongestionController, NetworkMetrics};
 mod constants;
@@ -1,6 +1,7 @@
 use super::{
     connection_error::ConnectionError, ice_and_sdp::ICEAndSDP, ice_phase::IcePhase,
-    outbound_sdp::OutboundSdp, rtp_map::RtpMap, signaling_state::SignalingState,
+    media_direction::MediaDirection, outbound_sdp::OutboundSdp, rtp_map::PayloadTypeMap,
+    sdp_track::SdpTrack, signaling_state::SignalingState,
 };
 use crate::config::Config;
 use crate::connection_manager::config::{
@@ -8,11 +9,16 @@ use crate::connection_manager::config::{
     DEFAULT_PROTO,
 };
 use crate::connection_manager::ice_worker::IceWorker;
+use crate::core::rtc_event_log::RtcEventLog;
+use crate::core::timers::Timers;
+use crate::dtls::DtlsRole;
 use crate::ice::gathering_service;
 use crate::ice::type_ice::ice_agent::{IceAgent, IceRole};
 use crate::log::log_sink::LogSink;
 use crate::media_agent::spec::MediaType;
+use crate::media_agent::video_track::TrackId;
 use crate::media_transport::codec::CodecDescriptor;
+use crate::rtp::audio_level::{AUDIO_LEVEL_EXT_ID, AUDIO_LEVEL_URI};
 use crate::rtp_session::rtp_codec::RtpCodec;
 use crate::sdp::attribute::Attribute as SDPAttribute;
 use crate::sdp::connection::Connection as SDPConnection;
@@ -23,8 +29,7 @@ use crate::sdp::port_spec::PortSpec as SDPPortSpec;
 use crate::sdp::sdpc::Sdp;
 use crate::sdp::time_desc::TimeDesc as SDPTimeDesc;
 use crate::tls_utils::get_local_fingerprint_sha256;
-use crate::{sink_error, sink_info};
-use std::collections::HashSet;
+use crate::{sink_error, sink_info, sink_warn};
 use std::{
     io::ErrorKind,
     net::UdpSocket,
@@ -64,6 +69,22 @@ pub struct ConnectionManager {
     /// The SHA-256 fingerprint of our DTLS certificate
     local_fingerprint: String,
     pub remote_fingerprint: Option<String>,
+    /// Direction we want (`Inactive` while on hold)
+    local_direction: MediaDirection,
+    /// Direction the remote asked for in its last offer
+    remote_direction: MediaDirection,
+    /// Our direction as agreed by the last completed offer/answer
+    negotiated_direction: MediaDirection,
+    /// Extra video tracks we announce, with their SSRC
+    local_tracks: Vec<(TrackId, u32)>,
+    /// Extra video tracks announced by the remote
+    remote_tracks: Vec<SdpTrack>,
+    /// Capture of the ICE checks, if enabled
+    event_log: Option<RtcEventLog>,
+    /// Our DTLS role as agreed by `a=setup`, once known
+    dtls_role: Option<DtlsRole>,
+    /// Where the ICE worker runs
+    timers: Timers,
 }
 
 impl ConnectionManager {
@@ -91,9 +112,27 @@ impl ConnectionManager {
             ice_worker: None,
             local_fingerprint,
             remote_fingerprint: None,
+            local_direction: MediaDirection::SendRecv,
+            remote_direction: MediaDirection::SendRecv,
+            negotiated_direction: MediaDirection::SendRecv,
+            local_tracks: Vec::new(),
+            remote_tracks: Vec::new(),
+            event_log: None,
+            dtls_role: None,
+            timers: Timers::shared().clone(),
         }
     }
 
+    /// Records the ICE checks of the next ICE run to `event_log`.
+    pub fn set_event_log(&mut self, event_log: Option<RtcEventLog>) {
+        self.event_log = event_log;
+    }
+
+    /// Runs the next ICE worker on `timers` instead of the shared timer thread.
+    pub fn set_timers(&mut self, timers: Timers) {
+        self.timers = timers;
+    }
+
     /// Initiates a new SDP negotiation as an **offerer**.
     ///
     /// Returns an SDP `Offer` to be sent to the remote peer.
@@ -113,7 +152,9 @@ impl ConnectionManager {
                 );
                 self.local_description = Some(offer.clone());
                 self.signaling = SignalingState::HaveLocalOffer;
-                self.set_ice_role_from_signaling(true, false);
+                if !self.is_renegotiation() {
+                    self.set_ice_role_from_signaling(true, false);
+                }
                 Ok(OutboundSdp::Offer(offer))
             }
             SignalingState::HaveLocalOffer => Ok(OutboundSdp::None),
@@ -131,6 +172,10 @@ impl ConnectionManager {
     /// - `HaveLocalOffer` → treat as **Answer** → store and return None
     /// - `HaveRemoteOffer` → error
     ///
+    /// Once ICE has started this is a renegotiation (e.g. hold/resume): the
+    /// ICE credentials, candidates and roles are left untouched and only the
+    /// media description is updated.
+    ///
     /// # Errors
     ///
     /// - If SDP parsing fails
@@ -140,10 +185,19 @@ impl ConnectionManager {
         let sdp = Sdp::parse(remote).map_err(ConnectionError::Sdp)?;
         let out = match self.signaling {
             SignalingState::Stable => {
-                let (remote_is_ice_lite, _ufrag, _pwd) =
-                    self.extract_and_store_remote_ice_meta(&sdp)?;
+                let renegotiation = self.is_renegotiation();
+                let remote_is_ice_lite = if renegotiation {
+                    false
+                } else {
+                    self.extract_and_store_remote_ice_meta(&sdp)?.0
+                };
                 self.extract_and_store_rtp_meta(&sdp)?;
                 self.extract_and_store_fingerprint(&sdp)?;
+                if !renegotiation {
+                    self.dtls_role = dtls_role_for_setup(sdp_attr(&sdp, "setup"), true);
+                }
+                self.remote_direction = remote_media_direction(&sdp);
+                self.remote_tracks = SdpTrack::from_sdp(&sdp);
                 self.remote_description = Some(sdp);
                 self.signaling = SignalingState::HaveRemoteOffer;
 
@@ -154,7 +208,10 @@ impl ConnectionManager {
                     answer.encode()
                 );
                 self.local_description = Some(answer.clone());
-                self.set_ice_role_from_signaling(false, remote_is_ice_lite);
+                self.negotiated_direction = self.local_direction.answer_to(self.remote_direction);
+                if !renegotiation {
+                    self.set_ice_role_from_signaling(false, remote_is_ice_lite);
+                }
 
                 self.signaling = SignalingState::Stable;
                 Ok(OutboundSdp::Answer(answer))
@@ -164,10 +221,15 @@ impl ConnectionManager {
                     self.rollback_to_stable();
                     return self.apply_remote_sdp(remote);
                 }
-                let (_remote_is_ice_lite, _ufrag, _pwd) =
+                if !self.is_renegotiation() {
                     self.extract_and_store_remote_ice_meta(&sdp)?;
+                    self.dtls_role = dtls_role_for_setup(sdp_attr(&sdp, "setup"), false);
+                }
                 self.extract_and_store_rtp_meta(&sdp)?;
                 self.extract_and_store_fingerprint(&sdp)?;
+                self.negotiated_direction =
+                    self.local_direction.answer_to(remote_media_direction(&sdp));
+                self.remote_tracks = SdpTrack::from_sdp(&sdp);
                 self.remote_description = Some(sdp);
                 self.signaling = SignalingState::Stable;
                 Ok(OutboundSdp::None)
@@ -191,51 +253,76 @@ impl ConnectionManager {
         self.local_codecs = codecs;
     }
 
-    /// Extracts RTP payload types and parameters from a remote SDP and stores them internally.
-    ///
-    /// # Errors
-    ///
-    /// - Returns `ConnectionError::RtpMap` if the rtpmap attribute cannot be parsed.
-    pub fn extract_and_store_rtp_meta(&mut self, remote_sdp: &Sdp) -> Result<(), ConnectionError> {
-        let mut discovered: Vec<RtpCodec> = Vec::new();
+    /// Sets the direction advertised in the next offer or answer.
+    pub const fn set_local_direction(&mut self, direction: MediaDirection) {
+        self.local_direction = direction;
+    }
 
-        for m in remote_sdp.media() {
-            if !m.proto().to_uppercase().contains("RTP") {
-                continue;
-            }
+    /// The direction we currently want.
+    #[must_use]
+    pub const fn local_direction(&self) -> MediaDirection {
+        self.local_direction
+    }
 
-            let allowed_pts: HashSet<u8> = m
-                .fmts()
-                .iter()
-                .filter_map(|fmt| fmt.parse::<u8>().ok())
-                .collect();
+    /// The direction the remote asked for in its last offer.
+    #[must_use]
+    pub const fn remote_direction(&self) -> MediaDirection {
+        self.remote_direction
+    }
 
-            for a in m.attrs() {
-                if a.key() != "rtpmap" {
-                    continue;
-                }
-                let raw = a
-                    .value()
-                    .ok_or_else(|| ConnectionError::RtpMap("Wrong value".into()))?;
-                let rm: RtpMap = raw
-                    .parse()
-                    .map_err(|_| ConnectionError::RtpMap("Failed parsing rtpmap".into()))?;
-                if !allowed_pts.is_empty() && !allowed_pts.contains(&rm.payload_type) {
-                    continue;
-                }
+    /// Our direction as agreed by the last completed offer/answer exchange.
+    #[must_use]
+    pub const fn negotiated_direction(&self) -> MediaDirection {
+        self.negotiated_direction
+    }
 
-                discovered.push(RtpCodec::with_name(
-                    rm.payload_type,
-                    rm.clock_rate,
-                    rm.encoding_name.clone(),
-                ));
-            }
+    /// Announces an extra video track in the next offer or answer.
+    pub fn add_local_track(&mut self, id: TrackId, ssrc: u32) {
+        self.local_tracks.retain(|(t, _)| *t != id);
+        self.local_tracks.push((id, ssrc));
+    }
+
+    /// Stops announcing a track. Returns its SSRC if it was announced.
+    pub fn remove_local_track(&mut self, id: TrackId) -> Option<u32> {
+        let pos = self.local_tracks.iter().position(|(t, _)| *t == id)?;
+        Some(self.local_tracks.remove(pos).1)
+    }
+
+    /// Extra video tracks announced by the remote in its last description.
+    #[must_use]
+    pub fn remote_tracks(&self) -> &[SdpTrack] {
+        &self.remote_tracks
+    }
+
+    /// Our DTLS role: the one agreed by `a=setup` (the `active` side is the
+    /// client), or else the one implied by the ICE role.
+    #[must_use]
+    pub const fn dtls_role(&self) -> DtlsRole {
+        match self.dtls_role {
+            Some(role) => role,
+            None => match self.ice_agent.role {
+                IceRole::Controlling => DtlsRole::Server,
+                IceRole::Controlled => DtlsRole::Client,
+            },
         }
+    }
 
-        discovered.sort_by_key(|c| c.payload_type);
-        discovered.dedup_by_key(|c| c.payload_type);
+    /// `true` once ICE has started, i.e. further SDP exchanges only update
+    /// the media description.
+    #[must_use]
+    pub fn is_renegotiation(&self) -> bool {
+        self.ice_phase != IcePhase::Idle
+    }
 
-        self.remote_codecs = discovered;
+    /// Extracts RTP payload types and parameters from a remote SDP and stores them internally.
+    ///
+    /// # Errors
+    ///
+    /// - Returns `ConnectionError::RtpMap` if the rtpmap attribute cannot be parsed.
+    pub fn extract_and_store_rtp_meta(&mut self, remote_sdp: &Sdp) -> Result<(), ConnectionError> {
+        let payload_types = PayloadTypeMap::from_sdp(remote_sdp)
+            .map_err(|e| ConnectionError::RtpMap(format!("Failed parsing rtpmap: {e}")))?;
+        self.remote_codecs = payload_types.codecs();
         Ok(())
     }
 
@@ -263,44 +350,119 @@ impl ConnectionManager {
     // ----------------- Internal helpers -----------------
 
     /// Constructs a local SDP description (offer or answer) based on current local codecs and ICE info.
+    ///
+    /// An answer mirrors the m-lines of the offer, in order and with their
+    /// `mid`s, rejecting (port 0) those we cannot handle, such as data
+    /// channels. All accepted m-lines are bundled on one transport.
     fn build_local_sdp(&mut self) -> Sdp {
         // Gather candidates once to avoid duplication side-effects
         let candidates_attrs = get_local_candidates_as_attributes(self);
 
-        // Group codecs by MediaType
-        let mut audio_codecs = Vec::new();
-        let mut video_codecs = Vec::new();
-
-        for c in &self.local_codecs {
-            match c.spec.media_type() {
-                MediaType::Audio => audio_codecs.push(c.clone()),
-                MediaType::Video => video_codecs.push(c.clone()),
+        let offer = if matches!(self.signaling, SignalingState::HaveRemoteOffer) {
+            self.remote_description.clone()
+        } else {
+            None
+        };
+        let mut media = Vec::new();
+        let mut bundle = Vec::new();
+
+        if let Some(offer) = offer {
+            let mut answered = Vec::new();
+            for (idx, m) in offer.media().iter().enumerate() {
+                let mid = m
+                    .attrs()
+                    .iter()
+                    .find(|a| a.key() == "mid")
+                    .and_then(SDPAttribute::value)
+                    .map_or_else(|| idx.to_string(), ToOwned::to_owned);
+                let media_type = match m.kind() {
+                    MediaKind::Audio => Some(MediaType::Audio),
+                    MediaKind::Video => Some(MediaType::Video),
+                    _ => None,
+                }
+                .filter(|t| !answered.contains(t))
+                .filter(|_| m.port().base() != 0 && m.proto().to_uppercase().contains("RTP"));
+                let codecs = media_type
+                    .map(|t| self.answer_codecs(t, m))
+                    .unwrap_or_default();
+                match media_type {
+                    Some(t) if !codecs.is_empty() => {
+                        answered.push(t);
+                        media.push(self.build_media_description(
+                            t,
+                            &codecs,
+                            &candidates_attrs,
+                            &mid,
+                        ));
+                        bundle.push(mid);
+                    }
+                    _ => {
+                        sink_info!(
+                            &self.logger_handle,
+                            "Rejecting offered m-line {} ({} {})",
+                            mid,
+                            m.kind(),
+                            m.proto()
+                        );
+                        media.push(rejected_media_description(m, &mid));
+                    }
+                }
+            }
+        } else {
+            // Group codecs by MediaType
+            let mut audio_codecs = Vec::new();
+            let mut video_codecs = Vec::new();
+
+            for c in &self.local_codecs {
+                match c.spec.media_type() {
+                    MediaType::Audio => audio_codecs.push(c.clone()),
+                    MediaType::Video => video_codecs.push(c.clone()),
+                }
             }
-        }
 
-        let mut media = Vec::new();
+            // Add Audio m-line if present
+            if !audio_codecs.is_empty() {
+                let mid = media.len().to_string();
+                media.push(self.build_media_description(
+                    MediaType::Audio,
+                    &audio_codecs,
+                    &candidates_attrs,
+                    &mid,
+                ));
+                bundle.push(mid);
+            }
 
-        // Add Audio m-line if present
-        if !audio_codecs.is_empty() {
-            media.push(self.build_media_description(
-                MediaType::Audio,
-                &audio_codecs,
-                &candidates_attrs,
-            ));
+            // Add Video m-line if present
+            if !video_codecs.is_empty() {
+                let mid = media.len().to_string();
+                media.push(self.build_media_description(
+                    MediaType::Video,
+                    &video_codecs,
+                    &candidates_attrs,
+                    &mid,
+                ));
+                bundle.push(mid);
+            }
         }
 
-        // Add Video m-line if present
-        if !video_codecs.is_empty() {
+        // Fallback: if no codecs found (e.g. init), default to Video
+        if media.is_empty() {
+            let mid = "0".to_owned();
             media.push(self.build_media_description(
                 MediaType::Video,
-                &video_codecs,
+                &[],
                 &candidates_attrs,
+                &mid,
             ));
+            bundle.push(mid);
         }
 
-        // Fallback: if no codecs found (e.g. init), default to Video
-        if media.is_empty() {
-            media.push(self.build_media_description(MediaType::Video, &[], &candidates_attrs));
+        let mut session_attrs = Vec::new();
+        if !bundle.is_empty() {
+            session_attrs.push(SDPAttribute::new(
+                "group",
+                Some(format!("BUNDLE {}", bundle.join(" "))),
+            ));
         }
 
         Sdp::new(
@@ -314,12 +476,46 @@ impl ConnectionManager {
             None,
             Vec::new(),
             vec![SDPTimeDesc::new_blank()],
-            Vec::new(),
+            session_attrs,
             media,
             Vec::new(),
         )
     }
 
+    /// Our codecs of `media_type` that the offered m-line `offered` also
+    /// lists, renumbered to the offer's payload types. A codec offered
+    /// under our own payload type at another rate (L16) is kept as is.
+    fn answer_codecs(&self, media_type: MediaType, offered: &SDPMedia) -> Vec<CodecDescriptor> {
+        let offered_codecs: Vec<RtpCodec> = self
+            .remote_codecs
+            .iter()
+            .filter(|c| offered.fmts().contains(&c.payload_type.to_string()))
+            .cloned()
+            .collect();
+        self.local_codecs
+            .iter()
+            .filter(|c| c.spec.media_type() == media_type)
+            .filter_map(|c| {
+                let local = &c.rtp_representation;
+                let payload_type = match local.find_in(&offered_codecs) {
+                    Some(remote) => remote.payload_type,
+                    None => {
+                        offered_codecs
+                            .iter()
+                            .find(|r| {
+                                r.payload_type == local.payload_type
+                                    && r.name.eq_ignore_ascii_case(&local.name)
+                            })?
+                            .payload_type
+                    }
+                };
+                let mut codec = c.clone();
+                codec.rtp_representation.payload_type = payload_type;
+                Some(codec)
+            })
+            .collect()
+    }
+
     /// Drops local offer and resets signaling state to `Stable`.
     fn rollback_to_stable(&mut self) {
         self.local_description = None;
@@ -355,9 +551,19 @@ impl ConnectionManager {
                 match a.key() {
                     "candidate" => {
                         let value = a.value().ok_or(ConnectionError::IceAgent)?;
-                        let ice_and_sdp: ICEAndSDP =
-                            value.parse().map_err(|_| ConnectionError::IceAgent)?;
-                        self.ice_agent.add_remote_candidate(ice_and_sdp.candidate());
+                        // Browsers also send mDNS (`<uuid>.local`) and TCP
+                        // candidates; skip what we cannot use
+                        match value.parse::<ICEAndSDP>() {
+                            Ok(ice_and_sdp) => {
+                                self.ice_agent.add_remote_candidate(ice_and_sdp.candidate());
+                            }
+                            Err(e) => sink_warn!(
+                                &self.logger_handle,
+                                "Skipping remote candidate '{}': {}",
+                                value,
+                                e
+                            ),
+                        }
                     }
                     "ice-ufrag" => {
                         if ufrag.is_none() {
@@ -387,6 +593,9 @@ impl ConnectionManager {
 
     /// Starts ICE candidate connectivity checks if both local and remote SDPs are present.
     fn maybe_start_ice(&mut self) -> Result<(), ConnectionError> {
+        if self.is_renegotiation() {
+            return Ok(());
+        }
         let ready = self.local_description.is_some()
             && self.remote_description.is_some()
             && matches!(self.signaling, SignalingState::Stable);
@@ -459,6 +668,9 @@ impl ConnectionManager {
             for sock in &sockets {
                 match sock.recv_from(&mut buf) {
                     Ok((n, from)) => {
+                        if let Some(log) = &self.event_log {
+                            log.ice_check(true, from, &buf[..n]);
+                        }
                         self.ice_agent.handle_incoming_packet(&buf[..n], from);
                     }
                     Err(ref e)
@@ -475,7 +687,11 @@ impl ConnectionManager {
         if self.ice_worker.is_some() {
             return;
         }
-        self.ice_worker = Some(IceWorker::spawn(&self.ice_agent));
+        self.ice_worker = Some(IceWorker::spawn(
+            &self.ice_agent,
+            self.event_log.clone(),
+            &self.timers,
+        ));
     }
 
     /// Stops the ICE worker and clears it.
@@ -500,6 +716,12 @@ impl ConnectionManager {
         }
     }
 
+    /// Returns the current ICE phase.
+    #[must_use]
+    pub const fn ice_phase(&self) -> IcePhase {
+        self.ice_phase
+    }
+
     #[must_use]
     /// Returns the currently discovered remote RTP codecs.
     pub const fn remote_codecs(&self) -> &Vec<RtpCodec> {
@@ -512,6 +734,7 @@ impl ConnectionManager {
         media_type: MediaType,
         codecs: &[CodecDescriptor],
         candidates: &[SDPAttribute],
+        mid: &str,
     ) -> SDPMedia {
         let mut media_desc = SDPMedia::new_blank();
         let kind = match media_type {
@@ -537,7 +760,7 @@ impl ConnectionManager {
             DEFAULT_CONN_ADDR,
         )));
 
-        let mut attrs = Vec::new();
+        let mut attrs = vec![SDPAttribute::new("mid", Some(mid.to_owned()))];
         // Add candidates
         attrs.extend_from_slice(candidates);
 
@@ -550,13 +773,14 @@ impl ConnectionManager {
             "fingerprint",
             Some(format!("sha-256 {}", self.local_fingerprint)),
         ));
-        // --- Indicar setup role para DTLS ---
-        if matches!(self.signaling, SignalingState::Stable) {
-            attrs.push(SDPAttribute::new("setup", Some("actpass".into())));
-        } else {
-            // Si estamos respondiendo (Answer), generalmente tomamos el rol opuesto.
-            attrs.push(SDPAttribute::new("setup", Some("active".into())));
-        }
+        // --- Indicar setup role para DTLS (RFC 8842) ---
+        let setup = match (self.signaling, self.dtls_role) {
+            (SignalingState::Stable, _) => "actpass",
+            // The offerer took `active`, or asked us to keep being the server
+            (_, Some(DtlsRole::Server)) => "passive",
+            _ => "active",
+        };
+        attrs.push(SDPAttribute::new("setup", Some(setup.into())));
 
         if codecs.is_empty() {
             // Default fallback if absolutely no codecs provided
@@ -575,7 +799,11 @@ impl ConnectionManager {
                 } else {
                     &codec.name
                 };
-                let value = format!("{} {}/{}", codec.payload_type, name, codec.clock_rate);
+                let mut value = format!("{} {}/{}", codec.payload_type, name, codec.clock_rate);
+                if codec.channels > 1 {
+                    // Encoding parameter: channel count, omitted for mono
+                    value = format!("{value}/{}", codec.channels);
+                }
                 attrs.push(SDPAttribute::new("rtpmap", Some(value)));
                 if let Some(fmtp) = &descriptor.sdp_fmtp {
                     attrs.push(SDPAttribute::new(
@@ -587,29 +815,56 @@ impl ConnectionManager {
         }
 
         attrs.push(SDPAttribute::new("rtcp-mux", None));
+        if matches!(media_type, MediaType::Audio) {
+            attrs.push(SDPAttribute::new(
+                "extmap",
+                Some(format!("{AUDIO_LEVEL_EXT_ID} {AUDIO_LEVEL_URI}")),
+            ));
+        }
+        if matches!(media_type, MediaType::Video) {
+            for (id, ssrc) in &self.local_tracks {
+                let track = SdpTrack::new(*ssrc, format!("track-{id}"));
+                attrs.push(SDPAttribute::new("ssrc", Some(track.attr_value())));
+            }
+        }
+        let direction = if matches!(self.signaling, SignalingState::HaveRemoteOffer) {
+            self.local_direction.answer_to(self.remote_direction)
+        } else {
+            self.local_direction
+        };
+        attrs.push(SDPAttribute::new(direction.as_str(), None));
         media_desc.set_attrs(attrs);
         media_desc
     }
 
+    /// Stores the remote SHA-256 certificate fingerprint, which Firefox
+    /// sends at session level and Chrome on every m-line.
     fn extract_and_store_fingerprint(&mut self, remote: &Sdp) -> Result<(), ConnectionError> {
-        for m in remote.media() {
-            for a in m.attrs() {
-                if a.key() == "fingerprint"
-                    && let Some(val) = a.value()
-                {
-                    let parts: Vec<&str> = val.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        // parts[0] is "sha-256", parts[1] is the hash
-                        self.remote_fingerprint = Some(parts[1].to_string());
-                        return Ok(());
-                    }
-                }
-            }
+        let fingerprint = remote
+            .media()
+            .iter()
+            .flat_map(|m| m.attrs().iter())
+            .chain(remote.attrs().iter())
+            .filter(|a| a.key() == "fingerprint")
+            .filter_map(SDPAttribute::value)
+            .find_map(|val| {
+                let (hash, fingerprint) = val.trim().split_once(' ')?;
+                hash.eq_ignore_ascii_case("sha-256")
+                    .then(|| fingerprint.trim().to_owned())
+            });
+        if let Some(fingerprint) = fingerprint {
+            self.remote_fingerprint = Some(fingerprint);
+        } else if sdp_attr(remote, "fingerprint").is_some() {
+            sink_warn!(
+                &self.logger_handle,
+                "Remote SDP has no sha-256 fingerprint, the only one we verify"
+            );
         }
         // It is valid to not find one immediately if the SDP is partial,
         // but for a full connection, it's eventually required.
         Ok(())
     }
+
     /// Resets the manager to a clean state, ready for a new call.
     /// This clears ICE state, signaling state, and stops the worker.
     pub fn reset(&mut self) {
@@ -632,6 +887,12 @@ impl ConnectionManager {
         self.remote_description = None;
         self.remote_codecs.clear();
         self.remote_fingerprint = None;
+        self.local_direction = MediaDirection::SendRecv;
+        self.remote_direction = MediaDirection::SendRecv;
+        self.negotiated_direction = MediaDirection::SendRecv;
+        self.local_tracks.clear();
+        self.remote_tracks.clear();
+        self.dtls_role = None;
 
         // We keep local_codecs, local_fingerprint, and logger_handle
         // as they are consistent across calls.
@@ -643,9 +904,71 @@ const fn is_probably_offer(_sdp: &Sdp) -> bool {
     false
 }
 
+/// Reads the media direction of an SDP: the first direction attribute found
+/// on an m-line, then at session level, defaulting to `sendrecv`.
+fn remote_media_direction(sdp: &Sdp) -> MediaDirection {
+    sdp.media()
+        .iter()
+        .flat_map(|m| m.attrs().iter())
+        .chain(sdp.attrs().iter())
+        .find_map(|a| a.key().parse::<MediaDirection>().ok())
+        .unwrap_or_default()
+}
+
+/// Value of the first `key` attribute on an m-line, then at session level.
+fn sdp_attr<'a>(sdp: &'a Sdp, key: &str) -> Option<&'a str> {
+    sdp.media()
+        .iter()
+        .flat_map(|m| m.attrs().iter())
+        .chain(sdp.attrs().iter())
+        .find(|a| a.key() == key)
+        .and_then(SDPAttribute::value)
+}
+
+/// Our DTLS role given the remote `a=setup` (RFC 8842). Answering, we take
+/// `active` unless the offerer did; an answer tells which side it took.
+fn dtls_role_for_setup(remote_setup: Option<&str>, remote_is_offer: bool) -> Option<DtlsRole> {
+    match remote_setup.map(str::trim) {
+        Some("active") => Some(DtlsRole::Server),
+        Some("passive") => Some(DtlsRole::Client),
+        _ if remote_is_offer => Some(DtlsRole::Client),
+        _ => None,
+    }
+}
+
+/// A rejected answer to the offered m-line `offered`: port 0, same media
+/// and formats.
+fn rejected_media_description(offered: &SDPMedia, mid: &str) -> SDPMedia {
+    let mut media_desc = SDPMedia::new_blank();
+    media_desc.set_kind(offered.kind().clone());
+    media_desc.set_port(SDPPortSpec::new(0, None));
+    media_desc.set_proto(offered.proto());
+    media_desc.set_fmts(offered.fmts().clone());
+    media_desc.set_connection(Some(SDPConnection::new(
+        DEFAULT_NET_TYPE,
+        DEFAULT_ADDR_TYPE,
+        DEFAULT_CONN_ADDR,
+    )));
+    media_desc.set_attrs(vec![SDPAttribute::new("mid", Some(mid.to_owned()))]);
+    media_desc
+}
+
 /// Collects local host ICE candidates and converts them into SDP attributes.
+///
+/// Candidates already known to the ICE agent are reused, so renegotiating
+/// does not open new sockets.
 fn get_local_candidates_as_attributes(conn_manager: &mut ConnectionManager) -> Vec<SDPAttribute> {
-    gathering_service::gather_host_candidates()
+    if !conn_manager.ice_agent.local_candidates.is_empty() {
+        return conn_manager
+            .ice_agent
+            .local_candidates
+            .iter()
+            .map(|c| SDPAttribute::new("candidate", ICEAndSDP::new(c.clone()).to_string()))
+            .collect();
+    }
+    let preference = conn_manager.ice_agent.interface_preference().clone();
+    let filter = conn_manager.ice_agent.interface_filter().clone();
+    gathering_service::gather_host_candidates(&preference, &filter, &conn_manager.logger_handle)
         .into_iter()
         .map(|c| {
             let ice_cand_to_sdp = ICEAndSDP::new(c);
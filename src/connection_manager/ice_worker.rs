@@ -1,105 +1,203 @@
 use std::{
-    net::SocketAddr,
+    net::{SocketAddr, UdpSocket},
     sync::{
-        Arc,
+        Arc, Mutex, PoisonError,
         atomic::{AtomicBool, Ordering},
-        mpsc::{self, Receiver},
+        mpsc::{self, Receiver, Sender},
     },
-    thread,
     time::{Duration, Instant},
 };
 
-use crate::ice::type_ice::ice_agent::{BINDING_REQUEST, IceAgent};
+use crate::{
+    core::{
+        clock::SharedClock, rtc_event_log::RtcEventLog, timers::Timers, transport::PacketTransport,
+    },
+    ice::type_ice::ice_agent::IceAgent,
+};
+
+/// How often the sockets are drained.
+const POLL_EVERY: Duration = Duration::from_millis(20);
+/// How often unanswered Binding requests are sent again.
+const RESEND_EVERY: Duration = Duration::from_millis(200);
 
-/// A worker that handles ICE connectivity checks in a background thread.
+/// Handles ICE connectivity checks in the background, on the session timers.
 pub struct IceWorker {
     run: Arc<AtomicBool>,
     rx: Receiver<(Vec<u8>, SocketAddr)>,
-    handle: Option<thread::JoinHandle<()>>,
+    checks: Arc<Mutex<CheckLoop>>,
+}
+
+/// What one run of the worker works on.
+struct CheckLoop {
+    sockets: Vec<Arc<dyn PacketTransport>>,
+    /// The pending checks, per socket index.
+    checks_per_sock: Vec<Vec<(SocketAddr, Vec<u8>)>>,
+    last_tx: Instant,
+    event_log: Option<RtcEventLog>,
+    tx: Sender<(Vec<u8>, SocketAddr)>,
 }
 
 impl IceWorker {
-    /// Spawns a new `IceWorker` thread.
+    /// Starts an `IceWorker` for `agent`'s sockets on `timers`, recording
+    /// the checks it receives and sends to `event_log`.
     #[must_use]
-    pub fn spawn(agent: &IceAgent) -> Self {
-        let run = Arc::new(AtomicBool::new(true));
-        let (tx, rx) = mpsc::channel();
-
+    pub fn spawn(agent: &IceAgent, event_log: Option<RtcEventLog>, timers: &Timers) -> Self {
         // Snapshot sockets
-        let sockets: Vec<Arc<std::net::UdpSocket>> = agent
+        let sockets: Vec<Arc<UdpSocket>> = agent
             .local_candidates
             .iter()
             .filter_map(|c| c.socket.clone())
             .collect();
+        for s in &sockets {
+            let _ = s.set_nonblocking(true);
+        }
+        let sockets = sockets
+            .into_iter()
+            .map(|s| s as Arc<dyn PacketTransport>)
+            .collect();
+        Self::start(sockets, agent.pending_checks(), event_log, timers)
+    }
+
+    /// Starts an `IceWorker` on `timers` that sends `checks` (local address,
+    /// remote address, Binding request) from the matching socket of
+    /// `sockets`, which must not block.
+    #[must_use]
+    pub fn start(
+        sockets: Vec<Arc<dyn PacketTransport>>,
+        checks: Vec<(SocketAddr, SocketAddr, Vec<u8>)>,
+        event_log: Option<RtcEventLog>,
+        timers: &Timers,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
 
-        // Snapshot send targets per socket index
-        let mut targets_per_sock: Vec<Vec<SocketAddr>> = vec![Vec::new(); sockets.len()];
-        for pair in &agent.candidate_pairs {
-            if let Some(ls) = &pair.local.socket
-                && let Some(idx) = sockets.iter().position(|s| Arc::ptr_eq(s, ls))
+        let mut checks_per_sock: Vec<Vec<(SocketAddr, Vec<u8>)>> = vec![Vec::new(); sockets.len()];
+        for (local, remote, packet) in checks {
+            if let Some(idx) = sockets
+                .iter()
+                .position(|s| s.local_addr().is_ok_and(|a| a == local))
             {
-                targets_per_sock[idx].push(pair.remote.address);
+                checks_per_sock[idx].push((remote, packet));
             }
         }
 
-        let run2 = Arc::clone(&run);
-        let handle = thread::spawn(move || {
-            let () = sockets.iter().for_each(|s| {
-                let _ = s.set_nonblocking(true);
-            });
-            let mut buf = [0u8; 1500];
-            let resend_every = Duration::from_millis(200);
-            let mut last_tx = Instant::now();
-
-            while run2.load(Ordering::SeqCst) {
-                // Drain inbound
-                for s in &sockets {
-                    loop {
-                        match s.recv_from(&mut buf) {
-                            Ok((n, from)) => {
-                                let _ = tx.send((buf[..n].to_vec(), from));
-                            }
-                            Err(ref e)
-                                if e.kind() == std::io::ErrorKind::WouldBlock
-                                    || e.kind() == std::io::ErrorKind::TimedOut =>
-                            {
-                                break;
-                            }
-                            Err(_) => break,
-                        }
-                    }
-                }
-                // Periodic re-send BINDING_REQUEST
-                if last_tx.elapsed() >= resend_every {
-                    for (i, s) in sockets.iter().enumerate() {
-                        for &dst in &targets_per_sock[i] {
-                            let _ = s.send_to(BINDING_REQUEST, dst);
-                        }
-                    }
-                    last_tx = Instant::now();
+        let clock = timers.clock();
+        let run = Arc::new(AtomicBool::new(true));
+        let checks = Arc::new(Mutex::new(CheckLoop {
+            sockets,
+            checks_per_sock,
+            // The agent has just sent them
+            last_tx: clock.now(),
+            event_log,
+            tx,
+        }));
+        timers.schedule(Duration::ZERO, {
+            let run = Arc::clone(&run);
+            let checks = Arc::clone(&checks);
+            move || {
+                let mut checks = checks.lock().unwrap_or_else(PoisonError::into_inner);
+                if !run.load(Ordering::SeqCst) {
+                    return None;
                 }
-                thread::sleep(Duration::from_millis(20));
+                checks.step(&clock);
+                Some(POLL_EVERY)
             }
         });
 
-        Self {
-            run,
-            rx,
-            handle: Some(handle),
-        }
+        Self { run, rx, checks }
     }
 
-    /// Tries to receive a packet from the worker thread without blocking.
+    /// Tries to receive a packet from the worker without blocking.
     #[must_use]
     pub fn try_recv(&self) -> Option<(Vec<u8>, SocketAddr)> {
         self.rx.try_recv().ok()
     }
 
-    /// Stops the worker thread.
+    /// Stops the worker; once this returns it no longer reads the sockets.
     pub fn stop(&mut self) {
         self.run.store(false, Ordering::SeqCst);
-        if let Some(h) = self.handle.take() {
-            let _ = h.join();
+        // Wait out a run in progress
+        drop(self.checks.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+}
+
+impl CheckLoop {
+    fn step(&mut self, clock: &SharedClock) {
+        let mut buf = [0u8; 1500];
+        // Drain inbound
+        for s in &self.sockets {
+            while let Ok((n, from)) = s.recv_from(&mut buf) {
+                if let Some(log) = &self.event_log {
+                    log.ice_check(true, from, &buf[..n]);
+                }
+                let _ = self.tx.send((buf[..n].to_vec(), from));
+            }
+        }
+        // Periodic re-send of the unanswered Binding requests
+        let now = clock.now();
+        if now.duration_since(self.last_tx) >= RESEND_EVERY {
+            for (s, checks) in self.sockets.iter().zip(&self.checks_per_sock) {
+                for (dst, packet) in checks {
+                    let _ = s.send_to(packet, *dst);
+                    if let Some(log) = &self.event_log {
+                        log.ice_check(false, *dst, packet);
+                    }
+                }
+            }
+            self.last_tx = now;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::{
+        core::clock::ManualClock,
+        netsim::{Impairment, VirtualNet},
+    };
+
+    #[test]
+    fn checks_are_resent_until_stopped_on_virtual_time() {
+        let clock = ManualClock::new();
+        let timers = Timers::manual(clock.clone());
+        let net = VirtualNet::new(Arc::new(clock));
+        let local = Arc::new(net.bind());
+        let remote = net.bind();
+        let (local_addr, remote_addr) = (local.local_addr().unwrap(), remote.local_addr().unwrap());
+        // The remote's answers take 50 ms to come back
+        net.impair(
+            remote_addr,
+            local_addr,
+            Impairment {
+                delay: Duration::from_millis(50),
+                ..Impairment::default()
+            },
+        );
+
+        let mut worker = IceWorker::start(
+            vec![local],
+            vec![(local_addr, remote_addr, b"check".to_vec())],
+            None,
+            &timers,
+        );
+        let mut buf = [0u8; 16];
+        let mut received = 0;
+        timers.advance(Duration::from_millis(500));
+        while remote.recv_from(&mut buf).is_ok() {
+            received += 1;
+        }
+        // At 200 and 400 ms
+        assert_eq!(received, 2);
+
+        remote.send_to(b"answer", local_addr).unwrap();
+        timers.advance(Duration::from_millis(40));
+        assert_eq!(worker.try_recv(), None);
+        timers.advance(Duration::from_millis(20));
+        assert_eq!(worker.try_recv(), Some((b"answer".to_vec(), remote_addr)));
+
+        worker.stop();
+        timers.advance(Duration::from_secs(1));
+        assert!(remote.recv_from(&mut buf).is_err());
+    }
+}
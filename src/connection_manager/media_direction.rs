@@ -0,0 +1,111 @@
+use std::{fmt, str::FromStr};
+
+/// Media direction advertised per m-line (`a=sendrecv`, `a=sendonly`, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MediaDirection {
+    #[default]
+    SendRecv,
+    SendOnly,
+    RecvOnly,
+    Inactive,
+}
+
+impl MediaDirection {
+    /// Builds a direction from whether we send and/or receive.
+    #[must_use]
+    pub const fn from_flags(send: bool, recv: bool) -> Self {
+        match (send, recv) {
+            (true, true) => Self::SendRecv,
+            (true, false) => Self::SendOnly,
+            (false, true) => Self::RecvOnly,
+            (false, false) => Self::Inactive,
+        }
+    }
+
+    #[must_use]
+    pub const fn sends(self) -> bool {
+        matches!(self, Self::SendRecv | Self::SendOnly)
+    }
+
+    #[must_use]
+    pub const fn receives(self) -> bool {
+        matches!(self, Self::SendRecv | Self::RecvOnly)
+    }
+
+    /// The direction seen from the other end of the m-line.
+    #[must_use]
+    pub const fn reversed(self) -> Self {
+        Self::from_flags(self.receives(), self.sends())
+    }
+
+    /// Direction to put in an answer when we want `self` and the remote
+    /// offered `offered` (RFC 3264 §6.1).
+    #[must_use]
+    pub const fn answer_to(self, offered: Self) -> Self {
+        Self::from_flags(
+            self.sends() && offered.receives(),
+            self.receives() && offered.sends(),
+        )
+    }
+
+    /// SDP attribute name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::SendRecv => "sendrecv",
+            Self::SendOnly => "sendonly",
+            Self::RecvOnly => "recvonly",
+            Self::Inactive => "inactive",
+        }
+    }
+}
+
+impl fmt::Display for MediaDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MediaDirection {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sendrecv" => Ok(Self::SendRecv),
+            "sendonly" => Ok(Self::SendOnly),
+            "recvonly" => Ok(Self::RecvOnly),
+            "inactive" => Ok(Self::Inactive),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn answer_intersects_with_offer() {
+        use MediaDirection::*;
+        assert_eq!(SendRecv.answer_to(SendRecv), SendRecv);
+        assert_eq!(SendRecv.answer_to(SendOnly), RecvOnly);
+        assert_eq!(SendRecv.answer_to(Inactive), Inactive);
+        assert_eq!(Inactive.answer_to(SendRecv), Inactive);
+        assert_eq!(SendRecv.answer_to(RecvOnly), SendOnly);
+    }
+
+    #[test]
+    fn round_trips_through_str() {
+        for d in [
+            MediaDirection::SendRecv,
+            MediaDirection::SendOnly,
+            MediaDirection::RecvOnly,
+            MediaDirection::Inactive,
+        ] {
+            assert_eq!(d.to_string().parse::<MediaDirection>(), Ok(d));
+            assert_eq!(d.reversed().reversed(), d);
+        }
+        assert!("bogus".parse::<MediaDirection>().is_err());
+    }
+}
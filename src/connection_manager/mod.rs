@@ -10,4 +10,6 @@ pub mod connection_error;
 pub use outbound_sdp::OutboundSdp;
 pub mod ice_and_sdp;
 pub mod ice_worker;
+pub mod media_direction;
 pub mod rtp_map;
+pub mod sdp_track;
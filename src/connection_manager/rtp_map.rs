@@ -1,4 +1,28 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+use crate::{
+    rtp::audio_level::AUDIO_LEVEL_URI,
+    rtp_session::rtp_codec::RtpCodec,
+    sdp::{media::Media, sdpc::Sdp},
+};
+
+/// Static payload types (RFC 3551 §6) an m-line may list without an
+/// `rtpmap`: number, encoding name and clock rate.
+const STATIC_PAYLOAD_TYPES: [(u8, &str, u32); 10] = [
+    (0, "PCMU", 8000),
+    (3, "GSM", 8000),
+    (4, "G723", 8000),
+    (8, "PCMA", 8000),
+    (9, "G722", 8000),
+    (13, "CN", 8000),
+    (18, "G729", 8000),
+    (26, "JPEG", 90_000),
+    (31, "H261", 90_000),
+    (34, "H263", 90_000),
+];
 
 /// Represents an `rtpmap` attribute from an SDP message.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -106,6 +130,143 @@ impl FromStr for RtpMap {
     }
 }
 
+/// The payload types the remote negotiated, each with its codec, clock
+/// rate, channels and `fmtp`. Receiving, it tells what an incoming PT
+/// carries; sending, which PT the peer gave each of our codecs.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadTypeMap {
+    codecs: BTreeMap<u8, RtpCodec>,
+}
+
+impl PayloadTypeMap {
+    /// Every payload type of the RTP m-lines of `sdp`: dynamic ones from
+    /// `a=rtpmap` (with their `a=fmtp`), static ones listed without one from
+    /// RFC 3551. The first m-line to use a number wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse error of the first malformed `rtpmap` of a listed
+    /// payload type.
+    pub fn from_sdp(sdp: &Sdp) -> Result<Self, RtpMapParseError> {
+        let mut map = Self::default();
+        for m in sdp.media() {
+            if m.proto().to_uppercase().contains("RTP") {
+                for codec in media_codecs(m)? {
+                    map.codecs.entry(codec.payload_type).or_insert(codec);
+                }
+            }
+        }
+        Ok(map)
+    }
+
+    /// A map of already negotiated codecs.
+    #[must_use]
+    pub fn from_codecs(codecs: &[RtpCodec]) -> Self {
+        let mut map = Self::default();
+        for codec in codecs {
+            map.codecs
+                .entry(codec.payload_type)
+                .or_insert_with(|| codec.clone());
+        }
+        map
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+
+    /// What payload type `pt` carries.
+    #[must_use]
+    pub fn get(&self, pt: u8) -> Option<&RtpCodec> {
+        self.codecs.get(&pt)
+    }
+
+    #[must_use]
+    pub fn clock_rate(&self, pt: u8) -> Option<u32> {
+        self.get(pt).map(|c| c.clock_rate)
+    }
+
+    /// The codecs, by payload type.
+    #[must_use]
+    pub fn codecs(&self) -> Vec<RtpCodec> {
+        self.codecs.values().cloned().collect()
+    }
+
+    /// `local` with the payload type the remote gave that codec, or as is
+    /// if the remote did not offer it (or nothing is negotiated yet).
+    #[must_use]
+    pub fn negotiated(&self, local: &RtpCodec) -> RtpCodec {
+        let remote: Vec<RtpCodec> = self
+            .codecs
+            .values()
+            .filter(|c| c.same_codec(local))
+            .cloned()
+            .collect();
+        local.negotiated(&remote)
+    }
+}
+
+/// The codecs of the payload types listed on `m`.
+fn media_codecs(m: &Media) -> Result<Vec<RtpCodec>, RtpMapParseError> {
+    let listed: Vec<u8> = m.fmts().iter().filter_map(|f| f.parse().ok()).collect();
+
+    // `a=fmtp:<pt> <params>`, by payload type
+    let fmtps: HashMap<u8, String> = m
+        .attrs()
+        .iter()
+        .filter(|a| a.key() == "fmtp")
+        .filter_map(|a| {
+            let (pt, params) = a.value()?.trim().split_once(' ')?;
+            Some((pt.parse().ok()?, params.trim().to_owned()))
+        })
+        .collect();
+
+    // `a=extmap:<id>[/<direction>] <uri>` for the audio level
+    let audio_level_ext = m
+        .attrs()
+        .iter()
+        .filter(|a| a.key() == "extmap")
+        .find_map(|a| {
+            let (id, uri) = a.value()?.trim().split_once(' ')?;
+            let id = id.split('/').next()?.parse().ok()?;
+            (uri.trim() == AUDIO_LEVEL_URI).then_some(id)
+        });
+
+    let mut rtpmaps = Vec::new();
+    for a in m.attrs().iter().filter(|a| a.key() == "rtpmap") {
+        let rm: RtpMap = a.value().ok_or(RtpMapParseError::MissingParts)?.parse()?;
+        if listed.is_empty() || listed.contains(&rm.payload_type) {
+            rtpmaps.push(rm);
+        }
+    }
+    // Static payload types need no rtpmap
+    for pt in &listed {
+        if !rtpmaps.iter().any(|rm| rm.payload_type == *pt)
+            && let Some(&(_, name, clock_rate)) =
+                STATIC_PAYLOAD_TYPES.iter().find(|(n, ..)| n == pt)
+        {
+            rtpmaps.push(RtpMap {
+                payload_type: *pt,
+                encoding_name: name.to_owned(),
+                clock_rate,
+                encoding_params: None,
+            });
+        }
+    }
+
+    Ok(rtpmaps
+        .into_iter()
+        .map(|rm| {
+            let mut codec = RtpCodec::with_name(rm.payload_type, rm.clock_rate, rm.encoding_name)
+                .with_channels(rm.encoding_params.unwrap_or(1));
+            codec.fmtp = fmtps.get(&rm.payload_type).cloned();
+            codec.audio_level_ext = audio_level_ext;
+            codec
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used)]
@@ -185,4 +346,34 @@ mod tests {
         let rm: RtpMap = "98 opus/48000/0".parse().unwrap();
         assert_eq!(rm.encoding_params, None);
     }
+
+    #[test]
+    fn payload_types_come_from_the_sdp() {
+        let sdp = Sdp::parse(
+            "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n\
+             m=audio 9 UDP/TLS/RTP/SAVPF 111 0 101\r\n\
+             a=rtpmap:111 L16/48000/2\r\n\
+             a=rtpmap:101 telephone-event/8000\r\n\
+             m=video 9 UDP/TLS/RTP/SAVPF 102\r\n\
+             a=rtpmap:102 H264/90000\r\n\
+             a=fmtp:102 packetization-mode=1;profile-level-id=42e01f\r\n",
+        )
+        .unwrap();
+        let map = PayloadTypeMap::from_sdp(&sdp).unwrap();
+
+        assert_eq!(map.get(111).unwrap().channels, 2);
+        // PCMU is listed without an rtpmap
+        assert_eq!(map.get(0).unwrap().name, "PCMU");
+        assert_eq!(map.clock_rate(101), Some(8000));
+        assert_eq!(
+            map.get(102).unwrap().fmtp.as_deref(),
+            Some("packetization-mode=1;profile-level-id=42e01f")
+        );
+        assert_eq!(map.get(96), None);
+
+        let ours = RtpCodec::with_name(96, 90_000, "H264");
+        assert_eq!(map.negotiated(&ours).payload_type, 102);
+        let vp8 = RtpCodec::with_name(97, 90_000, "VP8");
+        assert_eq!(map.negotiated(&vp8).payload_type, 97);
+    }
 }
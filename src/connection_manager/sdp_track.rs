@@ -0,0 +1,65 @@
+use crate::sdp::{media::MediaKind, sdpc::Sdp};
+
+/// Stream id put in the `msid` of every track we announce.
+pub const LOCAL_STREAM_ID: &str = "rustyrtc";
+
+/// An extra video track announced in SDP as `a=ssrc:<ssrc> msid:<stream> <label>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdpTrack {
+    pub ssrc: u32,
+    pub label: String,
+}
+
+impl SdpTrack {
+    #[must_use]
+    pub const fn new(ssrc: u32, label: String) -> Self {
+        Self { ssrc, label }
+    }
+
+    /// Value of the `ssrc` attribute announcing this track.
+    #[must_use]
+    pub fn attr_value(&self) -> String {
+        format!("{} msid:{LOCAL_STREAM_ID} {}", self.ssrc, self.label)
+    }
+
+    /// Parses the value of an `ssrc` attribute; only `msid` lines announce a track.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let (ssrc, rest) = value.split_once(' ')?;
+        let msid = rest.strip_prefix("msid:")?;
+        let label = msid.split_whitespace().nth(1)?;
+        Some(Self::new(ssrc.parse().ok()?, label.to_string()))
+    }
+
+    /// Every track announced on the video m-lines of `sdp`.
+    #[must_use]
+    pub fn from_sdp(sdp: &Sdp) -> Vec<Self> {
+        sdp.media()
+            .iter()
+            .filter(|m| matches!(m.kind(), MediaKind::Video))
+            .flat_map(|m| m.attrs().iter())
+            .filter(|a| a.key() == "ssrc")
+            .filter_map(|a| a.value().and_then(Self::parse))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn attr_round_trips() {
+        let t = SdpTrack::new(1234, "track-2".into());
+        assert_eq!(t.attr_value(), "1234 msid:rustyrtc track-2");
+        assert_eq!(SdpTrack::parse(&t.attr_value()), Some(t));
+    }
+
+    #[test]
+    fn ignores_non_msid_lines() {
+        assert_eq!(SdpTrack::parse("1234 cname:foo"), None);
+        assert_eq!(SdpTrack::parse("abc msid:s t"), None);
+        assert_eq!(SdpTrack::parse("1234 msid:only-stream"), None);
+    }
+}
@@ -0,0 +1,118 @@
+//! Reusable packet buffers for the receive path.
+//!
+//! At a few thousand packets per second, allocating a `Vec` per datagram
+//! shows up in profiles. The session receiver copies each datagram into a
+//! buffer from the pool, the RTP session decodes it in place
+//! ([`RtpPacket::decode_owned`](crate::rtp::rtp_packet::RtpPacket::decode_owned))
+//! and the depacketizer gives the payload back once it is reassembled, so in
+//! steady state the same few hundred buffers go round.
+
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Capacity of a new buffer: a full Ethernet MTU.
+pub const PACKET_BUFFER_CAPACITY: usize = 1500;
+/// Buffers kept by [`BufferPool::default`]; more are freed when given back.
+const DEFAULT_MAX_BUFFERS: usize = 512;
+/// Buffers that grew past this (jumbo datagrams) are freed, not kept.
+const MAX_POOLED_CAPACITY: usize = 4 * PACKET_BUFFER_CAPACITY;
+
+/// A shared free list of packet buffers. Clones share the list.
+///
+/// Buffers need not come back: one that is dropped instead is just freed,
+/// and the pool allocates again when it runs dry.
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+    free: Arc<Mutex<Vec<Vec<u8>>>>,
+    max_buffers: usize,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_BUFFERS)
+    }
+}
+
+impl BufferPool {
+    /// A pool that keeps at most `max_buffers` free buffers.
+    #[must_use]
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            free: Arc::new(Mutex::new(Vec::with_capacity(max_buffers))),
+            max_buffers,
+        }
+    }
+
+    /// An empty buffer with room for at least one MTU.
+    #[must_use]
+    pub fn take(&self) -> Vec<u8> {
+        self.free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop()
+            .unwrap_or_else(|| Vec::with_capacity(PACKET_BUFFER_CAPACITY))
+    }
+
+    /// A buffer from the pool holding a copy of `bytes`.
+    #[must_use]
+    pub fn copy_of(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut buf = self.take();
+        buf.extend_from_slice(bytes);
+        buf
+    }
+
+    /// Returns `buf` for reuse.
+    pub fn give(&self, mut buf: Vec<u8>) {
+        if buf.capacity() < PACKET_BUFFER_CAPACITY || buf.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+        buf.clear();
+        let mut free = self.free.lock().unwrap_or_else(PoisonError::into_inner);
+        if free.len() < self.max_buffers {
+            free.push(buf);
+        }
+    }
+
+    /// Free buffers currently in the pool.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused() {
+        let pool = BufferPool::new(2);
+        let buf = pool.copy_of(b"rtp");
+        assert_eq!(buf, b"rtp");
+        let ptr = buf.as_ptr();
+        pool.give(buf);
+        assert_eq!(pool.available(), 1);
+
+        let again = pool.take();
+        assert!(again.is_empty());
+        assert_eq!(again.as_ptr(), ptr);
+        assert_eq!(pool.available(), 0);
+    }
+
+    #[test]
+    fn keeps_at_most_max_buffers_of_packet_size() {
+        let pool = BufferPool::new(2);
+        for _ in 0..3 {
+            pool.give(Vec::with_capacity(PACKET_BUFFER_CAPACITY));
+        }
+        assert_eq!(pool.available(), 2);
+
+        let pool = BufferPool::new(2);
+        pool.give(Vec::with_capacity(16));
+        pool.give(Vec::with_capacity(MAX_POOLED_CAPACITY + 1));
+        assert_eq!(pool.available(), 0);
+    }
+}
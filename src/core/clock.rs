@@ -0,0 +1,96 @@
+//! Where timers, retransmissions and the congestion controller get the time.
+//!
+//! Production code uses [`SystemClock`]. Tests use a [`ManualClock`], which
+//! only moves when told to, so a handshake timeout or an ICE retransmission
+//! can be checked without waiting for it (see [`Timers::manual`]).
+//!
+//! [`Timers::manual`]: super::timers::Timers::manual
+
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// A clock shared between components.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The real time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The system clock as a [`SharedClock`].
+#[must_use]
+pub fn system() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that stands still until [`advance`](Self::advance)d. Clones share
+/// the same time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// A clock stopped at the current time.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += by;
+    }
+
+    /// Moves the clock forward to `at`; earlier times are ignored.
+    pub fn advance_to(&self, at: Instant) {
+        let mut now = self.now.lock().unwrap_or_else(PoisonError::into_inner);
+        *now = (*now).max(at);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn a_manual_clock_only_moves_forward_when_told() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        let t0 = clock.now();
+        assert_eq!(shared.now(), t0);
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(shared.now() - t0, Duration::from_secs(3));
+
+        shared.advance_to(t0);
+        assert_eq!(clock.now() - t0, Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,227 @@
+//! W3C-style connection states for a peer connection.
+//!
+//! `IceConnectionState` and `DtlsState` track the two transports; the
+//! aggregate `PeerConnectionState` is derived from them (plus the session
+//! handshake) and is what the UI should look at.
+
+use crate::core::events::EngineEvent;
+
+/// State of the ICE transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IceConnectionState {
+    /// No connectivity checks started yet.
+    #[default]
+    New,
+    /// Gathering candidates / running connectivity checks.
+    Checking,
+    /// A candidate pair was nominated.
+    Connected,
+    /// Connectivity was lost, but may come back.
+    Disconnected,
+    /// Checks finished without a usable pair.
+    Failed,
+    /// The transport was shut down.
+    Closed,
+}
+
+/// State of the DTLS transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DtlsState {
+    #[default]
+    New,
+    /// Handshake in progress.
+    Connecting,
+    /// Handshake done, SRTP keys derived.
+    Connected,
+    /// Handshake failed.
+    Failed,
+    /// Close notify sent/received.
+    Closed,
+}
+
+/// Aggregate state of a peer connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PeerConnectionState {
+    #[default]
+    New,
+    Connecting,
+    /// ICE and DTLS are up and the session handshake completed.
+    Connected,
+    Disconnected,
+    Failed,
+    Closed,
+}
+
+impl PeerConnectionState {
+    /// Derives the aggregate state from the transport states.
+    #[must_use]
+    pub const fn aggregate(
+        ice: IceConnectionState,
+        dtls: DtlsState,
+        session_established: bool,
+    ) -> Self {
+        use IceConnectionState as Ice;
+        match (ice, dtls) {
+            (Ice::Failed, _) | (_, DtlsState::Failed) => Self::Failed,
+            (Ice::Closed, _) | (_, DtlsState::Closed) => Self::Closed,
+            (Ice::Disconnected, _) => Self::Disconnected,
+            (Ice::Connected, DtlsState::Connected) if session_established => Self::Connected,
+            (Ice::New, DtlsState::New) => Self::New,
+            _ => Self::Connecting,
+        }
+    }
+
+    /// `true` while media can flow.
+    #[must_use]
+    pub const fn is_connected(self) -> bool {
+        matches!(self, Self::Connected)
+    }
+}
+
+/// Tracks the current states and turns changes into `EngineEvent`s.
+#[derive(Debug, Default)]
+pub struct ConnectionStateTracker {
+    ice: IceConnectionState,
+    dtls: DtlsState,
+    session_established: bool,
+    peer: PeerConnectionState,
+}
+
+impl ConnectionStateTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn ice(&self) -> IceConnectionState {
+        self.ice
+    }
+
+    #[must_use]
+    pub const fn dtls(&self) -> DtlsState {
+        self.dtls
+    }
+
+    #[must_use]
+    pub const fn peer(&self) -> PeerConnectionState {
+        self.peer
+    }
+
+    /// Updates the ICE state, returning the transition events (if any).
+    pub fn set_ice(&mut self, state: IceConnectionState) -> Vec<EngineEvent> {
+        if self.ice == state {
+            return Vec::new();
+        }
+        self.ice = state;
+        let mut out = vec![EngineEvent::IceConnectionStateChanged(state)];
+        out.extend(self.refresh());
+        out
+    }
+
+    /// Updates the DTLS state, returning the transition events (if any).
+    pub fn set_dtls(&mut self, state: DtlsState) -> Vec<EngineEvent> {
+        if self.dtls == state {
+            return Vec::new();
+        }
+        self.dtls = state;
+        let mut out = vec![EngineEvent::DtlsStateChanged(state)];
+        out.extend(self.refresh());
+        out
+    }
+
+    /// Records whether the session handshake (SYN/ACK) completed.
+    pub fn set_session_established(&mut self, established: bool) -> Vec<EngineEvent> {
+        self.session_established = established;
+        self.refresh().into_iter().collect()
+    }
+
+    /// Moves every transport to `Closed`.
+    pub fn close(&mut self) -> Vec<EngineEvent> {
+        self.session_established = false;
+        let mut out = self.set_ice(IceConnectionState::Closed);
+        out.extend(self.set_dtls(DtlsState::Closed));
+        out
+    }
+
+    fn refresh(&mut self) -> Option<EngineEvent> {
+        let next = PeerConnectionState::aggregate(self.ice, self.dtls, self.session_established);
+        if next == self.peer {
+            return None;
+        }
+        self.peer = next;
+        Some(EngineEvent::PeerConnectionStateChanged(next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn peer_changes(events: &[EngineEvent]) -> Vec<PeerConnectionState> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                EngineEvent::PeerConnectionStateChanged(s) => Some(*s),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn happy_path_reaches_connected() {
+        let mut t = ConnectionStateTracker::new();
+        let ev = t.set_ice(IceConnectionState::Checking);
+        assert_eq!(peer_changes(&ev), vec![PeerConnectionState::Connecting]);
+
+        let ev = t.set_ice(IceConnectionState::Connected);
+        assert!(peer_changes(&ev).is_empty());
+        t.set_dtls(DtlsState::Connecting);
+        let ev = t.set_dtls(DtlsState::Connected);
+        assert!(peer_changes(&ev).is_empty(), "waits for the session");
+
+        let ev = t.set_session_established(true);
+        assert_eq!(peer_changes(&ev), vec![PeerConnectionState::Connected]);
+        assert!(t.peer().is_connected());
+    }
+
+    #[test]
+    fn repeated_state_emits_nothing() {
+        let mut t = ConnectionStateTracker::new();
+        assert_eq!(t.set_ice(IceConnectionState::Checking).len(), 2);
+        assert!(t.set_ice(IceConnectionState::Checking).is_empty());
+    }
+
+    #[test]
+    fn dtls_failure_fails_the_connection() {
+        let mut t = ConnectionStateTracker::new();
+        t.set_ice(IceConnectionState::Connected);
+        let ev = t.set_dtls(DtlsState::Failed);
+        assert_eq!(peer_changes(&ev), vec![PeerConnectionState::Failed]);
+    }
+
+    #[test]
+    fn close_moves_everything_to_closed() {
+        let mut t = ConnectionStateTracker::new();
+        t.set_ice(IceConnectionState::Connected);
+        t.set_dtls(DtlsState::Connected);
+        t.set_session_established(true);
+        let ev = t.close();
+        assert_eq!(peer_changes(&ev), vec![PeerConnectionState::Closed]);
+        assert_eq!(t.ice(), IceConnectionState::Closed);
+        assert_eq!(t.dtls(), DtlsState::Closed);
+    }
+
+    #[test]
+    fn disconnected_ice_is_reported() {
+        assert_eq!(
+            PeerConnectionState::aggregate(
+                IceConnectionState::Disconnected,
+                DtlsState::Connected,
+                true
+            ),
+            PeerConnectionState::Disconnected
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Consent freshness and automatic recovery for an established session.
+//!
+//! While a session is up both peers exchange keepalives. If nothing is
+//! received for `timeout` the path is considered lost, but the session is
+//! kept alive: after `restart_after` the ICE checks are restarted on the
+//! existing candidate pairs, and if no traffic comes back within `grace`
+//! the connection is declared failed.
+//!
+//! Only what the peer alone could have sent counts as hearing from it (see
+//! [`PeerActivity`]): anyone able to reach the socket could otherwise keep
+//! a session alive for a peer that is gone.
+
+use std::{
+    sync::{
+        PoisonError, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{config::Config, core::clock::SharedClock};
+
+const DEFAULT_KEEPALIVE_MS: u64 = 1000;
+const DEFAULT_CONSENT_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_RESTART_AFTER_MS: u64 = 2000;
+const DEFAULT_RESTART_GRACE_MS: u64 = 30_000;
+
+/// Timers driving the consent monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsentConfig {
+    /// How often a keepalive is sent on an established session.
+    pub keepalive_every: Duration,
+    /// Silence after which the path is considered lost.
+    pub timeout: Duration,
+    /// Time spent disconnected before restarting ICE checks.
+    pub restart_after: Duration,
+    /// Total time (since the loss) before giving up.
+    pub grace: Duration,
+}
+
+impl Default for ConsentConfig {
+    fn default() -> Self {
+        Self {
+            keepalive_every: Duration::from_millis(DEFAULT_KEEPALIVE_MS),
+            timeout: Duration::from_millis(DEFAULT_CONSENT_TIMEOUT_MS),
+            restart_after: Duration::from_millis(DEFAULT_RESTART_AFTER_MS),
+            grace: Duration::from_millis(DEFAULT_RESTART_GRACE_MS),
+        }
+    }
+}
+
+impl ConsentConfig {
+    /// Reads the `[ICE]` consent keys, falling back to the defaults.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let ms = |key: &str, default: u64| {
+            Duration::from_millis(
+                config
+                    .get("ICE", key)
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(default),
+            )
+        };
+        Self {
+            keepalive_every: ms("consent_keepalive_ms", DEFAULT_KEEPALIVE_MS),
+            timeout: ms("consent_timeout_ms", DEFAULT_CONSENT_TIMEOUT_MS),
+            restart_after: ms("ice_restart_after_ms", DEFAULT_RESTART_AFTER_MS),
+            grace: ms("ice_restart_grace_ms", DEFAULT_RESTART_GRACE_MS),
+        }
+    }
+}
+
+/// When the peer was last heard from: a session message whose MAC checks
+/// out, an SRTP packet that unprotects or a DTLS record that decrypts.
+/// Shared by the threads that receive each of them.
+pub struct PeerActivity {
+    /// The clock, and the reference point of `last_ms`.
+    clock: RwLock<(SharedClock, Instant)>,
+    /// Milliseconds since the reference point when the peer was last heard.
+    last_ms: AtomicU64,
+}
+
+impl PeerActivity {
+    /// Counts from now on `clock`, as if the peer was just heard.
+    #[must_use]
+    pub fn new(clock: SharedClock) -> Self {
+        let epoch = clock.now();
+        Self {
+            clock: RwLock::new((clock, epoch)),
+            last_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Switches to `clock`, from now on.
+    pub fn use_clock(&self, clock: SharedClock) {
+        let epoch = clock.now();
+        *self.clock.write().unwrap_or_else(PoisonError::into_inner) = (clock, epoch);
+        self.last_ms.store(0, Ordering::SeqCst);
+    }
+
+    /// Something only the peer could have sent was just received.
+    pub fn heard(&self) {
+        self.last_ms.store(self.elapsed_ms(), Ordering::SeqCst);
+    }
+
+    /// Time since the peer was last heard.
+    #[must_use]
+    pub fn idle_for(&self) -> Duration {
+        let last = self.last_ms.load(Ordering::SeqCst);
+        Duration::from_millis(self.elapsed_ms().saturating_sub(last))
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        let guard = self.clock.read().unwrap_or_else(PoisonError::into_inner);
+        let (clock, epoch) = &*guard;
+        clock.now().duration_since(*epoch).as_millis() as u64
+    }
+}
+
+/// What the peer connection should do after a consent check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentAction {
+    /// Traffic stopped: report ICE `Disconnected`, keep the session.
+    Lost,
+    /// Still silent: restart the ICE checks.
+    RestartIce,
+    /// Traffic came back: report ICE `Connected` again.
+    Recovered,
+    /// Grace period over: report ICE `Failed`.
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConsentState {
+    Alive,
+    Lost { since: Instant },
+    Restarting { since: Instant },
+    Failed,
+}
+
+/// Tracks consent freshness for one session.
+#[derive(Debug, Clone)]
+pub struct ConsentMonitor {
+    cfg: ConsentConfig,
+    state: ConsentState,
+}
+
+impl ConsentMonitor {
+    #[must_use]
+    pub const fn new(cfg: ConsentConfig) -> Self {
+        Self {
+            cfg,
+            state: ConsentState::Alive,
+        }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &ConsentConfig {
+        &self.cfg
+    }
+
+    /// `true` between `RestartIce` and `Recovered`/`Failed`.
+    #[must_use]
+    pub const fn is_restarting(&self) -> bool {
+        matches!(self.state, ConsentState::Restarting { .. })
+    }
+
+    /// Back to `Alive` (e.g. the restarted checks validated the path).
+    pub const fn reset(&mut self) {
+        self.state = ConsentState::Alive;
+    }
+
+    /// Updates the monitor given how long the session has been silent.
+    pub fn tick(&mut self, now: Instant, idle: Duration) -> Option<ConsentAction> {
+        let fresh = idle < self.cfg.timeout;
+        match self.state {
+            ConsentState::Alive if !fresh => {
+                self.state = ConsentState::Lost { since: now };
+                Some(ConsentAction::Lost)
+            }
+            ConsentState::Lost { .. } | ConsentState::Restarting { .. } if fresh => {
+                self.state = ConsentState::Alive;
+                Some(ConsentAction::Recovered)
+            }
+            ConsentState::Lost { since } if now.duration_since(since) >= self.cfg.restart_after => {
+                self.state = ConsentState::Restarting { since };
+                Some(ConsentAction::RestartIce)
+            }
+            ConsentState::Restarting { since } if now.duration_since(since) >= self.cfg.grace => {
+                self.state = ConsentState::Failed;
+                Some(ConsentAction::Failed)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn cfg() -> ConsentConfig {
+        ConsentConfig {
+            keepalive_every: Duration::from_millis(100),
+            timeout: Duration::from_secs(1),
+            restart_after: Duration::from_secs(2),
+            grace: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn fresh_traffic_does_nothing() {
+        let mut m = ConsentMonitor::new(cfg());
+        assert_eq!(m.tick(Instant::now(), Duration::from_millis(200)), None);
+    }
+
+    #[test]
+    fn silence_leads_to_restart_then_failure() {
+        let mut m = ConsentMonitor::new(cfg());
+        let t0 = Instant::now();
+        let silent = Duration::from_secs(5);
+
+        assert_eq!(m.tick(t0, silent), Some(ConsentAction::Lost));
+        assert_eq!(m.tick(t0 + Duration::from_secs(1), silent), None);
+        assert_eq!(
+            m.tick(t0 + Duration::from_secs(2), silent),
+            Some(ConsentAction::RestartIce)
+        );
+        assert!(m.is_restarting());
+        assert_eq!(m.tick(t0 + Duration::from_secs(9), silent), None);
+        assert_eq!(
+            m.tick(t0 + Duration::from_secs(10), silent),
+            Some(ConsentAction::Failed)
+        );
+        assert_eq!(m.tick(t0 + Duration::from_secs(11), Duration::ZERO), None);
+    }
+
+    #[test]
+    fn traffic_during_restart_recovers() {
+        let mut m = ConsentMonitor::new(cfg());
+        let t0 = Instant::now();
+        m.tick(t0, Duration::from_secs(5));
+        m.tick(t0 + Duration::from_secs(3), Duration::from_secs(5));
+        assert!(m.is_restarting());
+        assert_eq!(
+            m.tick(t0 + Duration::from_secs(4), Duration::from_millis(10)),
+            Some(ConsentAction::Recovered)
+        );
+        assert!(!m.is_restarting());
+    }
+
+    #[test]
+    fn blip_recovers_before_restart() {
+        let mut m = ConsentMonitor::new(cfg());
+        let t0 = Instant::now();
+        assert_eq!(
+            m.tick(t0, Duration::from_secs(1)),
+            Some(ConsentAction::Lost)
+        );
+        assert_eq!(
+            m.tick(t0 + Duration::from_millis(500), Duration::ZERO),
+            Some(ConsentAction::Recovered)
+        );
+    }
+}
@@ -1,138 +1,332 @@
 //! Core WebRTC Engine module.
 //!
-//! The [`Engine`] struct is the main entry point for managing a WebRTC session,
-//! orchestrating signaling, ICE, DTLS, and media transport.
+//! The [`Engine`] struct is the main entry point for managing WebRTC sessions.
+//! It keeps one [`PeerConnection`] per remote peer, keyed by [`PeerId`], and
+//! tags every event with the peer it came from.
+//!
+//! The single-peer methods (`negotiate`, `poll`, `start`, ...) act on the
+//! *primary* peer, so one-to-one callers keep working unchanged.
 
 use std::{
-    net::SocketAddr,
-    sync::{
-        Arc, Mutex,
-        atomic::{AtomicBool, Ordering},
-        mpsc::{self, Receiver, Sender},
-    },
-    thread,
-    time::{Duration, Instant},
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, atomic::AtomicBool, mpsc::Receiver},
 };
 
 use crate::{
+    camera_manager::capture_settings::CaptureSettings,
     config::Config,
-    congestion_controller::CongestionController,
-    connection_manager::{ConnectionManager, OutboundSdp, connection_error::ConnectionError},
+    connection_manager::{connection_error::ConnectionError, media_direction::MediaDirection},
     core::{
+        connection_state::PeerConnectionState,
         events::EngineEvent,
-        session::{Session, SessionConfig, SessionInitArgs},
+        peer_connection::PeerConnection,
+        quality::{LowDataMode, QualityPreset, configured_max_bitrate},
+        rtc_event_log::RtcEventLog,
+        stats::StatsReport,
+        subscription::{EventBus, EventMask, SubscriptionId},
+        timers::Timers,
+    },
+    error::RtcError,
+    log::{
+        log_context::{ContextLogSink, LogContext},
+        log_sink::LogSink,
     },
-    dtls::{self, DtlsRole},
-    file_handler::{FileHandler, events::FileHandlerEvents},
-    ice::type_ice::ice_agent::IceRole,
-    log::log_sink::LogSink,
-    media_agent::video_frame::VideoFrame,
-    media_transport::{MediaTransport, media_transport_event::MediaTransportEvent},
-    sctp::events::SctpEvents,
-    sink_debug, sink_error, sink_info, sink_trace,
+    media_agent::{
+        recorder::{RecordStreams, RecordingStatus},
+        video_frame::VideoFrame,
+        video_track::{TrackId, VideoSource},
+    },
+    signaling::protocol::capabilities::Capabilities,
+    sink_info,
 };
 
-use super::constants::{MAX_BITRATE, MIN_BITRATE};
-use crate::connection_manager::ice_and_sdp::ICEAndSDP;
+/// Identifies a remote peer inside the engine (the signaling username).
+pub type PeerId = String;
+
+/// Settings [`Engine::update_config`] applies without a restart, as
+/// `(section, key)`; an empty section is a global. The engine applies the
+/// bitrate caps and keeps the rest for its consumers: the log level for
+/// the logger, `[UI] fps` for the GUI, the STUN server and the RTC event
+/// log switch for the next call.
+pub const LIVE_SETTINGS: [(&str, &str); 10] = [
+    ("", "log_level"),
+    ("Media", "max_bitrate"),
+    ("Media", "quality"),
+    ("Media", "quality_low_bitrate"),
+    ("Media", "quality_medium_bitrate"),
+    ("Media", "quality_high_bitrate"),
+    ("Media", "low_data_mode"),
+    ("ICE", "stun_server"),
+    ("UI", "fps"),
+    ("Diagnostics", "rtc_event_log"),
+];
+
+/// The peer every single-peer method talks to until another one is selected.
+pub const DEFAULT_PEER: &str = "default";
+
+/// An `EngineEvent` together with the peer that produced it.
+#[derive(Debug, Clone)]
+pub struct PeerEvent {
+    pub peer: PeerId,
+    pub event: EngineEvent,
+}
 
-/// The central orchestrator for a WebRTC peer connection.
-///
-/// Manages ICE, SDP negotiation, DTLS handshake, and media transport.
+/// The central orchestrator for all WebRTC peer connections.
 pub struct Engine {
     logger_sink: Arc<dyn LogSink>,
-    cm: ConnectionManager,
-    session: Arc<Mutex<Option<Session>>>,
-    event_tx: Sender<EngineEvent>,
-    ui_rx: Receiver<EngineEvent>,
-    media_transport: MediaTransport,
-    congestion_controller: CongestionController,
     config: Arc<Config>,
-    file_handler: Arc<Mutex<Option<Arc<FileHandler>>>>,
     sending_files: Arc<AtomicBool>,
     receiving_files: Arc<AtomicBool>,
+    peers: HashMap<PeerId, PeerConnection>,
+    primary: PeerId,
+    bus: EventBus,
+    /// Bitrate cap set through the API, applied to new connections too.
+    max_bitrate: Option<u32>,
+    /// Events raised by the engine itself, returned by the next `poll`.
+    pending: Vec<EngineEvent>,
+    /// Timers of every connection; the shared timer thread unless set.
+    timers: Option<Timers>,
 }
 
 impl Engine {
-    /// Creates a new `Engine` instance.
+    /// Creates a new `Engine` with a single, empty primary peer connection.
     pub fn new(
         logger_sink: Arc<dyn LogSink>,
         config: Arc<Config>,
         sending_files: Arc<AtomicBool>,
         receiving_files: Arc<AtomicBool>,
     ) -> Self {
-        let (ui_tx, ui_rx) = mpsc::channel();
-        let (event_tx, event_rx) = mpsc::channel();
-        let media_transport =
-            MediaTransport::new(event_tx.clone(), logger_sink.clone(), config.clone());
-        let initial_bitrate = crate::media_agent::constants::BITRATE;
-        let max_bitrate = config
-            .get("Media", "max_bitrate")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(MAX_BITRATE);
-
-        let min_bitrate = config
-            .get("Media", "min_bitrate")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(MIN_BITRATE);
-        let congestion_controller = CongestionController::new(
-            initial_bitrate,
-            min_bitrate,
-            max_bitrate,
-            logger_sink.clone(),
-            event_tx.clone(),
-        );
-
-        let logger = logger_sink.clone();
-
-        let media_tx = media_transport.media_transport_event_tx();
-        std::thread::spawn(move || {
-            while let Ok(ev) = event_rx.recv() {
-                match &ev {
-                    EngineEvent::RtpIn(pkt) => {
-                        sink_trace!(
-                            logger,
-                            "[Engine] Sending RTP Packet to MediaTransport::RtpIn"
-                        );
-                        sink_trace!(logger, "[Engine] ssrc: {} seq: {}", pkt.ssrc, pkt.seq);
-                        if let Some(tx) = &media_tx {
-                            let _ = tx.send(MediaTransportEvent::RtpIn(pkt.clone()));
-                        }
-                    }
-                    _ => {
-                        let _ = ui_tx.send(ev.clone());
-                    }
-                }
-            }
-        });
-
-        Self {
-            cm: ConnectionManager::new(logger_sink.clone(), config.clone()),
+        let mut engine = Self {
             logger_sink,
-            session: Arc::new(Mutex::new(None)),
-            event_tx,
-            media_transport,
-            congestion_controller,
-            ui_rx,
             config,
-            file_handler: Arc::new(Mutex::new(None)),
             sending_files,
             receiving_files,
+            peers: HashMap::new(),
+            primary: DEFAULT_PEER.to_string(),
+            bus: EventBus::new(),
+            max_bitrate: None,
+            pending: Vec::new(),
+            timers: None,
+        };
+        engine.add_peer(DEFAULT_PEER);
+        engine
+    }
+
+    fn new_peer_connection(&self, peer: &str) -> PeerConnection {
+        // Logs of a named peer carry its name as a structured field
+        let logger_sink: Arc<dyn LogSink> = if peer == DEFAULT_PEER {
+            self.logger_sink.clone()
+        } else {
+            Arc::new(ContextLogSink::new(
+                self.logger_sink.clone(),
+                LogContext::peer(peer),
+            ))
+        };
+        let mut pc = PeerConnection::new(
+            logger_sink,
+            self.config.clone(),
+            self.sending_files.clone(),
+            self.receiving_files.clone(),
+        );
+        if let Some(bps) = self.max_bitrate {
+            pc.set_max_bitrate(bps);
+        }
+        pc.set_event_log(RtcEventLog::from_config(&self.config, peer));
+        if let Some(timers) = &self.timers {
+            pc.set_timers(timers.clone());
+        }
+        pc
+    }
+
+    // ---- Peer map ----------------------------------------------------------
+
+    /// Returns the connection for `peer`, creating it if needed.
+    #[allow(clippy::expect_used)]
+    pub fn add_peer(&mut self, peer: &str) -> &mut PeerConnection {
+        if !self.peers.contains_key(peer) {
+            sink_info!(self.logger_sink, "[Engine] adding peer connection {}", peer);
+            let pc = self.new_peer_connection(peer);
+            self.peers.insert(peer.to_string(), pc);
+        }
+        self.peers
+            .get_mut(peer)
+            .expect("peer connection inserted above")
+    }
+
+    /// Stops and drops the connection for `peer`.
+    ///
+    /// Removing the primary peer replaces it with a fresh connection so the
+    /// single-peer API always has something to act on.
+    pub fn remove_peer(&mut self, peer: &str) {
+        if let Some(mut pc) = self.peers.remove(peer) {
+            sink_info!(
+                self.logger_sink,
+                "[Engine] removing peer connection {}",
+                peer
+            );
+            pc.stop();
+        }
+        if peer == self.primary {
+            let pc = self.new_peer_connection(peer);
+            self.peers.insert(self.primary.clone(), pc);
+        }
+    }
+
+    /// Stops the connection for `peer` and replaces it with a fresh one
+    /// (new ICE credentials, new media pipeline).
+    pub fn reset_peer(&mut self, peer: &str) {
+        if let Some(mut pc) = self.peers.remove(peer) {
+            pc.stop();
+        }
+        let pc = self.new_peer_connection(peer);
+        self.peers.insert(peer.to_string(), pc);
+    }
+
+    /// Makes `peer` the target of the single-peer API, creating it if needed.
+    pub fn set_primary_peer(&mut self, peer: &str) {
+        self.add_peer(peer);
+        self.primary = peer.to_string();
+    }
+
+    /// The peer the single-peer API currently acts on.
+    #[must_use]
+    pub fn primary_peer(&self) -> &str {
+        &self.primary
+    }
+
+    /// Ids of every peer connection, in no particular order.
+    #[must_use]
+    pub fn peer_ids(&self) -> Vec<PeerId> {
+        self.peers.keys().cloned().collect()
+    }
+
+    #[must_use]
+    pub fn peer(&self, peer: &str) -> Option<&PeerConnection> {
+        self.peers.get(peer)
+    }
+
+    pub fn peer_mut(&mut self, peer: &str) -> Option<&mut PeerConnection> {
+        self.peers.get_mut(peer)
+    }
+
+    /// Polls every peer connection, tagging events with their peer.
+    ///
+    /// Events are also delivered to subscribers.
+    pub fn poll_peers(&mut self) -> Vec<PeerEvent> {
+        let mut out = Vec::new();
+        for (peer, pc) in &mut self.peers {
+            out.extend(pc.poll().into_iter().map(|event| PeerEvent {
+                peer: peer.clone(),
+                event,
+            }));
+        }
+        for ev in &out {
+            self.bus.publish(ev);
+        }
+        out
+    }
+
+    /// Polls every peer except the primary, tagging events with their peer.
+    ///
+    /// Complements [`poll`](Self::poll), which drives the primary, for UIs
+    /// that handle the primary's events themselves. Events are also
+    /// delivered to subscribers.
+    pub fn poll_others(&mut self) -> Vec<PeerEvent> {
+        let mut out = Vec::new();
+        for (peer, pc) in &mut self.peers {
+            if *peer == self.primary {
+                continue;
+            }
+            out.extend(pc.poll().into_iter().map(|event| PeerEvent {
+                peer: peer.clone(),
+                event,
+            }));
+        }
+        for ev in &out {
+            self.bus.publish(ev);
+        }
+        out
+    }
+
+    /// Latest remote video frame of every peer except the primary (see
+    /// [`snapshot_frames`](Self::snapshot_frames)), sorted by peer.
+    #[must_use]
+    pub fn other_remote_frames(&self) -> Vec<(PeerId, VideoFrame)> {
+        let mut frames: Vec<(PeerId, VideoFrame)> = self
+            .peers
+            .iter()
+            .filter(|(peer, _)| **peer != self.primary)
+            .filter_map(|(peer, pc)| pc.snapshot_frames().1.map(|f| (peer.clone(), f)))
+            .collect();
+        frames.sort_by(|(a, _), (b, _)| a.cmp(b));
+        frames
+    }
+
+    // ---- Subscriptions -----------------------------------------------------
+
+    /// Calls `callback` for every event matching `mask` as soon as the engine
+    /// processes it (during `poll`/`poll_peers`).
+    pub fn subscribe(
+        &mut self,
+        mask: EventMask,
+        callback: impl FnMut(&PeerEvent) + Send + 'static,
+    ) -> SubscriptionId {
+        self.bus.subscribe_fn(mask, callback)
+    }
+
+    /// Returns a channel receiving every event matching `mask`.
+    ///
+    /// The channel buffers, so the receiver can be drained at its own pace
+    /// without missing events. Dropping it unsubscribes.
+    pub fn subscribe_channel(&mut self, mask: EventMask) -> (SubscriptionId, Receiver<PeerEvent>) {
+        self.bus.subscribe(mask)
+    }
+
+    /// Removes a subscriber. Returns `false` if `id` was not registered.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.bus.unsubscribe(id)
+    }
+
+    /// Stops every peer connection.
+    pub fn stop_all(&mut self) {
+        for pc in self.peers.values_mut() {
+            pc.stop();
         }
     }
 
+    /// Ends every connection for good, as when the application exits: each
+    /// peer gets a DTLS `close_notify`, the media workers are stopped and
+    /// the session threads joined. Safe to call more than once.
+    pub fn shutdown(&mut self) {
+        sink_info!(self.logger_sink, "[Engine] shutting down");
+        for pc in self.peers.values_mut() {
+            pc.stop();
+            pc.close_session();
+        }
+    }
+
+    fn primary_mut(&mut self) -> &mut PeerConnection {
+        let primary = self.primary.clone();
+        self.add_peer(&primary)
+    }
+
+    #[allow(clippy::expect_used)]
+    fn primary_ref(&self) -> &PeerConnection {
+        self.peers
+            .get(&self.primary)
+            .expect("primary peer connection always exists")
+    }
+
+    // ---- Single-peer API (acts on the primary peer) ---------------------------
+
     /// Initiates an SDP negotiation as an offerer.
     ///
     /// # Errors
     ///
     /// Returns `ConnectionError` if the negotiation fails.
     pub fn negotiate(&mut self) -> Result<Option<String>, ConnectionError> {
-        self.cm
-            .set_local_rtp_codecs(self.media_transport.codec_descriptors());
-        match self.cm.negotiate()? {
-            OutboundSdp::Offer(o) => Ok(Some(o.encode())),
-            OutboundSdp::Answer(a) => Ok(Some(a.encode())),
-            OutboundSdp::None => Ok(None),
-        }
+        self.primary_mut().negotiate()
     }
 
     /// Applies a remote SDP (offer or answer) received from the peer.
@@ -144,13 +338,7 @@ impl Engine {
         &mut self,
         remote_sdp: &str,
     ) -> Result<Option<String>, ConnectionError> {
-        self.cm
-            .set_local_rtp_codecs(self.media_transport.codec_descriptors());
-        match self.cm.apply_remote_sdp(remote_sdp)? {
-            OutboundSdp::Answer(a) => Ok(Some(a.encode())),
-            OutboundSdp::Offer(o) => Ok(Some(o.encode())),
-            OutboundSdp::None => Ok(None),
-        }
+        self.primary_mut().apply_remote_sdp(remote_sdp)
     }
 
     /// Applies a remote ICE candidate.
@@ -159,415 +347,439 @@ impl Engine {
     ///
     /// Returns `ConnectionError` if applying the candidate fails.
     pub fn apply_remote_candidate(&mut self, candidate_line: &str) -> Result<(), ConnectionError> {
-        self.cm.apply_remote_trickle_candidate(candidate_line)
+        self.primary_mut().apply_remote_candidate(candidate_line)
     }
 
     /// Returns local ICE candidates encoded as SDP attribute lines (`candidate:...`).
     pub fn local_candidates_as_sdp_lines(&self) -> Vec<String> {
-        self.cm
-            .ice_agent
-            .local_candidates
-            .iter()
-            .map(|c| ICEAndSDP::new(c.clone()).to_string())
-            .collect()
+        self.primary_ref().local_candidates_as_sdp_lines()
     }
 
     /// Starts the WebRTC session.
     ///
     /// # Errors
     ///
-    /// Returns a `String` error if no nominated ICE pair is available.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal session lock is poisoned.
-    #[allow(clippy::expect_used)]
-    pub fn start(&mut self) -> Result<(), String> {
-        let mut guard = self.session.lock().expect("session lock poisoned");
-        if let Some(sess) = guard.as_mut() {
-            sess.start();
-        } else {
-            return Err("no nominated pair yet".into());
-        }
-        Ok(())
+    /// Returns `RtcError::Ice` if no nominated ICE pair is available.
+    pub fn start(&mut self) -> Result<(), RtcError> {
+        self.primary_mut().start()
     }
 
     /// Stops the WebRTC session.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal session lock is poisoned.
-    #[allow(clippy::expect_used)]
     pub fn stop(&mut self) {
-        if let Some(sess) = self.session.lock().expect("session lock poisoned").as_mut() {
-            sess.request_close();
-        }
-        self.media_transport.stop();
-        // Stop file handler
-        if let Ok(mut fh_guard) = self.file_handler.lock() {
-            if let Some(fh) = fh_guard.as_ref() {
-                // Reset flags
-                self.sending_files.store(false, Ordering::SeqCst);
-                self.receiving_files.store(false, Ordering::SeqCst);
-                // Shutdown
-                fh.shutdown();
-            }
-            *fh_guard = None;
-        }
+        self.primary_mut().stop();
     }
+
     /// Closes the WebRTC session and resets the connection manager.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal session lock is poisoned.
-    #[allow(clippy::expect_used)]
     pub fn close_session(&mut self) {
-        let mut guard = self.session.lock().expect("session lock poisoned");
-        *guard = None;
-        self.cm.reset();
-        sink_debug!(
-            self.logger_sink,
-            "[Engine] Session closed and ConnectionManager reset."
-        );
-        // Reset file handler
-        if let Ok(mut fh) = self.file_handler.lock() {
-            *fh = None;
-        }
+        self.primary_mut().close_session();
     }
 
     pub fn send_file(&self, path: String, id: u32) {
-        println!(
-            "[CLI DEBUG] Engine::send_file called path={} id={}",
-            path, id
-        );
-        sink_info!(
-            self.logger_sink,
-            "[Engine] send_file called for path: {} (id: {})",
-            path,
-            id
-        );
-        if let Ok(fh_guard) = self.file_handler.lock() {
-            if let Some(fh) = fh_guard.as_ref() {
-                sink_info!(
-                    self.logger_sink,
-                    "[Engine] FileHandler found, sending ReadFile event"
-                );
-                self.sending_files.store(true, Ordering::SeqCst);
-                if let Err(e) = fh.send(FileHandlerEvents::ReadFile { path, id }) {
-                    sink_error!(
-                        self.logger_sink,
-                        "[Engine] Failed to send ReadFile event to FileHandler: {}",
-                        e
-                    );
-                }
-            } else {
-                sink_error!(
-                    self.logger_sink,
-                    "[Engine] FileHandler is None in send_file!"
-                );
-            }
-        } else {
-            sink_error!(
-                self.logger_sink,
-                "[Engine] Failed to lock FileHandler in send_file"
-            );
-        }
+        self.primary_ref().send_file(path, id);
     }
 
     pub fn accept_file(&self, id: u32, filename: String) {
-        if let Ok(sess_guard) = self.session.lock()
-            && let Some(sess) = sess_guard.as_ref()
-        {
-            // We are receiving a file
-            self.receiving_files.store(true, Ordering::SeqCst);
-            sess.send_sctp_event(SctpEvents::SendAccept { id });
-        }
-        // Notify local FileHandler to start writing
-        if let Ok(fh_guard) = self.file_handler.lock()
-            && let Some(fh) = fh_guard.as_ref()
-        {
-            let _ = fh.send(FileHandlerEvents::WriteFile { filename, id });
-        }
+        self.primary_ref().accept_file(id, filename);
     }
 
     pub fn reject_file(&self, id: u32) {
-        if let Ok(sess_guard) = self.session.lock()
-            && let Some(sess) = sess_guard.as_ref()
-        {
-            sess.send_sctp_event(SctpEvents::SendReject { id });
-        }
+        self.primary_ref().reject_file(id);
     }
 
     pub fn cancel_file(&self, id: u32) {
-        // Cancel can be local sender cancelling, or local receiver cancelling
-        // Notify Session to send Cancel msg
-        if let Ok(sess_guard) = self.session.lock()
-            && let Some(sess) = sess_guard.as_ref()
-        {
-            sess.send_sctp_event(SctpEvents::SendCancel { id });
+        self.primary_ref().cancel_file(id);
+    }
+
+    pub fn set_audio_mute(&mut self, mute: bool) {
+        self.primary_mut().set_audio_mute(mute);
+    }
+
+    /// Sends DTMF digits on the primary call as RFC 4733 telephone events, so
+    /// calls bridged into phone systems can navigate IVR menus.
+    ///
+    /// `digits` may hold `0-9`, `*`, `#`, `A-D` and `,` (a two second
+    /// pause). Digits are queued and played one after another; nothing is
+    /// sent if the peer did not negotiate `telephone-event`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtcError::MediaTransport` (`InvalidDtmf`) for any other
+    /// character, in which case nothing is sent.
+    pub fn send_dtmf(&self, digits: &str) -> Result<(), RtcError> {
+        Ok(self.primary_ref().send_dtmf(digits)?)
+    }
+
+    /// Enables or disables push-to-talk on the primary call. While enabled,
+    /// the microphone is only sent while the key is held, as reported by
+    /// [`set_push_to_talk_pressed`](Self::set_push_to_talk_pressed).
+    /// [`EngineEvent::Speaking`] reports voice activity either way.
+    pub fn set_push_to_talk(&self, enabled: bool) {
+        self.primary_ref().set_push_to_talk(enabled);
+    }
+
+    /// Reports whether the push-to-talk key is held.
+    pub fn set_push_to_talk_pressed(&self, pressed: bool) {
+        self.primary_ref().set_push_to_talk_pressed(pressed);
+    }
+
+    #[must_use]
+    pub fn push_to_talk(&self) -> bool {
+        self.primary_ref().push_to_talk()
+    }
+
+    /// Turns the camera of the primary call off (`true`) or back on.
+    ///
+    /// While off, the camera is released and a camera-off picture is sent at
+    /// two frames per second, so the video track stays up and no
+    /// renegotiation is needed. The peer reports it as
+    /// [`EngineEvent::RemoteVideoMuted`].
+    pub fn set_video_muted(&mut self, muted: bool) {
+        self.primary_mut().set_video_muted(muted);
+    }
+
+    #[must_use]
+    pub fn video_muted(&self) -> bool {
+        self.primary_ref().video_muted()
+    }
+
+    /// Switches the camera of the primary call, mid-call included.
+    ///
+    /// Devices can be listed with
+    /// [`list_cameras`](crate::camera_manager::devices::list_cameras).
+    pub fn switch_camera(&mut self, camera_id: i32) -> Option<String> {
+        self.primary_mut().switch_camera(camera_id)
+    }
+
+    /// Changes the capture resolution, frame rate and pixel format of the
+    /// primary call. Returns the capture status when media is running.
+    pub fn set_capture_settings(&mut self, settings: CaptureSettings) -> Option<String> {
+        self.primary_mut().set_capture_settings(settings)
+    }
+
+    #[must_use]
+    pub fn capture_settings(&self) -> CaptureSettings {
+        self.primary_ref().capture_settings()
+    }
+
+    /// Codecs, capture size and data channel support, to answer a
+    /// `CapabilityQuery`.
+    #[must_use]
+    pub fn local_capabilities(&self) -> Capabilities {
+        self.primary_ref().local_capabilities()
+    }
+
+    /// Selects the built-in test sources (color bars with a clock burn-in,
+    /// sine tone) for the primary call.
+    pub fn set_test_sources(&mut self, video: bool, audio: bool) {
+        self.primary_mut().set_test_sources(video, audio);
+    }
+
+    /// `(video, audio)`: whether the primary call uses the test sources.
+    #[must_use]
+    pub fn test_sources(&self) -> (bool, bool) {
+        self.primary_ref().test_sources()
+    }
+
+    /// Selects the microphone of the primary call by name (`None` = host
+    /// default). Devices can be listed with
+    /// [`list_input_devices`](crate::media_agent::audio_devices::list_input_devices).
+    pub fn set_audio_input(&mut self, device: Option<String>) {
+        self.primary_mut().set_audio_input(device);
+    }
+
+    /// Selects the speaker of the primary call by name (`None` = host
+    /// default), mid-call included. Devices can be listed with
+    /// [`list_output_devices`](crate::media_agent::audio_devices::list_output_devices).
+    pub fn set_audio_output(&mut self, device: Option<String>) {
+        self.primary_mut().set_audio_output(device);
+    }
+
+    /// `(input, output)` device names of the primary call.
+    #[must_use]
+    pub fn audio_devices(&self) -> (Option<String>, Option<String>) {
+        self.primary_ref().audio_devices()
+    }
+
+    /// Sets how loud `peer` plays (1.0 = as received, up to
+    /// [`MAX_GAIN`](crate::media_agent::audio_mixer::MAX_GAIN)), mid-call
+    /// included. Only local playout changes. Unknown peers are ignored.
+    pub fn set_peer_volume(&mut self, peer: &str, volume: f32) {
+        if let Some(pc) = self.peers.get_mut(peer) {
+            pc.set_remote_volume(volume);
         }
-        // Also notify local FileHandler to stop
-        if let Ok(fh_guard) = self.file_handler.lock()
-            && let Some(fh) = fh_guard.as_ref()
-        {
-            let _ = fh.send(FileHandlerEvents::Cancel(id));
+    }
+
+    /// Playout volume of `peer`, 1.0 for unknown peers.
+    #[must_use]
+    pub fn peer_volume(&self, peer: &str) -> f32 {
+        self.peers
+            .get(peer)
+            .map_or(1.0, PeerConnection::remote_volume)
+    }
+
+    /// Stops or resumes playing `peer`'s audio, locally only: `peer` still
+    /// sends it and is not told.
+    pub fn set_peer_muted(&mut self, peer: &str, muted: bool) {
+        if let Some(pc) = self.peers.get_mut(peer) {
+            pc.set_remote_audio_muted(muted);
         }
     }
 
-    pub fn set_audio_mute(&mut self, mute: bool) {
-        self.media_transport.set_audio_mute(mute);
+    #[must_use]
+    pub fn is_peer_muted(&self, peer: &str) -> bool {
+        self.peers
+            .get(peer)
+            .is_some_and(PeerConnection::remote_audio_muted)
     }
 
-    /// Polls for `EngineEvent`s and processes them.
-    /// This method is called repeatedly to drive the engine's state.
+    /// Records the primary call to a Matroska file: the H.264 streams as
+    /// sent/received plus PCM audio, one track per stream. `path` defaults
+    /// to a timestamped file in `[Media] recording_dir`.
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the internal session lock or file handler lock is poisoned.
-    #[allow(clippy::expect_used)]
-    pub fn poll(&mut self) -> Vec<EngineEvent> {
-        // keep ICE reactive
-        self.cm.drain_ice_events();
-
-        if self
-            .session
-            .lock()
-            .expect("session lock poisoned")
-            .is_none()
-            && let Ok((sock, peer)) = self.cm.ice_agent.get_data_channel_socket()
-        {
-            if let Err(e) = sock.connect(peer) {
-                let _ = self
-                    .event_tx
-                    .send(EngineEvent::Error(format!("socket.connect: {e}")));
-            } else {
-                let local = sock
-                    .local_addr()
-                    .unwrap_or_else(|_| SocketAddr::from(([0, 0, 0, 0], 0)));
-                let _ = self.event_tx.send(EngineEvent::IceNominated {
-                    local,
-                    remote: peer,
-                });
+    /// Returns `RtcError::MediaAgent` (`Io`) if a recording is already
+    /// running or the file cannot be created.
+    pub fn start_recording(
+        &mut self,
+        path: Option<PathBuf>,
+        streams: RecordStreams,
+    ) -> Result<PathBuf, RtcError> {
+        Ok(self.primary_mut().start_recording(path, streams)?)
+    }
 
-                self.cm.stop_ice_worker();
-
-                // --- IceRole -> DtlsRole ---
-                let dtls_role = match self.cm.ice_agent.role {
-                    IceRole::Controlling => DtlsRole::Server,
-                    IceRole::Controlled => DtlsRole::Client,
-                };
-
-                // Retrieve the remote fingerprint stored in CM
-                let remote_fp = self.cm.remote_fingerprint.clone();
-
-                // --- blocking DTLS handshake ---
-                // Modified to destructure the tuple
-                match dtls::run_dtls_handshake(
-                    Arc::clone(&sock),
-                    peer,
-                    dtls_role,
-                    self.logger_sink.clone(),
-                    Duration::from_secs_f32(5.0),
-                    remote_fp,
-                    self.config.clone(),
-                ) {
-                    Ok((srtp_cfg, ssl_stream)) => {
-                        // Create FileHandler
-                        let fh = Arc::new(FileHandler::new(
-                            self.config.clone(),
-                            self.logger_sink.clone(),
-                            self.event_tx.clone(),
-                        ));
-                        *self.file_handler.lock().expect("fh lock") = Some(fh.clone());
-
-                        // Spawn DrainChunks thread
-                        let sending_files_clone = self.sending_files.clone();
-                        let fh_weak = Arc::downgrade(&fh);
-                        let session_clone = self.session.clone();
-                        // Interval from config or default
-                        let drain_interval_ms = self
-                            .config
-                            .get("file_handler", "drain_interval_ms")
-                            .and_then(|s| s.parse().ok())
-                            .unwrap_or(1);
-                        let drain_interval = Duration::from_millis(drain_interval_ms);
-
-                        thread::spawn(move || {
-                            loop {
-                                thread::sleep(drain_interval);
-                                if sending_files_clone.load(Ordering::SeqCst) {
-                                    // Check buffered amount ONCE before the burst
-                                    let mut high_buffer = false;
-                                    if let Ok(guard) = session_clone.lock() {
-                                        if let Some(sess) = guard.as_ref() {
-                                            if sess.buffered_amount() > 512_000 {
-                                                high_buffer = true;
-                                            }
-                                        }
-                                    }
-
-                                    if !high_buffer {
-                                        for _ in 0..20 {
-                                            if let Some(fh) = fh_weak.upgrade() {
-                                                if fh.send(FileHandlerEvents::DrainChunks).is_err()
-                                                {
-                                                    return;
-                                                }
-                                            } else {
-                                                return;
-                                            }
-                                        }
-                                    }
-                                } else if fh_weak.strong_count() == 0 {
-                                    break;
-                                }
-                            }
-                        });
-
-                        let sess = Session::new(SessionInitArgs {
-                            sock: Arc::clone(&sock),
-                            peer,
-                            remote_codecs: self.cm.remote_codecs().clone(),
-                            event_tx: self.event_tx.clone(),
-                            logger: self.logger_sink.clone(),
-                            cfg: SessionConfig {
-                                handshake_timeout: Duration::from_secs(10),
-                                resend_every: Duration::from_millis(250),
-                                close_timeout: Duration::from_secs(5),
-                                close_resend_every: Duration::from_millis(250),
-                            },
-                            srtp_cfg: Some(srtp_cfg),
-                            ssl_stream,
-                            is_client: dtls_role == DtlsRole::Client,
-                        });
-                        *self.session.lock().expect("session lock poisoned") = Some(sess);
-                    }
-                    Err(e) => {
-                        let _ = self
-                            .event_tx
-                            .send(EngineEvent::Error(format!("DTLS handshake failed: {e}")));
-                    }
-                };
-            }
+    /// Stops the recording; returns the saved file, if one was being written.
+    /// Recordings are also finished when the call's media stops.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtcError::MediaAgent` (`Io`) if the file cannot be finalized.
+    pub fn stop_recording(&mut self) -> Result<Option<PathBuf>, RtcError> {
+        Ok(self.primary_mut().stop_recording()?)
+    }
+
+    /// Path and elapsed time of the running recording, if any.
+    #[must_use]
+    pub fn recording(&self) -> Option<RecordingStatus> {
+        self.primary_ref().recording()
+    }
+
+    /// Caps the video bitrate (bps) of every peer connection, mid-call included.
+    pub fn set_max_bitrate(&mut self, bps: u32) {
+        self.max_bitrate = Some(bps);
+        for pc in self.peers.values_mut() {
+            pc.set_max_bitrate(bps);
         }
+    }
 
-        let mut out = Vec::new();
-        let start = Instant::now();
-        let max_events = 500;
-        let max_time = Duration::from_millis(4);
-
-        let mut processed = 0;
-        loop {
-            if processed >= max_events || start.elapsed() >= max_time {
-                break;
-            }
-            match self.ui_rx.try_recv() {
-                Ok(ev) => match ev {
-                    EngineEvent::NetworkMetrics(m) => {
-                        self.congestion_controller.on_network_metrics(m.clone());
-                        processed += 1;
-                        out.push(EngineEvent::NetworkMetrics(m.clone()));
-                    }
-
-                    EngineEvent::UpdateBitrate(br) => {
-                        if let Some(media_transport_tx) =
-                            self.media_transport.media_transport_event_tx()
-                        {
-                            let _ = media_transport_tx.send(MediaTransportEvent::UpdateBitrate(br));
-                        }
-                        processed += 1;
-                        out.push(EngineEvent::UpdateBitrate(br));
-                    }
-
-                    EngineEvent::SendFileOffer(props) => {
-                        if let Ok(sess_guard) = self.session.lock()
-                            && let Some(sess) = sess_guard.as_ref()
-                        {
-                            sess.send_sctp_event(SctpEvents::SendOffer {
-                                file_properties: props,
-                            });
-                        }
-                    }
-                    EngineEvent::SendFileChunk(id, payload) => {
-                        if let Ok(sess_guard) = self.session.lock()
-                            && let Some(sess) = sess_guard.as_ref()
-                        {
-                            sess.send_sctp_event(SctpEvents::SendChunk {
-                                file_id: id,
-                                payload,
-                            });
-                        }
-                    }
-                    EngineEvent::SendFileEnd(id) => {
-                        if let Ok(sess_guard) = self.session.lock()
-                            && let Some(sess) = sess_guard.as_ref()
-                        {
-                            sess.send_sctp_event(SctpEvents::SendEndFile { id });
-                        }
-                        // Reset sending flag if no other files? For now simple reset.
-                        self.sending_files.store(false, Ordering::SeqCst);
-                    }
-                    EngineEvent::ReceivedFileChunk(id, _seq, payload) => {
-                        // Don't expose to UI, send to FileHandler
-                        if let Ok(fh_guard) = self.file_handler.lock()
-                            && let Some(fh) = fh_guard.as_ref()
-                        {
-                            let _ = fh.send(FileHandlerEvents::WriteChunk { id, payload });
-                        }
-                    }
-                    EngineEvent::ReceivedFileEnd(id) => {
-                        self.receiving_files.store(false, Ordering::SeqCst);
-                        out.push(EngineEvent::ReceivedFileEnd(id));
-                        processed += 1;
-                    }
-                    EngineEvent::ReceivedFileOffer(props) => {
-                        out.push(EngineEvent::ReceivedFileOffer(props));
-                        processed += 1;
-                    }
-                    EngineEvent::ReceivedFileAccept(id) => {
-                        // Peer accepted our file. Notify FileHandler to start sending.
-                        if let Ok(fh_guard) = self.file_handler.lock()
-                            && let Some(fh) = fh_guard.as_ref()
-                        {
-                            let _ = fh.send(FileHandlerEvents::RemoteAccepted(id));
-                        }
-                        out.push(EngineEvent::ReceivedFileAccept(id));
-                        processed += 1;
-                    }
-                    EngineEvent::ToggleAudio(mute) => {
-                        self.media_transport.set_audio_mute(mute);
-                        // We push it out so the UI can update its state if the event came from elsewhere
-                        out.push(EngineEvent::ToggleAudio(mute));
-                        processed += 1;
-                    }
-
-                    _ => {
-                        processed += 1;
-                        out.push(ev);
-                    }
-                },
-                Err(_) => break,
+    /// Runs every peer connection's timers, and times them, on `timers`.
+    /// With [`Timers::manual`] a test drives a call on virtual time. Set it
+    /// before calling.
+    pub fn set_timers(&mut self, timers: Timers) {
+        for pc in self.peers.values_mut() {
+            pc.set_timers(timers.clone());
+        }
+        self.timers = Some(timers);
+    }
+
+    /// Applies a quality preset to every peer connection.
+    pub fn set_quality_preset(&mut self, preset: QualityPreset) {
+        let cap = preset.max_bitrate(&self.config);
+        let cap =
+            LowDataMode::from_config(&self.config).map_or(cap, |mode| cap.min(mode.max_bitrate));
+        self.set_max_bitrate(cap);
+    }
+
+    /// Switches low data mode on or off. The bitrate cap changes right away;
+    /// the frame rate with the next `set_capture_settings` and the audio
+    /// codec with the next call.
+    pub fn set_low_data_mode(&mut self, on: bool) {
+        let mut config = (*self.config).clone();
+        config.set("Media", "low_data_mode", on.to_string());
+        self.config = Arc::new(config);
+        self.set_max_bitrate(configured_max_bitrate(&self.config));
+    }
+
+    /// Records the logged-in user, the `{user}` of the video watermark, for
+    /// the peer connections created from now on.
+    pub fn set_local_user(&mut self, username: &str) {
+        if self.config.get("Signaling", "username") == Some(username) {
+            return;
+        }
+        let mut config = (*self.config).clone();
+        config.set("Signaling", "username", username);
+        self.config = Arc::new(config);
+    }
+
+    /// Whether low data mode is on.
+    #[must_use]
+    pub fn low_data_mode(&self) -> bool {
+        LowDataMode::is_enabled(&self.config)
+    }
+
+    /// Switches to a reloaded configuration.
+    ///
+    /// A changed bitrate cap applies to every peer connection right away;
+    /// connections created from now on (the next call) use all of
+    /// `config`. The next [`poll`](Self::poll) returns
+    /// [`EngineEvent::ConfigUpdated`] with the [`LIVE_SETTINGS`] that changed.
+    pub fn update_config(&mut self, config: Arc<Config>) {
+        let value = |config: &Config, section: &str, key: &str| {
+            if section.is_empty() {
+                config.get_global(key).map(str::to_owned)
+            } else {
+                config.get(section, key).map(str::to_owned)
             }
+        };
+        let changed: Vec<String> = LIVE_SETTINGS
+            .iter()
+            .filter(|(section, key)| {
+                value(&self.config, section, key) != value(&config, section, key)
+            })
+            .map(|(section, key)| {
+                if section.is_empty() {
+                    (*key).to_owned()
+                } else {
+                    format!("[{section}] {key}")
+                }
+            })
+            .collect();
+
+        let cap = configured_max_bitrate(&config);
+        let cap_changed = cap != configured_max_bitrate(&self.config);
+        self.config = config.clone();
+        if cap_changed {
+            self.set_max_bitrate(cap);
         }
+        sink_info!(
+            self.logger_sink,
+            "[Engine] configuration reloaded, live changes: {:?}",
+            changed
+        );
+        self.pending
+            .push(EngineEvent::ConfigUpdated { config, changed });
+    }
 
-        out
+    /// Bitrate cap (bps) of the primary peer.
+    #[must_use]
+    pub fn max_bitrate(&self) -> u32 {
+        self.primary_ref().max_bitrate()
+    }
+
+    /// Sets the direction the primary peer offers from the next negotiation on.
+    pub fn set_local_direction(&mut self, direction: MediaDirection) {
+        self.primary_mut().set_local_direction(direction);
+    }
+
+    /// Puts the primary call on hold and returns the re-offer to send.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the re-offer cannot be created.
+    pub fn hold(&mut self) -> Result<Option<String>, ConnectionError> {
+        self.primary_mut().hold()
+    }
+
+    /// Takes the primary call off hold and returns the re-offer to send.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the re-offer cannot be created.
+    pub fn resume(&mut self) -> Result<Option<String>, ConnectionError> {
+        self.primary_mut().resume()
+    }
+
+    /// `true` if we put the primary call on hold.
+    #[must_use]
+    pub fn is_on_hold(&self) -> bool {
+        self.primary_ref().is_on_hold()
+    }
+
+    /// `true` if the remote put the primary call on hold.
+    #[must_use]
+    pub fn is_remote_on_hold(&self) -> bool {
+        self.primary_ref().is_remote_on_hold()
+    }
+
+    /// Adds a video track (screen share, second camera) to the primary call.
+    ///
+    /// Returns the track id and the re-offer to send to the remote.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the track cannot be started or the
+    /// re-offer cannot be created.
+    pub fn add_video_track(
+        &mut self,
+        source: VideoSource,
+    ) -> Result<(TrackId, Option<String>), ConnectionError> {
+        self.primary_mut().add_video_track(source)
+    }
+
+    /// Removes a track added with [`add_video_track`](Self::add_video_track)
+    /// and returns the re-offer to send.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if `id` is unknown or the re-offer cannot
+    /// be created.
+    pub fn remove_track(&mut self, id: TrackId) -> Result<Option<String>, ConnectionError> {
+        self.primary_mut().remove_track(id)
+    }
+
+    /// Polls the primary peer for `EngineEvent`s and processes them.
+    ///
+    /// Events are also delivered to subscribers. Other peers must be driven
+    /// with [`poll_peers`](Self::poll_peers).
+    pub fn poll(&mut self) -> Vec<EngineEvent> {
+        let mut events = std::mem::take(&mut self.pending);
+        events.extend(self.primary_mut().poll());
+        if !self.bus.is_empty() {
+            for event in &events {
+                self.bus.publish(&PeerEvent {
+                    peer: self.primary.clone(),
+                    event: event.clone(),
+                });
+            }
+        }
+        events
     }
 
     /// Returns a snapshot of the local and remote video frames.
     #[must_use]
     pub fn snapshot_frames(&self) -> (Option<VideoFrame>, Option<VideoFrame>) {
-        self.media_transport.snapshot_frames()
+        self.primary_ref().snapshot_frames()
+    }
+
+    /// Aggregate connection state of the primary peer.
+    #[must_use]
+    pub fn connection_state(&self) -> PeerConnectionState {
+        self.primary_ref().connection_state()
+    }
+
+    /// Statistics snapshot of the primary peer.
+    #[must_use]
+    pub fn get_stats(&self) -> StatsReport {
+        self.primary_ref().get_stats()
+    }
+
+    /// Statistics snapshot of every peer connection.
+    #[must_use]
+    pub fn get_all_stats(&self) -> HashMap<PeerId, StatsReport> {
+        self.peers
+            .iter()
+            .map(|(peer, pc)| (peer.clone(), pc.get_stats()))
+            .collect()
     }
 
     /// Starts the media transport event loops.
     pub fn start_media_transport(&mut self) {
-        self.media_transport.start_event_loops(self.session.clone());
-        sink_info!(
-            self.logger_sink,
-            "[Engine] Sending Established Event to Media Transport"
-        );
-        if let Some(media_transport_event_tx) = self.media_transport.media_transport_event_tx() {
-            let _ = media_transport_event_tx.send(MediaTransportEvent::Established);
-        }
+        self.primary_mut().start_media_transport();
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
@@ -1,8 +1,13 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use crate::{
-    congestion_controller::NetworkMetrics, log::log_msg::LogMsg,
-    media_transport::media_transport_event::RtpIn, sctp::events::SctpFileProperties,
+    config::Config,
+    congestion_controller::NetworkMetrics,
+    core::connection_state::{DtlsState, IceConnectionState, PeerConnectionState},
+    log::log_msg::LogMsg,
+    media_transport::media_transport_event::RtpIn,
+    rtp::audio_level::AudioLevel,
+    sctp::events::SctpFileProperties,
 };
 
 /// Represents events that can be emitted by the `Engine` to the UI or other components.
@@ -19,6 +24,12 @@ pub enum EngineEvent {
     },
     /// The WebRTC connection has been established.
     Established,
+    /// The ICE transport changed state.
+    IceConnectionStateChanged(IceConnectionState),
+    /// The DTLS transport changed state.
+    DtlsStateChanged(DtlsState),
+    /// The aggregate peer connection state changed.
+    PeerConnectionStateChanged(PeerConnectionState),
     /// The WebRTC connection is closing.
     Closing {
         graceful: bool,
@@ -60,4 +71,62 @@ pub enum EngineEvent {
 
     /// Updates the mute state of the audio capture (true = muted, false = active).
     ToggleAudio(bool),
+
+    /// The remote announced an extra video track (e.g. a screen share).
+    RemoteTrackAdded {
+        ssrc: u32,
+        label: String,
+    },
+    /// The remote stopped announcing an extra video track.
+    RemoteTrackRemoved {
+        ssrc: u32,
+    },
+    /// The local camera stopped delivering frames (unplugged, driver error).
+    /// A placeholder is sent while the device is re-opened.
+    CameraLost {
+        camera_id: i32,
+        reason: String,
+    },
+    /// The local camera (re)opened and is being captured again.
+    CameraRecovered {
+        camera_id: i32,
+    },
+    /// Voice activity started or stopped on one side of the call.
+    Speaking {
+        /// `false` for the local microphone, `true` for the peer.
+        remote: bool,
+        speaking: bool,
+    },
+    /// The peer turned its camera off (`true`) or back on, as told by its
+    /// video frame rate.
+    RemoteVideoMuted(bool),
+    /// Audio level the peer reported in its packets (RFC 6464), throttled
+    /// for display.
+    RemoteAudioLevel(AudioLevel),
+    /// The peer lost our video stream `ssrc` (RTCP PLI) and needs a
+    /// keyframe to resume decoding.
+    KeyframeRequested {
+        ssrc: u32,
+    },
+    /// The largest datagram the path carries, found by probing
+    /// (`[ICE] mtu_discovery`).
+    PathMtu(usize),
+    /// Sends on the session socket failed since the last such event, at
+    /// most one a second: packets are being lost before they reach the
+    /// network.
+    SocketError {
+        /// Datagrams dropped because the socket's send buffer was full.
+        send_buffer_full: u64,
+        /// Sends refused for other reasons (no route, permission).
+        send_errors: u64,
+        /// The OS error of the last failed send.
+        last_error: Option<String>,
+    },
+    /// The configuration file was reloaded. `changed` names the settings
+    /// applied live (`[Section] key`, or the key alone for a global);
+    /// the rest take effect from the next call.
+    ConfigUpdated {
+        config: Arc<Config>,
+        changed: Vec<String>,
+    },
 }
@@ -0,0 +1,189 @@
+//! Per-frame latency through the media pipeline.
+//!
+//! Every video frame is timed at the end of each stage against its origin:
+//! the capture time on the send side (capture → encode → packetize → send)
+//! and the arrival of its first RTP packet on the receive side (receive →
+//! depacketize → decode → render). The values are cumulative, so the gap
+//! between two consecutive stages is the time spent in the later one. The
+//! last [`WINDOW`] samples of each stage are kept for percentiles.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::media_agent::utils::now_millis;
+
+/// Samples kept per stage.
+pub const WINDOW: usize = 300;
+
+/// A point of the pipeline a frame is timed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyStage {
+    /// Encoded and handed to the transport.
+    Encode,
+    /// Split into RTP payloads.
+    Packetize,
+    /// Last packet written to the socket.
+    Send,
+    /// Reassembled from its packets.
+    Depacketize,
+    /// Decoded to an image.
+    Decode,
+    /// First shown on screen.
+    Render,
+}
+
+impl LatencyStage {
+    pub const ALL: [Self; 6] = [
+        Self::Encode,
+        Self::Packetize,
+        Self::Send,
+        Self::Depacketize,
+        Self::Decode,
+        Self::Render,
+    ];
+
+    /// Name used in the stats JSON.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Encode => "encode",
+            Self::Packetize => "packetize",
+            Self::Send => "send",
+            Self::Depacketize => "depacketize",
+            Self::Decode => "decode",
+            Self::Render => "render",
+        }
+    }
+
+    /// Label shown in the GUI.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Encode => "Encode",
+            Self::Packetize => "Packetize",
+            Self::Send => "Send",
+            Self::Depacketize => "Depacketize",
+            Self::Decode => "Decode",
+            Self::Render => "Render",
+        }
+    }
+}
+
+/// Percentiles of one stage, in milliseconds since the frame's origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub stage: LatencyStage,
+    /// Samples the percentiles are taken from (at most [`WINDOW`]).
+    pub samples: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+}
+
+/// The last [`WINDOW`] samples of every stage.
+#[derive(Debug, Default)]
+pub struct LatencySamples {
+    samples: [VecDeque<u64>; LatencyStage::ALL.len()],
+}
+
+impl LatencySamples {
+    pub fn record(&mut self, stage: LatencyStage, ms: u64) {
+        let window = &mut self.samples[stage as usize];
+        if window.len() == WINDOW {
+            window.pop_front();
+        }
+        window.push_back(ms);
+    }
+
+    /// Records the time elapsed since `origin_ms` (UNIX milliseconds).
+    pub fn record_since(&mut self, stage: LatencyStage, origin_ms: u128) {
+        let ms = now_millis().saturating_sub(origin_ms);
+        self.record(stage, u64::try_from(ms).unwrap_or(u64::MAX));
+    }
+
+    /// Percentiles of the stages with samples, in pipeline order.
+    #[must_use]
+    pub fn stats(&self) -> Vec<LatencyStats> {
+        LatencyStage::ALL
+            .into_iter()
+            .filter_map(|stage| {
+                let mut sorted: Vec<u64> = self.samples[stage as usize].iter().copied().collect();
+                if sorted.is_empty() {
+                    return None;
+                }
+                sorted.sort_unstable();
+                let pct = |p: usize| sorted[(sorted.len() - 1) * p / 100];
+                Some(LatencyStats {
+                    stage,
+                    samples: sorted.len(),
+                    p50_ms: pct(50),
+                    p95_ms: pct(95),
+                    p99_ms: pct(99),
+                })
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.iter_mut().for_each(VecDeque::clear);
+    }
+}
+
+/// [`LatencySamples`] shared by the pipeline threads of one call.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTracer(Arc<Mutex<LatencySamples>>);
+
+impl LatencyTracer {
+    /// Records the time elapsed since `origin_ms` (UNIX milliseconds).
+    pub fn record_since(&self, stage: LatencyStage, origin_ms: u128) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .record_since(stage, origin_ms);
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> Vec<LatencyStats> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn percentiles_over_the_last_window() {
+        let mut samples = LatencySamples::default();
+        for ms in 1..=WINDOW as u64 + 100 {
+            samples.record(LatencyStage::Decode, ms);
+        }
+        samples.record(LatencyStage::Encode, 7);
+
+        let stats = samples.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[0],
+            LatencyStats {
+                stage: LatencyStage::Encode,
+                samples: 1,
+                p50_ms: 7,
+                p95_ms: 7,
+                p99_ms: 7,
+            }
+        );
+        // Only 101..=400 are left
+        assert_eq!(stats[1].stage, LatencyStage::Decode);
+        assert_eq!(stats[1].samples, WINDOW);
+        assert_eq!(
+            (stats[1].p50_ms, stats[1].p95_ms, stats[1].p99_ms),
+            (250, 385, 397)
+        );
+    }
+}
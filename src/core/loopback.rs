@@ -0,0 +1,120 @@
+//! Loopback test calls: the client calls itself, with no signaling server.
+//!
+//! A second [`Engine`] in the same process answers the local engine's offer
+//! and the two connect over ICE, DTLS and SRTP like any two peers. The far
+//! end sends a test pattern with its microphone muted, and plays back what
+//! it receives, so the local camera shows up again once it has been encoded,
+//! packetized, sent, received and decoded ([`LoopbackPeer::received_frame`]),
+//! and the local microphone is heard on the speaker.
+
+use std::sync::{Arc, atomic::AtomicBool};
+
+use crate::{
+    config::Config,
+    connection_manager::connection_error::ConnectionError,
+    core::{engine::Engine, events::EngineEvent},
+    log::{
+        log_context::{ContextLogSink, LogContext},
+        log_sink::LogSink,
+    },
+    media_agent::video_frame::VideoFrame,
+    sink_warn,
+};
+
+/// Name of the far end in logs and the UI.
+pub const LOOPBACK_PEER: &str = "loopback";
+
+/// The far end of a loopback call.
+///
+/// Drive it with [`poll`](Self::poll) alongside the local engine; the local
+/// side's events are handled as in any call. Dropping it hangs up.
+pub struct LoopbackPeer {
+    engine: Engine,
+    logger: Arc<dyn LogSink>,
+    established: bool,
+}
+
+impl LoopbackPeer {
+    /// Answers an offer from `local`, and gives it the answer and the
+    /// candidates of both sides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either side fails to negotiate.
+    pub fn call(
+        local: &mut Engine,
+        logger: &Arc<dyn LogSink>,
+        config: Arc<Config>,
+    ) -> Result<Self, ConnectionError> {
+        let logger: Arc<dyn LogSink> = Arc::new(ContextLogSink::new(
+            logger.clone(),
+            LogContext::peer(LOOPBACK_PEER),
+        ));
+        let files = Arc::new(AtomicBool::new(false));
+        let mut engine = Engine::new(logger.clone(), config, files.clone(), files);
+        // Only the local side opens the camera and microphone
+        engine.set_test_sources(true, true);
+        engine.set_audio_mute(true);
+
+        let offer = local
+            .negotiate()?
+            .ok_or_else(|| ConnectionError::Negotiation("no offer generated".into()))?;
+        let answer = engine
+            .apply_remote_sdp(&offer)?
+            .ok_or_else(|| ConnectionError::Negotiation("no answer generated".into()))?;
+        local.apply_remote_sdp(&answer)?;
+        for line in local.local_candidates_as_sdp_lines() {
+            engine.apply_remote_candidate(&line)?;
+        }
+        for line in engine.local_candidates_as_sdp_lines() {
+            local.apply_remote_candidate(&line)?;
+        }
+
+        Ok(Self {
+            engine,
+            logger,
+            established: false,
+        })
+    }
+
+    /// Handles the far end's events; call it every tick of the local loop.
+    pub fn poll(&mut self) {
+        for ev in self.engine.poll() {
+            match ev {
+                EngineEvent::IceNominated { .. } => {
+                    if let Err(e) = self.engine.start() {
+                        sink_warn!(self.logger, "[Loopback] could not start: {}", e);
+                    }
+                }
+                EngineEvent::Established => {
+                    self.established = true;
+                    self.engine.start_media_transport();
+                }
+                EngineEvent::Closed => {
+                    self.established = false;
+                    self.engine.close_session();
+                }
+                EngineEvent::Error(e) => sink_warn!(self.logger, "[Loopback] {}", e),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the far end's session is up.
+    #[must_use]
+    pub const fn is_established(&self) -> bool {
+        self.established
+    }
+
+    /// The latest local video as the far end decoded it.
+    #[must_use]
+    pub fn received_frame(&self) -> Option<VideoFrame> {
+        self.engine.snapshot_frames().1
+    }
+}
+
+impl Drop for LoopbackPeer {
+    fn drop(&mut self) {
+        self.engine.stop();
+    }
+}
@@ -1,8 +1,26 @@
 //! The `core` module contains the main WebRTC engine logic, session management,
 //! and event handling.
+pub mod buffer_pool;
+pub mod clock;
+pub mod connection_state;
+pub mod consent;
 mod constants;
 pub mod engine;
 pub mod events;
+pub mod latency;
+pub mod loopback;
+pub mod path_mtu;
+pub mod peer_connection;
 pub mod protocol;
+pub mod quality;
+pub mod reactor;
 pub mod result;
+pub mod rtc_event_log;
 pub mod session;
+pub mod socket_stats;
+pub mod stats;
+pub mod subscription;
+pub mod thread_join;
+pub mod timers;
+pub mod transport;
+pub mod udp_batch;
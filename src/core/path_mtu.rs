@@ -0,0 +1,241 @@
+//! Size of the datagrams a session sends, and its discovery.
+//!
+//! `[ICE] mtu` is the largest UDP payload the session puts on the wire,
+//! 1200 bytes by default, which crosses almost any path. DTLS fragments its
+//! handshake to it, SCTP packets leave room for the DTLS record around
+//! them and the H.264 packetizer leaves room for the SRTP tag.
+//!
+//! A VPN or PPPoE link can make even that too much, and a plain Ethernet
+//! path takes more. With `[ICE] mtu_discovery = true` the session probes
+//! the path once established, the way DPLPMTUD does (RFC 8899): probes are
+//! session messages padded to the size tried and sent with the Don't
+//! Fragment bit, and the peer acknowledges each one it receives. A probe
+//! of [`MIN_MTU`] first checks that the peer answers probes at all; then a
+//! binary search finds the largest size that gets through, a size counting
+//! as too big once [`ATTEMPTS`] probes of it went unanswered.
+//!
+//! The discovered size reaches the packetizer, DTLS and SCTP. SCTP cannot
+//! resize an association, so with discovery on none is set up until the
+//! search ends: file transfers wait for it. The Don't Fragment bit is set
+//! on every datagram of the session during the search only, so that those
+//! still get through, fragmented, on a path narrower than configured.
+//! Setting it needs the `udp-batch` feature on Linux: elsewhere a probe
+//! too big for the path would be fragmented and still get through, so
+//! none is sent, the configured size is kept and a warning is logged.
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use crate::{config::Config, srtp::constants::AUTH_TAG_LEN};
+
+pub const DEFAULT_MTU: usize = 1200;
+/// Smallest datagram size accepted, and the first probe.
+pub const MIN_MTU: usize = 576;
+/// A 1500-byte Ethernet frame less the IPv4 and UDP headers.
+pub const MAX_MTU: usize = 1472;
+/// The same over IPv6, whose header is 20 bytes longer.
+const MAX_MTU_V6: usize = 1452;
+/// Room for a DTLS record around an SCTP packet: header, explicit IV or
+/// nonce, MAC and padding.
+const DTLS_RECORD_OVERHEAD: usize = 64;
+/// How long an unanswered probe is waited for.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Probes of a size sent before it counts as too big.
+pub const ATTEMPTS: u32 = 3;
+/// The search stops once the bounds are this close.
+const RESOLUTION: usize = 16;
+
+/// `[ICE] mtu` and `[ICE] mtu_discovery`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtuConfig {
+    /// Largest UDP payload the session sends.
+    pub mtu: usize,
+    /// Whether the path is probed for a better value.
+    pub discovery: bool,
+}
+
+impl Default for MtuConfig {
+    fn default() -> Self {
+        Self {
+            mtu: DEFAULT_MTU,
+            discovery: false,
+        }
+    }
+}
+
+impl MtuConfig {
+    /// Reads the `[ICE]` MTU keys; a missing or out of range `mtu` is 1200.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let mtu = config
+            .get_non_empty("ICE", "mtu")
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|mtu| (MIN_MTU..=MAX_MTU).contains(mtu))
+            .unwrap_or(DEFAULT_MTU);
+        Self {
+            mtu,
+            discovery: config.get_non_empty("ICE", "mtu_discovery") == Some("true"),
+        }
+    }
+}
+
+/// The packet size the H.264 packetizer gets from a datagram size: what is
+/// left once SRTP appends its tag.
+#[must_use]
+pub const fn rtp_mtu(mtu: usize) -> usize {
+    mtu.saturating_sub(AUTH_TAG_LEN)
+}
+
+/// The SCTP packet size that still fits `mtu` once inside a DTLS record.
+#[must_use]
+pub const fn sctp_payload_size(mtu: usize) -> usize {
+    mtu.saturating_sub(DTLS_RECORD_OVERHEAD)
+}
+
+/// Largest size worth probing towards `peer`.
+#[must_use]
+pub const fn max_probe(peer: SocketAddr) -> usize {
+    if peer.is_ipv6() { MAX_MTU_V6 } else { MAX_MTU }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    id: u32,
+    size: usize,
+    sent_at: Instant,
+    attempts: u32,
+}
+
+/// The search for the path MTU: which probe to send next, and what the
+/// answers tell.
+#[derive(Debug)]
+pub struct MtuProber {
+    /// Largest size that got through; `None` until the peer answered one.
+    low: Option<usize>,
+    /// Smallest size known to be too big.
+    high: usize,
+    probe: Option<Probe>,
+    next_id: u32,
+    /// The peer never answered the first probe.
+    gave_up: bool,
+}
+
+impl MtuProber {
+    /// A search up to `max` bytes.
+    #[must_use]
+    pub const fn new(max: usize) -> Self {
+        Self {
+            low: None,
+            high: max + 1,
+            probe: None,
+            next_id: 0,
+            gave_up: false,
+        }
+    }
+
+    /// The probe to send at `now` as `(id, size)`, if any: the next size
+    /// to try, or the last one again after [`PROBE_TIMEOUT`] unanswered.
+    pub fn poll(&mut self, now: Instant) -> Option<(u32, usize)> {
+        if let Some(probe) = &mut self.probe {
+            if now.saturating_duration_since(probe.sent_at) < PROBE_TIMEOUT {
+                return None;
+            }
+            if probe.attempts < ATTEMPTS {
+                probe.attempts += 1;
+                probe.sent_at = now;
+                return Some((probe.id, probe.size));
+            }
+            let size = probe.size;
+            self.probe = None;
+            if self.low.is_none() {
+                // Not even the smallest one: the peer does not answer probes
+                self.gave_up = true;
+            } else {
+                self.high = size;
+            }
+        }
+        let size = self.next_size()?;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.probe = Some(Probe {
+            id: self.next_id,
+            size,
+            sent_at: now,
+            attempts: 1,
+        });
+        Some((self.next_id, size))
+    }
+
+    /// The peer acknowledged probe `id`.
+    pub fn on_ack(&mut self, id: u32) {
+        if let Some(probe) = self.probe.filter(|p| p.id == id) {
+            self.low = Some(probe.size);
+            self.probe = None;
+        }
+    }
+
+    /// Whether the search is over.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.probe.is_none() && self.next_size().is_none()
+    }
+
+    /// The path MTU found, once done; `None` if the peer never answered.
+    #[must_use]
+    pub fn result(&self) -> Option<usize> {
+        self.low.filter(|_| self.is_done())
+    }
+
+    fn next_size(&self) -> Option<usize> {
+        match self.low {
+            _ if self.gave_up => None,
+            None => Some(MIN_MTU),
+            Some(low) if self.high - low > RESOLUTION => Some(low + (self.high - low) / 2),
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    /// Runs a search over a path that carries `path_mtu` bytes, with a
+    /// peer that answers probes if `answers`.
+    fn discover(path_mtu: usize, answers: bool) -> (Option<usize>, Duration) {
+        let mut prober = MtuProber::new(MAX_MTU);
+        let t0 = Instant::now();
+        let mut now = t0;
+        while !prober.is_done() {
+            if let Some((id, size)) = prober.poll(now)
+                && answers
+                && size <= path_mtu
+            {
+                prober.on_ack(id);
+            }
+            now += Duration::from_millis(100);
+        }
+        (prober.result(), now - t0)
+    }
+
+    #[test]
+    fn the_largest_size_through_the_path_is_found() {
+        // A VPN or PPPoE link below the configured 1200 bytes
+        let (found, _) = discover(1130, true);
+        let found = found.unwrap();
+        assert!(found <= 1130 && found > 1130 - RESOLUTION, "{found}");
+
+        let (found, _) = discover(MAX_MTU, true);
+        assert!(found.unwrap() > MAX_MTU - RESOLUTION);
+
+        // A peer that ignores probes: given up after the first size
+        let (found, took) = discover(MAX_MTU, false);
+        assert_eq!(found, None);
+        assert!(took <= PROBE_TIMEOUT * (ATTEMPTS + 1));
+
+        assert_eq!(rtp_mtu(DEFAULT_MTU), 1190);
+        assert_eq!(MtuConfig::default().mtu, DEFAULT_MTU);
+    }
+}
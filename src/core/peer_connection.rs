@@ -0,0 +1,1288 @@
+//! A single peer connection.
+//!
+//! The [`PeerConnection`] struct owns everything needed to talk to one remote
+//! peer: ICE, SDP negotiation, DTLS, the `Session` and the media transport.
+//! The [`Engine`](super::engine::Engine) keeps one of these per remote peer.
+
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    camera_manager::capture_settings::CaptureSettings,
+    config::Config,
+    congestion_controller::CongestionController,
+    connection_manager::ice_phase::IcePhase,
+    connection_manager::{
+        ConnectionManager, OutboundSdp, connection_error::ConnectionError,
+        media_direction::MediaDirection, sdp_track::SdpTrack,
+    },
+    core::{
+        clock::{self, SharedClock},
+        connection_state::{
+            ConnectionStateTracker, DtlsState, IceConnectionState, PeerConnectionState,
+        },
+        consent::{ConsentAction, ConsentConfig, ConsentMonitor},
+        events::EngineEvent,
+        path_mtu::MtuConfig,
+        quality::{QualityPreset, configured_max_bitrate},
+        rtc_event_log::RtcEventLog,
+        session::{Session, SessionConfig, SessionInitArgs},
+        socket_stats::SendFailures,
+        stats::{DataChannelStats, IcePairStats, StatsReport},
+        timers::Timers,
+    },
+    dtls::{self, DtlsRole},
+    error::RtcError,
+    file_handler::{FileHandler, events::FileHandlerEvents, reader_worker::CHUNK_SIZE},
+    ice::{ice_error::IceError, type_ice::candidate_pair::CandidatePairState},
+    log::log_sink::LogSink,
+    media_agent::{
+        media_agent_error::MediaAgentError,
+        recorder::{RecordStreams, RecordingStatus},
+        video_frame::VideoFrame,
+        video_track::{TrackId, VideoSource},
+    },
+    media_transport::{
+        MediaTransport, error::MediaTransportError, media_transport_event::MediaTransportEvent,
+    },
+    sctp::events::SctpEvents,
+    signaling::protocol::capabilities::Capabilities,
+    sink_debug, sink_error, sink_info, sink_trace, sink_warn,
+};
+
+use super::constants::MIN_BITRATE;
+use crate::connection_manager::ice_and_sdp::ICEAndSDP;
+
+/// The orchestrator for one WebRTC peer connection.
+///
+/// Manages ICE, SDP negotiation, DTLS handshake, and media transport.
+pub struct PeerConnection {
+    logger_sink: Arc<dyn LogSink>,
+    cm: ConnectionManager,
+    session: Arc<Mutex<Option<Session>>>,
+    event_tx: Sender<EngineEvent>,
+    ui_rx: Receiver<EngineEvent>,
+    media_transport: MediaTransport,
+    congestion_controller: CongestionController,
+    config: Arc<Config>,
+    file_handler: Arc<Mutex<Option<Arc<FileHandler>>>>,
+    sending_files: Arc<AtomicBool>,
+    receiving_files: Arc<AtomicBool>,
+    states: ConnectionStateTracker,
+    consent: ConsentMonitor,
+    ice_tx: Sender<(Vec<u8>, SocketAddr)>,
+    ice_rx: Receiver<(Vec<u8>, SocketAddr)>,
+    last_ice_retry: Instant,
+    next_track_id: TrackId,
+    /// Remote extra tracks with a registered recv stream.
+    remote_tracks: Vec<SdpTrack>,
+    /// Diagnostic capture of this call, if enabled.
+    event_log: Option<RtcEventLog>,
+    /// Run the session's timed work; their clock times consent and ICE
+    /// restarts.
+    timers: Timers,
+    clock: SharedClock,
+    /// Socket send failures already reported, and when.
+    socket_failures: SendFailures,
+    last_socket_error: Option<Instant>,
+}
+
+/// Least time between two `SocketError` events.
+const SOCKET_ERROR_EVERY: Duration = Duration::from_secs(1);
+
+/// How often the ICE checks are re-sent while an ICE restart is in progress.
+const ICE_RESTART_RETRY: Duration = Duration::from_millis(500);
+
+impl PeerConnection {
+    /// Creates a new `PeerConnection` instance.
+    pub fn new(
+        logger_sink: Arc<dyn LogSink>,
+        config: Arc<Config>,
+        sending_files: Arc<AtomicBool>,
+        receiving_files: Arc<AtomicBool>,
+    ) -> Self {
+        let (ui_tx, ui_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+        let media_transport =
+            MediaTransport::new(event_tx.clone(), logger_sink.clone(), config.clone());
+        let initial_bitrate = crate::media_agent::constants::BITRATE;
+        let max_bitrate = configured_max_bitrate(&config);
+
+        let min_bitrate = config
+            .get("Media", "min_bitrate")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(MIN_BITRATE);
+        let mut congestion_controller = CongestionController::new(
+            initial_bitrate,
+            min_bitrate,
+            max_bitrate,
+            logger_sink.clone(),
+            event_tx.clone(),
+        );
+        if let Some(weight) = config
+            .get("file_handler", "bulk_weight")
+            .and_then(|s| s.parse().ok())
+        {
+            congestion_controller.set_bulk_weight(weight);
+        }
+
+        let logger = logger_sink.clone();
+        let consent = ConsentMonitor::new(ConsentConfig::from_config(&config));
+        let (ice_tx, ice_rx) = mpsc::channel();
+
+        let media_tx = media_transport.media_transport_event_tx();
+        std::thread::spawn(move || {
+            while let Ok(ev) = event_rx.recv() {
+                match ev {
+                    EngineEvent::RtpIn(pkt) => {
+                        sink_trace!(
+                            logger,
+                            "[PeerConnection] Sending RTP Packet to MediaTransport::RtpIn"
+                        );
+                        sink_trace!(
+                            logger,
+                            "[PeerConnection] ssrc: {} seq: {}",
+                            pkt.ssrc,
+                            pkt.seq
+                        );
+                        if let Some(tx) = &media_tx {
+                            let _ = tx.send(MediaTransportEvent::RtpIn(pkt));
+                        }
+                    }
+                    ev => {
+                        let _ = ui_tx.send(ev);
+                    }
+                }
+            }
+        });
+
+        Self {
+            cm: ConnectionManager::new(logger_sink.clone(), config.clone()),
+            logger_sink,
+            session: Arc::new(Mutex::new(None)),
+            event_tx,
+            media_transport,
+            congestion_controller,
+            ui_rx,
+            config,
+            file_handler: Arc::new(Mutex::new(None)),
+            sending_files,
+            receiving_files,
+            states: ConnectionStateTracker::new(),
+            consent,
+            ice_tx,
+            ice_rx,
+            last_ice_retry: Instant::now(),
+            next_track_id: 1,
+            remote_tracks: Vec::new(),
+            event_log: None,
+            timers: Timers::shared().clone(),
+            clock: clock::system(),
+            socket_failures: SendFailures::default(),
+            last_socket_error: None,
+        }
+    }
+
+    /// Records this call's transport events (RTP/RTCP headers, ICE checks,
+    /// DTLS, congestion decisions) to `event_log`. Set it before the call
+    /// connects.
+    pub fn set_event_log(&mut self, event_log: Option<RtcEventLog>) {
+        if let Some(log) = &event_log {
+            sink_info!(
+                self.logger_sink,
+                "[PeerConnection] capturing RTC events to {}",
+                log.path().display()
+            );
+        }
+        self.cm.set_event_log(event_log.clone());
+        self.event_log = event_log;
+    }
+
+    /// Runs ICE, the session and congestion control on `timers` and their
+    /// clock, e.g. [`Timers::manual`] in tests. Set it before the call
+    /// connects.
+    pub fn set_timers(&mut self, timers: Timers) {
+        self.clock = timers.clock();
+        self.last_ice_retry = self.clock.now();
+        self.congestion_controller
+            .set_clock(Arc::clone(&self.clock));
+        self.cm.set_timers(timers.clone());
+        self.timers = timers;
+    }
+
+    /// Aggregate connection state.
+    #[must_use]
+    pub const fn connection_state(&self) -> PeerConnectionState {
+        self.states.peer()
+    }
+
+    /// Current ICE transport state.
+    #[must_use]
+    pub const fn ice_connection_state(&self) -> IceConnectionState {
+        self.states.ice()
+    }
+
+    /// Current DTLS transport state.
+    #[must_use]
+    pub const fn dtls_state(&self) -> DtlsState {
+        self.states.dtls()
+    }
+
+    /// Collects a statistics snapshot: ICE pairs, RTP streams, congestion
+    /// controller, data channel and media pipeline latency.
+    #[must_use]
+    pub fn get_stats(&self) -> StatsReport {
+        let mut report = StatsReport::new();
+        let ts = report.timestamp_ms;
+
+        let agent = &self.cm.ice_agent;
+        let nominated = agent
+            .nominated_pair
+            .as_ref()
+            .map(|p| (p.local.address, p.remote.address));
+        report.ice_pairs = agent
+            .candidate_pairs
+            .iter()
+            .map(|p| IcePairStats {
+                id: format!("ICEPair_{}_{}", p.local.address, p.remote.address),
+                timestamp_ms: ts,
+                local_address: p.local.address.to_string(),
+                remote_address: p.remote.address.to_string(),
+                local_type: format!("{:?}", p.local.cand_type),
+                remote_type: format!("{:?}", p.remote.cand_type),
+                state: format!("{:?}", p.state),
+                priority: p.priority,
+                nominated: p.is_nominated || nominated == Some((p.local.address, p.remote.address)),
+            })
+            .collect();
+
+        report.congestion = Some(self.congestion_controller.stats());
+        report.latency = self.media_transport.latency_stats();
+
+        if let Ok(guard) = self.session.lock()
+            && let Some(sess) = guard.as_ref()
+        {
+            let (outbound, inbound) = sess.rtp_stats();
+            report.outbound_rtp = outbound;
+            report.inbound_rtp = inbound;
+            report.sockets.extend(sess.socket_stats());
+            report.data_channels.push(DataChannelStats {
+                id: "DataChannel_files".to_string(),
+                timestamp_ms: ts,
+                label: "files".to_string(),
+                buffered_amount: sess.buffered_amount(),
+                sending: self.sending_files.load(Ordering::SeqCst),
+                receiving: self.receiving_files.load(Ordering::SeqCst),
+            });
+        }
+
+        report
+    }
+
+    /// Initiates an SDP negotiation as an offerer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the negotiation fails.
+    pub fn negotiate(&mut self) -> Result<Option<String>, ConnectionError> {
+        self.cm
+            .set_local_rtp_codecs(self.media_transport.codec_descriptors());
+        match self.cm.negotiate()? {
+            OutboundSdp::Offer(o) => Ok(Some(o.encode())),
+            OutboundSdp::Answer(a) => Ok(Some(a.encode())),
+            OutboundSdp::None => Ok(None),
+        }
+    }
+
+    /// Applies a remote SDP (offer or answer) received from the peer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if applying the remote SDP fails.
+    pub fn apply_remote_sdp(
+        &mut self,
+        remote_sdp: &str,
+    ) -> Result<Option<String>, ConnectionError> {
+        self.cm
+            .set_local_rtp_codecs(self.media_transport.codec_descriptors());
+        let out = match self.cm.apply_remote_sdp(remote_sdp)? {
+            OutboundSdp::Answer(a) => Some(a.encode()),
+            OutboundSdp::Offer(o) => Some(o.encode()),
+            OutboundSdp::None => None,
+        };
+        self.media_transport
+            .set_sending(self.cm.negotiated_direction().sends());
+        self.sync_remote_tracks();
+        // Renegotiated mid-call: the peer may have just started receiving
+        if self.states.peer().is_connected() {
+            self.keyframe_for_new_receiver();
+        }
+        Ok(out)
+    }
+
+    /// `[Media] keyframe_on_join`: whether a new receiver gets a keyframe
+    /// right away (the default) instead of at the next keyframe interval.
+    fn keyframe_on_join(&self) -> bool {
+        self.config
+            .get("Media", "keyframe_on_join")
+            .is_none_or(|v| !v.trim().eq_ignore_ascii_case("false"))
+    }
+
+    /// The peer can only start decoding our video at a keyframe: encode the
+    /// next frame as one, so the call doesn't open on gray or garbled video.
+    fn keyframe_for_new_receiver(&self) {
+        if self.keyframe_on_join() {
+            self.media_transport.request_keyframe();
+        }
+    }
+
+    /// Adds an outbound video track to the live call and returns its id
+    /// with the re-offer announcing it (`None` if an offer is pending).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError::Negotiation` if the media pipeline is not
+    /// running or the track cannot be started.
+    pub fn add_video_track(
+        &mut self,
+        source: VideoSource,
+    ) -> Result<(TrackId, Option<String>), ConnectionError> {
+        let id = self.next_track_id;
+        let ssrc = rand::random::<u32>();
+        self.media_transport
+            .add_video_track(id, ssrc, source)
+            .map_err(|e| ConnectionError::Negotiation(format!("add track: {e}")))?;
+        self.next_track_id += 1;
+        self.cm.add_local_track(id, ssrc);
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] added video track {} (ssrc={})",
+            id,
+            ssrc
+        );
+        Ok((id, self.negotiate()?))
+    }
+
+    /// Removes a track added with [`add_video_track`](Self::add_video_track)
+    /// and returns the re-offer announcing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError::Negotiation` if `id` is not a live track.
+    pub fn remove_track(&mut self, id: TrackId) -> Result<Option<String>, ConnectionError> {
+        if self.cm.remove_local_track(id).is_none() {
+            return Err(ConnectionError::Negotiation(format!("no track {id}")));
+        }
+        self.media_transport.remove_video_track(id);
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] removed video track {}",
+            id
+        );
+        self.negotiate()
+    }
+
+    /// Registers/unregisters recv streams so they match the tracks the
+    /// remote announced, and shows the most recent one.
+    #[allow(clippy::expect_used)]
+    fn sync_remote_tracks(&mut self) {
+        let announced = self.cm.remote_tracks().to_vec();
+        let guard = self.session.lock().expect("session lock poisoned");
+        let Some(sess) = guard.as_ref() else {
+            return;
+        };
+
+        let mut kept = Vec::new();
+        for track in self.remote_tracks.drain(..) {
+            if announced.contains(&track) {
+                kept.push(track);
+            } else {
+                let _ = sess.unregister_inbound_track(track.ssrc);
+                let _ = self
+                    .event_tx
+                    .send(EngineEvent::RemoteTrackRemoved { ssrc: track.ssrc });
+            }
+        }
+
+        let codec = self
+            .cm
+            .remote_codecs()
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case("H264"))
+            .cloned();
+        for track in announced {
+            if kept.contains(&track) {
+                continue;
+            }
+            let Some(codec) = codec.clone() else {
+                break;
+            };
+            match sess.register_inbound_track(codec, track.ssrc) {
+                Ok(()) => {
+                    // Same for us as a new receiver of the peer's track
+                    if self.keyframe_on_join() {
+                        let _ = sess.send_pli(track.ssrc);
+                    }
+                    let _ = self.event_tx.send(EngineEvent::RemoteTrackAdded {
+                        ssrc: track.ssrc,
+                        label: track.label.clone(),
+                    });
+                    kept.push(track);
+                }
+                Err(e) => sink_debug!(
+                    self.logger_sink,
+                    "[PeerConnection] remote track {} not registered yet: {}",
+                    track.ssrc,
+                    e
+                ),
+            }
+        }
+        drop(guard);
+
+        self.media_transport
+            .set_preferred_remote_video(kept.last().map(|t| t.ssrc));
+        self.remote_tracks = kept;
+    }
+
+    /// Sets the direction offered on every m-line from the next negotiation
+    /// on, e.g. `sendonly` to publish a stream or `recvonly` to play one.
+    pub const fn set_local_direction(&mut self, direction: MediaDirection) {
+        self.cm.set_local_direction(direction);
+    }
+
+    /// Puts the call on hold: stops sending media right away and returns a
+    /// re-offer marking every m-line `inactive`.
+    ///
+    /// The session, DTLS and ICE stay up. Returns `None` if an offer is
+    /// already pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the re-offer cannot be created.
+    pub fn hold(&mut self) -> Result<Option<String>, ConnectionError> {
+        sink_info!(self.logger_sink, "[PeerConnection] putting call on hold");
+        self.cm.set_local_direction(MediaDirection::Inactive);
+        self.media_transport.set_sending(false);
+        self.negotiate()
+    }
+
+    /// Takes the call off hold by re-offering `sendrecv`. Sending resumes
+    /// once the answer is applied (and only if the remote is not holding).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if the re-offer cannot be created.
+    pub fn resume(&mut self) -> Result<Option<String>, ConnectionError> {
+        sink_info!(self.logger_sink, "[PeerConnection] resuming call");
+        self.cm.set_local_direction(MediaDirection::SendRecv);
+        self.negotiate()
+    }
+
+    /// Caps the video bitrate (bps). Takes effect immediately, mid-call
+    /// included: the congestion controller never goes above it and the
+    /// encoder is reconfigured if it currently is.
+    pub fn set_max_bitrate(&mut self, bps: u32) {
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] max bitrate set to {} bps",
+            bps
+        );
+        self.congestion_controller.set_max_bitrate(bps);
+    }
+
+    /// Applies the bitrate cap of a quality preset.
+    pub fn set_quality_preset(&mut self, preset: QualityPreset) {
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] quality preset {}",
+            preset
+        );
+        self.set_max_bitrate(preset.max_bitrate(&self.config));
+    }
+
+    /// The current bitrate cap (bps).
+    #[must_use]
+    pub const fn max_bitrate(&self) -> u32 {
+        self.congestion_controller.max_bitrate()
+    }
+
+    /// `true` if we put the call on hold.
+    #[must_use]
+    pub fn is_on_hold(&self) -> bool {
+        !self.cm.local_direction().sends()
+    }
+
+    /// `true` if the remote put the call on hold in its last offer.
+    #[must_use]
+    pub fn is_remote_on_hold(&self) -> bool {
+        !self.cm.remote_direction().receives()
+    }
+
+    /// Applies a remote ICE candidate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConnectionError` if applying the candidate fails.
+    pub fn apply_remote_candidate(&mut self, candidate_line: &str) -> Result<(), ConnectionError> {
+        self.cm.apply_remote_trickle_candidate(candidate_line)
+    }
+
+    /// Returns local ICE candidates encoded as SDP attribute lines (`candidate:...`).
+    pub fn local_candidates_as_sdp_lines(&self) -> Vec<String> {
+        self.cm
+            .ice_agent
+            .local_candidates
+            .iter()
+            .map(|c| ICEAndSDP::new(c.clone()).to_string())
+            .collect()
+    }
+
+    /// Starts the WebRTC session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtcError::Ice` if no nominated ICE pair is available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal session lock is poisoned.
+    #[allow(clippy::expect_used)]
+    pub fn start(&mut self) -> Result<(), RtcError> {
+        let mut guard = self.session.lock().expect("session lock poisoned");
+        let sess = guard.as_mut().ok_or(IceError::NoNominatedPair)?;
+        sess.start();
+        Ok(())
+    }
+
+    /// Stops the WebRTC session.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal session lock is poisoned.
+    #[allow(clippy::expect_used)]
+    pub fn stop(&mut self) {
+        if let Some(sess) = self.session.lock().expect("session lock poisoned").as_mut() {
+            sess.request_close();
+        }
+        self.media_transport.stop();
+        // Stop file handler
+        if let Ok(mut fh_guard) = self.file_handler.lock() {
+            if let Some(fh) = fh_guard.as_ref() {
+                // Reset flags
+                self.sending_files.store(false, Ordering::SeqCst);
+                self.receiving_files.store(false, Ordering::SeqCst);
+                // Shutdown
+                fh.shutdown();
+            }
+            *fh_guard = None;
+        }
+        // Routed back through `poll()` like any other engine event.
+        for ev in self.states.close() {
+            let _ = self.event_tx.send(ev);
+        }
+    }
+    /// Closes the WebRTC session and resets the connection manager.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal session lock is poisoned.
+    #[allow(clippy::expect_used)]
+    pub fn close_session(&mut self) {
+        let mut guard = self.session.lock().expect("session lock poisoned");
+        *guard = None;
+        self.cm.reset();
+        sink_debug!(
+            self.logger_sink,
+            "[PeerConnection] Session closed and ConnectionManager reset."
+        );
+        // Reset file handler
+        if let Ok(mut fh) = self.file_handler.lock() {
+            *fh = None;
+        }
+        self.states = ConnectionStateTracker::new();
+        self.remote_tracks.clear();
+        self.media_transport.set_preferred_remote_video(None);
+        self.consent.reset();
+    }
+
+    pub fn send_file(&self, path: String, id: u32) {
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] send_file called for path: {} (id: {})",
+            path,
+            id
+        );
+        if let Ok(fh_guard) = self.file_handler.lock() {
+            if let Some(fh) = fh_guard.as_ref() {
+                sink_info!(
+                    self.logger_sink,
+                    "[PeerConnection] FileHandler found, sending ReadFile event"
+                );
+                self.sending_files.store(true, Ordering::SeqCst);
+                if let Err(e) = fh.send(FileHandlerEvents::ReadFile { path, id }) {
+                    sink_error!(
+                        self.logger_sink,
+                        "[PeerConnection] Failed to send ReadFile event to FileHandler: {}",
+                        e
+                    );
+                }
+            } else {
+                sink_error!(
+                    self.logger_sink,
+                    "[PeerConnection] FileHandler is None in send_file!"
+                );
+            }
+        } else {
+            sink_error!(
+                self.logger_sink,
+                "[PeerConnection] Failed to lock FileHandler in send_file"
+            );
+        }
+    }
+
+    pub fn accept_file(&self, id: u32, filename: String) {
+        if let Ok(sess_guard) = self.session.lock()
+            && let Some(sess) = sess_guard.as_ref()
+        {
+            // We are receiving a file
+            self.receiving_files.store(true, Ordering::SeqCst);
+            sess.send_sctp_event(SctpEvents::SendAccept { id });
+        }
+        // Notify local FileHandler to start writing
+        if let Ok(fh_guard) = self.file_handler.lock()
+            && let Some(fh) = fh_guard.as_ref()
+        {
+            let _ = fh.send(FileHandlerEvents::WriteFile { filename, id });
+        }
+    }
+
+    pub fn reject_file(&self, id: u32) {
+        if let Ok(sess_guard) = self.session.lock()
+            && let Some(sess) = sess_guard.as_ref()
+        {
+            sess.send_sctp_event(SctpEvents::SendReject { id });
+        }
+    }
+
+    pub fn cancel_file(&self, id: u32) {
+        // Cancel can be local sender cancelling, or local receiver cancelling
+        // Notify Session to send Cancel msg
+        if let Ok(sess_guard) = self.session.lock()
+            && let Some(sess) = sess_guard.as_ref()
+        {
+            sess.send_sctp_event(SctpEvents::SendCancel { id });
+        }
+        // Also notify local FileHandler to stop
+        if let Ok(fh_guard) = self.file_handler.lock()
+            && let Some(fh) = fh_guard.as_ref()
+        {
+            let _ = fh.send(FileHandlerEvents::Cancel(id));
+        }
+    }
+
+    pub fn set_audio_mute(&mut self, mute: bool) {
+        self.media_transport.set_audio_mute(mute);
+    }
+
+    /// Sends DTMF digits as telephone events on the audio stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaTransportError::InvalidDtmf` for a character that is not
+    /// a DTMF digit.
+    pub fn send_dtmf(&self, digits: &str) -> Result<(), MediaTransportError> {
+        self.media_transport.send_dtmf(digits)
+    }
+
+    /// Enables or disables push-to-talk mode.
+    pub fn set_push_to_talk(&self, enabled: bool) {
+        self.media_transport.set_push_to_talk(enabled);
+    }
+
+    /// Reports whether the push-to-talk key is held.
+    pub fn set_push_to_talk_pressed(&self, pressed: bool) {
+        self.media_transport.set_push_to_talk_pressed(pressed);
+    }
+
+    #[must_use]
+    pub fn push_to_talk(&self) -> bool {
+        self.media_transport.push_to_talk()
+    }
+
+    /// Stops the camera and sends a camera-off placeholder, or resumes it.
+    pub fn set_video_muted(&mut self, muted: bool) {
+        self.media_transport.set_video_muted(muted);
+    }
+
+    #[must_use]
+    pub fn video_muted(&self) -> bool {
+        self.media_transport.video_muted()
+    }
+
+    /// Switches the local camera, mid-call included, forcing a keyframe.
+    ///
+    /// Returns the capture status (resolution or fallback reason), or `None`
+    /// if media is not running yet and the device was stored for later.
+    pub fn switch_camera(&mut self, camera_id: i32) -> Option<String> {
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] switching to camera {}",
+            camera_id
+        );
+        self.media_transport.switch_camera(camera_id)
+    }
+
+    /// Changes the camera resolution, frame rate and pixel format, mid-call
+    /// included (the encoder restarts with a keyframe).
+    pub fn set_capture_settings(&mut self, settings: CaptureSettings) -> Option<String> {
+        self.media_transport.set_capture_settings(settings)
+    }
+
+    #[must_use]
+    pub const fn capture_settings(&self) -> CaptureSettings {
+        self.media_transport.capture_settings()
+    }
+
+    /// What this side supports; every connection has a data channel.
+    #[must_use]
+    pub fn local_capabilities(&self) -> Capabilities {
+        Capabilities {
+            data_channel: true,
+            ..self.media_transport.capabilities()
+        }
+    }
+
+    /// Sends color bars and/or a sine tone instead of the camera and
+    /// microphone, mid-call included.
+    pub fn set_test_sources(&mut self, video: bool, audio: bool) {
+        self.media_transport.set_test_sources(video, audio);
+    }
+
+    /// `(video, audio)`: whether the test sources are selected.
+    #[must_use]
+    pub const fn test_sources(&self) -> (bool, bool) {
+        self.media_transport.test_sources()
+    }
+
+    /// Switches the microphone, mid-call included.
+    pub fn set_audio_input(&mut self, device: Option<String>) {
+        self.media_transport.set_audio_input(device);
+    }
+
+    /// Switches the speaker, mid-call included, without restarting the
+    /// audio pipeline.
+    pub fn set_audio_output(&mut self, device: Option<String>) {
+        self.media_transport.set_audio_output(device);
+    }
+
+    /// `(input, output)` device names; `None` means the host default.
+    #[must_use]
+    pub fn audio_devices(&self) -> (Option<String>, Option<String>) {
+        self.media_transport.audio_devices()
+    }
+
+    /// How loud the remote audio plays (1.0 = as received), mid-call
+    /// included, without renegotiating.
+    pub fn set_remote_volume(&mut self, volume: f32) {
+        self.media_transport.set_remote_volume(volume);
+    }
+
+    #[must_use]
+    pub const fn remote_volume(&self) -> f32 {
+        self.media_transport.remote_volume()
+    }
+
+    /// Stops or resumes playing the remote audio; the peer still sends it.
+    pub fn set_remote_audio_muted(&mut self, muted: bool) {
+        self.media_transport.set_remote_audio_muted(muted);
+    }
+
+    #[must_use]
+    pub const fn remote_audio_muted(&self) -> bool {
+        self.media_transport.remote_audio_muted()
+    }
+
+    /// Starts recording the call to an MKV file and returns its path.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if a recording is already running or
+    /// the file cannot be created.
+    pub fn start_recording(
+        &mut self,
+        path: Option<PathBuf>,
+        streams: RecordStreams,
+    ) -> Result<PathBuf, MediaAgentError> {
+        self.media_transport.start_recording(path, streams)
+    }
+
+    /// Stops recording; returns the saved file, if one was being written.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if the file cannot be finalized.
+    pub fn stop_recording(&mut self) -> Result<Option<PathBuf>, MediaAgentError> {
+        self.media_transport.stop_recording()
+    }
+
+    #[must_use]
+    pub fn recording(&self) -> Option<RecordingStatus> {
+        self.media_transport.recording()
+    }
+
+    /// Polls for `EngineEvent`s and processes them.
+    /// This method is called repeatedly to drive the engine's state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal session lock or file handler lock is poisoned.
+    #[allow(clippy::expect_used)]
+    pub fn poll(&mut self) -> Vec<EngineEvent> {
+        let mut out = Vec::new();
+
+        // keep ICE reactive
+        self.cm.drain_ice_events();
+        if matches!(
+            self.cm.ice_phase(),
+            IcePhase::Gathering | IcePhase::Checking
+        ) {
+            out.extend(self.states.set_ice(IceConnectionState::Checking));
+        }
+
+        if self
+            .session
+            .lock()
+            .expect("session lock poisoned")
+            .is_none()
+            && let Ok((sock, peer)) = self.cm.ice_agent.get_data_channel_socket()
+        {
+            if let Err(e) = sock.connect(peer) {
+                let _ = self
+                    .event_tx
+                    .send(EngineEvent::Error(format!("socket.connect: {e}")));
+            } else {
+                let local = sock
+                    .local_addr()
+                    .unwrap_or_else(|_| SocketAddr::from(([0, 0, 0, 0], 0)));
+                let _ = self.event_tx.send(EngineEvent::IceNominated {
+                    local,
+                    remote: peer,
+                });
+                out.extend(self.states.set_ice(IceConnectionState::Connected));
+
+                self.cm.stop_ice_worker();
+
+                // --- a=setup (or IceRole) -> DtlsRole ---
+                let dtls_role = self.cm.dtls_role();
+
+                // Retrieve the remote fingerprint stored in CM
+                let remote_fp = self.cm.remote_fingerprint.clone();
+
+                // --- blocking DTLS handshake ---
+                out.extend(self.states.set_dtls(DtlsState::Connecting));
+                if let Some(log) = &self.event_log {
+                    log.dtls(&format!("handshake started as {dtls_role:?} with {peer}"));
+                }
+                // Modified to destructure the tuple
+                match dtls::run_dtls_handshake(
+                    Arc::clone(&sock),
+                    peer,
+                    dtls_role,
+                    self.logger_sink.clone(),
+                    Duration::from_secs_f32(5.0),
+                    remote_fp,
+                    self.config.clone(),
+                ) {
+                    Ok((srtp_cfg, ssl_stream)) => {
+                        out.extend(self.states.set_dtls(DtlsState::Connected));
+                        if let Some(log) = &self.event_log {
+                            log.dtls("handshake completed");
+                        }
+                        // Create FileHandler
+                        let fh = Arc::new(FileHandler::new(
+                            self.config.clone(),
+                            self.logger_sink.clone(),
+                            self.event_tx.clone(),
+                        ));
+                        *self.file_handler.lock().expect("fh lock") = Some(fh.clone());
+
+                        // Spawn DrainChunks thread
+                        let sending_files_clone = self.sending_files.clone();
+                        let fh_weak = Arc::downgrade(&fh);
+                        let session_clone = self.session.clone();
+                        let bulk_pacer = self.congestion_controller.bulk_pacer();
+                        // Interval from config or default
+                        let drain_interval_ms = self
+                            .config
+                            .get("file_handler", "drain_interval_ms")
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(1);
+                        let drain_interval = Duration::from_millis(drain_interval_ms);
+
+                        thread::spawn(move || {
+                            loop {
+                                thread::sleep(drain_interval);
+                                if sending_files_clone.load(Ordering::SeqCst) {
+                                    // Check buffered amount ONCE before the burst
+                                    let mut high_buffer = false;
+                                    if let Ok(guard) = session_clone.lock() {
+                                        if let Some(sess) = guard.as_ref() {
+                                            if sess.buffered_amount() > 512_000 {
+                                                high_buffer = true;
+                                            }
+                                        }
+                                    }
+
+                                    if !high_buffer {
+                                        for _ in 0..20 {
+                                            // Chunks go out at the rate the
+                                            // congestion controller leaves
+                                            // beside the media
+                                            if !bulk_pacer.try_take(CHUNK_SIZE, Instant::now()) {
+                                                break;
+                                            }
+                                            if let Some(fh) = fh_weak.upgrade() {
+                                                if fh.send(FileHandlerEvents::DrainChunks).is_err()
+                                                {
+                                                    return;
+                                                }
+                                            } else {
+                                                return;
+                                            }
+                                        }
+                                    }
+                                } else if fh_weak.strong_count() == 0 {
+                                    break;
+                                }
+                            }
+                        });
+
+                        let sess = Session::new(SessionInitArgs {
+                            sock: Arc::clone(&sock),
+                            peer,
+                            remote_codecs: self.cm.remote_codecs().clone(),
+                            event_tx: self.event_tx.clone(),
+                            logger: self.logger_sink.clone(),
+                            cfg: SessionConfig {
+                                close_timeout: Duration::from_secs(5),
+                                keepalive_every: self.consent.config().keepalive_every,
+                                mtu: MtuConfig::from_config(&self.config),
+                            },
+                            srtp_cfg: Some(srtp_cfg),
+                            ssl_stream,
+                            is_client: dtls_role == DtlsRole::Client,
+                            ice_tx: Some(self.ice_tx.clone()),
+                            event_log: self.event_log.clone(),
+                            buffers: self.media_transport.rtp_buffers(),
+                        })
+                        .with_timers(self.timers.clone());
+                        *self.session.lock().expect("session lock poisoned") = Some(sess);
+                    }
+                    Err(e) => {
+                        out.extend(self.states.set_dtls(DtlsState::Failed));
+                        if let Some(log) = &self.event_log {
+                            log.dtls(&format!("handshake failed: {e}"));
+                        }
+                        let _ = self
+                            .event_tx
+                            .send(EngineEvent::Error(format!("DTLS handshake failed: {e}")));
+                    }
+                };
+            }
+        }
+
+        self.check_consent(&mut out);
+        self.check_socket_errors(&mut out);
+
+        let start = Instant::now();
+        let max_events = 500;
+        let max_time = Duration::from_millis(4);
+
+        let mut processed = 0;
+        loop {
+            if processed >= max_events || start.elapsed() >= max_time {
+                break;
+            }
+            match self.ui_rx.try_recv() {
+                Ok(ev) => match ev {
+                    EngineEvent::NetworkMetrics(m) => {
+                        self.congestion_controller
+                            .set_bulk_active(self.sending_files.load(Ordering::SeqCst));
+                        let before = self.congestion_controller.current_bitrate();
+                        self.congestion_controller.on_network_metrics(m.clone());
+                        if let Some(log) = &self.event_log {
+                            log.congestion(
+                                &m,
+                                before,
+                                self.congestion_controller.current_bitrate(),
+                            );
+                        }
+                        processed += 1;
+                        out.push(EngineEvent::NetworkMetrics(m.clone()));
+                    }
+
+                    EngineEvent::KeyframeRequested { ssrc } => {
+                        self.media_transport.request_keyframe();
+                        processed += 1;
+                        out.push(EngineEvent::KeyframeRequested { ssrc });
+                    }
+
+                    EngineEvent::PathMtu(mtu) => {
+                        self.media_transport.set_mtu(mtu);
+                        processed += 1;
+                        out.push(EngineEvent::PathMtu(mtu));
+                    }
+
+                    EngineEvent::UpdateBitrate(br) => {
+                        if let Some(media_transport_tx) =
+                            self.media_transport.media_transport_event_tx()
+                        {
+                            let _ = media_transport_tx.send(MediaTransportEvent::UpdateBitrate(br));
+                        }
+                        processed += 1;
+                        out.push(EngineEvent::UpdateBitrate(br));
+                    }
+
+                    EngineEvent::SendFileOffer(props) => {
+                        if let Ok(sess_guard) = self.session.lock()
+                            && let Some(sess) = sess_guard.as_ref()
+                        {
+                            sess.send_sctp_event(SctpEvents::SendOffer {
+                                file_properties: props,
+                            });
+                        }
+                    }
+                    EngineEvent::SendFileChunk(id, payload) => {
+                        if let Ok(sess_guard) = self.session.lock()
+                            && let Some(sess) = sess_guard.as_ref()
+                        {
+                            sess.send_sctp_event(SctpEvents::SendChunk {
+                                file_id: id,
+                                payload,
+                            });
+                        }
+                    }
+                    EngineEvent::SendFileEnd(id) => {
+                        if let Ok(sess_guard) = self.session.lock()
+                            && let Some(sess) = sess_guard.as_ref()
+                        {
+                            sess.send_sctp_event(SctpEvents::SendEndFile { id });
+                        }
+                        // Reset sending flag if no other files? For now simple reset.
+                        self.sending_files.store(false, Ordering::SeqCst);
+                    }
+                    EngineEvent::ReceivedFileChunk(id, _seq, payload) => {
+                        // Don't expose to UI, send to FileHandler
+                        if let Ok(fh_guard) = self.file_handler.lock()
+                            && let Some(fh) = fh_guard.as_ref()
+                        {
+                            let _ = fh.send(FileHandlerEvents::WriteChunk { id, payload });
+                        }
+                    }
+                    EngineEvent::ReceivedFileEnd(id) => {
+                        self.receiving_files.store(false, Ordering::SeqCst);
+                        out.push(EngineEvent::ReceivedFileEnd(id));
+                        processed += 1;
+                    }
+                    EngineEvent::ReceivedFileOffer(props) => {
+                        out.push(EngineEvent::ReceivedFileOffer(props));
+                        processed += 1;
+                    }
+                    EngineEvent::ReceivedFileAccept(id) => {
+                        // Peer accepted our file. Notify FileHandler to start sending.
+                        if let Ok(fh_guard) = self.file_handler.lock()
+                            && let Some(fh) = fh_guard.as_ref()
+                        {
+                            let _ = fh.send(FileHandlerEvents::RemoteAccepted(id));
+                        }
+                        out.push(EngineEvent::ReceivedFileAccept(id));
+                        processed += 1;
+                    }
+                    EngineEvent::Established => {
+                        self.keyframe_for_new_receiver();
+                        self.sync_remote_tracks();
+                        out.extend(self.states.set_session_established(true));
+                        out.push(EngineEvent::Established);
+                        processed += 1;
+                    }
+                    EngineEvent::Closing { graceful } => {
+                        out.extend(self.states.close());
+                        out.push(EngineEvent::Closing { graceful });
+                        processed += 1;
+                    }
+                    EngineEvent::Closed => {
+                        out.extend(self.states.close());
+                        out.push(EngineEvent::Closed);
+                        processed += 1;
+                    }
+                    EngineEvent::ToggleAudio(mute) => {
+                        self.media_transport.set_audio_mute(mute);
+                        // We push it out so the UI can update its state if the event came from elsewhere
+                        out.push(EngineEvent::ToggleAudio(mute));
+                        processed += 1;
+                    }
+
+                    _ => {
+                        processed += 1;
+                        out.push(ev);
+                    }
+                },
+                Err(_) => break,
+            }
+        }
+
+        out
+    }
+
+    /// Reports send failures on the session socket that happened since the
+    /// last report.
+    fn check_socket_errors(&mut self, out: &mut Vec<EngineEvent>) {
+        let Some((failures, last_error)) = self.session.lock().ok().and_then(|guard| {
+            guard.as_ref().map(|s| {
+                let counters = s.socket_counters();
+                (counters.failures(), counters.last_error())
+            })
+        }) else {
+            return;
+        };
+        // A new session starts its counters from zero
+        if failures.errors < self.socket_failures.errors
+            || failures.buffer_full < self.socket_failures.buffer_full
+        {
+            self.socket_failures = SendFailures::default();
+        }
+        if failures == self.socket_failures {
+            return;
+        }
+        let now = self.clock.now();
+        if self
+            .last_socket_error
+            .is_some_and(|at| now.duration_since(at) < SOCKET_ERROR_EVERY)
+        {
+            return;
+        }
+        let send_buffer_full = failures.buffer_full - self.socket_failures.buffer_full;
+        let send_errors = failures.errors - self.socket_failures.errors;
+        sink_warn!(
+            self.logger_sink,
+            "[PeerConnection] {send_buffer_full} packets dropped on a full send buffer, {send_errors} sends failed (last: {})",
+            last_error.as_deref().unwrap_or("none")
+        );
+        out.push(EngineEvent::SocketError {
+            send_buffer_full,
+            send_errors,
+            last_error,
+        });
+        self.socket_failures = failures;
+        self.last_socket_error = Some(now);
+    }
+
+    /// Consent freshness: keeps the session through short outages, restarts
+    /// the ICE checks if the path stays silent, and resumes media (same DTLS
+    /// and SRTP state) once the path validates again.
+    #[allow(clippy::expect_used)]
+    fn check_consent(&mut self, out: &mut Vec<EngineEvent>) {
+        // ICE checks that arrived on the session socket.
+        while let Ok((pkt, from)) = self.ice_rx.try_recv() {
+            if let Some(log) = &self.event_log {
+                log.ice_check(true, from, &pkt);
+            }
+            self.cm.ice_agent.handle_incoming_packet(&pkt, from);
+        }
+
+        if !matches!(
+            self.states.peer(),
+            PeerConnectionState::Connected | PeerConnectionState::Disconnected
+        ) {
+            return;
+        }
+        let Some((idle, path)) = self
+            .session
+            .lock()
+            .expect("session lock poisoned")
+            .as_ref()
+            .map(|s| (s.idle_for(), s.path()))
+        else {
+            return;
+        };
+
+        let now = self.clock.now();
+        match self.consent.tick(now, idle) {
+            Some(ConsentAction::Lost) => {
+                sink_warn!(
+                    self.logger_sink,
+                    "[PeerConnection] nothing received for {} ms, keeping the session",
+                    idle.as_millis()
+                );
+                out.extend(self.states.set_ice(IceConnectionState::Disconnected));
+                out.push(EngineEvent::Status(
+                    "Connection lost, waiting for the network...".into(),
+                ));
+            }
+            Some(ConsentAction::RestartIce) => {
+                sink_info!(self.logger_sink, "[PeerConnection] restarting ICE checks");
+                self.cm.ice_agent.restart_checks();
+                self.last_ice_retry = now;
+                out.push(EngineEvent::Status("Restarting ICE...".into()));
+            }
+            Some(ConsentAction::Recovered) => {
+                sink_info!(self.logger_sink, "[PeerConnection] traffic resumed");
+                out.extend(self.states.set_ice(IceConnectionState::Connected));
+                out.push(EngineEvent::Status("Connection recovered.".into()));
+            }
+            Some(ConsentAction::Failed) => {
+                out.extend(self.states.set_ice(IceConnectionState::Failed));
+                out.push(EngineEvent::Error(
+                    "connection lost: ICE restart did not recover the path".into(),
+                ));
+            }
+            None => {}
+        }
+
+        if !self.consent.is_restarting() {
+            return;
+        }
+        let revalidated = path.is_some_and(|(local, remote)| {
+            self.cm.ice_agent.pair_state(local, remote) == Some(CandidatePairState::Succeeded)
+        });
+        if revalidated {
+            sink_info!(
+                self.logger_sink,
+                "[PeerConnection] ICE restart validated the path, resuming media"
+            );
+            self.consent.reset();
+            out.extend(self.states.set_ice(IceConnectionState::Connected));
+            out.push(EngineEvent::Status("Connection recovered.".into()));
+        } else if now.duration_since(self.last_ice_retry) >= ICE_RESTART_RETRY {
+            self.cm.ice_agent.retry_checks();
+            self.last_ice_retry = now;
+        }
+    }
+
+    /// Returns a snapshot of the local and remote video frames.
+    #[must_use]
+    pub fn snapshot_frames(&self) -> (Option<VideoFrame>, Option<VideoFrame>) {
+        self.media_transport.snapshot_frames()
+    }
+
+    /// Starts the media transport event loops.
+    pub fn start_media_transport(&mut self) {
+        self.media_transport.start_event_loops(self.session.clone());
+        sink_info!(
+            self.logger_sink,
+            "[PeerConnection] Sending Established Event to Media Transport"
+        );
+        if let Some(media_transport_event_tx) = self.media_transport.media_transport_event_tx() {
+            let _ = media_transport_event_tx.send(MediaTransportEvent::Established);
+        }
+    }
+}
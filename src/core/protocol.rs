@@ -1,53 +1,70 @@
-//! Defines the application-level signaling protocol messages used for session setup
-//! and tear-down.
+//! Defines the application-level messages sent on an established session.
 //!
-//! These messages are exchanged over the nominated ICE transport after DTLS handshake
-//! to establish and manage the application session state.
+//! The session itself is set up by the DTLS handshake and torn down by its
+//! `close_notify` alerts; what is left is the keepalive that tells a silent
+//! path from an idle one (consent freshness), and the probes that measure
+//! the path MTU (see [`crate::core::path_mtu`]). They are exchanged over
+//! the nominated ICE transport, outside DTLS.
+//!
+//! These messages travel in the clear, so each one carries a counter and a
+//! MAC keyed from the SRTP keys the DTLS handshake exported (see
+//! [`AppMsgAuth`]). A forged message fails the MAC and a captured one
+//! fails the counter when sent again, so neither refreshes consent: only
+//! the peer at the other end of the DTLS channel keeps the session alive.
+
+use std::{
+    fmt,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::srtp::{SrtpEndpointKeys, SrtpSessionConfig};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bytes of the HMAC-SHA256 kept in each message.
+const TAG_LEN: usize = 16;
+/// Domain separation of the MAC keys from the SRTP ones they come from.
+const KEY_LABEL: &[u8] = b"rustyrtc session messages";
+/// How far behind the highest counter seen a message may arrive, reordered.
+const REPLAY_WINDOW: u64 = 64;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AppMsg {
-    /// SYN message for handshake initiation, carrying a local token.
-    Syn { token: u64 },
-    /// SYN-ACK message for handshake response, echoing peer's token and carrying local token.
-    SynAck { your: u64, mine: u64 },
-    /// ACK message for handshake completion, acknowledging peer's token.
-    Ack { your: u64 },
-    /// FIN message for graceful session termination initiation, carrying a local token.
-    Fin { token: u64 },
-    /// FIN-ACK message for graceful session termination response, echoing peer's token and carrying local token.
-    FinAck { your: u64, mine: u64 },
-    /// FIN-ACK2 message for graceful session termination completion, acknowledging peer's token.
-    FinAck2 { your: u64 },
+    /// Keepalive sent periodically on an established session (consent freshness).
+    KeepAlive { token: u64 },
+    /// Path MTU probe, padded to the size tried.
+    Probe { id: u32 },
+    /// Acknowledges that probe `id` got through.
+    ProbeAck { id: u32 },
 }
 
-/// Encodes a SYN message.
-#[must_use]
-pub fn encode_syn(token: u64) -> String {
-    format!("SYN {token:016x}")
-}
-/// Encodes a SYN-ACK message.
-#[must_use]
-pub fn encode_synack(your: u64, mine: u64) -> String {
-    format!("SYN-ACK {your:016x} {mine:016x}")
-}
-/// Encodes an ACK message.
-#[must_use]
-pub fn encode_ack(your: u64) -> String {
-    format!("ACK {your:016x}")
-}
-/// Encodes a FIN message.
+/// Encodes a keepalive message.
 #[must_use]
-pub fn encode_fin(token: u64) -> String {
-    format!("FIN {token:016x}")
+pub fn encode_keepalive(token: u64) -> String {
+    format!("KA {token:016x}")
 }
-/// Encodes a FIN-ACK message.
+
+/// Encodes a path MTU probe, padded to `len` bytes if longer.
 #[must_use]
-pub fn encode_finack(your: u64, mine: u64) -> String {
-    format!("FIN-ACK {your:016x} {mine:016x}")
+pub fn encode_probe(id: u32, len: usize) -> String {
+    let mut msg = format!("PR {id:08x}");
+    if len > msg.len() {
+        msg.push(' ');
+        msg.extend(std::iter::repeat_n('.', len - msg.len()));
+    }
+    msg
 }
-/// Encodes a FIN-ACK2 message.
+
+/// Encodes the acknowledgment of probe `id`.
 #[must_use]
-pub fn encode_finack2(your: u64) -> String {
-    format!("FIN-ACK2 {your:016x}")
+pub fn encode_probe_ack(id: u32) -> String {
+    format!("PA {id:08x}")
 }
 
 fn parse_hex(t: &str) -> Option<u64> {
@@ -63,38 +80,228 @@ pub fn parse_app_msg(bytes: &[u8]) -> Option<AppMsg> {
     let kind = it.next()?;
 
     match kind {
-        "SYN" => {
+        "KA" => {
             let token = it.next().and_then(parse_hex);
-            token.map(|token| AppMsg::Syn { token })
+            token.map(|token| AppMsg::KeepAlive { token })
+        }
+        "PR" | "PA" => {
+            let id = it.next().and_then(|t| u32::from_str_radix(t, 16).ok())?;
+            Some(if kind == "PR" {
+                AppMsg::Probe { id }
+            } else {
+                AppMsg::ProbeAck { id }
+            })
+        }
+        _ => None,
+    }
+}
+
+/// MAC keys of the session messages, one per direction.
+///
+/// Each side signs with a key derived from its outbound SRTP keys and
+/// checks with one derived from its inbound ones, which are the peer's
+/// outbound keys. A message reflected back to its sender therefore fails
+/// too, and every new DTLS handshake brings new keys.
+///
+/// Each message is numbered, and a number is accepted once: the highest one
+/// seen and which of the [`REPLAY_WINDOW`] before it arrived are kept, as
+/// SRTP does.
+#[derive(Default)]
+pub struct AppMsgAuth {
+    send: Option<[u8; 32]>,
+    recv: Option<[u8; 32]>,
+    /// Number of the last message sealed.
+    sent: AtomicU64,
+    /// Highest number opened, and a bit per number below it that was.
+    replay: Mutex<(u64, u64)>,
+}
+
+impl fmt::Debug for AppMsgAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AppMsgAuth")
+            .field("authenticated", &self.is_authenticated())
+            .finish()
+    }
+}
+
+impl AppMsgAuth {
+    /// Keys from the SRTP configuration of the session; without one,
+    /// messages are sent and accepted without a MAC.
+    #[must_use]
+    pub fn from_srtp(cfg: Option<&SrtpSessionConfig>) -> Self {
+        cfg.map_or_else(Self::default, |cfg| Self {
+            send: Some(derive_key(&cfg.outbound)),
+            recv: Some(derive_key(&cfg.inbound)),
+            ..Self::default()
+        })
+    }
+
+    /// Whether messages are signed and checked.
+    #[must_use]
+    pub const fn is_authenticated(&self) -> bool {
+        self.send.is_some()
+    }
+
+    /// Bytes [`seal`](Self::seal) adds to a message.
+    #[must_use]
+    pub const fn overhead(&self) -> usize {
+        if self.is_authenticated() {
+            // " <counter> <tag>", both in hex
+            1 + 16 + 1 + 2 * TAG_LEN
+        } else {
+            0
         }
-        "SYN-ACK" => {
-            let your = it.next().and_then(parse_hex);
-            let mine = it.next().and_then(parse_hex);
-            match (your, mine) {
-                (Some(your), Some(mine)) => Some(AppMsg::SynAck { your, mine }),
-                _ => None,
+    }
+
+    /// `msg` (an encoded message) with its number and MAC appended.
+    #[must_use]
+    pub fn seal(&self, msg: String) -> String {
+        match &self.send {
+            Some(key) => {
+                let counter = self.sent.fetch_add(1, Ordering::SeqCst) + 1;
+                let numbered = format!("{msg} {counter:016x}");
+                let tag = hex(&mac(key, numbered.as_bytes())[..TAG_LEN]);
+                format!("{numbered} {tag}")
             }
+            None => msg,
         }
-        "ACK" => {
-            let your = it.next().and_then(parse_hex);
-            your.map(|your| AppMsg::Ack { your })
+    }
+
+    /// Parses a received message, if its MAC checks out and its number was
+    /// not seen before.
+    #[must_use]
+    pub fn open(&self, bytes: &[u8]) -> Option<AppMsg> {
+        let Some(key) = &self.recv else {
+            return parse_app_msg(bytes);
+        };
+        let text = std::str::from_utf8(bytes).ok()?.trim();
+        let (numbered, tag) = text.rsplit_once(' ')?;
+        let tag = unhex(tag)?;
+        if tag.len() != TAG_LEN {
+            return None;
         }
-        "FIN" => {
-            let token = it.next().and_then(parse_hex);
-            token.map(|token| AppMsg::Fin { token })
+        let mut check = HmacSha256::new_from_slice(key).ok()?;
+        check.update(numbered.as_bytes());
+        check.verify_truncated_left(&tag).ok()?;
+        let (msg, counter) = numbered.rsplit_once(' ')?;
+        let msg = parse_app_msg(msg.as_bytes())?;
+        self.accept(parse_hex(counter)?).then_some(msg)
+    }
+
+    /// Records `counter` as seen; `false` if it already was, or is too old
+    /// to tell.
+    fn accept(&self, counter: u64) -> bool {
+        let mut replay = self.replay.lock().unwrap_or_else(PoisonError::into_inner);
+        let (highest, seen) = &mut *replay;
+        if counter > *highest {
+            let shift = counter - *highest;
+            *seen = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                (*seen << shift) | (1 << (shift - 1))
+            };
+            *highest = counter;
+            return true;
         }
-        "FIN-ACK" => {
-            let your = it.next().and_then(parse_hex);
-            let mine = it.next().and_then(parse_hex);
-            match (your, mine) {
-                (Some(your), Some(mine)) => Some(AppMsg::FinAck { your, mine }),
-                _ => None,
-            }
+        let behind = *highest - counter;
+        if behind == 0 || behind > REPLAY_WINDOW || *seen & (1 << (behind - 1)) != 0 {
+            return false;
         }
-        "FIN-ACK2" => {
-            let your = it.next().and_then(parse_hex);
-            your.map(|your| AppMsg::FinAck2 { your })
+        *seen |= 1 << (behind - 1);
+        true
+    }
+}
+
+fn derive_key(keys: &SrtpEndpointKeys) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(KEY_LABEL)
+        .chain_update(&keys.master_key)
+        .chain_update(&keys.master_salt)
+        .finalize()
+        .into()
+}
+
+#[allow(clippy::expect_used)]
+fn mac(key: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::srtp::SrtpProfile;
+
+    fn keys(byte: u8) -> SrtpEndpointKeys {
+        SrtpEndpointKeys {
+            master_key: vec![byte; 16],
+            master_salt: vec![byte; 14],
         }
-        _ => None,
+    }
+
+    fn cfg(outbound: u8, inbound: u8) -> SrtpSessionConfig {
+        SrtpSessionConfig {
+            profile: SrtpProfile::Aes128CmHmacSha1_80,
+            outbound: keys(outbound),
+            inbound: keys(inbound),
+        }
+    }
+
+    #[test]
+    fn only_the_dtls_peer_can_send_session_messages() {
+        let alice = AppMsgAuth::from_srtp(Some(&cfg(1, 2)));
+        let bob = AppMsgAuth::from_srtp(Some(&cfg(2, 1)));
+        let ka = alice.seal(encode_keepalive(0xABCD));
+
+        assert_eq!(
+            bob.open(ka.as_bytes()),
+            Some(AppMsg::KeepAlive { token: 0xABCD })
+        );
+        // Captured and sent again
+        assert_eq!(bob.open(ka.as_bytes()), None);
+        // Unsigned, forged, or reflected back to its sender
+        assert_eq!(bob.open(encode_keepalive(0xABCD).as_bytes()), None);
+        let forged = ka.replacen("abcd", "abce", 1);
+        assert_eq!(bob.open(forged.as_bytes()), None);
+        assert_eq!(alice.open(ka.as_bytes()), None);
+        // Another DTLS session: other keys
+        let eve = AppMsgAuth::from_srtp(Some(&cfg(3, 1)));
+        assert_eq!(bob.open(eve.seal(encode_keepalive(1)).as_bytes()), None);
+
+        // Probes keep the size they were padded to
+        let probe = alice.seal(encode_probe(7, 1200 - alice.overhead()));
+        assert_eq!(probe.len(), 1200);
+        assert_eq!(bob.open(probe.as_bytes()), Some(AppMsg::Probe { id: 7 }));
+        let ack = bob.seal(encode_probe_ack(7));
+        assert_eq!(alice.open(ack.as_bytes()), Some(AppMsg::ProbeAck { id: 7 }));
+
+        // Reordered messages are still taken, each once
+        let (first, second) = (
+            alice.seal(encode_keepalive(1)),
+            alice.seal(encode_keepalive(2)),
+        );
+        assert!(bob.open(second.as_bytes()).is_some());
+        assert!(bob.open(first.as_bytes()).is_some());
+        assert_eq!(bob.open(first.as_bytes()), None);
+
+        let plain = AppMsgAuth::from_srtp(None);
+        assert!(!plain.is_authenticated());
+        assert_eq!(plain.seal(encode_keepalive(7)), encode_keepalive(7));
     }
 }
@@ -0,0 +1,172 @@
+//! Bandwidth caps for metered connections.
+//!
+//! A [`QualityPreset`] names a maximum video bitrate. The cap clamps both the
+//! congestion controller's output and the encoder configuration, and can be
+//! changed mid-call.
+//!
+//! [`LowDataMode`] goes further for constrained links: a lower cap, a
+//! lower capture frame rate, and G.711 audio instead of L16.
+
+use std::{fmt, str::FromStr};
+
+use crate::{config::Config, core::constants::MAX_BITRATE};
+
+const DEFAULT_LOW_BITRATE: u32 = 300_000;
+const DEFAULT_MEDIUM_BITRATE: u32 = 800_000;
+const DEFAULT_HIGH_BITRATE: u32 = 1_500_000;
+const DEFAULT_LOW_DATA_BITRATE: u32 = 300_000;
+const DEFAULT_LOW_DATA_FPS: u32 = 15;
+
+/// The configured bitrate cap (bps): an explicit `[Media] max_bitrate`
+/// wins over the `quality` preset, and low data mode lowers either.
+#[must_use]
+pub fn configured_max_bitrate(config: &Config) -> u32 {
+    let cap = config
+        .get("Media", "max_bitrate")
+        .and_then(|s| s.parse().ok())
+        .or_else(|| QualityPreset::from_config(config).map(|p| p.max_bitrate(config)))
+        .unwrap_or(MAX_BITRATE);
+    LowDataMode::from_config(config).map_or(cap, |mode| cap.min(mode.max_bitrate))
+}
+
+/// The "low data mode" preset, on with `[Media] low_data_mode = true`.
+///
+/// Video is capped at `low_data_bitrate` (bps) and captured at no more than
+/// `low_data_fps`; audio is offered as G.711 only (64 kbps) instead of L16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LowDataMode {
+    pub max_bitrate: u32,
+    pub max_fps: u32,
+}
+
+impl LowDataMode {
+    /// The preset, whether or not it is switched on.
+    #[must_use]
+    pub fn preset(config: &Config) -> Self {
+        let parse = |key| config.get("Media", key).and_then(|s| s.parse().ok());
+        Self {
+            max_bitrate: parse("low_data_bitrate").unwrap_or(DEFAULT_LOW_DATA_BITRATE),
+            max_fps: parse("low_data_fps").unwrap_or(DEFAULT_LOW_DATA_FPS),
+        }
+    }
+
+    /// The preset if `[Media] low_data_mode` switches it on.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Self::is_enabled(config).then(|| Self::preset(config))
+    }
+
+    #[must_use]
+    pub fn is_enabled(config: &Config) -> bool {
+        config.get_non_empty("Media", "low_data_mode") == Some("true")
+    }
+}
+
+/// Named video quality levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl QualityPreset {
+    /// Maximum bitrate (bps) for this preset, read from the `[Media]`
+    /// `quality_<preset>_bitrate` key.
+    #[must_use]
+    pub fn max_bitrate(self, config: &Config) -> u32 {
+        let (key, default) = match self {
+            Self::Low => ("quality_low_bitrate", DEFAULT_LOW_BITRATE),
+            Self::Medium => ("quality_medium_bitrate", DEFAULT_MEDIUM_BITRATE),
+            Self::High => ("quality_high_bitrate", DEFAULT_HIGH_BITRATE),
+        };
+        config
+            .get("Media", key)
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// The preset selected by the `[Media] quality` key, if any.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.get("Media", "quality").and_then(|s| s.parse().ok())
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for QualityPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for QualityPreset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn parses_case_insensitively() {
+        assert_eq!("Low".parse(), Ok(QualityPreset::Low));
+        assert_eq!(" medium ".parse(), Ok(QualityPreset::Medium));
+        assert_eq!("HIGH".parse(), Ok(QualityPreset::High));
+        assert!("ultra".parse::<QualityPreset>().is_err());
+    }
+
+    #[test]
+    fn presets_are_ordered_by_default() {
+        let config = Config::empty();
+        let low = QualityPreset::Low.max_bitrate(&config);
+        let medium = QualityPreset::Medium.max_bitrate(&config);
+        let high = QualityPreset::High.max_bitrate(&config);
+        assert!(low < medium && medium < high);
+        assert_eq!(QualityPreset::from_config(&config), None);
+    }
+
+    #[test]
+    fn explicit_cap_wins_over_the_preset() {
+        let mut config = Config::empty();
+        assert_eq!(configured_max_bitrate(&config), MAX_BITRATE);
+        config.set("Media", "quality", "low");
+        assert_eq!(configured_max_bitrate(&config), DEFAULT_LOW_BITRATE);
+        config.set("Media", "max_bitrate", "900000");
+        assert_eq!(configured_max_bitrate(&config), 900_000);
+    }
+
+    #[test]
+    fn low_data_mode_lowers_the_cap() {
+        let mut config = Config::empty();
+        config.set("Media", "max_bitrate", "900000");
+        assert_eq!(LowDataMode::from_config(&config), None);
+
+        config.set("Media", "low_data_mode", "true");
+        config.set("Media", "low_data_fps", "10");
+        let mode = LowDataMode::from_config(&config).unwrap();
+        assert_eq!(mode.max_fps, 10);
+        assert_eq!(configured_max_bitrate(&config), DEFAULT_LOW_DATA_BITRATE);
+
+        config.set("Media", "max_bitrate", "200000");
+        assert_eq!(configured_max_bitrate(&config), 200_000);
+    }
+}
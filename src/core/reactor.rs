@@ -0,0 +1,254 @@
+//! One thread waiting on the sockets of every session.
+//!
+//! Each session used to receive on a thread of its own, spinning on a
+//! non-blocking socket. Now the socket is registered here with the function
+//! that reads it: the reactor thread sleeps in a single wait on all of them
+//! (`epoll`, `kqueue`, IOCP through the `polling` crate) and calls the
+//! function of each socket that became readable. With dozens of calls that
+//! is one thread, idle until a packet arrives.
+//!
+//! Like [`crate::core::timers`], handlers run one after another on the
+//! reactor thread: they must read what is waiting and return, not block.
+//! Dropping a [`Registration`] returns once its handler is gone, so a
+//! session being torn down is never called again.
+//!
+//! The DTLS handshake is not a task here: it runs on the caller's thread
+//! (the peer connection's poll) until it completes or times out.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::UdpSocket,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError},
+    thread::{self, ThreadId},
+};
+
+use polling::{Event, Events, Poller};
+
+/// Reads what is waiting on its socket; `false` once it is done with it.
+pub type Handler = Box<dyn FnMut() -> bool + Send>;
+
+/// The reactor thread.
+pub struct Reactor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    poller: Poller,
+    sources: Mutex<Sources>,
+    /// Signalled each time a handler returns.
+    idle: Condvar,
+    thread: OnceLock<ThreadId>,
+}
+
+#[derive(Default)]
+struct Sources {
+    next_key: usize,
+    entries: HashMap<usize, Source>,
+    /// The key whose handler is running, if any.
+    running: Option<usize>,
+}
+
+struct Source {
+    sock: Arc<UdpSocket>,
+    /// Taken while the handler runs.
+    handler: Option<Handler>,
+}
+
+/// A socket registered with the [`Reactor`]; dropping it deregisters the
+/// socket and waits for its handler to finish, if it is running.
+pub struct Registration {
+    inner: Arc<Inner>,
+    key: usize,
+}
+
+impl Reactor {
+    /// The process-wide reactor thread, started on first use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OS fails to create the poller or the thread.
+    #[allow(clippy::expect_used)]
+    pub fn shared() -> &'static Self {
+        static SHARED: OnceLock<Reactor> = OnceLock::new();
+        SHARED.get_or_init(|| {
+            let inner = Arc::new(Inner {
+                poller: Poller::new().expect("create session-io poller"),
+                sources: Mutex::default(),
+                idle: Condvar::new(),
+                thread: OnceLock::new(),
+            });
+            let io = Arc::clone(&inner);
+            let handle = thread::Builder::new()
+                .name("session-io".into())
+                .spawn(move || io.run())
+                .expect("spawn session-io");
+            let _ = inner.thread.set(handle.thread().id());
+            Self { inner }
+        })
+    }
+
+    /// Calls `handler` on the reactor thread each time `sock` has something
+    /// to read, until it returns `false` or the registration is dropped.
+    /// The socket is made non-blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns the OS error if the socket cannot be made non-blocking or
+    /// added to the poller.
+    pub fn register(&self, sock: Arc<UdpSocket>, handler: Handler) -> io::Result<Registration> {
+        sock.set_nonblocking(true)?;
+        let mut sources = self.inner.lock();
+        let key = sources.next_key;
+        sources.next_key += 1;
+        // SAFETY: the socket is kept in `sources` until it is deleted from
+        // the poller, so the descriptor stays open while registered.
+        unsafe { self.inner.poller.add(&*sock, Event::readable(key))? };
+        sources.entries.insert(
+            key,
+            Source {
+                sock,
+                handler: Some(handler),
+            },
+        );
+        Ok(Registration {
+            inner: Arc::clone(&self.inner),
+            key,
+        })
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, Sources> {
+        self.sources.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn run(&self) {
+        let mut events = Events::new();
+        loop {
+            events.clear();
+            match self.poller.wait(&mut events, None) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => return,
+            }
+            for event in events.iter() {
+                self.dispatch(event.key);
+            }
+        }
+    }
+
+    /// Runs the handler of `key`, then re-arms its socket (interest is
+    /// one-shot) or drops it.
+    fn dispatch(&self, key: usize) {
+        let (mut handler, sock) = {
+            let mut sources = self.lock();
+            let Some(source) = sources.entries.get_mut(&key) else {
+                return;
+            };
+            let Some(handler) = source.handler.take() else {
+                return;
+            };
+            let sock = Arc::clone(&source.sock);
+            sources.running = Some(key);
+            (handler, sock)
+        };
+
+        // Not holding the lock while the handler runs: it may register
+        let keep = panic::catch_unwind(AssertUnwindSafe(&mut handler)).unwrap_or(false);
+
+        let finished = {
+            let mut sources = self.lock();
+            match sources.entries.get_mut(&key) {
+                Some(source) if keep => {
+                    source.handler = Some(handler);
+                    let _ = self.poller.modify(&*sock, Event::readable(key));
+                    None
+                }
+                Some(_) => {
+                    sources.entries.remove(&key);
+                    let _ = self.poller.delete(&*sock);
+                    Some(handler)
+                }
+                // Deregistered while it ran
+                None => Some(handler),
+            }
+        };
+        drop(finished);
+        self.lock().running = None;
+        self.idle.notify_all();
+    }
+
+    fn deregister(&self, key: usize) {
+        let mut sources = self.lock();
+        if let Some(source) = sources.entries.remove(&key) {
+            let _ = self.poller.delete(&*source.sock);
+        }
+        // A handler dropping its own registration cannot wait for itself
+        if self.thread.get() == Some(&thread::current().id()) {
+            return;
+        }
+        while sources.running == Some(key) {
+            sources = self
+                .idle
+                .wait(sources)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.inner.deregister(self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::{sync::mpsc, time::Duration};
+
+    #[test]
+    fn sockets_are_read_on_one_thread_until_deregistered() {
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut registrations = Vec::new();
+        let mut addrs = Vec::new();
+        for id in 0..2 {
+            let sock = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+            addrs.push(sock.local_addr().unwrap());
+            let reader = Arc::clone(&sock);
+            let tx = tx.clone();
+            let handler = Box::new(move || {
+                let mut buf = [0; 16];
+                while let Ok(n) = reader.recv(&mut buf) {
+                    tx.send((id, buf[..n].to_vec(), thread::current().id()))
+                        .unwrap();
+                }
+                true
+            });
+            registrations.push(Reactor::shared().register(sock, handler).unwrap());
+        }
+
+        for addr in &addrs {
+            sender.send_to(b"hi", addr).unwrap();
+        }
+        let got: Vec<_> = (0..2)
+            .map(|_| rx.recv_timeout(Duration::from_secs(2)).unwrap())
+            .collect();
+        assert!(got.iter().any(|(id, _, _)| *id == 0));
+        assert!(got.iter().any(|(id, _, _)| *id == 1));
+        assert_eq!(got[0].2, got[1].2);
+        assert_eq!(got[0].1, b"hi");
+
+        // Gone once dropped: nothing more is read from its socket
+        drop(registrations.remove(0));
+        sender.send_to(b"late", addrs[0]).unwrap();
+        sender.send_to(b"on", addrs[1]).unwrap();
+        let (id, payload, _) = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!((id, payload.as_slice()), (1, &b"on"[..]));
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
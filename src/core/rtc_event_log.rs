@@ -0,0 +1,355 @@
+//! Diagnostic capture of a call's transport events, for offline analysis.
+//!
+//! With `[Diagnostics] rtc_event_log = true`, every call writes one file
+//! with the RTP and RTCP headers it sends and receives, the ICE checks, the
+//! DTLS handshake and the congestion controller's decisions, each stamped
+//! with the microseconds since the capture started. Recording never blocks
+//! the media path: records go through a bounded queue to a writer thread
+//! and are dropped if it falls behind. The file is only created once the
+//! first record arrives, so calls that never connect leave nothing behind.
+//!
+//! # File format
+//!
+//! All integers are big endian. The file starts with [`MAGIC`] and the
+//! capture start as UNIX microseconds (`u64`), followed by records:
+//!
+//! | field  | size | meaning                          |
+//! |--------|------|----------------------------------|
+//! | kind   | 1    | [`RtcEventKind`]                 |
+//! | at     | 8    | microseconds since capture start |
+//! | length | 2    | length of `data`                 |
+//! | data   | *    | depends on `kind`                |
+//!
+//! * RTP and RTCP: the packet length (`u16`), then its first
+//!   [`RTP_HEADER_LEN`] or [`RTCP_HEADER_LEN`] bytes (headers are not
+//!   encrypted by SRTP).
+//! * ICE checks: the direction and address as text (`in 10.0.0.2:50000 `)
+//!   followed by the check message, up to [`ICE_CHECK_LEN`] bytes.
+//! * DTLS: a UTF-8 description (`handshake started as client`, ...).
+//! * Congestion: RTT in ms (`u32`), fraction lost (`u8`, /256), bitrate
+//!   before and after (`u32` each, bps).
+//!
+//! [`read_events`] decodes a file.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, SyncSender},
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::Config, congestion_controller::NetworkMetrics, log::logger};
+
+/// First bytes of every capture file; the last one is the format version.
+pub const MAGIC: &[u8; 8] = b"RRTCLOG\x01";
+/// Bytes kept of each RTP packet: the fixed header.
+pub const RTP_HEADER_LEN: usize = 12;
+/// Bytes kept of each RTCP packet: header and sender SSRC of the first
+/// packet of the compound.
+pub const RTCP_HEADER_LEN: usize = 8;
+/// Bytes kept of each ICE check.
+pub const ICE_CHECK_LEN: usize = 64;
+/// Records queued for the writer before new ones are dropped.
+const QUEUE_LEN: usize = 4096;
+
+/// What a record describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RtcEventKind {
+    RtpIn = 1,
+    RtpOut = 2,
+    RtcpIn = 3,
+    RtcpOut = 4,
+    IceCheck = 5,
+    Dtls = 6,
+    Congestion = 7,
+}
+
+impl RtcEventKind {
+    const ALL: [Self; 7] = [
+        Self::RtpIn,
+        Self::RtpOut,
+        Self::RtcpIn,
+        Self::RtcpOut,
+        Self::IceCheck,
+        Self::Dtls,
+        Self::Congestion,
+    ];
+
+    fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| *k as u8 == value)
+    }
+}
+
+/// One decoded record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtcEvent {
+    pub kind: RtcEventKind,
+    /// Microseconds since the capture started.
+    pub at_us: u64,
+    pub data: Vec<u8>,
+}
+
+type Record = (RtcEventKind, u64, Vec<u8>);
+
+/// Handle to one call's capture; cheap to clone, never blocks.
+#[derive(Debug, Clone)]
+pub struct RtcEventLog {
+    tx: SyncSender<Record>,
+    start: Instant,
+    path: PathBuf,
+}
+
+impl RtcEventLog {
+    /// A capture for a call with `peer` if `[Diagnostics] rtc_event_log` is
+    /// on. Files go to `[Diagnostics] rtc_event_log_path`, by default the
+    /// `logs` directory next to the executable.
+    #[must_use]
+    pub fn from_config(config: &Config, peer: &str) -> Option<Self> {
+        let enabled = config
+            .get("Diagnostics", "rtc_event_log")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("true"));
+        if !enabled {
+            return None;
+        }
+        let dir = config
+            .get_non_empty("Diagnostics", "rtc_event_log_path")
+            .map_or_else(
+                || logger::exe_dir_fallback_cwd().join("logs"),
+                PathBuf::from,
+            );
+        let peer: String = peer
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let name = format!(
+            "rtc-{peer}-{}-{}-pid{}.rtclog",
+            now.as_secs(),
+            now.subsec_millis(),
+            std::process::id()
+        );
+        Some(Self::create(dir.join(name)))
+    }
+
+    /// A capture written to `path` (created with the first record).
+    #[must_use]
+    pub fn create(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let start_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| u64::try_from(d.as_micros()).unwrap_or(u64::MAX));
+        let (tx, rx) = mpsc::sync_channel::<Record>(QUEUE_LEN);
+        let file_path = path.clone();
+        let _ = thread::Builder::new()
+            .name("rtc-event-log".into())
+            .spawn(move || {
+                let Ok(first) = rx.recv() else {
+                    return;
+                };
+                let Ok(mut out) = open(&file_path, start_us) else {
+                    return;
+                };
+                for (kind, at_us, data) in std::iter::once(first).chain(rx) {
+                    if write_record(&mut out, kind, at_us, &data).is_err() {
+                        return;
+                    }
+                }
+                let _ = out.flush();
+            });
+        Self {
+            tx,
+            start: Instant::now(),
+            path,
+        }
+    }
+
+    /// The capture file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn record(&self, kind: RtcEventKind, data: Vec<u8>) {
+        let at_us = u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX);
+        let _ = self.tx.try_send((kind, at_us, data));
+    }
+
+    fn record_header(&self, kind: RtcEventKind, pkt: &[u8], keep: usize) {
+        let len = u16::try_from(pkt.len()).unwrap_or(u16::MAX);
+        let mut data = len.to_be_bytes().to_vec();
+        data.extend_from_slice(&pkt[..pkt.len().min(keep)]);
+        self.record(kind, data);
+    }
+
+    /// A received RTP packet (decrypted or not; the header is the same).
+    pub fn rtp_in(&self, pkt: &[u8]) {
+        self.record_header(RtcEventKind::RtpIn, pkt, RTP_HEADER_LEN);
+    }
+
+    /// A sent RTP packet.
+    pub fn rtp_out(&self, pkt: &[u8]) {
+        self.record_header(RtcEventKind::RtpOut, pkt, RTP_HEADER_LEN);
+    }
+
+    /// A received RTCP (compound) packet.
+    pub fn rtcp_in(&self, pkt: &[u8]) {
+        self.record_header(RtcEventKind::RtcpIn, pkt, RTCP_HEADER_LEN);
+    }
+
+    /// A sent RTCP (compound) packet.
+    pub fn rtcp_out(&self, pkt: &[u8]) {
+        self.record_header(RtcEventKind::RtcpOut, pkt, RTCP_HEADER_LEN);
+    }
+
+    /// An ICE connectivity check received from or sent to `addr`.
+    pub fn ice_check(&self, incoming: bool, addr: SocketAddr, pkt: &[u8]) {
+        let direction = if incoming { "in" } else { "out" };
+        let mut data = format!("{direction} {addr} ").into_bytes();
+        data.extend_from_slice(&pkt[..pkt.len().min(ICE_CHECK_LEN)]);
+        self.record(RtcEventKind::IceCheck, data);
+    }
+
+    /// A step of the DTLS handshake.
+    pub fn dtls(&self, event: &str) {
+        self.record(RtcEventKind::Dtls, event.as_bytes().to_vec());
+    }
+
+    /// The congestion controller saw `metrics` and moved the bitrate from
+    /// `from_bps` to `to_bps` (equal if it kept it).
+    pub fn congestion(&self, metrics: &NetworkMetrics, from_bps: u32, to_bps: u32) {
+        let rtt_ms = u32::try_from(metrics.round_trip_time.as_millis()).unwrap_or(u32::MAX);
+        let mut data = Vec::with_capacity(13);
+        data.extend_from_slice(&rtt_ms.to_be_bytes());
+        data.push(metrics.fraction_lost);
+        data.extend_from_slice(&from_bps.to_be_bytes());
+        data.extend_from_slice(&to_bps.to_be_bytes());
+        self.record(RtcEventKind::Congestion, data);
+    }
+}
+
+fn open(path: &Path, start_us: u64) -> io::Result<BufWriter<File>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&start_us.to_be_bytes())?;
+    Ok(out)
+}
+
+fn write_record(
+    out: &mut impl Write,
+    kind: RtcEventKind,
+    at_us: u64,
+    data: &[u8],
+) -> io::Result<()> {
+    let len = u16::try_from(data.len()).unwrap_or(u16::MAX);
+    out.write_all(&[kind as u8])?;
+    out.write_all(&at_us.to_be_bytes())?;
+    out.write_all(&len.to_be_bytes())?;
+    out.write_all(&data[..usize::from(len)])
+}
+
+/// Decodes a capture: its start as UNIX microseconds, and its records.
+///
+/// # Errors
+///
+/// Returns an error if the input is not a capture or cannot be read. A
+/// record cut short at the end (the program stopped mid-write) is ignored.
+pub fn read_events(mut input: impl Read) -> io::Result<(u64, Vec<RtcEvent>)> {
+    let mut bytes = Vec::new();
+    input.read_to_end(&mut bytes)?;
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not an RTC event log");
+    let rest = bytes.strip_prefix(MAGIC.as_slice()).ok_or_else(invalid)?;
+    let (start, mut rest) = rest.split_first_chunk::<8>().ok_or_else(invalid)?;
+
+    let mut events = Vec::new();
+    while let Some((&[kind], tail)) = rest.split_first_chunk::<1>()
+        && let Some((at, tail)) = tail.split_first_chunk::<8>()
+        && let Some((len, tail)) = tail.split_first_chunk::<2>()
+        && let Some(data) = tail.get(..usize::from(u16::from_be_bytes(*len)))
+    {
+        rest = &tail[data.len()..];
+        let kind = RtcEventKind::from_u8(kind).ok_or_else(invalid)?;
+        events.push(RtcEvent {
+            kind,
+            at_us: u64::from_be_bytes(*at),
+            data: data.to_vec(),
+        });
+    }
+    Ok((u64::from_be_bytes(*start), events))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn records_round_trip_through_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("rustyrtc-rtclog-{}.rtclog", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let log = RtcEventLog::create(&path);
+        let rtp = [0x80, 96, 0, 1, 0, 0, 0, 90, 0xde, 0xad, 0xbe, 0xef, 1, 2, 3];
+        log.rtp_out(&rtp);
+        log.dtls("handshake started as client");
+        let metrics = NetworkMetrics {
+            round_trip_time: Duration::from_millis(120),
+            fraction_lost: 64,
+            packets_lost: 10,
+            highest_sequence_number: 1,
+        };
+        log.congestion(&metrics, 1_000_000, 850_000);
+        drop(log);
+
+        // The writer finishes once every handle is gone
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let (_, events) = loop {
+            if let Ok(file) = File::open(&path)
+                && let Ok((start, events)) = read_events(file)
+                && events.len() == 3
+            {
+                break (start, events);
+            }
+            assert!(Instant::now() < deadline, "capture not written");
+            thread::sleep(Duration::from_millis(20));
+        };
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(events[0].kind, RtcEventKind::RtpOut);
+        assert_eq!(events[0].data[..2], [0, 15]);
+        assert_eq!(events[0].data[2..], rtp[..RTP_HEADER_LEN]);
+        assert_eq!(events[1].data, b"handshake started as client");
+        assert_eq!(
+            events[2].data,
+            [0, 0, 0, 120, 64, 0, 0x0f, 0x42, 0x40, 0, 0x0c, 0xf8, 0x50]
+        );
+        assert!(events.windows(2).all(|w| w[0].at_us <= w[1].at_us));
+    }
+
+    #[test]
+    fn off_unless_enabled() {
+        let mut config = Config::empty();
+        assert!(RtcEventLog::from_config(&config, "bob").is_none());
+        config.set("Diagnostics", "rtc_event_log", "true");
+        config.set("Diagnostics", "rtc_event_log_path", "/tmp/captures");
+        let log = RtcEventLog::from_config(&config, "bob/../x").unwrap();
+        let name = log.path().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("rtc-bob____x-"), "{name}");
+        assert!(log.path().starts_with("/tmp/captures"));
+    }
+}
@@ -1,58 +1,90 @@
 //! Session management module.
 //!
-//! Handles the life cycle of a WebRTC session, including handshake, keep-alive,
-//! data transmission (RTP/SCTP), and tear-down.
+//! Handles the life cycle of a WebRTC session, including keep-alive, data
+//! transmission (RTP/SCTP), and tear-down.
+//!
+//! The session is established as soon as it starts, since the DTLS
+//! handshake that set it up already proved the peer is there and holds the
+//! keys. Closing sends a DTLS `close_notify`, which the peer answers with
+//! its own; a peer that vanishes instead is caught by ICE consent.
+//!
+//! With `[ICE] mtu_discovery` on, the session also probes the path MTU
+//! once established and reports what it found (see
+//! [`crate::core::path_mtu`]).
 
-use crate::{sink_debug, sink_error, sink_info, srtp::SrtpSessionConfig};
+use crate::{sink_debug, sink_error, sink_info, sink_warn, srtp::SrtpSessionConfig};
 use rand::{RngCore, rngs::OsRng};
 use std::{
     net::{self, UdpSocket},
     sync::{
         Arc, Mutex,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, Ordering},
         mpsc::{self, Sender},
     },
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use crate::rtp_session::{
     RtpSession, outbound_track_handle::OutboundTrackHandle, rtp_codec::RtpCodec,
-    rtp_recv_config::RtpRecvConfig,
+    rtp_recv_config::RtpRecvConfig, rtp_send_config::RtpSendConfig,
+    rtp_session_error::RtpSessionError,
 };
 use crate::{
+    connection_manager::rtp_map::PayloadTypeMap,
     core::{
+        buffer_pool::BufferPool,
+        clock::{self, SharedClock},
+        consent::PeerActivity,
         events::EngineEvent,
-        protocol::{self, AppMsg},
+        path_mtu::{self, MtuConfig, MtuProber},
+        protocol::{self, AppMsg, AppMsgAuth},
+        reactor::{Reactor, Registration},
+        rtc_event_log::RtcEventLog,
+        socket_stats::SocketCounters,
+        stats::{InboundRtpStats, OutboundRtpStats, SocketStats, now_unix_ms},
+        timers::Timers,
+        udp_batch::{self, BATCH, BatchReceiver},
     },
     dtls::buffered_udp_channel::BufferedUdpChannel,
+    error::RtcError,
+    ice::type_ice::ice_agent,
     log::log_sink::LogSink,
     media_transport::payload::rtp_payload_chunk::RtpPayloadChunk,
+    rtp::{rtp_header_extension::RtpHeaderExtension, rtp_packet::RtpPacket},
     sctp::{events::SctpEvents, sctp_session::SctpSession},
 };
 use openssl::ssl::SslStream;
 
+/// How often the close driver checks for work.
+const DRIVER_TICK: Duration = Duration::from_millis(40);
+/// How long the close driver waits for the peer's `close_notify` before
+/// sending ours again; doubled after each retransmission.
+const CLOSE_RETRANSMIT: Duration = Duration::from_millis(250);
+/// How often the path MTU prober checks for a probe to send.
+const PROBE_TICK: Duration = Duration::from_millis(100);
+
 #[allow(unused_variables)]
 #[derive(Clone, Copy)]
 /// Configuration for a `Session`.
 pub struct SessionConfig {
-    /// The duration after which a handshake attempt will time out.
-    pub handshake_timeout: Duration,
-    /// The duration after which a message will be resent if no acknowledgment is received.
-    pub resend_every: Duration,
-    /// The duration after which a close attempt will time out.
+    /// How long to wait for the peer's `close_notify` when closing.
     pub close_timeout: Duration,
-    /// The duration after which a close message will be resent if no acknowledgment is received.
-    pub close_resend_every: Duration,
+    /// How often a keepalive is sent once established.
+    pub keepalive_every: Duration,
+    /// Datagram size, and whether to probe the path for a better one.
+    pub mtu: MtuConfig,
 }
 
-/// Represents a single WebRTC session, managing the handshake, media transport,
-/// and session closing.
+/// Represents a single WebRTC session, managing the media transport and
+/// session closing.
 pub struct Session {
     /// The UDP socket used for communication.
     sock: Arc<UdpSocket>,
     /// The peer's socket address.
     peer: net::SocketAddr,
+    /// Packets, bytes and send errors on `sock`.
+    counters: Arc<SocketCounters>,
     /// List of remote RTP codecs.
     pub remote_codecs: Vec<RtpCodec>,
 
@@ -61,10 +93,8 @@ pub struct Session {
     /// Flag indicating if the session is established.
     established: Arc<AtomicBool>,
 
-    /// Local session token.
+    /// Local session token, sent in keepalives.
     token_local: u64,
-    /// Peer's session token.
-    token_peer: Arc<AtomicU64>,
 
     /// Flag indicating if we initiated the close.
     we_initiated_close: Arc<AtomicBool>,
@@ -86,13 +116,28 @@ pub struct Session {
     /// Sender for RTP media.
     rtp_media_tx: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
 
-    hs_got_syn: Arc<AtomicBool>,
-    hs_sent_synack: Arc<AtomicBool>,
-
     //SRTP config
     srtp_cfg: Option<SrtpSessionConfig>,
+    /// Signs and checks the keepalives.
+    app_auth: Arc<AppMsgAuth>,
 
     sctp_session: Arc<SctpSession>,
+    /// The path MTU search, while one runs.
+    mtu_prober: Arc<Mutex<Option<MtuProber>>>,
+
+    /// When the peer was last heard from, by the receiver, SCTP and RTP.
+    activity: Arc<PeerActivity>,
+    /// Where ICE connectivity checks arriving on the session socket are forwarded.
+    ice_tx: Option<Sender<(Vec<u8>, net::SocketAddr)>>,
+    event_log: Option<RtcEventLog>,
+    /// Buffers received packets are copied into.
+    buffers: BufferPool,
+    /// The socket's registration with the reactor, dropped with the session.
+    receiver: Option<Registration>,
+    /// Run the close and keepalive drivers and RTCP.
+    timers: Timers,
+    /// `timers`' clock.
+    clock: SharedClock,
 }
 
 /// Arguments for initializing a new `Session`.
@@ -115,24 +160,50 @@ pub struct SessionInitArgs {
     pub ssl_stream: SslStream<BufferedUdpChannel>,
     /// Whether we are the DTLS client (active opener)
     pub is_client: bool,
+    /// Receives ICE checks that arrive on the session socket (used by ICE restarts).
+    pub ice_tx: Option<Sender<(Vec<u8>, net::SocketAddr)>>,
+    /// Capture of the RTP and RTCP headers, if enabled.
+    pub event_log: Option<RtcEventLog>,
+    /// Buffers for received packets, given back by the depacketizer.
+    pub buffers: BufferPool,
 }
 
 impl Session {
     /// Creates a new `Session` instance.
     pub fn new(args: SessionInitArgs) -> Self {
         let (sctp_parent_tx, sctp_parent_rx) = mpsc::channel();
+        let activity = Arc::new(PeerActivity::new(clock::system()));
         let sctp_session = Arc::new(SctpSession::new(
             args.logger.clone(),
             sctp_parent_tx,
             args.ssl_stream,
             args.is_client,
+            args.cfg.mtu,
+            Arc::clone(&activity),
         ));
 
+        let established = Arc::new(AtomicBool::new(false));
+        let we_initiated_close = Arc::new(AtomicBool::new(false));
+        let peer_initiated_close = Arc::new(AtomicBool::new(false));
+        let close_done = Arc::new(AtomicBool::new(false));
+        let rtp_session = Arc::new(Mutex::new(None));
+        let rtp_media_tx = Arc::new(Mutex::new(None));
+
         // Spawn thread to forward SCTP events to EngineEvent
         let evt_tx_clone = args.event_tx.clone();
+        let peer_closed = PeerClosed {
+            established: Arc::clone(&established),
+            we_initiated_close: Arc::clone(&we_initiated_close),
+            peer_initiated_close: Arc::clone(&peer_initiated_close),
+            close_done: Arc::clone(&close_done),
+            rtp_session: Arc::clone(&rtp_session),
+            rtp_media_tx: Arc::clone(&rtp_media_tx),
+            logger: args.logger.clone(),
+        };
         thread::spawn(move || {
             while let Ok(ev) = sctp_parent_rx.recv() {
                 let engine_ev = match ev {
+                    SctpEvents::PeerClosed => peer_closed.handle(&evt_tx_clone),
                     SctpEvents::ReceivedOffer { file_properties } => {
                         Some(EngineEvent::ReceivedFileOffer(file_properties))
                     }
@@ -165,31 +236,48 @@ impl Session {
         Self {
             sock: args.sock,
             peer: args.peer,
+            counters: Arc::default(),
             remote_codecs: args.remote_codecs,
             run_flag: Arc::new(AtomicBool::new(false)),
-            established: Arc::new(AtomicBool::new(false)),
+            established,
             token_local: 0,
-            token_peer: Arc::new(AtomicU64::new(0)),
-            we_initiated_close: Arc::new(AtomicBool::new(false)),
-            peer_initiated_close: Arc::new(AtomicBool::new(false)),
-            close_done: Arc::new(AtomicBool::new(false)),
+            we_initiated_close,
+            peer_initiated_close,
+            close_done,
             tx_evt: args.event_tx,
             logger: args.logger,
             cfg: args.cfg,
-            rtp_session: Arc::new(Mutex::new(None)),
-            rtp_media_tx: Arc::new(Mutex::new(None)),
-            hs_got_syn: Arc::new(AtomicBool::new(false)),
-            hs_sent_synack: Arc::new(AtomicBool::new(false)),
+            rtp_session,
+            rtp_media_tx,
+            app_auth: Arc::new(AppMsgAuth::from_srtp(args.srtp_cfg.as_ref())),
             srtp_cfg: args.srtp_cfg,
             sctp_session,
+            mtu_prober: Arc::new(Mutex::new(None)),
+            activity,
+            ice_tx: args.ice_tx,
+            event_log: args.event_log,
+            buffers: args.buffers,
+            receiver: None,
+            timers: Timers::shared().clone(),
+            clock: clock::system(),
         }
     }
 
-    /// Starts the session, initiating the handshake process and media transport.
+    /// Runs the session's timed work on `timers` and its clock instead of
+    /// the shared timer thread.
+    #[must_use]
+    pub fn with_timers(mut self, timers: Timers) -> Self {
+        self.clock = timers.clock();
+        self.activity.use_clock(Arc::clone(&self.clock));
+        self.timers = timers;
+        self
+    }
+
+    /// Starts the session and its media transport. DTLS is already up, so
+    /// the session is established right away.
     pub fn start(&mut self) {
         // fresh tokens/flags
         self.token_local = OsRng.next_u64();
-        self.token_peer.store(0, Ordering::SeqCst);
         self.established.store(false, Ordering::SeqCst);
         self.we_initiated_close.store(false, Ordering::SeqCst);
         self.peer_initiated_close.store(false, Ordering::SeqCst);
@@ -202,9 +290,6 @@ impl Session {
 
         self.run_flag.store(true, Ordering::SeqCst);
 
-        self.hs_got_syn.store(false, Ordering::SeqCst);
-        self.hs_sent_synack.store(false, Ordering::SeqCst);
-
         // reset RTP plumbing before starting
         self.teardown_rtp();
 
@@ -219,13 +304,21 @@ impl Session {
         let rtp_result = RtpSession::new(
             Arc::clone(&self.sock),
             self.peer,
+            Arc::clone(&self.counters),
             self.tx_evt.clone(),
             self.logger.clone(),
             rx_media,
             initial_recv,
             Vec::new(),
             self.srtp_cfg.clone(),
+            self.event_log.clone(),
+            self.buffers.clone(),
         )
+        .map(|rtp| {
+            rtp.with_timers(self.timers.clone())
+                .with_payload_types(PayloadTypeMap::from_codecs(&self.remote_codecs))
+                .with_activity(Arc::clone(&self.activity))
+        })
         .and_then(|mut rtp| {
             if let Err(e) = rtp.start() {
                 Err(e)
@@ -252,202 +345,301 @@ impl Session {
             }
         }
 
-        self.spawn_receiver_thread();
-        self.spawn_handshake_driver_thread();
+        self.activity.heard();
+
+        self.register_receiver();
+        self.schedule_keepalive();
+        self.schedule_mtu_probes();
+
+        if !self.established.swap(true, Ordering::SeqCst) {
+            sink_debug!(&self.logger, "[SESSION] ESTABLISHED (DTLS up)");
+            let _ = self.tx_evt.send(EngineEvent::Established);
+        }
+    }
+
+    /// How long it has been since the peer was heard from: an
+    /// authenticated session message, SRTP packet or DTLS record.
+    #[must_use]
+    pub fn idle_for(&self) -> Duration {
+        self.activity.idle_for()
+    }
+
+    /// The local and remote addresses the session runs on.
+    #[must_use]
+    pub fn path(&self) -> Option<(net::SocketAddr, net::SocketAddr)> {
+        self.sock.local_addr().ok().map(|local| (local, self.peer))
+    }
+
+    /// What went through the session socket so far.
+    #[must_use]
+    pub fn socket_stats(&self) -> Option<SocketStats> {
+        self.path()
+            .map(|(local, remote)| self.counters.snapshot(now_unix_ms(), local, remote))
+    }
+
+    /// The socket counters, to check for new send errors.
+    #[must_use]
+    pub fn socket_counters(&self) -> &SocketCounters {
+        &self.counters
+    }
+
+    /// Sends keepalives while the session is established, so the peer can
+    /// tell a silent path from an idle one.
+    fn schedule_keepalive(&self) {
+        let run = Arc::clone(&self.run_flag);
+        let est = Arc::clone(&self.established);
+        let sock = Arc::clone(&self.sock);
+        let counters = Arc::clone(&self.counters);
+        let every = self.cfg.keepalive_every;
+        let token = self.token_local;
+        let auth = Arc::clone(&self.app_auth);
+
+        self.timers.schedule(Duration::ZERO, move || {
+            if !run.load(Ordering::SeqCst) {
+                return None;
+            }
+            if est.load(Ordering::SeqCst) {
+                let ka = auth.seal(protocol::encode_keepalive(token));
+                let _ = counters.record(sock.send(ka.as_bytes()));
+            }
+            Some(every)
+        });
+    }
+
+    /// Searches for the path MTU if `[ICE] mtu_discovery` is on, and
+    /// reports it with [`EngineEvent::PathMtu`] and to DTLS and SCTP, which
+    /// wait for it to set up the association. Probes need the Don't
+    /// Fragment bit, set on the socket for the time of the search; without
+    /// it the configured size is kept.
+    fn schedule_mtu_probes(&self) {
+        if !self.cfg.mtu.discovery {
+            return;
+        }
+        if let Err(e) = udp_batch::set_dont_fragment(&self.sock, true) {
+            sink_warn!(
+                &self.logger,
+                "[MTU] mtu_discovery is on but Don't Fragment cannot be set ({e}); keeping {} bytes",
+                self.cfg.mtu.mtu
+            );
+            self.sctp_session.path_mtu(None);
+            return;
+        }
+        let Ok(mut guard) = self.mtu_prober.lock() else {
+            self.sctp_session.path_mtu(None);
+            return;
+        };
+        *guard = Some(MtuProber::new(path_mtu::max_probe(self.peer)));
+        drop(guard);
+
+        let run = Arc::clone(&self.run_flag);
+        let est = Arc::clone(&self.established);
+        let sock = Arc::clone(&self.sock);
+        let prober = Arc::clone(&self.mtu_prober);
+        let auth = Arc::clone(&self.app_auth);
+        let clock = Arc::clone(&self.clock);
+        let tx = self.tx_evt.clone();
+        let sctp = Arc::clone(&self.sctp_session);
+        let logger = self.logger.clone();
+        let configured = self.cfg.mtu.mtu;
+
+        self.timers.schedule(Duration::ZERO, move || {
+            if !run.load(Ordering::SeqCst) {
+                return None;
+            }
+            let mut guard = prober.lock().ok()?;
+            let search = guard.as_mut()?;
+            if est.load(Ordering::SeqCst)
+                && let Some((id, size)) = search.poll(clock.now())
+            {
+                let probe = auth.seal(protocol::encode_probe(id, size - auth.overhead()));
+                // Too big for the local link: refused right away, which
+                // counts as lost like a probe dropped further on.
+                let _ = sock.send(probe.as_bytes());
+            }
+            if !search.is_done() {
+                return Some(PROBE_TICK);
+            }
+            let found = search.result();
+            sctp.path_mtu(found);
+            match found {
+                Some(mtu) => {
+                    sink_info!(&logger, "[MTU] path MTU is {mtu} bytes");
+                    let _ = tx.send(EngineEvent::PathMtu(mtu));
+                }
+                None => sink_info!(
+                    &logger,
+                    "[MTU] peer does not answer probes, keeping {configured} bytes"
+                ),
+            }
+            guard.take();
+            // Sizes past the configured one still fragment rather than drop
+            let _ = udp_batch::set_dont_fragment(&sock, false);
+            None
+        });
     }
 
-    /// Spawns a thread to receive and process incoming application messages.
-    fn spawn_receiver_thread(&self) {
+    /// Registers the socket with the shared reactor, which hands what
+    /// arrives to ICE, SCTP, RTP or the application messages.
+    fn register_receiver(&mut self) {
         let rx_run = Arc::clone(&self.run_flag);
         let rx_sock = Arc::clone(&self.sock);
-        let rx_tok_peer = Arc::clone(&self.token_peer);
+        let counters = Arc::clone(&self.counters);
         let rx_est = Arc::clone(&self.established);
-        let rx_close_done = Arc::clone(&self.close_done);
-        let rx_peer_init = Arc::clone(&self.peer_initiated_close);
-        let local_token = self.token_local;
         let tx = self.tx_evt.clone();
         let logger = self.logger.clone();
         let rtp_media_tx = Arc::clone(&self.rtp_media_tx);
-        let rtp_session_handle = Arc::clone(&self.rtp_session);
-        let hs_got_syn = Arc::clone(&self.hs_got_syn);
-        let hs_sent_synack = Arc::clone(&self.hs_sent_synack);
         let sctp_session = self.sctp_session.clone();
-
-        thread::spawn(move || {
-            let mut buf = [0u8; 65535];
-            let mut packet_batch: Vec<Vec<u8>> = Vec::with_capacity(64);
-
-            while rx_run.load(Ordering::SeqCst) {
-                // 1. Burst Drain
-                for _ in 0..64 {
-                    match rx_sock.recv(&mut buf) {
-                        Ok(n) => {
-                            if n > 0 {
-                                packet_batch.push(buf[..n].to_vec());
-                            }
-                        }
-                        Err(ref e)
-                            if e.kind() == std::io::ErrorKind::WouldBlock
-                                || e.kind() == std::io::ErrorKind::TimedOut =>
-                        {
-                            break;
-                        }
-                        Err(e) => {
-                            sink_error!(&logger, "recv error: {e}");
-                            let _ = tx.send(EngineEvent::Error(format!("recv error: {e}")));
-                            return;
-                        }
-                    }
+        let activity = Arc::clone(&self.activity);
+        let ice_tx = self.ice_tx.clone();
+        let peer = self.peer;
+        let buffers = self.buffers.clone();
+        let auth = Arc::clone(&self.app_auth);
+        let mtu_prober = Arc::clone(&self.mtu_prober);
+
+        let mut receiver = BatchReceiver::default();
+        let mut packet_batch: Vec<Vec<u8>> = Vec::with_capacity(BATCH);
+
+        let handler = Box::new(move || {
+            if !rx_run.load(Ordering::SeqCst) {
+                return false;
+            }
+            // 1. Burst Drain (one recvmmsg with the `udp-batch` feature)
+            let drained = receiver.recv(&rx_sock, |pkt| {
+                counters.received(pkt.len());
+                if !pkt.is_empty() {
+                    packet_batch.push(buffers.copy_of(pkt));
                 }
-
-                // 2. Process Batch
-                if packet_batch.is_empty() {
-                    thread::yield_now();
-                    continue;
+            });
+            match drained {
+                Ok(_) => {}
+                // ICMP port unreachable while the path is down: let the
+                // consent monitor decide whether the peer is gone.
+                Err(ref e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+                Err(e) => {
+                    sink_error!(&logger, "recv error: {e}");
+                    let _ = tx.send(EngineEvent::Error(format!("recv error: {e}")));
+                    return false;
                 }
+            }
 
-                for pkt in packet_batch.drain(..) {
-                    let first_byte = pkt[0];
-
-                    if (20..=63).contains(&first_byte) {
-                        // DTLS (SCTP)
-                        sctp_session.handle_sctp_packet(pkt);
-                    } else if (128..=191).contains(&first_byte) {
-                        // RTP/RTCP
-                        if rx_est.load(Ordering::SeqCst) {
-                            let maybe_tx = rtp_media_tx
-                                .lock()
-                                .ok()
-                                .and_then(|guard| guard.as_ref().cloned());
-                            if let Some(tx_media) = maybe_tx {
-                                let _ = tx_media.send(pkt);
-                            }
+            // 2. Process Batch
+            // Consent is refreshed by what authenticates below: SCTP
+            // and RTP do it once DTLS or SRTP took the packet
+            for pkt in packet_batch.drain(..) {
+                let first_byte = pkt[0];
+
+                if ice_agent::is_check_packet(&pkt) {
+                    if let Some(ice_tx) = &ice_tx {
+                        let _ = ice_tx.send((pkt, peer));
+                    }
+                } else if (20..=63).contains(&first_byte) {
+                    // DTLS (SCTP)
+                    sctp_session.handle_sctp_packet(pkt);
+                } else if (128..=191).contains(&first_byte) {
+                    // RTP/RTCP
+                    if rx_est.load(Ordering::SeqCst) {
+                        let maybe_tx = rtp_media_tx
+                            .lock()
+                            .ok()
+                            .and_then(|guard| guard.as_ref().cloned());
+                        if let Some(tx_media) = maybe_tx {
+                            let _ = tx_media.send(pkt);
+                            continue;
                         }
-                    } else {
-                        // AppMsg
-                        if let Some(msg) = protocol::parse_app_msg(&pkt) {
-                            let args = HandleAppMsgArgs {
-                                msg,
-                                rx_sock: &rx_sock,
-                                rx_tok_peer: &rx_tok_peer,
-                                rx_est: &rx_est,
-                                rx_close_done: &rx_close_done,
-                                rx_peer_init: &rx_peer_init,
-                                local_token,
-                                tx: &tx,
-                                logger: &logger,
-                                rtp_media_tx: &rtp_media_tx,
-                                rtp_session_handle: &rtp_session_handle,
-                                hs_got_syn: &hs_got_syn,
-                                hs_sent_synack: &hs_sent_synack,
-                            };
-                            handle_app_msg(args);
-                        } else {
-                            sink_debug!(&logger, "Ignored unknown packet (len={})", pkt.len());
+                    }
+                    buffers.give(pkt);
+                } else {
+                    // AppMsg, dropped unless the DTLS peer signed it
+                    let msg = auth.open(&pkt);
+                    if msg.is_some() {
+                        activity.heard();
+                    }
+                    match msg {
+                        Some(AppMsg::KeepAlive { .. }) => {}
+                        Some(AppMsg::Probe { id }) => {
+                            let ack = auth.seal(protocol::encode_probe_ack(id));
+                            let _ = counters.record(rx_sock.send(ack.as_bytes()));
                         }
+                        Some(AppMsg::ProbeAck { id }) => {
+                            if let Ok(mut guard) = mtu_prober.lock()
+                                && let Some(search) = guard.as_mut()
+                            {
+                                search.on_ack(id);
+                            }
+                        }
+                        None => sink_debug!(
+                            &logger,
+                            "Ignored unknown or unauthenticated packet (len={})",
+                            pkt.len()
+                        ),
                     }
+                    buffers.give(pkt);
                 }
             }
+            true
         });
-    }
-
-    /// Spawns a thread to drive the handshake process, sending SYN messages and retransmitting as needed.
-    fn spawn_handshake_driver_thread(&self) {
-        let hs_run = Arc::clone(&self.run_flag);
-        let hs_est = Arc::clone(&self.established);
-        let hs_sock = Arc::clone(&self.sock);
-        let hs_peer_tok = Arc::clone(&self.token_peer);
-        let tx2 = self.tx_evt.clone();
-        let logger2 = self.logger.clone();
-        let cfg = self.cfg;
-        let local_token2 = self.token_local;
-        let hs_got_syn = Arc::clone(&self.hs_got_syn);
-        let hs_sent_synack = Arc::clone(&self.hs_sent_synack);
 
-        thread::spawn(move || {
-            sink_debug!(&logger2, " [HS] start (local={local_token2:016x})");
-            let started_at = Instant::now();
-            let mut last_tx = Instant::now()
-                .checked_sub(cfg.resend_every)
-                .unwrap_or_else(Instant::now);
-
-            while hs_run.load(Ordering::SeqCst) && !hs_est.load(Ordering::SeqCst) {
-                if started_at.elapsed() >= cfg.handshake_timeout {
-                    let _ = tx2.send(EngineEvent::Error("handshake timeout".into()));
-                    break;
-                }
-
-                if last_tx.elapsed() >= cfg.resend_every {
-                    let syn = protocol::encode_syn(local_token2);
-                    let _ = hs_sock.send(syn.as_bytes());
-                    sink_debug!(&logger2, "[HS] send SYN (retransmit)");
-
-                    if hs_got_syn.load(Ordering::SeqCst) && hs_sent_synack.load(Ordering::SeqCst) {
-                        let their = hs_peer_tok.load(Ordering::SeqCst);
-                        if their != 0 {
-                            let synack = protocol::encode_synack(their, local_token2);
-                            let ack = protocol::encode_ack(their);
-                            let _ = hs_sock.send(synack.as_bytes());
-                            let _ = hs_sock.send(ack.as_bytes());
-
-                            sink_debug!(&logger2, "[HS] send SYN-ACK + ACK");
-                        }
-                    }
-                    last_tx = Instant::now();
-                }
-                thread::sleep(Duration::from_millis(40));
+        // A socket is registered once: the previous start's goes first
+        self.receiver = None;
+        match Reactor::shared().register(Arc::clone(&self.sock), handler) {
+            Ok(registration) => self.receiver = Some(registration),
+            Err(e) => {
+                sink_error!(&self.logger, "recv error: {e}");
+                let _ = self
+                    .tx_evt
+                    .send(EngineEvent::Error(format!("recv error: {e}")));
             }
-            sink_debug!(&logger2, "[HS] driver done");
-        });
+        }
     }
 
-    /// Initiates the session closing process.
+    /// Initiates the session closing process: sends our DTLS `close_notify`,
+    /// again with backoff while the peer does not answer, and reports
+    /// `Closed` once the peer answers with its own, or after
+    /// `close_timeout`.
     pub fn request_close(&mut self) {
         self.we_initiated_close.store(true, Ordering::SeqCst);
         self.established.store(false, Ordering::SeqCst);
 
         let io_flag = Arc::clone(&self.run_flag);
         let close_done = Arc::clone(&self.close_done);
-        let peer_tok = Arc::clone(&self.token_peer);
         let tx = self.tx_evt.clone();
         let logger = self.logger.clone();
-        let sock = Arc::clone(&self.sock);
         let cfg = self.cfg;
-        let local_tok = self.token_local;
 
         stop_rtp_session(&self.rtp_session, &self.rtp_media_tx);
+        self.sctp_session.close_notify();
 
-        thread::spawn(move || {
-            sink_debug!(&logger, "[CLOSE] driver start (local={local_tok:016x})");
-            let started_at = Instant::now();
-            let mut last_tx = Instant::now()
-                .checked_sub(cfg.close_resend_every)
-                .unwrap_or_else(Instant::now);
-
-            while io_flag.load(Ordering::SeqCst) && !close_done.load(Ordering::SeqCst) {
-                if started_at.elapsed() >= cfg.close_timeout {
-                    sink_debug!(&logger, "[CLOSE] timeout → forcing stop");
-                    break;
-                }
-                if last_tx.elapsed() >= cfg.close_resend_every {
-                    let fin = protocol::encode_fin(local_tok);
-                    let _ = sock.send(fin.as_bytes());
-
-                    sink_debug!(&logger, "[CLOSE] send FIN");
-                    let their = peer_tok.load(Ordering::SeqCst);
-                    if their != 0 {
-                        let finack = protocol::encode_finack(their, local_tok);
-                        let _ = sock.send(finack.as_bytes());
-                        sink_debug!(&logger, "[CLOSE] send FIN-ACK");
-                    }
-                    last_tx = Instant::now();
-                }
-                thread::sleep(Duration::from_millis(40));
+        let clock = Arc::clone(&self.clock);
+        let sctp = Arc::clone(&self.sctp_session);
+
+        sink_debug!(&logger, "[CLOSE] sent close_notify");
+        let started_at = clock.now();
+        let mut backoff = CLOSE_RETRANSMIT;
+        let mut resend_at = started_at + backoff;
+
+        self.timers.schedule(Duration::ZERO, move || {
+            let now = clock.now();
+            let timed_out = now.duration_since(started_at) >= cfg.close_timeout;
+            if timed_out {
+                sink_debug!(&logger, "[CLOSE] timeout → forcing stop");
+            }
+            if timed_out || !io_flag.load(Ordering::SeqCst) || close_done.load(Ordering::SeqCst) {
+                // stop all
+                io_flag.store(false, Ordering::SeqCst);
+                sink_debug!(&logger, "[CLOSE] driver done");
+                let _ = tx.send(EngineEvent::Closed);
+                return None;
+            }
+            if now >= resend_at {
+                sink_debug!(
+                    &logger,
+                    "[CLOSE] no close_notify from the peer yet, resending"
+                );
+                sctp.close_notify();
+                backoff *= 2;
+                resend_at = now + backoff;
             }
-            // stop all
-            io_flag.store(false, Ordering::SeqCst);
-            sink_debug!(&logger, "[CLOSE] driver done");
-            let _ = tx.send(EngineEvent::Closed);
+            Some(DRIVER_TICK)
         });
     }
 
@@ -456,20 +648,67 @@ impl Session {
     /// # Errors
     ///
     /// Returns an error if the rtp session is not running or the lock is poisoned.
-    pub fn register_outbound_track(&self, codec: RtpCodec) -> Result<OutboundTrackHandle, String> {
+    pub fn register_outbound_track(
+        &self,
+        codec: RtpCodec,
+    ) -> Result<OutboundTrackHandle, RtcError> {
+        self.with_rtp(|rtp| rtp.register_outbound_track(codec))
+    }
+
+    /// Registers an outbound track with a caller-chosen SSRC (the one
+    /// advertised in SDP).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn register_outbound_track_with_ssrc(
+        &self,
+        codec: RtpCodec,
+        ssrc: u32,
+    ) -> Result<OutboundTrackHandle, RtcError> {
+        self.with_rtp(|rtp| rtp.add_send_stream(RtpSendConfig::with_ssrc(codec, ssrc)))
+    }
+
+    /// Removes an outbound track.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn unregister_outbound_track(&self, ssrc: u32) -> Result<bool, RtcError> {
+        self.with_rtp(|rtp| rtp.remove_send_stream(ssrc))
+    }
+
+    /// Registers a recv stream for a remote track announced in SDP.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn register_inbound_track(&self, codec: RtpCodec, ssrc: u32) -> Result<(), RtcError> {
+        self.with_rtp(|rtp| rtp.add_recv_stream(RtpRecvConfig::new(codec, Some(ssrc))))
+    }
+
+    /// Removes the recv stream of a remote track.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn unregister_inbound_track(&self, ssrc: u32) -> Result<bool, RtcError> {
+        self.with_rtp(|rtp| rtp.remove_recv_stream(ssrc))
+    }
+
+    fn with_rtp<T>(
+        &self,
+        f: impl FnOnce(&RtpSession) -> Result<T, RtpSessionError>,
+    ) -> Result<T, RtcError> {
         let guard = self
             .rtp_session
             .lock()
-            .map_err(|_| "rtp session lock poisoned".to_string())?;
-        let rtp_sesh = guard
-            .as_ref()
-            .ok_or_else(|| "rtp session not running".to_string())?;
-        rtp_sesh
-            .register_outbound_track(codec)
-            .map_err(|e| e.to_string())
+            .map_err(|_| RtcError::LockPoisoned)?;
+        let rtp = guard.as_ref().ok_or(RtcError::NotRunning)?;
+        Ok(f(rtp)?)
     }
 
-    /// Sends RTP chunks for a video frame.
+    /// Sends the RTP chunks of a frame, each with `extension` if given.
     ///
     /// # Errors
     ///
@@ -479,16 +718,59 @@ impl Session {
         local_ssrc: u32,
         chunks: &[RtpPayloadChunk],
         timestamp: u32,
-    ) -> Result<(), String> {
-        let guard = self
-            .rtp_session
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtcError> {
+        self.with_rtp(|rtp| rtp.send_rtp_chunks_for_frame(local_ssrc, chunks, timestamp, extension))
+    }
+
+    /// Sends one payload on the stream `local_ssrc` with another payload type
+    /// (RFC 4733 telephone events share the audio SSRC).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn send_rtp_payload_as(
+        &self,
+        local_ssrc: u32,
+        payload_type: u8,
+        payload: &[u8],
+        timestamp: u32,
+        marker: bool,
+    ) -> Result<(), RtcError> {
+        self.with_rtp(|rtp| {
+            rtp.send_rtp_payload_as(local_ssrc, payload_type, payload, timestamp, marker)
+        })
+    }
+
+    /// Relays a packet from another session (SFU forwarding); its SSRC needs
+    /// no send stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the send fails.
+    pub fn forward_rtp(&self, packet: &RtpPacket) -> Result<(), RtcError> {
+        self.with_rtp(|rtp| rtp.forward_rtp(packet))
+    }
+
+    /// Asks the peer for a keyframe of its stream `remote_ssrc` (RTCP PLI).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rtp session is not running or the lock is poisoned.
+    pub fn send_pli(&self, remote_ssrc: u32) -> Result<(), RtcError> {
+        self.with_rtp(|rtp| {
+            rtp.send_pli(remote_ssrc);
+            Ok(())
+        })
+    }
+
+    /// Per-stream RTP statistics; empty while the RTP session is not running.
+    pub fn rtp_stats(&self) -> (Vec<OutboundRtpStats>, Vec<InboundRtpStats>) {
+        self.rtp_session
             .lock()
-            .map_err(|_| "rtp session lock poisoned".to_string())?;
-        let rtp = guard
-            .as_ref()
-            .ok_or_else(|| "rtp session not running".to_string())?;
-        rtp.send_rtp_chunks_for_frame(local_ssrc, chunks, timestamp)
-            .map_err(|e| e.to_string())
+            .ok()
+            .and_then(|guard| guard.as_ref().map(RtpSession::stats))
+            .unwrap_or_default()
     }
 
     /// Tears down the RTP session.
@@ -508,189 +790,43 @@ impl Session {
 impl Drop for Session {
     fn drop(&mut self) {
         self.sctp_session.shutdown();
+        // The receiver is not called again once deregistered; the timer
+        // tasks see the flag on their next tick.
+        self.run_flag.store(false, Ordering::SeqCst);
+        self.receiver = None;
     }
 }
 
-/// Helper struct to pass arguments to the `handle_app_msg` function.
-struct HandleAppMsgArgs<'a> {
-    msg: AppMsg,
-    rx_sock: &'a Arc<UdpSocket>,
-    rx_tok_peer: &'a Arc<AtomicU64>,
-    rx_est: &'a Arc<AtomicBool>,
-    rx_close_done: &'a Arc<AtomicBool>,
-    rx_peer_init: &'a Arc<AtomicBool>,
-    local_token: u64,
-    tx: &'a Sender<EngineEvent>,
-    logger: &'a Arc<dyn LogSink>,
-    rtp_media_tx: &'a Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
-    rtp_session_handle: &'a Arc<Mutex<Option<RtpSession>>>,
-    hs_got_syn: &'a Arc<AtomicBool>,
-    hs_sent_synack: &'a Arc<AtomicBool>,
+/// What the SCTP event thread needs to end the session on the peer's
+/// `close_notify`.
+struct PeerClosed {
+    established: Arc<AtomicBool>,
+    we_initiated_close: Arc<AtomicBool>,
+    peer_initiated_close: Arc<AtomicBool>,
+    close_done: Arc<AtomicBool>,
+    rtp_session: Arc<Mutex<Option<RtpSession>>>,
+    rtp_media_tx: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+    logger: Arc<dyn LogSink>,
 }
 
-/// Handles incoming application messages.
-fn handle_app_msg(args: HandleAppMsgArgs) {
-    match args.msg {
-        AppMsg::Syn { token: their } => {
-            // If already established, ignore handshake messages.
-            if args.rx_est.load(Ordering::SeqCst) {
-                sink_debug!(args.logger, "[HS] recv SYN after established -> ignored");
-                return;
-            }
-
-            sink_debug!(args.logger, "[HS] recv SYN({their:016x})");
-
-            // store the peer token always
-            args.rx_tok_peer.store(their, Ordering::SeqCst);
-
-            // glare resolution: higher token wins
-            if their > args.local_token {
-                // peer wins: respond with SYN-ACK (we act passive)
-                let synack = protocol::encode_synack(their, args.local_token);
-                let _ = args.rx_sock.send(synack.as_bytes());
-                // mark that we received a SYN and that we sent a SYN-ACK
-                args.hs_got_syn.store(true, Ordering::SeqCst);
-                args.hs_sent_synack.store(true, Ordering::SeqCst);
-                sink_debug!(
-                    args.logger,
-                    "[HS] peer token higher -> sent SYN-ACK({their:016x},{local_token:016x})",
-                    local_token = args.local_token
-                );
-            } else if their < args.local_token {
-                // we "win" the glare: do not reply with SYN-ACK (we keep sending SYN)
-                // but remember we saw their token (for later).
-                args.hs_got_syn.store(true, Ordering::SeqCst);
-                sink_debug!(
-                    args.logger,
-                    "[HS] recv SYN({their:016x}) but local token higher -> continue SYN sending",
-                );
-            } else {
-                // equal tokens extremely unlikely; deterministic tie-breaker:
-                // choose passive to avoid deadlock: respond with SYN-ACK
-                let synack = protocol::encode_synack(their, args.local_token);
-                let _ = args.rx_sock.send(synack.as_bytes());
-                args.hs_got_syn.store(true, Ordering::SeqCst);
-                args.hs_sent_synack.store(true, Ordering::SeqCst);
-                sink_debug!(
-                    args.logger,
-                    "[HS] recv SYN with equal token -> sent SYN-ACK (tie-breaker)",
-                );
-            }
-        }
-
-        AppMsg::SynAck { your, mine } => {
-            // If already established, ignore handshake messages.
-            if args.rx_est.load(Ordering::SeqCst) {
-                sink_debug!(
-                    args.logger,
-                    "[HS] recv SYN-ACK after established -> ignored"
-                );
-                return;
-            }
-
-            // 'your' is peer token, 'mine' is peer's view of our token
-            if your == args.local_token {
-                // This SYN-ACK is a reply to our SYN: good, proceed to ACK and mark established.
-                args.rx_tok_peer.store(mine, Ordering::SeqCst);
-
-                let ack = protocol::encode_ack(mine);
-                let _ = args.rx_sock.send(ack.as_bytes());
-                sink_debug!(args.logger, "[HS] recv SYN-ACK ok -> send ACK({mine:016x})");
-
-                // We sent the ACK that completes the 3-way handshake from our side.
-                // Mark established and notify the engine only once.
-                if !args.rx_est.swap(true, Ordering::SeqCst) {
-                    // rx_est was false, now true: emit event
-                    let _ = args.tx.send(EngineEvent::Established);
-                    sink_debug!(args.logger, "[HS] ESTABLISHED (via SYN-ACK -> ACK)");
-                } else {
-                    sink_debug!(args.logger, "[HS] ESTABLISHED already set");
-                }
-
-                // Clear handshake retransmit flags now that we're established.
-                args.hs_sent_synack.store(false, Ordering::SeqCst);
-                args.hs_got_syn.store(false, Ordering::SeqCst);
-            } else {
-                sink_debug!(args.logger, "[HS] recv SYN-ACK not for us -> ignored");
-            }
-        }
-
-        AppMsg::Ack { your } => {
-            // If already established, ignore duplicate ACKs.
-            if args.rx_est.load(Ordering::SeqCst) {
-                sink_debug!(args.logger, "[HS] recv ACK after established -> ignored");
-                return;
-            }
-
-            if your == args.local_token {
-                // ACK confirms the remote acknowledged our SYN-ACK (or echoed our FIN etc).
-                // Mark established and notify the engine only once.
-                if !args.rx_est.swap(true, Ordering::SeqCst) {
-                    let _ = args.tx.send(EngineEvent::Established);
-                    sink_debug!(args.logger, "[HS] ESTABLISHED (via ACK)");
-                } else {
-                    sink_debug!(args.logger, "[HS] ESTABLISHED already set");
-                }
-
-                // Clear handshake flags
-                args.hs_sent_synack.store(false, Ordering::SeqCst);
-                args.hs_got_syn.store(false, Ordering::SeqCst);
-            } else {
-                sink_debug!(args.logger, "[HS] recv ACK not for us -> ignored");
-            }
-        }
-
-        AppMsg::Fin { token: their } => {
-            // Close initiated by peer
-            args.rx_peer_init.store(true, Ordering::SeqCst);
-            args.rx_est.store(false, Ordering::SeqCst);
-            args.rx_tok_peer.store(their, Ordering::SeqCst);
-            let finack = protocol::encode_finack(their, args.local_token);
-            let _ = args.rx_sock.send(finack.as_bytes());
-            stop_rtp_session(args.rtp_session_handle, args.rtp_media_tx);
-            sink_debug!(
-                args.logger,
-                "[CLOSE] recv FIN({their:016x}) → send FIN-ACK({their:016x},{local_token:016x})",
-                local_token = args.local_token
-            );
-        }
-
-        AppMsg::FinAck { your, mine } => {
-            let peer_tok_now = args.rx_tok_peer.load(Ordering::SeqCst);
-            if your == args.local_token {
-                // they echoed our FIN → finish their side
-                let finack2 = protocol::encode_finack2(mine);
-                let _ = args.rx_sock.send(finack2.as_bytes());
-                sink_debug!(
-                    args.logger,
-                    "[CLOSE] recv FIN-ACK ok → send FIN-ACK2({mine:016x})"
-                );
-            } else if peer_tok_now != 0 && your == peer_tok_now {
-                // idempotent echo related to their-initiated close; ignore quietly
-                sink_debug!(
-                    args.logger,
-                    "[CLOSE] recv FIN-ACK for peer-initiated close -> ignored"
-                );
-            } else {
-                sink_debug!(args.logger, "[CLOSE] recv FIN-ACK not for us -> ignored");
-            }
-        }
-
-        AppMsg::FinAck2 { your } => {
-            if your == args.local_token {
-                // final ack of close: mark close done and notify engine
-                args.rx_est.store(false, Ordering::SeqCst);
-                args.rx_close_done.store(true, Ordering::SeqCst);
-                let _ = args.tx.send(EngineEvent::Closing { graceful: true });
-                let _ = args.tx.send(EngineEvent::Closed);
-                stop_rtp_session(args.rtp_session_handle, args.rtp_media_tx);
-                sink_info!(args.logger, "[CLOSE] graceful close complete",);
-            } else {
-                sink_debug!(args.logger, "[CLOSE] recv FIN-ACK2 not for us -> ignored");
-            }
+impl PeerClosed {
+    /// Ends the session: our own close is done (its driver reports
+    /// `Closed`), or the peer's is, which is reported here.
+    fn handle(&self, tx: &Sender<EngineEvent>) -> Option<EngineEvent> {
+        self.close_done.store(true, Ordering::SeqCst);
+        if self.we_initiated_close.load(Ordering::SeqCst) {
+            sink_debug!(&self.logger, "[CLOSE] peer answered close_notify");
+            return None;
         }
+        self.peer_initiated_close.store(true, Ordering::SeqCst);
+        self.established.store(false, Ordering::SeqCst);
+        stop_rtp_session(&self.rtp_session, &self.rtp_media_tx);
+        sink_info!(&self.logger, "[CLOSE] peer closed the session");
+        let _ = tx.send(EngineEvent::Closing { graceful: true });
+        Some(EngineEvent::Closed)
     }
 }
+
 /// Stops the RTP session and clears the media sender.
 fn stop_rtp_session(
     rtp_session: &Arc<Mutex<Option<RtpSession>>>,
@@ -706,3 +842,70 @@ fn stop_rtp_session(
         guard.take();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::{core::clock::ManualClock, dtls::test_pair, log::NoopLogSink};
+    use std::io::Write;
+
+    #[test]
+    fn only_what_the_peer_authenticates_keeps_the_session_alive() {
+        let ((sock, keys, stream), (peer_sock, peer_keys, mut peer_stream)) =
+            test_pair::handshake("consent");
+        let (addr, peer) = (sock.local_addr().unwrap(), peer_sock.local_addr().unwrap());
+        sock.connect(peer).unwrap();
+        let (event_tx, _events) = mpsc::channel();
+        let clock = ManualClock::new();
+        let mut session = Session::new(SessionInitArgs {
+            sock,
+            peer,
+            remote_codecs: Vec::new(),
+            event_tx,
+            logger: Arc::new(NoopLogSink),
+            cfg: SessionConfig {
+                close_timeout: Duration::from_secs(1),
+                keepalive_every: Duration::from_secs(1),
+                mtu: MtuConfig::default(),
+            },
+            srtp_cfg: Some(keys),
+            ssl_stream: stream,
+            is_client: true,
+            ice_tx: None,
+            event_log: None,
+            buffers: BufferPool::default(),
+        })
+        .with_timers(Timers::manual(clock.clone()));
+        session.start();
+        let settle = || thread::sleep(Duration::from_millis(200));
+
+        let peer_auth = AppMsgAuth::from_srtp(Some(&peer_keys));
+        let keepalive = peer_auth.seal(protocol::encode_keepalive(7));
+        clock.advance(Duration::from_secs(1));
+        peer_sock.send_to(keepalive.as_bytes(), addr).unwrap();
+        settle();
+        assert_eq!(session.idle_for(), Duration::ZERO);
+
+        // Unsigned, replayed, not SRTP, not a record of this DTLS session
+        let unsigned = protocol::encode_keepalive(7);
+        let fake_record = [23, 0xfe, 0xfd, 0, 1, 0, 0, 0, 0, 0, 9, 0, 4, 1, 2, 3, 4];
+        let forged: [&[u8]; 4] = [
+            unsigned.as_bytes(),
+            keepalive.as_bytes(),
+            &[0x80; 40],
+            &fake_record,
+        ];
+        for (i, datagram) in (1..).zip(forged) {
+            clock.advance(Duration::from_secs(1));
+            peer_sock.send_to(datagram, addr).unwrap();
+            settle();
+            assert_eq!(session.idle_for(), Duration::from_secs(i), "datagram {i}");
+        }
+
+        // A record the peer's DTLS sent
+        peer_stream.write_all(b"still here").unwrap();
+        settle();
+        assert_eq!(session.idle_for(), Duration::ZERO);
+    }
+}
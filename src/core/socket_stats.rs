@@ -0,0 +1,176 @@
+//! Counters of what goes through a session's UDP socket.
+//!
+//! The RTP send streams, RTCP, the session's control messages and the
+//! receiver thread all count into one shared [`SocketCounters`], so a call
+//! whose video freezes shows whether packets stopped leaving, stopped
+//! arriving, or were refused by the OS (a full send buffer, an unreachable
+//! network).
+
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use super::stats::SocketStats;
+
+/// `ENOBUFS`: the kernel ran out of buffer space for the datagram.
+#[cfg(target_os = "linux")]
+const ENOBUFS: i32 = 105;
+#[cfg(windows)]
+const ENOBUFS: i32 = 10055;
+#[cfg(not(any(target_os = "linux", windows)))]
+const ENOBUFS: i32 = 55;
+
+/// Packet, byte and error counts of one socket, shared by everything that
+/// sends or receives on it.
+#[derive(Debug, Default)]
+pub struct SocketCounters {
+    packets_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    packets_received: AtomicU64,
+    bytes_received: AtomicU64,
+    send_errors: AtomicU64,
+    send_buffer_full: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+/// The error counters at one point, to tell whether new errors happened
+/// since.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SendFailures {
+    /// Sends refused for any other reason.
+    pub errors: u64,
+    /// Datagrams dropped because the socket's send buffer was full.
+    pub buffer_full: u64,
+}
+
+impl SocketCounters {
+    /// Counts one datagram of `bytes` sent.
+    pub fn sent(&self, bytes: usize) {
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Counts one datagram of `bytes` received.
+    pub fn received(&self, bytes: usize) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Counts a datagram the OS did not take. On the non-blocking session
+    /// socket, `WouldBlock` means the send buffer is full, as `ENOBUFS`
+    /// does.
+    pub fn send_failed(&self, e: &io::Error) {
+        if e.kind() == io::ErrorKind::WouldBlock || e.raw_os_error() == Some(ENOBUFS) {
+            self.send_buffer_full.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.send_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        *self
+            .last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(e.to_string());
+    }
+
+    /// Counts `packets` dropped because a batch send stopped short, which
+    /// only happens once the send buffer is full.
+    pub fn batch_cut_short(&self, packets: usize) {
+        self.send_buffer_full
+            .fetch_add(packets as u64, Ordering::Relaxed);
+    }
+
+    /// Counts the outcome of sending one datagram and hands it back.
+    ///
+    /// # Errors
+    ///
+    /// Returns the send error unchanged.
+    pub fn record(&self, result: io::Result<usize>) -> io::Result<usize> {
+        match &result {
+            Ok(n) => self.sent(*n),
+            Err(e) => self.send_failed(e),
+        }
+        result
+    }
+
+    /// The error counters now.
+    #[must_use]
+    pub fn failures(&self) -> SendFailures {
+        SendFailures {
+            errors: self.send_errors.load(Ordering::Relaxed),
+            buffer_full: self.send_buffer_full.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The last send error, if any.
+    #[must_use]
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The counters as a stats entry of the socket `local` → `remote`.
+    #[must_use]
+    pub fn snapshot(
+        &self,
+        timestamp_ms: u64,
+        local: SocketAddr,
+        remote: SocketAddr,
+    ) -> SocketStats {
+        let failures = self.failures();
+        SocketStats {
+            id: format!("Socket_{local}_{remote}"),
+            timestamp_ms,
+            local_address: local.to_string(),
+            remote_address: remote.to_string(),
+            packets_sent: self.packets_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            send_errors: failures.errors,
+            send_buffer_full: failures.buffer_full,
+            last_error: self.last_error(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn sends_receives_and_failures_are_counted_apart() {
+        let counters = SocketCounters::default();
+        counters.record(Ok(100)).unwrap();
+        counters.sent(50);
+        counters.received(1200);
+        let full = io::Error::from(io::ErrorKind::WouldBlock);
+        assert!(counters.record(Err(full)).is_err());
+        counters.send_failed(&io::Error::from_raw_os_error(ENOBUFS));
+        counters.batch_cut_short(3);
+        counters.send_failed(&io::Error::from(io::ErrorKind::PermissionDenied));
+
+        assert_eq!(
+            counters.failures(),
+            SendFailures {
+                errors: 1,
+                buffer_full: 5
+            }
+        );
+        let local: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+        let remote: SocketAddr = "10.0.0.2:6000".parse().unwrap();
+        let stats = counters.snapshot(7, local, remote);
+        assert_eq!(stats.id, "Socket_10.0.0.1:5000_10.0.0.2:6000");
+        assert_eq!((stats.packets_sent, stats.bytes_sent), (2, 150));
+        assert_eq!((stats.packets_received, stats.bytes_received), (1, 1200));
+        assert_eq!(stats.last_error, counters.last_error());
+        assert!(stats.last_error.is_some());
+    }
+}
@@ -0,0 +1,406 @@
+//! Unified statistics snapshot (`getStats()`-style).
+//!
+//! A [`StatsReport`] gathers the metrics that used to be scattered across
+//! the ICE agent, the RTP send/recv streams, the congestion controller and
+//! the SCTP data channel into one structure. Every entry carries a stable
+//! `id` (so successive reports can be diffed) and the wall-clock time it was
+//! sampled at. [`StatsReport::to_json`] renders the report as JSON.
+
+use std::{
+    fmt::Write as _,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::core::latency::LatencyStats;
+
+/// Milliseconds since the Unix epoch, used as the sample timestamp.
+#[must_use]
+pub fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// One ICE candidate pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcePairStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub local_address: String,
+    pub remote_address: String,
+    pub local_type: String,
+    pub remote_type: String,
+    pub state: String,
+    pub priority: u64,
+    pub nominated: bool,
+}
+
+/// One outbound RTP stream (per local SSRC).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboundRtpStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub ssrc: u32,
+    pub codec: String,
+    pub payload_type: u8,
+    pub packets_sent: u32,
+    pub bytes_sent: u32,
+    /// As reported by the remote in its RTCP receiver reports.
+    pub remote_packets_lost: i32,
+    pub remote_fraction_lost: u8,
+    pub remote_jitter: u32,
+    /// Packets the remote NACKed; the ones still kept were resent, the
+    /// rest had left the send history.
+    pub nacked_packets: u32,
+    pub retransmitted_packets: u32,
+    pub unrecoverable_packets: u32,
+    /// Mean time from sending a packet to the remote NACKing it.
+    pub nack_delay_ms: Option<u32>,
+    pub rtt_ms: Option<u32>,
+}
+
+/// One inbound RTP stream (per remote SSRC).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundRtpStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub ssrc: u32,
+    pub codec: String,
+    pub payload_type: u8,
+    pub packets_received: u32,
+    pub packets_lost: i64,
+    pub highest_seq: u32,
+    /// Interarrival jitter in RTP timestamp units.
+    pub jitter: u32,
+    /// RTP clock rate of the codec, to convert `jitter` to time.
+    pub clock_rate: u32,
+    /// Copies of packets already received.
+    pub packets_duplicated: u32,
+    /// Packets that arrived after a later one.
+    pub packets_reordered: u32,
+    /// Most later packets that overtook a single one; the jitter buffer
+    /// needs at least this many packets of depth.
+    pub max_reorder_depth: u32,
+    /// Packets that arrived after the jitter buffer had moved past them.
+    pub packets_late: u32,
+}
+
+impl InboundRtpStats {
+    /// Interarrival jitter in milliseconds.
+    #[must_use]
+    pub fn jitter_ms(&self) -> f64 {
+        if self.clock_rate == 0 {
+            return 0.0;
+        }
+        f64::from(self.jitter) * 1000.0 / f64::from(self.clock_rate)
+    }
+}
+
+/// State of the congestion controller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CongestionStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub current_bitrate_bps: u32,
+    pub min_bitrate_bps: u32,
+    pub max_bitrate_bps: u32,
+    /// The file transfer's rate while one runs.
+    pub bulk_bitrate_bps: Option<u32>,
+    pub last_rtt_ms: Option<u64>,
+    pub last_fraction_lost: Option<u8>,
+}
+
+/// The SCTP data channel used for file transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataChannelStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub label: String,
+    pub buffered_amount: usize,
+    pub sending: bool,
+    pub receiving: bool,
+}
+
+/// The UDP socket a session runs on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketStats {
+    pub id: String,
+    pub timestamp_ms: u64,
+    pub local_address: String,
+    pub remote_address: String,
+    /// RTP, RTCP and the session's own messages; DTLS records are not
+    /// counted on the way out.
+    pub packets_sent: u64,
+    pub bytes_sent: u64,
+    pub packets_received: u64,
+    pub bytes_received: u64,
+    /// Sends the OS refused, other than for a full send buffer.
+    pub send_errors: u64,
+    /// Datagrams dropped because the send buffer was full.
+    pub send_buffer_full: u64,
+    pub last_error: Option<String>,
+}
+
+/// Full statistics snapshot of one peer connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsReport {
+    pub timestamp_ms: u64,
+    pub ice_pairs: Vec<IcePairStats>,
+    pub outbound_rtp: Vec<OutboundRtpStats>,
+    pub inbound_rtp: Vec<InboundRtpStats>,
+    pub congestion: Option<CongestionStats>,
+    pub data_channels: Vec<DataChannelStats>,
+    pub sockets: Vec<SocketStats>,
+    /// Media pipeline latency percentiles, per stage with samples.
+    pub latency: Vec<LatencyStats>,
+}
+
+impl StatsReport {
+    /// Creates an empty report stamped with the current time.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            timestamp_ms: now_unix_ms(),
+            ice_pairs: Vec::new(),
+            outbound_rtp: Vec::new(),
+            inbound_rtp: Vec::new(),
+            congestion: None,
+            data_channels: Vec::new(),
+            sockets: Vec::new(),
+            latency: Vec::new(),
+        }
+    }
+
+    /// Renders the report as a JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(out, r#"{{"timestamp":{}"#, self.timestamp_ms);
+
+        out.push_str(r#","iceCandidatePairs":["#);
+        push_joined(&mut out, &self.ice_pairs, |o, p| {
+            let _ = write!(
+                o,
+                r#"{{"id":{},"timestamp":{},"localAddress":{},"remoteAddress":{},"localType":{},"remoteType":{},"state":{},"priority":{},"nominated":{}}}"#,
+                json_str(&p.id),
+                p.timestamp_ms,
+                json_str(&p.local_address),
+                json_str(&p.remote_address),
+                json_str(&p.local_type),
+                json_str(&p.remote_type),
+                json_str(&p.state),
+                p.priority,
+                p.nominated
+            );
+        });
+
+        out.push_str(r#"],"outboundRtp":["#);
+        push_joined(&mut out, &self.outbound_rtp, |o, s| {
+            let _ = write!(
+                o,
+                r#"{{"id":{},"timestamp":{},"ssrc":{},"codec":{},"payloadType":{},"packetsSent":{},"bytesSent":{},"remotePacketsLost":{},"remoteFractionLost":{},"remoteJitter":{},"nackedPackets":{},"retransmittedPackets":{},"unrecoverablePackets":{},"nackDelayMs":{},"rttMs":{}}}"#,
+                json_str(&s.id),
+                s.timestamp_ms,
+                s.ssrc,
+                json_str(&s.codec),
+                s.payload_type,
+                s.packets_sent,
+                s.bytes_sent,
+                s.remote_packets_lost,
+                s.remote_fraction_lost,
+                s.remote_jitter,
+                s.nacked_packets,
+                s.retransmitted_packets,
+                s.unrecoverable_packets,
+                json_opt(s.nack_delay_ms),
+                json_opt(s.rtt_ms)
+            );
+        });
+
+        out.push_str(r#"],"inboundRtp":["#);
+        push_joined(&mut out, &self.inbound_rtp, |o, s| {
+            let _ = write!(
+                o,
+                r#"{{"id":{},"timestamp":{},"ssrc":{},"codec":{},"payloadType":{},"packetsReceived":{},"packetsLost":{},"highestSeq":{},"jitter":{},"clockRate":{},"packetsDuplicated":{},"packetsReordered":{},"maxReorderDepth":{},"packetsLate":{}}}"#,
+                json_str(&s.id),
+                s.timestamp_ms,
+                s.ssrc,
+                json_str(&s.codec),
+                s.payload_type,
+                s.packets_received,
+                s.packets_lost,
+                s.highest_seq,
+                s.jitter,
+                s.clock_rate,
+                s.packets_duplicated,
+                s.packets_reordered,
+                s.max_reorder_depth,
+                s.packets_late
+            );
+        });
+
+        out.push_str(r#"],"congestion":"#);
+        match &self.congestion {
+            Some(c) => {
+                let _ = write!(
+                    out,
+                    r#"{{"id":{},"timestamp":{},"currentBitrateBps":{},"minBitrateBps":{},"maxBitrateBps":{},"bulkBitrateBps":{},"lastRttMs":{},"lastFractionLost":{}}}"#,
+                    json_str(&c.id),
+                    c.timestamp_ms,
+                    c.current_bitrate_bps,
+                    c.min_bitrate_bps,
+                    c.max_bitrate_bps,
+                    json_opt(c.bulk_bitrate_bps),
+                    json_opt(c.last_rtt_ms),
+                    json_opt(c.last_fraction_lost)
+                );
+            }
+            None => out.push_str("null"),
+        }
+
+        out.push_str(r#","dataChannels":["#);
+        push_joined(&mut out, &self.data_channels, |o, d| {
+            let _ = write!(
+                o,
+                r#"{{"id":{},"timestamp":{},"label":{},"bufferedAmount":{},"sending":{},"receiving":{}}}"#,
+                json_str(&d.id),
+                d.timestamp_ms,
+                json_str(&d.label),
+                d.buffered_amount,
+                d.sending,
+                d.receiving
+            );
+        });
+
+        out.push_str(r#"],"sockets":["#);
+        push_joined(&mut out, &self.sockets, |o, s| {
+            let _ = write!(
+                o,
+                r#"{{"id":{},"timestamp":{},"localAddress":{},"remoteAddress":{},"packetsSent":{},"bytesSent":{},"packetsReceived":{},"bytesReceived":{},"sendErrors":{},"sendBufferFull":{},"lastError":{}}}"#,
+                json_str(&s.id),
+                s.timestamp_ms,
+                json_str(&s.local_address),
+                json_str(&s.remote_address),
+                s.packets_sent,
+                s.bytes_sent,
+                s.packets_received,
+                s.bytes_received,
+                s.send_errors,
+                s.send_buffer_full,
+                s.last_error
+                    .as_deref()
+                    .map_or_else(|| "null".to_string(), json_str)
+            );
+        });
+
+        out.push_str(r#"],"pipelineLatency":["#);
+        push_joined(&mut out, &self.latency, |o, l| {
+            let _ = write!(
+                o,
+                r#"{{"stage":{},"samples":{},"p50Ms":{},"p95Ms":{},"p99Ms":{}}}"#,
+                json_str(l.stage.name()),
+                l.samples,
+                l.p50_ms,
+                l.p95_ms,
+                l.p99_ms
+            );
+        });
+        out.push_str("]}");
+        out
+    }
+}
+
+impl Default for StatsReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn push_joined<T>(out: &mut String, items: &[T], mut f: impl FnMut(&mut String, &T)) {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        f(out, item);
+    }
+}
+
+fn json_opt<T: std::fmt::Display>(v: Option<T>) -> String {
+    v.map_or_else(|| "null".to_string(), |v| v.to_string())
+}
+
+/// Quotes and escapes `s` as a JSON string.
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn empty_report_serializes() {
+        let mut r = StatsReport::new();
+        r.timestamp_ms = 42;
+        assert_eq!(
+            r.to_json(),
+            r#"{"timestamp":42,"iceCandidatePairs":[],"outboundRtp":[],"inboundRtp":[],"congestion":null,"dataChannels":[],"sockets":[],"pipelineLatency":[]}"#
+        );
+    }
+
+    #[test]
+    fn entries_are_serialized_with_ids() {
+        let mut r = StatsReport::new();
+        r.outbound_rtp.push(OutboundRtpStats {
+            id: "OutboundRTP_1".into(),
+            timestamp_ms: 1,
+            ssrc: 1,
+            codec: "H264".into(),
+            payload_type: 96,
+            packets_sent: 10,
+            bytes_sent: 1000,
+            remote_packets_lost: 0,
+            remote_fraction_lost: 0,
+            remote_jitter: 3,
+            nacked_packets: 0,
+            retransmitted_packets: 0,
+            unrecoverable_packets: 0,
+            nack_delay_ms: None,
+            rtt_ms: None,
+        });
+        r.outbound_rtp.push(OutboundRtpStats {
+            id: "OutboundRTP_2".into(),
+            rtt_ms: Some(25),
+            ..r.outbound_rtp[0].clone()
+        });
+        let json = r.to_json();
+        assert!(json.contains(r#""id":"OutboundRTP_1""#));
+        assert!(json.contains(r#""rttMs":null}"#));
+        assert!(json.contains(r#"},{"id":"OutboundRTP_2""#));
+        assert!(json.contains(r#""rttMs":25}"#));
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(json_str("a\"b\\c\n"), r#""a\"b\\c\n""#);
+        assert_eq!(json_str("\u{1}"), r#""\u0001""#);
+    }
+}
@@ -0,0 +1,271 @@
+//! Push-style delivery of engine events.
+//!
+//! Besides draining `Engine::poll()`, callers can register subscribers that
+//! get every matching [`PeerEvent`] as soon as the engine processes it,
+//! either through their own channel or through a callback. Channels buffer,
+//! so a subscriber that reads less often than once per frame does not miss
+//! events.
+
+use std::{
+    ops::{BitOr, BitOrAssign},
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+use crate::core::{engine::PeerEvent, events::EngineEvent};
+
+/// Set of event categories a subscriber is interested in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventMask(u32);
+
+impl EventMask {
+    pub const NONE: Self = Self(0);
+    /// `Status` messages.
+    pub const STATUS: Self = Self(1 << 0);
+    /// `Log` messages.
+    pub const LOG: Self = Self(1 << 1);
+    /// ICE/DTLS/peer state changes, `Established`, `Closing`, `Closed`.
+    pub const CONNECTION: Self = Self(1 << 2);
+    /// `Error` and `SocketError`.
+    pub const ERROR: Self = Self(1 << 3);
+    /// Media plumbing (`RtpIn`, `ToggleAudio`, remote tracks, camera loss,
+    /// voice activity, remote camera off and audio level).
+    pub const MEDIA: Self = Self(1 << 4);
+    /// `NetworkMetrics`, `UpdateBitrate` and `PathMtu`.
+    pub const METRICS: Self = Self(1 << 5);
+    /// File transfer offers, chunks and progress.
+    pub const FILES: Self = Self(1 << 6);
+    pub const ALL: Self = Self(u32::MAX);
+
+    /// The category an event belongs to.
+    #[must_use]
+    pub const fn of(event: &EngineEvent) -> Self {
+        match event {
+            EngineEvent::Status(_) | EngineEvent::ConfigUpdated { .. } => Self::STATUS,
+            EngineEvent::Log(_) => Self::LOG,
+            EngineEvent::IceNominated { .. }
+            | EngineEvent::Established
+            | EngineEvent::IceConnectionStateChanged(_)
+            | EngineEvent::DtlsStateChanged(_)
+            | EngineEvent::PeerConnectionStateChanged(_)
+            | EngineEvent::Closing { .. }
+            | EngineEvent::Closed => Self::CONNECTION,
+            EngineEvent::Error(_) | EngineEvent::SocketError { .. } => Self::ERROR,
+            EngineEvent::RtpIn(_)
+            | EngineEvent::ToggleAudio(_)
+            | EngineEvent::RemoteTrackAdded { .. }
+            | EngineEvent::RemoteTrackRemoved { .. }
+            | EngineEvent::CameraLost { .. }
+            | EngineEvent::CameraRecovered { .. }
+            | EngineEvent::Speaking { .. }
+            | EngineEvent::RemoteVideoMuted(_)
+            | EngineEvent::RemoteAudioLevel(_)
+            | EngineEvent::KeyframeRequested { .. } => Self::MEDIA,
+            EngineEvent::NetworkMetrics(_)
+            | EngineEvent::UpdateBitrate(_)
+            | EngineEvent::PathMtu(_) => Self::METRICS,
+            EngineEvent::SendFileOffer(_)
+            | EngineEvent::SendFileAccept(_)
+            | EngineEvent::SendFileReject(_)
+            | EngineEvent::SendFileCancel(_)
+            | EngineEvent::SendFileChunk(..)
+            | EngineEvent::SendFileEnd(_)
+            | EngineEvent::ReceivedFileOffer(_)
+            | EngineEvent::ReceivedFileAccept(_)
+            | EngineEvent::ReceivedFileReject(_)
+            | EngineEvent::ReceivedFileCancel(_)
+            | EngineEvent::ReceivedFileChunk(..)
+            | EngineEvent::ReceivedFileEnd(_)
+            | EngineEvent::UploadProgress { .. }
+            | EngineEvent::DownloadProgress { .. } => Self::FILES,
+        }
+    }
+
+    /// `true` if the masks share at least one category.
+    #[must_use]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// `true` if `event` falls in one of the categories of this mask.
+    #[must_use]
+    pub const fn matches(self, event: &EngineEvent) -> bool {
+        self.intersects(Self::of(event))
+    }
+}
+
+impl BitOr for EventMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for EventMask {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Handle returned by `subscribe*`, used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// A boxed event callback.
+pub type EventCallback = Box<dyn FnMut(&PeerEvent) + Send>;
+
+enum Delivery {
+    Channel(Sender<PeerEvent>),
+    Callback(EventCallback),
+}
+
+struct Subscriber {
+    id: SubscriptionId,
+    mask: EventMask,
+    delivery: Delivery,
+}
+
+/// Fan-out of engine events to registered subscribers.
+#[derive(Default)]
+pub struct EventBus {
+    next_id: u64,
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn register(&mut self, mask: EventMask, delivery: Delivery) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push(Subscriber { id, mask, delivery });
+        id
+    }
+
+    /// Registers a channel subscriber. Dropping the receiver unsubscribes it.
+    pub fn subscribe(&mut self, mask: EventMask) -> (SubscriptionId, Receiver<PeerEvent>) {
+        let (tx, rx) = mpsc::channel();
+        (self.register(mask, Delivery::Channel(tx)), rx)
+    }
+
+    /// Registers a callback subscriber. The callback runs on the thread that
+    /// drives the engine, so it should return quickly.
+    pub fn subscribe_fn(
+        &mut self,
+        mask: EventMask,
+        callback: impl FnMut(&PeerEvent) + Send + 'static,
+    ) -> SubscriptionId {
+        self.register(mask, Delivery::Callback(Box::new(callback)))
+    }
+
+    /// Removes a subscriber. Returns `false` if it was not registered.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscribers.len();
+        self.subscribers.retain(|s| s.id != id);
+        self.subscribers.len() != before
+    }
+
+    /// Delivers `event` to every matching subscriber, dropping channel
+    /// subscribers whose receiver is gone.
+    pub fn publish(&mut self, event: &PeerEvent) {
+        let category = EventMask::of(&event.event);
+        self.subscribers.retain_mut(|s| {
+            if !s.mask.intersects(category) {
+                return true;
+            }
+            match &mut s.delivery {
+                Delivery::Channel(tx) => tx.send(event.clone()).is_ok(),
+                Delivery::Callback(f) => {
+                    f(event);
+                    true
+                }
+            }
+        });
+    }
+
+    /// Number of registered subscribers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    fn ev(event: EngineEvent) -> PeerEvent {
+        PeerEvent {
+            peer: "bob".into(),
+            event,
+        }
+    }
+
+    #[test]
+    fn channel_subscriber_only_gets_matching_events() {
+        let mut bus = EventBus::new();
+        let (_, rx) = bus.subscribe(EventMask::CONNECTION | EventMask::ERROR);
+
+        bus.publish(&ev(EngineEvent::Status("hi".into())));
+        bus.publish(&ev(EngineEvent::Established));
+        bus.publish(&ev(EngineEvent::Error("boom".into())));
+
+        let got: Vec<_> = rx.try_iter().collect();
+        assert_eq!(got.len(), 2);
+        assert!(matches!(got[0].event, EngineEvent::Established));
+        assert!(matches!(got[1].event, EngineEvent::Error(_)));
+        assert_eq!(got[0].peer, "bob");
+    }
+
+    #[test]
+    fn callback_subscriber_is_invoked() {
+        let mut bus = EventBus::new();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let h = hits.clone();
+        bus.subscribe_fn(EventMask::ALL, move |_| {
+            h.fetch_add(1, Ordering::SeqCst);
+        });
+        bus.publish(&ev(EngineEvent::Closed));
+        bus.publish(&ev(EngineEvent::UpdateBitrate(1)));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn dropped_receiver_is_pruned_and_unsubscribe_works() {
+        let mut bus = EventBus::new();
+        let (_, rx) = bus.subscribe(EventMask::ALL);
+        let id = bus.subscribe_fn(EventMask::ALL, |_| {});
+        assert_eq!(bus.len(), 2);
+
+        drop(rx);
+        bus.publish(&ev(EngineEvent::Closed));
+        assert_eq!(bus.len(), 1);
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        assert!(bus.is_empty());
+    }
+
+    #[test]
+    fn masks_combine() {
+        let mut m = EventMask::NONE;
+        assert!(!m.matches(&EngineEvent::Closed));
+        m |= EventMask::CONNECTION;
+        assert!(m.matches(&EngineEvent::Closed));
+        assert!(!m.matches(&EngineEvent::UpdateBitrate(1)));
+        assert!(EventMask::ALL.matches(&EngineEvent::UpdateBitrate(1)));
+    }
+}
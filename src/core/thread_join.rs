@@ -0,0 +1,51 @@
+//! Joining worker threads without hanging on one that is stuck.
+//!
+//! A camera or audio device that stops answering can leave its worker
+//! blocked in a read that never returns, and a plain `join` on it would
+//! freeze the window on exit. [`join_timeout`] waits a bounded time and
+//! then lets the thread go; it ends with the process.
+
+use std::{
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// How long a stopping pipeline waits for each of its threads.
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+const POLL: Duration = Duration::from_millis(5);
+
+/// Joins `handle` if its thread finishes within `timeout`. Returns `false`,
+/// leaving the thread detached, if it does not.
+pub fn join_timeout(handle: JoinHandle<()>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !handle.is_finished() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(POLL);
+    }
+    let _ = handle.join();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn stuck_threads_are_left_behind() {
+        let done = thread::spawn(|| {});
+        assert!(join_timeout(done, JOIN_TIMEOUT));
+
+        let (tx, rx) = mpsc::channel::<()>();
+        let stuck = thread::spawn(move || {
+            let _ = rx.recv();
+        });
+        let started = Instant::now();
+        assert!(!join_timeout(stuck, Duration::from_millis(20)));
+        assert!(started.elapsed() < JOIN_TIMEOUT);
+        drop(tx);
+    }
+}
@@ -0,0 +1,263 @@
+//! One thread for the periodic work of every session.
+//!
+//! The close driver, keepalives, path MTU probes, RTCP reports and ICE
+//! connectivity checks only need a few microseconds every few tens of
+//! milliseconds, so instead of a sleeping thread each, sessions schedule
+//! them here. With dozens of calls that is one thread instead of dozens.
+//!
+//! Sockets are waited on by the [`reactor`](crate::core::reactor) thread,
+//! likewise shared. Work that blocks keeps its own threads: the RTP,
+//! capture and codec workers of each call.
+//!
+//! Tasks run one after another on the timer thread: they must not block.
+//! Tests can use [`Timers::manual`] instead, which runs the tasks on the
+//! caller's thread as a [`ManualClock`] is advanced.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        Arc, Mutex, MutexGuard, OnceLock, PoisonError,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::clock::{self, Clock, ManualClock, SharedClock};
+
+/// Returns how long until it should run again, or `None` when it is done.
+type Task = Box<dyn FnMut() -> Option<Duration> + Send>;
+
+/// A timer thread, or a manual timer queue. Clones share it.
+#[derive(Clone)]
+pub struct Timers {
+    backend: Backend,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Thread(Sender<(Duration, Task)>),
+    Manual {
+        clock: ManualClock,
+        queue: Arc<Mutex<ManualQueue>>,
+    },
+}
+
+#[derive(Default)]
+struct ManualQueue {
+    /// Due time, order scheduled, task.
+    pending: Vec<(Instant, u64, Task)>,
+    scheduled: u64,
+}
+
+impl Timers {
+    /// Starts a timer thread; it exits once every `Timers` handle is dropped
+    /// and its tasks are done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OS fails to create the thread.
+    #[must_use]
+    #[allow(clippy::expect_used)]
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("session-timers".into())
+            .spawn(move || run(&rx))
+            .expect("spawn session-timers");
+        Self {
+            backend: Backend::Thread(tx),
+        }
+    }
+
+    /// The process-wide timer thread, started on first use: sessions, their
+    /// RTP sessions and ICE checks all schedule on it.
+    pub fn shared() -> &'static Self {
+        static SHARED: OnceLock<Timers> = OnceLock::new();
+        SHARED.get_or_init(Self::new)
+    }
+
+    /// Timers on virtual time: tasks only run inside
+    /// [`advance`](Self::advance), in due order, with `clock` set to the time
+    /// each was due.
+    #[must_use]
+    pub fn manual(clock: ManualClock) -> Self {
+        Self {
+            backend: Backend::Manual {
+                clock,
+                queue: Arc::default(),
+            },
+        }
+    }
+
+    /// The time the tasks are scheduled against.
+    #[must_use]
+    pub fn clock(&self) -> SharedClock {
+        match &self.backend {
+            Backend::Thread(_) => clock::system(),
+            Backend::Manual { clock, .. } => Arc::new(clock.clone()),
+        }
+    }
+
+    /// Runs `task` after `delay`, then again after each delay it returns,
+    /// until it returns `None`. A task that panics is dropped.
+    pub fn schedule(
+        &self,
+        delay: Duration,
+        task: impl FnMut() -> Option<Duration> + Send + 'static,
+    ) {
+        match &self.backend {
+            // The thread only exits once every sender is gone
+            Backend::Thread(tx) => {
+                let _ = tx.send((delay, Box::new(task)));
+            }
+            Backend::Manual { clock, queue } => {
+                lock(queue).push(clock.now() + delay, Box::new(task));
+            }
+        }
+    }
+
+    /// Moves manual timers forward by `by`, running every task that falls
+    /// due on the way, including ones scheduled by those tasks. Does nothing
+    /// on a timer thread.
+    pub fn advance(&self, by: Duration) {
+        let Backend::Manual { clock, queue } = &self.backend else {
+            return;
+        };
+        let until = clock.now() + by;
+        loop {
+            // Not holding the lock while a task runs: it may schedule more
+            let next = lock(queue).pop_due(until);
+            let Some((at, mut task)) = next else {
+                break;
+            };
+            clock.advance_to(at);
+            if let Ok(Some(after)) = panic::catch_unwind(AssertUnwindSafe(&mut task)) {
+                lock(queue).push(at + after, task);
+            }
+        }
+        clock.advance_to(until);
+    }
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualQueue {
+    fn push(&mut self, at: Instant, task: Task) {
+        self.scheduled += 1;
+        self.pending.push((at, self.scheduled, task));
+    }
+
+    fn pop_due(&mut self, until: Instant) -> Option<(Instant, Task)> {
+        let next = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, (at, _, _))| *at <= until)
+            .min_by_key(|(_, (at, order, _))| (*at, *order))
+            .map(|(i, _)| i)?;
+        let (at, _, task) = self.pending.swap_remove(next);
+        Some((at, task))
+    }
+}
+
+fn lock(queue: &Mutex<ManualQueue>) -> MutexGuard<'_, ManualQueue> {
+    queue.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn run(rx: &Receiver<(Duration, Task)>) {
+    let mut pending: Vec<(Instant, Task)> = Vec::new();
+    let mut open = true;
+    loop {
+        let next = pending.iter().map(|(at, _)| *at).min();
+        let received = match (open, next) {
+            (true, None) => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            (true, Some(at)) => rx.recv_timeout(at.saturating_duration_since(Instant::now())),
+            (false, None) => return,
+            (false, Some(at)) => {
+                thread::sleep(at.saturating_duration_since(Instant::now()));
+                Err(RecvTimeoutError::Timeout)
+            }
+        };
+        match received {
+            Ok((delay, task)) => pending.push((Instant::now() + delay, task)),
+            Err(RecvTimeoutError::Disconnected) => open = false,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        let now = Instant::now();
+        let (due, later): (Vec<_>, Vec<_>) = pending.drain(..).partition(|(at, _)| *at <= now);
+        pending = later;
+        for (_, mut task) in due {
+            if let Ok(Some(after)) = panic::catch_unwind(AssertUnwindSafe(&mut task)) {
+                pending.push((now + after, task));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn tasks_repeat_until_done_and_share_the_thread() {
+        let timers = Timers::new();
+        let (tx, rx) = mpsc::channel();
+        for id in 0..3 {
+            let tx = tx.clone();
+            let mut runs = 0;
+            timers.schedule(Duration::ZERO, move || {
+                runs += 1;
+                tx.send((id, thread::current().id())).unwrap();
+                (runs < 2).then_some(Duration::from_millis(5))
+            });
+        }
+        timers.schedule(Duration::ZERO, || panic!("dropped, not fatal"));
+        drop(tx);
+
+        let runs: Vec<_> = rx.iter().collect();
+        assert_eq!(runs.len(), 6);
+        for id in 0..3 {
+            assert_eq!(runs.iter().filter(|(i, _)| *i == id).count(), 2);
+        }
+        assert!(runs.iter().all(|(_, thread)| *thread == runs[0].1));
+        assert_ne!(runs[0].1, thread::current().id());
+    }
+
+    #[test]
+    fn manual_timers_run_in_due_order_on_virtual_time() {
+        let clock = ManualClock::new();
+        let timers = Timers::manual(clock.clone());
+        let t0 = clock.now();
+        let (tx, rx) = mpsc::channel();
+        for (id, every) in [("fast", 10), ("slow", 25)] {
+            let tx = tx.clone();
+            let at = timers.clock();
+            timers.schedule(Duration::from_millis(every), move || {
+                tx.send((id, at.now() - t0)).unwrap();
+                Some(Duration::from_millis(every))
+            });
+        }
+
+        timers.advance(Duration::from_millis(49));
+        let ms = |n| Duration::from_millis(n);
+        let runs: Vec<_> = rx.try_iter().collect();
+        assert_eq!(
+            runs,
+            [
+                ("fast", ms(10)),
+                ("fast", ms(20)),
+                ("slow", ms(25)),
+                ("fast", ms(30)),
+                ("fast", ms(40)),
+            ]
+        );
+        assert_eq!(clock.now() - t0, ms(49));
+    }
+}
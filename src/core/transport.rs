@@ -0,0 +1,51 @@
+//! The datagram socket seen by code that can run on a virtual network.
+//!
+//! [`PacketTransport`] is what a `UdpSocket` offers the ICE checks; tests
+//! swap in a [`VirtualSocket`], whose packets never touch the OS and arrive
+//! on a [`ManualClock`]'s time.
+//!
+//! [`VirtualSocket`]: crate::netsim::VirtualSocket
+//! [`ManualClock`]: super::clock::ManualClock
+
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+};
+
+/// An unconnected, non-blocking datagram socket.
+pub trait PacketTransport: Send + Sync {
+    /// Sends `buf` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the datagram cannot be sent.
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize>;
+
+    /// Receives one datagram if one is waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WouldBlock` when nothing is waiting, or the socket error.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+
+    /// The address the socket is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be read.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+impl PacketTransport for UdpSocket {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        Self::send_to(self, buf, to)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        Self::recv_from(self, buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Self::local_addr(self)
+    }
+}
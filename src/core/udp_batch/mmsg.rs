@@ -0,0 +1,202 @@
+//! `recvmmsg`/`sendmmsg`: up to [`BATCH`] datagrams per system call.
+
+use std::{
+    io, mem,
+    net::{SocketAddr, UdpSocket},
+    os::fd::AsRawFd,
+    ptr,
+};
+
+use super::{BATCH, is_idle};
+
+/// Room for one received datagram. Media, SCTP and STUN packets fit an MTU;
+/// longer datagrams are truncated by the kernel and dropped here.
+const SLOT: usize = 2048;
+
+/// Reads bursts of datagrams from a socket.
+pub struct BatchReceiver {
+    /// `BATCH` slots of `SLOT` bytes.
+    slots: Box<[u8]>,
+}
+
+impl Default for BatchReceiver {
+    fn default() -> Self {
+        Self {
+            slots: vec![0; BATCH * SLOT].into_boxed_slice(),
+        }
+    }
+}
+
+impl BatchReceiver {
+    /// Hands each datagram waiting on `sock` to `on_packet`, up to
+    /// [`BATCH`] of them. Returns how many were read: 0 when none was
+    /// waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket error if the first read fails; a later failure
+    /// ends the burst and shows up on the next call.
+    pub fn recv(
+        &mut self,
+        sock: &UdpSocket,
+        mut on_packet: impl FnMut(&[u8]),
+    ) -> io::Result<usize> {
+        // SAFETY: all-zero iovecs and message headers are valid (null
+        // pointers, zero lengths); every one used is filled in below.
+        let mut iovecs: [libc::iovec; BATCH] = unsafe { mem::zeroed() };
+        let mut msgs: [libc::mmsghdr; BATCH] = unsafe { mem::zeroed() };
+        for ((iov, msg), slot) in iovecs
+            .iter_mut()
+            .zip(msgs.iter_mut())
+            .zip(self.slots.chunks_mut(SLOT))
+        {
+            iov.iov_base = slot.as_mut_ptr().cast();
+            iov.iov_len = slot.len();
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+        }
+
+        // SAFETY: every header points at one iovec, and every iovec at its
+        // own `SLOT` bytes of `self.slots`, all alive for the call. A null
+        // timeout returns as soon as the socket has nothing more.
+        let n = unsafe {
+            libc::recvmmsg(
+                sock.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                BATCH as libc::c_uint,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if n < 0 {
+            let e = io::Error::last_os_error();
+            return if is_idle(&e) { Ok(0) } else { Err(e) };
+        }
+
+        let n = n as usize;
+        for (msg, slot) in msgs[..n].iter().zip(self.slots.chunks(SLOT)) {
+            if msg.msg_hdr.msg_flags & libc::MSG_TRUNC == 0 {
+                on_packet(&slot[..msg.msg_len as usize]);
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// Sends `packets` to `peer` in order, [`BATCH`] per system call. Returns
+/// how many were sent; fewer than all when the socket fails midway.
+///
+/// # Errors
+///
+/// Returns the socket error if not even the first packet could be sent.
+pub fn send_batch(sock: &UdpSocket, peer: SocketAddr, packets: &[Vec<u8>]) -> io::Result<usize> {
+    let (addr, addr_len) = to_sockaddr(peer);
+    let mut sent = 0;
+    for chunk in packets.chunks(BATCH) {
+        // SAFETY: as in `recv`, all-zero is a valid starting point.
+        let mut iovecs: [libc::iovec; BATCH] = unsafe { mem::zeroed() };
+        let mut msgs: [libc::mmsghdr; BATCH] = unsafe { mem::zeroed() };
+        for ((iov, msg), pkt) in iovecs.iter_mut().zip(msgs.iter_mut()).zip(chunk) {
+            // sendmmsg only reads through these pointers
+            iov.iov_base = pkt.as_ptr().cast_mut().cast();
+            iov.iov_len = pkt.len();
+            msg.msg_hdr.msg_name = ptr::from_ref(&addr).cast_mut().cast();
+            msg.msg_hdr.msg_namelen = addr_len;
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+        }
+
+        // SAFETY: the first `chunk.len()` headers point at `addr` and at one
+        // iovec each, which point into `chunk`; all alive for the call.
+        let n = unsafe {
+            libc::sendmmsg(
+                sock.as_raw_fd(),
+                msgs.as_mut_ptr(),
+                chunk.len() as libc::c_uint,
+                0,
+            )
+        };
+        if n < 0 {
+            let e = io::Error::last_os_error();
+            return if sent == 0 { Err(e) } else { Ok(sent) };
+        }
+        sent += n as usize;
+        if (n as usize) < chunk.len() {
+            break;
+        }
+    }
+    Ok(sent)
+}
+
+/// Sets (`on`) the Don't Fragment bit on every datagram `sock` sends, so
+/// one too big for the path is dropped (or refused with `EMSGSIZE`)
+/// instead of being fragmented, or goes back to the kernel's default.
+///
+/// # Errors
+///
+/// Returns the OS error if the option cannot be set.
+pub fn set_dont_fragment(sock: &UdpSocket, on: bool) -> io::Result<()> {
+    let v6 = sock.local_addr()?.is_ipv6();
+    let (level, name) = if v6 {
+        (libc::IPPROTO_IPV6, libc::IPV6_MTU_DISCOVER)
+    } else {
+        (libc::IPPROTO_IP, libc::IP_MTU_DISCOVER)
+    };
+    let value: libc::c_int = match (v6, on) {
+        (true, true) => libc::IPV6_PMTUDISC_PROBE,
+        (true, false) => libc::IPV6_PMTUDISC_WANT,
+        (false, true) => libc::IP_PMTUDISC_PROBE,
+        (false, false) => libc::IP_PMTUDISC_WANT,
+    };
+    // SAFETY: `value` is a valid `c_int` for the duration of the call.
+    let ret = unsafe {
+        libc::setsockopt(
+            sock.as_raw_fd(),
+            level,
+            name,
+            ptr::from_ref(&value).cast(),
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// `addr` as a C socket address and its length.
+fn to_sockaddr(addr: SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    // SAFETY: all-zero is a valid `sockaddr_storage`.
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(a) => {
+            let sin = libc::sockaddr_in {
+                sin_family: libc::AF_INET as libc::sa_family_t,
+                sin_port: a.port().to_be(),
+                sin_addr: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(a.ip().octets()),
+                },
+                sin_zero: [0; 8],
+            };
+            // SAFETY: `sockaddr_storage` is large and aligned enough for any
+            // socket address.
+            unsafe { ptr::write(ptr::from_mut(&mut storage).cast(), sin) };
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(a) => {
+            let sin6 = libc::sockaddr_in6 {
+                sin6_family: libc::AF_INET6 as libc::sa_family_t,
+                sin6_port: a.port().to_be(),
+                sin6_flowinfo: a.flowinfo(),
+                sin6_addr: libc::in6_addr {
+                    s6_addr: a.ip().octets(),
+                },
+                sin6_scope_id: a.scope_id(),
+            };
+            // SAFETY: as above.
+            unsafe { ptr::write(ptr::from_mut(&mut storage).cast(), sin6) };
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
@@ -0,0 +1,78 @@
+//! Batched UDP I/O for the media path.
+//!
+//! With the `udp-batch` feature on Linux, the session receiver drains the
+//! socket with `recvmmsg` and the packets of a video frame leave in one
+//! `sendmmsg`: one system call for up to [`BATCH`] datagrams, which matters
+//! on the busy receive loop and during keyframe bursts. Elsewhere, or
+//! without the feature, the same calls loop over `recv`/`send_to`.
+//!
+//! The same `libc` calls set the Don't Fragment bit for path MTU probes
+//! ([`set_dont_fragment`]); other builds cannot.
+
+#[cfg(all(feature = "udp-batch", target_os = "linux"))]
+mod mmsg;
+#[cfg(not(all(feature = "udp-batch", target_os = "linux")))]
+mod per_packet;
+
+#[cfg(all(feature = "udp-batch", target_os = "linux"))]
+pub use mmsg::{BatchReceiver, send_batch, set_dont_fragment};
+#[cfg(not(all(feature = "udp-batch", target_os = "linux")))]
+pub use per_packet::{BatchReceiver, send_batch, set_dont_fragment};
+
+use std::io;
+
+/// Most datagrams moved by one call.
+pub const BATCH: usize = 64;
+
+/// Whether this build batches system calls.
+#[must_use]
+pub const fn is_batched() -> bool {
+    cfg!(all(feature = "udp-batch", target_os = "linux"))
+}
+
+/// Nothing to read right now (non-blocking socket or read timeout).
+fn is_idle(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::{
+        net::UdpSocket,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn a_burst_arrives_whole_and_in_order() {
+        let rx = UdpSocket::bind("127.0.0.1:0").unwrap();
+        rx.set_nonblocking(true).unwrap();
+        let tx = UdpSocket::bind("127.0.0.1:0").unwrap();
+        // More than one batch
+        let packets: Vec<Vec<u8>> = (0..100u8).map(|i| vec![i; 100 + usize::from(i)]).collect();
+
+        let sent = send_batch(&tx, rx.local_addr().unwrap(), &packets).unwrap();
+        assert_eq!(sent, packets.len());
+
+        let mut receiver = BatchReceiver::default();
+        let mut got = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while got.len() < packets.len() && Instant::now() < deadline {
+            let n = receiver.recv(&rx, |pkt| got.push(pkt.to_vec())).unwrap();
+            assert!(n <= BATCH);
+            if n == 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        assert_eq!(got, packets);
+        assert_eq!(
+            receiver.recv(&rx, |_| panic!("no more packets")).unwrap(),
+            0
+        );
+    }
+}
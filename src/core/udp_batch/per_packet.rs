@@ -0,0 +1,82 @@
+//! One system call per datagram, for builds without `recvmmsg`/`sendmmsg`.
+
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+};
+
+use super::{BATCH, is_idle};
+
+/// Largest UDP payload.
+const MAX_DATAGRAM: usize = 65535;
+
+/// Reads bursts of datagrams from a socket.
+pub struct BatchReceiver {
+    buf: Box<[u8]>,
+}
+
+impl Default for BatchReceiver {
+    fn default() -> Self {
+        Self {
+            buf: vec![0; MAX_DATAGRAM].into_boxed_slice(),
+        }
+    }
+}
+
+impl BatchReceiver {
+    /// Hands each datagram waiting on `sock` to `on_packet`, up to
+    /// [`BATCH`] of them. Returns how many were read: 0 when none was
+    /// waiting.
+    ///
+    /// # Errors
+    ///
+    /// Returns the socket error if the first read fails; a later failure
+    /// ends the burst and shows up on the next call.
+    pub fn recv(
+        &mut self,
+        sock: &UdpSocket,
+        mut on_packet: impl FnMut(&[u8]),
+    ) -> io::Result<usize> {
+        let mut count = 0;
+        while count < BATCH {
+            match sock.recv(&mut self.buf) {
+                Ok(n) => {
+                    on_packet(&self.buf[..n]);
+                    count += 1;
+                }
+                Err(e) if is_idle(&e) => break,
+                Err(_) if count > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(count)
+    }
+}
+
+/// Sends `packets` to `peer` in order. Returns how many were sent; fewer
+/// than all when the socket fails midway.
+///
+/// # Errors
+///
+/// Returns the socket error if not even the first packet could be sent.
+pub fn send_batch(sock: &UdpSocket, peer: SocketAddr, packets: &[Vec<u8>]) -> io::Result<usize> {
+    for (sent, pkt) in packets.iter().enumerate() {
+        if let Err(e) = sock.send_to(pkt, peer) {
+            return if sent == 0 { Err(e) } else { Ok(sent) };
+        }
+    }
+    Ok(packets.len())
+}
+
+/// The standard library cannot set the Don't Fragment bit, so it is never
+/// on.
+///
+/// # Errors
+///
+/// Fails with [`io::ErrorKind::Unsupported`] when asked to set it.
+pub fn set_dont_fragment(_sock: &UdpSocket, on: bool) -> io::Result<()> {
+    if on {
+        return Err(io::ErrorKind::Unsupported.into());
+    }
+    Ok(())
+}
@@ -20,6 +20,8 @@ pub struct BufferedUdpChannel {
     manual_mode: bool,
     logger: Arc<dyn LogSink>,
     outgoing_queue: VecDeque<Vec<u8>>,
+    /// Copies of the datagrams written since `start_recording`.
+    recorded: Option<Vec<Vec<u8>>>,
 }
 
 impl fmt::Debug for BufferedUdpChannel {
@@ -42,6 +44,7 @@ impl BufferedUdpChannel {
             manual_mode: false,
             logger,
             outgoing_queue: VecDeque::new(),
+            recorded: None,
         }
     }
 
@@ -56,6 +59,18 @@ impl BufferedUdpChannel {
     pub fn has_pending_writes(&self) -> bool {
         !self.outgoing_queue.is_empty()
     }
+
+    /// Keeps a copy of every datagram written from now on, until
+    /// [`take_recorded`](Self::take_recorded).
+    pub fn start_recording(&mut self) {
+        self.recorded = Some(Vec::new());
+    }
+
+    /// The datagrams written since [`start_recording`](Self::start_recording),
+    /// and stops recording.
+    pub fn take_recorded(&mut self) -> Vec<Vec<u8>> {
+        self.recorded.take().unwrap_or_default()
+    }
 }
 
 impl Read for BufferedUdpChannel {
@@ -108,6 +123,9 @@ impl Read for BufferedUdpChannel {
 
 impl Write for BufferedUdpChannel {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(recorded) = &mut self.recorded {
+            recorded.push(buf.to_vec());
+        }
         // If queue is not empty, we must queue this new packet to maintain order
         if !self.outgoing_queue.is_empty() {
             self.outgoing_queue.push_back(buf.to_vec());
@@ -29,6 +29,8 @@ impl fmt::Display for DtlsError {
     }
 }
 
+impl std::error::Error for DtlsError {}
+
 impl From<io::Error> for DtlsError {
     fn from(e: io::Error) -> Self {
         DtlsError::Io(e)
@@ -5,5 +5,7 @@ pub mod dtls_error;
 pub mod dtls_role;
 pub mod runtime;
 pub mod socket_blocking_guard;
+#[cfg(test)]
+pub(crate) mod test_pair;
 pub use dtls_role::DtlsRole;
 pub use runtime::run_dtls_handshake;
@@ -1,5 +1,6 @@
 use crate::{
     config::Config,
+    core::path_mtu::MtuConfig,
     dtls::{
         buffered_udp_channel::BufferedUdpChannel, dtls_error::DtlsError, dtls_role::DtlsRole,
         socket_blocking_guard::SocketBlockingGuard,
@@ -9,7 +10,9 @@ use crate::{
     srtp::{SrtpEndpointKeys, SrtpProfile, SrtpSessionConfig},
     tls_utils::{DTLS_CERT_PATH, DTLS_KEY_PATH},
 };
-use openssl::ssl::{HandshakeError, Ssl, SslContextBuilder, SslFiletype, SslMethod, SslStream};
+use openssl::ssl::{
+    HandshakeError, Ssl, SslContextBuilder, SslFiletype, SslMethod, SslOptions, SslStream,
+};
 use std::{
     io::{self},
     net::{SocketAddr, UdpSocket},
@@ -17,6 +20,7 @@ use std::{
     time::Duration,
 };
 
+use foreign_types::ForeignTypeRef;
 use openssl::hash::MessageDigest;
 use openssl::ssl::SslVerifyMode;
 
@@ -39,7 +43,8 @@ use openssl::ssl::SslVerifyMode;
 /// * `timeout` - The maximum duration to wait for the handshake to complete.
 /// * `expected_fingerprint` - An optional SHA-256 fingerprint string for certificate validation.
 ///   If `None`, certificate verification is disabled (INSECURE).
-/// * `config` - The application configuration, used to get certificate paths.
+/// * `config` - The application configuration, used to get certificate paths
+///   and the MTU.
 ///
 /// # Errors
 ///
@@ -154,8 +159,7 @@ fn dtls_connect_openssl(
         .check_private_key()
         .map_err(|e| DtlsError::Ssl(format!("Private key does not match certificate: {}", e)))?;
 
-    let ssl = Ssl::new(&builder.build())
-        .map_err(|e| DtlsError::Ssl(format!("Ssl::new failed: {}", e)))?;
+    let ssl = new_ssl(builder, &config)?;
 
     sink_debug!(&logger, "[DTLS] Client: Starting connect()...");
     match ssl.connect(stream) {
@@ -201,8 +205,7 @@ fn dtls_accept_openssl(
         .check_private_key()
         .map_err(|e| DtlsError::Ssl(format!("Private key does not match certificate: {}", e)))?;
 
-    let ssl = Ssl::new(&builder.build())
-        .map_err(|e| DtlsError::Ssl(format!("Ssl::new failed: {}", e)))?;
+    let ssl = new_ssl(builder, &config)?;
 
     sink_debug!(&logger, "[DTLS] Server: Starting accept()...");
     match ssl.accept(stream) {
@@ -211,6 +214,43 @@ fn dtls_accept_openssl(
     }
 }
 
+/// Creates the DTLS connection, its datagrams at most `[ICE] mtu` bytes.
+///
+/// # Errors
+///
+/// Returns a `DtlsError` if OpenSSL refuses the connection or the MTU.
+fn new_ssl(mut builder: SslContextBuilder, config: &Config) -> Result<Ssl, DtlsError> {
+    // The size comes from the configuration, not from the socket
+    builder.set_options(SslOptions::NO_QUERY_MTU);
+    let mut ssl = Ssl::new(&builder.build())
+        .map_err(|e| DtlsError::Ssl(format!("Ssl::new failed: {}", e)))?;
+    ssl.set_mtu(MtuConfig::from_config(config).mtu as u32)
+        .map_err(|e| DtlsError::Ssl(format!("set_mtu failed: {}", e)))?;
+    Ok(ssl)
+}
+
+/// Makes an established DTLS connection fit its datagrams to `mtu` bytes,
+/// once the path MTU is known.
+///
+/// # Errors
+///
+/// Returns a `DtlsError` if OpenSSL refuses the MTU.
+pub fn set_stream_mtu(
+    stream: &mut SslStream<BufferedUdpChannel>,
+    mtu: usize,
+) -> Result<(), DtlsError> {
+    let mtu = std::ffi::c_long::try_from(mtu)
+        .map_err(|_| DtlsError::Ssl(format!("MTU {} out of range", mtu)))?;
+    // SAFETY: the pointer is the live `SSL` the stream owns, borrowed
+    // mutably for the call; `openssl` only exposes `set_mtu` on an `Ssl`
+    // not yet handshaken
+    let set = unsafe { openssl_sys::SSL_set_mtu(stream.ssl().as_ptr(), mtu) };
+    if set == 0 {
+        return Err(DtlsError::Ssl(format!("set_mtu({}) failed", mtu)));
+    }
+    Ok(())
+}
+
 /// Derives SRTP session keys from an established DTLS session.
 ///
 /// # Errors
@@ -0,0 +1,71 @@
+//! Two ends of a DTLS connection over loopback, for tests.
+
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::{net::UdpSocket, path::Path, sync::Arc, thread, time::Duration};
+
+use openssl::ssl::SslStream;
+
+use crate::{
+    config::Config,
+    dtls::{DtlsRole, buffered_udp_channel::BufferedUdpChannel, run_dtls_handshake},
+    log::{NoopLogSink, log_sink::LogSink},
+    signaling::cert_gen::ensure_signaling_cert,
+    srtp::SrtpSessionConfig,
+};
+
+/// One end: its socket, the SRTP keys it exported and its stream.
+pub(crate) type End = (
+    Arc<UdpSocket>,
+    SrtpSessionConfig,
+    SslStream<BufferedUdpChannel>,
+);
+
+/// A configuration whose DTLS identity is a certificate issued in `dir`.
+fn dtls_config(dir: &Path) -> Arc<Config> {
+    let mut config = Config::empty();
+    let path = |name: &str| dir.join(name).display().to_string();
+    config.set("TLS", "generate_signaling_cert", "true");
+    config.set("TLS", "signaling_cert", path("cert.pem"));
+    config.set("TLS", "signaling_key", path("key.pem"));
+    config.set("TLS", "signaling_ca_cert", path("ca.pem"));
+    config.set("TLS", "signaling_ca_key", path("ca-key.pem"));
+    config.set("TLS", "signaling_sans", "127.0.0.1");
+    ensure_signaling_cert(&config).unwrap();
+    config.set("TLS", "dtls_cert", path("cert.pem"));
+    config.set("TLS", "dtls_key", path("key.pem"));
+    Arc::new(config)
+}
+
+/// Runs a handshake between two loopback sockets, with a certificate
+/// issued in a directory named after `test`. Returns `(client, server)`.
+pub(crate) fn handshake(test: &str) -> (End, End) {
+    let dir = std::env::temp_dir().join(format!("rustyrtc-{test}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let config = dtls_config(&dir);
+    let a = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+    let b = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+    let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+    let log: Arc<dyn LogSink> = Arc::new(NoopLogSink);
+    let timeout = Duration::from_secs(5);
+
+    let server = {
+        let (b, log, config) = (b.clone(), log.clone(), config.clone());
+        thread::spawn(move || {
+            run_dtls_handshake(b, a_addr, DtlsRole::Server, log, timeout, None, config)
+        })
+    };
+    let (a_keys, a_stream) = run_dtls_handshake(
+        a.clone(),
+        b_addr,
+        DtlsRole::Client,
+        log,
+        timeout,
+        None,
+        config,
+    )
+    .unwrap();
+    let (b_keys, b_stream) = server.join().unwrap().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+    ((a, a_keys, a_stream), (b, b_keys, b_stream))
+}
@@ -0,0 +1,219 @@
+//! The error type of the public API.
+//!
+//! Each module keeps its own error enum; `RtcError` gathers those the
+//! engine's calls can fail with, so that a caller can match on what failed
+//! (ICE, DTLS, SDP, RTP, media, ...) instead of parsing a message. Each of
+//! them converts into it with `?`.
+
+use std::{fmt, io};
+
+use crate::{
+    config::ConfigError,
+    connection_manager::connection_error::ConnectionError,
+    dtls::dtls_error::DtlsError,
+    ice::{ice_error::IceError, stun::StunError},
+    media_agent::{media_agent_error::MediaAgentError, watermark::WatermarkError},
+    media_transport::error::MediaTransportError,
+    rtp::rtp_error::RtpError,
+    rtp_session::rtp_session_error::RtpSessionError,
+    sdp::sdp_error::SdpError,
+    signaling::{forward_policy::UnknownForwardPolicy, protocol::errors::ProtoError},
+};
+
+/// Result of the public API.
+pub type Result<T> = std::result::Result<T, RtcError>;
+
+/// What went wrong in a call to the engine.
+#[derive(Debug)]
+pub enum RtcError {
+    Ice(IceError),
+    Stun(StunError),
+    Dtls(DtlsError),
+    Sdp(SdpError),
+    Rtp(RtpError),
+    RtpSession(RtpSessionError),
+    /// A signaling message is malformed.
+    Proto(ProtoError),
+    /// Offer/answer negotiation failed.
+    Connection(ConnectionError),
+    /// The configuration file could not be loaded or written.
+    Config(ConfigError),
+    /// `[Signaling] forward_policy` names no policy.
+    ForwardPolicy(UnknownForwardPolicy),
+    /// The watermark picture could not be loaded.
+    Watermark(WatermarkError),
+    /// Sending media failed, or a DTMF string holds a non-digit.
+    MediaTransport(MediaTransportError),
+    /// Capture, codecs or recording failed.
+    MediaAgent(MediaAgentError),
+    Io(io::Error),
+    /// The session is not running: not started yet, or already closed.
+    NotRunning,
+    /// A thread panicked while holding a lock this call needs.
+    LockPoisoned,
+}
+
+impl fmt::Display for RtcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ice(e) => write!(f, "ICE error: {e}"),
+            Self::Stun(e) => write!(f, "STUN error: {e}"),
+            Self::Dtls(e) => write!(f, "DTLS error: {e}"),
+            Self::Sdp(e) => write!(f, "SDP error: {e}"),
+            Self::Rtp(e) => write!(f, "RTP error: {e}"),
+            Self::RtpSession(e) => write!(f, "RTP session error: {e}"),
+            Self::Proto(e) => write!(f, "signaling protocol error: {e}"),
+            Self::Connection(e) => write!(f, "connection error: {e}"),
+            Self::Config(e) => write!(f, "configuration error: {e}"),
+            Self::ForwardPolicy(e) => write!(f, "signaling error: {e}"),
+            Self::Watermark(e) => write!(f, "watermark error: {e}"),
+            Self::MediaTransport(e) => write!(f, "media transport error: {e}"),
+            Self::MediaAgent(e) => write!(f, "media error: {e}"),
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::NotRunning => write!(f, "session not running"),
+            Self::LockPoisoned => write!(f, "lock poisoned"),
+        }
+    }
+}
+
+impl std::error::Error for RtcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Ice(e) => Some(e),
+            Self::Stun(e) => Some(e),
+            Self::Dtls(e) => Some(e),
+            Self::Sdp(e) => Some(e),
+            Self::Rtp(e) => Some(e),
+            Self::RtpSession(e) => Some(e),
+            Self::Proto(e) => Some(e),
+            Self::Connection(e) => Some(e),
+            Self::Config(e) => Some(e),
+            Self::ForwardPolicy(e) => Some(e),
+            Self::Watermark(e) => Some(e),
+            Self::MediaTransport(e) => Some(e),
+            Self::MediaAgent(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::NotRunning | Self::LockPoisoned => None,
+        }
+    }
+}
+
+impl From<IceError> for RtcError {
+    fn from(e: IceError) -> Self {
+        Self::Ice(e)
+    }
+}
+
+impl From<StunError> for RtcError {
+    fn from(e: StunError) -> Self {
+        Self::Stun(e)
+    }
+}
+
+impl From<DtlsError> for RtcError {
+    fn from(e: DtlsError) -> Self {
+        Self::Dtls(e)
+    }
+}
+
+impl From<SdpError> for RtcError {
+    fn from(e: SdpError) -> Self {
+        Self::Sdp(e)
+    }
+}
+
+impl From<RtpError> for RtcError {
+    fn from(e: RtpError) -> Self {
+        Self::Rtp(e)
+    }
+}
+
+impl From<RtpSessionError> for RtcError {
+    fn from(e: RtpSessionError) -> Self {
+        Self::RtpSession(e)
+    }
+}
+
+impl From<ProtoError> for RtcError {
+    fn from(e: ProtoError) -> Self {
+        Self::Proto(e)
+    }
+}
+
+impl From<ConnectionError> for RtcError {
+    fn from(e: ConnectionError) -> Self {
+        Self::Connection(e)
+    }
+}
+
+impl From<ConfigError> for RtcError {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
+impl From<UnknownForwardPolicy> for RtcError {
+    fn from(e: UnknownForwardPolicy) -> Self {
+        Self::ForwardPolicy(e)
+    }
+}
+
+impl From<WatermarkError> for RtcError {
+    fn from(e: WatermarkError) -> Self {
+        Self::Watermark(e)
+    }
+}
+
+impl From<MediaTransportError> for RtcError {
+    fn from(e: MediaTransportError) -> Self {
+        Self::MediaTransport(e)
+    }
+}
+
+impl From<MediaAgentError> for RtcError {
+    fn from(e: MediaAgentError) -> Self {
+        Self::MediaAgent(e)
+    }
+}
+
+impl From<io::Error> for RtcError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn module_errors_convert_and_keep_their_kind() {
+        fn ice() -> Result<()> {
+            let failed: std::result::Result<(), IceError> = Err(IceError::NoNominatedPair);
+            Ok(failed?)
+        }
+        let err = ice().unwrap_err();
+        assert!(matches!(err, RtcError::Ice(IceError::NoNominatedPair)));
+        assert_eq!(err.to_string(), "ICE error: no nominated pair available");
+        assert!(err.source().is_some());
+
+        let err = RtcError::from(SdpError::Missing("m="));
+        assert!(matches!(err, RtcError::Sdp(SdpError::Missing("m="))));
+        assert!(RtcError::NotRunning.source().is_none());
+        assert!(matches!(
+            RtcError::from(MediaTransportError::InvalidDtmf('x')),
+            RtcError::MediaTransport(MediaTransportError::InvalidDtmf('x'))
+        ));
+
+        fn config() -> Result<()> {
+            crate::config::Config::from_toml("[Media]\nfps = 0\n")?;
+            Ok(())
+        }
+        assert!(matches!(
+            config().unwrap_err(),
+            RtcError::Config(ConfigError::Invalid(_))
+        ));
+    }
+}
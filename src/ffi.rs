@@ -0,0 +1,519 @@
+//! C ABI for embedding the engine from C, C++ or Python (`ctypes`/`cffi`).
+//!
+//! The engine is exposed as an opaque [`RtcEngine`] handle. Strings returned
+//! to the caller are heap-allocated and must be released with
+//! [`rtc_string_free`]. Functions returning `i32` use the `RTC_*` codes; the
+//! message of the last failure is available from [`rtc_engine_last_error`].
+//!
+//! The matching header is `include/rustyrtc.h` (generated with
+//! `cbindgen --config cbindgen.toml --output include/rustyrtc.h`).
+//!
+//! Signaling stays with the caller: SDPs and candidates produced here must be
+//! delivered to the remote peer by the embedding application.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::{CStr, CString, c_char},
+    ptr,
+    sync::{
+        Arc,
+        atomic::AtomicBool,
+        mpsc::{self, Sender},
+    },
+};
+
+use crate::{
+    config::Config,
+    core::{engine::Engine, events::EngineEvent},
+    log::{log_sink::LogSink, logger::Logger},
+    media_agent::{
+        frame_format::FrameFormat,
+        utils::now_millis,
+        video_frame::{VideoFrame, VideoFrameData},
+        video_track::{TrackId, VideoSource},
+    },
+};
+
+/// Success.
+pub const RTC_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const RTC_ERR_NULL: i32 = -1;
+/// A string argument was not valid UTF-8.
+pub const RTC_ERR_UTF8: i32 = -2;
+/// The engine rejected the call; see [`rtc_engine_last_error`].
+pub const RTC_ERR_ENGINE: i32 = -3;
+
+/// Kind of an event returned by [`rtc_engine_poll_event`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtcEventKind {
+    /// No event was pending.
+    None = 0,
+    Status = 1,
+    IceNominated = 2,
+    Established = 3,
+    StateChanged = 4,
+    Closing = 5,
+    Closed = 6,
+    Error = 7,
+    RemoteTrackAdded = 8,
+    RemoteTrackRemoved = 9,
+    /// Any other engine event; `text` holds its debug representation.
+    Other = 10,
+}
+
+/// An engine event. `text` is owned by the caller (free it with
+/// [`rtc_string_free`]) and may be null.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RtcEvent {
+    pub kind: RtcEventKind,
+    pub text: *mut c_char,
+}
+
+/// Opaque engine handle.
+pub struct RtcEngine {
+    engine: Engine,
+    // Keeps the log writer alive for the lifetime of the engine.
+    _logger: Logger,
+    events: VecDeque<EngineEvent>,
+    frame_tracks: HashMap<TrackId, Sender<VideoFrame>>,
+    last_error: Option<CString>,
+}
+
+impl RtcEngine {
+    fn fail(&mut self, msg: impl Into<String>) -> i32 {
+        self.last_error = CString::new(msg.into()).ok();
+        RTC_ERR_ENGINE
+    }
+
+    /// Drives the engine and returns the next event worth reporting.
+    ///
+    /// The session and the media pipeline are started automatically, so the
+    /// caller only has to exchange SDPs and candidates.
+    fn next_event(&mut self) -> Option<EngineEvent> {
+        if self.events.is_empty() {
+            self.events.extend(self.engine.poll());
+        }
+        while let Some(ev) = self.events.pop_front() {
+            match &ev {
+                EngineEvent::IceNominated { .. } => {
+                    if let Err(e) = self.engine.start() {
+                        self.last_error = CString::new(e.to_string()).ok();
+                    }
+                }
+                EngineEvent::Established => self.engine.start_media_transport(),
+                EngineEvent::Log(_)
+                | EngineEvent::RtpIn(_)
+                | EngineEvent::SendFileChunk(..)
+                | EngineEvent::ReceivedFileChunk(..) => continue,
+                _ => {}
+            }
+            return Some(ev);
+        }
+        None
+    }
+}
+
+/// Converts an event to its C form.
+fn to_c_event(ev: &EngineEvent) -> RtcEvent {
+    let (kind, text) = match ev {
+        EngineEvent::Status(s) => (RtcEventKind::Status, s.clone()),
+        EngineEvent::IceNominated { local, remote } => {
+            (RtcEventKind::IceNominated, format!("{local} -> {remote}"))
+        }
+        EngineEvent::Established => (RtcEventKind::Established, String::new()),
+        EngineEvent::PeerConnectionStateChanged(state) => {
+            (RtcEventKind::StateChanged, format!("{state:?}"))
+        }
+        EngineEvent::Closing { graceful } => {
+            (RtcEventKind::Closing, format!("graceful={graceful}"))
+        }
+        EngineEvent::Closed => (RtcEventKind::Closed, String::new()),
+        EngineEvent::Error(e) => (RtcEventKind::Error, e.clone()),
+        EngineEvent::RemoteTrackAdded { ssrc, label } => {
+            (RtcEventKind::RemoteTrackAdded, format!("{ssrc} {label}"))
+        }
+        EngineEvent::RemoteTrackRemoved { ssrc } => {
+            (RtcEventKind::RemoteTrackRemoved, ssrc.to_string())
+        }
+        other => (RtcEventKind::Other, format!("{other:?}")),
+    };
+    RtcEvent {
+        kind,
+        text: into_c_string(text),
+    }
+}
+
+/// Hands a string to C. Interior NULs are dropped.
+fn into_c_string(s: String) -> *mut c_char {
+    let bytes: Vec<u8> = s.into_bytes().into_iter().filter(|&b| b != 0).collect();
+    CString::new(bytes).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Reads a C string argument.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string.
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, i32> {
+    if s.is_null() {
+        return Err(RTC_ERR_NULL);
+    }
+    // SAFETY: non-null and NUL-terminated per the caller contract.
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|_| RTC_ERR_UTF8)
+}
+
+/// Writes an optional SDP to `out` (null when there is nothing to send).
+///
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn write_sdp(out: *mut *mut c_char, sdp: Option<String>) {
+    if !out.is_null() {
+        // SAFETY: valid for writes per the caller contract.
+        unsafe { *out = sdp.map_or(ptr::null_mut(), into_c_string) };
+    }
+}
+
+/// Creates an engine. `config_path` may be null to use built-in defaults.
+///
+/// Returns null if the configuration cannot be loaded.
+///
+/// # Safety
+///
+/// `config_path` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_create(config_path: *const c_char) -> *mut RtcEngine {
+    let config = if config_path.is_null() {
+        Config::empty()
+    } else {
+        // SAFETY: forwarded caller contract.
+        let Ok(path) = (unsafe { read_str(config_path) }) else {
+            return ptr::null_mut();
+        };
+        match Config::load(path) {
+            Ok(c) => c,
+            Err(_) => return ptr::null_mut(),
+        }
+    };
+    let config = Arc::new(config);
+    let logger = Logger::start_client(4096, 256, 50, config.clone());
+    let sink: Arc<dyn LogSink> = Arc::new(logger.handle());
+    let engine = Engine::new(
+        sink,
+        config,
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(AtomicBool::new(false)),
+    );
+    Box::into_raw(Box::new(RtcEngine {
+        engine,
+        _logger: logger,
+        events: VecDeque::new(),
+        frame_tracks: HashMap::new(),
+        last_error: None,
+    }))
+}
+
+/// Stops and frees an engine. Null is ignored.
+///
+/// # Safety
+///
+/// `engine` must be null or a handle from [`rtc_engine_create`] that has not
+/// been destroyed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_destroy(engine: *mut RtcEngine) {
+    if engine.is_null() {
+        return;
+    }
+    // SAFETY: the handle came from `Box::into_raw` and is destroyed once.
+    let mut engine = unsafe { Box::from_raw(engine) };
+    engine.frame_tracks.clear();
+    engine.engine.stop_all();
+}
+
+/// Creates an offer. `*out_sdp` receives it, or null if an offer is
+/// already pending.
+///
+/// # Safety
+///
+/// `engine` must be a live handle; `out_sdp` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_negotiate(
+    engine: *mut RtcEngine,
+    out_sdp: *mut *mut c_char,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    match h.engine.negotiate() {
+        Ok(sdp) => {
+            // SAFETY: forwarded caller contract.
+            unsafe { write_sdp(out_sdp, sdp) };
+            RTC_OK
+        }
+        Err(e) => h.fail(e.to_string()),
+    }
+}
+
+/// Applies a remote offer or answer. For an offer, `*out_answer` receives
+/// the answer to send back; otherwise it is set to null.
+///
+/// # Safety
+///
+/// `engine` must be a live handle, `sdp` a NUL-terminated string and
+/// `out_answer` null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_apply_sdp(
+    engine: *mut RtcEngine,
+    sdp: *const c_char,
+    out_answer: *mut *mut c_char,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    // SAFETY: forwarded caller contract.
+    let sdp = match unsafe { read_str(sdp) } {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    match h.engine.apply_remote_sdp(sdp) {
+        Ok(answer) => {
+            // SAFETY: forwarded caller contract.
+            unsafe { write_sdp(out_answer, answer) };
+            RTC_OK
+        }
+        Err(e) => h.fail(e.to_string()),
+    }
+}
+
+/// Applies a remote ICE candidate line (`candidate:...`).
+///
+/// # Safety
+///
+/// `engine` must be a live handle and `candidate` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_apply_candidate(
+    engine: *mut RtcEngine,
+    candidate: *const c_char,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    // SAFETY: forwarded caller contract.
+    let line = match unsafe { read_str(candidate) } {
+        Ok(s) => s,
+        Err(code) => return code,
+    };
+    match h.engine.apply_remote_candidate(line) {
+        Ok(()) => RTC_OK,
+        Err(e) => h.fail(e.to_string()),
+    }
+}
+
+/// Drives the engine and pops the next event into `*out`.
+///
+/// Returns 1 if an event was written, 0 if none was pending (`out->kind`
+/// is then `None`), or a negative `RTC_*` code. Call it regularly (e.g.
+/// every 10-50 ms): it also starts the session and the media pipeline.
+///
+/// # Safety
+///
+/// `engine` must be a live handle and `out` valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_poll_event(engine: *mut RtcEngine, out: *mut RtcEvent) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    if out.is_null() {
+        return RTC_ERR_NULL;
+    }
+    let (event, found) = match h.next_event() {
+        Some(ev) => (to_c_event(&ev), 1),
+        None => (
+            RtcEvent {
+                kind: RtcEventKind::None,
+                text: ptr::null_mut(),
+            },
+            0,
+        ),
+    };
+    // SAFETY: valid for writes per the caller contract.
+    unsafe { out.write(event) };
+    found
+}
+
+/// Adds a video track fed by [`rtc_engine_send_frame`]. `*out_track`
+/// receives its id and `*out_sdp` the re-offer to send (null if an offer is
+/// already pending). The call must be established.
+///
+/// # Safety
+///
+/// `engine` must be a live handle; `out_track` and `out_sdp` must be null
+/// or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_add_frame_track(
+    engine: *mut RtcEngine,
+    out_track: *mut u32,
+    out_sdp: *mut *mut c_char,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    let (tx, rx) = mpsc::channel();
+    match h.engine.add_video_track(VideoSource::Frames(rx)) {
+        Ok((id, sdp)) => {
+            h.frame_tracks.insert(id, tx);
+            if !out_track.is_null() {
+                // SAFETY: valid for writes per the caller contract.
+                unsafe { *out_track = id };
+            }
+            // SAFETY: forwarded caller contract.
+            unsafe { write_sdp(out_sdp, sdp) };
+            RTC_OK
+        }
+        Err(e) => h.fail(e.to_string()),
+    }
+}
+
+/// Pushes one packed RGB24 frame (`width * height * 3` bytes) to a track
+/// created with [`rtc_engine_add_frame_track`]. The pixels are copied.
+///
+/// # Safety
+///
+/// `engine` must be a live handle and `rgb` must point to at least
+/// `width * height * 3` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_send_frame(
+    engine: *mut RtcEngine,
+    track: u32,
+    rgb: *const u8,
+    width: u32,
+    height: u32,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    if rgb.is_null() {
+        return RTC_ERR_NULL;
+    }
+    let Some(tx) = h.frame_tracks.get(&track) else {
+        return h.fail(format!("no frame track {track}"));
+    };
+    let len = width as usize * height as usize * 3;
+    // SAFETY: `len` readable bytes per the caller contract.
+    let pixels = unsafe { std::slice::from_raw_parts(rgb, len) }.to_vec();
+    let frame = VideoFrame {
+        width,
+        height,
+        timestamp_ms: now_millis(),
+        format: FrameFormat::Rgb,
+        data: VideoFrameData::Rgb(Arc::new(pixels)),
+    };
+    if tx.send(frame).is_err() {
+        h.frame_tracks.remove(&track);
+        return h.fail(format!("frame track {track} is closed"));
+    }
+    RTC_OK
+}
+
+/// Removes a track created with [`rtc_engine_add_frame_track`]. `*out_sdp`
+/// receives the re-offer to send.
+///
+/// # Safety
+///
+/// `engine` must be a live handle; `out_sdp` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_remove_track(
+    engine: *mut RtcEngine,
+    track: u32,
+    out_sdp: *mut *mut c_char,
+) -> i32 {
+    // SAFETY: live handle per the caller contract.
+    let Some(h) = (unsafe { engine.as_mut() }) else {
+        return RTC_ERR_NULL;
+    };
+    h.frame_tracks.remove(&track);
+    match h.engine.remove_track(track) {
+        Ok(sdp) => {
+            // SAFETY: forwarded caller contract.
+            unsafe { write_sdp(out_sdp, sdp) };
+            RTC_OK
+        }
+        Err(e) => h.fail(e.to_string()),
+    }
+}
+
+/// Message of the last failed call on `engine`, or null. The string is owned
+/// by the engine and valid until the next call on it.
+///
+/// # Safety
+///
+/// `engine` must be null or a live handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_engine_last_error(engine: *const RtcEngine) -> *const c_char {
+    // SAFETY: null or live handle per the caller contract.
+    unsafe { engine.as_ref() }
+        .and_then(|h| h.last_error.as_ref())
+        .map_or(ptr::null(), |e| e.as_ptr())
+}
+
+/// Frees a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library, freed once.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rtc_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: allocated by `CString::into_raw` in this module.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn null_handles_are_rejected() {
+        let mut out = RtcEvent {
+            kind: RtcEventKind::Other,
+            text: ptr::null_mut(),
+        };
+        unsafe {
+            assert_eq!(
+                rtc_engine_negotiate(ptr::null_mut(), ptr::null_mut()),
+                RTC_ERR_NULL
+            );
+            assert_eq!(
+                rtc_engine_poll_event(ptr::null_mut(), &mut out),
+                RTC_ERR_NULL
+            );
+            assert!(rtc_engine_last_error(ptr::null()).is_null());
+            rtc_engine_destroy(ptr::null_mut());
+            rtc_string_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn events_convert_to_owned_c_strings() {
+        let ev = to_c_event(&EngineEvent::Status("hello\0world".into()));
+        assert_eq!(ev.kind, RtcEventKind::Status);
+        let text = unsafe { CStr::from_ptr(ev.text) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_eq!(text, "helloworld");
+        unsafe { rtc_string_free(ev.text) };
+    }
+}
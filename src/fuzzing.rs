@@ -0,0 +1,143 @@
+//! Entry points of the fuzz targets in `fuzz/`.
+//!
+//! Each one feeds arbitrary bytes to the parsers of one wire format, the way
+//! they arrive from the network: a parser may reject the input, but must not
+//! panic, loop forever or allocate more than a length field that the input
+//! cannot back up. Run one with `cargo +nightly fuzz run rtcp` from the
+//! repository root (see `fuzz/README.md`).
+
+use crate::{
+    ice::stun::{self, StunMessage},
+    rtcp::RtcpPacket,
+    rtp::rtp_packet::RtpPacket,
+    sctp::{debug_utils::parse_sctp_packet_summary, protocol::SctpProtocolMessage},
+    sdp::sdpc::Sdp,
+    signaling::protocol::{MAX_BODY_LEN, decode_msg, read_frame},
+};
+
+/// A stream of signaling frames, as read from the TCP/TLS connection.
+pub fn signaling_frames(data: &[u8]) {
+    let mut stream = data;
+    while let Ok((msg_type, body)) = read_frame(&mut stream, MAX_BODY_LEN) {
+        let _ = decode_msg(msg_type, &body);
+    }
+}
+
+/// A session description; non-UTF-8 input is rejected before the parser,
+/// as by the signaling client.
+pub fn sdp(data: &[u8]) {
+    if let Ok(text) = std::str::from_utf8(data)
+        && let Ok(sdp) = Sdp::parse(text)
+    {
+        let _ = sdp.encode();
+    }
+}
+
+/// One RTP packet, through both decoders.
+pub fn rtp(data: &[u8]) {
+    let _ = RtpPacket::decode(data);
+    let _ = RtpPacket::decode_owned(data.to_vec());
+}
+
+/// A compound RTCP packet.
+pub fn rtcp(data: &[u8]) {
+    if let Ok(packets) = RtcpPacket::decode_compound(data) {
+        let _ = RtcpPacket::encode_compound(&packets);
+    }
+}
+
+/// A STUN message, decoded and checked for integrity.
+pub fn stun(data: &[u8]) {
+    let _ = StunMessage::decode(data);
+    let _ = stun::verify_integrity(data, b"fuzz");
+}
+
+/// An SCTP packet's chunks, and a file transfer message carried in them.
+pub fn sctp(data: &[u8]) {
+    let _ = parse_sctp_packet_summary(data);
+    let _ = SctpProtocolMessage::deserialize(data);
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::signaling::protocol::{SignalingMsg, candidate_item::CandidateItem, write_msg};
+
+    /// Valid messages of every format, to mutate.
+    fn seeds() -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let candidates = SignalingMsg::Candidates {
+            from: "alice".into(),
+            to: "bob".into(),
+            items: vec![CandidateItem {
+                mid: "0".into(),
+                mline_index: 0,
+                cand: b"candidate:1 1 udp 2130706431 10.0.0.1 5000 typ host".to_vec(),
+            }],
+            end_of_candidates: true,
+        };
+        write_msg(&mut frames, &candidates).unwrap();
+        write_msg(&mut frames, &SignalingMsg::Ping { nonce: 7 }).unwrap();
+
+        let sdp = b"v=0\r\no=- 1 1 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n\
+                    m=video 9 UDP/TLS/RTP/SAVPF 96\r\nc=IN IP4 0.0.0.0\r\n\
+                    a=rtpmap:96 H264/90000\r\n";
+        // V=2 P X CC=1, an extension of one word, two bytes of padding
+        let rtp = [
+            0xB1, 0x60, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0xBE, 0xDE, 0, 1, 1, 2, 3, 4, 9,
+            2,
+        ];
+        // RR with one report block, then a PLI
+        let mut rtcp = vec![0x81, 201, 0, 7, 0, 0, 0, 1];
+        rtcp.extend([0u8; 24]);
+        rtcp.extend([0x81, 206, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+        let stun = StunMessage::binding_request().encode(b"fuzz");
+        // Common header, a DATA chunk carrying a file transfer message
+        let mut sctp = vec![0u8; 12];
+        let transfer = SctpProtocolMessage::Chunk {
+            id: 1,
+            seq: 2,
+            payload: vec![3; 5],
+        }
+        .serialize()
+        .unwrap();
+        let chunk_len = 16 + transfer.len() as u16;
+        sctp.extend([0, 3]); // DATA, first and last fragment
+        sctp.extend(chunk_len.to_be_bytes());
+        sctp.extend([0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 51]); // TSN, stream, SSN, PPID
+        sctp.extend(&transfer);
+
+        vec![
+            frames,
+            sdp.to_vec(),
+            rtp.to_vec(),
+            rtcp,
+            stun,
+            sctp,
+            transfer,
+        ]
+    }
+
+    /// Every prefix of every seed, and every seed with one byte zeroed or
+    /// maxed out, through every parser: the cases where length fields lie.
+    #[test]
+    fn truncated_and_corrupted_inputs_do_not_panic() {
+        let targets: [fn(&[u8]); 6] = [signaling_frames, sdp, rtp, rtcp, stun, sctp];
+        for seed in seeds() {
+            for target in targets {
+                target(&seed);
+                for len in 0..seed.len() {
+                    target(&seed[..len]);
+                }
+                for i in 0..seed.len() {
+                    for byte in [0x00, 0xFF] {
+                        let mut corrupt = seed.clone();
+                        corrupt[i] = byte;
+                        target(&corrupt);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,19 @@
 use std::net::Ipv4Addr;
 use std::{
+    collections::HashMap,
     net::{IpAddr, SocketAddr, UdpSocket},
     sync::Arc,
 };
 
-use crate::ice::type_ice::candidate::Candidate;
+use crate::{
+    ice::{
+        interface_preference::{InterfaceKind, InterfacePreference},
+        local_interfaces::{InterfaceFilter, LocalInterface, local_interfaces},
+        type_ice::candidate::Candidate,
+    },
+    log::log_sink::LogSink,
+    sink_warn,
+};
 
 const ERROR_MSG: &str = "ERROR";
 const WHITESPACE: &str = " ";
@@ -26,43 +35,59 @@ const TRANSPORT_UDP: &str = "udp"; // lowercase is safer across stacks
 
 /// Gathers local host ICE candidates.
 ///
-/// This function discovers the primary local IPv4 address and creates a host
-/// candidate bound to that interface. It also attempts to gather a loopback
-/// candidate for same-host demos.
+/// This function creates a host candidate bound to each IPv4 interface of
+/// the host that `filter` lets through, falling back to the primary local
+/// address where the interfaces can't be listed. It also attempts to gather
+/// a loopback candidate for same-host demos. Each gets its local preference
+/// from `preference`, by the kind of interface it is on, so no two share a
+/// priority. Interfaces that fail are reported to `logger` and skipped.
 ///
 /// # Returns
 ///
 /// A `Vec<Candidate>` containing the gathered host candidates.
-pub fn gather_host_candidates() -> Vec<Candidate> {
+pub fn gather_host_candidates(
+    preference: &InterfacePreference,
+    filter: &InterfaceFilter,
+    logger: &Arc<dyn LogSink>,
+) -> Vec<Candidate> {
     let mut out = Vec::new();
 
-    // Discover primary local IPv4 via a TEMP socket
-    let local_ip = match discover_local_ipv4() {
-        Ok(ip) => ip,
-        Err(e) => {
-            eprintln!("{e}");
-            return out;
-        }
-    };
-
-    // Fresh, unconnected socket bound to that interface
-    match create_main_socket(local_ip) {
-        Ok((addr, sock)) => {
-            out.push(Candidate::host(
-                addr,
-                TRANSPORT_UDP,
-                DEFAULT_COMPONENT_ID,
-                Some(Arc::new(sock)),
-            ));
+    let mut interfaces = local_interfaces();
+    if interfaces.is_empty() {
+        // Discover primary local IPv4 via a TEMP socket
+        match discover_local_ipv4() {
+            Ok(ip) => interfaces.push(LocalInterface {
+                name: String::new(),
+                ip,
+                kind: InterfaceKind::detect(ip),
+            }),
+            Err(e) => sink_warn!(logger, "[ICE] no local address found: {e}"),
         }
-        Err(e) => {
-            eprintln!("{e}");
-            return out;
+    }
+
+    // Addresses of the same kind are told apart by their index
+    let mut per_kind: HashMap<InterfaceKind, u16> = HashMap::new();
+    for interface in interfaces.iter().filter(|i| !filter.excludes(i)) {
+        // Fresh, unconnected socket bound to that interface
+        match create_main_socket(interface.ip) {
+            Ok((addr, sock)) => {
+                let index = per_kind.entry(interface.kind).or_default();
+                out.push(Candidate::host(
+                    addr,
+                    TRANSPORT_UDP,
+                    DEFAULT_COMPONENT_ID,
+                    preference.local_preference(interface.kind, *index),
+                    Some(Arc::new(sock)),
+                ));
+                *index += 1;
+            }
+            Err(e) => sink_warn!(logger, "[ICE] no candidate on {}: {e}", interface.ip),
         }
     }
 
     //(Opcional) add loopback
-    if let Some(loopback_candidate) = gather_loopback_candidate() {
+    let loopback_pref = preference.local_preference(InterfaceKind::Loopback, 0);
+    if let Some(loopback_candidate) = gather_loopback_candidate(loopback_pref) {
         out.push(loopback_candidate);
     }
 
@@ -115,13 +140,14 @@ fn create_main_socket(local_ip: IpAddr) -> Result<(SocketAddr, UdpSocket), Strin
     Ok((addr, sock))
 }
 
-/// Gathers a loopback candidate for same-host testing.
+/// Gathers a loopback candidate for same-host testing, with local
+/// preference `local_pref`.
 ///
 /// # Returns
 ///
 /// An `Option<Candidate>` which is `Some` if a loopback candidate could be
 /// successfully created and bound, `None` otherwise.
-fn gather_loopback_candidate() -> Option<Candidate> {
+fn gather_loopback_candidate(local_pref: u16) -> Option<Candidate> {
     UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
         .map_err(|_| {
             eprintln!("{}", error_message(BINDING_SOCKET_LOOPBACK_ERROR));
@@ -137,6 +163,7 @@ fn gather_loopback_candidate() -> Option<Candidate> {
                         loop_addr,
                         TRANSPORT_UDP,
                         DEFAULT_COMPONENT_ID,
+                        local_pref,
                         Some(Arc::new(loop_sock)),
                     )
                 })
@@ -148,12 +175,22 @@ fn gather_loopback_candidate() -> Option<Candidate> {
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used)]
     use super::*;
+    use crate::log::NoopLogSink;
 
     #[test]
     fn test_gather_host_return_candidates() {
         const EXPECTED_ERROR_MSG: &str = "Not found local candidates";
-        let candidates = gather_host_candidates();
+        let logger: Arc<dyn LogSink> = Arc::new(NoopLogSink);
+        let candidates = gather_host_candidates(
+            &InterfacePreference::default(),
+            &InterfaceFilter::parse(""),
+            &logger,
+        );
         assert!(!candidates.is_empty(), "{EXPECTED_ERROR_MSG}");
+        // RFC 8445 §5.1.2.1: no two candidates of a host share a priority
+        for (i, a) in candidates.iter().enumerate() {
+            assert!(candidates[i + 1..].iter().all(|b| b.priority != a.priority));
+        }
     }
 
     #[test]
@@ -166,7 +203,7 @@ mod tests {
     #[test]
     fn test_gather_loopback_candidate_ok() {
         const EXPECTED_ERROR_MSG: &str = "Should return a valid loopback candidate";
-        let cand = gather_loopback_candidate();
+        let cand = gather_loopback_candidate(0);
         assert!(cand.is_some(), "{EXPECTED_ERROR_MSG}");
     }
 }
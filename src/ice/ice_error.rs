@@ -0,0 +1,57 @@
+use std::{fmt, io, net::SocketAddr};
+
+use crate::ice::type_ice::candidate_pair::CandidatePairState;
+
+/// Errors of the ICE agent once checks are done: using the nominated pair,
+/// or asking a STUN server for a reflexive address.
+#[derive(Debug)]
+pub enum IceError {
+    /// No pair has been nominated yet.
+    NoNominatedPair,
+    /// The nominated pair did not pass its checks.
+    PairNotSucceeded(CandidatePairState),
+    /// The nominated pair is not among the checked pairs.
+    PairNotFound,
+    /// The local candidate of the nominated pair has no socket.
+    NoSocket(SocketAddr),
+    Io(io::Error),
+    /// The peer answered something else than expected.
+    UnexpectedReply(String),
+    /// The STUN server name does not resolve.
+    StunServer(String),
+    /// The STUN response is too short or has no XOR-MAPPED-ADDRESS.
+    StunResponse(&'static str),
+}
+
+impl fmt::Display for IceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoNominatedPair => write!(f, "no nominated pair available"),
+            Self::PairNotSucceeded(state) => {
+                write!(
+                    f,
+                    "nominated pair not in Succeeded state (current: {state:?})"
+                )
+            }
+            Self::PairNotFound => write!(f, "nominated pair not found in candidate pairs"),
+            Self::NoSocket(addr) => {
+                write!(
+                    f,
+                    "nominated local candidate {addr} has no associated socket"
+                )
+            }
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::UnexpectedReply(msg) => write!(f, "unexpected reply: {msg}"),
+            Self::StunServer(server) => write!(f, "cannot resolve STUN server: {server}"),
+            Self::StunResponse(why) => write!(f, "invalid STUN response: {why}"),
+        }
+    }
+}
+
+impl std::error::Error for IceError {}
+
+impl From<io::Error> for IceError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -0,0 +1,284 @@
+//! Local preference of host candidates by the kind of interface they are on.
+//!
+//! RFC 8445 §5.1.2.1 leaves the local preference to the agent, as long as a
+//! multihomed host gives each of its addresses a different one.
+//! `[ICE] interface_preference` ranks the interface kinds, best first; the
+//! default prefers a cable over Wi-Fi and both over a VPN. The loopback
+//! always comes last.
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr},
+    str::FromStr,
+};
+
+use crate::{config::Config, ice::type_ice::candidate::MAX_LOCAL_PREF};
+
+/// Gap between the local preferences of two ranks; addresses of the same
+/// kind are told apart inside it.
+const RANK_STEP: u16 = 8192;
+const DEFAULT_ORDER: [InterfaceKind; 5] = [
+    InterfaceKind::Wired,
+    InterfaceKind::Wifi,
+    InterfaceKind::Unknown,
+    InterfaceKind::Vpn,
+    InterfaceKind::Virtual,
+];
+/// Where Linux lists its routes.
+pub(crate) const ROUTE_TABLE: &str = "/proc/net/route";
+
+/// What kind of link an interface is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InterfaceKind {
+    Wired,
+    Wifi,
+    Vpn,
+    /// A bridge or veth of containers or virtual machines (`docker0`,
+    /// `virbr0`, ...), only reachable from this host.
+    Virtual,
+    /// Could not be told.
+    Unknown,
+    Loopback,
+}
+
+impl InterfaceKind {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Wired => "wired",
+            Self::Wifi => "wifi",
+            Self::Vpn => "vpn",
+            Self::Virtual => "virtual",
+            Self::Unknown => "unknown",
+            Self::Loopback => "loopback",
+        }
+    }
+
+    /// Whether this is a real network link (wired, Wi-Fi, or one that
+    /// could not be told apart).
+    #[must_use]
+    pub const fn is_physical(self) -> bool {
+        matches!(self, Self::Wired | Self::Wifi | Self::Unknown)
+    }
+
+    /// Guesses the kind from an interface name (`eth0`, `enp3s0`, `wlan0`,
+    /// `wg0`, `tun0`, `docker0`, ...).
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        let starts = |prefixes: &[&str]| prefixes.iter().any(|p| name.starts_with(p));
+        if starts(&["lo"]) {
+            Self::Loopback
+        } else if starts(&[
+            "docker", "veth", "br-", "virbr", "vboxnet", "vmnet", "cni", "flannel", "podman",
+            "lxc", "lxd",
+        ]) {
+            Self::Virtual
+        } else if starts(&["wl", "wifi", "ath", "ra"]) {
+            Self::Wifi
+        } else if starts(&[
+            "tun",
+            "tap",
+            "wg",
+            "ppp",
+            "utun",
+            "ipsec",
+            "tailscale",
+            "zt",
+        ]) {
+            Self::Vpn
+        } else if starts(&["eth", "en", "em"]) {
+            Self::Wired
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// The kind of interface `name`, holding `ip`. The loopback and the
+    /// shared address space that overlay VPNs use (100.64.0.0/10) are told
+    /// by address, anything else by name.
+    #[must_use]
+    pub fn classify(name: &str, ip: IpAddr) -> Self {
+        if ip.is_loopback() {
+            return Self::Loopback;
+        }
+        if let IpAddr::V4(v4) = ip
+            && is_shared_address(v4)
+        {
+            return Self::Vpn;
+        }
+        Self::from_name(name)
+    }
+
+    /// The kind of the interface `ip` is on, when only the address is
+    /// known: it is taken to be on the interface of the default route, as
+    /// the primary address is (Linux only).
+    #[must_use]
+    pub fn detect(ip: IpAddr) -> Self {
+        let name = std::fs::read_to_string(ROUTE_TABLE)
+            .ok()
+            .and_then(|table| default_route_interface(&table))
+            .unwrap_or_default();
+        Self::classify(&name, ip)
+    }
+}
+
+impl fmt::Display for InterfaceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for InterfaceKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "wired" | "ethernet" => Ok(Self::Wired),
+            "wifi" | "wi-fi" | "wireless" => Ok(Self::Wifi),
+            "vpn" => Ok(Self::Vpn),
+            "virtual" => Ok(Self::Virtual),
+            "unknown" | "other" => Ok(Self::Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// 100.64.0.0/10 (RFC 6598).
+fn is_shared_address(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    a == 100 && (64..128).contains(&b)
+}
+
+/// The interface of the default route with the lowest metric in a
+/// `/proc/net/route` table.
+fn default_route_interface(table: &str) -> Option<String> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+            let (iface, destination, metric, mask) = (
+                fields.first()?,
+                fields.get(1)?,
+                fields.get(6)?,
+                fields.get(7)?,
+            );
+            (*destination == "00000000" && *mask == "00000000").then(|| {
+                (
+                    metric.parse::<u32>().unwrap_or(u32::MAX),
+                    (*iface).to_owned(),
+                )
+            })
+        })
+        .min()
+        .map(|(_, iface)| iface)
+}
+
+/// The interface kinds, best first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfacePreference {
+    order: Vec<InterfaceKind>,
+}
+
+impl Default for InterfacePreference {
+    fn default() -> Self {
+        Self {
+            order: DEFAULT_ORDER.to_vec(),
+        }
+    }
+}
+
+impl InterfacePreference {
+    /// `[ICE] interface_preference`, a comma-separated list of `wired`,
+    /// `wifi`, `vpn`, `virtual` and `unknown`. Unrecognized entries are skipped; kinds
+    /// left out rank after the listed ones, in the default order.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let Some(list) = config.get_non_empty("ICE", "interface_preference") else {
+            return Self::default();
+        };
+        let mut order: Vec<InterfaceKind> = Vec::new();
+        for kind in list.split(',').filter_map(|s| s.parse().ok()) {
+            if !order.contains(&kind) {
+                order.push(kind);
+            }
+        }
+        for kind in DEFAULT_ORDER {
+            if !order.contains(&kind) {
+                order.push(kind);
+            }
+        }
+        Self { order }
+    }
+
+    /// Local preference of the `index`-th address of `kind` (counting from
+    /// 0), unique as long as each address of a kind has its own index.
+    #[must_use]
+    pub fn local_preference(&self, kind: InterfaceKind, index: u16) -> u16 {
+        let rank = self
+            .order
+            .iter()
+            .position(|k| *k == kind)
+            .unwrap_or(DEFAULT_ORDER.len());
+        let rank = u16::try_from(rank).unwrap_or(u16::MAX);
+        MAX_LOCAL_PREF
+            .saturating_sub(rank.saturating_mul(RANK_STEP))
+            .saturating_sub(index.min(RANK_STEP - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn kinds_rank_in_the_configured_order() {
+        let default = InterfacePreference::default();
+        let wired = default.local_preference(InterfaceKind::Wired, 0);
+        let wifi = default.local_preference(InterfaceKind::Wifi, 0);
+        let vpn = default.local_preference(InterfaceKind::Vpn, 0);
+        let lo = default.local_preference(InterfaceKind::Loopback, 0);
+        assert_eq!(wired, MAX_LOCAL_PREF);
+        assert!(wired > wifi && wifi > vpn && vpn > lo);
+        // A second Wi-Fi address gets its own preference
+        assert!(default.local_preference(InterfaceKind::Wifi, 1) < wifi);
+
+        let mut config = Config::empty();
+        config.set("ICE", "interface_preference", "vpn, bogus, wifi");
+        let custom = InterfacePreference::from_config(&config);
+        assert_eq!(
+            custom.local_preference(InterfaceKind::Vpn, 0),
+            MAX_LOCAL_PREF
+        );
+        assert!(
+            custom.local_preference(InterfaceKind::Wifi, 0)
+                > custom.local_preference(InterfaceKind::Wired, 0)
+        );
+    }
+
+    #[test]
+    fn interfaces_are_told_by_name_and_address() {
+        assert_eq!(InterfaceKind::from_name("enp3s0"), InterfaceKind::Wired);
+        assert_eq!(InterfaceKind::from_name("wlp2s0"), InterfaceKind::Wifi);
+        assert_eq!(InterfaceKind::from_name("wg0"), InterfaceKind::Vpn);
+        assert_eq!(InterfaceKind::from_name("docker0"), InterfaceKind::Virtual);
+        assert_eq!(InterfaceKind::from_name("bond0"), InterfaceKind::Unknown);
+        assert_eq!(
+            InterfaceKind::detect("127.0.0.1".parse().unwrap()),
+            InterfaceKind::Loopback
+        );
+        assert_eq!(
+            InterfaceKind::detect("100.101.102.103".parse().unwrap()),
+            InterfaceKind::Vpn
+        );
+
+        let table = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+                     wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n\
+                     eth0\t00000000\t0100000A\t0003\t0\t0\t100\t00000000\n\
+                     eth0\t0000000A\t00000000\t0001\t0\t0\t100\t000000FF\n";
+        assert_eq!(default_route_interface(table), Some("eth0".to_owned()));
+    }
+}
@@ -0,0 +1,208 @@
+//! The IPv4 interfaces of this host and which of them host candidates are
+//! gathered on.
+//!
+//! Container bridges, VPN tunnels and the like add addresses the peer can
+//! rarely reach, and every extra candidate multiplies the pairs to check.
+//! `[ICE] exclude_interfaces` lists what is left out, by kind (`virtual`,
+//! `vpn`, ...) or by interface name (`docker0`, or `tun*` for a prefix);
+//! by default the virtual ones.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+use crate::{
+    config::Config,
+    ice::interface_preference::{InterfaceKind, ROUTE_TABLE},
+};
+
+const DEFAULT_EXCLUDE: &str = "virtual";
+/// Port the route probes "connect" to; nothing is ever sent.
+const PROBE_PORT: u16 = 9;
+
+/// An IPv4 address of this host and the interface it is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalInterface {
+    pub name: String,
+    pub ip: IpAddr,
+    pub kind: InterfaceKind,
+}
+
+/// The non-loopback IPv4 addresses of this host, one per interface with a
+/// subnet route. Each address is the one the kernel picks as the source
+/// for its subnet, found by connecting a UDP socket towards it (which sends
+/// nothing), so no platform interface API is needed. Empty where the route
+/// table can't be read (other than Linux).
+#[must_use]
+pub fn local_interfaces() -> Vec<LocalInterface> {
+    let Ok(table) = std::fs::read_to_string(ROUTE_TABLE) else {
+        return Vec::new();
+    };
+    let mut out: Vec<LocalInterface> = Vec::new();
+    for (name, probe) in subnet_routes(&table) {
+        let Some(ip) = source_address(probe) else {
+            continue;
+        };
+        if ip.is_loopback() || out.iter().any(|i| i.ip == ip) {
+            continue;
+        }
+        let kind = InterfaceKind::classify(&name, ip);
+        out.push(LocalInterface { name, ip, kind });
+    }
+    out
+}
+
+/// The interface and an address inside its subnet for every on-link
+/// (gatewayless) route of a `/proc/net/route` table.
+fn subnet_routes(table: &str) -> Vec<(String, Ipv4Addr)> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            // Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+            let (iface, destination, gateway, mask) = (
+                fields.first()?,
+                fields.get(1)?,
+                fields.get(2)?,
+                fields.get(7)?,
+            );
+            if *gateway != "00000000" || *mask == "00000000" {
+                return None;
+            }
+            let network = route_address(destination)?;
+            let mask = route_address(mask)?;
+            // The first host of the subnet; a /31 or /32 has no room for it
+            let probe = if mask.to_bits().leading_ones() < 31 {
+                Ipv4Addr::from_bits(network.to_bits() | 1)
+            } else {
+                network
+            };
+            Some(((*iface).to_owned(), probe))
+        })
+        .collect()
+}
+
+/// An address as the route table prints it: the in-memory bytes of a
+/// network-order `u32`, read in host order.
+fn route_address(hex: &str) -> Option<Ipv4Addr> {
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .map(|v| Ipv4Addr::from(v.to_ne_bytes()))
+}
+
+/// The address the kernel would send from towards `target`.
+fn source_address(target: Ipv4Addr) -> Option<IpAddr> {
+    let probe = UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)).ok()?;
+    probe.connect((target, PROBE_PORT)).ok()?;
+    let ip = probe.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+/// What `[ICE] exclude_interfaces` leaves out of gathering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceFilter {
+    kinds: Vec<InterfaceKind>,
+    /// Interface names; a trailing `*` matches any name starting so.
+    names: Vec<String>,
+}
+
+impl Default for InterfaceFilter {
+    fn default() -> Self {
+        Self::parse(DEFAULT_EXCLUDE)
+    }
+}
+
+impl InterfaceFilter {
+    /// `[ICE] exclude_interfaces`, or the virtual interfaces if unset. An
+    /// empty value excludes nothing.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        config
+            .get("ICE", "exclude_interfaces")
+            .map_or_else(Self::default, Self::parse)
+    }
+
+    /// A comma-separated list of interface kinds and names.
+    #[must_use]
+    pub fn parse(list: &str) -> Self {
+        let mut filter = Self {
+            kinds: Vec::new(),
+            names: Vec::new(),
+        };
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.parse::<InterfaceKind>() {
+                Ok(kind) => filter.kinds.push(kind),
+                Err(()) => filter.names.push(entry.to_owned()),
+            }
+        }
+        filter
+    }
+
+    /// Whether no candidate is gathered on `interface`.
+    #[must_use]
+    pub fn excludes(&self, interface: &LocalInterface) -> bool {
+        self.kinds.contains(&interface.kind)
+            || self.names.iter().any(|name| match name.strip_suffix('*') {
+                Some(prefix) => interface.name.starts_with(prefix),
+                None => interface.name == *name,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn interface(name: &str, ip: &str) -> LocalInterface {
+        let ip = ip.parse().unwrap();
+        LocalInterface {
+            name: name.to_owned(),
+            ip,
+            kind: InterfaceKind::classify(name, ip),
+        }
+    }
+
+    #[test]
+    fn virtual_interfaces_are_excluded_by_default() {
+        let eth = interface("eth0", "192.168.1.20");
+        let docker = interface("docker0", "172.17.0.1");
+        let tun = interface("tun0", "10.8.0.2");
+
+        let default = InterfaceFilter::default();
+        assert!(!default.excludes(&eth));
+        assert!(default.excludes(&docker));
+        assert!(!default.excludes(&tun));
+
+        let mut config = Config::empty();
+        config.set("ICE", "exclude_interfaces", "vpn, eth*");
+        let custom = InterfaceFilter::from_config(&config);
+        assert!(custom.excludes(&eth) && custom.excludes(&tun));
+        assert!(!custom.excludes(&docker));
+
+        config.set("ICE", "exclude_interfaces", "");
+        assert!(!InterfaceFilter::from_config(&config).excludes(&docker));
+    }
+
+    #[test]
+    fn subnet_routes_are_probed_inside_the_subnet() {
+        let octets = |ip: [u8; 4]| format!("{:08X}", u32::from_ne_bytes(ip));
+        let table = format!(
+            "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+             eth0\t00000000\t{gw}\t0003\t0\t0\t100\t00000000\n\
+             eth0\t{lan}\t00000000\t0001\t0\t0\t100\t{lan_mask}\n\
+             tun0\t{tun}\t00000000\t0005\t0\t0\t0\t{host_mask}\n",
+            gw = octets([192, 168, 1, 1]),
+            lan = octets([192, 168, 1, 0]),
+            lan_mask = octets([255, 255, 255, 0]),
+            tun = octets([10, 8, 0, 1]),
+            host_mask = octets([255, 255, 255, 255]),
+        );
+        assert_eq!(
+            subnet_routes(&table),
+            vec![
+                ("eth0".to_owned(), Ipv4Addr::new(192, 168, 1, 1)),
+                ("tun0".to_owned(), Ipv4Addr::new(10, 8, 0, 1)),
+            ]
+        );
+    }
+}
@@ -1,2 +1,6 @@
 pub mod gathering_service;
+pub mod ice_error;
+pub mod interface_preference;
+pub mod local_interfaces;
+pub mod stun;
 pub mod type_ice;
@@ -0,0 +1,401 @@
+//! STUN messages for the ICE connectivity checks (RFC 5389, RFC 8445 §7).
+//!
+//! Only what the checks need: Binding requests and success responses with
+//! USERNAME, PRIORITY, ICE-CONTROLLING / ICE-CONTROLLED, USE-CANDIDATE and
+//! XOR-MAPPED-ADDRESS. Checks are authenticated with MESSAGE-INTEGRITY, keyed
+//! with the ICE password of the agent that answers, and end with FINGERPRINT.
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use flate2::Crc;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+type HmacSha1 = Hmac<Sha1>;
+
+pub const MAGIC_COOKIE: u32 = 0x2112_A442;
+const HEADER_LEN: usize = 20;
+/// Every attribute starts with a 2-byte type and a 2-byte length.
+const ATTR_HEADER_LEN: usize = 4;
+const INTEGRITY_LEN: usize = 20;
+const FINGERPRINT_XOR: u32 = 0x5354_554E;
+
+pub const BINDING_REQUEST: u16 = 0x0001;
+pub const BINDING_SUCCESS: u16 = 0x0101;
+
+const ATTR_USERNAME: u16 = 0x0006;
+const ATTR_MESSAGE_INTEGRITY: u16 = 0x0008;
+const ATTR_XOR_MAPPED_ADDRESS: u16 = 0x0020;
+const ATTR_PRIORITY: u16 = 0x0024;
+const ATTR_USE_CANDIDATE: u16 = 0x0025;
+const ATTR_FINGERPRINT: u16 = 0x8028;
+const ATTR_ICE_CONTROLLED: u16 = 0x8029;
+const ATTR_ICE_CONTROLLING: u16 = 0x802A;
+
+const FAMILY_IPV4: u8 = 0x01;
+const FAMILY_IPV6: u8 = 0x02;
+
+/// `true` if `packet` looks like a STUN message: the first two bits are
+/// zero (RFC 7983 demultiplexing), the magic cookie is in place and the
+/// length matches.
+#[must_use]
+pub fn is_stun(packet: &[u8]) -> bool {
+    packet.len() >= HEADER_LEN
+        && packet[0] < 4
+        && packet[4..8] == MAGIC_COOKIE.to_be_bytes()
+        && usize::from(u16::from_be_bytes([packet[2], packet[3]])) + HEADER_LEN == packet.len()
+}
+
+/// Why a packet was not accepted as a STUN message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StunError {
+    /// Not a STUN message, or a truncated one.
+    Malformed,
+    /// The FINGERPRINT does not match the message.
+    BadFingerprint,
+}
+
+impl fmt::Display for StunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed STUN message"),
+            Self::BadFingerprint => write!(f, "STUN FINGERPRINT mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for StunError {}
+
+/// A decoded Binding request or response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StunMessage {
+    /// Message class and method, e.g. [`BINDING_REQUEST`].
+    pub msg_type: u16,
+    pub transaction_id: [u8; 12],
+    /// `<receiver ufrag>:<sender ufrag>` on requests.
+    pub username: Option<String>,
+    /// Priority of the peer-reflexive candidate the check would create.
+    pub priority: Option<u32>,
+    /// Set by the controlling agent to nominate the pair.
+    pub use_candidate: bool,
+    /// Tie-breaker of a sender in the controlling role.
+    pub ice_controlling: Option<u64>,
+    /// Tie-breaker of a sender in the controlled role.
+    pub ice_controlled: Option<u64>,
+    /// Source address of the request, as seen by the responder.
+    pub xor_mapped_address: Option<SocketAddr>,
+}
+
+impl StunMessage {
+    /// A Binding request with a fresh transaction ID.
+    #[must_use]
+    pub fn binding_request() -> Self {
+        Self::new(BINDING_REQUEST, rand::random())
+    }
+
+    /// The success response to `request`, telling the sender it was seen
+    /// at `from`.
+    #[must_use]
+    pub fn binding_success(request: &Self, from: SocketAddr) -> Self {
+        Self {
+            xor_mapped_address: Some(from),
+            ..Self::new(BINDING_SUCCESS, request.transaction_id)
+        }
+    }
+
+    const fn new(msg_type: u16, transaction_id: [u8; 12]) -> Self {
+        Self {
+            msg_type,
+            transaction_id,
+            username: None,
+            priority: None,
+            use_candidate: false,
+            ice_controlling: None,
+            ice_controlled: None,
+            xor_mapped_address: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_request(&self) -> bool {
+        self.msg_type == BINDING_REQUEST
+    }
+
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        self.msg_type == BINDING_SUCCESS
+    }
+
+    /// Serializes the message, adding MESSAGE-INTEGRITY keyed with `key`
+    /// and a FINGERPRINT.
+    #[must_use]
+    pub fn encode(&self, key: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(128);
+        out.extend_from_slice(&self.msg_type.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes());
+        out.extend_from_slice(&MAGIC_COOKIE.to_be_bytes());
+        out.extend_from_slice(&self.transaction_id);
+
+        if let Some(username) = &self.username {
+            push_attr(&mut out, ATTR_USERNAME, username.as_bytes());
+        }
+        if let Some(priority) = self.priority {
+            push_attr(&mut out, ATTR_PRIORITY, &priority.to_be_bytes());
+        }
+        if self.use_candidate {
+            push_attr(&mut out, ATTR_USE_CANDIDATE, &[]);
+        }
+        if let Some(tie_breaker) = self.ice_controlling {
+            push_attr(&mut out, ATTR_ICE_CONTROLLING, &tie_breaker.to_be_bytes());
+        }
+        if let Some(tie_breaker) = self.ice_controlled {
+            push_attr(&mut out, ATTR_ICE_CONTROLLED, &tie_breaker.to_be_bytes());
+        }
+        if let Some(addr) = self.xor_mapped_address {
+            let value = xor_address(addr, &self.transaction_id);
+            push_attr(&mut out, ATTR_XOR_MAPPED_ADDRESS, &value);
+        }
+
+        // The length covers each trailer attribute as it is computed
+        set_length(&mut out, ATTR_HEADER_LEN + INTEGRITY_LEN);
+        let integrity = hmac_sha1(key, &out);
+        push_attr(&mut out, ATTR_MESSAGE_INTEGRITY, &integrity);
+        set_length(&mut out, ATTR_HEADER_LEN + 4);
+        let fingerprint = crc32(&out) ^ FINGERPRINT_XOR;
+        push_attr(&mut out, ATTR_FINGERPRINT, &fingerprint.to_be_bytes());
+        out
+    }
+
+    /// Parses a Binding message, checking its FINGERPRINT if present.
+    /// Unknown attributes are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StunError`] if `packet` is not a well-formed STUN message
+    /// or its fingerprint does not match.
+    pub fn decode(packet: &[u8]) -> Result<Self, StunError> {
+        if !is_stun(packet) {
+            return Err(StunError::Malformed);
+        }
+        let mut transaction_id = [0u8; 12];
+        transaction_id.copy_from_slice(&packet[8..HEADER_LEN]);
+        let mut msg = Self::new(u16::from_be_bytes([packet[0], packet[1]]), transaction_id);
+
+        for (offset, attr_type, value) in attributes(packet)? {
+            match attr_type {
+                ATTR_USERNAME => {
+                    msg.username =
+                        Some(String::from_utf8(value.to_vec()).map_err(|_| StunError::Malformed)?);
+                }
+                ATTR_PRIORITY => msg.priority = Some(u32::from_be_bytes(array(value)?)),
+                ATTR_USE_CANDIDATE => msg.use_candidate = true,
+                ATTR_ICE_CONTROLLING => {
+                    msg.ice_controlling = Some(u64::from_be_bytes(array(value)?));
+                }
+                ATTR_ICE_CONTROLLED => {
+                    msg.ice_controlled = Some(u64::from_be_bytes(array(value)?));
+                }
+                ATTR_XOR_MAPPED_ADDRESS => {
+                    msg.xor_mapped_address = Some(unxor_address(value, &transaction_id)?);
+                }
+                ATTR_FINGERPRINT => {
+                    let expected = crc32(&packet[..offset]) ^ FINGERPRINT_XOR;
+                    if u32::from_be_bytes(array(value)?) != expected {
+                        return Err(StunError::BadFingerprint);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(msg)
+    }
+}
+
+/// `true` if `packet` carries a MESSAGE-INTEGRITY computed with `key`.
+#[must_use]
+pub fn verify_integrity(packet: &[u8], key: &[u8]) -> bool {
+    let Ok(attrs) = attributes(packet) else {
+        return false;
+    };
+    let Some((offset, _, value)) = attrs
+        .into_iter()
+        .find(|(_, attr_type, _)| *attr_type == ATTR_MESSAGE_INTEGRITY)
+    else {
+        return false;
+    };
+    // Computed as if the message ended right after MESSAGE-INTEGRITY
+    let mut signed = packet[..offset].to_vec();
+    set_length(&mut signed, ATTR_HEADER_LEN + INTEGRITY_LEN);
+    let Ok(mut mac) = HmacSha1::new_from_slice(key) else {
+        return false;
+    };
+    mac.update(&signed);
+    mac.verify_slice(value).is_ok()
+}
+
+/// `(offset, type, value)` of every attribute; `offset` is where the
+/// attribute header starts.
+fn attributes(packet: &[u8]) -> Result<Vec<(usize, u16, &[u8])>, StunError> {
+    let mut attrs = Vec::new();
+    let mut offset = HEADER_LEN;
+    while offset < packet.len() {
+        let header = packet
+            .get(offset..offset + ATTR_HEADER_LEN)
+            .ok_or(StunError::Malformed)?;
+        let attr_type = u16::from_be_bytes([header[0], header[1]]);
+        let len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+        let start = offset + ATTR_HEADER_LEN;
+        let value = packet.get(start..start + len).ok_or(StunError::Malformed)?;
+        attrs.push((offset, attr_type, value));
+        offset = start + len.next_multiple_of(4);
+    }
+    Ok(attrs)
+}
+
+/// Appends an attribute, zero-padded to 4 bytes.
+fn push_attr(out: &mut Vec<u8>, attr_type: u16, value: &[u8]) {
+    out.extend_from_slice(&attr_type.to_be_bytes());
+    out.extend_from_slice(&u16::try_from(value.len()).unwrap_or(u16::MAX).to_be_bytes());
+    out.extend_from_slice(value);
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+/// Sets the header length to the attributes so far plus `extra` bytes.
+fn set_length(out: &mut [u8], extra: usize) {
+    let len = u16::try_from(out.len() - HEADER_LEN + extra).unwrap_or(u16::MAX);
+    out[2..4].copy_from_slice(&len.to_be_bytes());
+}
+
+fn array<const N: usize>(value: &[u8]) -> Result<[u8; N], StunError> {
+    value.try_into().map_err(|_| StunError::Malformed)
+}
+
+fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; INTEGRITY_LEN] {
+    let mut out = [0u8; INTEGRITY_LEN];
+    // HMAC takes keys of any length
+    if let Ok(mut mac) = HmacSha1::new_from_slice(key) {
+        mac.update(data);
+        out.copy_from_slice(&mac.finalize().into_bytes());
+    }
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// XOR-MAPPED-ADDRESS value: the port is XORed with the top of the magic
+/// cookie, an IPv6 address with the cookie and the transaction ID.
+fn xor_address(addr: SocketAddr, transaction_id: &[u8; 12]) -> Vec<u8> {
+    let mask = xor_mask(transaction_id);
+    let port = addr.port() ^ ((MAGIC_COOKIE >> 16) as u16);
+    let (family, ip): (u8, Vec<u8>) = match addr.ip() {
+        IpAddr::V4(ip) => (FAMILY_IPV4, ip.octets().to_vec()),
+        IpAddr::V6(ip) => (FAMILY_IPV6, ip.octets().to_vec()),
+    };
+    let mut value = vec![0, family];
+    value.extend_from_slice(&port.to_be_bytes());
+    value.extend(ip.iter().zip(mask).map(|(b, m)| b ^ m));
+    value
+}
+
+fn unxor_address(value: &[u8], transaction_id: &[u8; 12]) -> Result<SocketAddr, StunError> {
+    let mask = xor_mask(transaction_id);
+    let (&family, rest) = value
+        .get(1..)
+        .and_then(<[u8]>::split_first)
+        .ok_or(StunError::Malformed)?;
+    let port = u16::from_be_bytes(array(rest.get(..2).ok_or(StunError::Malformed)?)?)
+        ^ ((MAGIC_COOKIE >> 16) as u16);
+    let ip: Vec<u8> = rest[2..].iter().zip(mask).map(|(b, m)| b ^ m).collect();
+    let ip = match family {
+        FAMILY_IPV4 => IpAddr::V4(Ipv4Addr::from(array::<4>(&ip)?)),
+        FAMILY_IPV6 => IpAddr::V6(Ipv6Addr::from(array::<16>(&ip)?)),
+        _ => return Err(StunError::Malformed),
+    };
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// Magic cookie followed by the transaction ID.
+fn xor_mask(transaction_id: &[u8; 12]) -> [u8; 16] {
+    let mut mask = [0u8; 16];
+    mask[..4].copy_from_slice(&MAGIC_COOKIE.to_be_bytes());
+    mask[4..].copy_from_slice(transaction_id);
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// RFC 5769 §2.1: sample request with short-term credentials.
+    #[test]
+    fn decodes_the_rfc_5769_sample_request() {
+        let packet = hex(concat!(
+            "000100582112a442b7e7a701bc34d686fa87dfae",
+            "802200105354554e207465737420636c69656e74",
+            "002400046e0001ff",
+            "80290008932ff9b151263b36",
+            "000600096576746a3a68367659202020",
+            "000800149aeaa70cbfd8cb56781ef2b5b2d3f249c1b571a2",
+            "80280004e57a3bcf",
+        ));
+        assert!(is_stun(&packet));
+        let msg = StunMessage::decode(&packet).unwrap();
+        assert!(msg.is_request());
+        assert_eq!(msg.username.as_deref(), Some("evtj:h6vY"));
+        assert_eq!(msg.priority, Some(0x6e00_01ff));
+        assert_eq!(msg.ice_controlled, Some(0x932f_f9b1_5126_3b36));
+        assert!(verify_integrity(&packet, b"VOkJxbRl1RmTxUk/WvJxBt"));
+        assert!(!verify_integrity(&packet, b"wrong password"));
+
+        let mut tampered = packet;
+        tampered[30] ^= 1;
+        assert_eq!(
+            StunMessage::decode(&tampered),
+            Err(StunError::BadFingerprint)
+        );
+    }
+
+    #[test]
+    fn response_round_trips_with_integrity() {
+        let mut request = StunMessage::binding_request();
+        request.username = Some("remote:local".into());
+        request.priority = Some(1_845_501_695);
+        request.use_candidate = true;
+        request.ice_controlling = Some(42);
+        let encoded = request.encode(b"remote-password");
+        assert_eq!(StunMessage::decode(&encoded).unwrap(), request);
+        assert!(verify_integrity(&encoded, b"remote-password"));
+
+        for from in [
+            "192.0.2.1:32853",
+            "[2001:db8:1234:5678:11:2233:4455:6677]:32853",
+        ] {
+            let from: SocketAddr = from.parse().unwrap();
+            let response = StunMessage::binding_success(&request, from);
+            let encoded = response.encode(b"local-password");
+            let decoded = StunMessage::decode(&encoded).unwrap();
+            assert!(decoded.is_success());
+            assert_eq!(decoded.transaction_id, request.transaction_id);
+            assert_eq!(decoded.xor_mapped_address, Some(from));
+            assert!(verify_integrity(&encoded, b"local-password"));
+        }
+
+        assert!(!is_stun(b"BINDING-REQUEST"));
+        assert!(!is_stun(&[0x80; 40]));
+    }
+}
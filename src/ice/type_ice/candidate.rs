@@ -11,8 +11,8 @@ const PEER_REFLEXIVE_TYPE_PREF: u32 = 110;
 const SERVER_REFLEXIVE_TYPE_PREF: u32 = 100;
 const RELAYED_TYPE_PREF: u32 = 0;
 
-/// Maximum local preference (interface-insensitive)
-const MAX_LOCAL_PREF: u16 = u16::MAX; // 65535
+/// Maximum local preference, used when the interface is not ranked
+pub const MAX_LOCAL_PREF: u16 = u16::MAX; // 65535
 
 /// Offsets used in the priority calculation -> RFC 8445 §5.1.2.1
 const TYPE_PREF_SHIFT: u32 = 24;
@@ -87,18 +87,21 @@ impl Candidate {
     }
 
     #[must_use]
-    /// Convenience for host candidates.
+    /// Convenience for host candidates. `local_pref` ranks the interface
+    /// the address is on and must differ between the addresses of a host
+    /// (RFC 8445 §5.1.2.1).
     pub fn host(
         address: SocketAddr,
         transport: &str,
         component: u8,
+        local_pref: u16,
         socket: Option<Arc<UdpSocket>>,
     ) -> Self {
         Self::new(
             String::new(),
             component,
             transport,
-            0, // let ctor compute
+            Self::calculate_priority(&CandidateType::Host, local_pref, component),
             address,
             CandidateType::Host,
             None,
@@ -131,8 +134,10 @@ impl Candidate {
         format!("{:x}", hasher.finish())
     }
 
-    // RFC 8445 §5.1.2.1 — 32-bit candidate priority
-    const fn calculate_priority(
+    /// RFC 8445 §5.1.2.1 — 32-bit candidate priority: type preference,
+    /// then local preference, then component.
+    #[must_use]
+    pub const fn calculate_priority(
         cand_type: &CandidateType,
         local_pref: u16,
         component_id: u8,
@@ -149,6 +154,13 @@ impl Candidate {
             | (COMPONENT_OFFSET - component_id as u32)
     }
 
+    /// Priority this candidate would have as peer reflexive, sent in the
+    /// PRIORITY attribute of its checks (RFC 8445 §7.1.1).
+    #[must_use]
+    pub const fn peer_reflexive_priority(&self) -> u32 {
+        (PEER_REFLEXIVE_TYPE_PREF << TYPE_PREF_SHIFT) | (self.priority & 0x00FF_FFFF)
+    }
+
     #[must_use]
     /// Creates a shallow copy of a Candidate without cloning the underlying socket.
     pub fn clone_light(&self) -> Candidate {
@@ -1,27 +1,33 @@
 use super::candidate::Candidate;
 use super::candidate_pair::CandidatePair;
 use crate::config::Config;
+use crate::ice::stun::{self, StunMessage};
 use crate::ice::type_ice::candidate_type::CandidateType::ServerReflexive;
 use crate::ice::{
-    gathering_service::gather_host_candidates, type_ice::candidate_pair::CandidatePairState,
+    gathering_service::gather_host_candidates,
+    ice_error::IceError,
+    interface_preference::{InterfaceKind, InterfacePreference},
+    local_interfaces::InterfaceFilter,
+    type_ice::candidate_pair::CandidatePairState,
 };
 use crate::log::log_sink::LogSink;
 use crate::{sink_debug, sink_error, sink_info, sink_warn};
 use rand::{Rng, rngs::OsRng};
+use std::collections::HashMap;
 use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
 use std::sync::Arc;
 use std::{io::Error, time::Duration};
 
-const NOMINATION_REQUEST: &[u8] = b"NOMINATE-BINDING-REQUEST";
-
 /// Error message formatting constants
 const ERROR_MSG: &str = "ERROR";
 const WHITESPACE: &str = " ";
 const QUOTE: &str = "\"";
 
-/// Mensajes simulados para los checks
-pub const BINDING_REQUEST: &[u8] = b"BINDING-REQUEST";
-pub const BINDING_RESPONSE: &[u8] = b"BINDING-RESPONSE";
+/// `true` if `packet` is a connectivity check (a STUN message).
+#[must_use]
+pub fn is_check_packet(packet: &[u8]) -> bool {
+    stun::is_stun(packet)
+}
 
 /// Default configuration constants
 const DEFAULT_STUN_SERVER: &str = "stun.l.google.com:19302";
@@ -53,6 +59,10 @@ pub struct IceAgent {
     stun_request_timeout: Duration,
     /// Maximum number of candidate pairs to form.
     max_candidate_pairs: usize,
+    /// Ranks the interfaces local candidates are gathered on.
+    interface_preference: InterfacePreference,
+    /// Interfaces no local candidate is gathered on.
+    interface_filter: InterfaceFilter,
     /// Set of local candidates.
     pub local_candidates: Vec<Candidate>,
     /// Set of remote candidates.
@@ -67,6 +77,19 @@ pub struct IceAgent {
     remote_pwd: String,
     /// The currently nominated candidate pair.
     pub nominated_pair: Option<CandidatePair>,
+    /// Settles role conflicts (RFC 8445 §7.3.1.1).
+    tie_breaker: u64,
+    /// Binding requests waiting for a response, by transaction ID.
+    checks: HashMap<[u8; 12], Check>,
+}
+
+/// A Binding request sent on the pair `local` → `remote`.
+#[derive(Debug, Clone)]
+struct Check {
+    local: SocketAddr,
+    remote: SocketAddr,
+    /// Encoded request, re-sent as is until answered.
+    packet: Vec<u8>,
 }
 
 impl IceAgent {
@@ -100,11 +123,16 @@ impl IceAgent {
             .and_then(|s| s.parse().ok())
             .unwrap_or(DEFAULT_MAX_CANDIDATE_PAIRS);
 
+        let interface_preference = InterfacePreference::from_config(config);
+        let interface_filter = InterfaceFilter::from_config(config);
+
         Self {
             logger,
             stun_server,
             stun_request_timeout: Duration::from_secs(stun_request_timeout_secs),
             max_candidate_pairs,
+            interface_preference,
+            interface_filter,
             local_candidates: vec![],
             remote_candidates: vec![],
             candidate_pairs: vec![],
@@ -114,6 +142,8 @@ impl IceAgent {
             remote_ufrag: String::new(),
             remote_pwd: String::new(),
             nominated_pair: None,
+            tie_breaker: rand::random(),
+            checks: HashMap::new(),
         }
     }
 
@@ -129,37 +159,28 @@ impl IceAgent {
     /// `Ok(())` if the data channel is established successfully.
     ///
     /// # Errors
-    /// Returns an `Err(String)` if:
+    /// Returns an `IceError` if:
     /// - No nominated pair is available.
     /// - Opening or connecting the UDP channel fails.
     /// - Sending the test message fails.
     /// - An unexpected message is received instead of "BINDING-ACK".
-    pub fn start_data_channel(&mut self) -> Result<(), String> {
+    pub fn start_data_channel(&mut self) -> Result<(), IceError> {
         sink_info!(self.logger, "🔹 Starting ICE data channel...");
 
         if self.nominated_pair.is_none() {
-            return Err("Cannot start data channel: no nominated pair available.".into());
+            return Err(IceError::NoNominatedPair);
         }
 
-        let (socket, remote_addr) = self
-            .get_data_channel_socket()
-            .map_err(|e| format!("Failed to open UDP channel: {e}"))?;
-
-        socket
-            .connect(remote_addr)
-            .map_err(|e| format!("Failed to connect UDP channel: {e}"))?;
+        let (socket, remote_addr) = self.get_data_channel_socket()?;
+        socket.connect(remote_addr)?;
+        self.send_test_message(&socket, "hola ICE")?;
 
-        self.send_test_message(&socket, "hola ICE")
-            .map_err(|e| format!("Failed to send test message: {e}"))?;
-
-        match self.receive_test_message(&socket) {
-            Ok(msg) if msg.contains("BINDING-ACK") => {
-                sink_info!(self.logger, "ICE Data Channel established successfully!");
-                Ok(())
-            }
-            Ok(msg) => Err(format!("Unexpected message received instead of ACK: {msg}")),
-            Err(e) => Err(format!("Failed to receive ACK: {e}")),
+        let msg = self.receive_test_message(&socket)?;
+        if !msg.contains("BINDING-ACK") {
+            return Err(IceError::UnexpectedReply(msg));
         }
+        sink_info!(self.logger, "ICE Data Channel established successfully!");
+        Ok(())
     }
 
     /// Sends a test message (e.g., "BINDING-DATA hello ICE") to the remote candidate.
@@ -172,12 +193,10 @@ impl IceAgent {
     /// * `Ok(())` if the message was sent successfully.
     ///
     /// # Errors
-    /// * `Err(String)` if there was no nominated pair or sending to the socket failed.
-    pub fn send_test_message(&self, socket: &UdpSocket, msg: &str) -> Result<(), String> {
+    /// * `IceError` if there was no nominated pair or sending to the socket failed.
+    pub fn send_test_message(&self, socket: &UdpSocket, msg: &str) -> Result<(), IceError> {
         let Some(pair) = &self.nominated_pair else {
-            return Err(String::from(
-                "Cannot send message: no nominated pair available",
-            ));
+            return Err(IceError::NoNominatedPair);
         };
 
         let remote_addr = pair.remote.address;
@@ -194,7 +213,7 @@ impl IceAgent {
                 );
                 Ok(())
             }
-            Err(e) => Err(format!("Failed to send UDP message to {remote_addr}: {e}")),
+            Err(e) => Err(IceError::Io(e)),
         }
     }
 
@@ -207,11 +226,9 @@ impl IceAgent {
     /// * `Ok(String)` - The received message.
     ///
     /// # Errors
-    /// * `Err(String)` - Timeout or read error.
-    pub fn receive_test_message(&self, socket: &UdpSocket) -> Result<String, String> {
-        socket
-            .set_read_timeout(Some(Duration::from_secs(1)))
-            .map_err(|e| format!("Failed to set timeout: {e}"))?;
+    /// * `IceError::Io` - Timeout or read error.
+    pub fn receive_test_message(&self, socket: &UdpSocket) -> Result<String, IceError> {
+        socket.set_read_timeout(Some(Duration::from_secs(1)))?;
 
         let mut buf = [0u8; 512];
         match socket.recv_from(&mut buf) {
@@ -226,7 +243,7 @@ impl IceAgent {
                     "Timeout or error while receiving UDP message: {}",
                     e
                 );
-                Err(format!("Timeout or error while receiving UDP message: {e}"))
+                Err(IceError::Io(e))
             }
         }
     }
@@ -240,21 +257,18 @@ impl IceAgent {
     ///   and the remote peer's address.
     ///
     /// # Errors
-    /// * `Err(String)` — If no nominated pair exists, the pair is not in a 'Succeeded' state,
+    /// * `IceError` — If no nominated pair exists, the pair is not in a 'Succeeded' state,
     ///   the nominated pair cannot be found in the agent's candidate pairs,
     ///   or the local candidate lacks an associated socket.
-    pub fn get_data_channel_socket(&self) -> Result<(Arc<UdpSocket>, SocketAddr), String> {
+    pub fn get_data_channel_socket(&self) -> Result<(Arc<UdpSocket>, SocketAddr), IceError> {
         // 1) Must have a nominated pair recorded
         let np = self
             .nominated_pair
             .as_ref()
-            .ok_or_else(|| "No nominated pair available to get UDP channel socket.".to_string())?;
+            .ok_or(IceError::NoNominatedPair)?;
 
         if !matches!(np.state, CandidatePairState::Succeeded) {
-            return Err(format!(
-                "Cannot get UDP channel socket — pair not in Succeeded state (current: {:?})",
-                np.state
-            ));
+            return Err(IceError::PairNotSucceeded(np.state));
         }
 
         // 2) Find the corresponding full pair (with socket) by addresses
@@ -262,19 +276,14 @@ impl IceAgent {
             .candidate_pairs
             .iter()
             .find(|p| p.local.address == np.local.address && p.remote.address == np.remote.address)
-            .ok_or_else(|| "Nominated pair not found in candidate_pairs.".to_string())?;
+            .ok_or(IceError::PairNotFound)?;
 
         // 3) Extract socket and peer
         let sock = pair
             .local
             .socket
             .as_ref()
-            .ok_or_else(|| {
-                format!(
-                    "Nominated local candidate {} has no associated socket.",
-                    pair.local.address
-                )
-            })?
+            .ok_or(IceError::NoSocket(pair.local.address))?
             .clone();
 
         Ok((sock, pair.remote.address))
@@ -375,6 +384,18 @@ impl IceAgent {
         self.local_candidates.push(candidate);
     }
 
+    /// How the interfaces local candidates are gathered on are ranked.
+    #[must_use]
+    pub const fn interface_preference(&self) -> &InterfacePreference {
+        &self.interface_preference
+    }
+
+    /// Which interfaces no local candidate is gathered on.
+    #[must_use]
+    pub const fn interface_filter(&self) -> &InterfaceFilter {
+        &self.interface_filter
+    }
+
     /// Adds a remote candidate to the agent's list of remote candidates.
     ///
     /// # Arguments
@@ -394,7 +415,11 @@ impl IceAgent {
     /// # Errors
     /// Returns an `Error` if candidate gathering fails (e.g., STUN server issues).
     pub fn gather_candidates(&mut self) -> Result<&Vec<Candidate>, Error> {
-        let mut candidates = gather_host_candidates();
+        let mut candidates = gather_host_candidates(
+            &self.interface_preference,
+            &self.interface_filter,
+            &self.logger,
+        );
         match self.gather_stun_candidates(&self.stun_server) {
             Ok(srflx) => candidates.extend(srflx),
             Err(e) => sink_warn!(self.logger, "STUN gathering failed: {}", e),
@@ -422,25 +447,20 @@ impl IceAgent {
     ///
     /// # Returns
     /// * `Ok(Vec<Candidate>)` with one `ServerReflexive` candidate
-    /// * `Err(String)` if no reflexive address could be retrieved
-    pub fn gather_stun_candidates(&self, stun_server: &str) -> Result<Vec<Candidate>, String> {
+    /// * `Err(IceError)` if no reflexive address could be retrieved
+    pub fn gather_stun_candidates(&self, stun_server: &str) -> Result<Vec<Candidate>, IceError> {
         // Resolver STUN server
         let server_addr = stun_server
             .to_socket_addrs()
-            .map_err(|_| format!("Cannot resolve STUN server: {stun_server}"))?
-            .next()
-            .ok_or_else(|| format!("No valid address found for STUN server: {stun_server}"))?;
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| IceError::StunServer(stun_server.to_owned()))?;
 
         // Bind UDP socket localmente (0.0.0.0:0 → cualquier puerto libre)
-        let socket =
-            UdpSocket::bind("0.0.0.0:0").map_err(|e| format!("Failed to bind UDP socket: {e}"))?;
-        socket
-            .set_read_timeout(Some(self.stun_request_timeout))
-            .map_err(|e| format!("Failed to set socket timeout: {e}"))?;
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_read_timeout(Some(self.stun_request_timeout))?;
 
-        let local_addr = socket
-            .local_addr()
-            .map_err(|e| format!("Could not get local address: {e}"))?;
+        let local_addr = socket.local_addr()?;
 
         // Construir un STUN Binding Request minimal
         let transaction_id: [u8; 12] = rand::random();
@@ -451,18 +471,14 @@ impl IceAgent {
         request.extend_from_slice(&transaction_id);
 
         //  Enviar el request al STUN server
-        socket
-            .send_to(&request, server_addr)
-            .map_err(|e| format!("Failed to send STUN request: {e}"))?;
+        socket.send_to(&request, server_addr)?;
 
         //  Esperar respuesta (Binding Response)
         let mut buf = [0u8; 512];
-        let (len, _) = socket
-            .recv_from(&mut buf)
-            .map_err(|e| format!("No STUN response received: {e}"))?;
+        let (len, _) = socket.recv_from(&mut buf)?;
 
         if len < 20 {
-            return Err("Invalid STUN response (too short)".into());
+            return Err(IceError::StunResponse("too short"));
         }
 
         // Parsear XOR-MAPPED-ADDRESS
@@ -493,7 +509,7 @@ impl IceAgent {
             offset += attr_len + (attr_len % 4);
         }
 
-        let public_addr = reflexive_addr.ok_or("XOR-MAPPED-ADDRESS not found in STUN response")?;
+        let public_addr = reflexive_addr.ok_or(IceError::StunResponse("no XOR-MAPPED-ADDRESS"))?;
 
         sink_info!(
             self.logger,
@@ -502,12 +518,16 @@ impl IceAgent {
             public_addr
         );
 
-        // Create candidate of type ServerReflexive
+        // Create candidate of type ServerReflexive, ranked by the interface
+        // its base is on
+        let local_pref = self
+            .interface_preference
+            .local_preference(InterfaceKind::detect(local_addr.ip()), 0);
         let candidate = Candidate::new(
             String::new(), // calculate foundation by default
             1,
             "udp",
-            0, // calculate priority by default
+            Candidate::calculate_priority(&ServerReflexive, local_pref, 1),
             public_addr,
             ServerReflexive,
             Some(local_addr),
@@ -556,7 +576,7 @@ impl IceAgent {
                     continue;
                 }
 
-                if local.transport != remote.transport {
+                if !local.transport.eq_ignore_ascii_case(&remote.transport) {
                     sink_error!(
                         self.logger,
                         "{}",
@@ -601,177 +621,290 @@ impl IceAgent {
 
     /// Initiates connectivity checks for all `Waiting` pairs.
     ///
-    /// This method sends a BINDING-REQUEST for each pair but does not await a response.
+    /// This method sends a STUN Binding request for each pair but does not await a response.
     /// It changes the state of the pairs to `InProgress`.
     pub fn start_checks(&mut self) {
         sink_info!(self.logger, "ICE: Starting connectivity checks...");
-        for pair in &mut self.candidate_pairs {
-            if !matches!(pair.state, CandidatePairState::Waiting) {
+        for idx in 0..self.candidate_pairs.len() {
+            if !matches!(self.candidate_pairs[idx].state, CandidatePairState::Waiting) {
                 continue;
             }
-
-            let Some(local_sock) = &pair.local.socket else {
-                sink_warn!(
-                    self.logger,
-                    "No socket for local candidate: {}",
-                    pair.local.address
-                );
-                pair.state = CandidatePairState::Failed;
-                continue;
+            let sent = self.send_check(idx, false);
+            self.candidate_pairs[idx].state = if sent {
+                CandidatePairState::InProgress
+            } else {
+                CandidatePairState::Failed
             };
+        }
+    }
 
-            if let Err(e) = local_sock.send_to(BINDING_REQUEST, pair.remote.address) {
-                sink_error!(
-                    self.logger,
-                    "Send failed from {} → {}: {}",
-                    pair.local.address,
-                    pair.remote.address,
-                    e
-                );
-                pair.state = CandidatePairState::Failed;
-            } else {
-                pair.state = CandidatePairState::InProgress;
+    /// Sends a Binding request on pair `idx`, with USE-CANDIDATE if
+    /// `nominate`. Returns `false` if it could not be sent.
+    fn send_check(&mut self, idx: usize, nominate: bool) -> bool {
+        let pair = &self.candidate_pairs[idx];
+        let Some(local_sock) = &pair.local.socket else {
+            sink_warn!(
+                self.logger,
+                "No socket for local candidate: {}",
+                pair.local.address
+            );
+            return false;
+        };
+
+        let mut request = StunMessage::binding_request();
+        request.username = Some(format!("{}:{}", self.remote_ufrag, self.ufrag));
+        request.priority = Some(pair.local.peer_reflexive_priority());
+        request.use_candidate = nominate;
+        match self.role {
+            IceRole::Controlling => request.ice_controlling = Some(self.tie_breaker),
+            IceRole::Controlled => request.ice_controlled = Some(self.tie_breaker),
+        }
+        let packet = request.encode(self.remote_pwd.as_bytes());
+
+        if let Err(e) = local_sock.send_to(&packet, pair.remote.address) {
+            sink_error!(
+                self.logger,
+                "Send failed from {} → {}: {}",
+                pair.local.address,
+                pair.remote.address,
+                e
+            );
+            return false;
+        }
+        self.checks.insert(
+            request.transaction_id,
+            Check {
+                local: pair.local.address,
+                remote: pair.remote.address,
+                packet,
+            },
+        );
+        true
+    }
+
+    /// Requests still waiting for a response, as `(local, remote, packet)`.
+    /// Retransmissions re-send the same packet (RFC 8489 §6.2.1).
+    #[must_use]
+    pub fn pending_checks(&self) -> Vec<(SocketAddr, SocketAddr, Vec<u8>)> {
+        self.checks
+            .values()
+            .map(|c| (c.local, c.remote, c.packet.clone()))
+            .collect()
+    }
+
+    /// ICE restart on the existing candidates: puts every pair back to
+    /// `Waiting` and re-sends the checks. The nominated pair is kept.
+    pub fn restart_checks(&mut self) {
+        sink_info!(self.logger, "ICE: Restarting connectivity checks...");
+        for pair in &mut self.candidate_pairs {
+            pair.state = CandidatePairState::Waiting;
+        }
+        self.checks.clear();
+        self.start_checks();
+    }
+
+    /// Re-sends the checks for every pair that has not succeeded yet.
+    pub fn retry_checks(&mut self) {
+        for pair in &mut self.candidate_pairs {
+            if !matches!(pair.state, CandidatePairState::Succeeded) {
+                pair.state = CandidatePairState::Waiting;
             }
         }
+        self.checks.clear();
+        self.start_checks();
+    }
+
+    /// Current state of the pair `local` → `remote`, if it exists.
+    #[must_use]
+    pub fn pair_state(&self, local: SocketAddr, remote: SocketAddr) -> Option<CandidatePairState> {
+        self.candidate_pairs
+            .iter()
+            .find(|p| p.local.address == local && p.remote.address == remote)
+            .map(|p| p.state)
     }
 
     /// Handles an incoming UDP packet received by the `ConnectionManager`.
     /// This function is the core of reactive ICE.
     ///
+    /// Requests must carry our ufrag and be signed with our password; they
+    /// are answered, and nominate the pair when they carry USE-CANDIDATE
+    /// and we are controlled. Responses must match a request we sent and be
+    /// signed with the remote password.
+    ///
     /// # Arguments
     /// * `packet` - The bytes of the received packet.
     /// * `from_addr` - The `SocketAddr` from which the packet originated.
     pub fn handle_incoming_packet(&mut self, packet: &[u8], from_addr: SocketAddr) {
-        let Some(pair) = self
+        let msg = match StunMessage::decode(packet) {
+            Ok(msg) => msg,
+            Err(e) => {
+                sink_warn!(
+                    self.logger,
+                    "[ICE] Unknown packet from {} ({:?}): {:?}",
+                    from_addr,
+                    e,
+                    &packet[..packet.len().min(32)]
+                );
+                return;
+            }
+        };
+        if msg.is_request() {
+            self.handle_binding_request(&msg, packet, from_addr);
+        } else if msg.is_success() {
+            self.handle_binding_success(&msg, packet, from_addr);
+        } else {
+            sink_debug!(
+                self.logger,
+                "[ICE] Ignoring STUN message type {:#06x} from {}",
+                msg.msg_type,
+                from_addr
+            );
+        }
+    }
+
+    fn handle_binding_request(&mut self, msg: &StunMessage, packet: &[u8], from_addr: SocketAddr) {
+        let for_us = msg
+            .username
+            .as_deref()
+            .and_then(|u| u.split_once(':'))
+            .is_some_and(|(receiver, _)| receiver == self.ufrag);
+        if !for_us || !stun::verify_integrity(packet, self.pwd.as_bytes()) {
+            sink_warn!(
+                self.logger,
+                "[ICE] Dropping unauthenticated Binding request from {}",
+                from_addr
+            );
+            return;
+        }
+        let Some(idx) = self
             .candidate_pairs
-            .iter_mut()
-            .find(|p| p.remote.address == from_addr)
+            .iter()
+            .position(|p| p.remote.address == from_addr)
         else {
             sink_warn!(
                 self.logger,
-                "[ICE] Ignoring unknown packet received from: {}",
+                "[ICE] Ignoring Binding request from unknown candidate: {}",
                 from_addr
             );
             return;
         };
+        sink_debug!(
+            self.logger,
+            "[ICE] Received Binding request from {}{}",
+            from_addr,
+            if msg.use_candidate {
+                " (USE-CANDIDATE)"
+            } else {
+                ""
+            }
+        );
 
-        if packet == BINDING_RESPONSE {
-            sink_info!(
+        let pair = &mut self.candidate_pairs[idx];
+        let Some(local_sock) = &pair.local.socket else {
+            sink_warn!(
+                self.logger,
+                "[ICE] No socket available to answer Binding request: {}",
+                pair.local.address
+            );
+            return;
+        };
+        let response = StunMessage::binding_success(msg, from_addr).encode(self.pwd.as_bytes());
+        if let Err(e) = local_sock.send_to(&response, from_addr) {
+            sink_error!(
+                self.logger,
+                "[ICE] Socket error sending Binding response to {}: {}",
+                from_addr,
+                e
+            );
+        }
+
+        if self.role == IceRole::Controlled
+            && msg.use_candidate
+            && self.nominated_pair.as_ref().is_none_or(|np| {
+                np.local.address != pair.local.address || np.remote.address != pair.remote.address
+            })
+        {
+            pair.is_nominated = true;
+            pair.state = CandidatePairState::Succeeded;
+            self.nominated_pair = Some(pair.clone_light());
+            sink_debug!(
+                self.logger,
+                "[ICE] Pair nominated by peer: [local={}, remote={}]",
+                pair.local.address,
+                pair.remote.address
+            );
+        } else if !matches!(pair.state, CandidatePairState::Succeeded) {
+            // Triggered check (RFC 8445 §7.3.1.4)
+            self.send_check(idx, false);
+        }
+    }
+
+    fn handle_binding_success(&mut self, msg: &StunMessage, packet: &[u8], from_addr: SocketAddr) {
+        let Some(check) = self.checks.get(&msg.transaction_id) else {
+            sink_debug!(
                 self.logger,
-                "[ICE] Received BINDING-RESPONSE from {}",
+                "[ICE] Ignoring Binding response to an unknown request from {}",
                 from_addr
             );
-            if !matches!(pair.state, CandidatePairState::Succeeded) {
-                pair.state = CandidatePairState::Succeeded;
-                sink_info!(
-                    self.logger,
-                    "[ICE] Candidate Peer Succeeded: [local={}, remote={}]",
-                    pair.local.address,
-                    pair.remote.address
-                );
+            return;
+        };
+        if check.remote != from_addr || !stun::verify_integrity(packet, self.remote_pwd.as_bytes())
+        {
+            sink_warn!(
+                self.logger,
+                "[ICE] Dropping unauthenticated Binding response from {}",
+                from_addr
+            );
+            return;
+        }
+        let local = check.local;
+        self.checks.remove(&msg.transaction_id);
+        let Some(idx) = self
+            .candidate_pairs
+            .iter()
+            .position(|p| p.local.address == local && p.remote.address == from_addr)
+        else {
+            return;
+        };
 
-                if self.role == IceRole::Controlling {
-                    let should_nominate = match &self.nominated_pair {
-                        None => true,
-                        Some(current_nominated) => pair.priority > current_nominated.priority,
-                    };
-
-                    if should_nominate {
-                        sink_debug!(
-                            self.logger,
-                            "[ICE] Nominating pair: [local={}, remote={}]",
-                            pair.local.address,
-                            pair.remote.address
-                        );
-                        pair.is_nominated = true;
-                        self.nominated_pair = Some(pair.clone_light());
-
-                        if let Some(local_sock) = &pair.local.socket {
-                            if let Err(e) =
-                                local_sock.send_to(NOMINATION_REQUEST, pair.remote.address)
-                            {
-                                sink_debug!(
-                                    self.logger,
-                                    "[ICE] Error sending NOMINATION_REQUEST to {}: {}",
-                                    pair.remote.address,
-                                    e
-                                );
-                            } else {
-                                sink_debug!(
-                                    self.logger,
-                                    "[ICE] Sent NOMINATION_REQUEST to {}",
-                                    pair.remote.address
-                                );
-                            }
-                        } else {
-                            sink_warn!(
-                                self.logger,
-                                "[ICE] Cannot nominate: No local socket for pair."
-                            );
-                        }
-                    }
-                }
-            }
-        } else if packet == BINDING_REQUEST || packet == NOMINATION_REQUEST {
-            if self.role == IceRole::Controlled && packet == NOMINATION_REQUEST {
-                sink_debug!(
-                    self.logger,
-                    "[ICE] Received NOMINATION_REQUEST from {}",
-                    from_addr
-                );
-                if self.nominated_pair.as_ref().is_none_or(|np| {
-                    np.local.address != pair.local.address
-                        || np.remote.address != pair.remote.address
-                }) {
-                    pair.is_nominated = true;
-                    pair.state = CandidatePairState::Succeeded;
-                    self.nominated_pair = Some(pair.clone_light());
-                    sink_debug!(
-                        self.logger,
-                        "[ICE] Pair nominated by peer: [local={}, remote={}]",
-                        pair.local.address,
-                        pair.remote.address
-                    );
-                }
-            } else {
-                sink_debug!(
-                    self.logger,
-                    "[ICE] Received BINDING-REQUEST from {}",
-                    from_addr
-                );
-            }
+        sink_info!(
+            self.logger,
+            "[ICE] Received Binding response from {}",
+            from_addr
+        );
+        let pair = &mut self.candidate_pairs[idx];
+        if matches!(pair.state, CandidatePairState::Succeeded) {
+            return;
+        }
+        pair.state = CandidatePairState::Succeeded;
+        sink_info!(
+            self.logger,
+            "[ICE] Candidate Peer Succeeded: [local={}, remote={}]",
+            pair.local.address,
+            pair.remote.address
+        );
 
-            let Some(local_sock) = &pair.local.socket else {
-                sink_warn!(
-                    self.logger,
-                    "[ICE] No socket available to answer BINDING-REQUEST: {}",
-                    pair.local.address
-                );
-                return;
+        if self.role == IceRole::Controlling {
+            let should_nominate = match &self.nominated_pair {
+                None => true,
+                Some(current_nominated) => pair.priority > current_nominated.priority,
             };
-            if let Err(e) = local_sock.send_to(BINDING_RESPONSE, from_addr) {
-                sink_error!(
-                    self.logger,
-                    "[ICE] Socket error sending BINDING-RESPONSE to {}: {}",
-                    from_addr,
-                    e
-                );
-            } else {
+            if should_nominate {
                 sink_debug!(
                     self.logger,
-                    "[ICE] Sending BINDING-RESPONSE to {}",
-                    from_addr
+                    "[ICE] Nominating pair: [local={}, remote={}]",
+                    pair.local.address,
+                    pair.remote.address
                 );
+                pair.is_nominated = true;
+                self.nominated_pair = Some(pair.clone_light());
+                if !self.send_check(idx, true) {
+                    sink_warn!(self.logger, "[ICE] Could not send the nomination.");
+                }
             }
-        } else {
-            sink_warn!(
-                self.logger,
-                "[ICE] Unknown packet from {}: {:?}",
-                from_addr,
-                packet
-            );
         }
     }
+
     pub(crate) fn local_credentials(&self) -> (String, String) {
         (self.ufrag.clone(), self.pwd.clone())
     }
@@ -1283,6 +1416,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_retry_checks_keeps_succeeded_pairs_but_restart_does_not() {
+        let mut agent = IceAgent::new(IceRole::Controlling, mock_logger(), &Config::empty());
+        agent.candidate_pairs = vec![
+            mock_pair_with_state(CandidatePairState::Succeeded),
+            mock_pair_with_states(CandidatePairState::Failed),
+        ];
+        let local: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        let remote: SocketAddr = "127.0.0.1:5001".parse().unwrap();
+
+        agent.retry_checks();
+        assert_eq!(
+            agent.pair_state(local, remote),
+            Some(CandidatePairState::Succeeded)
+        );
+
+        // The mocks have no sockets, so restarted checks fail right away.
+        agent.restart_checks();
+        assert_eq!(
+            agent.pair_state(local, remote),
+            Some(CandidatePairState::Failed)
+        );
+        assert_eq!(agent.pair_state(remote, local), None);
+    }
+
     #[test]
     fn test_form_candidate_pairs_skips_different_transports() {
         let mut agent = IceAgent::new(IceRole::Controlling, mock_logger(), &Config::empty());
@@ -1449,14 +1607,23 @@ mod tests {
         let port = 0;
 
         let mut agent = IceAgent::new(IceRole::Controlling, mock_logger(), &Config::empty());
+        agent.set_remote_ufrag("remote".into());
+        agent.set_remote_pwd("remote-password-0123456789".into());
         let local = mock_candidate_with_socket(ip_address, port);
         let remote = mock_candidate_with_socket(ip_address, port);
 
         let remote_sock = remote.socket.as_ref().unwrap().clone();
         let handle = thread::spawn(move || {
-            let mut buf = [0u8; 64];
-            if let Ok((_, src)) = remote_sock.recv_from(&mut buf) {
-                remote_sock.send_to(BINDING_RESPONSE, src).unwrap();
+            let mut buf = [0u8; 1500];
+            if let Ok((n, src)) = remote_sock.recv_from(&mut buf) {
+                assert!(stun::verify_integrity(
+                    &buf[..n],
+                    b"remote-password-0123456789"
+                ));
+                let request = StunMessage::decode(&buf[..n]).unwrap();
+                let response = StunMessage::binding_success(&request, src)
+                    .encode(b"remote-password-0123456789");
+                remote_sock.send_to(&response, src).unwrap();
             }
         });
 
@@ -1468,7 +1635,7 @@ mod tests {
 
         thread::sleep(std::time::Duration::from_millis(100));
 
-        let mut buf = [0u8; 64];
+        let mut buf = [0u8; 1500];
         let local_sock = agent.candidate_pairs[0].local.socket.as_ref().unwrap();
         local_sock
             .set_read_timeout(Some(std::time::Duration::from_millis(500)))
@@ -1513,6 +1680,13 @@ mod tests {
         controlled_agent.local_candidates = vec![controlled_local.clone()];
         controlled_agent.remote_candidates = vec![controlled_remote_candidate];
 
+        let (ufrag, pwd) = controlling_agent.local_credentials();
+        controlled_agent.set_remote_ufrag(ufrag);
+        controlled_agent.set_remote_pwd(pwd);
+        let (ufrag, controlled_pwd) = controlled_agent.local_credentials();
+        controlling_agent.set_remote_ufrag(ufrag);
+        controlling_agent.set_remote_pwd(controlled_pwd.clone());
+
         controlling_agent.form_candidate_pairs();
         controlled_agent.form_candidate_pairs();
 
@@ -1539,29 +1713,34 @@ mod tests {
             .unwrap()
             .clone();
 
+        // Answers the checks like the controlled agent would and hands back
+        // the nomination (the request carrying USE-CANDIDATE).
         let controlled_handle = thread::spawn(move || {
-            let mut buf = [0u8; 128];
+            let mut buf = [0u8; 1500];
             loop {
                 controlled_socket
                     .set_read_timeout(Some(Duration::from_millis(200)))
                     .expect("Failed to set read timeout on controlled socket");
                 match controlled_socket.recv_from(&mut buf) {
                     Ok((size, src)) => {
-                        let request = &buf[..size];
-                        if request == BINDING_REQUEST || request == NOMINATION_REQUEST {
-                            println!(
-                                "[Controlled Echo] Received request from {}, sending BINDING_RESPONSE",
-                                src
-                            );
-                            controlled_socket
-                                .send_to(BINDING_RESPONSE, src)
-                                .expect("Controlled failed to send response");
-                            if request == NOMINATION_REQUEST {
-                                println!(
-                                    "[Controlled Echo] Received NOMINATION_REQUEST, stopping echo."
-                                );
-                                break;
-                            }
+                        let Ok(request) = StunMessage::decode(&buf[..size]) else {
+                            continue;
+                        };
+                        if !request.is_request() {
+                            continue;
+                        }
+                        println!(
+                            "[Controlled Echo] Received request from {}, sending Binding response",
+                            src
+                        );
+                        let response = StunMessage::binding_success(&request, src)
+                            .encode(controlled_pwd.as_bytes());
+                        controlled_socket
+                            .send_to(&response, src)
+                            .expect("Controlled failed to send response");
+                        if request.use_candidate {
+                            println!("[Controlled Echo] Received nomination, stopping echo.");
+                            return Some(buf[..size].to_vec());
                         }
                     }
                     Err(e)
@@ -1569,11 +1748,11 @@ mod tests {
                             || e.kind() == std::io::ErrorKind::TimedOut =>
                     {
                         println!("[Controlled Echo] Timeout waiting for request, stopping echo.");
-                        break;
+                        return None;
                     }
                     Err(e) => {
                         eprintln!("[Controlled Echo] Error receiving: {}", e);
-                        break;
+                        return None;
                     }
                 }
             }
@@ -1582,7 +1761,7 @@ mod tests {
         controlling_agent.start_checks();
 
         thread::sleep(Duration::from_millis(50));
-        let mut buf_controlling = [0u8; 128];
+        let mut buf_controlling = [0u8; 1500];
         controlling_socket
             .set_read_timeout(Some(Duration::from_millis(500)))
             .unwrap();
@@ -1591,7 +1770,7 @@ mod tests {
                 controlling_agent.handle_incoming_packet(&buf_controlling[..bytes], src);
             }
             Err(e) => panic!(
-                "Controlling agent failed to receive BINDING_RESPONSE: {}",
+                "Controlling agent failed to receive the Binding response: {}",
                 e
             ),
         }
@@ -1605,9 +1784,11 @@ mod tests {
             "Controlling agent's pair should be marked nominated"
         );
 
-        thread::sleep(Duration::from_millis(50));
-
-        controlled_agent.handle_incoming_packet(NOMINATION_REQUEST, controlling_local_addr);
+        let nomination = controlled_handle
+            .join()
+            .unwrap()
+            .expect("The controlled side should have received the nomination");
+        controlled_agent.handle_incoming_packet(&nomination, controlling_local_addr);
         assert!(
             controlled_agent.nominated_pair.is_some(),
             "Controlled agent should have accepted nomination"
@@ -1639,9 +1820,5 @@ mod tests {
             controlling_local_addr,
             "Controlled nominated wrong pair (remote mismatch)"
         );
-
-        controlled_handle
-            .join()
-            .expect("Controlled echo thread panicked");
     }
 }
@@ -21,8 +21,14 @@ pub mod connection_manager;
 pub mod core;
 /// DTLS (Datagram Transport Layer Security) implementation.
 pub mod dtls;
+/// Error type of the public API, gathering the errors of every module.
+pub mod error;
+/// C ABI for embedding the engine from other languages.
+pub mod ffi;
 /// File handler for P2P file transfer.
 pub mod file_handler;
+/// Entry points of the fuzz targets in `fuzz/`.
+pub mod fuzzing;
 /// ICE (Interactive Connectivity Establishment) implementation for NAT traversal.
 pub mod ice;
 /// Logging utilities for the application.
@@ -31,16 +37,24 @@ pub mod log;
 pub mod media_agent;
 /// Manages RTP/RTCP media transport.
 pub mod media_transport;
+/// Network impairment simulator for loopback testing.
+pub mod netsim;
 /// RTCP (RTP Control Protocol) packet parsing and building.
 pub mod rtcp;
 /// RTP (Real-time Transport Protocol) packet parsing and building.
 pub mod rtp;
 /// Manages RTP sessions for sending and receiving media.
 pub mod rtp_session;
+/// RTSP ingest: IP cameras as a video source.
+pub mod rtsp;
 /// SCTP implementation for file transfer.
 pub mod sctp;
 /// SDP (Session Description Protocol) parsing and building.
 pub mod sdp;
+/// User settings saved across launches.
+pub mod settings;
+/// Selective forwarding unit: the server relays room media instead of a full mesh.
+pub mod sfu;
 /// Signaling server implementation for coordinating WebRTC connections.
 pub mod signaling;
 /// Signaling client for communicating with the signaling server.
@@ -49,3 +63,5 @@ pub mod signaling_client;
 pub mod srtp;
 /// TLS (Transport Layer Security) utility functions.
 pub mod tls_utils;
+/// WHIP/WHEP clients to publish to or play from media servers over HTTP.
+pub mod whip;
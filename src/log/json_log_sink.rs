@@ -0,0 +1,118 @@
+//! JSON log lines, for collectors such as Loki or Elasticsearch.
+//!
+//! Each message is one JSON object on its own line:
+//!
+//! ```text
+//! {"ts_ms":1730000000000,"level":"info","target":"rustyrtc::core::engine","message":"...","session":"sess-1","peer":"alice"}
+//! ```
+//!
+//! `session` and `peer` are only present when the message has a
+//! [`LogContext`]. The file logger writes this format when
+//! `[Logging] format = "json"`.
+
+use std::{
+    fmt::Write as _,
+    io::Write,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{
+    core::stats::json_str,
+    log::{log_context::LogContext, log_level::LogLevel, log_msg::LogMsg, log_sink::LogSink},
+    media_agent::utils::now_millis,
+};
+
+/// Renders `msg` as one JSON object, without the trailing newline.
+#[must_use]
+pub fn to_json_line(msg: &LogMsg) -> String {
+    let level = format!("{:?}", msg.level).to_ascii_lowercase();
+    let mut out = format!(
+        "{{\"ts_ms\":{},\"level\":{},\"target\":{},\"message\":{}",
+        msg.ts_ms,
+        json_str(&level),
+        json_str(msg.target),
+        json_str(&msg.text)
+    );
+    if let Some(context) = &msg.context {
+        if let Some(session) = &context.session {
+            let _ = write!(out, ",\"session\":{}", json_str(session));
+        }
+        if let Some(peer) = &context.peer {
+            let _ = write!(out, ",\"peer\":{}", json_str(peer));
+        }
+    }
+    out.push('}');
+    out
+}
+
+/// A [`LogSink`] writing JSON lines to `out` (a file, stdout, a socket).
+pub struct JsonLogSink<W: Write + Send> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonLogSink<W> {
+    #[must_use]
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Gives back the writer.
+    #[must_use]
+    pub fn into_inner(self) -> W {
+        self.out
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<W: Write + Send> LogSink for JsonLogSink<W> {
+    fn log(&self, level: LogLevel, msg: &str, target: &'static str) {
+        self.log_with_context(level, msg, target, &LogContext::default());
+    }
+
+    fn log_with_context(
+        &self,
+        level: LogLevel,
+        msg: &str,
+        target: &'static str,
+        context: &LogContext,
+    ) {
+        let mut msg = LogMsg::new(level, msg, target, now_millis());
+        msg.context = Some(context.clone()).filter(|c| !c.is_empty());
+        let line = to_json_line(&msg);
+        let mut out = self.out.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = writeln!(out, "{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::log::log_context::ContextLogSink;
+    use std::sync::Arc;
+
+    #[test]
+    fn writes_one_object_per_line_with_context() {
+        let mut msg = LogMsg::new(LogLevel::Warn, "say \"hi\"\n", "rustyrtc::x", 42);
+        assert_eq!(
+            to_json_line(&msg),
+            r#"{"ts_ms":42,"level":"warn","target":"rustyrtc::x","message":"say \"hi\"\n"}"#
+        );
+        msg.context = Some(LogContext::peer("alice"));
+        assert!(to_json_line(&msg).ends_with(r#","peer":"alice"}"#));
+
+        let sink = Arc::new(JsonLogSink::new(Vec::new()));
+        let tagged = ContextLogSink::new(sink.clone(), LogContext::session("sess-1"));
+        tagged.log_with_context(LogLevel::Info, "joined", "t", &LogContext::peer("bob"));
+        drop(tagged);
+        let out = String::from_utf8(Arc::into_inner(sink).unwrap().into_inner()).unwrap();
+        assert_eq!(out.lines().count(), 1);
+        assert!(
+            out.contains(r#""message":"joined","session":"sess-1","peer":"bob"}"#),
+            "{out}"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Session and peer a log message is about.
+//!
+//! Structured sinks (see [`JsonLogSink`](super::json_log_sink::JsonLogSink))
+//! write the context as separate fields; the text log puts it in front of
+//! the message.
+
+use std::{fmt, sync::Arc};
+
+use crate::log::{log_level::LogLevel, log_sink::LogSink};
+
+/// Session and peer fields attached to a log message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogContext {
+    /// Signaling session (room) id.
+    pub session: Option<String>,
+    /// Remote peer (username).
+    pub peer: Option<String>,
+}
+
+impl LogContext {
+    #[must_use]
+    pub fn session(id: impl Into<String>) -> Self {
+        Self {
+            session: Some(id.into()),
+            peer: None,
+        }
+    }
+
+    #[must_use]
+    pub fn peer(peer: impl Into<String>) -> Self {
+        Self {
+            session: None,
+            peer: Some(peer.into()),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.session.is_none() && self.peer.is_none()
+    }
+
+    /// `self`, with the fields it lacks taken from `outer`.
+    #[must_use]
+    pub fn or(&self, outer: &Self) -> Self {
+        Self {
+            session: self.session.clone().or_else(|| outer.session.clone()),
+            peer: self.peer.clone().or_else(|| outer.peer.clone()),
+        }
+    }
+}
+
+impl fmt::Display for LogContext {
+    /// `session=<id> peer=<name>`, omitting missing fields.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        if let Some(session) = &self.session {
+            write!(f, "session={session}")?;
+            sep = " ";
+        }
+        if let Some(peer) = &self.peer {
+            write!(f, "{sep}peer={peer}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Tags every message logged through it with a [`LogContext`].
+pub struct ContextLogSink {
+    inner: Arc<dyn LogSink>,
+    context: LogContext,
+}
+
+impl ContextLogSink {
+    #[must_use]
+    pub fn new(inner: Arc<dyn LogSink>, context: LogContext) -> Self {
+        Self { inner, context }
+    }
+}
+
+impl LogSink for ContextLogSink {
+    fn log(&self, level: LogLevel, msg: &str, target: &'static str) {
+        self.inner
+            .log_with_context(level, msg, target, &self.context);
+    }
+
+    fn log_with_context(
+        &self,
+        level: LogLevel,
+        msg: &str,
+        target: &'static str,
+        context: &LogContext,
+    ) {
+        self.inner
+            .log_with_context(level, msg, target, &context.or(&self.context));
+    }
+}
@@ -1,5 +1,9 @@
+use std::str::FromStr;
+
 /// Defines the severity levels for log messages.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+///
+/// Levels are ordered by severity, `Trace` lowest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
     /// Designates very fine-grained informational events.
     Trace,
@@ -12,3 +16,29 @@ pub enum LogLevel {
     /// Designates error events that might still allow the application to continue running.
     Error,
 }
+
+impl LogLevel {
+    /// Every level, lowest first.
+    pub const ALL: [Self; 5] = [
+        Self::Trace,
+        Self::Debug,
+        Self::Info,
+        Self::Warn,
+        Self::Error,
+    ];
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "trace" => Ok(Self::Trace),
+            "debug" => Ok(Self::Debug),
+            "info" => Ok(Self::Info),
+            "warn" | "warning" => Ok(Self::Warn),
+            "error" => Ok(Self::Error),
+            other => Err(format!("unknown log level '{other}'")),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::log::log_level::LogLevel;
+use crate::log::{log_context::LogContext, log_level::LogLevel};
 
 /// Represents a single log message event.
 ///
@@ -14,6 +14,8 @@ pub struct LogMsg {
     pub text: String,
     /// The target source of the log, typically the static module path.
     pub target: &'static str, // module path
+    /// Session and peer the message is about, if known.
+    pub context: Option<LogContext>,
 }
 
 impl LogMsg {
@@ -29,7 +31,7 @@ impl LogMsg {
     /// # Example
     ///
     /// ```rust,ignore
-    /// use crate::log::log_level::LogLevel;
+    /// use crate::log::{log_context::LogContext, log_level::LogLevel};
     /// use crate::log::LogMsg;
     ///
     /// let msg = LogMsg::new(
@@ -50,6 +52,7 @@ impl LogMsg {
             ts_ms,
             text: text.into(),
             target,
+            context: None,
         }
     }
 }
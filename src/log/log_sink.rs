@@ -1,4 +1,4 @@
-use crate::log::log_level::LogLevel;
+use crate::log::{log_context::LogContext, log_level::LogLevel};
 
 /// Defines a destination (sink) for log messages.
 ///
@@ -33,4 +33,18 @@ pub trait LogSink: Send + Sync {
     /// }
     /// ```
     fn log(&self, level: LogLevel, msg: &str, target: &'static str);
+
+    /// Records a log message about a session or peer.
+    ///
+    /// Sinks without structured fields ignore the context by default.
+    fn log_with_context(
+        &self,
+        level: LogLevel,
+        msg: &str,
+        target: &'static str,
+        context: &LogContext,
+    ) {
+        let _ = context;
+        self.log(level, msg, target);
+    }
 }
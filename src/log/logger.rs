@@ -1,14 +1,21 @@
 use crate::{
     config::Config,
-    log::{log_level::LogLevel, log_msg::LogMsg, logger_handle::LoggerHandle},
+    log::{
+        json_log_sink::to_json_line,
+        log_level::LogLevel,
+        log_msg::LogMsg,
+        logger_handle::LoggerHandle,
+        rotation::{RotatingFile, RotationPolicy},
+    },
 };
 
 use std::{
-    fs::{self, OpenOptions},
+    fs::OpenOptions,
     io::{self, BufWriter, Write},
     path::{Path, PathBuf},
     sync::{
         Arc,
+        atomic::{AtomicU8, Ordering},
         mpsc::{self, TrySendError},
     },
     thread,
@@ -29,6 +36,39 @@ const FLUSH_BATCH_SIZE: u32 = 1_000;
 
 // -----------------------------------------------------------------------------
 
+/// Format of the lines in the log file, from `[Logging] format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[Level] ts_ms | session=.. peer=.. | message`, for people.
+    #[default]
+    Text,
+    /// One JSON object per line (see [`to_json_line`]), for collectors.
+    Json,
+}
+
+impl LogFormat {
+    /// `[Logging] format`: `"json"` or `"text"` (the default).
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        match config.get_non_empty("Logging", "format") {
+            Some(f) if f.trim().eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+
+    /// The line written for `m`, without the trailing newline.
+    #[must_use]
+    pub fn format(self, m: &LogMsg) -> String {
+        match self {
+            Self::Json => to_json_line(m),
+            Self::Text => match m.context.as_ref().filter(|c| !c.is_empty()) {
+                Some(context) => format!("[{:?}] {} | {} | {}", m.level, m.ts_ms, context, m.text),
+                None => format!("[{:?}] {} | {}", m.level, m.ts_ms, m.text),
+            },
+        }
+    }
+}
+
 /// Bounded, non-blocking logger that writes to a per-process log file.
 ///
 /// This struct manages a background worker thread that consumes log messages from a
@@ -47,6 +87,8 @@ pub struct Logger {
     _thread: Option<std::thread::JoinHandle<()>>,
     file_path: std::path::PathBuf,
     _sample_every: u32,
+    /// Lowest level written, as an index into [`LogLevel::ALL`].
+    min_level: Arc<AtomicU8>,
 }
 
 impl Logger {
@@ -90,14 +132,25 @@ impl Logger {
         sample_every: u32,
         config: Arc<Config>,
     ) -> Self {
-        let app_name = config.get_non_empty("Loggin", fn_key);
-
-        if let Some(dir_str) = config.get_non_empty("Logging", path_key) {
-            let dir = expand_path(dir_str);
-            Self::start_in_dir(dir, app_name, cap, ui_cap, sample_every)
-        } else {
-            Self::start_default(app_name, cap, ui_cap, sample_every)
-        }
+        let app_name = config.get_non_empty("Logging", fn_key);
+        let min_level = Self::configured_level(&config);
+        let format = LogFormat::from_config(&config);
+        let rotation = RotationPolicy::from_config(&config);
+
+        let dir = match config.get_non_empty("Logging", path_key) {
+            Some(dir_str) => expand_path(dir_str),
+            None => exe_dir_fallback_cwd().join("logs"),
+        };
+        Self::spawn(
+            dir,
+            app_name,
+            cap,
+            ui_cap,
+            sample_every,
+            min_level,
+            format,
+            rotation,
+        )
     }
 
     /// Creates a `logs/` directory next to the executable and starts the logger there.
@@ -135,25 +188,63 @@ impl Logger {
         cap: usize,
         ui_cap: usize,
         sample_every: u32,
+    ) -> Self {
+        Self::spawn(
+            dir,
+            app_name,
+            cap,
+            ui_cap,
+            sample_every,
+            LogLevel::Trace,
+            LogFormat::Text,
+            RotationPolicy::default(),
+        )
+    }
+
+    /// [`start_in_dir`](Self::start_in_dir), dropping messages below
+    /// `min_level`, writing lines in `format` and rotating the file as
+    /// `rotation` says.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn<D: AsRef<Path>>(
+        dir: D,
+        app_name: Option<&str>,
+        cap: usize,
+        ui_cap: usize,
+        sample_every: u32,
+        min_level: LogLevel,
+        format: LogFormat,
+        rotation: RotationPolicy,
     ) -> Self {
         let dir = dir.as_ref().to_path_buf();
-        let _ = fs::create_dir_all(&dir);
 
         // Avoid potential modulo-by-zero later.
         let _sample_every = sample_every.max(1);
 
-        // Calculated once to avoid code repetition.
-        let ts = timestamp_for_filename();
         let pid = std::process::id();
+        let prefix = app_name.map(|name| format!("{name}-")).unwrap_or_default();
+
+        // Named again on each rotation, so every file gets its own timestamp.
+        let name_prefix = prefix.clone();
+        let file_name = move || format!("{name_prefix}{}-pid{pid}.log", timestamp_for_filename());
+
+        // Try target file -> temp file -> sink (never panic).
+        let (writer, file_path): (Box<dyn Write + Send>, PathBuf) =
+            match RotatingFile::open(&dir, prefix, file_name, rotation) {
+                Ok(file) => {
+                    let path = file.path().to_path_buf();
+                    (Box::new(file), path)
+                }
+                Err(_) => {
+                    let fallback = std::env::temp_dir().join("roomrtc-fallback.log");
+                    match OpenOptions::new().create(true).append(true).open(&fallback) {
+                        Ok(f) => (Box::new(BufWriter::new(f)), fallback),
+                        Err(_) => (Box::new(io::sink()), fallback),
+                    }
+                }
+            };
 
-        // Determine filename based on whether app_name is provided.
-        let fname = if let Some(name) = app_name {
-            format!("{}-{}-pid{}.log", name, ts, pid)
-        } else {
-            format!("{}-pid{}.log", ts, pid)
-        };
-
-        let file_path = dir.join(&fname);
+        let min_level = Arc::new(AtomicU8::new(min_level as u8));
+        let worker_min_level = min_level.clone();
 
         let (tx, rx) = mpsc::sync_channel::<LogMsg>(cap);
         let (ui_tx, ui_rx) = mpsc::sync_channel::<String>(ui_cap);
@@ -161,34 +252,21 @@ impl Logger {
         // No redundant clone: consume `tx` into the handle (we don't use `tx` afterwards).
         let handle_for_field = LoggerHandle { tx };
 
-        let file_path_clone = file_path.clone();
-
         let _thread = thread::Builder::new()
             .name("logger-worker".into())
             .spawn(move || {
-                // Try target file -> temp file -> sink (never panic).
-                let writer: Box<dyn Write + Send> = if let Ok(f) = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&file_path_clone)
-                {
-                    Box::new(f)
-                } else {
-                    let fallback = std::env::temp_dir().join("roomrtc-fallback.log");
-                    match OpenOptions::new().create(true).append(true).open(&fallback) {
-                        Ok(f) => Box::new(f),
-                        Err(_) => Box::new(io::sink()),
-                    }
-                };
-
-                let mut out: BufWriter<Box<dyn Write + Send>> = BufWriter::new(writer);
+                // Buffered already; rotation happens between lines.
+                let mut out = writer;
 
                 let mut n: u32 = 0;
                 let mut lines_written: u32 = 0;
                 let mut dropped_to_ui: usize = 0;
 
                 while let Ok(m) = rx.recv() {
-                    let _ = writeln!(&mut out, "[{:?}] {} | {}", m.level, m.ts_ms, m.text);
+                    if (m.level as u8) < worker_min_level.load(Ordering::Relaxed) {
+                        continue;
+                    }
+                    let _ = writeln!(&mut out, "{}", format.format(&m));
                     lines_written = lines_written.wrapping_add(1);
 
                     // Flush periodically to ensure data persists on crash.
@@ -230,9 +308,33 @@ impl Logger {
             _thread,
             file_path,
             _sample_every,
+            min_level,
         }
     }
 
+    /// The global `log_level`: messages below it are not written. `Trace`
+    /// (everything the build logs) if missing or invalid.
+    #[must_use]
+    pub fn configured_level(config: &Config) -> LogLevel {
+        config
+            .get_global("log_level")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(LogLevel::Trace)
+    }
+
+    /// Changes the lowest level written to the file, e.g. after the
+    /// configuration is reloaded.
+    pub fn set_min_level(&self, level: LogLevel) {
+        self.min_level.store(level as u8, Ordering::Relaxed);
+    }
+
+    /// The lowest level written to the file.
+    #[must_use]
+    pub fn min_level(&self) -> LogLevel {
+        let index = usize::from(self.min_level.load(Ordering::Relaxed));
+        LogLevel::ALL.get(index).copied().unwrap_or(LogLevel::Trace)
+    }
+
     /// Attempts to enqueue a log message without blocking the current thread.
     ///
     /// This method sends the message to the logger’s internal synchronous channel.
@@ -282,7 +384,8 @@ impl Logger {
         self.ui_log_rx.try_recv().ok()
     }
 
-    /// Returns the path of the active log file.
+    /// Returns the path of the log file opened at startup (later files, if
+    /// the log rotates, are named the same way with a newer timestamp).
     ///
     /// Useful for debugging or displaying the log location to the user.
     #[must_use]
@@ -293,7 +396,7 @@ impl Logger {
 
 /// Locates the `logs` directory next to the executable (target/{debug,release}),
 /// or falls back to the current working directory on error.
-fn exe_dir_fallback_cwd() -> PathBuf {
+pub(crate) fn exe_dir_fallback_cwd() -> PathBuf {
     std::env::current_exe()
         .ok()
         .and_then(|p| p.parent().map(Path::to_path_buf))
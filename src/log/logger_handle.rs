@@ -1,7 +1,7 @@
 use std::sync::mpsc;
 
 use crate::{
-    log::{log_level::LogLevel, log_msg::LogMsg, log_sink::LogSink},
+    log::{log_context::LogContext, log_level::LogLevel, log_msg::LogMsg, log_sink::LogSink},
     media_agent,
 };
 
@@ -29,6 +29,18 @@ impl LogSink for LoggerHandle {
         // `try_log` takes Into<String>; &str works (it will allocate).
         let _ = self.try_log(level, msg, target);
     }
+
+    fn log_with_context(
+        &self,
+        level: LogLevel,
+        msg: &str,
+        target: &'static str,
+        context: &LogContext,
+    ) {
+        let mut msg = LogMsg::new(level, msg, target, media_agent::utils::now_millis());
+        msg.context = Some(context.clone()).filter(|c| !c.is_empty());
+        let _ = self.tx.try_send(msg);
+    }
 }
 
 impl LoggerHandle {
@@ -60,6 +72,7 @@ impl LoggerHandle {
             ts_ms: media_agent::utils::now_millis(),
             text: text.into(),
             target,
+            context: None,
         };
         self.tx.try_send(msg)
     }
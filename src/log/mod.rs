@@ -1,3 +1,5 @@
+pub mod json_log_sink;
+pub mod log_context;
 pub mod log_level;
 pub mod log_macros;
 pub mod log_msg;
@@ -5,4 +7,5 @@ pub mod log_sink;
 pub mod logger;
 pub mod logger_handle;
 pub mod noop_log_sink;
+pub mod rotation;
 pub use noop_log_sink::NoopLogSink;
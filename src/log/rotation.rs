@@ -0,0 +1,335 @@
+//! Log file rotation and retention.
+//!
+//! [`RotatingFile`] starts a new log file when the current one reaches a
+//! size limit or when the (UTC) day changes, gzips the file it leaves and
+//! deletes the oldest log files beyond a retention count. The policy comes
+//! from the `[Logging]` section (see [`RotationPolicy::from_config`]); with
+//! none of its keys set, the file grows without limit as before.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{self, Sender},
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{Compression, write::GzEncoder};
+
+use crate::config::Config;
+
+const SECS_PER_DAY: u64 = 86_400;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// When to start a new log file and how many to keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate once the file reaches this size.
+    pub max_bytes: Option<u64>,
+    /// Rotate when the UTC date changes.
+    pub daily: bool,
+    /// Log files kept in the directory, the current one included; the
+    /// oldest are deleted. `None` keeps them all.
+    pub max_files: Option<usize>,
+    /// Gzip rotated files (`.log.gz`).
+    pub compress: bool,
+}
+
+impl RotationPolicy {
+    /// `[Logging] max_file_size_mb`, `rotate_daily`, `max_files` and
+    /// `compress`. Empty or zero values turn the limit off.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let num = |key| {
+            config
+                .get_non_empty("Logging", key)
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|&n| n > 0)
+        };
+        let flag = |key| {
+            config
+                .get("Logging", key)
+                .is_some_and(|s| s.trim().eq_ignore_ascii_case("true"))
+        };
+        Self {
+            max_bytes: num("max_file_size_mb").map(|mb| mb.saturating_mul(BYTES_PER_MB)),
+            daily: flag("rotate_daily"),
+            max_files: num("max_files").and_then(|n| usize::try_from(n).ok()),
+            compress: flag("compress"),
+        }
+    }
+}
+
+/// Days since the UNIX epoch (UTC).
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / SECS_PER_DAY
+}
+
+/// A log file that rotates according to a [`RotationPolicy`].
+///
+/// Files are named by `name_for` (called again on each rotation); rotation
+/// only happens between lines, so a line never spans two files. Rotated
+/// files are compressed and pruned by a background thread, one at a time.
+pub struct RotatingFile {
+    dir: PathBuf,
+    name_for: Box<dyn Fn() -> String + Send>,
+    policy: RotationPolicy,
+    path: PathBuf,
+    /// `path`, as seen by the retention thread.
+    current: Arc<Mutex<PathBuf>>,
+    /// Rotated files, to the retention thread.
+    rotated: Option<Sender<PathBuf>>,
+    out: BufWriter<File>,
+    written: u64,
+    day: u64,
+    at_line_start: bool,
+}
+
+impl RotatingFile {
+    /// Opens a new file in `dir` (created if needed) named by `name_for`.
+    /// `prefix` is the start shared by every file name `name_for` returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the directory or the file cannot be
+    /// created.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        prefix: impl Into<String>,
+        name_for: impl Fn() -> String + Send + 'static,
+        policy: RotationPolicy,
+    ) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let path = unique_path(&dir, &name_for());
+        let out = BufWriter::new(OpenOptions::new().create(true).append(true).open(&path)?);
+        let current = Arc::new(Mutex::new(path.clone()));
+        let retention = Retention {
+            dir: dir.clone(),
+            prefix: prefix.into(),
+            current: current.clone(),
+            policy,
+        };
+        retention.prune();
+
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let rotated = thread::Builder::new()
+            .name("log-rotation".into())
+            .spawn(move || {
+                for old in rx {
+                    retention.rotated(&old);
+                }
+            })
+            .ok()
+            .map(|_| tx);
+        Ok(Self {
+            dir,
+            name_for: Box::new(name_for),
+            policy,
+            path,
+            current,
+            rotated,
+            out,
+            written: 0,
+            day: today(),
+            at_line_start: true,
+        })
+    }
+
+    /// The file being written.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn should_rotate(&self, incoming: usize) -> bool {
+        let full = self.policy.max_bytes.is_some_and(|max| {
+            self.written > 0 && self.written.saturating_add(incoming as u64) > max
+        });
+        full || (self.policy.daily && today() != self.day)
+    }
+
+    /// Switches to a new file and hands the old one to the retention
+    /// thread.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        let path = unique_path(&self.dir, &(self.name_for)());
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        *self.current.lock().unwrap_or_else(PoisonError::into_inner) = path.clone();
+        let old = std::mem::replace(&mut self.path, path);
+        self.out = BufWriter::new(file);
+        self.written = 0;
+        self.day = today();
+        if let Some(rotated) = &self.rotated {
+            let _ = rotated.send(old);
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.at_line_start && !buf.is_empty() && self.should_rotate(buf.len()) {
+            self.rotate()?;
+        }
+        let n = self.out.write(buf)?;
+        self.written = self.written.saturating_add(n as u64);
+        if n > 0 {
+            self.at_line_start = buf[n - 1] == b'\n';
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Compression of rotated files and deletion of the oldest ones.
+struct Retention {
+    dir: PathBuf,
+    /// Start of every log file name in `dir`.
+    prefix: String,
+    current: Arc<Mutex<PathBuf>>,
+    policy: RotationPolicy,
+}
+
+impl Retention {
+    fn rotated(&self, old: &Path) {
+        if self.policy.compress {
+            let _ = gzip(old);
+        }
+        self.prune();
+    }
+
+    /// Deletes the oldest log files beyond `max_files`, never the current
+    /// one.
+    fn prune(&self) {
+        let Some(max_files) = self.policy.max_files else {
+            return;
+        };
+        let current = self
+            .current
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let mut logs: Vec<(SystemTime, PathBuf)> = fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path != &current && is_log_file(path, &self.prefix))
+            .map(|path| {
+                let modified = fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .unwrap_or(UNIX_EPOCH);
+                (modified, path)
+            })
+            .collect();
+        // Newest first; the current file takes one of the slots
+        logs.sort_by(|a, b| b.cmp(a));
+        for (_, path) in logs.into_iter().skip(max_files.saturating_sub(1)) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// `path` is a log file (`.log` or `.log.gz`) whose name starts with
+/// `prefix`.
+fn is_log_file(path: &Path, prefix: &str) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|name| {
+            name.starts_with(prefix) && (name.ends_with(".log") || name.ends_with(".log.gz"))
+        })
+}
+
+/// `dir/name`, or `dir/<stem>-<n>.log` if that file already exists.
+fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    if !path.exists() {
+        return path;
+    }
+    let stem = name.strip_suffix(".log").unwrap_or(name);
+    (1..)
+        .map(|n| dir.join(format!("{stem}-{n}.log")))
+        .find(|p| !p.exists())
+        .unwrap_or(path)
+}
+
+/// Replaces `path` with `path.gz`, keeping its modification time so
+/// retention still sees the files in the order they were written.
+fn gzip(path: &Path) -> io::Result<()> {
+    let mut gz_name = path.as_os_str().to_owned();
+    gz_name.push(".gz");
+    let mut input = File::open(path)?;
+    let modified = input.metadata()?.modified()?;
+    let mut encoder = GzEncoder::new(File::create(&gz_name)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.set_modified(modified)?;
+    fs::remove_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest_files() {
+        let dir = std::env::temp_dir().join(format!("rustyrtc-rotation-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let counter = AtomicU32::new(0);
+        let policy = RotationPolicy {
+            max_bytes: Some(10),
+            daily: false,
+            max_files: Some(2),
+            compress: true,
+        };
+        let mut file = RotatingFile::open(
+            &dir,
+            "app-",
+            move || format!("app-{}.log", counter.fetch_add(1, Ordering::Relaxed)),
+            policy,
+        )
+        .unwrap();
+        for line in ["first line\n", "second line\n", "third line\n"] {
+            // A line is never split, even one longer than the limit
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+        assert!(file.path().ends_with("app-2.log"));
+
+        // app-0 was gzipped then pruned; app-1.log.gz and app-2.log remain
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let names = loop {
+            let mut names: Vec<String> = fs::read_dir(&dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .collect();
+            names.sort();
+            if names == ["app-1.log.gz", "app-2.log"] || Instant::now() > deadline {
+                break names;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(names, ["app-1.log.gz", "app-2.log"]);
+        let gz = fs::read(dir.join("app-1.log.gz")).unwrap();
+        assert_eq!(gz[..2], [0x1f, 0x8b]);
+        assert_eq!(
+            fs::read_to_string(dir.join("app-2.log")).unwrap(),
+            "third line\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,12 +1,14 @@
 use crate::log::log_sink::LogSink;
 use crate::media_agent::{
     audio_capture_error::AudioCaptureError,
+    audio_devices::input_device,
+    audio_format::AudioFormat,
     audio_frame::AudioFrame,
     media_agent_error::{MediaAgentError, Result},
     utils::now_millis,
 };
 use crate::{sink_debug, sink_error, sink_info, sink_warn};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use std::collections::VecDeque;
 use std::sync::{
     Arc, Mutex,
@@ -16,6 +18,9 @@ use std::sync::{
 use std::thread;
 use std::time::Duration;
 
+/// Length of the captured frames.
+const FRAME_MS: u32 = 20;
+
 #[allow(clippy::expect_used)]
 /// Event sent by the AudioCaptureWorker.
 #[derive(Debug)]
@@ -26,13 +31,14 @@ pub enum AudioCaptureEvent {
 
 /// Spawns the audio capture worker.
 ///
-/// This function initializes the default input device and starts capturing audio frames.
+/// This function opens the input device and starts capturing audio frames.
 ///
 /// # Arguments
 ///
 /// * `logger` - Logger instance.
 /// * `running` - Atomic flag to control the worker loop.
 /// * `is_muted` - Atomic flag to control audio muting.
+/// * `device` - Input device name; `None` (or a missing device) uses the default.
 ///
 /// # Returns
 ///
@@ -41,25 +47,37 @@ pub fn spawn_audio_capture_worker(
     logger: Arc<dyn LogSink>,
     running: Arc<AtomicBool>,
     is_muted: Arc<AtomicBool>,
+    device: Option<String>,
 ) -> (
     std::sync::mpsc::Receiver<AudioCaptureEvent>,
     Option<thread::JoinHandle<()>>,
 ) {
     let (tx, rx) = std::sync::mpsc::channel();
+    let handle = spawn_audio_capture_worker_into(logger, running, is_muted, device, tx);
+    (rx, handle)
+}
 
-    let handle = thread::Builder::new()
+/// Like [`spawn_audio_capture_worker`], but sends into an existing channel so
+/// the source can be swapped without touching the consumer.
+pub fn spawn_audio_capture_worker_into(
+    logger: Arc<dyn LogSink>,
+    running: Arc<AtomicBool>,
+    is_muted: Arc<AtomicBool>,
+    device: Option<String>,
+    tx: Sender<AudioCaptureEvent>,
+) -> Option<thread::JoinHandle<()>> {
+    thread::Builder::new()
         .name("media-agent-audio-capture".into())
         .spawn(move || {
-            if let Err(e) = run_audio_capture(logger.clone(), tx.clone(), running, is_muted) {
+            if let Err(e) = run_audio_capture(logger.clone(), tx.clone(), running, is_muted, device)
+            {
                 sink_error!(logger, "[AudioCaptureWorker] Error: {}", e);
                 let _ = tx.send(AudioCaptureEvent::Error(AudioCaptureError::Runtime(
                     e.to_string(),
                 )));
             }
         })
-        .ok();
-
-    (rx, handle)
+        .ok()
 }
 
 fn run_audio_capture(
@@ -67,25 +85,34 @@ fn run_audio_capture(
     tx: Sender<AudioCaptureEvent>,
     running: Arc<AtomicBool>,
     is_muted: Arc<AtomicBool>,
+    device_name: Option<String>,
 ) -> Result<()> {
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or_else(|| MediaAgentError::Io("Failed to get default input device".to_string()))?;
-
+    let device = input_device(device_name.as_deref())
+        .ok_or_else(|| MediaAgentError::Io("Failed to get an audio input device".to_string()))?;
+
+    // The device's own format; the media agent converts to the negotiated one
+    let format = device
+        .default_input_config()
+        .map_or(AudioFormat::G711, |c| {
+            AudioFormat::new(c.sample_rate().0, c.channels())
+        });
     sink_info!(
         logger,
-        "[AudioCaptureWorker] Using audio device: {}",
-        device.name().unwrap_or_default()
+        "[AudioCaptureWorker] Using audio device: {} ({} Hz, {} channel(s))",
+        device.name().unwrap_or_default(),
+        format.sample_rate,
+        format.channels
     );
 
     let config = cpal::StreamConfig {
-        channels: 1,
-        sample_rate: cpal::SampleRate(8000),
+        channels: format.channels,
+        sample_rate: cpal::SampleRate(format.sample_rate),
         buffer_size: cpal::BufferSize::Default,
     };
+    let frame_samples = (format.sample_rate * FRAME_MS / 1000) as usize;
+    let frame_len = frame_samples * usize::from(format.channels);
 
-    let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(320)));
+    let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(frame_len * 2)));
     let buffer_clone = buffer.clone();
 
     let logger_clone = logger.clone();
@@ -119,13 +146,13 @@ fn run_audio_capture(
                     buf.extend(data.iter().cloned());
                 }
 
-                while buf.len() >= 160 {
-                    let chunk: Vec<f32> = buf.drain(0..160).collect();
+                while buf.len() >= frame_len {
+                    let chunk: Vec<f32> = buf.drain(0..frame_len).collect();
                     let frame = AudioFrame {
                         data: Arc::new(chunk),
-                        samples: 160,
-                        sample_rate: 8000,
-                        channels: 1,
+                        samples: frame_samples,
+                        sample_rate: format.sample_rate,
+                        channels: format.channels,
                         timestamp_ms: now_millis(),
                     };
 
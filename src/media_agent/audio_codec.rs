@@ -1,7 +1,7 @@
-/// Audio Codec module for G.711 u-law encoding and decoding.
+/// Audio Codec module for G.711 u-law and L16 encoding and decoding.
 ///
 /// This module provides pure functions to convert between raw PCM audio samples (f32)
-/// and compressed G.711 u-law bytes (u8).
+/// and compressed G.711 u-law bytes (u8), or 16-bit linear PCM (L16).
 ///
 /// The internal algorithm works with 16-bit signed integers (i16), but the public API
 /// uses `f32` to maintain consistency with the rest of the media pipeline.
@@ -38,6 +38,22 @@ pub fn decode(ulaw_bytes: &[u8]) -> Vec<f32> {
         .collect()
 }
 
+/// Encodes f32 PCM samples as L16: 16-bit signed, network byte order (RFC 3551).
+pub fn encode_l16(pcm_samples: &[f32]) -> Vec<u8> {
+    pcm_samples
+        .iter()
+        .flat_map(|&sample| ((sample.clamp(-1.0, 1.0) * 32767.0) as i16).to_be_bytes())
+        .collect()
+}
+
+/// Decodes L16 bytes to f32 PCM samples. A trailing odd byte is ignored.
+pub fn decode_l16(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_be_bytes([b[0], b[1]]) as f32 / 32767.0)
+        .collect()
+}
+
 /// Converts a 16-bit linear PCM sample to 8-bit u-law.
 fn linear_to_ulaw(sample: i16) -> u8 {
     let sign = (sample >> 8) & 0x80;
@@ -112,6 +128,18 @@ mod tests {
         assert!(diff < 0.01, "Silence should be preserved reasonably well");
     }
 
+    #[test]
+    fn test_l16_round_trip() {
+        let encoded = encode_l16(&[0.5, -1.0, 0.0]);
+        assert_eq!(encoded.len(), 6);
+        // Big-endian: 0.5 -> 16383 = 0x3FFF
+        assert_eq!(&encoded[..2], &[0x3F, 0xFF]);
+        let decoded = decode_l16(&encoded);
+        assert!((decoded[0] - 0.5).abs() < 0.001);
+        assert!((decoded[1] + 1.0).abs() < 0.001);
+        assert_eq!(decoded[2], 0.0);
+    }
+
     #[test]
     fn test_clipping() {
         let original = 1.5f32; // > 1.0, should clip
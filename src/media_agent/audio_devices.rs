@@ -0,0 +1,118 @@
+//! Microphone and speaker enumeration through `cpal`.
+//!
+//! Devices are identified by name, which is what `cpal` exposes and what the
+//! `[Media] audio_input` / `audio_output` config keys hold.
+
+use cpal::traits::{DeviceTrait, HostTrait};
+
+use crate::config::Config;
+
+/// An audio input or output device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioDevice {
+    pub name: String,
+    /// Whether this is the host's default device.
+    pub is_default: bool,
+}
+
+impl AudioDevice {
+    /// Human-readable label for pickers, e.g. `"Built-in Mic (default)"`.
+    #[must_use]
+    pub fn label(&self) -> String {
+        if self.is_default {
+            format!("{} (default)", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// Lists the capture devices of the default host.
+#[must_use]
+pub fn list_input_devices() -> Vec<AudioDevice> {
+    let host = cpal::default_host();
+    let default = host.default_input_device().and_then(|d| d.name().ok());
+    host.input_devices()
+        .map(|devices| collect_devices(devices, default.as_deref()))
+        .unwrap_or_default()
+}
+
+/// Lists the playback devices of the default host.
+#[must_use]
+pub fn list_output_devices() -> Vec<AudioDevice> {
+    let host = cpal::default_host();
+    let default = host.default_output_device().and_then(|d| d.name().ok());
+    host.output_devices()
+        .map(|devices| collect_devices(devices, default.as_deref()))
+        .unwrap_or_default()
+}
+
+fn collect_devices(
+    devices: impl Iterator<Item = cpal::Device>,
+    default: Option<&str>,
+) -> Vec<AudioDevice> {
+    let mut out: Vec<AudioDevice> = Vec::new();
+    for name in devices.filter_map(|d| d.name().ok()) {
+        // Some backends list the same device once per configuration
+        if out.iter().any(|d| d.name == name) {
+            continue;
+        }
+        out.push(AudioDevice {
+            is_default: default == Some(name.as_str()),
+            name,
+        });
+    }
+    out
+}
+
+/// The input device called `name`, or the default one if `name` is `None` or
+/// no longer present.
+#[must_use]
+pub fn input_device(name: Option<&str>) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    name.and_then(|name| {
+        host.input_devices()
+            .ok()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+    })
+    .or_else(|| host.default_input_device())
+}
+
+/// The output device called `name`, or the default one if `name` is `None`
+/// or no longer present.
+#[must_use]
+pub fn output_device(name: Option<&str>) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    name.and_then(|name| {
+        host.output_devices()
+            .ok()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+    })
+    .or_else(|| host.default_output_device())
+}
+
+/// Device name from `[Media] <key>`; empty or missing means the default.
+#[must_use]
+pub fn configured_device(config: &Config, key: &str) -> Option<String> {
+    config
+        .get("Media", key)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn empty_device_key_means_default() {
+        assert_eq!(configured_device(&Config::empty(), "audio_input"), None);
+        let mic = AudioDevice {
+            name: "USB Mic".into(),
+            is_default: true,
+        };
+        assert_eq!(mic.label(), "USB Mic (default)");
+    }
+}
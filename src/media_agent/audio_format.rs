@@ -0,0 +1,209 @@
+//! Audio formats: what the devices deliver and what is negotiated in SDP.
+//!
+//! The local receive format comes from `[Media] audio_sample_rate` and
+//! `audio_channels` and is offered as `L16/<rate>/<channels>` with a
+//! `stereo=` fmtp. The send format follows what the peer offered; peers
+//! without L16 get G.711 (8 kHz mono), and so does everybody in low data
+//! mode.
+
+use crate::{
+    config::Config,
+    core::quality::LowDataMode,
+    media_agent::{
+        audio_codec, constants::AUDIO_SAMPLE_RATE, resampler::Resampler, spec::CodecSpec,
+    },
+    rtp_session::rtp_codec::RtpCodec,
+};
+
+/// Largest audio payload per RTP packet, well under the 1200 byte MTU.
+const MAX_AUDIO_PAYLOAD: usize = 1000;
+/// Preferred packet duration.
+const PACKET_MS: u32 = 20;
+/// Shortest packet duration, for high rates in stereo.
+const MIN_PACKET_MS: u32 = 5;
+
+/// Sample rate and channel count of interleaved `f32` audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl AudioFormat {
+    /// G.711: 8 kHz mono.
+    pub const G711: Self = Self::new(AUDIO_SAMPLE_RATE, 1);
+
+    #[must_use]
+    pub const fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+        }
+    }
+
+    /// Local receive format (`[Media] audio_sample_rate`, `audio_channels`).
+    ///
+    /// Rates are clamped to 8-48 kHz and channels to mono or stereo; the
+    /// default, and the only format in low data mode, is G.711's 8 kHz mono.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        if LowDataMode::is_enabled(config) {
+            return Self::G711;
+        }
+        let sample_rate = config
+            .get("Media", "audio_sample_rate")
+            .and_then(|s| s.parse::<u32>().ok())
+            .map_or(AUDIO_SAMPLE_RATE, |r| r.clamp(8000, 48_000));
+        let channels = config
+            .get("Media", "audio_channels")
+            .and_then(|s| s.parse::<u16>().ok())
+            .map_or(1, |c| c.clamp(1, 2));
+        Self::new(sample_rate, channels)
+    }
+
+    /// SDP `fmtp` parameters advertising the channel preference.
+    #[must_use]
+    pub fn to_fmtp(&self) -> String {
+        format!("stereo={}", u8::from(self.channels > 1))
+    }
+
+    /// Format to send to a peer that offered `codec`: its `rtpmap` rate and
+    /// channel count, mono if its fmtp says `stereo=0`.
+    #[must_use]
+    pub fn from_remote(codec: &RtpCodec) -> Self {
+        let mono = codec.fmtp.as_deref().is_some_and(|fmtp| {
+            fmtp.split(';')
+                .any(|p| p.trim().eq_ignore_ascii_case("stereo=0"))
+        });
+        let channels = if mono { 1 } else { codec.channels.clamp(1, 2) };
+        Self::new(codec.clock_rate, channels)
+    }
+
+    /// Samples per channel in one RTP packet: 20 ms, shortened so a 16 bit
+    /// payload stays under the MTU.
+    #[must_use]
+    pub fn packet_samples(&self) -> usize {
+        let mut ms = PACKET_MS;
+        while ms > MIN_PACKET_MS && self.payload_bytes(ms) > MAX_AUDIO_PAYLOAD {
+            ms /= 2;
+        }
+        (self.sample_rate * ms / 1000) as usize
+    }
+
+    const fn payload_bytes(&self, ms: u32) -> usize {
+        (self.sample_rate * ms / 1000) as usize * self.channels as usize * 2
+    }
+}
+
+/// One packet worth of encoded audio.
+#[derive(Debug)]
+pub struct EncodedAudio {
+    pub payload: Vec<u8>,
+    /// Samples per channel, i.e. the RTP clock advance.
+    pub samples: u32,
+}
+
+/// Send path: converts captured audio to the negotiated format, cuts it into
+/// packets and encodes them.
+#[derive(Debug)]
+pub struct AudioEncoder {
+    codec: CodecSpec,
+    resampler: Resampler,
+    pending: Vec<f32>,
+}
+
+impl Default for AudioEncoder {
+    fn default() -> Self {
+        Self::new(CodecSpec::G711U, AudioFormat::G711)
+    }
+}
+
+impl AudioEncoder {
+    #[must_use]
+    pub const fn new(codec: CodecSpec, format: AudioFormat) -> Self {
+        Self {
+            codec,
+            resampler: Resampler::new(format),
+            pending: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub const fn codec(&self) -> CodecSpec {
+        self.codec
+    }
+
+    #[must_use]
+    pub const fn format(&self) -> AudioFormat {
+        self.resampler.output_format()
+    }
+
+    /// Feeds captured samples (interleaved in `from`); returns the packets
+    /// that are complete.
+    pub fn push(&mut self, from: AudioFormat, samples: &[f32]) -> Vec<EncodedAudio> {
+        let format = self.format();
+        self.pending.extend(self.resampler.process(from, samples));
+        let per_packet = format.packet_samples();
+        let chunk = per_packet * usize::from(format.channels.max(1));
+        if chunk == 0 {
+            return Vec::new();
+        }
+
+        let mut out = Vec::new();
+        while self.pending.len() >= chunk {
+            let pcm: Vec<f32> = self.pending.drain(..chunk).collect();
+            let payload = match self.codec {
+                CodecSpec::L16 => audio_codec::encode_l16(&pcm),
+                _ => audio_codec::encode(&pcm),
+            };
+            out.push(EncodedAudio {
+                payload,
+                samples: u32::try_from(per_packet).unwrap_or(u32::MAX),
+            });
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn remote_format_and_packet_size() {
+        let mut codec = RtpCodec::with_name(97, 48_000, "L16").with_channels(2);
+        assert_eq!(
+            AudioFormat::from_remote(&codec),
+            AudioFormat::new(48_000, 2)
+        );
+        codec.fmtp = Some("stereo=0".into());
+        assert_eq!(
+            AudioFormat::from_remote(&codec),
+            AudioFormat::new(48_000, 1)
+        );
+
+        assert_eq!(AudioFormat::G711.packet_samples(), 160);
+        assert_eq!(AudioFormat::new(16_000, 1).packet_samples(), 320);
+        // 20 ms of 48 kHz stereo is 3840 bytes: down to 5 ms (960 bytes)
+        assert_eq!(AudioFormat::new(48_000, 2).packet_samples(), 240);
+        assert_eq!(AudioFormat::new(48_000, 2).to_fmtp(), "stereo=1");
+    }
+
+    #[test]
+    fn encoder_cuts_device_audio_into_packets() {
+        let mut enc = AudioEncoder::new(CodecSpec::L16, AudioFormat::new(16_000, 1));
+        let device = AudioFormat::new(48_000, 2);
+        // 10 ms of device audio: half a packet
+        assert!(enc.push(device, &[0.5; 960]).is_empty());
+        let packets = enc.push(device, &[0.5; 960]);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].samples, 320);
+        assert_eq!(packets[0].payload.len(), 640);
+
+        let mut g711 = AudioEncoder::default();
+        let packets = g711.push(AudioFormat::G711, &[0.0; 400]);
+        assert_eq!(packets.len(), 2);
+        assert!(packets.iter().all(|p| p.payload.len() == 160));
+    }
+}
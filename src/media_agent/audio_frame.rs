@@ -3,9 +3,9 @@ use std::sync::Arc;
 /// Represents a single audio frame with associated metadata.
 #[derive(Debug, Clone)]
 pub struct AudioFrame {
-    /// The raw audio samples (mono, f32).
+    /// The raw audio samples (f32, interleaved when there are several channels).
     pub data: Arc<Vec<f32>>,
-    /// Number of samples in this frame.
+    /// Number of samples per channel in this frame.
     pub samples: usize,
     /// Sample rate in Hz (e.g., 48000).
     pub sample_rate: u32,
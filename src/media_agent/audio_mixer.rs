@@ -0,0 +1,193 @@
+//! Playout mixer: sums the decoded audio of several remote tracks into the
+//! one stream the output device plays.
+//!
+//! Each source has its own queue, already converted to the device format,
+//! and its own gain. The device callback pulls from every queue at once, so
+//! a source that runs dry (loss, a peer on mute) plays silence without
+//! holding the others back. Sums beyond full scale go through a soft
+//! limiter instead of clipping hard.
+//!
+//! A source named `group/part` belongs to `group`: the gain set for the
+//! group applies to it as well, e.g. to the extra streams an SFU relays
+//! over one remote peer's connection.
+
+use std::collections::{HashMap, VecDeque};
+
+/// The source a single remote peer's audio is played as.
+pub const REMOTE_SOURCE: &str = "remote";
+/// Highest gain a source can be given (+12 dB).
+pub const MAX_GAIN: f32 = 4.0;
+/// Level up to which the limiter leaves samples alone.
+const LIMIT_KNEE: f32 = 0.8;
+
+#[derive(Debug)]
+struct Source {
+    queue: VecDeque<f32>,
+    gain: f32,
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            gain: 1.0,
+        }
+    }
+}
+
+/// Per-source playout queues and gains, mixed down on demand.
+#[derive(Debug, Default)]
+pub struct AudioMixer {
+    sources: HashMap<String, Source>,
+}
+
+impl AudioMixer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `samples` of `source`, keeping at most `max_buffer` of them by
+    /// dropping the oldest. Returns how many were dropped.
+    pub fn push(&mut self, source: &str, samples: Vec<f32>, max_buffer: usize) -> usize {
+        let queue = &mut self.source_mut(source).queue;
+        let overflow = (queue.len() + samples.len()).saturating_sub(max_buffer);
+        let from_queue = overflow.min(queue.len());
+        queue.drain(..from_queue);
+        // More than fits in one go: only the newest part is kept
+        let skip = overflow - from_queue;
+        queue.extend(samples.into_iter().skip(skip));
+        overflow
+    }
+
+    /// Samples queued for `source`.
+    #[must_use]
+    pub fn buffered(&self, source: &str) -> usize {
+        self.sources.get(source).map_or(0, |s| s.queue.len())
+    }
+
+    /// Sets the gain of `source` and the sources of its group, clamped to
+    /// `0.0..=MAX_GAIN`. A gain set before the source's first samples is
+    /// kept for them.
+    pub fn set_gain(&mut self, source: &str, gain: f32) {
+        let gain = if gain.is_nan() {
+            1.0
+        } else {
+            gain.clamp(0.0, MAX_GAIN)
+        };
+        self.source_mut(source).gain = gain;
+        for (name, s) in &mut self.sources {
+            if name
+                .strip_prefix(source)
+                .is_some_and(|rest| rest.starts_with('/'))
+            {
+                s.gain = gain;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn gain(&self, source: &str) -> f32 {
+        self.sources.get(source).map_or(1.0, |s| s.gain)
+    }
+
+    /// Forgets `source`, its queued samples and its gain.
+    pub fn remove(&mut self, source: &str) {
+        self.sources.remove(source);
+    }
+
+    /// Drops every queued sample, keeping the gains.
+    pub fn clear(&mut self) {
+        for source in self.sources.values_mut() {
+            source.queue.clear();
+        }
+    }
+
+    /// Fills `out` with the next samples of every source, scaled by their
+    /// gains, summed and limited. Missing samples are silence.
+    pub fn mix_into(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        for source in self.sources.values_mut() {
+            let take = out.len().min(source.queue.len());
+            for (sample, s) in out.iter_mut().zip(source.queue.drain(..take)) {
+                *sample += s * source.gain;
+            }
+        }
+        for sample in out.iter_mut() {
+            *sample = soft_clip(*sample);
+        }
+    }
+
+    fn source_mut(&mut self, source: &str) -> &mut Source {
+        // A new member of a group starts at the group's gain
+        let gain = source
+            .split_once('/')
+            .map_or(1.0, |(group, _)| self.gain(group));
+        self.sources
+            .entry(source.to_owned())
+            .or_insert_with(|| Source {
+                gain,
+                ..Source::default()
+            })
+    }
+}
+
+/// Passes samples up to [`LIMIT_KNEE`] through and bends louder ones
+/// smoothly towards full scale, so they never reach it.
+#[must_use]
+pub fn soft_clip(x: f32) -> f32 {
+    let level = x.abs();
+    if level <= LIMIT_KNEE {
+        return x;
+    }
+    let headroom = 1.0 - LIMIT_KNEE;
+    (LIMIT_KNEE + headroom * ((level - LIMIT_KNEE) / headroom).tanh()).copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn sources_are_summed_with_their_gains() {
+        let mut mixer = AudioMixer::new();
+        mixer.set_gain("bob", 0.5);
+        mixer.push("alice", vec![0.1, 0.2, 0.3], 100);
+        mixer.push("bob", vec![0.2], 100);
+
+        let mut out = [1.0; 4];
+        mixer.mix_into(&mut out);
+        // Bob ran dry after one sample; the tail is silence
+        assert_eq!(out, [0.2, 0.2, 0.3, 0.0]);
+        assert_eq!(mixer.buffered("alice"), 0);
+        assert_eq!(mixer.gain("bob"), 0.5);
+
+        // Only the newest samples fit
+        assert_eq!(mixer.push("alice", vec![0.1, 0.2, 0.3], 2), 1);
+        mixer.mix_into(&mut out[..2]);
+        assert_eq!(out[..2], [0.2, 0.3]);
+    }
+
+    #[test]
+    fn group_gains_apply_to_their_streams() {
+        let mut mixer = AudioMixer::new();
+        mixer.set_gain("remote", 0.5);
+        mixer.push("remote/0000000b", vec![0.2], 100);
+        assert_eq!(mixer.gain("remote/0000000b"), 0.5);
+        mixer.set_gain("remote", 0.0);
+        assert_eq!(mixer.gain("remote/0000000b"), 0.0);
+        assert_eq!(mixer.gain("remoteb"), 1.0);
+    }
+
+    #[test]
+    fn loud_sums_are_limited_below_full_scale() {
+        assert_eq!(soft_clip(0.5), 0.5);
+        assert_eq!(soft_clip(-0.8), -0.8);
+        let loud = soft_clip(1.6);
+        assert!(loud > 0.95 && loud < 1.0, "{loud}");
+        assert_eq!(soft_clip(-1.6), -loud);
+        // Still increasing, so loud passages keep their shape
+        assert!(soft_clip(1.2) < loud);
+    }
+}
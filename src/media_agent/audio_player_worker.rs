@@ -1,39 +1,69 @@
 use std::{
-    collections::VecDeque,
+    collections::HashMap,
     sync::{
         Arc, Mutex,
         atomic::{AtomicBool, Ordering},
         mpsc::{Receiver, RecvTimeoutError},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 
-use crate::{log::log_sink::LogSink, sink_debug, sink_error, sink_info, sink_trace, sink_warn};
+use crate::{
+    log::log_sink::LogSink,
+    media_agent::{
+        audio_devices::output_device, audio_format::AudioFormat, audio_mixer::AudioMixer,
+        drift_compensator::DriftCompensator, resampler::Resampler,
+    },
+    sink_debug, sink_error, sink_info, sink_trace, sink_warn,
+};
 
 /// Commands sent from the MediaAgent to the AudioPlayerWorker.
 pub enum AudioPlayerCommand {
-    /// Play a chunk of decoded audio samples.
-    PlayFrame(Vec<f32>),
+    /// Play a chunk of decoded audio samples of `source`, interleaved in
+    /// `format`. They are converted to the device format before buffering
+    /// and mixed with the other sources.
+    PlayFrame {
+        source: String,
+        samples: Vec<f32>,
+        format: AudioFormat,
+    },
+    /// Scale `source` by `gain` (1.0 = as received, 0.0 = silent).
+    SetGain { source: String, gain: f32 },
+    /// Stop playing `source` and drop what is buffered of it.
+    RemoveSource(String),
+    /// Move playback to another output device (`None` = default). Buffered
+    /// audio is kept, so the switch is seamless apart from the device gap.
+    SwitchDevice(Option<String>),
 }
 
-/// Max buffer size in samples before dropping data to reduce latency.
-/// 8kHz * 0.5s = 4000 samples.
-const MAX_BUFFER_SIZE: usize = 4000;
+type SharedMixer = Arc<Mutex<AudioMixer>>;
+
+/// Max buffered audio per source before dropping data to reduce latency.
+/// Clock drift is corrected long before this; it only catches bursts.
+const MAX_BUFFER_MS: usize = 500;
+
+/// How often the measured clock drift is logged.
+const DRIFT_LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Device format used when the host does not report one.
+const FALLBACK_FORMAT: AudioFormat = AudioFormat::G711;
 
 #[allow(clippy::expect_used)]
 /// Spawns the audio player worker.
 ///
-/// This worker manages the audio output device and a jitter buffer.
-/// It receives decoded audio frames via `command_rx` and plays them.
+/// This worker manages the audio output device and an [`AudioMixer`] with
+/// one jitter buffer per source. It receives decoded audio frames via
+/// `command_rx` and plays them, mixed.
 ///
 /// # Arguments
 ///
 /// * `logger` - Logger instance.
 /// * `command_rx` - Channel to receive playback commands.
 /// * `running` - Atomic flag to control the worker's lifecycle.
+/// * `device` - Output device name; `None` (or a missing device) uses the default.
 ///
 /// # Returns
 ///
@@ -42,89 +72,72 @@ pub fn spawn_audio_player_worker(
     logger: Arc<dyn LogSink>,
     command_rx: Receiver<AudioPlayerCommand>,
     running: Arc<AtomicBool>,
+    device: Option<String>,
 ) -> JoinHandle<()> {
     sink_info!(logger, "[AudioPlayer] Starting...");
 
     thread::Builder::new()
         .name("media-agent-audio-player".into())
         .spawn(move || {
-            let host = cpal::default_host();
-            let device = match host.default_output_device() {
-                Some(d) => d,
-                None => {
-                    sink_error!(logger, "[AudioPlayer] No default output device found");
-                    return;
-                }
-            };
-
-            sink_info!(logger, "[AudioPlayer] Using output device: {}", device.name().unwrap_or_default());
-
-            let config = cpal::StreamConfig {
-                channels: 1,
-                sample_rate: cpal::SampleRate(8000),
-                buffer_size: cpal::BufferSize::Default,
-            };
-
-            // Shared buffer between the event loop (producer) and the audio callback (consumer).
-            let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(MAX_BUFFER_SIZE * 2)));
-            let buffer_cb = buffer.clone();
-
-            let logger_cb = logger.clone();
-
-            let err_fn = move |err| {
-                sink_warn!(logger_cb, "[AudioPlayer] Stream error: {}", err);
-            };
-
-            let stream = match device.build_output_stream(
-                &config,
-                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                    let mut buf = buffer_cb.lock().expect("audio buffer lock poisoned");
-                    for sample in data.iter_mut() {
-                        if let Some(s) = buf.pop_front() {
-                            *sample = s;
-                        } else {
-                            // Buffer empty (underrun), play silence
-                            *sample = 0.0;
-                        }
-                    }
-                },
-                err_fn,
-                None,
-            ) {
-                Ok(s) => s,
-                Err(e) => {
-                    sink_error!(logger, "[AudioPlayer] Failed to build output stream: {}", e);
-                    return;
-                }
-            };
-
-            if let Err(e) = stream.play() {
-                sink_error!(logger, "[AudioPlayer] Failed to play stream: {}", e);
-                return;
-            }
+            // Shared between the event loop (producer) and the audio callback (consumer).
+            let mixer: SharedMixer = Arc::new(Mutex::new(AudioMixer::new()));
 
-            sink_debug!(logger, "[AudioPlayer] Playback started");
+            // Kept alive for as long as it plays; replaced on SwitchDevice.
+            let (mut stream, mut format) = open_output_stream(&logger, device.as_deref(), &mixer);
+            // Per source: conversion to the device format, and a steady
+            // buffer level despite sender/device clock drift
+            let mut sources: HashMap<String, (Resampler, DriftCompensator)> = HashMap::new();
+            let mut last_drift_log = Instant::now();
 
             while running.load(Ordering::Relaxed) {
                 // Poll for commands
                 match command_rx.recv_timeout(Duration::from_millis(100)) {
                     Ok(cmd) => match cmd {
-                        AudioPlayerCommand::PlayFrame(samples) => {
-                            let mut buf = buffer.lock().expect("audio buffer lock poisoned");
+                        AudioPlayerCommand::PlayFrame { source, samples, format: from } => {
+                            let (resampler, drift) = sources.entry(source.clone()).or_insert_with(|| {
+                                (Resampler::new(format), DriftCompensator::new(format))
+                            });
+                            let samples = resampler.process(from, &samples);
+                            let max_buffer = format.sample_rate as usize
+                                * usize::from(format.channels)
+                                * MAX_BUFFER_MS
+                                / 1000;
+                            let mut mix = mixer.lock().expect("audio mixer lock poisoned");
+                            let samples = drift.process(mix.buffered(&source), samples);
+                            if last_drift_log.elapsed() >= DRIFT_LOG_INTERVAL {
+                                last_drift_log = Instant::now();
+                                sink_info!(logger, "[AudioPlayer] Clock drift of {}: {:+.0} ppm", source, drift.drift_ppm());
+                            }
 
-                            // Latency control: if buffer is too full, drop old data
-                            let current_len = buf.len();
+                            // Latency control: if the buffer is too full, old data is dropped
                             let incoming_len = samples.len();
-
-                            if current_len + incoming_len > MAX_BUFFER_SIZE {
-                                let drop_count = (current_len + incoming_len) - MAX_BUFFER_SIZE;
-                                let to_drop = drop_count.min(current_len);
-                                sink_trace!(logger, "[AudioPlayer] Buffer full, dropping {} samples for latency catch-up", drop_count);
-                                buf.drain(0..to_drop);
+                            let dropped = mix.push(&source, samples, max_buffer);
+                            if dropped > 0 {
+                                sink_trace!(logger, "[AudioPlayer] Buffer of {} full, dropped {} samples for latency catch-up", source, dropped);
+                            }
+                            sink_trace!(logger, "[AudioPlayer] Buffered {} samples of {}. Total buffered: {}", incoming_len, source, mix.buffered(&source));
+                        }
+                        AudioPlayerCommand::SetGain { source, gain } => {
+                            sink_debug!(logger, "[AudioPlayer] Gain of {}: {:.2}", source, gain);
+                            mixer.lock().expect("audio mixer lock poisoned").set_gain(&source, gain);
+                        }
+                        AudioPlayerCommand::RemoveSource(source) => {
+                            sink_debug!(logger, "[AudioPlayer] Removing source {}", source);
+                            sources.remove(&source);
+                            mixer.lock().expect("audio mixer lock poisoned").remove(&source);
+                        }
+                        AudioPlayerCommand::SwitchDevice(name) => {
+                            // Release the old device before opening the new one
+                            drop(stream.take());
+                            let (new_stream, new_format) =
+                                open_output_stream(&logger, name.as_deref(), &mixer);
+                            stream = new_stream;
+                            if new_format != format {
+                                // Buffered samples are in the old device format
+                                format = new_format;
+                                mixer.lock().expect("audio mixer lock poisoned").clear();
+                                sources.clear();
                             }
-
-                            buf.extend(samples);
-                            sink_trace!(logger, "[AudioPlayer] Buffered {} samples. Total buffered: {}", incoming_len, buf.len());
                         }
                     },
                     Err(RecvTimeoutError::Timeout) => {
@@ -137,7 +150,76 @@ pub fn spawn_audio_player_worker(
                 }
             }
 
+            drop(stream);
             sink_debug!(logger, "[AudioPlayer] Stopped");
         })
         .expect("spawn media-agent-audio-player")
 }
+
+/// Opens `name` (or the default output device), in its default format, and
+/// starts playing the mix of `mixer`.
+///
+/// Returns the stream, or `None` after logging if the device cannot be used,
+/// and the format the mixer's sources must be in.
+#[allow(clippy::expect_used)]
+fn open_output_stream(
+    logger: &Arc<dyn LogSink>,
+    name: Option<&str>,
+    mixer: &SharedMixer,
+) -> (Option<cpal::Stream>, AudioFormat) {
+    let Some(device) = output_device(name) else {
+        sink_error!(logger, "[AudioPlayer] No output device found");
+        return (None, FALLBACK_FORMAT);
+    };
+
+    let format = device.default_output_config().map_or(FALLBACK_FORMAT, |c| {
+        AudioFormat::new(c.sample_rate().0, c.channels())
+    });
+    sink_info!(
+        logger,
+        "[AudioPlayer] Using output device: {} ({} Hz, {} channel(s))",
+        device.name().unwrap_or_default(),
+        format.sample_rate,
+        format.channels
+    );
+
+    let config = cpal::StreamConfig {
+        channels: format.channels,
+        sample_rate: cpal::SampleRate(format.sample_rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let mixer_cb = mixer.clone();
+    let logger_cb = logger.clone();
+
+    let err_fn = move |err| {
+        sink_warn!(logger_cb, "[AudioPlayer] Stream error: {}", err);
+    };
+
+    let stream = match device.build_output_stream(
+        &config,
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            // Sources that underrun play silence
+            mixer_cb
+                .lock()
+                .expect("audio mixer lock poisoned")
+                .mix_into(data);
+        },
+        err_fn,
+        None,
+    ) {
+        Ok(s) => s,
+        Err(e) => {
+            sink_error!(logger, "[AudioPlayer] Failed to build output stream: {}", e);
+            return (None, format);
+        }
+    };
+
+    if let Err(e) = stream.play() {
+        sink_error!(logger, "[AudioPlayer] Failed to play stream: {}", e);
+        return (None, format);
+    }
+
+    sink_debug!(logger, "[AudioPlayer] Playback started");
+    (Some(stream), format)
+}
@@ -1,10 +1,13 @@
 use crate::{
     camera_manager::{
-        camera_error::CameraError, camera_manager_c::CameraManager, utils::tight_rgb_bytes,
+        camera_error::CameraError, camera_manager_c::CameraManager,
+        capture_settings::CaptureSettings, utils::tight_rgb_bytes,
     },
+    core::events::EngineEvent,
     log::log_sink::LogSink,
     logger_error, logger_warn,
     media_agent::{
+        constants::{CAMERA_LOST_AFTER_FAILURES, CAMERA_RETRY_INTERVAL},
         frame_format::FrameFormat,
         media_agent_error::{MediaAgentError, Result},
         utils::now_millis,
@@ -23,6 +26,15 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Why [`camera_loop`] returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CameraLoopExit {
+    /// `running` was cleared or the frame receiver hung up.
+    Stopped,
+    /// The device stopped delivering frames (unplugged, driver failure).
+    Lost(String),
+}
+
 /// Runs the main capture loop for a physical camera device.
 ///
 /// This function continuously captures frames from the provided `CameraManager`,
@@ -32,29 +44,31 @@ use std::{
 /// # error handling
 ///
 /// * Non-critical errors (e.g., `NotFrame`, `CaptureFailed`) are logged as warnings,
-///   and the loop continues.
-/// * Critical errors (e.g., `CameraOff`) are logged as errors.
+///   and the loop continues. After [`CAMERA_LOST_AFTER_FAILURES`] of them in a row
+///   the device is considered gone and [`CameraLoopExit::Lost`] is returned.
+/// * Critical errors (e.g., `CameraOff`) return [`CameraLoopExit::Lost`] right away.
 /// * Conversion errors propagate and will terminate the loop.
 ///
 /// # Errors
 ///
 /// Returns a [`MediaAgentError`] if:
 /// * The frame conversion from OpenCV BGR to internal RGB fails.
-/// * Any underlying OpenCV operation returns a critical failure that cannot be handled gracefully.
 pub fn camera_loop(
     logger: Arc<dyn LogSink>,
     mut cam: CameraManager,
     tx: Sender<VideoFrame>,
     target_fps: u32,
     running: Arc<AtomicBool>,
-) -> Result<()> {
+) -> Result<CameraLoopExit> {
     let fps = target_fps.clamp(1, 120);
     let period = Duration::from_millis(1000 / fps as u64);
     let mut next_deadline = Instant::now() + period;
+    let mut failures = 0u32;
 
     while running.load(Ordering::SeqCst) {
         match cam.get_frame() {
             Ok(frame) => {
+                failures = 0;
                 let w = cam.width();
                 let h = cam.height();
                 // Propagates conversion errors immediately
@@ -66,24 +80,22 @@ pub fn camera_loop(
                 }
             }
             Err(err) => match err {
-                CameraError::NotFrame | CameraError::CaptureFailed(_) => {
+                CameraError::CameraOff | CameraError::InitializationFailed(_) => {
+                    logger_error!(logger, "Critical camera error: {err}");
+                    return Ok(CameraLoopExit::Lost(err.to_string()));
+                }
+                _ => {
                     logger_warn!(
                         logger,
                         "Warning: camera did not return a valid frame: {}",
                         err
                     );
-                    // Log and continue; do not stop the app.
-                }
-                CameraError::CameraOff | CameraError::InitializationFailed(_) => {
-                    logger_error!(logger, "Critical camera error: {err}");
-                    // Potential recovery logic:
-                    // cam.reinit()?;
-                }
-                CameraError::OpenCvError(e) => {
-                    logger_error!(logger, "OpenCV error: {e}");
-                }
-                _ => {
-                    logger_error!(logger, "Unexpected camera error: {err}");
+                    failures += 1;
+                    if failures >= CAMERA_LOST_AFTER_FAILURES {
+                        return Ok(CameraLoopExit::Lost(format!(
+                            "{failures} consecutive capture failures ({err})"
+                        )));
+                    }
                 }
             },
         }
@@ -98,6 +110,75 @@ pub fn camera_loop(
         }
     }
 
+    Ok(CameraLoopExit::Stopped)
+}
+
+/// Keeps a camera source alive across unplugs and driver failures.
+///
+/// Runs [`camera_loop`] while the device works. When it is lost (or could
+/// not be opened in the first place), placeholder frames are sent so the
+/// outbound video keeps flowing, and the device is re-opened every
+/// [`CAMERA_RETRY_INTERVAL`]. Loss and recovery are reported on `events`.
+///
+/// # Errors
+///
+/// Propagates frame conversion errors from [`camera_loop`].
+fn supervise_camera(
+    logger: &Arc<dyn LogSink>,
+    camera_id: i32,
+    mut cam: Option<CameraManager>,
+    tx: &Sender<VideoFrame>,
+    settings: &CaptureSettings,
+    running: &Arc<AtomicBool>,
+    events: Option<&Sender<EngineEvent>>,
+) -> Result<()> {
+    let fps = settings.fps.clamp(1, 120);
+    let period = Duration::from_millis(1_000 / fps as u64);
+    let mut phase = 0u8;
+    let mut next_retry = Instant::now() + CAMERA_RETRY_INTERVAL;
+
+    while running.load(Ordering::SeqCst) {
+        if let Some(device) = cam.take() {
+            match camera_loop(logger.clone(), device, tx.clone(), fps, running.clone())? {
+                CameraLoopExit::Stopped => break,
+                CameraLoopExit::Lost(reason) => {
+                    logger_error!(
+                        logger,
+                        "Camera {camera_id} lost: {reason}. Using placeholder frames."
+                    );
+                    if let Some(events) = events {
+                        let _ = events.send(EngineEvent::CameraLost { camera_id, reason });
+                    }
+                    next_retry = Instant::now() + CAMERA_RETRY_INTERVAL;
+                }
+            }
+            continue;
+        }
+
+        if tx.send(VideoFrame::synthetic_rgb(320, 240, phase)).is_err() {
+            break;
+        }
+        phase = phase.wrapping_add(1);
+
+        if Instant::now() >= next_retry {
+            next_retry = Instant::now() + CAMERA_RETRY_INTERVAL;
+            if let Ok(device) = CameraManager::with_settings(camera_id, settings, logger.clone()) {
+                sink_info!(
+                    logger,
+                    "[CameraWorker] camera {} opened ({}x{})",
+                    camera_id,
+                    device.width(),
+                    device.height()
+                );
+                if let Some(events) = events {
+                    let _ = events.send(EngineEvent::CameraRecovered { camera_id });
+                }
+                cam = Some(device);
+                continue;
+            }
+        }
+        thread::sleep(period);
+    }
     Ok(())
 }
 
@@ -167,12 +248,12 @@ pub fn synthetic_loop(
 /// Initializes and spawns the camera background worker.
 ///
 /// Tries to open the physical camera specified by `camera_id`. If successful, spawns
-/// a thread running [`camera_loop`]. If the camera fails to open, it falls back to
-/// spawning a thread running [`synthetic_loop`].
+/// a thread running [`camera_loop`]. If the camera fails to open (or is lost later),
+/// the thread sends a test pattern instead and keeps trying to re-open the device.
 ///
 /// # Arguments
 ///
-/// * `target_fps` - Desired frame rate.
+/// * `settings` - Requested resolution, frame rate and pixel format.
 /// * `logger` - Logger instance.
 /// * `camera_id` - OpenCV camera index (usually 0 for default webcam).
 /// * `running` - Atomic flag to control the worker's lifecycle.
@@ -184,53 +265,65 @@ pub fn synthetic_loop(
 /// 2. `Option<String>`: A status message describing the initialized source (Camera resolution or Error).
 /// 3. `Option<JoinHandle<()>>`: The handle to the spawned background thread.
 pub fn spawn_camera_worker(
-    target_fps: u32,
+    settings: CaptureSettings,
     logger: Arc<dyn LogSink>,
     camera_id: i32,
     running: Arc<AtomicBool>,
 ) -> (Receiver<VideoFrame>, Option<String>, Option<JoinHandle<()>>) {
-    sink_info!(logger, "[CameraWorker] Starting camera worker");
     let (local_frame_tx, local_frame_rx) = mpsc::channel();
+    let (status, handle) =
+        spawn_camera_worker_into(settings, logger, camera_id, running, local_frame_tx, None);
+    (local_frame_rx, status, handle)
+}
+
+/// Like [`spawn_camera_worker`], but sends frames into an existing channel
+/// and reports camera loss/recovery on `events`.
+///
+/// Used to swap the capture device mid-call without touching the consumer
+/// side of the channel.
+pub fn spawn_camera_worker_into(
+    settings: CaptureSettings,
+    logger: Arc<dyn LogSink>,
+    camera_id: i32,
+    running: Arc<AtomicBool>,
+    local_frame_tx: Sender<VideoFrame>,
+    events: Option<Sender<EngineEvent>>,
+) -> (Option<String>, Option<JoinHandle<()>>) {
+    sink_info!(
+        logger,
+        "[CameraWorker] Starting camera worker (device {camera_id})"
+    );
 
     // Attempt to initialize physical hardware
-    let camera_manager = CameraManager::new(camera_id, logger.clone());
+    let camera_manager = CameraManager::with_settings(camera_id, &settings, logger.clone());
 
     let status = match &camera_manager {
         Ok(cam) => Some(format!(
-            "Using camera source with resolution {}x{}",
+            "Using camera source with resolution {}x{}@{}",
             cam.width(),
-            cam.height()
+            cam.height(),
+            cam.fps()
         )),
         Err(e) => Some(format!("Camera error: {}. Using test pattern.", e)),
     };
 
-    let log_for_cam = logger.clone();
-    let log_for_synthetic = logger.clone();
-
     let handle = thread::Builder::new()
         .name("media-agent-camera".into())
         .spawn(move || {
-            // Select strategy based on initialization success
-            if let Ok(cam) = camera_manager {
-                if let Err(e) = camera_loop(
-                    log_for_cam,
-                    cam,
-                    local_frame_tx,
-                    target_fps,
-                    running.clone(),
-                ) {
-                    logger_error!(logger, "camera loop stopped: {e:?}");
-                }
-            } else if let Err(e) = synthetic_loop(
-                log_for_synthetic,
-                local_frame_tx,
-                target_fps,
-                running.clone(),
+            // Without a device, start on placeholder frames and keep retrying
+            if let Err(e) = supervise_camera(
+                &logger,
+                camera_id,
+                camera_manager.ok(),
+                &local_frame_tx,
+                &settings,
+                &running,
+                events.as_ref(),
             ) {
-                logger_error!(logger, "synthetic loop stopped: {e:?}");
+                logger_error!(logger, "camera loop stopped: {e:?}");
             }
         })
         .ok();
 
-    (local_frame_rx, status, handle)
+    (status, handle)
 }
@@ -1,5 +1,14 @@
 pub const TARGET_FPS: u32 = 30;
 pub const BITRATE: u32 = 1_500_000;
 pub const KEYINT: u32 = 90;
+/// Largest H.264 slice the encoder aims for: a 1200-byte datagram minus
+/// the SRTP tag and the RTP header, so a slice never needs FU-A fragments.
+pub const SLICE_MAX_BYTES: u32 = 1178;
 pub const DEFAULT_CAMERA_ID: i32 = 0;
 pub const CHANNELS_TIMEOUT: u64 = 50;
+/// Consecutive failed reads after which a camera is considered lost.
+pub const CAMERA_LOST_AFTER_FAILURES: u32 = 30;
+/// How often a lost (or missing) camera is re-opened.
+pub const CAMERA_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// Sample rate of G.711 audio, and of the audio in recordings.
+pub const AUDIO_SAMPLE_RATE: u32 = 8000;
@@ -5,5 +5,7 @@ pub enum DecoderEvent {
     AnnexBFrameReady {
         codec_spec: CodecSpec,
         bytes: Vec<u8>,
+        /// Arrival of the frame's first packet (UNIX milliseconds).
+        received_ms: u128,
     },
 }
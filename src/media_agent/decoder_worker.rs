@@ -5,15 +5,18 @@ use std::{
         mpsc::{Receiver, RecvTimeoutError, Sender},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
+    config::Config,
+    core::latency::{LatencyStage, LatencyTracer},
     log::log_sink::LogSink,
     logger_debug, logger_error,
     media_agent::{
         constants::CHANNELS_TIMEOUT, decoder_event::DecoderEvent, events::MediaAgentEvent,
-        frame_format::FrameFormat, h264_decoder::H264Decoder, spec::CodecSpec,
+        frame_format::FrameFormat, keyframe_gate::KeyframeGate, parameter_sets::ParameterSetCache,
+        spec::CodecSpec, video_decoder::VideoDecoder,
     },
     sink_debug, sink_info, sink_trace,
 };
@@ -33,8 +36,15 @@ const FRAME_FORMAT: FrameFormat = FrameFormat::Yuv420;
 /// 1. **Input**: Receives `DecoderEvent::AnnexBFrameReady` containing NAL units.
 /// 2. **Process**:
 ///    - Inspects NAL headers for diagnostic logging (identifying Keyframes/IDR, SPS, PPS).
-///    - Feeds data to the underlying decoder (e.g., OpenH264 or FFmpeg wrapper).
-/// 3. **Output**: Sends `MediaAgentEvent::DecodedVideoFrame` containing the raw YUV image.
+///    - Caches the latest SPS/PPS and puts them back in front of IDR frames
+///      that arrive without them.
+///    - Feeds data to the underlying decoder (hardware through FFmpeg if available, OpenH264 otherwise).
+///    - After a decode error, drops frames until the next IDR (the last good
+///      frame stays on screen) and asks for a keyframe with
+///      `MediaAgentEvent::KeyframeNeeded`.
+/// 3. **Output**: Sends `MediaAgentEvent::DecodedVideoFrame` containing the raw YUV image,
+///    stamped with the arrival time of the frame's first packet so the render
+///    latency can be measured against it.
 ///
 /// # Lifecycle
 ///
@@ -48,6 +58,8 @@ const FRAME_FORMAT: FrameFormat = FrameFormat::Yuv420;
 /// * `ma_decoder_event_rx` - Channel receiver for incoming encoded data packets.
 /// * `media_agent_event_tx` - Channel sender for outgoing decoded video frames.
 /// * `running` - Atomic flag to control the shutdown of the worker thread.
+/// * `config` - Application configuration (`[Media] hw_codec`).
+/// * `latency` - Where the decode stage of each frame is recorded.
 ///
 /// # Panics
 ///
@@ -58,18 +70,26 @@ pub fn spawn_decoder_worker(
     ma_decoder_event_rx: Receiver<DecoderEvent>,
     media_agent_event_tx: Sender<MediaAgentEvent>,
     running: Arc<AtomicBool>,
+    config: Arc<Config>,
+    latency: LatencyTracer,
 ) -> JoinHandle<()> {
     sink_info!(logger, "[Decoder] Starting...");
     thread::Builder::new()
         .name("media-agent-decoder".into())
         .spawn(move || {
-            let mut h264_decoder = H264Decoder::new(logger.clone());
+            let mut h264_decoder = VideoDecoder::new(logger.clone(), &config);
+            let mut parameter_sets = ParameterSetCache::new();
+            let mut keyframe_gate = KeyframeGate::new();
 
             while running.load(Ordering::Relaxed){
                 match ma_decoder_event_rx.recv_timeout(Duration::from_millis(CHANNELS_TIMEOUT)) {
                     Ok(event) => {
                         match event {
-                            DecoderEvent::AnnexBFrameReady { codec_spec, bytes } => {
+                            DecoderEvent::AnnexBFrameReady {
+                                codec_spec,
+                                bytes,
+                                received_ms,
+                            } => {
                                 // --- Diagnostic Logging (NAL Inspection) ---
                                 if bytes.len() > 4 {
                                     let nal_type = bytes[4] & 0x1F;
@@ -110,11 +130,24 @@ pub fn spawn_decoder_worker(
                                             bytes.len(),
                                             &bytes[..bytes.len().min(12)]
                                         );
-                                        let t0 = std::time::Instant::now();
+                                        // Put back the SPS/PPS an IDR came without
+                                        let bytes = parameter_sets.prepare(&bytes);
+                                        if !keyframe_gate.admits(&bytes) {
+                                            // Predicted from a frame that failed: would only show corruption
+                                            sink_trace!(logger, "[Decoder] Dropping frame while waiting for a keyframe");
+                                            if keyframe_gate.on_dropped(Instant::now()) {
+                                                let _ = media_agent_event_tx.send(MediaAgentEvent::KeyframeNeeded);
+                                            }
+                                            continue;
+                                        }
+                                        let t0 = Instant::now();
 
                                         match h264_decoder.decode_frame(&bytes, FRAME_FORMAT) {
-                                            Ok(Some(frame)) => {
+                                            Ok(Some(mut frame)) => {
+                                                keyframe_gate.on_decoded();
                                                 let took = t0.elapsed();
+                                                latency.record_since(LatencyStage::Decode, received_ms);
+                                                frame.timestamp_ms = received_ms;
                                                 sink_trace!(
                                                     logger,
                                                     "[Decoder] Frame Ready sending MediaAgentEvent::DecodedVideoFrame"
@@ -142,6 +175,11 @@ pub fn spawn_decoder_worker(
                                                     bytes.len(),
                                                     &bytes[..bytes.len().min(12)]
                                                 );
+                                                // Drop it, keep the last good frame and ask for a keyframe
+                                                if keyframe_gate.on_error(Instant::now()) {
+                                                    sink_info!(logger, "[Decoder] Requesting a keyframe after a decode error");
+                                                    let _ = media_agent_event_tx.send(MediaAgentEvent::KeyframeNeeded);
+                                                }
                                             }
                                         }
                                     },
@@ -0,0 +1,199 @@
+//! Clock drift compensation for audio playout.
+//!
+//! The sender's capture clock and our output device clock never run at
+//! exactly the same speed: a 100 ppm difference is 360 ms per hour. Left
+//! alone, the playout buffer slowly grows (latency) or drains (underruns).
+//!
+//! The compensator watches the smoothed buffer level and, when it strays
+//! from the target, drops or inserts single sample frames spread over the
+//! incoming audio. Each correction merges or splits two neighbouring frames,
+//! so it is inaudible for speech; the speed change is capped at 1%. The net
+//! correction over time is the measured drift.
+
+use crate::media_agent::audio_format::AudioFormat;
+
+/// Buffer level the compensator steers towards.
+const TARGET_MS: usize = 60;
+/// No correction while the smoothed level is within this of the target.
+const DEAD_BAND_MS: usize = 20;
+/// Weight of the newest level in the moving average (about 50 chunks).
+const SMOOTHING: f64 = 0.02;
+/// One extra correction per this many frames of error.
+const FRAMES_PER_STEP: usize = 256;
+/// At most one correction per this many frames of audio (1%).
+const MAX_RATE_DIVISOR: usize = 100;
+
+/// Steers the playout buffer level by dropping or inserting frames.
+#[derive(Debug, Clone)]
+pub struct DriftCompensator {
+    channels: usize,
+    target: f64,
+    dead_band: f64,
+    /// Smoothed buffer level, in frames (samples per channel).
+    level: Option<f64>,
+    /// Frames inserted (positive) or dropped (negative) so far.
+    corrected: i64,
+    /// Frames received so far.
+    received: u64,
+}
+
+impl DriftCompensator {
+    /// Compensator for a buffer holding audio in `format`.
+    #[must_use]
+    pub fn new(format: AudioFormat) -> Self {
+        let frames_per_ms = f64::from(format.sample_rate) / 1000.0;
+        Self {
+            channels: usize::from(format.channels.max(1)),
+            target: TARGET_MS as f64 * frames_per_ms,
+            dead_band: DEAD_BAND_MS as f64 * frames_per_ms,
+            level: None,
+            corrected: 0,
+            received: 0,
+        }
+    }
+
+    /// Adjusts interleaved `samples` about to be appended to a buffer that
+    /// currently holds `buffered` samples.
+    pub fn process(&mut self, buffered: usize, samples: Vec<f32>) -> Vec<f32> {
+        let ch = self.channels;
+        let frames = samples.len() / ch;
+        self.received += frames as u64;
+
+        let now = (buffered / ch) as f64;
+        let level = match self.level {
+            Some(level) => level + SMOOTHING * (now - level),
+            None => now,
+        };
+        self.level = Some(level);
+
+        let error = level - self.target;
+        if error.abs() <= self.dead_band {
+            return samples;
+        }
+        let steps = ((error.abs() - self.dead_band) as usize / FRAMES_PER_STEP + 1)
+            .min(frames / MAX_RATE_DIVISOR);
+        if steps == 0 {
+            return samples;
+        }
+
+        let out = if error > 0.0 {
+            self.corrected -= steps as i64;
+            drop_frames(&samples, ch, steps)
+        } else {
+            self.corrected += steps as i64;
+            insert_frames(&samples, ch, steps)
+        };
+        // The corrections are now in the buffer: account for them right away
+        // instead of overshooting while the average catches up.
+        self.level = Some(level + out.len() as f64 / ch as f64 - frames as f64);
+        out
+    }
+
+    /// Measured drift of the sender's clock against the output clock, in
+    /// parts per million (positive: the sender is slower and frames had to be
+    /// inserted).
+    #[must_use]
+    pub fn drift_ppm(&self) -> f64 {
+        if self.received == 0 {
+            return 0.0;
+        }
+        self.corrected as f64 * 1e6 / self.received as f64
+    }
+}
+
+/// Positions (in frames) of `steps` corrections spread over `frames`.
+fn positions(frames: usize, steps: usize) -> impl Iterator<Item = usize> {
+    let spacing = frames / (steps + 1);
+    (1..=steps).map(move |i| i * spacing)
+}
+
+/// Merges `steps` pairs of neighbouring frames into their average.
+fn drop_frames(samples: &[f32], ch: usize, steps: usize) -> Vec<f32> {
+    let frames: Vec<&[f32]> = samples.chunks_exact(ch).collect();
+    let mut out = Vec::with_capacity(samples.len());
+    let mut at = positions(frames.len(), steps).peekable();
+    let mut i = 0;
+    while i < frames.len() {
+        if at.next_if_eq(&i).is_some() && i + 1 < frames.len() {
+            out.extend(
+                frames[i]
+                    .iter()
+                    .zip(frames[i + 1])
+                    .map(|(a, b)| (a + b) / 2.0),
+            );
+            i += 2;
+        } else {
+            out.extend_from_slice(frames[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Adds `steps` frames, each the average of its two neighbours.
+fn insert_frames(samples: &[f32], ch: usize, steps: usize) -> Vec<f32> {
+    let frames: Vec<&[f32]> = samples.chunks_exact(ch).collect();
+    let mut out = Vec::with_capacity(samples.len() + steps * ch);
+    let mut at = positions(frames.len(), steps).peekable();
+    for (i, frame) in frames.iter().enumerate() {
+        if at.next_if_eq(&i).is_some()
+            && let Some(prev) = i.checked_sub(1).map(|p| frames[p])
+        {
+            out.extend(prev.iter().zip(*frame).map(|(a, b)| (a + b) / 2.0));
+        }
+        out.extend_from_slice(frame);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    const FORMAT: AudioFormat = AudioFormat::new(48_000, 2);
+    /// 20 ms of 48 kHz stereo.
+    const CHUNK: usize = 960 * 2;
+
+    #[test]
+    fn steady_buffer_is_left_alone() {
+        let mut comp = DriftCompensator::new(FORMAT);
+        // 60 ms buffered, right on target
+        for _ in 0..100 {
+            assert_eq!(comp.process(2880 * 2, vec![0.1; CHUNK]).len(), CHUNK);
+        }
+        assert_eq!(comp.drift_ppm(), 0.0);
+    }
+
+    #[test]
+    fn growing_buffer_drops_and_draining_buffer_inserts() {
+        let mut comp = DriftCompensator::new(FORMAT);
+        // 200 ms buffered: well above target
+        let out = comp.process(9600 * 2, vec![0.5; CHUNK]);
+        assert!(out.len() < CHUNK);
+        assert_eq!(out.len() % 2, 0);
+        // Capped at 1% of the chunk
+        assert!(out.len() >= CHUNK - 9 * 2);
+        assert!(out.iter().all(|s| (s - 0.5).abs() < 1e-6));
+        assert!(comp.drift_ppm() < 0.0);
+
+        let mut comp = DriftCompensator::new(FORMAT);
+        let out = comp.process(0, vec![0.5; CHUNK]);
+        assert!(out.len() > CHUNK);
+        assert!(comp.drift_ppm() > 0.0);
+    }
+
+    #[test]
+    fn frames_are_merged_and_split_smoothly() {
+        // Mono ramp: corrections land between neighbours
+        let ramp: Vec<f32> = (0..8).map(|i| i as f32).collect();
+        assert_eq!(
+            drop_frames(&ramp, 1, 1),
+            [0.0, 1.0, 2.0, 3.0, 4.5, 6.0, 7.0]
+        );
+        assert_eq!(
+            insert_frames(&ramp, 1, 1),
+            [0.0, 1.0, 2.0, 3.0, 3.5, 4.0, 5.0, 6.0, 7.0]
+        );
+    }
+}
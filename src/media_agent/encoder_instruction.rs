@@ -2,5 +2,11 @@ use crate::media_agent::video_frame::VideoFrame;
 
 pub enum EncoderInstruction {
     Encode(VideoFrame, bool), // (frame, force_keyframe)
-    SetConfig { fps: u32, bitrate: u32, keyint: u32 },
+    SetConfig {
+        fps: u32,
+        bitrate: u32,
+        keyint: u32,
+    },
+    /// Changes only the frame rate, keeping bitrate and keyframe interval.
+    SetFps(u32),
 }
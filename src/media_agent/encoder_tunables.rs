@@ -0,0 +1,289 @@
+//! H.264 encoder options read from the `[Media]` section.
+//!
+//! These are the x264-style knobs a real-time sender cares about: profile,
+//! level, keyframe interval, B-frames, the zero-latency tune and a slice size
+//! that keeps every slice inside one RTP packet. Both the OpenH264 encoder and
+//! the hardware backends are built from one [`EncoderTunables`], so the same
+//! configuration gives the same stream shape whichever one is encoding.
+//!
+//! [`IntraRefresh`] adds a keyframe deadline in time on top of the
+//! frame-counted keyframe interval.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use crate::{
+    config::Config,
+    media_agent::constants::{KEYINT, SLICE_MAX_BYTES},
+    media_transport::payload::h264_packetizer::split_annexb_nalus,
+};
+
+const NAL_IDR: u8 = 5;
+
+/// H.264 profile of the encoded stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum H264Profile {
+    /// Constrained Baseline, what the SDP offers (`42e01f`).
+    #[default]
+    Baseline,
+    Main,
+    High,
+}
+
+impl H264Profile {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Main => "main",
+            Self::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for H264Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for H264Profile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "baseline" | "constrained_baseline" => Ok(Self::Baseline),
+            "main" => Ok(Self::Main),
+            "high" => Ok(Self::High),
+            _ => Err(()),
+        }
+    }
+}
+
+/// H.264 level, as its `level_idc` (3.1 is 31).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct H264Level(u8);
+
+impl H264Level {
+    /// Levels 1 to 5.2, without 1b.
+    const KNOWN: [u8; 16] = [
+        10, 11, 12, 13, 20, 21, 22, 30, 31, 32, 40, 41, 42, 50, 51, 52,
+    ];
+
+    #[must_use]
+    pub const fn idc(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for H264Level {
+    /// 3.1, what the SDP offers: 720p at 30 fps.
+    fn default() -> Self {
+        Self(31)
+    }
+}
+
+impl fmt::Display for H264Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.0 / 10, self.0 % 10)
+    }
+}
+
+impl FromStr for H264Level {
+    type Err = ();
+
+    /// `"3.1"`, `"3"` or `"31"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let idc = match s.split_once('.') {
+            Some((major, minor)) if minor.len() == 1 => {
+                let major: u8 = major.parse().map_err(|_| ())?;
+                let minor: u8 = minor.parse().map_err(|_| ())?;
+                major.checked_mul(10).and_then(|m| m.checked_add(minor))
+            }
+            Some(_) => None,
+            None => match s.parse::<u8>().map_err(|_| ())? {
+                major @ 1..=5 => Some(major * 10),
+                idc => Some(idc),
+            },
+        };
+        idc.filter(|idc| Self::KNOWN.contains(idc))
+            .map(Self)
+            .ok_or(())
+    }
+}
+
+/// How the H.264 encoder shapes the stream, apart from the frame rate and
+/// bitrate the call adapts as it goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncoderTunables {
+    pub profile: H264Profile,
+    pub level: H264Level,
+    /// Frames between keyframes.
+    pub keyint: u32,
+    /// Consecutive B-frames allowed; 0 (the default) turns them off, as they
+    /// add a frame of delay each. OpenH264 never produces them.
+    pub b_frames: u32,
+    /// Tune for the lowest latency (`tune = "zerolatency"`, the default)
+    /// rather than for quality (`tune = "none"`).
+    pub zero_latency: bool,
+    /// Largest slice in bytes, or `None` for one slice per frame. OpenH264
+    /// only; hardware encoders pick their own slicing.
+    pub slice_max_bytes: Option<u32>,
+    /// Longest time between keyframes, or `None` to rely on `keyint` only.
+    /// Unlike `keyint` it holds when the frame rate drops.
+    pub intra_refresh: Option<Duration>,
+}
+
+impl Default for EncoderTunables {
+    fn default() -> Self {
+        Self {
+            profile: H264Profile::default(),
+            level: H264Level::default(),
+            keyint: KEYINT,
+            b_frames: 0,
+            zero_latency: true,
+            slice_max_bytes: Some(SLICE_MAX_BYTES),
+            intra_refresh: None,
+        }
+    }
+}
+
+impl EncoderTunables {
+    /// Reads `[Media] h264_profile`, `h264_level`, `keyframe_interval`,
+    /// `b_frames`, `tune`, `slice_max_bytes` and `intra_refresh_ms`; missing
+    /// or invalid keys keep their default. `slice_max_bytes = 0` turns
+    /// slicing off, `intra_refresh_ms = 0` the refresh.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let defaults = Self::default();
+        let parse = |key| config.get_non_empty("Media", key);
+        Self {
+            profile: parse("h264_profile")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.profile),
+            level: parse("h264_level")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.level),
+            keyint: parse("keyframe_interval")
+                .and_then(|s| s.parse().ok())
+                .filter(|keyint| *keyint > 0)
+                .unwrap_or(defaults.keyint),
+            b_frames: parse("b_frames")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(defaults.b_frames),
+            zero_latency: parse("tune").map_or(defaults.zero_latency, |s| {
+                s.eq_ignore_ascii_case("zerolatency")
+            }),
+            slice_max_bytes: parse("slice_max_bytes")
+                .and_then(|s| s.parse::<u32>().ok())
+                .map_or(defaults.slice_max_bytes, |max| (max > 0).then_some(max)),
+            intra_refresh: parse("intra_refresh_ms")
+                .and_then(|s| s.parse::<u64>().ok())
+                .map_or(defaults.intra_refresh, |ms| {
+                    (ms > 0).then(|| Duration::from_millis(ms))
+                }),
+        }
+    }
+}
+
+/// Periodic intra refresh: asks for a keyframe once `every` has passed since
+/// the last one, whatever produced that one (the keyframe interval, a PLI, a
+/// new receiver). Times are the frames' capture timestamps in milliseconds.
+#[derive(Debug, Clone)]
+pub struct IntraRefresh {
+    every_ms: u128,
+    last_keyframe_ms: Option<u128>,
+}
+
+impl IntraRefresh {
+    #[must_use]
+    pub fn new(every: Duration) -> Self {
+        Self {
+            every_ms: every.as_millis(),
+            last_keyframe_ms: None,
+        }
+    }
+
+    /// Whether the frame captured at `now_ms` should be a keyframe.
+    #[must_use]
+    pub fn due(&self, now_ms: u128) -> bool {
+        self.last_keyframe_ms
+            .is_none_or(|at| now_ms.saturating_sub(at) >= self.every_ms)
+    }
+
+    /// Records what the encoder made of the frame captured at `now_ms`.
+    pub fn on_encoded(&mut self, annexb: &[u8], now_ms: u128) {
+        if is_keyframe(annexb) {
+            self.last_keyframe_ms = Some(now_ms);
+        }
+    }
+}
+
+/// Whether an Annex-B access unit holds an IDR slice.
+#[must_use]
+pub fn is_keyframe(annexb: &[u8]) -> bool {
+    split_annexb_nalus(annexb)
+        .iter()
+        .any(|nal| nal.first().is_some_and(|h| h & 0x1F == NAL_IDR))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn levels_parse_in_every_spelling() {
+        assert_eq!("3.1".parse::<H264Level>().unwrap().idc(), 31);
+        assert_eq!("4".parse::<H264Level>().unwrap().idc(), 40);
+        assert_eq!("52".parse::<H264Level>().unwrap().idc(), 52);
+        assert_eq!(H264Level::default().to_string(), "3.1");
+        for bad in ["3.3", "6", "3.10", "", "x"] {
+            assert!(bad.parse::<H264Level>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn tunables_come_from_the_media_section() {
+        assert_eq!(
+            EncoderTunables::from_config(&Config::empty()),
+            EncoderTunables::default()
+        );
+
+        let mut config = Config::empty();
+        config.set("Media", "h264_profile", "High");
+        config.set("Media", "h264_level", "4.1");
+        config.set("Media", "keyframe_interval", "60");
+        config.set("Media", "b_frames", "2");
+        config.set("Media", "tune", "none");
+        config.set("Media", "slice_max_bytes", "0");
+        let tunables = EncoderTunables::from_config(&config);
+        assert_eq!(tunables.profile, H264Profile::High);
+        assert_eq!(tunables.level.idc(), 41);
+        assert_eq!(tunables.keyint, 60);
+        assert_eq!(tunables.b_frames, 2);
+        assert!(!tunables.zero_latency);
+        assert_eq!(tunables.slice_max_bytes, None);
+        assert_eq!(tunables.intra_refresh, None);
+
+        config.set("Media", "intra_refresh_ms", "2000");
+        let tunables = EncoderTunables::from_config(&config);
+        assert_eq!(tunables.intra_refresh, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn intra_refresh_counts_from_the_last_keyframe() {
+        let idr = [0, 0, 0, 1, 0x67, 1, 0, 0, 0, 1, 0x65, 2];
+        let delta = [0, 0, 0, 1, 0x41, 3];
+        let mut refresh = IntraRefresh::new(Duration::from_secs(1));
+        assert!(refresh.due(0));
+
+        refresh.on_encoded(&idr, 0);
+        refresh.on_encoded(&delta, 500);
+        assert!(!refresh.due(999));
+        // A keyframe asked for by a PLI pushes the deadline back
+        refresh.on_encoded(&idr, 800);
+        assert!(!refresh.due(1000));
+        assert!(refresh.due(1800));
+    }
+}
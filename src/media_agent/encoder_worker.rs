@@ -15,12 +15,13 @@ use crate::{
     logger_debug, logger_error,
     media_agent::{
         constants::CHANNELS_TIMEOUT, encoder_instruction::EncoderInstruction,
-        events::MediaAgentEvent, h264_encoder::H264Encoder, spec::CodecSpec,
+        encoder_tunables::EncoderTunables, events::MediaAgentEvent, spec::CodecSpec,
+        video_encoder::VideoEncoder, watermark::Watermark,
     },
     sink_debug,
 };
 
-use super::constants::{BITRATE, KEYINT, TARGET_FPS};
+use super::constants::{BITRATE, TARGET_FPS};
 
 /// Spawns a dedicated background thread for H.264 video encoding.
 ///
@@ -30,11 +31,15 @@ use super::constants::{BITRATE, KEYINT, TARGET_FPS};
 ///
 /// # Architecture
 ///
-/// 1. **Initialization**: Reads initial encoding parameters (FPS, Bitrate, Keyint) from the
-///    provided `Config`, falling back to constants if keys are missing.
+/// 1. **Initialization**: Reads initial encoding parameters (FPS, Bitrate) and the
+///    [`EncoderTunables`] (profile, level, keyframe interval, B-frames, tune, slice size)
+///    from the provided `Config`, falling back to defaults if keys are missing.
+///    A [`Watermark`] is set up too when `[Media] watermark_text` or
+///    `watermark_image` is set.
 /// 2. **Loop**:
 ///    - Listens for `EncoderInstruction`.
-///    - **On `Encode`**: Compresses the frame using `H264Encoder`. If `force_keyframe` is true,
+///    - **On `Encode`**: Draws the watermark, if any, then compresses the frame using `VideoEncoder` (hardware if available,
+///      OpenH264 otherwise). If `force_keyframe` is true,
 ///      it requests an IDR frame immediately.
 ///    - **On `SetConfig`**: Dynamically reconfigures the encoder without restarting the thread.
 /// 3. **Output**: Sends `MediaAgentEvent::EncodedVideoFrame` (Annex B format) to the media agent.
@@ -79,18 +84,27 @@ pub fn spawn_encoder_worker(
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(BITRATE);
 
-            let keyint = config
-                .get("Media", "keyint")
-                .and_then(|s| s.parse().ok())
-                .unwrap_or(KEYINT);
+            let tunables = EncoderTunables::from_config(&config);
+
+            let mut h264_encoder =
+                VideoEncoder::new(logger.clone(), &config, target_fps, bitrate, tunables);
 
-            let mut h264_encoder = H264Encoder::new(target_fps, bitrate, keyint);
+            let mut watermark = Watermark::from_config(&config);
+            if let Some(wm) = watermark.as_mut()
+                && let Some(path) = config.get_non_empty("Media", "watermark_image")
+                && let Err(e) = wm.load_image(path)
+            {
+                logger_error!(logger, "[Encoder] watermark image not loaded: {e}");
+            }
 
             // --- Main Loop ---
             while running.load(Ordering::Relaxed) {
                 match ma_encoder_event_rx.recv_timeout(Duration::from_millis(CHANNELS_TIMEOUT)) {
                     Ok(order) => match order {
-                        EncoderInstruction::Encode(frame, force_keyframe) => {
+                        EncoderInstruction::Encode(mut frame, force_keyframe) => {
+                            if let Some(wm) = &watermark {
+                                wm.apply(&mut frame);
+                            }
                             if force_keyframe {
                                 h264_encoder.request_keyframe();
                             }
@@ -125,6 +139,13 @@ pub fn spawn_encoder_worker(
                                 logger_error!(logger, "[EncoderWorker] set_config error: {e:?}");
                             }
                         }
+                        EncoderInstruction::SetFps(fps) => {
+                            let (bitrate, keyint) =
+                                (h264_encoder.target_bps(), h264_encoder.keyint());
+                            if let Err(e) = h264_encoder.set_config(fps, bitrate, keyint) {
+                                logger_error!(logger, "[EncoderWorker] set_config error: {e:?}");
+                            }
+                        }
                     },
 
                     Err(RecvTimeoutError::Timeout) => {
@@ -1,10 +1,12 @@
-use crate::media_agent::{spec::CodecSpec, video_frame::VideoFrame};
+use crate::media_agent::{audio_format::AudioFormat, spec::CodecSpec, video_frame::VideoFrame};
 
 #[derive(Debug)]
 pub enum MediaAgentEvent {
     AnnexBFrameReady {
         codec_spec: CodecSpec,
         bytes: Vec<u8>,
+        /// Arrival of the frame's first packet (UNIX milliseconds).
+        received_ms: u128,
     },
     EncodedVideoFrame {
         annexb_frame: Vec<u8>,
@@ -14,7 +16,16 @@ pub enum MediaAgentEvent {
     EncodedAudioFrame {
         payload: Vec<u8>,
         codec_spec: CodecSpec,
+        /// Stream it arrived on: a peer sends one, an SFU may relay several.
+        ssrc: u32,
     },
     DecodedVideoFrame(Box<VideoFrame>),
+    /// Audio codec and format negotiated for sending.
+    SetAudioSendFormat {
+        codec_spec: CodecSpec,
+        format: AudioFormat,
+    },
     UpdateBitrate(u32),
+    /// The decoder lost the remote video and waits for a keyframe.
+    KeyframeNeeded,
 }
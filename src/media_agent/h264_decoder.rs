@@ -10,7 +10,7 @@ use crate::{
         frame_format::FrameFormat,
         media_agent_error::{MediaAgentError, Result},
         utils::now_millis,
-        video_frame::{VideoFrame, VideoFrameData},
+        video_frame::{Crop, VideoFrame, VideoFrameData},
     },
     sink_debug,
 };
@@ -203,6 +203,7 @@ fn yuv_to_yuv420frame(yuv: &DecodedYUV<'_>) -> VideoFrame {
             y_stride: y_stride_new,
             u_stride: u_stride_new,
             v_stride: v_stride_new,
+            crop: Crop::default(),
         },
     }
 }
@@ -210,7 +211,7 @@ fn yuv_to_yuv420frame(yuv: &DecodedYUV<'_>) -> VideoFrame {
 /// Calculates the byte stride required to meet wgpu alignment standards.
 ///
 /// Current standard: `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT` is 256 bytes.
-fn aligned_stride(width: usize) -> usize {
+pub(crate) fn aligned_stride(width: usize) -> usize {
     const ALIGNMENT: usize = 256;
     width.div_ceil(ALIGNMENT) * ALIGNMENT
 }
@@ -1,20 +1,23 @@
 use openh264::{
     OpenH264API,
     encoder::{
-        BitRate, Encoder, EncoderConfig, FrameRate, IntraFramePeriod, RateControlMode,
-        SpsPpsStrategy, UsageType,
+        BitRate, Encoder, EncoderConfig, FrameRate, IntraFramePeriod, Level, Profile,
+        RateControlMode, SpsPpsStrategy, UsageType,
     },
     formats::{RgbSliceU8, YUVBuffer},
 };
 
 use crate::media_agent::{
-    frame_format::FrameFormat, media_agent_error::MediaAgentError, video_frame::VideoFrame,
+    encoder_tunables::{EncoderTunables, H264Level, H264Profile},
+    frame_format::FrameFormat,
+    media_agent_error::MediaAgentError,
+    video_frame::VideoFrame,
 };
 
 /// A high-level wrapper around the OpenH264 encoder.
 ///
-/// This struct manages the configuration state (FPS, Bitrate, Keyframe Interval)
-/// and handles the conversion of incoming frames into a format compatible with
+/// This struct manages the configuration state (FPS, Bitrate and the
+/// [`EncoderTunables`]) and handles the conversion of incoming frames into a format compatible with
 /// the underlying encoder engine.
 ///
 /// # Color Space Conversion
@@ -24,7 +27,9 @@ pub struct H264Encoder {
     enc: Option<Encoder>,
     target_fps: u32,
     target_bps: u32,
-    keyint: u32,
+    tunables: EncoderTunables,
+    /// Size of the last encoded frame; a change re-initializes the encoder.
+    frame_size: Option<(u32, u32)>,
 }
 
 impl H264Encoder {
@@ -34,13 +39,14 @@ impl H264Encoder {
     ///
     /// * `frame_rate` - Target frames per second (e.g., 30).
     /// * `bit_rate` - Target bitrate in bits per second (e.g., 1_500_000).
-    /// * `keyint` - Intra-frame period (keyframe interval).
-    pub fn new(frame_rate: u32, bit_rate: u32, keyint: u32) -> Self {
+    /// * `tunables` - Profile, level, keyframe interval, tune and slicing.
+    pub fn new(frame_rate: u32, bit_rate: u32, tunables: EncoderTunables) -> Self {
         let mut me = Self {
             enc: None,
             target_fps: frame_rate,
             target_bps: bit_rate,
-            keyint,
+            tunables,
+            frame_size: None,
         };
         me.init_encoder();
         me
@@ -48,18 +54,29 @@ impl H264Encoder {
 
     /// Internal helper to initialize (or re-initialize) the OpenH264 instance.
     ///
-    /// Configures the encoder for real-time camera usage (`UsageType::CameraVideoRealTime`),
-    /// using Constant ID strategy for SPS/PPS insertion.
+    /// Configures the encoder for real-time camera usage (`UsageType::CameraVideoRealTime`)
+    /// unless the zero-latency tune is off, using Constant ID strategy for SPS/PPS insertion.
     fn init_encoder(&mut self) {
+        let t = self.tunables;
+        let usage = if t.zero_latency {
+            UsageType::CameraVideoRealTime
+        } else {
+            UsageType::CameraVideoNonRealTime
+        };
         // Build config via builder methods (OpenH264 0.9 API style)
-        let cfg = EncoderConfig::new()
-            .usage_type(UsageType::CameraVideoRealTime)
+        let mut cfg = EncoderConfig::new()
+            .usage_type(usage)
+            .profile(openh264_profile(t.profile))
+            .level(openh264_level(t.level))
             .max_frame_rate(FrameRate::from_hz(self.target_fps as f32))
             .bitrate(BitRate::from_bps(self.target_bps))
             .rate_control_mode(RateControlMode::Bitrate)
             // Strategy: Insert SPS/PPS with every IDR frame to ensure stream joinability.
             .sps_pps_strategy(SpsPpsStrategy::ConstantId)
-            .intra_frame_period(IntraFramePeriod::from_num_frames(self.keyint));
+            .intra_frame_period(IntraFramePeriod::from_num_frames(t.keyint));
+        if let Some(max) = t.slice_max_bytes {
+            cfg = cfg.max_slice_len(max);
+        }
 
         let api = OpenH264API::from_source();
         // Use the config-aware constructor to apply settings immediately
@@ -91,6 +108,14 @@ impl H264Encoder {
             FrameFormat::Yuv420 => {}
         }
 
+        // A new capture size starts a fresh stream (beginning with an IDR)
+        // rather than relying on the encoder to adapt mid-stream.
+        let size = (frame.width, frame.height);
+        if self.frame_size.is_some_and(|old| old != size) {
+            self.init_encoder();
+        }
+        self.frame_size = Some(size);
+
         let Some(enc) = self.enc.as_mut() else {
             return Err(MediaAgentError::Codec(
                 "openh264 encoder unavailable".into(),
@@ -134,19 +159,20 @@ impl H264Encoder {
         }
     }
 
-    #[allow(dead_code)]
     pub fn target_fps(&self) -> u32 {
         self.target_fps
     }
 
-    #[allow(dead_code)]
     pub fn target_bps(&self) -> u32 {
         self.target_bps
     }
 
-    #[allow(dead_code)]
     pub fn keyint(&self) -> u32 {
-        self.keyint
+        self.tunables.keyint
+    }
+
+    pub fn tunables(&self) -> EncoderTunables {
+        self.tunables
     }
 
     /// Updates the encoder configuration dynamically.
@@ -174,7 +200,7 @@ impl H264Encoder {
         }
         self.target_fps = new_fps;
         self.target_bps = new_bitrate;
-        self.keyint = new_keyint;
+        self.tunables.keyint = new_keyint;
 
         // Re-init returns the new encoder via Encoder::with_api_config.
         // If it fails, we catch it here.
@@ -191,6 +217,37 @@ impl H264Encoder {
 
     /// Helper to determine if a config update is necessary.
     fn should_skip_update(&self, new_fps: u32, new_bitrate: u32, new_keyint: u32) -> bool {
-        new_fps == self.target_fps && new_bitrate == self.target_bps && new_keyint == self.keyint
+        new_fps == self.target_fps
+            && new_bitrate == self.target_bps
+            && new_keyint == self.tunables.keyint
+    }
+}
+
+fn openh264_profile(profile: H264Profile) -> Profile {
+    match profile {
+        H264Profile::Baseline => Profile::Baseline,
+        H264Profile::Main => Profile::Main,
+        H264Profile::High => Profile::High,
+    }
+}
+
+fn openh264_level(level: H264Level) -> Level {
+    match level.idc() {
+        10 => Level::Level_1_0,
+        11 => Level::Level_1_1,
+        12 => Level::Level_1_2,
+        13 => Level::Level_1_3,
+        20 => Level::Level_2_0,
+        21 => Level::Level_2_1,
+        22 => Level::Level_2_2,
+        30 => Level::Level_3_0,
+        31 => Level::Level_3_1,
+        32 => Level::Level_3_2,
+        40 => Level::Level_4_0,
+        41 => Level::Level_4_1,
+        42 => Level::Level_4_2,
+        50 => Level::Level_5_0,
+        51 => Level::Level_5_1,
+        _ => Level::Level_5_2,
     }
 }
@@ -0,0 +1,434 @@
+//! FFmpeg-backed hardware H.264 encoder and decoder.
+//!
+//! * Encode: `h264_nvenc`, `h264_videotoolbox` (NV12 input) and `h264_vaapi`
+//!   (NV12 uploaded to VAAPI surfaces).
+//! * Decode: `h264_cuvid` for NVIDIA; VAAPI and VideoToolbox go through the
+//!   native H.264 decoder with a hardware device attached, and surfaces are
+//!   downloaded back to NV12.
+
+use std::ptr;
+
+use ffmpeg_next::{
+    self as ffmpeg, Dictionary, Packet, Rational,
+    codec::{self, context::Context},
+    ffi,
+    format::Pixel,
+    frame, picture,
+};
+
+use super::{HwBackend, nv12_to_frame, rgb_to_nv12};
+use crate::media_agent::{
+    encoder_tunables::{EncoderTunables, H264Profile},
+    frame_format::FrameFormat,
+    media_agent_error::{MediaAgentError, Result},
+    video_frame::{VideoFrame, VideoFrameData},
+};
+
+fn codec_err(e: impl std::fmt::Display) -> MediaAgentError {
+    MediaAgentError::Codec(e.to_string())
+}
+
+impl HwBackend {
+    const fn encoder_name(self) -> &'static str {
+        match self {
+            Self::Vaapi => "h264_vaapi",
+            Self::Nvenc => "h264_nvenc",
+            Self::VideoToolbox => "h264_videotoolbox",
+        }
+    }
+
+    const fn device_type(self) -> ffi::AVHWDeviceType {
+        match self {
+            Self::Vaapi => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VAAPI,
+            Self::Nvenc => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_CUDA,
+            Self::VideoToolbox => ffi::AVHWDeviceType::AV_HWDEVICE_TYPE_VIDEOTOOLBOX,
+        }
+    }
+}
+
+/// Owned reference to an FFmpeg hardware device or frames context.
+struct HwBufferRef(*mut ffi::AVBufferRef);
+
+impl HwBufferRef {
+    /// Opens the default device of `backend`.
+    fn device(backend: HwBackend) -> Result<Self> {
+        let mut raw = ptr::null_mut();
+        // SAFETY: `raw` is a valid out-pointer; a null name selects the
+        // default device and no options are passed.
+        let ret = unsafe {
+            ffi::av_hwdevice_ctx_create(
+                &mut raw,
+                backend.device_type(),
+                ptr::null(),
+                ptr::null_mut(),
+                0,
+            )
+        };
+        if ret < 0 || raw.is_null() {
+            return Err(codec_err(format!(
+                "{backend}: no device ({})",
+                ffmpeg::Error::from(ret)
+            )));
+        }
+        Ok(Self(raw))
+    }
+
+    /// A pool of NV12-backed surfaces of the given size on `device`.
+    fn frames(device: &Self, width: u32, height: u32) -> Result<Self> {
+        // SAFETY: `device` is a live device context. The frames context is
+        // fully configured before `av_hwframe_ctx_init`, as FFmpeg requires,
+        // and is released by `Drop` on every path.
+        unsafe {
+            let raw = ffi::av_hwframe_ctx_alloc(device.0);
+            if raw.is_null() {
+                return Err(codec_err("av_hwframe_ctx_alloc failed"));
+            }
+            let pool = Self(raw);
+            let ctx = (*raw).data.cast::<ffi::AVHWFramesContext>();
+            (*ctx).format = ffi::AVPixelFormat::AV_PIX_FMT_VAAPI;
+            (*ctx).sw_format = ffi::AVPixelFormat::AV_PIX_FMT_NV12;
+            (*ctx).width = i32::try_from(width).map_err(codec_err)?;
+            (*ctx).height = i32::try_from(height).map_err(codec_err)?;
+            (*ctx).initial_pool_size = 8;
+            let ret = ffi::av_hwframe_ctx_init(raw);
+            if ret < 0 {
+                return Err(codec_err(ffmpeg::Error::from(ret)));
+            }
+            Ok(pool)
+        }
+    }
+
+    /// A new reference for a codec context to own.
+    fn new_ref(&self) -> *mut ffi::AVBufferRef {
+        // SAFETY: `self.0` is a live buffer reference.
+        unsafe { ffi::av_buffer_ref(self.0) }
+    }
+}
+
+impl Drop for HwBufferRef {
+    fn drop(&mut self) {
+        // SAFETY: we own this reference; `av_buffer_unref` nulls the pointer.
+        unsafe { ffi::av_buffer_unref(&mut self.0) };
+    }
+}
+
+/// Hardware H.264 encoder for one frame size.
+pub struct HwEncoder {
+    backend: HwBackend,
+    encoder: ffmpeg::encoder::video::Encoder,
+    /// Surface pool frames are uploaded to (VAAPI only).
+    frames: Option<HwBufferRef>,
+    width: u32,
+    height: u32,
+    pts: i64,
+}
+
+impl HwEncoder {
+    /// Opens the `backend` encoder for `width`x`height` input, shaped by
+    /// `tunables` as far as the backend allows.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if FFmpeg lacks the encoder or no
+    /// device is available.
+    pub fn open(
+        backend: HwBackend,
+        width: u32,
+        height: u32,
+        fps: u32,
+        bitrate: u32,
+        tunables: EncoderTunables,
+    ) -> Result<Self> {
+        ffmpeg::init().map_err(codec_err)?;
+        let codec = ffmpeg::encoder::find_by_name(backend.encoder_name()).ok_or_else(|| {
+            codec_err(format!(
+                "{} is not available in this FFmpeg build",
+                backend.encoder_name()
+            ))
+        })?;
+
+        let mut video = Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .map_err(codec_err)?;
+        let fps = i32::try_from(fps.max(1)).map_err(codec_err)?;
+        video.set_width(width);
+        video.set_height(height);
+        video.set_time_base(Rational::new(1, fps));
+        video.set_frame_rate(Some(Rational::new(fps, 1)));
+        video.set_bit_rate(bitrate as usize);
+        video.set_max_bit_rate(bitrate as usize);
+        video.set_gop(tunables.keyint);
+        video.set_max_b_frames(tunables.b_frames as usize);
+
+        let frames = if backend == HwBackend::Vaapi {
+            let device = HwBufferRef::device(backend)?;
+            let frames = HwBufferRef::frames(&device, width, height)?;
+            video.set_format(Pixel::VAAPI);
+            // SAFETY: the codec context takes ownership of a new reference.
+            unsafe { (*video.as_mut_ptr()).hw_frames_ctx = frames.new_ref() };
+            Some(frames)
+        } else {
+            video.set_format(Pixel::NV12);
+            None
+        };
+
+        let mut opts = Dictionary::new();
+        let level = tunables.level.to_string();
+        match backend {
+            HwBackend::Nvenc => {
+                opts.set("preset", "p1");
+                if tunables.zero_latency {
+                    opts.set("tune", "ull");
+                    opts.set("zerolatency", "1");
+                } else {
+                    opts.set("tune", "ll");
+                }
+                opts.set("profile", tunables.profile.as_str());
+                opts.set("level", &level);
+                // Turn forced I frames into IDRs
+                opts.set("forced-idr", "1");
+            }
+            HwBackend::VideoToolbox => {
+                opts.set("realtime", if tunables.zero_latency { "1" } else { "0" });
+                opts.set("profile", tunables.profile.as_str());
+                opts.set("allow_sw", "0");
+            }
+            HwBackend::Vaapi => {
+                let profile = match tunables.profile {
+                    H264Profile::Baseline => "constrained_baseline",
+                    other => other.as_str(),
+                };
+                opts.set("profile", profile);
+                opts.set("level", &level);
+            }
+        }
+        let encoder = video.open_with(opts).map_err(codec_err)?;
+
+        Ok(Self {
+            backend,
+            encoder,
+            frames,
+            width,
+            height,
+            pts: 0,
+        })
+    }
+
+    /// Encodes an RGB frame of the size given to [`open`](Self::open) into
+    /// Annex-B NAL units (empty while the encoder is buffering).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` for non-RGB input, a size mismatch
+    /// or an FFmpeg failure.
+    pub fn encode(&mut self, frame: &VideoFrame, force_keyframe: bool) -> Result<Vec<u8>> {
+        let VideoFrameData::Rgb(rgb) = &frame.data else {
+            return Err(codec_err("hardware encoder expects RGB frames"));
+        };
+        if (frame.width, frame.height) != (self.width, self.height) {
+            return Err(codec_err("frame size differs from the encoder's"));
+        }
+
+        let (w, h) = (self.width as usize, self.height as usize);
+        let (y, uv) = rgb_to_nv12(rgb, w, h);
+        let mut sw = frame::Video::new(Pixel::NV12, self.width, self.height);
+        copy_plane(&y, w, &mut sw, 0, h);
+        copy_plane(&uv, w.div_ceil(2) * 2, &mut sw, 1, h.div_ceil(2));
+        sw.set_pts(Some(self.pts));
+        self.pts += 1;
+        if force_keyframe {
+            sw.set_kind(picture::Type::I);
+        }
+
+        let uploaded = match &self.frames {
+            Some(pool) => Some(upload(pool, &sw)?),
+            None => None,
+        };
+        self.encoder
+            .send_frame(uploaded.as_ref().unwrap_or(&sw))
+            .map_err(codec_err)?;
+
+        let mut out = Vec::new();
+        let mut packet = Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            if let Some(data) = packet.data() {
+                out.extend_from_slice(data);
+            }
+        }
+        Ok(out)
+    }
+
+    #[must_use]
+    pub const fn backend(&self) -> HwBackend {
+        self.backend
+    }
+
+    #[must_use]
+    pub const fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+/// Copies `rows` rows of `row_len` bytes into plane `plane` of `dst`.
+fn copy_plane(src: &[u8], row_len: usize, dst: &mut frame::Video, plane: usize, rows: usize) {
+    let stride = dst.stride(plane);
+    let data = dst.data_mut(plane);
+    for row in 0..rows {
+        data[row * stride..row * stride + row_len]
+            .copy_from_slice(&src[row * row_len..(row + 1) * row_len]);
+    }
+}
+
+/// Copies a system-memory frame onto a surface from `pool`.
+fn upload(pool: &HwBufferRef, sw: &frame::Video) -> Result<frame::Video> {
+    let mut hw = frame::Video::empty();
+    // SAFETY: `hw` is an empty frame that receives a surface from the live
+    // pool; `sw` is a valid NV12 frame of the pool's size.
+    unsafe {
+        let ret = ffi::av_hwframe_get_buffer(pool.0, hw.as_mut_ptr(), 0);
+        if ret < 0 {
+            return Err(codec_err(ffmpeg::Error::from(ret)));
+        }
+        let ret = ffi::av_hwframe_transfer_data(hw.as_mut_ptr(), sw.as_ptr(), 0);
+        if ret < 0 {
+            return Err(codec_err(ffmpeg::Error::from(ret)));
+        }
+    }
+    hw.set_pts(sw.pts());
+    hw.set_kind(sw.kind());
+    Ok(hw)
+}
+
+/// Hardware H.264 decoder.
+pub struct HwDecoder {
+    backend: HwBackend,
+    decoder: ffmpeg::decoder::Video,
+    /// Keeps the device alive for as long as the decoder uses it.
+    _device: Option<HwBufferRef>,
+}
+
+impl HwDecoder {
+    /// Opens a decoder on `backend`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if FFmpeg lacks the decoder or no
+    /// device is available.
+    pub fn open(backend: HwBackend) -> Result<Self> {
+        ffmpeg::init().map_err(codec_err)?;
+        let (codec, device) = match backend {
+            HwBackend::Nvenc => (ffmpeg::decoder::find_by_name("h264_cuvid"), None),
+            HwBackend::Vaapi | HwBackend::VideoToolbox => (
+                ffmpeg::decoder::find(codec::Id::H264),
+                Some(HwBufferRef::device(backend)?),
+            ),
+        };
+        let codec =
+            codec.ok_or_else(|| codec_err(format!("no {backend} H.264 decoder in FFmpeg")))?;
+
+        let mut ctx = Context::new_with_codec(codec);
+        if let Some(device) = &device {
+            // SAFETY: the codec context takes ownership of a new reference;
+            // FFmpeg then negotiates the hardware pixel format on its own.
+            unsafe { (*ctx.as_mut_ptr()).hw_device_ctx = device.new_ref() };
+        }
+        let decoder = ctx
+            .decoder()
+            .open_as(codec)
+            .and_then(|d| d.video())
+            .map_err(codec_err)?;
+
+        Ok(Self {
+            backend,
+            decoder,
+            _device: device,
+        })
+    }
+
+    /// Decodes one Annex-B access unit; `Ok(None)` while no picture is ready.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if FFmpeg rejects the data or the
+    /// surface cannot be downloaded.
+    pub fn decode(&mut self, bytes: &[u8], format: FrameFormat) -> Result<Option<VideoFrame>> {
+        self.decoder
+            .send_packet(&Packet::copy(bytes))
+            .map_err(codec_err)?;
+
+        // Keep only the newest picture if several come out at once
+        let mut latest = None;
+        let mut decoded = frame::Video::empty();
+        while self.decoder.receive_frame(&mut decoded).is_ok() {
+            latest = Some(std::mem::replace(&mut decoded, frame::Video::empty()));
+        }
+        let Some(picture) = latest else {
+            return Ok(None);
+        };
+
+        let sw = if matches!(
+            picture.format(),
+            Pixel::VAAPI | Pixel::VIDEOTOOLBOX | Pixel::CUDA
+        ) {
+            download(&picture)?
+        } else {
+            picture
+        };
+        to_video_frame(&sw, format).map(Some)
+    }
+
+    #[must_use]
+    pub const fn backend(&self) -> HwBackend {
+        self.backend
+    }
+}
+
+/// Copies a hardware surface back to system memory (NV12).
+fn download(hw: &frame::Video) -> Result<frame::Video> {
+    let mut sw = frame::Video::empty();
+    // SAFETY: both frames are valid; FFmpeg allocates `sw`'s buffers.
+    let ret = unsafe { ffi::av_hwframe_transfer_data(sw.as_mut_ptr(), hw.as_ptr(), 0) };
+    if ret < 0 {
+        return Err(codec_err(ffmpeg::Error::from(ret)));
+    }
+    Ok(sw)
+}
+
+/// Converts an NV12 or I420 system-memory frame into a [`VideoFrame`].
+fn to_video_frame(sw: &frame::Video, format: FrameFormat) -> Result<VideoFrame> {
+    let (w, h) = (sw.width() as usize, sw.height() as usize);
+    match sw.format() {
+        Pixel::NV12 => Ok(nv12_to_frame(
+            sw.data(0),
+            sw.stride(0),
+            sw.data(1),
+            sw.stride(1),
+            w,
+            h,
+            format,
+        )),
+        Pixel::YUV420P | Pixel::YUVJ420P => {
+            let (uv_w, uv_h) = (w.div_ceil(2), h.div_ceil(2));
+            let (u, v) = (sw.data(1), sw.data(2));
+            let (u_stride, v_stride) = (sw.stride(1), sw.stride(2));
+            let mut uv = Vec::with_capacity(uv_w * 2 * uv_h);
+            for row in 0..uv_h {
+                for col in 0..uv_w {
+                    uv.push(u[row * u_stride + col]);
+                    uv.push(v[row * v_stride + col]);
+                }
+            }
+            Ok(nv12_to_frame(
+                sw.data(0),
+                sw.stride(0),
+                &uv,
+                uv_w * 2,
+                w,
+                h,
+                format,
+            ))
+        }
+        other => Err(codec_err(format!("unsupported decoded format {other:?}"))),
+    }
+}
@@ -0,0 +1,282 @@
+//! Optional hardware H.264 encode/decode through FFmpeg.
+//!
+//! Backends are compiled in with the `hw-vaapi`, `hw-nvenc` and
+//! `hw-videotoolbox` features and picked at runtime with `[Media] hw_codec`.
+//! Without any of those features the [`HwEncoder`]/[`HwDecoder`] stubs never
+//! open, so [`VideoEncoder`](super::video_encoder::VideoEncoder) and
+//! [`VideoDecoder`](super::video_decoder::VideoDecoder) always take the
+//! OpenH264 software path.
+
+#[cfg(feature = "hw-codec")]
+mod ffmpeg;
+#[cfg(not(feature = "hw-codec"))]
+mod stub;
+
+#[cfg(feature = "hw-codec")]
+pub use ffmpeg::{HwDecoder, HwEncoder};
+#[cfg(not(feature = "hw-codec"))]
+pub use stub::{HwDecoder, HwEncoder};
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use crate::{
+    config::Config,
+    media_agent::{
+        frame_format::FrameFormat,
+        h264_decoder::aligned_stride,
+        utils::now_millis,
+        video_frame::{Crop, VideoFrame, VideoFrameData},
+    },
+};
+
+/// A hardware codec API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwBackend {
+    /// Intel/AMD on Linux.
+    Vaapi,
+    /// NVIDIA (NVENC for encode, NVDEC/CUVID for decode).
+    Nvenc,
+    /// Apple.
+    VideoToolbox,
+}
+
+impl HwBackend {
+    /// Backends compiled into this binary, in auto-selection order.
+    #[must_use]
+    pub fn compiled() -> Vec<Self> {
+        let mut out = Vec::new();
+        if cfg!(feature = "hw-nvenc") {
+            out.push(Self::Nvenc);
+        }
+        if cfg!(feature = "hw-vaapi") {
+            out.push(Self::Vaapi);
+        }
+        if cfg!(feature = "hw-videotoolbox") {
+            out.push(Self::VideoToolbox);
+        }
+        out
+    }
+
+    /// Backends to try, from `[Media] hw_codec`: `"auto"` (default) tries
+    /// every compiled backend, `"off"` none, a backend name only that one.
+    #[must_use]
+    pub fn preferred(config: &Config) -> Vec<Self> {
+        let compiled = Self::compiled();
+        match config
+            .get("Media", "hw_codec")
+            .map(|s| s.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            None | Some("" | "auto") => compiled,
+            Some("off" | "none" | "software") => Vec::new(),
+            Some(name) => name
+                .parse::<Self>()
+                .ok()
+                .filter(|b| compiled.contains(b))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Vaapi => "vaapi",
+            Self::Nvenc => "nvenc",
+            Self::VideoToolbox => "videotoolbox",
+        }
+    }
+}
+
+impl fmt::Display for HwBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HwBackend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "vaapi" => Ok(Self::Vaapi),
+            "nvenc" | "nvidia" | "cuda" => Ok(Self::Nvenc),
+            "videotoolbox" | "vt" => Ok(Self::VideoToolbox),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Converts packed RGB to NV12 (BT.601, limited range).
+///
+/// Returns the Y plane (`w * h`) and the interleaved UV plane
+/// (`w.div_ceil(2) * 2 * h.div_ceil(2)`), both tightly packed.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::many_single_char_names
+)]
+pub fn rgb_to_nv12(rgb: &[u8], width: usize, height: usize) -> (Vec<u8>, Vec<u8>) {
+    let uv_w = width.div_ceil(2);
+    let uv_h = height.div_ceil(2);
+    let mut y_plane = vec![0u8; width * height];
+    let mut uv_plane = vec![128u8; uv_w * 2 * uv_h];
+
+    let px = |x: usize, y: usize| {
+        let i = (y * width + x) * 3;
+        (
+            f32::from(rgb[i]),
+            f32::from(rgb[i + 1]),
+            f32::from(rgb[i + 2]),
+        )
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = px(x, y);
+            y_plane[y * width + x] = (16.0 + 0.257 * r + 0.504 * g + 0.098 * b)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    }
+    for cy in 0..uv_h {
+        for cx in 0..uv_w {
+            // Top-left sample of each 2x2 block
+            let (r, g, b) = px(cx * 2, cy * 2);
+            let u = 128.0 - 0.148 * r - 0.291 * g + 0.439 * b;
+            let v = 128.0 + 0.439 * r - 0.368 * g - 0.071 * b;
+            let i = cy * uv_w * 2 + cx * 2;
+            uv_plane[i] = u.round().clamp(0.0, 255.0) as u8;
+            uv_plane[i + 1] = v.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    (y_plane, uv_plane)
+}
+
+/// Builds a [`VideoFrame`] from NV12 planes with arbitrary strides.
+///
+/// `Yuv420` output uses the same 256-byte aligned strides as the software
+/// decoder, so the GPU renderer can take either.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::many_single_char_names
+)]
+pub fn nv12_to_frame(
+    y: &[u8],
+    y_stride: usize,
+    uv: &[u8],
+    uv_stride: usize,
+    width: usize,
+    height: usize,
+    format: FrameFormat,
+) -> VideoFrame {
+    let uv_w = width.div_ceil(2);
+    let uv_h = height.div_ceil(2);
+
+    let data = match format {
+        FrameFormat::Yuv420 => {
+            let y_out_stride = aligned_stride(width);
+            let c_stride = aligned_stride(uv_w);
+            let mut y_out = vec![0u8; y_out_stride * height];
+            let mut u_out = vec![0u8; c_stride * uv_h];
+            let mut v_out = vec![0u8; c_stride * uv_h];
+            for row in 0..height {
+                y_out[row * y_out_stride..row * y_out_stride + width]
+                    .copy_from_slice(&y[row * y_stride..row * y_stride + width]);
+            }
+            for row in 0..uv_h {
+                for col in 0..uv_w {
+                    u_out[row * c_stride + col] = uv[row * uv_stride + col * 2];
+                    v_out[row * c_stride + col] = uv[row * uv_stride + col * 2 + 1];
+                }
+            }
+            VideoFrameData::Yuv420 {
+                y: Arc::new(y_out),
+                u: Arc::new(u_out),
+                v: Arc::new(v_out),
+                y_stride: y_out_stride,
+                u_stride: c_stride,
+                v_stride: c_stride,
+                crop: Crop::default(),
+            }
+        }
+        FrameFormat::Rgb => {
+            let mut rgb = Vec::with_capacity(width * height * 3);
+            for row in 0..height {
+                for col in 0..width {
+                    let l = f32::from(y[row * y_stride + col]) - 16.0;
+                    let c = (row / 2) * uv_stride + (col / 2) * 2;
+                    let u = f32::from(uv[c]) - 128.0;
+                    let v = f32::from(uv[c + 1]) - 128.0;
+                    let r = 1.164 * l + 1.596 * v;
+                    let g = 1.164 * l - 0.392 * u - 0.813 * v;
+                    let b = 1.164 * l + 2.017 * u;
+                    rgb.extend(
+                        [r, g, b]
+                            .into_iter()
+                            .map(|c| c.round().clamp(0.0, 255.0) as u8),
+                    );
+                }
+            }
+            VideoFrameData::Rgb(Arc::new(rgb))
+        }
+    };
+
+    VideoFrame {
+        width: width as u32,
+        height: height as u32,
+        timestamp_ms: now_millis(),
+        format,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn backend_names_parse() {
+        assert_eq!("VAAPI".parse(), Ok(HwBackend::Vaapi));
+        assert_eq!("cuda".parse(), Ok(HwBackend::Nvenc));
+        assert_eq!("vt".parse(), Ok(HwBackend::VideoToolbox));
+        assert!("qsv".parse::<HwBackend>().is_err());
+        // With nothing configured every compiled backend is tried
+        assert_eq!(
+            HwBackend::preferred(&Config::empty()),
+            HwBackend::compiled()
+        );
+    }
+
+    #[test]
+    fn nv12_round_trips_through_rgb() {
+        let (w, h) = (4, 2);
+        let rgb: Vec<u8> = [[235u8, 16, 16], [16, 16, 235]]
+            .iter()
+            .flat_map(|c| std::iter::repeat_n(*c, 4))
+            .flatten()
+            .collect();
+        let (y, uv) = rgb_to_nv12(&rgb, w, h);
+        assert_eq!(y.len(), 8);
+        assert_eq!(uv.len(), 4);
+
+        let frame = nv12_to_frame(&y, w, &uv, w, w, h, FrameFormat::Rgb);
+        let VideoFrameData::Rgb(out) = &frame.data else {
+            panic!("expected RGB");
+        };
+        // Chroma is subsampled, so only check the first (sampled) pixel
+        for (a, b) in out[..3].iter().zip(&rgb[..3]) {
+            assert!(a.abs_diff(*b) <= 3, "{out:?}");
+        }
+
+        let yuv = nv12_to_frame(&y, w, &uv, w, w, h, FrameFormat::Yuv420);
+        let VideoFrameData::Yuv420 { y_stride, .. } = yuv.data else {
+            panic!("expected YUV");
+        };
+        assert_eq!(y_stride, 256);
+    }
+}
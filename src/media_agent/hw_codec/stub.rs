@@ -0,0 +1,75 @@
+//! Stand-ins used when no hardware backend feature is enabled.
+//!
+//! They can never be opened, so callers always fall back to software.
+
+use std::convert::Infallible;
+
+use super::HwBackend;
+use crate::media_agent::{
+    encoder_tunables::EncoderTunables,
+    frame_format::FrameFormat,
+    media_agent_error::{MediaAgentError, Result},
+    video_frame::VideoFrame,
+};
+
+pub struct HwEncoder(Infallible);
+
+impl HwEncoder {
+    /// # Errors
+    ///
+    /// Always: no hardware backend is compiled in.
+    pub fn open(
+        backend: HwBackend,
+        _width: u32,
+        _height: u32,
+        _fps: u32,
+        _bitrate: u32,
+        _tunables: EncoderTunables,
+    ) -> Result<Self> {
+        Err(MediaAgentError::Codec(format!(
+            "{backend} support not compiled in"
+        )))
+    }
+
+    /// # Errors
+    ///
+    /// Never returns: a stub encoder cannot exist.
+    pub fn encode(&mut self, _frame: &VideoFrame, _force_keyframe: bool) -> Result<Vec<u8>> {
+        match self.0 {}
+    }
+
+    #[must_use]
+    pub const fn backend(&self) -> HwBackend {
+        match self.0 {}
+    }
+
+    #[must_use]
+    pub const fn size(&self) -> (u32, u32) {
+        match self.0 {}
+    }
+}
+
+pub struct HwDecoder(Infallible);
+
+impl HwDecoder {
+    /// # Errors
+    ///
+    /// Always: no hardware backend is compiled in.
+    pub fn open(backend: HwBackend) -> Result<Self> {
+        Err(MediaAgentError::Codec(format!(
+            "{backend} support not compiled in"
+        )))
+    }
+
+    /// # Errors
+    ///
+    /// Never returns: a stub decoder cannot exist.
+    pub fn decode(&mut self, _bytes: &[u8], _format: FrameFormat) -> Result<Option<VideoFrame>> {
+        match self.0 {}
+    }
+
+    #[must_use]
+    pub const fn backend(&self) -> HwBackend {
+        match self.0 {}
+    }
+}
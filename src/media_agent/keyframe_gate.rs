@@ -0,0 +1,110 @@
+//! Recovery of the remote video after a decode error.
+//!
+//! Once a frame fails to decode, every frame predicted from it would come
+//! out gray or smeared until the next IDR. Rather than show that, the
+//! decoder drops frames until an IDR arrives, the last good frame stays on
+//! screen, and the sender is asked for a keyframe with a PLI. The request
+//! is repeated while waiting, since the PLI or the keyframe itself may be
+//! lost, but no more than once per [`PLI_INTERVAL`].
+
+use std::time::{Duration, Instant};
+
+use crate::media_transport::payload::h264_packetizer::split_annexb_nalus;
+
+const NAL_IDR: u8 = 5;
+/// Least time between two keyframe requests.
+pub const PLI_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Whether the decoder is waiting for a keyframe, and when one was last
+/// asked for.
+#[derive(Debug, Default)]
+pub struct KeyframeGate {
+    waiting: bool,
+    last_request: Option<Instant>,
+}
+
+impl KeyframeGate {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether frames are being dropped until the next IDR.
+    #[must_use]
+    pub const fn is_waiting(&self) -> bool {
+        self.waiting
+    }
+
+    /// Whether the Annex-B access unit `au` should be decoded: always,
+    /// unless waiting and it holds no IDR slice.
+    #[must_use]
+    pub fn admits(&self, au: &[u8]) -> bool {
+        !self.waiting || is_idr(au)
+    }
+
+    /// A frame failed to decode: drop the following ones until an IDR.
+    /// Returns whether to request a keyframe now.
+    pub fn on_error(&mut self, now: Instant) -> bool {
+        self.waiting = true;
+        self.should_request(now)
+    }
+
+    /// A frame was dropped while waiting. Returns whether to request a
+    /// keyframe again.
+    pub fn on_dropped(&mut self, now: Instant) -> bool {
+        self.should_request(now)
+    }
+
+    /// A frame decoded: the stream is sound again.
+    pub const fn on_decoded(&mut self) {
+        self.waiting = false;
+    }
+
+    fn should_request(&mut self, now: Instant) -> bool {
+        if self
+            .last_request
+            .is_some_and(|last| now.saturating_duration_since(last) < PLI_INTERVAL)
+        {
+            return false;
+        }
+        self.last_request = Some(now);
+        true
+    }
+}
+
+/// Whether the access unit holds an IDR slice.
+fn is_idr(au: &[u8]) -> bool {
+    split_annexb_nalus(au)
+        .iter()
+        .any(|nalu| nalu.first().is_some_and(|h| h & 0x1F == NAL_IDR))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    const IDR: &[u8] = &[0, 0, 0, 1, 0x67, 0x42, 0, 0, 0, 1, 0x65, 0x88];
+    const P: &[u8] = &[0, 0, 0, 1, 0x41, 0x9A];
+
+    #[test]
+    fn frames_are_dropped_until_an_idr_and_requests_are_throttled() {
+        let mut gate = KeyframeGate::new();
+        let t0 = Instant::now();
+        assert!(gate.admits(P));
+
+        assert!(gate.on_error(t0));
+        assert!(gate.is_waiting());
+        assert!(!gate.admits(P));
+        assert!(gate.admits(IDR));
+
+        // Asked again only once the interval is over
+        assert!(!gate.on_dropped(t0 + PLI_INTERVAL / 2));
+        assert!(gate.on_dropped(t0 + PLI_INTERVAL));
+        assert!(!gate.on_error(t0 + PLI_INTERVAL));
+
+        gate.on_decoded();
+        assert!(!gate.is_waiting());
+        assert!(gate.admits(P));
+    }
+}
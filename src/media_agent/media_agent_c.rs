@@ -1,35 +1,53 @@
-use super::constants::{KEYINT, TARGET_FPS};
 use crate::config::Config;
-use crate::media_agent::constants::DEFAULT_CAMERA_ID;
+use crate::media_agent::constants::{AUDIO_SAMPLE_RATE, DEFAULT_CAMERA_ID};
 use crate::{
-    core::events::EngineEvent,
+    camera_manager::capture_settings::CaptureSettings,
+    core::{
+        events::EngineEvent,
+        latency::LatencyTracer,
+        thread_join::{JOIN_TIMEOUT, join_timeout},
+    },
     log::log_sink::LogSink,
     media_agent::{
-        audio_capture_worker::{AudioCaptureEvent, spawn_audio_capture_worker},
+        audio_capture_worker::{AudioCaptureEvent, spawn_audio_capture_worker_into},
         audio_codec,
+        audio_devices::configured_device,
+        audio_format::{AudioEncoder, AudioFormat},
+        audio_mixer::{MAX_GAIN, REMOTE_SOURCE},
         audio_player_worker::{AudioPlayerCommand, spawn_audio_player_worker},
-        camera_worker::spawn_camera_worker,
+        camera_worker::spawn_camera_worker_into,
         decoder_event::DecoderEvent,
         decoder_worker::spawn_decoder_worker,
         encoder_instruction::EncoderInstruction,
+        encoder_tunables::EncoderTunables,
         encoder_worker::spawn_encoder_worker,
         events::MediaAgentEvent,
         media_agent_error::MediaAgentError,
+        recorder::{RecordSide, RecordStreams, Recorder, RecordingStatus},
+        resampler::Resampler,
         spec::{CodecSpec, MediaSpec, MediaType},
+        test_source::{DEFAULT_TEST_TONE_HZ, spawn_test_audio_worker, test_video_loop},
         utils::discover_camera_id,
+        vad::{PushToTalk, VoiceActivity, rms_dbfs},
         video_frame::VideoFrame,
+        video_mute::{RemoteVideoMonitor, camera_off_loop},
+        video_track::{TrackId, VideoSource, VideoTrackWorker},
     },
     media_transport::media_transport_event::MediaTransportEvent,
+    rtp::audio_level::AudioLevel,
+    rtsp::rtsp_source::rtsp_video_loop,
     sink_debug, sink_error, sink_info, sink_trace, sink_warn,
 };
 use std::{
+    collections::HashMap,
+    path::PathBuf,
     sync::{
         Arc, Mutex,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU32, Ordering},
         mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
     },
     thread::{self, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 /// The central orchestrator of the media pipeline.
@@ -63,6 +81,46 @@ pub struct MediaAgent {
     /// Flag to track if we have successfully sent at least one keyframe.
     sent_any_frame: Arc<AtomicBool>,
 
+    /// Device picked with [`switch_camera`](Self::switch_camera), if any.
+    camera_id: Option<i32>,
+    /// Lifecycle flag of the current camera worker only, so the device can be
+    /// swapped without stopping the rest of the pipeline.
+    camera_running: Arc<AtomicBool>,
+    /// Producer side of the local frame channel, reused by a new camera worker.
+    local_frame_tx: Option<Sender<VideoFrame>>,
+    /// Engine event channel, where camera loss/recovery is reported.
+    event_tx: Option<Sender<EngineEvent>>,
+
+    /// Requested camera resolution, frame rate and pixel format.
+    capture: CaptureSettings,
+    /// Capture frame rate, shared with the listener for encoder reconfiguration.
+    capture_fps: Arc<AtomicU32>,
+
+    /// Send color bars instead of the camera (`[Media] video_source = "test"`).
+    test_video: bool,
+    /// Send an IP camera's H.264 as is (`[Media] video_source = "rtsp://..."`).
+    rtsp_url: Option<String>,
+    /// Send a sine tone instead of the microphone (`[Media] audio_source = "test"`).
+    test_audio: bool,
+    /// Camera released and replaced by the camera-off placeholder.
+    video_muted: bool,
+    /// Lifecycle flag of the current audio source only.
+    audio_running: Arc<AtomicBool>,
+    /// Producer side of the captured audio channel, reused by a new source.
+    audio_frame_tx: Option<Sender<AudioCaptureEvent>>,
+    /// Microphone name (`[Media] audio_input`); `None` is the host default.
+    audio_input: Option<String>,
+    /// Speaker name (`[Media] audio_output`); `None` is the host default.
+    audio_output: Option<String>,
+    /// Playout gain of the remote audio, kept while it is muted.
+    remote_volume: f32,
+    /// Remote audio is not played, locally only.
+    remote_muted: bool,
+    /// Push-to-talk mode and key state, read by the listener before sending audio.
+    push_to_talk: PushToTalk,
+    /// Format we receive audio in, offered as L16 when it is not plain G.711.
+    audio_format: AudioFormat,
+
     // --- Channels ---
     /// Channel to send events back to the listener loop from outside.
     media_agent_event_tx: Option<Sender<MediaAgentEvent>>,
@@ -70,6 +128,16 @@ pub struct MediaAgent {
     ma_encoder_event_tx: Option<Sender<EncoderInstruction>>,
     /// Channel to send instructions to the audio player worker.
     audio_player_tx: Option<Sender<AudioPlayerCommand>>,
+    /// Channel to the media transport, kept for tracks added mid-call.
+    media_transport_event_tx: Option<Sender<MediaTransportEvent>>,
+
+    /// Extra video tracks added at runtime (screen share, second camera).
+    video_tracks: HashMap<TrackId, VideoTrackWorker>,
+
+    /// Active call recording, fed by the listener.
+    recorder: Arc<Mutex<Option<Recorder>>>,
+    /// Per-stage frame latency, shared with the media transport.
+    latency: LatencyTracer,
 
     running: Arc<AtomicBool>,
     is_audio_muted: Arc<AtomicBool>,
@@ -84,6 +152,39 @@ struct MediaAgentContext<'a> {
     media_transport_event_tx: &'a Sender<MediaTransportEvent>,
     remote_frame: &'a Arc<Mutex<Option<VideoFrame>>>,
     config: &'a Arc<Config>,
+    capture_fps: &'a AtomicU32,
+    recorder: &'a Mutex<Option<Recorder>>,
+    voice: &'a mut VoiceActivity,
+    audio: &'a mut ListenerAudio,
+    remote_video: &'a mut RemoteVideoMonitor,
+    event_tx: Option<&'a Sender<EngineEvent>>,
+}
+
+/// Audio conversion state owned by the listener thread.
+struct ListenerAudio {
+    /// Captured audio to the negotiated send codec and format.
+    encoder: AudioEncoder,
+    /// Format of incoming L16 audio (ours, as offered).
+    receive_format: AudioFormat,
+    /// Recordings are kept at 8 kHz mono for both sides.
+    record_local: Resampler,
+    record_remote: Resampler,
+    /// SSRC of the first remote audio stream, played and recorded as
+    /// [`REMOTE_SOURCE`]. Any other (streams an SFU relays side by side)
+    /// is played alongside it.
+    remote_stream: Option<u32>,
+}
+
+impl ListenerAudio {
+    fn new(receive_format: AudioFormat) -> Self {
+        Self {
+            encoder: AudioEncoder::default(),
+            receive_format,
+            record_local: Resampler::new(AudioFormat::G711),
+            record_remote: Resampler::new(AudioFormat::G711),
+            remote_stream: None,
+        }
+    }
 }
 
 impl MediaAgent {
@@ -93,8 +194,22 @@ impl MediaAgent {
     /// call [`start`](Self::start).
     pub fn new(logger: Arc<dyn LogSink>, config: Arc<Config>) -> Self {
         let sent_any_frame = Arc::new(AtomicBool::new(false));
+        let test_video = config
+            .get("Media", "video_source")
+            .is_some_and(|s| s.eq_ignore_ascii_case("test"));
+        let rtsp_url = config
+            .get_non_empty("Media", "video_source")
+            .filter(|s| s.starts_with("rtsp://"))
+            .map(str::to_string);
+        let test_audio = config
+            .get("Media", "audio_source")
+            .is_some_and(|s| s.eq_ignore_ascii_case("test"));
+        let capture = CaptureSettings::from_config(&config);
+        let audio_input = configured_device(&config, "audio_input");
+        let audio_output = configured_device(&config, "audio_output");
+        let audio_format = AudioFormat::from_config(&config);
 
-        let supported_media = vec![
+        let mut supported_media = vec![
             MediaSpec {
                 media_type: MediaType::Video,
                 codec_spec: CodecSpec::H264,
@@ -103,7 +218,18 @@ impl MediaAgent {
                 media_type: MediaType::Audio,
                 codec_spec: CodecSpec::G711U,
             },
+            MediaSpec {
+                media_type: MediaType::Audio,
+                codec_spec: CodecSpec::TelephoneEvent,
+            },
         ];
+        if audio_format != AudioFormat::G711 {
+            // G.711 stays as the fallback for peers without L16
+            supported_media.push(MediaSpec {
+                media_type: MediaType::Audio,
+                codec_spec: CodecSpec::L16,
+            });
+        }
 
         Self {
             logger,
@@ -117,9 +243,31 @@ impl MediaAgent {
             audio_handle: None,
             audio_player_handle: None,
             sent_any_frame,
+            camera_id: None,
+            camera_running: Arc::new(AtomicBool::new(false)),
+            local_frame_tx: None,
+            event_tx: None,
+            capture,
+            capture_fps: Arc::new(AtomicU32::new(capture.fps)),
+            test_video,
+            rtsp_url,
+            test_audio,
+            video_muted: false,
+            audio_running: Arc::new(AtomicBool::new(false)),
+            audio_frame_tx: None,
+            audio_input,
+            audio_output,
+            remote_volume: 1.0,
+            remote_muted: false,
+            push_to_talk: PushToTalk::from_config(&config),
+            audio_format,
             media_agent_event_tx: None,
             ma_encoder_event_tx: None,
             audio_player_tx: None,
+            media_transport_event_tx: None,
+            video_tracks: HashMap::new(),
+            recorder: Arc::new(Mutex::new(None)),
+            latency: LatencyTracer::default(),
             running: Arc::new(AtomicBool::new(false)),
             is_audio_muted: Arc::new(AtomicBool::new(false)),
             config,
@@ -152,43 +300,31 @@ impl MediaAgent {
         let running = self.running.clone();
         let remote_frame = self.remote_frame.clone();
         let local_frame = self.local_frame.clone();
-
-        let default_camera_id = self
-            .config
-            .get("Media", "default_camera")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(DEFAULT_CAMERA_ID);
+        self.media_transport_event_tx = Some(media_transport_event_tx.clone());
+        // Created first: an RTSP camera feeds encoded frames straight into it
+        let (media_agent_event_tx, media_agent_event_rx) = mpsc::channel::<MediaAgentEvent>();
+        self.media_agent_event_tx = Some(media_agent_event_tx.clone());
 
         // --- 1. Start Camera Worker ---
-        let camera_id = discover_camera_id().unwrap_or(default_camera_id);
         sink_debug!(logger.clone(), "[MediaAgent] Starting Camera Worker...");
-
-        let target_fps = self
-            .config
-            .get("Media", "fps")
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(TARGET_FPS);
-
-        let (local_frame_rx, status, handle) =
-            spawn_camera_worker(target_fps, logger.clone(), camera_id, running.clone());
+        let (local_frame_tx, local_frame_rx) = mpsc::channel();
+        self.local_frame_tx = Some(local_frame_tx);
+        self.event_tx = Some(event_tx.clone());
+        let status = self.spawn_video_source();
         sink_debug!(logger.clone(), "[MediaAgent] Camera Worker Started");
 
         if let Some(msg) = status {
             let _ = event_tx.send(EngineEvent::Status(format!("[MediaAgent] {msg}")));
         }
-        self.camera_handle = handle;
 
         // --- Start Audio Capture Worker ---
         sink_debug!(
             logger.clone(),
             "[MediaAgent] Starting Audio Capture Worker..."
         );
-        let (audio_frame_rx, audio_handle) = spawn_audio_capture_worker(
-            logger.clone(),
-            running.clone(),
-            self.is_audio_muted.clone(),
-        );
-        self.audio_handle = audio_handle;
+        let (audio_frame_tx, audio_frame_rx) = mpsc::channel();
+        self.audio_frame_tx = Some(audio_frame_tx);
+        self.spawn_audio_source();
         sink_debug!(logger.clone(), "[MediaAgent] Audio Capture Worker Started");
 
         // --- Start Audio Player Worker ---
@@ -199,16 +335,18 @@ impl MediaAgent {
             logger.clone(),
             "[MediaAgent] Starting Audio Player Worker..."
         );
-        let audio_player_handle =
-            spawn_audio_player_worker(logger.clone(), audio_player_rx, running.clone());
+        let audio_player_handle = spawn_audio_player_worker(
+            logger.clone(),
+            audio_player_rx,
+            running.clone(),
+            self.audio_output.clone(),
+        );
         self.audio_player_handle = Some(audio_player_handle);
+        self.apply_remote_gain();
         sink_debug!(logger.clone(), "[MediaAgent] Audio Player Worker Started");
 
         // Setup internal channels
         let (ma_decoder_event_tx, ma_decoder_event_rx) = mpsc::channel::<DecoderEvent>();
-        let (media_agent_event_tx, media_agent_event_rx) = mpsc::channel::<MediaAgentEvent>();
-        let media_agent_event_tx_clone = media_agent_event_tx.clone();
-        self.media_agent_event_tx = Some(media_agent_event_tx_clone);
 
         // --- 2. Start Decoder Worker ---
         sink_debug!(logger.clone(), "[MediaAgent] Starting Decoder Worker...");
@@ -217,6 +355,8 @@ impl MediaAgent {
             ma_decoder_event_rx,
             media_agent_event_tx.clone(),
             running.clone(),
+            self.config.clone(),
+            self.latency.clone(),
         ));
         self.decoder_handle = decoder_handle;
         sink_debug!(logger.clone(), "[MediaAgent] Decoder Worker Started");
@@ -254,6 +394,10 @@ impl MediaAgent {
             self.sent_any_frame.clone(),
             running,
             self.config.clone(),
+            self.capture_fps.clone(),
+            self.recorder.clone(),
+            self.event_tx.clone(),
+            self.push_to_talk.clone(),
         );
         self.listener_handle = listener_handle;
         sink_info!(logger.clone(), "[MediaAgent] Listener Started");
@@ -266,32 +410,41 @@ impl MediaAgent {
     /// Signals the `running` atomic flag to false and joins all threads.
     pub fn stop(&mut self) {
         self.running.store(false, Ordering::SeqCst);
+        self.camera_running.store(false, Ordering::SeqCst);
+        self.audio_running.store(false, Ordering::SeqCst);
+        self.local_frame_tx = None;
+        self.audio_frame_tx = None;
+        self.event_tx = None;
 
-        self.media_agent_event_tx = None;
-        self.ma_encoder_event_tx = None;
-
-        if let Some(handle) = self.listener_handle.take() {
-            let _ = handle.join();
-        }
-
-        if let Some(handle) = self.decoder_handle.take() {
-            let _ = handle.join();
+        for (_, mut track) in self.video_tracks.drain() {
+            track.stop();
         }
 
-        if let Some(handle) = self.encoder_handle.take() {
-            let _ = handle.join();
-        }
-
-        if let Some(handle) = self.camera_handle.take() {
-            let _ = handle.join();
-        }
+        self.media_agent_event_tx = None;
+        self.ma_encoder_event_tx = None;
+        self.media_transport_event_tx = None;
 
-        if let Some(handle) = self.audio_handle.take() {
-            let _ = handle.join();
+        // A worker stuck on its device must not hold up the rest
+        for (name, handle) in [
+            ("listener", self.listener_handle.take()),
+            ("decoder", self.decoder_handle.take()),
+            ("encoder", self.encoder_handle.take()),
+            ("camera", self.camera_handle.take()),
+            ("audio capture", self.audio_handle.take()),
+            ("audio player", self.audio_player_handle.take()),
+        ] {
+            if let Some(handle) = handle
+                && !join_timeout(handle, JOIN_TIMEOUT)
+            {
+                sink_warn!(
+                    self.logger,
+                    "[MediaAgent] {name} thread did not stop within {JOIN_TIMEOUT:?}, leaving it"
+                );
+            }
         }
 
-        if let Some(handle) = self.audio_player_handle.take() {
-            let _ = handle.join();
+        if let Err(e) = self.stop_recording() {
+            sink_error!(self.logger, "[MediaAgent] failed to finish recording: {e}");
         }
 
         self.sent_any_frame.store(false, Ordering::SeqCst);
@@ -318,6 +471,415 @@ impl MediaAgent {
         sink_info!(self.logger, "[MediaAgent] Microphone {}", status);
     }
 
+    /// Enables or disables push-to-talk. While enabled, the microphone is
+    /// only sent while [`set_push_to_talk_pressed`](Self::set_push_to_talk_pressed)
+    /// reports the key as held.
+    pub fn set_push_to_talk(&self, enabled: bool) {
+        self.push_to_talk.set_enabled(enabled);
+        let status = if enabled { "on" } else { "off" };
+        sink_info!(self.logger, "[MediaAgent] Push-to-talk {}", status);
+    }
+
+    /// Reports the push-to-talk key state.
+    pub fn set_push_to_talk_pressed(&self, pressed: bool) {
+        self.push_to_talk.set_pressed(pressed);
+    }
+
+    #[must_use]
+    pub fn push_to_talk(&self) -> bool {
+        self.push_to_talk.is_enabled()
+    }
+
+    /// Format we receive audio in (`[Media] audio_sample_rate`, `audio_channels`).
+    #[must_use]
+    pub const fn audio_format(&self) -> AudioFormat {
+        self.audio_format
+    }
+
+    /// Switches the capture device, mid-call included.
+    ///
+    /// The new device feeds the same encoder, and the next frame is forced
+    /// to be a keyframe so the remote decoder can pick up the new stream
+    /// (resolution changes included). When the agent is not running, the
+    /// device is remembered for the next [`start`](Self::start).
+    ///
+    /// Returns the capture status message (resolution or fallback reason).
+    pub fn switch_camera(&mut self, camera_id: i32) -> Option<String> {
+        self.camera_id = Some(camera_id);
+        self.test_video = false;
+        self.rtsp_url = None;
+        let status = self.restart_video_source();
+        if status.is_some() {
+            sink_info!(self.logger, "[MediaAgent] switched to camera {}", camera_id);
+        }
+        status
+    }
+
+    /// Selects the built-in test sources (color bars with a clock burn-in,
+    /// sine tone) instead of the camera and microphone.
+    ///
+    /// Takes effect right away when running (with a forced keyframe), or at
+    /// the next [`start`](Self::start) otherwise.
+    pub fn set_test_sources(&mut self, video: bool, audio: bool) {
+        if self.test_video != video {
+            self.test_video = video;
+            let _ = self.restart_video_source();
+        }
+        if self.test_audio != audio {
+            self.test_audio = audio;
+            self.restart_audio_source();
+        }
+        sink_info!(
+            self.logger,
+            "[MediaAgent] test sources: video={} audio={}",
+            video,
+            audio
+        );
+    }
+
+    /// `(video, audio)`: whether the test sources are selected.
+    #[must_use]
+    pub const fn test_sources(&self) -> (bool, bool) {
+        (self.test_video, self.test_audio)
+    }
+
+    /// Stops (or resumes) camera capture.
+    ///
+    /// While muted, the camera is released and a camera-off picture is sent
+    /// at a low frame rate instead, so the track stays negotiated and the
+    /// peer can show a camera-off tile. Takes effect right away when
+    /// running, or at the next [`start`](Self::start) otherwise.
+    pub fn set_video_muted(&mut self, muted: bool) {
+        if self.video_muted == muted {
+            return;
+        }
+        self.video_muted = muted;
+        let _ = self.restart_video_source();
+        let status = if muted { "off" } else { "on" };
+        sink_info!(self.logger, "[MediaAgent] Camera {}", status);
+    }
+
+    #[must_use]
+    pub const fn video_muted(&self) -> bool {
+        self.video_muted
+    }
+
+    /// Encodes the next camera frame as a keyframe (the peer lost the
+    /// stream and sent a PLI).
+    pub fn request_keyframe(&self) {
+        self.sent_any_frame.store(false, Ordering::SeqCst);
+    }
+
+    /// Replaces the running video source with the selected one.
+    fn restart_video_source(&mut self) -> Option<String> {
+        self.local_frame_tx.as_ref()?;
+
+        self.camera_running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.camera_handle.take() {
+            let _ = handle.join();
+        }
+        let status = self.spawn_video_source();
+        self.sent_any_frame.store(false, Ordering::SeqCst);
+        status
+    }
+
+    /// Spawns the camera (or test pattern, or camera-off placeholder) worker
+    /// into `local_frame_tx`, or the RTSP puller into the encoded frames.
+    fn spawn_video_source(&mut self) -> Option<String> {
+        let tx = self.local_frame_tx.clone()?;
+        self.camera_running = Arc::new(AtomicBool::new(true));
+
+        if self.video_muted {
+            let logger = self.logger.clone();
+            let running = self.camera_running.clone();
+            self.camera_handle = thread::Builder::new()
+                .name("media-agent-camera-off".into())
+                .spawn(move || camera_off_loop(logger, tx, running))
+                .ok();
+            return Some("Camera off".into());
+        }
+
+        if self.test_video {
+            let logger = self.logger.clone();
+            let running = self.camera_running.clone();
+            let fps = self.target_fps();
+            self.camera_handle = thread::Builder::new()
+                .name("media-agent-test-video".into())
+                .spawn(move || {
+                    let _ = test_video_loop(logger, tx, fps, running);
+                })
+                .ok();
+            return Some("Using test source (color bars)".into());
+        }
+
+        if let Some(url) = self.rtsp_url.clone() {
+            let event_tx = self.media_agent_event_tx.clone()?;
+            let logger = self.logger.clone();
+            let running = self.camera_running.clone();
+            self.camera_handle = thread::Builder::new()
+                .name("media-agent-rtsp".into())
+                .spawn(move || rtsp_video_loop(&url, &logger, &event_tx, &running))
+                .ok();
+            return Some("Using RTSP camera".into());
+        }
+
+        let (status, handle) = spawn_camera_worker_into(
+            self.capture,
+            self.logger.clone(),
+            self.selected_camera_id(),
+            self.camera_running.clone(),
+            tx,
+            self.event_tx.clone(),
+        );
+        self.camera_handle = handle;
+        status
+    }
+
+    /// Selects the microphone by name (`None` = host default), mid-call
+    /// included. Ignored by the test tone until it is turned off.
+    pub fn set_audio_input(&mut self, device: Option<String>) {
+        sink_info!(self.logger, "[MediaAgent] audio input: {:?}", device);
+        self.audio_input = device;
+        if !self.test_audio {
+            self.restart_audio_source();
+        }
+    }
+
+    /// Selects the speaker by name (`None` = host default), mid-call
+    /// included. Only the output stream is reopened; the player keeps its
+    /// buffer and the rest of the pipeline keeps running.
+    pub fn set_audio_output(&mut self, device: Option<String>) {
+        sink_info!(self.logger, "[MediaAgent] audio output: {:?}", device);
+        self.audio_output = device.clone();
+        if let Some(tx) = &self.audio_player_tx {
+            let _ = tx.send(AudioPlayerCommand::SwitchDevice(device));
+        }
+    }
+
+    /// Sets how loud the remote audio plays (1.0 = as received, up to
+    /// [`MAX_GAIN`]), mid-call included. Only playout changes; nothing is
+    /// renegotiated and the peer is not told.
+    pub fn set_remote_volume(&mut self, volume: f32) {
+        self.remote_volume = volume.clamp(0.0, MAX_GAIN);
+        self.apply_remote_gain();
+    }
+
+    #[must_use]
+    pub const fn remote_volume(&self) -> f32 {
+        self.remote_volume
+    }
+
+    /// Stops or resumes playing the remote audio, locally only. The volume
+    /// is kept for when it is unmuted.
+    pub fn set_remote_audio_muted(&mut self, muted: bool) {
+        sink_info!(self.logger, "[MediaAgent] remote audio muted: {}", muted);
+        self.remote_muted = muted;
+        self.apply_remote_gain();
+    }
+
+    #[must_use]
+    pub const fn remote_audio_muted(&self) -> bool {
+        self.remote_muted
+    }
+
+    fn apply_remote_gain(&self) {
+        let gain = if self.remote_muted {
+            0.0
+        } else {
+            self.remote_volume
+        };
+        if let Some(tx) = &self.audio_player_tx {
+            let _ = tx.send(AudioPlayerCommand::SetGain {
+                source: REMOTE_SOURCE.to_owned(),
+                gain,
+            });
+        }
+    }
+
+    /// `(input, output)` device names; `None` means the host default.
+    #[must_use]
+    pub fn audio_devices(&self) -> (Option<String>, Option<String>) {
+        (self.audio_input.clone(), self.audio_output.clone())
+    }
+
+    /// Replaces the running audio source with the selected one.
+    fn restart_audio_source(&mut self) {
+        if self.audio_frame_tx.is_none() {
+            return;
+        }
+        self.audio_running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.audio_handle.take() {
+            let _ = handle.join();
+        }
+        self.spawn_audio_source();
+    }
+
+    /// Spawns the microphone (or test tone) worker into `audio_frame_tx`.
+    fn spawn_audio_source(&mut self) {
+        let Some(tx) = self.audio_frame_tx.clone() else {
+            return;
+        };
+        self.audio_running = Arc::new(AtomicBool::new(true));
+
+        self.audio_handle = if self.test_audio {
+            let freq = self
+                .config
+                .get("Media", "test_tone_hz")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_TEST_TONE_HZ);
+            spawn_test_audio_worker(
+                self.logger.clone(),
+                self.audio_running.clone(),
+                self.is_audio_muted.clone(),
+                freq,
+                tx,
+            )
+        } else {
+            spawn_audio_capture_worker_into(
+                self.logger.clone(),
+                self.audio_running.clone(),
+                self.is_audio_muted.clone(),
+                self.audio_input.clone(),
+                tx,
+            )
+        };
+    }
+
+    /// Device used by the main camera worker.
+    ///
+    /// Priority: [`switch_camera`](Self::switch_camera), the `[Media] camera`
+    /// key, the first device that opens, then `[Media] default_camera`.
+    fn selected_camera_id(&self) -> i32 {
+        self.camera_id
+            .or_else(|| {
+                self.config
+                    .get("Media", "camera")
+                    .and_then(|s| s.parse().ok())
+            })
+            .or_else(discover_camera_id)
+            .unwrap_or_else(|| {
+                self.config
+                    .get("Media", "default_camera")
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(DEFAULT_CAMERA_ID)
+            })
+    }
+
+    const fn target_fps(&self) -> u32 {
+        self.capture.fps
+    }
+
+    /// Changes the camera resolution, frame rate and pixel format.
+    ///
+    /// When running, the camera worker re-opens the device with the new
+    /// format and the encoder restarts with a keyframe at the new size and
+    /// frame rate. Returns the capture status, or `None` when not running.
+    pub fn set_capture_settings(&mut self, settings: CaptureSettings) -> Option<String> {
+        self.capture = settings;
+        self.capture_fps.store(settings.fps, Ordering::Relaxed);
+        if let Some(tx) = &self.ma_encoder_event_tx {
+            let _ = tx.send(EncoderInstruction::SetFps(settings.fps));
+        }
+        sink_info!(self.logger, "[MediaAgent] capture settings: {:?}", settings);
+        self.restart_video_source()
+    }
+
+    #[must_use]
+    pub const fn capture_settings(&self) -> CaptureSettings {
+        self.capture
+    }
+
+    /// Starts recording the call to a Matroska file.
+    ///
+    /// `path` defaults to a timestamped file in `[Media] recording_dir`. The
+    /// next local frame is forced to be a keyframe so the video track starts
+    /// right away; the remote track starts at the peer's next keyframe.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if a recording is already running or the
+    /// file cannot be created.
+    pub fn start_recording(
+        &mut self,
+        path: Option<PathBuf>,
+        streams: RecordStreams,
+    ) -> Result<PathBuf, MediaAgentError> {
+        let mut guard = self
+            .recorder
+            .lock()
+            .map_err(|_| MediaAgentError::Io("recorder lock poisoned".into()))?;
+        if guard.is_some() {
+            return Err(MediaAgentError::Io("already recording".into()));
+        }
+        let path = match path {
+            Some(path) => path,
+            None => Recorder::default_path(&self.config)?,
+        };
+        *guard = Some(Recorder::create(&path, streams, AUDIO_SAMPLE_RATE)?);
+        self.sent_any_frame.store(false, Ordering::SeqCst);
+        sink_info!(self.logger, "[MediaAgent] recording to {}", path.display());
+        Ok(path)
+    }
+
+    /// Stops the recording and returns the file path, or `None` if nothing
+    /// was being recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if the file cannot be finalized.
+    pub fn stop_recording(&mut self) -> Result<Option<PathBuf>, MediaAgentError> {
+        let recorder = self.recorder.lock().ok().and_then(|mut guard| guard.take());
+        let Some(recorder) = recorder else {
+            return Ok(None);
+        };
+        let path = recorder.finish()?;
+        sink_info!(
+            self.logger,
+            "[MediaAgent] saved recording {}",
+            path.display()
+        );
+        Ok(Some(path))
+    }
+
+    /// Path and elapsed time of the running recording.
+    #[must_use]
+    pub fn recording(&self) -> Option<RecordingStatus> {
+        self.recorder
+            .lock()
+            .ok()
+            .and_then(|guard| guard.as_ref().map(Recorder::status))
+    }
+
+    /// Starts capturing and encoding an extra video track.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Send` if the agent is not running, or
+    /// `MediaAgentError::EncoderSpawn` if the track workers cannot start.
+    pub fn add_video_track(
+        &mut self,
+        id: TrackId,
+        source: VideoSource,
+    ) -> Result<(), MediaAgentError> {
+        let Some(tx) = self.media_transport_event_tx.clone() else {
+            return Err(MediaAgentError::Send("media agent not running".into()));
+        };
+        let worker =
+            VideoTrackWorker::spawn(id, source, self.logger.clone(), self.config.clone(), tx)?;
+        if let Some(mut old) = self.video_tracks.insert(id, worker) {
+            old.stop();
+        }
+        Ok(())
+    }
+
+    /// Stops an extra video track. Returns `false` if it did not exist.
+    pub fn remove_video_track(&mut self, id: TrackId) -> bool {
+        self.video_tracks.remove(&id).is_some_and(|mut track| {
+            track.stop();
+            true
+        })
+    }
+
     /// Enqueues an event into the MediaAgent's internal processing loop.
     pub fn post_event(&self, event: MediaAgentEvent) {
         if let Some(media_agent_event_tx) = self.media_agent_event_tx.clone()
@@ -335,6 +897,12 @@ impl MediaAgent {
         self.media_agent_event_tx.clone()
     }
 
+    /// Per-stage latency of the frames going through this agent.
+    #[must_use]
+    pub fn latency(&self) -> &LatencyTracer {
+        &self.latency
+    }
+
     /// Returns a snapshot of the current local and remote frames.
     ///
     /// Used by the UI layer to render the latest available video.
@@ -368,6 +936,10 @@ impl MediaAgent {
         sent_any_frame: Arc<AtomicBool>,
         running: Arc<AtomicBool>,
         config: Arc<Config>,
+        capture_fps: Arc<AtomicU32>,
+        recorder: Arc<Mutex<Option<Recorder>>>,
+        event_tx: Option<Sender<EngineEvent>>,
+        push_to_talk: PushToTalk,
     ) -> Option<JoinHandle<()>> {
         sink_info!(logger, "[MA Listener] Starting...");
         thread::Builder::new()
@@ -387,6 +959,10 @@ impl MediaAgent {
                     sent_any_frame,
                     running,
                     config,
+                    capture_fps,
+                    recorder,
+                    event_tx,
+                    push_to_talk,
                 );
             })
             .ok()
@@ -412,7 +988,14 @@ impl MediaAgent {
         sent_any_frame: Arc<AtomicBool>,
         running: Arc<AtomicBool>,
         config: Arc<Config>,
+        capture_fps: Arc<AtomicU32>,
+        recorder: Arc<Mutex<Option<Recorder>>>,
+        event_tx: Option<Sender<EngineEvent>>,
+        push_to_talk: PushToTalk,
     ) {
+        let mut voice = VoiceActivity::new(&config, event_tx.clone());
+        let mut remote_video = RemoteVideoMonitor::new();
+        let mut audio = ListenerAudio::new(AudioFormat::from_config(&config));
         while running.load(Ordering::Relaxed) {
             // Prioritize clearing the camera buffer to avoid latency build-up
             Self::drain_camera_frames(
@@ -423,7 +1006,15 @@ impl MediaAgent {
                 &sent_any_frame,
             );
 
-            Self::drain_audio_frames(&logger, &audio_frame_rx, &media_transport_event_tx);
+            Self::drain_audio_frames(
+                &logger,
+                &audio_frame_rx,
+                &media_transport_event_tx,
+                &recorder,
+                &mut voice,
+                &mut audio,
+                &push_to_talk,
+            );
 
             // Poll for other events with a short timeout to keep the loop responsive
             match media_agent_event_rx.recv_timeout(Duration::from_millis(5)) {
@@ -436,6 +1027,12 @@ impl MediaAgent {
                         media_transport_event_tx: &media_transport_event_tx,
                         remote_frame: &remote_frame,
                         config: &config,
+                        capture_fps: &capture_fps,
+                        recorder: &recorder,
+                        voice: &mut voice,
+                        audio: &mut audio,
+                        remote_video: &mut remote_video,
+                        event_tx: event_tx.as_ref(),
                     };
                     Self::handle_media_agent_event(ctx, event);
                 }
@@ -448,6 +1045,11 @@ impl MediaAgent {
                     break;
                 }
             }
+
+            // The peer stopped sending video altogether
+            if let Some(muted) = remote_video.poll(Instant::now()) {
+                Self::report_remote_video(event_tx.as_ref(), muted);
+            }
         }
         sink_debug!(logger, "[MediaAgent Listener] Thread closing gracefully");
     }
@@ -487,6 +1089,10 @@ impl MediaAgent {
         logger: &Arc<dyn LogSink>,
         audio_frame_rx: &Receiver<AudioCaptureEvent>,
         media_transport_event_tx: &Sender<MediaTransportEvent>,
+        recorder: &Mutex<Option<Recorder>>,
+        voice: &mut VoiceActivity,
+        audio: &mut ListenerAudio,
+        push_to_talk: &PushToTalk,
     ) {
         loop {
             match audio_frame_rx.try_recv() {
@@ -499,15 +1105,33 @@ impl MediaAgent {
                             frame.samples
                         );
 
-                        let encoded_payload = audio_codec::encode(&frame.data);
+                        if !push_to_talk.transmits() {
+                            // Push-to-talk key released: nothing is sent
+                            voice.observe(false, &[]);
+                            continue;
+                        }
+                        voice.observe(false, &frame.data);
 
-                        let _ = media_transport_event_tx.send(
-                            MediaTransportEvent::SendEncodedAudioFrame {
-                                payload: encoded_payload,
-                                timestamp_ms: frame.timestamp_ms,
-                                codec_spec: CodecSpec::G711U,
-                            },
-                        );
+                        let from = AudioFormat::new(frame.sample_rate, frame.channels);
+                        Self::record(logger, recorder, |r| {
+                            let pcm = audio.record_local.process(from, &frame.data);
+                            r.write_audio(RecordSide::Local, &pcm)
+                        });
+
+                        let codec_spec = audio.encoder.codec();
+                        let level =
+                            AudioLevel::from_dbfs(rms_dbfs(&frame.data), voice.local_speaking());
+                        for packet in audio.encoder.push(from, &frame.data) {
+                            let _ = media_transport_event_tx.send(
+                                MediaTransportEvent::SendEncodedAudioFrame {
+                                    payload: packet.payload,
+                                    timestamp_ms: frame.timestamp_ms,
+                                    codec_spec,
+                                    samples: packet.samples,
+                                    level,
+                                },
+                            );
+                        }
                     }
                     AudioCaptureEvent::Error(e) => {
                         sink_warn!(logger, "[MediaAgent] Audio capture error: {}", e);
@@ -558,13 +1182,22 @@ impl MediaAgent {
         }
     }
 
+    fn report_remote_video(event_tx: Option<&Sender<EngineEvent>>, muted: bool) {
+        if let Some(tx) = event_tx {
+            let _ = tx.send(EngineEvent::RemoteVideoMuted(muted));
+        }
+    }
+
     /// Routes system events to their appropriate destinations.
-    fn handle_media_agent_event(ctx: MediaAgentContext, event: MediaAgentEvent) {
+    fn handle_media_agent_event(mut ctx: MediaAgentContext, event: MediaAgentEvent) {
         match event {
             MediaAgentEvent::DecodedVideoFrame(frame) => {
                 sink_trace!(ctx.logger, "[MediaAgent] Received DecodedVideoFrame");
                 let frame = *frame;
                 let ts = frame.timestamp_ms;
+                if let Some(muted) = ctx.remote_video.on_frame(Instant::now()) {
+                    Self::report_remote_video(ctx.event_tx, muted);
+                }
 
                 // Update remote UI snapshot
                 if let Ok(mut guard) = ctx.remote_frame.lock() {
@@ -591,6 +1224,9 @@ impl MediaAgent {
                     ctx.logger,
                     "[MediaAgent] Received EncodedVideoFrame from Encoder. Now sending SendEncodedFrame to Media Transport"
                 );
+                Self::record(ctx.logger, ctx.recorder, |r| {
+                    r.write_video(RecordSide::Local, &annexb_frame)
+                });
                 // Forward to network layer
                 if ctx
                     .media_transport_event_tx
@@ -607,16 +1243,27 @@ impl MediaAgent {
                     );
                 }
             }
-            MediaAgentEvent::AnnexBFrameReady { codec_spec, bytes } => {
+            MediaAgentEvent::AnnexBFrameReady {
+                codec_spec,
+                bytes,
+                received_ms,
+            } => {
                 sink_trace!(
                     ctx.logger,
                     "[MediaAgent] forwarding AnnexB payload to decoder ({:?})",
                     codec_spec
                 );
+                Self::record(ctx.logger, ctx.recorder, |r| {
+                    r.write_video(RecordSide::Remote, &bytes)
+                });
                 // Forward to decoder worker
                 if ctx
                     .ma_decoder_event_tx
-                    .send(DecoderEvent::AnnexBFrameReady { codec_spec, bytes })
+                    .send(DecoderEvent::AnnexBFrameReady {
+                        codec_spec,
+                        bytes,
+                        received_ms,
+                    })
                     .is_err()
                 {
                     sink_warn!(
@@ -626,16 +1273,8 @@ impl MediaAgent {
                 }
             }
             MediaAgentEvent::UpdateBitrate(b) => {
-                let fps = ctx
-                    .config
-                    .get("Media", "fps")
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(TARGET_FPS);
-                let keyint = ctx
-                    .config
-                    .get("Media", "keyframe_interval")
-                    .and_then(|s| s.parse().ok())
-                    .unwrap_or(KEYINT);
+                let fps = ctx.capture_fps.load(Ordering::Relaxed);
+                let keyint = EncoderTunables::from_config(&ctx.config).keyint;
 
                 let instruction = EncoderInstruction::SetConfig {
                     fps,
@@ -646,20 +1285,59 @@ impl MediaAgent {
                     sink_debug!(ctx.logger, "Reconfigured H264 encoder: bitrate={}bps", b,);
                 }
             }
+            MediaAgentEvent::KeyframeNeeded => {
+                if ctx
+                    .media_transport_event_tx
+                    .send(MediaTransportEvent::RequestKeyframe)
+                    .is_err()
+                {
+                    sink_warn!(
+                        ctx.logger,
+                        "[MediaAgent] media transport channel dropped keyframe request"
+                    );
+                }
+            }
+            MediaAgentEvent::SetAudioSendFormat { codec_spec, format } => {
+                sink_info!(
+                    ctx.logger,
+                    "[MediaAgent] Sending audio as {:?} at {} Hz, {} channel(s)",
+                    codec_spec,
+                    format.sample_rate,
+                    format.channels
+                );
+                ctx.audio.encoder = AudioEncoder::new(codec_spec, format);
+            }
             MediaAgentEvent::EncodedAudioFrame {
                 payload,
                 codec_spec,
+                ssrc,
             } => {
                 sink_trace!(
                     ctx.logger,
                     "[MediaAgent] Decoding audio frame ({:?})",
                     codec_spec
                 );
-                let decoded_samples = audio_codec::decode(&payload);
-                if let Err(e) = ctx
-                    .audio_player_tx
-                    .send(AudioPlayerCommand::PlayFrame(decoded_samples))
-                {
+                let (decoded_samples, format) = match codec_spec {
+                    CodecSpec::L16 => (audio_codec::decode_l16(&payload), ctx.audio.receive_format),
+                    _ => (audio_codec::decode(&payload), AudioFormat::G711),
+                };
+                ctx.voice.observe(true, &decoded_samples);
+                let audio = &mut *ctx.audio;
+                let source = if *audio.remote_stream.get_or_insert(ssrc) == ssrc {
+                    // The recording has a single remote track
+                    Self::record(ctx.logger, ctx.recorder, |r| {
+                        let pcm = audio.record_remote.process(format, &decoded_samples);
+                        r.write_audio(RecordSide::Remote, &pcm)
+                    });
+                    REMOTE_SOURCE.to_owned()
+                } else {
+                    format!("{REMOTE_SOURCE}/{ssrc:08x}")
+                };
+                if let Err(e) = ctx.audio_player_tx.send(AudioPlayerCommand::PlayFrame {
+                    source,
+                    samples: decoded_samples,
+                    format,
+                }) {
                     sink_error!(
                         ctx.logger,
                         "[MediaAgent] Failed to send PlayFrame command: {}",
@@ -669,4 +1347,24 @@ impl MediaAgent {
             }
         }
     }
+
+    /// Feeds the active recording, if any. A write error ends the recording.
+    fn record(
+        logger: &Arc<dyn LogSink>,
+        recorder: &Mutex<Option<Recorder>>,
+        write: impl FnOnce(&mut Recorder) -> Result<(), MediaAgentError>,
+    ) {
+        let Ok(mut guard) = recorder.lock() else {
+            return;
+        };
+        let Some(rec) = guard.as_mut() else {
+            return;
+        };
+        if let Err(e) = write(rec) {
+            sink_error!(logger, "[MediaAgent] recording stopped: {e}");
+            if let Some(rec) = guard.take() {
+                let _ = rec.finish();
+            }
+        }
+    }
 }
@@ -1,21 +1,38 @@
 pub mod audio_capture_error;
 pub mod audio_capture_worker;
 pub mod audio_codec;
+pub mod audio_devices;
+pub mod audio_format;
 pub mod audio_frame;
+pub mod audio_mixer;
 pub mod audio_player_worker;
 pub mod camera_worker;
 pub mod constants;
 pub mod decoder_event;
 pub mod decoder_worker;
+pub mod drift_compensator;
 pub mod encoder_instruction;
+pub mod encoder_tunables;
 pub mod encoder_worker;
 pub mod events;
 pub mod frame_format;
 pub mod h264_decoder;
 mod h264_encoder;
+pub mod hw_codec;
+pub mod keyframe_gate;
 pub mod media_agent_c;
 pub mod media_agent_error;
+pub mod parameter_sets;
+pub mod recorder;
+pub mod resampler;
 pub mod spec;
+pub mod test_source;
 pub mod utils;
+pub mod vad;
+pub mod video_decoder;
+pub mod video_encoder;
 pub mod video_frame;
+pub mod video_mute;
+pub mod video_track;
+pub mod watermark;
 pub use media_agent_c::MediaAgent;
@@ -0,0 +1,111 @@
+//! Receive-side cache of the H.264 parameter sets.
+//!
+//! A decoder can't start on an IDR frame without the SPS and PPS it refers
+//! to. Some senders only put them in front of their first keyframe, and ours
+//! is lost to the decoder whenever it is reset after an error or the call is
+//! joined late. The latest SPS and PPS seen are kept and put back in front
+//! of any IDR access unit that comes without them.
+
+use std::borrow::Cow;
+
+use crate::media_transport::payload::h264_packetizer::split_annexb_nalus;
+
+const NAL_IDR: u8 = 5;
+const NAL_SPS: u8 = 7;
+const NAL_PPS: u8 = 8;
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+/// The latest SPS and PPS of a stream.
+#[derive(Debug, Default)]
+pub struct ParameterSetCache {
+    sps: Option<Vec<u8>>,
+    pps: Option<Vec<u8>>,
+}
+
+impl ParameterSetCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remembers the parameter sets of the Annex-B access unit `au` and
+    /// returns it ready to decode: unchanged, or, if it is an IDR missing
+    /// its SPS or PPS, with the cached ones in front.
+    pub fn prepare<'a>(&mut self, au: &'a [u8]) -> Cow<'a, [u8]> {
+        let (mut has_sps, mut has_pps, mut is_idr) = (false, false, false);
+        for nalu in split_annexb_nalus(au) {
+            match nalu.first().map(|h| h & 0x1F) {
+                Some(NAL_SPS) => {
+                    has_sps = true;
+                    self.sps = Some(nalu.to_vec());
+                }
+                Some(NAL_PPS) => {
+                    has_pps = true;
+                    self.pps = Some(nalu.to_vec());
+                }
+                Some(NAL_IDR) => is_idr = true,
+                _ => {}
+            }
+        }
+        if !is_idr || (has_sps && has_pps) {
+            return Cow::Borrowed(au);
+        }
+
+        let mut out = Vec::with_capacity(au.len() + 64);
+        for (present, set) in [(has_sps, &self.sps), (has_pps, &self.pps)] {
+            if !present && let Some(set) = set {
+                out.extend_from_slice(&START_CODE);
+                out.extend_from_slice(set);
+            }
+        }
+        if out.is_empty() {
+            // Nothing seen yet to put back
+            return Cow::Borrowed(au);
+        }
+        out.extend_from_slice(au);
+        Cow::Owned(out)
+    }
+
+    /// Whether both an SPS and a PPS have been seen.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.sps.is_some() && self.pps.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    const SPS: &[u8] = &[0x67, 0x42, 0xC0, 0x1E];
+    const PPS: &[u8] = &[0x68, 0xCE, 0x3C, 0x80];
+
+    fn annexb(nalus: &[&[u8]]) -> Vec<u8> {
+        nalus
+            .iter()
+            .flat_map(|n| [&START_CODE[..], n].concat())
+            .collect()
+    }
+
+    #[test]
+    fn idr_without_parameter_sets_gets_the_cached_ones() {
+        let mut cache = ParameterSetCache::new();
+        let bare_idr = annexb(&[&[0x65, 0x88, 0x84]]);
+        // Nothing cached yet: passed through as is
+        assert_eq!(cache.prepare(&bare_idr).as_ref(), &bare_idr[..]);
+
+        let full_idr = annexb(&[SPS, PPS, &[0x65, 0x88, 0x84]]);
+        assert!(matches!(cache.prepare(&full_idr), Cow::Borrowed(_)));
+        assert!(cache.is_complete());
+
+        // P slices are never touched
+        let p = annexb(&[&[0x41, 0x9A]]);
+        assert!(matches!(cache.prepare(&p), Cow::Borrowed(_)));
+
+        assert_eq!(cache.prepare(&bare_idr).as_ref(), &full_idr[..]);
+        // Only what is missing is added
+        let idr_with_pps = annexb(&[PPS, &[0x65, 0x88, 0x84]]);
+        assert_eq!(cache.prepare(&idr_with_pps).as_ref(), &full_idr[..]);
+    }
+}
@@ -0,0 +1,253 @@
+//! H.264 helpers for muxing: Annex-B to length-prefixed NAL units, `avcC`
+//! construction and SPS picture size parsing.
+
+use crate::media_transport::payload::h264_packetizer::split_annexb_nalus;
+
+use super::mkv::AvcConfig;
+
+const NAL_IDR: u8 = 5;
+const NAL_SPS: u8 = 7;
+const NAL_PPS: u8 = 8;
+const NAL_AUD: u8 = 9;
+
+/// One access unit, split for the muxer.
+pub struct AccessUnit<'a> {
+    pub sps: Option<&'a [u8]>,
+    pub pps: Option<&'a [u8]>,
+    pub keyframe: bool,
+    /// NAL units with 4-byte big-endian length prefixes (AUDs dropped).
+    pub avcc_payload: Vec<u8>,
+}
+
+#[must_use]
+pub fn parse_access_unit(annexb: &[u8]) -> AccessUnit<'_> {
+    let mut au = AccessUnit {
+        sps: None,
+        pps: None,
+        keyframe: false,
+        avcc_payload: Vec::with_capacity(annexb.len() + 8),
+    };
+    for nal in split_annexb_nalus(annexb) {
+        let Some(header) = nal.first() else {
+            continue;
+        };
+        match header & 0x1F {
+            NAL_IDR => au.keyframe = true,
+            NAL_SPS => au.sps = Some(nal),
+            NAL_PPS => au.pps = Some(nal),
+            NAL_AUD => continue,
+            _ => {}
+        }
+        let Ok(len) = u32::try_from(nal.len()) else {
+            continue;
+        };
+        au.avcc_payload.extend_from_slice(&len.to_be_bytes());
+        au.avcc_payload.extend_from_slice(nal);
+    }
+    au
+}
+
+/// Builds the track configuration from one SPS and PPS.
+///
+/// Returns `None` if the SPS cannot be parsed.
+#[must_use]
+pub fn avc_config(sps: &[u8], pps: &[u8]) -> Option<AvcConfig> {
+    let (width, height) = sps_picture_size(sps)?;
+    let sps_len = u16::try_from(sps.len()).ok()?;
+    let pps_len = u16::try_from(pps.len()).ok()?;
+
+    let mut avcc = vec![
+        1,      // configurationVersion
+        sps[1], // AVCProfileIndication
+        sps[2], // profile_compatibility
+        sps[3], // AVCLevelIndication
+        0xFF,   // 4-byte NAL lengths
+        0xE1,   // one SPS
+    ];
+    avcc.extend_from_slice(&sps_len.to_be_bytes());
+    avcc.extend_from_slice(sps);
+    avcc.push(1);
+    avcc.extend_from_slice(&pps_len.to_be_bytes());
+    avcc.extend_from_slice(pps);
+
+    Some(AvcConfig {
+        width,
+        height,
+        avcc,
+    })
+}
+
+/// Cropped picture size from an SPS NAL unit (header byte included).
+#[must_use]
+pub fn sps_picture_size(sps: &[u8]) -> Option<(u32, u32)> {
+    let rbsp = unescape(sps.get(1..)?);
+    let mut r = BitReader::new(&rbsp);
+
+    let profile_idc = r.bits(8)?;
+    r.bits(16)?; // constraint flags + level_idc
+    r.ue()?; // seq_parameter_set_id
+
+    let mut chroma_format_idc = 1;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = r.ue()?;
+        if chroma_format_idc == 3 {
+            r.bits(1)?; // separate_colour_plane_flag
+        }
+        r.ue()?; // bit_depth_luma_minus8
+        r.ue()?; // bit_depth_chroma_minus8
+        r.bits(1)?; // qpprime_y_zero_transform_bypass_flag
+        if r.bits(1)? == 1 {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if r.bits(1)? == 1 {
+                    skip_scaling_list(&mut r, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    r.ue()?; // log2_max_frame_num_minus4
+    match r.ue()? {
+        0 => {
+            r.ue()?; // log2_max_pic_order_cnt_lsb_minus4
+        }
+        1 => {
+            r.bits(1)?; // delta_pic_order_always_zero_flag
+            r.se()?; // offset_for_non_ref_pic
+            r.se()?; // offset_for_top_to_bottom_field
+            for _ in 0..r.ue()? {
+                r.se()?;
+            }
+        }
+        _ => {}
+    }
+    r.ue()?; // max_num_ref_frames
+    r.bits(1)?; // gaps_in_frame_num_value_allowed_flag
+
+    let width_mbs = r.ue()? + 1;
+    let height_map_units = r.ue()? + 1;
+    let frame_mbs_only = r.bits(1)?;
+    if frame_mbs_only == 0 {
+        r.bits(1)?; // mb_adaptive_frame_field_flag
+    }
+    r.bits(1)?; // direct_8x8_inference_flag
+
+    let mut width = width_mbs * 16;
+    let mut height = (2 - frame_mbs_only) * height_map_units * 16;
+    if r.bits(1)? == 1 {
+        let (left, right, top, bottom) = (r.ue()?, r.ue()?, r.ue()?, r.ue()?);
+        let (crop_x, crop_y) = match chroma_format_idc {
+            0 | 3 => (1, 2 - frame_mbs_only),
+            2 => (2, 2 - frame_mbs_only),
+            _ => (2, 2 * (2 - frame_mbs_only)),
+        };
+        width = width.checked_sub((left + right) * crop_x)?;
+        height = height.checked_sub((top + bottom) * crop_y)?;
+    }
+    Some((width, height))
+}
+
+fn skip_scaling_list(r: &mut BitReader, size: usize) -> Option<()> {
+    let mut last = 8i64;
+    let mut next = 8i64;
+    for _ in 0..size {
+        if next != 0 {
+            next = (last + i64::from(r.se()?) + 256) % 256;
+        }
+        if next != 0 {
+            last = next;
+        }
+    }
+    Some(())
+}
+
+/// Removes emulation prevention bytes (`00 00 03` -> `00 00`).
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &b in data {
+        if zeros >= 2 && b == 3 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+        out.push(b);
+    }
+    out
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bits(&mut self, n: u32) -> Option<u32> {
+        let mut v = 0u32;
+        for _ in 0..n {
+            let byte = self.data.get(self.pos / 8)?;
+            let bit = (byte >> (7 - self.pos % 8)) & 1;
+            v = (v << 1) | u32::from(bit);
+            self.pos += 1;
+        }
+        Some(v)
+    }
+
+    /// Unsigned Exp-Golomb.
+    fn ue(&mut self) -> Option<u32> {
+        let mut zeros = 0;
+        while self.bits(1)? == 0 {
+            zeros += 1;
+            if zeros > 31 {
+                return None;
+            }
+        }
+        Some((1u32 << zeros) - 1 + self.bits(zeros)?)
+    }
+
+    /// Signed Exp-Golomb.
+    fn se(&mut self) -> Option<i32> {
+        let k = i64::from(self.ue()?);
+        let v = if k % 2 == 1 { (k + 1) / 2 } else { -(k / 2) };
+        i32::try_from(v).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    /// Baseline SPS for 640x480 (40x30 macroblocks, no cropping).
+    const SPS_640X480: [u8; 9] = [0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x02, 0x80, 0xF6, 0x40];
+
+    #[test]
+    fn sps_size_and_avcc() {
+        assert_eq!(sps_picture_size(&SPS_640X480), Some((640, 480)));
+
+        let pps = [0x68, 0xCE, 0x3C, 0x80];
+        let cfg = avc_config(&SPS_640X480, &pps).unwrap();
+        assert_eq!((cfg.width, cfg.height), (640, 480));
+        assert_eq!(&cfg.avcc[..6], &[1, 0x42, 0xC0, 0x1E, 0xFF, 0xE1]);
+        assert_eq!(cfg.avcc.len(), 6 + 2 + 9 + 1 + 2 + 4);
+    }
+
+    #[test]
+    fn access_unit_is_length_prefixed() {
+        let annexb = [
+            0, 0, 0, 1, 0x09, 0xF0, // AUD, dropped
+            0, 0, 0, 1, 0x65, 0xAA, 0xBB, // IDR
+        ];
+        let au = parse_access_unit(&annexb);
+        assert!(au.keyframe);
+        assert!(au.sps.is_none());
+        assert_eq!(au.avcc_payload, [0, 0, 0, 3, 0x65, 0xAA, 0xBB]);
+    }
+}
@@ -0,0 +1,405 @@
+//! Minimal Matroska (MKV) muxer.
+//!
+//! Writes the EBML header, segment info and track list up front, then one
+//! cluster of `SimpleBlock`s at a time (millisecond timecodes). The track
+//! list lives in a fixed-size region padded with a `Void` element, so it can
+//! be rewritten in place once the H.264 parameter sets are known. The segment
+//! size and duration are patched on [`MkvWriter::finish`]; a file that was
+//! never finished still plays, as the segment size starts out as "unknown".
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+const EBML: u32 = 0x1A45_DFA3;
+const EBML_VERSION: u32 = 0x4286;
+const EBML_READ_VERSION: u32 = 0x42F7;
+const EBML_MAX_ID_LENGTH: u32 = 0x42F2;
+const EBML_MAX_SIZE_LENGTH: u32 = 0x42F3;
+const DOC_TYPE: u32 = 0x4282;
+const DOC_TYPE_VERSION: u32 = 0x4287;
+const DOC_TYPE_READ_VERSION: u32 = 0x4285;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMECODE_SCALE: u32 = 0x2A_D7B1;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_UID: u32 = 0x73C5;
+const TRACK_TYPE: u32 = 0x83;
+const FLAG_LACING: u32 = 0x9C;
+const NAME: u32 = 0x536E;
+const CODEC_ID: u32 = 0x86;
+const CODEC_PRIVATE: u32 = 0x63A2;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const BIT_DEPTH: u32 = 0x6264;
+const CLUSTER: u32 = 0x1F43_B675;
+const TIMECODE: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+const VOID: u32 = 0xEC;
+
+/// Bytes reserved for the `Tracks` element (plus `Void` padding).
+const TRACKS_RESERVED: usize = 1024;
+/// A new cluster is started after this many milliseconds.
+const CLUSTER_MS: u64 = 1_000;
+/// 8-byte "unknown" size marker.
+const UNKNOWN_SIZE: [u8; 8] = [0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+/// H.264 decoder configuration of a video track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AvcConfig {
+    pub width: u32,
+    pub height: u32,
+    /// `AVCDecoderConfigurationRecord` (`avcC`).
+    pub avcc: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackCodec {
+    /// H.264 with length-prefixed NAL units; `None` until the first SPS/PPS.
+    H264(Option<AvcConfig>),
+    /// Signed 16-bit little-endian PCM.
+    Pcm { sample_rate: u32, channels: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub number: u64,
+    pub name: &'static str,
+    pub codec: TrackCodec,
+}
+
+/// Streaming Matroska writer over a seekable output.
+pub struct MkvWriter<W: Write + Seek> {
+    out: W,
+    /// Offset of the segment size field.
+    segment_size_pos: u64,
+    /// Offset of the first byte inside the segment.
+    segment_data_pos: u64,
+    /// Offset of the `Duration` float payload.
+    duration_pos: u64,
+    /// Offset of the reserved `Tracks` region.
+    tracks_pos: u64,
+    cluster_ts: Option<u64>,
+    cluster: Vec<u8>,
+    last_ts: u64,
+}
+
+impl<W: Write + Seek> MkvWriter<W> {
+    /// Writes the file header and track list.
+    ///
+    /// # Errors
+    ///
+    /// Returns the underlying I/O error, or `InvalidData` if the track list
+    /// does not fit the reserved region.
+    pub fn new(mut out: W, tracks: &[Track]) -> io::Result<Self> {
+        let mut head = Vec::new();
+        master(&mut head, EBML, |b| {
+            uint(b, EBML_VERSION, 1);
+            uint(b, EBML_READ_VERSION, 1);
+            uint(b, EBML_MAX_ID_LENGTH, 4);
+            uint(b, EBML_MAX_SIZE_LENGTH, 8);
+            string(b, DOC_TYPE, "matroska");
+            uint(b, DOC_TYPE_VERSION, 4);
+            uint(b, DOC_TYPE_READ_VERSION, 2);
+        });
+        write_id(&mut head, SEGMENT);
+        out.write_all(&head)?;
+        let segment_size_pos = out.stream_position()?;
+        out.write_all(&UNKNOWN_SIZE)?;
+        let segment_data_pos = out.stream_position()?;
+
+        let mut info = Vec::new();
+        master(&mut info, INFO, |b| {
+            uint(b, TIMECODE_SCALE, 1_000_000);
+            string(b, MUXING_APP, "rustyrtc");
+            string(b, WRITING_APP, "rustyrtc");
+            // Last, so its payload is the final 8 bytes of the element
+            float(b, DURATION, 0.0);
+        });
+        out.write_all(&info)?;
+        let duration_pos = out.stream_position()? - 8;
+
+        let tracks_pos = out.stream_position()?;
+        let mut me = Self {
+            out,
+            segment_size_pos,
+            segment_data_pos,
+            duration_pos,
+            tracks_pos,
+            cluster_ts: None,
+            cluster: Vec::new(),
+            last_ts: 0,
+        };
+        me.write_tracks(tracks)?;
+        Ok(me)
+    }
+
+    /// Rewrites the track list in place (e.g. once `CodecPrivate` is known).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`new`](Self::new).
+    pub fn update_tracks(&mut self, tracks: &[Track]) -> io::Result<()> {
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(self.tracks_pos))?;
+        self.write_tracks(tracks)?;
+        self.out.seek(SeekFrom::Start(end))?;
+        Ok(())
+    }
+
+    fn write_tracks(&mut self, tracks: &[Track]) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(TRACKS_RESERVED);
+        master(&mut buf, TRACKS, |b| {
+            for track in tracks {
+                track_entry(b, track);
+            }
+        });
+        let padding = TRACKS_RESERVED.saturating_sub(buf.len());
+        if buf.len() > TRACKS_RESERVED || padding == 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "track list does not fit the reserved header space",
+            ));
+        }
+        if padding > 0 {
+            void(&mut buf, padding);
+        }
+        self.out.write_all(&buf)
+    }
+
+    /// Adds one frame to `track` at `ts_ms` (milliseconds since the start).
+    ///
+    /// Timecodes are clamped to be non-decreasing across the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the underlying I/O error when a cluster is flushed.
+    pub fn write_block(
+        &mut self,
+        track: u64,
+        ts_ms: u64,
+        keyframe: bool,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let ts = ts_ms.max(self.last_ts);
+        self.last_ts = ts;
+
+        let cluster_ts = match self.cluster_ts {
+            Some(start) if ts - start < CLUSTER_MS => start,
+            _ => {
+                self.flush_cluster()?;
+                self.cluster_ts = Some(ts);
+                ts
+            }
+        };
+        // Below CLUSTER_MS, so it always fits the 16-bit relative timecode
+        let relative = i16::try_from(ts - cluster_ts).unwrap_or(i16::MAX);
+
+        let mut body = Vec::with_capacity(data.len() + 4);
+        write_size(&mut body, track);
+        body.extend_from_slice(&relative.to_be_bytes());
+        body.push(if keyframe { 0x80 } else { 0x00 });
+        body.extend_from_slice(data);
+        element(&mut self.cluster, SIMPLE_BLOCK, &body);
+        Ok(())
+    }
+
+    fn flush_cluster(&mut self) -> io::Result<()> {
+        let Some(ts) = self.cluster_ts.take() else {
+            return Ok(());
+        };
+        let mut body = Vec::with_capacity(self.cluster.len() + 8);
+        uint(&mut body, TIMECODE, ts);
+        body.append(&mut self.cluster);
+        let mut buf = Vec::with_capacity(body.len() + 12);
+        element(&mut buf, CLUSTER, &body);
+        self.out.write_all(&buf)
+    }
+
+    /// Milliseconds covered so far.
+    #[must_use]
+    pub const fn duration_ms(&self) -> u64 {
+        self.last_ts
+    }
+
+    /// Flushes the last cluster and patches the segment size and duration.
+    ///
+    /// # Errors
+    ///
+    /// Returns the underlying I/O error.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_cluster()?;
+        let end = self.out.stream_position()?;
+
+        let size = end - self.segment_data_pos;
+        let mut field = size.to_be_bytes();
+        field[0] = 0x01;
+        self.out.seek(SeekFrom::Start(self.segment_size_pos))?;
+        self.out.write_all(&field)?;
+
+        self.out.seek(SeekFrom::Start(self.duration_pos))?;
+        self.out.write_all(&(self.last_ts as f64).to_be_bytes())?;
+
+        self.out.seek(SeekFrom::Start(end))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn track_entry(out: &mut Vec<u8>, track: &Track) {
+    master(out, TRACK_ENTRY, |b| {
+        uint(b, TRACK_NUMBER, track.number);
+        uint(b, TRACK_UID, track.number);
+        uint(b, FLAG_LACING, 0);
+        string(b, NAME, track.name);
+        match &track.codec {
+            TrackCodec::H264(avc) => {
+                uint(b, TRACK_TYPE, 1);
+                string(b, CODEC_ID, "V_MPEG4/ISO/AVC");
+                if let Some(avc) = avc {
+                    element(b, CODEC_PRIVATE, &avc.avcc);
+                }
+                let (width, height) = avc.as_ref().map_or((0, 0), |a| (a.width, a.height));
+                master(b, VIDEO, |v| {
+                    uint(v, PIXEL_WIDTH, u64::from(width));
+                    uint(v, PIXEL_HEIGHT, u64::from(height));
+                });
+            }
+            TrackCodec::Pcm {
+                sample_rate,
+                channels,
+            } => {
+                uint(b, TRACK_TYPE, 2);
+                string(b, CODEC_ID, "A_PCM/INT/LIT");
+                master(b, AUDIO, |a| {
+                    float(a, SAMPLING_FREQUENCY, f64::from(*sample_rate));
+                    uint(a, CHANNELS, u64::from(*channels));
+                    uint(a, BIT_DEPTH, 16);
+                });
+            }
+        }
+    });
+}
+
+fn write_id(out: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+/// EBML variable-length size, in the fewest bytes (all-ones is reserved).
+fn write_size(out: &mut Vec<u8>, size: u64) {
+    let mut len = 1;
+    while len < 8 && size >= (1u64 << (7 * len)) - 1 {
+        len += 1;
+    }
+    let marked = size | (1u64 << (7 * len));
+    out.extend_from_slice(&marked.to_be_bytes()[8 - len..]);
+}
+
+fn element(out: &mut Vec<u8>, id: u32, body: &[u8]) {
+    write_id(out, id);
+    write_size(out, body.len() as u64);
+    out.extend_from_slice(body);
+}
+
+fn master(out: &mut Vec<u8>, id: u32, fill: impl FnOnce(&mut Vec<u8>)) {
+    let mut body = Vec::new();
+    fill(&mut body);
+    element(out, id, &body);
+}
+
+fn uint(out: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count().min(7);
+    element(out, id, &bytes[skip..]);
+}
+
+fn float(out: &mut Vec<u8>, id: u32, value: f64) {
+    element(out, id, &value.to_be_bytes());
+}
+
+fn string(out: &mut Vec<u8>, id: u32, value: &str) {
+    element(out, id, value.as_bytes());
+}
+
+/// A `Void` element exactly `total` bytes long (`total >= 2`).
+fn void(out: &mut Vec<u8>, total: usize) {
+    write_id(out, VOID);
+    if total - 2 < 0x7F {
+        write_size(out, (total - 2) as u64);
+        out.resize(out.len() + total - 2, 0);
+    } else {
+        // Fixed 8-byte size field: 1 (id) + 8 (size) + payload
+        let mut field = ((total - 9) as u64).to_be_bytes();
+        field[0] = 0x01;
+        out.extend_from_slice(&field);
+        out.resize(out.len() + total - 9, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn sizes_use_the_shortest_encoding() {
+        let mut out = Vec::new();
+        write_size(&mut out, 5);
+        write_size(&mut out, 127);
+        write_size(&mut out, 300);
+        assert_eq!(out, [0x85, 0x40, 0x7F, 0x41, 0x2C]);
+
+        for total in [2, 100, 129, 130, 700] {
+            let mut out = Vec::new();
+            void(&mut out, total);
+            assert_eq!(out.len(), total);
+        }
+    }
+
+    #[test]
+    fn header_is_patched_on_finish() {
+        let tracks = [Track {
+            number: 1,
+            name: "local",
+            codec: TrackCodec::Pcm {
+                sample_rate: 8000,
+                channels: 1,
+            },
+        }];
+        let mut writer = MkvWriter::new(Cursor::new(Vec::new()), &tracks).unwrap();
+        let tracks_pos = writer.tracks_pos as usize;
+        writer.write_block(1, 0, true, &[1, 2]).unwrap();
+        writer.write_block(1, 1_500, true, &[3, 4]).unwrap();
+        let file = writer.finish().unwrap().into_inner();
+
+        assert_eq!(&file[..4], &EBML.to_be_bytes());
+        // Two clusters: a new one starts after CLUSTER_MS
+        let cluster_id = CLUSTER.to_be_bytes();
+        let clusters = file.windows(4).filter(|w| *w == cluster_id).count();
+        assert_eq!(clusters, 2);
+        // Clusters start right after the reserved track region
+        assert_eq!(&file[tracks_pos + TRACKS_RESERVED..][..4], &cluster_id);
+
+        // The segment size now covers everything after the size field
+        let pos = file
+            .windows(4)
+            .position(|w| w == SEGMENT.to_be_bytes())
+            .unwrap()
+            + 4;
+        let mut size = [0u8; 8];
+        size.copy_from_slice(&file[pos..pos + 8]);
+        size[0] = 0;
+        assert_eq!(u64::from_be_bytes(size) as usize, file.len() - pos - 8);
+    }
+}
@@ -0,0 +1,287 @@
+//! Call recording to Matroska (`.mkv`).
+//!
+//! The media agent taps the encoded H.264 access units (local ones from the
+//! encoder, remote ones before decoding) and the PCM audio on both sides, and
+//! hands them to a [`Recorder`]. No re-encoding happens; video is muxed as is
+//! and audio is stored as 16-bit PCM. Each stream gets its own track, so a
+//! player can pick the local or remote side. The file can be remuxed to MP4
+//! without re-encoding, e.g. `ffmpeg -i call.mkv -c:v copy -c:a aac call.mp4`.
+
+pub mod avc;
+pub mod mkv;
+
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    media_agent::{
+        media_agent_error::{MediaAgentError, Result},
+        utils::now_millis,
+    },
+};
+
+use mkv::{MkvWriter, Track, TrackCodec};
+
+const DEFAULT_RECORDING_DIR: &str = "recordings";
+
+/// Which side of the call to record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordStreams {
+    pub local: bool,
+    pub remote: bool,
+}
+
+impl Default for RecordStreams {
+    fn default() -> Self {
+        Self {
+            local: true,
+            remote: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordSide {
+    Local,
+    Remote,
+}
+
+/// What the UI shows while a recording runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingStatus {
+    pub path: PathBuf,
+    pub elapsed: Duration,
+}
+
+/// One muxed stream.
+struct RecTrack {
+    side: RecordSide,
+    video: bool,
+    /// Video is dropped until the first keyframe with parameter sets.
+    started: bool,
+}
+
+/// An open recording.
+pub struct Recorder {
+    writer: MkvWriter<BufWriter<File>>,
+    path: PathBuf,
+    started: Instant,
+    /// Same order (and numbering, 1-based) as `tracks`.
+    rec_tracks: Vec<RecTrack>,
+    tracks: Vec<Track>,
+}
+
+impl Recorder {
+    /// Creates `path` and writes the file header.
+    ///
+    /// Audio tracks are PCM at `audio_rate` Hz, mono.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if the file cannot be created, or if
+    /// `streams` selects nothing.
+    pub fn create(path: &Path, streams: RecordStreams, audio_rate: u32) -> Result<Self> {
+        let mut rec_tracks = Vec::new();
+        for (side, enabled) in [
+            (RecordSide::Local, streams.local),
+            (RecordSide::Remote, streams.remote),
+        ] {
+            if enabled {
+                for video in [true, false] {
+                    rec_tracks.push(RecTrack {
+                        side,
+                        video,
+                        started: false,
+                    });
+                }
+            }
+        }
+        if rec_tracks.is_empty() {
+            return Err(MediaAgentError::Io("nothing selected to record".into()));
+        }
+
+        let tracks = rec_tracks
+            .iter()
+            .zip(1u64..)
+            .map(|(t, number)| Track {
+                number,
+                name: match (t.side, t.video) {
+                    (RecordSide::Local, true) => "local video",
+                    (RecordSide::Local, false) => "local audio",
+                    (RecordSide::Remote, true) => "remote video",
+                    (RecordSide::Remote, false) => "remote audio",
+                },
+                codec: if t.video {
+                    TrackCodec::H264(None)
+                } else {
+                    TrackCodec::Pcm {
+                        sample_rate: audio_rate,
+                        channels: 1,
+                    }
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let file = File::create(path).map_err(io_err)?;
+        let writer = MkvWriter::new(BufWriter::new(file), &tracks).map_err(io_err)?;
+        Ok(Self {
+            writer,
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            rec_tracks,
+            tracks,
+        })
+    }
+
+    /// `recording_dir/call-<unix seconds>.mkv`, from `[Media] recording_dir`
+    /// (default `recordings`). The directory is created if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if the directory cannot be created.
+    pub fn default_path(config: &Config) -> Result<PathBuf> {
+        let dir = config
+            .get("Media", "recording_dir")
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or(DEFAULT_RECORDING_DIR);
+        fs::create_dir_all(dir).map_err(io_err)?;
+        Ok(Path::new(dir).join(format!("call-{}.mkv", now_millis() / 1_000)))
+    }
+
+    fn track_index(&self, side: RecordSide, video: bool) -> Option<usize> {
+        self.rec_tracks
+            .iter()
+            .position(|t| t.side == side && t.video == video)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// Adds one H.264 access unit (Annex-B).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if writing fails.
+    pub fn write_video(&mut self, side: RecordSide, annexb: &[u8]) -> Result<()> {
+        let Some(index) = self.track_index(side, true) else {
+            return Ok(());
+        };
+        let au = avc::parse_access_unit(annexb);
+
+        if let TrackCodec::H264(config @ None) = &mut self.tracks[index].codec {
+            let (Some(sps), Some(pps)) = (au.sps, au.pps) else {
+                return Ok(());
+            };
+            let Some(avc) = avc::avc_config(sps, pps) else {
+                return Ok(());
+            };
+            *config = Some(avc);
+            self.writer.update_tracks(&self.tracks).map_err(io_err)?;
+        }
+
+        let track = &mut self.rec_tracks[index];
+        if !track.started {
+            if !au.keyframe {
+                return Ok(());
+            }
+            track.started = true;
+        }
+
+        let ts = self.now_ms();
+        self.writer
+            .write_block(index as u64 + 1, ts, au.keyframe, &au.avcc_payload)
+            .map_err(io_err)
+    }
+
+    /// Adds mono PCM samples in `[-1.0, 1.0]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if writing fails.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write_audio(&mut self, side: RecordSide, samples: &[f32]) -> Result<()> {
+        let Some(index) = self.track_index(side, false) else {
+            return Ok(());
+        };
+        let pcm: Vec<u8> = samples
+            .iter()
+            .flat_map(|s| ((s.clamp(-1.0, 1.0) * 32767.0) as i16).to_le_bytes())
+            .collect();
+        let ts = self.now_ms();
+        self.writer
+            .write_block(index as u64 + 1, ts, true, &pcm)
+            .map_err(io_err)
+    }
+
+    #[must_use]
+    pub fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            path: self.path.clone(),
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    /// Closes the file and returns its path.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Io` if the final write fails.
+    pub fn finish(self) -> Result<PathBuf> {
+        self.writer.finish().map_err(io_err)?;
+        Ok(self.path)
+    }
+}
+
+fn io_err(e: std::io::Error) -> MediaAgentError {
+    MediaAgentError::Io(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn video_waits_for_parameter_sets_and_keyframe() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-rec-{}.mkv", std::process::id()));
+        let streams = RecordStreams {
+            local: true,
+            remote: false,
+        };
+        let mut rec = Recorder::create(&path, streams, 8000).unwrap();
+
+        // P-slice before any SPS/PPS: dropped
+        rec.write_video(RecordSide::Local, &[0, 0, 0, 1, 0x41, 0x9A])
+            .unwrap();
+        assert_eq!(rec.tracks[0].codec, TrackCodec::H264(None));
+
+        let idr = [
+            &[0, 0, 0, 1][..],
+            &[0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x02, 0x80, 0xF6, 0x40],
+            &[0, 0, 0, 1, 0x68, 0xCE, 0x3C, 0x80],
+            &[0, 0, 0, 1, 0x65, 0x88, 0x84],
+        ]
+        .concat();
+        rec.write_video(RecordSide::Local, &idr).unwrap();
+        assert!(rec.rec_tracks[0].started);
+        let TrackCodec::H264(Some(avc)) = &rec.tracks[0].codec else {
+            panic!("expected avcC");
+        };
+        assert_eq!((avc.width, avc.height), (640, 480));
+
+        // Remote side is not recorded
+        rec.write_audio(RecordSide::Remote, &[0.5; 160]).unwrap();
+        rec.write_audio(RecordSide::Local, &[0.5; 160]).unwrap();
+
+        let out = rec.finish().unwrap();
+        assert!(fs::metadata(&out).unwrap().len() > 1024);
+        let _ = fs::remove_file(out);
+    }
+}
@@ -0,0 +1,142 @@
+//! Streaming sample-rate and channel conversion for interleaved `f32` audio.
+//!
+//! Devices run at whatever format the host picks (often 44.1 or 48 kHz,
+//! stereo), while the wire format is negotiated in SDP. Channels are mixed
+//! down (average) or up (copied) first, then the rate is converted by linear
+//! interpolation. Good enough for voice; state is kept across calls so frame
+//! boundaries do not click.
+
+use crate::media_agent::audio_format::AudioFormat;
+
+/// Converts any input format to a fixed output format.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    to: AudioFormat,
+    from: Option<AudioFormat>,
+    /// Last input frame (already in the output channel layout).
+    prev: Vec<f32>,
+    /// Position of the next output frame, in input frames after `prev`.
+    phase: f64,
+}
+
+impl Resampler {
+    #[must_use]
+    pub const fn new(to: AudioFormat) -> Self {
+        Self {
+            to,
+            from: None,
+            prev: Vec::new(),
+            phase: 0.0,
+        }
+    }
+
+    #[must_use]
+    pub const fn output_format(&self) -> AudioFormat {
+        self.to
+    }
+
+    /// Converts `input`, interleaved in `from`, to the output format.
+    ///
+    /// A change of `from` (e.g. a device switch) restarts the conversion.
+    pub fn process(&mut self, from: AudioFormat, input: &[f32]) -> Vec<f32> {
+        if self.from != Some(from) {
+            self.from = Some(from);
+            self.prev.clear();
+            self.phase = 0.0;
+        }
+        let mixed = remix(input, from.channels, self.to.channels);
+        if from.sample_rate == self.to.sample_rate {
+            return mixed;
+        }
+
+        let ch = usize::from(self.to.channels.max(1));
+        let step = f64::from(from.sample_rate) / f64::from(self.to.sample_rate.max(1));
+        // Index 0 is the previous frame, so interpolation spans the boundary
+        let mut frames = std::mem::take(&mut self.prev);
+        frames.extend_from_slice(&mixed);
+        let total = frames.len() / ch;
+        if total < 2 {
+            self.prev = frames;
+            return Vec::new();
+        }
+
+        let last = (total - 1) as f64;
+        let mut out = Vec::with_capacity(((total as f64 / step) as usize + 1) * ch);
+        while self.phase <= last {
+            let i = self.phase.floor() as usize;
+            let frac = (self.phase - i as f64) as f32;
+            let j = (i + 1).min(total - 1);
+            for c in 0..ch {
+                let a = frames[i * ch + c];
+                let b = frames[j * ch + c];
+                out.push(a + (b - a) * frac);
+            }
+            self.phase += step;
+        }
+        self.phase -= last;
+        self.prev = frames[(total - 1) * ch..].to_vec();
+        out
+    }
+}
+
+/// Maps interleaved audio from `from` to `to` channels.
+fn remix(input: &[f32], from: u16, to: u16) -> Vec<f32> {
+    let from = usize::from(from.max(1));
+    let to = usize::from(to.max(1));
+    if from == to {
+        return input.to_vec();
+    }
+    let mut out = Vec::with_capacity(input.len() / from * to);
+    for frame in input.chunks_exact(from) {
+        if to == 1 {
+            out.push(frame.iter().sum::<f32>() / from as f32);
+        } else if from == 1 {
+            out.extend(std::iter::repeat_n(frame[0], to));
+        } else {
+            // Keep the channels both sides have, silence the rest
+            out.extend((0..to).map(|c| frame.get(c).copied().unwrap_or(0.0)));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    const MONO_8K: AudioFormat = AudioFormat::new(8000, 1);
+
+    #[test]
+    fn same_format_passes_through() {
+        let mut r = Resampler::new(MONO_8K);
+        let input = [0.1, -0.2, 0.3];
+        assert_eq!(r.process(MONO_8K, &input), input);
+    }
+
+    #[test]
+    fn stereo_48k_to_mono_8k() {
+        let mut r = Resampler::new(MONO_8K);
+        let from = AudioFormat::new(48_000, 2);
+        // 20 ms, left 0.2 and right 0.6: a constant 0.4 once mixed down
+        let frame: Vec<f32> = (0..960).flat_map(|_| [0.2, 0.6]).collect();
+        let mut out = Vec::new();
+        for _ in 0..5 {
+            out.extend(r.process(from, &frame));
+        }
+        // 100 ms at 8 kHz, give or take the frame held back for interpolation
+        assert!((799..=801).contains(&out.len()), "{}", out.len());
+        assert!(out.iter().all(|s| (s - 0.4).abs() < 1e-6));
+    }
+
+    #[test]
+    fn upsampling_interpolates_across_frames() {
+        let mut r = Resampler::new(AudioFormat::new(16_000, 2));
+        let a = r.process(MONO_8K, &[0.0, 1.0]);
+        let b = r.process(MONO_8K, &[2.0]);
+        let left: Vec<f32> = a.iter().chain(&b).step_by(2).copied().collect();
+        assert_eq!(left, [0.0, 0.5, 1.0, 1.5, 2.0]);
+        // Both channels carry the mono signal
+        assert!(a.chunks(2).all(|f| f[0] == f[1]));
+    }
+}
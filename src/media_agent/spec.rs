@@ -8,13 +8,17 @@ pub enum MediaType {
 pub enum CodecSpec {
     H264,
     G711U,
+    /// Linear 16-bit PCM (RFC 3551), at the rate and channels in the SDP.
+    L16,
+    /// DTMF digits as RTP events (RFC 4733), sent on the audio stream.
+    TelephoneEvent,
 }
 
 impl CodecSpec {
     pub fn media_type(&self) -> MediaType {
         match self {
             CodecSpec::H264 => MediaType::Video,
-            CodecSpec::G711U => MediaType::Audio,
+            CodecSpec::G711U | CodecSpec::L16 | CodecSpec::TelephoneEvent => MediaType::Audio,
         }
     }
 }
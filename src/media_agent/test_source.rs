@@ -0,0 +1,278 @@
+//! Built-in test sources for machines without a camera or microphone.
+//!
+//! The video source draws moving color bars with the sender's wall clock
+//! (`HH:MM:SS.mmm`, UTC) burned in, so end-to-end latency can be read by
+//! comparing the remote picture with the local clock. The audio source is a
+//! sine tone.
+
+use std::{
+    f32::consts::TAU,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    log::log_sink::LogSink,
+    media_agent::{
+        audio_capture_worker::AudioCaptureEvent,
+        audio_frame::AudioFrame,
+        frame_format::FrameFormat,
+        media_agent_error::Result,
+        utils::now_millis,
+        video_frame::{VideoFrame, VideoFrameData},
+        watermark::glyph,
+    },
+    sink_debug, sink_info,
+};
+
+pub const TEST_VIDEO_WIDTH: u32 = 640;
+pub const TEST_VIDEO_HEIGHT: u32 = 480;
+pub const DEFAULT_TEST_TONE_HZ: u32 = 440;
+
+const TONE_AMPLITUDE: f32 = 0.3;
+const AUDIO_SAMPLE_RATE: u32 = 8000;
+const AUDIO_FRAME_SAMPLES: usize = 160;
+
+/// White, yellow, cyan, green, magenta, red, blue.
+const BARS: [[u8; 3]; 7] = [
+    [235, 235, 235],
+    [235, 235, 16],
+    [16, 235, 235],
+    [16, 235, 16],
+    [235, 16, 235],
+    [235, 16, 16],
+    [16, 16, 235],
+];
+
+const GLYPH_SCALE: usize = 6;
+
+/// Formats a Unix time in milliseconds as `HH:MM:SS.mmm` (UTC).
+#[must_use]
+pub fn clock_text(unix_ms: u128) -> String {
+    let ms = unix_ms % 1_000;
+    let secs = (unix_ms / 1_000) % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        secs / 3_600,
+        (secs / 60) % 60,
+        secs % 60,
+        ms
+    )
+}
+
+/// Draws one color-bar frame.
+///
+/// `tick` moves a white sweep line across the bars; `unix_ms` is burned in
+/// on a black strip at the bottom.
+#[must_use]
+pub fn color_bars_frame(width: u32, height: u32, tick: u32, unix_ms: u128) -> VideoFrame {
+    let w = width as usize;
+    let h = height as usize;
+    let mut data = vec![0u8; w * h * 3];
+
+    let strip_h = (5 * GLYPH_SCALE + 2 * GLYPH_SCALE).min(h);
+    let bars_h = h - strip_h;
+    let sweep_x = if w == 0 { 0 } else { (tick as usize * 4) % w };
+    for y in 0..bars_h {
+        for x in 0..w {
+            let color = if x == sweep_x {
+                [255, 255, 255]
+            } else {
+                BARS[x * BARS.len() / w]
+            };
+            let i = (y * w + x) * 3;
+            data[i..i + 3].copy_from_slice(&color);
+        }
+    }
+
+    draw_text(&mut data, w, h, &clock_text(unix_ms), bars_h + GLYPH_SCALE);
+
+    VideoFrame {
+        width,
+        height,
+        timestamp_ms: unix_ms,
+        format: FrameFormat::Rgb,
+        data: VideoFrameData::Rgb(Arc::new(data)),
+    }
+}
+
+/// Draws `text` in white at row `top`, clipped to the frame.
+fn draw_text(data: &mut [u8], w: usize, h: usize, text: &str, top: usize) {
+    let advance = 4 * GLYPH_SCALE;
+    for (n, ch) in text.chars().enumerate() {
+        let rows = glyph(ch);
+        let left = GLYPH_SCALE + n * advance;
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        let x = left + col * GLYPH_SCALE + dx;
+                        let y = top + row * GLYPH_SCALE + dy;
+                        if x < w && y < h {
+                            let i = (y * w + x) * 3;
+                            data[i..i + 3].copy_from_slice(&[255, 255, 255]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sends color-bar frames at `target_fps` until `running` is cleared or the
+/// receiver hangs up.
+///
+/// # Errors
+///
+/// Never fails; the `Result` matches the other capture loops.
+pub fn test_video_loop(
+    logger: Arc<dyn LogSink>,
+    tx: Sender<VideoFrame>,
+    target_fps: u32,
+    running: Arc<AtomicBool>,
+) -> Result<()> {
+    let fps = target_fps.clamp(1, 120);
+    let period = Duration::from_millis(1_000 / u64::from(fps));
+    let mut next_deadline = Instant::now() + period;
+    let mut tick = 0u32;
+
+    sink_info!(logger, "[TestSource] sending color bars");
+    while running.load(Ordering::SeqCst) {
+        let frame = color_bars_frame(TEST_VIDEO_WIDTH, TEST_VIDEO_HEIGHT, tick, now_millis());
+        tick = tick.wrapping_add(1);
+        if tx.send(frame).is_err() {
+            break;
+        }
+
+        let now = Instant::now();
+        if now < next_deadline {
+            thread::sleep(next_deadline - now);
+            next_deadline += period;
+        } else {
+            next_deadline = now + period;
+        }
+    }
+    sink_debug!(logger, "[TestSource] color bars stopped");
+    Ok(())
+}
+
+/// `count` samples of a sine tone starting at sample index `start`.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn sine_samples(freq_hz: u32, sample_rate: u32, start: u64, count: usize) -> Vec<f32> {
+    let rate = u64::from(sample_rate.max(1));
+    (0..count as u64)
+        .map(|n| {
+            // Integer phase, so it stays exact however long the call runs
+            let phase = ((start + n) * u64::from(freq_hz)) % rate;
+            TONE_AMPLITUDE * (TAU * phase as f32 / rate as f32).sin()
+        })
+        .collect()
+}
+
+/// Spawns a worker that sends a sine tone in place of the microphone.
+///
+/// Frames match the capture worker (8 kHz mono, 20 ms) and honour `is_muted`.
+pub fn spawn_test_audio_worker(
+    logger: Arc<dyn LogSink>,
+    running: Arc<AtomicBool>,
+    is_muted: Arc<AtomicBool>,
+    freq_hz: u32,
+    tx: Sender<AudioCaptureEvent>,
+) -> Option<JoinHandle<()>> {
+    thread::Builder::new()
+        .name("media-agent-test-audio".into())
+        .spawn(move || {
+            sink_info!(logger, "[TestSource] sending {} Hz tone", freq_hz);
+            let period = Duration::from_millis(
+                (AUDIO_FRAME_SAMPLES as u64 * 1_000) / u64::from(AUDIO_SAMPLE_RATE),
+            );
+            let mut next_deadline = Instant::now() + period;
+            let mut sample = 0u64;
+
+            while running.load(Ordering::SeqCst) {
+                let data = if is_muted.load(Ordering::Relaxed) {
+                    vec![0.0; AUDIO_FRAME_SAMPLES]
+                } else {
+                    sine_samples(freq_hz, AUDIO_SAMPLE_RATE, sample, AUDIO_FRAME_SAMPLES)
+                };
+                sample += AUDIO_FRAME_SAMPLES as u64;
+
+                let frame = AudioFrame {
+                    data: Arc::new(data),
+                    samples: AUDIO_FRAME_SAMPLES,
+                    sample_rate: AUDIO_SAMPLE_RATE,
+                    channels: 1,
+                    timestamp_ms: now_millis(),
+                };
+                if tx.send(AudioCaptureEvent::Frame(frame)).is_err() {
+                    break;
+                }
+
+                let now = Instant::now();
+                if now < next_deadline {
+                    thread::sleep(next_deadline - now);
+                }
+                next_deadline += period;
+            }
+            sink_debug!(logger, "[TestSource] tone stopped");
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn pixel(frame: &VideoFrame, x: usize, y: usize) -> [u8; 3] {
+        let VideoFrameData::Rgb(data) = &frame.data else {
+            panic!("expected RGB");
+        };
+        let i = (y * frame.width as usize + x) * 3;
+        [data[i], data[i + 1], data[i + 2]]
+    }
+
+    #[test]
+    fn clock_text_formats_utc_time_of_day() {
+        // 1970-01-02 01:02:03.045
+        let ms = ((86_400 + 3_600 + 2 * 60 + 3) * 1_000 + 45) as u128;
+        assert_eq!(clock_text(ms), "01:02:03.045");
+    }
+
+    #[test]
+    fn bars_and_burn_in_are_drawn() {
+        let frame = color_bars_frame(TEST_VIDEO_WIDTH, TEST_VIDEO_HEIGHT, 1, 0);
+        // Yellow bar, away from the sweep line
+        assert_eq!(pixel(&frame, 120, 10), BARS[1]);
+        // Blue bar
+        assert_eq!(pixel(&frame, 630, 10), BARS[6]);
+
+        let later = color_bars_frame(TEST_VIDEO_WIDTH, TEST_VIDEO_HEIGHT, 1, 1_111);
+        let VideoFrameData::Rgb(a) = &frame.data else {
+            panic!()
+        };
+        let VideoFrameData::Rgb(b) = &later.data else {
+            panic!()
+        };
+        assert_ne!(a, b, "burn-in must follow the clock");
+    }
+
+    #[test]
+    fn sine_is_continuous_across_frames() {
+        let whole = sine_samples(440, 8000, 0, 320);
+        let mut split = sine_samples(440, 8000, 0, 160);
+        split.extend(sine_samples(440, 8000, 160, 160));
+        assert_eq!(whole, split);
+        assert!(whole.iter().all(|s| s.abs() <= TONE_AMPLITUDE));
+        assert!(whole.iter().any(|s| *s > 0.2));
+    }
+}
@@ -0,0 +1,214 @@
+//! Voice activity detection and push-to-talk gating.
+//!
+//! The detector is energy based: a 20 ms frame counts as voice when its RMS
+//! level is above a threshold in dBFS. A couple of loud frames are needed to
+//! start speaking, and speech ends after a hangover of quiet frames, so short
+//! clicks and the gaps between words do not flicker the indicator.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+    mpsc::Sender,
+};
+
+use crate::{config::Config, core::events::EngineEvent};
+
+/// Default level above which a frame counts as voice.
+pub const DEFAULT_VAD_THRESHOLD_DB: f32 = -45.0;
+/// Loud frames in a row needed to start speaking (40 ms).
+const ATTACK_FRAMES: u32 = 2;
+/// Quiet frames in a row needed to stop speaking (300 ms).
+const HANGOVER_FRAMES: u32 = 15;
+
+/// RMS level of `samples` in dBFS (`-inf` for silence or an empty slice).
+#[must_use]
+pub fn rms_dbfs(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let energy: f32 = samples.iter().map(|s| s * s).sum();
+    #[allow(clippy::cast_precision_loss)]
+    let mean = energy / samples.len() as f32;
+    10.0 * mean.log10()
+}
+
+/// Speaking/silent state machine over consecutive audio frames.
+#[derive(Debug, Clone)]
+pub struct Vad {
+    threshold_db: f32,
+    speaking: bool,
+    loud: u32,
+    quiet: u32,
+}
+
+impl Vad {
+    #[must_use]
+    pub const fn new(threshold_db: f32) -> Self {
+        Self {
+            threshold_db,
+            speaking: false,
+            loud: 0,
+            quiet: 0,
+        }
+    }
+
+    /// Threshold from `[Media] vad_threshold_db`.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(
+            config
+                .get("Media", "vad_threshold_db")
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(DEFAULT_VAD_THRESHOLD_DB),
+        )
+    }
+
+    #[must_use]
+    pub const fn is_speaking(&self) -> bool {
+        self.speaking
+    }
+
+    /// Feeds one frame. Returns the new state when it changes.
+    pub fn process(&mut self, samples: &[f32]) -> Option<bool> {
+        if rms_dbfs(samples) >= self.threshold_db {
+            self.loud += 1;
+            self.quiet = 0;
+        } else {
+            self.quiet += 1;
+            self.loud = 0;
+        }
+
+        let speaking = if self.speaking {
+            self.quiet < HANGOVER_FRAMES
+        } else {
+            self.loud >= ATTACK_FRAMES
+        };
+        if speaking == self.speaking {
+            return None;
+        }
+        self.speaking = speaking;
+        Some(speaking)
+    }
+}
+
+/// Push-to-talk switch shared between the UI and the audio path.
+///
+/// When enabled, the microphone is only sent while the key is held.
+#[derive(Debug, Clone, Default)]
+pub struct PushToTalk {
+    enabled: Arc<AtomicBool>,
+    pressed: Arc<AtomicBool>,
+}
+
+impl PushToTalk {
+    /// Initial mode from `[Media] push_to_talk`.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let ptt = Self::default();
+        ptt.set_enabled(
+            config
+                .get("Media", "push_to_talk")
+                .is_some_and(|s| s.trim().eq_ignore_ascii_case("true")),
+        );
+        ptt
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Reports the hotkey state.
+    pub fn set_pressed(&self, pressed: bool) {
+        self.pressed.store(pressed, Ordering::SeqCst);
+    }
+
+    /// Whether captured audio should be sent right now.
+    #[must_use]
+    pub fn transmits(&self) -> bool {
+        !self.is_enabled() || self.pressed.load(Ordering::SeqCst)
+    }
+}
+
+/// Speaking detection for both sides of a call, reported as
+/// [`EngineEvent::Speaking`].
+pub struct VoiceActivity {
+    local: Vad,
+    remote: Vad,
+    event_tx: Option<Sender<EngineEvent>>,
+}
+
+impl VoiceActivity {
+    #[must_use]
+    pub fn new(config: &Config, event_tx: Option<Sender<EngineEvent>>) -> Self {
+        let vad = Vad::from_config(config);
+        Self {
+            local: vad.clone(),
+            remote: vad,
+            event_tx,
+        }
+    }
+
+    /// Whether the local microphone is currently picking up speech.
+    #[must_use]
+    pub const fn local_speaking(&self) -> bool {
+        self.local.is_speaking()
+    }
+
+    /// Feeds one frame of the local (sent) or remote (received) audio.
+    pub fn observe(&mut self, remote: bool, samples: &[f32]) {
+        let vad = if remote {
+            &mut self.remote
+        } else {
+            &mut self.local
+        };
+        if let Some(speaking) = vad.process(samples)
+            && let Some(tx) = &self.event_tx
+        {
+            let _ = tx.send(EngineEvent::Speaking { remote, speaking });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn tone(amplitude: f32) -> Vec<f32> {
+        (0..160)
+            .map(|i| amplitude * (i as f32 * 0.35).sin())
+            .collect()
+    }
+
+    #[test]
+    fn vad_needs_attack_and_hangs_over() {
+        let mut vad = Vad::new(DEFAULT_VAD_THRESHOLD_DB);
+        assert_eq!(vad.process(&[0.0; 160]), None);
+        assert_eq!(vad.process(&tone(0.001)), None);
+
+        let voice = tone(0.3);
+        assert_eq!(vad.process(&voice), None);
+        assert_eq!(vad.process(&voice), Some(true));
+
+        for _ in 1..HANGOVER_FRAMES {
+            assert_eq!(vad.process(&[0.0; 160]), None);
+        }
+        assert_eq!(vad.process(&[0.0; 160]), Some(false));
+        assert!(!vad.is_speaking());
+    }
+
+    #[test]
+    fn push_to_talk_gates_only_when_enabled() {
+        let ptt = PushToTalk::default();
+        assert!(ptt.transmits());
+        ptt.set_enabled(true);
+        assert!(!ptt.transmits());
+        ptt.set_pressed(true);
+        assert!(ptt.transmits());
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    log::log_sink::LogSink,
+    media_agent::{
+        frame_format::FrameFormat,
+        h264_decoder::H264Decoder,
+        hw_codec::{HwBackend, HwDecoder},
+        media_agent_error::Result,
+        video_frame::VideoFrame,
+    },
+    sink_info, sink_warn,
+};
+
+/// H.264 decoder that prefers a hardware backend and falls back to OpenH264.
+///
+/// The first backend from `[Media] hw_codec` that opens is used. If it later
+/// fails, it is dropped for good and the software decoder takes over; the
+/// picture resumes with the next keyframe.
+pub struct VideoDecoder {
+    software: H264Decoder,
+    hardware: Option<HwDecoder>,
+    logger: Arc<dyn LogSink>,
+}
+
+impl VideoDecoder {
+    pub fn new(logger: Arc<dyn LogSink>, config: &Config) -> Self {
+        let mut hardware = None;
+        for backend in HwBackend::preferred(config) {
+            match HwDecoder::open(backend) {
+                Ok(dec) => {
+                    sink_info!(logger, "[VideoDecoder] using {backend}");
+                    hardware = Some(dec);
+                    break;
+                }
+                Err(e) => sink_warn!(logger, "[VideoDecoder] {backend} unavailable: {e}"),
+            }
+        }
+        Self {
+            software: H264Decoder::new(logger.clone()),
+            hardware,
+            logger,
+        }
+    }
+
+    /// Decodes one Annex-B access unit. Same contract as
+    /// [`H264Decoder::decode_frame`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if the software decoder fails; hardware
+    /// failures are logged and handled by falling back.
+    pub fn decode_frame(
+        &mut self,
+        bytes: &[u8],
+        frame_format: FrameFormat,
+    ) -> Result<Option<VideoFrame>> {
+        if let Some(hw) = self.hardware.as_mut() {
+            match hw.decode(bytes, frame_format) {
+                Ok(frame) => return Ok(frame),
+                Err(e) => {
+                    sink_warn!(
+                        self.logger,
+                        "[VideoDecoder] {} failed ({e}); using software",
+                        hw.backend()
+                    );
+                    self.hardware = None;
+                }
+            }
+        }
+        self.software.decode_frame(bytes, frame_format)
+    }
+
+    /// Backend currently decoding, or `None` for software.
+    pub fn backend(&self) -> Option<HwBackend> {
+        self.hardware.as_ref().map(HwDecoder::backend)
+    }
+}
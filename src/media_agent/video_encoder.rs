@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use crate::{
+    config::Config,
+    log::log_sink::LogSink,
+    media_agent::{
+        encoder_tunables::{EncoderTunables, IntraRefresh},
+        h264_encoder::H264Encoder,
+        hw_codec::{HwBackend, HwEncoder},
+        media_agent_error::MediaAgentError,
+        video_frame::{VideoFrame, VideoFrameData},
+    },
+    sink_info, sink_warn,
+};
+
+/// H.264 encoder that prefers a hardware backend and falls back to OpenH264.
+///
+/// The hardware encoder is opened lazily on the first frame (and again after
+/// a size or configuration change), since it needs the frame size. A backend
+/// that fails to open or encode is not tried again for the rest of the call;
+/// the software encoder takes over and is asked for a keyframe so the remote
+/// decoder can resync.
+///
+/// With `intra_refresh` set in the tunables, a keyframe is also forced
+/// whenever that long has passed since the last one, on either encoder.
+pub struct VideoEncoder {
+    software: H264Encoder,
+    hardware: Option<HwEncoder>,
+    /// Backends still worth trying, in preference order.
+    candidates: Vec<HwBackend>,
+    /// Keyframe requested while the hardware encoder is active.
+    keyframe_pending: bool,
+    refresh: Option<IntraRefresh>,
+    logger: Arc<dyn LogSink>,
+}
+
+impl VideoEncoder {
+    /// Creates an encoder; hardware backends come from `[Media] hw_codec`.
+    pub fn new(
+        logger: Arc<dyn LogSink>,
+        config: &Config,
+        frame_rate: u32,
+        bit_rate: u32,
+        tunables: EncoderTunables,
+    ) -> Self {
+        Self {
+            software: H264Encoder::new(frame_rate, bit_rate, tunables),
+            hardware: None,
+            candidates: HwBackend::preferred(config),
+            keyframe_pending: false,
+            refresh: tunables.intra_refresh.map(IntraRefresh::new),
+            logger,
+        }
+    }
+
+    /// Encodes a frame into Annex-B NAL units.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if the software encoder fails; hardware
+    /// failures are logged and handled by falling back.
+    pub fn encode_frame_to_h264(&mut self, frame: &VideoFrame) -> Result<Vec<u8>, MediaAgentError> {
+        if self
+            .refresh
+            .as_ref()
+            .is_some_and(|r| r.due(frame.timestamp_ms))
+        {
+            self.request_keyframe();
+        }
+        let annexb = self.encode(frame)?;
+        if let Some(refresh) = self.refresh.as_mut() {
+            refresh.on_encoded(&annexb, frame.timestamp_ms);
+        }
+        Ok(annexb)
+    }
+
+    fn encode(&mut self, frame: &VideoFrame) -> Result<Vec<u8>, MediaAgentError> {
+        // Hardware backends only take RGB input
+        if matches!(frame.data, VideoFrameData::Rgb(_)) {
+            if self
+                .hardware
+                .as_ref()
+                .is_some_and(|hw| hw.size() != (frame.width, frame.height))
+            {
+                self.hardware = None;
+            }
+            if self.hardware.is_none() {
+                self.open_hardware(frame.width, frame.height);
+            }
+        }
+
+        if let Some(hw) = self.hardware.as_mut() {
+            let force_keyframe = std::mem::take(&mut self.keyframe_pending);
+            match hw.encode(frame, force_keyframe) {
+                Ok(annexb) => return Ok(annexb),
+                Err(e) => {
+                    let backend = hw.backend();
+                    sink_warn!(
+                        self.logger,
+                        "[VideoEncoder] {backend} failed ({e}); using software"
+                    );
+                    self.hardware = None;
+                    self.candidates.retain(|b| *b != backend);
+                    self.software.request_keyframe();
+                }
+            }
+        }
+        self.software.encode_frame_to_h264(frame)
+    }
+
+    fn open_hardware(&mut self, width: u32, height: u32) {
+        while let Some(&backend) = self.candidates.first() {
+            match HwEncoder::open(
+                backend,
+                width,
+                height,
+                self.software.target_fps(),
+                self.software.target_bps(),
+                self.software.tunables(),
+            ) {
+                Ok(hw) => {
+                    sink_info!(
+                        self.logger,
+                        "[VideoEncoder] using {backend} for {width}x{height}"
+                    );
+                    // A fresh encoder starts with an IDR anyway
+                    self.keyframe_pending = false;
+                    self.hardware = Some(hw);
+                    return;
+                }
+                Err(e) => {
+                    sink_warn!(self.logger, "[VideoEncoder] {backend} unavailable: {e}");
+                    self.candidates.remove(0);
+                }
+            }
+        }
+    }
+
+    /// Forces a keyframe (IDR) on the next encode call.
+    pub fn request_keyframe(&mut self) {
+        self.keyframe_pending = true;
+        self.software.request_keyframe();
+    }
+
+    /// Applies new rate settings; a hardware encoder is reopened with them on
+    /// the next frame. Same contract as [`H264Encoder::set_config`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::Codec` if the software encoder cannot be
+    /// re-initialized.
+    pub fn set_config(
+        &mut self,
+        new_fps: u32,
+        new_bitrate: u32,
+        new_keyint: u32,
+    ) -> Result<bool, MediaAgentError> {
+        let changed = self.software.set_config(new_fps, new_bitrate, new_keyint)?;
+        if changed {
+            self.hardware = None;
+        }
+        Ok(changed)
+    }
+
+    pub fn target_bps(&self) -> u32 {
+        self.software.target_bps()
+    }
+
+    pub fn keyint(&self) -> u32 {
+        self.software.keyint()
+    }
+
+    /// Backend currently encoding, or `None` for software.
+    pub fn backend(&self) -> Option<HwBackend> {
+        self.hardware.as_ref().map(HwEncoder::backend)
+    }
+}
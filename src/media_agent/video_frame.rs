@@ -7,6 +7,17 @@ use crate::media_agent::{frame_format::FrameFormat, utils::now_millis};
 /// Tuple layout: `(Y_Plane, U_Plane, V_Plane, Y_Stride, U_Stride, V_Stride)`
 pub type YuvPlanes<'a> = (&'a [u8], &'a [u8], &'a [u8], usize, usize, usize);
 
+/// Where the picture starts in planes decoded at a larger, coded size.
+///
+/// H.264 codes in whole macroblocks, so 1080p is decoded as 1920x1088 and
+/// cropped; the picture is `width` x `height` pixels from this corner. The
+/// offsets are in luma pixels and even, as 4:2:0 crops are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Crop {
+    pub left: usize,
+    pub top: usize,
+}
+
 /// Represents a single video frame with associated metadata.
 ///
 /// This struct serves as the primary unit of data passed through the media pipeline.
@@ -37,7 +48,8 @@ pub enum VideoFrameData {
     /// Planar YUV 4:2:0 data.
     ///
     /// The data is split into three separate planes. Note that U and V planes
-    /// are typically subsampled (half width/height of Y).
+    /// are typically subsampled (half width/height of Y). The planes may be
+    /// larger than the frame (see [`Crop`]).
     Yuv420 {
         y: Arc<Vec<u8>>,
         u: Arc<Vec<u8>>,
@@ -48,6 +60,8 @@ pub enum VideoFrameData {
         u_stride: usize,
         /// The byte width of a row in the V plane.
         v_stride: usize,
+        /// Offset of the picture in the planes.
+        crop: Crop,
     },
 }
 
@@ -129,6 +143,7 @@ impl VideoFrame {
                 y_stride,
                 u_stride: uv_stride,
                 v_stride: uv_stride,
+                crop: Crop::default(),
             },
         }
     }
@@ -147,6 +162,9 @@ impl VideoFrame {
 
     /// Attempts to retrieve the raw planes and strides if the frame is YUV420.
     ///
+    /// Each plane starts at the first pixel of the picture, so `width` x
+    /// `height` pixels can be read from it with its stride, cropped or not.
+    ///
     /// # Returns
     /// * `Some(YuvPlanes)` containing references to Y, U, V buffers and their strides.
     /// * `None` if the frame is in `Rgb` format.
@@ -159,15 +177,87 @@ impl VideoFrame {
                 y_stride,
                 u_stride,
                 v_stride,
-            } => Some((
-                y.as_ref(),
-                u.as_ref(),
-                v.as_ref(),
-                *y_stride,
-                *u_stride,
-                *v_stride,
-            )),
+                crop,
+            } => {
+                let (left, top) = (crop.left, crop.top);
+                Some((
+                    from_corner(y, *y_stride, left, top),
+                    from_corner(u, *u_stride, left / 2, top / 2),
+                    from_corner(v, *v_stride, left / 2, top / 2),
+                    *y_stride,
+                    *u_stride,
+                    *v_stride,
+                ))
+            }
             _ => None,
         }
     }
+
+    /// Whether `other` is a clone of this very frame, not just an equal one.
+    ///
+    /// Cheap (no pixel comparison): the UI uses it to skip converting and
+    /// uploading a frame it already shows. Holding on to the frame keeps its
+    /// buffers from being reused for a new one.
+    #[must_use]
+    pub fn is_same_frame(&self, other: &Self) -> bool {
+        self.timestamp_ms == other.timestamp_ms
+            && match (&self.data, &other.data) {
+                (VideoFrameData::Rgb(a), VideoFrameData::Rgb(b)) => Arc::ptr_eq(a, b),
+                (VideoFrameData::Yuv420 { y: a, .. }, VideoFrameData::Yuv420 { y: b, .. }) => {
+                    Arc::ptr_eq(a, b)
+                }
+                _ => false,
+            }
+    }
+}
+
+/// `plane` from pixel (`x`, `y`) on; empty if that is past its end.
+fn from_corner(plane: &[u8], stride: usize, x: usize, y: usize) -> &[u8] {
+    plane.get(y * stride + x..).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn clones_are_the_same_frame_equal_pixels_are_not() {
+        let frame = VideoFrame::synthetic_yuv420(16, 16, 3);
+        assert!(frame.is_same_frame(&frame.clone()));
+
+        let mut copy = frame.clone();
+        if let VideoFrameData::Yuv420 { y, .. } = &mut copy.data {
+            *y = Arc::new(y.as_ref().clone());
+        }
+        assert!(!frame.is_same_frame(&copy));
+        assert!(!frame.is_same_frame(&VideoFrame::synthetic_rgb(16, 16, 3)));
+    }
+
+    #[test]
+    fn cropped_planes_start_at_the_picture() {
+        // 4x2 picture at (2, 2) of 8x4 coded planes with padded rows
+        let (y_stride, c_stride) = (10, 6);
+        let y: Vec<u8> = (0..y_stride * 4).map(|i| i as u8).collect();
+        let c: Vec<u8> = (0..c_stride * 2).map(|i| i as u8).collect();
+        let frame = VideoFrame {
+            width: 4,
+            height: 2,
+            timestamp_ms: 0,
+            format: FrameFormat::Yuv420,
+            data: VideoFrameData::Yuv420 {
+                y: Arc::new(y),
+                u: Arc::new(c.clone()),
+                v: Arc::new(c),
+                y_stride,
+                u_stride: c_stride,
+                v_stride: c_stride,
+                crop: Crop { left: 2, top: 2 },
+            },
+        };
+        let (y, u, _, y_stride, ..) = frame.as_yuv_planes().unwrap();
+        assert_eq!(y[0], 22);
+        assert_eq!(y[y_stride], 32);
+        assert_eq!(u[0], 7);
+    }
 }
@@ -0,0 +1,219 @@
+//! Video mute: a camera-off placeholder for the local side, and detection of
+//! the peer's placeholder on the remote side.
+//!
+//! While muted, the camera is released and a dark "camera off" picture is
+//! sent at [`PLACEHOLDER_FPS`], so the track and the remote decoder stay
+//! alive without renegotiation. The peer recognizes the mute by the drop in
+//! frame rate: there is no signaling for it.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    log::log_sink::LogSink,
+    media_agent::{
+        frame_format::FrameFormat,
+        utils::now_millis,
+        video_frame::{VideoFrame, VideoFrameData},
+    },
+    sink_debug, sink_info,
+};
+
+pub const PLACEHOLDER_WIDTH: u32 = 320;
+pub const PLACEHOLDER_HEIGHT: u32 = 240;
+/// Frame rate of the camera-off picture.
+pub const PLACEHOLDER_FPS: u32 = 2;
+
+const BACKGROUND: [u8; 3] = [32, 32, 36];
+const ICON: [u8; 3] = [150, 150, 150];
+const SLASH: [u8; 3] = [200, 40, 40];
+
+/// A remote frame interval at least this long counts as "slow".
+const MUTED_INTERVAL: Duration = Duration::from_millis(300);
+/// A remote frame interval below this counts as "fast".
+const LIVE_INTERVAL: Duration = Duration::from_millis(200);
+/// Slow intervals in a row before the peer is considered muted.
+const MUTE_AFTER: u32 = 2;
+/// Fast intervals in a row before the peer is considered live again.
+const UNMUTE_AFTER: u32 = 3;
+/// No remote frame at all for this long also counts as muted.
+const STALL_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Draws the camera-off picture: a crossed-out camera on a dark background.
+#[must_use]
+pub fn camera_off_frame(width: u32, height: u32) -> VideoFrame {
+    let w = width as usize;
+    let h = height as usize;
+    let mut data = BACKGROUND.repeat(w * h);
+    let mut put = |x: usize, y: usize, color: [u8; 3]| {
+        if x < w && y < h {
+            let i = (y * w + x) * 3;
+            data[i..i + 3].copy_from_slice(&color);
+        }
+    };
+
+    // Camera body, with the lens as a triangle on its right
+    let (cx, cy) = (w / 2, h / 2);
+    let (body_w, body_h) = (w / 4, h / 5);
+    let left = cx.saturating_sub(body_w * 2 / 3);
+    let top = cy.saturating_sub(body_h / 2);
+    for y in top..top + body_h {
+        for x in left..left + body_w {
+            put(x, y, ICON);
+        }
+    }
+    let lens_left = left + body_w + body_w / 12;
+    for dx in 0..body_h / 2 {
+        for y in cy.saturating_sub(dx)..=cy + dx {
+            put(lens_left + dx, y, ICON);
+        }
+    }
+
+    // Slash from top-left to bottom-right of the icon
+    let span = body_w + body_w / 2;
+    let slash_top = cy.saturating_sub(span / 2);
+    let slash_left = cx.saturating_sub(span / 2);
+    for d in 0..span {
+        for t in 0..4 {
+            put(slash_left + d + t, slash_top + d, SLASH);
+        }
+    }
+
+    VideoFrame {
+        width,
+        height,
+        timestamp_ms: now_millis(),
+        format: FrameFormat::Rgb,
+        data: VideoFrameData::Rgb(Arc::new(data)),
+    }
+}
+
+/// Sends the camera-off picture at [`PLACEHOLDER_FPS`] until `running` is
+/// cleared or the receiver hangs up.
+pub fn camera_off_loop(logger: Arc<dyn LogSink>, tx: Sender<VideoFrame>, running: Arc<AtomicBool>) {
+    let period = Duration::from_millis(1_000 / u64::from(PLACEHOLDER_FPS));
+    let tick = Duration::from_millis(20);
+    let frame = camera_off_frame(PLACEHOLDER_WIDTH, PLACEHOLDER_HEIGHT);
+
+    sink_info!(logger, "[VideoMute] camera off, sending placeholder");
+    let mut next = Instant::now();
+    while running.load(Ordering::SeqCst) {
+        if Instant::now() >= next {
+            let mut frame = frame.clone();
+            frame.timestamp_ms = now_millis();
+            if tx.send(frame).is_err() {
+                break;
+            }
+            next += period;
+        }
+        // Short sleeps, so unmuting does not wait for a whole period
+        thread::sleep(tick);
+    }
+    sink_debug!(logger, "[VideoMute] placeholder stopped");
+}
+
+/// Tells from the remote frame rate whether the peer muted its video.
+#[derive(Debug, Default)]
+pub struct RemoteVideoMonitor {
+    last_frame: Option<Instant>,
+    slow: u32,
+    fast: u32,
+    muted: bool,
+}
+
+impl RemoteVideoMonitor {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Records a decoded remote frame. Returns the new state on a change.
+    pub fn on_frame(&mut self, now: Instant) -> Option<bool> {
+        let last = self.last_frame.replace(now)?;
+        let interval = now.saturating_duration_since(last);
+        if interval >= MUTED_INTERVAL {
+            self.slow += 1;
+            self.fast = 0;
+        } else if interval < LIVE_INTERVAL {
+            self.fast += 1;
+            self.slow = 0;
+        }
+
+        if !self.muted && self.slow >= MUTE_AFTER {
+            self.muted = true;
+            return Some(true);
+        }
+        if self.muted && self.fast >= UNMUTE_AFTER {
+            self.muted = false;
+            return Some(false);
+        }
+        None
+    }
+
+    /// Checks for a stalled stream. Returns `Some(true)` when the peer just
+    /// stopped sending altogether.
+    pub fn poll(&mut self, now: Instant) -> Option<bool> {
+        let last = self.last_frame?;
+        if !self.muted && now.saturating_duration_since(last) >= STALL_TIMEOUT {
+            self.muted = true;
+            self.fast = 0;
+            return Some(true);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn placeholder_is_dark_with_an_icon() {
+        let frame = camera_off_frame(PLACEHOLDER_WIDTH, PLACEHOLDER_HEIGHT);
+        let VideoFrameData::Rgb(data) = &frame.data else {
+            panic!("expected RGB");
+        };
+        assert_eq!(data.len(), 320 * 240 * 3);
+        assert_eq!(data[..3], BACKGROUND);
+        assert!(data.chunks(3).any(|p| p == ICON));
+        assert!(data.chunks(3).any(|p| p == SLASH));
+    }
+
+    #[test]
+    fn frame_rate_drop_is_reported_as_mute() {
+        let mut monitor = RemoteVideoMonitor::new();
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+
+        // Live at ~30 fps
+        for n in 0..10 {
+            assert_eq!(monitor.on_frame(ms(n * 33)), None);
+        }
+        // Placeholder at 2 fps
+        assert_eq!(monitor.on_frame(ms(797)), None);
+        assert_eq!(monitor.on_frame(ms(1_297)), Some(true));
+        assert_eq!(monitor.on_frame(ms(1_797)), None);
+        assert!(monitor.is_muted());
+
+        // Back to live
+        assert_eq!(monitor.on_frame(ms(1_830)), None);
+        assert_eq!(monitor.on_frame(ms(1_863)), None);
+        assert_eq!(monitor.on_frame(ms(1_896)), Some(false));
+
+        assert_eq!(monitor.poll(ms(2_000)), None);
+        assert_eq!(monitor.poll(ms(5_000)), Some(true));
+    }
+}
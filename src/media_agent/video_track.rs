@@ -0,0 +1,222 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    camera_manager::capture_settings::CaptureSettings,
+    config::Config,
+    core::thread_join::{JOIN_TIMEOUT, join_timeout},
+    log::log_sink::LogSink,
+    media_agent::{
+        camera_worker::{spawn_camera_worker, synthetic_loop},
+        constants::{CHANNELS_TIMEOUT, TARGET_FPS},
+        encoder_instruction::EncoderInstruction,
+        encoder_worker::spawn_encoder_worker,
+        events::MediaAgentEvent,
+        media_agent_error::{MediaAgentError, Result},
+        video_frame::VideoFrame,
+    },
+    media_transport::media_transport_event::MediaTransportEvent,
+    sink_debug, sink_error, sink_info, sink_warn,
+};
+
+/// Identifies an extra outbound video track added at runtime.
+pub type TrackId = u32;
+
+/// Where the frames of an extra video track come from.
+#[derive(Debug)]
+pub enum VideoSource {
+    /// A camera device (OpenCV index). Falls back to a test pattern if it
+    /// cannot be opened.
+    Camera(i32),
+    /// A moving test pattern.
+    TestPattern,
+    /// Frames pushed by the application (e.g. a screen grabber).
+    Frames(Receiver<VideoFrame>),
+}
+
+/// Capture + encoder pipeline of one extra video track.
+///
+/// Encoded frames are tagged with the track id and handed to the media
+/// transport, which sends them on the track's own SSRC.
+pub struct VideoTrackWorker {
+    id: TrackId,
+    logger: Arc<dyn LogSink>,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl VideoTrackWorker {
+    /// Starts the source, an encoder and the pump thread for `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError::EncoderSpawn` if a worker thread cannot be spawned.
+    pub fn spawn(
+        id: TrackId,
+        source: VideoSource,
+        logger: Arc<dyn LogSink>,
+        config: Arc<Config>,
+        media_transport_event_tx: Sender<MediaTransportEvent>,
+    ) -> Result<Self> {
+        let running = Arc::new(AtomicBool::new(true));
+        let mut handles = Vec::new();
+        let target_fps = config
+            .get("Media", "fps")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(TARGET_FPS);
+
+        let frame_rx = match source {
+            VideoSource::Camera(camera_id) => {
+                let settings = CaptureSettings::from_config(&config);
+                let (rx, status, handle) =
+                    spawn_camera_worker(settings, logger.clone(), camera_id, running.clone());
+                if let Some(msg) = status {
+                    sink_info!(logger, "[VideoTrack {}] {}", id, msg);
+                }
+                handles.extend(handle);
+                rx
+            }
+            VideoSource::TestPattern => {
+                let (tx, rx) = mpsc::channel();
+                let log = logger.clone();
+                let run = running.clone();
+                let handle = thread::Builder::new()
+                    .name(format!("video-track-{id}-pattern"))
+                    .spawn(move || {
+                        let _ = synthetic_loop(log, tx, target_fps, run);
+                    })
+                    .map_err(|e| MediaAgentError::EncoderSpawn(e.to_string()))?;
+                handles.push(handle);
+                rx
+            }
+            VideoSource::Frames(rx) => rx,
+        };
+
+        let (encoder_tx, encoder_rx) = mpsc::channel::<EncoderInstruction>();
+        let (encoded_tx, encoded_rx) = mpsc::channel::<MediaAgentEvent>();
+        handles.push(
+            spawn_encoder_worker(
+                logger.clone(),
+                encoder_rx,
+                encoded_tx,
+                running.clone(),
+                config,
+            )
+            .map_err(|e| MediaAgentError::EncoderSpawn(e.to_string()))?,
+        );
+
+        let log = logger.clone();
+        let run = running.clone();
+        let pump = thread::Builder::new()
+            .name(format!("video-track-{id}"))
+            .spawn(move || {
+                pump_loop(
+                    id,
+                    &log,
+                    &frame_rx,
+                    &encoder_tx,
+                    &encoded_rx,
+                    &media_transport_event_tx,
+                    &run,
+                );
+            })
+            .map_err(|e| MediaAgentError::EncoderSpawn(e.to_string()))?;
+        handles.push(pump);
+
+        sink_info!(logger, "[VideoTrack {}] started", id);
+        Ok(Self {
+            id,
+            logger,
+            running,
+            handles,
+        })
+    }
+
+    #[must_use]
+    pub const fn id(&self) -> TrackId {
+        self.id
+    }
+
+    /// Stops every thread of the track and waits for them.
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        for handle in self.handles.drain(..) {
+            if !join_timeout(handle, JOIN_TIMEOUT) {
+                sink_warn!(
+                    self.logger,
+                    "[VideoTrack {}] thread did not stop within {JOIN_TIMEOUT:?}, leaving it",
+                    self.id
+                );
+            }
+        }
+        sink_info!(self.logger, "[VideoTrack {}] stopped", self.id);
+    }
+}
+
+impl Drop for VideoTrackWorker {
+    fn drop(&mut self) {
+        if !self.handles.is_empty() {
+            self.stop();
+        }
+    }
+}
+
+/// Feeds captured frames to the encoder and encoded frames to the transport.
+fn pump_loop(
+    id: TrackId,
+    logger: &Arc<dyn LogSink>,
+    frame_rx: &Receiver<VideoFrame>,
+    encoder_tx: &Sender<EncoderInstruction>,
+    encoded_rx: &Receiver<MediaAgentEvent>,
+    media_transport_event_tx: &Sender<MediaTransportEvent>,
+    running: &AtomicBool,
+) {
+    let mut sent_any = false;
+    while running.load(Ordering::Relaxed) {
+        match frame_rx.recv_timeout(Duration::from_millis(CHANNELS_TIMEOUT)) {
+            Ok(frame) => {
+                // The first frame of a new track must be decodable on its own.
+                let force_keyframe = !sent_any;
+                sent_any = true;
+                if encoder_tx
+                    .send(EncoderInstruction::Encode(frame, force_keyframe))
+                    .is_err()
+                {
+                    sink_error!(logger, "[VideoTrack {}] encoder offline", id);
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                sink_debug!(logger, "[VideoTrack {}] source ended", id);
+                break;
+            }
+        }
+
+        while let Ok(event) = encoded_rx.try_recv() {
+            if let MediaAgentEvent::EncodedVideoFrame {
+                annexb_frame,
+                timestamp_ms,
+                codec_spec,
+            } = event
+                && media_transport_event_tx
+                    .send(MediaTransportEvent::SendTrackFrame {
+                        track_id: id,
+                        annexb_frame,
+                        timestamp_ms,
+                        codec_spec,
+                    })
+                    .is_err()
+            {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! Watermark compositor: burns a line of text, and optionally a picture,
+//! into outbound frames before they are encoded, for calls that are
+//! recorded for compliance.
+//!
+//! `[Media] watermark_text` is a template: `{user}` becomes the local user
+//! (`[Signaling] username`) and `{time}` the capture time as `YYYY-MM-DD
+//! HH:MM:SS` (UTC). `watermark_image` is a picture file drawn next to the
+//! text, and `watermark_position` picks the corner. Only what is sent is
+//! marked; the local preview stays clean.
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use opencv::{core::Mat, imgcodecs, imgproc, prelude::*};
+
+use crate::{
+    camera_manager::utils::tight_rgb_bytes,
+    config::Config,
+    media_agent::video_frame::{VideoFrame, VideoFrameData},
+};
+
+/// 3x5 glyphs; bit 2 is the left column. Lowercase is drawn as uppercase,
+/// and anything else as `?`.
+const GLYPHS: [(char, [u8; 5]); 46] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+    (' ', [0b000, 0b000, 0b000, 0b000, 0b000]),
+    (':', [0b000, 0b010, 0b000, 0b010, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    (',', [0b000, 0b000, 0b000, 0b010, 0b100]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('_', [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('/', [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('@', [0b010, 0b101, 0b111, 0b100, 0b011]),
+    ('#', [0b101, 0b111, 0b101, 0b111, 0b101]),
+    ('?', [0b111, 0b001, 0b010, 0b000, 0b010]),
+];
+
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+/// Share of the picture kept under the text's backdrop, in 256ths.
+const BACKDROP_KEEP: u16 = 96;
+/// Opacity of the watermark picture, in 256ths.
+const IMAGE_ALPHA: u16 = 192;
+
+/// The rows of `ch`'s glyph.
+#[must_use]
+pub fn glyph(ch: char) -> [u8; 5] {
+    let ch = ch.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(c, _)| *c == ch)
+        .or_else(|| GLYPHS.last())
+        .map_or([0; 5], |(_, rows)| *rows)
+}
+
+/// Formats a Unix time in milliseconds as `YYYY-MM-DD HH:MM:SS` (UTC).
+#[must_use]
+pub fn timestamp_text(unix_ms: u128) -> String {
+    let secs = unix_ms / 1_000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3_600,
+        (secs / 60) % 60,
+        secs % 60
+    )
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's
+/// `civil_from_days`).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Corner of the frame the watermark sits in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::TopLeft => "top_left",
+            Self::TopRight => "top_right",
+            Self::BottomLeft => "bottom_left",
+            Self::BottomRight => "bottom_right",
+        }
+    }
+
+    const fn is_right(self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+
+    const fn is_bottom(self) -> bool {
+        matches!(self, Self::BottomLeft | Self::BottomRight)
+    }
+}
+
+impl fmt::Display for Corner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Corner {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "top_left" => Ok(Self::TopLeft),
+            "top_right" => Ok(Self::TopRight),
+            "bottom_left" => Ok(Self::BottomLeft),
+            "bottom_right" => Ok(Self::BottomRight),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A picture to stamp, as tight RGB.
+#[derive(Debug, Clone)]
+struct Image {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+}
+
+/// Why the watermark picture could not be loaded.
+#[derive(Debug)]
+pub enum WatermarkError {
+    /// The file is missing or not a picture OpenCV reads.
+    NotAPicture(String),
+    /// OpenCV failed to convert the picture.
+    OpenCv(opencv::Error),
+}
+
+impl fmt::Display for WatermarkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAPicture(path) => write!(f, "{path}: not a picture"),
+            Self::OpenCv(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WatermarkError {}
+
+impl From<opencv::Error> for WatermarkError {
+    fn from(e: opencv::Error) -> Self {
+        Self::OpenCv(e)
+    }
+}
+
+/// What is drawn on each outbound frame.
+#[derive(Debug, Clone)]
+pub struct Watermark {
+    template: String,
+    user: String,
+    corner: Corner,
+    image: Option<Image>,
+}
+
+impl Watermark {
+    /// The `[Media] watermark_*` keys, or `None` when neither a text nor a
+    /// picture is set. The picture is loaded separately, see
+    /// [`load_image`](Self::load_image).
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let template = config.get("Media", "watermark_text").unwrap_or_default();
+        if template.is_empty() && config.get_non_empty("Media", "watermark_image").is_none() {
+            return None;
+        }
+        Some(Self {
+            template: template.to_owned(),
+            user: config
+                .get("Signaling", "username")
+                .unwrap_or_default()
+                .to_owned(),
+            corner: config
+                .get("Media", "watermark_position")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            image: None,
+        })
+    }
+
+    /// Loads the picture at `path` (any format OpenCV reads).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WatermarkError::NotAPicture`] if the file can't be read
+    /// or decoded, [`WatermarkError::OpenCv`] if converting it fails.
+    pub fn load_image(&mut self, path: &str) -> Result<(), WatermarkError> {
+        let bgr = imgcodecs::imread(path, imgcodecs::IMREAD_COLOR)?;
+        if bgr.empty() {
+            return Err(WatermarkError::NotAPicture(path.to_owned()));
+        }
+        let mut rgb = Mat::default();
+        imgproc::cvt_color(
+            &bgr,
+            &mut rgb,
+            imgproc::COLOR_BGR2RGB,
+            0,
+            opencv::core::AlgorithmHint::ALGO_HINT_DEFAULT,
+        )?;
+        let (width, height) = (rgb.cols().max(0) as u32, rgb.rows().max(0) as u32);
+        let bytes = tight_rgb_bytes(&rgb, width, height)?;
+        self.image = Some(Image {
+            width: width as usize,
+            height: height as usize,
+            rgb: bytes,
+        });
+        Ok(())
+    }
+
+    /// The text for a frame captured at `unix_ms`.
+    #[must_use]
+    pub fn text(&self, unix_ms: u128) -> String {
+        self.template
+            .replace("{user}", &self.user)
+            .replace("{time}", &timestamp_text(unix_ms))
+    }
+
+    /// Draws the watermark onto `frame`. The pixels are copied first if
+    /// anyone else (the local preview) still holds them.
+    pub fn apply(&self, frame: &mut VideoFrame) {
+        let (w, h) = (frame.width as usize, frame.height as usize);
+        let text = self.text(frame.timestamp_ms);
+        let mut canvas = match &mut frame.data {
+            VideoFrameData::Rgb(data) => Canvas::Rgb {
+                data: Arc::make_mut(data),
+                stride: w * 3,
+            },
+            VideoFrameData::Yuv420 { y, y_stride, .. } => Canvas::Luma {
+                data: Arc::make_mut(y),
+                stride: *y_stride,
+            },
+        };
+
+        // One font pixel per 120 rows keeps the text readable at any size
+        let scale = (h / 120).max(1);
+        let margin = 2 * scale;
+        let text_w = text.chars().count() * 4 * scale + scale;
+        let text_h = if text.is_empty() { 0 } else { 7 * scale };
+        let (image_w, image_h) = self
+            .image
+            .as_ref()
+            .map_or((0, 0), |img| (img.width, img.height + margin));
+        let block_w = text_w.max(image_w);
+        let block_h = text_h + image_h;
+        if block_w + 2 * margin > w || block_h + 2 * margin > h {
+            return;
+        }
+        let left = if self.corner.is_right() {
+            w - margin - block_w
+        } else {
+            margin
+        };
+        let top = if self.corner.is_bottom() {
+            h - margin - block_h
+        } else {
+            margin
+        };
+
+        if let Some(img) = &self.image {
+            let img_left = if self.corner.is_right() {
+                left + block_w - img.width
+            } else {
+                left
+            };
+            for y in 0..img.height {
+                for x in 0..img.width {
+                    let i = (y * img.width + x) * 3;
+                    let color = [img.rgb[i], img.rgb[i + 1], img.rgb[i + 2]];
+                    canvas.blend(img_left + x, top + y, color, IMAGE_ALPHA);
+                }
+            }
+        }
+        if text.is_empty() {
+            return;
+        }
+
+        let text_left = if self.corner.is_right() {
+            left + block_w - text_w
+        } else {
+            left
+        };
+        let text_top = top + image_h;
+        for y in text_top..text_top + text_h {
+            for x in text_left..text_left + text_w {
+                canvas.darken(x, y, BACKDROP_KEEP);
+            }
+        }
+        for (n, ch) in text.chars().enumerate() {
+            let glyph_left = text_left + scale + n * 4 * scale;
+            for (row, bits) in glyph(ch).iter().enumerate() {
+                for col in 0..3 {
+                    if bits & (0b100 >> col) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = glyph_left + col * scale + dx;
+                            let y = text_top + scale + row * scale + dy;
+                            canvas.blend(x, y, TEXT_COLOR, 256);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The plane the watermark is drawn on: packed RGB, or only the luma of a
+/// YUV frame (the picture then shows up in grey).
+enum Canvas<'a> {
+    Rgb { data: &'a mut [u8], stride: usize },
+    Luma { data: &'a mut [u8], stride: usize },
+}
+
+impl Canvas<'_> {
+    /// Mixes `color` into the pixel at `(x, y)`; `alpha` is in 256ths.
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 3], alpha: u16) {
+        let mix = |under: u8, over: u8| {
+            ((u16::from(under) * (256 - alpha) + u16::from(over) * alpha) >> 8) as u8
+        };
+        match self {
+            Self::Rgb { data, stride } => {
+                let i = y * *stride + x * 3;
+                if let Some(px) = data.get_mut(i..i + 3) {
+                    for (under, over) in px.iter_mut().zip(color) {
+                        *under = mix(*under, over);
+                    }
+                }
+            }
+            Self::Luma { data, stride } => {
+                let [r, g, b] = color.map(u32::from);
+                // BT.601 studio range
+                let luma = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+                if let Some(px) = data.get_mut(y * *stride + x) {
+                    *px = mix(*px, luma as u8);
+                }
+            }
+        }
+    }
+
+    /// Scales the pixel at `(x, y)` down to `keep` 256ths.
+    fn darken(&mut self, x: usize, y: usize, keep: u16) {
+        let dim = |v: &mut u8| *v = ((u16::from(*v) * keep) >> 8) as u8;
+        match self {
+            Self::Rgb { data, stride } => {
+                let i = y * *stride + x * 3;
+                if let Some(px) = data.get_mut(i..i + 3) {
+                    px.iter_mut().for_each(dim);
+                }
+            }
+            Self::Luma { data, stride } => {
+                if let Some(px) = data.get_mut(y * *stride + x) {
+                    dim(px);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::media_agent::frame_format::FrameFormat;
+
+    #[test]
+    fn template_fills_in_user_and_utc_time() {
+        // 2024-02-29 13:05:09 UTC
+        let ms = 1_709_211_909_000u128;
+        assert_eq!(timestamp_text(ms), "2024-02-29 13:05:09");
+
+        let mut config = Config::empty();
+        assert!(Watermark::from_config(&config).is_none());
+        config.set("Media", "watermark_text", "{user} {time}");
+        config.set("Signaling", "username", "alice");
+        let wm = Watermark::from_config(&config).unwrap();
+        assert_eq!(wm.corner, Corner::BottomRight);
+        assert_eq!(wm.text(ms), "alice 2024-02-29 13:05:09");
+        assert_eq!(glyph('a'), glyph('A'));
+        assert_eq!(glyph('~'), glyph('?'));
+    }
+
+    #[test]
+    fn text_is_drawn_in_the_corner_of_sent_frames_only() {
+        let mut config = Config::empty();
+        config.set("Media", "watermark_text", "REC");
+        config.set("Media", "watermark_position", "top-left");
+        let wm = Watermark::from_config(&config).unwrap();
+
+        let (w, h) = (160usize, 120usize);
+        let preview = Arc::new(vec![128u8; w * h * 3]);
+        let mut frame = VideoFrame {
+            width: w as u32,
+            height: h as u32,
+            timestamp_ms: 0,
+            format: FrameFormat::Rgb,
+            data: VideoFrameData::Rgb(preview.clone()),
+        };
+        wm.apply(&mut frame);
+        let VideoFrameData::Rgb(sent) = &frame.data else {
+            panic!("expected RGB");
+        };
+        // Top-left pixel of the R, on the backdrop, and the far corner
+        let px = |x: usize, y: usize| sent[(y * w + x) * 3];
+        assert_eq!(px(3, 3), 255);
+        assert_eq!(px(2, 2), 48);
+        assert_eq!(px(w - 1, h - 1), 128);
+        assert!(preview.iter().all(|&v| v == 128));
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{media_agent::spec::CodecSpec, rtp_session::rtp_codec::RtpCodec};
+use crate::{
+    media_agent::{audio_format::AudioFormat, spec::CodecSpec},
+    media_transport::payload::telephone_event::TELEPHONE_EVENT_CLOCK_RATE,
+    rtp_session::rtp_codec::RtpCodec,
+};
 
 /// Describes the complete configuration of a media codec for network negotiation.
 ///
@@ -17,7 +21,7 @@ pub struct CodecDescriptor {
     ///
     /// This string contains specific configuration parameters negotiated via SDP.
     /// For H.264, this includes the Profile-Level-ID and Packetization Mode.
-    /// Example: `"profile-level-id=42e01f;packetization-mode=1"`
+    /// Example: `"level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"`
     pub sdp_fmtp: Option<String>,
 
     /// The internal enum identifier used by the `MediaAgent` logic.
@@ -39,6 +43,8 @@ impl CodecDescriptor {
     ///
     /// * **Clock Rate**: 90,000 Hz (Standard for video).
     /// * **Packetization Mode**: 1 (Non-interleaved mode, allows fragmentation unit NALs).
+    /// * **Level Asymmetry Allowed**: 1, as browsers offer it, so each side may
+    ///   send at a different level.
     /// * **Profile Level ID**: `42e01f`
     ///   - `42`: Baseline Profile
     ///   - `e0`: Constraint set flags (Constrained Baseline)
@@ -48,7 +54,9 @@ impl CodecDescriptor {
             codec_name: "H264",
             rtp_representation: RtpCodec::with_name(pt, 90_000, "H264"),
             // Packetization mode 1 is required for FU-A fragmentation support.
-            sdp_fmtp: Some("profile-level-id=42e01f;packetization-mode=1".into()),
+            sdp_fmtp: Some(
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f".into(),
+            ),
             spec: CodecSpec::H264,
         }
     }
@@ -61,4 +69,31 @@ impl CodecDescriptor {
             spec: CodecSpec::G711U,
         }
     }
+
+    /// Linear PCM (RFC 3551) at the local receive `format`: the rate and
+    /// channels go in the `rtpmap`, the channel preference in the `fmtp`.
+    pub fn l16_dynamic(pt: u8, format: AudioFormat) -> Self {
+        Self {
+            codec_name: "L16",
+            rtp_representation: RtpCodec::with_name(pt, format.sample_rate, "L16")
+                .with_channels(format.channels),
+            sdp_fmtp: Some(format.to_fmtp()),
+            spec: CodecSpec::L16,
+        }
+    }
+
+    /// DTMF events (RFC 4733) on the audio clock. The `fmtp` advertises the
+    /// 16 DTMF events (`0-9`, `*`, `#`, `A-D`).
+    pub fn telephone_event_dynamic(pt: u8) -> Self {
+        Self {
+            codec_name: "telephone-event",
+            rtp_representation: RtpCodec::with_name(
+                pt,
+                TELEPHONE_EVENT_CLOCK_RATE,
+                "telephone-event",
+            ),
+            sdp_fmtp: Some("0-15".into()),
+            spec: CodecSpec::TelephoneEvent,
+        }
+    }
 }
@@ -3,7 +3,8 @@
 //! Input : a stream of RTP payloads with the same timestamp, ending with M=1.
 //! Output: an Annex-B access unit (frame) as bytes, or None if more packets are needed.
 //!
-//! Scope : non-interleaved, packetization-mode=1. STAP-A is ignored (not used by your packetizer).
+//! Scope : non-interleaved, packetization-mode=1. STAP-A is unpacked (our packetizer sends
+//!         SPS/PPS and small slices that way, and so do cameras).
 
 #[derive(Debug, Clone)]
 struct FuState {
@@ -111,7 +112,19 @@ impl H264Depacketizer {
                     }
                 }
             }
-            24 => { /* ignore STAP-A as before */ }
+            24 => {
+                // STAP-A: [size:u16][NALU] repeated after the STAP-A header
+                let mut rest = &payload[1..];
+                while rest.len() >= 2 {
+                    let size = usize::from(u16::from_be_bytes([rest[0], rest[1]]));
+                    let Some(nalu) = rest.get(2..2 + size).filter(|n| !n.is_empty()) else {
+                        self.frame_corrupted = true;
+                        break;
+                    };
+                    self.push_slice_if_new(nalu);
+                    rest = &rest[2 + size..];
+                }
+            }
             _ => {
                 self.frame_corrupted = true;
             }
@@ -276,6 +289,35 @@ mod tests {
         assert_eq!(frame, expected_frame);
     }
 
+    #[test]
+    fn stap_a_is_unpacked() {
+        let mut d = H264Depacketizer::new();
+        let mut seq = 7;
+
+        let sps = mk_nalu(7, 0x60, 4);
+        let pps = mk_nalu(8, 0x60, 3);
+        let idr = mk_nalu(5, 0x60, 6);
+        let mut stap = vec![0x78]; // NRI=3, type 24
+        for nalu in [&sps, &pps] {
+            stap.extend_from_slice(&u16::try_from(nalu.len()).unwrap().to_be_bytes());
+            stap.extend_from_slice(nalu);
+        }
+
+        assert!(push_seq(&mut d, &stap, false, 500, &mut seq).is_none());
+        let frame = push_seq(&mut d, &idr, true, 500, &mut seq).expect("Frame expected");
+
+        let mut expected_frame = Vec::new();
+        for nalu in [&sps, &pps, &idr] {
+            expected_frame.extend_from_slice(&[0, 0, 0, 1]);
+            expected_frame.extend_from_slice(nalu);
+        }
+        assert_eq!(frame, expected_frame);
+
+        // A size running past the packet corrupts the frame
+        let truncated = [0x78, 0x00, 0x09, 0x67, 0x42];
+        assert!(push_seq(&mut d, &truncated, true, 600, &mut seq).is_none());
+    }
+
     #[test]
     fn fua_three_fragments_reassembles() {
         let mut d = H264Depacketizer::new();
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     sync::{
         Arc, RwLock,
         mpsc::{Receiver, Sender},
@@ -7,10 +7,16 @@ use std::{
     thread::{self, JoinHandle},
 };
 
-use crate::media_transport::{codec::CodecDescriptor, events::DepacketizerEvent};
+use crate::media_transport::{
+    codec::CodecDescriptor, events::DepacketizerEvent, frame_crypto::FrameCryptor,
+};
 use crate::{
+    core::{
+        buffer_pool::BufferPool,
+        latency::{LatencyStage, LatencyTracer},
+    },
     log::log_sink::LogSink,
-    media_agent::spec::CodecSpec,
+    media_agent::{spec::CodecSpec, utils::now_millis},
     media_transport::{
         depacketizer::h264_depacketizer::H264Depacketizer, media_transport_event::RtpIn,
     },
@@ -25,38 +31,55 @@ use crate::{
 ///
 /// # Architecture
 ///
-/// 1. **Filtering**: Checks if the packet's Payload Type (PT) is in the `allowed_pts` set.
+/// 1. **Filtering**: Checks if the packet's Payload Type (PT) is in the `allowed_pts` map.
 ///    This allows dynamic filtering based on SDP negotiation (e.g., ignoring unnegotiated streams).
 /// 2. **Lookup**: Retrieves codec details from `payload_map` to associate the PT with a codec spec.
 /// 3. **Reassembly**: Uses `H264Depacketizer` to buffer fragments (FU-A) until the "Marker" bit
 ///    or a complete NAL unit signifies the end of a frame.
-/// 4. **Output**: Sends `AnnexBFrameReady` containing the full byte buffer of the frame.
+/// 4. **Output**: Sends `AnnexBFrameReady` containing the full byte buffer of the frame
+///    and the arrival time of its first packet, the origin of the receive-side
+///    latency stages.
 ///
 /// # Arguments
 ///
 /// * `logger` - Shared logger for tracing packet flow.
-/// * `allowed_pts` - A thread-safe set of currently valid RTP Payload Types (updated via SDP).
+/// * `allowed_pts` - Currently valid RTP Payload Types (updated via SDP), mapped to
+///   our Payload Type for the same codec.
 /// * `rtp_packet_rx` - Input channel for raw RTP packets.
 /// * `event_tx` - Output channel for reassembled frames.
 /// * `payload_map` - Static mapping between Payload Types and `CodecDescriptor`s.
+/// * `preferred_video_ssrc` - When set, only video from this SSRC is forwarded
+///   (the decoder handles a single stream at a time).
+/// * `latency` - Where the depacketize stage of each frame is recorded.
+/// * `buffers` - Pool the video payloads are given back to once reassembled.
+/// * `cryptor` - When set, frames are end-to-end decrypted; those that fail
+///   are dropped.
 ///
 /// # Panics
 ///
 /// Panics if the OS fails to create the thread (`expect` on `thread::spawn`).
-#[allow(clippy::expect_used)]
+#[allow(clippy::expect_used, clippy::too_many_arguments)]
 pub fn spawn_depacketizer_worker(
     logger: Arc<dyn LogSink>,
-    allowed_pts: Arc<RwLock<HashSet<u8>>>,
+    allowed_pts: Arc<RwLock<HashMap<u8, u8>>>,
     rtp_packet_rx: Receiver<RtpIn>,
     event_tx: Sender<DepacketizerEvent>,
     payload_map: Arc<HashMap<u8, CodecDescriptor>>,
+    preferred_video_ssrc: Arc<RwLock<Option<u32>>>,
+    latency: LatencyTracer,
+    buffers: BufferPool,
+    cryptor: Option<Arc<FrameCryptor>>,
 ) -> JoinHandle<()> {
     thread::Builder::new()
         .name("media-transport-depack".into())
         .spawn(move || {
             // Currently hardcoded to H264. 
             // In the future, this could be a dynamic trait object based on the Payload Type.
-            let mut depacketizer = H264Depacketizer::new();
+            // One reassembly buffer per SSRC, so extra tracks do not corrupt each other.
+            let mut depacketizers: HashMap<u32, H264Depacketizer> = HashMap::new();
+            // RTP timestamp and arrival of the first packet of the frame being
+            // reassembled, per SSRC.
+            let mut frame_starts: HashMap<u32, (u32, u128)> = HashMap::new();
 
             while let Ok(pkt) = rtp_packet_rx.recv() {
                 sink_trace!(logger, "[Depacketizer] Received RTP Packet");
@@ -69,18 +92,18 @@ pub fn spawn_depacketizer_worker(
                 );
 
                 // 1. Verify if this Payload Type is currently negotiated/allowed.
-                let ok_pt = allowed_pts
+                let local_pt = allowed_pts
                     .read()
-                    .map(|set| set.contains(&pkt.pt))
-                    .unwrap_or(false);
+                    .ok()
+                    .and_then(|map| map.get(&pkt.pt).copied());
 
-                if !ok_pt {
+                let Some(local_pt) = local_pt else {
                     sink_trace!(logger, "[MediaTransport] dropping RTP PT={}", pkt.pt);
                     continue;
-                }
+                };
 
                 // 2. Resolve the codec specification.
-                let Some(codec_desc) = payload_map.get(&pkt.pt) else {
+                let Some(codec_desc) = payload_map.get(&local_pt) else {
                     sink_trace!(logger, "[MediaTransport] unknown payload type {}", pkt.pt);
                     continue;
                 };
@@ -95,6 +118,21 @@ pub fn spawn_depacketizer_worker(
 
                 match codec_desc.spec {
                     CodecSpec::H264 => {
+                        let preferred = preferred_video_ssrc.read().ok().and_then(|p| *p);
+                        if preferred.is_some_and(|ssrc| ssrc != pkt.ssrc) {
+                            sink_trace!(logger, "[Depacketizer] skipping video ssrc {}", pkt.ssrc);
+                            continue;
+                        }
+                        let start = frame_starts
+                            .entry(pkt.ssrc)
+                            .or_insert((pkt.timestamp_90khz, now_millis()));
+                        if start.0 != pkt.timestamp_90khz {
+                            *start = (pkt.timestamp_90khz, now_millis());
+                        }
+                        let received_ms = start.1;
+                        let depacketizer = depacketizers
+                            .entry(pkt.ssrc)
+                            .or_insert_with(H264Depacketizer::new);
                         // 3. Feed the packet into the reassembly logic.
                         // The depacketizer returns `Some(bytes)` only when a full frame is complete.
                         if let Some(annex_b_frame) =
@@ -104,17 +142,39 @@ pub fn spawn_depacketizer_worker(
                                 logger,
                                 "[Depacketizer] AnnexBFrameReady sending it to DepcketizerEventLoop (MT)"
                             );
-                            let _ = event_tx.send(DepacketizerEvent::AnnexBFrameReady {
+                            latency.record_since(LatencyStage::Depacketize, received_ms);
+                            let frame = match &cryptor {
+                                Some(cryptor) => cryptor.decrypt_frame(codec_desc.spec, &annex_b_frame),
+                                None => Some(annex_b_frame),
+                            };
+                            if let Some(bytes) = frame {
+                                let _ = event_tx.send(DepacketizerEvent::AnnexBFrameReady {
+                                    codec_spec: codec_desc.spec,
+                                    bytes,
+                                    received_ms,
+                                });
+                            } else {
+                                sink_trace!(logger, "[Depacketizer] dropping frame that failed decryption");
+                            }
+                        }
+                        // Copied into the frame: the buffer can take another packet
+                        buffers.give(pkt.payload);
+                    }
+                    CodecSpec::G711U | CodecSpec::L16 => {
+                        let payload = match &cryptor {
+                            Some(cryptor) => cryptor.decrypt_frame(codec_desc.spec, &pkt.payload),
+                            None => Some(pkt.payload),
+                        };
+                        if let Some(payload) = payload {
+                            let _ = event_tx.send(DepacketizerEvent::EncodedAudioFrameReady {
                                 codec_spec: codec_desc.spec,
-                                bytes: annex_b_frame,
+                                payload,
+                                ssrc: pkt.ssrc,
                             });
                         }
                     }
-                    CodecSpec::G711U => {
-                         let _ = event_tx.send(DepacketizerEvent::EncodedAudioFrameReady {
-                            codec_spec: codec_desc.spec,
-                            payload: pkt.payload,
-                        });
+                    CodecSpec::TelephoneEvent => {
+                        sink_trace!(logger, "[Depacketizer] Ignoring incoming telephone event");
                     }
                 }
             }
@@ -4,6 +4,8 @@ use std::fmt;
 pub enum MediaTransportError {
     Send(String),
     Mutex(String),
+    /// A character in a dial string that is not a DTMF digit.
+    InvalidDtmf(char),
 }
 
 impl fmt::Display for MediaTransportError {
@@ -12,6 +14,7 @@ impl fmt::Display for MediaTransportError {
         match self {
             Send(e) => write!(f, "Send error: {e}"),
             Mutex(e) => write!(f, "Mutex error: {e}"),
+            InvalidDtmf(c) => write!(f, "Invalid DTMF digit: {c:?}"),
         }
     }
 }
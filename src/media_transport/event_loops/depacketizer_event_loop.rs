@@ -74,18 +74,26 @@ impl DepacketizerEventLoop {
                 match depacketizer_event_rx.recv_timeout(TIMEOUT) {
                     Ok(event) => {
                         let _ = match event {
-                            DepacketizerEvent::AnnexBFrameReady { codec_spec, bytes } => {
+                            DepacketizerEvent::AnnexBFrameReady {
+                                codec_spec,
+                                bytes,
+                                received_ms,
+                            } => {
                                 sink_trace!(
                                     logger,
                                     "[DepacketizerEventLoop (MT)] Received AnnexBFrameReady. Sending it to MediaAgent"
                                 );
                                 // Forward the reassembled frame to the upper layer
-                                media_agent_event_tx
-                                    .send(MediaAgentEvent::AnnexBFrameReady { codec_spec, bytes })
+                                media_agent_event_tx.send(MediaAgentEvent::AnnexBFrameReady {
+                                    codec_spec,
+                                    bytes,
+                                    received_ms,
+                                })
                             }
                             DepacketizerEvent::EncodedAudioFrameReady {
                                 codec_spec,
                                 payload,
+                                ssrc,
                             } => {
                                 sink_trace!(
                                     logger,
@@ -94,6 +102,7 @@ impl DepacketizerEventLoop {
                                 media_agent_event_tx.send(MediaAgentEvent::EncodedAudioFrame {
                                     codec_spec,
                                     payload,
+                                    ssrc,
                                 })
                             }
                         };
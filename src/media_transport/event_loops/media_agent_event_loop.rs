@@ -1,26 +1,33 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     sync::{
         Arc, Mutex, RwLock,
         atomic::{AtomicBool, Ordering},
         mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender},
     },
     thread::JoinHandle,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
-    core::{events::EngineEvent, session::Session},
+    core::{
+        events::EngineEvent,
+        latency::{LatencyStage, LatencyTracer},
+        session::Session,
+    },
     log::log_sink::LogSink,
-    media_agent::events::MediaAgentEvent,
+    media_agent::{
+        audio_format::AudioFormat, events::MediaAgentEvent, spec::CodecSpec, video_track::TrackId,
+    },
     media_transport::{
         codec::CodecDescriptor,
         error::{MediaTransportError, Result},
         event_loops::constants::RECV_TIMEOUT,
         media_transport_event::{MediaTransportEvent, RtpIn},
         packetizer_worker::PacketizeOrder,
+        payload::telephone_event::{DEFAULT_DTMF_TONE, DtmfSender, EventPacket},
     },
-    rtp_session::outbound_track_handle::OutboundTrackHandle,
+    rtp_session::{outbound_track_handle::OutboundTrackHandle, rtp_codec::RtpCodec},
     sink_debug, sink_error, sink_info, sink_trace,
 };
 
@@ -36,10 +43,12 @@ pub struct MediaAgentEventLoop {
     stop_flag: Arc<AtomicBool>,
     event_loop_handler: Option<JoinHandle<()>>,
     target_fps: u32,
+    /// Where the encode stage of each video frame is recorded.
+    latency: LatencyTracer,
 }
 
 impl MediaAgentEventLoop {
-    pub fn new(target_fps: u32, logger: Arc<dyn LogSink>) -> Self {
+    pub fn new(target_fps: u32, logger: Arc<dyn LogSink>, latency: LatencyTracer) -> Self {
         let stop_flag = Arc::new(AtomicBool::new(false));
         let running_flag = Arc::new(AtomicBool::new(false));
         Self {
@@ -48,6 +57,7 @@ impl MediaAgentEventLoop {
             stop_flag,
             event_loop_handler: None,
             target_fps,
+            latency,
         }
     }
 
@@ -61,8 +71,9 @@ impl MediaAgentEventLoop {
     /// * `session`: Reference to the core RTP Session.
     /// * `payload_map`: Configured codecs.
     /// * `outbound_tracks`: State of active outbound RTP streams.
+    /// * `track_handles`: Send streams of the extra video tracks, by track id.
     /// * `event_tx`: Channel to report errors/status to the main Engine.
-    /// * `allowed_pts`: Set of allowed Payload Types (updated upon negotiation).
+    /// * `allowed_pts`: Allowed Payload Types, mapped to ours (updated upon negotiation).
     /// * `media_agent_tx`: Back-channel to the Media Agent (e.g., for bitrate commands).
     #[allow(clippy::too_many_arguments, clippy::similar_names)]
     #[allow(clippy::expect_used)]
@@ -74,8 +85,9 @@ impl MediaAgentEventLoop {
         session: Arc<Mutex<Option<Session>>>,
         payload_map: Arc<HashMap<u8, CodecDescriptor>>,
         outbound_tracks: Arc<Mutex<HashMap<u8, OutboundTrackHandle>>>,
+        track_handles: Arc<Mutex<HashMap<TrackId, OutboundTrackHandle>>>,
         event_tx: Sender<EngineEvent>,
-        allowed_pts: Arc<RwLock<HashSet<u8>>>,
+        allowed_pts: Arc<RwLock<HashMap<u8, u8>>>,
         media_agent_tx: Sender<MediaAgentEvent>,
     ) {
         let stop_flag = self.stop_flag.clone();
@@ -86,17 +98,48 @@ impl MediaAgentEventLoop {
         let rtp_ts_step = 90_000 / self.target_fps;
 
         let logger = self.logger.clone();
+        let latency = self.latency.clone();
 
         let handle = std::thread::spawn(move || {
             let mut last_received_local_ts_ms = None;
-            let mut last_received_audio_ts_ms = None;
 
             // Initialize random start timestamp for security/standard compliance.
             let mut video_rtp_ts = rand::random::<u32>();
             let mut audio_rtp_ts = rand::random::<u32>();
+            let mut sending = true;
+            // Extra tracks: SSRCs waiting for the RTP session, and RTP clocks.
+            let mut pending_tracks: HashMap<TrackId, u32> = HashMap::new();
+            let mut track_rtp_ts: HashMap<TrackId, u32> = HashMap::new();
+            // DTMF digits, sent on the audio stream in place of audio.
+            let mut dtmf = DtmfSender::new(DEFAULT_DTMF_TONE);
+            // Audio codec picked for the peer on `Established`.
+            let mut audio_spec = CodecSpec::G711U;
+            // Id of the audio level extension, if the peer accepts it.
+            let mut audio_level_ext: Option<u8> = None;
 
             while !stop_flag.load(Ordering::SeqCst) {
-                match media_transport_event_rx.recv_timeout(Duration::from_millis(RECV_TIMEOUT)) {
+                if !dtmf.is_idle() {
+                    let packets = dtmf.poll(Instant::now(), audio_rtp_ts);
+                    if let Some(last) = packets.last().filter(|p| p.event.end) {
+                        // Audio resumes right after the event
+                        audio_rtp_ts = last.rtp_ts.wrapping_add(u32::from(last.event.duration));
+                    }
+                    if sending && !packets.is_empty() {
+                        send_telephone_events(
+                            &session,
+                            &payload_map,
+                            &outbound_tracks,
+                            &packets,
+                            &logger,
+                        );
+                    }
+                }
+                let timeout = dtmf
+                    .next_deadline(Instant::now())
+                    .unwrap_or(Duration::MAX)
+                    .min(Duration::from_millis(RECV_TIMEOUT));
+
+                match media_transport_event_rx.recv_timeout(timeout) {
                     Ok(event) => match event {
                         // --- Egress Video Path ---
                         MediaTransportEvent::SendEncodedFrame {
@@ -109,16 +152,20 @@ impl MediaAgentEventLoop {
                                 "[MT Event Loop MA] Received SendEncodedFrame."
                             );
                             // Simple deduplication logic
-                            if last_received_local_ts_ms == Some(timestamp_ms) {
+                            if !sending || last_received_local_ts_ms == Some(timestamp_ms) {
                                 continue;
                             }
                             last_received_local_ts_ms = Some(timestamp_ms);
+                            latency.record_since(LatencyStage::Encode, timestamp_ms);
 
                             // Construct the order for the packetizer worker
                             let order = PacketizeOrder {
                                 payload: annexb_frame,
                                 rtp_ts: video_rtp_ts, // Assign the monotonic RTP timestamp
                                 codec_spec,
+                                track: None,
+                                header_extension: None,
+                                captured_ms: Some(timestamp_ms),
                             };
 
                             sink_trace!(
@@ -135,27 +182,90 @@ impl MediaAgentEventLoop {
                         // --- Egress Audio Path ---
                         MediaTransportEvent::SendEncodedAudioFrame {
                             payload,
-                            timestamp_ms,
                             codec_spec,
+                            samples,
+                            level,
+                            ..
                         } => {
                             sink_debug!(
                                 logger.clone(),
                                 "[MT Event Loop MA] Received SendEncodedAudioFrame."
                             );
-                            if last_received_audio_ts_ms == Some(timestamp_ms) {
+                            // One capture frame may be cut into several packets
+                            // sharing its timestamp, so there is no dedup here
+                            if !sending || dtmf.is_active() {
                                 continue;
                             }
-                            last_received_audio_ts_ms = Some(timestamp_ms);
 
                             let order = PacketizeOrder {
                                 payload,
                                 rtp_ts: audio_rtp_ts,
                                 codec_spec,
+                                track: None,
+                                header_extension: audio_level_ext.map(|id| level.to_extension(id)),
+                                captured_ms: None,
                             };
 
                             if packetizer_order_tx.send(order).is_ok() {
-                                // 160 samples per frame for 20ms @ 8kHz
-                                audio_rtp_ts = audio_rtp_ts.wrapping_add(160);
+                                // e.g. 160 samples per frame for 20ms @ 8kHz
+                                audio_rtp_ts = audio_rtp_ts.wrapping_add(samples);
+                            }
+                        }
+
+                        // --- Extra Video Tracks ---
+                        MediaTransportEvent::SendTrackFrame {
+                            track_id,
+                            annexb_frame,
+                            codec_spec,
+                            ..
+                        } => {
+                            if !sending {
+                                continue;
+                            }
+                            let rtp_ts = track_rtp_ts
+                                .entry(track_id)
+                                .or_insert_with(rand::random::<u32>);
+                            let order = PacketizeOrder {
+                                payload: annexb_frame,
+                                rtp_ts: *rtp_ts,
+                                codec_spec,
+                                track: Some(track_id),
+                                header_extension: None,
+                                captured_ms: None,
+                            };
+                            if packetizer_order_tx.send(order).is_ok() {
+                                *rtp_ts = rtp_ts.wrapping_add(rtp_ts_step);
+                            }
+                        }
+                        MediaTransportEvent::AddTrack { track_id, ssrc } => {
+                            pending_tracks.insert(track_id, ssrc);
+                            let sess_guard = session.lock().expect("session lock poisoned");
+                            if let Some(sess) = sess_guard.as_ref() {
+                                register_pending_tracks(
+                                    sess,
+                                    &mut pending_tracks,
+                                    &payload_map,
+                                    &track_handles,
+                                    &logger,
+                                );
+                            }
+                        }
+                        MediaTransportEvent::RemoveTrack(track_id) => {
+                            pending_tracks.remove(&track_id);
+                            track_rtp_ts.remove(&track_id);
+                            let handle = track_handles
+                                .lock()
+                                .expect("track_handles lock poisoned")
+                                .remove(&track_id);
+                            let sess_guard = session.lock().expect("session lock poisoned");
+                            if let (Some(handle), Some(sess)) = (handle, sess_guard.as_ref()) {
+                                let _ = sess.unregister_outbound_track(handle.local_ssrc);
+                                sink_info!(
+                                    logger,
+                                    "[MT Event Loop MA] Removed track {} (ssrc={})",
+                                    track_id,
+                                    handle.local_ssrc
+                                );
                             }
                         }
 
@@ -184,13 +294,44 @@ impl MediaAgentEventLoop {
                                     let _ = event_tx
                                         .send(EngineEvent::Error(format!("media tracks: {e:?}")));
                                 }
+                                register_pending_tracks(
+                                    sess,
+                                    &mut pending_tracks,
+                                    &payload_map,
+                                    &track_handles,
+                                    &logger,
+                                );
 
-                                // 2. Update allowed Payload Types based on remote SDP negotiation
+                                // 2. Update allowed Payload Types based on remote SDP negotiation,
+                                // mapping the peer's numbers to ours codec by codec
                                 let allowed_pts = allowed_pts.clone();
                                 if let Ok(mut w) = allowed_pts.write() {
                                     w.clear();
-                                    w.extend(sess.remote_codecs.iter().map(|c| c.payload_type));
+                                    w.extend(sess.remote_codecs.iter().map(|remote| {
+                                        let local = payload_map
+                                            .iter()
+                                            .find(|(_, d)| d.rtp_representation.same_codec(remote))
+                                            .map_or(remote.payload_type, |(&pt, _)| pt);
+                                        (remote.payload_type, local)
+                                    }));
                                 }
+
+                                // 3. Send audio in the format the peer asked for
+                                let (spec, format) =
+                                    negotiate_audio(&payload_map, &sess.remote_codecs);
+                                sink_info!(
+                                    logger,
+                                    "[MT Event Loop MA] Sending audio as {:?} {:?}",
+                                    spec,
+                                    format
+                                );
+                                audio_spec = spec;
+                                audio_level_ext =
+                                    sess.remote_codecs.iter().find_map(|c| c.audio_level_ext);
+                                let _ = media_agent_tx.send(MediaAgentEvent::SetAudioSendFormat {
+                                    codec_spec: spec,
+                                    format,
+                                });
                             }
                         }
 
@@ -200,6 +341,64 @@ impl MediaAgentEventLoop {
                                 .lock()
                                 .expect("outbound_tracks lock poisoned");
                             guard.clear();
+                            track_handles
+                                .lock()
+                                .expect("track_handles lock poisoned")
+                                .clear();
+                        }
+
+                        // --- Hold / Resume ---
+                        MediaTransportEvent::SetSending(on) => {
+                            sink_info!(logger, "[MT Event Loop MA] Sending local media: {}", on);
+                            sending = on;
+                        }
+
+                        // --- DTMF ---
+                        MediaTransportEvent::SendDtmf(digits) => {
+                            let negotiated = session
+                                .lock()
+                                .expect("session lock poisoned")
+                                .as_ref()
+                                .is_some_and(|sess| {
+                                    sess.remote_codecs
+                                        .iter()
+                                        .any(|c| c.name.eq_ignore_ascii_case("telephone-event"))
+                                });
+                            if !negotiated {
+                                let _ = event_tx.send(EngineEvent::Status(
+                                    "[MediaTransport] Peer does not accept DTMF (telephone-event)"
+                                        .into(),
+                                ));
+                                continue;
+                            }
+                            // Events share the 8 kHz clock of the G.711 stream
+                            if audio_spec != CodecSpec::G711U {
+                                let _ = event_tx.send(EngineEvent::Status(
+                                    "[MediaTransport] DTMF needs G.711 audio".into(),
+                                ));
+                                continue;
+                            }
+                            sink_info!(logger, "[MT Event Loop MA] Sending DTMF {}", digits);
+                            let _ = dtmf.push(&digits);
+                        }
+
+                        // --- Video Loss Recovery ---
+                        MediaTransportEvent::RequestKeyframe => {
+                            let sess_guard = session.lock().expect("session lock poisoned");
+                            if let Some(sess) = sess_guard.as_ref() {
+                                let (_, inbound) = sess.rtp_stats();
+                                for stream in inbound
+                                    .iter()
+                                    .filter(|s| s.codec.eq_ignore_ascii_case("H264"))
+                                {
+                                    sink_debug!(
+                                        logger,
+                                        "[MT Event Loop MA] Sending PLI for ssrc {}",
+                                        stream.ssrc
+                                    );
+                                    let _ = sess.send_pli(stream.ssrc);
+                                }
+                            }
                         }
 
                         // --- Flow Control ---
@@ -270,6 +469,10 @@ fn ensure_outbound_tracks(
     logger: Arc<dyn LogSink>,
 ) -> Result<()> {
     for (pt, codec) in payload_map.iter() {
+        // Telephone events go out on the audio stream
+        if codec.spec == CodecSpec::TelephoneEvent {
+            continue;
+        }
         let mut guard = outbound_tracks
             .lock()
             .expect("outbound_tracks lock poisoned");
@@ -278,7 +481,8 @@ fn ensure_outbound_tracks(
             continue;
         }
 
-        // Register new track with the underlying RTP session
+        // Register new track with the underlying RTP session, which sends
+        // with the peer's Payload Type for the codec
         let handle = session
             .register_outbound_track(codec.rtp_representation.clone())
             .map_err(|e| MediaTransportError::Send(e.to_string()))?;
@@ -293,3 +497,121 @@ fn ensure_outbound_tracks(
     }
     Ok(())
 }
+
+/// Audio codec and format to send: L16 in the peer's format when both
+/// sides offered it, G.711 otherwise.
+fn negotiate_audio(
+    payload_map: &HashMap<u8, CodecDescriptor>,
+    remote_codecs: &[RtpCodec],
+) -> (CodecSpec, AudioFormat) {
+    let local_l16 = payload_map.values().any(|c| c.spec == CodecSpec::L16);
+    remote_codecs
+        .iter()
+        .find(|c| local_l16 && c.name.eq_ignore_ascii_case("L16"))
+        .map_or((CodecSpec::G711U, AudioFormat::G711), |c| {
+            (CodecSpec::L16, AudioFormat::from_remote(c))
+        })
+}
+
+/// Sends RFC 4733 event packets on the audio stream's SSRC.
+#[allow(clippy::expect_used)]
+fn send_telephone_events(
+    session: &Mutex<Option<Session>>,
+    payload_map: &HashMap<u8, CodecDescriptor>,
+    outbound_tracks: &Mutex<HashMap<u8, OutboundTrackHandle>>,
+    packets: &[EventPacket],
+    logger: &Arc<dyn LogSink>,
+) {
+    let pt_of = |spec| {
+        payload_map
+            .iter()
+            .find(|(_, desc)| desc.spec == spec)
+            .map(|(&pt, _)| pt)
+    };
+    let (Some(audio_pt), Some(event_pt)) =
+        (pt_of(CodecSpec::G711U), pt_of(CodecSpec::TelephoneEvent))
+    else {
+        return;
+    };
+    let Some(ssrc) = outbound_tracks
+        .lock()
+        .expect("outbound_tracks lock poisoned")
+        .get(&audio_pt)
+        .map(|h| h.local_ssrc)
+    else {
+        sink_debug!(
+            logger,
+            "[MT Event Loop MA] No audio track yet, dropping DTMF"
+        );
+        return;
+    };
+
+    let sess_guard = session.lock().expect("session lock poisoned");
+    let Some(sess) = sess_guard.as_ref() else {
+        return;
+    };
+    let event_pt = payload_map.get(&event_pt).map_or(event_pt, |desc| {
+        desc.rtp_representation
+            .negotiated(&sess.remote_codecs)
+            .payload_type
+    });
+    for packet in packets {
+        if let Err(e) = sess.send_rtp_payload_as(
+            ssrc,
+            event_pt,
+            &packet.event.encode(),
+            packet.rtp_ts,
+            packet.marker,
+        ) {
+            sink_error!(logger, "[MT Event Loop MA] DTMF send failed: {}", e);
+            return;
+        }
+    }
+}
+
+/// Registers the send streams of extra tracks that are still pending.
+///
+/// Tracks whose registration fails (e.g. the RTP session is not running yet)
+/// stay pending and are retried on `Established`.
+#[allow(clippy::expect_used)]
+fn register_pending_tracks(
+    session: &Session,
+    pending: &mut HashMap<TrackId, u32>,
+    payload_map: &HashMap<u8, CodecDescriptor>,
+    track_handles: &Mutex<HashMap<TrackId, OutboundTrackHandle>>,
+    logger: &Arc<dyn LogSink>,
+) {
+    let Some(codec) = payload_map
+        .values()
+        .find(|c| c.spec == CodecSpec::H264)
+        .map(|c| c.rtp_representation.clone())
+    else {
+        return;
+    };
+    pending.retain(|&track_id, &mut ssrc| {
+        match session.register_outbound_track_with_ssrc(codec.clone(), ssrc) {
+            Ok(handle) => {
+                sink_info!(
+                    logger,
+                    "[MT Event Loop MA] Added track {} (ssrc={})",
+                    track_id,
+                    ssrc
+                );
+                track_handles
+                    .lock()
+                    .expect("track_handles lock poisoned")
+                    .insert(track_id, handle);
+                false
+            }
+            Err(e) => {
+                sink_debug!(
+                    logger,
+                    "[MT Event Loop MA] Track {} not registered yet: {}",
+                    track_id,
+                    e
+                );
+                true
+            }
+        }
+    });
+}
@@ -10,8 +10,13 @@ use std::{
 };
 
 use crate::{
-    core::{events::EngineEvent, session::Session},
+    core::{
+        events::EngineEvent,
+        latency::{LatencyStage, LatencyTracer},
+        session::Session,
+    },
     log::log_sink::LogSink,
+    media_agent::video_track::TrackId,
     media_transport::{
         codec::CodecDescriptor, event_loops::constants::RECV_TIMEOUT, events::PacketizerEvent,
     },
@@ -34,11 +39,13 @@ pub struct PacketizerEventLoop {
     running_flag: Arc<AtomicBool>,
     stop_flag: Arc<AtomicBool>,
     event_loop_handler: Option<JoinHandle<()>>,
+    /// Where the packetize and send stages of each video frame are recorded.
+    latency: LatencyTracer,
 }
 
 impl PacketizerEventLoop {
     /// Creates a new, stopped instance of the loop.
-    pub fn new(logger: Arc<dyn LogSink>) -> Self {
+    pub fn new(logger: Arc<dyn LogSink>, latency: LatencyTracer) -> Self {
         let stop_flag = Arc::new(AtomicBool::new(false));
         let running_flag = Arc::new(AtomicBool::new(false));
         Self {
@@ -46,6 +53,7 @@ impl PacketizerEventLoop {
             running_flag,
             stop_flag,
             event_loop_handler: None,
+            latency,
         }
     }
 
@@ -55,6 +63,7 @@ impl PacketizerEventLoop {
     ///
     /// * `packetizer_event_rx`: Input channel receiving `PacketizedFrame`s.
     /// * `outbound_tracks`: Map of active RTP tracks (SSRCs) indexed by Payload Type.
+    /// * `track_handles`: Send streams of the extra video tracks, by track id.
     /// * `payload_map`: Configuration map to resolve CodecSpec to Payload Type.
     /// * `session`: The network session used for sending data.
     /// * `event_tx`: Channel to report critical errors to the engine.
@@ -63,6 +72,7 @@ impl PacketizerEventLoop {
         &mut self,
         packetizer_event_rx: Receiver<PacketizerEvent>,
         outbound_tracks: Arc<Mutex<HashMap<u8, OutboundTrackHandle>>>,
+        track_handles: Arc<Mutex<HashMap<TrackId, OutboundTrackHandle>>>,
         payload_map: Arc<HashMap<u8, CodecDescriptor>>,
         session: Arc<Mutex<Option<Session>>>,
         event_tx: Sender<EngineEvent>,
@@ -71,6 +81,7 @@ impl PacketizerEventLoop {
         let running_flag = self.running_flag.clone();
 
         let logger = self.logger.clone();
+        let latency = self.latency.clone();
         let handle = std::thread::spawn(move || {
             while !stop_flag.load(Ordering::SeqCst) {
                 const TIMEOUT: Duration = Duration::from_millis(RECV_TIMEOUT);
@@ -83,6 +94,40 @@ impl PacketizerEventLoop {
                                 logger,
                                 "[Packetizer Event Loop (MT)] Received FramePacketized from Packetizer"
                             );
+                            if let Some(captured_ms) = frame.captured_ms {
+                                latency.record_since(LatencyStage::Packetize, captured_ms);
+                            }
+
+                            // Frames of extra tracks go out on the track's own SSRC.
+                            if let Some(track_id) = frame.track {
+                                let ssrc = track_handles
+                                    .lock()
+                                    .expect("track_handles lock poisoned")
+                                    .get(&track_id)
+                                    .map(|h| h.local_ssrc);
+                                let Some(ssrc) = ssrc else {
+                                    sink_debug!(
+                                        logger,
+                                        "[Packetizer Event Loop (MT)] Track {} not registered, dropping frame",
+                                        track_id
+                                    );
+                                    continue;
+                                };
+                                let mut sess_guard = session.lock().expect("session lock poisoned");
+                                if let Some(sess) = sess_guard.as_mut()
+                                    && let Err(e) = sess.send_rtp_chunks_for_frame(
+                                        ssrc,
+                                        &frame.chunks,
+                                        frame.rtp_ts,
+                                        frame.header_extension.as_ref(),
+                                    )
+                                {
+                                    let _ = event_tx.send(EngineEvent::Error(format!(
+                                        "[Packetizer Event Loop (MT)] send track frame failed: {e:?}"
+                                    )));
+                                }
+                                continue;
+                            }
 
                             // 1. Lock track registry to ensure thread safety
                             let guard = outbound_tracks
@@ -127,16 +172,24 @@ impl PacketizerEventLoop {
                             );
 
                             // Actual network IO happens here
-                            if let Some(sess) = sess_guard.as_mut()
-                                && let Err(e) = sess.send_rtp_chunks_for_frame(
+                            if let Some(sess) = sess_guard.as_mut() {
+                                match sess.send_rtp_chunks_for_frame(
                                     handle.local_ssrc,
                                     &frame.chunks,
                                     frame.rtp_ts,
-                                )
-                            {
-                                let _ = event_tx.send(EngineEvent::Error(format!(
-                                    "[Packetizer Event Loop (MT)] send local frame failed: {e:?}"
-                                )));
+                                    frame.header_extension.as_ref(),
+                                ) {
+                                    Ok(()) => {
+                                        if let Some(captured_ms) = frame.captured_ms {
+                                            latency.record_since(LatencyStage::Send, captured_ms);
+                                        }
+                                    }
+                                    Err(e) => {
+                                        let _ = event_tx.send(EngineEvent::Error(format!(
+                                            "[Packetizer Event Loop (MT)] send local frame failed: {e:?}"
+                                        )));
+                                    }
+                                }
                             }
                         }
                     },
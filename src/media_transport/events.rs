@@ -7,10 +7,14 @@ pub enum DepacketizerEvent {
     AnnexBFrameReady {
         codec_spec: CodecSpec,
         bytes: Vec<u8>,
+        /// Arrival of the frame's first packet (UNIX milliseconds).
+        received_ms: u128,
     },
     EncodedAudioFrameReady {
         codec_spec: CodecSpec,
         payload: Vec<u8>,
+        /// Stream it arrived on.
+        ssrc: u32,
     },
 }
 
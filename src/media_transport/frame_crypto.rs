@@ -0,0 +1,274 @@
+//! End-to-end encryption of encoded frames, before SRTP.
+//!
+//! SRTP only protects media between the two ends of a DTLS handshake; an
+//! SFU that terminates DTLS sees every frame. With `[Media] e2ee_key` set,
+//! each encoded frame is also encrypted with a key the participants share
+//! out of band, so whatever relays the packets can route them but not read
+//! them.
+//!
+//! Each H.264 NAL unit keeps its one-byte header in the clear, for the
+//! packetizer and any relay, and has its body replaced by
+//!
+//! ```text
+//! counter (u64 BE) | AES-128-CTR ciphertext | HMAC-SHA1-80 tag | 0x80
+//! ```
+//!
+//! with emulation prevention applied, so the result is still a valid
+//! Annex B stream. Audio payloads are encrypted whole, without escaping.
+//! The tag covers the clear header and the counter; a frame that fails it
+//! is dropped.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use aes::{
+    Aes128,
+    cipher::{KeyIvInit, StreamCipher},
+};
+use ctr::Ctr128BE;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    config::Config, media_agent::spec::CodecSpec,
+    media_transport::payload::h264_packetizer::split_annexb_nalus,
+};
+
+type Aes128Ctr = Ctr128BE<Aes128>;
+type HmacSha1 = Hmac<Sha1>;
+
+const COUNTER_LEN: usize = 8;
+const TAG_LEN: usize = 10;
+/// Ends every encrypted NAL body, so it never ends in a zero byte that an
+/// Annex B split would take for part of the next start code.
+const TRAILER: u8 = 0x80;
+
+/// Encrypts outgoing and decrypts incoming frames with one shared key.
+#[derive(Debug)]
+pub struct FrameCryptor {
+    enc_key: [u8; 16],
+    auth_key: [u8; 20],
+    salt: [u8; 16],
+    /// Next counter to use. Starts at a random value, so peers sharing the
+    /// key do not reuse each other's keystream.
+    counter: AtomicU64,
+}
+
+impl FrameCryptor {
+    /// A cryptor whose keys are derived from `secret`.
+    #[must_use]
+    pub fn from_secret(secret: &str) -> Self {
+        let derive = |label: &[u8]| -> [u8; 32] {
+            let mut hasher = Sha256::new();
+            hasher.update(b"rustyrtc e2ee ");
+            hasher.update(label);
+            hasher.update(secret.as_bytes());
+            hasher.finalize().into()
+        };
+        let (enc, auth, salt) = (derive(b"enc"), derive(b"auth"), derive(b"salt"));
+        let mut cryptor = Self {
+            enc_key: [0; 16],
+            auth_key: [0; 20],
+            salt: [0; 16],
+            counter: AtomicU64::new(rand::random::<u64>() >> 1),
+        };
+        cryptor.enc_key.copy_from_slice(&enc[..16]);
+        cryptor.auth_key.copy_from_slice(&auth[..20]);
+        cryptor.salt.copy_from_slice(&salt[..16]);
+        cryptor
+    }
+
+    /// The cryptor `[Media] e2ee_key` asks for, if any.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config
+            .get_non_empty("Media", "e2ee_key")
+            .map(Self::from_secret)
+    }
+
+    /// Encrypts one encoded frame of `codec`.
+    #[must_use]
+    pub fn encrypt_frame(&self, codec: CodecSpec, frame: &[u8]) -> Vec<u8> {
+        match codec {
+            CodecSpec::H264 => {
+                let mut out = Vec::with_capacity(frame.len() + 64);
+                for nalu in split_annexb_nalus(frame) {
+                    let (header, body) = nalu.split_at(1);
+                    let mut sealed = self.seal(header, body);
+                    sealed.push(TRAILER);
+                    out.extend_from_slice(&[0, 0, 0, 1]);
+                    out.extend_from_slice(header);
+                    escape_into(&sealed, &mut out);
+                }
+                out
+            }
+            CodecSpec::G711U | CodecSpec::L16 => self.seal(&[], frame),
+            CodecSpec::TelephoneEvent => frame.to_vec(),
+        }
+    }
+
+    /// Decrypts one frame of `codec`; `None` if it was not encrypted with
+    /// this key or was altered.
+    #[must_use]
+    pub fn decrypt_frame(&self, codec: CodecSpec, frame: &[u8]) -> Option<Vec<u8>> {
+        match codec {
+            CodecSpec::H264 => {
+                let mut out = Vec::with_capacity(frame.len());
+                for nalu in split_annexb_nalus(frame) {
+                    let (header, body) = nalu.split_at(1);
+                    let mut sealed = unescape(body);
+                    if sealed.pop() != Some(TRAILER) {
+                        return None;
+                    }
+                    out.extend_from_slice(&[0, 0, 0, 1]);
+                    out.extend_from_slice(header);
+                    out.extend_from_slice(&self.open(header, &sealed)?);
+                }
+                Some(out)
+            }
+            CodecSpec::G711U | CodecSpec::L16 => self.open(&[], frame),
+            CodecSpec::TelephoneEvent => Some(frame.to_vec()),
+        }
+    }
+
+    /// `counter | ciphertext | tag` of `body`, authenticating `header` too.
+    fn seal(&self, header: &[u8], body: &[u8]) -> Vec<u8> {
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        let mut out = Vec::with_capacity(COUNTER_LEN + body.len() + TAG_LEN + 1);
+        out.extend_from_slice(&counter.to_be_bytes());
+        out.extend_from_slice(body);
+        self.keystream(counter, &mut out[COUNTER_LEN..]);
+        let tag = self.tag(header, &out);
+        out.extend_from_slice(&tag[..TAG_LEN]);
+        out
+    }
+
+    /// The body `seal` produced `sealed` from, if the tag matches.
+    fn open(&self, header: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < COUNTER_LEN + TAG_LEN {
+            return None;
+        }
+        let (data, tag) = sealed.split_at(sealed.len() - TAG_LEN);
+        let mut mac = self.mac();
+        mac.update(header);
+        mac.update(data);
+        mac.verify_truncated_left(tag).ok()?;
+
+        let mut counter = [0u8; COUNTER_LEN];
+        counter.copy_from_slice(&data[..COUNTER_LEN]);
+        let mut body = data[COUNTER_LEN..].to_vec();
+        self.keystream(u64::from_be_bytes(counter), &mut body);
+        Some(body)
+    }
+
+    fn keystream(&self, counter: u64, data: &mut [u8]) {
+        let mut iv = self.salt;
+        for (b, c) in iv[4..12].iter_mut().zip(counter.to_be_bytes()) {
+            *b ^= c;
+        }
+        Aes128Ctr::new(&self.enc_key.into(), &iv.into()).apply_keystream(data);
+    }
+
+    fn tag(&self, header: &[u8], data: &[u8]) -> [u8; 20] {
+        let mut mac = self.mac();
+        mac.update(header);
+        mac.update(data);
+        mac.finalize().into_bytes().into()
+    }
+
+    #[allow(clippy::expect_used)]
+    fn mac(&self) -> HmacSha1 {
+        HmacSha1::new_from_slice(&self.auth_key).expect("HMAC takes keys of any length")
+    }
+}
+
+/// Appends `data` to `out` with an emulation prevention byte after every
+/// two zeros followed by a byte up to 3, so no start code appears.
+fn escape_into(data: &[u8], out: &mut Vec<u8>) {
+    let mut zeros = 0;
+    for &b in data {
+        if zeros >= 2 && b <= 3 {
+            out.push(3);
+            zeros = 0;
+        }
+        out.push(b);
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+    }
+}
+
+/// Undoes [`escape_into`].
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &b in data {
+        if zeros >= 2 && b == 3 {
+            zeros = 0;
+            continue;
+        }
+        out.push(b);
+        zeros = if b == 0 { zeros + 1 } else { 0 };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn annexb(nalus: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for n in nalus {
+            out.extend_from_slice(&[0, 0, 0, 1]);
+            out.extend_from_slice(n);
+        }
+        out
+    }
+
+    #[test]
+    fn h264_frames_stay_annex_b_and_round_trip() {
+        let sender = FrameCryptor::from_secret("shared secret");
+        let receiver = FrameCryptor::from_secret("shared secret");
+        let sps: &[u8] = &[0x67, 0x42, 0x00, 0x1f];
+        let idr: Vec<u8> = std::iter::once(0x65)
+            .chain((0..600).map(|i| if i % 7 == 0 { 0 } else { i as u8 }))
+            .collect();
+        let frame = annexb(&[sps, &idr]);
+
+        let sealed = sender.encrypt_frame(CodecSpec::H264, &frame);
+        let nalus = split_annexb_nalus(&sealed);
+        assert_eq!(nalus.len(), 2);
+        assert_eq!((nalus[0][0], nalus[1][0]), (0x67, 0x65));
+        assert!(!sealed.windows(idr.len()).any(|w| w == idr.as_slice()));
+        assert_eq!(
+            receiver.decrypt_frame(CodecSpec::H264, &sealed),
+            Some(frame)
+        );
+
+        let stranger = FrameCryptor::from_secret("another secret");
+        assert_eq!(stranger.decrypt_frame(CodecSpec::H264, &sealed), None);
+    }
+
+    #[test]
+    fn audio_payloads_round_trip_and_reject_tampering() {
+        let cryptor = FrameCryptor::from_secret("k");
+        let payload = vec![0xffu8; 160];
+        let mut sealed = cryptor.encrypt_frame(CodecSpec::G711U, &payload);
+        assert_eq!(sealed.len(), payload.len() + COUNTER_LEN + TAG_LEN);
+        assert_eq!(
+            cryptor.decrypt_frame(CodecSpec::G711U, &sealed),
+            Some(payload)
+        );
+        sealed[20] ^= 1;
+        assert_eq!(cryptor.decrypt_frame(CodecSpec::G711U, &sealed), None);
+    }
+
+    #[test]
+    fn escaping_hides_start_codes_and_reverses() {
+        let data = [0, 0, 1, 0, 0, 0, 0, 0, 3, 7, 0, 0];
+        let mut escaped = Vec::new();
+        escape_into(&data, &mut escaped);
+        assert!(!escaped.windows(3).any(|w| w == [0, 0, 1] || w == [0, 0, 0]));
+        assert_eq!(unescape(&escaped), data);
+    }
+}
@@ -1,27 +1,49 @@
 use crate::{
+    camera_manager::capture_settings::CaptureSettings,
     config::Config,
-    core::{events::EngineEvent, session::Session},
+    core::{
+        buffer_pool::BufferPool,
+        events::EngineEvent,
+        latency::LatencyStats,
+        path_mtu::MtuConfig,
+        session::Session,
+        thread_join::{JOIN_TIMEOUT, join_timeout},
+    },
     log::log_sink::LogSink,
-    media_agent::{MediaAgent, constants::TARGET_FPS, spec::CodecSpec, video_frame::VideoFrame},
+    media_agent::{
+        MediaAgent,
+        constants::TARGET_FPS,
+        media_agent_error::MediaAgentError,
+        recorder::{RecordStreams, RecordingStatus},
+        spec::{CodecSpec, MediaType},
+        video_frame::VideoFrame,
+        video_track::{TrackId, VideoSource},
+    },
     media_transport::{
         codec::CodecDescriptor,
         constants::{DYNAMIC_PAYLOAD_TYPE_START, RTP_TX_CHANNEL_SIZE},
         depacketizer_worker::spawn_depacketizer_worker,
+        error::MediaTransportError,
         event_loops::{
             depacketizer_event_loop::DepacketizerEventLoop,
             media_agent_event_loop::MediaAgentEventLoop,
             packetizer_event_loop::PacketizerEventLoop,
         },
+        frame_crypto::FrameCryptor,
         media_transport_event::{MediaTransportEvent, RtpIn},
         packetizer_worker::spawn_packetizer_worker,
+        payload::telephone_event::parse_dtmf,
     },
     rtp_session::{outbound_track_handle::OutboundTrackHandle, rtp_codec::RtpCodec},
-    sink_error, sink_info,
+    signaling::protocol::capabilities::Capabilities,
+    sink_error, sink_info, sink_warn,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
+    path::PathBuf,
     sync::{
         Arc, Mutex, RwLock,
+        atomic::{AtomicUsize, Ordering},
         mpsc::{self, Receiver, Sender, SyncSender},
     },
     thread::JoinHandle,
@@ -60,8 +82,18 @@ pub struct MediaTransport {
     payload_map: Arc<HashMap<u8, CodecDescriptor>>,
     /// Tracks state for outbound RTP streams (SSRCs, sequence numbers).
     outbound_tracks: Arc<Mutex<HashMap<u8, OutboundTrackHandle>>>,
+    /// Send streams of the extra video tracks added at runtime.
+    track_handles: Arc<Mutex<HashMap<TrackId, OutboundTrackHandle>>>,
+    /// Remote video SSRC shown locally (`None`: the main stream).
+    preferred_remote_video: Arc<RwLock<Option<u32>>>,
     /// Filter set for incoming RTP packets (only allow negotiated PTs).
-    allowed_pts: Option<Arc<RwLock<HashSet<u8>>>>,
+    allowed_pts: Option<Arc<RwLock<HashMap<u8, u8>>>>,
+    /// Buffers of received packets, shared with the session that fills them.
+    rtp_buffers: BufferPool,
+    /// End-to-end frame encryption, when `[Media] e2ee_key` is set.
+    frame_cryptor: Option<Arc<FrameCryptor>>,
+    /// Largest datagram sent: `[ICE] mtu`, then the discovered path MTU.
+    mtu: Arc<AtomicUsize>,
 
     // --- Internal Channels ---
     media_transport_event_tx: Option<Sender<MediaTransportEvent>>,
@@ -85,9 +117,11 @@ impl MediaTransport {
             .and_then(|s| s.parse().ok())
             .unwrap_or(TARGET_FPS);
 
-        let media_agent_event_loop = MediaAgentEventLoop::new(target_fps, logger.clone());
+        let latency = media_agent.latency().clone();
+        let media_agent_event_loop =
+            MediaAgentEventLoop::new(target_fps, logger.clone(), latency.clone());
         let depacketizer_event_loop = DepacketizerEventLoop::new(logger.clone());
-        let packetizer_event_loop = PacketizerEventLoop::new(logger.clone());
+        let packetizer_event_loop = PacketizerEventLoop::new(logger.clone(), latency);
 
         let (mt_event_tx, mt_event_rx) = mpsc::channel();
         let media_transport_event_tx = Some(mt_event_tx);
@@ -101,6 +135,10 @@ impl MediaTransport {
             let codec_descriptor = match spec.codec_spec {
                 CodecSpec::H264 => CodecDescriptor::h264_dynamic(current_pt),
                 CodecSpec::G711U => CodecDescriptor::pcmu_dynamic(DEFAULT_AUDIO_PT),
+                CodecSpec::L16 => {
+                    CodecDescriptor::l16_dynamic(current_pt, media_agent.audio_format())
+                }
+                CodecSpec::TelephoneEvent => CodecDescriptor::telephone_event_dynamic(current_pt),
             };
             let pt = codec_descriptor.rtp_representation.payload_type;
             payload_map_inner.insert(pt, codec_descriptor);
@@ -110,6 +148,13 @@ impl MediaTransport {
             }
         }
         let payload_map = Arc::new(payload_map_inner);
+        let frame_cryptor = FrameCryptor::from_config(&config).map(Arc::new);
+        if frame_cryptor.is_some() {
+            sink_info!(
+                logger,
+                "[MediaTransport] end-to-end frame encryption enabled"
+            );
+        }
 
         Self {
             logger,
@@ -123,7 +168,12 @@ impl MediaTransport {
             packetizer_handle: None,
             payload_map,
             outbound_tracks: Arc::new(Mutex::new(HashMap::new())),
+            track_handles: Arc::new(Mutex::new(HashMap::new())),
+            preferred_remote_video: Arc::new(RwLock::new(None)),
             allowed_pts: None,
+            rtp_buffers: BufferPool::default(),
+            frame_cryptor,
+            mtu: Arc::new(AtomicUsize::new(MtuConfig::from_config(&config).mtu)),
             media_transport_event_tx,
             media_transport_event_rx,
         }
@@ -172,7 +222,10 @@ impl MediaTransport {
         self.rtp_tx = Some(rtp_tx_clone);
 
         let allowed_pts = Arc::new(RwLock::new(
-            payload_map.keys().copied().collect::<HashSet<u8>>(),
+            payload_map
+                .keys()
+                .map(|&pt| (pt, pt))
+                .collect::<HashMap<u8, u8>>(),
         ));
         let allowed_pts_clone = allowed_pts.clone();
         self.allowed_pts = Some(allowed_pts_clone);
@@ -187,6 +240,10 @@ impl MediaTransport {
             rtp_rx,
             depacketizer_event_tx,
             payload_map_for_worker.clone(),
+            self.preferred_remote_video.clone(),
+            self.media_agent.latency().clone(),
+            self.rtp_buffers.clone(),
+            self.frame_cryptor.clone(),
         ));
 
         // Connect Depacketizer output -> MediaAgent input
@@ -217,6 +274,7 @@ impl MediaTransport {
                 session.clone(),
                 payload_map_for_worker.clone(),
                 self.outbound_tracks.clone(),
+                self.track_handles.clone(),
                 self.event_tx.clone(),
                 allowed_pts.clone(),
                 media_agent_event_tx,
@@ -227,17 +285,33 @@ impl MediaTransport {
         self.packetizer_handle = Some(spawn_packetizer_worker(
             packetizer_order_rx,
             packetizer_event_tx,
+            self.frame_cryptor.clone(),
+            self.mtu.clone(),
             logger.clone(),
         ));
         self.packetizer_event_loop.start(
             packetizer_event_rx,
             self.outbound_tracks.clone(),
+            self.track_handles.clone(),
             payload_map_for_worker.clone(),
             session,
             self.event_tx.clone(),
         );
     }
 
+    /// The pool received packets are read into; hand it to the `Session` so
+    /// the depacketizer can give the buffers back.
+    #[must_use]
+    pub fn rtp_buffers(&self) -> BufferPool {
+        self.rtp_buffers.clone()
+    }
+
+    /// Per-stage latency of the video frames sent and received.
+    #[must_use]
+    pub fn latency_stats(&self) -> Vec<LatencyStats> {
+        self.media_agent.latency().stats()
+    }
+
     /// Passthrough to get the latest video snapshots from the `MediaAgent`.
     #[must_use]
     pub fn snapshot_frames(&self) -> (Option<VideoFrame>, Option<VideoFrame>) {
@@ -245,9 +319,32 @@ impl MediaTransport {
     }
 
     /// Returns the list of supported codecs as descriptors for SDP generation.
+    ///
+    /// Telephone events come last, so they are never the preferred audio format.
     #[must_use]
     pub fn codec_descriptors(&self) -> Vec<CodecDescriptor> {
-        self.payload_map.values().cloned().collect()
+        let mut descriptors: Vec<CodecDescriptor> = self.payload_map.values().cloned().collect();
+        descriptors.sort_by_key(|c| c.spec == CodecSpec::TelephoneEvent);
+        descriptors
+    }
+
+    /// The codecs and largest capture size this side supports, for a
+    /// `CapabilityReply`.
+    #[must_use]
+    pub fn capabilities(&self) -> Capabilities {
+        let mut caps = Capabilities::default();
+        for codec in self.codec_descriptors() {
+            let list = match codec.spec.media_type() {
+                MediaType::Video => &mut caps.video_codecs,
+                MediaType::Audio => &mut caps.audio_codecs,
+            };
+            list.push(codec.codec_name.to_owned());
+        }
+        let settings = self.capture_settings();
+        let clamp = |v: Option<u32>| v.map_or(0, |v| u16::try_from(v).unwrap_or(u16::MAX));
+        caps.max_width = clamp(settings.width);
+        caps.max_height = clamp(settings.height);
+        caps
     }
 
     /// Returns the RTP specific codec configurations (PT, ClockRate, Name).
@@ -263,10 +360,199 @@ impl MediaTransport {
         self.media_transport_event_tx.clone()
     }
 
+    /// Queues DTMF digits (`0-9`, `*`, `#`, `A-D`, `,` for a pause), sent
+    /// as RFC 4733 telephone events on the audio stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaTransportError::InvalidDtmf` for a character that is not
+    /// a DTMF digit, or `MediaTransportError::Send` if the pipeline is gone.
+    pub fn send_dtmf(&self, digits: &str) -> Result<(), MediaTransportError> {
+        parse_dtmf(digits).map_err(MediaTransportError::InvalidDtmf)?;
+        self.media_transport_event_tx
+            .as_ref()
+            .ok_or_else(|| MediaTransportError::Send("media transport stopped".into()))?
+            .send(MediaTransportEvent::SendDtmf(digits.to_owned()))
+            .map_err(|e| MediaTransportError::Send(e.to_string()))
+    }
+
     pub fn set_audio_mute(&self, mute: bool) {
         self.media_agent.set_audio_mute(mute);
     }
 
+    /// Enables push-to-talk. See [`MediaAgent::set_push_to_talk`].
+    pub fn set_push_to_talk(&self, enabled: bool) {
+        self.media_agent.set_push_to_talk(enabled);
+    }
+
+    /// Reports the push-to-talk key state.
+    pub fn set_push_to_talk_pressed(&self, pressed: bool) {
+        self.media_agent.set_push_to_talk_pressed(pressed);
+    }
+
+    #[must_use]
+    pub fn push_to_talk(&self) -> bool {
+        self.media_agent.push_to_talk()
+    }
+
+    /// Stops or resumes the camera. See [`MediaAgent::set_video_muted`].
+    pub fn set_video_muted(&mut self, muted: bool) {
+        self.media_agent.set_video_muted(muted);
+    }
+
+    #[must_use]
+    pub fn video_muted(&self) -> bool {
+        self.media_agent.video_muted()
+    }
+
+    /// Sizes the video packets for datagrams of at most `mtu` bytes, from
+    /// the next frame on.
+    pub fn set_mtu(&self, mtu: usize) {
+        self.mtu.store(mtu, Ordering::SeqCst);
+    }
+
+    /// See [`MediaAgent::request_keyframe`].
+    pub fn request_keyframe(&self) {
+        self.media_agent.request_keyframe();
+    }
+
+    /// Switches the local capture device. See [`MediaAgent::switch_camera`].
+    pub fn switch_camera(&mut self, camera_id: i32) -> Option<String> {
+        self.media_agent.switch_camera(camera_id)
+    }
+
+    /// Changes the capture format. See [`MediaAgent::set_capture_settings`].
+    pub fn set_capture_settings(&mut self, settings: CaptureSettings) -> Option<String> {
+        self.media_agent.set_capture_settings(settings)
+    }
+
+    #[must_use]
+    pub const fn capture_settings(&self) -> CaptureSettings {
+        self.media_agent.capture_settings()
+    }
+
+    /// Selects the built-in test sources. See [`MediaAgent::set_test_sources`].
+    pub fn set_test_sources(&mut self, video: bool, audio: bool) {
+        self.media_agent.set_test_sources(video, audio);
+    }
+
+    /// `(video, audio)`: whether the test sources are selected.
+    #[must_use]
+    pub const fn test_sources(&self) -> (bool, bool) {
+        self.media_agent.test_sources()
+    }
+
+    /// Selects the microphone. See [`MediaAgent::set_audio_input`].
+    pub fn set_audio_input(&mut self, device: Option<String>) {
+        self.media_agent.set_audio_input(device);
+    }
+
+    /// Selects the speaker. See [`MediaAgent::set_audio_output`].
+    pub fn set_audio_output(&mut self, device: Option<String>) {
+        self.media_agent.set_audio_output(device);
+    }
+
+    #[must_use]
+    pub fn audio_devices(&self) -> (Option<String>, Option<String>) {
+        self.media_agent.audio_devices()
+    }
+
+    /// Remote playout volume. See [`MediaAgent::set_remote_volume`].
+    pub fn set_remote_volume(&mut self, volume: f32) {
+        self.media_agent.set_remote_volume(volume);
+    }
+
+    #[must_use]
+    pub const fn remote_volume(&self) -> f32 {
+        self.media_agent.remote_volume()
+    }
+
+    /// Local mute of the remote audio. See
+    /// [`MediaAgent::set_remote_audio_muted`].
+    pub fn set_remote_audio_muted(&mut self, muted: bool) {
+        self.media_agent.set_remote_audio_muted(muted);
+    }
+
+    #[must_use]
+    pub const fn remote_audio_muted(&self) -> bool {
+        self.media_agent.remote_audio_muted()
+    }
+
+    /// Starts recording the call. See [`MediaAgent::start_recording`].
+    ///
+    /// # Errors
+    ///
+    /// Propagates the `MediaAgentError` from the media agent.
+    pub fn start_recording(
+        &mut self,
+        path: Option<PathBuf>,
+        streams: RecordStreams,
+    ) -> Result<PathBuf, MediaAgentError> {
+        self.media_agent.start_recording(path, streams)
+    }
+
+    /// Stops recording. See [`MediaAgent::stop_recording`].
+    ///
+    /// # Errors
+    ///
+    /// Propagates the `MediaAgentError` from the media agent.
+    pub fn stop_recording(&mut self) -> Result<Option<PathBuf>, MediaAgentError> {
+        self.media_agent.stop_recording()
+    }
+
+    #[must_use]
+    pub fn recording(&self) -> Option<RecordingStatus> {
+        self.media_agent.recording()
+    }
+
+    /// Starts or stops sending local media without touching the session.
+    ///
+    /// Frames produced while sending is off are dropped before packetization.
+    pub fn set_sending(&self, sending: bool) {
+        if let Some(tx) = &self.media_transport_event_tx {
+            let _ = tx.send(MediaTransportEvent::SetSending(sending));
+        }
+    }
+
+    /// Starts an extra outbound video track sent on `ssrc`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MediaAgentError` if the transport is not started or the
+    /// track pipeline cannot be spawned.
+    pub fn add_video_track(
+        &mut self,
+        id: TrackId,
+        ssrc: u32,
+        source: VideoSource,
+    ) -> Result<(), MediaAgentError> {
+        let Some(tx) = self.media_transport_event_tx.clone() else {
+            return Err(MediaAgentError::Send(
+                "media transport not started".to_string(),
+            ));
+        };
+        self.media_agent.add_video_track(id, source)?;
+        tx.send(MediaTransportEvent::AddTrack { track_id: id, ssrc })
+            .map_err(|e| MediaAgentError::Send(e.to_string()))
+    }
+
+    /// Stops an extra video track. Returns `false` if it did not exist.
+    pub fn remove_video_track(&mut self, id: TrackId) -> bool {
+        let existed = self.media_agent.remove_video_track(id);
+        if let Some(tx) = &self.media_transport_event_tx {
+            let _ = tx.send(MediaTransportEvent::RemoveTrack(id));
+        }
+        existed
+    }
+
+    /// Selects which remote video SSRC is decoded (`None`: any, i.e. the
+    /// main stream).
+    pub fn set_preferred_remote_video(&self, ssrc: Option<u32>) {
+        if let Ok(mut guard) = self.preferred_remote_video.write() {
+            *guard = ssrc;
+        }
+    }
+
     /// Stops all threads and cleans up resources.
     ///
     /// This stops the `MediaAgent` first, then the transport event loops,
@@ -283,11 +569,18 @@ impl MediaTransport {
 
         self.rtp_tx = None;
 
-        if let Some(handle) = self.depacketizer_handle.take() {
-            let _ = handle.join();
-        }
-        if let Some(handle) = self.packetizer_handle.take() {
-            let _ = handle.join();
+        for (name, handle) in [
+            ("depacketizer", self.depacketizer_handle.take()),
+            ("packetizer", self.packetizer_handle.take()),
+        ] {
+            if let Some(handle) = handle
+                && !join_timeout(handle, JOIN_TIMEOUT)
+            {
+                sink_warn!(
+                    self.logger,
+                    "[MediaTransport] {name} thread did not stop within {JOIN_TIMEOUT:?}, leaving it"
+                );
+            }
         }
 
         self.allowed_pts = None;
@@ -1,4 +1,7 @@
-use crate::media_agent::spec::CodecSpec;
+use crate::{
+    media_agent::{spec::CodecSpec, video_track::TrackId},
+    rtp::audio_level::AudioLevel,
+};
 
 #[derive(Debug, Clone)]
 pub struct RtpIn {
@@ -21,8 +24,32 @@ pub enum MediaTransportEvent {
         payload: Vec<u8>,
         timestamp_ms: u128,
         codec_spec: CodecSpec,
+        /// Samples per channel, i.e. how far the RTP clock advances.
+        samples: u32,
+        /// Level of the audio, sent as a header extension when negotiated.
+        level: AudioLevel,
     },
+    /// Encoded frame of an extra video track.
+    SendTrackFrame {
+        track_id: TrackId,
+        annexb_frame: Vec<u8>,
+        timestamp_ms: u128,
+        codec_spec: CodecSpec,
+    },
+    /// Registers the send stream of an extra track on the RTP session.
+    AddTrack {
+        track_id: TrackId,
+        ssrc: u32,
+    },
+    /// Unregisters the send stream of an extra track.
+    RemoveTrack(TrackId),
     UpdateBitrate(u32),
+    /// Sends a PLI for the remote video, whose decoding broke off.
+    RequestKeyframe,
+    /// Enables/disables sending local media (hold keeps the session up).
+    SetSending(bool),
+    /// Queues DTMF digits (already validated) as telephone events.
+    SendDtmf(String),
     Established,
     Closed,
     RtpIn(RtpIn),
@@ -5,6 +5,7 @@ pub mod depacketizer_worker;
 pub mod error;
 pub mod event_loops;
 pub mod events;
+pub mod frame_crypto;
 pub mod media_transport_c;
 pub mod media_transport_event;
 pub mod packetizer_worker;
@@ -1,16 +1,25 @@
 use std::{
     sync::{
         Arc,
+        atomic::{AtomicUsize, Ordering},
         mpsc::{Receiver, Sender},
     },
     thread::{self, JoinHandle},
 };
 
-use super::events::PacketizerEvent;
+use bytes::Bytes;
+
+use super::{events::PacketizerEvent, frame_crypto::FrameCryptor};
 use crate::media_transport::payload::{
     h264_packetizer::H264Packetizer, rtp_payload_chunk::RtpPayloadChunk,
 };
-use crate::{log::log_sink::LogSink, media_agent::spec::CodecSpec, sink_trace};
+use crate::{
+    core::path_mtu,
+    log::log_sink::LogSink,
+    media_agent::{spec::CodecSpec, video_track::TrackId},
+    rtp::rtp_header_extension::RtpHeaderExtension,
+    sink_trace,
+};
 
 /// Represents a request sent to the Packetizer worker to process a frame.
 #[derive(Debug)]
@@ -22,6 +31,12 @@ pub struct PacketizeOrder {
     pub rtp_ts: u32,
     /// The codec used, determining the packetization strategy (e.g., H.264 NAL units).
     pub codec_spec: CodecSpec,
+    /// Extra video track the frame belongs to (`None` for the default tracks).
+    pub track: Option<TrackId>,
+    /// Header extension for every packet of the frame (audio level).
+    pub header_extension: Option<RtpHeaderExtension>,
+    /// Capture time of a traced video frame (UNIX milliseconds).
+    pub captured_ms: Option<u128>,
 }
 
 /// The result of the packetization process.
@@ -36,6 +51,12 @@ pub struct PacketizedFrame {
     pub rtp_ts: u32,
     /// The codec specification.
     pub codec_spec: CodecSpec,
+    /// Extra video track the frame belongs to (`None` for the default tracks).
+    pub track: Option<TrackId>,
+    /// Header extension for every packet of the frame (audio level).
+    pub header_extension: Option<RtpHeaderExtension>,
+    /// Capture time of a traced video frame (UNIX milliseconds).
+    pub captured_ms: Option<u128>,
 }
 
 /// Spawns a dedicated thread for fragmenting video frames into network packets.
@@ -44,14 +65,17 @@ pub struct PacketizedFrame {
 /// codec-specific logic (currently H.264) to split the frame into MTU-safe chunks.
 ///
 /// # MTU Strategy
-/// The packetizer is initialized with a conservative MTU of **1200 bytes**.
-/// This safeguards against IP fragmentation on the internet (where standard MTU is 1500),
-/// leaving ample room for IP, UDP, and RTP headers.
+/// Packets are sized so that, once SRTP appends its tag, each datagram fits
+/// `mtu`: `[ICE] mtu` (a conservative **1200 bytes** by default), or the path
+/// MTU once discovered. The value is read again for every frame, so a
+/// discovered one applies from the next frame on.
 ///
 /// # Arguments
 ///
 /// * `order_rx` - Channel receiving frames to be packetized.
 /// * `event_tx` - Channel to output the result (`PacketizedFrame`).
+/// * `cryptor` - When set, every frame is end-to-end encrypted first.
+/// * `mtu` - Largest datagram to send.
 /// * `logger` - Logger instance.
 ///
 /// # Panics
@@ -61,33 +85,44 @@ pub struct PacketizedFrame {
 pub fn spawn_packetizer_worker(
     order_rx: Receiver<PacketizeOrder>,
     event_tx: Sender<PacketizerEvent>,
+    cryptor: Option<Arc<FrameCryptor>>,
+    mtu: Arc<AtomicUsize>,
     logger: Arc<dyn LogSink>,
 ) -> JoinHandle<()> {
     thread::Builder::new()
         .name("media-transport-packetizer".into())
         .spawn(move || {
-            // MTU is hardcoded to 1200 bytes.
-            // This leaves ~300 bytes of headroom for headers (IP+UDP+RTP+Extensions)
-            // before hitting the standard 1500 byte Ethernet limit.
-            let h264_packetizer = H264Packetizer::new(1200);
+            let mut current_mtu = mtu.load(Ordering::SeqCst);
+            let mut h264_packetizer = H264Packetizer::new(path_mtu::rtp_mtu(current_mtu));
 
-            while let Ok(order) = order_rx.recv() {
+            while let Ok(mut order) = order_rx.recv() {
+                let new_mtu = mtu.load(Ordering::SeqCst);
+                if new_mtu != current_mtu {
+                    current_mtu = new_mtu;
+                    h264_packetizer = H264Packetizer::new(path_mtu::rtp_mtu(current_mtu));
+                }
                 sink_trace!(
                     logger.clone(),
                     "[Packetizer] Received Order"
                 );
+                if let Some(cryptor) = &cryptor {
+                    order.payload = cryptor.encrypt_frame(order.codec_spec, &order.payload);
+                }
 
                 match order.codec_spec {
                     CodecSpec::H264 => {
-                        // Performs the slicing (identifies NAL boundaries, handles FU-A)
-                        let chunks =
-                            h264_packetizer.packetize_annexb_to_payloads(&order.payload);
+                        // Performs the slicing (identifies NAL boundaries, handles FU-A);
+                        // the chunks point into the frame, nothing is copied yet
+                        let chunks = h264_packetizer.packetize(Bytes::from(order.payload));
 
                         if !chunks.is_empty() {
                             let packetized_frame = PacketizedFrame {
                                 chunks,
                                 rtp_ts: order.rtp_ts,
                                 codec_spec: order.codec_spec,
+                                track: order.track,
+                                header_extension: order.header_extension,
+                                captured_ms: order.captured_ms,
                             };
 
                             sink_trace!(
@@ -100,14 +135,14 @@ pub fn spawn_packetizer_worker(
                                 event_tx.send(PacketizerEvent::FramePacketized(packetized_frame));
                         }
                     }
-                    CodecSpec::G711U => {
+                    CodecSpec::G711U | CodecSpec::L16 => {
                          let packetized_frame = PacketizedFrame {
-                            chunks: vec![RtpPayloadChunk {
-                                bytes: order.payload,
-                                marker: true,
-                            }],
+                            chunks: vec![RtpPayloadChunk::new(order.payload, true)],
                             rtp_ts: order.rtp_ts,
                             codec_spec: order.codec_spec,
+                            track: order.track,
+                            header_extension: order.header_extension,
+                            captured_ms: order.captured_ms,
                         };
 
                         sink_trace!(
@@ -117,6 +152,10 @@ pub fn spawn_packetizer_worker(
 
                         let _ = event_tx.send(PacketizerEvent::FramePacketized(packetized_frame));
                     }
+                    CodecSpec::TelephoneEvent => {
+                        // Paced by the media agent event loop, never packetized here
+                        sink_trace!(logger.clone(), "[Packetizer] Ignoring telephone event order");
+                    }
                 }
             }
         })
@@ -1,4 +1,4 @@
-//! RFC 6184 H.264 -> RTP packetizer (Single NALU + FU-A).
+//! RFC 6184 H.264 -> RTP packetizer (Single NALU + STAP-A + FU-A).
 //!
 //! Input  : one Annex-B "access unit" (frame) as a byte slice (may contain multiple NAL units).
 //! Output : a vector of RTP payload chunks; each chunk is ready to become an RTP payload.
@@ -6,8 +6,8 @@
 //!
 //! Scope  : non-interleaved mode (packetization-mode=1). We support:
 //!          - Single NAL Unit packets (no start codes in payload)
+//!          - STAP-A aggregation of consecutive small NALUs (SPS/PPS/SEI and small slices)
 //!          - FU-A fragmentation for large NALUs
-//!          (STAP-A aggregation is optional and omitted to keep v1 simple).
 //!
 //! Marker : The `marker` flag is set to true ONLY on the *last* payload chunk of the frame.
 //!
@@ -26,6 +26,8 @@
 //! Or build full RTP packets (keeps `seq` locally and returns the next value):
 //!   let (pkts, next_seq) = p.packetize_annexb_to_rtp(annexb_frame, pt, ts, ssrc, seq_start);
 
+use bytes::Bytes;
+
 use crate::rtp::rtp_packet::RtpPacket;
 
 use super::rtp_payload_chunk::RtpPayloadChunk;
@@ -38,6 +40,8 @@ pub struct H264Packetizer {
     /// - RTP header (12 B)
     /// - any extensions, SRTP tag, etc.
     rtp_overhead: usize,
+    /// Whether consecutive small NALUs share one STAP-A packet.
+    aggregate: bool,
 }
 
 impl H264Packetizer {
@@ -46,6 +50,7 @@ impl H264Packetizer {
         Self {
             mtu,
             rtp_overhead: 12,
+            aggregate: true,
         }
     }
 
@@ -55,6 +60,13 @@ impl H264Packetizer {
         self
     }
 
+    /// Turn STAP-A aggregation on or off (on by default); when off, every NALU
+    /// that fits goes in its own Single-NALU packet.
+    pub fn with_aggregation(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
     #[inline]
     fn max_payload(&self) -> usize {
         self.mtu.saturating_sub(self.rtp_overhead)
@@ -63,28 +75,50 @@ impl H264Packetizer {
     /// Split an Annex-B access unit (frame) into RTP payload chunks.
     ///
     /// - Removes Annex-B start codes.
+    /// - Packs consecutive NALUs into STAP-A while they fit in max_payload.
     /// - Uses Single-NALU if nal.len() <= max_payload, else FU-A.
     /// - The `marker` flag is true on the *last* returned chunk only.
     pub fn packetize_annexb_to_payloads(&self, annexb_frame: &[u8]) -> Vec<RtpPayloadChunk> {
+        self.packetize(Bytes::copy_from_slice(annexb_frame))
+    }
+
+    /// Like [`packetize_annexb_to_payloads`](Self::packetize_annexb_to_payloads),
+    /// without copying: every chunk's body is a view into `annexb_frame`,
+    /// which stays alive until the last packet is written. STAP-A chunks are
+    /// the exception: their NALUs are copied between the size fields.
+    pub fn packetize(&self, annexb_frame: Bytes) -> Vec<RtpPayloadChunk> {
         let mut out = Vec::new();
-        let nalus = split_annexb_nalus(annexb_frame);
+        let nalus = split_annexb_nalus(&annexb_frame);
         if nalus.is_empty() {
             return out; // nothing to send
         }
         let max_payload = self.max_payload();
+        // NALUs waiting to go out together, and the STAP-A they would make
+        let mut group: Vec<&[u8]> = Vec::new();
+        let mut group_len = 1;
 
-        for (ni, nalu) in nalus.iter().enumerate() {
+        for nalu in nalus {
             if nalu.is_empty() {
                 continue;
             }
 
             if nalu.len() <= max_payload {
-                // Single NALU packet: payload is the NALU bytes (no start code).
-                out.push(RtpPayloadChunk {
-                    bytes: nalu.to_vec(),
-                    marker: false, // we'll fix the last one after the loop
-                });
+                if !self.aggregate || u16::try_from(nalu.len()).is_err() {
+                    flush_group(&annexb_frame, &mut group, &mut out);
+                    group.push(nalu);
+                    flush_group(&annexb_frame, &mut group, &mut out);
+                    continue;
+                }
+                if group_len + 2 + nalu.len() > max_payload {
+                    flush_group(&annexb_frame, &mut group, &mut out);
+                    group_len = 1;
+                }
+                group.push(nalu);
+                group_len += 2 + nalu.len();
             } else {
+                flush_group(&annexb_frame, &mut group, &mut out);
+                group_len = 1;
+
                 // FU-A fragmentation
                 // Original header
                 let nalu_header = nalu[0];
@@ -115,27 +149,22 @@ impl H264Packetizer {
                     let e_bit = if offset + take == n { 0x40 } else { 0x00 };
                     let fu_header = s_bit | e_bit | fu_header_base;
 
-                    let mut payload = Vec::with_capacity(2 + take);
-                    payload.push(fu_indicator);
-                    payload.push(fu_header);
-                    payload.extend_from_slice(&nalu[offset..offset + take]);
-
-                    out.push(RtpPayloadChunk {
-                        bytes: payload,
-                        marker: false, // fixed after loop
-                    });
+                    // marker fixed after loop
+                    out.push(RtpPayloadChunk::with_prefix(
+                        &[fu_indicator, fu_header],
+                        annexb_frame.slice_ref(&nalu[offset..offset + take]),
+                        false,
+                    ));
 
                     offset += take;
                 }
             }
+        }
+        flush_group(&annexb_frame, &mut group, &mut out);
 
-            // If this NALU was the last NALU of the AU and we already pushed at least one chunk,
-            // mark the last emitted chunk as marker=true (end of frame).
-            if ni + 1 == nalus.len()
-                && let Some(last) = out.last_mut()
-            {
-                last.marker = true;
-            }
+        // Mark the last emitted chunk as marker=true (end of frame).
+        if let Some(last) = out.last_mut() {
+            last.marker = true;
         }
 
         out
@@ -160,7 +189,7 @@ impl H264Packetizer {
         let mut seq = seq_start;
 
         for ch in chunks {
-            let pkt = RtpPacket::simple(payload_type, ch.marker, seq, timestamp, ssrc, ch.bytes);
+            let pkt = RtpPacket::simple(payload_type, ch.marker, seq, timestamp, ssrc, ch.to_vec());
             packets.push(pkt);
             seq = seq.wrapping_add(1);
         }
@@ -169,10 +198,34 @@ impl H264Packetizer {
     }
 }
 
+/// Emit the pending NALUs: one alone as a Single-NALU packet (a view into
+/// the frame), several as one STAP-A packet.
+fn flush_group(frame: &Bytes, group: &mut Vec<&[u8]>, out: &mut Vec<RtpPayloadChunk>) {
+    match group.as_slice() {
+        [] => {}
+        [nalu] => out.push(RtpPayloadChunk::new(frame.slice_ref(nalu), false)),
+        nalus => {
+            // STAP-A header: F if any F is set, the highest NRI, type 24
+            let f_bit = nalus.iter().fold(0, |f, n| f | (n[0] & 0x80));
+            let nri = nalus.iter().map(|n| n[0] & 0x60).max().unwrap_or(0);
+            let mut stap = Vec::with_capacity(1 + nalus.iter().map(|n| 2 + n.len()).sum::<usize>());
+            stap.push(f_bit | nri | 24);
+            for nalu in nalus {
+                // Callers only group NALUs whose size fits the 16-bit field
+                let size = u16::try_from(nalu.len()).unwrap_or(u16::MAX);
+                stap.extend_from_slice(&size.to_be_bytes());
+                stap.extend_from_slice(nalu);
+            }
+            out.push(RtpPayloadChunk::new(stap, false));
+        }
+    }
+    group.clear();
+}
+
 /// Find all NAL units in an Annex-B byte stream.
 /// This is a "lossy" split, as it does not preserve trailing zeros in the original data,
 /// but this is fine for RTP packetization which is size-based.
-fn split_annexb_nalus(data: &[u8]) -> Vec<&[u8]> {
+pub(crate) fn split_annexb_nalus(data: &[u8]) -> Vec<&[u8]> {
     let (mut sc_pos, mut sc_len) = match find_start_code(data, 0) {
         Some(t) => t,
         None => {
@@ -281,14 +334,56 @@ mod tests {
 
     #[test]
     fn packetize_small_nalus_single() {
-        let p = H264Packetizer::new(1200);
+        let p = H264Packetizer::new(1200).with_aggregation(false);
         let a = annexb(&[&[0x65, 1, 2], &[0x41, 3]]);
         let chunks = p.packetize_annexb_to_payloads(&a);
         assert_eq!(chunks.len(), 2);
         assert!(!chunks[0].marker);
         assert!(chunks[1].marker);
-        assert_eq!(chunks[0].bytes, &[0x65, 1, 2]);
-        assert_eq!(chunks[1].bytes, &[0x41, 3]);
+        assert_eq!(chunks[0].to_vec(), &[0x65, 1, 2]);
+        assert_eq!(chunks[1].to_vec(), &[0x41, 3]);
+    }
+
+    #[test]
+    fn small_nalus_are_aggregated_while_they_fit() {
+        // max_payload = 30 - 12 = 18
+        let p = H264Packetizer::new(30).with_overhead(12);
+        let sps = [0x67, 1, 2, 3];
+        let pps = [0x68, 4, 5];
+        let sei = [0x06, 6, 7, 8, 9];
+        let slice = [0x41, 10, 11, 12, 13, 14, 15];
+        let chunks = p.packetize_annexb_to_payloads(&annexb(&[&sps, &pps, &sei, &slice]));
+
+        // 1 + (2+4) + (2+3) + (2+5) = 19 > 18: the SEI starts the next STAP-A
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].to_vec(),
+            [&[0x78, 0, 4][..], &sps, &[0, 3], &pps].concat()
+        );
+        assert_eq!(
+            chunks[1].to_vec(),
+            [&[0x58, 0, 5][..], &sei, &[0, 7], &slice].concat()
+        );
+        assert!(!chunks[0].marker);
+        assert!(chunks[1].marker);
+    }
+
+    #[test]
+    fn packetize_borrows_the_frame() {
+        let p = H264Packetizer::new(22).with_overhead(12); // max_payload=10
+        let mut big = vec![0x65];
+        big.extend(1u8..=20);
+        let frame = Bytes::from(annexb(&[&[0x41, 9], &big]));
+        let range = frame.as_ptr_range();
+
+        let chunks = p.packetize(frame.clone());
+        assert_eq!(chunks[0].slices(), [&[][..], &[0x41, 9][..]]);
+        for ch in &chunks[1..] {
+            let [prefix, body] = ch.slices();
+            assert_eq!(prefix.len(), 2);
+            assert!(range.contains(&body.as_ptr()));
+        }
+        assert_eq!(chunks, p.packetize_annexb_to_payloads(&frame));
     }
 
     #[test]
@@ -301,9 +396,9 @@ mod tests {
         let a = annexb(&[&nalu]);
         let chunks = p.packetize_annexb_to_payloads(&a);
         assert_eq!(chunks.len(), 1);
-        assert_eq!(chunks[0].bytes.len(), 18);
+        assert_eq!(chunks[0].len(), 18);
         // Single NALU payload should start with original header (0x41), not FU-A indicator (type 28)
-        assert_ne!(chunks[0].bytes[0] & 0x1F, 28);
+        assert_ne!(chunks[0].to_vec()[0] & 0x1F, 28);
         assert!(chunks[0].marker);
     }
 
@@ -318,8 +413,8 @@ mod tests {
         assert_eq!(chunks.len(), 2);
         // Check FU-A headers
         for (i, ch) in chunks.iter().enumerate() {
-            assert_eq!(ch.bytes[0] & 0x1F, 28); // FU-A
-            let fu_hdr = ch.bytes[1];
+            assert_eq!(ch.to_vec()[0] & 0x1F, 28); // FU-A
+            let fu_hdr = ch.to_vec()[1];
             let s = fu_hdr & 0x80 != 0;
             let e = fu_hdr & 0x40 != 0;
             if i == 0 {
@@ -439,9 +534,9 @@ mod tests {
         assert!(chunks.len() >= 3);
         for (i, ch) in chunks.iter().enumerate() {
             // FU-A has 2-byte header
-            assert!(ch.bytes.len() <= 10);
-            assert_eq!(ch.bytes[0] & 0x1F, 28); // FU-A indicator type
-            let fu_hdr = ch.bytes[1];
+            assert!(ch.len() <= 10);
+            assert_eq!(ch.to_vec()[0] & 0x1F, 28); // FU-A indicator type
+            let fu_hdr = ch.to_vec()[1];
             let is_start = fu_hdr & 0x80 != 0;
             let is_end = fu_hdr & 0x40 != 0;
             if i == 0 {
@@ -1,2 +1,3 @@
 pub mod h264_packetizer;
 pub mod rtp_payload_chunk;
+pub mod telephone_event;
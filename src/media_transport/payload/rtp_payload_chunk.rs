@@ -1,7 +1,64 @@
+use bytes::Bytes;
+
+/// Most bytes a payload format puts in front of the media (FU-A: indicator
+/// and header).
+const MAX_PREFIX: usize = 2;
+
 /// A single RTP payload chunk plus whether it carries the end-of-frame marker.
-#[derive(Debug, Clone)]
+///
+/// The payload is `prefix` followed by `body`, kept apart so nothing is
+/// copied until the packet is written: `body` is a view into the frame being
+/// packetized, and the send stream copies the RTP header, `prefix` and `body`
+/// into its packet buffer in one pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RtpPayloadChunk {
-    pub bytes: Vec<u8>,
+    prefix: [u8; MAX_PREFIX],
+    prefix_len: usize,
+    body: Bytes,
     /// true only for the *last* chunk of the access unit (frame)
     pub marker: bool,
 }
+
+impl RtpPayloadChunk {
+    /// A chunk whose payload is `body` as is.
+    pub fn new(body: impl Into<Bytes>, marker: bool) -> Self {
+        Self {
+            prefix: [0; MAX_PREFIX],
+            prefix_len: 0,
+            body: body.into(),
+            marker,
+        }
+    }
+
+    /// A chunk whose payload is `prefix` (at most two bytes) then `body`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is longer than two bytes.
+    pub fn with_prefix(prefix: &[u8], body: Bytes, marker: bool) -> Self {
+        assert!(prefix.len() <= MAX_PREFIX, "payload prefix too long");
+        let mut chunk = Self::new(body, marker);
+        chunk.prefix[..prefix.len()].copy_from_slice(prefix);
+        chunk.prefix_len = prefix.len();
+        chunk
+    }
+
+    /// The payload, in the order it goes on the wire.
+    pub fn slices(&self) -> [&[u8]; 2] {
+        [&self.prefix[..self.prefix_len], &self.body]
+    }
+
+    /// Payload size in bytes.
+    pub fn len(&self) -> usize {
+        self.prefix_len + self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The payload in one buffer (copies it).
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.slices().concat()
+    }
+}
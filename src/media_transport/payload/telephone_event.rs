@@ -0,0 +1,273 @@
+//! DTMF as RTP telephone events (RFC 4733).
+//!
+//! Each digit is sent as a series of 4-byte event payloads, one per 20 ms,
+//! all carrying the RTP timestamp of the event start and a growing duration.
+//! The first packet has the marker bit set and the last one the end bit,
+//! repeated three times in case of loss.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Telephone-event clock rate, same as the G.711 audio it replaces.
+pub const TELEPHONE_EVENT_CLOCK_RATE: u32 = 8000;
+/// Default tone length of one digit.
+pub const DEFAULT_DTMF_TONE: Duration = Duration::from_millis(100);
+/// Silence between two digits.
+const INTER_DIGIT_GAP: Duration = Duration::from_millis(50);
+/// Pause inserted by a `,` in the digit string.
+const COMMA_PAUSE: Duration = Duration::from_secs(2);
+/// One packet every 20 ms (160 samples at 8 kHz).
+const PACKET_INTERVAL: Duration = Duration::from_millis(20);
+const SAMPLES_PER_PACKET: u32 = 160;
+/// How many times the final (end bit) packet is sent.
+const END_REPEATS: usize = 3;
+/// Tone power, in -dBm0.
+const VOLUME: u8 = 10;
+
+/// Event code of a DTMF digit: `0-9`, `*`, `#`, `A-D`.
+#[must_use]
+pub fn dtmf_event_code(digit: char) -> Option<u8> {
+    match digit.to_ascii_uppercase() {
+        d @ '0'..='9' => d.to_digit(10).and_then(|d| u8::try_from(d).ok()),
+        '*' => Some(10),
+        '#' => Some(11),
+        d @ 'A'..='D' => Some(d as u8 - b'A' + 12),
+        _ => None,
+    }
+}
+
+/// One telephone-event payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelephoneEvent {
+    pub event: u8,
+    pub end: bool,
+    pub volume: u8,
+    /// In clock ticks since the event start.
+    pub duration: u16,
+}
+
+impl TelephoneEvent {
+    #[must_use]
+    pub fn encode(&self) -> [u8; 4] {
+        let [hi, lo] = self.duration.to_be_bytes();
+        [
+            self.event,
+            (u8::from(self.end) << 7) | (self.volume & 0x3F),
+            hi,
+            lo,
+        ]
+    }
+
+    #[must_use]
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        let [event, flags, hi, lo] = *payload.get(..4)? else {
+            return None;
+        };
+        Some(Self {
+            event,
+            end: flags & 0x80 != 0,
+            volume: flags & 0x3F,
+            duration: u16::from_be_bytes([hi, lo]),
+        })
+    }
+}
+
+/// A packet ready to go out on the audio stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventPacket {
+    pub event: TelephoneEvent,
+    pub rtp_ts: u32,
+    pub marker: bool,
+}
+
+/// One element of a dial string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtmfStep {
+    /// Event code, see [`dtmf_event_code`].
+    Digit(u8),
+    Pause,
+}
+
+/// Parses a dial string (digits, `,` for a pause; whitespace is ignored).
+///
+/// Returns the first character that is not a DTMF digit.
+pub fn parse_dtmf(digits: &str) -> Result<Vec<DtmfStep>, char> {
+    digits
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            ',' => Ok(DtmfStep::Pause),
+            c => dtmf_event_code(c).map(DtmfStep::Digit).ok_or(c),
+        })
+        .collect()
+}
+
+struct ActiveEvent {
+    code: u8,
+    rtp_ts: u32,
+    started: Instant,
+    sent: u32,
+}
+
+/// Turns queued digits into paced telephone-event packets.
+pub struct DtmfSender {
+    tone: Duration,
+    queue: VecDeque<DtmfStep>,
+    active: Option<ActiveEvent>,
+    idle_until: Option<Instant>,
+}
+
+impl DtmfSender {
+    #[must_use]
+    pub const fn new(tone: Duration) -> Self {
+        Self {
+            tone,
+            queue: VecDeque::new(),
+            active: None,
+            idle_until: None,
+        }
+    }
+
+    /// Queues `digits`; `,` is a two second pause.
+    ///
+    /// Returns the first character that is not a DTMF digit, in which case
+    /// nothing is queued.
+    pub fn push(&mut self, digits: &str) -> Result<(), char> {
+        self.queue.extend(parse_dtmf(digits)?);
+        Ok(())
+    }
+
+    /// Whether an event is being sent; the audio stream should stay quiet.
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Whether anything is left to send.
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.active.is_none() && self.queue.is_empty()
+    }
+
+    /// Time until [`poll`](Self::poll) has something to do, if anything is pending.
+    #[must_use]
+    pub fn next_deadline(&self, now: Instant) -> Option<Duration> {
+        if let Some(active) = &self.active {
+            let due = active.started + PACKET_INTERVAL * active.sent;
+            return Some(due.saturating_duration_since(now));
+        }
+        if self.queue.is_empty() {
+            return None;
+        }
+        Some(
+            self.idle_until
+                .map_or(Duration::ZERO, |t| t.saturating_duration_since(now)),
+        )
+    }
+
+    /// Packets due at `now`. A new event starts at `audio_ts`, the RTP
+    /// timestamp the next audio packet would have used.
+    pub fn poll(&mut self, now: Instant, audio_ts: u32) -> Vec<EventPacket> {
+        let mut out = Vec::new();
+        if self.active.is_none() {
+            if self.idle_until.is_some_and(|t| now < t) {
+                return out;
+            }
+            self.idle_until = None;
+            match self.queue.pop_front() {
+                Some(DtmfStep::Digit(code)) => {
+                    self.active = Some(ActiveEvent {
+                        code,
+                        rtp_ts: audio_ts,
+                        started: now,
+                        sent: 0,
+                    });
+                }
+                Some(DtmfStep::Pause) => {
+                    self.idle_until = Some(now + COMMA_PAUSE);
+                    return out;
+                }
+                None => return out,
+            }
+        }
+
+        let Some(active) = self.active.as_mut() else {
+            return out;
+        };
+        let tone_packets = self.tone.as_millis().div_ceil(PACKET_INTERVAL.as_millis());
+        let tone_packets = u32::try_from(tone_packets).unwrap_or(u32::MAX).max(1);
+
+        while now >= active.started + PACKET_INTERVAL * active.sent {
+            active.sent += 1;
+            let end = active.sent >= tone_packets;
+            let duration = (active.sent * SAMPLES_PER_PACKET).min(u32::from(u16::MAX));
+            let packet = EventPacket {
+                event: TelephoneEvent {
+                    event: active.code,
+                    end,
+                    volume: VOLUME,
+                    duration: u16::try_from(duration).unwrap_or(u16::MAX),
+                },
+                rtp_ts: active.rtp_ts,
+                marker: active.sent == 1,
+            };
+            if end {
+                out.extend(std::iter::repeat_n(packet, END_REPEATS));
+                self.active = None;
+                self.idle_until = Some(now + INTER_DIGIT_GAP);
+                break;
+            }
+            out.push(packet);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn event_payload_round_trip() {
+        assert_eq!(dtmf_event_code('5'), Some(5));
+        assert_eq!(dtmf_event_code('#'), Some(11));
+        assert_eq!(dtmf_event_code('d'), Some(15));
+        assert_eq!(dtmf_event_code('x'), None);
+
+        let ev = TelephoneEvent {
+            event: 11,
+            end: true,
+            volume: 10,
+            duration: 800,
+        };
+        assert_eq!(ev.encode(), [11, 0x8A, 0x03, 0x20]);
+        assert_eq!(TelephoneEvent::decode(&ev.encode()), Some(ev));
+    }
+
+    #[test]
+    fn digit_is_paced_and_ends_three_times() {
+        let mut dtmf = DtmfSender::new(Duration::from_millis(60));
+        assert_eq!(dtmf.push("1x"), Err('x'));
+        assert!(dtmf.is_idle());
+        dtmf.push("1").unwrap();
+
+        let t0 = Instant::now();
+        let first = dtmf.poll(t0, 1000);
+        assert_eq!(first.len(), 1);
+        assert!(first[0].marker);
+        assert_eq!(first[0].event.duration, 160);
+        assert!(dtmf.is_active());
+
+        // Late by 10 ms: nothing yet
+        assert!(dtmf.poll(t0 + Duration::from_millis(10), 5000).is_empty());
+        let rest = dtmf.poll(t0 + Duration::from_millis(40), 5000);
+        assert_eq!(rest.len(), 1 + END_REPEATS);
+        assert!(rest.iter().all(|p| p.rtp_ts == 1000 && !p.marker));
+        assert!(rest[1..].iter().all(|p| p.event.end));
+        assert_eq!(rest[3].event.duration, 480);
+        assert!(dtmf.is_idle());
+    }
+}
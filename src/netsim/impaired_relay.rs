@@ -0,0 +1,252 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use super::impairment::{Impairer, Impairment};
+
+/// Longest the relay sleeps when there is nothing to do.
+const IDLE_WAIT: Duration = Duration::from_millis(1);
+
+/// Packet counts of one direction of the link.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LinkStats {
+    pub received: u64,
+    /// Copies sent on, duplicates included.
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    received: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> LinkStats {
+        LinkStats {
+            received: self.received.load(Ordering::Relaxed),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A UDP relay between two endpoints that impairs what it forwards.
+///
+/// Endpoint A sends to [`addr_for_a`](Self::addr_for_a) as if it were B, and
+/// B to [`addr_for_b`](Self::addr_for_b); each sees the other's packets
+/// coming from the relay address it sends to, so connected sockets work.
+/// The relay stops when dropped.
+pub struct ImpairedRelay {
+    addr_for_a: SocketAddr,
+    addr_for_b: SocketAddr,
+    counters: Arc<[Counters; 2]>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// One direction: packets received on `inbound` go out of `outbound` to `to`.
+struct Direction {
+    inbound: usize,
+    outbound: usize,
+    to: SocketAddr,
+    impairer: Impairer,
+    /// Packets in flight, by arrival time (then order received).
+    in_flight: BinaryHeap<Reverse<(Instant, u64, Vec<u8>)>>,
+}
+
+impl ImpairedRelay {
+    /// Starts relaying between `a` and `b` on loopback, impairing packets
+    /// from A with `a_to_b` and packets from B with `b_to_a`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the relay sockets cannot be set up.
+    pub fn start(
+        a: SocketAddr,
+        b: SocketAddr,
+        a_to_b: Impairment,
+        b_to_a: Impairment,
+    ) -> io::Result<Self> {
+        let bind = |peer: SocketAddr| -> io::Result<UdpSocket> {
+            let sock = UdpSocket::bind(SocketAddr::new(peer.ip(), 0))?;
+            sock.set_nonblocking(true)?;
+            Ok(sock)
+        };
+        let socks = [bind(b)?, bind(a)?];
+        let addr_for_a = socks[0].local_addr()?;
+        let addr_for_b = socks[1].local_addr()?;
+
+        let dirs = [
+            Direction::new(0, 1, b, a_to_b),
+            Direction::new(1, 0, a, b_to_a),
+        ];
+        let counters: Arc<[Counters; 2]> = Arc::default();
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = thread::Builder::new()
+            .name("impaired-relay".into())
+            .spawn({
+                let counters = Arc::clone(&counters);
+                let running = Arc::clone(&running);
+                move || relay(&socks, dirs, &counters, &running)
+            })?;
+
+        Ok(Self {
+            addr_for_a,
+            addr_for_b,
+            counters,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    /// Where A sends its packets for B.
+    #[must_use]
+    pub const fn addr_for_a(&self) -> SocketAddr {
+        self.addr_for_a
+    }
+
+    /// Where B sends its packets for A.
+    #[must_use]
+    pub const fn addr_for_b(&self) -> SocketAddr {
+        self.addr_for_b
+    }
+
+    /// Counts from A to B and from B to A.
+    #[must_use]
+    pub fn stats(&self) -> (LinkStats, LinkStats) {
+        (self.counters[0].snapshot(), self.counters[1].snapshot())
+    }
+}
+
+impl Drop for ImpairedRelay {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Direction {
+    fn new(inbound: usize, outbound: usize, to: SocketAddr, cfg: Impairment) -> Self {
+        Self {
+            inbound,
+            outbound,
+            to,
+            impairer: Impairer::new(cfg),
+            in_flight: BinaryHeap::new(),
+        }
+    }
+}
+
+fn relay(
+    socks: &[UdpSocket; 2],
+    mut dirs: [Direction; 2],
+    counters: &[Counters; 2],
+    running: &AtomicBool,
+) {
+    let mut buf = vec![0u8; 65535];
+    let mut order = 0u64;
+    while running.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        let mut busy = false;
+
+        for (dir, count) in dirs.iter_mut().zip(counters) {
+            while let Ok(n) = socks[dir.inbound].recv(&mut buf) {
+                busy = true;
+                count.received.fetch_add(1, Ordering::Relaxed);
+                let arrivals = dir.impairer.arrivals(n, now);
+                if arrivals.is_empty() {
+                    count.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                for at in arrivals {
+                    order += 1;
+                    dir.in_flight.push(Reverse((at, order, buf[..n].to_vec())));
+                }
+            }
+
+            while let Some(Reverse((at, _, _))) = dir.in_flight.peek()
+                && *at <= now
+            {
+                if let Some(Reverse((_, _, pkt))) = dir.in_flight.pop() {
+                    let _ = socks[dir.outbound].send_to(&pkt, dir.to);
+                    count.delivered.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        if !busy {
+            let next = dirs
+                .iter()
+                .filter_map(|d| d.in_flight.peek().map(|Reverse((at, _, _))| *at))
+                .min();
+            let wait = next.map_or(IDLE_WAIT, |at| {
+                at.saturating_duration_since(Instant::now()).min(IDLE_WAIT)
+            });
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn endpoint() -> UdpSocket {
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sock.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        sock
+    }
+
+    #[test]
+    fn relays_both_ways_and_impairs_each_on_its_own() {
+        let a = endpoint();
+        let b = endpoint();
+        let lossy = Impairment {
+            loss: 1.0,
+            ..Impairment::default()
+        };
+        let delayed = Impairment {
+            delay: Duration::from_millis(30),
+            ..Impairment::default()
+        };
+        let relay = ImpairedRelay::start(
+            a.local_addr().unwrap(),
+            b.local_addr().unwrap(),
+            delayed,
+            lossy,
+        )
+        .unwrap();
+        a.connect(relay.addr_for_a()).unwrap();
+        b.connect(relay.addr_for_b()).unwrap();
+
+        let sent = Instant::now();
+        a.send(b"hello").unwrap();
+        let mut buf = [0u8; 16];
+        let n = b.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        assert!(sent.elapsed() >= Duration::from_millis(30));
+
+        b.send(b"lost").unwrap();
+        a.set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        assert!(a.recv(&mut buf).is_err());
+
+        let (a_to_b, b_to_a) = relay.stats();
+        assert_eq!(a_to_b.delivered, 1);
+        assert_eq!((b_to_a.received, b_to_a.dropped), (1, 1));
+    }
+}
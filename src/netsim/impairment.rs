@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// How one direction of a simulated link mistreats packets.
+///
+/// The default is a perfect link: no loss, no delay, unlimited rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impairment {
+    /// Probability (0 to 1) that a packet is lost.
+    pub loss: f64,
+    /// Probability that a packet arrives twice.
+    pub duplicate: f64,
+    /// Probability that a packet is held back by `reorder_delay`, so the
+    /// packets behind it overtake it.
+    pub reorder: f64,
+    pub reorder_delay: Duration,
+    /// One-way delay of every packet.
+    pub delay: Duration,
+    /// Extra delay, uniform between zero and this. Large jitter reorders
+    /// packets too.
+    pub jitter: Duration,
+    /// Link rate in bits per second; packets queue behind each other.
+    /// `None` is unlimited.
+    pub bandwidth_bps: Option<u64>,
+    /// Longest a packet may wait for the link; later ones are dropped, like
+    /// a router's full queue.
+    pub max_queue_delay: Duration,
+    /// Seed of the random choices: the same seed and traffic give the same
+    /// losses, duplicates and delays.
+    pub seed: u64,
+}
+
+impl Default for Impairment {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            reorder_delay: Duration::from_millis(20),
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bandwidth_bps: None,
+            max_queue_delay: Duration::from_millis(500),
+            seed: 0,
+        }
+    }
+}
+
+/// Decides the fate of each packet on one direction of a link.
+#[derive(Debug)]
+pub struct Impairer {
+    cfg: Impairment,
+    rng: StdRng,
+    /// When the link finishes sending what is already queued.
+    link_free_at: Option<Instant>,
+}
+
+impl Impairer {
+    #[must_use]
+    pub fn new(cfg: Impairment) -> Self {
+        Self {
+            cfg,
+            rng: StdRng::seed_from_u64(cfg.seed),
+            link_free_at: None,
+        }
+    }
+
+    /// When the copies of a `len`-byte packet sent at `now` arrive: none if
+    /// it is lost, two if it is duplicated.
+    pub fn arrivals(&mut self, len: usize, now: Instant) -> Vec<Instant> {
+        if self.chance(self.cfg.loss) {
+            return Vec::new();
+        }
+
+        let mut departure = now;
+        if let Some(bps) = self.cfg.bandwidth_bps.filter(|&bps| bps > 0) {
+            let start = self.link_free_at.map_or(now, |free| free.max(now));
+            if start.duration_since(now) > self.cfg.max_queue_delay {
+                return Vec::new();
+            }
+            let bits = len as u64 * 8;
+            departure = start + Duration::from_micros(bits.saturating_mul(1_000_000) / bps);
+            self.link_free_at = Some(departure);
+        }
+
+        let mut arrival = departure + self.cfg.delay + self.jitter();
+        if self.chance(self.cfg.reorder) {
+            arrival += self.cfg.reorder_delay;
+        }
+        if self.chance(self.cfg.duplicate) {
+            let copy = departure + self.cfg.delay + self.jitter();
+            return vec![arrival, copy];
+        }
+        vec![arrival]
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        p > 0.0 && self.rng.gen_bool(p.min(1.0))
+    }
+
+    fn jitter(&mut self) -> Duration {
+        let max = self.cfg.jitter.as_micros() as u64;
+        if max == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_micros(self.rng.gen_range(0..=max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    /// Arrival delays of `packets` 1000-byte packets sent at once.
+    fn run(cfg: Impairment, packets: usize) -> Vec<Vec<Duration>> {
+        let now = Instant::now();
+        let mut impairer = Impairer::new(cfg);
+        (0..packets)
+            .map(|_| {
+                let arrivals = impairer.arrivals(1000, now);
+                arrivals.iter().map(|at| at.duration_since(now)).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_perfect_link_delivers_everything_at_once() {
+        assert_eq!(run(Impairment::default(), 3), [[Duration::ZERO]; 3]);
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_network() {
+        let cfg = Impairment {
+            loss: 0.1,
+            duplicate: 0.05,
+            reorder: 0.05,
+            jitter: Duration::from_millis(30),
+            seed: 42,
+            ..Impairment::default()
+        };
+        let fates = run(cfg, 500);
+        assert_eq!(fates, run(cfg, 500));
+        assert_ne!(fates, run(Impairment { seed: 43, ..cfg }, 500));
+
+        let lost = fates.iter().filter(|f| f.is_empty()).count();
+        assert!((25..=75).contains(&lost), "lost {lost} of 500");
+        assert!(fates.iter().any(|f| f.len() == 2));
+    }
+
+    #[test]
+    fn the_rate_limit_queues_then_drops() {
+        let cfg = Impairment {
+            // 1000-byte packets take 8 ms at 1 Mbit/s
+            bandwidth_bps: Some(1_000_000),
+            max_queue_delay: Duration::from_millis(30),
+            ..Impairment::default()
+        };
+        let fates = run(cfg, 5);
+        for (i, fate) in fates[..4].iter().enumerate() {
+            assert_eq!(fate, &[Duration::from_millis(8 * (i as u64 + 1))]);
+        }
+        assert!(fates[4].is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+//! A simulated bad network for testing media code on one machine.
+//!
+//! [`ImpairedRelay`] sits between two in-process endpoints (two sessions,
+//! two engines, a test and a socket) and loses, duplicates, reorders, delays
+//! and rate-limits what it forwards, so congestion control, NACK/FEC and the
+//! jitter buffer can be checked against loss and jitter without a real bad
+//! network. Each direction takes its own [`Impairment`], and a seed makes a
+//! run repeatable.
+//!
+//! [`VirtualNet`] goes further for tests: its sockets never touch the OS and
+//! deliver on a virtual clock, so timeouts and retransmissions run instantly
+//! and deterministically.
+pub mod impaired_relay;
+pub mod impairment;
+pub mod virtual_net;
+pub use impaired_relay::{ImpairedRelay, LinkStats};
+pub use impairment::{Impairer, Impairment};
+pub use virtual_net::{VirtualNet, VirtualSocket};
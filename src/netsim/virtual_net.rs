@@ -0,0 +1,166 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
+
+use super::impairment::{Impairer, Impairment};
+use crate::core::{clock::SharedClock, transport::PacketTransport};
+
+/// An in-process network of [`VirtualSocket`]s.
+///
+/// Packets are delivered on the network's clock: with a
+/// [`ManualClock`](crate::core::clock::ManualClock), a delayed packet only
+/// shows up once the clock has been advanced past its arrival, so tests run
+/// as fast as the code allows and the same way every time. Clones share the
+/// network.
+#[derive(Clone)]
+pub struct VirtualNet {
+    clock: SharedClock,
+    inner: Arc<Mutex<Net>>,
+}
+
+/// Packets in flight to one address, by arrival (then order sent).
+type Inbox = BinaryHeap<Reverse<(Instant, u64, SocketAddr, Vec<u8>)>>;
+
+#[derive(Default)]
+struct Net {
+    next_port: u16,
+    inboxes: HashMap<SocketAddr, Inbox>,
+    /// Impairment of the packets from one address to another.
+    links: HashMap<(SocketAddr, SocketAddr), Impairer>,
+    sent: u64,
+}
+
+/// A socket on a [`VirtualNet`]; unbound when dropped.
+pub struct VirtualSocket {
+    addr: SocketAddr,
+    net: VirtualNet,
+}
+
+impl VirtualNet {
+    #[must_use]
+    pub fn new(clock: SharedClock) -> Self {
+        Self {
+            clock,
+            inner: Arc::default(),
+        }
+    }
+
+    /// Binds a socket on the next free port of 10.0.0.1.
+    #[must_use]
+    pub fn bind(&self) -> VirtualSocket {
+        let mut net = self.lock();
+        let addr = loop {
+            net.next_port = net.next_port.wrapping_add(1).max(1);
+            let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), net.next_port);
+            if !net.inboxes.contains_key(&addr) {
+                break addr;
+            }
+        };
+        net.inboxes.insert(addr, BinaryHeap::new());
+        VirtualSocket {
+            addr,
+            net: self.clone(),
+        }
+    }
+
+    /// Impairs the packets `from` sends `to`; links are perfect otherwise.
+    pub fn impair(&self, from: SocketAddr, to: SocketAddr, cfg: Impairment) {
+        self.lock().links.insert((from, to), Impairer::new(cfg));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Net> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl PacketTransport for VirtualSocket {
+    /// Packets to an unbound address are silently lost, as with UDP.
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        let now = self.net.clock.now();
+        let mut guard = self.net.lock();
+        let net = &mut *guard;
+        let arrivals = match net.links.get_mut(&(self.addr, to)) {
+            Some(impairer) => impairer.arrivals(buf.len(), now),
+            None => vec![now],
+        };
+        if let Some(inbox) = net.inboxes.get_mut(&to) {
+            for at in arrivals {
+                net.sent += 1;
+                inbox.push(Reverse((at, net.sent, self.addr, buf.to_vec())));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let now = self.net.clock.now();
+        let mut net = self.net.lock();
+        let inbox = net
+            .inboxes
+            .get_mut(&self.addr)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+        match inbox.peek() {
+            Some(Reverse((at, ..))) if *at <= now => {}
+            _ => return Err(io::ErrorKind::WouldBlock.into()),
+        }
+        let Some(Reverse((_, _, from, pkt))) = inbox.pop() else {
+            return Err(io::ErrorKind::WouldBlock.into());
+        };
+        // Like UDP, a datagram too big for the buffer is truncated
+        let n = pkt.len().min(buf.len());
+        buf[..n].copy_from_slice(&pkt[..n]);
+        Ok((n, from))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+impl Drop for VirtualSocket {
+    fn drop(&mut self) {
+        self.net.lock().inboxes.remove(&self.addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::clock::ManualClock;
+
+    #[test]
+    fn packets_arrive_on_the_virtual_clock() {
+        let clock = ManualClock::new();
+        let net = VirtualNet::new(Arc::new(clock.clone()));
+        let a = net.bind();
+        let b = net.bind();
+        let (a_addr, b_addr) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        net.impair(
+            a_addr,
+            b_addr,
+            Impairment {
+                delay: Duration::from_millis(50),
+                ..Impairment::default()
+            },
+        );
+
+        let mut buf = [0u8; 16];
+        a.send_to(b"late", b_addr).unwrap();
+        b.send_to(b"now", a_addr).unwrap();
+        assert_eq!(a.recv_from(&mut buf).unwrap(), (3, b_addr));
+        assert_eq!(&buf[..3], b"now");
+
+        let err = b.recv_from(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(b.recv_from(&mut buf).unwrap(), (4, a_addr));
+    }
+}
@@ -0,0 +1,178 @@
+//! Client-to-mixer audio level (RFC 6464) in a one-byte header extension
+//! (RFC 8285).
+//!
+//! Every audio packet can carry the level of its audio in -dBov (0 is the
+//! loudest, 127 is silence) plus a voice activity flag, so the receiver can
+//! show who is talking without decoding. The extension is offered with
+//! `a=extmap:<id> urn:ietf:params:rtp-hdrext:ssrc-audio-level` and each side
+//! uses the id from the other side's description.
+
+use std::time::{Duration, Instant};
+
+use super::rtp_header_extension::RtpHeaderExtension;
+
+/// `extmap` URI of the audio level extension.
+pub const AUDIO_LEVEL_URI: &str = "urn:ietf:params:rtp-hdrext:ssrc-audio-level";
+/// Id we offer for the extension.
+pub const AUDIO_LEVEL_EXT_ID: u8 = 1;
+/// Profile of RFC 8285 one-byte header extensions.
+pub const ONE_BYTE_PROFILE: u16 = 0xBEDE;
+/// Level of digital silence, in -dBov.
+pub const SILENCE_DBOV: u8 = 127;
+
+/// How often a changing level is reported while the voice flag is steady.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Level of one audio packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioLevel {
+    /// Level in -dBov, 0..=127.
+    pub level: u8,
+    /// The sender's voice activity detector considers this speech.
+    pub voice: bool,
+}
+
+impl AudioLevel {
+    #[must_use]
+    pub fn new(level: u8, voice: bool) -> Self {
+        Self {
+            level: level.min(SILENCE_DBOV),
+            voice,
+        }
+    }
+
+    /// From an RMS level in dBFS (`-inf` for silence).
+    #[must_use]
+    pub fn from_dbfs(dbfs: f32, voice: bool) -> Self {
+        let level = if dbfs.is_finite() {
+            (-dbfs).round().clamp(0.0, f32::from(SILENCE_DBOV)) as u8
+        } else {
+            SILENCE_DBOV
+        };
+        Self::new(level, voice)
+    }
+
+    /// The level as a header extension element with id `id`.
+    #[must_use]
+    pub fn to_extension(self, id: u8) -> RtpHeaderExtension {
+        // One-byte header: 4 bit id, 4 bit length - 1 (one data byte)
+        let data = vec![(id & 0x0F) << 4, (u8::from(self.voice) << 7) | self.level];
+        RtpHeaderExtension::new(ONE_BYTE_PROFILE, data)
+    }
+
+    /// Finds the element `id` in a one-byte header extension.
+    #[must_use]
+    pub fn from_extension(ext: &RtpHeaderExtension, id: u8) -> Option<Self> {
+        if ext.profile != ONE_BYTE_PROFILE {
+            return None;
+        }
+        let mut i = 0;
+        while i < ext.data.len() {
+            let header = ext.data[i];
+            if header == 0 {
+                // Padding between elements
+                i += 1;
+                continue;
+            }
+            let elem_id = header >> 4;
+            if elem_id == 15 {
+                // Reserved: stop processing
+                return None;
+            }
+            let len = usize::from(header & 0x0F) + 1;
+            if elem_id == id {
+                let byte = *ext.data.get(i + 1)?;
+                return Some(Self::new(byte & 0x7F, byte & 0x80 != 0));
+            }
+            i += 1 + len;
+        }
+        None
+    }
+}
+
+/// Thins out per-packet levels to what a UI needs: voice flag changes right
+/// away, level changes at most every [`REPORT_INTERVAL`].
+#[derive(Debug, Default)]
+pub struct LevelReporter {
+    last: Option<(Instant, AudioLevel)>,
+}
+
+impl LevelReporter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the level of a received packet. Returns it when it should be
+    /// reported.
+    pub fn offer(&mut self, now: Instant, level: AudioLevel) -> Option<AudioLevel> {
+        let report = match self.last {
+            None => true,
+            Some((at, last)) => {
+                last.voice != level.voice
+                    || (last.level != level.level
+                        && now.saturating_duration_since(at) >= REPORT_INTERVAL)
+            }
+        };
+        if report {
+            self.last = Some((now, level));
+            return Some(level);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::rtp::{rtp_header::RtpHeader, rtp_packet::RtpPacket};
+
+    #[test]
+    fn level_roundtrips_through_a_packet() {
+        let level = AudioLevel::from_dbfs(-30.4, true);
+        assert_eq!(level, AudioLevel::new(30, true));
+
+        let hdr = RtpHeader::new(0, 1, 160, 0x1234).with_extension(Some(level.to_extension(3)));
+        let bytes = RtpPacket::new(hdr, vec![0xFF; 160]).encode().unwrap();
+        let ext = RtpPacket::decode(&bytes)
+            .unwrap()
+            .header
+            .header_extension
+            .unwrap();
+        assert_eq!(ext.data, [0x30, 0x80 | 30, 0, 0]);
+        assert_eq!(AudioLevel::from_extension(&ext, 3), Some(level));
+        assert_eq!(AudioLevel::from_extension(&ext, 1), None);
+
+        assert_eq!(AudioLevel::from_dbfs(f32::NEG_INFINITY, false).level, 127);
+        assert_eq!(AudioLevel::from_dbfs(2.0, false).level, 0);
+    }
+
+    #[test]
+    fn parser_skips_other_elements() {
+        // Padding, a 2 byte element id 2, then the level with id 1
+        let ext = RtpHeaderExtension::new(ONE_BYTE_PROFILE, vec![0, 0x21, 9, 9, 0x10, 45, 0, 0]);
+        assert_eq!(
+            AudioLevel::from_extension(&ext, 1),
+            Some(AudioLevel::new(45, false))
+        );
+        let two_byte = RtpHeaderExtension::new(0x1000, vec![1, 1, 45, 0]);
+        assert_eq!(AudioLevel::from_extension(&two_byte, 1), None);
+    }
+
+    #[test]
+    fn reporter_throttles_level_changes() {
+        let mut reporter = LevelReporter::new();
+        let t0 = Instant::now();
+        let ms = |n: u64| t0 + Duration::from_millis(n);
+
+        assert!(reporter.offer(ms(0), AudioLevel::new(60, false)).is_some());
+        assert!(reporter.offer(ms(20), AudioLevel::new(50, false)).is_none());
+        // Voice starts: reported at once
+        assert!(reporter.offer(ms(40), AudioLevel::new(20, true)).is_some());
+        assert!(reporter.offer(ms(60), AudioLevel::new(25, true)).is_none());
+        assert!(reporter.offer(ms(140), AudioLevel::new(25, true)).is_some());
+        // Same level again is not news
+        assert!(reporter.offer(ms(400), AudioLevel::new(25, true)).is_none());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod audio_level;
 pub mod config;
 pub mod rtp_error;
 pub mod rtp_header;
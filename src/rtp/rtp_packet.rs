@@ -7,7 +7,7 @@ use super::{
     config::RTP_VERSION, rtp_error::RtpError, rtp_header::RtpHeader,
     rtp_header_extension::RtpHeaderExtension,
 };
-use std::convert::TryInto;
+use std::{convert::TryInto, ops::Range};
 
 /// Complete RTP packet (header + payload).
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -49,28 +49,64 @@ impl RtpPacket {
     /// Returns `RtpError::HeaderExtensionTooLong` if the header extension data is too large.
     pub fn encode(&self) -> Result<Vec<u8>, RtpError> {
         let mut out = Vec::with_capacity(12 + self.header.csrcs.len() * 4 + self.payload.len() + 4);
-
-        let cc = (self.header.csrcs.len() & 0x0F) as u8;
-        let has_ext = self.header.header_extension.is_some();
         let has_pad = self.padding_bytes > 0;
-        let vpxcc = (self.header.version & 0b11) << 6
-            | (u8::from(has_pad) << 5)
-            | (u8::from(has_ext) << 4)
-            | cc;
+        Self::encode_header(&self.header, has_pad, &mut out)?;
+
+        // For encode(), we *do not* add RTP padding by default because the
+        // session layer should decide this. If header.padding is true and
+        // padding_bytes > 0, we append that many zero octets and set P bit.
+        out.extend_from_slice(&self.payload);
+
+        if has_pad {
+            // Add (padding_bytes - 1) filler bytes (any value is legal; use 0) and end with the pad count
+            if self.padding_bytes > 1 {
+                out.extend(std::iter::repeat_n(0u8, (self.padding_bytes - 1) as usize));
+            }
+            out.push(self.padding_bytes);
+        }
+
+        Ok(out)
+    }
+
+    /// Writes `header` and then `payload`, given as consecutive slices, into
+    /// `out` (cleared first). Each slice is copied once, straight into the
+    /// packet, and `out` can be reused from packet to packet. No padding.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtpError::HeaderExtensionTooLong` if the header extension data is too large.
+    pub fn encode_parts_into(
+        header: &RtpHeader,
+        payload: &[&[u8]],
+        out: &mut Vec<u8>,
+    ) -> Result<(), RtpError> {
+        out.clear();
+        Self::encode_header(header, false, out)?;
+        for part in payload {
+            out.extend_from_slice(part);
+        }
+        Ok(())
+    }
+
+    fn encode_header(header: &RtpHeader, has_pad: bool, out: &mut Vec<u8>) -> Result<(), RtpError> {
+        let cc = (header.csrcs.len() & 0x0F) as u8;
+        let has_ext = header.header_extension.is_some();
+        let vpxcc =
+            (header.version & 0b11) << 6 | (u8::from(has_pad) << 5) | (u8::from(has_ext) << 4) | cc;
 
-        let m_pt = (u8::from(self.header.marker) << 7) | (self.header.payload_type & 0x7F);
+        let m_pt = (u8::from(header.marker) << 7) | (header.payload_type & 0x7F);
 
         out.push(vpxcc);
         out.push(m_pt);
-        out.extend_from_slice(&self.header.sequence_number.to_be_bytes());
-        out.extend_from_slice(&self.header.timestamp.to_be_bytes());
-        out.extend_from_slice(&self.header.ssrc.to_be_bytes());
+        out.extend_from_slice(&header.sequence_number.to_be_bytes());
+        out.extend_from_slice(&header.timestamp.to_be_bytes());
+        out.extend_from_slice(&header.ssrc.to_be_bytes());
 
-        for csrc in &self.header.csrcs {
+        for csrc in &header.csrcs {
             out.extend_from_slice(&csrc.to_be_bytes());
         }
 
-        if let Some(ext) = &self.header.header_extension {
+        if let Some(ext) = &header.header_extension {
             // RFC3550: 16-bit profile, 16-bit length in 32-bit words
             let words = ext.data.len().div_ceil(4) as u32;
             if words > u16::MAX as u32 {
@@ -87,21 +123,7 @@ impl RtpPacket {
                 out.extend(std::iter::repeat_n(0u8, pad));
             }
         }
-
-        // For encode(), we *do not* add RTP padding by default because the
-        // session layer should decide this. If header.padding is true and
-        // padding_bytes > 0, we append that many zero octets and set P bit.
-        out.extend_from_slice(&self.payload);
-
-        if has_pad {
-            // Add (padding_bytes - 1) filler bytes (any value is legal; use 0) and end with the pad count
-            if self.padding_bytes > 1 {
-                out.extend(std::iter::repeat_n(0u8, (self.padding_bytes - 1) as usize));
-            }
-            out.push(self.padding_bytes);
-        }
-
-        Ok(out)
+        Ok(())
     }
 
     /// Decode a single RTP packet from `buf`.
@@ -111,6 +133,35 @@ impl RtpPacket {
     /// Returns an `RtpError` if the buffer is too short, has an invalid version,
     /// or contains malformed header fields.
     pub fn decode(buf: &[u8]) -> Result<Self, RtpError> {
+        let (header, payload, padding_bytes) = Self::parse(buf)?;
+        Ok(RtpPacket {
+            header,
+            payload: buf[payload].to_vec(),
+            padding_bytes,
+        })
+    }
+
+    /// Like [`decode`](Self::decode), but keeps `buf`'s allocation as the
+    /// payload instead of copying it: the receive path hands buffers from
+    /// its pool straight through to the depacketizer.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`decode`](Self::decode).
+    pub fn decode_owned(mut buf: Vec<u8>) -> Result<Self, RtpError> {
+        let (header, payload, padding_bytes) = Self::parse(&buf)?;
+        buf.truncate(payload.end);
+        buf.drain(..payload.start);
+        Ok(RtpPacket {
+            header,
+            payload: buf,
+            padding_bytes,
+        })
+    }
+
+    /// Parses the header; returns it with the payload's range in `buf` and
+    /// the padding count.
+    fn parse(buf: &[u8]) -> Result<(RtpHeader, Range<usize>, u8), RtpError> {
         if buf.len() < 12 {
             return Err(RtpError::TooShort);
         }
@@ -206,11 +257,9 @@ impl RtpPacket {
             payload_end -= pad as usize;
         }
 
-        let payload = if payload_end >= idx {
-            buf[idx..payload_end].to_vec()
-        } else {
+        if payload_end < idx {
             return Err(RtpError::Invalid);
-        };
+        }
 
         let header = RtpHeader {
             version,
@@ -225,11 +274,7 @@ impl RtpPacket {
             header_extension,
         };
 
-        Ok(RtpPacket {
-            header,
-            payload,
-            padding_bytes,
-        })
+        Ok((header, idx..payload_end, padding_bytes))
     }
 
     // Convenience getters
@@ -414,6 +459,38 @@ mod tests {
         assert_eq!(dec.padding_bytes, 0);
     }
 
+    #[test]
+    fn decode_owned_keeps_the_buffer() {
+        let mut pkt = RtpPacket::simple(96, false, 7, 3_000, 0x0102_0304, b"payload".to_vec());
+        pkt.header.csrcs = vec![0xDEAD_BEEF];
+        pkt.header.header_extension = Some(RtpHeaderExtension {
+            profile: 0xBEDE,
+            data: vec![0x10, 0xAB, 0, 0],
+        });
+        pkt.padding_bytes = 3;
+        let enc = pkt.encode().expect("encode");
+        let buf_ptr = enc.as_ptr();
+
+        let owned = RtpPacket::decode_owned(enc.clone()).expect("decode");
+        assert_eq!(owned, RtpPacket::decode(&enc).expect("decode"));
+        assert_eq!(owned.payload, b"payload");
+
+        let owned = RtpPacket::decode_owned(enc).expect("decode");
+        assert_eq!(owned.payload.as_ptr(), buf_ptr);
+    }
+
+    #[test]
+    fn encode_parts_matches_encode() {
+        let mut pkt = RtpPacket::simple(96, true, 9, 1_234, 0x0A0B_0C0D, b"\x7c\x85idr".to_vec());
+        pkt.header.header_extension = Some(RtpHeaderExtension {
+            profile: 0xBEDE,
+            data: vec![0x10, 0x7F, 0, 0],
+        });
+        let mut out = vec![0xFF; 3];
+        RtpPacket::encode_parts_into(&pkt.header, &[b"\x7c\x85", b"idr"], &mut out).unwrap();
+        assert_eq!(out, pkt.encode().unwrap());
+    }
+
     #[test]
     fn roundtrip_with_padding_1() {
         let mut hdr = RtpHeader::new(111, 65_535, 0xDEAD_BEEF, 0x0102_0304).with_marker(false);
@@ -13,5 +13,6 @@ pub mod rx_tracker;
 pub mod rx_tracker_error;
 pub mod seq_ext;
 pub mod time;
+pub mod tx_history;
 pub mod tx_tracker;
 pub use rtp_session_c::RtpSession;
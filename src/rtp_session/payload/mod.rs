@@ -45,7 +45,7 @@ mod roundtrip_tests {
         let mut out = None;
 
         for ch in chunks {
-            let maybe = d.push_rtp(&ch.bytes, ch.marker, ts, seq);
+            let maybe = d.push_rtp(&ch.to_vec(), ch.marker, ts, seq);
             seq = seq.wrapping_add(1);
             if maybe.is_some() {
                 out = maybe;
@@ -1,8 +1,17 @@
+use std::cmp::Reverse;
+
 #[derive(Debug, Clone)]
 pub struct RtpCodec {
     pub payload_type: u8,
     pub clock_rate: u32, // e.g., 90_000 video, 48_000 Opus
     pub name: String,
+    /// Audio channels (`rtpmap` encoding parameter), 1 when absent.
+    pub channels: u16,
+    /// Remote `fmtp` parameters, if any.
+    pub fmtp: Option<String>,
+    /// Id the remote gave the audio level header extension on this codec's
+    /// m-line (`a=extmap`), if it offered one.
+    pub audio_level_ext: Option<u8>,
 }
 
 impl RtpCodec {
@@ -11,6 +20,9 @@ impl RtpCodec {
             payload_type: pt,
             clock_rate: clock,
             name: String::new(),
+            channels: 1,
+            fmtp: None,
+            audio_level_ext: None,
         }
     }
 
@@ -19,6 +31,97 @@ impl RtpCodec {
             payload_type: pt,
             clock_rate: clock,
             name: name.into(),
+            channels: 1,
+            fmtp: None,
+            audio_level_ext: None,
         }
     }
+
+    #[must_use]
+    pub fn with_channels(mut self, channels: u16) -> Self {
+        self.channels = channels.max(1);
+        self
+    }
+
+    /// `true` if `other` is the same codec, whatever its payload type.
+    #[must_use]
+    pub fn same_codec(&self, other: &Self) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name) && self.clock_rate == other.clock_rate
+    }
+
+    /// The entry for this codec in `remote`. Browsers offer H.264 several
+    /// times; the one we can send (packetization mode 1, Constrained
+    /// Baseline) wins.
+    #[must_use]
+    pub fn find_in<'a>(&self, remote: &'a [Self]) -> Option<&'a Self> {
+        remote
+            .iter()
+            .filter(|r| r.same_codec(self))
+            .min_by_key(|r| Reverse(h264_fmtp_score(r.fmtp.as_deref())))
+    }
+
+    /// This codec with the payload type the remote gave it, if it offered
+    /// it, so RTP goes out with the numbers the peer negotiated.
+    #[must_use]
+    pub fn negotiated(&self, remote: &[Self]) -> Self {
+        let mut codec = self.clone();
+        if let Some(r) = self.find_in(remote) {
+            codec.payload_type = r.payload_type;
+        }
+        codec
+    }
+}
+
+/// How well an H.264 `fmtp` suits what we send: FU-A needs
+/// `packetization-mode=1`, and the encoder produces Constrained Baseline.
+fn h264_fmtp_score(fmtp: Option<&str>) -> u8 {
+    let param = |key: &str| {
+        fmtp.unwrap_or_default()
+            .split(';')
+            .find_map(|p| p.trim().strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_ascii_lowercase)
+    };
+    let mode_1 = param("packetization-mode").is_some_and(|m| m == "1");
+    let profile = param("profile-level-id").unwrap_or_default();
+    u8::from(mode_1) * 4
+        + u8::from(profile.starts_with("42e0")) * 2
+        + u8::from(profile.starts_with("42"))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn negotiated_takes_the_remote_payload_type_of_the_best_h264() {
+        let remote = |pt, fmtp: &str| RtpCodec {
+            fmtp: Some(fmtp.into()),
+            ..RtpCodec::with_name(pt, 90_000, "H264")
+        };
+        let offer = [
+            RtpCodec::with_name(96, 90_000, "VP8"),
+            remote(
+                102,
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42001f",
+            ),
+            remote(
+                127,
+                "level-asymmetry-allowed=1;packetization-mode=0;profile-level-id=42e01f",
+            ),
+            remote(
+                125,
+                "level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f",
+            ),
+            RtpCodec::with_name(0, 8000, "PCMU"),
+        ];
+
+        let h264 = RtpCodec::with_name(96, 90_000, "H264");
+        assert_eq!(h264.negotiated(&offer).payload_type, 125);
+        let pcmu = RtpCodec::with_name(0, 8000, "pcmu");
+        assert_eq!(pcmu.negotiated(&offer).payload_type, 0);
+        let l16 = RtpCodec::with_name(98, 48_000, "L16");
+        assert!(l16.find_in(&offer).is_none());
+        assert_eq!(l16.negotiated(&offer).payload_type, 98);
+    }
 }
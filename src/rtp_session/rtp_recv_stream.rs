@@ -1,8 +1,10 @@
 use crate::core::events::EngineEvent;
+use crate::core::stats::{InboundRtpStats, now_unix_ms};
 use crate::log::log_sink::LogSink;
 use crate::media_transport::media_transport_event::RtpIn;
 use crate::rtcp::report_block::ReportBlock;
 use crate::rtcp::sender_info::SenderInfo;
+use crate::rtp::audio_level::{AudioLevel, LevelReporter};
 use crate::rtp::rtp_packet::RtpPacket;
 use crate::{sink_debug, sink_trace, sink_warn};
 
@@ -33,6 +35,9 @@ pub struct RtpRecvStream {
     jitter_buffer: BTreeMap<u16, BufferedPacket>,
     next_seq: Option<u16>,
     max_latency: Duration,
+
+    /// Throttles the audio levels read from the header extension.
+    levels: LevelReporter,
 }
 
 impl RtpRecvStream {
@@ -53,6 +58,7 @@ impl RtpRecvStream {
             jitter_buffer: BTreeMap::new(),
             next_seq: None,
             max_latency: Duration::from_millis(200),
+            levels: LevelReporter::new(),
         }
     }
 
@@ -91,11 +97,27 @@ impl RtpRecvStream {
         let arrival_rtp = self.instant_to_rtp_units(now);
 
         // 3) Update RX tracker immediately for stats
-        self.rx
-            .on_rtp(packet.seq(), packet.timestamp(), arrival_rtp);
+        let seq = packet.seq();
+        if !self.rx.on_rtp(seq, packet.timestamp(), arrival_rtp) {
+            sink_debug!(self.logger, "[RTP] duplicate packet seq={}", seq);
+            return;
+        }
+
+        // Behind the playout point: the buffer already skipped it
+        if let Some(next) = self.next_seq
+            && (seq.wrapping_sub(next) as i16) < 0
+        {
+            self.rx.on_late();
+            sink_debug!(
+                self.logger,
+                "[RTP] late packet seq={} (playing {})",
+                seq,
+                next
+            );
+            return;
+        }
 
         // 4) Buffer the packet for reordering and playout
-        let seq = packet.seq();
         let buffered_packet = BufferedPacket {
             packet,
             received_at: now,
@@ -114,6 +136,21 @@ impl RtpRecvStream {
         self.process_buffer();
     }
 
+    /// Reports the sender's audio level, if the packet carries one.
+    fn report_audio_level(&mut self, packet: &RtpPacket) {
+        let Some(id) = self.codec.audio_level_ext else {
+            return;
+        };
+        if let Some(ext) = &packet.header.header_extension
+            && let Some(level) = AudioLevel::from_extension(ext, id)
+            && let Some(level) = self.levels.offer(Instant::now(), level)
+        {
+            let _ = self
+                .event_transmitter
+                .send(EngineEvent::RemoteAudioLevel(level));
+        }
+    }
+
     fn process_buffer(&mut self) {
         let Some(s) = self.next_seq else {
             return; // Nothing to do if not initialized
@@ -136,6 +173,8 @@ impl RtpRecvStream {
                     sink_trace!(self.logger, "[Recv Stream {}] RTP Packet seq: {}", ssrc, s);
                 }
 
+                self.report_audio_level(&packet);
+
                 let evt = EngineEvent::RtpIn(RtpIn {
                     pt: packet.payload_type(),
                     marker: packet.marker(),
@@ -222,4 +261,26 @@ impl RtpRecvStream {
         self.remote_ssrc
             .map(|ssrc| self.rx.build_report_block(ssrc))
     }
+
+    /// Snapshot of this stream for `get_stats()`. `None` until the SSRC is known.
+    #[must_use]
+    pub fn stats(&self) -> Option<InboundRtpStats> {
+        let ssrc = self.remote_ssrc?;
+        Some(InboundRtpStats {
+            id: format!("InboundRTP_{ssrc}"),
+            timestamp_ms: now_unix_ms(),
+            ssrc,
+            codec: self.codec.name.clone(),
+            payload_type: self.codec.payload_type,
+            packets_received: self.rx.packets_received(),
+            packets_lost: self.rx.cumulative_lost(),
+            highest_seq: self.rx.highest_ext_seq(),
+            jitter: self.rx.jitter(),
+            clock_rate: self.codec.clock_rate,
+            packets_duplicated: self.rx.duplicates(),
+            packets_reordered: self.rx.reordered(),
+            max_reorder_depth: self.rx.max_reorder_depth(),
+            packets_late: self.rx.late(),
+        })
+    }
 }
@@ -1,15 +1,29 @@
 use std::{
+    mem,
     net::{SocketAddr, UdpSocket},
     sync::{Arc, Mutex},
     time::Instant,
 };
 
 use super::rtp_send_error::RtpSendError;
-use super::{rtp_codec::RtpCodec, rtp_send_config::RtpSendConfig, tx_tracker::TxTracker};
+use super::{
+    rtp_codec::RtpCodec, rtp_send_config::RtpSendConfig, tx_history::TxHistory,
+    tx_tracker::TxTracker,
+};
 
+use crate::core::socket_stats::SocketCounters;
+use crate::core::stats::{OutboundRtpStats, now_unix_ms};
+use crate::core::{rtc_event_log::RtcEventLog, udp_batch};
 use crate::rtp_session::time;
 use crate::{congestion_controller::NetworkMetrics, srtp::srtp_context::SrtpContext};
-use crate::{log::log_sink::LogSink, rtp::rtp_packet::RtpPacket};
+use crate::{
+    log::log_sink::LogSink,
+    media_transport::payload::rtp_payload_chunk::RtpPayloadChunk,
+    rtp::{rtp_header::RtpHeader, rtp_header_extension::RtpHeaderExtension, rtp_packet::RtpPacket},
+};
+
+/// Initial size of the packet buffer: an MTU, SRTP tag included.
+const PACKET_BUF_CAPACITY: usize = 1500;
 use crate::{
     rtcp::{report_block::ReportBlock, sender_info::SenderInfo, sender_report::SenderReport},
     sink_warn,
@@ -26,12 +40,22 @@ pub struct RtpSendStream {
 
     sock: Arc<UdpSocket>,
     peer: SocketAddr,
+    /// Shared with everything else sending on `sock`.
+    counters: Arc<SocketCounters>,
 
     last_sr_built: Instant,
     last_pkt_sent: Instant,
 
     pub tx: TxTracker,
+    /// What was sent recently, for NACKed retransmissions and loss stats.
+    history: TxHistory,
     srtp_context: Option<Arc<Mutex<SrtpContext>>>,
+    event_log: Option<RtcEventLog>,
+    /// Reused for every packet: header, payload and SRTP tag are written
+    /// here and sent from here.
+    packet_buf: Vec<u8>,
+    /// Same, for the packets of a frame sent together.
+    batch_bufs: Vec<Vec<u8>>,
 }
 
 impl RtpSendStream {
@@ -40,7 +64,9 @@ impl RtpSendStream {
         cfg: RtpSendConfig,
         sock: Arc<UdpSocket>,
         peer: SocketAddr,
+        counters: Arc<SocketCounters>,
         srtp_context: Option<Arc<Mutex<SrtpContext>>>,
+        event_log: Option<RtcEventLog>,
     ) -> Self {
         use rand::{RngCore, rngs::OsRng};
         Self {
@@ -53,10 +79,15 @@ impl RtpSendStream {
             octet_count: 0,
             sock,
             peer,
+            counters,
             last_sr_built: Instant::now(),
             last_pkt_sent: Instant::now(),
             tx: TxTracker::default(),
+            history: TxHistory::new(),
             srtp_context,
+            event_log,
+            packet_buf: Vec::with_capacity(PACKET_BUF_CAPACITY),
+            batch_bufs: Vec::new(),
         }
     }
 
@@ -107,6 +138,51 @@ impl RtpSendStream {
         NetworkMetrics::from_tracker(&self.tx, rb)
     }
 
+    /// Snapshot of this stream for `get_stats()`.
+    #[must_use]
+    pub fn stats(&self) -> OutboundRtpStats {
+        OutboundRtpStats {
+            id: format!("OutboundRTP_{}", self.local_ssrc),
+            timestamp_ms: now_unix_ms(),
+            ssrc: self.local_ssrc,
+            codec: self.codec.name.clone(),
+            payload_type: self.codec.payload_type,
+            packets_sent: self.packet_count,
+            bytes_sent: self.octet_count,
+            remote_packets_lost: self.tx.remote_cum_lost,
+            remote_fraction_lost: self.tx.remote_fraction_lost,
+            remote_jitter: self.tx.remote_jitter,
+            nacked_packets: self.history.nacked(),
+            retransmitted_packets: self.history.retransmitted(),
+            unrecoverable_packets: self.history.unrecoverable(),
+            nack_delay_ms: self.history.mean_nack_delay_ms(),
+            rtt_ms: self.tx.rtt_ms,
+        }
+    }
+
+    /// Resends the packets a Generic NACK from the remote asks for, those
+    /// still in the history. Returns how many were resent.
+    pub fn on_nack(&mut self, entries: &[(u16, u16)]) -> usize {
+        let resend = self.history.on_nack(entries, Instant::now());
+        let mut sent = 0;
+        for packet in resend {
+            match self.counters.record(self.sock.send_to(&packet, self.peer)) {
+                Ok(_) => {
+                    if let Some(log) = &self.event_log {
+                        log.rtp_out(&packet);
+                    }
+                    self.history.note_retransmitted();
+                    sent += 1;
+                }
+                Err(e) => {
+                    sink_warn!(self.logger, "[RTP] retransmission failed: {}", e);
+                    break;
+                }
+            }
+        }
+        sent
+    }
+
     /// Optional: expose some outbound health summary for logging/telemetry.
     pub fn outbound_summary(&self) -> String {
         let rtt = self
@@ -115,7 +191,7 @@ impl RtpSendStream {
             .map(|v| format!("{v} ms"))
             .unwrap_or_else(|| "-".into());
         format!(
-            "SSRC={:#010x} sent={} pkts, {} bytes; remote_lost={} (frac={}), remote_jitter={}, RTT={}",
+            "SSRC={:#010x} sent={} pkts, {} bytes; remote_lost={} (frac={}), remote_jitter={}, RTT={}, nacked={} (resent={}, gone={})",
             self.local_ssrc,
             self.packet_count,
             self.octet_count,
@@ -123,49 +199,195 @@ impl RtpSendStream {
             self.tx.remote_fraction_lost,
             self.tx.remote_jitter,
             rtt,
+            self.history.nacked(),
+            self.history.retransmitted(),
+            self.history.unrecoverable(),
         )
     }
     /// Send one RTP payload with explicit timestamp & marker.
     /// Increments seqno and updates SR counters. Does NOT change pacing itself.
-    #[allow(clippy::expect_used)]
     pub fn send_rtp_payload(
         &mut self,
         payload: &[u8],
         timestamp: u32,
         marker: bool,
     ) -> Result<(), RtpSendError> {
-        let pkt = RtpPacket::simple(
+        self.send_rtp_payload_as(self.codec.payload_type, payload, timestamp, marker)
+    }
+
+    /// Like [`send_rtp_payload`](Self::send_rtp_payload), with another payload
+    /// type on the same SSRC and sequence space (e.g. telephone events on
+    /// the audio stream).
+    pub fn send_rtp_payload_as(
+        &mut self,
+        payload_type: u8,
+        payload: &[u8],
+        timestamp: u32,
+        marker: bool,
+    ) -> Result<(), RtpSendError> {
+        self.send_packet(payload_type, &[payload], timestamp, marker, None)
+    }
+
+    /// Like [`send_rtp_payload`](Self::send_rtp_payload), with a header
+    /// extension (e.g. the audio level).
+    pub fn send_rtp_payload_with_extension(
+        &mut self,
+        payload: &[u8],
+        timestamp: u32,
+        marker: bool,
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtpSendError> {
+        self.send_packet(
             self.codec.payload_type,
-            marker,
-            self.seq,
+            &[payload],
             timestamp,
-            self.local_ssrc,
-            payload.to_vec(),
-        );
-        let mut encoded = pkt.encode()?;
+            marker,
+            extension,
+        )
+    }
+
+    /// Sends the chunks of one frame. Each is written straight into its
+    /// packet and the packets leave together, in one system call per
+    /// [`BATCH`](udp_batch::BATCH) with the `udp-batch` feature.
+    pub fn send_rtp_chunks(
+        &mut self,
+        chunks: &[RtpPayloadChunk],
+        timestamp: u32,
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtpSendError> {
+        let mut bufs = mem::take(&mut self.batch_bufs);
+        if bufs.len() < chunks.len() {
+            bufs.resize_with(chunks.len(), || Vec::with_capacity(PACKET_BUF_CAPACITY));
+        }
+        let result = self.send_chunks_in(&mut bufs[..chunks.len()], chunks, timestamp, extension);
+        self.batch_bufs = bufs;
+        result
+    }
+
+    fn send_chunks_in(
+        &mut self,
+        bufs: &mut [Vec<u8>],
+        chunks: &[RtpPayloadChunk],
+        timestamp: u32,
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtpSendError> {
+        let first_seq = self.seq;
+        for (buf, chunk) in bufs.iter_mut().zip(chunks) {
+            self.write_packet(
+                buf,
+                self.codec.payload_type,
+                &chunk.slices(),
+                timestamp,
+                chunk.marker,
+                extension,
+            )?;
+        }
+        let sent = match udp_batch::send_batch(&self.sock, self.peer, bufs) {
+            Ok(sent) => sent,
+            Err(e) => {
+                self.counters.send_failed(&e);
+                return Err(e.into());
+            }
+        };
+        for buf in &bufs[..sent] {
+            self.counters.sent(buf.len());
+        }
+        if sent < bufs.len() {
+            self.counters.batch_cut_short(bufs.len() - sent);
+            sink_warn!(
+                self.logger,
+                "[RTP] sent {} of {} packets of the frame",
+                sent,
+                bufs.len()
+            );
+        }
+        let now = Instant::now();
+        for ((buf, chunk), seq) in bufs[..sent].iter().zip(chunks).zip(0u16..) {
+            if let Some(log) = &self.event_log {
+                log.rtp_out(buf);
+            }
+            self.history.record(first_seq.wrapping_add(seq), buf, now);
+            self.count_sent(chunk.len(), timestamp);
+        }
+        Ok(())
+    }
+
+    /// `payload` is the concatenation of its slices.
+    fn send_packet(
+        &mut self,
+        payload_type: u8,
+        payload: &[&[u8]],
+        timestamp: u32,
+        marker: bool,
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtpSendError> {
+        let mut buf = mem::take(&mut self.packet_buf);
+        let seq = self.seq;
+        let result = self
+            .write_packet(
+                &mut buf,
+                payload_type,
+                payload,
+                timestamp,
+                marker,
+                extension,
+            )
+            .and_then(|()| {
+                self.counters.record(self.sock.send_to(&buf, self.peer))?;
+                Ok(())
+            });
+        if result.is_ok() {
+            if let Some(log) = &self.event_log {
+                log.rtp_out(&buf);
+            }
+            self.history.record(seq, &buf, Instant::now());
+            self.count_sent(payload.iter().map(|part| part.len()).sum(), timestamp);
+        }
+        self.packet_buf = buf;
+        result
+    }
+
+    /// Writes the next packet into `out`, encrypted, and takes its sequence
+    /// number: once protected, a sequence number is never reused, even if
+    /// the send fails.
+    #[allow(clippy::expect_used)]
+    fn write_packet(
+        &mut self,
+        out: &mut Vec<u8>,
+        payload_type: u8,
+        payload: &[&[u8]],
+        timestamp: u32,
+        marker: bool,
+        extension: Option<&RtpHeaderExtension>,
+    ) -> Result<(), RtpSendError> {
+        let header = RtpHeader::new(payload_type, self.seq, timestamp, self.local_ssrc)
+            .with_marker(marker)
+            .with_extension(extension.cloned());
+        RtpPacket::encode_parts_into(&header, payload, out)?;
 
         // SRTP Protect
         if let Some(ctx) = &self.srtp_context {
             // ssrc se necesita para el ROC
             ctx.lock()
                 .expect("SRTP outbound lock poisoned")
-                .protect(self.local_ssrc, &mut encoded)
+                .protect(self.local_ssrc, out)
                 .map_err(|e| {
                     RtpSendError::SRTP(format!("[SRTP] could not protect packet: {e}").to_owned())
                 })?;
         } else {
             sink_warn!(self.logger, "Sending UNENCRYPTED packet");
         }
-        self.sock.send_to(&encoded, self.peer)?;
-        self.last_pkt_sent = Instant::now();
-
-        // Accounting
         self.seq = self.seq.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Accounting for a packet that left.
+    fn count_sent(&mut self, payload_len: usize, timestamp: u32) {
+        self.last_pkt_sent = Instant::now();
         self.packet_count = self.packet_count.wrapping_add(1);
-        self.octet_count = self.octet_count.wrapping_add(payload.len() as u32);
+        self.octet_count = self.octet_count.wrapping_add(payload_len as u32);
 
         // Track last timestamp used so SRs reflect the current media clock
         self.timestamp = timestamp;
-        Ok(())
     }
 }
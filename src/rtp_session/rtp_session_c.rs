@@ -16,16 +16,26 @@ use std::{
 use super::{
     outbound_track_handle::OutboundTrackHandle, rtp_codec::RtpCodec,
     rtp_recv_config::RtpRecvConfig, rtp_recv_stream::RtpRecvStream, rtp_send_config::RtpSendConfig,
-    rtp_send_stream::RtpSendStream, rtp_session_error::RtpSessionError,
+    rtp_send_error::RtpSendError, rtp_send_stream::RtpSendStream,
+    rtp_session_error::RtpSessionError,
 };
 use crate::{
-    core::events::EngineEvent,
+    connection_manager::rtp_map::PayloadTypeMap,
+    core::{
+        buffer_pool::BufferPool,
+        consent::PeerActivity,
+        events::EngineEvent,
+        rtc_event_log::RtcEventLog,
+        socket_stats::SocketCounters,
+        stats::{InboundRtpStats, OutboundRtpStats},
+        timers::Timers,
+    },
     log::log_sink::LogSink,
     rtcp::{
         packet_type::RtcpPacketType, receiver_report::ReceiverReport, report_block::ReportBlock,
         sdes::Sdes,
     },
-    rtp::rtp_packet::RtpPacket,
+    rtp::{rtp_header_extension::RtpHeaderExtension, rtp_packet::RtpPacket},
     sink_error,
 };
 use crate::{
@@ -37,6 +47,8 @@ use rand::{RngCore, rngs::OsRng};
 pub struct RtpSession {
     sock: Arc<UdpSocket>,
     peer: SocketAddr,
+    /// What is sent on `sock`, shared with the session.
+    counters: Arc<SocketCounters>,
 
     recv_streams: Arc<Mutex<HashMap<u32, RtpRecvStream>>>, // key: remote_ssrc
     pending_recv: Arc<Mutex<Vec<RtpRecvStream>>>,          // remote_ssrc=None
@@ -56,6 +68,16 @@ pub struct RtpSession {
     // Contextos SRTP protegidos por Mutex para acceso compartido
     srtp_inbound: Option<Arc<Mutex<SrtpContext>>>,
     srtp_outbound: Option<Arc<Mutex<SrtpContext>>>,
+    event_log: Option<RtcEventLog>,
+    /// Where `rx_media`'s buffers come from; RTCP and dropped packets go
+    /// back to it, RTP keeps its buffer as the payload.
+    buffers: BufferPool,
+    /// Runs the periodic RTCP sender.
+    timers: Timers,
+    /// What the negotiated payload types carry, both ways.
+    payload_types: Arc<PayloadTypeMap>,
+    /// Told of every packet that authenticates (see [`Self::with_activity`]).
+    activity: Option<Arc<PeerActivity>>,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -63,12 +85,15 @@ impl RtpSession {
     pub fn new(
         sock: Arc<UdpSocket>,
         peer: SocketAddr,
+        counters: Arc<SocketCounters>,
         tx_evt: Sender<EngineEvent>,
         logger: Arc<dyn LogSink>,
         rx_media: Receiver<Vec<u8>>,
         initial_recv: Vec<RtpRecvConfig>,
         initial_send: Vec<RtpSendConfig>,
         srtp_cfg: Option<SrtpSessionConfig>,
+        event_log: Option<RtcEventLog>,
+        buffers: BufferPool,
     ) -> Result<Self, RtpSessionError> {
         let (srtp_inbound, srtp_outbound) = if let Some(srtp_session_cfg) = &srtp_cfg {
             (
@@ -87,6 +112,7 @@ impl RtpSession {
         let this = Self {
             sock,
             peer,
+            counters,
             recv_streams: Arc::new(Mutex::new(HashMap::new())),
             pending_recv: Arc::new(Mutex::new(Vec::new())),
             send_streams: Arc::new(Mutex::new(HashMap::new())),
@@ -100,6 +126,11 @@ impl RtpSession {
             srtp_cfg,
             srtp_inbound,
             srtp_outbound,
+            event_log,
+            buffers,
+            timers: Timers::shared().clone(),
+            payload_types: Arc::new(PayloadTypeMap::default()),
+            activity: None,
         };
 
         this.add_recv_streams(initial_recv)?;
@@ -108,6 +139,32 @@ impl RtpSession {
         Ok(this)
     }
 
+    /// Sends RTCP on `timers` instead of the shared timer thread.
+    #[must_use]
+    pub fn with_timers(mut self, timers: Timers) -> Self {
+        self.timers = timers;
+        self
+    }
+
+    /// Sends with, and receives by, the payload types in `payload_types`
+    /// (the negotiated ones) instead of the numbers of the codecs given.
+    /// Set it before `start`.
+    #[must_use]
+    pub fn with_payload_types(mut self, payload_types: PayloadTypeMap) -> Self {
+        self.payload_types = Arc::new(payload_types);
+        self
+    }
+
+    /// Tells `activity` of every packet only the peer could have sent: one
+    /// that SRTP or SRTCP unprotects, or any valid one on a session without
+    /// SRTP.
+    /// Set it before `start`.
+    #[must_use]
+    pub fn with_activity(mut self, activity: Arc<PeerActivity>) -> Self {
+        self.activity = Some(activity);
+        self
+    }
+
     pub fn add_recv_stream(&self, cfg: RtpRecvConfig) -> Result<(), RtpSessionError> {
         let remote_ssrc = cfg.remote_ssrc;
         let st = RtpRecvStream::new(cfg, self.tx_evt.clone(), self.logger.clone());
@@ -126,10 +183,13 @@ impl RtpSession {
         Ok(())
     }
 
+    /// Adds a send stream, sending with the payload type the peer gave its
+    /// codec.
     pub fn add_send_stream(
         &self,
-        rtp_send_config: RtpSendConfig,
+        mut rtp_send_config: RtpSendConfig,
     ) -> Result<OutboundTrackHandle, RtpSessionError> {
+        rtp_send_config.codec = self.payload_types.negotiated(&rtp_send_config.codec);
         let ssrc = rtp_send_config.local_ssrc;
         let codec = rtp_send_config.codec.clone();
         let st = RtpSendStream::new(
@@ -137,7 +197,9 @@ impl RtpSession {
             rtp_send_config,
             Arc::clone(&self.sock),
             self.peer,
+            Arc::clone(&self.counters),
             self.srtp_outbound.clone(),
+            self.event_log.clone(),
         );
         self.send_streams.lock()?.insert(ssrc, st);
         Ok(OutboundTrackHandle {
@@ -164,6 +226,16 @@ impl RtpSession {
         let cfg = RtpSendConfig::new(codec);
         self.add_send_stream(cfg)
     }
+
+    /// Drops the send stream for `local_ssrc`. Returns `false` if it did not exist.
+    pub fn remove_send_stream(&self, local_ssrc: u32) -> Result<bool, RtpSessionError> {
+        Ok(self.send_streams.lock()?.remove(&local_ssrc).is_some())
+    }
+
+    /// Drops the recv stream for `remote_ssrc`. Returns `false` if it did not exist.
+    pub fn remove_recv_stream(&self, remote_ssrc: u32) -> Result<bool, RtpSessionError> {
+        Ok(self.recv_streams.lock()?.remove(&remote_ssrc).is_some())
+    }
     #[allow(clippy::expect_used)]
     pub fn start(&mut self) -> Result<(), RtpSessionError> {
         self.run.store(true, Ordering::SeqCst);
@@ -180,6 +252,15 @@ impl RtpSession {
         let tx_evt = self.tx_evt.clone();
         let logger = self.logger.clone();
         let srtp_inbound = self.srtp_inbound.clone();
+        let event_log = self.event_log.clone();
+        let buffers = self.buffers.clone();
+        let payload_types = Arc::clone(&self.payload_types);
+        let activity = self.activity.clone();
+        let heard = move || {
+            if let Some(activity) = &activity {
+                activity.heard();
+            }
+        };
 
         thread::spawn(move || {
             while run.load(Ordering::SeqCst) {
@@ -187,13 +268,26 @@ impl RtpSession {
                     Ok(mut pkt) => {
                         if pkt.len() < 2 {
                             sink_error!(&logger, "[RTP] packet too short");
+                            buffers.give(pkt);
                             continue;
                         }
 
                         // ---- RTCP ----
                         if is_rtcp(&pkt) {
-                            // TODO: Implement SRTCP unprotect here in the future.
-                            // For now, pass cleartext or drop if peer encrypts RTCP.
+                            if let Some(ctx) = &srtp_inbound
+                                && let Err(e) = ctx
+                                    .lock()
+                                    .expect("SRTP inbound lock poisoned")
+                                    .unprotect_rtcp(&mut pkt)
+                            {
+                                sink_warn!(&logger, "[SRTCP] Unprotect failed: {}", e);
+                                buffers.give(pkt);
+                                continue;
+                            }
+                            heard();
+                            if let Some(log) = &event_log {
+                                log.rtcp_in(&pkt);
+                            }
                             if let Err(e) = handle_rtcp(
                                 &pkt,
                                 &recv_map,
@@ -204,14 +298,19 @@ impl RtpSession {
                             ) {
                                 sink_error!(&logger, "[RTCP] error: {e:?}");
                             }
+                            buffers.give(pkt);
                             continue;
                         }
 
                         // ---- RTP fast-path ----
                         if pkt.len() < 12 || (pkt[0] >> 6) != 2 {
                             sink_error!(&logger, "[RTP] invalid header/version");
+                            buffers.give(pkt);
                             continue;
                         }
+                        if let Some(log) = &event_log {
+                            log.rtp_in(&pkt);
+                        }
 
                         // 3. SRTP Unprotect
                         if let Some(ctx) = &srtp_inbound {
@@ -223,20 +322,26 @@ impl RtpSession {
                             {
                                 Ok(_) => {
                                     // Success: pkt is now cleartext RTP
+                                    heard();
                                 }
                                 Err(e) => {
                                     sink_warn!(&logger, "[SRTP] Unprotect failed: {}", e);
                                     // Drop the packet! Do not try to parse garbage.
+                                    buffers.give(pkt);
                                     continue;
                                 }
                             }
                         }
 
                         // Decode RTP (adapt if your API returns Result)
-                        let Ok(rtp) = RtpPacket::decode(&pkt) else {
+                        // The buffer becomes the payload, no copy
+                        let Ok(rtp) = RtpPacket::decode_owned(pkt) else {
                             sink_error!(logger, " RTP] decode failed");
                             continue;
                         };
+                        if srtp_inbound.is_none() {
+                            heard();
+                        }
 
                         sink_trace!(logger, "[RTP Session] Received RTP packet");
 
@@ -264,13 +369,26 @@ impl RtpSession {
                             continue;
                         }
 
-                        // 3) Unknown SSRC/PT
+                        // 3) A new SSRC on another negotiated PT: receive it as
+                        // the codec that PT carries
+                        if let Some(codec) = payload_types.get(pt) {
+                            let cfg = RtpRecvConfig::new(codec.clone(), Some(ssrc));
+                            let mut st = RtpRecvStream::new(cfg, tx_evt.clone(), logger.clone());
+                            st.receive_rtp_packet(rtp);
+                            if let Ok(mut map) = recv_map.lock() {
+                                map.insert(ssrc, st);
+                            }
+                            continue;
+                        }
+
+                        // 4) Unknown SSRC/PT
                         sink_warn!(
                             logger,
                             "[RTP] unknown remote SSRC={:#010x} PT={}, couldn't map codec to payload type on the pool of pending receivers",
                             ssrc,
                             pt
                         );
+                        buffers.give(rtp.payload);
                     }
                     Err(RecvTimeoutError::Timeout) => {
                         sink_trace!(logger, "[RTP Session] Received nothing in timeout");
@@ -285,6 +403,7 @@ impl RtpSession {
         // === periodic RTCP sender: SR, RR, SDES ===
         let run2 = Arc::clone(&self.run);
         let sock = Arc::clone(&self.sock);
+        let counters = Arc::clone(&self.counters);
         let peer = self.peer;
         let recv_map2 = Arc::clone(&self.recv_streams);
         let send_map2 = Arc::clone(&self.send_streams);
@@ -293,67 +412,79 @@ impl RtpSession {
         let interval = self.rtcp_interval;
         let rr_ssrc = self.local_rtcp_ssrc;
         let cname = self.cname.clone();
+        let event_log2 = self.event_log.clone();
+        let srtp_outbound = self.srtp_outbound.clone();
 
-        thread::spawn(move || {
-            while run2.load(Ordering::SeqCst) {
-                std::thread::sleep(interval);
-
-                let mut comp_pkt = Vec::new();
-
-                // Build Sender Reports (SR) for each sending stream ---
-                if let Ok(mut guard) = send_map2.lock() {
-                    for st in guard.values_mut() {
-                        if let Some(sr) = st.maybe_build_sr() {
-                            let mut sr_bytes = Vec::new();
-                            if let Err(e) = sr.encode_into(&mut sr_bytes) {
-                                sink_error!(logger2, "[RTCP] failed to encode SR: {e}");
-                                continue;
-                            }
+        self.timers.schedule(interval, move || {
+            if !run2.load(Ordering::SeqCst) {
+                return None;
+            }
 
-                            comp_pkt.extend_from_slice(&sr_bytes);
+            let mut comp_pkt = Vec::new();
 
-                            sink_trace!(logger2, "[RTCP] tx built SR ssrc={:#010x}", st.local_ssrc);
+            // Build Sender Reports (SR) for each sending stream ---
+            if let Ok(mut guard) = send_map2.lock() {
+                for st in guard.values_mut() {
+                    if let Some(sr) = st.maybe_build_sr() {
+                        let mut sr_bytes = Vec::new();
+                        if let Err(e) = sr.encode_into(&mut sr_bytes) {
+                            sink_error!(logger2, "[RTCP] failed to encode SR: {e}");
+                            continue;
                         }
-                    }
-                }
 
-                // Build one Receiver Report (RR) for all receiving streams ---
-                let mut blocks: Vec<ReportBlock> = Vec::new();
-                if let Ok(mut guard) = recv_map2.lock() {
-                    for st in guard.values_mut() {
-                        if let Some(rb) = st.build_report_block() {
-                            blocks.push(rb);
-                        }
+                        comp_pkt.extend_from_slice(&sr_bytes);
+
+                        sink_trace!(logger2, "[RTCP] tx built SR ssrc={:#010x}", st.local_ssrc);
                     }
                 }
+            }
 
-                // Only send RR if there are blocks. If we are a pure sender, we might not have any.
-                if !blocks.is_empty() {
-                    let rr = ReceiverReport::new(rr_ssrc, blocks);
-                    let mut rr_bytes = Vec::new();
-                    if let Err(e) = rr.encode_into(&mut rr_bytes) {
-                        sink_error!(logger2, "[RTCP] failed to encode RR: {e}");
-                    } else {
-                        comp_pkt.extend_from_slice(&rr_bytes);
-                        sink_trace!(logger2, "[RTCP] tx built RR");
+            // Build one Receiver Report (RR) for all receiving streams ---
+            let mut blocks: Vec<ReportBlock> = Vec::new();
+            if let Ok(mut guard) = recv_map2.lock() {
+                for st in guard.values_mut() {
+                    if let Some(rb) = st.build_report_block() {
+                        blocks.push(rb);
                     }
                 }
+            }
 
-                // --- 3) Build SDES with CNAME ---
-                // Note: could be conditional if you only want to send it once or twice.
-                let sdes = Sdes::cname(rr_ssrc, cname.clone());
-                let mut sdes_bytes = Vec::new();
-                if let Err(e) = sdes.encode_into(&mut sdes_bytes) {
-                    sink_error!(logger2, "[RTCP] failed to encode SDES: {e}");
+            // Only send RR if there are blocks. If we are a pure sender, we might not have any.
+            if !blocks.is_empty() {
+                let rr = ReceiverReport::new(rr_ssrc, blocks);
+                let mut rr_bytes = Vec::new();
+                if let Err(e) = rr.encode_into(&mut rr_bytes) {
+                    sink_error!(logger2, "[RTCP] failed to encode RR: {e}");
                 } else {
-                    comp_pkt.extend_from_slice(&sdes_bytes);
+                    comp_pkt.extend_from_slice(&rr_bytes);
+                    sink_trace!(logger2, "[RTCP] tx built RR");
                 }
+            }
+
+            // --- 3) Build SDES with CNAME ---
+            // Note: could be conditional if you only want to send it once or twice.
+            let sdes = Sdes::cname(rr_ssrc, cname.clone());
+            let mut sdes_bytes = Vec::new();
+            if let Err(e) = sdes.encode_into(&mut sdes_bytes) {
+                sink_error!(logger2, "[RTCP] failed to encode SDES: {e}");
+            } else {
+                comp_pkt.extend_from_slice(&sdes_bytes);
+            }
 
-                // --- 4) Send compound packet if not empty ---
-                if !comp_pkt.is_empty() {
-                    let _ = sock.send_to(&comp_pkt, peer);
+            // --- 4) Send compound packet if not empty ---
+            if !comp_pkt.is_empty() {
+                if let Some(log) = &event_log2 {
+                    log.rtcp_out(&comp_pkt);
+                }
+                match protect_rtcp(srtp_outbound.as_ref(), &mut comp_pkt) {
+                    Ok(()) => {
+                        let _ = counters.record(sock.send_to(&comp_pkt, peer));
+                    }
+                    Err(e) => sink_error!(logger2, "[SRTCP] could not protect packet: {e}"),
                 }
             }
+
+            Some(interval)
         });
 
         Ok(())
@@ -368,10 +499,64 @@ impl RtpSession {
         let pli = PictureLossIndication::new(self.local_rtcp_ssrc, remote_ssrc);
         let mut buf = Vec::new();
         let _ = pli.encode_into(&mut buf);
-        let _ = self.sock.send_to(&buf, self.peer);
+        if let Some(log) = &self.event_log {
+            log.rtcp_out(&buf);
+        }
+        if let Err(e) = protect_rtcp(self.srtp_outbound.as_ref(), &mut buf) {
+            sink_error!(self.logger, "[SRTCP] could not protect PLI: {e}");
+            return;
+        }
+        let _ = self.counters.record(self.sock.send_to(&buf, self.peer));
         sink_trace!(self.logger, "[RTCP] tx sent PLI media_ssrc={remote_ssrc}");
     }
 
+    /// Sends a packet built elsewhere as is, bypassing the send streams (its
+    /// SSRC needs none): how a forwarder relays media it did not encode.
+    pub fn forward_rtp(&self, packet: &RtpPacket) -> Result<(), RtpSessionError> {
+        let ssrc = packet.ssrc();
+        let mut encoded = packet.encode()?;
+        if let Some(ctx) = &self.srtp_outbound {
+            ctx.lock()?
+                .protect(ssrc, &mut encoded)
+                .map_err(|e| RtpSessionError::SendStream {
+                    source: RtpSendError::SRTP(format!("[SRTP] could not protect packet: {e}")),
+                    ssrc,
+                })?;
+        }
+        self.counters
+            .record(self.sock.send_to(&encoded, self.peer))
+            .map_err(|e| RtpSessionError::SendStream {
+                source: RtpSendError::Network(e),
+                ssrc,
+            })?;
+        if let Some(log) = &self.event_log {
+            log.rtp_out(&encoded);
+        }
+        Ok(())
+    }
+
+    /// Per-stream statistics: `(outbound, inbound)`, sorted by SSRC.
+    #[allow(clippy::expect_used)]
+    pub fn stats(&self) -> (Vec<OutboundRtpStats>, Vec<InboundRtpStats>) {
+        let mut outbound: Vec<_> = self
+            .send_streams
+            .lock()
+            .expect("send_streams lock poisoned")
+            .values()
+            .map(RtpSendStream::stats)
+            .collect();
+        let mut inbound: Vec<_> = self
+            .recv_streams
+            .lock()
+            .expect("recv_streams lock poisoned")
+            .values()
+            .filter_map(RtpRecvStream::stats)
+            .collect();
+        outbound.sort_by_key(|s| s.ssrc);
+        inbound.sort_by_key(|s| s.ssrc);
+        (outbound, inbound)
+    }
+
     /// Convenience: does this remote SSRC exist as a recv stream?
     #[allow(clippy::expect_used)]
     pub fn has_recv_ssrc(&self, remote_ssrc: u32) -> bool {
@@ -399,25 +584,46 @@ impl RtpSession {
             })
     }
 
+    /// Sends one payload on `local_ssrc` with a payload type other than the
+    /// stream's codec (telephone events on the audio stream).
+    pub fn send_rtp_payload_as(
+        &self,
+        local_ssrc: u32,
+        payload_type: u8,
+        payload: &[u8],
+        timestamp: u32,
+        marker: bool,
+    ) -> Result<(), RtpSessionError> {
+        let mut g = self.send_streams.lock()?;
+        let st = g
+            .get_mut(&local_ssrc)
+            .ok_or(RtpSessionError::SendStreamMissing { ssrc: local_ssrc })?;
+        st.send_rtp_payload_as(payload_type, payload, timestamp, marker)
+            .map_err(|source| RtpSessionError::SendStream {
+                source,
+                ssrc: local_ssrc,
+            })
+    }
+
+    /// Sends the chunks of one frame; `extension`, if any, goes on every
+    /// packet.
     pub fn send_rtp_chunks_for_frame(
         &self,
         local_ssrc: u32,
         chunks: &[RtpPayloadChunk],
         timestamp: u32,
+        extension: Option<&RtpHeaderExtension>,
     ) -> Result<(), RtpSessionError> {
         let mut g = self.send_streams.lock()?;
         let st = g
             .get_mut(&local_ssrc)
             .ok_or(RtpSessionError::SendStreamMissing { ssrc: local_ssrc })?;
 
-        for ch in chunks {
-            st.send_rtp_payload(&ch.bytes, timestamp, ch.marker)
-                .map_err(|source| RtpSessionError::SendStream {
-                    source,
-                    ssrc: local_ssrc,
-                })?;
-        }
-        Ok(())
+        st.send_rtp_chunks(chunks, timestamp, extension)
+            .map_err(|source| RtpSessionError::SendStream {
+                source,
+                ssrc: local_ssrc,
+            })
     }
 }
 
@@ -439,6 +645,18 @@ fn is_rtcp(pkt: &[u8]) -> bool {
     matches!(pkt[1], 200..=206)
 }
 
+/// Encrypts `rtcp` in place if the session has SRTP: peers on DTLS-SRTP
+/// drop RTCP that is not SRTCP.
+fn protect_rtcp(srtp: Option<&Arc<Mutex<SrtpContext>>>, rtcp: &mut Vec<u8>) -> Result<(), String> {
+    match srtp {
+        Some(ctx) => ctx
+            .lock()
+            .map_err(|_| "SRTP outbound lock poisoned")?
+            .protect_rtcp(rtcp),
+        None => Ok(()),
+    }
+}
+
 #[inline]
 fn ntp_to_compact(msw: u32, lsw: u32) -> u32 {
     (msw << 16) | (lsw >> 16)
@@ -522,23 +740,29 @@ fn handle_rtcp(
 
             RtcpPacket::Pli(pli) => {
                 // Inbound PLI means the remote wants a keyframe for media_ssrc
-                // Route to the *sender* stream of that SSRC, or surface an event:
                 sink_trace!(
                     logger,
                     "[RTCP][PLI] keyframe requested for ssrc={:#010x}",
                     pli.media_ssrc
-                )
-                // If you have encoder wiring, signal it here.
+                );
+                let _ = tx_evt.send(EngineEvent::KeyframeRequested {
+                    ssrc: pli.media_ssrc,
+                });
             }
 
             RtcpPacket::Nack(nack) => {
-                // Inbound NACK asks us to retransmit lost seqnos on media_ssrc
-                // Route to the *sender* stream (implement your RTX/repair path there)
+                // Inbound NACK asks us to retransmit lost seqnos on media_ssrc:
+                // the sender stream resends them from its history
+                let resent = send_map.lock().ok().and_then(|mut g| {
+                    g.get_mut(&nack.media_ssrc)
+                        .map(|st| st.on_nack(&nack.entries))
+                });
                 sink_trace!(
                     logger,
-                    "[RTCP][NACK] for media_ssrc={:#010x} fci_count={}",
+                    "[RTCP][NACK] for media_ssrc={:#010x} fci_count={} resent={:?}",
                     nack.media_ssrc,
-                    nack.entries.len()
+                    nack.entries.len(),
+                    resent
                 )
             }
 
@@ -43,6 +43,8 @@ impl fmt::Display for RtpSessionError {
     }
 }
 
+impl std::error::Error for RtpSessionError {}
+
 impl From<RtcpError> for RtpSessionError {
     fn from(e: RtcpError) -> Self {
         Self::Rtcp(e)
@@ -2,6 +2,10 @@ use super::seq_ext::SeqExt;
 use super::time;
 use crate::rtcp::report_block::ReportBlock;
 
+/// Sequence numbers below the highest that are remembered to tell
+/// duplicates from reordered packets.
+const HISTORY: u32 = 128;
+
 #[derive(Debug, Default, Clone)]
 pub struct RxTracker {
     // sequence/loss
@@ -12,6 +16,13 @@ pub struct RxTracker {
     expected_prev: u32,
     received_prev: u32,
 
+    // reordering: bit i set = `highest_ext_seq - i` received
+    history: u128,
+    duplicates: u32,
+    reordered: u32,
+    max_reorder_depth: u32,
+    late: u32,
+
     // jitter (RFC3550 A.8)
     jitter: u32,
     last_transit: Option<u32>,
@@ -22,15 +33,38 @@ pub struct RxTracker {
 }
 
 impl RxTracker {
-    /// Call on every *unique* RTP packet (after dup filtering) for this SSRC.
-    /// `arrival_rtp_units` is arrival time expressed in RTP clock units (use monotonic clock).
-    pub fn on_rtp(&mut self, seq: u16, rtp_ts: u32, arrival_rtp_units: u32) {
+    /// Call on every RTP packet for this SSRC. `arrival_rtp_units` is
+    /// arrival time expressed in RTP clock units (use monotonic clock).
+    /// Returns `false` for a duplicate, which is counted as such and
+    /// otherwise ignored.
+    pub fn on_rtp(&mut self, seq: u16, rtp_ts: u32, arrival_rtp_units: u32) -> bool {
         let ext = self.seqext.update(seq);
         if self.base_ext_seq.is_none() {
             self.base_ext_seq = Some(ext);
-        }
-        if ext > self.highest_ext_seq {
             self.highest_ext_seq = ext;
+            self.history = 1;
+        } else if ext > self.highest_ext_seq {
+            let ahead = ext - self.highest_ext_seq;
+            self.history = if ahead >= HISTORY {
+                1
+            } else {
+                (self.history << ahead) | 1
+            };
+            self.highest_ext_seq = ext;
+        } else {
+            // Behind the highest: a duplicate, or a packet overtaken by
+            // `depth` later ones
+            let depth = self.highest_ext_seq - ext;
+            if depth < HISTORY {
+                let bit = 1u128 << depth;
+                if self.history & bit != 0 {
+                    self.duplicates = self.duplicates.saturating_add(1);
+                    return false;
+                }
+                self.history |= bit;
+            }
+            self.reordered = self.reordered.saturating_add(1);
+            self.max_reorder_depth = self.max_reorder_depth.max(depth);
         }
         self.received_unique = self.received_unique.wrapping_add(1);
 
@@ -43,6 +77,37 @@ impl RxTracker {
             );
         }
         self.last_transit = Some(transit);
+        true
+    }
+
+    /// Call when a packet arrives after its playout time, i.e. after the
+    /// jitter buffer already gave up on it.
+    pub const fn on_late(&mut self) {
+        self.late = self.late.saturating_add(1);
+    }
+
+    /// Duplicate packets received (not counted as received).
+    #[must_use]
+    pub const fn duplicates(&self) -> u32 {
+        self.duplicates
+    }
+
+    /// Packets that arrived after one with a higher sequence number.
+    #[must_use]
+    pub const fn reordered(&self) -> u32 {
+        self.reordered
+    }
+
+    /// Most packets that overtook a single reordered one.
+    #[must_use]
+    pub const fn max_reorder_depth(&self) -> u32 {
+        self.max_reorder_depth
+    }
+
+    /// Packets that arrived too late to be played.
+    #[must_use]
+    pub const fn late(&self) -> u32 {
+        self.late
     }
 
     /// Call when an SR is received (to later fill LSR/DLSR in our RR).
@@ -51,6 +116,34 @@ impl RxTracker {
         self.last_sr_arrival_compact = Some(ntp_compact(now_ntp.0, now_ntp.1));
     }
 
+    /// Unique packets received so far.
+    #[must_use]
+    pub const fn packets_received(&self) -> u32 {
+        self.received_unique
+    }
+
+    /// Highest extended sequence number seen.
+    #[must_use]
+    pub const fn highest_ext_seq(&self) -> u32 {
+        self.highest_ext_seq
+    }
+
+    /// Current interarrival jitter estimate, in RTP timestamp units.
+    #[must_use]
+    pub const fn jitter(&self) -> u32 {
+        self.jitter
+    }
+
+    /// Cumulative packets lost (expected - received). May be negative with duplicates.
+    #[must_use]
+    pub fn cumulative_lost(&self) -> i64 {
+        let Some(base) = self.base_ext_seq else {
+            return 0;
+        };
+        let expected_total = self.highest_ext_seq.saturating_sub(base) + 1;
+        i64::from(expected_total) - i64::from(self.received_unique)
+    }
+
     /// Build one RTCP `ReportBlock` for this remote SSRC (consumes interval deltas).
     pub fn build_report_block(&mut self, ssrc: u32) -> ReportBlock {
         let base = self.base_ext_seq.unwrap_or(0);
@@ -100,3 +193,31 @@ fn now_ntp_compact() -> u32 {
     let (s, f) = time::ntp_now();
     ntp_compact(s, f)
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn duplicates_and_reordering_are_counted() {
+        let mut rx = RxTracker::default();
+        // 0 overtaken by 1 across the wrap, then 1 again, 5, and 2 overtaken
+        // by 5
+        for seq in [65534, 65535, 1, 0] {
+            assert!(rx.on_rtp(seq, 0, 0));
+        }
+        assert!(!rx.on_rtp(1, 0, 0));
+        assert!(rx.on_rtp(5, 0, 0));
+        assert!(rx.on_rtp(2, 0, 0));
+
+        assert_eq!(rx.duplicates(), 1);
+        assert_eq!(rx.reordered(), 2);
+        assert_eq!(rx.max_reorder_depth(), 3);
+        assert_eq!(rx.highest_ext_seq(), (1 << 16) | 5);
+        // 65534..=65541 expected, 3 and 4 missing; the duplicate is not a
+        // receipt
+        assert_eq!(rx.packets_received(), 6);
+        assert_eq!(rx.cumulative_lost(), 2);
+    }
+}
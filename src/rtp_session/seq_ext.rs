@@ -1,16 +1,23 @@
 #[derive(Debug, Default, Clone)]
 pub struct SeqExt {
-    cycles: u32, // multiples of 2^16
-    last: u16,   // last sequence number we saw
+    /// Highest extended sequence number seen, once one has been.
+    highest: Option<u32>,
 }
 
 impl SeqExt {
+    /// Extends `seq` to 32 bits, taking the 16-bit value closest to the
+    /// highest one seen, so packets reordered across a wrap keep their cycle.
     pub fn update(&mut self, seq: u16) -> u32 {
-        // If we went "backwards" by more than half the space, it's a wrap
-        if seq < self.last && self.last.wrapping_sub(seq) > 0x8000 {
-            self.cycles = self.cycles.wrapping_add(1 << 16);
+        let Some(highest) = self.highest else {
+            self.highest = Some(u32::from(seq));
+            return u32::from(seq);
+        };
+        // Distance in the 16-bit space, -32768..32767
+        let delta = i32::from(seq.wrapping_sub(highest as u16) as i16);
+        let ext = highest.saturating_add_signed(delta);
+        if ext > highest {
+            self.highest = Some(ext);
         }
-        self.last = seq;
-        self.cycles | u32::from(seq) // same as cycles + seq because cycles % 2^16 == 0
+        ext
     }
 }
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Packets a send stream keeps: about half a second of 720p video.
+pub const TX_HISTORY_CAPACITY: usize = 512;
+
+/// One packet as it left.
+#[derive(Debug, Clone)]
+pub struct SentPacket {
+    pub seq: u16,
+    pub sent_at: Instant,
+    /// The packet as sent, SRTP included; resent as is when NACKed. The
+    /// receiver never got it, so its SRTP replay check lets it through.
+    pub bytes: Vec<u8>,
+}
+
+/// Bounded history of the packets a send stream sent, by sequence number,
+/// and what the remote's NACKs made of it.
+#[derive(Debug, Default)]
+pub struct TxHistory {
+    packets: VecDeque<SentPacket>,
+    /// Sequence numbers the remote asked for again.
+    nacked: u32,
+    /// Of those, the ones no longer kept.
+    unrecoverable: u32,
+    retransmitted: u32,
+    /// Time from sending a packet to its NACK arriving, summed over `nacked
+    /// - unrecoverable`.
+    nack_delay_total_ms: u64,
+}
+
+impl TxHistory {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps a copy of the packet `seq`, dropping the oldest when full.
+    pub fn record(&mut self, seq: u16, bytes: &[u8], sent_at: Instant) {
+        let mut reused = if self.packets.len() == TX_HISTORY_CAPACITY {
+            self.packets
+                .pop_front()
+                .map(|p| p.bytes)
+                .unwrap_or_default()
+        } else {
+            Vec::with_capacity(bytes.len())
+        };
+        reused.clear();
+        reused.extend_from_slice(bytes);
+        self.packets.push_back(SentPacket {
+            seq,
+            sent_at,
+            bytes: reused,
+        });
+    }
+
+    /// The packet `seq`, if still kept.
+    #[must_use]
+    pub fn get(&self, seq: u16) -> Option<&SentPacket> {
+        // Sequence numbers mostly run consecutively: try where it should be
+        let newest = self.packets.back()?.seq;
+        let back = usize::from(newest.wrapping_sub(seq));
+        if let Some(idx) = self.packets.len().checked_sub(back + 1)
+            && self.packets[idx].seq == seq
+        {
+            return Some(&self.packets[idx]);
+        }
+        self.packets.iter().rev().find(|p| p.seq == seq)
+    }
+
+    /// The packets a Generic NACK's `(PID, BLP)` entries ask for, still
+    /// kept, for resending.
+    pub fn on_nack(&mut self, entries: &[(u16, u16)], now: Instant) -> Vec<Vec<u8>> {
+        let mut resend = Vec::new();
+        for seq in entries.iter().flat_map(|&(pid, blp)| nacked_seqs(pid, blp)) {
+            self.nacked = self.nacked.saturating_add(1);
+            match self.get(seq) {
+                Some(packet) => {
+                    let delay = now.saturating_duration_since(packet.sent_at);
+                    let delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+                    self.nack_delay_total_ms = self.nack_delay_total_ms.saturating_add(delay_ms);
+                    resend.push(packet.bytes.clone());
+                }
+                None => self.unrecoverable = self.unrecoverable.saturating_add(1),
+            }
+        }
+        resend
+    }
+
+    /// Counts a packet resent after a NACK.
+    pub const fn note_retransmitted(&mut self) {
+        self.retransmitted = self.retransmitted.saturating_add(1);
+    }
+
+    #[must_use]
+    pub const fn nacked(&self) -> u32 {
+        self.nacked
+    }
+
+    #[must_use]
+    pub const fn unrecoverable(&self) -> u32 {
+        self.unrecoverable
+    }
+
+    #[must_use]
+    pub const fn retransmitted(&self) -> u32 {
+        self.retransmitted
+    }
+
+    /// Mean time from sending a packet to the remote reporting it lost.
+    #[must_use]
+    pub fn mean_nack_delay_ms(&self) -> Option<u32> {
+        let found = u64::from(self.nacked - self.unrecoverable);
+        (found > 0).then(|| u32::try_from(self.nack_delay_total_ms / found).unwrap_or(u32::MAX))
+    }
+}
+
+/// The sequence numbers one `(PID, BLP)` entry names (RFC 4585 §6.2.1).
+fn nacked_seqs(pid: u16, blp: u16) -> impl Iterator<Item = u16> {
+    std::iter::once(pid).chain(
+        (0..16u16)
+            .filter(move |bit| blp & (1 << bit) != 0)
+            .map(move |bit| pid.wrapping_add(bit + 1)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn nacks_resend_kept_packets_and_count_the_rest() {
+        let start = Instant::now();
+        let mut history = TxHistory::new();
+        for i in 0..(TX_HISTORY_CAPACITY as u16 + 4) {
+            history.record(65_530u16.wrapping_add(i), &[i as u8], start);
+        }
+        // The first four were dropped
+        assert!(history.get(65_533).is_none());
+        assert_eq!(history.get(65_534).unwrap().bytes, [4]);
+
+        // 65535, then 0 and 2 via the bitmask; 65533 is gone
+        let now = start + Duration::from_millis(80);
+        let resend = history.on_nack(&[(65_535, 0b101), (65_533, 0)], now);
+        assert_eq!(resend, [vec![5], vec![6], vec![8]]);
+        assert_eq!(history.nacked(), 4);
+        assert_eq!(history.unrecoverable(), 1);
+        assert_eq!(history.mean_nack_delay_ms(), Some(80));
+    }
+}
@@ -0,0 +1,13 @@
+//! RTSP ingest from IP cameras.
+//!
+//! Setting `[Media] video_source` to an `rtsp://` URL makes the camera's
+//! H.264 stream the video sent in calls, without decoding or re-encoding it.
+//! `rustyrtc-rtsp` uses this to put a LAN camera on the signaling server as
+//! a user anyone can call.
+pub mod rtsp_client;
+pub mod rtsp_error;
+pub mod rtsp_source;
+pub mod rtsp_url;
+pub use rtsp_client::RtspClient;
+pub use rtsp_error::RtspError;
+pub use rtsp_url::RtspUrl;
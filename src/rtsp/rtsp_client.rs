@@ -0,0 +1,654 @@
+//! Just enough RTSP/1.0 (RFC 2326) to pull H.264 from an IP camera:
+//! `DESCRIBE`, `SETUP`, `PLAY` and `TEARDOWN` on one TCP connection, with
+//! RTP interleaved on it (`$` framing), so no UDP ports need to be open
+//! between the camera and us.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use openssl::{base64, hash::MessageDigest};
+
+use crate::{
+    rtp::rtp_packet::RtpPacket,
+    rtsp::{rtsp_error::RtspError, rtsp_url::RtspUrl},
+    sdp::{media::MediaKind, sdpc::Sdp},
+};
+
+/// How long connecting, and each read or write, may take.
+const IO_TIMEOUT_MS: u64 = 10_000;
+/// Session timeout when the camera does not announce one (RFC 2326 12.37).
+const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+/// Largest response head or body we accept.
+const MAX_RESPONSE_LEN: usize = 64 * 1024;
+
+/// A complete RTSP response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtspResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RtspResponse {
+    /// First header named `name` (case-insensitive).
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Reads one response: status line, headers and a `Content-Length` body.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Io` if the connection fails and
+    /// `RtspError::BadResponse` if the response is malformed.
+    pub fn read(r: &mut impl BufRead) -> Result<Self, RtspError> {
+        let bad = |e: &str| RtspError::BadResponse(e.to_string());
+        let mut line = String::new();
+        if r.read_line(&mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let status_line = line.trim_end().to_string();
+        let mut parts = status_line.splitn(3, ' ');
+        if parts.next() != Some("RTSP/1.0") {
+            return Err(bad(&format!("status line '{status_line}'")));
+        }
+        let status = parts
+            .next()
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| bad(&format!("status line '{status_line}'")))?;
+        let reason = parts.next().unwrap_or_default().to_string();
+
+        let mut headers = Vec::new();
+        let mut head_len = 0;
+        loop {
+            line.clear();
+            if r.read_line(&mut line)? == 0 {
+                return Err(bad("headers not terminated"));
+            }
+            head_len += line.len();
+            if head_len > MAX_RESPONSE_LEN {
+                return Err(bad("headers too long"));
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| bad(&format!("header '{header}'")))?;
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+
+        let mut response = Self {
+            status,
+            reason,
+            headers,
+            body: Vec::new(),
+        };
+        if let Some(len) = response.header("Content-Length") {
+            let len: usize = len.parse().map_err(|_| bad("bad Content-Length"))?;
+            if len > MAX_RESPONSE_LEN {
+                return Err(bad("body too long"));
+            }
+            response.body = vec![0; len];
+            r.read_exact(&mut response.body)?;
+        }
+        Ok(response)
+    }
+}
+
+/// One `$`-framed packet on the RTSP connection (RFC 2326 10.12).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interleaved {
+    pub channel: u8,
+    pub data: Vec<u8>,
+}
+
+impl Interleaved {
+    /// Reads a frame; the `$` must be the next byte.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Io` if the connection fails and
+    /// `RtspError::BadResponse` if the frame does not start with `$`.
+    pub fn read(r: &mut impl Read) -> Result<Self, RtspError> {
+        let mut head = [0u8; 4];
+        r.read_exact(&mut head)?;
+        if head[0] != b'$' {
+            return Err(RtspError::BadResponse(format!(
+                "expected '$', got 0x{:02x}",
+                head[0]
+            )));
+        }
+        let mut data = vec![0; usize::from(u16::from_be_bytes([head[2], head[3]]))];
+        r.read_exact(&mut data)?;
+        Ok(Self {
+            channel: head[1],
+            data,
+        })
+    }
+}
+
+/// The H.264 track of a stream description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoTrack {
+    /// URL to `SETUP`.
+    pub control: String,
+    pub payload_type: u8,
+    /// SPS and PPS from `sprop-parameter-sets`, without start codes.
+    pub parameter_sets: Vec<Vec<u8>>,
+}
+
+impl VideoTrack {
+    /// Finds the first H.264 video track of a `DESCRIBE` body, resolving
+    /// its control URL against `base`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::BadResponse` if the SDP cannot be parsed and
+    /// `RtspError::NoVideo` if it has no H.264 track.
+    pub fn from_sdp(sdp: &str, base: &str) -> Result<Self, RtspError> {
+        let sdp = Sdp::parse(sdp).map_err(|e| RtspError::BadResponse(format!("SDP: {e}")))?;
+        for media in sdp.media() {
+            if !matches!(media.kind(), MediaKind::Video) {
+                continue;
+            }
+            let attr = |key: &str| {
+                media
+                    .attrs()
+                    .iter()
+                    .filter(move |a| a.key() == key)
+                    .filter_map(|a| a.value())
+            };
+            let Some(payload_type) = attr("rtpmap").find_map(|v| {
+                let (pt, encoding) = v.split_once(' ')?;
+                let h264 = encoding.trim().to_ascii_uppercase().starts_with("H264/");
+                h264.then(|| pt.trim().parse().ok()).flatten()
+            }) else {
+                continue;
+            };
+            let control = attr("control").next().unwrap_or("*");
+            let parameter_sets = attr("fmtp")
+                .filter_map(|v| v.split_once(' '))
+                .filter(|(pt, _)| pt.trim().parse() == Ok(payload_type))
+                .flat_map(|(_, params)| params.split(';'))
+                .filter_map(|p| p.trim().strip_prefix("sprop-parameter-sets="))
+                .flat_map(|sets| sets.split(','))
+                .filter_map(|set| base64::decode_block(set.trim()).ok())
+                .filter(|nalu| !nalu.is_empty())
+                .collect();
+            return Ok(Self {
+                control: RtspUrl::join_control(base, control),
+                payload_type,
+                parameter_sets,
+            });
+        }
+        Err(RtspError::NoVideo)
+    }
+}
+
+/// A `WWW-Authenticate` challenge we can answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Challenge {
+    Basic,
+    Digest {
+        realm: String,
+        nonce: String,
+        opaque: Option<String>,
+        /// The server asked for `qop=auth` (RFC 2617).
+        qop_auth: bool,
+    },
+}
+
+impl Challenge {
+    /// Picks Digest over Basic among the `WWW-Authenticate` headers.
+    fn pick(response: &RtspResponse) -> Option<Self> {
+        let offered: Vec<Self> = response
+            .headers
+            .iter()
+            .filter(|(n, _)| n.eq_ignore_ascii_case("WWW-Authenticate"))
+            .filter_map(|(_, v)| Self::parse(v))
+            .collect();
+        offered
+            .iter()
+            .find(|c| matches!(c, Self::Digest { .. }))
+            .or_else(|| offered.first())
+            .cloned()
+    }
+
+    fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ').unwrap_or((header, ""));
+        if scheme.eq_ignore_ascii_case("Basic") {
+            return Some(Self::Basic);
+        }
+        if !scheme.eq_ignore_ascii_case("Digest") {
+            return None;
+        }
+        let params = auth_params(params);
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+        };
+        Some(Self::Digest {
+            realm: param("realm")?,
+            nonce: param("nonce")?,
+            opaque: param("opaque"),
+            qop_auth: param("qop").is_some_and(|q| q.split(',').any(|q| q.trim() == "auth")),
+        })
+    }
+
+    /// `Authorization` header value for request number `nc` on this nonce.
+    fn authorization(
+        &self,
+        user: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        nc: u32,
+    ) -> String {
+        match self {
+            Self::Basic => format!(
+                "Basic {}",
+                base64::encode_block(format!("{user}:{password}").as_bytes())
+            ),
+            Self::Digest {
+                realm,
+                nonce,
+                opaque,
+                qop_auth,
+            } => {
+                let ha1 = md5_hex(&format!("{user}:{realm}:{password}"));
+                let ha2 = md5_hex(&format!("{method}:{uri}"));
+                let mut header = format!(
+                    "Digest username=\"{user}\", realm=\"{realm}\", nonce=\"{nonce}\", uri=\"{uri}\""
+                );
+                if *qop_auth {
+                    let cnonce = format!("{:016x}", rand::random::<u64>());
+                    let response = md5_hex(&format!("{ha1}:{nonce}:{nc:08x}:{cnonce}:auth:{ha2}"));
+                    header.push_str(&format!(
+                        ", qop=auth, nc={nc:08x}, cnonce=\"{cnonce}\", response=\"{response}\""
+                    ));
+                } else {
+                    let response = md5_hex(&format!("{ha1}:{nonce}:{ha2}"));
+                    header.push_str(&format!(", response=\"{response}\""));
+                }
+                if let Some(opaque) = opaque {
+                    header.push_str(&format!(", opaque=\"{opaque}\""));
+                }
+                header
+            }
+        }
+    }
+}
+
+/// `name=value` pairs of a challenge, commas inside quotes included.
+fn auth_params(s: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in s.chars().chain(std::iter::once(',')) {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                if let Some((name, value)) = current.split_once('=') {
+                    out.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+    out
+}
+
+fn md5_hex(s: &str) -> String {
+    openssl::hash::hash(MessageDigest::md5(), s.as_bytes()).map_or_else(
+        |_| String::new(),
+        |digest| digest.iter().map(|b| format!("{b:02x}")).collect(),
+    )
+}
+
+/// An RTSP session with one camera stream.
+pub struct RtspClient {
+    url: RtspUrl,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    cseq: u32,
+    session: Option<String>,
+    session_timeout: Duration,
+    challenge: Option<Challenge>,
+    /// Requests sent with the current Digest nonce.
+    nonce_count: u32,
+    /// URL for `PLAY` and `TEARDOWN`: the session-level control.
+    play_uri: String,
+    rtp_channel: u8,
+    last_request: Instant,
+}
+
+impl RtspClient {
+    /// Connects to the camera; nothing is requested yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Url` for a bad URL and `RtspError::Io` if the
+    /// camera cannot be reached.
+    pub fn connect(url: &str) -> Result<Self, RtspError> {
+        let url = RtspUrl::parse(url)?;
+        let timeout = Duration::from_millis(IO_TIMEOUT_MS);
+        let addr = url
+            .authority()
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| RtspError::Url(format!("{url}: host not found")))?;
+        let stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        let _ = stream.set_nodelay(true);
+        Ok(Self {
+            play_uri: url.to_string(),
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            url,
+            cseq: 0,
+            session: None,
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+            challenge: None,
+            nonce_count: 0,
+            rtp_channel: 0,
+            last_request: Instant::now(),
+        })
+    }
+
+    /// Asks for the stream description and returns its H.264 track.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Status` if the camera refuses (wrong credentials
+    /// included) and `RtspError::NoVideo` if there is no H.264 track.
+    pub fn describe(&mut self) -> Result<VideoTrack, RtspError> {
+        let uri = self.url.to_string();
+        let response = self.request("DESCRIBE", &uri, &[("Accept", "application/sdp")])?;
+        let base = response
+            .header("Content-Base")
+            .or_else(|| response.header("Content-Location"))
+            .map_or(uri, str::to_string);
+        let sdp = String::from_utf8_lossy(&response.body);
+        let track = VideoTrack::from_sdp(&sdp, &base)?;
+        // An aggregate control at session level is what PLAY goes to
+        self.play_uri = sdp
+            .lines()
+            .take_while(|l| !l.starts_with("m="))
+            .find_map(|l| l.trim().strip_prefix("a=control:"))
+            .map_or(base.clone(), |control| {
+                RtspUrl::join_control(&base, control)
+            });
+        Ok(track)
+    }
+
+    /// Sets `track` up to be sent interleaved on this connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Status` if the camera refuses, e.g. it does not
+    /// support RTP over TCP.
+    pub fn setup(&mut self, track: &VideoTrack) -> Result<(), RtspError> {
+        let response = self.request(
+            "SETUP",
+            &track.control,
+            &[("Transport", "RTP/AVP/TCP;unicast;interleaved=0-1")],
+        )?;
+        if self.session.is_none() {
+            return Err(RtspError::BadResponse("SETUP without Session".into()));
+        }
+        self.rtp_channel = response
+            .header("Transport")
+            .and_then(|t| {
+                t.split(';')
+                    .find_map(|p| p.trim().strip_prefix("interleaved="))
+                    .and_then(|ch| ch.split('-').next())
+                    .and_then(|ch| ch.trim().parse().ok())
+            })
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    /// Starts the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Status` if the camera refuses.
+    pub fn play(&mut self) -> Result<(), RtspError> {
+        let uri = self.play_uri.clone();
+        self.request("PLAY", &uri, &[("Range", "npt=0.000-")])?;
+        Ok(())
+    }
+
+    /// Next RTP packet of the video track, or `None` for anything else on
+    /// the connection (RTCP, responses to keep-alives). Keeps the session
+    /// alive while it is read.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtspError::Io` if the connection fails or stalls.
+    pub fn read_rtp(&mut self) -> Result<Option<RtpPacket>, RtspError> {
+        if self.last_request.elapsed() >= self.session_timeout / 2 {
+            let uri = self.play_uri.clone();
+            self.send("OPTIONS", &uri, &[])?;
+        }
+        let first = *self
+            .reader
+            .fill_buf()?
+            .first()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if first != b'$' {
+            RtspResponse::read(&mut self.reader)?;
+            return Ok(None);
+        }
+        let frame = Interleaved::read(&mut self.reader)?;
+        if frame.channel != self.rtp_channel {
+            return Ok(None);
+        }
+        Ok(RtpPacket::decode(&frame.data).ok())
+    }
+
+    /// Ends the session; the camera is not waited for.
+    pub fn teardown(&mut self) {
+        if self.session.is_some() {
+            let uri = self.play_uri.clone();
+            let _ = self.send("TEARDOWN", &uri, &[]);
+            self.session = None;
+        }
+    }
+
+    /// Sends a request and waits for its response, answering one
+    /// authentication challenge.
+    fn request(
+        &mut self,
+        method: &'static str,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<RtspResponse, RtspError> {
+        let mut response = self.exchange(method, uri, headers)?;
+        if response.status == 401
+            && self.url.user.is_some()
+            && let Some(challenge) = Challenge::pick(&response)
+        {
+            self.challenge = Some(challenge);
+            self.nonce_count = 0;
+            response = self.exchange(method, uri, headers)?;
+        }
+        if response.status != 200 {
+            return Err(RtspError::Status {
+                method,
+                code: response.status,
+                reason: response.reason,
+            });
+        }
+        if let Some(session) = response.header("Session") {
+            let mut parts = session.split(';');
+            self.session = parts.next().map(|id| id.trim().to_string());
+            if let Some(secs) = parts
+                .find_map(|p| p.trim().strip_prefix("timeout="))
+                .and_then(|t| t.trim().parse().ok())
+            {
+                self.session_timeout = Duration::from_secs(secs);
+            }
+        }
+        Ok(response)
+    }
+
+    fn exchange(
+        &mut self,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<RtspResponse, RtspError> {
+        let cseq = self.send(method, uri, headers)?;
+        loop {
+            // Media may already flow while we wait (e.g. after PLAY)
+            if self.reader.fill_buf()?.first() == Some(&b'$') {
+                Interleaved::read(&mut self.reader)?;
+                continue;
+            }
+            let response = RtspResponse::read(&mut self.reader)?;
+            // Skip answers to keep-alives sent earlier
+            if response
+                .header("CSeq")
+                .is_none_or(|c| c.trim().parse() == Ok(cseq))
+            {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// Writes a request and returns its `CSeq`.
+    fn send(
+        &mut self,
+        method: &str,
+        uri: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<u32, RtspError> {
+        self.cseq += 1;
+        let mut req = format!(
+            "{method} {uri} RTSP/1.0\r\nCSeq: {}\r\nUser-Agent: RoomRTC\r\n",
+            self.cseq
+        );
+        if let Some(session) = &self.session {
+            req.push_str(&format!("Session: {session}\r\n"));
+        }
+        if let (Some(challenge), Some(user)) = (&self.challenge, &self.url.user) {
+            self.nonce_count += 1;
+            let password = self.url.password.as_deref().unwrap_or_default();
+            let auth = challenge.authorization(user, password, method, uri, self.nonce_count);
+            req.push_str(&format!("Authorization: {auth}\r\n"));
+        }
+        for (name, value) in headers {
+            req.push_str(&format!("{name}: {value}\r\n"));
+        }
+        req.push_str("\r\n");
+        self.writer.write_all(req.as_bytes())?;
+        self.last_request = Instant::now();
+        Ok(self.cseq)
+    }
+}
+
+impl Drop for RtspClient {
+    fn drop(&mut self) {
+        self.teardown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::io::Cursor;
+
+    const DESCRIBE_SDP: &str = "v=0\r\n\
+o=- 1 1 IN IP4 192.168.1.64\r\n\
+s=Media Presentation\r\n\
+t=0 0\r\n\
+a=control:*\r\n\
+m=video 0 RTP/AVP 96\r\n\
+a=rtpmap:96 H264/90000\r\n\
+a=fmtp:96 profile-level-id=420029; packetization-mode=1; sprop-parameter-sets=Z0IAKeKQFAe2AtwEBAaQeJEV,aM48gA==\r\n\
+a=control:trackID=1\r\n\
+m=audio 0 RTP/AVP 0\r\n\
+a=control:trackID=2\r\n";
+
+    #[test]
+    fn responses_and_media_share_the_connection() {
+        let mut raw = b"$\x01\x00\x02ab".to_vec();
+        raw.extend_from_slice(
+            b"RTSP/1.0 200 OK\r\nCSeq: 3\r\nSession: 12AB;timeout=30\r\nContent-Length: 4\r\n\r\nbody",
+        );
+        let mut r = Cursor::new(raw);
+        let frame = Interleaved::read(&mut r).unwrap();
+        assert_eq!((frame.channel, frame.data.as_slice()), (1, &b"ab"[..]));
+        let response = RtspResponse::read(&mut r).unwrap();
+        assert_eq!((response.status, response.reason.as_str()), (200, "OK"));
+        assert_eq!(response.header("session"), Some("12AB;timeout=30"));
+        assert_eq!(response.body, b"body");
+        assert!(RtspResponse::read(&mut Cursor::new(b"HTTP/1.1 200 OK\r\n\r\n")).is_err());
+    }
+
+    #[test]
+    fn the_h264_track_and_its_parameter_sets_are_found() {
+        let track = VideoTrack::from_sdp(DESCRIBE_SDP, "rtsp://cam/Streaming/101/").unwrap();
+        assert_eq!(track.control, "rtsp://cam/Streaming/101/trackID=1");
+        assert_eq!(track.payload_type, 96);
+        assert_eq!(track.parameter_sets.len(), 2);
+        assert_eq!(track.parameter_sets[0][0] & 0x1F, 7);
+        assert_eq!(track.parameter_sets[1][0] & 0x1F, 8);
+
+        let audio_only = "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\nm=audio 0 RTP/AVP 0\r\n";
+        assert!(matches!(
+            VideoTrack::from_sdp(audio_only, "rtsp://cam/"),
+            Err(RtspError::NoVideo)
+        ));
+    }
+
+    #[test]
+    fn digest_is_preferred_and_answered() {
+        let response = RtspResponse {
+            status: 401,
+            reason: "Unauthorized".into(),
+            headers: vec![
+                ("WWW-Authenticate".into(), "Basic realm=\"cam\"".into()),
+                (
+                    "WWW-Authenticate".into(),
+                    "Digest realm=\"IP Camera, 2\", nonce=\"a1b2\", stale=\"FALSE\"".into(),
+                ),
+            ],
+            body: Vec::new(),
+        };
+        let challenge = Challenge::pick(&response).unwrap();
+        assert!(
+            matches!(&challenge, Challenge::Digest { realm, nonce, qop_auth: false, .. }
+            if realm == "IP Camera, 2" && nonce == "a1b2")
+        );
+
+        let auth = challenge.authorization("admin", "12345", "DESCRIBE", "rtsp://cam/live", 1);
+        let ha1 = md5_hex("admin:IP Camera, 2:12345");
+        let ha2 = md5_hex("DESCRIBE:rtsp://cam/live");
+        let expected = md5_hex(&format!("{ha1}:a1b2:{ha2}"));
+        assert!(auth.starts_with("Digest username=\"admin\""));
+        assert!(auth.contains(&format!("response=\"{expected}\"")));
+        assert_eq!(md5_hex(""), "d41d8cd98f00b204e9800998ecf8427e");
+
+        assert_eq!(
+            Challenge::Basic.authorization("admin", "12345", "DESCRIBE", "rtsp://cam/", 1),
+            "Basic YWRtaW46MTIzNDU="
+        );
+    }
+}
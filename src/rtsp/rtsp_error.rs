@@ -0,0 +1,43 @@
+use std::{fmt, io};
+
+/// Errors while pulling a stream from an RTSP camera.
+#[derive(Debug)]
+pub enum RtspError {
+    /// The `rtsp://` URL cannot be used.
+    Url(String),
+    Io(io::Error),
+    /// The camera answered a request with an unexpected status.
+    Status {
+        method: &'static str,
+        code: u16,
+        reason: String,
+    },
+    /// The response is not valid RTSP or lacks what we need.
+    BadResponse(String),
+    /// The stream has no H.264 video track.
+    NoVideo,
+}
+
+impl fmt::Display for RtspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(e) => write!(f, "invalid URL: {e}"),
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::Status {
+                method,
+                code,
+                reason,
+            } => write!(f, "{method} failed: {code} {reason}"),
+            Self::BadResponse(e) => write!(f, "bad RTSP response: {e}"),
+            Self::NoVideo => write!(f, "no H.264 video track in the stream"),
+        }
+    }
+}
+
+impl std::error::Error for RtspError {}
+
+impl From<io::Error> for RtspError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -0,0 +1,217 @@
+//! An RTSP camera as the local video source (`[Media] video_source =
+//! "rtsp://..."`).
+//!
+//! The camera's H.264 is not decoded: its RTP is reassembled into access
+//! units, which go to the media transport like the encoder's output and are
+//! packetized again for the call. Sending starts at the first IDR frame, with
+//! the SPS/PPS in front when the camera only announced them in the SDP.
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    log::log_sink::LogSink,
+    media_agent::{events::MediaAgentEvent, spec::CodecSpec, utils::now_millis},
+    media_transport::{
+        depacketizer::h264_depacketizer::H264Depacketizer,
+        payload::h264_packetizer::split_annexb_nalus,
+    },
+    rtp::rtp_packet::RtpPacket,
+    rtsp::{rtsp_client::RtspClient, rtsp_error::RtspError},
+    sink_debug, sink_info, sink_warn,
+};
+
+/// Wait before reconnecting to a camera that failed or went away.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+/// H.264 RTP clock, ticks per millisecond.
+const TICKS_PER_MS: u64 = 90;
+
+const NALU_IDR: u8 = 5;
+const NALU_SPS: u8 = 7;
+const NALU_PPS: u8 = 8;
+
+/// Turns the camera's RTP into access units that can be sent as they are.
+struct FrameAssembler {
+    depacketizer: H264Depacketizer,
+    sps: Option<Vec<u8>>,
+    pps: Option<Vec<u8>>,
+    /// No IDR seen yet: earlier frames cannot be decoded.
+    waiting_for_idr: bool,
+    last_ts: Option<u32>,
+    /// RTP ticks since the first frame, unwrapped.
+    elapsed_ticks: u64,
+    /// Wall clock of the first frame (UNIX milliseconds).
+    base_ms: u128,
+}
+
+impl FrameAssembler {
+    fn new(parameter_sets: &[Vec<u8>], base_ms: u128) -> Self {
+        let mut assembler = Self {
+            depacketizer: H264Depacketizer::new(),
+            sps: None,
+            pps: None,
+            waiting_for_idr: true,
+            last_ts: None,
+            elapsed_ticks: 0,
+            base_ms,
+        };
+        for nalu in parameter_sets {
+            assembler.learn(nalu);
+        }
+        assembler
+    }
+
+    fn learn(&mut self, nalu: &[u8]) {
+        match nalu.first().map(|h| h & 0x1F) {
+            Some(NALU_SPS) => self.sps = Some(nalu.to_vec()),
+            Some(NALU_PPS) => self.pps = Some(nalu.to_vec()),
+            _ => {}
+        }
+    }
+
+    /// Feeds one RTP packet; returns the Annex-B frame it completes and its
+    /// timestamp (UNIX milliseconds).
+    fn push(&mut self, pkt: &RtpPacket) -> Option<(Vec<u8>, u128)> {
+        let frame =
+            self.depacketizer
+                .push_rtp(&pkt.payload, pkt.marker(), pkt.timestamp(), pkt.seq())?;
+        let nalus: Vec<Vec<u8>> = split_annexb_nalus(&frame)
+            .into_iter()
+            .map(<[u8]>::to_vec)
+            .collect();
+        let types: Vec<u8> = nalus
+            .iter()
+            .filter_map(|n| n.first())
+            .map(|h| h & 0x1F)
+            .collect();
+        for nalu in &nalus {
+            self.learn(nalu);
+        }
+        let idr = types.contains(&NALU_IDR);
+        if self.waiting_for_idr && !idr {
+            return None;
+        }
+        self.waiting_for_idr = false;
+
+        let mut out = Vec::with_capacity(frame.len() + 64);
+        if idr && !types.contains(&NALU_SPS) {
+            for set in [&self.sps, &self.pps].into_iter().flatten() {
+                out.extend_from_slice(&[0, 0, 0, 1]);
+                out.extend_from_slice(set);
+            }
+        }
+        out.extend_from_slice(&frame);
+
+        let ts = pkt.timestamp();
+        if let Some(last) = self.last_ts {
+            // Wrapping difference: the clock may wrap, frames may be reordered
+            #[allow(clippy::cast_possible_wrap)]
+            let delta = ts.wrapping_sub(last) as i32;
+            self.elapsed_ticks = self.elapsed_ticks.saturating_add_signed(i64::from(delta));
+        }
+        self.last_ts = Some(ts);
+        Some((
+            out,
+            self.base_ms + u128::from(self.elapsed_ticks / TICKS_PER_MS),
+        ))
+    }
+}
+
+/// Pulls `url` into `tx` until `running` clears or `tx` is dropped,
+/// reconnecting whenever the camera fails.
+pub fn rtsp_video_loop(
+    url: &str,
+    logger: &Arc<dyn LogSink>,
+    tx: &Sender<MediaAgentEvent>,
+    running: &AtomicBool,
+) {
+    while running.load(Ordering::SeqCst) {
+        match pull(url, logger, tx, running) {
+            Ok(()) => break,
+            Err(e) => sink_warn!(logger, "[RTSP] camera stream failed: {}", e),
+        }
+        let mut waited = Duration::ZERO;
+        while waited < RETRY_DELAY && running.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(100));
+            waited += Duration::from_millis(100);
+        }
+    }
+    sink_debug!(logger, "[RTSP] source stopped");
+}
+
+/// One session with the camera. `Ok` when we were asked to stop.
+fn pull(
+    url: &str,
+    logger: &Arc<dyn LogSink>,
+    tx: &Sender<MediaAgentEvent>,
+    running: &AtomicBool,
+) -> Result<(), RtspError> {
+    let mut client = RtspClient::connect(url)?;
+    let track = client.describe()?;
+    client.setup(&track)?;
+    client.play()?;
+    sink_info!(logger, "[RTSP] playing {}", track.control);
+
+    let mut frames = FrameAssembler::new(&track.parameter_sets, now_millis());
+    while running.load(Ordering::Relaxed) {
+        let Some(pkt) = client.read_rtp()? else {
+            continue;
+        };
+        if pkt.payload_type() != track.payload_type {
+            continue;
+        }
+        if let Some((annexb_frame, timestamp_ms)) = frames.push(&pkt)
+            && tx
+                .send(MediaAgentEvent::EncodedVideoFrame {
+                    annexb_frame,
+                    timestamp_ms,
+                    codec_spec: CodecSpec::H264,
+                })
+                .is_err()
+        {
+            break;
+        }
+    }
+    client.teardown();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn pkt(seq: u16, ts: u32, nalu: &[u8]) -> RtpPacket {
+        RtpPacket::simple(96, true, seq, ts, 0x1234, nalu.to_vec())
+    }
+
+    #[test]
+    fn frames_start_at_an_idr_with_its_parameter_sets() {
+        let sps = vec![0x67, 0x42, 0x00, 0x29];
+        let pps = vec![0x68, 0xCE, 0x3C, 0x80];
+        let mut frames = FrameAssembler::new(&[sps.clone(), pps.clone()], 1_000);
+
+        // A P-frame before any IDR is useless to the receiver
+        assert!(frames.push(&pkt(1, 90_000, &[0x41, 1, 2])).is_none());
+
+        let (idr, ts) = frames.push(&pkt(2, 93_000, &[0x65, 3, 4])).unwrap();
+        let mut expected = vec![0, 0, 0, 1];
+        expected.extend_from_slice(&sps);
+        expected.extend_from_slice(&[0, 0, 0, 1]);
+        expected.extend_from_slice(&pps);
+        expected.extend_from_slice(&[0, 0, 0, 1, 0x65, 3, 4]);
+        assert_eq!(idr, expected);
+        assert_eq!(ts, 1_000);
+
+        let (p, ts) = frames.push(&pkt(3, 96_000, &[0x41, 5])).unwrap();
+        assert_eq!(p, [0, 0, 0, 1, 0x41, 5]);
+        assert_eq!(ts, 1_000 + 3_000 / 90);
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+
+use crate::rtsp::rtsp_error::RtspError;
+
+/// Port RTSP servers listen on unless the URL says otherwise.
+const DEFAULT_PORT: u16 = 554;
+
+/// An `rtsp://[user[:password]@]host[:port]/path` URL. The credentials are
+/// kept apart: they are sent in `Authorization` headers, never in request
+/// lines.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RtspUrl {
+    /// Host name or IP address, IPv6 without brackets.
+    pub host: String,
+    pub port: u16,
+    /// Path and query, always starting with `/`.
+    pub path: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl RtspUrl {
+    /// # Errors
+    ///
+    /// Returns `RtspError::Url` if the scheme is not `rtsp` or the host or
+    /// port are missing or invalid.
+    pub fn parse(url: &str) -> Result<Self, RtspError> {
+        let url = url.trim();
+        let rest = url
+            .strip_prefix("rtsp://")
+            .ok_or_else(|| RtspError::Url(format!("{url}: expected rtsp://")))?;
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('/') => (&rest[..i], rest[i..].to_string()),
+            Some(i) => (&rest[..i], format!("/{}", &rest[i..])),
+            None => (rest, "/".to_string()),
+        };
+        // The password may contain '@', the host never does
+        let (userinfo, authority) = match authority.rsplit_once('@') {
+            Some((userinfo, host)) => (Some(userinfo), host),
+            None => (None, authority),
+        };
+        let (user, password) = match userinfo.map(|u| u.split_once(':').unwrap_or((u, ""))) {
+            Some((user, password)) => (
+                Some(user.to_string()),
+                (!password.is_empty()).then(|| password.to_string()),
+            ),
+            None => (None, None),
+        };
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6
+                .split_once(']')
+                .ok_or_else(|| RtspError::Url(format!("{url}: unclosed '['")))?;
+            (host, after.strip_prefix(':'))
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(RtspError::Url(format!("{url}: missing host")));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| RtspError::Url(format!("{url}: bad port '{port}'")))?,
+            None => DEFAULT_PORT,
+        };
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path,
+            user,
+            password,
+        })
+    }
+
+    /// `host:port` to connect to.
+    #[must_use]
+    pub fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Resolves a media `control` attribute (RFC 2326 C.1.1) against
+    /// `base`, the `Content-Base` of the description or the URL itself.
+    #[must_use]
+    pub fn join_control(base: &str, control: &str) -> String {
+        let control = control.trim();
+        if control.is_empty() || control == "*" {
+            base.to_string()
+        } else if control.starts_with("rtsp://") {
+            control.to_string()
+        } else if base.ends_with('/') {
+            format!("{base}{control}")
+        } else {
+            format!("{base}/{control}")
+        }
+    }
+}
+
+/// The URL without credentials, as used in request lines.
+impl fmt::Display for RtspUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == DEFAULT_PORT {
+            write!(f, "rtsp://{host}{}", self.path)
+        } else {
+            write!(f, "rtsp://{host}:{}{}", self.port, self.path)
+        }
+    }
+}
+
+/// Hides the password, so URLs can be logged.
+impl fmt::Debug for RtspUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.user {
+            Some(user) => write!(f, "RtspUrl({user}@{self})"),
+            None => write!(f, "RtspUrl({self})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn parses_credentials_port_and_path() {
+        let url = RtspUrl::parse("rtsp://admin:p@ss@192.168.1.64:8554/Streaming/101?tcp").unwrap();
+        assert_eq!(url.host, "192.168.1.64");
+        assert_eq!(url.port, 8554);
+        assert_eq!(url.path, "/Streaming/101?tcp");
+        assert_eq!(url.user.as_deref(), Some("admin"));
+        assert_eq!(url.password.as_deref(), Some("p@ss"));
+        assert_eq!(
+            url.to_string(),
+            "rtsp://192.168.1.64:8554/Streaming/101?tcp"
+        );
+        assert!(!format!("{url:?}").contains("p@ss"));
+
+        let url = RtspUrl::parse("rtsp://[fe80::1]/live").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("fe80::1", 554));
+        assert_eq!(url.authority(), "[fe80::1]:554");
+        assert!(RtspUrl::parse("http://cam/live").is_err());
+        assert!(RtspUrl::parse("rtsp://cam:port/live").is_err());
+    }
+
+    #[test]
+    fn controls_resolve_against_the_base() {
+        let base = "rtsp://cam/live";
+        assert_eq!(
+            RtspUrl::join_control(base, "trackID=1"),
+            "rtsp://cam/live/trackID=1"
+        );
+        assert_eq!(
+            RtspUrl::join_control("rtsp://cam/live/", "track1"),
+            "rtsp://cam/live/track1"
+        );
+        assert_eq!(RtspUrl::join_control(base, "*"), base);
+        assert_eq!(
+            RtspUrl::join_control(base, "rtsp://cam:554/live/video"),
+            "rtsp://cam:554/live/video"
+        );
+    }
+}
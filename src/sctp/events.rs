@@ -25,5 +25,9 @@ pub enum SctpEvents {
     SctpErr(String),
     TransmitSctpPacket { payload: Vec<u8> },
     KickSender,
+    CloseNotify,
+    // The path MTU search ended; `None` keeps the configured size
+    PathMtu { mtu: Option<usize> },
+    PeerClosed,
     Shutdown,
 }
@@ -87,8 +87,7 @@ impl SctpProtocolMessage {
                 let id = cursor.read_u32::<BigEndian>()?;
                 let file_size = cursor.read_u64::<BigEndian>()?;
                 let filename_len = cursor.read_u16::<BigEndian>()?;
-                let mut filename_bytes = vec![0u8; filename_len as usize];
-                cursor.read_exact(&mut filename_bytes)?;
+                let filename_bytes = read_bytes(&mut cursor, filename_len as usize)?;
                 let filename = String::from_utf8(filename_bytes)
                     .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
                 Ok(SctpProtocolMessage::Offer {
@@ -113,8 +112,7 @@ impl SctpProtocolMessage {
                 let id = cursor.read_u32::<BigEndian>()?;
                 let seq = cursor.read_u64::<BigEndian>()?;
                 let payload_len = cursor.read_u32::<BigEndian>()?;
-                let mut payload = vec![0u8; payload_len as usize];
-                cursor.read_exact(&mut payload)?;
+                let payload = read_bytes(&mut cursor, payload_len as usize)?;
                 Ok(SctpProtocolMessage::Chunk { id, seq, payload })
             }
             Self::TYPE_END_FILE => {
@@ -131,3 +129,37 @@ impl SctpProtocolMessage {
         }
     }
 }
+
+/// Reads `len` bytes, checking that many are left before allocating them:
+/// the length comes from the peer.
+fn read_bytes(cursor: &mut Cursor<&[u8]>, len: usize) -> Result<Vec<u8>, std::io::Error> {
+    let left = (cursor.get_ref().len() as u64).saturating_sub(cursor.position());
+    if len as u64 > left {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    let mut bytes = vec![0u8; len];
+    cursor.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn a_chunk_longer_than_the_message_is_rejected() {
+        let msg = SctpProtocolMessage::Chunk {
+            id: 7,
+            seq: 3,
+            payload: vec![1, 2, 3],
+        };
+        let mut bytes = msg.serialize().unwrap();
+        assert_eq!(SctpProtocolMessage::deserialize(&bytes).unwrap(), msg);
+
+        // Claim a 4 GiB payload
+        bytes[13..17].copy_from_slice(&u32::MAX.to_be_bytes());
+        let err = SctpProtocolMessage::deserialize(&bytes).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::core::consent::PeerActivity;
+use crate::core::path_mtu::{self, MtuConfig};
 use crate::dtls::buffered_udp_channel::BufferedUdpChannel;
 use crate::log::log_sink::LogSink;
 use crate::sctp::events::SctpEvents;
@@ -17,12 +19,27 @@ pub struct SctpSession {
     association: Arc<Mutex<Option<Association>>>,
 }
 
+/// Endpoint whose associations send SCTP packets that fit `mtu` once in
+/// DTLS.
+fn new_endpoint(mtu: usize) -> Endpoint {
+    let mut config = EndpointConfig::default();
+    config.max_payload_size(path_mtu::sctp_payload_size(mtu) as u32);
+    let server_config = ServerConfig::default();
+    // Wrap config in Arc as required by Endpoint::new
+    Endpoint::new(Arc::new(config), Some(Arc::new(server_config)))
+}
+
 impl SctpSession {
+    /// With `mtu.discovery` on, no association is set up until
+    /// [`Self::path_mtu`] tells the outcome of the search, so that it is
+    /// sized to the path: SCTP cannot change its packet size afterwards.
     pub fn new(
         log_sink: Arc<dyn LogSink>,
         parent_tx: Sender<SctpEvents>,
         ssl_stream: SslStream<BufferedUdpChannel>,
         is_client: bool,
+        mtu: MtuConfig,
+        activity: Arc<PeerActivity>,
     ) -> Self {
         let (tx, rx) = channel();
 
@@ -37,12 +54,7 @@ impl SctpSession {
         let association_handle = Arc::new(Mutex::new(None::<AssociationHandle>));
 
         // Init Endpoint
-        let mut config = EndpointConfig::default();
-        config.max_payload_size(1200);
-        let server_config = ServerConfig::default();
-        // Wrap config in Arc as required by Endpoint::new
-        let endpoint = Endpoint::new(Arc::new(config), Some(Arc::new(server_config)));
-        let endpoint = Arc::new(Mutex::new(endpoint));
+        let endpoint = Arc::new(Mutex::new(new_endpoint(mtu.mtu)));
 
         // Receiver
         let receiver = SctpReceiver::new(
@@ -65,6 +77,7 @@ impl SctpSession {
             streams.clone(),
             endpoint.clone(),
             is_client,
+            mtu.discovery,
         );
 
         // Transport
@@ -73,6 +86,7 @@ impl SctpSession {
             log_sink.clone(),
             tx.clone(), // Transport sends ReadableSctpPacket back to Router via main tx
             rx_transport,
+            activity,
         );
 
         // Spawn threads
@@ -84,6 +98,9 @@ impl SctpSession {
         let tx_receiver_clone = tx_receiver.clone();
         let tx_sender_clone = tx_sender.clone();
         let tx_transport_clone = tx_transport.clone();
+        // Packets from the peer wait here while the path is searched, so
+        // that its INIT does not set up an association too early
+        let mut held: Option<Vec<SctpEvents>> = mtu.discovery.then(Vec::new);
 
         thread::spawn(move || {
             while let Ok(event) = rx.recv() {
@@ -94,8 +111,27 @@ impl SctpSession {
                     SctpEvents::IncomingSctpPacket { .. } => {
                         let _ = tx_transport_clone.send(event);
                     }
-                    SctpEvents::ReadableSctpPacket { .. } => {
-                        let _ = tx_receiver_clone.send(event);
+                    SctpEvents::ReadableSctpPacket { .. } => match held.as_mut() {
+                        Some(held) => held.push(event),
+                        None => {
+                            let _ = tx_receiver_clone.send(event);
+                        }
+                    },
+                    SctpEvents::PathMtu { mtu: found } => {
+                        let Some(held) = held.take() else {
+                            continue;
+                        };
+                        if let Some(found) = found
+                            && let Ok(mut endpoint) = endpoint.lock()
+                        {
+                            *endpoint = new_endpoint(found);
+                        }
+                        // DTLS takes the new size; the sender may connect
+                        let _ = tx_transport_clone.send(event.clone());
+                        let _ = tx_sender_clone.send(event);
+                        for packet in held {
+                            let _ = tx_receiver_clone.send(packet);
+                        }
                     }
                     SctpEvents::SendOffer { .. }
                     | SctpEvents::SendAccept { .. }
@@ -106,7 +142,7 @@ impl SctpSession {
                     | SctpEvents::KickSender => {
                         let _ = tx_sender_clone.send(event);
                     }
-                    SctpEvents::TransmitSctpPacket { .. } => {
+                    SctpEvents::TransmitSctpPacket { .. } | SctpEvents::CloseNotify => {
                         let _ = tx_transport_clone.send(event);
                     }
                     SctpEvents::ReceivedAccept { id } => {
@@ -120,6 +156,7 @@ impl SctpSession {
                     | SctpEvents::ReceivedCancel { .. }
                     | SctpEvents::ReceivedChunk { .. }
                     | SctpEvents::ReceivedEndFile { .. }
+                    | SctpEvents::PeerClosed
                     | SctpEvents::SctpErr(_) => {
                         // Forward to parent
                         let _ = parent_tx.send(event);
@@ -138,6 +175,16 @@ impl SctpSession {
         let _ = self.tx.send(SctpEvents::Shutdown);
     }
 
+    /// Sends our `close_notify`; the peer answers with its own.
+    pub fn close_notify(&self) {
+        let _ = self.tx.send(SctpEvents::CloseNotify);
+    }
+
+    /// The path MTU search ended with `mtu`, `None` if it found nothing.
+    pub fn path_mtu(&self, mtu: Option<usize>) {
+        let _ = self.tx.send(SctpEvents::PathMtu { mtu });
+    }
+
     pub fn handle_sctp_packet(&self, packet: Vec<u8>) {
         let _ = self.tx.send(SctpEvents::IncomingSctpPacket {
             sctp_packet: packet,
@@ -8,6 +8,7 @@ use sctp_proto::{
     Association, AssociationHandle, ClientConfig, Endpoint, Error, Payload,
     PayloadProtocolIdentifier,
 };
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::mpsc::{Receiver, Sender};
@@ -23,6 +24,9 @@ pub struct SctpSender {
     pub streams: Arc<RwLock<HashMap<u32, SctpStream>>>,
     pub endpoint: Arc<Mutex<Endpoint>>,
     pub is_client: bool,
+    /// Set until the path MTU search ends; no association is started
+    /// meanwhile.
+    waiting_for_mtu: Cell<bool>,
 }
 
 impl SctpSender {
@@ -35,6 +39,7 @@ impl SctpSender {
         streams: Arc<RwLock<HashMap<u32, SctpStream>>>,
         endpoint: Arc<Mutex<Endpoint>>,
         is_client: bool,
+        wait_for_path_mtu: bool,
     ) -> Self {
         Self {
             log_sink,
@@ -45,6 +50,7 @@ impl SctpSender {
             streams,
             endpoint,
             is_client,
+            waiting_for_mtu: Cell::new(wait_for_path_mtu),
         }
     }
 
@@ -224,6 +230,10 @@ impl SctpSender {
                     }
                     self.send_message(SctpProtocolMessage::EndFile { id }, &mut pending_messages);
                 }
+                Ok(SctpEvents::PathMtu { .. }) => {
+                    self.waiting_for_mtu.set(false);
+                    self.ensure_connection();
+                }
                 Ok(SctpEvents::SctpConnected) => {
                     sink_info!(
                         self.log_sink,
@@ -289,7 +299,7 @@ impl SctpSender {
     fn ensure_connection(&self) {
         let mut assoc_guard = self.association.lock().expect("association lock poisoned");
         if assoc_guard.is_none() {
-            if !self.is_client {
+            if !self.is_client || self.waiting_for_mtu.get() {
                 // If we are server, we wait for incoming connection (handled by Receiver)
                 return;
             }
@@ -1,8 +1,10 @@
+use crate::core::consent::PeerActivity;
 use crate::dtls::buffered_udp_channel::BufferedUdpChannel;
+use crate::dtls::runtime::set_stream_mtu;
 use crate::log::log_sink::LogSink;
 use crate::sctp::events::SctpEvents;
 use crate::{sink_debug, sink_error, sink_trace};
-use openssl::ssl::SslStream;
+use openssl::ssl::{ShutdownState, SslStream};
 use std::io::{Read, Write};
 use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Sender};
@@ -12,6 +14,12 @@ pub struct SctpTransport {
     log_sink: Arc<dyn LogSink>,
     router_tx: Sender<SctpEvents>,
     rx: Receiver<SctpEvents>,
+    /// Whether the peer's `close_notify` was reported.
+    peer_closed: bool,
+    /// The datagrams of our `close_notify`, once sent.
+    close_notify: Vec<Vec<u8>>,
+    /// Told of every record that decrypts: the peer is there.
+    activity: Arc<PeerActivity>,
 }
 
 impl SctpTransport {
@@ -20,6 +28,7 @@ impl SctpTransport {
         log_sink: Arc<dyn LogSink>,
         router_tx: Sender<SctpEvents>,
         rx: Receiver<SctpEvents>,
+        activity: Arc<PeerActivity>,
     ) -> Self {
         // Set manual mode on the channel so we don't race with Session's socket reading
         let mut stream = ssl_stream;
@@ -29,6 +38,9 @@ impl SctpTransport {
             log_sink,
             router_tx,
             rx,
+            peer_closed: false,
+            close_notify: Vec::new(),
+            activity,
         }
     }
 
@@ -76,6 +88,11 @@ impl SctpTransport {
                             );
                             // Push to internal queue (Bulk Injection)
                             self.ssl_stream.get_mut().push_incoming(sctp_packet);
+                            // The peer is still sending after its close_notify:
+                            // our answer may have been lost
+                            if self.peer_closed {
+                                self.send_close_notify();
+                            }
                         }
                         SctpEvents::TransmitSctpPacket { payload } => {
                             // Encrypt and send
@@ -98,6 +115,13 @@ impl SctpTransport {
                                 payload.len()
                             );
                         }
+                        SctpEvents::CloseNotify => self.send_close_notify(),
+                        SctpEvents::PathMtu { mtu: Some(mtu) } => {
+                            sink_debug!(self.log_sink, "[SctpTransport] DTLS MTU now {}", mtu);
+                            if let Err(e) = set_stream_mtu(&mut self.ssl_stream, mtu) {
+                                sink_error!(self.log_sink, "[SctpTransport] {}", e);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -123,6 +147,7 @@ impl SctpTransport {
                         read_count += 1;
                         let elapsed = start.elapsed();
                         if n > 0 {
+                            self.activity.heard();
                             sink_trace!(
                                 self.log_sink,
                                 "[SCTP_TRANSPORT] DTLS decryption time: {:?} (decrypted {} bytes)",
@@ -141,6 +166,7 @@ impl SctpTransport {
                                 sctp_packet: decrypted,
                             });
                         } else {
+                            self.on_zero_read();
                             break;
                         }
                     }
@@ -156,4 +182,122 @@ impl SctpTransport {
         }
         sink_debug!(self.log_sink, "[SctpTransport] Stopped");
     }
+
+    /// A read of nothing is the peer's `close_notify`: answer it with ours
+    /// (unless we sent it first) and report the end of the session once.
+    fn on_zero_read(&mut self) {
+        if self.peer_closed
+            || !self
+                .ssl_stream
+                .get_shutdown()
+                .contains(ShutdownState::RECEIVED)
+        {
+            return;
+        }
+        self.peer_closed = true;
+        sink_debug!(self.log_sink, "[SctpTransport] Peer sent close_notify");
+        if self.close_notify.is_empty() {
+            self.send_close_notify();
+        }
+        if let Err(e) = self.ssl_stream.get_mut().flush() {
+            sink_error!(self.log_sink, "[SctpTransport] Flush error: {}", e);
+        }
+        let _ = self.router_tx.send(SctpEvents::PeerClosed);
+    }
+
+    /// Sends our `close_notify`, written to the channel and flushed by the
+    /// caller. Later calls send the same datagrams again, for when one was
+    /// lost: the peer drops a copy of one it already got as a replay.
+    fn send_close_notify(&mut self) {
+        if self.close_notify.is_empty() {
+            sink_debug!(self.log_sink, "[SctpTransport] Sending close_notify");
+            self.ssl_stream.get_mut().start_recording();
+            let _ = self.ssl_stream.shutdown();
+            self.close_notify = self.ssl_stream.get_mut().take_recorded();
+            return;
+        }
+        sink_debug!(self.log_sink, "[SctpTransport] Sending close_notify again");
+        let channel = self.ssl_stream.get_mut();
+        for datagram in &self.close_notify {
+            let _ = channel.write(datagram);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::core::clock;
+    use crate::dtls::test_pair;
+    use crate::log::NoopLogSink;
+    use std::net::UdpSocket;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Runs a transport over `stream`, fed what `sock` receives; while
+    /// `lose` is set, the next datagram is dropped instead.
+    fn start(
+        stream: SslStream<BufferedUdpChannel>,
+        sock: Arc<UdpSocket>,
+        lose: Arc<AtomicBool>,
+    ) -> (Sender<SctpEvents>, Receiver<SctpEvents>) {
+        let (to_transport, rx) = channel();
+        let (router_tx, from_transport) = channel();
+        let activity = Arc::new(PeerActivity::new(clock::system()));
+        let transport = SctpTransport::new(stream, Arc::new(NoopLogSink), router_tx, rx, activity);
+        thread::spawn(move || transport.run());
+        sock.set_nonblocking(false).unwrap();
+        sock.set_read_timeout(Some(Duration::from_millis(20)))
+            .unwrap();
+        let feed = to_transport.clone();
+        thread::spawn(move || {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let mut buf = [0u8; 2048];
+            while Instant::now() < deadline {
+                let Ok((n, _)) = sock.recv_from(&mut buf) else {
+                    continue;
+                };
+                if lose.swap(false, Ordering::SeqCst) {
+                    continue;
+                }
+                let sctp_packet = buf[..n].to_vec();
+                if feed
+                    .send(SctpEvents::IncomingSctpPacket { sctp_packet })
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        (to_transport, from_transport)
+    }
+
+    #[test]
+    fn a_lost_close_notify_is_sent_again() {
+        let ((a, _, a_stream), (b, _, b_stream)) = test_pair::handshake("close");
+
+        let lose_to_b = Arc::new(AtomicBool::new(false));
+        let (a_tx, a_events) = start(a_stream, a, Arc::new(AtomicBool::new(false)));
+        let (_b_tx, b_events) = start(b_stream, b, lose_to_b.clone());
+
+        lose_to_b.store(true, Ordering::SeqCst);
+        a_tx.send(SctpEvents::CloseNotify).unwrap();
+        assert!(b_events.recv_timeout(Duration::from_millis(300)).is_err());
+
+        // What the close driver does while no answer comes
+        a_tx.send(SctpEvents::CloseNotify).unwrap();
+        let timeout = Duration::from_secs(2);
+        assert!(matches!(
+            b_events.recv_timeout(timeout),
+            Ok(SctpEvents::PeerClosed)
+        ));
+        // b answered with its own
+        assert!(matches!(
+            a_events.recv_timeout(timeout),
+            Ok(SctpEvents::PeerClosed)
+        ));
+    }
 }
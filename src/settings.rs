@@ -0,0 +1,236 @@
+//! User settings persisted across launches.
+//!
+//! Choices made in the GUI (devices, capture resolution, quality preset,
+//! last signaling server and username, window layout) are written to a small
+//! INI file. It uses the configuration's own sections and keys, and is
+//! applied on top of it at startup, so a saved choice overrides the
+//! configured default.
+//!
+//! The file lives next to the configuration when one is already there, and
+//! in the per-user configuration directory otherwise (see [`user_dir`]).
+
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Config;
+
+/// Extension of the settings file, next to the configuration file.
+pub const SETTINGS_EXTENSION: &str = "settings";
+/// Subdirectory of the per-user configuration directory.
+const APP_DIR: &str = "rustyrtc";
+
+/// Per-user configuration directory: `%APPDATA%` on Windows,
+/// `~/Library/Application Support` on macOS, `$XDG_CONFIG_HOME` or
+/// `~/.config` elsewhere; with a `rustyrtc` subdirectory.
+#[must_use]
+pub fn user_dir() -> Option<PathBuf> {
+    let var = |name| {
+        env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    let base = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join(APP_DIR))
+}
+
+/// Saved overrides for the configuration, by section and key.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Settings {
+    path: Option<PathBuf>,
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Settings {
+    /// Settings file for the configuration at `config_path`
+    /// (`client_roomrtc.conf` -> `client_roomrtc.settings`).
+    #[must_use]
+    pub fn path_for(config_path: impl AsRef<Path>) -> PathBuf {
+        config_path.as_ref().with_extension(SETTINGS_EXTENSION)
+    }
+
+    /// Where the settings for the configuration at `config_path` are kept:
+    /// next to it if a settings file is already there, else in
+    /// [`user_dir`], else next to it.
+    #[must_use]
+    pub fn locate(config_path: impl AsRef<Path>) -> PathBuf {
+        let local = Self::path_for(config_path);
+        if local.exists() {
+            return local;
+        }
+        match (user_dir(), local.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => local,
+        }
+    }
+
+    /// Loads the settings for the configuration at `config_path`, from
+    /// wherever [`locate`](Self::locate) finds them.
+    #[must_use]
+    pub fn load_for(config_path: impl AsRef<Path>) -> Self {
+        Self::load(Self::locate(config_path))
+    }
+
+    /// Loads the settings saved at `path`. A missing or unreadable file
+    /// gives empty settings that will be saved there.
+    #[must_use]
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let sections = path
+            .to_str()
+            .and_then(|p| Config::load(p).ok())
+            .map(|config| {
+                config
+                    .sections
+                    .into_iter()
+                    .map(|(name, values)| (name, values.into_iter().collect()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            sections,
+        }
+    }
+
+    /// Settings that are never written anywhere.
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    #[must_use]
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.sections
+            .get(section)
+            .and_then(|s| s.get(key))
+            .map(String::as_str)
+    }
+
+    /// Stores a value; `None` removes it so the configuration applies again.
+    pub fn set(&mut self, section: &str, key: &str, value: Option<String>) {
+        match value {
+            Some(value) => {
+                self.sections
+                    .entry(section.to_owned())
+                    .or_default()
+                    .insert(key.to_owned(), value);
+            }
+            None => {
+                if let Some(values) = self.sections.get_mut(section) {
+                    values.remove(key);
+                    if values.is_empty() {
+                        self.sections.remove(section);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Overrides `config` with every saved value.
+    pub fn apply_to(&self, config: &mut Config) {
+        for (section, values) in &self.sections {
+            for (key, value) in values {
+                config.set(section, key, value.clone());
+            }
+        }
+    }
+
+    /// Writes the settings to their file (a no-op for in-memory settings),
+    /// creating its directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be written.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut out = String::from("# Saved by RoomRTC; overrides the configuration file.\n");
+        for (section, values) in &self.sections {
+            out.push_str(&format!("\n[{section}]\n"));
+            for (key, value) in values {
+                out.push_str(&format!("{key} = {value}\n"));
+            }
+        }
+        fs::write(path, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn saved_values_override_the_config() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-{}.settings", std::process::id()));
+        let mut settings = Settings::load(&path);
+        settings.set("Media", "audio_input", Some("USB Mic (2)".into()));
+        settings.set("Signaling", "server_address", Some("10.0.0.2:5005".into()));
+        settings.set("Media", "camera", Some("1".into()));
+        settings.set("Media", "camera", None);
+        settings.save().unwrap();
+
+        let loaded = Settings::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded, settings);
+        assert_eq!(loaded.get("Media", "camera"), None);
+
+        let mut config = Config::empty();
+        config.set("Signaling", "server_address", "127.0.0.1:5005");
+        loaded.apply_to(&mut config);
+        assert_eq!(
+            config.get("Signaling", "server_address"),
+            Some("10.0.0.2:5005")
+        );
+        assert_eq!(config.get("Media", "audio_input"), Some("USB Mic (2)"));
+    }
+
+    #[test]
+    fn settings_live_next_to_the_config() {
+        assert_eq!(
+            Settings::path_for("conf/client_roomrtc.conf"),
+            Path::new("conf/client_roomrtc.settings")
+        );
+        assert!(Settings::in_memory().save().is_ok());
+    }
+
+    #[test]
+    fn existing_local_file_wins_over_the_user_dir() {
+        let dir = std::env::temp_dir().join(format!("rustyrtc-settings-{}", std::process::id()));
+        let config = dir.join("client_roomrtc.conf");
+        let local = dir.join("client_roomrtc.settings");
+
+        // Nothing saved yet: the user directory, with the same file name
+        let located = Settings::locate(&config);
+        assert_eq!(located.file_name(), local.file_name());
+
+        // save() creates the directory
+        let mut settings = Settings::load(&local);
+        settings.set("Signaling", "username", Some("alice".into()));
+        settings.save().unwrap();
+        assert_eq!(Settings::locate(&config), local);
+        assert_eq!(
+            Settings::load_for(&config).get("Signaling", "username"),
+            Some("alice")
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
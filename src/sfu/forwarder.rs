@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    media_transport::media_transport_event::RtpIn, rtp::rtp_packet::RtpPacket,
+    signaling::protocol::UserName,
+};
+
+/// How long the dominant speaker keeps the floor after its last voiced
+/// packet before someone else can take it.
+const SPEAKER_HOLD: Duration = Duration::from_secs(1);
+/// Minimum time between two keyframe requests for the same source.
+const PLI_INTERVAL: Duration = Duration::from_millis(500);
+/// Timestamp gap put between two speakers on the room audio stream: one
+/// 20 ms PCMU frame.
+const SWITCH_TS_STEP: u32 = 160;
+
+/// What a forwarded stream carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    Audio,
+    Video,
+}
+
+/// One publisher's video stream as forwarded to one subscriber.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardedTrack {
+    pub subscriber: UserName,
+    pub publisher: UserName,
+    /// SSRC the subscriber receives it on.
+    pub ssrc: u32,
+    /// SSRC the publisher sends it on.
+    pub source_ssrc: u32,
+}
+
+/// Result of [`Forwarder::forward`].
+#[derive(Debug, Default)]
+pub struct Forwarded {
+    /// Rewritten packets and the member each one goes to.
+    pub packets: Vec<(UserName, RtpPacket)>,
+    /// Video streams seen for the first time, to announce to their
+    /// subscriber.
+    pub new_tracks: Vec<ForwardedTrack>,
+}
+
+/// Maps the packets of one or more sources onto one outgoing stream whose
+/// SSRC, sequence numbers and timestamps stay continuous when the source
+/// changes.
+#[derive(Debug)]
+struct Rewriter {
+    ssrc: u32,
+    source: Option<(UserName, u32)>,
+    seq_delta: u16,
+    ts_delta: u32,
+    /// Highest sequence number sent and its timestamp, once started.
+    last: Option<(u16, u32)>,
+    first_seq: u16,
+}
+
+impl Rewriter {
+    fn new(ssrc: u32, first_seq: u16) -> Self {
+        Self {
+            ssrc,
+            source: None,
+            seq_delta: 0,
+            ts_delta: 0,
+            last: None,
+            first_seq,
+        }
+    }
+
+    fn random() -> Self {
+        Self::new(rand::random(), rand::random())
+    }
+
+    /// Rewrites `pkt` from `publisher`. A new source continues right after
+    /// the last packet sent, and its first packet carries the marker bit.
+    fn rewrite(&mut self, publisher: &str, pkt: &RtpIn) -> RtpPacket {
+        let mut marker = pkt.marker;
+        let is_current = self
+            .source
+            .as_ref()
+            .is_some_and(|(p, ssrc)| p == publisher && *ssrc == pkt.ssrc);
+        if !is_current {
+            let (next_seq, next_ts) = match self.last {
+                Some((seq, ts)) => (seq.wrapping_add(1), ts.wrapping_add(SWITCH_TS_STEP)),
+                None => (self.first_seq, pkt.timestamp_90khz),
+            };
+            self.seq_delta = next_seq.wrapping_sub(pkt.seq);
+            self.ts_delta = next_ts.wrapping_sub(pkt.timestamp_90khz);
+            self.source = Some((publisher.to_owned(), pkt.ssrc));
+            marker |= self.last.is_some();
+        }
+        let seq = pkt.seq.wrapping_add(self.seq_delta);
+        let ts = pkt.timestamp_90khz.wrapping_add(self.ts_delta);
+        // Reordered packets keep their place; only newer ones advance
+        let newer = self
+            .last
+            .is_none_or(|(last_seq, _)| seq.wrapping_sub(last_seq) < 0x8000);
+        if newer {
+            self.last = Some((seq, ts));
+        }
+        RtpPacket::simple(pkt.pt, marker, seq, ts, self.ssrc, pkt.payload.clone())
+    }
+}
+
+/// What one member receives.
+#[derive(Debug, Default)]
+struct Subscriber {
+    /// The room audio: whoever holds the floor, on a single stream.
+    audio: Option<Rewriter>,
+    /// Audio of the publishers that send no level, one stream each.
+    unmeasured_audio: HashMap<UserName, Rewriter>,
+    /// One stream per publisher video source.
+    video: HashMap<(UserName, u32), Rewriter>,
+}
+
+/// Decides, for one room, which packets every member receives and how they
+/// are rewritten.
+///
+/// Video is forwarded from everyone to everyone else, one stream per
+/// source. Audio is reduced to the dominant speaker (told by the RFC 6464
+/// voice flag, else whoever was heard first) so each member decodes a
+/// single audio stream, whoever is talking. A publisher whose packets carry
+/// no level cannot be ranked: its audio goes to everyone else on a stream
+/// of its own, so that it is never silenced.
+#[derive(Debug, Default)]
+pub struct Forwarder {
+    members: HashMap<UserName, Subscriber>,
+    /// Members that reported an audio level, so take part in the floor.
+    measured: HashSet<UserName>,
+    speaker: Option<UserName>,
+    speaker_voiced_at: Option<Instant>,
+    last_pli: HashMap<(UserName, u32), Instant>,
+}
+
+impl Forwarder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_member(&mut self, member: &str) {
+        self.members.entry(member.to_owned()).or_default();
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Removes a member. Returns the tracks forwarded to others from it.
+    pub fn remove_member(&mut self, member: &str) -> Vec<ForwardedTrack> {
+        self.members.remove(member);
+        self.measured.remove(member);
+        if self.speaker.as_deref() == Some(member) {
+            self.speaker = None;
+            self.speaker_voiced_at = None;
+        }
+        self.last_pli
+            .retain(|(publisher, _), _| publisher != member);
+        let mut removed = Vec::new();
+        for (subscriber, sub) in &mut self.members {
+            sub.unmeasured_audio.remove(member);
+            sub.video.retain(|(publisher, source_ssrc), rw| {
+                if publisher != member {
+                    return true;
+                }
+                removed.push(ForwardedTrack {
+                    subscriber: subscriber.clone(),
+                    publisher: publisher.clone(),
+                    ssrc: rw.ssrc,
+                    source_ssrc: *source_ssrc,
+                });
+                false
+            });
+        }
+        removed
+    }
+
+    /// The member whose audio everyone else hears.
+    #[must_use]
+    pub fn speaker(&self) -> Option<&str> {
+        self.speaker.as_deref()
+    }
+
+    /// Audio level `member` reported; a voiced packet takes the floor once
+    /// the current speaker has been quiet for a while.
+    pub fn on_audio_level(&mut self, member: &str, voice: bool, now: Instant) {
+        if !self.members.contains_key(member) {
+            return;
+        }
+        if !self.measured.contains(member) {
+            self.measured.insert(member.to_owned());
+        }
+        if !voice {
+            return;
+        }
+        let holds_floor = self.speaker.as_deref() == Some(member);
+        let floor_free = self
+            .speaker_voiced_at
+            .is_none_or(|at| now.duration_since(at) >= SPEAKER_HOLD);
+        if holds_floor || floor_free {
+            self.speaker = Some(member.to_owned());
+            self.speaker_voiced_at = Some(now);
+        }
+    }
+
+    /// Routes one packet `from` sent to every other member.
+    pub fn forward(&mut self, from: &str, kind: StreamKind, pkt: &RtpIn) -> Forwarded {
+        let mut out = Forwarded::default();
+        if !self.members.contains_key(from) {
+            return out;
+        }
+        match kind {
+            StreamKind::Audio if !self.measured.contains(from) => {
+                for (member, sub) in &mut self.members {
+                    if member == from {
+                        continue;
+                    }
+                    let rw = sub
+                        .unmeasured_audio
+                        .entry(from.to_owned())
+                        .or_insert_with(Rewriter::random);
+                    out.packets.push((member.clone(), rw.rewrite(from, pkt)));
+                }
+            }
+            StreamKind::Audio => {
+                if self.speaker.is_none() {
+                    self.speaker = Some(from.to_owned());
+                }
+                if self.speaker.as_deref() != Some(from) {
+                    return out;
+                }
+                for (member, sub) in &mut self.members {
+                    if member == from {
+                        continue;
+                    }
+                    let rw = sub.audio.get_or_insert_with(Rewriter::random);
+                    out.packets.push((member.clone(), rw.rewrite(from, pkt)));
+                }
+            }
+            StreamKind::Video => {
+                for (member, sub) in &mut self.members {
+                    if member == from {
+                        continue;
+                    }
+                    let rw = sub
+                        .video
+                        .entry((from.to_owned(), pkt.ssrc))
+                        .or_insert_with(|| {
+                            let rw = Rewriter::random();
+                            out.new_tracks.push(ForwardedTrack {
+                                subscriber: member.clone(),
+                                publisher: from.to_owned(),
+                                ssrc: rw.ssrc,
+                                source_ssrc: pkt.ssrc,
+                            });
+                            rw
+                        });
+                    out.packets.push((member.clone(), rw.rewrite(from, pkt)));
+                }
+            }
+        }
+        out
+    }
+
+    /// The publisher and SSRC behind the stream `ssrc` sent to `subscriber`.
+    #[must_use]
+    pub fn source_of(&self, subscriber: &str, ssrc: u32) -> Option<(UserName, u32)> {
+        self.members
+            .get(subscriber)?
+            .video
+            .iter()
+            .find(|(_, rw)| rw.ssrc == ssrc)
+            .map(|(source, _)| source.clone())
+    }
+
+    /// `subscriber` lost the stream `ssrc` (RTCP PLI). Returns the source to
+    /// ask for a keyframe, unless one was requested moments ago: every
+    /// subscriber's PLI for the same loss is relayed once.
+    pub fn on_pli(&mut self, subscriber: &str, ssrc: u32, now: Instant) -> Option<(UserName, u32)> {
+        let source = self.source_of(subscriber, ssrc)?;
+        self.request_keyframe(source, now)
+    }
+
+    /// `subscriber` just accepted the stream `ssrc` and can only start
+    /// decoding it at a keyframe. Unlike a PLI this is never throttled: a
+    /// keyframe already on its way may reach the forwarder before the
+    /// subscriber's track does.
+    pub fn on_subscribe(
+        &mut self,
+        subscriber: &str,
+        ssrc: u32,
+        now: Instant,
+    ) -> Option<(UserName, u32)> {
+        let source = self.source_of(subscriber, ssrc)?;
+        self.last_pli.insert(source.clone(), now);
+        Some(source)
+    }
+
+    /// Throttles keyframe requests to one source; `None` if one was sent
+    /// less than `PLI_INTERVAL` ago.
+    pub fn request_keyframe(
+        &mut self,
+        source: (UserName, u32),
+        now: Instant,
+    ) -> Option<(UserName, u32)> {
+        if let Some(at) = self.last_pli.get(&source)
+            && now.duration_since(*at) < PLI_INTERVAL
+        {
+            return None;
+        }
+        self.last_pli.insert(source.clone(), now);
+        Some(source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn pkt(ssrc: u32, seq: u16, ts: u32) -> RtpIn {
+        RtpIn {
+            pt: 0,
+            marker: false,
+            timestamp_90khz: ts,
+            seq,
+            ssrc,
+            payload: vec![0xAB; 4],
+        }
+    }
+
+    fn room(members: &[&str]) -> Forwarder {
+        let mut f = Forwarder::new();
+        for m in members {
+            f.add_member(m);
+        }
+        f
+    }
+
+    fn to<'a>(out: &'a Forwarded, member: &str) -> Vec<&'a RtpPacket> {
+        out.packets
+            .iter()
+            .filter(|(m, _)| m == member)
+            .map(|(_, p)| p)
+            .collect()
+    }
+
+    #[test]
+    fn video_goes_to_everyone_else_on_its_own_ssrc() {
+        let mut f = room(&["alice", "bob", "carol"]);
+        let out = f.forward("alice", StreamKind::Video, &pkt(0x1111, 100, 9000));
+        assert!(to(&out, "alice").is_empty());
+        let (bob, carol) = (to(&out, "bob"), to(&out, "carol"));
+        assert_eq!((bob.len(), carol.len()), (1, 1));
+        assert_ne!(bob[0].ssrc(), 0x1111);
+        assert_ne!(bob[0].ssrc(), carol[0].ssrc());
+        assert_eq!(bob[0].timestamp(), 9000);
+        assert_eq!(out.new_tracks.len(), 2);
+        assert!(out.new_tracks.iter().all(|t| t.publisher == "alice"));
+
+        // Same stream: no new track, consecutive sequence numbers
+        let next = f.forward("alice", StreamKind::Video, &pkt(0x1111, 101, 9000));
+        assert!(next.new_tracks.is_empty());
+        assert_eq!(to(&next, "bob")[0].seq(), bob[0].seq().wrapping_add(1));
+        assert_eq!(to(&next, "bob")[0].ssrc(), bob[0].ssrc());
+    }
+
+    #[test]
+    fn room_audio_stays_continuous_across_speakers() {
+        let now = Instant::now();
+        let mut f = room(&["alice", "bob", "carol"]);
+        f.on_audio_level("alice", false, now);
+        f.on_audio_level("bob", false, now);
+        let first = f.forward("alice", StreamKind::Audio, &pkt(0xA, 500, 8000));
+        // Only the speaker is heard
+        let quiet = f.forward("bob", StreamKind::Audio, &pkt(0xB, 7, 320));
+        assert!(quiet.packets.is_empty());
+        let carol_a = to(&first, "carol")[0].clone();
+
+        f.on_audio_level("alice", true, now);
+        f.on_audio_level("bob", true, now + Duration::from_millis(200));
+        assert_eq!(f.speaker(), Some("alice"), "alice still holds the floor");
+        f.on_audio_level("bob", true, now + SPEAKER_HOLD);
+        assert_eq!(f.speaker(), Some("bob"));
+
+        let second = f.forward("bob", StreamKind::Audio, &pkt(0xB, 8, 480));
+        assert!(to(&second, "bob").is_empty(), "nobody hears themselves");
+        let carol_b = to(&second, "carol")[0];
+        assert_eq!(carol_b.ssrc(), carol_a.ssrc());
+        assert_eq!(carol_b.seq(), carol_a.seq().wrapping_add(1));
+        assert_eq!(
+            carol_b.timestamp(),
+            carol_a.timestamp().wrapping_add(SWITCH_TS_STEP)
+        );
+        assert!(carol_b.marker(), "a new talkspurt starts");
+        assert_eq!(to(&second, "alice").len(), 1);
+    }
+
+    #[test]
+    fn audio_without_levels_reaches_everyone_on_its_own_stream() {
+        let now = Instant::now();
+        let mut f = room(&["alice", "bob", "carol"]);
+        f.on_audio_level("alice", true, now);
+        let alice = f.forward("alice", StreamKind::Audio, &pkt(0xA, 1, 0));
+        assert_eq!(f.speaker(), Some("alice"));
+
+        // Bob's client sends no level: he is heard while alice holds the floor
+        let bob = f.forward("bob", StreamKind::Audio, &pkt(0xB, 1, 0));
+        assert_eq!(f.speaker(), Some("alice"));
+        assert_eq!(to(&bob, "alice").len(), 1);
+        let (carol_a, carol_b) = (to(&alice, "carol")[0], to(&bob, "carol")[0]);
+        assert_ne!(carol_a.ssrc(), carol_b.ssrc());
+        let next = f.forward("bob", StreamKind::Audio, &pkt(0xB, 2, 160));
+        assert_eq!(to(&next, "carol")[0].ssrc(), carol_b.ssrc());
+        assert_eq!(to(&next, "carol")[0].seq(), carol_b.seq().wrapping_add(1));
+    }
+
+    #[test]
+    fn plis_map_back_to_the_source_and_are_throttled() {
+        let now = Instant::now();
+        let mut f = room(&["alice", "bob", "carol"]);
+        let out = f.forward("alice", StreamKind::Video, &pkt(0x1111, 1, 0));
+        let bob_ssrc = to(&out, "bob")[0].ssrc();
+        let carol_ssrc = to(&out, "carol")[0].ssrc();
+
+        assert_eq!(
+            f.on_pli("bob", bob_ssrc, now),
+            Some(("alice".to_string(), 0x1111))
+        );
+        assert_eq!(f.on_pli("carol", carol_ssrc, now), None);
+        assert_eq!(f.on_pli("bob", carol_ssrc, now), None, "not bob's stream");
+        assert!(f.on_pli("carol", carol_ssrc, now + PLI_INTERVAL).is_some());
+
+        // A new subscription always gets its keyframe, and restarts the throttle
+        let later = now + PLI_INTERVAL + PLI_INTERVAL / 2;
+        assert!(f.on_subscribe("bob", bob_ssrc, later).is_some());
+        assert_eq!(f.on_pli("carol", carol_ssrc, later), None);
+    }
+
+    #[test]
+    fn leaving_drops_the_member_streams() {
+        let now = Instant::now();
+        let mut f = room(&["alice", "bob", "carol"]);
+        f.forward("alice", StreamKind::Video, &pkt(0x1111, 1, 0));
+        f.forward("bob", StreamKind::Video, &pkt(0x2222, 1, 0));
+        f.on_audio_level("alice", true, now);
+
+        let mut removed = f.remove_member("alice");
+        removed.sort_by(|a, b| a.subscriber.cmp(&b.subscriber));
+        let subscribers: Vec<_> = removed.iter().map(|t| t.subscriber.as_str()).collect();
+        assert_eq!(subscribers, ["bob", "carol"]);
+        assert_eq!(f.speaker(), None);
+        assert!(
+            f.forward("alice", StreamKind::Video, &pkt(0x1111, 2, 0))
+                .packets
+                .is_empty()
+        );
+        assert_eq!(
+            f.forward("bob", StreamKind::Video, &pkt(0x2222, 2, 0))
+                .packets
+                .len(),
+            1
+        );
+    }
+}
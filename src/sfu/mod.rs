@@ -0,0 +1,20 @@
+//! Selective forwarding unit.
+//!
+//! With `[SFU] enabled = true` the signaling server shows a member named
+//! [`SFU_USERNAME`] in every room. Calling it sets up a regular call with the
+//! server, which then relays the room's media: everyone's video arrives on
+//! its own stream and the audio of whoever is speaking on one shared stream.
+//! Members whose audio carries no level (RFC 6464) are relayed on streams of
+//! their own instead, since the server cannot tell when they speak.
+
+pub mod forwarder;
+pub mod sfu_c;
+pub mod sfu_command;
+pub mod sfu_peer;
+
+pub use sfu_c::Sfu;
+pub use sfu_command::SfuCommand;
+
+/// Reserved username the SFU answers to; no client may log in with it while
+/// the SFU is enabled.
+pub const SFU_USERNAME: &str = "sfu";
@@ -0,0 +1,255 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    core::{buffer_pool::BufferPool, events::EngineEvent},
+    log::log_sink::LogSink,
+    media_transport::media_transport_event::RtpIn,
+    sfu::{forwarder::Forwarder, sfu_command::SfuCommand, sfu_peer::SfuPeer},
+    signaling::{
+        protocol::{SessionId, SignalingMsg, UserName},
+        server_event::ServerEvent,
+    },
+    sink_info, sink_warn,
+};
+
+/// How long the loop waits for a command before polling the sessions;
+/// bounds the latency added to forwarded packets.
+const POLL_EVERY: Duration = Duration::from_millis(2);
+
+/// The media sessions of one signaling room.
+#[derive(Default)]
+struct Room {
+    forwarder: Forwarder,
+    peers: HashMap<UserName, SfuPeer>,
+}
+
+impl Room {
+    /// Forwards a packet `from` sent to the rest of the room.
+    fn relay(&mut self, from: &str, pkt: &RtpIn) {
+        let Some(publisher) = self.peers.get(from) else {
+            return;
+        };
+        let (Some(kind), Some(codec)) = (
+            publisher.kind_of(pkt.pt),
+            publisher.codec_name(pkt.pt).map(str::to_owned),
+        ) else {
+            return;
+        };
+        let forwarded = self.forwarder.forward(from, kind, pkt);
+        for track in forwarded.new_tracks {
+            if let Some(peer) = self.peers.get_mut(&track.subscriber) {
+                peer.add_track(track.ssrc);
+            }
+        }
+        for (member, packet) in forwarded.packets {
+            if let Some(peer) = self.peers.get(&member) {
+                peer.send(packet, &codec, kind);
+            }
+        }
+    }
+
+    /// Asks the publisher behind `subscriber`'s new stream `ssrc` for a
+    /// keyframe.
+    fn request_keyframe(&mut self, subscriber: &str, ssrc: u32, now: Instant) {
+        if let Some((publisher, source_ssrc)) = self.forwarder.on_subscribe(subscriber, ssrc, now)
+            && let Some(peer) = self.peers.get(&publisher)
+        {
+            peer.send_pli(source_ssrc);
+        }
+    }
+}
+
+/// Selective forwarding unit: instead of every member of a room sending its
+/// media to every other (full mesh), each one connects to the server only,
+/// calling the reserved user [`SFU_USERNAME`](super::SFU_USERNAME), and the
+/// server relays the streams. A member uploads its media once, whatever the
+/// size of the room.
+///
+/// Runs on its own thread; the signaling server hands it the messages
+/// members address to it and it answers through `ServerEvent::FromSfu`.
+pub struct Sfu {
+    rooms: HashMap<SessionId, Room>,
+    /// Packet buffers of every session, recycled once relayed.
+    buffers: BufferPool,
+    config: Arc<Config>,
+    log: Arc<dyn LogSink>,
+    to_server: Sender<ServerEvent>,
+}
+
+impl Sfu {
+    #[must_use]
+    pub fn new(config: Arc<Config>, log: Arc<dyn LogSink>, to_server: Sender<ServerEvent>) -> Self {
+        Self {
+            rooms: HashMap::new(),
+            buffers: BufferPool::default(),
+            config,
+            log,
+            to_server,
+        }
+    }
+
+    /// `[SFU] enabled` in the server configuration.
+    #[must_use]
+    pub fn enabled(config: &Config) -> bool {
+        config
+            .get("SFU", "enabled")
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+    }
+
+    /// Starts the SFU thread. It stops once every sender is dropped.
+    #[must_use]
+    pub fn spawn(self) -> Sender<SfuCommand> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || self.run(&rx));
+        tx
+    }
+
+    fn run(mut self, rx: &Receiver<SfuCommand>) {
+        sink_info!(self.log, "[SFU] started");
+        loop {
+            match rx.recv_timeout(POLL_EVERY) {
+                Ok(cmd) => self.handle(cmd),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            while let Ok(cmd) = rx.try_recv() {
+                self.handle(cmd);
+            }
+            self.poll_rooms();
+        }
+        sink_info!(self.log, "[SFU] stopped");
+    }
+
+    fn handle(&mut self, cmd: SfuCommand) {
+        match cmd {
+            SfuCommand::Signal { room, from, msg } => self.handle_signal(room, &from, msg),
+            SfuCommand::Leave { room, username } => self.leave(&room, &username),
+        }
+    }
+
+    fn handle_signal(&mut self, room_id: SessionId, from: &str, msg: SignalingMsg) {
+        match msg {
+            SignalingMsg::Bye { .. } => self.leave(&room_id, from),
+            SignalingMsg::Offer { txn_id, sdp, .. } => {
+                let room = self.rooms.entry(room_id).or_default();
+                room.forwarder.add_member(from);
+                let peer = room.peers.entry(from.to_owned()).or_insert_with(|| {
+                    SfuPeer::new(
+                        from.to_owned(),
+                        self.log.clone(),
+                        self.config.clone(),
+                        self.buffers.clone(),
+                    )
+                });
+                let out = peer.handle_offer(txn_id, &sdp);
+                self.send_all(out);
+            }
+            msg => {
+                let Some(room) = self.rooms.get_mut(&room_id) else {
+                    return;
+                };
+                let Some(peer) = room.peers.get_mut(from) else {
+                    return;
+                };
+                match msg {
+                    SignalingMsg::Answer { sdp, .. } => {
+                        let accepted = peer.handle_answer(&sdp);
+                        let now = Instant::now();
+                        for ssrc in accepted {
+                            room.request_keyframe(from, ssrc, now);
+                        }
+                    }
+                    SignalingMsg::Candidate { cand, .. } => peer.apply_candidate(&cand),
+                    SignalingMsg::Candidates { items, .. } => {
+                        for item in items {
+                            peer.apply_candidate(&item.cand);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Ends `member`'s session and stops forwarding its streams.
+    fn leave(&mut self, room_id: &str, member: &str) {
+        let Some(room) = self.rooms.get_mut(room_id) else {
+            return;
+        };
+        if room.peers.remove(member).is_some() {
+            sink_info!(self.log, "[SFU] {} left room {}", member, room_id);
+        }
+        for track in room.forwarder.remove_member(member) {
+            if let Some(peer) = room.peers.get_mut(&track.subscriber) {
+                peer.remove_track(track.ssrc);
+            }
+        }
+        if room.peers.is_empty() {
+            self.rooms.remove(room_id);
+        }
+    }
+
+    /// Relays what every session received and renegotiates the members whose
+    /// forwarded tracks changed.
+    fn poll_rooms(&mut self) {
+        let now = Instant::now();
+        let mut out = Vec::new();
+        let mut closed = Vec::new();
+        for (room_id, room) in &mut self.rooms {
+            let events: Vec<(UserName, Vec<EngineEvent>)> = room
+                .peers
+                .iter_mut()
+                .map(|(member, peer)| (member.clone(), peer.poll()))
+                .collect();
+            for (member, events) in events {
+                for ev in events {
+                    match ev {
+                        EngineEvent::RtpIn(pkt) => {
+                            room.relay(&member, &pkt);
+                            self.buffers.give(pkt.payload);
+                        }
+                        EngineEvent::RemoteAudioLevel(level) => {
+                            room.forwarder.on_audio_level(&member, level.voice, now);
+                        }
+                        EngineEvent::KeyframeRequested { ssrc } => {
+                            if let Some((publisher, source_ssrc)) =
+                                room.forwarder.on_pli(&member, ssrc, now)
+                                && let Some(peer) = room.peers.get(&publisher)
+                            {
+                                peer.send_pli(source_ssrc);
+                            }
+                        }
+                        EngineEvent::Closed => closed.push((room_id.clone(), member.clone())),
+                        EngineEvent::Error(e) => {
+                            sink_warn!(self.log, "[SFU] {} in room {}: {}", member, room_id, e);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            out.extend(room.peers.values_mut().filter_map(SfuPeer::renegotiate));
+        }
+        for (room_id, member) in closed {
+            self.leave(&room_id, &member);
+        }
+        self.send_all(out);
+    }
+
+    fn send_all(&self, msgs: Vec<SignalingMsg>) {
+        for msg in msgs {
+            if self.to_server.send(ServerEvent::FromSfu { msg }).is_err() {
+                sink_warn!(self.log, "[SFU] server loop is gone");
+                return;
+            }
+        }
+    }
+}
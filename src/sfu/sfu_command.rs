@@ -0,0 +1,15 @@
+use crate::signaling::protocol::{SessionId, SignalingMsg, UserName};
+
+/// Requests from the signaling server to the SFU thread.
+#[derive(Debug)]
+pub enum SfuCommand {
+    /// `from`, a member of `room`, sent an Offer/Answer/Candidate/Bye
+    /// addressed to the SFU.
+    Signal {
+        room: SessionId,
+        from: UserName,
+        msg: SignalingMsg,
+    },
+    /// `username` left `room` or disconnected: its media session ends.
+    Leave { room: SessionId, username: UserName },
+}
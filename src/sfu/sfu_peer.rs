@@ -0,0 +1,448 @@
+use std::{
+    collections::HashSet,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        mpsc::{self, Receiver, Sender, TryRecvError},
+    },
+    thread,
+    time::Duration,
+};
+
+use openssl::ssl::SslStream;
+
+use crate::{
+    config::Config,
+    connection_manager::{ConnectionManager, OutboundSdp},
+    core::{
+        buffer_pool::BufferPool,
+        consent::ConsentConfig,
+        events::EngineEvent,
+        path_mtu::MtuConfig,
+        session::{Session, SessionConfig, SessionInitArgs},
+    },
+    dtls::{self, DtlsRole, buffered_udp_channel::BufferedUdpChannel},
+    log::log_sink::LogSink,
+    media_agent::video_track::TrackId,
+    media_transport::codec::CodecDescriptor,
+    rtp::rtp_packet::RtpPacket,
+    sfu::{SFU_USERNAME, forwarder::StreamKind},
+    signaling::protocol::{SignalingMsg, TxnId, UserName},
+    sink_debug, sink_info, sink_warn,
+    srtp::SrtpSessionConfig,
+};
+
+type Handshake = Result<
+    (
+        Arc<UdpSocket>,
+        SocketAddr,
+        SrtpSessionConfig,
+        SslStream<BufferedUdpChannel>,
+    ),
+    String,
+>;
+
+/// The server's end of one room member's connection: ICE, DTLS and a
+/// `Session` whose RTP is relayed by the [`Sfu`](super::Sfu), with no media
+/// pipeline of its own.
+pub struct SfuPeer {
+    username: UserName,
+    cm: ConnectionManager,
+    config: Arc<Config>,
+    logger: Arc<dyn LogSink>,
+    event_tx: Sender<EngineEvent>,
+    event_rx: Receiver<EngineEvent>,
+    /// Shared by the SFU's sessions; relayed payloads go back to it.
+    buffers: BufferPool,
+    handshake: Option<Receiver<Handshake>>,
+    session: Option<Session>,
+    /// Video streams we forward to the member, announced as tracks
+    tracks: Vec<(TrackId, u32)>,
+    next_track_id: TrackId,
+    /// Tracks the member has accepted in an answer, so it can decode them
+    accepted: HashSet<u32>,
+    /// Remote tracks registered as recv streams
+    remote_tracks: HashSet<u32>,
+    /// Our offer awaiting an answer
+    offer_pending: bool,
+    /// Tracks changed since the last offer
+    tracks_changed: bool,
+    next_txn_id: TxnId,
+}
+
+impl SfuPeer {
+    #[must_use]
+    pub fn new(
+        username: UserName,
+        logger: Arc<dyn LogSink>,
+        config: Arc<Config>,
+        buffers: BufferPool,
+    ) -> Self {
+        let mut cm = ConnectionManager::new(logger.clone(), config.clone());
+        cm.set_local_rtp_codecs(vec![
+            CodecDescriptor::pcmu_dynamic(0),
+            CodecDescriptor::h264_dynamic(96),
+            CodecDescriptor::telephone_event_dynamic(97),
+        ]);
+        let (event_tx, event_rx) = mpsc::channel();
+        Self {
+            username,
+            cm,
+            config,
+            logger,
+            event_tx,
+            event_rx,
+            buffers,
+            handshake: None,
+            session: None,
+            tracks: Vec::new(),
+            next_track_id: 1,
+            accepted: HashSet::new(),
+            remote_tracks: HashSet::new(),
+            offer_pending: false,
+            tracks_changed: false,
+            next_txn_id: 1,
+        }
+    }
+
+    #[must_use]
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Answers an offer (the first one, or a renegotiation from the member).
+    /// Returns what to send back: an `Ack` then the `Answer`.
+    pub fn handle_offer(&mut self, txn_id: TxnId, sdp: &[u8]) -> Vec<SignalingMsg> {
+        let mut out = vec![SignalingMsg::Ack {
+            from: SFU_USERNAME.to_string(),
+            to: self.username.clone(),
+            txn_id,
+        }];
+        let Ok(sdp) = std::str::from_utf8(sdp) else {
+            sink_warn!(
+                self.logger,
+                "[SFU] offer from {} is not UTF-8",
+                self.username
+            );
+            return out;
+        };
+        // Their offer replaces ours; the tracks go again in the next one
+        if self.offer_pending {
+            self.offer_pending = false;
+            self.tracks_changed = true;
+        }
+        match self.cm.apply_remote_sdp(sdp) {
+            Ok(OutboundSdp::Answer(answer)) => {
+                self.sync_remote_tracks();
+                out.push(SignalingMsg::Answer {
+                    txn_id,
+                    from: SFU_USERNAME.to_string(),
+                    to: self.username.clone(),
+                    sdp: answer.encode().into_bytes(),
+                });
+            }
+            Ok(_) => {}
+            Err(e) => sink_warn!(
+                self.logger,
+                "[SFU] cannot answer {}: {:?}",
+                self.username,
+                e
+            ),
+        }
+        out
+    }
+
+    /// Applies the member's answer to our renegotiation offer. Returns the
+    /// tracks it accepted with it.
+    pub fn handle_answer(&mut self, sdp: &[u8]) -> Vec<u32> {
+        let Ok(sdp) = std::str::from_utf8(sdp) else {
+            return Vec::new();
+        };
+        if !self.offer_pending {
+            sink_debug!(
+                self.logger,
+                "[SFU] unexpected answer from {}",
+                self.username
+            );
+            return Vec::new();
+        }
+        self.offer_pending = false;
+        if let Err(e) = self.cm.apply_remote_sdp(sdp) {
+            sink_warn!(
+                self.logger,
+                "[SFU] bad answer from {}: {:?}",
+                self.username,
+                e
+            );
+            return Vec::new();
+        }
+        self.sync_remote_tracks();
+        let announced: HashSet<u32> = self.tracks.iter().map(|(_, ssrc)| *ssrc).collect();
+        let added = announced.difference(&self.accepted).copied().collect();
+        self.accepted = announced;
+        added
+    }
+
+    pub fn apply_candidate(&mut self, cand: &[u8]) {
+        let Ok(line) = std::str::from_utf8(cand) else {
+            return;
+        };
+        if let Err(e) = self.cm.apply_remote_trickle_candidate(line) {
+            sink_debug!(
+                self.logger,
+                "[SFU] ignoring candidate from {}: {:?}",
+                self.username,
+                e
+            );
+        }
+    }
+
+    /// Announces a video stream we forward to the member, in the next offer.
+    pub fn add_track(&mut self, ssrc: u32) {
+        let id = self.next_track_id;
+        self.next_track_id += 1;
+        self.cm.add_local_track(id, ssrc);
+        self.tracks.push((id, ssrc));
+        self.tracks_changed = true;
+    }
+
+    pub fn remove_track(&mut self, ssrc: u32) {
+        if let Some(pos) = self.tracks.iter().position(|(_, s)| *s == ssrc) {
+            let (id, _) = self.tracks.remove(pos);
+            self.cm.remove_local_track(id);
+            self.accepted.remove(&ssrc);
+            self.tracks_changed = true;
+        }
+    }
+
+    /// A renegotiation offer announcing the current tracks, if they changed
+    /// and no offer is already waiting for its answer.
+    pub fn renegotiate(&mut self) -> Option<SignalingMsg> {
+        if !self.tracks_changed || self.offer_pending || self.session.is_none() {
+            return None;
+        }
+        match self.cm.negotiate() {
+            Ok(OutboundSdp::Offer(offer)) => {
+                self.tracks_changed = false;
+                self.offer_pending = true;
+                let txn_id = self.next_txn_id;
+                self.next_txn_id += 1;
+                Some(SignalingMsg::Offer {
+                    txn_id,
+                    from: SFU_USERNAME.to_string(),
+                    to: self.username.clone(),
+                    sdp: offer.encode().into_bytes(),
+                })
+            }
+            Ok(_) => None,
+            Err(e) => {
+                sink_warn!(
+                    self.logger,
+                    "[SFU] cannot renegotiate with {}: {:?}",
+                    self.username,
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Drives ICE and DTLS until the session runs, then returns what the
+    /// session reported since the last call.
+    pub fn poll(&mut self) -> Vec<EngineEvent> {
+        self.cm.drain_ice_events();
+        if self.session.is_none() && self.handshake.is_none() {
+            self.start_handshake();
+        }
+        if let Some(rx) = &self.handshake {
+            match rx.try_recv() {
+                Ok(Ok((sock, peer, srtp_cfg, ssl_stream))) => {
+                    self.handshake = None;
+                    self.start_session(sock, peer, srtp_cfg, ssl_stream);
+                }
+                Ok(Err(e)) => {
+                    self.handshake = None;
+                    let _ = self.event_tx.send(EngineEvent::Error(e));
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.handshake = None,
+            }
+        }
+        let mut out = Vec::new();
+        while let Ok(ev) = self.event_rx.try_recv() {
+            out.push(ev);
+        }
+        out
+    }
+
+    /// Once ICE nominated a pair, runs the (blocking) DTLS handshake on its
+    /// own thread so other members are not held up.
+    fn start_handshake(&mut self) {
+        let Ok((sock, peer)) = self.cm.ice_agent.get_data_channel_socket() else {
+            return;
+        };
+        if let Err(e) = sock.connect(peer) {
+            let _ = self
+                .event_tx
+                .send(EngineEvent::Error(format!("socket.connect: {e}")));
+            return;
+        }
+        self.cm.stop_ice_worker();
+        sink_info!(
+            self.logger,
+            "[SFU] ICE nominated {} for {}",
+            peer,
+            self.username
+        );
+        let (tx, rx) = mpsc::channel();
+        let role = self.cm.dtls_role();
+        let remote_fp = self.cm.remote_fingerprint.clone();
+        let logger = self.logger.clone();
+        let config = self.config.clone();
+        thread::spawn(move || {
+            let result = dtls::run_dtls_handshake(
+                Arc::clone(&sock),
+                peer,
+                role,
+                logger,
+                Duration::from_secs(5),
+                remote_fp,
+                config,
+            )
+            .map(|(srtp_cfg, ssl_stream)| (sock, peer, srtp_cfg, ssl_stream))
+            .map_err(|e| format!("DTLS handshake failed: {e}"));
+            let _ = tx.send(result);
+        });
+        self.handshake = Some(rx);
+    }
+
+    fn start_session(
+        &mut self,
+        sock: Arc<UdpSocket>,
+        peer: SocketAddr,
+        srtp_cfg: SrtpSessionConfig,
+        ssl_stream: SslStream<BufferedUdpChannel>,
+    ) {
+        let mut session = Session::new(SessionInitArgs {
+            sock,
+            peer,
+            remote_codecs: self.cm.remote_codecs().clone(),
+            event_tx: self.event_tx.clone(),
+            logger: self.logger.clone(),
+            cfg: SessionConfig {
+                close_timeout: Duration::from_secs(5),
+                keepalive_every: ConsentConfig::from_config(&self.config).keepalive_every,
+                // Forwarded packets keep the size their sender gave them
+                mtu: MtuConfig {
+                    discovery: false,
+                    ..MtuConfig::from_config(&self.config)
+                },
+            },
+            srtp_cfg: Some(srtp_cfg),
+            ssl_stream,
+            is_client: self.cm.dtls_role() == DtlsRole::Client,
+            ice_tx: None,
+            event_log: None,
+            buffers: self.buffers.clone(),
+        });
+        session.start();
+        sink_info!(self.logger, "[SFU] media session up with {}", self.username);
+        self.session = Some(session);
+        self.remote_tracks.clear();
+        self.sync_remote_tracks();
+    }
+
+    /// Receives the extra video tracks (screen shares) the member announces.
+    fn sync_remote_tracks(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let Some(codec) = self
+            .cm
+            .remote_codecs()
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case("H264"))
+            .cloned()
+        else {
+            return;
+        };
+        for track in self.cm.remote_tracks() {
+            if self.remote_tracks.insert(track.ssrc)
+                && let Err(e) = session.register_inbound_track(codec.clone(), track.ssrc)
+            {
+                sink_debug!(self.logger, "[SFU] track {}: {}", track.ssrc, e);
+            }
+        }
+    }
+
+    /// What the member's payload type `pt` carries.
+    #[must_use]
+    pub fn kind_of(&self, pt: u8) -> Option<StreamKind> {
+        let codec = self
+            .cm
+            .remote_codecs()
+            .iter()
+            .find(|c| c.payload_type == pt)?;
+        Some(if codec.name.eq_ignore_ascii_case("H264") {
+            StreamKind::Video
+        } else {
+            StreamKind::Audio
+        })
+    }
+
+    /// The codec name behind the member's payload type `pt`.
+    #[must_use]
+    pub fn codec_name(&self, pt: u8) -> Option<&str> {
+        self.cm
+            .remote_codecs()
+            .iter()
+            .find(|c| c.payload_type == pt)
+            .map(|c| c.name.as_str())
+    }
+
+    /// Sends a forwarded packet, with the payload type the member uses for
+    /// `codec`. Video goes only on tracks the member accepted.
+    pub fn send(&self, mut packet: RtpPacket, codec: &str, kind: StreamKind) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        if kind == StreamKind::Video && !self.accepted.contains(&packet.ssrc()) {
+            return;
+        }
+        let Some(pt) = self
+            .cm
+            .remote_codecs()
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(codec))
+            .map(|c| c.payload_type)
+        else {
+            return;
+        };
+        packet.header.payload_type = pt;
+        if let Err(e) = session.forward_rtp(&packet) {
+            sink_debug!(self.logger, "[SFU] forward to {}: {}", self.username, e);
+        }
+    }
+
+    /// Asks the member for a keyframe of its stream `ssrc`.
+    pub fn send_pli(&self, ssrc: u32) {
+        if let Some(session) = &self.session {
+            let _ = session.send_pli(ssrc);
+        }
+    }
+
+    /// Ends the media session; the member said `Bye` or left the room.
+    pub fn close(&mut self) {
+        self.cm.stop_ice_worker();
+        if let Some(session) = &mut self.session {
+            session.request_close();
+        }
+        self.session = None;
+    }
+}
+
+impl Drop for SfuPeer {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
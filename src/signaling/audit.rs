@@ -0,0 +1,196 @@
+//! Audit trail of what clients did on the signaling server.
+//!
+//! Logins, registrations, session creation, joins, leaves and disconnects
+//! are written as JSON lines, one per event, apart from the debug log:
+//!
+//! ```text
+//! {"ts_ms":1730000000000,"event":"login","client_id":3,"ip":"10.0.0.7:51234","username":"alice","outcome":"ok"}
+//! ```
+//!
+//! `ip` is `null` when the connection's address is unknown and `username`
+//! when the client had not logged in. `session` is present for session
+//! events. The server writes the trail to `[Signaling] audit_log_path`.
+
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::{self, Write},
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::{
+    config::Config, core::stats::json_str, log::log_sink::LogSink, media_agent::utils::now_millis,
+    signaling::types::ClientId, sink_info, sink_warn,
+};
+
+/// What a client did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    CertificateLogin,
+    Register,
+    CreateSession,
+    Join,
+    Leave,
+    Disconnect,
+}
+
+impl AuditAction {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Login => "login",
+            Self::CertificateLogin => "certificate_login",
+            Self::Register => "register",
+            Self::CreateSession => "create_session",
+            Self::Join => "join",
+            Self::Leave => "leave",
+            Self::Disconnect => "disconnect",
+        }
+    }
+}
+
+/// One audited event.
+#[derive(Debug, Clone)]
+pub struct AuditRecord<'a> {
+    pub action: AuditAction,
+    pub client_id: ClientId,
+    pub ip: Option<SocketAddr>,
+    pub username: Option<&'a str>,
+    /// `"ok"`, or why the server refused.
+    pub outcome: &'a str,
+    pub session: Option<&'a str>,
+}
+
+impl AuditRecord<'_> {
+    /// Renders the record as one JSON object, without the trailing newline.
+    #[must_use]
+    pub fn to_json_line(&self, ts_ms: u128) -> String {
+        let mut out = format!(
+            "{{\"ts_ms\":{},\"event\":{},\"client_id\":{}",
+            ts_ms,
+            json_str(self.action.as_str()),
+            self.client_id
+        );
+        match self.ip {
+            Some(ip) => {
+                let _ = write!(out, ",\"ip\":{}", json_str(&ip.to_string()));
+            }
+            None => out.push_str(",\"ip\":null"),
+        }
+        match self.username {
+            Some(username) => {
+                let _ = write!(out, ",\"username\":{}", json_str(username));
+            }
+            None => out.push_str(",\"username\":null"),
+        }
+        let _ = write!(out, ",\"outcome\":{}", json_str(self.outcome));
+        if let Some(session) = self.session {
+            let _ = write!(out, ",\"session\":{}", json_str(session));
+        }
+        out.push('}');
+        out
+    }
+}
+
+/// Where audit records go. The default writes nowhere; clones share the
+/// destination.
+#[derive(Clone, Default)]
+pub struct AuditLog {
+    out: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+}
+
+impl AuditLog {
+    /// Appends records to the file at `path`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file cannot be opened.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::to_writer(file))
+    }
+
+    /// Writes records to `out`.
+    #[must_use]
+    pub fn to_writer(out: impl Write + Send + 'static) -> Self {
+        Self {
+            out: Some(Arc::new(Mutex::new(Box::new(out)))),
+        }
+    }
+
+    /// The audit log `[Signaling] audit_log_path` asks for. A file that
+    /// cannot be opened is logged and leaves auditing off.
+    #[must_use]
+    pub fn from_config(config: &Config, log: &dyn LogSink) -> Self {
+        let Some(path) = config.get_non_empty("Signaling", "audit_log_path") else {
+            return Self::default();
+        };
+        match Self::open(path) {
+            Ok(audit) => {
+                sink_info!(log, "[signaling] writing the audit log to {}", path);
+                audit
+            }
+            Err(e) => {
+                sink_warn!(log, "[signaling] cannot open audit log {}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether records go anywhere.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.out.is_some()
+    }
+
+    /// Writes and flushes one record. Write errors are ignored: a full disk
+    /// must not stop the server.
+    pub fn record(&self, record: &AuditRecord<'_>) {
+        let Some(out) = &self.out else {
+            return;
+        };
+        let mut line = record.to_json_line(now_millis());
+        line.push('\n');
+        let mut out = out.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = out.write_all(line.as_bytes()).and_then(|()| out.flush());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn records_render_as_json_lines() {
+        let record = AuditRecord {
+            action: AuditAction::Join,
+            client_id: 3,
+            ip: Some("10.0.0.7:51234".parse().unwrap()),
+            username: Some("al\"ice"),
+            outcome: "full",
+            session: Some("123456"),
+        };
+        assert_eq!(
+            record.to_json_line(42),
+            "{\"ts_ms\":42,\"event\":\"join\",\"client_id\":3,\"ip\":\"10.0.0.7:51234\",\
+             \"username\":\"al\\\"ice\",\"outcome\":\"full\",\"session\":\"123456\"}"
+        );
+
+        let anonymous = AuditRecord {
+            action: AuditAction::Disconnect,
+            client_id: 4,
+            ip: None,
+            username: None,
+            outcome: "ok",
+            session: None,
+        };
+        assert_eq!(
+            anonymous.to_json_line(1),
+            "{\"ts_ms\":1,\"event\":\"disconnect\",\"client_id\":4,\"ip\":null,\
+             \"username\":null,\"outcome\":\"ok\"}"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+};
+
+use crate::signaling::auth::AuthBackend;
+use crate::signaling::server_event::ServerEvent;
+use crate::signaling::types::ClientId;
+
+/// How many logins are checked at once unless told otherwise.
+pub const DEFAULT_MAX_CHECKS: usize = 8;
+
+/// Checks logins on worker threads, for backends too slow to run on the
+/// server loop (an external command may take seconds).
+///
+/// Each check posts its outcome back to the loop as
+/// [`ServerEvent::LoginChecked`]. At most `max` checks run at once; a login
+/// beyond that is not started, and the caller refuses it.
+pub struct AuthWorkers {
+    backend: Arc<dyn AuthBackend>,
+    events: Sender<ServerEvent>,
+    in_flight: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl AuthWorkers {
+    #[must_use]
+    pub fn new(backend: impl AuthBackend + 'static, events: Sender<ServerEvent>) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            events,
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max: DEFAULT_MAX_CHECKS,
+        }
+    }
+
+    /// Builder-style: run at most `max` checks at once (at least one).
+    #[must_use]
+    pub fn with_max(mut self, max: usize) -> Self {
+        self.max = max.max(1);
+        self
+    }
+
+    /// Starts checking `username` and `password` for `client_id`. Returns
+    /// `false`, without starting anything, when `max` checks are running.
+    pub fn check(&self, client_id: ClientId, username: String, password: String) -> bool {
+        let reserved = self
+            .in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.max).then_some(n + 1)
+            })
+            .is_ok();
+        if !reserved {
+            return false;
+        }
+        let slot = Slot(self.in_flight.clone());
+        let backend = self.backend.clone();
+        let events = self.events.clone();
+        thread::spawn(move || {
+            let result = backend.verify(&username, &password);
+            // Free the slot first, so the loop may start another check as
+            // soon as it sees this one's outcome
+            drop(slot);
+            let _ = events.send(ServerEvent::LoginChecked {
+                client_id,
+                username,
+                result,
+            });
+        });
+        true
+    }
+}
+
+/// One running check; frees its place when dropped, even if the backend
+/// panicked.
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::signaling::auth::AuthError;
+    use std::sync::{Mutex, mpsc};
+    use std::time::Duration;
+
+    /// Accepts "alice" once `release` gets a message.
+    struct Gated {
+        release: Mutex<mpsc::Receiver<()>>,
+    }
+
+    impl AuthBackend for Gated {
+        fn verify(&self, username: &str, _password: &str) -> Result<(), AuthError> {
+            self.release.lock().unwrap().recv().unwrap();
+            if username == "alice" {
+                Ok(())
+            } else {
+                Err(AuthError::InvalidCredentials)
+            }
+        }
+    }
+
+    #[test]
+    fn checks_run_off_the_caller_and_are_capped() {
+        let (release_tx, release_rx) = mpsc::channel();
+        let (ev_tx, ev_rx) = mpsc::channel();
+        let workers = AuthWorkers::new(
+            Gated {
+                release: Mutex::new(release_rx),
+            },
+            ev_tx,
+        )
+        .with_max(1);
+
+        // Returns while the backend is still blocked
+        assert!(workers.check(1, "alice".into(), "pw".into()));
+        assert!(!workers.check(2, "bob".into(), "pw".into()));
+
+        release_tx.send(()).unwrap();
+        match ev_rx.recv_timeout(Duration::from_secs(2)).unwrap() {
+            ServerEvent::LoginChecked {
+                client_id,
+                username,
+                result,
+            } => {
+                assert_eq!((client_id, username.as_str(), result), (1, "alice", Ok(())));
+            }
+            _ => panic!("expected LoginChecked"),
+        }
+
+        // The finished check freed its place
+        assert!(workers.check(2, "bob".into(), "pw".into()));
+        release_tx.send(()).unwrap();
+        assert!(matches!(
+            ev_rx.recv_timeout(Duration::from_secs(2)).unwrap(),
+            ServerEvent::LoginChecked {
+                result: Err(AuthError::InvalidCredentials),
+                ..
+            }
+        ));
+    }
+}
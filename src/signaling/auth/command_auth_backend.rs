@@ -0,0 +1,154 @@
+use std::{
+    io::{self, Write},
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::config::Config;
+use crate::signaling::auth::{AuthBackend, AuthError};
+
+/// How long a check may run when `[Signaling] auth_command_timeout_ms` is
+/// not set.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Auth backend that asks an external program, so an organization can check
+/// logins against a directory it already has (an LDAP bind through
+/// `ldapwhoami`, a PAM helper, a curl call to an HTTP hook).
+///
+/// The program gets the username and the password on stdin, one per line,
+/// so neither shows up in the process list. Exit status 0 accepts the login,
+/// 1 rejects the credentials; anything else, a crash or running past the
+/// timeout is an internal error. Accounts live in the directory, so
+/// registration is unsupported.
+#[derive(Debug, Clone)]
+pub struct CommandAuthBackend {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+}
+
+impl CommandAuthBackend {
+    #[must_use]
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Builder-style: kill checks that run longer than `timeout`.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The backend `[Signaling] auth_command` names, if any. The command is
+    /// split on whitespace into the program and its arguments.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let command = config.get_non_empty("Signaling", "auth_command")?;
+        let mut words = command.split_whitespace().map(str::to_owned);
+        let program = words.next()?;
+        let mut backend = Self::new(program, words.collect());
+        if let Some(ms) = config
+            .get_non_empty("Signaling", "auth_command_timeout_ms")
+            .and_then(|v| v.parse::<u64>().ok())
+        {
+            backend = backend.with_timeout(Duration::from_millis(ms));
+        }
+        Some(backend)
+    }
+
+    /// Runs the program on one set of credentials.
+    fn run(&self, username: &str, password: &str) -> io::Result<Option<ExitStatus>> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A program that exits without reading its input is still heard.
+            let _ = writeln!(stdin, "{username}\n{password}");
+        }
+        self.wait(&mut child)
+    }
+
+    /// Waits for `child` until the timeout, then kills it and returns `None`.
+    fn wait(&self, child: &mut Child) -> io::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(None);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl AuthBackend for CommandAuthBackend {
+    fn verify(&self, username: &str, password: &str) -> Result<(), AuthError> {
+        // Each goes on its own line; a newline would shift them.
+        if username.is_empty() || username.contains('\n') || password.contains('\n') {
+            return Err(AuthError::InvalidCredentials);
+        }
+        match self.run(username, password) {
+            Ok(Some(status)) => match status.code() {
+                Some(0) => Ok(()),
+                Some(1) => Err(AuthError::InvalidCredentials),
+                _ => Err(AuthError::Internal),
+            },
+            Ok(None) | Err(_) => Err(AuthError::Internal),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::signaling::auth::RegisterError;
+
+    fn sh(script: &str) -> CommandAuthBackend {
+        CommandAuthBackend::new("sh", vec!["-c".into(), script.into()])
+    }
+
+    #[test]
+    fn exit_status_decides_the_login() {
+        let mut backend =
+            sh(r#"read u; read p; [ "$u" = alice ] || exit 1; [ "$p" = secret ] || exit 1"#);
+        assert_eq!(backend.verify("alice", "secret"), Ok(()));
+        assert_eq!(
+            backend.verify("alice", "wrong"),
+            Err(AuthError::InvalidCredentials)
+        );
+        assert_eq!(
+            backend.verify("alice", "secret\nalice"),
+            Err(AuthError::InvalidCredentials)
+        );
+        assert_eq!(sh("exit 3").verify("bob", "pw"), Err(AuthError::Internal));
+        assert_eq!(
+            backend.register("carol", "pw"),
+            Err(RegisterError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn slow_or_missing_programs_are_internal_errors() {
+        let slow = sh("sleep 5").with_timeout(Duration::from_millis(50));
+        let started = Instant::now();
+        assert_eq!(slow.verify("alice", "pw"), Err(AuthError::Internal));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let missing = CommandAuthBackend::new("/nonexistent/auth-helper", Vec::new());
+        assert_eq!(missing.verify("alice", "pw"), Err(AuthError::Internal));
+    }
+}
@@ -1,10 +1,14 @@
 mod auth_backend;
 mod auth_error;
+mod auth_workers;
+mod command_auth_backend;
 mod file_user_store;
 mod in_memory_auth_backend;
 mod register_error;
 pub use auth_backend::AuthBackend;
 pub use auth_error::AuthError;
+pub use auth_workers::{AuthWorkers, DEFAULT_MAX_CHECKS};
+pub use command_auth_backend::CommandAuthBackend;
 pub use file_user_store::FileUserStore;
 pub use in_memory_auth_backend::{AllowAllAuthBackend, InMemoryAuthBackend};
 pub use register_error::RegisterError;
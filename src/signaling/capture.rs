@@ -0,0 +1,428 @@
+//! Recording and replay of signaling traffic, for debugging.
+//!
+//! With `[Signaling] record_path` set, the client records every message it
+//! exchanges with the server, and the server every message it exchanges
+//! with its clients, to that file. A recording can then be fed back:
+//! [`replay_into_server`] runs the messages the server received through a
+//! fresh [`ServerEngine`], and [`ReplayTransport`] plays the messages a client
+//! received to a [`SignalingClient`](crate::signaling_client::SignalingClient).
+//! Either way a session captured in the field becomes a regression test.
+//!
+//! The file starts with [`MAGIC`]; each record is then the milliseconds since
+//! recording started (`u64`), a [`CaptureKind`] byte, the client id (`u64`,
+//! 0 on the client side), all big-endian, and for messages the frame as sent
+//! on the wire.
+
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    log::log_sink::LogSink,
+    signaling::{
+        protocol::{self, FrameError, ProtoError, SignalingMsg},
+        server_engine::ServerEngine,
+        types::{ClientId, OutgoingMsg},
+    },
+    signaling_client::transport::SignalingTransport,
+    sink_info, sink_warn,
+};
+
+/// First bytes of a recording.
+pub const MAGIC: &[u8; 8] = b"RRTCSIG1";
+
+/// What a record holds, as seen by whoever recorded it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureKind {
+    Received = 0,
+    Sent = 1,
+    /// The connection closed; no message follows.
+    Disconnected = 2,
+}
+
+impl CaptureKind {
+    const fn from_u8(b: u8) -> Option<Self> {
+        match b {
+            0 => Some(Self::Received),
+            1 => Some(Self::Sent),
+            2 => Some(Self::Disconnected),
+            _ => None,
+        }
+    }
+}
+
+/// One record of a recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Captured {
+    /// Time since the recording started.
+    pub at: Duration,
+    pub kind: CaptureKind,
+    /// The client the message came from or went to; 0 on the client side.
+    pub client_id: ClientId,
+    /// `None` for [`CaptureKind::Disconnected`].
+    pub msg: Option<SignalingMsg>,
+}
+
+/// Appends signaling traffic to a recording. Clones share the file.
+#[derive(Clone)]
+pub struct SignalingRecorder {
+    inner: Arc<Mutex<RecorderInner>>,
+}
+
+struct RecorderInner {
+    out: Box<dyn Write + Send>,
+    started: Instant,
+}
+
+impl SignalingRecorder {
+    /// Starts a recording in a new file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::to_writer(BufWriter::new(File::create(path)?))
+    }
+
+    /// Starts a recording written to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the header cannot be written.
+    pub fn to_writer(mut out: impl Write + Send + 'static) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.flush()?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(RecorderInner {
+                out: Box::new(out),
+                started: Instant::now(),
+            })),
+        })
+    }
+
+    /// The recorder `[Signaling] record_path` asks for, if any. A file that
+    /// cannot be created is logged and leaves recording off.
+    #[must_use]
+    pub fn from_config(config: &Config, log: &dyn LogSink) -> Option<Self> {
+        let path = config.get_non_empty("Signaling", "record_path")?;
+        match Self::create(path) {
+            Ok(recorder) => {
+                sink_info!(log, "[signaling] recording signaling traffic to {}", path);
+                Some(recorder)
+            }
+            Err(e) => {
+                sink_warn!(log, "[signaling] cannot record to {}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Records a message received from `client_id`.
+    pub fn received(&self, client_id: ClientId, msg: &SignalingMsg) {
+        self.record(CaptureKind::Received, client_id, Some(msg));
+    }
+
+    /// Records a message sent to `client_id`.
+    pub fn sent(&self, client_id: ClientId, msg: &SignalingMsg) {
+        self.record(CaptureKind::Sent, client_id, Some(msg));
+    }
+
+    /// Records that the connection of `client_id` closed.
+    pub fn disconnected(&self, client_id: ClientId) {
+        self.record(CaptureKind::Disconnected, client_id, None);
+    }
+
+    /// Writes and flushes one record, so a crash loses nothing. A recording
+    /// is a debugging aid: write errors are ignored.
+    fn record(&self, kind: CaptureKind, client_id: ClientId, msg: Option<&SignalingMsg>) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let ms = inner.started.elapsed().as_millis() as u64;
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&ms.to_be_bytes());
+        buf.push(kind as u8);
+        buf.extend_from_slice(&client_id.to_be_bytes());
+        if let Some(msg) = msg
+            && protocol::write_msg(&mut buf, msg).is_err()
+        {
+            return;
+        }
+        let _ = inner.out.write_all(&buf).and_then(|()| inner.out.flush());
+    }
+}
+
+/// Reads a whole recording. A record cut short at the end, as left by a
+/// crash, ends it.
+///
+/// # Errors
+///
+/// Returns `FrameError` if the header is wrong, or a record cannot be read or
+/// decoded.
+pub fn read_recording<R: Read>(mut r: R) -> Result<Vec<Captured>, FrameError> {
+    let mut magic = [0u8; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(ProtoError::InvalidFormat("not a signaling recording").into());
+    }
+
+    let mut records = Vec::new();
+    loop {
+        let mut head = [0u8; 17];
+        match r.read_exact(&mut head) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        let (mut ms, mut client) = ([0u8; 8], [0u8; 8]);
+        ms.copy_from_slice(&head[..8]);
+        client.copy_from_slice(&head[9..]);
+        let at = Duration::from_millis(u64::from_be_bytes(ms));
+        let client_id = ClientId::from_be_bytes(client);
+        let kind = CaptureKind::from_u8(head[8])
+            .ok_or(ProtoError::InvalidFormat("unknown record kind"))?;
+        let msg = match kind {
+            CaptureKind::Disconnected => None,
+            CaptureKind::Received | CaptureKind::Sent => match protocol::read_msg(&mut r) {
+                Ok(msg) => Some(msg),
+                Err(FrameError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            },
+        };
+        records.push(Captured {
+            at,
+            kind,
+            client_id,
+            msg,
+        });
+    }
+    Ok(records)
+}
+
+/// Runs what a server recorded receiving through `engine`, in order, and
+/// returns everything the engine sent back. Compare it with the `Sent`
+/// records to check that the server still answers the same way.
+pub fn replay_into_server(engine: &mut ServerEngine, records: &[Captured]) -> Vec<OutgoingMsg> {
+    let mut out = Vec::new();
+    for record in records {
+        match (record.kind, &record.msg) {
+            (CaptureKind::Received, Some(msg)) => {
+                out.extend(engine.handle(record.client_id, msg.clone()));
+            }
+            (CaptureKind::Disconnected, _) => {
+                out.extend(engine.handle_disconnect(record.client_id));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Wraps a transport and records everything read from and written to it.
+pub struct RecordingTransport {
+    inner: Box<dyn SignalingTransport>,
+    recorder: SignalingRecorder,
+}
+
+impl RecordingTransport {
+    #[must_use]
+    pub fn new(inner: Box<dyn SignalingTransport>, recorder: SignalingRecorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl SignalingTransport for RecordingTransport {
+    fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
+        self.inner.write_msg(msg)?;
+        self.recorder.sent(0, msg);
+        Ok(())
+    }
+
+    fn read_msg(&mut self) -> Result<SignalingMsg, FrameError> {
+        let msg = self.inner.read_msg()?;
+        self.recorder.received(0, &msg);
+        Ok(msg)
+    }
+
+    fn shutdown(&mut self) {
+        self.recorder.disconnected(0);
+        self.inner.shutdown();
+    }
+
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+}
+
+/// A transport that plays back what a client recorded receiving, one
+/// message per read, then stays silent. What the client writes is kept in
+/// [`sent`](Self::sent).
+pub struct ReplayTransport {
+    inbound: VecDeque<SignalingMsg>,
+    sent: Arc<Mutex<Vec<SignalingMsg>>>,
+}
+
+impl ReplayTransport {
+    #[must_use]
+    pub fn new(records: &[Captured]) -> Self {
+        Self {
+            inbound: records
+                .iter()
+                .filter(|r| r.kind == CaptureKind::Received)
+                .filter_map(|r| r.msg.clone())
+                .collect(),
+            sent: Arc::default(),
+        }
+    }
+
+    /// The messages the client has written so far.
+    #[must_use]
+    pub fn sent(&self) -> Arc<Mutex<Vec<SignalingMsg>>> {
+        Arc::clone(&self.sent)
+    }
+}
+
+impl SignalingTransport for ReplayTransport {
+    fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
+        self.sent
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(msg.clone());
+        Ok(())
+    }
+
+    fn read_msg(&mut self) -> Result<SignalingMsg, FrameError> {
+        self.inbound
+            .pop_front()
+            .ok_or_else(|| FrameError::Io(io::ErrorKind::WouldBlock.into()))
+    }
+
+    fn shutdown(&mut self) {}
+
+    fn describe(&self) -> String {
+        "replay://".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    /// A writer the test can read back.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn login(username: &str) -> SignalingMsg {
+        SignalingMsg::Login {
+            username: username.into(),
+            password: "secret".into(),
+        }
+    }
+
+    #[test]
+    fn recordings_read_back_and_tolerate_a_cut_tail() {
+        let buf = Shared::default();
+        let recorder = SignalingRecorder::to_writer(buf.clone()).unwrap();
+        recorder.received(3, &login("alice"));
+        recorder.sent(3, &SignalingMsg::ListPeers);
+        recorder.disconnected(3);
+
+        let bytes = buf.0.lock().unwrap().clone();
+        let records = read_recording(&bytes[..]).unwrap();
+        let kinds: Vec<_> = records.iter().map(|r| (r.kind, r.client_id)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (CaptureKind::Received, 3),
+                (CaptureKind::Sent, 3),
+                (CaptureKind::Disconnected, 3)
+            ]
+        );
+        assert_eq!(records[0].msg, Some(login("alice")));
+        assert_eq!(records[2].msg, None);
+
+        // Cut inside the second message
+        let cut = read_recording(&bytes[..bytes.len() - 20]).unwrap();
+        assert_eq!(cut.len(), 1);
+        assert!(read_recording(&b"RRTCSIG0"[..]).is_err());
+    }
+
+    #[test]
+    fn server_recordings_replay_through_a_fresh_engine() {
+        let buf = Shared::default();
+        let recorder = SignalingRecorder::to_writer(buf.clone()).unwrap();
+        let mut live = ServerEngine::new();
+        for (client, msg) in [(1, login("alice")), (2, login("bob"))] {
+            recorder.received(client, &msg);
+            for out in live.handle(client, msg) {
+                recorder.sent(out.client_id_target, &out.msg);
+            }
+        }
+
+        let records = read_recording(&buf.0.lock().unwrap()[..]).unwrap();
+        let expected: Vec<_> = records
+            .iter()
+            .filter(|r| r.kind == CaptureKind::Sent)
+            .map(|r| (r.client_id, r.msg.clone().unwrap()))
+            .collect();
+        let replayed: Vec<_> = replay_into_server(&mut ServerEngine::new(), &records)
+            .into_iter()
+            .map(|out| (out.client_id_target, out.msg))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(replayed, expected);
+    }
+
+    #[test]
+    fn client_recordings_replay_to_a_client() {
+        use crate::{
+            log::NoopLogSink,
+            signaling_client::{SignalingClient, SignalingEvent},
+        };
+        use std::thread;
+
+        let buf = Shared::default();
+        let recorder = SignalingRecorder::to_writer(buf.clone()).unwrap();
+        recorder.sent(0, &login("alice"));
+        let ok = SignalingMsg::LoginOk {
+            username: "alice".into(),
+        };
+        recorder.received(0, &ok);
+        let records = read_recording(&buf.0.lock().unwrap()[..]).unwrap();
+
+        let replay = ReplayTransport::new(&records);
+        let sent = replay.sent();
+        let client = SignalingClient::with_transport(Box::new(replay), Arc::new(NoopLogSink));
+        let deadline = Instant::now() + Duration::from_secs(2);
+        let mut got = None;
+        while got.is_none() && Instant::now() < deadline {
+            match client.try_recv() {
+                Some(SignalingEvent::ServerMsg(msg)) => got = Some(msg),
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        assert_eq!(got, Some(ok));
+        assert!(matches!(
+            sent.lock().unwrap().first(),
+            Some(SignalingMsg::Hello { .. })
+        ));
+        client.disconnect();
+    }
+}
@@ -0,0 +1,360 @@
+//! Runtime generation of the signaling server's TLS certificate.
+//!
+//! With `[TLS] generate_signaling_cert = true` the server no longer needs
+//! mkcert: at startup it loads (or creates) a private CA, and issues the
+//! server certificate from it whenever the current one is missing, about to
+//! expire, signed by another CA or missing one of the configured SANs. The
+//! CA certificate is written next to it so clients can trust it through
+//! their own `[TLS] signaling_ca_cert`.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+};
+
+use openssl::{
+    asn1::{Asn1Integer, Asn1Time},
+    bn::{BigNum, MsbOption},
+    ec::{EcGroup, EcKey},
+    error::ErrorStack,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{PKey, Private},
+    x509::{
+        X509, X509Name, X509NameBuilder,
+        extension::{
+            AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage,
+            SubjectAlternativeName, SubjectKeyIdentifier,
+        },
+    },
+};
+
+use crate::{config::Config, tls_utils::SIGNALING_DOMAIN};
+
+/// Validity of a generated CA.
+const CA_DAYS: u32 = 3650;
+/// Validity of a generated server certificate (the most browsers accept).
+const CERT_DAYS: u32 = 825;
+/// A server certificate expiring within this many days is reissued.
+const RENEW_BEFORE_DAYS: u32 = 30;
+
+const DEFAULT_CA_CERT_PATH: &str = "certs/signaling/ca.pem";
+const DEFAULT_CA_KEY_PATH: &str = "certs/signaling/ca-key.pem";
+
+/// What [`ensure_signaling_cert`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertOutcome {
+    /// Generation is off; the configured files are used as they are.
+    Disabled,
+    /// The existing certificate is still valid for the configured SANs.
+    Kept,
+    /// A new server certificate was written, by a new CA if `ca_created`.
+    Issued { ca_created: bool, sans: Vec<String> },
+}
+
+/// Creates or renews the signaling certificate and key at `[TLS]
+/// signaling_cert` / `signaling_key` when `[TLS] generate_signaling_cert` is
+/// on. The CA lives at `[TLS] signaling_ca_cert` / `signaling_ca_key`.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if a file cannot be read or written, or if
+/// OpenSSL fails to generate a key or certificate.
+pub fn ensure_signaling_cert(config: &Config) -> io::Result<CertOutcome> {
+    let enabled = config
+        .get("TLS", "generate_signaling_cert")
+        .is_some_and(|s| s.trim().eq_ignore_ascii_case("true"));
+    if !enabled {
+        return Ok(CertOutcome::Disabled);
+    }
+    let cert_path =
+        config.get_non_empty_or_default("TLS", "signaling_cert", "certs/signaling/cert.pem");
+    let key_path =
+        config.get_non_empty_or_default("TLS", "signaling_key", "certs/signaling/key.pem");
+    let ca_cert_path =
+        config.get_non_empty_or_default("TLS", "signaling_ca_cert", DEFAULT_CA_CERT_PATH);
+    let ca_key_path =
+        config.get_non_empty_or_default("TLS", "signaling_ca_key", DEFAULT_CA_KEY_PATH);
+    let sans = configured_sans(config);
+
+    let (ca, ca_key, ca_created) =
+        load_or_create_ca(Path::new(ca_cert_path), Path::new(ca_key_path))?;
+    if !ca_created && is_current(Path::new(cert_path), Path::new(key_path), &ca, &sans) {
+        return Ok(CertOutcome::Kept);
+    }
+
+    let key = new_key().map_err(io::Error::other)?;
+    let cert = issue_cert(&ca, &ca_key, &key, &sans).map_err(io::Error::other)?;
+    let mut chain = cert.to_pem().map_err(io::Error::other)?;
+    chain.extend(ca.to_pem().map_err(io::Error::other)?);
+    write_file(
+        Path::new(key_path),
+        &key.private_key_to_pem_pkcs8().map_err(io::Error::other)?,
+        true,
+    )?;
+    write_file(Path::new(cert_path), &chain, false)?;
+    Ok(CertOutcome::Issued {
+        ca_created,
+        sans: sans.into_iter().collect(),
+    })
+}
+
+/// `[TLS] signaling_sans` (comma-separated DNS names and IP addresses), or
+/// by default `[Signaling] tls_domain` plus the IP of `listen_address`.
+#[must_use]
+pub fn configured_sans(config: &Config) -> BTreeSet<String> {
+    let listed: BTreeSet<String> = config
+        .get("TLS", "signaling_sans")
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| normalize_san(s.trim()))
+        .filter(|s| !s.is_empty())
+        .collect();
+    if !listed.is_empty() {
+        return listed;
+    }
+    let mut sans = BTreeSet::from([config
+        .get_non_empty_or_default("Signaling", "tls_domain", SIGNALING_DOMAIN)
+        .to_owned()]);
+    if let Some(addr) = config
+        .get_non_empty("Signaling", "listen_address")
+        .and_then(|a| a.parse::<SocketAddr>().ok())
+        .filter(|a| !a.ip().is_unspecified())
+    {
+        sans.insert(addr.ip().to_string());
+    }
+    sans
+}
+
+/// IP addresses in their canonical form, so `::1` and `0:0::1` compare equal.
+fn normalize_san(san: &str) -> String {
+    san.parse::<IpAddr>()
+        .map_or_else(|_| san.to_owned(), |ip| ip.to_string())
+}
+
+fn load_or_create_ca(cert_path: &Path, key_path: &Path) -> io::Result<(X509, PKey<Private>, bool)> {
+    if cert_path.exists() && key_path.exists() {
+        let cert = X509::from_pem(&fs::read(cert_path)?).map_err(io::Error::other)?;
+        let key = PKey::private_key_from_pem(&fs::read(key_path)?).map_err(io::Error::other)?;
+        return Ok((cert, key, false));
+    }
+    let key = new_key().map_err(io::Error::other)?;
+    let cert = create_ca(&key).map_err(io::Error::other)?;
+    write_file(
+        key_path,
+        &key.private_key_to_pem_pkcs8().map_err(io::Error::other)?,
+        true,
+    )?;
+    write_file(cert_path, &cert.to_pem().map_err(io::Error::other)?, false)?;
+    Ok((cert, key, true))
+}
+
+/// The certificate at `cert_path` matches the key, was issued by `ca`, is
+/// not about to expire and covers exactly `sans`.
+fn is_current(cert_path: &Path, key_path: &Path, ca: &X509, sans: &BTreeSet<String>) -> bool {
+    let (Ok(cert_pem), Ok(key_pem)) = (fs::read(cert_path), fs::read(key_path)) else {
+        return false;
+    };
+    let (Ok(cert), Ok(key)) = (
+        X509::from_pem(&cert_pem),
+        PKey::private_key_from_pem(&key_pem),
+    ) else {
+        return false;
+    };
+    let matches_key = cert.public_key().is_ok_and(|public| public.public_eq(&key));
+    let signed_by_ca = ca
+        .public_key()
+        .and_then(|ca_key| cert.verify(&ca_key))
+        .unwrap_or(false);
+    let fresh =
+        Asn1Time::days_from_now(RENEW_BEFORE_DAYS).is_ok_and(|limit| cert.not_after() > limit);
+    matches_key && signed_by_ca && fresh && cert_sans(&cert) == *sans
+}
+
+fn cert_sans(cert: &X509) -> BTreeSet<String> {
+    cert.subject_alt_names()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| {
+            if let Some(dns) = name.dnsname() {
+                return Some(dns.to_owned());
+            }
+            let ip = match name.ipaddress()? {
+                &[a, b, c, d] => IpAddr::V4(Ipv4Addr::new(a, b, c, d)),
+                bytes => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+            };
+            Some(ip.to_string())
+        })
+        .collect()
+}
+
+fn new_key() -> Result<PKey<Private>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    PKey::from_ec_key(EcKey::generate(&group)?)
+}
+
+fn serial_number() -> Result<Asn1Integer, ErrorStack> {
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    serial.to_asn1_integer()
+}
+
+fn common_name(cn: &str) -> Result<X509Name, ErrorStack> {
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "RoomRTC")?;
+    name.append_entry_by_nid(Nid::COMMONNAME, cn)?;
+    Ok(name.build())
+}
+
+fn create_ca(key: &PKey<Private>) -> Result<X509, ErrorStack> {
+    let name = common_name("RoomRTC signaling CA")?;
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial_number()?)?;
+    builder.set_subject_name(&name)?;
+    builder.set_issuer_name(&name)?;
+    builder.set_pubkey(key)?;
+    builder.set_not_before(&Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&Asn1Time::days_from_now(CA_DAYS)?)?;
+    builder.append_extension(BasicConstraints::new().critical().ca().pathlen(0).build()?)?;
+    builder.append_extension(
+        KeyUsage::new()
+            .critical()
+            .key_cert_sign()
+            .crl_sign()
+            .build()?,
+    )?;
+    let key_id = SubjectKeyIdentifier::new().build(&builder.x509v3_context(None, None))?;
+    builder.append_extension(key_id)?;
+    builder.sign(key, MessageDigest::sha256())?;
+    Ok(builder.build())
+}
+
+fn issue_cert(
+    ca: &X509,
+    ca_key: &PKey<Private>,
+    key: &PKey<Private>,
+    sans: &BTreeSet<String>,
+) -> Result<X509, ErrorStack> {
+    let cn = sans.first().map_or(SIGNALING_DOMAIN, String::as_str);
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(&serial_number()?)?;
+    builder.set_subject_name(&common_name(cn)?)?;
+    builder.set_issuer_name(ca.subject_name())?;
+    builder.set_pubkey(key)?;
+    builder.set_not_before(&Asn1Time::days_from_now(0)?)?;
+    builder.set_not_after(&Asn1Time::days_from_now(CERT_DAYS)?)?;
+    builder.append_extension(BasicConstraints::new().critical().build()?)?;
+    builder.append_extension(
+        KeyUsage::new()
+            .critical()
+            .digital_signature()
+            .key_encipherment()
+            .build()?,
+    )?;
+    builder.append_extension(ExtendedKeyUsage::new().server_auth().build()?)?;
+    let mut alt_names = SubjectAlternativeName::new();
+    for san in sans {
+        if san.parse::<IpAddr>().is_ok() {
+            alt_names.ip(san);
+        } else {
+            alt_names.dns(san);
+        }
+    }
+    let alt_names = alt_names.build(&builder.x509v3_context(Some(ca), None))?;
+    builder.append_extension(alt_names)?;
+    let key_id = SubjectKeyIdentifier::new().build(&builder.x509v3_context(Some(ca), None))?;
+    builder.append_extension(key_id)?;
+    let authority_id = AuthorityKeyIdentifier::new()
+        .keyid(false)
+        .build(&builder.x509v3_context(Some(ca), None))?;
+    builder.append_extension(authority_id)?;
+    builder.sign(ca_key, MessageDigest::sha256())?;
+    Ok(builder.build())
+}
+
+/// Writes `contents` to `path`, creating its directory. Private keys are
+/// only readable by their owner.
+fn write_file(path: &Path, contents: &[u8], private: bool) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if private {
+            options.mode(0o600);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    let mut file = options
+        .open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("writing {}: {e}", path.display())))?;
+    file.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::tls_utils::{load_certs, load_private_key};
+
+    fn config(dir: &Path, sans: &str) -> Config {
+        let mut config = Config::empty();
+        let path = |name: &str| dir.join(name).display().to_string();
+        config.set("TLS", "generate_signaling_cert", "true");
+        config.set("TLS", "signaling_cert", path("cert.pem"));
+        config.set("TLS", "signaling_key", path("key.pem"));
+        config.set("TLS", "signaling_ca_cert", path("ca.pem"));
+        config.set("TLS", "signaling_ca_key", path("ca-key.pem"));
+        config.set("TLS", "signaling_sans", sans);
+        config
+    }
+
+    #[test]
+    fn issues_once_and_reissues_when_sans_change() {
+        let dir = std::env::temp_dir().join(format!("rustyrtc-certgen-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let first = config(&dir, "signal.internal, 127.0.0.1");
+        assert_eq!(
+            ensure_signaling_cert(&first).unwrap(),
+            CertOutcome::Issued {
+                ca_created: true,
+                sans: vec!["127.0.0.1".into(), "signal.internal".into()],
+            }
+        );
+        assert_eq!(ensure_signaling_cert(&first).unwrap(), CertOutcome::Kept);
+
+        let ca = X509::from_pem(&fs::read(dir.join("ca.pem")).unwrap()).unwrap();
+        let second = config(&dir, "signal.internal,::1");
+        assert!(matches!(
+            ensure_signaling_cert(&second).unwrap(),
+            CertOutcome::Issued {
+                ca_created: false,
+                ..
+            }
+        ));
+        let certs = load_certs(dir.join("cert.pem").to_str().unwrap()).unwrap();
+        assert_eq!(certs.len(), 2);
+        let leaf = X509::from_der(&certs[0]).unwrap();
+        assert!(leaf.verify(&ca.public_key().unwrap()).unwrap());
+        assert_eq!(
+            cert_sans(&leaf),
+            BTreeSet::from(["::1".to_owned(), "signal.internal".to_owned()])
+        );
+        assert!(load_private_key(dir.join("key.pem").to_str().unwrap()).is_ok());
+
+        let mut off = second;
+        off.set("TLS", "generate_signaling_cert", "false");
+        assert_eq!(ensure_signaling_cert(&off).unwrap(), CertOutcome::Disabled);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,234 @@
+//! Per-user contact and block lists.
+//!
+//! Contacts feed the `contacts` forwarding policy; a user someone blocked
+//! cannot call them and neither sees the other online. Stored one user per
+//! line, `owner:contact,contact:blocked,blocked`, in the file
+//! `[Signaling] contacts_path` names; without one they only last as long as
+//! the server.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config, log::log_sink::LogSink, signaling::protocol::UserName, sink_info, sink_warn,
+};
+
+/// A change a user makes to their lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContactChange {
+    /// Does nothing for a blocked user; unblock them first.
+    Add,
+    Remove,
+    /// Blocking also drops the user from the contacts.
+    Block,
+    Unblock,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Lists {
+    contacts: BTreeSet<UserName>,
+    blocked: BTreeSet<UserName>,
+}
+
+#[derive(Debug, Default)]
+pub struct ContactBook {
+    path: Option<PathBuf>,
+    users: HashMap<UserName, Lists>,
+}
+
+impl ContactBook {
+    /// A book kept in memory only.
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Loads the book at `path`, if it exists; changes are written back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the file exists but cannot be read.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut users = HashMap::new();
+        if path.exists() {
+            for line in fs::read_to_string(&path)?.lines() {
+                let mut fields = line.trim().split(':');
+                let (Some(owner), Some(contacts)) = (fields.next(), fields.next()) else {
+                    continue;
+                };
+                let names = |list: &str| -> BTreeSet<UserName> {
+                    list.split(',')
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_owned)
+                        .collect()
+                };
+                let lists = Lists {
+                    contacts: names(contacts),
+                    blocked: fields.next().map(names).unwrap_or_default(),
+                };
+                users.insert(owner.to_owned(), lists);
+            }
+        }
+        Ok(Self {
+            path: Some(path),
+            users,
+        })
+    }
+
+    /// The book `[Signaling] contacts_path` names, or an in-memory one. A
+    /// file that cannot be read is logged and ignored.
+    #[must_use]
+    pub fn from_config(config: &Config, log: &dyn LogSink) -> Self {
+        let Some(path) = config.get_non_empty("Signaling", "contacts_path") else {
+            return Self::in_memory();
+        };
+        match Self::open(path) {
+            Ok(book) => {
+                sink_info!(log, "[signaling] contact lists in {}", path);
+                book
+            }
+            Err(e) => {
+                sink_warn!(log, "[signaling] cannot read contacts {}: {}", path, e);
+                Self::in_memory()
+            }
+        }
+    }
+
+    /// Applies `change` about `user` to `owner`'s lists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the book cannot be saved.
+    pub fn apply(&mut self, owner: &str, change: ContactChange, user: &str) -> io::Result<()> {
+        let lists = self.users.entry(owner.to_owned()).or_default();
+        let changed = match change {
+            ContactChange::Add => {
+                !lists.blocked.contains(user) && lists.contacts.insert(user.to_owned())
+            }
+            ContactChange::Remove => lists.contacts.remove(user),
+            ContactChange::Block => {
+                lists.contacts.remove(user);
+                lists.blocked.insert(user.to_owned())
+            }
+            ContactChange::Unblock => lists.blocked.remove(user),
+        };
+        if changed { self.save() } else { Ok(()) }
+    }
+
+    /// `owner`'s contacts, sorted.
+    #[must_use]
+    pub fn list(&self, owner: &str) -> Vec<UserName> {
+        self.users
+            .get(owner)
+            .map(|lists| lists.contacts.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The users `owner` blocked, sorted.
+    #[must_use]
+    pub fn blocked(&self, owner: &str) -> Vec<UserName> {
+        self.users
+            .get(owner)
+            .map(|lists| lists.blocked.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether `owner` blocked `user`.
+    #[must_use]
+    pub fn is_blocked(&self, owner: &str, user: &str) -> bool {
+        self.users
+            .get(owner)
+            .is_some_and(|lists| lists.blocked.contains(user))
+    }
+
+    /// Whether `a` and `b` each have the other as a contact.
+    #[must_use]
+    pub fn are_mutual(&self, a: &str, b: &str) -> bool {
+        let has = |owner: &str, contact: &str| {
+            self.users
+                .get(owner)
+                .is_some_and(|lists| lists.contacts.contains(contact))
+        };
+        has(a, b) && has(b, a)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut owners: Vec<_> = self.users.iter().collect();
+        owners.sort_by(|a, b| a.0.cmp(b.0));
+        let join = |list: &BTreeSet<UserName>| -> String {
+            list.iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut out = String::new();
+        for (owner, lists) in owners {
+            if !lists.contacts.is_empty() || !lists.blocked.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "{owner}:{}:{}",
+                    join(&lists.contacts),
+                    join(&lists.blocked)
+                );
+            }
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, out)?;
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn contacts_are_mutual_and_survive_a_reload() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-contacts-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut book = ContactBook::open(&path).unwrap();
+        book.apply("alice", ContactChange::Add, "bob").unwrap();
+        assert!(!book.are_mutual("alice", "bob"));
+        book.apply("bob", ContactChange::Add, "alice").unwrap();
+        book.apply("bob", ContactChange::Add, "carol").unwrap();
+        assert!(book.are_mutual("bob", "alice"));
+
+        let mut book = ContactBook::open(&path).unwrap();
+        assert_eq!(book.list("bob"), ["alice", "carol"]);
+        book.apply("alice", ContactChange::Remove, "bob").unwrap();
+        assert!(!book.are_mutual("alice", "bob"));
+        assert!(ContactBook::open(&path).unwrap().list("alice").is_empty());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn blocking_drops_the_contact_and_is_kept() {
+        let path = std::env::temp_dir().join(format!("rustyrtc-blocked-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut book = ContactBook::open(&path).unwrap();
+        book.apply("alice", ContactChange::Add, "mallory").unwrap();
+        book.apply("alice", ContactChange::Block, "mallory")
+            .unwrap();
+        book.apply("alice", ContactChange::Add, "mallory").unwrap();
+        assert!(book.list("alice").is_empty());
+
+        let mut book = ContactBook::open(&path).unwrap();
+        assert!(book.is_blocked("alice", "mallory"));
+        assert!(!book.is_blocked("mallory", "alice"));
+        book.apply("alice", ContactChange::Unblock, "mallory")
+            .unwrap();
+        assert!(book.blocked("alice").is_empty());
+        let _ = fs::remove_file(&path);
+    }
+}
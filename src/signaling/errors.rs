@@ -13,6 +13,28 @@ impl LoginErrorCode {
     pub fn as_u16(self) -> u16 {
         self as u16
     }
+
+    #[must_use]
+    pub const fn from_u16(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Self::AlreadyLoggedIn),
+            2 => Some(Self::NotAuthorized),
+            3 => Some(Self::InvalidCredentials),
+            4 => Some(Self::Internal),
+            _ => None,
+        }
+    }
+
+    /// What the code means, in English; clients translate it.
+    #[must_use]
+    pub const fn reason(self) -> &'static str {
+        match self {
+            Self::AlreadyLoggedIn => "user is already logged in",
+            Self::NotAuthorized => "not authorized to log in",
+            Self::InvalidCredentials => "invalid username or password",
+            Self::Internal => "internal server error",
+        }
+    }
 }
 
 #[repr(u16)]
@@ -27,6 +49,60 @@ impl JoinErrorCode {
     pub fn as_u16(self) -> u16 {
         self as u16
     }
+
+    #[must_use]
+    pub const fn from_u16(code: u16) -> Option<Self> {
+        match code {
+            10 => Some(Self::NotLoggedIn),
+            20 => Some(Self::NotFound),
+            21 => Some(Self::Full),
+            _ => None,
+        }
+    }
+
+    /// What the code means, in English; clients translate it.
+    #[must_use]
+    pub const fn reason(self) -> &'static str {
+        match self {
+            Self::NotLoggedIn => "not logged in",
+            Self::NotFound => "room not found",
+            Self::Full => "room is full",
+        }
+    }
+}
+
+/// Why the server did not deliver an offer (`OfferErr`).
+#[repr(u16)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallErrorCode {
+    /// The callee blocked the caller.
+    Blocked = 30,
+    /// The forwarding policy keeps the two apart.
+    NotAllowed = 31,
+}
+
+impl CallErrorCode {
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    #[must_use]
+    pub const fn from_u16(code: u16) -> Option<Self> {
+        match code {
+            30 => Some(Self::Blocked),
+            31 => Some(Self::NotAllowed),
+            _ => None,
+        }
+    }
+
+    /// What the code means, in English; clients translate it.
+    #[must_use]
+    pub const fn reason(self) -> &'static str {
+        match self {
+            Self::Blocked => "the user does not accept your calls",
+            Self::NotAllowed => "the server does not let you call this user",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -42,6 +118,30 @@ impl RegisterErrorCode {
     pub fn as_u16(self) -> u16 {
         self as u16
     }
+
+    #[must_use]
+    pub const fn from_u16(code: u16) -> Option<Self> {
+        match code {
+            1 => Some(Self::UsernameTaken),
+            2 => Some(Self::InvalidUsername),
+            3 => Some(Self::WeakPassword),
+            4 => Some(Self::Internal),
+            5 => Some(Self::Unsupported),
+            _ => None,
+        }
+    }
+
+    /// What the code means, in English; clients translate it.
+    #[must_use]
+    pub const fn reason(self) -> &'static str {
+        match self {
+            Self::UsernameTaken => "username already taken",
+            Self::InvalidUsername => "invalid username",
+            Self::WeakPassword => "password is too weak",
+            Self::Internal => "internal server error",
+            Self::Unsupported => "registration is not supported",
+        }
+    }
 }
 
 impl From<RegisterError> for RegisterErrorCode {
@@ -0,0 +1,64 @@
+use std::{fmt, str::FromStr};
+
+use crate::config::Config;
+
+/// Who may send call signaling (offers, answers, candidates, hang-ups) to
+/// whom. Set with `[Signaling] forward_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForwardPolicy {
+    /// Any logged-in user may call any other.
+    #[default]
+    Open,
+    /// Only users that share a session (room) reach each other.
+    SameSession,
+    /// Only users that have added each other as contacts reach each other.
+    Contacts,
+}
+
+impl ForwardPolicy {
+    /// The policy `[Signaling] forward_policy` names; `Open` when unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnknownForwardPolicy`] if the value is not a known policy.
+    pub fn from_config(config: &Config) -> Result<Self, UnknownForwardPolicy> {
+        config
+            .get_non_empty("Signaling", "forward_policy")
+            .map_or(Ok(Self::Open), str::parse)
+    }
+}
+
+/// A `forward_policy` value that names no policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownForwardPolicy(pub String);
+
+impl fmt::Display for UnknownForwardPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown forward policy '{}'", self.0)
+    }
+}
+
+impl std::error::Error for UnknownForwardPolicy {}
+
+impl FromStr for ForwardPolicy {
+    type Err = UnknownForwardPolicy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "open" => Ok(Self::Open),
+            "same-session" | "same_session" => Ok(Self::SameSession),
+            "contacts" => Ok(Self::Contacts),
+            other => Err(UnknownForwardPolicy(other.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for ForwardPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::SameSession => write!(f, "same-session"),
+            Self::Contacts => write!(f, "contacts"),
+        }
+    }
+}
@@ -1,7 +1,13 @@
+pub mod audit;
 pub mod auth;
+pub mod capture;
+pub mod cert_gen;
+pub mod contacts;
 pub mod errors;
+pub mod forward_policy;
 pub mod presence;
 pub mod protocol;
+pub mod replay;
 pub mod router;
 pub mod run;
 pub mod runtime;
@@ -13,5 +19,8 @@ pub mod tls;
 pub mod transport;
 pub mod types;
 
-pub use auth::{AllowAllAuthBackend, AuthBackend, AuthError, FileUserStore, InMemoryAuthBackend};
+pub use auth::{
+    AllowAllAuthBackend, AuthBackend, AuthError, CommandAuthBackend, FileUserStore,
+    InMemoryAuthBackend,
+};
 pub use signaling_server::SignalingServer;
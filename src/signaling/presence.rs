@@ -1,14 +1,18 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use crate::signaling::protocol::UserName;
 use crate::signaling::types::ClientId;
 
-/// Tracks which clients are logged in as which users.
+/// Tracks which clients are logged in as which users. A user may be logged
+/// in on several clients (devices) at once.
 #[derive(Debug, Default)]
 pub struct Presence {
-    user_to_client: HashMap<UserName, ClientId>,
+    user_to_clients: HashMap<UserName, BTreeSet<ClientId>>,
     client_to_user: HashMap<ClientId, UserName>,
+    device_labels: HashMap<ClientId, String>,
     busy_users: HashSet<UserName>,
+    /// The device each user is calling on, once it offered or answered.
+    call_devices: HashMap<UserName, ClientId>,
 }
 
 impl Presence {
@@ -18,29 +22,61 @@ impl Presence {
 
     /// Log in a user on a given client.
     ///
-    /// Returns:
-    /// - Some(previous_client) if this user was already logged in somewhere else.
-    /// - None if user was not previously logged in.
-    pub fn login(&mut self, client_id: ClientId, username: UserName) -> Option<ClientId> {
-        let old_client = self.user_to_client.insert(username.clone(), client_id);
+    /// Returns how many clients the user is now logged in on.
+    pub fn login(&mut self, client_id: ClientId, username: UserName) -> usize {
+        let clients = self.user_to_clients.entry(username.clone()).or_default();
+        clients.insert(client_id);
+        let devices = clients.len();
         self.client_to_user.insert(client_id, username);
-        old_client
+        devices
     }
+
     /// Remove client from presence; returns the username if any.
     pub fn logout(&mut self, client_id: ClientId) -> Option<UserName> {
-        if let Some(username) = self.client_to_user.remove(&client_id) {
-            self.user_to_client.remove(&username);
-            // Auto-clear busy status on disconnect
-            self.busy_users.remove(&username);
-            Some(username)
-        } else {
-            None
+        let username = self.client_to_user.remove(&client_id)?;
+        self.device_labels.remove(&client_id);
+        let last = self
+            .user_to_clients
+            .get_mut(&username)
+            .is_none_or(|clients| {
+                clients.remove(&client_id);
+                clients.is_empty()
+            });
+        if last {
+            self.user_to_clients.remove(&username);
+        }
+        // Auto-clear busy status when the device in the call disconnects
+        if last || self.call_devices.get(&username) == Some(&client_id) {
+            self.set_busy(&username, false);
         }
+        Some(username)
     }
 
-    /// Get the currently logged-in client for a username.
+    /// The client a message for `username` goes to when only one should get
+    /// it: the device in the call, else the first one that logged in.
     pub fn client_id_for(&self, username: &UserName) -> Option<ClientId> {
-        self.user_to_client.get(username).cloned()
+        self.call_devices.get(username).copied().or_else(|| {
+            self.user_to_clients
+                .get(username)
+                .and_then(|clients| clients.first().copied())
+        })
+    }
+
+    /// Every client `username` is logged in on.
+    pub fn client_ids_for(&self, username: &str) -> Vec<ClientId> {
+        self.user_to_clients
+            .get(username)
+            .map(|clients| clients.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Where call signaling for `username` goes: the device in the call, or
+    /// every device while none is.
+    pub fn delivery_targets(&self, username: &str) -> Vec<ClientId> {
+        match self.call_devices.get(username) {
+            Some(&client) => vec![client],
+            None => self.client_ids_for(username),
+        }
     }
 
     /// Get username for a client, if logged in.
@@ -50,18 +86,46 @@ impl Presence {
 
     /// Return all usernames currently online.
     pub fn online_usernames(&self) -> Vec<UserName> {
-        self.user_to_client.keys().cloned().collect()
+        self.user_to_clients.keys().cloned().collect()
     }
     /// Return all client IDs currently logged in.
     /// This is used to iterate over all clients to broadcast updates.
     pub fn all_client_ids(&self) -> Vec<ClientId> {
         self.client_to_user.keys().copied().collect()
     }
+
+    /// Names `client`'s device ("laptop", "phone") for the user's other
+    /// devices.
+    pub fn set_device_label(&mut self, client: ClientId, label: String) {
+        self.device_labels.insert(client, label);
+    }
+
+    /// `client`'s device label, or a generic one.
+    pub fn device_label(&self, client: ClientId) -> String {
+        self.device_labels
+            .get(&client)
+            .cloned()
+            .unwrap_or_else(|| format!("device {client}"))
+    }
+
+    /// The device `username` is in a call on, if any.
+    pub fn call_device(&self, username: &str) -> Option<ClientId> {
+        self.call_devices.get(username).copied()
+    }
+
+    /// Pins `username`'s call signaling to `client` until the call ends.
+    pub fn set_call_device(&mut self, username: &str, client: ClientId) {
+        self.call_devices.insert(username.to_string(), client);
+    }
+
+    /// Marking a user available also ends their call, so signaling reaches
+    /// all their devices again.
     pub fn set_busy(&mut self, username: &str, busy: bool) {
         if busy {
             self.busy_users.insert(username.to_string());
         } else {
             self.busy_users.remove(username);
+            self.call_devices.remove(username);
         }
     }
 
@@ -69,3 +133,29 @@ impl Presence {
         self.busy_users.contains(username)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn calls_pin_one_device_until_they_end() {
+        let mut presence = Presence::new();
+        assert_eq!(presence.login(1, "alice".into()), 1);
+        assert_eq!(presence.login(2, "alice".into()), 2);
+        assert_eq!(presence.delivery_targets("alice"), [1, 2]);
+
+        presence.set_call_device("alice", 2);
+        presence.set_busy("alice", true);
+        assert_eq!(presence.delivery_targets("alice"), [2]);
+        assert_eq!(presence.client_id_for(&"alice".into()), Some(2));
+
+        // The other device leaving does not end the call
+        presence.logout(1);
+        assert!(presence.is_busy("alice"));
+        presence.logout(2);
+        assert!(!presence.is_busy("alice"));
+        assert!(presence.online_usernames().is_empty());
+    }
+}
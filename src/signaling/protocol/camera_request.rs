@@ -0,0 +1,43 @@
+/// What a peer asks of the other's camera during a call (remote
+/// assistance), sent as `CameraControl`. The other side asks its user
+/// before doing it and answers with `CameraControlReply`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraRequest {
+    /// Switch to the next camera.
+    NextCamera,
+    Resolution {
+        width: u16,
+        height: u16,
+    },
+    /// Zoom level in the camera's own units; 0 is fully out.
+    Zoom {
+        level: u16,
+    },
+    Torch {
+        on: bool,
+    },
+}
+
+impl CameraRequest {
+    pub(super) const fn tag(self) -> u8 {
+        match self {
+            Self::NextCamera => 0,
+            Self::Resolution { .. } => 1,
+            Self::Zoom { .. } => 2,
+            Self::Torch { .. } => 3,
+        }
+    }
+
+    /// What is asked, for the permission prompt (English, for the
+    /// translation catalog) and the logs.
+    #[must_use]
+    pub const fn describe(self) -> &'static str {
+        match self {
+            Self::NextCamera => "switch camera",
+            Self::Resolution { .. } => "change resolution",
+            Self::Zoom { .. } => "zoom",
+            Self::Torch { on: true } => "turn the light on",
+            Self::Torch { on: false } => "turn the light off",
+        }
+    }
+}
@@ -0,0 +1,7 @@
+/// One ICE candidate inside a batched `Candidates` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateItem {
+    pub mid: String,
+    pub mline_index: u16,
+    pub cand: Vec<u8>, // raw UTF-8 text
+}
@@ -0,0 +1,63 @@
+/// What a client can send and receive, exchanged before a call with
+/// `CapabilityQuery`/`CapabilityReply` so the caller knows what to offer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Video codec names, as in SDP (`"H264"`).
+    pub video_codecs: Vec<String>,
+    /// Audio codec names, as in SDP (`"PCMU"`, `"L16"`).
+    pub audio_codecs: Vec<String>,
+    /// Largest video the client captures; 0 when it does not say.
+    pub max_width: u16,
+    pub max_height: u16,
+    /// Whether the client opens a data channel (file transfer).
+    pub data_channel: bool,
+}
+
+impl Capabilities {
+    #[must_use]
+    pub fn supports_video(&self) -> bool {
+        !self.video_codecs.is_empty()
+    }
+
+    #[must_use]
+    pub fn supports_audio(&self) -> bool {
+        !self.audio_codecs.is_empty()
+    }
+
+    /// The codecs both `self` and `other` support, video first.
+    #[must_use]
+    pub fn common_codecs(&self, other: &Self) -> Vec<String> {
+        let shared = |ours: &[String], theirs: &[String]| -> Vec<String> {
+            ours.iter()
+                .filter(|c| theirs.iter().any(|t| t.eq_ignore_ascii_case(c)))
+                .cloned()
+                .collect()
+        };
+        let mut out = shared(&self.video_codecs, &other.video_codecs);
+        out.extend(shared(&self.audio_codecs, &other.audio_codecs));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn common_codecs_and_media_support() {
+        let ours = Capabilities {
+            video_codecs: vec!["H264".into()],
+            audio_codecs: vec!["PCMU".into(), "L16".into()],
+            ..Capabilities::default()
+        };
+        let audio_only = Capabilities {
+            audio_codecs: vec!["pcmu".into()],
+            ..Capabilities::default()
+        };
+        assert!(!audio_only.supports_video());
+        assert!(audio_only.supports_audio());
+        assert_eq!(ours.common_codecs(&audio_only), ["PCMU"]);
+        assert_eq!(ours.common_codecs(&ours), ["H264", "PCMU", "L16"]);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::signaling::protocol::peer_status::PeerStatus;
+use crate::signaling::protocol::{
+    camera_request::CameraRequest, candidate_item::CandidateItem, capabilities::Capabilities,
+    peer_status::PeerStatus,
+};
 
 use super::{MsgType, ProtoError, SignalingMsg};
 use std::str;
@@ -23,9 +26,14 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             put_str16(&mut body, username)?;
             MsgType::LoginOk
         }
-        LoginErr { code } => {
+        LoginErr { code, reason } => {
             put_u16(&mut body, *code);
-            MsgType::LoginErr
+            put_reason(
+                &mut body,
+                reason.as_deref(),
+                MsgType::LoginErr,
+                MsgType::LoginErrV2,
+            )?
         }
         Register { username, password } => {
             put_str16(&mut body, username)?;
@@ -36,9 +44,18 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             put_str16(&mut body, username)?;
             MsgType::RegisterOk
         }
-        RegisterErr { code } => {
+        RegisterErr { code, reason } => {
             put_u16(&mut body, *code);
-            MsgType::RegisterErr
+            put_reason(
+                &mut body,
+                reason.as_deref(),
+                MsgType::RegisterErr,
+                MsgType::RegisterErrV2,
+            )?
+        }
+        SetDevice { label } => {
+            put_str16(&mut body, label)?;
+            MsgType::SetDevice
         }
         ListPeers => MsgType::ListPeers,
         SignalingMsg::PeersOnline { peers } => {
@@ -79,9 +96,18 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             put_str16(&mut body, session_id)?;
             MsgType::JoinOk
         }
-        JoinErr { code } => {
+        JoinErr { code, reason } => {
             put_u16(&mut body, *code);
-            MsgType::JoinErr
+            put_reason(
+                &mut body,
+                reason.as_deref(),
+                MsgType::JoinErr,
+                MsgType::JoinErrV2,
+            )?
+        }
+        Leave { session_id } => {
+            put_str16(&mut body, session_id)?;
+            MsgType::Leave
         }
         PeerJoined {
             session_id,
@@ -141,6 +167,27 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             body.extend_from_slice(cand);
             MsgType::Candidate
         }
+        Candidates {
+            from,
+            to,
+            items,
+            end_of_candidates,
+        } => {
+            if items.len() > u16::MAX as usize {
+                return Err(ProtoError::InvalidFormat("too many candidates"));
+            }
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            put_u8(&mut body, u8::from(*end_of_candidates));
+            put_u16(&mut body, items.len() as u16);
+            for item in items {
+                put_str16(&mut body, &item.mid)?;
+                put_u16(&mut body, item.mline_index);
+                put_u32(&mut body, item.cand.len() as u32);
+                body.extend_from_slice(&item.cand);
+            }
+            MsgType::Candidates
+        }
         Ack { txn_id, from, to } => {
             put_str16(&mut body, from)?;
             put_str16(&mut body, to)?;
@@ -156,6 +203,89 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             }
             MsgType::Bye
         }
+        Transfer { from, to, target } => {
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            put_str16(&mut body, target)?;
+            MsgType::Transfer
+        }
+        CapabilityQuery { from, to } => {
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            MsgType::CapabilityQuery
+        }
+        CapabilityReply { from, to, caps } => {
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            for codecs in [&caps.video_codecs, &caps.audio_codecs] {
+                if codecs.len() > u8::MAX as usize {
+                    return Err(ProtoError::InvalidFormat("too many codecs"));
+                }
+                put_u8(&mut body, codecs.len() as u8);
+                for codec in codecs {
+                    put_str16(&mut body, codec)?;
+                }
+            }
+            put_u16(&mut body, caps.max_width);
+            put_u16(&mut body, caps.max_height);
+            put_u8(&mut body, u8::from(caps.data_channel));
+            MsgType::CapabilityReply
+        }
+        AddContact { username } => {
+            put_str16(&mut body, username)?;
+            MsgType::AddContact
+        }
+        RemoveContact { username } => {
+            put_str16(&mut body, username)?;
+            MsgType::RemoveContact
+        }
+        Block { username } => {
+            put_str16(&mut body, username)?;
+            MsgType::Block
+        }
+        Unblock { username } => {
+            put_str16(&mut body, username)?;
+            MsgType::Unblock
+        }
+        ListContacts => MsgType::ListContacts,
+        Contacts { contacts, blocked } => {
+            for list in [contacts, blocked] {
+                if list.len() > u16::MAX as usize {
+                    return Err(ProtoError::InvalidFormat("too many contacts"));
+                }
+                put_u16(&mut body, list.len() as u16);
+                for name in list {
+                    put_str16(&mut body, name)?;
+                }
+            }
+            MsgType::Contacts
+        }
+        OfferErr { to, code, reason } => {
+            put_str16(&mut body, to)?;
+            put_u16(&mut body, *code);
+            put_str16(&mut body, reason.as_deref().unwrap_or_default())?;
+            MsgType::OfferErr
+        }
+        CameraControl { from, to, request } => {
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            put_camera_request(&mut body, *request);
+            MsgType::CameraControl
+        }
+        CameraControlReply {
+            from,
+            to,
+            request,
+            accepted,
+            reason,
+        } => {
+            put_str16(&mut body, from)?;
+            put_str16(&mut body, to)?;
+            put_camera_request(&mut body, *request);
+            put_u8(&mut body, u8::from(*accepted));
+            put_str16(&mut body, reason.as_deref().unwrap_or_default())?;
+            MsgType::CameraControlReply
+        }
         Ping { nonce } => {
             put_u64(&mut body, *nonce);
             MsgType::Ping
@@ -164,6 +294,25 @@ pub fn encode_msg(msg: &SignalingMsg) -> Result<(MsgType, Vec<u8>), ProtoError>
             put_u64(&mut body, *nonce);
             MsgType::Pong
         }
+        ResumeFrom { seq } => {
+            put_u64(&mut body, *seq);
+            MsgType::ResumeFrom
+        }
+        Resumed { replayed, complete } => {
+            put_u32(&mut body, *replayed);
+            put_u8(&mut body, u8::from(*complete));
+            MsgType::Resumed
+        }
+        Sequenced { seq, msg } => {
+            if matches!(**msg, Sequenced { .. }) {
+                return Err(ProtoError::InvalidFormat("nested Sequenced"));
+            }
+            let (inner_type, inner_body) = encode_msg(msg)?;
+            put_u64(&mut body, *seq);
+            put_u8(&mut body, inner_type.as_u8());
+            body.extend_from_slice(&inner_body);
+            MsgType::Sequenced
+        }
     };
 
     Ok((msg_type, body))
@@ -194,7 +343,12 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
         }
         MsgType::LoginErr => {
             let code = cursor.get_u16()?;
-            LoginErr { code }
+            LoginErr { code, reason: None }
+        }
+        MsgType::LoginErrV2 => {
+            let code = cursor.get_u16()?;
+            let reason = Some(cursor.get_str16()?.to_owned());
+            LoginErr { code, reason }
         }
         MsgType::Register => {
             let u = cursor.get_str16()?.to_owned();
@@ -210,12 +364,22 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
         }
         MsgType::RegisterErr => {
             let code = cursor.get_u16()?;
-            RegisterErr { code }
+            RegisterErr { code, reason: None }
+        }
+        MsgType::RegisterErrV2 => {
+            let code = cursor.get_u16()?;
+            let reason = Some(cursor.get_str16()?.to_owned());
+            RegisterErr { code, reason }
+        }
+        MsgType::SetDevice => {
+            let label = cursor.get_str16()?.to_owned();
+            SetDevice { label }
         }
         MsgType::ListPeers => ListPeers,
         MsgType::PeersOnline => {
             let count = cursor.get_u16()? as usize;
-            let mut peers = Vec::with_capacity(count);
+            // Entries take body bytes: don't trust `count` to size the Vec
+            let mut peers = Vec::with_capacity(count.min(cursor.remaining()));
             for _ in 0..count {
                 let peer = cursor.get_str16()?.to_owned();
 
@@ -255,7 +419,16 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
         }
         MsgType::JoinErr => {
             let code = cursor.get_u16()?;
-            JoinErr { code }
+            JoinErr { code, reason: None }
+        }
+        MsgType::JoinErrV2 => {
+            let code = cursor.get_u16()?;
+            let reason = Some(cursor.get_str16()?.to_owned());
+            JoinErr { code, reason }
+        }
+        MsgType::Leave => {
+            let sid = cursor.get_str16()?.to_owned();
+            Leave { session_id: sid }
         }
 
         MsgType::PeerJoined => {
@@ -316,6 +489,34 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
                 cand,
             }
         }
+        MsgType::Candidates => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            let end_of_candidates = match cursor.get_u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(ProtoError::InvalidFormat("invalid end-of-candidates flag")),
+            };
+            let count = cursor.get_u16()? as usize;
+            let mut items = Vec::with_capacity(count.min(cursor.remaining()));
+            for _ in 0..count {
+                let mid = cursor.get_str16()?.to_owned();
+                let mline_index = cursor.get_u16()?;
+                let len = cursor.get_u32()? as usize;
+                let cand = cursor.get_bytes(len)?.to_vec();
+                items.push(CandidateItem {
+                    mid,
+                    mline_index,
+                    cand,
+                });
+            }
+            Candidates {
+                from,
+                to,
+                items,
+                end_of_candidates,
+            }
+        }
         MsgType::Ack => {
             let from = cursor.get_str16()?.to_owned();
             let to = cursor.get_str16()?.to_owned();
@@ -329,6 +530,104 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
             let reason = if s.is_empty() { None } else { Some(s) };
             Bye { from, to, reason }
         }
+        MsgType::Transfer => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            let target = cursor.get_str16()?.to_owned();
+            Transfer { from, to, target }
+        }
+        MsgType::CapabilityQuery => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            CapabilityQuery { from, to }
+        }
+        MsgType::CapabilityReply => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            let mut lists = [Vec::new(), Vec::new()];
+            for list in &mut lists {
+                let count = cursor.get_u8()?;
+                for _ in 0..count {
+                    list.push(cursor.get_str16()?.to_owned());
+                }
+            }
+            let [video_codecs, audio_codecs] = lists;
+            let max_width = cursor.get_u16()?;
+            let max_height = cursor.get_u16()?;
+            let data_channel = match cursor.get_u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(ProtoError::InvalidFormat("invalid data channel flag")),
+            };
+            CapabilityReply {
+                from,
+                to,
+                caps: Capabilities {
+                    video_codecs,
+                    audio_codecs,
+                    max_width,
+                    max_height,
+                    data_channel,
+                },
+            }
+        }
+        MsgType::AddContact => {
+            let username = cursor.get_str16()?.to_owned();
+            AddContact { username }
+        }
+        MsgType::RemoveContact => {
+            let username = cursor.get_str16()?.to_owned();
+            RemoveContact { username }
+        }
+        MsgType::Block => {
+            let username = cursor.get_str16()?.to_owned();
+            Block { username }
+        }
+        MsgType::Unblock => {
+            let username = cursor.get_str16()?.to_owned();
+            Unblock { username }
+        }
+        MsgType::ListContacts => ListContacts,
+        MsgType::Contacts => {
+            let mut lists = [Vec::new(), Vec::new()];
+            for list in &mut lists {
+                let count = cursor.get_u16()? as usize;
+                list.reserve(count.min(cursor.remaining()));
+                for _ in 0..count {
+                    list.push(cursor.get_str16()?.to_owned());
+                }
+            }
+            let [contacts, blocked] = lists;
+            Contacts { contacts, blocked }
+        }
+        MsgType::OfferErr => {
+            let to = cursor.get_str16()?.to_owned();
+            let code = cursor.get_u16()?;
+            let reason = cursor.get_str16()?;
+            let reason = (!reason.is_empty()).then(|| reason.to_owned());
+            OfferErr { to, code, reason }
+        }
+        MsgType::CameraControl => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            let request = get_camera_request(&mut cursor)?;
+            CameraControl { from, to, request }
+        }
+        MsgType::CameraControlReply => {
+            let from = cursor.get_str16()?.to_owned();
+            let to = cursor.get_str16()?.to_owned();
+            let request = get_camera_request(&mut cursor)?;
+            let accepted = cursor.get_u8()? != 0;
+            let reason = cursor.get_str16()?;
+            let reason = (!reason.is_empty()).then(|| reason.to_owned());
+            CameraControlReply {
+                from,
+                to,
+                request,
+                accepted,
+                reason,
+            }
+        }
         MsgType::Ping => {
             let nonce = cursor.get_u64()?;
             Ping { nonce }
@@ -337,6 +636,25 @@ pub fn decode_msg(msg_type: MsgType, body: &[u8]) -> Result<SignalingMsg, ProtoE
             let nonce = cursor.get_u64()?;
             Pong { nonce }
         }
+        MsgType::ResumeFrom => {
+            let seq = cursor.get_u64()?;
+            ResumeFrom { seq }
+        }
+        MsgType::Resumed => {
+            let replayed = cursor.get_u32()?;
+            let complete = cursor.get_u8()? != 0;
+            Resumed { replayed, complete }
+        }
+        MsgType::Sequenced => {
+            let seq = cursor.get_u64()?;
+            let inner_type = MsgType::from_u8(cursor.get_u8()?)?;
+            if inner_type == MsgType::Sequenced {
+                return Err(ProtoError::InvalidFormat("nested Sequenced"));
+            }
+            let inner_body = cursor.get_bytes(cursor.remaining())?;
+            let msg = Box::new(decode_msg(inner_type, inner_body)?);
+            Sequenced { seq, msg }
+        }
     };
 
     cursor.finish()?;
@@ -361,6 +679,23 @@ fn put_u64(buf: &mut Vec<u8>, v: u64) {
     buf.extend_from_slice(&v.to_be_bytes());
 }
 
+/// Appends an error reply's optional reason: with one, the reply goes out
+/// as its `V2` type, without one as the original type peers already know.
+fn put_reason(
+    buf: &mut Vec<u8>,
+    reason: Option<&str>,
+    v1: MsgType,
+    v2: MsgType,
+) -> Result<MsgType, ProtoError> {
+    match reason {
+        Some(reason) => {
+            put_str16(buf, reason)?;
+            Ok(v2)
+        }
+        None => Ok(v1),
+    }
+}
+
 /// str16 = u16 length + UTF-8 bytes
 fn put_str16(buf: &mut Vec<u8>, s: &str) -> Result<(), ProtoError> {
     let bytes = s.as_bytes();
@@ -378,6 +713,37 @@ fn put_str16(buf: &mut Vec<u8>, s: &str) -> Result<(), ProtoError> {
     Ok(())
 }
 
+/// tag (u8) + the request's fields
+fn put_camera_request(buf: &mut Vec<u8>, request: CameraRequest) {
+    put_u8(buf, request.tag());
+    match request {
+        CameraRequest::NextCamera => {}
+        CameraRequest::Resolution { width, height } => {
+            put_u16(buf, width);
+            put_u16(buf, height);
+        }
+        CameraRequest::Zoom { level } => put_u16(buf, level),
+        CameraRequest::Torch { on } => put_u8(buf, u8::from(on)),
+    }
+}
+
+fn get_camera_request(cursor: &mut Cursor<'_>) -> Result<CameraRequest, ProtoError> {
+    Ok(match cursor.get_u8()? {
+        0 => CameraRequest::NextCamera,
+        1 => CameraRequest::Resolution {
+            width: cursor.get_u16()?,
+            height: cursor.get_u16()?,
+        },
+        2 => CameraRequest::Zoom {
+            level: cursor.get_u16()?,
+        },
+        3 => CameraRequest::Torch {
+            on: cursor.get_u8()? != 0,
+        },
+        _ => return Err(ProtoError::InvalidFormat("unknown camera request")),
+    })
+}
+
 // ---- Cursor for decoding --------------------------------------------------
 
 #[derive(Debug)]
@@ -390,7 +756,6 @@ impl<'a> Cursor<'a> {
         Self { buf }
     }
 
-    #[allow(dead_code)]
     fn remaining(&self) -> usize {
         self.buf.len()
     }
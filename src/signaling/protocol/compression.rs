@@ -0,0 +1,123 @@
+//! Optional deflate compression of large frame bodies.
+//!
+//! Every frame this version writes sets `FLAG_ACCEPTS_DEFLATE`, and every
+//! reader inflates bodies flagged `FLAG_DEFLATE`. A connection compresses
+//! an Offer, Answer or Candidate body (numbered or not) only once the
+//! peer's frames have carried the first flag, so older peers, which never
+//! set it, keep getting plain frames.
+
+use std::{
+    io::{self, Read, Write},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use flate2::{Compression as Level, read::DeflateDecoder, write::DeflateEncoder};
+
+use super::{ProtoError, errors::FrameError, msg_type::MsgType};
+
+/// Header flag: the body is deflate-compressed.
+pub const FLAG_DEFLATE: u16 = 0x0001;
+/// Header flag: the sender inflates frames flagged `FLAG_DEFLATE`.
+pub const FLAG_ACCEPTS_DEFLATE: u16 = 0x0002;
+
+/// Bodies shorter than this are sent as they are: deflate would not save
+/// enough to matter.
+const MIN_COMPRESSED_BODY: usize = 512;
+
+/// What one connection knows about its peer. Clones share the state, so a
+/// reader and a writer thread can use one each.
+#[derive(Debug, Clone, Default)]
+pub struct Compression {
+    peer_accepts: Arc<AtomicBool>,
+}
+
+impl Compression {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the flags of a frame read from the peer.
+    pub fn observe(&self, flags: u16) {
+        if flags & FLAG_ACCEPTS_DEFLATE != 0 {
+            self.peer_accepts.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the peer has said it reads compressed frames.
+    #[must_use]
+    pub fn peer_accepts(&self) -> bool {
+        self.peer_accepts.load(Ordering::Relaxed)
+    }
+
+    /// `body` deflated, if it is worth sending that way to this peer.
+    #[must_use]
+    pub fn compress(&self, msg_type: MsgType, body: &[u8]) -> Option<Vec<u8>> {
+        let compressible = matches!(
+            msg_type,
+            MsgType::Offer
+                | MsgType::Answer
+                | MsgType::Candidate
+                | MsgType::Candidates
+                | MsgType::Sequenced
+        );
+        if !self.peer_accepts() || !compressible || body.len() < MIN_COMPRESSED_BODY {
+            return None;
+        }
+        let packed = deflate(body).ok()?;
+        (packed.len() < body.len()).then_some(packed)
+    }
+}
+
+fn deflate(body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::with_capacity(body.len() / 2), Level::default());
+    encoder.write_all(body)?;
+    encoder.finish()
+}
+
+/// Inflates a compressed body, refusing to grow it past `max_body`.
+///
+/// # Errors
+///
+/// Returns `FrameError::Proto(ProtoError::TooLarge)` if the body inflates
+/// past `max_body` and `FrameError::Proto(ProtoError::InvalidFormat)` if it
+/// is not valid deflate data.
+pub fn inflate(body: &[u8], max_body: usize) -> Result<Vec<u8>, FrameError> {
+    let mut out = Vec::with_capacity(body.len().saturating_mul(3).min(max_body));
+    DeflateDecoder::new(body)
+        .take(max_body as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|_| ProtoError::InvalidFormat("bad deflate body"))?;
+    if out.len() > max_body {
+        return Err(ProtoError::TooLarge.into());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn only_large_sdp_bodies_to_willing_peers_are_compressed() {
+        let sdp = b"a=candidate:1 1 udp 2130706431 192.168.0.10 50000 typ host\r\n".repeat(40);
+        let compression = Compression::new();
+        assert_eq!(compression.compress(MsgType::Offer, &sdp), None);
+
+        compression.observe(FLAG_ACCEPTS_DEFLATE);
+        let packed = compression.compress(MsgType::Offer, &sdp).unwrap();
+        assert!(packed.len() < sdp.len() / 4);
+        assert_eq!(inflate(&packed, sdp.len()).unwrap(), sdp);
+        assert!(matches!(
+            inflate(&packed, sdp.len() - 1),
+            Err(FrameError::Proto(ProtoError::TooLarge))
+        ));
+
+        assert_eq!(compression.compress(MsgType::Hello, &sdp), None);
+        assert_eq!(compression.compress(MsgType::Offer, &sdp[..100]), None);
+    }
+}
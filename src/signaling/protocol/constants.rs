@@ -2,6 +2,7 @@
 ///
 /// Header:
 ///   [ver: u8][msg_type: u8][flags: u16][body_len: u32]
+///   flags: `FLAG_DEFLATE`, `FLAG_ACCEPTS_DEFLATE` (see `compression`)
 /// Body:
 ///   [payload bytes...], up to `MAX_BODY_LEN`.
 pub const PROTO_VERSION: u8 = 1;
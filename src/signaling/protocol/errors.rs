@@ -1,4 +1,4 @@
-use std::io;
+use std::{fmt, io};
 
 /// Protocol-level errors (body parsing/format issues, etc.).
 #[derive(Debug)]
@@ -11,6 +11,23 @@ pub enum ProtoError {
     StringTooLong { max: usize, actual: usize },
 }
 
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownType(t) => write!(f, "unknown message type {t}"),
+            Self::Truncated => write!(f, "truncated message"),
+            Self::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            Self::TooLarge => write!(f, "message too large"),
+            Self::InvalidFormat(what) => write!(f, "invalid format: {what}"),
+            Self::StringTooLong { max, actual } => {
+                write!(f, "string too long ({actual} bytes, max {max})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {}
+
 /// Frame-level error wrapper: IO vs protocol.
 #[derive(Debug)]
 pub enum FrameError {
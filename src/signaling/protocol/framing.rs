@@ -1,13 +1,33 @@
-use super::{PROTO_VERSION, ProtoError, errors::FrameError, msg_type::MsgType};
+use super::{
+    PROTO_VERSION, ProtoError,
+    compression::{FLAG_ACCEPTS_DEFLATE, FLAG_DEFLATE, inflate},
+    errors::FrameError,
+    msg_type::MsgType,
+};
 use std::io::{self, Read, Write};
 
-/// Write a single frame: `[ver][type][reserved u16=0][len u32][body...]`
+/// Write a single uncompressed frame: `[ver][type][flags u16][len u32][body...]`
 ///
 /// # Errors
 ///
 /// Returns an `io::Error` if the body is too large or if writing to the stream fails.
-#[allow(clippy::cast_possible_truncation)]
 pub fn write_frame<W: Write>(w: &mut W, msg_type: MsgType, body: &[u8]) -> io::Result<()> {
+    write_frame_with_flags(w, msg_type, 0, body)
+}
+
+/// Write a single frame with header `flags`. `FLAG_ACCEPTS_DEFLATE` is
+/// always added: this reader inflates compressed bodies.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the body is too large or if writing to the stream fails.
+#[allow(clippy::cast_possible_truncation)]
+pub fn write_frame_with_flags<W: Write>(
+    w: &mut W,
+    msg_type: MsgType,
+    flags: u16,
+    body: &[u8],
+) -> io::Result<()> {
     if body.len() > u32::MAX as usize {
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -18,8 +38,7 @@ pub fn write_frame<W: Write>(w: &mut W, msg_type: MsgType, body: &[u8]) -> io::R
     let mut header = [0u8; 8];
     header[0] = PROTO_VERSION;
     header[1] = msg_type.as_u8();
-    header[2] = 0;
-    header[3] = 0;
+    header[2..4].copy_from_slice(&(flags | FLAG_ACCEPTS_DEFLATE).to_be_bytes());
     header[4..8].copy_from_slice(&len.to_be_bytes());
     w.write_all(&header)?;
     w.write_all(body)?;
@@ -34,6 +53,21 @@ pub fn write_frame<W: Write>(w: &mut W, msg_type: MsgType, body: &[u8]) -> io::R
 /// Returns a `FrameError` if reading from the stream fails, the frame is malformed,
 /// the message type is unknown, or the body length exceeds `max_body`.
 pub fn read_frame<R: Read>(r: &mut R, max_body: usize) -> Result<(MsgType, Vec<u8>), FrameError> {
+    let (msg_type, _flags, body) = read_frame_with_flags(r, max_body)?;
+    Ok((msg_type, body))
+}
+
+/// Like [`read_frame`], also returning the header flags. A compressed body
+/// is inflated, and must not inflate past `max_body` either.
+///
+/// # Errors
+///
+/// Returns a `FrameError` if reading from the stream fails, the frame is malformed,
+/// the message type is unknown, or the body length exceeds `max_body`.
+pub fn read_frame_with_flags<R: Read>(
+    r: &mut R,
+    max_body: usize,
+) -> Result<(MsgType, u16, Vec<u8>), FrameError> {
     let mut header = [0u8; 8];
 
     r.read_exact(&mut header)?; // io::Error -> FrameError::Io
@@ -47,7 +81,7 @@ pub fn read_frame<R: Read>(r: &mut R, max_body: usize) -> Result<(MsgType, Vec<u
 
     let msg_type = MsgType::from_u8(msg_type_byte)?; // ProtoError -> FrameError::Proto
 
-    // flags ignored for now
+    let flags = u16::from_be_bytes([header[2], header[3]]);
     let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
     if len > max_body {
         return Err(ProtoError::TooLarge.into());
@@ -56,5 +90,8 @@ pub fn read_frame<R: Read>(r: &mut R, max_body: usize) -> Result<(MsgType, Vec<u
     let mut body = vec![0u8; len];
     r.read_exact(&mut body)?; // io::Error -> FrameError::Io
 
-    Ok((msg_type, body))
+    if flags & FLAG_DEFLATE != 0 {
+        body = inflate(&body, max_body)?;
+    }
+    Ok((msg_type, flags, body))
 }
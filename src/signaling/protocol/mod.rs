@@ -1,6 +1,10 @@
 use std::io::{Read, Write};
 
+pub mod camera_request;
+pub mod candidate_item;
+pub mod capabilities;
 mod codec;
+mod compression;
 mod constants;
 mod errors;
 mod framing;
@@ -10,9 +14,10 @@ pub mod peer_status;
 mod types;
 
 pub use codec::{decode_msg, encode_msg};
+pub use compression::{Compression, FLAG_ACCEPTS_DEFLATE, FLAG_DEFLATE};
 pub use constants::{MAX_BODY_LEN, PROTO_VERSION};
 pub use errors::{FrameError, ProtoError};
-pub use framing::{read_frame, write_frame};
+pub use framing::{read_frame, read_frame_with_flags, write_frame, write_frame_with_flags};
 pub use msg::SignalingMsg;
 pub use msg_type::MsgType;
 pub use types::{SessionCode, SessionId, TxnId, UserName};
@@ -40,6 +45,42 @@ pub fn read_msg<R: Read>(r: &mut R) -> Result<SignalingMsg, FrameError> {
     Ok(msg)
 }
 
+/// Like [`write_msg`], deflating the body when `compression` says the peer
+/// reads compressed frames and it is worth it.
+///
+/// # Errors
+///
+/// Returns `FrameError` if the message cannot be encoded or written to the stream.
+pub fn write_msg_compressed<W: Write>(
+    w: &mut W,
+    msg: &SignalingMsg,
+    compression: &Compression,
+) -> Result<(), FrameError> {
+    let (msg_type, body) = encode_msg(msg)?;
+    match compression.compress(msg_type, &body) {
+        Some(packed) => write_frame_with_flags(w, msg_type, FLAG_DEFLATE, &packed)?,
+        None => write_frame(w, msg_type, &body)?,
+    }
+    Ok(())
+}
+
+/// Like [`read_msg`], recording in `compression` whether the peer reads
+/// compressed frames.
+///
+/// # Errors
+///
+/// Returns `FrameError` if a complete frame cannot be read or if the message body
+/// cannot be decoded.
+pub fn read_msg_compressed<R: Read>(
+    r: &mut R,
+    compression: &Compression,
+) -> Result<SignalingMsg, FrameError> {
+    let (msg_type, flags, body) = read_frame_with_flags(r, MAX_BODY_LEN)?;
+    compression.observe(flags);
+    let msg = decode_msg(msg_type, &body)?;
+    Ok(msg)
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used)]
@@ -114,6 +155,16 @@ mod tests {
         assert_eq!(decoded, original);
     }
 
+    #[test]
+    fn roundtrip_leave() {
+        let original = SignalingMsg::Leave {
+            session_id: "sess-123".to_string(),
+        };
+
+        let decoded = roundtrip(&original);
+        assert_eq!(decoded, original);
+    }
+
     #[test]
     fn roundtrip_offer_answer_candidate() {
         let sdp = b"v=0\r\no=- 0 0 IN IP4 127.0.0.1\r\n".to_vec();
@@ -148,6 +199,38 @@ mod tests {
         assert_eq!(decoded_candidate, candidate);
     }
 
+    #[test]
+    fn roundtrip_candidates_batch() {
+        use candidate_item::CandidateItem;
+
+        let batch = SignalingMsg::Candidates {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            items: vec![
+                CandidateItem {
+                    mid: "0".to_string(),
+                    mline_index: 0,
+                    cand: b"candidate:1 1 udp 2122252543 192.0.2.1 54400 typ host".to_vec(),
+                },
+                CandidateItem {
+                    mid: "1".to_string(),
+                    mline_index: 1,
+                    cand: b"candidate:2 1 udp 1686052607 203.0.113.7 61000 typ srflx".to_vec(),
+                },
+            ],
+            end_of_candidates: true,
+        };
+        assert_eq!(roundtrip(&batch), batch);
+
+        let empty_eoc = SignalingMsg::Candidates {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            items: Vec::new(),
+            end_of_candidates: true,
+        };
+        assert_eq!(roundtrip(&empty_eoc), empty_eoc);
+    }
+
     #[test]
     fn roundtrip_bye_some_and_none() {
         let bye_some = SignalingMsg::Bye {
@@ -167,6 +250,170 @@ mod tests {
         assert_eq!(decoded_none, bye_none);
     }
 
+    #[test]
+    fn roundtrip_capability_query_and_reply() {
+        let query = SignalingMsg::CapabilityQuery {
+            from: "alice".into(),
+            to: "bob".into(),
+        };
+        assert_eq!(roundtrip(&query), query);
+
+        let reply = SignalingMsg::CapabilityReply {
+            from: "bob".into(),
+            to: "alice".into(),
+            caps: capabilities::Capabilities {
+                video_codecs: vec!["H264".into()],
+                audio_codecs: vec!["PCMU".into(), "telephone-event".into()],
+                max_width: 1280,
+                max_height: 720,
+                data_channel: true,
+            },
+        };
+        assert_eq!(roundtrip(&reply), reply);
+    }
+
+    #[test]
+    fn roundtrip_camera_control() {
+        let ask = SignalingMsg::CameraControl {
+            from: "helpdesk".into(),
+            to: "bob".into(),
+            request: camera_request::CameraRequest::Resolution {
+                width: 1280,
+                height: 720,
+            },
+        };
+        assert_eq!(roundtrip(&ask), ask);
+
+        let reply = SignalingMsg::CameraControlReply {
+            from: "bob".into(),
+            to: "helpdesk".into(),
+            request: camera_request::CameraRequest::Torch { on: true },
+            accepted: false,
+            reason: Some("no camera light".into()),
+        };
+        assert_eq!(roundtrip(&reply), reply);
+    }
+
+    #[test]
+    fn roundtrip_transfer() {
+        let transfer = SignalingMsg::Transfer {
+            from: "desk".into(),
+            to: "caller".into(),
+            target: "sales".into(),
+        };
+        assert_eq!(roundtrip(&transfer), transfer);
+    }
+
+    #[test]
+    fn roundtrip_set_device() {
+        let msg = SignalingMsg::SetDevice {
+            label: "laptop".into(),
+        };
+        assert_eq!(roundtrip(&msg), msg);
+    }
+
+    #[test]
+    fn roundtrip_resume_and_sequenced() {
+        for msg in [
+            SignalingMsg::ResumeFrom { seq: 41 },
+            SignalingMsg::Resumed {
+                replayed: 3,
+                complete: true,
+            },
+            SignalingMsg::Sequenced {
+                seq: 42,
+                msg: Box::new(SignalingMsg::Bye {
+                    from: "alice".into(),
+                    to: "bob".into(),
+                    reason: None,
+                }),
+            },
+        ] {
+            assert_eq!(roundtrip(&msg), msg);
+        }
+
+        let nested = SignalingMsg::Sequenced {
+            seq: 1,
+            msg: Box::new(SignalingMsg::Sequenced {
+                seq: 2,
+                msg: Box::new(SignalingMsg::ListPeers),
+            }),
+        };
+        assert!(encode_msg(&nested).is_err());
+    }
+
+    #[test]
+    fn roundtrip_contacts() {
+        for msg in [
+            SignalingMsg::AddContact {
+                username: "bob".into(),
+            },
+            SignalingMsg::RemoveContact {
+                username: "carol".into(),
+            },
+            SignalingMsg::Block {
+                username: "mallory".into(),
+            },
+            SignalingMsg::Unblock {
+                username: "mallory".into(),
+            },
+            SignalingMsg::ListContacts,
+            SignalingMsg::Contacts {
+                contacts: vec!["bob".into(), "dave".into()],
+                blocked: vec!["mallory".into()],
+            },
+            SignalingMsg::OfferErr {
+                to: "bob".into(),
+                code: 30,
+                reason: Some("blocked".into()),
+            },
+            SignalingMsg::OfferErr {
+                to: "bob".into(),
+                code: 31,
+                reason: None,
+            },
+        ] {
+            assert_eq!(roundtrip(&msg), msg);
+        }
+    }
+
+    #[test]
+    fn error_replies_carry_a_reason_only_as_v2() {
+        let plain = SignalingMsg::JoinErr {
+            code: 21,
+            reason: None,
+        };
+        assert_eq!(encode_msg(&plain).unwrap().0, MsgType::JoinErr);
+        assert_eq!(roundtrip(&plain), plain);
+
+        let explained = [
+            SignalingMsg::LoginErr {
+                code: 3,
+                reason: Some("invalid username or password".into()),
+            },
+            SignalingMsg::RegisterErr {
+                code: 1,
+                reason: Some("username already taken".into()),
+            },
+            SignalingMsg::JoinErr {
+                code: 21,
+                reason: Some("room is full".into()),
+            },
+        ];
+        let types: Vec<_> = explained.iter().map(|m| encode_msg(m).unwrap().0).collect();
+        assert_eq!(
+            types,
+            [
+                MsgType::LoginErrV2,
+                MsgType::RegisterErrV2,
+                MsgType::JoinErrV2
+            ]
+        );
+        for msg in &explained {
+            assert_eq!(&roundtrip(msg), msg);
+        }
+    }
+
     #[test]
     #[allow(clippy::similar_names)]
     fn roundtrip_ping_pong() {
@@ -180,6 +427,33 @@ mod tests {
         assert_eq!(decoded_pong, pong);
     }
 
+    #[test]
+    fn large_offers_are_compressed_once_the_peer_accepts_it() {
+        let offer = SignalingMsg::Offer {
+            txn_id: 1,
+            from: "alice".into(),
+            to: "bob".into(),
+            sdp: b"a=candidate:1 1 udp 2130706431 10.0.0.1 50000 typ host\r\n".repeat(50),
+        };
+        let (alice, bob) = (Compression::new(), Compression::new());
+
+        // Alice knows nothing of Bob yet, so her offer goes out plain.
+        let mut wire = Vec::new();
+        write_msg_compressed(&mut wire, &offer, &alice).unwrap();
+        let plain_len = wire.len();
+        assert_eq!(
+            read_msg_compressed(&mut wire.as_slice(), &bob).unwrap(),
+            offer
+        );
+        assert!(bob.peer_accepts());
+
+        alice.observe(FLAG_ACCEPTS_DEFLATE);
+        let mut wire = Vec::new();
+        write_msg_compressed(&mut wire, &offer, &alice).unwrap();
+        assert!(wire.len() < plain_len / 4);
+        assert_eq!(read_msg(&mut wire.as_slice()).unwrap(), offer);
+    }
+
     // ---------- Encoding border cases ----------
 
     #[test]
@@ -1,10 +1,11 @@
 // ---- Public message enum --------------------------------------------------
 
 use crate::signaling::protocol::{
-    SessionCode, SessionId, TxnId, UserName, peer_status::PeerStatus,
+    SessionCode, SessionId, TxnId, UserName, camera_request::CameraRequest,
+    candidate_item::CandidateItem, capabilities::Capabilities, peer_status::PeerStatus,
 };
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SignalingMsg {
     // Handshake / auth
     Hello {
@@ -17,8 +18,10 @@ pub enum SignalingMsg {
     LoginOk {
         username: UserName,
     },
+    /// `reason` is sent as `LoginErrV2`; peers that predate it send none.
     LoginErr {
         code: u16, // map to our AuthErrorCode later
+        reason: Option<String>,
     },
     Register {
         username: UserName,
@@ -29,6 +32,11 @@ pub enum SignalingMsg {
     },
     RegisterErr {
         code: u16, // maps from RegisterErrorCode
+        reason: Option<String>,
+    },
+    /// Names the sender's device, for the user's other devices.
+    SetDevice {
+        label: String,
     },
     ListPeers,
     PeersOnline {
@@ -51,6 +59,10 @@ pub enum SignalingMsg {
     },
     JoinErr {
         code: u16, // map to JoinErrorCode
+        reason: Option<String>,
+    },
+    Leave {
+        session_id: SessionId,
     },
     // Session membership notifications (server → clients)
     PeerJoined {
@@ -82,6 +94,14 @@ pub enum SignalingMsg {
         mline_index: u16,
         cand: Vec<u8>, // raw UTF-8 text
     },
+    /// Several trickled candidates in one frame, applied in order.
+    /// `end_of_candidates` signals the sender finished gathering.
+    Candidates {
+        from: UserName,
+        to: UserName,
+        items: Vec<CandidateItem>,
+        end_of_candidates: bool,
+    },
     Ack {
         from: UserName,
         to: UserName,
@@ -92,6 +112,65 @@ pub enum SignalingMsg {
         to: UserName,
         reason: Option<String>,
     },
+    /// `from` hands its call with `to` over to `target`: the server tells
+    /// both, and `target` calls `to`.
+    Transfer {
+        from: UserName,
+        to: UserName,
+        target: UserName,
+    },
+
+    /// Asks `to` what it supports, before calling it.
+    CapabilityQuery {
+        from: UserName,
+        to: UserName,
+    },
+    CapabilityReply {
+        from: UserName,
+        to: UserName,
+        caps: Capabilities,
+    },
+    /// `from` asks to control `to`'s camera.
+    CameraControl {
+        from: UserName,
+        to: UserName,
+        request: CameraRequest,
+    },
+    /// Whether the `request` was carried out; `reason` says why not.
+    CameraControlReply {
+        from: UserName,
+        to: UserName,
+        request: CameraRequest,
+        accepted: bool,
+        reason: Option<String>,
+    },
+
+    // Contacts (the `contacts` forwarding policy only connects mutual contacts)
+    AddContact {
+        username: UserName,
+    },
+    RemoveContact {
+        username: UserName,
+    },
+    /// Refuse calls from `username` and hide each from the other.
+    Block {
+        username: UserName,
+    },
+    Unblock {
+        username: UserName,
+    },
+    ListContacts,
+    /// Server's answer to any of the above: the caller's lists.
+    Contacts {
+        contacts: Vec<UserName>,
+        blocked: Vec<UserName>,
+    },
+    /// Server → caller: the offer to `to` was not delivered.
+    OfferErr {
+        to: UserName,
+        code: u16, // maps from CallErrorCode
+        reason: Option<String>,
+    },
 
     // Keepalive
     Ping {
@@ -100,4 +179,24 @@ pub enum SignalingMsg {
     Pong {
         nonce: u64,
     },
+
+    // Resuming after a reconnect
+    /// Client → server. `seq` 0 asks for the server's messages to be
+    /// numbered; after logging in again on a new connection, the last `seq`
+    /// received on the old one asks for what came after it.
+    ResumeFrom {
+        seq: u64,
+    },
+    /// Server → client, never numbered: the answer to a `ResumeFrom` with a
+    /// `seq`. When not `complete`, messages were lost and the client starts
+    /// afresh; numbering goes on from its `seq` either way.
+    Resumed {
+        replayed: u32,
+        complete: bool,
+    },
+    /// Server → client: `msg`, numbered on a connection that asked for it.
+    Sequenced {
+        seq: u64,
+        msg: Box<SignalingMsg>,
+    },
 }
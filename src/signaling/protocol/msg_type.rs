@@ -14,6 +14,10 @@ pub enum MsgType {
     RegisterErr = 0x07,
     ListPeers = 0x08,
     PeersOnline = 0x09,
+    // Error replies that also carry a human-readable reason.
+    LoginErrV2 = 0x0A,
+    RegisterErrV2 = 0x0B,
+    SetDevice = 0x0C,
 
     CreateSession = 0x10,
     Created = 0x11,
@@ -22,15 +26,34 @@ pub enum MsgType {
     JoinErr = 0x14,
     PeerJoined = 0x15,
     PeerLeft = 0x16,
+    Leave = 0x17,
+    JoinErrV2 = 0x18,
 
     Offer = 0x20,
     Answer = 0x21,
     Candidate = 0x22,
     Ack = 0x23,
     Bye = 0x24,
+    Candidates = 0x25,
+    Transfer = 0x26,
+    CapabilityQuery = 0x27,
+    CapabilityReply = 0x28,
+    OfferErr = 0x29,
+    CameraControl = 0x2A,
+    CameraControlReply = 0x2B,
 
     Ping = 0x30,
     Pong = 0x31,
+    ResumeFrom = 0x32,
+    Resumed = 0x33,
+    Sequenced = 0x34,
+
+    AddContact = 0x40,
+    RemoveContact = 0x41,
+    ListContacts = 0x42,
+    Contacts = 0x43,
+    Block = 0x44,
+    Unblock = 0x45,
 }
 
 impl MsgType {
@@ -48,6 +71,9 @@ impl MsgType {
             0x07 => Ok(Self::RegisterErr),
             0x08 => Ok(Self::ListPeers),
             0x09 => Ok(Self::PeersOnline),
+            0x0A => Ok(Self::LoginErrV2),
+            0x0B => Ok(Self::RegisterErrV2),
+            0x0C => Ok(Self::SetDevice),
             0x10 => Ok(Self::CreateSession),
             0x11 => Ok(Self::Created),
             0x12 => Ok(Self::Join),
@@ -55,13 +81,31 @@ impl MsgType {
             0x14 => Ok(Self::JoinErr),
             0x15 => Ok(Self::PeerJoined),
             0x16 => Ok(Self::PeerLeft),
+            0x17 => Ok(Self::Leave),
+            0x18 => Ok(Self::JoinErrV2),
             0x20 => Ok(Self::Offer),
             0x21 => Ok(Self::Answer),
             0x22 => Ok(Self::Candidate),
             0x23 => Ok(Self::Ack),
             0x24 => Ok(Self::Bye),
+            0x25 => Ok(Self::Candidates),
+            0x26 => Ok(Self::Transfer),
+            0x27 => Ok(Self::CapabilityQuery),
+            0x28 => Ok(Self::CapabilityReply),
+            0x29 => Ok(Self::OfferErr),
+            0x2A => Ok(Self::CameraControl),
+            0x2B => Ok(Self::CameraControlReply),
             0x30 => Ok(Self::Ping),
             0x31 => Ok(Self::Pong),
+            0x32 => Ok(Self::ResumeFrom),
+            0x33 => Ok(Self::Resumed),
+            0x34 => Ok(Self::Sequenced),
+            0x40 => Ok(Self::AddContact),
+            0x41 => Ok(Self::RemoveContact),
+            0x42 => Ok(Self::ListContacts),
+            0x43 => Ok(Self::Contacts),
+            0x44 => Ok(Self::Block),
+            0x45 => Ok(Self::Unblock),
             other => Err(ProtoError::UnknownType(other)),
         }
     }
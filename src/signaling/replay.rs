@@ -0,0 +1,117 @@
+//! Numbered server → client messages, kept for replay after a reconnect.
+//!
+//! A client that sent `ResumeFrom { seq: 0 }` gets every message wrapped in
+//! `Sequenced`. When its connection drops, the `Router` keeps the client
+//! logged in for [`RESUME_GRACE`] and goes on numbering and keeping what is
+//! sent to it; a new connection that logs in as the same user and sends the
+//! last `seq` it saw takes the old one over and gets the rest replayed.
+//! Either way the numbers go on from that `seq`, and the `Resumed` answer
+//! itself is not numbered.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::signaling::protocol::SignalingMsg;
+
+/// Messages kept per client. A blip longer than this many messages cannot
+/// be resumed.
+pub const REPLAY_CAPACITY: usize = 256;
+
+/// How long a dropped client stays logged in waiting to be resumed.
+pub const RESUME_GRACE: Duration = Duration::from_secs(30);
+
+/// The numbered messages sent to one client.
+#[derive(Debug)]
+pub struct Stream {
+    next_seq: u64,
+    sent: VecDeque<SignalingMsg>,
+}
+
+impl Default for Stream {
+    fn default() -> Self {
+        Self {
+            next_seq: 1,
+            sent: VecDeque::new(),
+        }
+    }
+}
+
+impl Stream {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A stream whose first message is numbered `seq + 1`, for a client
+    /// that asked to resume from `seq` and could not: its numbering goes on.
+    #[must_use]
+    pub fn after(seq: u64) -> Self {
+        Self {
+            next_seq: seq + 1,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Numbers `msg` and keeps a copy; returns the `Sequenced` to send.
+    pub fn number(&mut self, msg: SignalingMsg) -> SignalingMsg {
+        let numbered = SignalingMsg::Sequenced {
+            seq: self.next_seq,
+            msg: Box::new(msg),
+        };
+        self.next_seq += 1;
+        if self.sent.len() == REPLAY_CAPACITY {
+            self.sent.pop_front();
+        }
+        self.sent.push_back(numbered.clone());
+        numbered
+    }
+
+    /// The highest `seq` handed out, 0 before the first.
+    #[must_use]
+    pub const fn last_seq(&self) -> u64 {
+        self.next_seq - 1
+    }
+
+    /// Whether a client that last saw `seq` can be brought up to date.
+    #[must_use]
+    pub fn can_resume_from(&self, seq: u64) -> bool {
+        let oldest = self.last_seq() + 1 - self.sent.len() as u64;
+        seq <= self.last_seq() && seq + 1 >= oldest
+    }
+
+    /// The messages after `seq`, in order.
+    #[must_use]
+    pub fn since(&self, seq: u64) -> Vec<SignalingMsg> {
+        self.sent
+            .iter()
+            .filter(|msg| matches!(msg, SignalingMsg::Sequenced { seq: s, .. } if *s > seq))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn replays_what_came_after_the_last_seen_message() {
+        let mut stream = Stream::new();
+        for nonce in 0..(REPLAY_CAPACITY as u64 + 10) {
+            stream.number(SignalingMsg::Pong { nonce });
+        }
+        let last = stream.last_seq();
+        assert_eq!(last, REPLAY_CAPACITY as u64 + 10);
+
+        let rest = stream.since(last - 2);
+        assert_eq!(rest.len(), 2);
+        assert!(matches!(rest[0], SignalingMsg::Sequenced { seq, .. } if seq == last - 1));
+        assert!(stream.can_resume_from(last - 2));
+
+        // The first ten fell out of the buffer
+        assert!(stream.can_resume_from(10));
+        assert!(!stream.can_resume_from(9));
+        assert!(!stream.can_resume_from(last + 1));
+    }
+}
@@ -1,17 +1,30 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use crate::log::NoopLogSink;
 use crate::log::log_sink::LogSink;
-use crate::signaling::auth::AuthBackend;
+use crate::signaling::auth::{AuthBackend, AuthError};
 use crate::signaling::protocol::SignalingMsg;
+use crate::signaling::replay::{RESUME_GRACE, Stream};
 use crate::signaling::server_engine::ServerEngine;
 use crate::signaling::types::{ClientId, OutgoingMsg};
 
 /// Router glues the `ServerEngine` state machine to per-client "sinks".
+///
+/// Outboxes are per connection. A connection that resumed a dropped client
+/// (see [`crate::signaling::replay`]) talks to the engine as that client.
 pub struct Router {
     server: ServerEngine,
     outboxes: HashMap<ClientId, Vec<SignalingMsg>>,
+    /// Numbered clients, by the id the engine knows them by.
+    streams: HashMap<ClientId, Stream>,
+    /// Connection → the client it resumed.
+    resumed: HashMap<ClientId, ClientId>,
+    /// Resumed client → the connection now serving it.
+    connections: HashMap<ClientId, ClientId>,
+    /// Numbered clients whose connection dropped, and since when.
+    lingering: HashMap<ClientId, Instant>,
 }
 
 impl Router {
@@ -22,17 +35,22 @@ impl Router {
 
     #[must_use]
     pub fn with_log(log: Arc<dyn LogSink>) -> Self {
-        Self {
-            server: ServerEngine::with_log(log),
-            outboxes: HashMap::new(),
-        }
+        Self::with_server(ServerEngine::with_log(log))
     }
     /// New: build a Router with explicit log sink *and* auth backend.
     #[must_use]
     pub fn with_log_and_auth(log: Arc<dyn LogSink>, auth_backend: Box<dyn AuthBackend>) -> Self {
+        Self::with_server(ServerEngine::with_log_and_auth(log, auth_backend))
+    }
+
+    fn with_server(server: ServerEngine) -> Self {
         Self {
-            server: ServerEngine::with_log_and_auth(log, auth_backend),
+            server,
             outboxes: HashMap::new(),
+            streams: HashMap::new(),
+            resumed: HashMap::new(),
+            connections: HashMap::new(),
+            lingering: HashMap::new(),
         }
     }
 
@@ -46,12 +64,33 @@ impl Router {
     /// Unregister a client:
     /// - removes its outbox
     /// - lets the server clean up presence/sessions and emit any notifications.
+    ///
+    /// A logged-in numbered client lingers instead: it stays logged in, and
+    /// what is sent to it is kept, until it is resumed or
+    /// [`Self::expire_lingering`] drops it.
     pub fn unregister_client(&mut self, client_id: ClientId) {
         self.outboxes.remove(&client_id);
 
-        let out_msgs = self.server.handle_disconnect(client_id);
-        for out_msg in out_msgs {
-            self.enqueue(out_msg);
+        let client = self.resumed.remove(&client_id).unwrap_or(client_id);
+        self.connections.remove(&client);
+        if self.streams.contains_key(&client) && self.server.username_for(client).is_some() {
+            self.lingering.insert(client, Instant::now());
+            self.server.set_lingering(client, true);
+            return;
+        }
+        self.drop_client(client);
+    }
+
+    /// Drops the clients that lingered longer than [`RESUME_GRACE`].
+    pub fn expire_lingering(&mut self, now: Instant) {
+        let expired: Vec<ClientId> = self
+            .lingering
+            .iter()
+            .filter(|(_, since)| now.duration_since(**since) >= RESUME_GRACE)
+            .map(|(client, _)| *client)
+            .collect();
+        for client in expired {
+            self.drop_client(client);
         }
     }
 
@@ -60,7 +99,42 @@ impl Router {
     /// This calls into the `ServerEngine` and enqueues any resulting messages into the
     /// appropriate client outboxes.
     pub fn handle_from_client(&mut self, from_cid: ClientId, msg: SignalingMsg) {
-        let out_msgs = self.server.handle(from_cid, msg);
+        if let SignalingMsg::ResumeFrom { seq } = msg {
+            self.resume(from_cid, seq);
+            return;
+        }
+        let out_msgs = self.server.handle(self.client_of(from_cid), msg);
+        for out_msg in out_msgs {
+            self.enqueue(out_msg);
+        }
+    }
+
+    /// Log `client_id` in as the user its TLS client certificate names.
+    pub fn login_with_certificate(&mut self, client_id: ClientId, username: &str) {
+        let out_msgs = self
+            .server
+            .handle_certificate_login(self.client_of(client_id), username);
+        for out_msg in out_msgs {
+            self.enqueue(out_msg);
+        }
+    }
+
+    /// The auth workers answered the password login of `client_id`.
+    pub fn finish_login(
+        &mut self,
+        client_id: ClientId,
+        username: &str,
+        result: Result<(), AuthError>,
+    ) {
+        let out_msgs = self.server.finish_login(client_id, username, result);
+        for out_msg in out_msgs {
+            self.enqueue(out_msg);
+        }
+    }
+
+    /// Route a message from the SFU to the client it is addressed to.
+    pub fn handle_from_sfu(&mut self, msg: SignalingMsg) {
+        let out_msgs = self.server.deliver_from_sfu(msg);
         for out_msg in out_msgs {
             self.enqueue(out_msg);
         }
@@ -114,8 +188,92 @@ impl Router {
     }
 
     fn enqueue(&mut self, out_msg: OutgoingMsg) {
-        let queue = self.outboxes.entry(out_msg.client_id_target).or_default();
-        queue.push(out_msg.msg);
+        let client = out_msg.client_id_target;
+        let msg = match self.streams.get_mut(&client) {
+            Some(stream) => stream.number(out_msg.msg),
+            None => out_msg.msg,
+        };
+        if self.lingering.contains_key(&client) {
+            // Kept in the stream until the client resumes
+            return;
+        }
+        let connection = self.connections.get(&client).copied().unwrap_or(client);
+        let queue = self.outboxes.entry(connection).or_default();
+        queue.push(msg);
+    }
+
+    /// The client `connection` speaks for: itself, or the one it resumed.
+    fn client_of(&self, connection: ClientId) -> ClientId {
+        self.resumed.get(&connection).copied().unwrap_or(connection)
+    }
+
+    /// `ResumeFrom { seq }` from `connection`. With `seq` 0 its messages are
+    /// numbered from now on. Otherwise it must be logged in, and takes over
+    /// the lingering client of the same user that can replay from `seq`.
+    fn resume(&mut self, connection: ClientId, seq: u64) {
+        if seq == 0 {
+            self.streams.entry(connection).or_default();
+            return;
+        }
+        let username = self.server.username_for(connection).map(str::to_owned);
+        let old = username.and_then(|username| {
+            self.lingering
+                .iter()
+                .filter(|(client, _)| {
+                    self.server.username_for(**client) == Some(username.as_str())
+                        && self
+                            .streams
+                            .get(client)
+                            .is_some_and(|stream| stream.can_resume_from(seq))
+                })
+                .max_by_key(|(_, since)| **since)
+                .map(|(client, _)| *client)
+        });
+        let Some(old) = old else {
+            // The client already counts from `seq`
+            self.streams.insert(connection, Stream::after(seq));
+            self.outboxes
+                .entry(connection)
+                .or_default()
+                .push(SignalingMsg::Resumed {
+                    replayed: 0,
+                    complete: false,
+                });
+            return;
+        };
+
+        self.lingering.remove(&old);
+        self.server.set_lingering(old, false);
+        self.resumed.insert(connection, old);
+        self.connections.insert(old, connection);
+        let replay = self
+            .streams
+            .get(&old)
+            .map(|stream| stream.since(seq))
+            .unwrap_or_default();
+        let replayed = u32::try_from(replay.len()).unwrap_or(u32::MAX);
+        let outbox = self.outboxes.entry(connection).or_default();
+        outbox.extend(replay);
+        outbox.push(SignalingMsg::Resumed {
+            replayed,
+            complete: true,
+        });
+
+        // The connection's own login gives way to the client it resumed
+        self.streams.remove(&connection);
+        for out_msg in self.server.handle_disconnect(connection) {
+            self.enqueue(out_msg);
+        }
+    }
+
+    /// Logs `client` out for good.
+    fn drop_client(&mut self, client: ClientId) {
+        self.lingering.remove(&client);
+        self.streams.remove(&client);
+        let out_msgs = self.server.handle_disconnect(client);
+        for out_msg in out_msgs {
+            self.enqueue(out_msg);
+        }
     }
 }
 impl Default for Router {
@@ -239,6 +397,116 @@ mod tests {
         }
     }
 
+    fn login(router: &mut Router, client: ClientId, username: &str) {
+        router.register_client(client);
+        router.handle_from_client(
+            client,
+            SignalingMsg::Login {
+                username: username.into(),
+                password: "pw".into(),
+            },
+        );
+    }
+
+    fn candidate(to: &str) -> SignalingMsg {
+        SignalingMsg::Candidate {
+            from: "alice".into(),
+            to: to.into(),
+            mid: "0".into(),
+            mline_index: 0,
+            cand: b"candidate".to_vec(),
+        }
+    }
+
+    #[test]
+    fn a_dropped_client_is_resumed_with_what_it_missed() {
+        let mut router = Router::new();
+        router.server_mut().set_max_devices(1);
+        login(&mut router, 1, "alice");
+        router.register_client(2);
+        router.handle_from_client(2, SignalingMsg::ResumeFrom { seq: 0 });
+        router.handle_from_client(
+            2,
+            SignalingMsg::Login {
+                username: "bob".into(),
+                password: "pw".into(),
+            },
+        );
+        let seen = router.take_outgoing_for(2);
+        assert!(
+            seen.iter()
+                .all(|m| matches!(m, SignalingMsg::Sequenced { .. }))
+        );
+        let last_seen = seen.len() as u64;
+
+        // Bob's connection drops; alice's candidate is kept for him
+        router.unregister_client(2);
+        router.handle_from_client(1, candidate("bob"));
+        assert!(router.take_outgoing_for(2).is_empty());
+
+        // Logging in again works though bob may only have one device
+        login(&mut router, 3, "bob");
+        assert!(
+            router
+                .take_outgoing_for(3)
+                .iter()
+                .any(|m| matches!(m, SignalingMsg::LoginOk { .. }))
+        );
+        router.handle_from_client(3, SignalingMsg::ResumeFrom { seq: last_seen });
+        let out = router.take_outgoing_for(3);
+        assert!(matches!(
+            &out[0],
+            SignalingMsg::Sequenced { seq, msg }
+                if *seq == last_seen + 1 && matches!(**msg, SignalingMsg::Candidate { .. })
+        ));
+        // The candidate and the peer list sent when bob logged in again
+        assert!(out.contains(&SignalingMsg::Resumed {
+            replayed: 2,
+            complete: true
+        }));
+
+        // Connection 3 now speaks for bob's old client
+        router.handle_from_client(1, candidate("bob"));
+        assert_eq!(router.take_outgoing_for(3).len(), 1);
+    }
+
+    #[test]
+    fn a_failed_resume_keeps_the_clients_numbering() {
+        let mut router = Router::new();
+        login(&mut router, 1, "alice");
+        login(&mut router, 2, "bob");
+        router.take_outgoing_for(2);
+
+        // Nothing of bob's lingers to resume from
+        router.handle_from_client(2, SignalingMsg::ResumeFrom { seq: 7 });
+        assert_eq!(
+            router.take_outgoing_for(2),
+            vec![SignalingMsg::Resumed {
+                replayed: 0,
+                complete: false
+            }]
+        );
+
+        router.handle_from_client(1, candidate("bob"));
+        let out = router.take_outgoing_for(2);
+        assert!(matches!(
+            out.as_slice(),
+            [SignalingMsg::Sequenced { seq: 8, msg }] if matches!(**msg, SignalingMsg::Candidate { .. })
+        ));
+    }
+
+    #[test]
+    fn a_lingering_client_expires_after_the_grace() {
+        let mut router = Router::new();
+        login(&mut router, 1, "alice");
+        router.handle_from_client(1, SignalingMsg::ResumeFrom { seq: 0 });
+        router.unregister_client(1);
+        assert_eq!(router.server().username_for(1), Some("alice"));
+
+        router.expire_lingering(Instant::now() + RESUME_GRACE);
+        assert_eq!(router.server().username_for(1), None);
+    }
+
     #[test]
     fn drain_all_outgoing_collects_messages_for_all_clients() {
         let mut router = Router::new();
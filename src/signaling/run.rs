@@ -1,13 +1,15 @@
 use crate::config::Config;
+use crate::log::NoopLogSink;
 use crate::log::log_sink::LogSink;
-use crate::signaling::SignalingServer;
+use crate::signaling::{CommandAuthBackend, SignalingServer};
 use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 /// Run the signaling server on `addr` using the given log sink.
 ///
-/// Uses `FileUserStore` at `RUSTYRTC_USERS_PATH` or `users.db` by default.
+/// Checks logins with `[Signaling] auth_command` when set; otherwise uses
+/// `FileUserStore` at `RUSTYRTC_USERS_PATH` or `users.db` by default.
 ///
 /// # Errors
 ///
@@ -17,6 +19,10 @@ pub fn run_signaling_server_with_log(
     log_sink: Arc<dyn LogSink>,
     config: Arc<Config>,
 ) -> io::Result<()> {
+    if let Some(backend) = CommandAuthBackend::from_config(&config) {
+        return SignalingServer::with_command_auth(addr.to_string(), log_sink, backend, config)
+            .run();
+    }
     let users_path = user_store_path(&config);
 
     let server = SignalingServer::with_file_store(addr.to_string(), log_sink, users_path, config)?;
@@ -24,12 +30,21 @@ pub fn run_signaling_server_with_log(
 }
 
 /// Convenience: run signaling server with a `NoopLogSink` (no logging),
-/// still using `FileUserStore` at the configured path.
+/// still using the configured auth command or `FileUserStore`.
 ///
 /// # Errors
 ///
 /// Returns an `io::Error` if the server cannot be started.
 pub fn run_signaling_server(addr: &str, config: Arc<Config>) -> io::Result<()> {
+    if let Some(backend) = CommandAuthBackend::from_config(&config) {
+        return SignalingServer::with_command_auth(
+            addr.to_string(),
+            Arc::new(NoopLogSink),
+            backend,
+            config,
+        )
+        .run();
+    }
     let users_path = user_store_path(&config);
 
     let server = SignalingServer::with_file_store_no_log(addr.to_string(), users_path, config)?;
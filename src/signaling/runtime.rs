@@ -1,26 +1,54 @@
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
 
 use crate::log::log_sink::LogSink;
+use crate::signaling::capture::SignalingRecorder;
 use crate::signaling::protocol::SignalingMsg;
 use crate::signaling::router::Router;
 use crate::signaling::server_event::ServerEvent;
 use crate::signaling::types::ClientId;
 use crate::{sink_debug, sink_info, sink_warn};
 
+/// How often the loop drops clients that lingered past their resume grace.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Central server loop: owns `Router` + maps `client_id` -> `Sender<Msg>`.
-pub fn run_server_loop(mut router: Router, log: Arc<dyn LogSink>, rx: Receiver<ServerEvent>) {
+///
+/// With a `recorder`, every message from and to a client and every
+/// disconnect is recorded.
+pub fn run_server_loop(
+    mut router: Router,
+    log: Arc<dyn LogSink>,
+    rx: Receiver<ServerEvent>,
+    recorder: Option<SignalingRecorder>,
+) {
     let mut clients: HashMap<ClientId, Sender<SignalingMsg>> = HashMap::new();
 
-    while let Ok(ev) = rx.recv() {
+    loop {
+        let ev = match rx.recv_timeout(EXPIRE_INTERVAL) {
+            Ok(ev) => ev,
+            Err(RecvTimeoutError::Timeout) => {
+                router.expire_lingering(Instant::now());
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        // Delivered along with the event's own messages
+        router.expire_lingering(Instant::now());
         match ev {
             ServerEvent::RegisterClient {
                 client_id,
                 to_client,
+                addr,
             } => {
                 sink_info!(log, "RegisterClient: client_id={}", client_id);
                 router.register_client(client_id);
+                if let Some(addr) = addr {
+                    router.server_mut().set_client_addr(client_id, addr);
+                }
                 clients.insert(client_id, to_client);
 
                 sink_info!(
@@ -33,32 +61,45 @@ pub fn run_server_loop(mut router: Router, log: Arc<dyn LogSink>, rx: Receiver<S
 
             ServerEvent::MsgFromClient { client_id, msg } => {
                 sink_debug!(log, "MsgFromClient: client_id={} msg={:?}", client_id, msg);
+                if let Some(recorder) = &recorder {
+                    recorder.received(client_id, &msg);
+                }
 
                 // Let Router+Server handle it
                 router.handle_from_client(client_id, msg);
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
+            }
 
-                // Drain all pending outgoing msgs and deliver them to reader
-                // threads
-                let outgoing_msgs = router.drain_all_outgoing();
-                for (c_target_id, out_msg) in outgoing_msgs {
-                    if let Some(tx) = clients.get(&c_target_id) {
-                        if tx.send(out_msg).is_err() {
-                            sink_warn!(
-                                log,
-                                "failed to deliver message to client {} (channel closed)",
-                                c_target_id
-                            );
-                        }
-                    } else {
-                        sink_warn!(log, "no client {} to deliver outgoing message", c_target_id);
-                    }
-                }
+            ServerEvent::CertificateLogin {
+                client_id,
+                username,
+            } => {
+                router.login_with_certificate(client_id, &username);
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
+            }
+
+            ServerEvent::LoginChecked {
+                client_id,
+                username,
+                result,
+            } => {
+                router.finish_login(client_id, &username, result);
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
+            }
+
+            ServerEvent::FromSfu { msg } => {
+                router.handle_from_sfu(msg);
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
             }
 
             ServerEvent::Disconnected { client_id } => {
                 sink_info!(log, "Disconnected: client_id={}", client_id);
+                if let Some(recorder) = &recorder {
+                    recorder.disconnected(client_id);
+                }
                 router.unregister_client(client_id);
                 clients.remove(&client_id);
+                deliver_outgoing(&mut router, &clients, &log, recorder.as_ref());
             }
         }
     }
@@ -81,6 +122,7 @@ const fn msg_name(msg: &SignalingMsg) -> &'static str {
         SignalingMsg::Register { .. } => "Register",
         SignalingMsg::RegisterOk { .. } => "RegisterOk",
         SignalingMsg::RegisterErr { .. } => "RegisterErr",
+        SignalingMsg::SetDevice { .. } => "SetDevice",
         SignalingMsg::ListPeers => "ListPeers",
         SignalingMsg::PeersOnline { .. } => "PeersOnline",
         SignalingMsg::CreateSession { .. } => "CreateSession",
@@ -88,17 +130,60 @@ const fn msg_name(msg: &SignalingMsg) -> &'static str {
         SignalingMsg::Join { .. } => "Join",
         SignalingMsg::JoinOk { .. } => "JoinOk",
         SignalingMsg::JoinErr { .. } => "JoinErr",
+        SignalingMsg::Leave { .. } => "Leave",
         SignalingMsg::PeerJoined { .. } => "PeerJoined",
         SignalingMsg::PeerLeft { .. } => "PeerLeft",
         SignalingMsg::Offer { .. } => "Offer",
         SignalingMsg::Answer { .. } => "Answer",
         SignalingMsg::Candidate { .. } => "Candidate",
+        SignalingMsg::Candidates { .. } => "Candidates",
         SignalingMsg::Ack { .. } => "Ack",
         SignalingMsg::Bye { .. } => "Bye",
+        SignalingMsg::Transfer { .. } => "Transfer",
+        SignalingMsg::CapabilityQuery { .. } => "CapabilityQuery",
+        SignalingMsg::CapabilityReply { .. } => "CapabilityReply",
+        SignalingMsg::CameraControl { .. } => "CameraControl",
+        SignalingMsg::CameraControlReply { .. } => "CameraControlReply",
+        SignalingMsg::OfferErr { .. } => "OfferErr",
+        SignalingMsg::AddContact { .. } => "AddContact",
+        SignalingMsg::RemoveContact { .. } => "RemoveContact",
+        SignalingMsg::Block { .. } => "Block",
+        SignalingMsg::Unblock { .. } => "Unblock",
+        SignalingMsg::ListContacts => "ListContacts",
+        SignalingMsg::Contacts { .. } => "Contacts",
         SignalingMsg::Ping { .. } => "Ping",
         SignalingMsg::Pong { .. } => "Pong",
+        SignalingMsg::ResumeFrom { .. } => "ResumeFrom",
+        SignalingMsg::Resumed { .. } => "Resumed",
+        SignalingMsg::Sequenced { .. } => "Sequenced",
     }
 }
+/// Drain all pending outgoing msgs and deliver them to the connection
+/// threads.
+fn deliver_outgoing(
+    router: &mut Router,
+    clients: &HashMap<ClientId, Sender<SignalingMsg>>,
+    log: &Arc<dyn LogSink>,
+    recorder: Option<&SignalingRecorder>,
+) {
+    for (c_target_id, out_msg) in router.drain_all_outgoing() {
+        if let Some(recorder) = recorder {
+            recorder.sent(c_target_id, &out_msg);
+        }
+        if let Some(tx) = clients.get(&c_target_id) {
+            if tx.send(out_msg).is_err() {
+                sink_warn!(
+                    log,
+                    "failed to deliver message to client {} (channel closed)",
+                    c_target_id
+                );
+            }
+        } else {
+            sink_warn!(log, "no client {} to deliver outgoing message", c_target_id);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used)]
@@ -120,7 +205,7 @@ mod tests {
         // Spawn the server loop in a background thread
         thread::spawn(move || {
             let router = Router::new();
-            run_server_loop(router, log, ev_rx);
+            run_server_loop(router, log, ev_rx, None);
         });
 
         // Channel for server -> client 1
@@ -132,6 +217,7 @@ mod tests {
             .send(ServerEvent::RegisterClient {
                 client_id,
                 to_client: to_client_tx,
+                addr: None,
             })
             .unwrap();
 
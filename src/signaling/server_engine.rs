@@ -1,11 +1,18 @@
 use rand::Rng;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::sync::mpsc::Sender;
 
 use crate::log::NoopLogSink;
+use crate::log::log_context::{ContextLogSink, LogContext};
 use crate::log::log_sink::LogSink;
-use crate::signaling::auth::{AllowAllAuthBackend, AuthBackend, AuthError};
-use crate::signaling::errors::{JoinErrorCode, LoginErrorCode, RegisterErrorCode};
+use crate::sfu::{SFU_USERNAME, SfuCommand};
+use crate::signaling::audit::{AuditAction, AuditLog, AuditRecord};
+use crate::signaling::auth::{AllowAllAuthBackend, AuthBackend, AuthError, AuthWorkers};
+use crate::signaling::contacts::{ContactBook, ContactChange};
+use crate::signaling::errors::{CallErrorCode, JoinErrorCode, LoginErrorCode, RegisterErrorCode};
+use crate::signaling::forward_policy::ForwardPolicy;
 use crate::signaling::presence::Presence;
 use crate::signaling::protocol::peer_status::PeerStatus;
 use crate::signaling::protocol::{SessionCode, SessionId, SignalingMsg, UserName};
@@ -13,6 +20,10 @@ use crate::signaling::sessions::{JoinError, Session, Sessions};
 use crate::signaling::types::{ClientId, OutgoingMsg};
 use crate::{sink_debug, sink_info, sink_trace, sink_warn};
 
+/// How many clients one user may be logged in on when
+/// `[Signaling] max_devices_per_user` is not set.
+pub const DEFAULT_MAX_DEVICES: usize = 4;
+
 pub struct ServerEngine {
     presence: Presence,
     sessions: Sessions,
@@ -20,6 +31,21 @@ pub struct ServerEngine {
     next_session_id: u64,
     log: Arc<dyn LogSink>,
     auth: Box<dyn AuthBackend>,
+    /// Set when the SFU runs: signaling addressed to [`SFU_USERNAME`] goes
+    /// there instead of to a client.
+    sfu: Option<Sender<SfuCommand>>,
+    audit: AuditLog,
+    /// Remote address of each connection, for the audit log.
+    client_addrs: HashMap<ClientId, SocketAddr>,
+    forward_policy: ForwardPolicy,
+    contacts: ContactBook,
+    max_devices: usize,
+    /// Clients whose connection dropped but that may still be resumed.
+    lingering: HashSet<ClientId>,
+    /// Set when logins are checked off the server loop.
+    auth_workers: Option<AuthWorkers>,
+    /// Clients whose login the workers are checking.
+    pending_logins: HashSet<ClientId>,
 }
 
 impl ServerEngine {
@@ -49,9 +75,99 @@ impl ServerEngine {
             next_session_id: 1,
             log,
             auth,
+            sfu: None,
+            audit: AuditLog::default(),
+            client_addrs: HashMap::new(),
+            forward_policy: ForwardPolicy::Open,
+            contacts: ContactBook::in_memory(),
+            max_devices: DEFAULT_MAX_DEVICES,
+            lingering: HashSet::new(),
+            auth_workers: None,
+            pending_logins: HashSet::new(),
         }
     }
 
+    /// Routes calls to [`SFU_USERNAME`] to the SFU and lists it in every
+    /// session.
+    pub fn set_sfu(&mut self, sfu: Sender<SfuCommand>) {
+        self.sfu = Some(sfu);
+    }
+
+    /// Writes logins, registrations and session membership changes to
+    /// `audit`.
+    pub fn set_audit(&mut self, audit: AuditLog) {
+        self.audit = audit;
+    }
+
+    /// Limits who may send call signaling to whom.
+    pub fn set_forward_policy(&mut self, policy: ForwardPolicy) {
+        self.forward_policy = policy;
+    }
+
+    /// Uses `contacts` for the contact list messages and the `contacts`
+    /// policy.
+    pub fn set_contacts(&mut self, contacts: ContactBook) {
+        self.contacts = contacts;
+    }
+
+    /// Lets each user log in on up to `max` clients at once (at least one).
+    pub fn set_max_devices(&mut self, max: usize) {
+        self.max_devices = max.max(1);
+    }
+
+    /// Checks password logins on `workers` instead of calling the auth
+    /// backend on the server loop; the outcome arrives through
+    /// [`Self::finish_login`].
+    pub fn set_auth_workers(&mut self, workers: AuthWorkers) {
+        self.auth_workers = Some(workers);
+    }
+
+    /// Marks `client` as dropped but awaiting a resume. It stays logged in,
+    /// but does not count against the user's devices, so the user can log in
+    /// again to resume it.
+    pub fn set_lingering(&mut self, client: ClientId, lingering: bool) {
+        if lingering {
+            self.lingering.insert(client);
+        } else {
+            self.lingering.remove(&client);
+        }
+    }
+
+    /// The user `client` is logged in as, if any.
+    #[must_use]
+    pub fn username_for(&self, client: ClientId) -> Option<&str> {
+        self.presence.username_for(client).map(String::as_str)
+    }
+
+    /// Remembers where `client` connected from; audit records carry it.
+    pub fn set_client_addr(&mut self, client: ClientId, addr: SocketAddr) {
+        self.client_addrs.insert(client, addr);
+    }
+
+    /// Writes one audit record about `client`.
+    fn audit(
+        &self,
+        action: AuditAction,
+        client: ClientId,
+        username: Option<&str>,
+        outcome: &str,
+        session: Option<&str>,
+    ) {
+        self.audit.record(&AuditRecord {
+            action,
+            client_id: client,
+            ip: self.client_addrs.get(&client).copied(),
+            username,
+            outcome,
+            session,
+        });
+    }
+
+    /// Logger whose messages carry `session_id` as a structured field.
+    fn session_log(&self, session_id: &str) -> ContextLogSink {
+        ContextLogSink::new(self.log.clone(), LogContext::session(session_id))
+    }
+
     /// Returns Some(username) if client is logged in, None otherwise.
     fn require_logged_in(&self, client_id: ClientId) -> Option<UserName> {
         self.presence.username_for(client_id).cloned()
@@ -115,6 +231,12 @@ impl ServerEngine {
                 self.handle_register(from_cid, &username, &password)
             }
 
+            SignalingMsg::SetDevice { label } => {
+                if self.require_logged_in(from_cid).is_some() {
+                    self.presence.set_device_label(from_cid, label);
+                }
+                Vec::new()
+            }
             SignalingMsg::ListPeers => self.handle_list_peers(from_cid),
 
             SignalingMsg::CreateSession { capacity } => {
@@ -123,17 +245,51 @@ impl ServerEngine {
 
             SignalingMsg::Join { session_code } => self.handle_join(from_cid, &session_code),
 
+            SignalingMsg::Leave { session_id } => self.handle_leave(from_cid, &session_id),
+
             SignalingMsg::Offer { .. }
             | SignalingMsg::Answer { .. }
             | SignalingMsg::Candidate { .. }
+            | SignalingMsg::Candidates { .. }
             | SignalingMsg::Ack { .. }
-            | SignalingMsg::Bye { .. } => self.forward_signaling(from_cid, msg),
+            | SignalingMsg::Bye { .. }
+            | SignalingMsg::CapabilityQuery { .. }
+            | SignalingMsg::CapabilityReply { .. }
+            | SignalingMsg::CameraControl { .. }
+            | SignalingMsg::CameraControlReply { .. } => self.forward_signaling(from_cid, msg),
+
+            SignalingMsg::Transfer { to, target, .. } => {
+                self.handle_transfer(from_cid, &to, &target)
+            }
+
+            SignalingMsg::AddContact { username } => {
+                self.handle_contacts(from_cid, Some((ContactChange::Add, &username)))
+            }
+            SignalingMsg::RemoveContact { username } => {
+                self.handle_contacts(from_cid, Some((ContactChange::Remove, &username)))
+            }
+            SignalingMsg::Block { username } => {
+                self.handle_contacts(from_cid, Some((ContactChange::Block, &username)))
+            }
+            SignalingMsg::Unblock { username } => {
+                self.handle_contacts(from_cid, Some((ContactChange::Unblock, &username)))
+            }
+            SignalingMsg::ListContacts => self.handle_contacts(from_cid, None),
 
             SignalingMsg::Ping { nonce } => vec![OutgoingMsg {
                 client_id_target: from_cid,
                 msg: SignalingMsg::Pong { nonce },
             }],
             SignalingMsg::Pong { .. } => Vec::new(),
+            SignalingMsg::ResumeFrom { .. } => {
+                // Streams are numbered by the Router
+                sink_debug!(
+                    self.log,
+                    "client {} ResumeFrom reached the engine",
+                    from_cid
+                );
+                Vec::new()
+            }
             SignalingMsg::LoginOk { .. }
             | SignalingMsg::LoginErr { .. }
             | SignalingMsg::RegisterOk { .. }
@@ -143,7 +299,11 @@ impl ServerEngine {
             | SignalingMsg::JoinOk { .. }
             | SignalingMsg::JoinErr { .. }
             | SignalingMsg::PeerJoined { .. }
-            | SignalingMsg::PeerLeft { .. } => {
+            | SignalingMsg::PeerLeft { .. }
+            | SignalingMsg::Contacts { .. }
+            | SignalingMsg::OfferErr { .. }
+            | SignalingMsg::Resumed { .. }
+            | SignalingMsg::Sequenced { .. } => {
                 sink_warn!(
                     self.log,
                     "ignoring server-only msg from client {}: {:?}",
@@ -164,7 +324,7 @@ impl ServerEngine {
                 // Filter: everyone except me, mapped to (Name, Status)
                 let peers = all_usernames
                     .iter()
-                    .filter(|u| *u != my_username)
+                    .filter(|u| *u != my_username && !self.hidden_from(my_username, u))
                     .map(|u| {
                         let status = if self.presence.is_busy(u) {
                             PeerStatus::Busy
@@ -188,12 +348,22 @@ impl ServerEngine {
     pub fn handle_disconnect(&mut self, client: ClientId) -> Vec<OutgoingMsg> {
         let mut out_msgs = Vec::new();
 
+        self.pending_logins.remove(&client);
         // Remove from presence
         let username_opt = self.presence.logout(client);
 
         // Remove from any sessions (and find who remains)
         let left_sessions = self.sessions.leave_all(client);
         let n_sessions = left_sessions.len();
+        self.audit(
+            AuditAction::Disconnect,
+            client,
+            username_opt.as_deref(),
+            "ok",
+            None,
+        );
+        self.client_addrs.remove(&client);
+        self.lingering.remove(&client);
 
         if let Some(username) = username_opt {
             sink_info!(
@@ -205,6 +375,7 @@ impl ServerEngine {
             );
 
             for (session_id, remaining_members) in left_sessions {
+                self.leave_sfu(&session_id, &username);
                 for member in remaining_members {
                     out_msgs.push(OutgoingMsg {
                         client_id_target: member,
@@ -244,9 +415,57 @@ impl ServerEngine {
             client,
             username
         );
-        let mut out = Vec::new();
+        if let Some(workers) = &self.auth_workers {
+            if self.pending_logins.contains(&client) {
+                sink_warn!(
+                    self.log,
+                    "login refused: client_id={} already has a login being checked",
+                    client
+                );
+            } else if workers.check(client, username.to_owned(), password.to_owned()) {
+                self.pending_logins.insert(client);
+                return Vec::new();
+            } else {
+                sink_warn!(
+                    self.log,
+                    "login refused: client_id={} too many logins being checked",
+                    client
+                );
+            }
+            return self.login_checked(client, username, Err(AuthError::Internal));
+        }
         // 1) Auth backend decides if username/password are valid.
-        if let Err(err) = self.auth.verify(username, password) {
+        let result = self.auth.verify(username, password);
+        self.login_checked(client, username, result)
+    }
+
+    /// The auth workers answered the login of `client`. Dropped if the
+    /// client disconnected meanwhile.
+    pub fn finish_login(
+        &mut self,
+        client: ClientId,
+        username: &str,
+        result: Result<(), AuthError>,
+    ) -> Vec<OutgoingMsg> {
+        if !self.pending_logins.remove(&client) {
+            sink_debug!(
+                self.log,
+                "login check for client_id={} outlived its connection",
+                client
+            );
+            return Vec::new();
+        }
+        self.login_checked(client, username, result)
+    }
+
+    fn login_checked(
+        &mut self,
+        client: ClientId,
+        username: &str,
+        result: Result<(), AuthError>,
+    ) -> Vec<OutgoingMsg> {
+        let mut out = Vec::new();
+        if let Err(err) = result {
             sink_warn!(
                 self.log,
                 "login failed: client_id={} username={} err={:?}",
@@ -255,30 +474,79 @@ impl ServerEngine {
                 err
             );
             // Map AuthError to our protocol-level login error code.
-            let code = match err {
-                AuthError::InvalidCredentials => LoginErrorCode::InvalidCredentials.as_u16(),
-                AuthError::Internal => LoginErrorCode::Internal.as_u16(),
+            let (code, outcome) = match err {
+                AuthError::InvalidCredentials => {
+                    (LoginErrorCode::InvalidCredentials, "invalid_credentials")
+                }
+                AuthError::Internal => (LoginErrorCode::Internal, "internal_error"),
             };
+            self.audit(AuditAction::Login, client, Some(username), outcome, None);
 
             out.push(OutgoingMsg {
                 client_id_target: client,
-                msg: SignalingMsg::LoginErr { code },
+                msg: login_err(code),
             });
             return out;
         }
+        self.complete_login(AuditAction::Login, client, username)
+    }
 
-        // 2) Reject if the user is already logged in on another client.
-        if let Some(existing_client) = self.presence.client_id_for(&username.to_string()) {
+    /// Logs `client` in as the user named by its TLS client certificate,
+    /// which the handshake already verified; no password is needed.
+    pub fn handle_certificate_login(
+        &mut self,
+        client: ClientId,
+        username: &str,
+    ) -> Vec<OutgoingMsg> {
+        sink_info!(
+            self.log,
+            "certificate login: client_id={} username={}",
+            client,
+            username
+        );
+        self.complete_login(AuditAction::CertificateLogin, client, username)
+    }
+
+    /// Logs in an authenticated `client` unless the user is already online.
+    fn complete_login(
+        &mut self,
+        action: AuditAction,
+        client: ClientId,
+        username: &str,
+    ) -> Vec<OutgoingMsg> {
+        let mut out = Vec::new();
+        if self.sfu.is_some() && username == SFU_USERNAME {
+            sink_warn!(
+                self.log,
+                "login rejected: client_id={} used the SFU's reserved name",
+                client
+            );
+            self.audit(action, client, Some(username), "reserved_name", None);
+            out.push(OutgoingMsg {
+                client_id_target: client,
+                msg: login_err(LoginErrorCode::NotAuthorized),
+            });
+            return out;
+        }
+        // 2) Reject if the user is already logged in on as many clients as
+        //    allowed.
+        let devices: Vec<ClientId> = self
+            .presence
+            .client_ids_for(username)
+            .into_iter()
+            .filter(|c| !self.lingering.contains(c))
+            .collect();
+        if devices.len() >= self.max_devices {
             sink_warn!(
                 self.log,
-                "login rejected: username={} already logged in as client_id={}",
+                "login rejected: username={} already logged in as client_ids={:?}",
                 username,
-                existing_client
+                devices
             );
-            let code = LoginErrorCode::AlreadyLoggedIn.as_u16();
+            self.audit(action, client, Some(username), "already_logged_in", None);
             out.push(OutgoingMsg {
                 client_id_target: client,
-                msg: SignalingMsg::LoginErr { code },
+                msg: login_err(LoginErrorCode::AlreadyLoggedIn),
             });
             return out;
         }
@@ -288,8 +556,12 @@ impl ServerEngine {
             client,
             username
         );
+        self.audit(action, client, Some(username), "ok", None);
         // 3) Success: record presence and send LoginOk.
-        let _ = self.presence.login(client, username.to_string());
+        let devices = self.presence.login(client, username.to_string());
+        if devices > 1 {
+            sink_info!(self.log, "{} is now on {} devices", username, devices);
+        }
         out.push(OutgoingMsg {
             client_id_target: client,
             msg: SignalingMsg::LoginOk {
@@ -319,6 +591,7 @@ impl ServerEngine {
                     username,
                     client_id
                 );
+                self.audit(AuditAction::Register, client_id, Some(username), "ok", None);
                 out.push(OutgoingMsg {
                     client_id_target: client_id,
                     msg: SignalingMsg::RegisterOk {
@@ -336,10 +609,25 @@ impl ServerEngine {
                     err,
                     code.as_u16()
                 );
+                let outcome = match code {
+                    RegisterErrorCode::UsernameTaken => "username_taken",
+                    RegisterErrorCode::InvalidUsername => "invalid_username",
+                    RegisterErrorCode::WeakPassword => "weak_password",
+                    RegisterErrorCode::Internal => "internal_error",
+                    RegisterErrorCode::Unsupported => "unsupported",
+                };
+                self.audit(
+                    AuditAction::Register,
+                    client_id,
+                    Some(username),
+                    outcome,
+                    None,
+                );
                 out.push(OutgoingMsg {
                     client_id_target: client_id,
                     msg: SignalingMsg::RegisterErr {
                         code: code.as_u16(),
+                        reason: Some(code.reason().to_owned()),
                     },
                 });
             }
@@ -378,6 +666,11 @@ impl ServerEngine {
             .online_usernames()
             .into_iter()
             .filter(|peer| Some(peer) != requester.as_ref()) // Exclude the requester
+            .filter(|peer| {
+                !requester
+                    .as_ref()
+                    .is_some_and(|me| self.hidden_from(me, peer))
+            })
             .map(|peer| {
                 let status = if self.presence.is_busy(&peer) {
                     PeerStatus::Busy
@@ -400,14 +693,19 @@ impl ServerEngine {
 
         // Require login first
         let Some(username) = self.require_logged_in(client_id) else {
-            let msg = SignalingMsg::JoinErr {
-                code: JoinErrorCode::NotLoggedIn.as_u16(),
-            };
+            let msg = join_err(JoinErrorCode::NotLoggedIn);
             sink_warn!(
                 self.log,
                 "client {} attempted CreateSession without login",
                 client_id
             );
+            self.audit(
+                AuditAction::CreateSession,
+                client_id,
+                None,
+                "not_logged_in",
+                None,
+            );
             out_msg.push(OutgoingMsg {
                 client_id_target: client_id,
                 msg,
@@ -429,9 +727,16 @@ impl ServerEngine {
         };
 
         self.sessions.insert(session);
+        self.audit(
+            AuditAction::CreateSession,
+            client_id,
+            Some(&username),
+            "ok",
+            Some(&id),
+        );
 
         sink_info!(
-            self.log,
+            self.session_log(&id),
             "client {} ({}) created session id={} code={} capacity={}",
             client_id,
             username,
@@ -441,13 +746,14 @@ impl ServerEngine {
         );
 
         let msg = SignalingMsg::Created {
-            session_id: id,
+            session_id: id.clone(),
             session_code: code,
         };
         out_msg.push(OutgoingMsg {
             client_id_target: client_id,
             msg,
         });
+        out_msg.extend(self.sfu_joined(client_id, &id));
         out_msg
     }
 
@@ -456,14 +762,13 @@ impl ServerEngine {
 
         // require login
         let Some(username) = self.require_logged_in(client_id) else {
-            let msg = SignalingMsg::JoinErr {
-                code: JoinErrorCode::NotLoggedIn.as_u16(),
-            };
+            let msg = join_err(JoinErrorCode::NotLoggedIn);
             sink_warn!(
                 self.log,
                 "client {} attempted Join without login",
                 client_id
             );
+            self.audit(AuditAction::Join, client_id, None, "not_logged_in", None);
             out_msgs.push(OutgoingMsg {
                 client_id_target: client_id,
                 msg,
@@ -477,13 +782,20 @@ impl ServerEngine {
         {
             Ok(session_id) => {
                 sink_info!(
-                    self.log,
+                    self.session_log(&session_id),
                     "Join success: client_id={} ({}) joined session_code={} (session_id={})",
                     client_id,
                     username,
                     session_code,
                     session_id
                 );
+                self.audit(
+                    AuditAction::Join,
+                    client_id,
+                    Some(&username),
+                    "ok",
+                    Some(&session_id),
+                );
                 // 1) JoinOk to the joiner
                 let join_ok = SignalingMsg::JoinOk {
                     session_id: session_id.clone(),
@@ -492,8 +804,10 @@ impl ServerEngine {
                     client_id_target: client_id,
                     msg: join_ok,
                 });
+                out_msgs.extend(self.sfu_joined(client_id, &session_id));
 
-                // 2) PeerJoined to existing members
+                // 2) PeerJoined to existing members, and one per existing
+                //    member to the joiner so it knows who is in the room
                 if let Some(sess) = self.sessions.get(&session_id) {
                     for &member in &sess.members {
                         if member == client_id {
@@ -506,6 +820,15 @@ impl ServerEngine {
                                 username: username.clone(),
                             },
                         });
+                        if let Some(member_name) = self.presence.username_for(member) {
+                            out_msgs.push(OutgoingMsg {
+                                client_id_target: client_id,
+                                msg: SignalingMsg::PeerJoined {
+                                    session_id: session_id.clone(),
+                                    username: member_name.clone(),
+                                },
+                            });
+                        }
                     }
                 }
             }
@@ -517,9 +840,14 @@ impl ServerEngine {
                     username,
                     session_code
                 );
-                let msg = SignalingMsg::JoinErr {
-                    code: JoinErrorCode::NotFound.as_u16(),
-                };
+                self.audit(
+                    AuditAction::Join,
+                    client_id,
+                    Some(&username),
+                    "not_found",
+                    None,
+                );
+                let msg = join_err(JoinErrorCode::NotFound);
                 out_msgs.push(OutgoingMsg {
                     client_id_target: client_id,
                     msg,
@@ -533,9 +861,8 @@ impl ServerEngine {
                     username,
                     session_code
                 );
-                let msg = SignalingMsg::JoinErr {
-                    code: JoinErrorCode::Full.as_u16(),
-                };
+                self.audit(AuditAction::Join, client_id, Some(&username), "full", None);
+                let msg = join_err(JoinErrorCode::Full);
                 out_msgs.push(OutgoingMsg {
                     client_id_target: client_id,
                     msg,
@@ -546,6 +873,130 @@ impl ServerEngine {
         out_msgs
     }
 
+    /// Removes the client from one session and tells the remaining members.
+    fn handle_leave(&mut self, client_id: ClientId, session_id: &SessionId) -> Vec<OutgoingMsg> {
+        let Some(username) = self.require_logged_in(client_id) else {
+            sink_warn!(
+                self.log,
+                "client {} attempted Leave without login",
+                client_id
+            );
+            self.audit(
+                AuditAction::Leave,
+                client_id,
+                None,
+                "not_logged_in",
+                Some(session_id),
+            );
+            return Vec::new();
+        };
+        let Some(remaining) = self.sessions.leave(session_id, client_id) else {
+            sink_debug!(
+                self.log,
+                "client {} ({}) left session {} it was not in",
+                client_id,
+                username,
+                session_id
+            );
+            self.audit(
+                AuditAction::Leave,
+                client_id,
+                Some(&username),
+                "not_a_member",
+                Some(session_id),
+            );
+            return Vec::new();
+        };
+        sink_info!(
+            self.session_log(session_id),
+            "client {} ({}) left session {}",
+            client_id,
+            username,
+            session_id
+        );
+        self.audit(
+            AuditAction::Leave,
+            client_id,
+            Some(&username),
+            "ok",
+            Some(session_id),
+        );
+        self.leave_sfu(session_id, &username);
+        remaining
+            .into_iter()
+            .map(|member| OutgoingMsg {
+                client_id_target: member,
+                msg: SignalingMsg::PeerLeft {
+                    session_id: session_id.clone(),
+                    username: username.clone(),
+                },
+            })
+            .collect()
+    }
+
+    /// Hands the call between the sender and `to` over to `target`: both
+    /// are told, and `target`'s client then calls `to`. The sender's call
+    /// ends without a Bye, so the sender and `to` are available again.
+    fn handle_transfer(&mut self, from: ClientId, to: &str, target: &str) -> Vec<OutgoingMsg> {
+        let Some(from_username) = self.require_logged_in(from) else {
+            sink_warn!(
+                self.log,
+                "unauthenticated client {} attempted a transfer",
+                from
+            );
+            return Vec::new();
+        };
+        let to_client = self.presence.client_id_for(&to.to_string());
+        let target_client = self.presence.client_id_for(&target.to_string());
+        let (Some(to_client), Some(target_client)) = (to_client, target_client) else {
+            sink_warn!(
+                self.log,
+                "transfer from {} of {} to {} dropped: a party is offline",
+                from_username,
+                to,
+                target
+            );
+            return Vec::new();
+        };
+        if target == from_username || target == to || self.presence.is_busy(target) {
+            sink_warn!(
+                self.log,
+                "transfer from {} of {} to {} dropped: target unavailable",
+                from_username,
+                to,
+                target
+            );
+            return Vec::new();
+        }
+        sink_info!(
+            self.log,
+            "{} transfers the call with {} to {}",
+            from_username,
+            to,
+            target
+        );
+        self.presence.set_busy(&from_username, false);
+        self.presence.set_busy(to, false);
+
+        let transfer = SignalingMsg::Transfer {
+            from: from_username,
+            to: to.to_string(),
+            target: target.to_string(),
+        };
+        let mut out = vec![
+            OutgoingMsg {
+                client_id_target: to_client,
+                msg: transfer.clone(),
+            },
+            OutgoingMsg {
+                client_id_target: target_client,
+                msg: transfer,
+            },
+        ];
+        out.extend(self.broadcast_peer_list_update());
+        out
+    }
+
     /// Forward Offer/Answer/Candidate, enforcing:
     /// - sender must be logged in
     /// - target must be logged in
@@ -561,35 +1012,87 @@ impl ServerEngine {
             );
             return Vec::new();
         };
+        if self.sfu.is_some() && signal_target(&msg) == Some(SFU_USERNAME) {
+            return self.signal_sfu(from, from_username, msg);
+        }
+        // A Bye always goes through, so no call outlives a block or a
+        // policy change.
+        if !matches!(msg, SignalingMsg::Bye { .. })
+            && let Some(to) = forward_target(&msg)
+        {
+            let refused = if self.contacts.is_blocked(to, &from_username) {
+                Some(CallErrorCode::Blocked)
+            } else if !self.may_forward(from, &from_username, to) {
+                Some(CallErrorCode::NotAllowed)
+            } else {
+                None
+            };
+            if let Some(code) = refused {
+                sink_warn!(
+                    self.log,
+                    "dropping {} from client {} ({}) to {}: {:?} ({} policy)",
+                    msg_kind(&msg),
+                    from,
+                    from_username,
+                    to,
+                    code,
+                    self.forward_policy
+                );
+                // Only the offer is answered; the rest of a refused call
+                // just goes nowhere.
+                if !matches!(msg, SignalingMsg::Offer { .. }) {
+                    return Vec::new();
+                }
+                return vec![OutgoingMsg {
+                    client_id_target: from,
+                    msg: SignalingMsg::OfferErr {
+                        to: to.to_owned(),
+                        code: code.as_u16(),
+                        reason: Some(code.reason().to_owned()),
+                    },
+                }];
+            }
+        }
         let mut status_changed = false;
 
         let forward_msgs = match msg {
             SignalingMsg::Offer {
                 txn_id, to, sdp, ..
-            } => self.forward(from, &from_username, txn_id, &to, |username, txn_id, to| {
-                SignalingMsg::Offer {
-                    txn_id,
-                    from: username,
-                    to: to.to_string(),
-                    sdp,
-                }
-            }),
+            } => {
+                // The answer goes back to the device that offered; the offer
+                // rings every device of `to` that is not in a call.
+                self.presence.set_call_device(&from_username, from);
+                self.forward(from, &from_username, txn_id, &to, |username, txn_id, to| {
+                    SignalingMsg::Offer {
+                        txn_id,
+                        from: username,
+                        to: to.to_string(),
+                        sdp,
+                    }
+                })
+            }
             SignalingMsg::Answer {
                 txn_id, to, sdp, ..
             } => {
+                let mut out = self.answered_on(from, &from_username, &to);
                 // Mark both as busy
                 self.presence.set_busy(&from_username, true);
                 self.presence.set_busy(&to, true);
                 status_changed = true;
 
-                self.forward(from, &from_username, txn_id, &to, |username, txn_id, to| {
-                    SignalingMsg::Answer {
+                out.extend(self.forward(
+                    from,
+                    &from_username,
+                    txn_id,
+                    &to,
+                    |username, txn_id, to| SignalingMsg::Answer {
                         txn_id,
                         from: username,
                         to: to.to_string(),
                         sdp,
-                    }
-                })
+                    },
+                ));
+                out
             }
             SignalingMsg::Candidate {
                 to,
@@ -606,6 +1109,19 @@ impl ServerEngine {
                     cand,
                 }
             }),
+            SignalingMsg::Candidates {
+                to,
+                items,
+                end_of_candidates,
+                ..
+            } => self.forward(from, &from_username, 0, &to, |username, _txn_id, to| {
+                SignalingMsg::Candidates {
+                    from: username,
+                    to: to.to_string(),
+                    items,
+                    end_of_candidates,
+                }
+            }),
             SignalingMsg::Ack { txn_id, to, .. } => {
                 self.forward(from, &from_username, txn_id, &to, |username, txn_id, to| {
                     SignalingMsg::Ack {
@@ -629,6 +1145,47 @@ impl ServerEngine {
                     }
                 })
             }
+            SignalingMsg::CapabilityQuery { to, .. } => {
+                self.forward(from, &from_username, 0, &to, |username, _, to| {
+                    SignalingMsg::CapabilityQuery {
+                        from: username,
+                        to: to.to_string(),
+                    }
+                })
+            }
+            SignalingMsg::CapabilityReply { to, caps, .. } => {
+                self.forward(from, &from_username, 0, &to, |username, _, to| {
+                    SignalingMsg::CapabilityReply {
+                        from: username,
+                        to: to.to_string(),
+                        caps,
+                    }
+                })
+            }
+            SignalingMsg::CameraControl { to, request, .. } => {
+                self.forward(from, &from_username, 0, &to, |username, _, to| {
+                    SignalingMsg::CameraControl {
+                        from: username,
+                        to: to.to_string(),
+                        request,
+                    }
+                })
+            }
+            SignalingMsg::CameraControlReply {
+                to,
+                request,
+                accepted,
+                reason,
+                ..
+            } => self.forward(from, &from_username, 0, &to, |username, _, to| {
+                SignalingMsg::CameraControlReply {
+                    from: username,
+                    to: to.to_string(),
+                    request,
+                    accepted,
+                    reason,
+                }
+            }),
             other => {
                 sink_warn!(
                     self.log,
@@ -639,14 +1196,152 @@ impl ServerEngine {
             }
         };
 
-        if status_changed {
-            forward_msgs
-                .into_iter()
-                .chain(self.broadcast_peer_list_update())
-                .collect()
-        } else {
-            forward_msgs
+        if status_changed {
+            forward_msgs
+                .into_iter()
+                .chain(self.broadcast_peer_list_update())
+                .collect()
+        } else {
+            forward_msgs
+        }
+    }
+
+    /// Hands a message a session member addressed to the SFU over to it.
+    fn signal_sfu(
+        &mut self,
+        from: ClientId,
+        from_username: UserName,
+        msg: SignalingMsg,
+    ) -> Vec<OutgoingMsg> {
+        let Some(room) = self.sessions.session_of(from) else {
+            sink_warn!(
+                self.log,
+                "client {} ({}) called the SFU outside of a session",
+                from,
+                from_username
+            );
+            return Vec::new();
+        };
+        let bye = matches!(msg, SignalingMsg::Bye { .. });
+        if let Some(sfu) = &self.sfu
+            && sfu
+                .send(SfuCommand::Signal {
+                    room,
+                    from: from_username.clone(),
+                    msg,
+                })
+                .is_err()
+        {
+            sink_warn!(
+                self.log,
+                "SFU is gone; dropping message from {}",
+                from_username
+            );
+        }
+        if bye {
+            self.presence.set_busy(&from_username, false);
+            return self.broadcast_peer_list_update();
+        }
+        Vec::new()
+    }
+
+    /// Routes a message from the SFU to the member named in its `to`.
+    pub fn deliver_from_sfu(&mut self, msg: SignalingMsg) -> Vec<OutgoingMsg> {
+        let Some(to) = signal_target(&msg).map(str::to_owned) else {
+            sink_warn!(self.log, "SFU sent an unexpected message {:?}", msg);
+            return Vec::new();
+        };
+        let Some(target) = self.presence.client_id_for(&to) else {
+            sink_debug!(self.log, "SFU message for offline user {}", to);
+            return Vec::new();
+        };
+        let answered = matches!(msg, SignalingMsg::Answer { .. });
+        let mut out = vec![OutgoingMsg {
+            client_id_target: target,
+            msg,
+        }];
+        if answered {
+            self.presence.set_busy(&to, true);
+            out.extend(self.broadcast_peer_list_update());
+        }
+        out
+    }
+
+    /// Lists the SFU as a member of `session_id` for `client`.
+    fn sfu_joined(&self, client: ClientId, session_id: &SessionId) -> Option<OutgoingMsg> {
+        self.sfu.as_ref()?;
+        Some(OutgoingMsg {
+            client_id_target: client,
+            msg: SignalingMsg::PeerJoined {
+                session_id: session_id.clone(),
+                username: SFU_USERNAME.to_string(),
+            },
+        })
+    }
+
+    /// Ends `username`'s media session with the SFU in `session_id`.
+    fn leave_sfu(&self, session_id: &SessionId, username: &UserName) {
+        if let Some(sfu) = &self.sfu {
+            let _ = sfu.send(SfuCommand::Leave {
+                room: session_id.clone(),
+                username: username.clone(),
+            });
+        }
+    }
+
+    /// Whether the forwarding policy lets `from` send call signaling to
+    /// `to_username`.
+    fn may_forward(&self, from: ClientId, from_username: &str, to_username: &str) -> bool {
+        match self.forward_policy {
+            ForwardPolicy::Open => true,
+            ForwardPolicy::SameSession => self
+                .presence
+                .client_ids_for(to_username)
+                .into_iter()
+                .any(|to| self.sessions.share_session(from, to)),
+            ForwardPolicy::Contacts => self.contacts.are_mutual(from_username, to_username),
+        }
+    }
+
+    /// Whether `viewer` and `peer` are hidden from each other because one
+    /// blocked the other.
+    fn hidden_from(&self, viewer: &str, peer: &str) -> bool {
+        self.contacts.is_blocked(viewer, peer) || self.contacts.is_blocked(peer, viewer)
+    }
+
+    /// Applies `change` to the lists of `client`'s user, if asked to, and
+    /// answers with the lists. A block or unblock also refreshes everyone's
+    /// peer list.
+    fn handle_contacts(
+        &mut self,
+        client: ClientId,
+        change: Option<(ContactChange, &str)>,
+    ) -> Vec<OutgoingMsg> {
+        let Some(owner) = self.require_logged_in(client) else {
+            sink_warn!(
+                self.log,
+                "unauthenticated client {} asked for contacts",
+                client
+            );
+            return Vec::new();
+        };
+        if let Some((change, user)) = change
+            && user != owner
+            && let Err(e) = self.contacts.apply(&owner, change, user)
+        {
+            sink_warn!(self.log, "cannot save contacts of {}: {}", owner, e);
+        }
+        let mut out = vec![OutgoingMsg {
+            client_id_target: client,
+            msg: SignalingMsg::Contacts {
+                contacts: self.contacts.list(&owner),
+                blocked: self.contacts.blocked(&owner),
+            },
+        }];
+        if let Some((ContactChange::Block | ContactChange::Unblock, _)) = change {
+            out.extend(self.broadcast_peer_list_update());
         }
+        out
     }
 
     #[allow(clippy::needless_pass_by_ref_mut)]
@@ -661,8 +1356,9 @@ impl ServerEngine {
     where
         F: FnOnce(UserName, u64, &str) -> SignalingMsg,
     {
-        // 2) resolve target client by username
-        let Some(target_client) = self.presence.client_id_for(&to_username.to_string()) else {
+        // 2) resolve target clients by username
+        let targets = self.presence.delivery_targets(to_username);
+        if targets.is_empty() {
             sink_warn!(
                 self.log,
                 "client {} ({}) tried to send signaling to offline user {}",
@@ -671,31 +1367,54 @@ impl ServerEngine {
                 to_username
             );
             return Vec::new();
-        };
+        }
 
         let msg = builder(from_username.to_string(), txn_id, to_username);
 
-        let kind = match &msg {
-            SignalingMsg::Offer { .. } => "Offer",
-            SignalingMsg::Answer { .. } => "Answer",
-            SignalingMsg::Candidate { .. } => "Candidate",
-            _ => "Signaling",
-        };
+        let kind = msg_kind(&msg);
 
         sink_debug!(
             self.log,
-            "forwarding {} from client {} ({}) to client {} ({})",
+            "forwarding {} from client {} ({}) to clients {:?} ({})",
             kind,
             from,
             from_username,
-            target_client,
+            targets,
             to_username
         );
 
-        vec![OutgoingMsg {
-            client_id_target: target_client,
-            msg,
-        }]
+        targets
+            .into_iter()
+            .map(|target| OutgoingMsg {
+                client_id_target: target,
+                msg: msg.clone(),
+            })
+            .collect()
+    }
+
+    /// `username` answered `caller` on the device `client`: pins the call
+    /// to it and stops the user's other devices ringing, with a Bye that
+    /// says where the call was picked up.
+    fn answered_on(&mut self, client: ClientId, username: &str, caller: &str) -> Vec<OutgoingMsg> {
+        if self.presence.call_device(username) == Some(client) {
+            // A renegotiation within the call
+            return Vec::new();
+        }
+        self.presence.set_call_device(username, client);
+        let reason = format!("answered on {}", self.presence.device_label(client));
+        self.presence
+            .client_ids_for(username)
+            .into_iter()
+            .filter(|&other| other != client)
+            .map(|other| OutgoingMsg {
+                client_id_target: other,
+                msg: SignalingMsg::Bye {
+                    from: caller.to_string(),
+                    to: username.to_string(),
+                    reason: Some(reason.clone()),
+                },
+            })
+            .collect()
     }
 
     #[allow(dead_code, clippy::needless_pass_by_ref_mut)]
@@ -758,6 +1477,57 @@ impl Default for ServerEngine {
     }
 }
 
+/// A `LoginErr` with `code` and its reason.
+fn login_err(code: LoginErrorCode) -> SignalingMsg {
+    SignalingMsg::LoginErr {
+        code: code.as_u16(),
+        reason: Some(code.reason().to_owned()),
+    }
+}
+
+/// A `JoinErr` with `code` and its reason.
+fn join_err(code: JoinErrorCode) -> SignalingMsg {
+    SignalingMsg::JoinErr {
+        code: code.as_u16(),
+        reason: Some(code.reason().to_owned()),
+    }
+}
+
+/// The user a peer-to-peer signaling message is addressed to.
+fn signal_target(msg: &SignalingMsg) -> Option<&str> {
+    match msg {
+        SignalingMsg::Offer { to, .. }
+        | SignalingMsg::Answer { to, .. }
+        | SignalingMsg::Candidate { to, .. }
+        | SignalingMsg::Candidates { to, .. }
+        | SignalingMsg::Ack { to, .. }
+        | SignalingMsg::Bye { to, .. } => Some(to),
+        _ => None,
+    }
+}
+
+/// The user any forwarded message is addressed to.
+fn forward_target(msg: &SignalingMsg) -> Option<&str> {
+    match msg {
+        SignalingMsg::CapabilityQuery { to, .. }
+        | SignalingMsg::CapabilityReply { to, .. }
+        | SignalingMsg::CameraControl { to, .. }
+        | SignalingMsg::CameraControlReply { to, .. } => Some(to),
+        _ => signal_target(msg),
+    }
+}
+
+/// Name of a forwarded message, for the logs.
+const fn msg_kind(msg: &SignalingMsg) -> &'static str {
+    match msg {
+        SignalingMsg::Offer { .. } => "Offer",
+        SignalingMsg::Answer { .. } => "Answer",
+        SignalingMsg::Candidate { .. } => "Candidate",
+        SignalingMsg::Candidates { .. } => "Candidates",
+        _ => "Signaling",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::unwrap_used, clippy::expect_used)]
@@ -796,6 +1566,60 @@ mod tests {
         assert!(has_login_ok, "Expected LoginOk for the user");
     }
 
+    #[test]
+    fn audit_log_records_logins_sessions_and_disconnects() {
+        use std::io::{self, Write};
+        use std::sync::{Mutex, PoisonError};
+
+        #[derive(Clone, Default)]
+        struct Shared(Arc<Mutex<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buf = Shared::default();
+        let mut server = new_server_with_in_memory_auth();
+        server.set_audit(AuditLog::to_writer(buf.clone()));
+        server.set_client_addr(1, "10.0.0.7:51234".parse().unwrap());
+        server.handle(
+            1,
+            SignalingMsg::Login {
+                username: "alice".into(),
+                password: "wrong".into(),
+            },
+        );
+        server.handle(
+            1,
+            SignalingMsg::Login {
+                username: "alice".into(),
+                password: "secret".into(),
+            },
+        );
+        server.handle(1, SignalingMsg::CreateSession { capacity: 2 });
+        server.handle_disconnect(1);
+
+        let text = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].contains("\"event\":\"login\""));
+        assert!(lines[0].contains("\"ip\":\"10.0.0.7:51234\""));
+        assert!(lines[0].contains("\"outcome\":\"invalid_credentials\""));
+        assert!(lines[1].contains("\"outcome\":\"ok\""));
+        assert!(lines[2].contains("\"event\":\"create_session\""));
+        assert!(lines[2].contains("\"session\":\"sess-1\""));
+        assert!(lines[3].contains("\"event\":\"disconnect\""));
+        assert!(lines[3].contains("\"username\":\"alice\""));
+    }
+
     #[test]
     fn login_and_create_session_roundtrip() {
         let mut server = ServerEngine::new();
@@ -922,6 +1746,62 @@ mod tests {
         }
     }
 
+    #[test]
+    fn joiner_learns_existing_members() {
+        let mut server = new_server();
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "bob");
+        login(&mut server, 3, "carol");
+
+        let created = server.handle(1, SignalingMsg::CreateSession { capacity: 3 });
+        let Some(SignalingMsg::Created { session_code, .. }) = created.first().map(|m| &m.msg)
+        else {
+            panic!("expected Created, got {created:?}");
+        };
+        let session_code = session_code.clone();
+        server.handle(
+            2,
+            SignalingMsg::Join {
+                session_code: session_code.clone(),
+            },
+        );
+
+        let joined = server.handle(3, SignalingMsg::Join { session_code });
+        let mut members: Vec<&str> = joined
+            .iter()
+            .filter(|m| m.client_id_target == 3)
+            .filter_map(|m| match &m.msg {
+                SignalingMsg::PeerJoined { username, .. } => Some(username.as_str()),
+                _ => None,
+            })
+            .collect();
+        members.sort_unstable();
+        assert_eq!(members, ["alice", "bob"]);
+
+        let Some(SignalingMsg::JoinOk { session_id }) = joined.first().map(|m| &m.msg) else {
+            panic!("expected JoinOk, got {joined:?}");
+        };
+        let session_id = session_id.clone();
+        let left = server.handle(
+            3,
+            SignalingMsg::Leave {
+                session_id: session_id.clone(),
+            },
+        );
+        let mut told: Vec<ClientId> = left
+            .iter()
+            .filter(|m| matches!(&m.msg, SignalingMsg::PeerLeft { username, .. } if username == "carol"))
+            .map(|m| m.client_id_target)
+            .collect();
+        told.sort_unstable();
+        assert_eq!(told, [1, 2]);
+        assert!(
+            server
+                .handle(3, SignalingMsg::Leave { session_id })
+                .is_empty()
+        );
+    }
+
     #[test]
     fn offer_with_shared_session_is_forwarded() {
         let mut server = new_server();
@@ -1144,6 +2024,53 @@ mod tests {
         }
     }
 
+    // ---- Candidates invariants --------------------------------------------
+
+    #[test]
+    fn candidates_batch_is_forwarded_opaquely() {
+        use crate::signaling::protocol::candidate_item::CandidateItem;
+
+        let mut server = new_server();
+
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "bob");
+
+        let items = vec![
+            CandidateItem {
+                mid: "0".into(),
+                mline_index: 0,
+                cand: b"candidate:1 1 udp 2122252543 192.0.2.1 54400 typ host".to_vec(),
+            },
+            CandidateItem {
+                mid: "0".into(),
+                mline_index: 0,
+                cand: b"candidate:2 1 udp 1686052607 203.0.113.7 61000 typ srflx".to_vec(),
+            },
+        ];
+
+        let res = server.handle(
+            1,
+            SignalingMsg::Candidates {
+                from: "mallory".into(),
+                to: "bob".into(),
+                items: items.clone(),
+                end_of_candidates: true,
+            },
+        );
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].client_id_target, 2);
+        assert_eq!(
+            res[0].msg,
+            SignalingMsg::Candidates {
+                from: "alice".into(),
+                to: "bob".into(),
+                items,
+                end_of_candidates: true,
+            }
+        );
+    }
+
     // ---- Bye invariants ---------------------------------------------------
 
     #[test]
@@ -1197,6 +2124,194 @@ mod tests {
         }
     }
 
+    #[test]
+    fn transfer_is_sent_to_the_transferee_and_the_target() {
+        let mut server = new_server();
+        login(&mut server, 1, "desk");
+        login(&mut server, 2, "caller");
+        login(&mut server, 3, "sales");
+        server.presence.set_busy("desk", true);
+        server.presence.set_busy("caller", true);
+
+        let transfer = SignalingMsg::Transfer {
+            from: "desk".into(),
+            to: "caller".into(),
+            target: "sales".into(),
+        };
+        let mut targets: Vec<_> = server
+            .handle(1, transfer.clone())
+            .into_iter()
+            .filter(|m| m.msg == transfer)
+            .map(|m| m.client_id_target)
+            .collect();
+        targets.sort_unstable();
+        assert_eq!(targets, [2, 3]);
+        assert!(!server.presence.is_busy("desk"));
+        assert!(!server.presence.is_busy("caller"));
+
+        // A busy target is refused
+        server.presence.set_busy("sales", true);
+        assert!(server.handle(1, transfer).is_empty());
+    }
+
+    fn offer_reaches(server: &mut ServerEngine, from: ClientId, from_user: &str, to: &str) -> bool {
+        let offer = SignalingMsg::Offer {
+            txn_id: 1,
+            from: from_user.into(),
+            to: to.into(),
+            sdp: b"v=0".to_vec(),
+        };
+        server
+            .handle(from, offer)
+            .iter()
+            .any(|m| matches!(m.msg, SignalingMsg::Offer { .. }))
+    }
+
+    #[test]
+    fn answering_on_one_device_stops_the_others_ringing() {
+        let mut server = new_server();
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "bob");
+        login(&mut server, 3, "bob");
+        server.handle(
+            3,
+            SignalingMsg::SetDevice {
+                label: "phone".into(),
+            },
+        );
+
+        let out = server.handle(
+            1,
+            SignalingMsg::Offer {
+                txn_id: 1,
+                from: "alice".into(),
+                to: "bob".into(),
+                sdp: b"v=0".to_vec(),
+            },
+        );
+        let mut rung: Vec<_> = out
+            .iter()
+            .filter(|m| matches!(m.msg, SignalingMsg::Offer { .. }))
+            .map(|m| m.client_id_target)
+            .collect();
+        rung.sort_unstable();
+        assert_eq!(rung, [2, 3]);
+
+        let out = server.handle(
+            3,
+            SignalingMsg::Answer {
+                txn_id: 1,
+                from: "bob".into(),
+                to: "alice".into(),
+                sdp: b"v=0".to_vec(),
+            },
+        );
+        assert!(out.iter().any(|m| m.client_id_target == 2
+            && matches!(&m.msg, SignalingMsg::Bye { reason: Some(r), .. } if r == "answered on phone")));
+        assert!(
+            out.iter()
+                .any(|m| m.client_id_target == 1 && matches!(m.msg, SignalingMsg::Answer { .. }))
+        );
+
+        let out = server.handle(
+            1,
+            SignalingMsg::Candidate {
+                from: "alice".into(),
+                to: "bob".into(),
+                mid: "0".into(),
+                mline_index: 0,
+                cand: b"candidate".to_vec(),
+            },
+        );
+        let targets: Vec<_> = out.iter().map(|m| m.client_id_target).collect();
+        assert_eq!(targets, [3]);
+    }
+
+    #[test]
+    fn same_session_policy_needs_a_shared_room() {
+        let mut server = new_server();
+        server.set_forward_policy(ForwardPolicy::SameSession);
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "bob");
+        assert!(!offer_reaches(&mut server, 1, "alice", "bob"));
+
+        let out = server.handle(1, SignalingMsg::CreateSession { capacity: 2 });
+        let code = out
+            .iter()
+            .find_map(|m| match &m.msg {
+                SignalingMsg::Created { session_code, .. } => Some(session_code.clone()),
+                _ => None,
+            })
+            .unwrap();
+        server.handle(2, SignalingMsg::Join { session_code: code });
+        assert!(offer_reaches(&mut server, 1, "alice", "bob"));
+    }
+
+    #[test]
+    fn contacts_policy_needs_mutual_contacts() {
+        let mut server = new_server();
+        server.set_forward_policy(ForwardPolicy::Contacts);
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "bob");
+
+        let out = server.handle(
+            1,
+            SignalingMsg::AddContact {
+                username: "bob".into(),
+            },
+        );
+        assert_eq!(
+            out[0].msg,
+            SignalingMsg::Contacts {
+                contacts: vec!["bob".into()],
+                blocked: Vec::new(),
+            }
+        );
+        assert!(!offer_reaches(&mut server, 1, "alice", "bob"));
+
+        server.handle(
+            2,
+            SignalingMsg::AddContact {
+                username: "alice".into(),
+            },
+        );
+        assert!(offer_reaches(&mut server, 1, "alice", "bob"));
+        assert!(offer_reaches(&mut server, 2, "bob", "alice"));
+    }
+
+    #[test]
+    fn blocked_users_are_hidden_and_their_offers_refused() {
+        let mut server = new_server();
+        login(&mut server, 1, "alice");
+        login(&mut server, 2, "mallory");
+
+        let out = server.handle(
+            1,
+            SignalingMsg::Block {
+                username: "mallory".into(),
+            },
+        );
+        let mallory_sees = out.iter().find_map(|m| match &m.msg {
+            SignalingMsg::PeersOnline { peers } if m.client_id_target == 2 => Some(peers),
+            _ => None,
+        });
+        assert_eq!(mallory_sees, Some(&Vec::new()));
+
+        let offer = SignalingMsg::Offer {
+            txn_id: 1,
+            from: "mallory".into(),
+            to: "alice".into(),
+            sdp: b"v=0".to_vec(),
+        };
+        let out = server.handle(2, offer);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].client_id_target, 2);
+        assert!(matches!(&out[0].msg, SignalingMsg::OfferErr { code, .. }
+            if *code == CallErrorCode::Blocked.as_u16()));
+        // The block is one way: Alice can still call out
+        assert!(offer_reaches(&mut server, 1, "alice", "mallory"));
+    }
+
     #[test]
     fn ping_replies_with_pong() {
         let mut server = new_server();
@@ -1289,12 +2404,13 @@ mod tests {
 
         assert_eq!(out.len(), 1);
         match &out[0].msg {
-            SignalingMsg::LoginErr { code } => {
+            SignalingMsg::LoginErr { code, reason } => {
                 assert_eq!(
                     *code,
                     LoginErrorCode::InvalidCredentials.as_u16(),
                     "expected InvalidCredentials code, got {code}"
                 );
+                assert_eq!(reason.as_deref(), Some("invalid username or password"));
             }
             other => panic!("expected LoginErr, got {other:?}"),
         }
@@ -1322,4 +2438,118 @@ mod tests {
             other => panic!("expected LoginOk, got {other:?}"),
         }
     }
+
+    #[test]
+    fn certificate_login_needs_no_password_but_one_session_per_user() {
+        let mut server = new_server_with_in_memory_auth();
+        server.set_max_devices(1);
+
+        let out = server.handle_certificate_login(1, "kiosk");
+        assert!(out.iter().any(|m| m.client_id_target == 1
+            && matches!(&m.msg, SignalingMsg::LoginOk { username } if username == "kiosk")));
+
+        let out = server.handle_certificate_login(2, "kiosk");
+        assert!(out.iter().any(|m| m.client_id_target == 2
+            && matches!(m.msg, SignalingMsg::LoginErr { code, .. }
+                if code == LoginErrorCode::AlreadyLoggedIn.as_u16())));
+    }
+
+    #[test]
+    fn calls_to_the_sfu_are_handed_to_it() {
+        let (sfu_tx, sfu_rx) = std::sync::mpsc::channel();
+        let mut server = new_server();
+        server.set_sfu(sfu_tx);
+        login(&mut server, 1, "alice");
+
+        let created = server.handle(1, SignalingMsg::CreateSession { capacity: 4 });
+        let Some(SignalingMsg::Created { session_id, .. }) = created.first().map(|m| &m.msg) else {
+            panic!("expected Created, got {created:?}");
+        };
+        let session_id = session_id.clone();
+        assert!(created.iter().any(|m| m.client_id_target == 1
+            && matches!(&m.msg, SignalingMsg::PeerJoined { username, .. } if username == SFU_USERNAME)));
+
+        let out = server.handle(
+            1,
+            SignalingMsg::Offer {
+                txn_id: 7,
+                from: "alice".into(),
+                to: SFU_USERNAME.into(),
+                sdp: b"v=0".to_vec(),
+            },
+        );
+        assert!(out.is_empty());
+        match sfu_rx.try_recv().unwrap() {
+            SfuCommand::Signal { room, from, msg } => {
+                assert_eq!(room, session_id);
+                assert_eq!(from, "alice");
+                assert!(matches!(msg, SignalingMsg::Offer { txn_id: 7, .. }));
+            }
+            other => panic!("expected Signal, got {other:?}"),
+        }
+
+        let out = server.deliver_from_sfu(SignalingMsg::Answer {
+            txn_id: 7,
+            from: SFU_USERNAME.into(),
+            to: "alice".into(),
+            sdp: b"v=0".to_vec(),
+        });
+        assert!(
+            out.iter()
+                .any(|m| m.client_id_target == 1 && matches!(m.msg, SignalingMsg::Answer { .. }))
+        );
+
+        server.handle(1, SignalingMsg::Leave { session_id });
+        assert!(matches!(
+            sfu_rx.try_recv().unwrap(),
+            SfuCommand::Leave { username, .. } if username == "alice"
+        ));
+
+        let out = server.handle(
+            2,
+            SignalingMsg::Login {
+                username: SFU_USERNAME.into(),
+                password: "pw".into(),
+            },
+        );
+        assert!(
+            out.iter()
+                .any(|m| matches!(m.msg, SignalingMsg::LoginErr { code, .. }
+            if code == LoginErrorCode::NotAuthorized.as_u16()))
+        );
+    }
+
+    #[test]
+    fn worker_checked_logins_finish_on_the_answer() {
+        let (ev_tx, ev_rx) = std::sync::mpsc::channel();
+        let mut server = new_server_with_in_memory_auth();
+        let auth = InMemoryAuthBackend::new().with_user("alice", "secret");
+        server.set_auth_workers(AuthWorkers::new(auth, ev_tx).with_max(1));
+        let login = || SignalingMsg::Login {
+            username: "alice".into(),
+            password: "secret".into(),
+        };
+
+        // Nothing is answered until the check is back
+        assert!(server.handle(1, login()).is_empty());
+        let Ok(crate::signaling::server_event::ServerEvent::LoginChecked {
+            client_id,
+            username,
+            result,
+        }) = ev_rx.recv_timeout(std::time::Duration::from_secs(2))
+        else {
+            panic!("expected LoginChecked");
+        };
+        let out = server.finish_login(client_id, &username, result);
+        assert!(
+            out.iter()
+                .any(|m| m.client_id_target == 1 && matches!(m.msg, SignalingMsg::LoginOk { .. }))
+        );
+
+        // An answer for a client that left meanwhile is dropped
+        assert!(server.handle(2, login()).is_empty());
+        server.handle_disconnect(2);
+        assert!(server.finish_login(2, "alice", Ok(())).is_empty());
+        assert_eq!(server.username_for(2), None);
+    }
 }
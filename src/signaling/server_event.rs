@@ -1,6 +1,6 @@
-use std::sync::mpsc::Sender;
+use std::{net::SocketAddr, sync::mpsc::Sender};
 
-use crate::signaling::{protocol::SignalingMsg, types::ClientId};
+use crate::signaling::{auth::AuthError, protocol::SignalingMsg, types::ClientId};
 
 /// Events sent *to* the central server thread.
 pub enum ServerEvent {
@@ -13,9 +13,29 @@ pub enum ServerEvent {
     /// A client disconnected (TCP/TLS closed or errored).
     Disconnected { client_id: ClientId },
 
+    /// A client presented a certificate naming `username`; it is logged in
+    /// without a password.
+    CertificateLogin {
+        client_id: ClientId,
+        username: String,
+    },
+
+    /// A login checked by [`crate::signaling::auth::AuthWorkers`] got its
+    /// answer.
+    LoginChecked {
+        client_id: ClientId,
+        username: String,
+        result: Result<(), AuthError>,
+    },
+
     /// A new client is registered with its outgoing channel.
     RegisterClient {
         client_id: ClientId,
         to_client: Sender<SignalingMsg>,
+        /// Where the connection comes from, when known.
+        addr: Option<SocketAddr>,
     },
+
+    /// The SFU has a message for one of the room members it serves.
+    FromSfu { msg: SignalingMsg },
 }
@@ -119,6 +119,23 @@ impl Sessions {
         result
     }
 
+    /// Remove `client_id` from one session, dropping it once empty.
+    ///
+    /// Returns the remaining members, or `None` if the client was not in it.
+    pub fn leave(&mut self, session_id: &SessionId, client_id: ClientId) -> Option<Vec<ClientId>> {
+        let sess = self.by_sess_id.get_mut(session_id)?;
+        if !sess.members.remove(&client_id) {
+            return None;
+        }
+        let remaining: Vec<ClientId> = sess.members.iter().copied().collect();
+        if remaining.is_empty()
+            && let Some(sess) = self.by_sess_id.remove(session_id)
+        {
+            self.by_sess_code.remove(&sess.session_code);
+        }
+        Some(remaining)
+    }
+
     /// Return true if both clients are members of at least one common session.
     #[must_use]
     pub fn share_session(&self, a: ClientId, b: ClientId) -> bool {
@@ -129,6 +146,15 @@ impl Sessions {
             .any(|sess| sess.members.contains(&a) && sess.members.contains(&b))
     }
 
+    /// A session `client_id` is a member of, if any.
+    #[must_use]
+    pub fn session_of(&self, client_id: ClientId) -> Option<SessionId> {
+        self.by_sess_id
+            .values()
+            .find(|sess| sess.members.contains(&client_id))
+            .map(|sess| sess.session_id.clone())
+    }
+
     /// Returns true if a session with this code already exists.
     #[must_use]
     pub fn contains_code(&self, code: &SessionCode) -> bool {
@@ -1,9 +1,16 @@
 use crate::config::Config;
 use crate::log::NoopLogSink;
 use crate::log::log_sink::LogSink;
-use crate::signaling::auth::{AuthBackend, FileUserStore};
+use crate::sfu::Sfu;
+use crate::signaling::audit::AuditLog;
+use crate::signaling::auth::{AuthBackend, AuthWorkers, CommandAuthBackend, FileUserStore};
+use crate::signaling::capture::SignalingRecorder;
+use crate::signaling::cert_gen::{CertOutcome, ensure_signaling_cert};
+use crate::signaling::contacts::ContactBook;
+use crate::signaling::forward_policy::ForwardPolicy;
 use crate::signaling::router::Router;
 use crate::signaling::runtime::run_server_loop;
+use crate::signaling::server_engine::DEFAULT_MAX_DEVICES;
 use crate::signaling::server_event::ServerEvent;
 use crate::signaling::tls::build_signaling_server_config;
 use crate::signaling::transport::spawn_tls_connection_thread;
@@ -28,6 +35,9 @@ pub struct SignalingServer {
     bind_addr: String,
     log: Arc<dyn LogSink>,
     auth_backend: Box<dyn AuthBackend>,
+    /// Set when logins are checked by a command, which runs on worker
+    /// threads so a slow one does not stall the server loop.
+    auth_command: Option<CommandAuthBackend>,
     /// Optional: kept only for nicer logging/debugging.
     user_store_path: Option<PathBuf>,
     config: Arc<Config>,
@@ -49,11 +59,28 @@ impl SignalingServer {
             bind_addr: bind_addr.into(),
             log,
             auth_backend: Box::new(auth_backend),
+            auth_command: None,
             user_store_path: None,
             config,
         }
     }
 
+    /// Construct a server that checks logins with `backend`, off the server
+    /// loop.
+    pub fn with_command_auth<S>(
+        bind_addr: S,
+        log: Arc<dyn LogSink>,
+        backend: CommandAuthBackend,
+        config: Arc<Config>,
+    ) -> Self
+    where
+        S: Into<String>,
+    {
+        let mut server = Self::with_auth(bind_addr, log, backend.clone(), config);
+        server.auth_command = Some(backend);
+        server
+    }
+
     /// Construct a server that uses a `FileUserStore` at `users_path`.
     ///
     /// # Errors
@@ -73,6 +100,7 @@ impl SignalingServer {
             bind_addr: bind_addr.into(),
             log,
             auth_backend: Box::new(store),
+            auth_command: None,
             user_store_path: Some(users_path),
             config,
         })
@@ -103,11 +131,36 @@ impl SignalingServer {
             bind_addr,
             log,
             auth_backend,
+            auth_command,
             user_store_path,
             config,
         } = self;
 
-        // --- TLS config (mkcert server cert + key) ---
+        // --- TLS config (mkcert or generated server cert + key) ---
+        match ensure_signaling_cert(&config)? {
+            CertOutcome::Disabled | CertOutcome::Kept => {}
+            CertOutcome::Issued { ca_created, sans } => {
+                if ca_created {
+                    sink_warn!(
+                        log,
+                        "created a new signaling CA; clients must trust it (set [TLS] signaling_ca_cert)"
+                    );
+                }
+                sink_info!(log, "issued signaling certificate for {}", sans.join(", "));
+            }
+        }
+        let sfu_config = Sfu::enabled(&config).then(|| config.clone());
+        let recorder = SignalingRecorder::from_config(&config, log.as_ref());
+        let audit = AuditLog::from_config(&config, log.as_ref());
+        let forward_policy = ForwardPolicy::from_config(&config).unwrap_or_else(|e| {
+            sink_warn!(log, "{}; forwarding to everyone", e);
+            ForwardPolicy::Open
+        });
+        let contacts = ContactBook::from_config(&config, log.as_ref());
+        let max_devices = config
+            .get_non_empty("Signaling", "max_devices_per_user")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_DEVICES);
         let tls_config = build_signaling_server_config(config)?;
 
         let listener = TcpListener::bind(&bind_addr)?;
@@ -125,11 +178,25 @@ impl SignalingServer {
         {
             let log_for_loop = log.clone();
             let log_for_router = log.clone();
+            let sfu =
+                sfu_config.map(|config| Sfu::new(config, log.clone(), server_tx.clone()).spawn());
+            let auth_workers =
+                auth_command.map(|backend| AuthWorkers::new(backend, server_tx.clone()));
 
             thread::spawn(move || {
                 sink_info!(log_for_loop, "[signaling] server loop started");
-                let router = Router::with_log_and_auth(log_for_router, auth_backend);
-                run_server_loop(router, log_for_loop, server_rx);
+                let mut router = Router::with_log_and_auth(log_for_router, auth_backend);
+                if let Some(sfu) = sfu {
+                    router.server_mut().set_sfu(sfu);
+                }
+                if let Some(workers) = auth_workers {
+                    router.server_mut().set_auth_workers(workers);
+                }
+                router.server_mut().set_audit(audit);
+                router.server_mut().set_forward_policy(forward_policy);
+                router.server_mut().set_contacts(contacts);
+                router.server_mut().set_max_devices(max_devices);
+                run_server_loop(router, log_for_loop, server_rx, recorder);
             });
         }
 
@@ -1,14 +1,78 @@
 use crate::{
     config::Config,
-    tls_utils::{SIGNALING_CA_PEM, load_signaling_certs, load_signaling_private_key},
+    signaling_client::trust::{PinningVerifier, TrustOptions},
+    tls_utils::{
+        SIGNALING_CA_PEM, load_certs, load_private_key, load_signaling_certs,
+        load_signaling_private_key,
+    },
+};
+use openssl::{nid::Nid, x509::X509};
+use rustls::{
+    ClientConfig, RootCertStore, ServerConfig,
+    client::WebPkiServerVerifier,
+    pki_types::CertificateDer,
+    server::{WebPkiClientVerifier, danger::ClientCertVerifier},
 };
-use rustls::{ClientConfig, RootCertStore, ServerConfig, pki_types::CertificateDer};
 use rustls_pemfile::certs;
 use std::{
     io::{self, Cursor},
+    str::FromStr,
     sync::Arc,
 };
 
+/// Whether the signaling server asks clients for a certificate
+/// (`[TLS] client_auth`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientAuth {
+    /// Clients log in with a password only.
+    #[default]
+    None,
+    /// Clients with a certificate are logged in by it; others use a password.
+    Optional,
+    /// Only clients with a certificate may connect.
+    Required,
+}
+
+impl FromStr for ClientAuth {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(Self::None),
+            "optional" => Ok(Self::Optional),
+            "required" => Ok(Self::Required),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("[TLS] client_auth must be none, optional or required, not {other:?}"),
+            )),
+        }
+    }
+}
+
+impl ClientAuth {
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if `[TLS] client_auth` holds an unknown mode.
+    pub fn from_config(config: &Config) -> io::Result<Self> {
+        config.get("TLS", "client_auth").unwrap_or_default().parse()
+    }
+}
+
+/// The username a client certificate logs in as: its subject common name.
+#[must_use]
+pub fn client_cert_username(cert_der: &[u8]) -> Option<String> {
+    let cert = X509::from_der(cert_der).ok()?;
+    let cn = cert
+        .subject_name()
+        .entries_by_nid(Nid::COMMONNAME)
+        .next()?
+        .data()
+        .as_utf8()
+        .ok()?;
+    let username = cn.trim();
+    (!username.is_empty()).then(|| username.to_owned())
+}
+
 /// Build a `RootCertStore` that trusts ONLY the pinned mkcert CA.
 ///
 /// # Errors
@@ -39,41 +103,160 @@ fn build_pinned_root_store() -> io::Result<RootCertStore> {
     Ok(root_store)
 }
 
-/// `ClientConfig` for the signaling client, using ONLY the pinned mkcert CA.
+/// `ClientConfig` for the signaling client. The server certificate must chain
+/// to the pinned mkcert CA or to the CA bundle in `trust`, unless `trust` pins
+/// or accepts its key (see [`PinningVerifier`]).
 ///
 /// This is what we'll pass to `SignalingClient::connect_tls`.
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if the root CA certificate file cannot be read or parsed.
-pub fn build_signaling_client_config() -> io::Result<Arc<ClientConfig>> {
-    let root_store = build_pinned_root_store()?;
+/// Returns an `io::Error` if a root CA certificate file cannot be read or parsed.
+pub fn build_signaling_client_config(trust: &TrustOptions) -> io::Result<Arc<ClientConfig>> {
+    let mut root_store = build_pinned_root_store()?;
+    if let Some(path) = &trust.extra_ca {
+        for cert in load_certs(path)? {
+            root_store.add(cert).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("bad CA cert: {e}"))
+            })?;
+        }
+    }
+    let ca_verifier = WebPkiServerVerifier::builder(Arc::new(root_store))
+        .build()
+        .map_err(|e| io::Error::other(format!("TLS verifier error: {e}")))?;
 
-    let config = ClientConfig::builder()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
+    let builder = ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinningVerifier::new(ca_verifier, trust)));
+    let config = match &trust.identity {
+        Some(identity) => builder
+            .with_client_auth_cert(
+                load_certs(&identity.cert)?,
+                load_private_key(&identity.key)?,
+            )
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("client certificate error: {e}"),
+                )
+            })?,
+        None => builder.with_no_client_auth(),
+    };
 
     Ok(Arc::new(config))
 }
 
-/// `ServerConfig` for the signaling server, using *no* client auth, with our mkcert-issued cert.
+/// Verifier for client certificates issued by the CAs at
+/// `[TLS] client_ca_cert`, or `None` if `[TLS] client_auth` is off.
+fn build_client_verifier(config: &Config) -> io::Result<Option<Arc<dyn ClientCertVerifier>>> {
+    let mode = ClientAuth::from_config(config)?;
+    if mode == ClientAuth::None {
+        return Ok(None);
+    }
+    let path = config
+        .get_non_empty("TLS", "client_ca_cert")
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "[TLS] client_auth needs client_ca_cert",
+            )
+        })?;
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots
+            .add(cert)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("bad CA cert: {e}")))?;
+    }
+    let builder = WebPkiClientVerifier::builder(Arc::new(roots));
+    let builder = if mode == ClientAuth::Optional {
+        builder.allow_unauthenticated()
+    } else {
+        builder
+    };
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| io::Error::other(format!("TLS verifier error: {e}")))
+}
+
+/// `ServerConfig` for the signaling server, with our mkcert-issued (or
+/// runtime-generated) cert. Client certificates are asked for according to
+/// `[TLS] client_auth` (see [`ClientAuth`]).
 ///
 /// We’ll call this once at startup, then re-use the `Arc<ServerConfig>`
 /// for each accepted TCP connection (wrapping in `ServerConnection` / `StreamOwned` later).
 ///
 /// # Errors
 ///
-/// Returns an `io::Error` if the certificate or private key cannot be loaded or are invalid.
+/// Returns an `io::Error` if the certificate or private key cannot be loaded or are invalid,
+/// or if client authentication is misconfigured.
 pub fn build_signaling_server_config(config: Arc<Config>) -> io::Result<Arc<ServerConfig>> {
     let certs = load_signaling_certs(config.as_ref())?;
     let key = load_signaling_private_key(config.as_ref())?;
 
-    let config = ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)
-        .map_err(|e| {
-            io::Error::new(io::ErrorKind::InvalidData, format!("TLS config error: {e}"))
-        })?;
+    let builder = match build_client_verifier(config.as_ref())? {
+        Some(verifier) => ServerConfig::builder().with_client_cert_verifier(verifier),
+        None => ServerConfig::builder().with_no_client_auth(),
+    };
+    let config = builder.with_single_cert(certs, key).map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("TLS config error: {e}"))
+    })?;
 
     Ok(Arc::new(config))
 }
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use openssl::{
+        asn1::Asn1Time,
+        ec::{EcGroup, EcKey},
+        hash::MessageDigest,
+        pkey::PKey,
+        x509::X509NameBuilder,
+    };
+
+    #[test]
+    fn client_auth_modes_parse() {
+        let mut config = Config::empty();
+        assert_eq!(ClientAuth::from_config(&config).unwrap(), ClientAuth::None);
+        config.set("TLS", "client_auth", " Required ");
+        assert_eq!(
+            ClientAuth::from_config(&config).unwrap(),
+            ClientAuth::Required
+        );
+        config.set("TLS", "client_auth", "sometimes");
+        assert!(ClientAuth::from_config(&config).is_err());
+        // Asking for certificates without a CA to check them against
+        config.set("TLS", "client_auth", "optional");
+        assert!(build_client_verifier(&config).is_err());
+    }
+
+    #[test]
+    fn username_is_the_subject_common_name() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::ORGANIZATIONNAME, "Kiosks")
+            .unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "lobby-kiosk")
+            .unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let der = builder.build().to_der().unwrap();
+
+        assert_eq!(client_cert_username(&der).as_deref(), Some("lobby-kiosk"));
+        assert_eq!(client_cert_username(b"not a certificate"), None);
+    }
+}
@@ -3,52 +3,87 @@ use std::net::TcpStream;
 use std::sync::Arc;
 use std::sync::mpsc::{self, Sender, TryRecvError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::log::log_sink::LogSink;
-use crate::signaling::protocol::{self, FrameError, SignalingMsg};
+use crate::signaling::protocol::{self, Compression, FrameError, SignalingMsg};
 use crate::signaling::server_event::ServerEvent;
+use crate::signaling::tls::client_cert_username;
 use crate::signaling::types::ClientId;
-use crate::sink_error;
+use crate::{sink_error, sink_info};
 use rustls::{ServerConnection, StreamOwned};
 
+/// How long a client may take to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Thin wrapper over a blocking stream that speaks in `Msg`.
 pub struct Connection<S> {
     pub client_id: ClientId,
     stream: S,
+    compression: Compression,
 }
 
 impl<S> Connection<S>
 where
     S: Read + Write,
 {
-    pub const fn new(id: ClientId, stream: S) -> Self {
+    pub fn new(id: ClientId, stream: S) -> Self {
         Self {
             client_id: id,
             stream,
+            compression: Compression::new(),
         }
     }
 
+    /// Builder-style: share compression state with another connection on
+    /// the same socket, e.g. the reader half of a split stream.
+    #[must_use]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
     /// # Errors
     /// Returns `FrameError` on I/O or protocol-level read errors.
     pub fn recv(&mut self) -> Result<SignalingMsg, FrameError> {
-        protocol::read_msg(&mut self.stream)
+        protocol::read_msg_compressed(&mut self.stream, &self.compression)
     }
 
     /// # Errors
     /// Returns `FrameError` on I/O or protocol-level write errors.
     pub fn send(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
-        protocol::write_msg(&mut self.stream, msg)
+        protocol::write_msg_compressed(&mut self.stream, msg, &self.compression)
+    }
+}
+
+/// Completes the TLS handshake on a socket with a read timeout.
+fn complete_handshake(stream: &mut StreamOwned<ServerConnection, TcpStream>) -> io::Result<()> {
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    while stream.conn.is_handshaking() {
+        match stream.conn.complete_io(&mut stream.sock) {
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) && Instant::now() < deadline => {}
+            Err(e) => return Err(e),
+        }
     }
+    Ok(())
 }
 
 /// TLS-enabled variant: single thread that handles both reading and writing.
 ///
-/// `stream` is a rustls `StreamOwned<ServerConnection, TcpStream>`.
+/// `stream` is a rustls `StreamOwned<ServerConnection, TcpStream>`. The
+/// handshake is completed first; a client that presented a certificate is
+/// logged in as the user it names.
 #[allow(clippy::expect_used)]
 pub(crate) fn spawn_tls_connection_thread(
     client_id: ClientId,
-    stream: StreamOwned<ServerConnection, TcpStream>,
+    mut stream: StreamOwned<ServerConnection, TcpStream>,
     server_tx: Sender<ServerEvent>,
     log: Arc<dyn LogSink>,
 ) {
@@ -59,10 +94,39 @@ pub(crate) fn spawn_tls_connection_thread(
         .send(ServerEvent::RegisterClient {
             client_id,
             to_client: to_client_tx,
+            addr: stream.sock.peer_addr().ok(),
         })
         .expect("server loop should be alive");
 
     thread::spawn(move || {
+        if let Err(e) = complete_handshake(&mut stream) {
+            sink_error!(log, "[conn {}] TLS handshake failed: {}", client_id, e);
+            let _ = server_tx.send(ServerEvent::Disconnected { client_id });
+            return;
+        }
+        let cert_username = stream
+            .conn
+            .peer_certificates()
+            .and_then(|chain| chain.first())
+            .and_then(|cert| client_cert_username(cert));
+        if let Some(username) = cert_username {
+            sink_info!(
+                log,
+                "[conn {}] client certificate identifies {}",
+                client_id,
+                username
+            );
+            if server_tx
+                .send(ServerEvent::CertificateLogin {
+                    client_id,
+                    username,
+                })
+                .is_err()
+            {
+                return;
+            }
+        }
+
         let mut conn = Connection::new(client_id, stream);
 
         loop {
@@ -152,18 +216,21 @@ pub(crate) fn spawn_connection_threads(
         .send(ServerEvent::RegisterClient {
             client_id,
             to_client: to_client_tx,
+            addr: stream.peer_addr().ok(),
         })
         .expect("server loop should be alive");
 
     let read_stream = stream.try_clone()?;
     let write_stream = stream;
     let log_for_read = log.clone();
+    let compression = Compression::new();
 
     // READER THREAD: socket -> ServerEvent::MsgFromClient
     {
         let server_tx = server_tx.clone();
+        let compression = compression.clone();
         thread::spawn(move || {
-            let mut conn = Connection::new(client_id, read_stream);
+            let mut conn = Connection::new(client_id, read_stream).with_compression(compression);
 
             loop {
                 match conn.recv() {
@@ -207,7 +274,7 @@ pub(crate) fn spawn_connection_threads(
 
     {
         thread::spawn(move || {
-            let mut conn = Connection::new(client_id, write_stream);
+            let mut conn = Connection::new(client_id, write_stream).with_compression(compression);
 
             while let Ok(msg) = to_client_rx.recv() {
                 if let Err(e) = conn.send(&msg) {
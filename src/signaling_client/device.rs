@@ -0,0 +1,34 @@
+//! How this client names itself to the user's other devices.
+
+use crate::config::Config;
+
+/// Label used when neither the config nor the environment names the host.
+const FALLBACK_LABEL: &str = "desktop";
+
+/// The label sent with `SetDevice` after logging in: `[Signaling]
+/// device_label`, else the host name, else a generic one.
+#[must_use]
+pub fn device_label(config: &Config) -> String {
+    config
+        .get_non_empty("Signaling", "device_label")
+        .map(str::to_owned)
+        .or_else(|| {
+            ["HOSTNAME", "COMPUTERNAME"]
+                .into_iter()
+                .find_map(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        })
+        .unwrap_or_else(|| FALLBACK_LABEL.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn configured_label_wins() {
+        let mut config = Config::empty();
+        config.set("Signaling", "device_label", "laptop");
+        assert_eq!(device_label(&config), "laptop");
+    }
+}
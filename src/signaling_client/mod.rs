@@ -1,6 +1,11 @@
+pub mod device;
+pub mod reliability;
 pub mod signaling_client_c;
 pub mod signaling_client_error;
 pub mod signaling_command;
 pub mod signaling_event;
+pub mod transport;
+pub mod trust;
+pub use device::device_label;
 pub use signaling_client_c::SignalingClient;
 pub use signaling_event::SignalingEvent;
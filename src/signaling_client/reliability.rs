@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::signaling::protocol::{SignalingMsg, TxnId, UserName};
+
+/// Retransmission schedule for reliable signaling messages (Offer/Answer).
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Delay before the first retransmission.
+    pub initial_backoff: Duration,
+    /// Upper bound for the exponential backoff.
+    pub max_backoff: Duration,
+    /// Total number of sends (original + retransmissions) before giving up.
+    pub max_attempts: u32,
+    /// How long a received txn is remembered for deduplication.
+    pub dedup_ttl: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(4),
+            max_attempts: 6,
+            dedup_ttl: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Which reliable message a transaction refers to.
+///
+/// Offer and Answer share the same `txn_id`, so dedup must tell them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum TxnKind {
+    Offer,
+    Answer,
+}
+
+#[derive(Debug)]
+struct PendingTxn {
+    msg: SignalingMsg,
+    attempts: u32,
+    backoff: Duration,
+    next_retry: Instant,
+}
+
+/// A reliable message that exhausted its retransmissions without an `Ack`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxnFailure {
+    pub peer: UserName,
+    pub txn_id: TxnId,
+}
+
+/// What the network thread should do with an inbound message.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InboundVerdict {
+    /// First time we see it: hand it to the application.
+    Deliver,
+    /// Already delivered: drop it and re-send this `Ack` so the peer stops retrying.
+    Duplicate(SignalingMsg),
+}
+
+/// Client-side reliability layer for Offer/Answer.
+///
+/// - Outbound Offers/Answers are kept until the peer `Ack`s their `txn_id`,
+///   and retransmitted with exponential backoff in the meantime.
+/// - Inbound Offers/Answers are deduplicated by `(kind, from, txn_id)`.
+/// - A `Bye` in either direction cancels pending retransmissions for that peer,
+///   and so does an `OfferErr` saying the server refused to deliver to them.
+#[derive(Debug, Default)]
+pub struct TxnReliability {
+    policy: RetryPolicy,
+    pending: HashMap<(UserName, TxnId), PendingTxn>,
+    seen: HashMap<(TxnKind, UserName, TxnId), Instant>,
+}
+
+impl TxnReliability {
+    #[must_use]
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy,
+            pending: HashMap::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Records a message that was just written to the wire.
+    pub fn on_outbound(&mut self, msg: &SignalingMsg, now: Instant) {
+        match msg {
+            SignalingMsg::Offer { to, txn_id, .. } | SignalingMsg::Answer { to, txn_id, .. } => {
+                self.pending.insert(
+                    (to.clone(), *txn_id),
+                    PendingTxn {
+                        msg: msg.clone(),
+                        attempts: 1,
+                        backoff: self.policy.initial_backoff,
+                        next_retry: now + self.policy.initial_backoff,
+                    },
+                );
+            }
+            SignalingMsg::Bye { to, .. } => self.cancel_peer(to),
+            _ => {}
+        }
+    }
+
+    /// Inspects a message read from the wire, clearing acked txns and
+    /// flagging duplicates.
+    pub fn on_inbound(&mut self, msg: &SignalingMsg, now: Instant) -> InboundVerdict {
+        let (kind, from, to, txn_id) = match msg {
+            SignalingMsg::Ack { from, txn_id, .. } => {
+                self.pending.remove(&(from.clone(), *txn_id));
+                return InboundVerdict::Deliver;
+            }
+            SignalingMsg::Bye { from: peer, .. } | SignalingMsg::OfferErr { to: peer, .. } => {
+                self.cancel_peer(peer);
+                return InboundVerdict::Deliver;
+            }
+            SignalingMsg::Offer {
+                from, to, txn_id, ..
+            } => (TxnKind::Offer, from, to, *txn_id),
+            SignalingMsg::Answer {
+                from, to, txn_id, ..
+            } => (TxnKind::Answer, from, to, *txn_id),
+            _ => return InboundVerdict::Deliver,
+        };
+
+        if let Some(first_seen) = self.seen.get(&(kind, from.clone(), txn_id))
+            && now.duration_since(*first_seen) < self.policy.dedup_ttl
+        {
+            return InboundVerdict::Duplicate(SignalingMsg::Ack {
+                from: to.clone(),
+                to: from.clone(),
+                txn_id,
+            });
+        }
+        self.seen.insert((kind, from.clone(), txn_id), now);
+        InboundVerdict::Deliver
+    }
+
+    /// Returns the messages due for retransmission and the txns that gave up.
+    pub fn poll(&mut self, now: Instant) -> (Vec<SignalingMsg>, Vec<TxnFailure>) {
+        let mut resend = Vec::new();
+        let mut failed = Vec::new();
+
+        for ((peer, txn_id), txn) in &mut self.pending {
+            if now < txn.next_retry {
+                continue;
+            }
+            if txn.attempts >= self.policy.max_attempts {
+                failed.push(TxnFailure {
+                    peer: peer.clone(),
+                    txn_id: *txn_id,
+                });
+                continue;
+            }
+            txn.attempts += 1;
+            txn.backoff = (txn.backoff * 2).min(self.policy.max_backoff);
+            txn.next_retry = now + txn.backoff;
+            resend.push(txn.msg.clone());
+        }
+
+        for f in &failed {
+            self.pending.remove(&(f.peer.clone(), f.txn_id));
+        }
+
+        let ttl = self.policy.dedup_ttl;
+        self.seen
+            .retain(|_, first| now.duration_since(*first) < ttl);
+
+        (resend, failed)
+    }
+
+    /// Number of transactions still waiting for an `Ack`.
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn cancel_peer(&mut self, peer: &str) {
+        self.pending.retain(|(p, _), _| p != peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    fn offer(from: &str, to: &str, txn_id: u64) -> SignalingMsg {
+        SignalingMsg::Offer {
+            txn_id,
+            from: from.into(),
+            to: to.into(),
+            sdp: b"v=0".to_vec(),
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(400),
+            max_attempts: 3,
+            dedup_ttl: Duration::from_secs(10),
+        }
+    }
+
+    #[test]
+    fn refused_offer_is_not_retransmitted() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        rel.on_outbound(&offer("mallory", "alice", 1), t0);
+        let refused = SignalingMsg::OfferErr {
+            to: "alice".into(),
+            code: 30,
+            reason: None,
+        };
+        assert_eq!(rel.on_inbound(&refused, t0), InboundVerdict::Deliver);
+        assert_eq!(rel.pending_len(), 0);
+    }
+
+    #[test]
+    fn ack_clears_pending_offer() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        rel.on_outbound(&offer("alice", "bob", 7), t0);
+        assert_eq!(rel.pending_len(), 1);
+
+        let ack = SignalingMsg::Ack {
+            from: "bob".into(),
+            to: "alice".into(),
+            txn_id: 7,
+        };
+        assert_eq!(rel.on_inbound(&ack, t0), InboundVerdict::Deliver);
+        assert_eq!(rel.pending_len(), 0);
+
+        let (resend, failed) = rel.poll(t0 + Duration::from_secs(5));
+        assert!(resend.is_empty());
+        assert!(failed.is_empty());
+    }
+
+    #[test]
+    fn unacked_offer_is_retransmitted_then_fails() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        rel.on_outbound(&offer("alice", "bob", 1), t0);
+
+        let (resend, _) = rel.poll(t0 + Duration::from_millis(50));
+        assert!(resend.is_empty(), "not due yet");
+
+        let (resend, failed) = rel.poll(t0 + Duration::from_millis(100));
+        assert_eq!(resend, vec![offer("alice", "bob", 1)]);
+        assert!(failed.is_empty());
+
+        // Backoff doubled to 200ms.
+        let (resend, _) = rel.poll(t0 + Duration::from_millis(250));
+        assert!(resend.is_empty());
+        let (resend, _) = rel.poll(t0 + Duration::from_millis(300));
+        assert_eq!(resend.len(), 1);
+
+        let (resend, failed) = rel.poll(t0 + Duration::from_secs(2));
+        assert!(resend.is_empty());
+        assert_eq!(
+            failed,
+            vec![TxnFailure {
+                peer: "bob".into(),
+                txn_id: 1
+            }]
+        );
+        assert_eq!(rel.pending_len(), 0);
+    }
+
+    #[test]
+    fn duplicate_offer_is_dropped_and_reacked() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        let msg = offer("alice", "bob", 3);
+
+        assert_eq!(rel.on_inbound(&msg, t0), InboundVerdict::Deliver);
+        assert_eq!(
+            rel.on_inbound(&msg, t0 + Duration::from_millis(500)),
+            InboundVerdict::Duplicate(SignalingMsg::Ack {
+                from: "bob".into(),
+                to: "alice".into(),
+                txn_id: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn answer_with_same_txn_as_offer_is_not_a_duplicate() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        assert_eq!(
+            rel.on_inbound(&offer("alice", "bob", 3), t0),
+            InboundVerdict::Deliver
+        );
+        let answer = SignalingMsg::Answer {
+            txn_id: 3,
+            from: "alice".into(),
+            to: "bob".into(),
+            sdp: b"v=0".to_vec(),
+        };
+        assert_eq!(rel.on_inbound(&answer, t0), InboundVerdict::Deliver);
+    }
+
+    #[test]
+    fn bye_cancels_pending_for_peer() {
+        let mut rel = TxnReliability::new(policy());
+        let t0 = Instant::now();
+        rel.on_outbound(&offer("alice", "bob", 1), t0);
+        rel.on_outbound(&offer("alice", "carol", 2), t0);
+
+        let bye = SignalingMsg::Bye {
+            from: "bob".into(),
+            to: "alice".into(),
+            reason: None,
+        };
+        rel.on_inbound(&bye, t0);
+        assert_eq!(rel.pending_len(), 1);
+    }
+}
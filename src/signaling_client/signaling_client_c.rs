@@ -1,9 +1,9 @@
 use std::{
-    io::{self, Read, Write},
-    net::TcpStream,
+    io,
     sync::{
         Arc,
-        mpsc::{self, Receiver, Sender},
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
     },
     thread,
     time::{Duration, Instant},
@@ -11,25 +11,34 @@ use std::{
 
 use crate::{
     log::log_sink::LogSink,
-    signaling::protocol::{self, FrameError, SignalingMsg},
+    signaling::{
+        capture::{RecordingTransport, SignalingRecorder},
+        protocol::{FrameError, SignalingMsg},
+    },
     signaling_client::{
-        signaling_client_error::SignalingClientError, signaling_command::SignalingCommand,
+        reliability::{InboundVerdict, RetryPolicy, TxnReliability},
+        signaling_client_error::SignalingClientError,
+        signaling_command::SignalingCommand,
         signaling_event::SignalingEvent,
+        transport::{SignalingTransport, TcpTransport, TlsTransport, TransportKind},
+        trust::TrustOptions,
     },
     sink_debug, sink_error, sink_info, sink_trace, sink_warn,
 };
 
 use crate::signaling::tls::build_signaling_client_config;
-use rustls::{ClientConfig, ClientConnection, StreamOwned, pki_types::ServerName};
+use rustls::ClientConfig;
 
 /// Thin client responsible for sending/receiving signaling messages.
 ///
-/// - Only the background thread touches the underlying stream (`TcpStream`,
-///   TLS stream, etc.).
+/// - Only the background thread touches the underlying `SignalingTransport`
+///   (plain TCP, TLS, etc.).
 /// - The GUI sends `SignalingCommand`s in, and receives `SignalingEvent`s out.
 pub struct SignalingClient {
     cmd_tx: Sender<SignalingCommand>,
     events: Receiver<SignalingEvent>,
+    /// The last `Sequenced` number received, 0 before the first.
+    last_seq: Arc<AtomicU64>,
 }
 
 impl SignalingClient {
@@ -46,7 +55,7 @@ impl SignalingClient {
     ///
     /// Returns an `io::Error` if the root CA certificate file cannot be read or parsed.
     pub fn default_tls_config() -> io::Result<Arc<ClientConfig>> {
-        build_signaling_client_config()
+        build_signaling_client_config(&TrustOptions::default())
     }
 
     /// Connects to the signaling server over plain TCP and starts the
@@ -60,34 +69,8 @@ impl SignalingClient {
     ///
     /// Returns an `io::Error` if the initial TCP connection to the server fails.
     pub fn connect(addr: &str, log: Arc<dyn LogSink>) -> io::Result<Self> {
-        let stream = TcpStream::connect(addr)?;
-
-        // Configure TCP specifics here (before we might wrap it in TLS in other ctors).
-        if let Err(e) = stream.set_nodelay(true) {
-            sink_warn!(
-                log,
-                "[signaling_client] set_nodelay failed for {}: {e:?}",
-                addr
-            );
-        }
-        if let Err(e) = stream.set_read_timeout(Some(Duration::from_millis(200))) {
-            sink_warn!(
-                log,
-                "[signaling_client] set_read_timeout failed for {}: {e:?}",
-                addr
-            );
-        }
-
-        let (cmd_tx, cmd_rx) = mpsc::channel::<SignalingCommand>();
-        let (ev_tx, ev_rx) = mpsc::channel::<SignalingEvent>();
-
-        // Hand the raw TcpStream to the generic network thread.
-        Self::spawn_network_thread(addr.to_string(), stream, cmd_rx, ev_tx, log);
-
-        Ok(Self {
-            cmd_tx,
-            events: ev_rx,
-        })
+        let transport = TcpTransport::connect(addr)?;
+        Ok(Self::with_transport(Box::new(transport), log))
     }
 
     /// TLS-enabled constructor (using `rustls`).
@@ -103,69 +86,84 @@ impl SignalingClient {
         tls_config: Arc<ClientConfig>,
         log: Arc<dyn LogSink>,
     ) -> io::Result<Self> {
-        // 1) Establish and configure the underlying TCP socket.
-        let tcp = TcpStream::connect(addr)?;
-        if let Err(e) = tcp.set_nodelay(true) {
-            sink_warn!(
-                log,
-                "[signaling_client] (tls) set_nodelay failed for {}: {e:?}",
-                addr
-            );
-        }
-        if let Err(e) = tcp.set_read_timeout(Some(Duration::from_millis(200))) {
-            sink_warn!(
-                log,
-                "[signaling_client] (tls) set_read_timeout failed for {}: {e:?}",
-                addr
-            );
-        }
-
-        // 2) Build the rustls client connection.
-        let server_name = ServerName::try_from(domain.to_owned())
-            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid DNS name"))?;
-
-        let conn = ClientConnection::new(tls_config, server_name)
-            .map_err(|e| io::Error::other(format!("TLS error: {e}")))?;
+        let transport = TlsTransport::connect(addr, domain, tls_config)?;
+        Ok(Self::with_transport(Box::new(transport), log))
+    }
 
-        // 3) Wrap TCP + TLS into a single stream that implements Read + Write.
-        let tls_stream = StreamOwned::new(conn, tcp);
+    /// Connects using the transport selected at runtime (e.g. from config).
+    ///
+    /// `domain` and `trust` are only used for TLS. With a `recorder`, every
+    /// message to and from the server is recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the chosen transport cannot be established.
+    /// A server certificate that is not trusted gives an error holding an
+    /// [`UntrustedCertificate`](crate::signaling_client::trust::UntrustedCertificate).
+    pub fn connect_with(
+        kind: TransportKind,
+        addr: &str,
+        domain: &str,
+        trust: &TrustOptions,
+        recorder: Option<SignalingRecorder>,
+        log: Arc<dyn LogSink>,
+    ) -> io::Result<Self> {
+        let transport: Box<dyn SignalingTransport> = match kind {
+            TransportKind::Tcp => Box::new(TcpTransport::connect(addr)?),
+            TransportKind::Tls => {
+                let tls_cfg = build_signaling_client_config(trust)?;
+                Box::new(TlsTransport::connect(addr, domain, tls_cfg)?)
+            }
+        };
+        let transport: Box<dyn SignalingTransport> = match recorder {
+            Some(recorder) => Box::new(RecordingTransport::new(transport, recorder)),
+            None => transport,
+        };
+        Ok(Self::with_transport(transport, log))
+    }
 
+    /// Starts the background network thread over an already-connected transport.
+    ///
+    /// This is the single entry point used by every constructor, and the hook
+    /// for custom or mock transports.
+    #[must_use]
+    pub fn with_transport(transport: Box<dyn SignalingTransport>, log: Arc<dyn LogSink>) -> Self {
         let (cmd_tx, cmd_rx) = mpsc::channel::<SignalingCommand>();
         let (ev_tx, ev_rx) = mpsc::channel::<SignalingEvent>();
+        let last_seq = Arc::new(AtomicU64::new(0));
 
-        // 4) Reuse the same generic network thread.
-        Self::spawn_network_thread(format!("tls://{addr}"), tls_stream, cmd_rx, ev_tx, log);
+        Self::spawn_network_thread(transport, cmd_rx, ev_tx, last_seq.clone(), log);
 
-        Ok(Self {
+        Self {
             cmd_tx,
             events: ev_rx,
-        })
+            last_seq,
+        }
     }
 
-    /// Background network thread: owns the stream (TCP or TLS), handles:
+    /// Background network thread: owns the transport (TCP, TLS, ...), handles:
     /// - Hello
     /// - Reads incoming messages
     /// - Processes commands (Send/Disconnect)
     /// - Sends periodic Ping and enforces heartbeat timeout
+    /// - Retransmits un-acked Offers/Answers and drops duplicate ones
+    /// - Unwraps numbered messages, dropping ones already seen
     #[allow(clippy::too_many_lines)]
-    fn spawn_network_thread<S>(
-        addr: String,
-        mut stream: S,
+    fn spawn_network_thread(
+        mut transport: Box<dyn SignalingTransport>,
         cmd_rx: Receiver<SignalingCommand>,
         ev_tx: Sender<SignalingEvent>,
+        last_seq: Arc<AtomicU64>,
         log: Arc<dyn LogSink>,
-    ) where
-        S: Read + Write + Send + 'static,
-    {
+    ) {
         thread::spawn(move || {
+            let addr = transport.describe();
+
             // Initial Hello
             sink_debug!(log, "[signaling_client] sending Hello to {}", addr);
-            if let Err(err) = protocol::write_msg(
-                &mut stream,
-                &SignalingMsg::Hello {
-                    client_version: Self::CLIENT_VERSION.to_string(),
-                },
-            ) {
+            if let Err(err) = transport.write_msg(&SignalingMsg::Hello {
+                client_version: Self::CLIENT_VERSION.to_string(),
+            }) {
                 sink_error!(
                     log,
                     "[signaling_client] hello failed to {}: {:?}",
@@ -187,6 +185,9 @@ impl SignalingClient {
             let mut next_ping = Instant::now() + ping_interval;
             let mut nonce: u64 = 1;
 
+            // Offer/Answer reliability state
+            let mut reliability = TxnReliability::new(RetryPolicy::default());
+
             loop {
                 // 1) Drain commands from the GUI.
                 let mut disconnect_requested = false;
@@ -194,7 +195,7 @@ impl SignalingClient {
                     match cmd_rx.try_recv() {
                         Ok(SignalingCommand::Send(msg)) => {
                             sink_debug!(log, "[signaling_client] send {:?}", msg_name(&msg));
-                            if let Err(e) = protocol::write_msg(&mut stream, &msg) {
+                            if let Err(e) = transport.write_msg(&msg) {
                                 match e {
                                     FrameError::Io(ioe) => {
                                         sink_error!(
@@ -221,6 +222,11 @@ impl SignalingClient {
                                 disconnect_requested = true;
                                 break;
                             }
+                            if let SignalingMsg::ResumeFrom { seq } = msg {
+                                // Numbers go on from there, resumed or not
+                                last_seq.store(seq, Ordering::Relaxed);
+                            }
+                            reliability.on_outbound(&msg, Instant::now());
                         }
                         Ok(SignalingCommand::Disconnect) => {
                             sink_info!(log, "[signaling_client] disconnect requested by client");
@@ -245,12 +251,49 @@ impl SignalingClient {
 
                 // 2) Try to read a message.
                 //
-                // For TCP we rely on `set_read_timeout` configured by the constructor.
-                // For TLS, the underlying TCP's timeout still applies to `read()`.
-                match protocol::read_msg(&mut stream) {
+                // Transports configure a short read timeout on the socket, so this
+                // returns TimedOut/WouldBlock when nothing arrived.
+                match transport.read_msg() {
                     Ok(msg) => {
                         last_seen = Instant::now();
+                        let msg = match msg {
+                            SignalingMsg::Sequenced { seq, msg } => {
+                                if seq <= last_seq.load(Ordering::Relaxed) {
+                                    sink_debug!(
+                                        log,
+                                        "[signaling_client] replayed message {} already seen",
+                                        seq
+                                    );
+                                    continue;
+                                }
+                                last_seq.store(seq, Ordering::Relaxed);
+                                *msg
+                            }
+                            msg => msg,
+                        };
                         sink_debug!(log, "[signaling_client] recv {:?}", msg_name(&msg));
+                        if let InboundVerdict::Duplicate(ack) =
+                            reliability.on_inbound(&msg, last_seen)
+                        {
+                            sink_debug!(
+                                log,
+                                "[signaling_client] duplicate {} dropped, re-acking",
+                                msg_name(&msg)
+                            );
+                            if let Err(e) = transport.write_msg(&ack) {
+                                sink_error!(
+                                    log,
+                                    "[signaling_client] failed to re-ack duplicate to {}: {:?}",
+                                    addr,
+                                    e
+                                );
+                                let _ = ev_tx.send(SignalingEvent::Error(format!(
+                                    "failed to send Ack: {e:?}"
+                                )));
+                                break;
+                            }
+                            continue;
+                        }
                         if ev_tx.send(SignalingEvent::ServerMsg(msg)).is_err() {
                             sink_warn!(
                                 log,
@@ -290,7 +333,41 @@ impl SignalingClient {
                     }
                 }
 
-                // 3) Heartbeat / Ping.
+                // 3) Retransmit un-acked Offers/Answers, fail the ones that gave up.
+                let (resend, failed) = reliability.poll(Instant::now());
+                let mut resend_failed = false;
+                for msg in resend {
+                    sink_debug!(log, "[signaling_client] retransmit {:?}", msg_name(&msg));
+                    if let Err(e) = transport.write_msg(&msg) {
+                        sink_error!(
+                            log,
+                            "[signaling_client] failed to retransmit to {}: {:?}",
+                            addr,
+                            e
+                        );
+                        let _ =
+                            ev_tx.send(SignalingEvent::Error(format!("retransmit failed: {e:?}")));
+                        resend_failed = true;
+                        break;
+                    }
+                }
+                if resend_failed {
+                    break;
+                }
+                for f in failed {
+                    sink_warn!(
+                        log,
+                        "[signaling_client] txn {} to {} was never acked, giving up",
+                        f.txn_id,
+                        f.peer
+                    );
+                    let _ = ev_tx.send(SignalingEvent::CallFailed {
+                        peer: f.peer,
+                        txn_id: f.txn_id,
+                    });
+                }
+
+                // 4) Heartbeat / Ping.
                 let now = Instant::now();
                 let idle = now.duration_since(last_seen);
 
@@ -307,7 +384,7 @@ impl SignalingClient {
 
                 if now >= next_ping {
                     let ping_msg = SignalingMsg::Ping { nonce };
-                    if let Err(e) = protocol::write_msg(&mut stream, &ping_msg) {
+                    if let Err(e) = transport.write_msg(&ping_msg) {
                         match e {
                             FrameError::Io(ioe) => {
                                 sink_error!(
@@ -344,11 +421,11 @@ impl SignalingClient {
                     next_ping = now + ping_interval;
                 }
 
-                // 4) Small sleep to avoid busy-spinning when idle.
+                // 5) Small sleep to avoid busy-spinning when idle.
                 thread::sleep(Duration::from_millis(10));
             }
 
-            // Dropping `stream` closes the underlying connection (TCP or TLS).
+            transport.shutdown();
             let _ = ev_tx.send(SignalingEvent::Disconnected);
         });
     }
@@ -376,6 +453,33 @@ impl SignalingClient {
         let _ = self.cmd_tx.send(SignalingCommand::Disconnect);
     }
 
+    /// Closes the connection once every message queued before it is sent,
+    /// waiting up to `timeout` for that, e.g. for a `Bye` on exit. Events
+    /// that were still pending are dropped. Returns whether it closed in time.
+    pub fn close(&self, timeout: Duration) -> bool {
+        self.disconnect();
+        let deadline = Instant::now() + timeout;
+        loop {
+            match self
+                .events
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(SignalingEvent::Disconnected) | Err(RecvTimeoutError::Disconnected) => {
+                    return true;
+                }
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return false,
+            }
+        }
+    }
+
+    /// The number of the last message received, to resume from after a
+    /// reconnect; 0 while the server does not number them.
+    #[must_use]
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq.load(Ordering::Relaxed)
+    }
+
     /// Polls the next pending event from the background thread.
     #[must_use]
     pub fn try_recv(&self) -> Option<SignalingEvent> {
@@ -392,6 +496,7 @@ const fn msg_name(msg: &SignalingMsg) -> &'static str {
         SignalingMsg::Register { .. } => "Register",
         SignalingMsg::RegisterOk { .. } => "RegisterOk",
         SignalingMsg::RegisterErr { .. } => "RegisterErr",
+        SignalingMsg::SetDevice { .. } => "SetDevice",
         SignalingMsg::ListPeers => "ListPeers",
         SignalingMsg::PeersOnline { .. } => "PeersOnline",
         SignalingMsg::CreateSession { .. } => "CreateSession",
@@ -399,14 +504,151 @@ const fn msg_name(msg: &SignalingMsg) -> &'static str {
         SignalingMsg::Join { .. } => "Join",
         SignalingMsg::JoinOk { .. } => "JoinOk",
         SignalingMsg::JoinErr { .. } => "JoinErr",
+        SignalingMsg::Leave { .. } => "Leave",
         SignalingMsg::PeerJoined { .. } => "PeerJoined",
         SignalingMsg::PeerLeft { .. } => "PeerLeft",
         SignalingMsg::Offer { .. } => "Offer",
         SignalingMsg::Answer { .. } => "Answer",
         SignalingMsg::Candidate { .. } => "Candidate",
+        SignalingMsg::Candidates { .. } => "Candidates",
         SignalingMsg::Ack { .. } => "Ack",
         SignalingMsg::Bye { .. } => "Bye",
+        SignalingMsg::Transfer { .. } => "Transfer",
+        SignalingMsg::CapabilityQuery { .. } => "CapabilityQuery",
+        SignalingMsg::CapabilityReply { .. } => "CapabilityReply",
+        SignalingMsg::CameraControl { .. } => "CameraControl",
+        SignalingMsg::CameraControlReply { .. } => "CameraControlReply",
+        SignalingMsg::OfferErr { .. } => "OfferErr",
+        SignalingMsg::AddContact { .. } => "AddContact",
+        SignalingMsg::RemoveContact { .. } => "RemoveContact",
+        SignalingMsg::Block { .. } => "Block",
+        SignalingMsg::Unblock { .. } => "Unblock",
+        SignalingMsg::ListContacts => "ListContacts",
+        SignalingMsg::Contacts { .. } => "Contacts",
         SignalingMsg::Ping { .. } => "Ping",
         SignalingMsg::Pong { .. } => "Pong",
+        SignalingMsg::ResumeFrom { .. } => "ResumeFrom",
+        SignalingMsg::Resumed { .. } => "Resumed",
+        SignalingMsg::Sequenced { .. } => "Sequenced",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use crate::log::NoopLogSink;
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    /// In-memory transport: `inbound` is what the "server" sends, `outbound`
+    /// records everything the client wrote.
+    struct MockTransport {
+        inbound: Arc<Mutex<VecDeque<SignalingMsg>>>,
+        outbound: Arc<Mutex<Vec<SignalingMsg>>>,
+    }
+
+    impl SignalingTransport for MockTransport {
+        fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
+            self.outbound.lock().unwrap().push(msg.clone());
+            Ok(())
+        }
+
+        fn read_msg(&mut self) -> Result<SignalingMsg, FrameError> {
+            self.inbound
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or_else(|| FrameError::Io(io::Error::from(io::ErrorKind::WouldBlock)))
+        }
+
+        fn shutdown(&mut self) {}
+
+        fn describe(&self) -> String {
+            "mock://".into()
+        }
+    }
+
+    fn wait_for<F: FnMut() -> bool>(mut cond: F) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if cond() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn client_runs_over_mock_transport() {
+        let inbound = Arc::new(Mutex::new(VecDeque::new()));
+        let outbound = Arc::new(Mutex::new(Vec::new()));
+        inbound.lock().unwrap().push_back(SignalingMsg::LoginOk {
+            username: "alice".into(),
+        });
+
+        let client = SignalingClient::with_transport(
+            Box::new(MockTransport {
+                inbound: inbound.clone(),
+                outbound: outbound.clone(),
+            }),
+            Arc::new(NoopLogSink),
+        );
+
+        let mut events = Vec::new();
+        assert!(wait_for(|| {
+            while let Some(ev) = client.try_recv() {
+                events.push(ev);
+            }
+            events.len() >= 2
+        }));
+        assert!(matches!(events[0], SignalingEvent::Connected));
+        assert!(matches!(
+            &events[1],
+            SignalingEvent::ServerMsg(SignalingMsg::LoginOk { username }) if username == "alice"
+        ));
+
+        client.send(SignalingMsg::ListPeers).unwrap();
+        assert!(wait_for(|| {
+            outbound
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|m| *m == SignalingMsg::ListPeers)
+        }));
+        assert!(matches!(
+            outbound.lock().unwrap()[0],
+            SignalingMsg::Hello { .. }
+        ));
+
+        client.disconnect();
+        assert!(wait_for(|| matches!(
+            client.try_recv(),
+            Some(SignalingEvent::Disconnected)
+        )));
+    }
+
+    #[test]
+    fn close_sends_queued_messages_first() {
+        let outbound = Arc::new(Mutex::new(Vec::new()));
+        let client = SignalingClient::with_transport(
+            Box::new(MockTransport {
+                inbound: Arc::new(Mutex::new(VecDeque::new())),
+                outbound: outbound.clone(),
+            }),
+            Arc::new(NoopLogSink),
+        );
+        let bye = SignalingMsg::Bye {
+            from: "alice".into(),
+            to: "bob".into(),
+            reason: None,
+        };
+        client.send(bye.clone()).unwrap();
+
+        assert!(client.close(Duration::from_secs(2)));
+        assert_eq!(outbound.lock().unwrap().last(), Some(&bye));
     }
 }
@@ -1,4 +1,4 @@
-use crate::signaling::protocol::SignalingMsg;
+use crate::signaling::protocol::{SignalingMsg, TxnId, UserName};
 
 /// Events generated by the background signaling connection.
 #[derive(Debug)]
@@ -7,4 +7,9 @@ pub enum SignalingEvent {
     Disconnected,
     Error(String),
     ServerMsg(SignalingMsg),
+    /// An Offer/Answer was retransmitted until giving up without an `Ack`.
+    CallFailed {
+        peer: UserName,
+        txn_id: TxnId,
+    },
 }
@@ -0,0 +1,207 @@
+use std::{
+    fmt, io,
+    net::{Shutdown, TcpStream},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rustls::{ClientConfig, ClientConnection, StreamOwned, pki_types::ServerName};
+
+use crate::signaling::protocol::{self, Compression, FrameError, SignalingMsg};
+
+/// Read timeout applied to the underlying socket so the network thread can
+/// interleave reads with commands and heartbeats.
+const READ_TIMEOUT_MS: u64 = 200;
+/// How long the TLS handshake may take.
+const HANDSHAKE_TIMEOUT_MS: u64 = 10_000;
+
+/// A framed, bidirectional channel to the signaling server.
+///
+/// The network thread only talks to the server through this trait, so the
+/// concrete transport (plain TCP, TLS, a future WebSocket, a mock in tests)
+/// can be chosen at runtime.
+///
+/// `read_msg` must not block forever: implementations should return an
+/// `io::ErrorKind::TimedOut`/`WouldBlock` `FrameError::Io` when no frame is
+/// available yet.
+pub trait SignalingTransport: Send {
+    /// Writes one complete framed message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameError` if the message cannot be encoded or written.
+    fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError>;
+
+    /// Reads one complete framed message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FrameError` on IO failures (including read timeouts) or if the
+    /// frame cannot be decoded.
+    fn read_msg(&mut self) -> Result<SignalingMsg, FrameError>;
+
+    /// Closes the connection. Further reads/writes are expected to fail.
+    fn shutdown(&mut self);
+
+    /// Human-readable peer description used in logs (e.g. `tls://host:port`).
+    fn describe(&self) -> String;
+}
+
+/// Which transport the client should use to reach the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransportKind {
+    Tcp,
+    #[default]
+    Tls,
+}
+
+impl FromStr for TransportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tcp" => Ok(Self::Tcp),
+            "tls" => Ok(Self::Tls),
+            other => Err(format!("unknown signaling transport '{other}'")),
+        }
+    }
+}
+
+impl fmt::Display for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp => write!(f, "tcp"),
+            Self::Tls => write!(f, "tls"),
+        }
+    }
+}
+
+/// Opens a TCP connection configured for signaling (no Nagle, short read timeout).
+fn connect_tcp(addr: &str) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(Duration::from_millis(READ_TIMEOUT_MS)))?;
+    Ok(stream)
+}
+
+/// Plain TCP transport. Only meant for local testing.
+pub struct TcpTransport {
+    addr: String,
+    stream: TcpStream,
+    compression: Compression,
+}
+
+impl TcpTransport {
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the TCP connection cannot be established or configured.
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        Ok(Self {
+            addr: addr.to_string(),
+            stream: connect_tcp(addr)?,
+            compression: Compression::new(),
+        })
+    }
+}
+
+impl SignalingTransport for TcpTransport {
+    fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
+        protocol::write_msg_compressed(&mut self.stream, msg, &self.compression)
+    }
+
+    fn read_msg(&mut self) -> Result<SignalingMsg, FrameError> {
+        protocol::read_msg_compressed(&mut self.stream, &self.compression)
+    }
+
+    fn shutdown(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+
+    fn describe(&self) -> String {
+        format!("tcp://{}", self.addr)
+    }
+}
+
+/// TLS over TCP transport (rustls).
+pub struct TlsTransport {
+    addr: String,
+    stream: StreamOwned<ClientConnection, TcpStream>,
+    compression: Compression,
+}
+
+impl TlsTransport {
+    /// Connects and completes the TLS handshake, so an untrusted server
+    /// certificate is reported here rather than on the first message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if the TCP connection fails, if `domain` is not a
+    /// valid DNS name, or if the TLS handshake fails or times out.
+    pub fn connect(addr: &str, domain: &str, tls_config: Arc<ClientConfig>) -> io::Result<Self> {
+        let tcp = connect_tcp(addr)?;
+
+        let server_name = ServerName::try_from(domain.to_owned())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid DNS name"))?;
+
+        let conn = ClientConnection::new(tls_config, server_name)
+            .map_err(|e| io::Error::other(format!("TLS error: {e}")))?;
+
+        let mut stream = StreamOwned::new(conn, tcp);
+        let deadline = Instant::now() + Duration::from_millis(HANDSHAKE_TIMEOUT_MS);
+        while stream.conn.is_handshaking() {
+            match stream.conn.complete_io(&mut stream.sock) {
+                Ok(_) => {}
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) && Instant::now() < deadline => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Self {
+            addr: addr.to_string(),
+            stream,
+            compression: Compression::new(),
+        })
+    }
+}
+
+impl SignalingTransport for TlsTransport {
+    fn write_msg(&mut self, msg: &SignalingMsg) -> Result<(), FrameError> {
+        protocol::write_msg_compressed(&mut self.stream, msg, &self.compression)
+    }
+
+    fn read_msg(&mut self) -> Result<SignalingMsg, FrameError> {
+        protocol::read_msg_compressed(&mut self.stream, &self.compression)
+    }
+
+    fn shutdown(&mut self) {
+        self.stream.conn.send_close_notify();
+        let _ = self.stream.conn.complete_io(&mut self.stream.sock);
+        let _ = self.stream.sock.shutdown(Shutdown::Both);
+    }
+
+    fn describe(&self) -> String {
+        format!("tls://{}", self.addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn transport_kind_parses_case_insensitively() {
+        assert_eq!("TLS".parse::<TransportKind>().unwrap(), TransportKind::Tls);
+        assert_eq!(
+            " tcp ".parse::<TransportKind>().unwrap(),
+            TransportKind::Tcp
+        );
+        assert!("ws".parse::<TransportKind>().is_err());
+        assert_eq!(TransportKind::default(), TransportKind::Tls);
+    }
+}
@@ -0,0 +1,284 @@
+//! How the signaling client decides to trust the server certificate.
+//!
+//! By default the certificate must chain to the pinned mkcert CA or to the
+//! CA bundle at `[TLS] signaling_ca_cert`. On top of that:
+//!
+//! - `[TLS] signaling_pins` pins the server key: only certificates whose
+//!   SubjectPublicKeyInfo hashes to one of them are accepted, CA or not.
+//! - `[TLS] signaling_accepted_keys` lists keys the user chose to trust
+//!   although no CA vouches for them (trust on first use).
+//!
+//! When the server asks for a client certificate, the one at
+//! `[TLS] signaling_client_cert` / `signaling_client_key` is presented.
+//!
+//! A rejected certificate fails the handshake with an [`UntrustedCertificate`]
+//! carrying its key fingerprint, so the GUI can ask whether to trust it.
+
+use std::{error::Error, fmt, io, sync::Arc};
+
+use openssl::x509::X509;
+use rustls::{
+    CertificateError, DigitallySignedStruct, Error as TlsError, OtherError, SignatureScheme,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+/// Trust settings for the signaling server certificate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustOptions {
+    /// PEM bundle of CA certificates trusted besides the pinned one.
+    pub extra_ca: Option<String>,
+    /// If not empty, the only server keys accepted (SPKI fingerprints).
+    pub pins: Vec<String>,
+    /// Server keys trusted without a CA (SPKI fingerprints).
+    pub accepted: Vec<String>,
+    /// Offer to trust an unknown server key instead of just failing.
+    pub trust_on_first_use: bool,
+    /// Certificate presented to servers that authenticate clients.
+    pub identity: Option<ClientIdentity>,
+}
+
+/// PEM files of a client certificate chain and its private key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientIdentity {
+    pub cert: String,
+    pub key: String,
+}
+
+impl TrustOptions {
+    /// `[TLS] signaling_ca_cert`, `signaling_pins`,
+    /// `signaling_accepted_keys`, `signaling_trust_on_first_use` and
+    /// `signaling_client_cert` / `signaling_client_key`.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let list = |key| parse_fingerprints(config.get("TLS", key).unwrap_or_default());
+        Self {
+            extra_ca: config
+                .get_non_empty("TLS", "signaling_ca_cert")
+                .map(str::to_owned),
+            pins: list("signaling_pins"),
+            accepted: list("signaling_accepted_keys"),
+            trust_on_first_use: config
+                .get("TLS", "signaling_trust_on_first_use")
+                .is_some_and(|s| s.trim().eq_ignore_ascii_case("true")),
+            identity: config
+                .get_non_empty("TLS", "signaling_client_cert")
+                .zip(config.get_non_empty("TLS", "signaling_client_key"))
+                .map(|(cert, key)| ClientIdentity {
+                    cert: cert.to_owned(),
+                    key: key.to_owned(),
+                }),
+        }
+    }
+
+    /// Trusts `fingerprint` from now on.
+    pub fn accept(&mut self, fingerprint: &str) {
+        let fingerprint = normalize_fingerprint(fingerprint);
+        if !self.accepted.contains(&fingerprint) {
+            self.accepted.push(fingerprint);
+        }
+    }
+
+    /// The accepted keys as saved in `[TLS] signaling_accepted_keys`.
+    #[must_use]
+    pub fn accepted_setting(&self) -> String {
+        self.accepted.join(",")
+    }
+}
+
+/// Comma-separated fingerprints, normalized.
+fn parse_fingerprints(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(normalize_fingerprint)
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// Uppercase hex pairs separated by colons, whatever the input separators.
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    let hex: Vec<char> = fingerprint
+        .chars()
+        .filter(char::is_ascii_hexdigit)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    hex.chunks(2)
+        .map(|pair| pair.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// SHA-256 of the certificate's SubjectPublicKeyInfo, as `XX:YY:...`.
+/// Unlike the certificate hash, it survives reissuing with the same key.
+#[must_use]
+pub fn spki_fingerprint(cert_der: &[u8]) -> Option<String> {
+    let spki = X509::from_der(cert_der)
+        .ok()?
+        .public_key()
+        .ok()?
+        .public_key_to_der()
+        .ok()?;
+    let hex: Vec<String> = Sha256::digest(spki)
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect();
+    Some(hex.join(":"))
+}
+
+/// The server certificate was not trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UntrustedCertificate {
+    /// SPKI fingerprint of the server key.
+    pub fingerprint: String,
+    /// Why the certificate was rejected.
+    pub reason: String,
+}
+
+impl UntrustedCertificate {
+    /// The rejection behind `err`, if a certificate was rejected.
+    #[must_use]
+    pub fn from_io(err: &io::Error) -> Option<&Self> {
+        let mut source: Option<&(dyn Error + 'static)> = err.get_ref().map(|e| e as _);
+        while let Some(e) = source {
+            if let Some(untrusted) = e.downcast_ref::<Self>() {
+                return Some(untrusted);
+            }
+            if let Some(TlsError::InvalidCertificate(CertificateError::Other(other))) =
+                e.downcast_ref::<TlsError>()
+            {
+                return other.0.downcast_ref::<Self>();
+            }
+            source = e.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for UntrustedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "untrusted server certificate ({}), key fingerprint {}",
+            self.reason, self.fingerprint
+        )
+    }
+}
+
+impl Error for UntrustedCertificate {}
+
+/// Checks the server certificate against the CAs, then the pins and the
+/// accepted keys. Handshake signatures are checked as usual.
+#[derive(Debug)]
+pub struct PinningVerifier {
+    ca: Arc<WebPkiServerVerifier>,
+    pins: Vec<String>,
+    accepted: Vec<String>,
+}
+
+impl PinningVerifier {
+    #[must_use]
+    pub fn new(ca: Arc<WebPkiServerVerifier>, trust: &TrustOptions) -> Self {
+        Self {
+            ca,
+            pins: trust.pins.clone(),
+            accepted: trust.accepted.clone(),
+        }
+    }
+
+    fn reject(fingerprint: String, reason: String) -> TlsError {
+        TlsError::InvalidCertificate(CertificateError::Other(OtherError(Arc::new(
+            UntrustedCertificate {
+                fingerprint,
+                reason,
+            },
+        ))))
+    }
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        let fingerprint = spki_fingerprint(end_entity)
+            .ok_or(TlsError::InvalidCertificate(CertificateError::BadEncoding))?;
+        if !self.pins.is_empty() {
+            return if self.pins.contains(&fingerprint) {
+                Ok(ServerCertVerified::assertion())
+            } else {
+                Err(Self::reject(fingerprint, "key not pinned".into()))
+            };
+        }
+        match self
+            .ca
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+        {
+            Ok(verified) => Ok(verified),
+            Err(_) if self.accepted.contains(&fingerprint) => Ok(ServerCertVerified::assertion()),
+            Err(e) => Err(Self::reject(fingerprint, e.to_string())),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.ca.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.ca.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.ca.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn options_come_from_config_normalized() {
+        let mut config = Config::empty();
+        config.set("TLS", "signaling_pins", "ab:cd:ef, 0102");
+        config.set("TLS", "signaling_trust_on_first_use", "true");
+        let mut trust = TrustOptions::from_config(&config);
+        assert_eq!(trust.pins, ["AB:CD:EF", "01:02"]);
+        assert!(trust.accepted.is_empty());
+        assert!(trust.trust_on_first_use);
+        assert_eq!(trust.extra_ca, None);
+        assert_eq!(trust.identity, None);
+
+        trust.accept("aa bb");
+        trust.accept("AA:BB");
+        assert_eq!(trust.accepted_setting(), "AA:BB");
+    }
+
+    #[test]
+    fn rejection_is_found_behind_io_errors() {
+        let rejected = PinningVerifier::reject("AA:BB".into(), "UnknownIssuer".into());
+        let err = io::Error::new(io::ErrorKind::InvalidData, rejected);
+        let untrusted = UntrustedCertificate::from_io(&err).unwrap();
+        assert_eq!(untrusted.fingerprint, "AA:BB");
+        assert!(UntrustedCertificate::from_io(&io::Error::other("refused")).is_none());
+    }
+}
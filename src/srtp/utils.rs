@@ -11,10 +11,7 @@ use sha1::Sha1;
 use crate::{
     srtp::SrtpEndpointKeys,
     srtp::{
-        constants::{
-            SESSION_AUTH_LEN, SESSION_KEY_LEN, SESSION_SALT_LEN, SRTP_LABEL_AUTH,
-            SRTP_LABEL_ENCRYPTION, SRTP_LABEL_SALT,
-        },
+        constants::{SESSION_AUTH_LEN, SESSION_KEY_LEN, SESSION_SALT_LEN},
         session_keys::SessionKeys,
     },
 };
@@ -32,7 +29,12 @@ pub(super) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     result == 0
 }
 
-pub(super) fn derive_session_keys(master: &SrtpEndpointKeys) -> SessionKeys {
+/// Session keys for the `[encryption, auth, salt]` labels: SRTP's or
+/// SRTCP's (RFC 3711 §4.3).
+pub(super) fn derive_session_keys(
+    master: &SrtpEndpointKeys,
+    [enc_label, auth_label, salt_label]: [u8; 3],
+) -> SessionKeys {
     let mut enc_key = [0u8; SESSION_KEY_LEN];
     let mut auth_key = [0u8; SESSION_AUTH_LEN];
     let mut salt = [0u8; SESSION_SALT_LEN];
@@ -44,19 +46,9 @@ pub(super) fn derive_session_keys(master: &SrtpEndpointKeys) -> SessionKeys {
         salt_pad[..master.master_salt.len()].copy_from_slice(&master.master_salt);
     }
 
-    aes_cm_prf(
-        &master.master_key,
-        &salt_pad,
-        SRTP_LABEL_ENCRYPTION,
-        &mut enc_key,
-    );
-    aes_cm_prf(
-        &master.master_key,
-        &salt_pad,
-        SRTP_LABEL_AUTH,
-        &mut auth_key,
-    );
-    aes_cm_prf(&master.master_key, &salt_pad, SRTP_LABEL_SALT, &mut salt);
+    aes_cm_prf(&master.master_key, &salt_pad, enc_label, &mut enc_key);
+    aes_cm_prf(&master.master_key, &salt_pad, auth_label, &mut auth_key);
+    aes_cm_prf(&master.master_key, &salt_pad, salt_label, &mut salt);
 
     SessionKeys {
         enc_key,
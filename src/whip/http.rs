@@ -0,0 +1,348 @@
+//! Just enough HTTP/1.1 for WHIP and WHEP: one request per connection
+//! (`Connection: close`), over plain TCP or rustls.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    time::Duration,
+};
+
+use rustls::{ClientConfig, ClientConnection, StreamOwned, pki_types::ServerName};
+
+use crate::whip::whip_error::WhipError;
+
+/// How long connecting, and each read or write, may take.
+const IO_TIMEOUT_MS: u64 = 10_000;
+
+/// An `http://` or `https://` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub tls: bool,
+    /// Host name or IP address, IPv6 without brackets.
+    pub host: String,
+    pub port: u16,
+    /// Path and query, always starting with `/`.
+    pub path: String,
+}
+
+impl HttpUrl {
+    /// # Errors
+    ///
+    /// Returns `WhipError::Url` if the scheme is not `http`/`https` or the
+    /// host or port are missing or invalid.
+    pub fn parse(url: &str) -> Result<Self, WhipError> {
+        let url = url.trim();
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(WhipError::Url(format!(
+                "{url}: expected http:// or https://"
+            )));
+        };
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(i) if rest[i..].starts_with('/') => (&rest[..i], rest[i..].to_string()),
+            Some(i) => (&rest[..i], format!("/{}", &rest[i..])),
+            None => (rest, "/".to_string()),
+        };
+        // `[v6]:port` keeps its colons inside the brackets
+        let (host, port) = if let Some(v6) = authority.strip_prefix('[') {
+            let (host, after) = v6
+                .split_once(']')
+                .ok_or_else(|| WhipError::Url(format!("{url}: unclosed '['")))?;
+            (host, after.strip_prefix(':'))
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(WhipError::Url(format!("{url}: missing host")));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .map_err(|_| WhipError::Url(format!("{url}: bad port '{port}'")))?,
+            None if tls => 443,
+            None => 80,
+        };
+        Ok(Self {
+            tls,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    /// Resolves `location` (a `Location` header) against this URL: absolute
+    /// URLs are taken as is, the rest keep our scheme, host and port.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WhipError::Url` if `location` is an invalid absolute URL.
+    pub fn join(&self, location: &str) -> Result<Self, WhipError> {
+        let location = location.trim();
+        if location.contains("://") {
+            return Self::parse(location);
+        }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else {
+            let dir = self
+                .path
+                .split('?')
+                .next()
+                .and_then(|p| p.rsplit_once('/'))
+                .map_or("", |(dir, _)| dir);
+            format!("{dir}/{location}")
+        };
+        Ok(Self {
+            path,
+            ..self.clone()
+        })
+    }
+
+    /// `Host` header value: the port is left out when it is the default one.
+    #[must_use]
+    pub fn host_header(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == if self.tls { 443 } else { 80 } {
+            host
+        } else {
+            format!("{host}:{}", self.port)
+        }
+    }
+}
+
+impl fmt::Display for HttpUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = if self.tls { "https" } else { "http" };
+        write!(f, "{scheme}://{}{}", self.host_header(), self.path)
+    }
+}
+
+/// A complete HTTP response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// First header named `name` (case-insensitive).
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses a response read until the server closed the connection. The
+    /// body is cut at `Content-Length`, or de-chunked.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WhipError::BadResponse` if the status line, headers or
+    /// chunks are malformed, or the body is shorter than announced.
+    pub fn parse(raw: &[u8]) -> Result<Self, WhipError> {
+        let bad = |e: &str| WhipError::BadResponse(e.to_string());
+        let head_end = raw
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .ok_or_else(|| bad("headers not terminated"))?;
+        let head = std::str::from_utf8(&raw[..head_end]).map_err(|_| bad("headers not UTF-8"))?;
+        let mut lines = head.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        if !parts.next().is_some_and(|v| v.starts_with("HTTP/1.")) {
+            return Err(bad(&format!("status line '{status_line}'")));
+        }
+        let status = parts
+            .next()
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| bad(&format!("status line '{status_line}'")))?;
+        let headers = lines
+            .map(|line| {
+                line.split_once(':')
+                    .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+                    .ok_or_else(|| bad(&format!("header '{line}'")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut response = Self {
+            status,
+            headers,
+            body: Vec::new(),
+        };
+        let rest = &raw[head_end + 4..];
+        response.body = if response
+            .header("Transfer-Encoding")
+            .is_some_and(|te| te.eq_ignore_ascii_case("chunked"))
+        {
+            dechunk(rest)?
+        } else if let Some(len) = response.header("Content-Length") {
+            let len: usize = len.parse().map_err(|_| bad("bad Content-Length"))?;
+            rest.get(..len)
+                .ok_or_else(|| bad("body shorter than Content-Length"))?
+                .to_vec()
+        } else {
+            rest.to_vec()
+        };
+        Ok(response)
+    }
+}
+
+/// Joins the chunks of a `Transfer-Encoding: chunked` body.
+fn dechunk(mut rest: &[u8]) -> Result<Vec<u8>, WhipError> {
+    let bad = || WhipError::BadResponse("malformed chunk".to_string());
+    let mut body = Vec::new();
+    loop {
+        let line_end = rest.windows(2).position(|w| w == b"\r\n").ok_or_else(bad)?;
+        let size_line = std::str::from_utf8(&rest[..line_end]).map_err(|_| bad())?;
+        // Chunk extensions (`;name=value`) are ignored
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| bad())?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        body.extend_from_slice(rest.get(..size).ok_or_else(bad)?);
+        rest = rest.get(size + 2..).ok_or_else(bad)?;
+    }
+}
+
+/// Sends one request and reads the whole response. `tls_config` is required
+/// for `https` URLs.
+///
+/// # Errors
+///
+/// Returns `WhipError::Io` on connection or TLS failures and
+/// `WhipError::BadResponse` if the response cannot be parsed.
+pub fn request(
+    method: &str,
+    url: &HttpUrl,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    tls_config: Option<&Arc<ClientConfig>>,
+) -> Result<HttpResponse, WhipError> {
+    let mut req = format!(
+        "{method} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+        url.path,
+        url.host_header(),
+        body.len()
+    );
+    for (name, value) in headers {
+        req.push_str(&format!("{name}: {value}\r\n"));
+    }
+    req.push_str("\r\n");
+    let mut req = req.into_bytes();
+    req.extend_from_slice(body);
+
+    let timeout = Duration::from_millis(IO_TIMEOUT_MS);
+    let addr = (url.host.as_str(), url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| WhipError::Url(format!("{}: host not found", url.host)))?;
+    let tcp = TcpStream::connect_timeout(&addr, timeout)?;
+    tcp.set_read_timeout(Some(timeout))?;
+    tcp.set_write_timeout(Some(timeout))?;
+
+    let raw = if url.tls {
+        let config = tls_config
+            .cloned()
+            .ok_or_else(|| WhipError::Url(format!("{url}: no TLS configuration for https")))?;
+        let server_name = ServerName::try_from(url.host.clone())
+            .map_err(|_| WhipError::Url(format!("{}: invalid server name", url.host)))?;
+        let conn = ClientConnection::new(config, server_name)
+            .map_err(|e| io::Error::other(format!("TLS error: {e}")))?;
+        exchange(StreamOwned::new(conn, tcp), &req)?
+    } else {
+        exchange(tcp, &req)?
+    };
+    HttpResponse::parse(&raw)
+}
+
+/// Writes `req` and reads until the server closes the connection.
+fn exchange<S: Read + Write>(mut stream: S, req: &[u8]) -> io::Result<Vec<u8>> {
+    stream.write_all(req)?;
+    stream.flush()?;
+    let mut raw = Vec::new();
+    match stream.read_to_end(&mut raw) {
+        Ok(_) => Ok(raw),
+        // Plenty of servers close TLS without a close_notify
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !raw.is_empty() => Ok(raw),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+
+    #[test]
+    fn urls_parse_with_default_ports() {
+        let url = HttpUrl::parse("https://media.example.com/whip/live?key=1").unwrap();
+        assert!(url.tls);
+        assert_eq!(url.host, "media.example.com");
+        assert_eq!(url.port, 443);
+        assert_eq!(url.path, "/whip/live?key=1");
+        assert_eq!(url.to_string(), "https://media.example.com/whip/live?key=1");
+
+        let url = HttpUrl::parse("http://[::1]:8889").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", 8889));
+        assert_eq!(url.path, "/");
+        assert_eq!(url.host_header(), "[::1]:8889");
+
+        assert!(HttpUrl::parse("ftp://host/").is_err());
+        assert!(HttpUrl::parse("http://host:port/").is_err());
+    }
+
+    #[test]
+    fn locations_resolve_against_the_endpoint() {
+        let endpoint = HttpUrl::parse("http://10.0.0.1:8080/whip/endpoint?room=a").unwrap();
+        assert_eq!(
+            endpoint.join("/resource/42").unwrap().to_string(),
+            "http://10.0.0.1:8080/resource/42"
+        );
+        assert_eq!(
+            endpoint.join("resource/42").unwrap().to_string(),
+            "http://10.0.0.1:8080/whip/resource/42"
+        );
+        assert_eq!(
+            endpoint
+                .join("https://cdn.example.com/r/1")
+                .unwrap()
+                .to_string(),
+            "https://cdn.example.com/r/1"
+        );
+    }
+
+    #[test]
+    fn responses_parse_with_length_or_chunks() {
+        let raw =
+            b"HTTP/1.1 201 Created\r\nLocation: /r/1\r\ncontent-length: 5\r\n\r\nv=0\r\nextra";
+        let response = HttpResponse::parse(raw).unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.header("location"), Some("/r/1"));
+        assert_eq!(response.body, b"v=0\r\n");
+
+        let raw = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4;x=y\r\nv=0\r\r\n1\r\n\n\r\n0\r\n\r\n";
+        assert_eq!(HttpResponse::parse(raw).unwrap().body, b"v=0\r\n");
+
+        assert!(
+            HttpResponse::parse(b"HTTP/1.1 201 Created\r\nContent-Length: 9\r\n\r\nv=0").is_err()
+        );
+        assert!(HttpResponse::parse(b"SIP/2.0 200 OK\r\n\r\n").is_err());
+    }
+}
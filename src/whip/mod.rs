@@ -0,0 +1,11 @@
+//! WHIP (ingest) and WHEP (playback) over plain HTTP.
+//!
+//! Instead of the signaling server, the SDP offer is POSTed to a media
+//! server's endpoint, which replies `201 Created` with its answer and a
+//! `Location` for the session; DELETEing that resource ends it. Offers carry
+//! every candidate, so no trickle (`PATCH`) is needed.
+pub mod http;
+pub mod whip_client;
+pub mod whip_error;
+pub use whip_client::{WhipClient, WhipMode};
+pub use whip_error::WhipError;
@@ -0,0 +1,264 @@
+use std::sync::Arc;
+
+use rustls::ClientConfig;
+
+use crate::{
+    config::Config,
+    signaling::tls::build_signaling_client_config,
+    signaling_client::trust::TrustOptions,
+    whip::{
+        http::{self, HttpResponse, HttpUrl},
+        whip_error::WhipError,
+    },
+};
+
+/// Which side of the stream we are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhipMode {
+    /// WHIP: publish our outbound tracks (`sendonly`).
+    Publish,
+    /// WHEP: play the server's stream (`recvonly`).
+    Play,
+}
+
+/// One WHIP or WHEP session: the offer is POSTed to the endpoint, the
+/// answer comes back with the URL of the session resource, which is
+/// DELETEd to end it.
+#[derive(Debug)]
+pub struct WhipClient {
+    mode: WhipMode,
+    endpoint: HttpUrl,
+    token: Option<String>,
+    tls_config: Option<Arc<ClientConfig>>,
+    resource: Option<HttpUrl>,
+}
+
+impl WhipClient {
+    /// # Errors
+    ///
+    /// Returns `WhipError::Url` if `endpoint` is not an HTTP(S) URL.
+    pub fn new(mode: WhipMode, endpoint: &str) -> Result<Self, WhipError> {
+        Ok(Self {
+            mode,
+            endpoint: HttpUrl::parse(endpoint)?,
+            token: None,
+            tls_config: None,
+            resource: None,
+        })
+    }
+
+    /// The endpoint, bearer token and CA bundle from `[WHIP]`
+    /// (`whip_url`/`whep_url`, `token`, `ca_cert`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `WhipError::Url` if the URL is missing or invalid, and
+    /// `WhipError::Io` if the CA bundle cannot be loaded.
+    pub fn from_config(mode: WhipMode, config: &Config) -> Result<Self, WhipError> {
+        let key = match mode {
+            WhipMode::Publish => "whip_url",
+            WhipMode::Play => "whep_url",
+        };
+        let endpoint = config
+            .get_non_empty("WHIP", key)
+            .ok_or_else(|| WhipError::Url(format!("[WHIP] {key} is not set")))?;
+        let mut client = Self::new(mode, endpoint)?;
+        client.token = config.get_non_empty("WHIP", "token").map(str::to_owned);
+        if client.endpoint.tls {
+            let trust = TrustOptions {
+                extra_ca: config.get_non_empty("WHIP", "ca_cert").map(str::to_owned),
+                ..TrustOptions::default()
+            };
+            client.tls_config = Some(build_signaling_client_config(&trust)?);
+        }
+        Ok(client)
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.token = token;
+    }
+
+    /// TLS settings for `https` endpoints and resources.
+    pub fn set_tls_config(&mut self, tls_config: Arc<ClientConfig>) {
+        self.tls_config = Some(tls_config);
+    }
+
+    #[must_use]
+    pub const fn mode(&self) -> WhipMode {
+        self.mode
+    }
+
+    /// The session resource, once the offer was accepted.
+    #[must_use]
+    pub const fn resource(&self) -> Option<&HttpUrl> {
+        self.resource.as_ref()
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &HttpUrl,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<HttpResponse, WhipError> {
+        let auth = self.token.as_ref().map(|t| format!("Bearer {t}"));
+        let mut headers = Vec::new();
+        if let Some(content_type) = content_type {
+            headers.push(("Content-Type", content_type));
+            headers.push(("Accept", content_type));
+        }
+        if let Some(auth) = &auth {
+            headers.push(("Authorization", auth.as_str()));
+        }
+        http::request(method, url, &headers, body, self.tls_config.as_ref())
+    }
+
+    /// POSTs our SDP offer (with all its candidates, WHIP does not require
+    /// trickle) and returns the server's answer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WhipError::Status` unless the server answers `201 Created`,
+    /// and `WhipError::BadResponse` if the answer or `Location` is missing.
+    pub fn offer(&mut self, sdp: &str) -> Result<String, WhipError> {
+        let response = self.send(
+            "POST",
+            &self.endpoint,
+            Some("application/sdp"),
+            sdp.as_bytes(),
+        )?;
+        self.resource = Some(self.accept_answer_response(&response)?);
+        String::from_utf8(response.body)
+            .map_err(|_| WhipError::BadResponse("answer is not UTF-8".to_string()))
+    }
+
+    /// Checks a response to the offer and resolves its `Location`.
+    fn accept_answer_response(&self, response: &HttpResponse) -> Result<HttpUrl, WhipError> {
+        if response.status != 201 {
+            return Err(WhipError::Status {
+                code: response.status,
+                body: String::from_utf8_lossy(&response.body).trim().to_string(),
+            });
+        }
+        if response.body.is_empty() {
+            return Err(WhipError::BadResponse(
+                "201 without an SDP answer".to_string(),
+            ));
+        }
+        let location = response
+            .header("Location")
+            .ok_or_else(|| WhipError::BadResponse("201 without a Location".to_string()))?;
+        self.endpoint.join(location)
+    }
+
+    /// Ends the session by DELETEing its resource. Does nothing if no
+    /// session was created.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WhipError` if the request fails or is not answered `2xx`.
+    pub fn close(&mut self) -> Result<(), WhipError> {
+        let Some(resource) = self.resource.take() else {
+            return Ok(());
+        };
+        let response = self.send("DELETE", &resource, None, &[])?;
+        if (200..300).contains(&response.status) {
+            Ok(())
+        } else {
+            Err(WhipError::Status {
+                code: response.status,
+                body: String::from_utf8_lossy(&response.body).trim().to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used, clippy::expect_used)]
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    /// Answers one connection with `response` and returns the request it got.
+    fn serve_once(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/whip/endpoint", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Headers, then the Content-Length bytes of body
+            loop {
+                let n = conn.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let len = head
+                        .lines()
+                        .find_map(|l| l.strip_prefix("Content-Length: "))
+                        .and_then(|l| l.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        break;
+                    }
+                }
+            }
+            conn.write_all(response.as_bytes()).unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn publishes_and_deletes_the_resource() {
+        let (url, server) = serve_once(
+            "HTTP/1.1 201 Created\r\nLocation: resource/abc\r\nContent-Type: application/sdp\r\n\
+             Content-Length: 10\r\n\r\nv=0\r\no=x\r\n",
+        );
+        let mut client = WhipClient::new(WhipMode::Publish, &url).unwrap();
+        client.set_token(Some("s3cret".into()));
+        let answer = client.offer("v=0\r\n").unwrap();
+        assert_eq!(answer, "v=0\r\no=x\r\n");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /whip/endpoint HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/sdp\r\n"));
+        assert!(request.contains("Authorization: Bearer s3cret\r\n"));
+        assert!(request.ends_with("\r\n\r\nv=0\r\n"));
+        assert_eq!(client.resource().unwrap().path, "/whip/resource/abc");
+
+        let (url, server) = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        // Point the resource at the new listener, same path
+        client.resource.as_mut().unwrap().port = HttpUrl::parse(&url).unwrap().port;
+        client.close().unwrap();
+        assert!(
+            server
+                .join()
+                .unwrap()
+                .starts_with("DELETE /whip/resource/abc HTTP/1.1\r\n")
+        );
+        assert!(client.resource().is_none());
+    }
+
+    #[test]
+    fn rejections_keep_the_server_reason() {
+        let (url, server) =
+            serve_once("HTTP/1.1 401 Unauthorized\r\nContent-Length: 13\r\n\r\nbad token\r\n\r\n");
+        let mut client = WhipClient::new(WhipMode::Play, &url).unwrap();
+        match client.offer("v=0\r\n") {
+            Err(WhipError::Status { code, body }) => {
+                assert_eq!(code, 401);
+                assert_eq!(body, "bad token");
+            }
+            other => panic!("expected a 401, got {other:?}"),
+        }
+        server.join().unwrap();
+        assert!(client.resource().is_none());
+        client.close().unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+use std::{fmt, io};
+
+/// Errors while publishing to a WHIP endpoint or playing from a WHEP one.
+#[derive(Debug)]
+pub enum WhipError {
+    /// The endpoint or `Location` URL cannot be used.
+    Url(String),
+    Io(io::Error),
+    /// The server answered with an unexpected status.
+    Status {
+        code: u16,
+        body: String,
+    },
+    /// The response is not valid HTTP or lacks what WHIP requires.
+    BadResponse(String),
+}
+
+impl fmt::Display for WhipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(e) => write!(f, "invalid URL: {e}"),
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::Status { code, body } if body.is_empty() => write!(f, "HTTP status {code}"),
+            Self::Status { code, body } => write!(f, "HTTP status {code}: {body}"),
+            Self::BadResponse(e) => write!(f, "bad HTTP response: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for WhipError {}
+
+impl From<io::Error> for WhipError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
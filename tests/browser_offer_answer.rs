@@ -0,0 +1,235 @@
+//! Offer/answer with browsers: what Chrome and Firefox check before they
+//! start ICE and DTLS with us.
+//!
+//! This is SDP compatibility only. What follows DTLS is SRTP and SRTCP,
+//! checked against libsrtp's reference packets in `srtp::srtp_context`; no
+//! call with a live browser has been checked yet.
+//!
+//! The SDPs in `tests/browser_sdp` follow the shape of the browsers' (m-lines,
+//! codecs, attributes) with made-up addresses, ids and fingerprints. Offers
+//! saved from a real browser (e.g. from `chrome://webrtc-internals` or
+//! `about:webrtc`) go in `tests/browser_sdp/captured`, one `.sdp` file
+//! each, and are checked by `cargo test -- --ignored`.
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
+use std::sync::Arc;
+
+use rustyrtc::{
+    config::Config,
+    connection_manager::{ConnectionManager, OutboundSdp},
+    dtls::DtlsRole,
+    log::NoopLogSink,
+    media_transport::codec::CodecDescriptor,
+    sdp::{media::Media, sdpc::Sdp},
+};
+
+fn browser_sdp(name: &str) -> String {
+    let path = format!("{}/tests/browser_sdp/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("reading {path}: {e}"))
+}
+
+fn connection_manager() -> ConnectionManager {
+    let mut cm = ConnectionManager::new(Arc::new(NoopLogSink), Arc::new(Config::empty()));
+    cm.set_local_rtp_codecs(vec![
+        CodecDescriptor::pcmu_dynamic(0),
+        CodecDescriptor::h264_dynamic(96),
+        CodecDescriptor::telephone_event_dynamic(97),
+    ]);
+    cm
+}
+
+fn answer_to(offer: &str, cm: &mut ConnectionManager) -> Sdp {
+    match cm.apply_remote_sdp(offer).expect("browser offer rejected") {
+        OutboundSdp::Answer(answer) => {
+            // What goes on the wire must parse back the same
+            Sdp::parse(&answer.encode()).expect("answer does not parse")
+        }
+        other => panic!("expected an answer, got {other:?}"),
+    }
+}
+
+fn attr<'a>(media: &'a Media, key: &str) -> Option<&'a str> {
+    media
+        .attrs()
+        .iter()
+        .find(|a| a.key() == key)
+        .map(|a| a.value().unwrap_or_default())
+}
+
+fn attrs<'a>(media: &'a Media, key: &str) -> Vec<&'a str> {
+    media
+        .attrs()
+        .iter()
+        .filter(|a| a.key() == key)
+        .filter_map(|a| a.value())
+        .collect()
+}
+
+/// What every accepted m-line needs for the browser to connect.
+fn assert_transport_attrs(media: &Media) {
+    assert!(attr(media, "ice-ufrag").is_some_and(|u| u.len() >= 4));
+    assert!(attr(media, "ice-pwd").is_some_and(|p| p.len() >= 22));
+    assert!(attr(media, "fingerprint").is_some_and(|f| f.starts_with("sha-256 ")));
+    assert_eq!(attr(media, "setup"), Some("active"));
+    assert!(attr(media, "rtcp-mux").is_some());
+    assert!(!attrs(media, "candidate").is_empty());
+}
+
+#[test]
+fn answers_chrome_offer() {
+    let mut cm = connection_manager();
+    let answer = answer_to(&browser_sdp("chrome_offer.sdp"), &mut cm);
+
+    assert_eq!(
+        answer
+            .attrs()
+            .iter()
+            .find(|a| a.key() == "group")
+            .and_then(|a| a.value()),
+        Some("BUNDLE 0 1")
+    );
+    let media = answer.media();
+    assert_eq!(media.len(), 3, "one m-line per offered m-line");
+    let mids: Vec<_> = media.iter().filter_map(|m| attr(m, "mid")).collect();
+    assert_eq!(mids, ["0", "1", "2"]);
+
+    let audio = &media[0];
+    assert_transport_attrs(audio);
+    assert_eq!(audio.fmts(), &["0", "126"]);
+    assert_eq!(
+        attrs(audio, "rtpmap"),
+        ["0 PCMU/8000", "126 telephone-event/8000"]
+    );
+
+    let video = &media[1];
+    assert_transport_attrs(video);
+    assert_eq!(
+        video.fmts(),
+        &["106"],
+        "the Constrained Baseline, mode 1 H.264"
+    );
+    assert_eq!(attrs(video, "rtpmap"), ["106 H264/90000"]);
+    assert_eq!(
+        attrs(video, "fmtp"),
+        ["106 level-asymmetry-allowed=1;packetization-mode=1;profile-level-id=42e01f"]
+    );
+
+    // Data channels are not supported: rejected, not dropped
+    let data = &media[2];
+    assert_eq!(data.port().base(), 0);
+    assert_eq!(data.proto(), "UDP/DTLS/SCTP");
+
+    assert_eq!(
+        cm.remote_fingerprint.as_deref(),
+        Some(
+            "9F:3A:1C:77:E2:45:0B:D8:6A:11:C4:92:5E:3F:08:B7:AA:19:64:0C:DE:F2:37:81:5B:9D:42:C0:E6:13:7A:58"
+        )
+    );
+    assert_eq!(cm.dtls_role(), DtlsRole::Client);
+    // The mDNS candidate is skipped, the UDP and TCP ones are kept
+    assert_eq!(cm.ice_agent.remote_candidates.len(), 2);
+    assert!(
+        cm.ice_agent
+            .remote_candidates
+            .iter()
+            .all(|c| c.address.ip().to_string() == "192.0.2.10")
+    );
+
+    cm.reset();
+}
+
+#[test]
+fn answers_firefox_offer() {
+    let mut cm = connection_manager();
+    let answer = answer_to(&browser_sdp("firefox_offer.sdp"), &mut cm);
+
+    let media = answer.media();
+    assert_eq!(media.len(), 2);
+    for m in media {
+        assert_transport_attrs(m);
+    }
+    assert_eq!(
+        attrs(&media[0], "rtpmap"),
+        ["0 PCMU/8000", "101 telephone-event/8000"]
+    );
+    // 97 is H.264 too, but without packetization mode 1
+    assert_eq!(media[1].fmts(), &["126"]);
+
+    // Firefox sends the fingerprint at session level only
+    assert_eq!(
+        cm.remote_fingerprint.as_deref(),
+        Some(
+            "4C:1D:92:E8:0A:73:5F:B6:21:CD:48:07:9E:3B:A5:F0:6C:12:D9:84:7B:E1:30:5A:C8:2F:96:4D:0B:E7:19:A3"
+        )
+    );
+    assert_eq!(cm.dtls_role(), DtlsRole::Client);
+    assert_eq!(cm.ice_agent.remote_candidates.len(), 2);
+
+    cm.reset();
+}
+
+#[test]
+fn offers_to_chrome_and_takes_its_answer() {
+    let mut cm = connection_manager();
+    let OutboundSdp::Offer(offer) = cm.negotiate().expect("no offer") else {
+        panic!("expected an offer");
+    };
+    let offer = Sdp::parse(&offer.encode()).expect("offer does not parse");
+    assert_eq!(
+        offer
+            .attrs()
+            .iter()
+            .find(|a| a.key() == "group")
+            .and_then(|a| a.value()),
+        Some("BUNDLE 0 1")
+    );
+    for m in offer.media() {
+        assert_eq!(attr(m, "setup"), Some("actpass"));
+        assert!(attr(m, "mid").is_some());
+    }
+
+    let out = cm
+        .apply_remote_sdp(&browser_sdp("chrome_answer.sdp"))
+        .expect("browser answer rejected");
+    assert!(matches!(out, OutboundSdp::None));
+    // Chrome took `active`, so it is the DTLS client
+    assert_eq!(cm.dtls_role(), DtlsRole::Server);
+    assert!(cm.remote_fingerprint.is_some());
+    assert!(
+        cm.remote_codecs()
+            .iter()
+            .any(|c| c.payload_type == 96 && c.name == "H264")
+    );
+
+    cm.reset();
+}
+
+#[test]
+#[ignore = "needs offers saved from a browser in tests/browser_sdp/captured"]
+fn answers_captured_browser_offers() {
+    let dir = format!("{}/tests/browser_sdp/captured", env!("CARGO_MANIFEST_DIR"));
+    let mut offers: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("reading {dir}: {e}"))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sdp"))
+        .collect();
+    offers.sort();
+    assert!(!offers.is_empty(), "no .sdp file in {dir}");
+
+    for path in offers {
+        let offer = std::fs::read_to_string(&path).unwrap();
+        let mut cm = connection_manager();
+        let answer = answer_to(&offer, &mut cm);
+        let accepted: Vec<_> = answer
+            .media()
+            .iter()
+            .filter(|m| m.port().base() != 0)
+            .collect();
+        assert!(!accepted.is_empty(), "{}: nothing accepted", path.display());
+        for m in accepted {
+            assert_transport_attrs(m);
+        }
+        assert!(cm.remote_fingerprint.is_some(), "{}", path.display());
+        cm.reset();
+    }
+}